@@ -0,0 +1,153 @@
+//! Adapter enumeration and device selection for hosts with more than one GPU.
+//!
+//! Devices are identified by their position in `Instance::enumerate_adapters`,
+//! which is stable for a given machine and driver set. A [`DeviceSelection`]
+//! is written as `auto`, `all` or `gpu:<index>`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use wgpu::{Adapter, Backends, DeviceType, Instance, InstanceDescriptor};
+
+/// Description of one adapter visible to wgpu
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuDevice {
+    pub index: usize,
+    pub name: String,
+    pub vendor: u32,
+    pub device: u32,
+    pub device_type: String,
+    pub backend: String,
+    pub driver: String,
+}
+
+impl GpuDevice {
+    fn from_adapter(index: usize, adapter: &Adapter) -> Self {
+        let info = adapter.get_info();
+        Self {
+            index,
+            name: info.name,
+            vendor: info.vendor,
+            device: info.device,
+            device_type: match info.device_type {
+                DeviceType::DiscreteGpu => "discrete",
+                DeviceType::IntegratedGpu => "integrated",
+                DeviceType::VirtualGpu => "virtual",
+                DeviceType::Cpu => "cpu",
+                DeviceType::Other => "other",
+            }
+            .to_string(),
+            backend: info.backend.to_str().to_string(),
+            driver: info.driver,
+        }
+    }
+
+    /// Software rasterizers are listed but never picked for `all`
+    pub fn is_hardware(&self) -> bool {
+        self.device_type != "cpu"
+    }
+
+    /// Rank used by `auto`; lower is better, ties go to the lowest index
+    pub(crate) fn preference(&self) -> u8 {
+        match self.device_type.as_str() {
+            "discrete" => 0,
+            "integrated" => 1,
+            "virtual" => 2,
+            "other" => 3,
+            _ => 4,
+        }
+    }
+}
+
+/// Which adapters the worker generates chunks on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeviceSelection {
+    /// Use the most capable adapter (discrete over integrated over software)
+    #[default]
+    Auto,
+    /// Pin all work to one adapter
+    Device(usize),
+    /// Split batches across every hardware adapter
+    All,
+}
+
+impl fmt::Display for DeviceSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceSelection::Auto => write!(f, "auto"),
+            DeviceSelection::Device(index) => write!(f, "gpu:{}", index),
+            DeviceSelection::All => write!(f, "all"),
+        }
+    }
+}
+
+impl FromStr for DeviceSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        match s.as_str() {
+            "" | "auto" => Ok(DeviceSelection::Auto),
+            "all" => Ok(DeviceSelection::All),
+            _ => s
+                .strip_prefix("gpu:")
+                .unwrap_or(&s)
+                .parse()
+                .map(DeviceSelection::Device)
+                .map_err(|_| format!("invalid GPU device selection '{}', expected auto, all or gpu:<index>", s)),
+        }
+    }
+}
+
+impl Serialize for DeviceSelection {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for DeviceSelection {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+pub(crate) fn create_instance() -> Instance {
+    Instance::new(InstanceDescriptor {
+        backends: Backends::all(),
+        ..Default::default()
+    })
+}
+
+/// Adapters in index order, paired with their descriptions
+pub(crate) fn enumerate(instance: &Instance) -> Vec<(GpuDevice, Adapter)> {
+    instance
+        .enumerate_adapters(Backends::all())
+        .into_iter()
+        .enumerate()
+        .map(|(index, adapter)| (GpuDevice::from_adapter(index, &adapter), adapter))
+        .collect()
+}
+
+/// List every adapter on this machine
+pub fn list_devices() -> Vec<GpuDevice> {
+    enumerate(&create_instance()).into_iter().map(|(device, _)| device).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selection_round_trip() {
+        for selection in [DeviceSelection::Auto, DeviceSelection::All, DeviceSelection::Device(3)] {
+            assert_eq!(selection.to_string().parse::<DeviceSelection>().unwrap(), selection);
+            let json = serde_json::to_string(&selection).unwrap();
+            assert_eq!(serde_json::from_str::<DeviceSelection>(&json).unwrap(), selection);
+        }
+
+        assert_eq!("1".parse::<DeviceSelection>().unwrap(), DeviceSelection::Device(1));
+        assert_eq!(" GPU:2 ".parse::<DeviceSelection>().unwrap(), DeviceSelection::Device(2));
+        assert!("cuda:0".parse::<DeviceSelection>().is_err());
+    }
+}
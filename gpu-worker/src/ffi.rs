@@ -1,5 +1,7 @@
 use std::ffi::CString;
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_int};
+
+use crate::kernels::{TerrainPreset, WorldGenSettings};
 
 /// Chunk job structure for C ABI
 #[repr(C)]
@@ -11,6 +13,16 @@ pub struct ChunkJob {
     pub dimension: *const c_char,
     pub tick_count: i32,
     pub rule_version: *const c_char,
+    /// World settings; `octaves` 0 keeps the default
+    pub sea_level: i32,
+    pub octaves: u32,
+    pub amplified: c_int,
+    /// Preset name (`normal`, `large_biomes`, `flat` or `custom`); null means `normal`
+    pub preset: *const c_char,
+    /// Used only by the `custom` preset
+    pub height_scale: f32,
+    pub horizontal_scale: f32,
+    pub biome_scale: f32,
 }
 
 /// Chunk result structure for C ABI
@@ -27,6 +39,10 @@ pub struct ChunkResult {
     pub mask_data_size: i32,
     pub biome_data: *mut u8,
     pub biome_data_size: i32,
+    pub carve_data: *mut u8,
+    pub carve_data_size: i32,
+    pub structure_data: *mut u8,
+    pub structure_data_size: i32,
     pub status: i32, // 0 = success, 1 = error, 2 = not ready
 }
 
@@ -48,6 +64,7 @@ impl ChunkJob {
         tick_count: i32,
         rule_version: *const c_char,
     ) -> Self {
+        let defaults = WorldGenSettings::default();
         Self {
             chunk_x,
             chunk_z,
@@ -55,6 +72,13 @@ impl ChunkJob {
             dimension,
             tick_count,
             rule_version,
+            sea_level: defaults.sea_level,
+            octaves: defaults.octaves,
+            amplified: 0,
+            preset: std::ptr::null(),
+            height_scale: 1.0,
+            horizontal_scale: 1.0,
+            biome_scale: 1.0,
         }
     }
     
@@ -71,6 +95,37 @@ impl ChunkJob {
         }
     }
     
+    /// World settings carried by the job
+    pub fn get_settings(&self) -> Result<WorldGenSettings, String> {
+        let preset_name = unsafe {
+            if self.preset.is_null() {
+                String::new()
+            } else {
+                std::ffi::CStr::from_ptr(self.preset).to_string_lossy().to_string()
+            }
+        };
+        let preset = if preset_name.eq_ignore_ascii_case("custom") {
+            TerrainPreset::Custom {
+                height_scale: self.height_scale,
+                horizontal_scale: self.horizontal_scale,
+                biome_scale: self.biome_scale,
+                caves: true,
+            }
+        } else {
+            preset_name.parse()?
+        };
+
+        let defaults = WorldGenSettings::default();
+        let settings = WorldGenSettings {
+            sea_level: self.sea_level,
+            octaves: if self.octaves == 0 { defaults.octaves } else { self.octaves },
+            amplified: self.amplified != 0,
+            preset,
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+
     /// Get rule version as string
     pub fn get_rule_version(&self) -> String {
         unsafe {
@@ -87,6 +142,7 @@ impl ChunkJob {
 
 impl ChunkResult {
     /// Create a new chunk result
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         chunk_x: i32,
         chunk_z: i32,
@@ -95,6 +151,8 @@ impl ChunkResult {
         density_data: Vec<u8>,
         mask_data: Vec<u8>,
         biome_data: Vec<u8>,
+        carve_data: Vec<u8>,
+        structure_data: Vec<u8>,
     ) -> Self {
         let hash_cstring = CString::new(content_hash).unwrap();
         let hash_ptr = hash_cstring.into_raw();
@@ -117,6 +175,18 @@ impl ChunkResult {
             biome_data.as_ptr() as *mut u8
         };
         
+        let carve_ptr = if carve_data.is_empty() {
+            std::ptr::null_mut()
+        } else {
+            carve_data.as_ptr() as *mut u8
+        };
+        
+        let structure_ptr = if structure_data.is_empty() {
+            std::ptr::null_mut()
+        } else {
+            structure_data.as_ptr() as *mut u8
+        };
+        
         Self {
             chunk_x,
             chunk_z,
@@ -128,6 +198,10 @@ impl ChunkResult {
             mask_data_size: mask_data.len() as i32,
             biome_data: biome_ptr,
             biome_data_size: biome_data.len() as i32,
+            carve_data: carve_ptr,
+            carve_data_size: carve_data.len() as i32,
+            structure_data: structure_ptr,
+            structure_data_size: structure_data.len() as i32,
             status: 0, // success
         }
     }
@@ -177,6 +251,28 @@ impl ChunkResult {
             }
         }
     }
+    
+    /// Get carver mask data as slice
+    pub fn get_carve_data(&self) -> &[u8] {
+        unsafe {
+            if self.carve_data.is_null() || self.carve_data_size <= 0 {
+                &[]
+            } else {
+                std::slice::from_raw_parts(self.carve_data, self.carve_data_size as usize)
+            }
+        }
+    }
+    
+    /// Get structure candidate data as slice
+    pub fn get_structure_data(&self) -> &[u8] {
+        unsafe {
+            if self.structure_data.is_null() || self.structure_data_size <= 0 {
+                &[]
+            } else {
+                std::slice::from_raw_parts(self.structure_data, self.structure_data_size as usize)
+            }
+        }
+    }
 }
 
 impl Drop for ChunkResult {
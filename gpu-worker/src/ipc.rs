@@ -0,0 +1,498 @@
+//! Local IPC protocol between the GPU worker and hostd.
+//!
+//! Messages are newline-delimited JSON over a Unix domain socket (or a named
+//! pipe on Windows). Every request gets exactly one response line.
+//!
+//! Chunk payloads can instead travel through the worker's shared-memory
+//! result ring (see [`crate::shm`]): a client asks for the ring with a
+//! `shared_memory` handshake and then waits with `shared: true`.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize, Serializer};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tracing::{debug, info, warn};
+
+use crate::queue::{ChunkOutput, ChunkRequest, GpuWorkerHandle, HealthReport, JobId, JobStatus};
+use crate::selftest::{SelfTestConfig, SelfTestReport};
+use crate::shm::{ResultRing, RingInfo, SharedChunk, ShmError, SlotRef};
+
+/// Requests a client can send to the worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum IpcRequest {
+    Submit { jobs: Vec<ChunkRequest> },
+    Status { id: JobId },
+    Wait {
+        id: JobId,
+        /// Answer with a shared-memory slot when the ring has room
+        #[serde(default)]
+        shared: bool,
+    },
+    Cancel { id: JobId },
+    Health,
+    /// Handshake for the shared-memory result ring
+    SharedMemory,
+    SelfTest {
+        #[serde(default)]
+        config: SelfTestConfig,
+    },
+}
+
+/// Responses sent back by the worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IpcResponse {
+    Submitted { ids: Vec<JobId> },
+    Status { status: JobStatus },
+    Completed { output: ChunkOutput },
+    CompletedShared { slot: SlotRef },
+    Cancelled { cancelled: bool },
+    Health(HealthReport),
+    SharedMemory { ring: Option<RingInfo> },
+    SelfTest(SelfTestReport),
+    Error { message: String },
+}
+
+/// Errors seen by IPC clients
+#[derive(Debug, thiserror::Error)]
+pub enum IpcError {
+    #[error("GPU worker IPC transport error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed GPU worker IPC message: {0}")]
+    Protocol(#[from] serde_json::Error),
+    #[error("GPU worker error: {0}")]
+    Remote(String),
+    #[error("GPU worker shared memory error: {0}")]
+    SharedMemory(#[from] ShmError),
+    #[error("unexpected response from GPU worker")]
+    UnexpectedResponse,
+}
+
+/// Default endpoint shared by the standalone worker and hostd
+pub fn default_endpoint() -> String {
+    #[cfg(windows)]
+    {
+        r"\\.\pipe\guardian-gpu-worker".to_string()
+    }
+    #[cfg(not(windows))]
+    {
+        std::env::temp_dir()
+            .join("guardian-gpu-worker.sock")
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// A finished chunk, either read in place from the result ring or sent inline
+pub enum ChunkView<'a> {
+    Shared(SharedChunk<'a>),
+    Inline(ChunkOutput),
+}
+
+impl ChunkView<'_> {
+    /// Copy the result into an owned output
+    pub fn into_output(self) -> ChunkOutput {
+        match self {
+            ChunkView::Shared(chunk) => chunk.to_output(),
+            ChunkView::Inline(output) => output,
+        }
+    }
+}
+
+/// Serializes as a [`ChunkOutput`] either way
+impl Serialize for ChunkView<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ChunkView::Shared(chunk) => chunk.serialize(serializer),
+            ChunkView::Inline(output) => output.serialize(serializer),
+        }
+    }
+}
+
+/// Answer a single request against the local queue
+async fn dispatch(handle: &GpuWorkerHandle, ring: Option<&ResultRing>, request: IpcRequest) -> IpcResponse {
+    match request {
+        IpcRequest::Submit { jobs } => match handle.submit_batch(jobs) {
+            Ok(ids) => IpcResponse::Submitted { ids },
+            Err(e) => IpcResponse::Error { message: e.to_string() },
+        },
+        IpcRequest::Status { id } => match handle.status(id) {
+            Some(status) => IpcResponse::Status { status },
+            None => IpcResponse::Error { message: format!("GPU job {} not found", id) },
+        },
+        IpcRequest::Wait { id, shared } => match handle.wait(id).await {
+            // A full ring falls back to sending the payload inline
+            Ok(output) => match ring.filter(|_| shared).and_then(|ring| ring.write(&output)) {
+                Some(slot) => IpcResponse::CompletedShared { slot },
+                None => IpcResponse::Completed { output },
+            },
+            Err(e) => IpcResponse::Error { message: e.to_string() },
+        },
+        IpcRequest::Cancel { id } => IpcResponse::Cancelled { cancelled: handle.cancel(id) },
+        IpcRequest::Health => IpcResponse::Health(handle.health()),
+        IpcRequest::SharedMemory => IpcResponse::SharedMemory { ring: ring.map(ResultRing::info) },
+        IpcRequest::SelfTest { config } => match handle.self_test(config).await {
+            Ok(report) => IpcResponse::SelfTest(report),
+            Err(e) => IpcResponse::Error { message: e.to_string() },
+        },
+    }
+}
+
+async fn handle_connection<S>(handle: GpuWorkerHandle, ring: Option<Arc<ResultRing>>, stream: S) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<IpcRequest>(&line) {
+            Ok(request) => dispatch(&handle, ring.as_deref(), request).await,
+            Err(e) => IpcResponse::Error { message: format!("invalid request: {}", e) },
+        };
+
+        let mut payload = serde_json::to_vec(&response)?;
+        payload.push(b'\n');
+        writer.write_all(&payload).await?;
+        writer.flush().await?;
+    }
+
+    Ok(())
+}
+
+/// IPC listener bound to a local endpoint
+pub struct IpcServer {
+    endpoint: String,
+    /// Shared-memory ring offered to clients
+    ring: Option<Arc<ResultRing>>,
+    #[cfg(unix)]
+    listener: tokio::net::UnixListener,
+    #[cfg(windows)]
+    pipe: tokio::net::windows::named_pipe::NamedPipeServer,
+}
+
+impl IpcServer {
+    /// Bind the endpoint so clients can connect as soon as this returns
+    #[cfg(unix)]
+    pub fn bind(endpoint: &str) -> std::io::Result<Self> {
+        // A socket file left behind by a crashed worker blocks bind()
+        if std::path::Path::new(endpoint).exists() {
+            std::fs::remove_file(endpoint)?;
+        }
+        let listener = tokio::net::UnixListener::bind(endpoint)?;
+        Ok(Self { endpoint: endpoint.to_string(), ring: None, listener })
+    }
+
+    /// Bind the endpoint so clients can connect as soon as this returns
+    #[cfg(windows)]
+    pub fn bind(endpoint: &str) -> std::io::Result<Self> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+        let pipe = ServerOptions::new().first_pipe_instance(true).create(endpoint)?;
+        Ok(Self { endpoint: endpoint.to_string(), ring: None, pipe })
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Offer a shared-memory result ring to clients that negotiate one
+    pub fn with_result_ring(mut self, ring: ResultRing) -> Self {
+        self.ring = Some(Arc::new(ring));
+        self
+    }
+
+    /// Accept connections until the listener fails
+    #[cfg(unix)]
+    pub async fn run(self, handle: GpuWorkerHandle) -> std::io::Result<()> {
+        info!("GPU worker IPC listening on {}", self.endpoint);
+        loop {
+            let (stream, _) = self.listener.accept().await?;
+            let handle = handle.clone();
+            let ring = self.ring.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(handle, ring, stream).await {
+                    debug!("GPU worker IPC connection closed: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Accept connections until the listener fails
+    #[cfg(windows)]
+    pub async fn run(self, handle: GpuWorkerHandle) -> std::io::Result<()> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+        info!("GPU worker IPC listening on {}", self.endpoint);
+        let mut pipe = self.pipe;
+        loop {
+            pipe.connect().await?;
+            let connected = pipe;
+            pipe = ServerOptions::new().create(&self.endpoint)?;
+            let handle = handle.clone();
+            let ring = self.ring.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(handle, ring, connected).await {
+                    debug!("GPU worker IPC connection closed: {}", e);
+                }
+            });
+        }
+    }
+}
+
+/// Client used by hostd to talk to a GPU worker over IPC
+#[derive(Debug, Clone)]
+pub struct IpcClient {
+    endpoint: String,
+    /// The worker's result ring, once negotiated
+    ring: Option<Arc<ResultRing>>,
+}
+
+impl IpcClient {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into(), ring: None }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Send one request on a fresh connection so long waits never block other callers
+    async fn request(&self, request: &IpcRequest) -> Result<IpcResponse, IpcError> {
+        #[cfg(unix)]
+        let stream = tokio::net::UnixStream::connect(&self.endpoint).await?;
+        #[cfg(windows)]
+        let stream = self.connect_pipe().await?;
+
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut payload = serde_json::to_vec(request)?;
+        payload.push(b'\n');
+        writer.write_all(&payload).await?;
+        writer.flush().await?;
+
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await?;
+        if line.is_empty() {
+            return Err(IpcError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+
+        match serde_json::from_str(&line)? {
+            IpcResponse::Error { message } => Err(IpcError::Remote(message)),
+            response => Ok(response),
+        }
+    }
+
+    #[cfg(windows)]
+    async fn connect_pipe(&self) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+        use tokio::net::windows::named_pipe::ClientOptions;
+        const ERROR_PIPE_BUSY: i32 = 231;
+
+        let mut attempts = 0;
+        loop {
+            match ClientOptions::new().open(&self.endpoint) {
+                Ok(client) => return Ok(client),
+                Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) && attempts < 20 => {
+                    attempts += 1;
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Queue a batch of chunk jobs
+    pub async fn submit_batch(&self, jobs: Vec<ChunkRequest>) -> Result<Vec<JobId>, IpcError> {
+        match self.request(&IpcRequest::Submit { jobs }).await? {
+            IpcResponse::Submitted { ids } => Ok(ids),
+            _ => Err(IpcError::UnexpectedResponse),
+        }
+    }
+
+    /// Queue a single chunk job
+    pub async fn submit(&self, job: ChunkRequest) -> Result<JobId, IpcError> {
+        self.submit_batch(vec![job])
+            .await?
+            .pop()
+            .ok_or(IpcError::UnexpectedResponse)
+    }
+
+    pub async fn status(&self, id: JobId) -> Result<JobStatus, IpcError> {
+        match self.request(&IpcRequest::Status { id }).await? {
+            IpcResponse::Status { status } => Ok(status),
+            _ => Err(IpcError::UnexpectedResponse),
+        }
+    }
+
+    /// Block until the job finishes and return its output
+    pub async fn wait(&self, id: JobId) -> Result<ChunkOutput, IpcError> {
+        Ok(self.wait_view(id).await?.into_output())
+    }
+
+    /// Block until the job finishes; reads the result in place when a ring was negotiated
+    pub async fn wait_view(&self, id: JobId) -> Result<ChunkView<'_>, IpcError> {
+        let shared = self.ring.is_some();
+        match self.request(&IpcRequest::Wait { id, shared }).await? {
+            IpcResponse::Completed { output } => Ok(ChunkView::Inline(output)),
+            IpcResponse::CompletedShared { slot } => match &self.ring {
+                Some(ring) => Ok(ChunkView::Shared(ring.read(slot)?)),
+                None => Err(IpcError::UnexpectedResponse),
+            },
+            _ => Err(IpcError::UnexpectedResponse),
+        }
+    }
+
+    /// Map the worker's shared-memory result ring; `None` when the worker has none
+    pub async fn negotiate_shared_memory(&mut self) -> Result<Option<RingInfo>, IpcError> {
+        match self.request(&IpcRequest::SharedMemory).await? {
+            IpcResponse::SharedMemory { ring: Some(info) } => {
+                let ring = ResultRing::open(&info.path)?;
+                self.ring = Some(Arc::new(ring));
+                Ok(Some(info))
+            }
+            IpcResponse::SharedMemory { ring: None } => {
+                self.ring = None;
+                Ok(None)
+            }
+            _ => Err(IpcError::UnexpectedResponse),
+        }
+    }
+
+    /// Whether results come back through shared memory
+    pub fn uses_shared_memory(&self) -> bool {
+        self.ring.is_some()
+    }
+
+    pub async fn cancel(&self, id: JobId) -> Result<bool, IpcError> {
+        match self.request(&IpcRequest::Cancel { id }).await? {
+            IpcResponse::Cancelled { cancelled } => Ok(cancelled),
+            _ => Err(IpcError::UnexpectedResponse),
+        }
+    }
+
+    pub async fn health(&self) -> Result<HealthReport, IpcError> {
+        match self.request(&IpcRequest::Health).await {
+            Ok(IpcResponse::Health(report)) => Ok(report),
+            Ok(_) => Err(IpcError::UnexpectedResponse),
+            Err(e) => {
+                warn!("GPU worker health check at {} failed: {}", self.endpoint, e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Run the determinism self-test on the worker
+    pub async fn self_test(&self, config: SelfTestConfig) -> Result<SelfTestReport, IpcError> {
+        match self.request(&IpcRequest::SelfTest { config }).await? {
+            IpcResponse::SelfTest(report) => Ok(report),
+            _ => Err(IpcError::UnexpectedResponse),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_wire_format() {
+        let request = IpcRequest::Submit {
+            jobs: vec![ChunkRequest {
+                chunk_x: 3,
+                chunk_z: -4,
+                seed: 42,
+                dimension: "nether".to_string(),
+                settings: Default::default(),
+                priority: Default::default(),
+            }],
+        };
+
+        let line = serde_json::to_string(&request).unwrap();
+        assert!(line.contains(r#""op":"submit""#));
+
+        match serde_json::from_str::<IpcRequest>(&line).unwrap() {
+            IpcRequest::Submit { jobs } => {
+                assert_eq!(jobs.len(), 1);
+                assert_eq!(jobs[0].chunk_z, -4);
+                assert_eq!(jobs[0].dimension, "nether");
+            }
+            other => panic!("unexpected request: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_dimension_defaults_to_overworld() {
+        let request: IpcRequest =
+            serde_json::from_str(r#"{"op":"submit","jobs":[{"chunk_x":0,"chunk_z":0,"seed":1}]}"#).unwrap();
+        match request {
+            IpcRequest::Submit { jobs } => assert_eq!(jobs[0].dimension, "overworld"),
+            other => panic!("unexpected request: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_self_test_config_defaults() {
+        let request: IpcRequest = serde_json::from_str(r#"{"op":"self_test"}"#).unwrap();
+        match request {
+            IpcRequest::SelfTest { config } => assert_eq!(config.iterations, SelfTestConfig::default().iterations),
+            other => panic!("unexpected request: {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_wait_through_shared_memory() {
+        use crate::kernels::CpuChunkGenerator;
+        use crate::queue::{GpuWorkerService, QueueConfig};
+        use crate::GpuWorker;
+
+        let name = format!("gpu-worker-ipc-test-{}", uuid::Uuid::new_v4());
+        let endpoint = std::env::temp_dir().join(format!("{}.sock", name)).to_string_lossy().to_string();
+        let ring = ResultRing::create(std::env::temp_dir().join(format!("{}.results", name)), 2).unwrap();
+
+        let handle = GpuWorkerService::spawn(GpuWorker::with_generator(Box::new(CpuChunkGenerator::new())), QueueConfig::default());
+        let server = IpcServer::bind(&endpoint).unwrap().with_result_ring(ring);
+        let server_task = tokio::spawn(server.run(handle.clone()));
+
+        let job = ChunkRequest {
+            chunk_x: 2,
+            chunk_z: 9,
+            seed: 5,
+            dimension: "overworld".to_string(),
+            settings: Default::default(),
+            priority: Default::default(),
+        };
+        let inline_client = IpcClient::new(endpoint.clone());
+        let inline = inline_client.wait(inline_client.submit(job.clone()).await.unwrap()).await.unwrap();
+
+        let mut client = IpcClient::new(endpoint);
+        assert!(client.negotiate_shared_memory().await.unwrap().is_some());
+        let id = client.submit(job).await.unwrap();
+        match client.wait_view(id).await.unwrap() {
+            ChunkView::Shared(chunk) => {
+                assert_eq!(chunk.content_hash(), inline.content_hash);
+                assert_eq!(chunk.density_data(), &inline.density_data[..]);
+            }
+            ChunkView::Inline(_) => panic!("expected a shared-memory result"),
+        }
+
+        server_task.abort();
+        handle.shutdown();
+    }
+
+    #[test]
+    fn test_status_response_round_trip() {
+        let response = IpcResponse::Status {
+            status: JobStatus::Failed { error: "device lost".to_string() },
+        };
+        let line = serde_json::to_string(&response).unwrap();
+        match serde_json::from_str::<IpcResponse>(&line).unwrap() {
+            IpcResponse::Status { status } => {
+                assert_eq!(status, JobStatus::Failed { error: "device lost".to_string() })
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,461 @@
+use wgpu::*;
+use anyhow::Result;
+
+/// Size in bytes of one chunk's biome grid
+pub const BIOME_BUFFER_SIZE: u64 = (16 * 16 * std::mem::size_of::<u32>()) as u64;
+
+// Vanilla numeric biome IDs (mirrored in biome.wgsl)
+pub const OCEAN: u32 = 0;
+pub const PLAINS: u32 = 1;
+pub const DESERT: u32 = 2;
+pub const MOUNTAINS: u32 = 3;
+pub const FOREST: u32 = 4;
+pub const TAIGA: u32 = 5;
+pub const SWAMP: u32 = 6;
+pub const NETHER_WASTES: u32 = 8;
+pub const THE_END: u32 = 9;
+pub const FROZEN_OCEAN: u32 = 10;
+pub const SNOWY_TUNDRA: u32 = 12;
+pub const JUNGLE: u32 = 21;
+pub const DEEP_OCEAN: u32 = 24;
+pub const BIRCH_FOREST: u32 = 27;
+pub const DARK_FOREST: u32 = 29;
+pub const SNOWY_TAIGA: u32 = 30;
+pub const GIANT_TREE_TAIGA: u32 = 32;
+pub const SAVANNA: u32 = 35;
+pub const BADLANDS: u32 = 37;
+pub const SMALL_END_ISLANDS: u32 = 40;
+pub const END_MIDLANDS: u32 = 41;
+pub const END_HIGHLANDS: u32 = 42;
+pub const END_BARRENS: u32 = 43;
+pub const WARM_OCEAN: u32 = 44;
+pub const SOUL_SAND_VALLEY: u32 = 170;
+pub const CRIMSON_FOREST: u32 = 171;
+pub const WARPED_FOREST: u32 = 172;
+pub const BASALT_DELTAS: u32 = 173;
+
+const TEMPERATURE_SALT: u32 = 0x2545f491;
+const HUMIDITY_SALT: u32 = 0x9e3779b9;
+const CONTINENTAL_SALT: u32 = 0x5deece66;
+const EROSION_SALT: u32 = 0x7f4a7c15;
+
+/// Biome kernel that fills the 16x16 biome grid of a chunk
+pub struct BiomeKernel {
+    bind_group_layout: BindGroupLayout,
+    compute_pipeline: ComputePipeline,
+}
+
+impl BiomeKernel {
+    /// Create a new biome kernel
+    pub async fn new(device: &Device) -> Result<Self> {
+        // Create bind group layout for biome classification
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Biome Kernel Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        // Create compute pipeline
+        let compute_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Biome Kernel Pipeline"),
+            layout: Some(&device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Biome Kernel Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            })),
+            module: &device.create_shader_module(ShaderModuleDescriptor {
+                label: Some("Biome Kernel Shader"),
+                source: ShaderSource::Wgsl(include_str!("biome.wgsl").into()),
+            }),
+            entry_point: "main",
+        });
+
+        Ok(Self {
+            bind_group_layout,
+            compute_pipeline,
+        })
+    }
+
+    /// Classify all 256 columns of a chunk into `biome_buffer`
+    pub async fn generate_biomes(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        biome_buffer: &Buffer,
+        params_buffer: &Buffer,
+    ) -> Result<()> {
+        // Create bind group
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Biome Kernel Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: biome_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        // Create command encoder
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Biome Generation Encoder"),
+        });
+
+        // One 16x16 workgroup covers the whole chunk
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("Biome Generation Compute Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.compute_pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        }
+
+        // Submit command buffer
+        queue.submit(std::iter::once(encoder.finish()));
+
+        Ok(())
+    }
+}
+
+// CPU reference implementation of biome.wgsl, used for verification and snapshot tests
+
+fn hash2(x: i32, z: i32, seed: u32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x27d4eb2d)
+        ^ (z as u32).wrapping_mul(0x165667b1)
+        ^ seed.wrapping_mul(0x9e3779b9);
+    h = (h ^ (h >> 15)).wrapping_mul(0x85ebca6b);
+    h = (h ^ (h >> 13)).wrapping_mul(0xc2b2ae35);
+    h ^ (h >> 16)
+}
+
+fn lattice(x: i32, z: i32, seed: u32) -> f32 {
+    (hash2(x, z, seed) & 0xffffff) as f32 / 16777215.0 * 2.0 - 1.0
+}
+
+fn value_noise(x: f32, z: f32, seed: u32) -> f32 {
+    let x0 = x.floor();
+    let z0 = z.floor();
+    let fx = x - x0;
+    let fz = z - z0;
+    let u = fx * fx * (3.0 - 2.0 * fx);
+    let v = fz * fz * (3.0 - 2.0 * fz);
+
+    let ix = x0 as i32;
+    let iz = z0 as i32;
+    let a = lattice(ix, iz, seed);
+    let b = lattice(ix.wrapping_add(1), iz, seed);
+    let c = lattice(ix, iz.wrapping_add(1), seed);
+    let d = lattice(ix.wrapping_add(1), iz.wrapping_add(1), seed);
+
+    let top = a + (b - a) * u;
+    let bottom = c + (d - c) * u;
+    top + (bottom - top) * v
+}
+
+fn fbm(x: f32, z: f32, seed: u32, octaves: u32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.0;
+    let mut total = 0.0;
+
+    for i in 0..octaves {
+        value += value_noise(x * frequency, z * frequency, seed.wrapping_add(i.wrapping_mul(0x632be5ab))) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    value / total
+}
+
+fn classify_overworld(world_x: f32, world_z: f32, seed: u32) -> u32 {
+    let continental = fbm(world_x / 1024.0, world_z / 1024.0, seed ^ CONTINENTAL_SALT, 4);
+    let temperature = fbm(world_x / 512.0, world_z / 512.0, seed ^ TEMPERATURE_SALT, 4);
+    let humidity = fbm(world_x / 384.0, world_z / 384.0, seed ^ HUMIDITY_SALT, 4);
+
+    if continental < -0.3 {
+        return if temperature < -0.45 {
+            FROZEN_OCEAN
+        } else if temperature > 0.5 {
+            WARM_OCEAN
+        } else if continental < -0.6 {
+            DEEP_OCEAN
+        } else {
+            OCEAN
+        };
+    }
+
+    if temperature < -0.45 {
+        if humidity >= 0.2 { SNOWY_TAIGA } else { SNOWY_TUNDRA }
+    } else if temperature < -0.15 {
+        if humidity < -0.25 {
+            MOUNTAINS
+        } else if humidity >= 0.25 {
+            GIANT_TREE_TAIGA
+        } else {
+            TAIGA
+        }
+    } else if temperature < 0.2 {
+        if humidity < -0.3 {
+            PLAINS
+        } else if humidity < 0.1 {
+            FOREST
+        } else if humidity >= 0.4 {
+            DARK_FOREST
+        } else {
+            BIRCH_FOREST
+        }
+    } else if temperature < 0.5 {
+        if humidity < -0.2 {
+            SAVANNA
+        } else if humidity >= 0.35 {
+            SWAMP
+        } else {
+            PLAINS
+        }
+    } else if humidity < 0.0 {
+        DESERT
+    } else if humidity >= 0.3 {
+        JUNGLE
+    } else {
+        BADLANDS
+    }
+}
+
+fn classify_nether(world_x: f32, world_z: f32, seed: u32) -> u32 {
+    let t = fbm(world_x / 256.0, world_z / 256.0, seed ^ TEMPERATURE_SALT, 3) * 2.0;
+    let h = fbm(world_x / 256.0, world_z / 256.0, seed ^ HUMIDITY_SALT, 3) * 2.0;
+
+    // Same evaluation order as the shader so ties resolve identically
+    let candidates = [
+        (SOUL_SAND_VALLEY, t * t + (h + 0.5) * (h + 0.5)),
+        (CRIMSON_FOREST, (t - 0.4) * (t - 0.4) + h * h),
+        (WARPED_FOREST, t * t + (h - 0.5) * (h - 0.5)),
+        (BASALT_DELTAS, (t + 0.5) * (t + 0.5) + h * h),
+    ];
+
+    let mut best = NETHER_WASTES;
+    let mut best_distance = t * t + h * h;
+    for (biome, distance) in candidates {
+        if distance < best_distance {
+            best = biome;
+            best_distance = distance;
+        }
+    }
+    best
+}
+
+fn classify_end(world_x: f32, world_z: f32, seed: u32) -> u32 {
+    let chunk_x = (world_x / 16.0).floor() as i32;
+    let chunk_z = (world_z / 16.0).floor() as i32;
+    if chunk_x * chunk_x + chunk_z * chunk_z <= 4096 {
+        return THE_END;
+    }
+
+    let erosion = fbm(world_x / 128.0, world_z / 128.0, seed ^ EROSION_SALT, 3);
+    if erosion > 0.25 {
+        END_HIGHLANDS
+    } else if erosion >= -0.0625 {
+        END_MIDLANDS
+    } else if erosion < -0.21875 {
+        SMALL_END_ISLANDS
+    } else {
+        END_BARRENS
+    }
+}
+
+/// Biome of a single block column (dimension: 0 = overworld, 1 = nether, 2 = end)
+pub fn classify_column(world_x: i32, world_z: i32, seed: u32, dimension: u32) -> u32 {
+    classify_column_scaled(world_x, world_z, seed, dimension, 1.0)
+}
+
+/// Biome of a column with overworld biomes stretched by `biome_scale` (4.0 for large biomes)
+pub fn classify_column_scaled(world_x: i32, world_z: i32, seed: u32, dimension: u32, biome_scale: f32) -> u32 {
+    let x = world_x as f32;
+    let z = world_z as f32;
+    match dimension {
+        1 => classify_nether(x, z, seed),
+        2 => classify_end(x, z, seed),
+        _ => classify_overworld(x / biome_scale, z / biome_scale, seed),
+    }
+}
+
+/// CPU equivalent of one biome kernel dispatch, indexed `z * 16 + x`
+pub fn classify_chunk(chunk_x: i32, chunk_z: i32, seed: u32, dimension: u32) -> [u32; 256] {
+    classify_chunk_scaled(chunk_x, chunk_z, seed, dimension, 1.0)
+}
+
+/// [`classify_chunk`] with overworld biomes stretched by `biome_scale`
+pub fn classify_chunk_scaled(chunk_x: i32, chunk_z: i32, seed: u32, dimension: u32, biome_scale: f32) -> [u32; 256] {
+    let mut biomes = [0u32; 256];
+    for z in 0..16 {
+        for x in 0..16 {
+            biomes[z * 16 + x] =
+                classify_column_scaled(chunk_x * 16 + x as i32, chunk_z * 16 + z as i32, seed, dimension, biome_scale);
+        }
+    }
+    biomes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::{ChunkParams, WorldGenSettings};
+
+    /// Sample a `size` x `size` grid of columns `step` blocks apart, one row per line
+    fn render_map(seed: u32, dimension: u32, origin_x: i32, origin_z: i32, size: i32, step: i32) -> String {
+        let mut out = String::new();
+        for row in 0..size {
+            let line: Vec<String> = (0..size)
+                .map(|col| classify_column(origin_x + col * step, origin_z + row * step, seed, dimension).to_string())
+                .collect();
+            out.push_str(&line.join(" "));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Compare against a layout this classifier produced earlier; run with BLESS_SNAPSHOTS=1 to
+    /// regenerate after intended changes. The snapshots only catch unintended drift in our own
+    /// output. They are not vanilla references and say nothing about matching real worldgen.
+    fn assert_snapshot(name: &str, actual: &str) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(name);
+        if std::env::var("BLESS_SNAPSHOTS").is_ok() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("missing snapshot {}: {}", path.display(), e));
+        assert_eq!(expected.replace("\r\n", "\n"), actual, "biome layout drifted from {}", name);
+    }
+
+    #[test]
+    fn test_overworld_layout_matches_snapshot() {
+        assert_snapshot("biomes_overworld_12345.txt", &render_map(12345, 0, -2048, -2048, 64, 64));
+    }
+
+    #[test]
+    fn test_nether_layout_matches_snapshot() {
+        assert_snapshot("biomes_nether_12345.txt", &render_map(12345, 1, -512, -512, 64, 16));
+    }
+
+    #[test]
+    fn test_end_layout_matches_snapshot() {
+        assert_snapshot("biomes_end_12345.txt", &render_map(12345, 2, -4096, -4096, 64, 128));
+    }
+
+    #[test]
+    fn test_end_central_island() {
+        assert!(classify_chunk(0, 0, 42, 2).iter().all(|&b| b == THE_END));
+        assert!(classify_chunk(-64, 0, 42, 2).iter().all(|&b| b == THE_END));
+    }
+
+    #[test]
+    fn test_overworld_has_variety() {
+        let map = render_map(8675309, 0, -4096, -4096, 64, 128);
+        let distinct: std::collections::HashSet<&str> = map.split_whitespace().collect();
+        assert!(distinct.len() >= 6, "only {} biomes in an 8k x 8k area", distinct.len());
+    }
+
+    #[test]
+    fn test_large_biomes_stretch_overworld() {
+        for &(x, z) in &[(0, 0), (-300, 125), (1024, -2048)] {
+            assert_eq!(classify_column_scaled(x * 4, z * 4, 77, 0, 4.0), classify_column(x, z, 77, 0));
+        }
+        assert_eq!(classify_chunk_scaled(5, -9, 77, 1, 4.0), classify_chunk(5, -9, 77, 1));
+    }
+
+    #[test]
+    fn test_dimensions_use_their_own_biomes() {
+        let nether = [NETHER_WASTES, SOUL_SAND_VALLEY, CRIMSON_FOREST, WARPED_FOREST, BASALT_DELTAS];
+        assert!(classify_chunk(10, -7, 99, 1).iter().all(|b| nether.contains(b)));
+
+        let end = [THE_END, SMALL_END_ISLANDS, END_MIDLANDS, END_HIGHLANDS, END_BARRENS];
+        assert!(classify_chunk(500, 500, 99, 2).iter().all(|b| end.contains(b)));
+    }
+
+    async fn test_device() -> Option<(Device, Queue)> {
+        let instance = Instance::new(InstanceDescriptor::default());
+        let adapter = instance.request_adapter(&RequestAdapterOptions::default()).await?;
+        adapter.request_device(&DeviceDescriptor::default(), None).await.ok()
+    }
+
+    async fn run_kernel(device: &Device, queue: &Queue, kernel: &BiomeKernel, params: ChunkParams) -> Vec<u32> {
+        use wgpu::util::DeviceExt;
+
+        let params_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Biome Test Params"),
+            contents: bytemuck::bytes_of(&params),
+            usage: BufferUsages::UNIFORM,
+        });
+        let biome_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Biome Test Output"),
+            size: BIOME_BUFFER_SIZE,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Biome Test Staging"),
+            size: BIOME_BUFFER_SIZE,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        kernel.generate_biomes(device, queue, &biome_buffer, &params_buffer).await.unwrap();
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(&biome_buffer, 0, &staging_buffer, 0, BIOME_BUFFER_SIZE);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = staging_buffer.slice(..);
+        slice.map_async(MapMode::Read, |_| {});
+        device.poll(Maintain::Wait);
+        let biomes = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+        staging_buffer.unmap();
+        biomes
+    }
+
+    #[test]
+    fn test_gpu_kernel_matches_cpu_reference() {
+        let Some((device, queue)) = pollster::block_on(test_device()) else {
+            eprintln!("no GPU adapter available, skipping");
+            return;
+        };
+
+        let kernel = pollster::block_on(BiomeKernel::new(&device)).unwrap();
+        for &(chunk_x, chunk_z, dimension, name) in &[(0, 0, 0u32, "overworld"), (-37, 112, 0, "overworld"), (5, -9, 1, "nether"), (300, 40, 2, "end")] {
+            let params = ChunkParams::new(chunk_x, chunk_z, 12345, name, &WorldGenSettings::default());
+            let gpu = pollster::block_on(run_kernel(&device, &queue, &kernel, params));
+            let cpu = classify_chunk(chunk_x, chunk_z, 12345, dimension);
+            let matching = gpu.iter().zip(cpu.iter()).filter(|(a, b)| a == b).count();
+            // Allow a couple of boundary columns where GPU float rounding differs
+            assert!(matching >= 250, "chunk ({}, {}) dim {}: {} / 256 columns match", chunk_x, chunk_z, dimension, matching);
+        }
+    }
+}
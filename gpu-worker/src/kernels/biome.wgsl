@@ -0,0 +1,223 @@
+// Biome Classification GPU Shader
+// Classifies each column of a chunk from temperature/humidity noise.
+// Must stay in sync with the CPU reference in biome.rs.
+
+struct BiomeParams {
+    chunk_x: i32,
+    chunk_z: i32,
+    seed: u32,
+    dimension: u32,
+    // Leading fields of ChunkParams; only biome_scale is used here
+    sea_level: i32,
+    octaves: u32,
+    height_scale: f32,
+    horizontal_scale: f32,
+    biome_scale: f32,
+}
+
+@group(0) @binding(0)
+var<storage, read_write> biome_output: array<u32>;
+
+@group(0) @binding(1)
+var<uniform> params: BiomeParams;
+
+// Vanilla numeric biome IDs
+const OCEAN: u32 = 0u;
+const PLAINS: u32 = 1u;
+const DESERT: u32 = 2u;
+const MOUNTAINS: u32 = 3u;
+const FOREST: u32 = 4u;
+const TAIGA: u32 = 5u;
+const SWAMP: u32 = 6u;
+const NETHER_WASTES: u32 = 8u;
+const THE_END: u32 = 9u;
+const FROZEN_OCEAN: u32 = 10u;
+const SNOWY_TUNDRA: u32 = 12u;
+const JUNGLE: u32 = 21u;
+const DEEP_OCEAN: u32 = 24u;
+const BIRCH_FOREST: u32 = 27u;
+const DARK_FOREST: u32 = 29u;
+const SNOWY_TAIGA: u32 = 30u;
+const GIANT_TREE_TAIGA: u32 = 32u;
+const SAVANNA: u32 = 35u;
+const BADLANDS: u32 = 37u;
+const SMALL_END_ISLANDS: u32 = 40u;
+const END_MIDLANDS: u32 = 41u;
+const END_HIGHLANDS: u32 = 42u;
+const END_BARRENS: u32 = 43u;
+const WARM_OCEAN: u32 = 44u;
+const SOUL_SAND_VALLEY: u32 = 170u;
+const CRIMSON_FOREST: u32 = 171u;
+const WARPED_FOREST: u32 = 172u;
+const BASALT_DELTAS: u32 = 173u;
+
+// Noise channel salts
+const TEMPERATURE_SALT: u32 = 0x2545f491u;
+const HUMIDITY_SALT: u32 = 0x9e3779b9u;
+const CONTINENTAL_SALT: u32 = 0x5deece66u;
+const EROSION_SALT: u32 = 0x7f4a7c15u;
+
+fn hash2(x: i32, z: i32, seed: u32) -> u32 {
+    var h = (bitcast<u32>(x) * 0x27d4eb2du) ^ (bitcast<u32>(z) * 0x165667b1u) ^ (seed * 0x9e3779b9u);
+    h = (h ^ (h >> 15u)) * 0x85ebca6bu;
+    h = (h ^ (h >> 13u)) * 0xc2b2ae35u;
+    return h ^ (h >> 16u);
+}
+
+fn lattice(x: i32, z: i32, seed: u32) -> f32 {
+    return f32(hash2(x, z, seed) & 0xffffffu) / 16777215.0 * 2.0 - 1.0;
+}
+
+fn value_noise(x: f32, z: f32, seed: u32) -> f32 {
+    let x0 = floor(x);
+    let z0 = floor(z);
+    let fx = x - x0;
+    let fz = z - z0;
+    let u = fx * fx * (3.0 - 2.0 * fx);
+    let v = fz * fz * (3.0 - 2.0 * fz);
+
+    let ix = i32(x0);
+    let iz = i32(z0);
+    let a = lattice(ix, iz, seed);
+    let b = lattice(ix + 1, iz, seed);
+    let c = lattice(ix, iz + 1, seed);
+    let d = lattice(ix + 1, iz + 1, seed);
+
+    let top = a + (b - a) * u;
+    let bottom = c + (d - c) * u;
+    return top + (bottom - top) * v;
+}
+
+fn fbm(x: f32, z: f32, seed: u32, octaves: u32) -> f32 {
+    var value = 0.0;
+    var amplitude = 0.5;
+    var frequency = 1.0;
+    var total = 0.0;
+
+    for (var i = 0u; i < octaves; i++) {
+        value += value_noise(x * frequency, z * frequency, seed + i * 0x632be5abu) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    return value / total;
+}
+
+fn classify_overworld(world_x: f32, world_z: f32, seed: u32) -> u32 {
+    let continental = fbm(world_x / 1024.0, world_z / 1024.0, seed ^ CONTINENTAL_SALT, 4u);
+    let temperature = fbm(world_x / 512.0, world_z / 512.0, seed ^ TEMPERATURE_SALT, 4u);
+    let humidity = fbm(world_x / 384.0, world_z / 384.0, seed ^ HUMIDITY_SALT, 4u);
+
+    if (continental < -0.3) {
+        if (temperature < -0.45) {
+            return FROZEN_OCEAN;
+        }
+        if (temperature > 0.5) {
+            return WARM_OCEAN;
+        }
+        return select(OCEAN, DEEP_OCEAN, continental < -0.6);
+    }
+
+    if (temperature < -0.45) {
+        return select(SNOWY_TUNDRA, SNOWY_TAIGA, humidity >= 0.2);
+    }
+    if (temperature < -0.15) {
+        if (humidity < -0.25) {
+            return MOUNTAINS;
+        }
+        return select(TAIGA, GIANT_TREE_TAIGA, humidity >= 0.25);
+    }
+    if (temperature < 0.2) {
+        if (humidity < -0.3) {
+            return PLAINS;
+        }
+        if (humidity < 0.1) {
+            return FOREST;
+        }
+        return select(BIRCH_FOREST, DARK_FOREST, humidity >= 0.4);
+    }
+    if (temperature < 0.5) {
+        if (humidity < -0.2) {
+            return SAVANNA;
+        }
+        return select(PLAINS, SWAMP, humidity >= 0.35);
+    }
+    if (humidity < 0.0) {
+        return DESERT;
+    }
+    return select(BADLANDS, JUNGLE, humidity >= 0.3);
+}
+
+// Nearest climate point, as in the vanilla nether multi-noise source
+fn classify_nether(world_x: f32, world_z: f32, seed: u32) -> u32 {
+    let t = fbm(world_x / 256.0, world_z / 256.0, seed ^ TEMPERATURE_SALT, 3u) * 2.0;
+    let h = fbm(world_x / 256.0, world_z / 256.0, seed ^ HUMIDITY_SALT, 3u) * 2.0;
+
+    var best = NETHER_WASTES;
+    var best_distance = t * t + h * h;
+
+    let soul = t * t + (h + 0.5) * (h + 0.5);
+    if (soul < best_distance) {
+        best = SOUL_SAND_VALLEY;
+        best_distance = soul;
+    }
+    let crimson = (t - 0.4) * (t - 0.4) + h * h;
+    if (crimson < best_distance) {
+        best = CRIMSON_FOREST;
+        best_distance = crimson;
+    }
+    let warped = t * t + (h - 0.5) * (h - 0.5);
+    if (warped < best_distance) {
+        best = WARPED_FOREST;
+        best_distance = warped;
+    }
+    let basalt = (t + 0.5) * (t + 0.5) + h * h;
+    if (basalt < best_distance) {
+        best = BASALT_DELTAS;
+    }
+    return best;
+}
+
+// Central island within 1024 blocks, outer islands by erosion thresholds
+fn classify_end(world_x: f32, world_z: f32, seed: u32) -> u32 {
+    let chunk_x = i32(floor(world_x / 16.0));
+    let chunk_z = i32(floor(world_z / 16.0));
+    if (chunk_x * chunk_x + chunk_z * chunk_z <= 4096) {
+        return THE_END;
+    }
+
+    let erosion = fbm(world_x / 128.0, world_z / 128.0, seed ^ EROSION_SALT, 3u);
+    if (erosion > 0.25) {
+        return END_HIGHLANDS;
+    }
+    if (erosion >= -0.0625) {
+        return END_MIDLANDS;
+    }
+    return select(END_BARRENS, SMALL_END_ISLANDS, erosion < -0.21875);
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let x = global_id.x;
+    let z = global_id.y;
+
+    if (x >= 16u || z >= 16u) {
+        return;
+    }
+
+    let world_x = f32(params.chunk_x * 16 + i32(x));
+    let world_z = f32(params.chunk_z * 16 + i32(z));
+
+    var biome = 0u;
+    if (params.dimension == 1u) {
+        biome = classify_nether(world_x, world_z, params.seed);
+    } else if (params.dimension == 2u) {
+        biome = classify_end(world_x, world_z, params.seed);
+    } else {
+        // Large biomes stretch the overworld climate noise only
+        biome = classify_overworld(world_x / params.biome_scale, world_z / params.biome_scale, params.seed);
+    }
+
+    biome_output[z * 16u + x] = biome;
+}
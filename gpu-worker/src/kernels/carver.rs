@@ -0,0 +1,289 @@
+use wgpu::*;
+use anyhow::Result;
+
+use super::settings::FLAG_CAVES;
+use super::ChunkParams;
+
+/// Blocks in one chunk's carve mask
+pub const CARVE_CELLS: usize = 16 * 16 * 384;
+
+/// Size in bytes of one chunk's carve mask
+pub const CARVE_BUFFER_SIZE: u64 = (CARVE_CELLS * std::mem::size_of::<u32>()) as u64;
+
+// Carve mask values (mirrored in carver.wgsl)
+pub const CARVE_NONE: u32 = 0;
+pub const CARVE_AIR: u32 = 1;
+pub const CARVE_LAVA: u32 = 2;
+
+/// Nothing is carved below this y, so the floor of the world stays intact
+pub const CARVE_MIN_Y: u32 = 5;
+
+/// Carved blocks below this y fill with lava (overworld / nether)
+const OVERWORLD_LAVA_LEVEL: u32 = 10;
+const NETHER_LAVA_LEVEL: u32 = 32;
+
+const CHEESE_SALT: u32 = 0x1b873593;
+const SPAGHETTI_A_SALT: u32 = 0xcc9e2d51;
+const SPAGHETTI_B_SALT: u32 = 0xe6546b64;
+
+/// Cave carving kernel: marks the blocks that cave carvers remove, indexed `y * 256 + z * 16 + x`.
+///
+/// The mask is independent of the density field; finishing passes only apply it
+/// to blocks that are solid.
+pub struct CarverKernel {
+    bind_group_layout: BindGroupLayout,
+    compute_pipeline: ComputePipeline,
+}
+
+impl CarverKernel {
+    /// Create a new carver kernel
+    pub async fn new(device: &Device) -> Result<Self> {
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Carver Kernel Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let compute_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Carver Kernel Pipeline"),
+            layout: Some(&device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Carver Kernel Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            })),
+            module: &device.create_shader_module(ShaderModuleDescriptor {
+                label: Some("Carver Kernel Shader"),
+                source: ShaderSource::Wgsl(include_str!("carver.wgsl").into()),
+            }),
+            entry_point: "main",
+        });
+
+        Ok(Self {
+            bind_group_layout,
+            compute_pipeline,
+        })
+    }
+
+    /// Fill `carve_buffer` with the carve mask of one chunk
+    pub async fn generate_carve_mask(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        carve_buffer: &Buffer,
+        params_buffer: &Buffer,
+    ) -> Result<()> {
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Carver Kernel Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: carve_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Cave Carving Encoder"),
+        });
+
+        // One 16x16 workgroup; each invocation walks its column
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("Cave Carving Compute Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.compute_pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        }
+
+        queue.submit(std::iter::once(encoder.finish()));
+
+        Ok(())
+    }
+}
+
+// CPU reference implementation of carver.wgsl
+
+fn hash3(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x27d4eb2d)
+        ^ (y as u32).wrapping_mul(0x4f6cdd1d)
+        ^ (z as u32).wrapping_mul(0x165667b1)
+        ^ seed.wrapping_mul(0x9e3779b9);
+    h = (h ^ (h >> 15)).wrapping_mul(0x85ebca6b);
+    h = (h ^ (h >> 13)).wrapping_mul(0xc2b2ae35);
+    h ^ (h >> 16)
+}
+
+fn lattice3(x: i32, y: i32, z: i32, seed: u32) -> f32 {
+    (hash3(x, y, z, seed) & 0xffffff) as f32 / 16777215.0 * 2.0 - 1.0
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Trilinearly interpolated value noise in [-1, 1]
+fn value_noise3(x: f32, y: f32, z: f32, seed: u32) -> f32 {
+    let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+    let (fx, fy, fz) = (x - x0, y - y0, z - z0);
+    let u = fx * fx * (3.0 - 2.0 * fx);
+    let v = fy * fy * (3.0 - 2.0 * fy);
+    let w = fz * fz * (3.0 - 2.0 * fz);
+
+    let (ix, iy, iz) = (x0 as i32, y0 as i32, z0 as i32);
+    let corner = |dx: i32, dy: i32, dz: i32| lattice3(ix.wrapping_add(dx), iy.wrapping_add(dy), iz.wrapping_add(dz), seed);
+
+    let bottom = lerp(lerp(corner(0, 0, 0), corner(1, 0, 0), u), lerp(corner(0, 0, 1), corner(1, 0, 1), u), w);
+    let top = lerp(lerp(corner(0, 1, 0), corner(1, 1, 0), u), lerp(corner(0, 1, 1), corner(1, 1, 1), u), w);
+    lerp(bottom, top, v)
+}
+
+/// Carve mask value of one block (dimension: 0 = overworld, 1 = nether, 2 = end)
+pub fn carve_block(world_x: i32, y: u32, world_z: i32, seed: u32, dimension: u32, flags: u32) -> u32 {
+    if dimension == 2 || flags & FLAG_CAVES == 0 || y < CARVE_MIN_Y {
+        return CARVE_NONE;
+    }
+
+    let (x, fy, z) = (world_x as f32, y as f32, world_z as f32);
+
+    // Large open caverns
+    let cheese = value_noise3(x / 64.0, fy / 32.0, z / 64.0, seed ^ CHEESE_SALT);
+    // Tunnels where two noise fields are both near zero
+    let tunnel_a = value_noise3(x / 32.0, fy / 16.0, z / 32.0, seed ^ SPAGHETTI_A_SALT);
+    let tunnel_b = value_noise3(x / 32.0, fy / 16.0, z / 32.0, seed ^ SPAGHETTI_B_SALT);
+
+    if cheese <= 0.55 && (tunnel_a.abs() >= 0.06 || tunnel_b.abs() >= 0.06) {
+        return CARVE_NONE;
+    }
+
+    let lava_level = if dimension == 1 { NETHER_LAVA_LEVEL } else { OVERWORLD_LAVA_LEVEL };
+    if y < lava_level {
+        CARVE_LAVA
+    } else {
+        CARVE_AIR
+    }
+}
+
+/// CPU equivalent of one carver kernel dispatch
+pub fn carve_chunk(params: &ChunkParams, carve_mask: &mut [u32; CARVE_CELLS]) {
+    let (chunk_x, chunk_z, seed, dimension, flags) =
+        (params.chunk_x, params.chunk_z, params.seed, params.dimension, params.flags);
+    for y in 0..384u32 {
+        for z in 0..16 {
+            for x in 0..16 {
+                carve_mask[y as usize * 256 + z * 16 + x] =
+                    carve_block(chunk_x * 16 + x as i32, y, chunk_z * 16 + z as i32, seed, dimension, flags);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::{TerrainPreset, WorldGenSettings};
+
+    fn carve(chunk_x: i32, chunk_z: i32, dimension: &str, settings: &WorldGenSettings) -> Box<[u32; CARVE_CELLS]> {
+        let params = ChunkParams::new(chunk_x, chunk_z, 4242, dimension, settings);
+        let mut mask = Box::new([0u32; CARVE_CELLS]);
+        carve_chunk(&params, &mut mask);
+        mask
+    }
+
+    #[test]
+    fn test_overworld_has_caves_but_keeps_its_floor() {
+        let mask = carve(3, -8, "overworld", &WorldGenSettings::default());
+        let carved = mask.iter().filter(|&&m| m != CARVE_NONE).count();
+        assert!(carved > 0 && carved < CARVE_CELLS / 4, "{} carved blocks", carved);
+        assert!(mask[..CARVE_MIN_Y as usize * 256].iter().all(|&m| m == CARVE_NONE));
+        assert!(mask[..OVERWORLD_LAVA_LEVEL as usize * 256].iter().all(|&m| m != CARVE_AIR));
+    }
+
+    #[test]
+    fn test_no_caves_in_end_or_flat_worlds() {
+        assert!(carve(50, 50, "end", &WorldGenSettings::default()).iter().all(|&m| m == CARVE_NONE));
+
+        let flat = WorldGenSettings { preset: TerrainPreset::Flat, ..Default::default() };
+        assert!(carve(3, -8, "overworld", &flat).iter().all(|&m| m == CARVE_NONE));
+    }
+
+    async fn test_device() -> Option<(Device, Queue)> {
+        let instance = Instance::new(InstanceDescriptor::default());
+        let adapter = instance.request_adapter(&RequestAdapterOptions::default()).await?;
+        adapter.request_device(&DeviceDescriptor::default(), None).await.ok()
+    }
+
+    #[test]
+    fn test_gpu_kernel_matches_cpu_reference() {
+        use wgpu::util::DeviceExt;
+
+        let Some((device, queue)) = pollster::block_on(test_device()) else {
+            eprintln!("no GPU adapter available, skipping");
+            return;
+        };
+
+        let kernel = pollster::block_on(CarverKernel::new(&device)).unwrap();
+        for &(chunk_x, chunk_z, dimension) in &[(0, 0, "overworld"), (-37, 112, "overworld"), (5, -9, "nether")] {
+            let params = ChunkParams::new(chunk_x, chunk_z, 4242, dimension, &WorldGenSettings::default());
+            let params_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+                label: Some("Carver Test Params"),
+                contents: bytemuck::bytes_of(&params),
+                usage: BufferUsages::UNIFORM,
+            });
+            let carve_buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("Carver Test Output"),
+                size: CARVE_BUFFER_SIZE,
+                usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            let staging_buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("Carver Test Staging"),
+                size: CARVE_BUFFER_SIZE,
+                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            pollster::block_on(kernel.generate_carve_mask(&device, &queue, &carve_buffer, &params_buffer)).unwrap();
+            let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+            encoder.copy_buffer_to_buffer(&carve_buffer, 0, &staging_buffer, 0, CARVE_BUFFER_SIZE);
+            queue.submit(std::iter::once(encoder.finish()));
+
+            let slice = staging_buffer.slice(..);
+            slice.map_async(MapMode::Read, |_| {});
+            device.poll(Maintain::Wait);
+            let gpu: Vec<u32> = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+            staging_buffer.unmap();
+
+            let cpu = carve(chunk_x, chunk_z, dimension, &WorldGenSettings::default());
+            let matching = gpu.iter().zip(cpu.iter()).filter(|(a, b)| a == b).count();
+            // Allow blocks right on a noise threshold where GPU float rounding differs
+            assert!(matching * 1000 >= CARVE_CELLS * 998, "chunk ({}, {}) {}: {} / {} blocks match", chunk_x, chunk_z, dimension, matching, CARVE_CELLS);
+        }
+    }
+}
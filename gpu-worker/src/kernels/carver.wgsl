@@ -0,0 +1,120 @@
+// Cave Carving GPU Shader
+// Marks the blocks removed by cave carvers (cheese caverns and spaghetti tunnels).
+// Must stay in sync with the CPU reference in carver.rs.
+
+struct ChunkParams {
+    chunk_x: i32,
+    chunk_z: i32,
+    seed: u32,
+    dimension: u32,
+    sea_level: i32,
+    octaves: u32,
+    height_scale: f32,
+    horizontal_scale: f32,
+    biome_scale: f32,
+    flags: u32,
+}
+
+@group(0) @binding(0)
+var<storage, read_write> carve_output: array<u32>;
+
+@group(0) @binding(1)
+var<uniform> params: ChunkParams;
+
+// Carve mask values
+const CARVE_NONE: u32 = 0u;
+const CARVE_AIR: u32 = 1u;
+const CARVE_LAVA: u32 = 2u;
+
+const CARVE_MIN_Y: u32 = 5u;
+const OVERWORLD_LAVA_LEVEL: u32 = 10u;
+const NETHER_LAVA_LEVEL: u32 = 32u;
+
+// Mirrors settings::FLAG_CAVES
+const FLAG_CAVES: u32 = 1u;
+
+// Noise channel salts
+const CHEESE_SALT: u32 = 0x1b873593u;
+const SPAGHETTI_A_SALT: u32 = 0xcc9e2d51u;
+const SPAGHETTI_B_SALT: u32 = 0xe6546b64u;
+
+fn hash3(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    var h = (bitcast<u32>(x) * 0x27d4eb2du) ^ (bitcast<u32>(y) * 0x4f6cdd1du) ^ (bitcast<u32>(z) * 0x165667b1u) ^ (seed * 0x9e3779b9u);
+    h = (h ^ (h >> 15u)) * 0x85ebca6bu;
+    h = (h ^ (h >> 13u)) * 0xc2b2ae35u;
+    return h ^ (h >> 16u);
+}
+
+fn lattice3(x: i32, y: i32, z: i32, seed: u32) -> f32 {
+    return f32(hash3(x, y, z, seed) & 0xffffffu) / 16777215.0 * 2.0 - 1.0;
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    return a + (b - a) * t;
+}
+
+fn value_noise3(x: f32, y: f32, z: f32, seed: u32) -> f32 {
+    let x0 = floor(x);
+    let y0 = floor(y);
+    let z0 = floor(z);
+    let fx = x - x0;
+    let fy = y - y0;
+    let fz = z - z0;
+    let u = fx * fx * (3.0 - 2.0 * fx);
+    let v = fy * fy * (3.0 - 2.0 * fy);
+    let w = fz * fz * (3.0 - 2.0 * fz);
+
+    let ix = i32(x0);
+    let iy = i32(y0);
+    let iz = i32(z0);
+
+    let bottom = lerp(
+        lerp(lattice3(ix, iy, iz, seed), lattice3(ix + 1, iy, iz, seed), u),
+        lerp(lattice3(ix, iy, iz + 1, seed), lattice3(ix + 1, iy, iz + 1, seed), u),
+        w,
+    );
+    let top = lerp(
+        lerp(lattice3(ix, iy + 1, iz, seed), lattice3(ix + 1, iy + 1, iz, seed), u),
+        lerp(lattice3(ix, iy + 1, iz + 1, seed), lattice3(ix + 1, iy + 1, iz + 1, seed), u),
+        w,
+    );
+    return lerp(bottom, top, v);
+}
+
+fn carve_block(world_x: i32, y: u32, world_z: i32) -> u32 {
+    if (params.dimension == 2u || (params.flags & FLAG_CAVES) == 0u || y < CARVE_MIN_Y) {
+        return CARVE_NONE;
+    }
+
+    let x = f32(world_x);
+    let fy = f32(y);
+    let z = f32(world_z);
+
+    let cheese = value_noise3(x / 64.0, fy / 32.0, z / 64.0, params.seed ^ CHEESE_SALT);
+    let tunnel_a = value_noise3(x / 32.0, fy / 16.0, z / 32.0, params.seed ^ SPAGHETTI_A_SALT);
+    let tunnel_b = value_noise3(x / 32.0, fy / 16.0, z / 32.0, params.seed ^ SPAGHETTI_B_SALT);
+
+    if (cheese <= 0.55 && (abs(tunnel_a) >= 0.06 || abs(tunnel_b) >= 0.06)) {
+        return CARVE_NONE;
+    }
+
+    let lava_level = select(OVERWORLD_LAVA_LEVEL, NETHER_LAVA_LEVEL, params.dimension == 1u);
+    return select(CARVE_AIR, CARVE_LAVA, y < lava_level);
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let x = global_id.x;
+    let z = global_id.y;
+
+    if (x >= 16u || z >= 16u) {
+        return;
+    }
+
+    let world_x = params.chunk_x * 16 + i32(x);
+    let world_z = params.chunk_z * 16 + i32(z);
+
+    for (var y = 0u; y < 384u; y++) {
+        carve_output[y * 256u + z * 16u + x] = carve_block(world_x, y, world_z);
+    }
+}
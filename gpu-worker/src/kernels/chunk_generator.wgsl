@@ -6,13 +6,24 @@ struct ChunkParams {
     chunk_z: i32,
     seed: u32,
     dimension: u32,
+    sea_level: i32,
+    octaves: u32,
+    height_scale: f32,
+    horizontal_scale: f32,
+    biome_scale: f32,
+    flags: u32,
 }
 
+// Mirrors settings::FLAG_CAVES
+const FLAG_CAVES: u32 = 1u;
+
 struct ChunkData {
     density_data: array<f32, 98304>, // 16x16x384 density values
     mask_data: array<u32, 98304>,    // 16x16x384 mask values
     biome_data: array<u32, 256>,     // 16x16 biome values
     content_hash: u32,
+    carve_mask: array<u32, 98304>,   // filled by the carver kernel
+    structure_candidates: array<u32, 256>, // filled by the structure kernel
 }
 
 @group(0) @binding(0)
@@ -77,8 +88,14 @@ fn generate_density(x: f32, y: f32, z: f32, seed: u32, dimension: u32) -> f32 {
         let height = 64.0 + fractal_noise(world_x * 0.05, world_z * 0.05, seed, 2u) * 8.0;
         return height - y;
     } else { // Overworld
-        // Overworld terrain generation
-        let height = 64.0 + fractal_noise(world_x * 0.01, world_z * 0.01, seed, 6u) * 32.0;
+        // Overworld terrain generation, shaped by the world's settings
+        let base = f32(params.sea_level + 1);
+        let noise = fractal_noise(world_x * 0.01 / params.horizontal_scale, world_z * 0.01 / params.horizontal_scale, seed, params.octaves);
+        let height = base + noise * 32.0 * params.height_scale;
+        if ((params.flags & FLAG_CAVES) == 0u) {
+            return height - y;
+        }
+
         let cave_noise = noise3d(world_x * 0.1, y * 0.1, world_z * 0.1, seed + 1000u);
         let cave_factor = 1.0 - smoothstep(0.3, 0.7, abs(cave_noise));
         
@@ -86,29 +103,6 @@ fn generate_density(x: f32, y: f32, z: f32, seed: u32, dimension: u32) -> f32 {
     }
 }
 
-fn generate_biome(x: f32, z: f32, seed: u32) -> u32 {
-    let world_x = f32(params.chunk_x) * 16.0 + x;
-    let world_z = f32(params.chunk_z) * 16.0 + z;
-    
-    let temperature = fractal_noise(world_x * 0.01, world_z * 0.01, seed, 4u);
-    let humidity = fractal_noise(world_x * 0.01, world_z * 0.01, seed + 1000u, 4u);
-    
-    // Simple biome determination
-    if (temperature > 0.5) {
-        if (humidity > 0.5) {
-            return 1u; // Forest
-        } else {
-            return 2u; // Desert
-        }
-    } else {
-        if (humidity > 0.5) {
-            return 3u; // Taiga
-        } else {
-            return 4u; // Plains
-        }
-    }
-}
-
 fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
     let t = clamp((x - edge0) / (edge1 - edge0), 0.0, 1.0);
     return t * t * (3.0 - 2.0 * t);
@@ -134,19 +128,13 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
         let local_z = f32(z);
         
         let density = generate_density(local_x, local_y, local_z, params.seed, params.dimension);
-        let biome = generate_biome(local_x, local_z, params.seed);
         
         let index = y * 256u + z * 16u + x;
         output.density_data[index] = density;
         output.mask_data[index] = select(0u, 1u, density > 0.0);
-        
-        if (y == 0u) {
-            let biome_index = z * 16u + x;
-            output.biome_data[biome_index] = biome;
-        }
     }
     
-    // Generate content hash
+    // Generate content hash (biome_data is filled by the biome kernel before this pass)
     if (x == 0u && z == 0u) {
         var hash = u32(0);
         for (var i = 0u; i < 256u; i++) {
@@ -0,0 +1,220 @@
+use anyhow::Result;
+
+use super::settings::FLAG_CAVES;
+use super::{biome, carver, structures, Backend, ChunkData, ChunkGenerator, ChunkParams, WorldGenSettings};
+
+const COLUMNS: usize = 16 * 16;
+const HEIGHT: usize = 384;
+
+/// CPU implementation of chunk_generator.wgsl used when no GPU adapter is available.
+///
+/// Work is laid out as flat 256-lane layers (one lane per column) so the inner
+/// loops are straight-line arithmetic over arrays that the compiler can vectorize.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CpuChunkGenerator;
+
+impl CpuChunkGenerator {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[inline(always)]
+fn mix_hash(mut hash: u32) -> u32 {
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(1274126177);
+    hash ^ (hash >> 16)
+}
+
+#[inline(always)]
+fn noise2d(x: f32, z: f32, seed: u32) -> f32 {
+    let x_int = ((x * 1000.0) as u32).wrapping_add(seed & 0xFFFF);
+    let z_int = ((z * 1000.0) as u32).wrapping_add((seed >> 16) & 0xFFFF);
+
+    let hash = x_int
+        .wrapping_mul(374761393)
+        .wrapping_add(z_int.wrapping_mul(668265263))
+        .wrapping_add(1274126177);
+
+    mix_hash(hash) as f32 / 4294967295.0 * 2.0 - 1.0
+}
+
+#[inline(always)]
+fn noise3d(x: f32, y: f32, z: f32, seed: u32) -> f32 {
+    let x_int = ((x * 1000.0) as u32).wrapping_add(seed & 0xFFFF);
+    let y_int = ((y * 1000.0) as u32).wrapping_add((seed >> 8) & 0xFF);
+    let z_int = ((z * 1000.0) as u32).wrapping_add((seed >> 16) & 0xFFFF);
+
+    let hash = x_int
+        .wrapping_mul(374761393)
+        .wrapping_add(y_int.wrapping_mul(668265263))
+        .wrapping_add(z_int.wrapping_mul(1274126177))
+        .wrapping_add(1274126177);
+
+    mix_hash(hash) as f32 / 4294967295.0 * 2.0 - 1.0
+}
+
+fn fractal_noise(x: f32, z: f32, seed: u32, octaves: u32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max_value = 0.0;
+
+    for i in 0..octaves {
+        value += noise2d(x * frequency, z * frequency, seed.wrapping_add(i)) * amplitude;
+        max_value += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    value / max_value
+}
+
+#[inline(always)]
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Surface height of a column; the density field is `height - y` (plus caves in the overworld)
+fn surface_height(world_x: f32, world_z: f32, params: &ChunkParams) -> f32 {
+    let seed = params.seed;
+    match params.dimension {
+        1 => 32.0 + fractal_noise(world_x * 0.1, world_z * 0.1, seed, 4) * 16.0,
+        2 => 64.0 + fractal_noise(world_x * 0.05, world_z * 0.05, seed, 2) * 8.0,
+        _ => {
+            let horizontal_scale = params.horizontal_scale;
+            let noise = fractal_noise(
+                world_x * 0.01 / horizontal_scale,
+                world_z * 0.01 / horizontal_scale,
+                seed,
+                params.octaves,
+            );
+            (params.sea_level + 1) as f32 + noise * 32.0 * params.height_scale
+        }
+    }
+}
+
+impl ChunkGenerator for CpuChunkGenerator {
+    fn backend(&self) -> Backend {
+        Backend::Cpu
+    }
+
+    fn generate_chunk(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        seed: u32,
+        dimension: &str,
+        settings: &WorldGenSettings,
+    ) -> Result<Box<ChunkData>> {
+        let params = ChunkParams::new(chunk_x, chunk_z, seed, dimension, settings);
+        let dimension = params.dimension;
+        let caves = dimension == 0 && params.flags & FLAG_CAVES != 0;
+        let mut chunk = ChunkData::boxed_zeroed();
+
+        chunk.biome_data = biome::classify_chunk_scaled(chunk_x, chunk_z, seed, dimension, params.biome_scale);
+
+        // Column-invariant inputs, computed once per chunk
+        let mut world_x = [0f32; COLUMNS];
+        let mut world_z = [0f32; COLUMNS];
+        let mut heights = [0f32; COLUMNS];
+        for lane in 0..COLUMNS {
+            world_x[lane] = chunk_x as f32 * 16.0 + (lane % 16) as f32;
+            world_z[lane] = chunk_z as f32 * 16.0 + (lane / 16) as f32;
+            heights[lane] = surface_height(world_x[lane], world_z[lane], &params);
+        }
+
+        let cave_seed = seed.wrapping_add(1000);
+        for y in 0..HEIGHT {
+            let layer = y * COLUMNS;
+            let fy = y as f32;
+            let density = &mut chunk.density_data[layer..layer + COLUMNS];
+
+            for lane in 0..COLUMNS {
+                density[lane] = heights[lane] - fy;
+            }
+
+            if caves {
+                for lane in 0..COLUMNS {
+                    let cave_noise = noise3d(world_x[lane] * 0.1, fy * 0.1, world_z[lane] * 0.1, cave_seed);
+                    density[lane] *= 1.0 - smoothstep(0.3, 0.7, cave_noise.abs());
+                }
+            }
+
+            let mask = &mut chunk.mask_data[layer..layer + COLUMNS];
+            for (solid, &density) in mask.iter_mut().zip(&chunk.density_data[layer..layer + COLUMNS]) {
+                *solid = (density > 0.0) as u32;
+            }
+        }
+
+        // Same content hash as the GPU shader
+        chunk.content_hash = chunk
+            .biome_data
+            .iter()
+            .fold(0u32, |hash, &biome| hash.wrapping_mul(31).wrapping_add(biome));
+
+        carver::carve_chunk(&params, &mut chunk.carve_mask);
+        chunk.structure_candidates = structures::candidate_map(&params);
+
+        Ok(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_generation_is_deterministic() {
+        let generator = CpuChunkGenerator::new();
+        let a = generator.generate_chunk(4, -2, 777, "overworld", &WorldGenSettings::default()).unwrap();
+        let b = generator.generate_chunk(4, -2, 777, "overworld", &WorldGenSettings::default()).unwrap();
+
+        assert_eq!(a.content_hash, b.content_hash);
+        assert_eq!(a.biome_data, b.biome_data);
+        assert!(a.density_data.iter().zip(b.density_data.iter()).all(|(x, y)| x.to_bits() == y.to_bits()));
+    }
+
+    #[test]
+    fn test_cpu_generation_fills_terrain() {
+        let chunk = CpuChunkGenerator::new().generate_chunk(0, 0, 1, "nether", &WorldGenSettings::default()).unwrap();
+
+        // Bottom layer is solid, top of the build height is air
+        assert!(chunk.mask_data[..COLUMNS].iter().all(|&m| m == 1));
+        assert!(chunk.mask_data[(HEIGHT - 1) * COLUMNS..].iter().all(|&m| m == 0));
+        assert_eq!(chunk.biome_data, biome::classify_chunk(0, 0, 1, 1));
+    }
+
+    #[test]
+    fn test_flat_preset_is_level_at_sea_level() {
+        let settings = WorldGenSettings {
+            sea_level: 40,
+            preset: crate::kernels::TerrainPreset::Flat,
+            ..Default::default()
+        };
+        let chunk = CpuChunkGenerator::new().generate_chunk(12, -3, 5, "overworld", &settings).unwrap();
+
+        // Solid up to y = 40, air from y = 41 in every column
+        assert!(chunk.mask_data[40 * COLUMNS..41 * COLUMNS].iter().all(|&m| m == 1));
+        assert!(chunk.mask_data[41 * COLUMNS..42 * COLUMNS].iter().all(|&m| m == 0));
+    }
+
+    #[test]
+    fn test_amplified_raises_relief() {
+        let relief = |settings: &WorldGenSettings| {
+            let chunk = CpuChunkGenerator::new().generate_chunk(30, 30, 9, "overworld", settings).unwrap();
+            chunk.density_data[..COLUMNS].iter().map(|d| (d - 64.0).abs()).fold(0.0f32, f32::max)
+        };
+        // Caves off so the bottom layer is pure terrain height
+        let preset = crate::kernels::TerrainPreset::Custom {
+            height_scale: 1.0,
+            horizontal_scale: 1.0,
+            biome_scale: 1.0,
+            caves: false,
+        };
+        let normal = WorldGenSettings { preset, ..Default::default() };
+        let amplified = WorldGenSettings { amplified: true, ..normal };
+        assert!((relief(&amplified) - 2.0 * relief(&normal)).abs() < 1e-3);
+    }
+}
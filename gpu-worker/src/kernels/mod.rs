@@ -1,13 +1,23 @@
+pub mod biome;
+pub mod carver;
+mod cpu;
 mod density;
 mod mask;
+pub mod settings;
+pub mod structures;
 
 use wgpu::*;
 use wgpu::util::DeviceExt;
 use anyhow::Result;
 use bytemuck::{Pod, Zeroable};
 
+pub use biome::{BiomeKernel, BIOME_BUFFER_SIZE};
+pub use carver::{CarverKernel, CARVE_BUFFER_SIZE};
+pub use cpu::CpuChunkGenerator;
 pub use density::DensityKernel;
 pub use mask::MaskKernel;
+pub use settings::{TerrainPreset, WorldGenSettings};
+pub use structures::{StructureKernel, STRUCTURE_BUFFER_SIZE};
 
 /// Chunk generation parameters
 #[repr(C, packed)]
@@ -17,6 +27,32 @@ pub struct ChunkParams {
     pub chunk_z: i32,
     pub seed: u32,
     pub dimension: u32, // 0 = overworld, 1 = nether, 2 = end
+    pub sea_level: i32,
+    pub octaves: u32,
+    pub height_scale: f32,
+    pub horizontal_scale: f32,
+    pub biome_scale: f32,
+    pub flags: u32, // settings::FLAG_* bits
+    pub _padding: [u32; 2],
+}
+
+impl ChunkParams {
+    /// Flatten a chunk job and its world settings into the kernel uniform
+    pub fn new(chunk_x: i32, chunk_z: i32, seed: u32, dimension: &str, settings: &WorldGenSettings) -> Self {
+        Self {
+            chunk_x,
+            chunk_z,
+            seed,
+            dimension: dimension_id(dimension),
+            sea_level: settings.sea_level,
+            octaves: settings.octaves,
+            height_scale: settings.height_scale(),
+            horizontal_scale: settings.horizontal_scale(),
+            biome_scale: settings.biome_scale(),
+            flags: settings.flags(),
+            _padding: [0; 2],
+        }
+    }
 }
 
 /// Chunk generation result
@@ -27,19 +63,77 @@ pub struct ChunkData {
     pub mask_data: [u32; 16 * 16 * 384],    // 16x16x384 mask values
     pub biome_data: [u32; 16 * 16],         // 16x16 biome values
     pub content_hash: u32,
+    pub carve_mask: [u32; 16 * 16 * 384],   // carver::CARVE_* per block
+    pub structure_candidates: [u32; 16 * 16], // structures::STRUCTURE_SETS bits for the surrounding 16x16 chunks
+}
+
+impl ChunkData {
+    /// Allocate a zeroed chunk directly on the heap (the struct is ~1.2 MiB)
+    pub fn boxed_zeroed() -> Box<Self> {
+        // SAFETY: every field is a plain integer or float array, for which all-zero bytes are valid
+        unsafe { Box::<Self>::new_zeroed().assume_init() }
+    }
+}
+
+/// Which implementation produced a chunk
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Gpu,
+    Cpu,
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Gpu => write!(f, "gpu"),
+            Backend::Cpu => write!(f, "cpu"),
+        }
+    }
+}
+
+/// Common interface of the GPU and CPU chunk generators
+pub trait ChunkGenerator: Send {
+    /// Backend this generator runs on
+    fn backend(&self) -> Backend;
+
+    /// Generate density, mask, biome, carver and structure data for one chunk
+    fn generate_chunk(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        seed: u32,
+        dimension: &str,
+        settings: &WorldGenSettings,
+    ) -> Result<Box<ChunkData>>;
+}
+
+/// Map a dimension name to the id used by the kernels
+pub fn dimension_id(dimension: &str) -> u32 {
+    match dimension {
+        "overworld" => 0,
+        "nether" => 1,
+        "end" => 2,
+        _ => 0,
+    }
 }
 
 /// Chunk generator that coordinates GPU kernels for world generation
-pub struct ChunkGenerator {
+pub struct GpuChunkGenerator {
+    device: Device,
+    queue: Queue,
     density_kernel: DensityKernel,
     mask_kernel: MaskKernel,
+    biome_kernel: BiomeKernel,
+    carver_kernel: CarverKernel,
+    structure_kernel: StructureKernel,
     bind_group_layout: BindGroupLayout,
     compute_pipeline: ComputePipeline,
 }
 
-impl ChunkGenerator {
-    /// Create a new chunk generator
-    pub async fn new(device: &Device) -> Result<Self> {
+impl GpuChunkGenerator {
+    /// Create a new chunk generator that owns the device and queue
+    pub async fn new(device: Device, queue: Queue) -> Result<Self> {
         // Create bind group layout
         let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("Chunk Generator Bind Group Layout"),
@@ -83,42 +177,39 @@ impl ChunkGenerator {
         });
 
         // Initialize kernels
-        let density_kernel = DensityKernel::new(device).await?;
-        let mask_kernel = MaskKernel::new(device).await?;
+        let density_kernel = DensityKernel::new(&device).await?;
+        let mask_kernel = MaskKernel::new(&device).await?;
+        let biome_kernel = BiomeKernel::new(&device).await?;
+        let carver_kernel = CarverKernel::new(&device).await?;
+        let structure_kernel = StructureKernel::new(&device).await?;
 
         Ok(Self {
+            device,
+            queue,
             density_kernel,
             mask_kernel,
+            biome_kernel,
+            carver_kernel,
+            structure_kernel,
             bind_group_layout,
             compute_pipeline,
         })
     }
 
     /// Generate a chunk using GPU kernels
-    pub async fn generate_chunk(
+    pub async fn generate_chunk_async(
         &self,
-        device: &Device,
-        queue: &Queue,
         chunk_x: i32,
         chunk_z: i32,
         seed: u32,
         dimension: &str,
-    ) -> Result<ChunkData> {
-        // Convert dimension string to u32
-        let dimension_id = match dimension {
-            "overworld" => 0,
-            "nether" => 1,
-            "end" => 2,
-            _ => 0,
-        };
+        settings: &WorldGenSettings,
+    ) -> Result<Box<ChunkData>> {
+        let device = &self.device;
+        let queue = &self.queue;
 
         // Create chunk parameters
-        let params = ChunkParams {
-            chunk_x,
-            chunk_z,
-            seed,
-            dimension: dimension_id,
-        };
+        let params = ChunkParams::new(chunk_x, chunk_z, seed, dimension, settings);
 
         // Create buffers
         let params_data = unsafe { std::slice::from_raw_parts(&params as *const ChunkParams as *const u8, std::mem::size_of::<ChunkParams>()) };
@@ -128,12 +219,46 @@ impl ChunkGenerator {
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
         });
 
+        let output_size = std::mem::size_of::<ChunkData>() as u64;
         let output_buffer = device.create_buffer(&BufferDescriptor {
             label: Some("Chunk Output Buffer"),
-            size: std::mem::size_of::<ChunkData>() as u64,
+            size: output_size,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let staging_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Chunk Staging Buffer"),
+            size: output_size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Classify biomes first; the main pass hashes them into content_hash
+        let biome_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Chunk Biome Buffer"),
+            size: BIOME_BUFFER_SIZE,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        self.biome_kernel.generate_biomes(device, queue, &biome_buffer, &params_buffer).await?;
+
+        // Carver and structure passes feed the CPU finishing passes, not the main pass
+        let carve_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Chunk Carve Buffer"),
+            size: CARVE_BUFFER_SIZE,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        self.carver_kernel.generate_carve_mask(device, queue, &carve_buffer, &params_buffer).await?;
+
+        let structure_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Chunk Structure Buffer"),
+            size: STRUCTURE_BUFFER_SIZE,
             usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
             mapped_at_creation: false,
         });
+        self.structure_kernel.generate_candidates(device, queue, &structure_buffer, &params_buffer).await?;
 
         // Create bind group
         let bind_group = device.create_bind_group(&BindGroupDescriptor {
@@ -156,6 +281,13 @@ impl ChunkGenerator {
             label: Some("Chunk Generation Encoder"),
         });
 
+        let biome_offset = std::mem::offset_of!(ChunkData, biome_data) as u64;
+        encoder.copy_buffer_to_buffer(&biome_buffer, 0, &output_buffer, biome_offset, BIOME_BUFFER_SIZE);
+        let carve_offset = std::mem::offset_of!(ChunkData, carve_mask) as u64;
+        encoder.copy_buffer_to_buffer(&carve_buffer, 0, &output_buffer, carve_offset, CARVE_BUFFER_SIZE);
+        let structure_offset = std::mem::offset_of!(ChunkData, structure_candidates) as u64;
+        encoder.copy_buffer_to_buffer(&structure_buffer, 0, &output_buffer, structure_offset, STRUCTURE_BUFFER_SIZE);
+
         // Dispatch compute shader
         {
             let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
@@ -167,11 +299,13 @@ impl ChunkGenerator {
             compute_pass.dispatch_workgroups(16, 16, 1); // 16x16 workgroups for 16x16 chunks
         }
 
+        encoder.copy_buffer_to_buffer(&output_buffer, 0, &staging_buffer, 0, output_size);
+
         // Submit command buffer
         queue.submit(std::iter::once(encoder.finish()));
 
         // Read back results
-        let buffer_slice = output_buffer.slice(..);
+        let buffer_slice = staging_buffer.slice(..);
         let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
         buffer_slice.map_async(MapMode::Read, move |result| {
             sender.send(result).unwrap();
@@ -182,13 +316,34 @@ impl ChunkGenerator {
 
         // Get the data
         let data = buffer_slice.get_mapped_range();
-        let chunk_data = unsafe { 
-            let ptr = data.as_ptr() as *const ChunkData;
-            (*ptr).clone()
-        };
+        let mut chunk_data = ChunkData::boxed_zeroed();
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                &mut *chunk_data as *mut ChunkData as *mut u8,
+                std::mem::size_of::<ChunkData>(),
+            );
+        }
         drop(data);
-        output_buffer.unmap();
+        staging_buffer.unmap();
 
         Ok(chunk_data)
     }
-}
\ No newline at end of file
+}
+
+impl ChunkGenerator for GpuChunkGenerator {
+    fn backend(&self) -> Backend {
+        Backend::Gpu
+    }
+
+    fn generate_chunk(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        seed: u32,
+        dimension: &str,
+        settings: &WorldGenSettings,
+    ) -> Result<Box<ChunkData>> {
+        pollster::block_on(self.generate_chunk_async(chunk_x, chunk_z, seed, dimension, settings))
+    }
+}
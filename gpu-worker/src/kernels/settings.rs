@@ -0,0 +1,217 @@
+//! World-generation settings shared by the GPU kernels and the CPU generator.
+//!
+//! The defaults reproduce a vanilla `minecraft:normal` world, so chunks generated
+//! without settings are unchanged. [`WorldGenSettings::from_level_type`] maps the
+//! `level-type` from server.properties onto a preset.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Caves are carved out of the overworld density field
+pub const FLAG_CAVES: u32 = 1;
+
+/// Terrain shape of a world type
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TerrainPreset {
+    #[default]
+    Normal,
+    /// Biomes four times as wide, terrain unchanged
+    LargeBiomes,
+    /// Level ground at sea level with no caves
+    Flat,
+    /// Explicit scales, as set by a datapack or `generator-settings`
+    Custom {
+        /// Multiplier on the overworld height variation
+        height_scale: f32,
+        /// Multiplier on the horizontal size of terrain features
+        horizontal_scale: f32,
+        /// Multiplier on the size of biomes
+        biome_scale: f32,
+        #[serde(default = "default_caves")]
+        caves: bool,
+    },
+}
+
+fn default_caves() -> bool {
+    true
+}
+
+impl fmt::Display for TerrainPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TerrainPreset::Normal => write!(f, "normal"),
+            TerrainPreset::LargeBiomes => write!(f, "large_biomes"),
+            TerrainPreset::Flat => write!(f, "flat"),
+            TerrainPreset::Custom { .. } => write!(f, "custom"),
+        }
+    }
+}
+
+impl FromStr for TerrainPreset {
+    type Err = String;
+
+    /// Parse a preset name, with or without the `minecraft:` namespace
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().trim_start_matches("minecraft:").to_ascii_lowercase().as_str() {
+            "" | "normal" | "default" => Ok(TerrainPreset::Normal),
+            "large_biomes" | "largebiomes" => Ok(TerrainPreset::LargeBiomes),
+            "flat" => Ok(TerrainPreset::Flat),
+            other => Err(format!("unknown terrain preset '{}'", other)),
+        }
+    }
+}
+
+/// World-generation settings for one chunk job
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldGenSettings {
+    /// Y level of the sea surface; overworld terrain is centred one block above it
+    pub sea_level: i32,
+    /// Octaves of overworld terrain noise
+    pub octaves: u32,
+    /// Doubles the height variation of overworld terrain
+    pub amplified: bool,
+    pub preset: TerrainPreset,
+}
+
+impl Default for WorldGenSettings {
+    fn default() -> Self {
+        Self {
+            sea_level: 63,
+            octaves: 6,
+            amplified: false,
+            preset: TerrainPreset::Normal,
+        }
+    }
+}
+
+impl WorldGenSettings {
+    pub const MIN_SEA_LEVEL: i32 = -64;
+    pub const MAX_SEA_LEVEL: i32 = 320;
+    pub const MAX_OCTAVES: u32 = 16;
+
+    /// Settings for a server.properties `level-type` such as `minecraft:amplified`
+    pub fn from_level_type(level_type: &str) -> Result<Self, String> {
+        let name = level_type.trim().trim_start_matches("minecraft:").to_ascii_lowercase();
+        if name == "amplified" {
+            return Ok(Self { amplified: true, ..Self::default() });
+        }
+        Ok(Self { preset: name.parse()?, ..Self::default() })
+    }
+
+    /// Reject settings the kernels cannot generate sensibly
+    pub fn validate(&self) -> Result<(), String> {
+        if !(Self::MIN_SEA_LEVEL..=Self::MAX_SEA_LEVEL).contains(&self.sea_level) {
+            return Err(format!(
+                "sea level {} is outside the build height ({}..={})",
+                self.sea_level,
+                Self::MIN_SEA_LEVEL,
+                Self::MAX_SEA_LEVEL
+            ));
+        }
+        if self.octaves == 0 || self.octaves > Self::MAX_OCTAVES {
+            return Err(format!("octaves must be between 1 and {}", Self::MAX_OCTAVES));
+        }
+        if let TerrainPreset::Custom { height_scale, horizontal_scale, biome_scale, .. } = self.preset {
+            // A height scale of zero is a flat world; the horizontal scales divide coordinates
+            if !height_scale.is_finite() || height_scale < 0.0 {
+                return Err("height_scale must not be negative".to_string());
+            }
+            for (name, value) in [("horizontal_scale", horizontal_scale), ("biome_scale", biome_scale)] {
+                if !value.is_finite() || value <= 0.0 {
+                    return Err(format!("{} must be a positive number", name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Multiplier on the overworld height variation
+    pub fn height_scale(&self) -> f32 {
+        let base = match self.preset {
+            TerrainPreset::Flat => 0.0,
+            TerrainPreset::Custom { height_scale, .. } => height_scale,
+            _ => 1.0,
+        };
+        if self.amplified {
+            base * 2.0
+        } else {
+            base
+        }
+    }
+
+    /// Multiplier on the horizontal size of terrain features
+    pub fn horizontal_scale(&self) -> f32 {
+        match self.preset {
+            TerrainPreset::Custom { horizontal_scale, .. } => horizontal_scale,
+            _ => 1.0,
+        }
+    }
+
+    /// Multiplier on the size of biomes
+    pub fn biome_scale(&self) -> f32 {
+        match self.preset {
+            TerrainPreset::LargeBiomes => 4.0,
+            TerrainPreset::Custom { biome_scale, .. } => biome_scale,
+            _ => 1.0,
+        }
+    }
+
+    /// Kernel flag bits
+    pub fn flags(&self) -> u32 {
+        let caves = match self.preset {
+            TerrainPreset::Flat => false,
+            TerrainPreset::Custom { caves, .. } => caves,
+            _ => true,
+        };
+        if caves {
+            FLAG_CAVES
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_types() {
+        assert_eq!(WorldGenSettings::from_level_type("minecraft:normal").unwrap(), WorldGenSettings::default());
+        assert!(WorldGenSettings::from_level_type("minecraft:amplified").unwrap().amplified);
+        assert_eq!(
+            WorldGenSettings::from_level_type("LARGE_BIOMES").unwrap().preset,
+            TerrainPreset::LargeBiomes
+        );
+        assert!(WorldGenSettings::from_level_type("minecraft:debug_all_block_states").is_err());
+    }
+
+    #[test]
+    fn test_flat_has_no_relief_or_caves() {
+        let flat = WorldGenSettings { preset: TerrainPreset::Flat, amplified: true, ..Default::default() };
+        assert_eq!(flat.height_scale(), 0.0);
+        assert_eq!(flat.flags() & FLAG_CAVES, 0);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(WorldGenSettings::default().validate().is_ok());
+        assert!(WorldGenSettings { octaves: 0, ..Default::default() }.validate().is_err());
+        assert!(WorldGenSettings { sea_level: 400, ..Default::default() }.validate().is_err());
+
+        let custom = TerrainPreset::Custom { height_scale: 1.5, horizontal_scale: 0.0, biome_scale: 1.0, caves: true };
+        assert!(WorldGenSettings { preset: custom, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_settings_default_when_missing() {
+        let settings: WorldGenSettings = serde_json::from_str(r#"{"amplified":true}"#).unwrap();
+        assert_eq!(settings.sea_level, 63);
+        assert_eq!(settings.octaves, 6);
+        assert_eq!(settings.height_scale(), 2.0);
+    }
+}
@@ -0,0 +1,286 @@
+use wgpu::*;
+use anyhow::Result;
+
+use super::ChunkParams;
+
+/// Chunks on each side of the structure candidate map
+pub const STRUCTURE_MAP_SIZE: usize = 16;
+
+/// Size in bytes of one chunk's structure candidate map
+pub const STRUCTURE_BUFFER_SIZE: u64 = (STRUCTURE_MAP_SIZE * STRUCTURE_MAP_SIZE * std::mem::size_of::<u32>()) as u64;
+
+/// How far the map reaches back from the job's chunk; structure pieces
+/// rarely extend more than 8 chunks from their start
+pub const STRUCTURE_MAP_OFFSET: i32 = 8;
+
+/// Random spread placement of one structure set (spacing and separation in chunks)
+#[derive(Debug, Clone, Copy)]
+pub struct StructureSet {
+    pub name: &'static str,
+    /// Bit in the candidate map
+    pub bit: u32,
+    pub spacing: i32,
+    pub separation: i32,
+    pub salt: u32,
+    pub dimension: u32,
+}
+
+/// Structure sets placed per region, with vanilla spacing, separation and salts.
+/// Mirrored in structures.wgsl; bit order must match.
+pub const STRUCTURE_SETS: [StructureSet; 12] = [
+    StructureSet { name: "village", bit: 0, spacing: 34, separation: 8, salt: 10387312, dimension: 0 },
+    StructureSet { name: "desert_pyramid", bit: 1, spacing: 32, separation: 8, salt: 14357617, dimension: 0 },
+    StructureSet { name: "igloo", bit: 2, spacing: 32, separation: 8, salt: 14357618, dimension: 0 },
+    StructureSet { name: "jungle_temple", bit: 3, spacing: 32, separation: 8, salt: 14357619, dimension: 0 },
+    StructureSet { name: "swamp_hut", bit: 4, spacing: 32, separation: 8, salt: 14357620, dimension: 0 },
+    StructureSet { name: "pillager_outpost", bit: 5, spacing: 32, separation: 8, salt: 165745296, dimension: 0 },
+    StructureSet { name: "ocean_monument", bit: 6, spacing: 32, separation: 5, salt: 10387313, dimension: 0 },
+    StructureSet { name: "woodland_mansion", bit: 7, spacing: 80, separation: 20, salt: 10387319, dimension: 0 },
+    StructureSet { name: "shipwreck", bit: 8, spacing: 24, separation: 4, salt: 165745295, dimension: 0 },
+    StructureSet { name: "ruined_portal", bit: 9, spacing: 40, separation: 15, salt: 34222645, dimension: 0 },
+    StructureSet { name: "nether_complexes", bit: 10, spacing: 27, separation: 4, salt: 30084232, dimension: 1 },
+    StructureSet { name: "end_city", bit: 11, spacing: 20, separation: 11, salt: 10387313, dimension: 2 },
+];
+
+/// Structure kernel: for the 16x16 chunks around a job's chunk, which structure
+/// sets would start there.
+///
+/// Cell `z * 16 + x` covers chunk `(chunk_x - 8 + x, chunk_z - 8 + z)` and holds
+/// a bitmask of [`STRUCTURE_SETS`]. These are placement candidates only; the
+/// finishing pass still checks biomes and terrain before placing anything.
+pub struct StructureKernel {
+    bind_group_layout: BindGroupLayout,
+    compute_pipeline: ComputePipeline,
+}
+
+impl StructureKernel {
+    /// Create a new structure kernel
+    pub async fn new(device: &Device) -> Result<Self> {
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Structure Kernel Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let compute_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Structure Kernel Pipeline"),
+            layout: Some(&device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Structure Kernel Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            })),
+            module: &device.create_shader_module(ShaderModuleDescriptor {
+                label: Some("Structure Kernel Shader"),
+                source: ShaderSource::Wgsl(include_str!("structures.wgsl").into()),
+            }),
+            entry_point: "main",
+        });
+
+        Ok(Self {
+            bind_group_layout,
+            compute_pipeline,
+        })
+    }
+
+    /// Fill `structure_buffer` with the candidate map around one chunk
+    pub async fn generate_candidates(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        structure_buffer: &Buffer,
+        params_buffer: &Buffer,
+    ) -> Result<()> {
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Structure Kernel Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: structure_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Structure Candidate Encoder"),
+        });
+
+        // One invocation per map cell
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("Structure Candidate Compute Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.compute_pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        }
+
+        queue.submit(std::iter::once(encoder.finish()));
+
+        Ok(())
+    }
+}
+
+// CPU reference implementation of structures.wgsl
+
+fn region_hash(region_x: i32, region_z: i32, seed: u32, salt: u32) -> u32 {
+    let mut h = (region_x as u32).wrapping_mul(0x27d4eb2d)
+        ^ (region_z as u32).wrapping_mul(0x165667b1)
+        ^ (seed ^ salt).wrapping_mul(0x9e3779b9);
+    h = (h ^ (h >> 15)).wrapping_mul(0x85ebca6b);
+    h = (h ^ (h >> 13)).wrapping_mul(0xc2b2ae35);
+    h ^ (h >> 16)
+}
+
+/// Whether `set` picks chunk (chunk_x, chunk_z) as the start of its region
+pub fn is_candidate(set: &StructureSet, chunk_x: i32, chunk_z: i32, seed: u32) -> bool {
+    let region_x = chunk_x.div_euclid(set.spacing);
+    let region_z = chunk_z.div_euclid(set.spacing);
+    let range = (set.spacing - set.separation) as u32;
+
+    let hash = region_hash(region_x, region_z, seed, set.salt);
+    let offset_x = (hash % range) as i32;
+    let offset_z = ((hash >> 16) % range) as i32;
+
+    chunk_x == region_x * set.spacing + offset_x && chunk_z == region_z * set.spacing + offset_z
+}
+
+/// Bitmask of structure sets starting in one chunk
+pub fn chunk_candidates(chunk_x: i32, chunk_z: i32, seed: u32, dimension: u32) -> u32 {
+    STRUCTURE_SETS
+        .iter()
+        .filter(|set| set.dimension == dimension && is_candidate(set, chunk_x, chunk_z, seed))
+        .fold(0, |mask, set| mask | (1 << set.bit))
+}
+
+/// CPU equivalent of one structure kernel dispatch
+pub fn candidate_map(params: &ChunkParams) -> [u32; STRUCTURE_MAP_SIZE * STRUCTURE_MAP_SIZE] {
+    let (chunk_x, chunk_z, seed, dimension) = (params.chunk_x, params.chunk_z, params.seed, params.dimension);
+    let mut map = [0u32; STRUCTURE_MAP_SIZE * STRUCTURE_MAP_SIZE];
+    for z in 0..STRUCTURE_MAP_SIZE {
+        for x in 0..STRUCTURE_MAP_SIZE {
+            map[z * STRUCTURE_MAP_SIZE + x] = chunk_candidates(
+                chunk_x - STRUCTURE_MAP_OFFSET + x as i32,
+                chunk_z - STRUCTURE_MAP_OFFSET + z as i32,
+                seed,
+                dimension,
+            );
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::WorldGenSettings;
+
+    #[test]
+    fn test_one_candidate_per_region() {
+        for set in &STRUCTURE_SETS {
+            let starts = (0..set.spacing)
+                .flat_map(|x| (0..set.spacing).map(move |z| (x, z)))
+                .filter(|&(x, z)| is_candidate(set, x - set.spacing * 3, z + set.spacing * 5, 99))
+                .collect::<Vec<_>>();
+            assert_eq!(starts.len(), 1, "{}", set.name);
+
+            // Separation keeps starts away from the far edge of the region
+            let (x, z) = starts[0];
+            assert!(x < set.spacing - set.separation && z < set.spacing - set.separation, "{}", set.name);
+        }
+    }
+
+    #[test]
+    fn test_map_only_holds_dimension_structures() {
+        let end_bits = 1 << STRUCTURE_SETS[11].bit;
+        for &(dimension, name) in &[(0u32, "overworld"), (1, "nether"), (2, "end")] {
+            let params = ChunkParams::new(20, -40, 7, name, &WorldGenSettings::default());
+            let map = candidate_map(&params);
+            let allowed = STRUCTURE_SETS
+                .iter()
+                .filter(|set| set.dimension == dimension)
+                .fold(0u32, |mask, set| mask | (1 << set.bit));
+            assert!(map.iter().all(|&cell| cell & !allowed == 0), "{}", name);
+            if dimension != 2 {
+                assert!(map.iter().all(|&cell| cell & end_bits == 0));
+            }
+        }
+    }
+
+    async fn test_device() -> Option<(Device, Queue)> {
+        let instance = Instance::new(InstanceDescriptor::default());
+        let adapter = instance.request_adapter(&RequestAdapterOptions::default()).await?;
+        adapter.request_device(&DeviceDescriptor::default(), None).await.ok()
+    }
+
+    #[test]
+    fn test_gpu_kernel_matches_cpu_reference() {
+        use wgpu::util::DeviceExt;
+
+        let Some((device, queue)) = pollster::block_on(test_device()) else {
+            eprintln!("no GPU adapter available, skipping");
+            return;
+        };
+
+        let kernel = pollster::block_on(StructureKernel::new(&device)).unwrap();
+        for &(chunk_x, chunk_z, dimension) in &[(0, 0, "overworld"), (-370, 1120, "overworld"), (5, -9, "nether"), (300, 40, "end")] {
+            let params = ChunkParams::new(chunk_x, chunk_z, 12345, dimension, &WorldGenSettings::default());
+            let params_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+                label: Some("Structure Test Params"),
+                contents: bytemuck::bytes_of(&params),
+                usage: BufferUsages::UNIFORM,
+            });
+            let structure_buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("Structure Test Output"),
+                size: STRUCTURE_BUFFER_SIZE,
+                usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            let staging_buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("Structure Test Staging"),
+                size: STRUCTURE_BUFFER_SIZE,
+                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            pollster::block_on(kernel.generate_candidates(&device, &queue, &structure_buffer, &params_buffer)).unwrap();
+            let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+            encoder.copy_buffer_to_buffer(&structure_buffer, 0, &staging_buffer, 0, STRUCTURE_BUFFER_SIZE);
+            queue.submit(std::iter::once(encoder.finish()));
+
+            let slice = staging_buffer.slice(..);
+            slice.map_async(MapMode::Read, |_| {});
+            device.poll(Maintain::Wait);
+            let gpu: Vec<u32> = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+            staging_buffer.unmap();
+
+            // Integer-only, so the maps must match exactly
+            assert_eq!(gpu, candidate_map(&params).to_vec(), "chunk ({}, {}) {}", chunk_x, chunk_z, dimension);
+        }
+    }
+}
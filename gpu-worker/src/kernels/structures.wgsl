@@ -0,0 +1,99 @@
+// Structure Placement GPU Shader
+// Marks which structure sets would start in each chunk around the job's chunk.
+// Must stay in sync with STRUCTURE_SETS and the CPU reference in structures.rs.
+
+struct ChunkParams {
+    chunk_x: i32,
+    chunk_z: i32,
+    seed: u32,
+    dimension: u32,
+    sea_level: i32,
+    octaves: u32,
+    height_scale: f32,
+    horizontal_scale: f32,
+    biome_scale: f32,
+    flags: u32,
+}
+
+struct StructureSet {
+    spacing: i32,
+    separation: i32,
+    salt: u32,
+    dimension: u32,
+}
+
+@group(0) @binding(0)
+var<storage, read_write> structure_output: array<u32>;
+
+@group(0) @binding(1)
+var<uniform> params: ChunkParams;
+
+const MAP_OFFSET: i32 = 8;
+const SET_COUNT: u32 = 12u;
+
+// Indexed by candidate bit
+const STRUCTURE_SETS = array<StructureSet, 12>(
+    StructureSet(34, 8, 10387312u, 0u),   // village
+    StructureSet(32, 8, 14357617u, 0u),   // desert_pyramid
+    StructureSet(32, 8, 14357618u, 0u),   // igloo
+    StructureSet(32, 8, 14357619u, 0u),   // jungle_temple
+    StructureSet(32, 8, 14357620u, 0u),   // swamp_hut
+    StructureSet(32, 8, 165745296u, 0u),  // pillager_outpost
+    StructureSet(32, 5, 10387313u, 0u),   // ocean_monument
+    StructureSet(80, 20, 10387319u, 0u),  // woodland_mansion
+    StructureSet(24, 4, 165745295u, 0u),  // shipwreck
+    StructureSet(40, 15, 34222645u, 0u),  // ruined_portal
+    StructureSet(27, 4, 30084232u, 1u),   // nether_complexes
+    StructureSet(20, 11, 10387313u, 2u),  // end_city
+);
+
+fn region_hash(region_x: i32, region_z: i32, seed: u32, salt: u32) -> u32 {
+    var h = (bitcast<u32>(region_x) * 0x27d4eb2du) ^ (bitcast<u32>(region_z) * 0x165667b1u) ^ ((seed ^ salt) * 0x9e3779b9u);
+    h = (h ^ (h >> 15u)) * 0x85ebca6bu;
+    h = (h ^ (h >> 13u)) * 0xc2b2ae35u;
+    return h ^ (h >> 16u);
+}
+
+// Floor division, matching i32::div_euclid for positive divisors.
+// Avoids `%` on negative operands, whose sign some backends get wrong.
+fn floor_div(a: i32, b: i32) -> i32 {
+    let q = a / b;
+    return select(q, q - 1, q * b > a);
+}
+
+fn is_candidate(structure: StructureSet, chunk_x: i32, chunk_z: i32) -> bool {
+    let region_x = floor_div(chunk_x, structure.spacing);
+    let region_z = floor_div(chunk_z, structure.spacing);
+    let range = u32(structure.spacing - structure.separation);
+
+    let hash = region_hash(region_x, region_z, params.seed, structure.salt);
+    let offset_x = i32(hash % range);
+    let offset_z = i32((hash >> 16u) % range);
+
+    return chunk_x == region_x * structure.spacing + offset_x && chunk_z == region_z * structure.spacing + offset_z;
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let x = global_id.x;
+    let z = global_id.y;
+
+    if (x >= 16u || z >= 16u) {
+        return;
+    }
+
+    let chunk_x = params.chunk_x - MAP_OFFSET + i32(x);
+    let chunk_z = params.chunk_z - MAP_OFFSET + i32(z);
+
+    // Constant arrays can only be indexed by constants, so copy it into a local
+    var sets = STRUCTURE_SETS;
+    var mask = 0u;
+    for (var i = 0u; i < SET_COUNT; i++) {
+        let structure = sets[i];
+        if (structure.dimension == params.dimension && is_candidate(structure, chunk_x, chunk_z)) {
+            mask |= 1u << i;
+        }
+    }
+
+    structure_output[z * 16u + x] = mask;
+}
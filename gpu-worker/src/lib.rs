@@ -1,51 +1,129 @@
 use std::os::raw::c_int;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::{error, info};
+use std::sync::Mutex;
+use tracing::{error, info, warn};
 use wgpu::*;
 use anyhow::Result;
 
 mod ffi;
 mod kernels;
+pub mod devices;
+pub mod ipc;
+pub mod queue;
+pub mod selftest;
+pub mod shm;
 
+use devices::{DeviceSelection, GpuDevice};
 use ffi::*;
-use kernels::ChunkGenerator;
+use kernels::GpuChunkGenerator;
+pub use kernels::{biome, carver, structures, Backend, ChunkData, ChunkGenerator, CpuChunkGenerator, TerrainPreset, WorldGenSettings};
+use queue::{ChunkRequest, GpuWorkerHandle, GpuWorkerService, JobPriority, QueueConfig};
 
-/// Global GPU worker instance
-static mut GPU_WORKER: Option<Arc<Mutex<GpuWorker>>> = None;
+/// Queue handle backing the C ABI
+static FFI_SERVICE: Mutex<Option<GpuWorkerHandle>> = Mutex::new(None);
+
+fn ffi_handle() -> Option<GpuWorkerHandle> {
+    FFI_SERVICE.lock().ok().and_then(|guard| guard.clone())
+}
 
 /// GPU Worker structure with real GPU acceleration
 pub struct GpuWorker {
-    device: Device,
-    queue: Queue,
-    chunk_generator: ChunkGenerator,
+    devices: Vec<WorkerDevice>,
     is_healthy: bool,
     worker_id: String,
 }
 
+/// One chunk generator and the adapter it runs on (`None` for the CPU backend)
+pub(crate) struct WorkerDevice {
+    pub(crate) info: Option<GpuDevice>,
+    pub(crate) generator: Box<dyn ChunkGenerator>,
+}
+
 impl GpuWorker {
-    /// Initialize the GPU worker with real GPU acceleration
+    /// Initialize the GPU worker, falling back to CPU generation when no adapter is usable
     pub async fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        info!("Initializing GPU worker...");
-        
-        // Initialize WebGPU
-        let instance = Instance::new(InstanceDescriptor {
-            backends: Backends::all(),
-            ..Default::default()
-        });
-        
-        // Get adapter
-        let adapter = instance
-            .request_adapter(&RequestAdapterOptions {
-                power_preference: PowerPreference::HighPerformance,
-                compatible_surface: None,
-                force_fallback_adapter: false,
-            })
-            .await
-            .ok_or("Failed to get WebGPU adapter")?;
-        
-        info!("WebGPU adapter: {:?}", adapter.get_info());
-        
+        Self::with_selection(DeviceSelection::Auto).await
+    }
+
+    /// Initialize the worker on the selected adapters.
+    ///
+    /// `Auto` and `All` fall back to CPU generation when no adapter is usable;
+    /// pinning a device that does not exist is an error.
+    pub async fn with_selection(selection: DeviceSelection) -> Result<Self, Box<dyn std::error::Error>> {
+        info!("Initializing GPU worker ({})...", selection);
+
+        let devices = match Self::init_devices(selection).await {
+            Ok(devices) => {
+                info!("GPU worker initialized successfully on {} device(s)", devices.len());
+                devices
+            }
+            Err(e) if matches!(selection, DeviceSelection::Device(_)) => return Err(e.into()),
+            Err(e) => {
+                warn!("GPU acceleration unavailable ({}); using CPU chunk generation", e);
+                vec![WorkerDevice {
+                    info: None,
+                    generator: Box::new(CpuChunkGenerator::new()),
+                }]
+            }
+        };
+
+        Ok(Self::from_devices(devices))
+    }
+
+    /// Create a worker around an explicit generator backend
+    pub fn with_generator(chunk_generator: Box<dyn ChunkGenerator>) -> Self {
+        Self::from_devices(vec![WorkerDevice {
+            info: None,
+            generator: chunk_generator,
+        }])
+    }
+
+    pub(crate) fn from_devices(devices: Vec<WorkerDevice>) -> Self {
+        Self {
+            devices,
+            is_healthy: true,
+            worker_id: format!("gpu-worker-{}", uuid::Uuid::new_v4()),
+        }
+    }
+
+    async fn init_devices(selection: DeviceSelection) -> Result<Vec<WorkerDevice>> {
+        let instance = devices::create_instance();
+
+        let adapters: Vec<(GpuDevice, Adapter)> = match selection {
+            DeviceSelection::Auto => devices::enumerate(&instance)
+                .into_iter()
+                .min_by_key(|(device, _)| device.preference())
+                .into_iter()
+                .collect(),
+            DeviceSelection::Device(index) => {
+                let adapter = devices::enumerate(&instance)
+                    .into_iter()
+                    .find(|(device, _)| device.index == index)
+                    .ok_or_else(|| anyhow::anyhow!("GPU device {} not found", index))?;
+                vec![adapter]
+            }
+            DeviceSelection::All => devices::enumerate(&instance)
+                .into_iter()
+                .filter(|(device, _)| device.is_hardware())
+                .collect(),
+        };
+
+        if adapters.is_empty() {
+            anyhow::bail!("No hardware WebGPU adapters found");
+        }
+
+        let mut devices = Vec::with_capacity(adapters.len());
+        for (info, adapter) in adapters {
+            info!("WebGPU adapter {}: {} ({})", info.index, info.name, info.backend);
+            devices.push(WorkerDevice {
+                generator: Box::new(Self::init_gpu(&adapter).await?),
+                info: Some(info),
+            });
+        }
+
+        Ok(devices)
+    }
+
+    async fn init_gpu(adapter: &Adapter) -> Result<GpuChunkGenerator> {
         // Get device and queue
         let (device, queue) = adapter
             .request_device(
@@ -61,50 +139,17 @@ impl GpuWorker {
         info!("WebGPU device initialized successfully");
         
         // Initialize chunk generator
-        let chunk_generator = ChunkGenerator::new(&device).await?;
-        
-        info!("GPU worker initialized successfully with real GPU acceleration");
-        
-        Ok(Self {
-            device,
-            queue,
-            chunk_generator,
-            is_healthy: true,
-            worker_id: format!("gpu-worker-{}", uuid::Uuid::new_v4()),
-        })
+        GpuChunkGenerator::new(device, queue).await
     }
-    
-    /// Submit a chunk generation job using real GPU acceleration
-    pub async fn submit_chunk_job(&mut self, job: ChunkJob) -> Result<ChunkResult, Box<dyn std::error::Error>> {
-        info!("Submitting GPU chunk job: ({}, {})", job.chunk_x, job.chunk_z);
-        
-        // Get dimension string from job
-        let dimension = job.get_dimension();
-        
-        // Use real GPU chunk generation
-        let chunk_data = self.chunk_generator.generate_chunk(
-            &self.device,
-            &self.queue,
-            job.chunk_x,
-            job.chunk_z,
-            job.seed as u32,
-            &dimension,
-        ).await?;
-        
-        info!("GPU chunk generation completed for ({}, {})", job.chunk_x, job.chunk_z);
-        
-        // Convert ChunkData to ChunkResult
-        let result = ChunkResult::new(
-            job.chunk_x,
-            job.chunk_z,
-            job.seed,
-            chunk_data.content_hash.to_string(),
-            bytemuck::cast_slice(&chunk_data.density_data).to_vec(),
-            bytemuck::cast_slice(&chunk_data.mask_data).to_vec(),
-            bytemuck::cast_slice(&chunk_data.biome_data).to_vec(),
-        );
-        
-        Ok(result)
+
+    /// Adapters this worker generates chunks on; empty when running on the CPU
+    pub fn devices(&self) -> impl Iterator<Item = &GpuDevice> {
+        self.devices.iter().filter_map(|device| device.info.as_ref())
+    }
+
+    /// Backend that actually generates chunks
+    pub fn backend(&self) -> Backend {
+        queue::worker_backend(&self.devices)
     }
     
     /// Check if the GPU worker is healthy
@@ -128,11 +173,17 @@ impl GpuWorker {
 /// Initialize the GPU worker (C ABI)
 #[no_mangle]
 pub extern "C" fn gpuw_init() -> c_int {
+    let mut service = match FFI_SERVICE.lock() {
+        Ok(service) => service,
+        Err(_) => return -1,
+    };
+    if service.is_some() {
+        return 0;
+    }
+
     match pollster::block_on(GpuWorker::new()) {
         Ok(worker) => {
-            unsafe {
-                GPU_WORKER = Some(Arc::new(Mutex::new(worker)));
-            }
+            *service = Some(GpuWorkerService::spawn(worker, QueueConfig::default()));
             info!("GPU worker initialized successfully");
             0
         }
@@ -143,33 +194,54 @@ pub extern "C" fn gpuw_init() -> c_int {
     }
 }
 
-/// Submit a chunk job (C ABI)
+/// Submit a chunk job and wait for it to finish (C ABI)
 #[no_mangle]
 pub unsafe extern "C" fn gpuw_submit_chunk_job(job: ChunkJob, out_handle: *mut JobHandle) -> c_int {
-    unsafe {
-        if let Some(worker_arc) = GPU_WORKER.as_ref() {
-            let worker = worker_arc.clone();
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            
-            match rt.block_on(async {
-                let mut worker_guard = worker.lock().await;
-                worker_guard.submit_chunk_job(job).await
-            }) {
-                Ok(result) => {
-                    let job_handle = JobHandle {
-                        result: Some(result),
-                        completed: true,
-                    };
-                    *out_handle = job_handle;
-                    0
-                }
-                Err(e) => {
-                    error!("Failed to submit chunk job: {}", e);
-                    -1
-                }
+    if out_handle.is_null() {
+        return -1;
+    }
+    let Some(handle) = ffi_handle() else {
+        error!("GPU worker not initialized");
+        return -1;
+    };
+
+    let request = ChunkRequest {
+        chunk_x: job.chunk_x,
+        chunk_z: job.chunk_z,
+        seed: job.seed,
+        dimension: job.get_dimension(),
+        settings: match job.get_settings() {
+            Ok(settings) => settings,
+            Err(e) => {
+                error!("Invalid world settings in chunk job: {}", e);
+                return -1;
             }
-        } else {
-            error!("GPU worker not initialized");
+        },
+        // The server thread is blocked until this chunk comes back
+        priority: JobPriority::Interactive,
+    };
+
+    match pollster::block_on(handle.generate(request)) {
+        Ok(output) => {
+            let result = ChunkResult::new(
+                output.chunk_x,
+                output.chunk_z,
+                output.seed,
+                output.content_hash.to_string(),
+                bytemuck::cast_slice(&output.density_data).to_vec(),
+                bytemuck::cast_slice(&output.mask_data).to_vec(),
+                bytemuck::cast_slice(&output.biome_data).to_vec(),
+                bytemuck::cast_slice(&output.carve_mask).to_vec(),
+                bytemuck::cast_slice(&output.structure_candidates).to_vec(),
+            );
+            *out_handle = JobHandle {
+                result: Some(result),
+                completed: true,
+            };
+            0
+        }
+        Err(e) => {
+            error!("Failed to submit chunk job: {}", e);
             -1
         }
     }
@@ -210,39 +282,18 @@ pub unsafe extern "C" fn gpuw_free_result(result: *mut ChunkResult) {
 /// Health check (C ABI)
 #[no_mangle]
 pub extern "C" fn gpuw_health_check() -> c_int {
-    unsafe {
-        if let Some(worker_arc) = GPU_WORKER.as_ref() {
-            let worker = worker_arc.clone();
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            
-            rt.block_on(async {
-                let worker_guard = worker.lock().await;
-                if worker_guard.is_healthy() {
-                    0
-                } else {
-                    -1
-                }
-            })
-        } else {
-            -1
-        }
+    match ffi_handle() {
+        Some(handle) if handle.is_healthy() => 0,
+        _ => -1,
     }
 }
 
 /// Cleanup (C ABI)
 #[no_mangle]
 pub extern "C" fn gpuw_cleanup() {
-    unsafe {
-        if let Some(worker_arc) = GPU_WORKER.as_ref() {
-            let worker = worker_arc.clone();
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            
-            rt.block_on(async {
-                let mut worker_guard = worker.lock().await;
-                worker_guard.cleanup();
-            });
-            
-            GPU_WORKER = None;
+    if let Ok(mut service) = FFI_SERVICE.lock() {
+        if let Some(handle) = service.take() {
+            handle.shutdown();
         }
     }
-}
\ No newline at end of file
+}
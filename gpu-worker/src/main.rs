@@ -1,23 +1,91 @@
+use gpu_worker::devices::DeviceSelection;
+use gpu_worker::ipc::{self, IpcServer};
+use gpu_worker::queue::{GpuWorkerService, QueueConfig};
+use gpu_worker::selftest::SelfTestConfig;
+use gpu_worker::shm::{self, ResultRing};
 use gpu_worker::GpuWorker;
-use tracing::info;
+use tracing::{error, info, warn};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
-    
+    // `gpu-worker --self-test [--seed N] [--iterations N]` checks determinism and exits
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let self_test = args.iter().any(|arg| arg == "--self-test");
+
+    // Initialize logging; the self-test report owns stdout
+    if self_test {
+        tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+    } else {
+        tracing_subscriber::fmt::init();
+    }
+
     info!("Starting GPU Worker...");
-    
-    // Initialize GPU worker
-    let mut worker = GpuWorker::new().await?;
-    
+
+    // Initialize GPU worker on the configured adapters and hand it to the job queue
+    let selection: DeviceSelection = std::env::var("GPU_WORKER_DEVICE").unwrap_or_default().parse()?;
+    let worker = GpuWorker::with_selection(selection).await?;
+    let handle = GpuWorkerService::spawn(worker, QueueConfig::default());
+
+    if self_test {
+        let config = self_test_config(&args)?;
+        let report = handle.self_test(config).await?;
+        handle.shutdown();
+
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        if report.passed {
+            info!("Self-test passed on {} ({} chunks x {} runs)", report.backend, report.chunks.len(), report.iterations);
+            return Ok(());
+        }
+        error!("Self-test failed on {}: chunk output differs between runs or from the CPU reference", report.backend);
+        std::process::exit(1);
+    }
+
+    // Expose the queue to hostd over local IPC
+    let endpoint = std::env::var("GPU_WORKER_ENDPOINT").unwrap_or_else(|_| ipc::default_endpoint());
+    let mut server = IpcServer::bind(&endpoint)?;
+
+    // Shared-memory ring so clients can read results without copying them over the socket
+    let slots = match std::env::var("GPU_WORKER_SHM_SLOTS") {
+        Ok(value) => value.parse()?,
+        Err(_) => shm::DEFAULT_SLOTS,
+    };
+    if slots > 0 {
+        match ResultRing::create(shm::default_path(&endpoint), slots) {
+            Ok(ring) => {
+                info!("Shared-memory result ring at {} ({} slots)", ring.info().path.display(), slots);
+                server = server.with_result_ring(ring);
+            }
+            Err(e) => warn!("Failed to create shared-memory result ring: {}; results will be sent inline", e),
+        }
+    }
+
     info!("GPU Worker started successfully");
-    
+
     // Keep the worker running
-    tokio::signal::ctrl_c().await?;
-    
+    tokio::select! {
+        result = server.run(handle.clone()) => {
+            if let Err(e) = result {
+                error!("GPU worker IPC server stopped: {}", e);
+            }
+        }
+        _ = tokio::signal::ctrl_c() => {}
+    }
+
     info!("Shutting down GPU Worker...");
-    worker.cleanup();
-    
+    handle.shutdown();
+
     Ok(())
 }
+
+fn self_test_config(args: &[String]) -> Result<SelfTestConfig, Box<dyn std::error::Error>> {
+    let mut config = SelfTestConfig::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => config.seed = args.next().ok_or("--seed needs a value")?.parse()?,
+            "--iterations" => config.iterations = args.next().ok_or("--iterations needs a value")?.parse()?,
+            _ => {}
+        }
+    }
+    Ok(config)
+}
@@ -0,0 +1,827 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::devices::GpuDevice;
+use crate::kernels::{Backend, ChunkData, WorldGenSettings};
+use crate::{GpuWorker, WorkerDevice};
+
+/// Identifier assigned to every queued job
+pub type JobId = Uuid;
+
+/// How long finished jobs are kept around for clients that never collect them
+const FINISHED_JOB_RETENTION: Duration = Duration::from_secs(600);
+
+/// Scheduling class of a job; devices always take the highest class queued
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    /// Background work such as pregeneration
+    Bulk,
+    /// On-demand chunk generation
+    #[default]
+    Normal,
+    /// Work a player is waiting on, such as lighting updates
+    Interactive,
+}
+
+impl JobPriority {
+    /// Highest priority first
+    pub const ALL: [JobPriority; 3] = [JobPriority::Interactive, JobPriority::Normal, JobPriority::Bulk];
+
+    fn lane(self) -> usize {
+        match self {
+            JobPriority::Interactive => 0,
+            JobPriority::Normal => 1,
+            JobPriority::Bulk => 2,
+        }
+    }
+}
+
+impl std::fmt::Display for JobPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobPriority::Bulk => write!(f, "bulk"),
+            JobPriority::Normal => write!(f, "normal"),
+            JobPriority::Interactive => write!(f, "interactive"),
+        }
+    }
+}
+
+/// A single chunk generation request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkRequest {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub seed: i64,
+    #[serde(default = "default_dimension")]
+    pub dimension: String,
+    /// World type of the server the chunk is generated for
+    #[serde(default)]
+    pub settings: WorldGenSettings,
+    #[serde(default)]
+    pub priority: JobPriority,
+}
+
+fn default_dimension() -> String {
+    "overworld".to_string()
+}
+
+/// Generated chunk data returned to clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkOutput {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub seed: i64,
+    pub content_hash: u32,
+    pub density_data: Vec<f32>,
+    pub mask_data: Vec<u32>,
+    pub biome_data: Vec<u32>,
+    /// Per-block carver output (`kernels::carver::CARVE_*`)
+    #[serde(default)]
+    pub carve_mask: Vec<u32>,
+    /// Structure start bitmasks for the 16x16 chunks around this one
+    #[serde(default)]
+    pub structure_candidates: Vec<u32>,
+}
+
+impl ChunkOutput {
+    /// Copy generator output into an owned, serializable result
+    pub fn from_chunk_data(request: &ChunkRequest, data: &ChunkData) -> Self {
+        Self {
+            chunk_x: request.chunk_x,
+            chunk_z: request.chunk_z,
+            seed: request.seed,
+            content_hash: data.content_hash,
+            density_data: data.density_data.to_vec(),
+            mask_data: data.mask_data.to_vec(),
+            biome_data: data.biome_data.to_vec(),
+            carve_mask: data.carve_mask.to_vec(),
+            structure_candidates: data.structure_candidates.to_vec(),
+        }
+    }
+}
+
+/// Lifecycle state of a queued job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed { error: String },
+    Cancelled,
+}
+
+impl JobStatus {
+    fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Failed { .. } | JobStatus::Cancelled)
+    }
+}
+
+/// Worker health snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub worker_id: String,
+    pub backend: Backend,
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    /// Per-device counters; one entry per generator thread
+    #[serde(default)]
+    pub devices: Vec<DeviceMetrics>,
+    #[serde(default)]
+    pub queue: QueueMetrics,
+}
+
+/// Scheduling counters for the job queue
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueueMetrics {
+    pub depth: usize,
+    pub capacity: usize,
+    /// Jobs that started ahead of lower-priority work queued before them
+    pub preemptions: u64,
+    /// Jobs that only started because they waited past the starvation threshold
+    pub starved: u64,
+    pub starvation_threshold_ms: u64,
+    /// One entry per priority, highest first
+    pub priorities: Vec<PriorityMetrics>,
+}
+
+/// Queue counters for one priority
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityMetrics {
+    pub priority: JobPriority,
+    pub depth: usize,
+    /// How long the oldest queued job has been waiting
+    pub oldest_wait_ms: u64,
+    /// Jobs handed to a device since the worker started
+    pub dispatched: u64,
+    /// Mean time from submission to start over dispatched jobs
+    pub average_wait_ms: f64,
+    pub starved: u64,
+}
+
+/// Work done by one device since the worker started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceMetrics {
+    /// Adapter the generator runs on; `None` for the CPU backend
+    pub device: Option<GpuDevice>,
+    pub backend: Backend,
+    pub busy: bool,
+    pub jobs_completed: u64,
+    pub jobs_failed: u64,
+    /// Fraction of uptime spent generating chunks (0.0 - 1.0)
+    pub utilization: f32,
+}
+
+/// Job queue configuration
+#[derive(Debug, Clone)]
+pub struct QueueConfig {
+    /// Maximum number of jobs waiting for the GPU
+    pub capacity: usize,
+    /// A job queued this long runs next regardless of priority
+    pub starvation_threshold: Duration,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            starvation_threshold: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Errors returned by the job queue
+#[derive(Debug, thiserror::Error)]
+pub enum QueueError {
+    #[error("GPU job queue is full")]
+    Full,
+    #[error("GPU worker is shutting down")]
+    Closed,
+    #[error("GPU job {0} not found")]
+    NotFound(JobId),
+    #[error("GPU job {0} was cancelled")]
+    Cancelled(JobId),
+    #[error("chunk generation failed: {0}")]
+    Generation(String),
+}
+
+struct JobEntry {
+    status: JobStatus,
+    cancel: Arc<AtomicBool>,
+    result: Option<ChunkOutput>,
+    finished_at: Option<Instant>,
+}
+
+struct QueuedJob {
+    id: JobId,
+    request: ChunkRequest,
+    cancel: Arc<AtomicBool>,
+    queued_at: Instant,
+}
+
+/// Jobs waiting for a device, one FIFO lane per priority
+#[derive(Default)]
+struct PendingJobs {
+    lanes: [VecDeque<QueuedJob>; 3],
+    closed: bool,
+}
+
+/// Why a job was picked over the others in the queue
+struct Dispatch {
+    job: QueuedJob,
+    /// It jumped ahead of lower-priority work that was queued earlier
+    preempted: bool,
+    /// It had waited past the starvation threshold
+    starved: bool,
+}
+
+impl PendingJobs {
+    fn len(&self) -> usize {
+        self.lanes.iter().map(VecDeque::len).sum()
+    }
+
+    /// Highest priority first, except that the longest-waiting job past the threshold goes before everything.
+    ///
+    /// Devices call this between chunks, so a bulk batch yields to newer
+    /// interactive jobs at the next chunk boundary.
+    fn pop(&mut self, starvation_threshold: Duration) -> Option<Dispatch> {
+        let starving = self
+            .lanes
+            .iter()
+            .enumerate()
+            .filter_map(|(lane, jobs)| jobs.front().map(|job| (lane, job.queued_at)))
+            .filter(|(_, queued_at)| queued_at.elapsed() >= starvation_threshold)
+            .min_by_key(|&(_, queued_at)| queued_at)
+            .map(|(lane, _)| lane);
+
+        let lane = starving.or_else(|| self.lanes.iter().position(|jobs| !jobs.is_empty()))?;
+        let job = self.lanes[lane].pop_front()?;
+        let preempted = self.lanes[lane + 1..]
+            .iter()
+            .filter_map(VecDeque::front)
+            .any(|waiting| waiting.queued_at < job.queued_at);
+
+        Some(Dispatch {
+            job,
+            preempted,
+            starved: starving.is_some(),
+        })
+    }
+}
+
+#[derive(Default)]
+struct LaneStats {
+    dispatched: AtomicU64,
+    starved: AtomicU64,
+    wait_nanos: AtomicU64,
+}
+
+struct DeviceStats {
+    device: Option<GpuDevice>,
+    backend: Backend,
+    busy: AtomicBool,
+    jobs_completed: AtomicU64,
+    jobs_failed: AtomicU64,
+    busy_nanos: AtomicU64,
+}
+
+impl DeviceStats {
+    fn snapshot(&self, uptime: Duration) -> DeviceMetrics {
+        let uptime = uptime.as_nanos().max(1) as f64;
+        DeviceMetrics {
+            device: self.device.clone(),
+            backend: self.backend,
+            busy: self.busy.load(Ordering::SeqCst),
+            jobs_completed: self.jobs_completed.load(Ordering::SeqCst),
+            jobs_failed: self.jobs_failed.load(Ordering::SeqCst),
+            utilization: (self.busy_nanos.load(Ordering::SeqCst) as f64 / uptime).min(1.0) as f32,
+        }
+    }
+}
+
+struct Shared {
+    jobs: Mutex<HashMap<JobId, JobEntry>>,
+    changed: Notify,
+    pending: Mutex<PendingJobs>,
+    /// Signalled when a job is queued or the queue closes
+    job_ready: Condvar,
+    starvation_threshold: Duration,
+    lanes: [LaneStats; 3],
+    preemptions: AtomicU64,
+    healthy: AtomicBool,
+    closing: AtomicBool,
+    worker_id: String,
+    backend: Backend,
+    capacity: usize,
+    devices: Vec<DeviceStats>,
+    started_at: Instant,
+}
+
+impl Shared {
+    fn set_status(&self, id: JobId, status: JobStatus, result: Option<ChunkOutput>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(entry) = jobs.get_mut(&id) {
+            // A cancelled job stays cancelled even if the GPU finished it afterwards
+            if entry.status == JobStatus::Cancelled {
+                return;
+            }
+            if status.is_finished() {
+                entry.finished_at = Some(Instant::now());
+            }
+            entry.status = status;
+            entry.result = result;
+        }
+        drop(jobs);
+        self.changed.notify_waiters();
+    }
+
+    fn queue_metrics(&self) -> QueueMetrics {
+        let pending = self.pending.lock().unwrap();
+        let priorities: Vec<PriorityMetrics> = JobPriority::ALL
+            .iter()
+            .map(|&priority| {
+                let jobs = &pending.lanes[priority.lane()];
+                let stats = &self.lanes[priority.lane()];
+                let dispatched = stats.dispatched.load(Ordering::SeqCst);
+                PriorityMetrics {
+                    priority,
+                    depth: jobs.len(),
+                    oldest_wait_ms: jobs.front().map_or(0, |job| job.queued_at.elapsed().as_millis() as u64),
+                    dispatched,
+                    average_wait_ms: match dispatched {
+                        0 => 0.0,
+                        n => stats.wait_nanos.load(Ordering::SeqCst) as f64 / n as f64 / 1_000_000.0,
+                    },
+                    starved: stats.starved.load(Ordering::SeqCst),
+                }
+            })
+            .collect();
+
+        QueueMetrics {
+            depth: pending.len(),
+            capacity: self.capacity,
+            preemptions: self.preemptions.load(Ordering::SeqCst),
+            starved: priorities.iter().map(|lane| lane.starved).sum(),
+            starvation_threshold_ms: self.starvation_threshold.as_millis() as u64,
+            priorities,
+        }
+    }
+
+    fn prune_finished(&self) {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.retain(|_, entry| match entry.finished_at {
+            Some(finished_at) => finished_at.elapsed() < FINISHED_JOB_RETENTION,
+            None => true,
+        });
+    }
+}
+
+/// Long-running service that owns the GPU worker and drains the job queue
+pub struct GpuWorkerService;
+
+impl GpuWorkerService {
+    /// Move each of the worker's devices onto a dedicated thread and return a handle to the shared queue.
+    ///
+    /// Device threads pull from the same queue, so a batch is split across devices
+    /// as each one finishes its previous chunk.
+    pub fn spawn(mut worker: GpuWorker, config: QueueConfig) -> GpuWorkerHandle {
+        let devices = std::mem::take(&mut worker.devices);
+        let shared = Arc::new(Shared {
+            jobs: Mutex::new(HashMap::new()),
+            changed: Notify::new(),
+            pending: Mutex::new(PendingJobs::default()),
+            job_ready: Condvar::new(),
+            starvation_threshold: config.starvation_threshold,
+            lanes: Default::default(),
+            preemptions: AtomicU64::new(0),
+            healthy: AtomicBool::new(worker.is_healthy()),
+            closing: AtomicBool::new(false),
+            worker_id: worker.get_worker_id().to_string(),
+            backend: worker_backend(&devices),
+            capacity: config.capacity.max(1),
+            devices: devices
+                .iter()
+                .map(|device| DeviceStats {
+                    device: device.info.clone(),
+                    backend: device.generator.backend(),
+                    busy: AtomicBool::new(false),
+                    jobs_completed: AtomicU64::new(0),
+                    jobs_failed: AtomicU64::new(0),
+                    busy_nanos: AtomicU64::new(0),
+                })
+                .collect(),
+            started_at: Instant::now(),
+        });
+
+        let worker = Arc::new(Mutex::new(worker));
+        let running = Arc::new(AtomicUsize::new(devices.len()));
+        for (slot, device) in devices.into_iter().enumerate() {
+            let runner = Runner {
+                slot,
+                device,
+                shared: shared.clone(),
+                worker: worker.clone(),
+                running: running.clone(),
+            };
+            std::thread::Builder::new()
+                .name(format!("gpu-worker-queue-{}", slot))
+                // Chunk buffers are copied through the stack on readback
+                .stack_size(8 * 1024 * 1024)
+                .spawn(move || runner.run())
+                .expect("failed to spawn GPU worker thread");
+        }
+
+        GpuWorkerHandle { shared }
+    }
+}
+
+pub(crate) fn worker_backend(devices: &[WorkerDevice]) -> Backend {
+    if devices.iter().any(|device| device.generator.backend() == Backend::Gpu) {
+        Backend::Gpu
+    } else {
+        Backend::Cpu
+    }
+}
+
+/// One device thread draining the shared queue
+struct Runner {
+    slot: usize,
+    device: WorkerDevice,
+    shared: Arc<Shared>,
+    worker: Arc<Mutex<GpuWorker>>,
+    running: Arc<AtomicUsize>,
+}
+
+impl Runner {
+    fn run(self) {
+        let shared = &self.shared;
+        let stats = &shared.devices[self.slot];
+        info!("GPU worker queue started ({}, device {})", shared.worker_id, self.slot);
+
+        while let Some(job) = self.next_job() {
+            if job.cancel.load(Ordering::SeqCst) {
+                shared.set_status(job.id, JobStatus::Cancelled, None);
+                continue;
+            }
+
+            shared.set_status(job.id, JobStatus::Running, None);
+            stats.busy.store(true, Ordering::SeqCst);
+            let started = Instant::now();
+            let result = self.device.generate(&job.request);
+            stats.busy_nanos.fetch_add(started.elapsed().as_nanos() as u64, Ordering::SeqCst);
+            stats.busy.store(false, Ordering::SeqCst);
+
+            match result {
+                Ok(output) => {
+                    stats.jobs_completed.fetch_add(1, Ordering::SeqCst);
+                    shared.set_status(job.id, JobStatus::Completed, Some(output));
+                }
+                Err(e) => {
+                    stats.jobs_failed.fetch_add(1, Ordering::SeqCst);
+                    error!("GPU job {} failed: {}", job.id, e);
+                    shared.set_status(job.id, JobStatus::Failed { error: e.to_string() }, None);
+                }
+            }
+        }
+
+        // Anything still queued will never run
+        let abandoned: Vec<QueuedJob> = {
+            let mut pending = shared.pending.lock().unwrap();
+            pending.lanes.iter_mut().flat_map(|jobs| jobs.drain(..)).collect()
+        };
+        for job in abandoned {
+            shared.set_status(job.id, JobStatus::Cancelled, None);
+        }
+
+        // The last device to stop takes the worker down
+        if self.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.worker.lock().unwrap().cleanup();
+            shared.healthy.store(false, Ordering::SeqCst);
+            shared.changed.notify_waiters();
+            info!("GPU worker queue stopped ({})", shared.worker_id);
+        }
+    }
+
+    /// Block until a job is queued; `None` once the queue is closed
+    fn next_job(&self) -> Option<QueuedJob> {
+        let shared = &self.shared;
+        let mut pending = shared.pending.lock().unwrap();
+        loop {
+            if pending.closed {
+                return None;
+            }
+            if let Some(dispatch) = pending.pop(shared.starvation_threshold) {
+                let lane = &shared.lanes[dispatch.job.request.priority.lane()];
+                lane.dispatched.fetch_add(1, Ordering::SeqCst);
+                lane.wait_nanos.fetch_add(dispatch.job.queued_at.elapsed().as_nanos() as u64, Ordering::SeqCst);
+                if dispatch.starved {
+                    lane.starved.fetch_add(1, Ordering::SeqCst);
+                    warn!(
+                        "GPU job {} ({}) waited past the starvation threshold",
+                        dispatch.job.id, dispatch.job.request.priority
+                    );
+                } else if dispatch.preempted {
+                    shared.preemptions.fetch_add(1, Ordering::SeqCst);
+                }
+                return Some(dispatch.job);
+            }
+            pending = shared.job_ready.wait(pending).unwrap();
+        }
+    }
+}
+
+/// Cloneable, thread-safe handle to a running [`GpuWorkerService`]
+#[derive(Clone)]
+pub struct GpuWorkerHandle {
+    shared: Arc<Shared>,
+}
+
+impl GpuWorkerHandle {
+    /// Queue a single chunk job without waiting for it to run
+    pub fn submit(&self, request: ChunkRequest) -> Result<JobId, QueueError> {
+        self.submit_batch(vec![request])?.pop().ok_or(QueueError::Full)
+    }
+
+    /// Queue a batch of chunk jobs; either all of them are queued or none are
+    pub fn submit_batch(&self, requests: Vec<ChunkRequest>) -> Result<Vec<JobId>, QueueError> {
+        if self.shared.closing.load(Ordering::SeqCst) {
+            return Err(QueueError::Closed);
+        }
+        self.shared.prune_finished();
+
+        let mut pending = self.shared.pending.lock().unwrap();
+        if pending.closed {
+            return Err(QueueError::Closed);
+        }
+        if pending.len() + requests.len() > self.shared.capacity {
+            return Err(QueueError::Full);
+        }
+
+        let queued_at = Instant::now();
+        let mut jobs = self.shared.jobs.lock().unwrap();
+        let ids = requests
+            .into_iter()
+            .map(|request| {
+                let id = Uuid::new_v4();
+                let cancel = Arc::new(AtomicBool::new(false));
+                jobs.insert(id, JobEntry {
+                    status: JobStatus::Queued,
+                    cancel: cancel.clone(),
+                    result: None,
+                    finished_at: None,
+                });
+                pending.lanes[request.priority.lane()].push_back(QueuedJob { id, request, cancel, queued_at });
+                id
+            })
+            .collect();
+        drop(jobs);
+        drop(pending);
+
+        self.shared.job_ready.notify_all();
+        Ok(ids)
+    }
+
+    /// Cancel a job. Queued jobs are skipped; a running job's result is discarded.
+    pub fn cancel(&self, id: JobId) -> bool {
+        let mut jobs = self.shared.jobs.lock().unwrap();
+        let cancelled = match jobs.get_mut(&id) {
+            Some(entry) if !entry.status.is_finished() => {
+                entry.cancel.store(true, Ordering::SeqCst);
+                entry.status = JobStatus::Cancelled;
+                entry.finished_at = Some(Instant::now());
+                true
+            }
+            _ => false,
+        };
+        drop(jobs);
+
+        if cancelled {
+            self.shared.changed.notify_waiters();
+        }
+        cancelled
+    }
+
+    /// Current status of a job
+    pub fn status(&self, id: JobId) -> Option<JobStatus> {
+        self.shared.jobs.lock().unwrap().get(&id).map(|entry| entry.status.clone())
+    }
+
+    /// Wait for a job to finish and take its result
+    pub async fn wait(&self, id: JobId) -> Result<ChunkOutput, QueueError> {
+        loop {
+            let notified = self.shared.changed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let mut jobs = self.shared.jobs.lock().unwrap();
+                let entry = jobs.get(&id).ok_or(QueueError::NotFound(id))?;
+                if entry.status.is_finished() {
+                    let entry = jobs.remove(&id).expect("job entry present");
+                    return match entry.status {
+                        JobStatus::Completed => entry.result.ok_or(QueueError::NotFound(id)),
+                        JobStatus::Failed { error } => Err(QueueError::Generation(error)),
+                        _ => Err(QueueError::Cancelled(id)),
+                    };
+                }
+                if !self.shared.healthy.load(Ordering::SeqCst) {
+                    return Err(QueueError::Closed);
+                }
+            }
+
+            notified.await;
+        }
+    }
+
+    /// Queue a job and wait for its result
+    pub async fn generate(&self, request: ChunkRequest) -> Result<ChunkOutput, QueueError> {
+        let id = self.submit(request)?;
+        self.wait(id).await
+    }
+
+    /// Check if the worker thread is still accepting jobs
+    pub fn is_healthy(&self) -> bool {
+        self.shared.healthy.load(Ordering::SeqCst) && !self.shared.closing.load(Ordering::SeqCst)
+    }
+
+    /// Health snapshot including queue depth
+    pub fn health(&self) -> HealthReport {
+        let queue = self.shared.queue_metrics();
+        HealthReport {
+            healthy: self.is_healthy(),
+            worker_id: self.shared.worker_id.clone(),
+            backend: self.shared.backend,
+            queue_depth: queue.depth,
+            queue_capacity: self.shared.capacity,
+            devices: self
+                .shared
+                .devices
+                .iter()
+                .map(|stats| stats.snapshot(self.shared.started_at.elapsed()))
+                .collect(),
+            queue,
+        }
+    }
+
+    /// Stop the worker thread after the job it is currently running; queued jobs are cancelled
+    pub fn shutdown(&self) {
+        self.shared.closing.store(true, Ordering::SeqCst);
+        self.shared.pending.lock().unwrap().closed = true;
+        self.shared.job_ready.notify_all();
+    }
+}
+
+impl GpuWorker {
+    /// Generate a chunk on this worker's first device
+    pub fn generate(&self, request: &ChunkRequest) -> anyhow::Result<ChunkOutput> {
+        let device = self.devices.first().ok_or_else(|| anyhow::anyhow!("GPU worker has no devices"))?;
+        device.generate(request)
+    }
+}
+
+impl WorkerDevice {
+    fn generate(&self, request: &ChunkRequest) -> anyhow::Result<ChunkOutput> {
+        request.settings.validate().map_err(anyhow::Error::msg)?;
+        let chunk_data = self.generator.generate_chunk(
+            request.chunk_x,
+            request.chunk_z,
+            request.seed as u32,
+            &request.dimension,
+            &request.settings,
+        )?;
+
+        Ok(ChunkOutput::from_chunk_data(request, &chunk_data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CpuChunkGenerator;
+
+    fn cpu_device() -> WorkerDevice {
+        WorkerDevice {
+            info: None,
+            generator: Box::new(CpuChunkGenerator::new()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_is_split_across_devices() {
+        let worker = GpuWorker::from_devices(vec![cpu_device(), cpu_device()]);
+        let handle = GpuWorkerService::spawn(worker, QueueConfig::default());
+
+        let requests = (0..8)
+            .map(|i| ChunkRequest {
+                chunk_x: i,
+                chunk_z: 0,
+                seed: 42,
+                dimension: default_dimension(),
+                settings: WorldGenSettings::default(),
+                priority: JobPriority::Bulk,
+            })
+            .collect();
+        let ids = handle.submit_batch(requests).unwrap();
+        for id in ids {
+            handle.wait(id).await.unwrap();
+        }
+
+        let health = handle.health();
+        assert_eq!(health.backend, Backend::Cpu);
+        assert_eq!(health.devices.len(), 2);
+        assert_eq!(health.devices.iter().map(|d| d.jobs_completed).sum::<u64>(), 8);
+
+        handle.shutdown();
+    }
+
+    fn queued(priority: JobPriority, waited: Duration) -> QueuedJob {
+        QueuedJob {
+            id: Uuid::new_v4(),
+            request: ChunkRequest {
+                chunk_x: 0,
+                chunk_z: 0,
+                seed: 1,
+                dimension: default_dimension(),
+                settings: WorldGenSettings::default(),
+                priority,
+            },
+            cancel: Arc::new(AtomicBool::new(false)),
+            queued_at: Instant::now().checked_sub(waited).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_interactive_job_preempts_bulk_backlog() {
+        let threshold = Duration::from_secs(30);
+        let mut pending = PendingJobs::default();
+        for waited in [3, 2, 1] {
+            let job = queued(JobPriority::Bulk, Duration::from_secs(waited));
+            pending.lanes[JobPriority::Bulk.lane()].push_back(job);
+        }
+        pending.lanes[JobPriority::Interactive.lane()].push_back(queued(JobPriority::Interactive, Duration::ZERO));
+
+        let first = pending.pop(threshold).unwrap();
+        assert_eq!(first.job.request.priority, JobPriority::Interactive);
+        assert!(first.preempted);
+        assert!(!first.starved);
+
+        // The batch then resumes in submission order
+        let rest: Vec<Duration> = std::iter::from_fn(|| pending.pop(threshold))
+            .map(|dispatch| dispatch.job.queued_at.elapsed())
+            .collect();
+        assert_eq!(rest.len(), 3);
+        assert!(rest.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn test_starving_bulk_job_runs_first() {
+        let mut pending = PendingJobs::default();
+        pending.lanes[JobPriority::Bulk.lane()].push_back(queued(JobPriority::Bulk, Duration::from_secs(60)));
+        pending.lanes[JobPriority::Interactive.lane()].push_back(queued(JobPriority::Interactive, Duration::ZERO));
+
+        let first = pending.pop(Duration::from_secs(30)).unwrap();
+        assert_eq!(first.job.request.priority, JobPriority::Bulk);
+        assert!(first.starved);
+        assert_eq!(pending.pop(Duration::from_secs(30)).unwrap().job.request.priority, JobPriority::Interactive);
+    }
+
+    #[tokio::test]
+    async fn test_interactive_job_overtakes_running_batch() {
+        let worker = GpuWorker::from_devices(vec![cpu_device()]);
+        let handle = GpuWorkerService::spawn(worker, QueueConfig::default());
+
+        let request = |chunk_x, priority| ChunkRequest {
+            chunk_x,
+            chunk_z: 0,
+            seed: 42,
+            dimension: default_dimension(),
+            settings: WorldGenSettings::default(),
+            priority,
+        };
+        let batch = handle
+            .submit_batch((0..6).map(|i| request(i, JobPriority::Bulk)).collect())
+            .unwrap();
+        let interactive = handle.submit(request(100, JobPriority::Interactive)).unwrap();
+
+        handle.wait(interactive).await.unwrap();
+        assert_ne!(handle.status(*batch.last().unwrap()), Some(JobStatus::Completed));
+
+        let queue = handle.health().queue;
+        assert_eq!(queue.preemptions, 1);
+        assert_eq!(queue.priorities[JobPriority::Interactive.lane()].dispatched, 1);
+
+        for id in batch {
+            handle.wait(id).await.unwrap();
+        }
+        handle.shutdown();
+    }
+}
@@ -0,0 +1,208 @@
+//! Determinism self-test for chunk generation.
+//!
+//! Generates a fixed set of sample chunks several times through the job queue
+//! and compares their content digests with each other and with the CPU
+//! reference implementation. Pregen output is only worth writing to a world if
+//! the same chunk always comes back bit-for-bit identical.
+
+use serde::{Deserialize, Serialize};
+
+use crate::kernels::{Backend, ChunkGenerator, CpuChunkGenerator, WorldGenSettings};
+use crate::queue::{ChunkOutput, ChunkRequest, GpuWorkerHandle, JobPriority, QueueError};
+
+/// Sample chunks covering every dimension, negative coordinates and far-out positions
+const SAMPLE_CHUNKS: [(i32, i32, &str); 6] = [
+    (0, 0, "overworld"),
+    (-7, 12, "overworld"),
+    (1875, -1875, "overworld"),
+    (3, -5, "nether"),
+    (-40, 40, "nether"),
+    (100, 100, "end"),
+];
+
+/// Self-test parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SelfTestConfig {
+    pub seed: i64,
+    /// How many times each sample chunk is generated on the worker
+    pub iterations: u32,
+}
+
+impl Default for SelfTestConfig {
+    fn default() -> Self {
+        Self { seed: 12345, iterations: 3 }
+    }
+}
+
+/// Outcome for one sample chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkCheck {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub dimension: String,
+    /// Digest of the CPU reference output
+    pub reference_digest: String,
+    /// Digest of every worker run, in submission order
+    pub digests: Vec<String>,
+    /// All worker runs produced the same output
+    pub deterministic: bool,
+    /// Worker output matches the CPU reference
+    pub matches_reference: bool,
+}
+
+/// Result of a determinism self-test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub worker_id: String,
+    /// Backend the worker generated on
+    pub backend: Backend,
+    pub seed: i64,
+    pub iterations: u32,
+    pub chunks: Vec<ChunkCheck>,
+    pub duration_ms: u64,
+}
+
+/// 64-bit FNV-1a over the full chunk payload.
+///
+/// Unlike `content_hash`, which only covers biomes, this includes every density
+/// value (by bit pattern), mask cell, carver cell and structure candidate.
+pub fn content_digest(output: &ChunkOutput) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let words = output
+        .density_data
+        .iter()
+        .map(|value| value.to_bits())
+        .chain(output.mask_data.iter().copied())
+        .chain(output.biome_data.iter().copied())
+        .chain(output.carve_mask.iter().copied())
+        .chain(output.structure_candidates.iter().copied());
+
+    words.fold(OFFSET, |hash, word| {
+        word.to_le_bytes()
+            .iter()
+            .fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+    })
+}
+
+fn format_digest(digest: u64) -> String {
+    format!("{:016x}", digest)
+}
+
+fn sample_requests(seed: i64) -> Vec<ChunkRequest> {
+    SAMPLE_CHUNKS
+        .iter()
+        .map(|&(chunk_x, chunk_z, dimension)| ChunkRequest {
+            chunk_x,
+            chunk_z,
+            seed,
+            dimension: dimension.to_string(),
+            settings: WorldGenSettings::default(),
+            priority: JobPriority::Normal,
+        })
+        .collect()
+}
+
+impl GpuWorkerHandle {
+    /// Generate the sample chunks `iterations` times and check every run against the CPU reference
+    pub async fn self_test(&self, config: SelfTestConfig) -> Result<SelfTestReport, QueueError> {
+        let started = std::time::Instant::now();
+        let requests = sample_requests(config.seed);
+        let iterations = config.iterations.max(1);
+
+        // One round per iteration so the batch never outgrows the queue
+        let mut runs: Vec<Vec<String>> = vec![Vec::with_capacity(iterations as usize); requests.len()];
+        for _ in 0..iterations {
+            let ids = self.submit_batch(requests.clone())?;
+            for (slot, id) in ids.into_iter().enumerate() {
+                let output = self.wait(id).await?;
+                runs[slot].push(format_digest(content_digest(&output)));
+            }
+        }
+
+        let references = tokio::task::spawn_blocking({
+            let requests = requests.clone();
+            move || -> anyhow::Result<Vec<String>> {
+                let generator = CpuChunkGenerator::new();
+                requests
+                    .iter()
+                    .map(|request| {
+                        let chunk = generator.generate_chunk(
+                            request.chunk_x,
+                            request.chunk_z,
+                            request.seed as u32,
+                            &request.dimension,
+                            &request.settings,
+                        )?;
+                        Ok(format_digest(content_digest(&ChunkOutput::from_chunk_data(request, &chunk))))
+                    })
+                    .collect()
+            }
+        })
+        .await
+        .map_err(|e| QueueError::Generation(e.to_string()))?
+        .map_err(|e| QueueError::Generation(e.to_string()))?;
+
+        let chunks: Vec<ChunkCheck> = requests
+            .into_iter()
+            .zip(references)
+            .zip(runs)
+            .map(|((request, reference_digest), digests)| ChunkCheck {
+                deterministic: digests.windows(2).all(|pair| pair[0] == pair[1]),
+                matches_reference: digests.iter().all(|digest| *digest == reference_digest),
+                chunk_x: request.chunk_x,
+                chunk_z: request.chunk_z,
+                dimension: request.dimension,
+                reference_digest,
+                digests,
+            })
+            .collect();
+
+        let health = self.health();
+        Ok(SelfTestReport {
+            passed: chunks.iter().all(|check| check.deterministic && check.matches_reference),
+            worker_id: health.worker_id,
+            backend: health.backend,
+            seed: config.seed,
+            iterations,
+            chunks,
+            duration_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::{GpuWorkerService, QueueConfig};
+    use crate::GpuWorker;
+
+    #[tokio::test]
+    async fn test_cpu_worker_passes_self_test() {
+        let worker = GpuWorker::with_generator(Box::new(CpuChunkGenerator::new()));
+        let handle = GpuWorkerService::spawn(worker, QueueConfig::default());
+
+        let report = handle.self_test(SelfTestConfig { seed: 99, iterations: 2 }).await.unwrap();
+        assert!(report.passed);
+        assert_eq!(report.backend, Backend::Cpu);
+        assert_eq!(report.chunks.len(), SAMPLE_CHUNKS.len());
+        assert!(report.chunks.iter().all(|check| check.digests.len() == 2));
+
+        handle.shutdown();
+    }
+
+    #[test]
+    fn test_digest_covers_density() {
+        let request = sample_requests(7).remove(0);
+        let chunk = CpuChunkGenerator::new().generate_chunk(0, 0, 7, "overworld", &WorldGenSettings::default()).unwrap();
+        let output = ChunkOutput::from_chunk_data(&request, &chunk);
+
+        let mut changed = output.clone();
+        changed.density_data[1000] += 1.0;
+        assert_eq!(output.content_hash, changed.content_hash);
+        assert_ne!(content_digest(&output), content_digest(&changed));
+    }
+}
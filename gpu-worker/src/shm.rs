@@ -0,0 +1,547 @@
+//! Shared-memory transport for chunk results.
+//!
+//! A generated chunk is well over a megabyte, and sending it as JSON over the
+//! IPC socket costs more than generating it. The worker creates a
+//! memory-mapped ring of fixed-size slots at startup; clients map the same
+//! file after an IPC handshake, and a shared `wait` is answered with a slot
+//! reference instead of the payload. The client reads the slot in place and
+//! hands it back by dropping the [`SharedChunk`].
+
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use memmap2::MmapMut;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use tracing::warn;
+
+use crate::queue::ChunkOutput;
+
+/// Slots created when the worker is not told otherwise
+pub const DEFAULT_SLOTS: u32 = 16;
+
+const MAGIC: u32 = u32::from_le_bytes(*b"GSHM");
+const VERSION: u32 = 1;
+
+/// The ring header gets a whole page so every slot starts page-aligned
+const RING_HEADER_SIZE: usize = 4096;
+const SLOT_HEADER_SIZE: usize = 64;
+
+/// Density, mask, biome, carve and structure sections, in that order
+const SECTIONS: usize = 5;
+
+/// Largest payload a slot holds: three per-block sections and two 16x16 maps
+const PAYLOAD_WORDS: usize = 3 * 16 * 16 * 384 + 2 * 16 * 16;
+
+/// A slot nobody read within this long (crashed client, dropped response) is reused
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+// Slot states
+const EMPTY: u32 = 0;
+const WRITING: u32 = 1;
+const READY: u32 = 2;
+const READING: u32 = 3;
+
+#[repr(C)]
+struct RingHeader {
+    magic: u32,
+    version: u32,
+    slot_count: u32,
+    _reserved: u32,
+    slot_size: u64,
+}
+
+#[repr(C)]
+struct SlotHeader {
+    state: AtomicU32,
+    content_hash: u32,
+    generation: u64,
+    written_at_ms: AtomicU64,
+    seed: i64,
+    chunk_x: i32,
+    chunk_z: i32,
+    lens: [u32; SECTIONS],
+    _reserved: u32,
+}
+
+const _: () = assert!(std::mem::size_of::<SlotHeader>() == SLOT_HEADER_SIZE);
+
+/// Errors from mapping or reading a result ring
+#[derive(Debug, thiserror::Error)]
+pub enum ShmError {
+    #[error("shared memory I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid shared memory ring: {0}")]
+    Layout(String),
+    #[error("shared memory slot {slot} no longer holds result generation {generation}")]
+    Stale { slot: u32, generation: u64 },
+}
+
+/// What a client needs to map the worker's ring
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RingInfo {
+    pub path: PathBuf,
+    pub slot_count: u32,
+    pub slot_size: u64,
+}
+
+/// A result written into the ring
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotRef {
+    pub slot: u32,
+    /// Guards against reading a slot that has since been reused
+    pub generation: u64,
+}
+
+/// Ring file next to the IPC endpoint, on tmpfs where available
+pub fn default_path(endpoint: &str) -> PathBuf {
+    let name = Path::new(endpoint)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "guardian-gpu-worker".to_string());
+    let dir = if Path::new("/dev/shm").is_dir() {
+        PathBuf::from("/dev/shm")
+    } else {
+        std::env::temp_dir()
+    };
+    dir.join(format!("{}.results", name))
+}
+
+fn slot_size() -> usize {
+    let bytes = SLOT_HEADER_SIZE + PAYLOAD_WORDS * std::mem::size_of::<u32>();
+    bytes.div_ceil(RING_HEADER_SIZE) * RING_HEADER_SIZE
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Fixed-size result slots in a memory-mapped file shared by worker and client
+pub struct ResultRing {
+    _map: MmapMut,
+    base: *mut u8,
+    path: PathBuf,
+    slot_count: u32,
+    slot_size: usize,
+    /// Next slot the writer tries
+    cursor: AtomicU32,
+    generation: AtomicU64,
+    /// The creating side removes the file when it goes away
+    owner: bool,
+}
+
+// SAFETY: slots change hands only through atomic transitions of their state
+// word, so no two threads touch the same slot's payload at once
+unsafe impl Send for ResultRing {}
+unsafe impl Sync for ResultRing {}
+
+impl std::fmt::Debug for ResultRing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResultRing")
+            .field("path", &self.path)
+            .field("slot_count", &self.slot_count)
+            .field("slot_size", &self.slot_size)
+            .finish()
+    }
+}
+
+impl ResultRing {
+    /// Create the ring file, replacing any left behind by an earlier worker
+    pub fn create(path: impl AsRef<Path>, slot_count: u32) -> Result<Self, ShmError> {
+        if slot_count == 0 {
+            return Err(ShmError::Layout("a ring needs at least one slot".to_string()));
+        }
+
+        let path = path.as_ref().to_path_buf();
+        let slot_size = slot_size();
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        file.set_len((RING_HEADER_SIZE + slot_size * slot_count as usize) as u64)?;
+
+        // SAFETY: the file was just created by us; other processes only map it after the handshake
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        let base = map.as_mut_ptr();
+        // SAFETY: the mapping is page-aligned and larger than the header; a fresh file is
+        // zero-filled, so every slot starts out EMPTY
+        unsafe {
+            std::ptr::write(
+                base as *mut RingHeader,
+                RingHeader {
+                    magic: MAGIC,
+                    version: VERSION,
+                    slot_count,
+                    _reserved: 0,
+                    slot_size: slot_size as u64,
+                },
+            );
+        }
+
+        Ok(Self {
+            _map: map,
+            base,
+            path,
+            slot_count,
+            slot_size,
+            cursor: AtomicU32::new(0),
+            generation: AtomicU64::new(1),
+            owner: true,
+        })
+    }
+
+    /// Map a ring created by a worker
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ShmError> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let len = file.metadata()?.len() as usize;
+        if len < RING_HEADER_SIZE {
+            return Err(ShmError::Layout(format!("{} is too small to hold a ring", path.display())));
+        }
+
+        // SAFETY: the worker never resizes the file while it is mapped
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        let base = map.as_mut_ptr();
+        // SAFETY: checked above that the header fits
+        let header = unsafe { std::ptr::read(base as *const RingHeader) };
+        if header.magic != MAGIC || header.version != VERSION {
+            return Err(ShmError::Layout(format!(
+                "{} is not a version {} result ring",
+                path.display(),
+                VERSION
+            )));
+        }
+        let slot_size = header.slot_size as usize;
+        if slot_size != self::slot_size() || len < RING_HEADER_SIZE + slot_size * header.slot_count as usize {
+            return Err(ShmError::Layout(format!("{} has an unexpected slot layout", path.display())));
+        }
+
+        Ok(Self {
+            _map: map,
+            base,
+            path,
+            slot_count: header.slot_count,
+            slot_size,
+            cursor: AtomicU32::new(0),
+            generation: AtomicU64::new(1),
+            owner: false,
+        })
+    }
+
+    pub fn info(&self) -> RingInfo {
+        RingInfo {
+            path: self.path.clone(),
+            slot_count: self.slot_count,
+            slot_size: self.slot_size as u64,
+        }
+    }
+
+    fn slot_header(&self, slot: u32) -> *mut SlotHeader {
+        // SAFETY: callers pass slot < slot_count, which lies inside the mapping
+        unsafe { self.base.add(RING_HEADER_SIZE + slot as usize * self.slot_size) as *mut SlotHeader }
+    }
+
+    fn slot_payload(&self, slot: u32) -> *mut u32 {
+        // SAFETY: as above; the payload directly follows the slot header
+        unsafe { (self.slot_header(slot) as *mut u8).add(SLOT_HEADER_SIZE) as *mut u32 }
+    }
+
+    fn state(&self, slot: u32) -> &AtomicU32 {
+        // SAFETY: the state word is only ever accessed atomically
+        unsafe { &(*self.slot_header(slot)).state }
+    }
+
+    /// Take a free slot for writing, reusing results no client collected in time
+    fn claim(&self) -> Option<u32> {
+        let now = now_ms();
+        for _ in 0..self.slot_count {
+            let slot = self.cursor.fetch_add(1, Ordering::Relaxed) % self.slot_count;
+            let state = self.state(slot);
+            if state.compare_exchange(EMPTY, WRITING, Ordering::Acquire, Ordering::Relaxed).is_ok() {
+                return Some(slot);
+            }
+
+            // SAFETY: written_at_ms is atomic and slot is in range
+            let written_at = unsafe { (*self.slot_header(slot)).written_at_ms.load(Ordering::Relaxed) };
+            if now.saturating_sub(written_at) > STALE_AFTER.as_millis() as u64
+                && state.compare_exchange(READY, WRITING, Ordering::Acquire, Ordering::Relaxed).is_ok()
+            {
+                warn!("Reusing shared memory slot {} that was never read", slot);
+                return Some(slot);
+            }
+        }
+        None
+    }
+
+    /// Copy a result into a free slot; `None` when the ring is full or the result does not fit
+    pub fn write(&self, output: &ChunkOutput) -> Option<SlotRef> {
+        let sections: [&[u32]; SECTIONS] = [
+            bytemuck::cast_slice(&output.density_data),
+            &output.mask_data,
+            &output.biome_data,
+            &output.carve_mask,
+            &output.structure_candidates,
+        ];
+        if sections.iter().map(|section| section.len()).sum::<usize>() > PAYLOAD_WORDS {
+            return None;
+        }
+
+        let slot = self.claim()?;
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed);
+        let header = self.slot_header(slot);
+        // SAFETY: claim() moved the slot to WRITING, so this thread owns it until the READY store
+        unsafe {
+            (*header).content_hash = output.content_hash;
+            (*header).generation = generation;
+            (*header).seed = output.seed;
+            (*header).chunk_x = output.chunk_x;
+            (*header).chunk_z = output.chunk_z;
+
+            let mut payload = self.slot_payload(slot);
+            for (index, section) in sections.iter().enumerate() {
+                (*header).lens[index] = section.len() as u32;
+                std::ptr::copy_nonoverlapping(section.as_ptr(), payload, section.len());
+                payload = payload.add(section.len());
+            }
+
+            (*header).written_at_ms.store(now_ms(), Ordering::Relaxed);
+        }
+        self.state(slot).store(READY, Ordering::Release);
+
+        Some(SlotRef { slot, generation })
+    }
+
+    /// Borrow a written result in place; the slot is released when the view is dropped
+    pub fn read(&self, slot: SlotRef) -> Result<SharedChunk<'_>, ShmError> {
+        let stale = ShmError::Stale { slot: slot.slot, generation: slot.generation };
+        if slot.slot >= self.slot_count {
+            return Err(stale);
+        }
+
+        let state = self.state(slot.slot);
+        if state.compare_exchange(READY, READING, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            return Err(stale);
+        }
+
+        let header = self.slot_header(slot.slot);
+        // SAFETY: the slot is READING, so the writer will not touch it
+        let (generation, lens) = unsafe { ((*header).generation, (*header).lens) };
+        if generation != slot.generation {
+            // Someone else's result; leave it for them
+            state.store(READY, Ordering::Release);
+            return Err(stale);
+        }
+        if lens.iter().map(|&len| len as usize).sum::<usize>() > PAYLOAD_WORDS {
+            state.store(EMPTY, Ordering::Release);
+            return Err(ShmError::Layout(format!("slot {} overflows its payload", slot.slot)));
+        }
+
+        Ok(SharedChunk { ring: self, slot: slot.slot })
+    }
+}
+
+impl Drop for ResultRing {
+    fn drop(&mut self) {
+        if self.owner {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// A chunk result read in place from the ring
+pub struct SharedChunk<'a> {
+    ring: &'a ResultRing,
+    slot: u32,
+}
+
+impl SharedChunk<'_> {
+    fn header(&self) -> &SlotHeader {
+        // SAFETY: the slot is READING for as long as this view lives
+        unsafe { &*self.ring.slot_header(self.slot) }
+    }
+
+    fn section(&self, index: usize) -> &[u32] {
+        let lens = &self.header().lens;
+        let offset: usize = lens[..index].iter().map(|&len| len as usize).sum();
+        // SAFETY: read() checked that all sections fit in the payload
+        unsafe { std::slice::from_raw_parts(self.ring.slot_payload(self.slot).add(offset), lens[index] as usize) }
+    }
+
+    pub fn chunk_x(&self) -> i32 {
+        self.header().chunk_x
+    }
+
+    pub fn chunk_z(&self) -> i32 {
+        self.header().chunk_z
+    }
+
+    pub fn seed(&self) -> i64 {
+        self.header().seed
+    }
+
+    pub fn content_hash(&self) -> u32 {
+        self.header().content_hash
+    }
+
+    pub fn density_data(&self) -> &[f32] {
+        bytemuck::cast_slice(self.section(0))
+    }
+
+    pub fn mask_data(&self) -> &[u32] {
+        self.section(1)
+    }
+
+    pub fn biome_data(&self) -> &[u32] {
+        self.section(2)
+    }
+
+    pub fn carve_mask(&self) -> &[u32] {
+        self.section(3)
+    }
+
+    pub fn structure_candidates(&self) -> &[u32] {
+        self.section(4)
+    }
+
+    /// Copy the result out of shared memory
+    pub fn to_output(&self) -> ChunkOutput {
+        ChunkOutput {
+            chunk_x: self.chunk_x(),
+            chunk_z: self.chunk_z(),
+            seed: self.seed(),
+            content_hash: self.content_hash(),
+            density_data: self.density_data().to_vec(),
+            mask_data: self.mask_data().to_vec(),
+            biome_data: self.biome_data().to_vec(),
+            carve_mask: self.carve_mask().to_vec(),
+            structure_candidates: self.structure_candidates().to_vec(),
+        }
+    }
+}
+
+impl Drop for SharedChunk<'_> {
+    fn drop(&mut self) {
+        self.ring.state(self.slot).store(EMPTY, Ordering::Release);
+    }
+}
+
+/// Serializes exactly like [`ChunkOutput`], straight from the mapping
+impl Serialize for SharedChunk<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ChunkOutput", 9)?;
+        state.serialize_field("chunk_x", &self.chunk_x())?;
+        state.serialize_field("chunk_z", &self.chunk_z())?;
+        state.serialize_field("seed", &self.seed())?;
+        state.serialize_field("content_hash", &self.content_hash())?;
+        state.serialize_field("density_data", self.density_data())?;
+        state.serialize_field("mask_data", self.mask_data())?;
+        state.serialize_field("biome_data", self.biome_data())?;
+        state.serialize_field("carve_mask", self.carve_mask())?;
+        state.serialize_field("structure_candidates", self.structure_candidates())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::{ChunkGenerator, CpuChunkGenerator, WorldGenSettings};
+    use crate::queue::ChunkRequest;
+
+    fn temp_ring_path() -> PathBuf {
+        std::env::temp_dir().join(format!("gpu-worker-test-{}.results", uuid::Uuid::new_v4()))
+    }
+
+    fn sample_output(chunk_x: i32) -> ChunkOutput {
+        let request = ChunkRequest {
+            chunk_x,
+            chunk_z: -3,
+            seed: 42,
+            dimension: "overworld".to_string(),
+            settings: WorldGenSettings::default(),
+            priority: Default::default(),
+        };
+        let chunk = CpuChunkGenerator::new()
+            .generate_chunk(chunk_x, -3, 42, "overworld", &request.settings)
+            .unwrap();
+        ChunkOutput::from_chunk_data(&request, &chunk)
+    }
+
+    #[test]
+    fn test_round_trip_between_mappings() {
+        let path = temp_ring_path();
+        let writer = ResultRing::create(&path, 2).unwrap();
+        let reader = ResultRing::open(&path).unwrap();
+        assert_eq!(reader.info(), writer.info());
+
+        let output = sample_output(5);
+        let slot = writer.write(&output).unwrap();
+        let chunk = reader.read(slot).unwrap();
+        assert_eq!(chunk.chunk_x(), 5);
+        assert_eq!(chunk.content_hash(), output.content_hash);
+        assert_eq!(chunk.density_data(), &output.density_data[..]);
+        assert_eq!(chunk.carve_mask(), &output.carve_mask[..]);
+        assert_eq!(
+            serde_json::to_value(&chunk).unwrap(),
+            serde_json::to_value(&output).unwrap()
+        );
+        drop(chunk);
+
+        // A released slot cannot be read again
+        assert!(matches!(reader.read(slot), Err(ShmError::Stale { .. })));
+
+        drop(reader);
+        drop(writer);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_full_ring_rejects_writes_until_released() {
+        let ring = ResultRing::create(temp_ring_path(), 1).unwrap();
+        let output = sample_output(0);
+
+        let first = ring.write(&output).unwrap();
+        assert!(ring.write(&output).is_none());
+
+        drop(ring.read(first).unwrap());
+        let second = ring.write(&output).unwrap();
+        assert_ne!(first.generation, second.generation);
+        assert!(matches!(ring.read(first), Err(ShmError::Stale { .. })));
+        assert!(ring.read(second).is_ok());
+    }
+
+    #[test]
+    fn test_open_rejects_foreign_files() {
+        let path = temp_ring_path();
+        std::fs::write(&path, vec![0u8; RING_HEADER_SIZE * 2]).unwrap();
+        assert!(matches!(ResultRing::open(&path), Err(ShmError::Layout(_))));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Repeated ring round-trips read back what the JSON payload would carry
+    #[test]
+    fn test_shared_memory_matches_inline() {
+        const ROUNDS: usize = 20;
+
+        let ring = ResultRing::create(temp_ring_path(), 4).unwrap();
+        let output = sample_output(1);
+        let payload = serde_json::to_vec(&output).unwrap();
+        let inline: ChunkOutput = serde_json::from_slice(&payload).unwrap();
+
+        for _ in 0..ROUNDS {
+            let slot = ring.write(&output).unwrap();
+            let chunk = ring.read(slot).unwrap();
+            assert_eq!(chunk.content_hash(), inline.content_hash);
+            assert_eq!(chunk.density_data(), inline.density_data.as_slice());
+            assert_eq!(chunk.mask_data(), inline.mask_data.as_slice());
+            assert_eq!(chunk.biome_data(), inline.biome_data.as_slice());
+            assert_eq!(chunk.carve_mask(), inline.carve_mask.as_slice());
+            assert_eq!(chunk.structure_candidates(), inline.structure_candidates.as_slice());
+        }
+    }
+}
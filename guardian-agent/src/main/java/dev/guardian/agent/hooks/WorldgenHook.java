@@ -5,7 +5,11 @@ import dev.guardian.agent.jni.GpuBridge;
 import net.minecraft.core.BlockPos;
 import net.minecraft.server.level.ServerLevel;
 import net.minecraft.world.level.chunk.ChunkAccess;
+import net.minecraft.world.level.chunk.ChunkGenerator;
 import net.minecraft.world.level.chunk.LevelChunk;
+import net.minecraft.world.level.levelgen.FlatLevelSource;
+import net.minecraft.world.level.levelgen.NoiseBasedChunkGenerator;
+import net.minecraft.world.level.levelgen.NoiseGeneratorSettings;
 import org.slf4j.Logger;
 import org.slf4j.LoggerFactory;
 
@@ -109,7 +113,10 @@ public class WorldgenHook {
                     level.getSeed(),
                     level.dimension().location().toString(),
                     level.getServer().getTickCount(),
-                    ruleEngine.getRuleVersion()
+                    ruleEngine.getRuleVersion(),
+                    level.getSeaLevel(),
+                    isAmplified(level),
+                    terrainPreset(level)
                 );
                 
                 // Submit to GPU worker
@@ -122,6 +129,29 @@ public class WorldgenHook {
         });
     }
     
+    /**
+     * Whether the level uses the amplified noise settings
+     */
+    private static boolean isAmplified(ServerLevel level) {
+        return level.getChunkSource().getGenerator() instanceof NoiseBasedChunkGenerator noise
+            && noise.generatorSettings().is(NoiseGeneratorSettings.AMPLIFIED);
+    }
+    
+    /**
+     * GPU worker terrain preset matching the level's chunk generator
+     */
+    private static String terrainPreset(ServerLevel level) {
+        ChunkGenerator generator = level.getChunkSource().getGenerator();
+        if (generator instanceof FlatLevelSource) {
+            return "flat";
+        }
+        if (generator instanceof NoiseBasedChunkGenerator noise
+                && noise.generatorSettings().is(NoiseGeneratorSettings.LARGE_BIOMES)) {
+            return "large_biomes";
+        }
+        return "normal";
+    }
+    
     /**
      * Integrates GPU-generated chunk data with Minecraft's chunk system
      */
@@ -237,14 +267,21 @@ public class WorldgenHook {
         public final String dimension;
         public final int tickCount;
         public final String ruleVersion;
+        public final int seaLevel;
+        public final boolean amplified;
+        public final String preset;
         
-        public ChunkJob(int chunkX, int chunkZ, long seed, String dimension, int tickCount, String ruleVersion) {
+        public ChunkJob(int chunkX, int chunkZ, long seed, String dimension, int tickCount, String ruleVersion,
+                        int seaLevel, boolean amplified, String preset) {
             this.chunkX = chunkX;
             this.chunkZ = chunkZ;
             this.seed = seed;
             this.dimension = dimension;
             this.tickCount = tickCount;
             this.ruleVersion = ruleVersion;
+            this.seaLevel = seaLevel;
+            this.amplified = amplified;
+            this.preset = preset;
         }
     }
     
@@ -57,12 +57,23 @@ public class GpuBridge {
         public String dimension;
         public int tickCount;
         public String ruleVersion;
+        // World settings; octaves 0 keeps the worker default
+        public int seaLevel;
+        public int octaves;
+        public int amplified;
+        public String preset;
+        // Only read for the "custom" preset
+        public float heightScale = 1.0f;
+        public float horizontalScale = 1.0f;
+        public float biomeScale = 1.0f;
         
         public static class ByValue extends ChunkJob implements Structure.ByValue {}
         
         @Override
         protected List<String> getFieldOrder() {
-            return Arrays.asList("chunkX", "chunkZ", "seed", "dimension", "tickCount", "ruleVersion");
+            return Arrays.asList("chunkX", "chunkZ", "seed", "dimension", "tickCount", "ruleVersion",
+                               "seaLevel", "octaves", "amplified", "preset",
+                               "heightScale", "horizontalScale", "biomeScale");
         }
     }
     
@@ -80,6 +91,10 @@ public class GpuBridge {
         public int maskDataSize;
         public Pointer biomeData;
         public int biomeDataSize;
+        public Pointer carveData;
+        public int carveDataSize;
+        public Pointer structureData;
+        public int structureDataSize;
         public int status; // 0 = success, 1 = error, 2 = not ready
         
         public static class ByReference extends ChunkResult implements Structure.ByReference {}
@@ -89,7 +104,9 @@ public class GpuBridge {
             return Arrays.asList("chunkX", "chunkZ", "seed", "contentHash", 
                                "densityData", "densityDataSize",
                                "maskData", "maskDataSize",
-                               "biomeData", "biomeDataSize", "status");
+                               "biomeData", "biomeDataSize",
+                               "carveData", "carveDataSize",
+                               "structureData", "structureDataSize", "status");
         }
     }
     
@@ -142,6 +159,9 @@ public class GpuBridge {
             jobValue.dimension = job.dimension;
             jobValue.tickCount = job.tickCount;
             jobValue.ruleVersion = job.ruleVersion;
+            jobValue.seaLevel = job.seaLevel;
+            jobValue.amplified = job.amplified ? 1 : 0;
+            jobValue.preset = job.preset;
             
             Pointer handle = new Pointer(0);
             int result = gpuLib.gpuw_submit_chunk_job(jobValue, handle);
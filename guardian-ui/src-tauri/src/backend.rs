@@ -0,0 +1,551 @@
+// Supervisor for the hostd backend
+//
+// Owns the hostd child process, tracks whether it is up, restarts it with
+// exponential backoff when it exits, and emits `backend:status` on every
+// change. A backend that was already running, such as the hostd service, is
+// used as is and only watched. Finding, checking and starting hostd go
+// through `Hostd` so the restart logic can be tested without one.
+
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::watch;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+/// Event carrying a `BackendStatus` whenever it changes
+pub const BACKEND_STATUS_EVENT: &str = "backend:status";
+
+const PORT_RANGE: std::ops::RangeInclusive<u16> = 52100..=52150;
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(20);
+const HEALTH_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Healthy this long after a restart and the backoff starts over
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+/// A hostd that stops answering for this long is killed and restarted
+const HUNG_AFTER: Duration = Duration::from_secs(30);
+/// How long hostd may take to shut down before it is killed: its servers get
+/// its `STOP_TIMEOUT` of 30s to stop, in parallel, then it saves its own state
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendState {
+    Starting,
+    Healthy,
+    /// Running but failing health checks
+    Degraded,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize, specta::Type)]
+pub struct BackendStatus {
+    pub state: BackendState,
+    pub url: Option<String>,
+    /// Whether the backend was already running rather than started by the app
+    pub external: bool,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+pub struct BackendSupervisor {
+    child: Mutex<Option<Child>>,
+    status: watch::Sender<BackendStatus>,
+    started: AtomicBool,
+    stopping: AtomicBool,
+}
+
+impl Default for BackendSupervisor {
+    fn default() -> Self {
+        let (status, _) = watch::channel(BackendStatus {
+            state: BackendState::Stopped,
+            url: None,
+            external: false,
+            restarts: 0,
+            last_error: None,
+        });
+        Self {
+            child: Mutex::new(None),
+            status,
+            started: AtomicBool::new(false),
+            stopping: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Delay before restart number `attempt` (1-based): 1s, 2s, 4s, ... up to a minute
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1u64 << attempt.saturating_sub(1).min(6)).min(MAX_BACKOFF)
+}
+
+async fn is_healthy(url: &str) -> bool {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .unwrap_or_default();
+    matches!(client.get(format!("{}/healthz", url)).send().await, Ok(resp) if resp.status().is_success())
+}
+
+/// Ask hostd to stop its servers and exit
+async fn request_shutdown(url: &str) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client.post(format!("{}/api/system/shutdown", url)).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("hostd answered {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Wait up to `timeout` for `child` to exit; false if it is still running
+fn wait_for_exit(child: &mut Child, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => return true,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(200)),
+            Ok(None) => return false,
+            Err(e) => {
+                crate::log_debug(&format!("Failed to check hostd: {}", e));
+                return false;
+            }
+        }
+    }
+}
+
+/// First port in the backend range answering `/healthz`
+async fn find_healthy_backend() -> Option<String> {
+    for port in PORT_RANGE {
+        let url = format!("http://127.0.0.1:{}", port);
+        if is_healthy(&url).await {
+            return Some(url);
+        }
+    }
+    None
+}
+
+/// The backend the supervisor looks after
+trait Hostd: Sync {
+    /// URL of a backend that is up, whether or not the app started it
+    async fn find_running(&self) -> Option<String>;
+    async fn is_healthy(&self, url: &str) -> bool;
+    async fn spawn(&self) -> Result<Child, String>;
+}
+
+/// The bundled hostd, run from the app's data directory
+struct LocalHostd<R: Runtime> {
+    app: AppHandle<R>,
+}
+
+impl<R: Runtime> Hostd for LocalHostd<R> {
+    async fn find_running(&self) -> Option<String> {
+        find_healthy_backend().await
+    }
+
+    async fn is_healthy(&self, url: &str) -> bool {
+        is_healthy(url).await
+    }
+
+    async fn spawn(&self) -> Result<Child, String> {
+        spawn_hostd(&self.app).await
+    }
+}
+
+impl BackendSupervisor {
+    pub fn status(&self) -> BackendStatus {
+        self.status.borrow().clone()
+    }
+
+    /// Change the status; subscribers only hear of changes to the state, URL
+    /// or restart count
+    fn update(&self, change: impl FnOnce(&mut BackendStatus)) {
+        self.status.send_if_modified(|status| {
+            let before = (status.state, status.url.clone(), status.restarts);
+            change(status);
+            before != (status.state, status.url.clone(), status.restarts)
+        });
+    }
+
+    /// Start supervising; later calls do nothing
+    pub fn start<R: Runtime>(&self, app: AppHandle<R>) {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let mut receiver = self.status.subscribe();
+        let events = app.clone();
+        tauri::async_runtime::spawn(async move {
+            while receiver.changed().await.is_ok() {
+                let status = receiver.borrow_and_update().clone();
+                crate::log_debug(&format!("Backend is {:?} at {:?}", status.state, status.url));
+                let _ = events.emit(BACKEND_STATUS_EVENT, status);
+            }
+        });
+        tauri::async_runtime::spawn(async move {
+            let supervisor = app.state::<BackendSupervisor>();
+            supervisor.supervise(&LocalHostd { app: app.clone() }).await;
+        });
+    }
+
+    /// Wait until the backend is healthy and return its URL
+    pub async fn wait_until_healthy(&self, timeout: Duration) -> Result<String, String> {
+        let mut receiver = self.status.subscribe();
+        let wait = receiver.wait_for(|status| status.state == BackendState::Healthy && status.url.is_some());
+        let result = tokio::time::timeout(timeout, wait).await;
+        match result {
+            Ok(Ok(status)) => Ok(status.url.clone().unwrap_or_default()),
+            _ => Err(self.status().last_error.unwrap_or_else(|| "Backend did not become healthy".to_string())),
+        }
+    }
+
+    /// Stop supervising and end the hostd process the app started. hostd is
+    /// asked to shut down first so its servers stop cleanly, and is only
+    /// killed if it is still running after `SHUTDOWN_TIMEOUT`. Blocks until
+    /// hostd is gone, so it must not be called from an async task.
+    pub fn stop(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        let child = self.child.lock().ok().and_then(|mut child| child.take());
+        if let Some(mut child) = child {
+            let asked = match self.status().url {
+                Some(url) => {
+                    crate::log_debug("Asking hostd to shut down...");
+                    let result = tauri::async_runtime::block_on(request_shutdown(&url));
+                    if let Err(e) = &result {
+                        crate::log_debug(&format!("Failed to ask hostd to shut down: {}", e));
+                    }
+                    result.is_ok()
+                }
+                None => false,
+            };
+            if !asked || !wait_for_exit(&mut child, SHUTDOWN_TIMEOUT) {
+                crate::log_debug("Terminating hostd process...");
+                let _ = child.kill();
+            }
+            let _ = child.wait();
+        }
+        self.status.send_modify(|status| status.state = BackendState::Stopped);
+    }
+
+    async fn supervise(&self, hostd: &impl Hostd) {
+        let mut attempt = 0;
+        while !self.stopping.load(Ordering::SeqCst) {
+            // A backend that is already up, e.g. the hostd service, is used as is
+            if let Some(url) = hostd.find_running().await {
+                self.update(|status| {
+                    status.state = BackendState::Healthy;
+                    status.url = Some(url.clone());
+                    status.external = true;
+                    status.last_error = None;
+                });
+                self.watch_external(hostd, &url).await;
+                continue;
+            }
+
+            if attempt > 0 {
+                let delay = backoff(attempt);
+                crate::log_debug(&format!("Restarting hostd in {:?} (attempt {})", delay, attempt));
+                tokio::time::sleep(delay).await;
+                if self.stopping.load(Ordering::SeqCst) {
+                    break;
+                }
+            }
+
+            self.update(|status| {
+                status.state = BackendState::Starting;
+                status.url = None;
+                status.external = false;
+            });
+            match hostd.spawn().await {
+                Ok(child) => {
+                    crate::log_debug(&format!("Hostd process started with PID: {}", child.id()));
+                    if let Ok(mut guard) = self.child.lock() {
+                        *guard = Some(child);
+                    }
+                    let healthy_since = self.watch_child(hostd).await;
+                    attempt = match healthy_since {
+                        Some(since) if since.elapsed() >= STABLE_AFTER => 1,
+                        _ => attempt + 1,
+                    };
+                }
+                Err(e) => {
+                    crate::log_debug(&format!("Failed to start hostd: {}", e));
+                    self.update(|status| {
+                        status.state = BackendState::Stopped;
+                        status.last_error = Some(e);
+                    });
+                    attempt += 1;
+                }
+            }
+            if !self.stopping.load(Ordering::SeqCst) {
+                self.update(|status| status.restarts += 1);
+            }
+        }
+    }
+
+    /// Follow a backend the app did not start until it stops answering
+    async fn watch_external(&self, hostd: &impl Hostd, url: &str) {
+        let mut failures = 0;
+        while !self.stopping.load(Ordering::SeqCst) {
+            tokio::time::sleep(HEALTH_INTERVAL).await;
+            if hostd.is_healthy(url).await {
+                failures = 0;
+                self.update(|status| status.state = BackendState::Healthy);
+                continue;
+            }
+            failures += 1;
+            if failures >= 3 {
+                return;
+            }
+            self.update(|status| status.state = BackendState::Degraded);
+        }
+    }
+
+    /// Follow the hostd child until it exits; returns when it first became healthy
+    async fn watch_child(&self, hostd: &impl Hostd) -> Option<Instant> {
+        let started = Instant::now();
+        let mut healthy_since = None;
+        let mut unhealthy_since: Option<Instant> = None;
+        loop {
+            tokio::time::sleep(HEALTH_INTERVAL).await;
+            if self.stopping.load(Ordering::SeqCst) {
+                return healthy_since;
+            }
+
+            let exit = self.child.lock().ok()
+                .and_then(|mut child| child.as_mut().map(|child| child.try_wait()));
+            match exit {
+                Some(Ok(Some(exit))) => {
+                    crate::log_debug(&format!("hostd exited with {}", exit));
+                    if let Ok(mut child) = self.child.lock() {
+                        child.take();
+                    }
+                    self.update(|status| {
+                        status.state = BackendState::Stopped;
+                        status.url = None;
+                        status.last_error = Some(format!("hostd exited with {}", exit));
+                    });
+                    return healthy_since;
+                }
+                Some(Err(e)) => crate::log_debug(&format!("Failed to check hostd: {}", e)),
+                _ => {}
+            }
+
+            let url = match self.status().url {
+                Some(url) => Some(url),
+                None => hostd.find_running().await,
+            };
+            match url {
+                Some(url) if hostd.is_healthy(&url).await => {
+                    healthy_since.get_or_insert_with(Instant::now);
+                    unhealthy_since = None;
+                    self.update(|status| {
+                        status.state = BackendState::Healthy;
+                        status.url = Some(url);
+                        status.last_error = None;
+                    });
+                }
+                _ if healthy_since.is_none() && started.elapsed() < STARTUP_TIMEOUT => {}
+                _ => {
+                    let error = if healthy_since.is_none() {
+                        "hostd did not become healthy within 20s"
+                    } else {
+                        "hostd is not answering health checks"
+                    };
+                    self.update(|status| {
+                        status.state = BackendState::Degraded;
+                        status.last_error = Some(error.to_string());
+                    });
+                    // Hung rather than slow; the exit is picked up on the next check
+                    if unhealthy_since.get_or_insert_with(Instant::now).elapsed() >= HUNG_AFTER {
+                        crate::log_debug("hostd is not responding, killing it");
+                        if let Ok(mut child) = self.child.lock() {
+                            if let Some(child) = child.as_mut() {
+                                let _ = child.kill();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn spawn_hostd<R: Runtime>(app: &AppHandle<R>) -> Result<Child, String> {
+    let hostd_path = crate::get_resource_path(app, "hostd.exe")
+        .or_else(|_| crate::get_resource_path_for_backend())
+        .map_err(|e| format!("Failed to find hostd.exe: {}", e))?;
+
+    // Run from the data directory so hostd finds guardian.db
+    let data_dir = data_dir();
+    std::fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    if !data_dir.join("guardian.db").exists() {
+        crate::initialize_database(&hostd_path, &data_dir).await?;
+    }
+
+    let log_file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_dir.join("hostd.log"))
+        .map_err(|e| format!("Failed to open hostd.log: {}", e))?;
+    let log_file_clone = log_file.try_clone()
+        .map_err(|e| format!("Failed to clone log file: {}", e))?;
+
+    let mut cmd = Command::new(&hostd_path);
+    cmd.current_dir(&data_dir);
+    cmd.env("DATABASE_URL", "sqlite:guardian.db");
+    cmd.env("RUST_LOG", "info");
+
+    // CRITICAL: Use this pattern for ALL process spawning
+    cmd.stdin(Stdio::null())
+       .stdout(Stdio::from(log_file))
+       .stderr(Stdio::from(log_file_clone));
+
+    #[cfg(target_os = "windows")]
+    {
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    cmd.spawn().map_err(|e| format!("Failed to spawn hostd: {}", e))
+}
+
+fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| std::env::current_dir().unwrap())
+        .join("Guardian")
+        .join("data")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+    use std::sync::Arc;
+
+    const URL: &str = "http://127.0.0.1:1";
+
+    /// A hostd that answers health checks while its process is up
+    #[derive(Default)]
+    struct FakeHostd {
+        spawns: AtomicU32,
+        /// Already running when supervision starts, like the hostd service
+        external: AtomicBool,
+        running: AtomicBool,
+    }
+
+    impl Hostd for FakeHostd {
+        async fn find_running(&self) -> Option<String> {
+            let up = self.external.load(Ordering::SeqCst) || self.running.load(Ordering::SeqCst);
+            up.then(|| URL.to_string())
+        }
+
+        async fn is_healthy(&self, _url: &str) -> bool {
+            true
+        }
+
+        async fn spawn(&self) -> Result<Child, String> {
+            self.spawns.fetch_add(1, Ordering::SeqCst);
+            self.running.store(true, Ordering::SeqCst);
+            #[cfg(windows)]
+            let child = Command::new("ping").args(["-n", "600", "127.0.0.1"]).stdout(Stdio::null()).spawn();
+            #[cfg(not(windows))]
+            let child = Command::new("sleep").arg("600").spawn();
+            child.map_err(|e| e.to_string())
+        }
+    }
+
+    fn supervise(supervisor: &Arc<BackendSupervisor>, hostd: &Arc<FakeHostd>) -> tokio::task::JoinHandle<()> {
+        let (supervisor, hostd) = (supervisor.clone(), hostd.clone());
+        tokio::spawn(async move { supervisor.supervise(hostd.as_ref()).await })
+    }
+
+    async fn wait_for(supervisor: &BackendSupervisor, state: BackendState, restarts: u32) {
+        let mut receiver = supervisor.status.subscribe();
+        receiver.wait_for(|status| status.state == state && status.restarts == restarts).await.unwrap();
+    }
+
+    /// Kill the hostd process the way a crash would
+    fn crash(supervisor: &BackendSupervisor, hostd: &FakeHostd) {
+        hostd.running.store(false, Ordering::SeqCst);
+        let mut child = supervisor.child.lock().unwrap();
+        child.as_mut().unwrap().kill().unwrap();
+    }
+
+    /// `stop` blocks, as it does when the app quits
+    async fn stop(supervisor: &Arc<BackendSupervisor>) {
+        let supervisor = supervisor.clone();
+        tokio::task::spawn_blocking(move || supervisor.stop()).await.unwrap();
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_a_minute() {
+        let delays: Vec<u64> = (1..=8).map(|attempt| backoff(attempt).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(backoff(0), Duration::from_secs(1));
+        assert_eq!(backoff(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_crashed_hostd_is_restarted_after_a_backoff() {
+        let supervisor = Arc::new(BackendSupervisor::default());
+        let hostd = Arc::new(FakeHostd::default());
+        let task = supervise(&supervisor, &hostd);
+
+        wait_for(&supervisor, BackendState::Starting, 0).await;
+        wait_for(&supervisor, BackendState::Healthy, 0).await;
+        assert_eq!(supervisor.status().url.as_deref(), Some(URL));
+        assert!(!supervisor.status().external);
+
+        crash(&supervisor, &hostd);
+        wait_for(&supervisor, BackendState::Stopped, 1).await;
+        assert!(supervisor.status().last_error.unwrap().starts_with("hostd exited"));
+        let crashed_at = tokio::time::Instant::now();
+        wait_for(&supervisor, BackendState::Starting, 1).await;
+        assert!(crashed_at.elapsed() >= backoff(1));
+        wait_for(&supervisor, BackendState::Healthy, 1).await;
+        assert_eq!(hostd.spawns.load(Ordering::SeqCst), 2);
+
+        stop(&supervisor).await;
+        task.await.unwrap();
+        assert_eq!(supervisor.status().state, BackendState::Stopped);
+        assert_eq!(supervisor.status().restarts, 1);
+        assert_eq!(hostd.spawns.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stop_during_backoff_prevents_the_restart() {
+        let supervisor = Arc::new(BackendSupervisor::default());
+        let hostd = Arc::new(FakeHostd::default());
+        let task = supervise(&supervisor, &hostd);
+
+        wait_for(&supervisor, BackendState::Healthy, 0).await;
+        crash(&supervisor, &hostd);
+        wait_for(&supervisor, BackendState::Stopped, 1).await;
+        // No process is left to shut down, so this returns before the backoff ends
+        supervisor.stop();
+
+        task.await.unwrap();
+        assert_eq!(hostd.spawns.load(Ordering::SeqCst), 1);
+        assert_eq!(supervisor.status().state, BackendState::Stopped);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_running_backend_is_watched_not_spawned() {
+        let supervisor = Arc::new(BackendSupervisor::default());
+        let hostd = Arc::new(FakeHostd::default());
+        hostd.external.store(true, Ordering::SeqCst);
+        let task = supervise(&supervisor, &hostd);
+
+        wait_for(&supervisor, BackendState::Healthy, 0).await;
+        assert!(supervisor.status().external);
+        stop(&supervisor).await;
+
+        task.await.unwrap();
+        assert_eq!(hostd.spawns.load(Ordering::SeqCst), 0);
+    }
+}
@@ -42,7 +42,12 @@ where
     let status = response.status();
     if !status.is_success() {
         let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("HTTP {}: {}", status, error_text));
+        // Error responses carry the same `success`/`error` wrapper as successful ones
+        let message = serde_json::from_str::<ApiResponse<serde_json::Value>>(&error_text)
+            .ok()
+            .and_then(|r| r.error)
+            .unwrap_or(error_text);
+        return Err(format!("HTTP {}: {}", status, message));
     }
     
     let response_text = response.text().await
@@ -61,6 +66,7 @@ where
 
 // HTTP request command for frontend
 #[tauri::command]
+#[specta::specta]
 pub async fn make_http_request(url: String, method: String, body: Option<String>) -> Result<String, String> {
     log::info!("Tauri HTTP command called: {} {}", method, url);
     
@@ -108,169 +114,201 @@ pub async fn make_http_request(url: String, method: String, body: Option<String>
 
 // Server management commands
 #[tauri::command]
+#[specta::specta]
 pub async fn get_server_summary(id: String) -> Result<ServerSummary, String> {
     make_api_call::<ServerSummary>(&format!("/servers/{}", id), "GET", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_servers() -> Result<Vec<ServerSummary>, String> {
     make_api_call::<Vec<ServerSummary>>("/servers", "GET", None).await
 }
 
 #[tauri::command]
-pub async fn create_server(data: CreateServerRequest) -> Result<ServerSummary, String> {
+#[specta::specta]
+pub async fn create_server(data: CreateServerRequest) -> Result<ServerCreation, String> {
     let body = serde_json::to_value(data).map_err(|e| format!("Failed to serialize request: {}", e))?;
-    make_api_call::<ServerSummary>("/servers", "POST", Some(body)).await
+    make_api_call::<ServerCreation>("/servers", "POST", Some(body)).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn delete_server(id: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}", id), "DELETE", None).await
 }
 
 // Server control commands
 #[tauri::command]
+#[specta::specta]
 pub async fn start_server(id: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}/start", id), "POST", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn stop_server(id: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}/stop", id), "POST", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn restart_server(id: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}/restart", id), "POST", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn promote_server(id: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}/promote", id), "POST", None).await
 }
 
 // Console and commands
 #[tauri::command]
+#[specta::specta]
 pub async fn send_rcon(id: String, cmd: String) -> Result<(), String> {
     let body = serde_json::json!({ "command": cmd });
     make_api_call::<()>(&format!("/servers/{}/command", id), "POST", Some(body)).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_console_messages(id: String) -> Result<Vec<ConsoleLine>, String> {
     make_api_call::<Vec<ConsoleLine>>(&format!("/servers/{}/console", id), "GET", None).await
 }
 
 // Server health and metrics
 #[tauri::command]
+#[specta::specta]
 pub async fn get_server_health(id: String) -> Result<ServerHealth, String> {
     make_api_call::<ServerHealth>(&format!("/servers/{}/health", id), "GET", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_players(id: String) -> Result<Vec<Player>, String> {
     make_api_call::<Vec<Player>>(&format!("/servers/{}/players", id), "GET", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_metrics(id: String) -> Result<Metrics, String> {
     make_api_call::<Metrics>(&format!("/servers/{}/metrics", id), "GET", None).await
 }
 
 // Player actions
 #[tauri::command]
+#[specta::specta]
 pub async fn kick_player(id: String, player_uuid: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}/players/{}/kick", id, player_uuid), "POST", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn ban_player(id: String, player_uuid: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}/players/{}/ban", id, player_uuid), "POST", None).await
 }
 
 // Backups
 #[tauri::command]
+#[specta::specta]
 pub async fn get_backups(id: String) -> Result<Vec<Snapshot>, String> {
     make_api_call::<Vec<Snapshot>>(&format!("/servers/{}/backups", id), "GET", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn create_backup(id: String, name: String) -> Result<Snapshot, String> {
     let body = serde_json::json!({ "name": name });
     make_api_call::<Snapshot>(&format!("/servers/{}/backups", id), "POST", Some(body)).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn delete_backup(id: String, snapshot_id: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}/backups/{}", id, snapshot_id), "DELETE", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn restore_backup(id: String, snapshot_id: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}/backups/{}/restore", id, snapshot_id), "POST", None).await
 }
 
 // World management
 #[tauri::command]
+#[specta::specta]
 pub async fn get_freeze_tickets(id: String) -> Result<Vec<FreezeTicket>, String> {
     make_api_call::<Vec<FreezeTicket>>(&format!("/servers/{}/world/freezes", id), "GET", None).await
 }
 
 #[tauri::command]
-pub async fn thaw_world(id: String, ticket_id: String) -> Result<(), String> {
-    make_api_call::<()>(&format!("/servers/{}/world/thaw/{}", id, ticket_id), "POST", None).await
+#[specta::specta]
+pub async fn thaw_world(id: String, ticket_id: String, request: ThawRequest) -> Result<FreezeTicket, String> {
+    let body = serde_json::to_value(request).map_err(|e| format!("Failed to serialize request: {}", e))?;
+    make_api_call::<FreezeTicket>(&format!("/servers/{}/world/thaw/{}", id, ticket_id), "POST", Some(body)).await
 }
 
 // Pregen jobs
 #[tauri::command]
+#[specta::specta]
 pub async fn get_pregen_jobs(id: String) -> Result<Vec<PregenJob>, String> {
     make_api_call::<Vec<PregenJob>>(&format!("/servers/{}/pregen", id), "GET", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn create_pregen_job(id: String, job: CreatePregenJobRequest) -> Result<PregenJob, String> {
     let body = serde_json::to_value(job).map_err(|e| format!("Failed to serialize request: {}", e))?;
     make_api_call::<PregenJob>(&format!("/servers/{}/pregen", id), "POST", Some(body)).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn start_pregen_job(id: String, job_id: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}/pregen/{}/start", id, job_id), "POST", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn stop_pregen_job(id: String, job_id: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}/pregen/{}/stop", id, job_id), "POST", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn delete_pregen_job(id: String, job_id: String) -> Result<(), String> {
     make_api_call::<()>(&format!("/servers/{}/pregen/{}", id, job_id), "DELETE", None).await
 }
 
 // Mods and rules
 #[tauri::command]
+#[specta::specta]
 pub async fn get_mods(id: String) -> Result<Vec<ModInfo>, String> {
     make_api_call::<Vec<ModInfo>>(&format!("/servers/{}/mods", id), "GET", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_rules(id: String) -> Result<Vec<Rule>, String> {
     make_api_call::<Vec<Rule>>(&format!("/servers/{}/rules", id), "GET", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_conflicts(id: String) -> Result<Vec<Conflict>, String> {
     make_api_call::<Vec<Conflict>>(&format!("/servers/{}/conflicts", id), "GET", None).await
 }
 
 // Settings
 #[tauri::command]
+#[specta::specta]
 pub async fn get_server_settings(id: String) -> Result<ServerSettings, String> {
     make_api_call::<ServerSettings>(&format!("/servers/{}/settings", id), "GET", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn update_server_settings(id: String, settings: ServerSettings) -> Result<ServerSettings, String> {
     let body = serde_json::to_value(settings).map_err(|e| format!("Failed to serialize request: {}", e))?;
     make_api_call::<ServerSettings>(&format!("/servers/{}/settings", id), "PUT", Some(body)).await
@@ -278,35 +316,99 @@ pub async fn update_server_settings(id: String, settings: ServerSettings) -> Res
 
 // Sharding
 #[tauri::command]
+#[specta::specta]
 pub async fn get_sharding_topology() -> Result<ShardingTopology, String> {
     make_api_call::<ShardingTopology>("/sharding/topology", "GET", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn get_shard_assignments() -> Result<Vec<ShardAssignment>, String> {
     make_api_call::<Vec<ShardAssignment>>("/sharding/assignments", "GET", None).await
 }
 
 // Events
+/// A page of the event feed, of one server or of all servers
 #[tauri::command]
-pub async fn get_events(id: String) -> Result<Vec<Event>, String> {
-    make_api_call::<Vec<Event>>(&format!("/servers/{}/events", id), "GET", None).await
+#[specta::specta]
+pub async fn get_events(id: Option<String>, query: Option<EventFeedQuery>) -> Result<EventPage, String> {
+    let path = match id {
+        Some(id) => format!("/servers/{}/events", id),
+        None => "/events".to_string(),
+    };
+    let query = query.unwrap_or_default();
+    let params: Vec<(&str, String)> = [
+        ("type", query.types),
+        ("category", query.category),
+        ("severity", query.severity),
+        ("since", query.since),
+        ("until", query.until),
+        ("cursor", query.cursor),
+        ("limit", query.limit.map(|limit| limit.to_string())),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| value.map(|value| (key, value)))
+    .collect();
+    let url = reqwest::Url::parse_with_params("http://localhost/", &params)
+        .map_err(|e| format!("Invalid event query: {}", e))?;
+    let endpoint = match url.query() {
+        Some(query) if !query.is_empty() => format!("{}?{}", path, query),
+        _ => path,
+    };
+    make_api_call::<EventPage>(&endpoint, "GET", None).await
 }
 
 #[tauri::command]
+#[specta::specta]
 pub async fn create_event(id: String, event: CreateEventRequest) -> Result<Event, String> {
     let body = serde_json::to_value(event).map_err(|e| format!("Failed to serialize request: {}", e))?;
     make_api_call::<Event>(&format!("/servers/{}/events", id), "POST", Some(body)).await
 }
 
+// Jobs
+#[tauri::command]
+#[specta::specta]
+pub async fn list_jobs(query: Option<JobListQuery>) -> Result<Vec<Job>, String> {
+    let query = query.unwrap_or_default();
+    let params: Vec<(&str, String)> = [
+        ("server_id", query.server_id),
+        ("kind", query.kind),
+        ("status", query.status),
+        ("limit", query.limit.map(|limit| limit.to_string())),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| value.map(|value| (key, value)))
+    .collect();
+    let url = reqwest::Url::parse_with_params("http://localhost/", &params)
+        .map_err(|e| format!("Invalid job query: {}", e))?;
+    let endpoint = match url.query() {
+        Some(query) if !query.is_empty() => format!("/jobs?{}", query),
+        _ => "/jobs".to_string(),
+    };
+    make_api_call::<Vec<Job>>(&endpoint, "GET", None).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_job(job_id: String) -> Result<Job, String> {
+    make_api_call::<Job>(&format!("/jobs/{}", job_id), "GET", None).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_job(job_id: String) -> Result<Job, String> {
+    make_api_call::<Job>(&format!("/jobs/{}/cancel", job_id), "POST", None).await
+}
+
 // GPU status command
 #[tauri::command]
+#[specta::specta]
 pub async fn get_gpu_status() -> Result<GpuStatus, String> {
     make_api_call::<GpuStatus>("/gpu/status", "GET", None).await
 }
 
 // Request types for commands
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, specta::Type)]
 pub struct CreateServerRequest {
     pub name: String,
     pub version: String,
@@ -315,7 +417,19 @@ pub struct CreateServerRequest {
     pub paths: PathSettings,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+/// `action` is `kill_entity` or `regenerate_chunk`; the other fields override the ticket
+#[derive(serde::Serialize, serde::Deserialize, specta::Type)]
+pub struct ThawRequest {
+    pub action: String,
+    pub entity_type: Option<String>,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub z: Option<f64>,
+    pub dimension: Option<String>,
+    pub radius: Option<f64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, specta::Type)]
 pub struct CreatePregenJobRequest {
     pub region: Region,
     pub dimension: String,
@@ -323,7 +437,7 @@ pub struct CreatePregenJobRequest {
     pub gpu_assist: bool,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, specta::Type)]
 pub struct CreateEventRequest {
     pub name: String,
     pub description: String,
@@ -333,6 +447,7 @@ pub struct CreateEventRequest {
 
 // Backend connection command
 #[tauri::command]
+#[specta::specta]
 pub async fn get_backend_url() -> Result<String, String> {
     log::info!("get_backend_url command called");
     // Try to find existing healthy backend first
@@ -24,6 +24,14 @@ pub struct ServerSummary {
     pub memory: Option<u32>,
 }
 
+/// A server being created; it exists once its `server_create` job is done
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct ServerCreation {
+    pub id: String,
+    pub job_id: String,
+    pub status: String, // "creating"
+}
+
 #[derive(Serialize, Deserialize, Type, Clone)]
 pub struct ConsoleLines {
     pub lines: Vec<ConsoleLine>,
@@ -57,18 +65,26 @@ pub struct Player {
 #[derive(Serialize, Deserialize, Type, Clone)]
 pub struct FreezeTicket {
     pub id: String,
-    pub actor_id: String,
-    pub location: Location,
-    pub duration: u32,
+    pub server_id: String,
+    pub status: String,
+    pub suspect_kind: String,
+    pub suspect_frame: Option<String>,
+    pub entity_type: Option<String>,
+    pub location: Option<Location>,
+    pub stack: Vec<String>,
+    pub thread_dump: Option<String>,
+    pub duration_ms: Option<u64>,
+    pub resolution: Option<String>,
     pub created_at: String,
+    pub resolved_at: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Type, Clone)]
 pub struct Location {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
-    pub dimension: String,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub dimension: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Type, Clone)]
@@ -158,6 +174,13 @@ pub struct JVMSettings {
 pub struct GPUSettings {
     pub enabled: bool,
     pub queue_size: u32,
+    /// Adapter pregeneration runs on: "auto", "all" or "gpu:<index>"
+    #[serde(default = "default_gpu_device", alias = "gpuDevice")]
+    pub device: String,
+}
+
+fn default_gpu_device() -> String {
+    "auto".to_string()
 }
 
 #[derive(Serialize, Deserialize, Type, Clone)]
@@ -211,6 +234,62 @@ pub struct Event {
     pub actions: Vec<String>,
 }
 
+/// An entry of the event feed
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct FeedEvent {
+    pub id: String,
+    pub server_id: Option<String>,
+    pub kind: String,
+    pub category: String, // "lifecycle" | "crash" | "console" | "backup" | "player" | "alert" | "other"
+    pub severity: String, // "info" | "warn" | "error"
+    pub message: String,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct EventPage {
+    pub events: Vec<FeedEvent>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Default)]
+pub struct EventFeedQuery {
+    /// Comma-separated kinds
+    pub types: Option<String>,
+    pub category: Option<String>,
+    /// Lowest severity to include
+    pub severity: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// A long-running operation on the host, such as a backup or lighting job
+#[derive(Serialize, Deserialize, Type, Clone)]
+pub struct Job {
+    pub id: String,
+    pub server_id: Option<String>,
+    pub kind: String, // "lighting" | "import" | "backup" | "modpack_install" | ...
+    pub status: String, // "pending" | "queued" | "running" | "done" | "failed" | "cancelled"
+    pub progress: f64,
+    /// Result message, or the error of a failed job
+    pub log: Option<String>,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Default)]
+pub struct JobListQuery {
+    pub server_id: Option<String>,
+    pub kind: Option<String>,
+    /// Comma-separated statuses; "active" stands for queued and running
+    pub status: Option<String>,
+    pub limit: Option<u32>,
+}
+
 #[derive(Serialize, Deserialize, Type, Clone)]
 pub struct Shard {
     pub id: String,
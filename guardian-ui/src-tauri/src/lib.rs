@@ -4,7 +4,6 @@ use std::fs;
 use std::io::Write;
 use std::sync::{Mutex, Once};
 use std::path::PathBuf;
-use tokio::time::sleep;
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -14,17 +13,16 @@ mod dto;
 mod commands;
 mod events;
 mod gpu_integration;
+mod tray;
+mod backend;
 
-// Global state to store the backend processes
+use backend::{BackendState, BackendStatus, BackendSupervisor};
+
+// Global state to store the GPU worker process; hostd is owned by BackendSupervisor
 struct AppState {
-    hostd_process: Mutex<Option<Child>>,
     gpu_worker_process: Mutex<Option<Child>>,
 }
 
-// Global initialization flag to prevent multiple backend starts
-use std::sync::atomic::{AtomicBool, Ordering};
-static BACKEND_INITIALIZING: AtomicBool = AtomicBool::new(false);
-
 // Enhanced logging function
 fn log_debug(message: &str) {
     let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC");
@@ -46,68 +44,14 @@ fn log_debug(message: &str) {
 
 // Start backend command - this is the key addition
 #[tauri::command]
-async fn start_backend() -> Result<String, String> {
+#[specta::specta]
+async fn start_backend<R: tauri::Runtime>(handle: tauri::AppHandle<R>) -> Result<String, String> {
     log_debug("Starting backend via Tauri command...");
     
-    // Check if already initializing to prevent race conditions
-    if BACKEND_INITIALIZING.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err() {
-        log_debug("Backend initialization already in progress, waiting...");
-        // Wait a bit and try to find existing backend
-        sleep(std::time::Duration::from_millis(500)).await;
-        for port in 52100..=52150 {
-            let url = format!("http://127.0.0.1:{}/healthz", port);
-            if let Ok(resp) = reqwest::get(&url).await {
-                if resp.status().is_success() { 
-                    return Ok(format!("http://127.0.0.1:{}", port)); 
-                }
-            }
-        }
-        return Err("Backend initialization in progress, please try again".to_string());
-    }
-    
-    // Do the initialization
-    let result = start_backend_internal().await;
-    
-    // Reset the flag
-    BACKEND_INITIALIZING.store(false, Ordering::SeqCst);
-    
-    result
-}
-
-// Initialize database function synchronously
-fn initialize_database_sync(hostd_path: &PathBuf, data_dir: &PathBuf) -> Result<(), String> {
-    log_debug("Initializing database synchronously...");
-    
-    // Get init_db path
-    let init_db_path = get_init_db_path_for_backend()
-        .map_err(|e| format!("Failed to find init_db.exe: {}", e))?;
-    
-    // Create init_db command
-    let mut init_cmd = Command::new(init_db_path);
-    init_cmd.current_dir(data_dir);
-    init_cmd.env("DATABASE_URL", "sqlite:guardian.db");
-    
-    // Set up process with no console window
-    init_cmd.stdin(Stdio::null())
-           .stdout(Stdio::null())
-           .stderr(Stdio::null());
-
-    #[cfg(target_os = "windows")]
-    {
-        init_cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-    }
-
-    let result = init_cmd.spawn()
-        .map_err(|e| format!("Failed to spawn init_db: {}", e))?
-        .wait()
-        .map_err(|e| format!("Failed to wait for init_db: {}", e))?;
-
-    if result.success() {
-        log_debug("Database initialization completed successfully");
-        Ok(())
-    } else {
-        Err(format!("Database initialization failed with exit code: {:?}", result.code()))
-    }
+    // The supervisor starts hostd once; later calls just wait for it
+    let supervisor = handle.state::<BackendSupervisor>();
+    supervisor.start(handle.clone());
+    supervisor.wait_until_healthy(std::time::Duration::from_secs(25)).await
 }
 
 // Initialize database function
@@ -146,245 +90,23 @@ async fn initialize_database(hostd_path: &PathBuf, data_dir: &PathBuf) -> Result
     }
 }
 
-// Internal backend startup function
-async fn start_backend_internal() -> Result<String, String> {
-    // 1) Try existing healthy backend (probe 52100–52150 /healthz)
-    let mut base = None;
-    for port in 52100..=52150 {
-        let url = format!("http://127.0.0.1:{}/healthz", port);
-        if let Ok(resp) = reqwest::get(&url).await {
-            if resp.status().is_success() { 
-                base = Some(format!("http://127.0.0.1:{}", port)); 
-                log_debug(&format!("Found existing healthy backend on port {}", port));
-                break; 
-            }
-        }
-    }
-    if let Some(base_url) = base {
-        return Ok(base_url);
-    }
-
-    // 2) Spawn sidecar hostd with no console window
-    log_debug("No existing backend found, spawning sidecar hostd...");
-    
-    // Get the hostd executable path
-    let hostd_path = match get_resource_path_for_backend() {
-        Ok(path) => path,
-        Err(e) => {
-            log_debug(&format!("Failed to find hostd.exe: {}", e));
-            return Err(format!("Failed to find hostd.exe: {}", e));
-        }
-    };
-    
-    let mut cmd = Command::new(&hostd_path);
-    
-    // Set the working directory to the data directory so hostd can find the database
-    let data_dir = dirs::data_dir().unwrap_or_else(|| std::env::current_dir().unwrap()).join("Guardian").join("data");
-    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
-    
-    // Initialize database if it doesn't exist
-    let db_path = data_dir.join("guardian.db");
-    if !db_path.exists() {
-        log_debug("Database not found, initializing...");
-        let init_result = initialize_database(&hostd_path, &data_dir).await;
-        if let Err(e) = init_result {
-            log_debug(&format!("Failed to initialize database: {}", e));
-            return Err(format!("Failed to initialize database: {}", e));
-        }
-        log_debug("Database initialized successfully");
-    }
-    
-    cmd.current_dir(&data_dir);
-    
-    // CRITICAL: Use this pattern for ALL process spawning
-    cmd.stdin(Stdio::null())
-       .stdout(Stdio::null())
-       .stderr(Stdio::null());
-
-    #[cfg(target_os = "windows")]
-    {
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-    }
-
-    let child = cmd.spawn()
-        .map_err(|e| format!("failed to spawn hostd: {e}"))?;
-
-    log_debug("Hostd sidecar spawned successfully");
-
-    // 3) Wait for healthz (max 20s)
-    log_debug("Waiting for backend to become healthy...");
-    let start = std::time::Instant::now();
-    loop {
-        for port in 52100..=52150 {
-            let url = format!("http://127.0.0.1:{}/healthz", port);
-            if let Ok(resp) = reqwest::get(&url).await {
-                if resp.status().is_success() {
-                    log_debug(&format!("Backend became healthy on port {}", port));
-                    return Ok(format!("http://127.0.0.1:{}", port));
-                }
-            }
-        }
-        if start.elapsed().as_secs() > 20 {
-            break;
-        }
-        sleep(std::time::Duration::from_millis(250)).await;
-    }
-
-    Err("backend did not become healthy within 20s".to_string())
-}
-
 // Ensure backend is running, attempt to start if not
 #[tauri::command]
+#[specta::specta]
 async fn ensure_backend<R: tauri::Runtime>(handle: tauri::AppHandle<R>) -> Result<String, String> {
-    // Try to find existing healthy backend first
-    for port in 52100..=52150 {
-        let url = format!("http://127.0.0.1:{}/healthz", port);
-        if let Ok(resp) = reqwest::get(&url).await {
-            if resp.status().is_success() {
-                return Ok("backend_running".to_string());
-            }
-        }
+    if handle.state::<BackendSupervisor>().status().state == BackendState::Healthy {
+        return Ok("backend_running".to_string());
     }
     
     // If no healthy backend found, try to start one
-    start_backend().await
+    start_backend(handle).await
 }
 
-// Start the hostd service synchronously
-fn start_hostd_service_sync<R: tauri::Runtime>(handle: &tauri::AppHandle<R>) -> Result<(), Box<dyn std::error::Error>> {
-    log_debug("Starting hostd service synchronously...");
-    
-    // Use enhanced resource path resolution
-    let hostd_path = match get_resource_path(handle, "hostd.exe") {
-        Ok(path) => path,
-        Err(e) => {
-            log_debug(&format!("Failed to find hostd.exe: {}", e));
-            return Err(format!("Failed to find hostd.exe: {}", e).into());
-        }
-    };
-    
-    log_debug(&format!("Found hostd.exe at: {:?}", hostd_path));
-    
-    // Create data directories
-    let data_dir = dirs::data_dir().unwrap_or_else(|| std::env::current_dir().unwrap()).join("Guardian").join("data");
-    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
-    log_debug(&format!("Created data directories: {:?}", data_dir));
-    
-    // Initialize database if it doesn't exist
-    let db_path = data_dir.join("guardian.db");
-    if !db_path.exists() {
-        log_debug("Database not found, initializing...");
-        // Initialize database synchronously
-        let init_result = initialize_database_sync(&hostd_path, &data_dir);
-        if let Err(e) = init_result {
-            log_debug(&format!("Failed to initialize database: {}", e));
-            return Err(format!("Failed to initialize database: {}", e).into());
-        }
-        log_debug("Database initialized successfully");
-    }
-
-    // Start hostd process
-    let mut hostd_cmd = Command::new(&hostd_path);
-    // Set the working directory to the data directory so hostd can find guardian.db
-    hostd_cmd.current_dir(&data_dir);
-    // hostd doesn't use command line arguments - it has its own configuration logic
-    
-    // Set environment variables
-    // Since we're running from the data directory, use relative path
-    hostd_cmd.env("DATABASE_URL", "sqlite:guardian.db");
-    hostd_cmd.env("RUST_LOG", "info");
-    
-    // CRITICAL: Use this pattern for ALL process spawning
-    hostd_cmd.stdin(Stdio::null())
-           .stdout(Stdio::null())
-           .stderr(Stdio::null());
-
-    #[cfg(target_os = "windows")]
-    {
-        hostd_cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-    }
-
-    let _child = hostd_cmd.spawn()
-        .map_err(|e| format!("failed to spawn hostd: {e}"))?;
-
-    log_debug("Hostd service started successfully");
-    Ok(())
-}
-
-// Start the hostd service
-async fn start_hostd_service<R: tauri::Runtime>(handle: &tauri::AppHandle<R>) -> Result<(), Box<dyn std::error::Error>> {
-    log_debug("Starting hostd service...");
-    
-    // Use enhanced resource path resolution
-    let hostd_path = match get_resource_path(handle, "hostd.exe") {
-        Ok(path) => path,
-        Err(_) => {
-            log_debug("Could not find hostd.exe in resource directory, trying build directory...");
-            // Fallback to build directory
-            let build_path = std::env::current_dir()?.join("build").join("executables").join("hostd.exe");
-            if build_path.exists() {
-                log_debug(&format!("Found hostd.exe in build directory: {:?}", build_path));
-                build_path
-            } else {
-                return Err("Could not find hostd.exe in resource directory or build directory".into());
-            }
-        }
-    };
-
-    log_debug(&format!("Starting hostd process from: {:?}", hostd_path));
-
-    // Create data directories
-    let data_dir = dirs::data_dir().unwrap_or_else(|| std::env::current_dir().unwrap()).join("Guardian").join("data");
-    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
-    log_debug(&format!("Created data directories: {:?}", data_dir));
-    
-    // Initialize database if it doesn't exist
-    let db_path = data_dir.join("guardian.db");
-    if !db_path.exists() {
-        log_debug("Database not found, initializing...");
-        let init_result = initialize_database(&hostd_path, &data_dir).await;
-        if let Err(e) = init_result {
-            log_debug(&format!("Failed to initialize database: {}", e));
-            return Err(format!("Failed to initialize database: {}", e).into());
-        }
-        log_debug("Database initialized successfully");
-    }
-
-    // Start hostd process
-    let mut hostd_cmd = Command::new(&hostd_path);
-    // Set the working directory to the data directory so hostd can find the database
-    hostd_cmd.current_dir(&data_dir);
-    // hostd doesn't use command line arguments - it has its own configuration logic
-    
-    // Create log file for backend output
-    let log_file = std::fs::File::create(data_dir.join("hostd.log"))
-        .map_err(|e| format!("Failed to create hostd.log: {}", e))?;
-    let log_file_clone = log_file.try_clone()
-        .map_err(|e| format!("Failed to clone log file: {}", e))?;
-    
-    // CRITICAL: Use this pattern for ALL process spawning
-    hostd_cmd.stdin(Stdio::null())
-              .stdout(Stdio::from(log_file))
-              .stderr(Stdio::from(log_file_clone));
-
-    #[cfg(target_os = "windows")]
-    {
-        hostd_cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-    }
-
-    let mut hostd_process = hostd_cmd.spawn()?;
-    let hostd_pid = hostd_process.id();
-    log_debug(&format!("Hostd process started with PID: {}", hostd_pid));
-
-    // Store the process in app state
-    if let Some(state) = handle.try_state::<AppState>() {
-        if let Ok(mut hostd_guard) = state.hostd_process.lock() {
-            *hostd_guard = Some(hostd_process);
-        }
-    }
-
-    log_debug("✅ Hostd started successfully");
-    Ok(())
+// Current backend state; changes are also emitted as `backend:status` events
+#[tauri::command]
+#[specta::specta]
+fn get_backend_status<R: tauri::Runtime>(handle: tauri::AppHandle<R>) -> BackendStatus {
+    handle.state::<BackendSupervisor>().status()
 }
 
 // Start the GPU worker service
@@ -514,6 +236,7 @@ fn get_init_db_path_for_backend() -> Result<PathBuf, Box<dyn std::error::Error>>
 
 // Open server folder command
 #[tauri::command]
+#[specta::specta]
 async fn open_server_folder(server_id: String) -> Result<(), String> {
     log_debug(&format!("Opening server folder for server: {}", server_id));
     
@@ -603,17 +326,14 @@ async fn open_server_folder(server_id: String) -> Result<(), String> {
 fn cleanup_processes<R: tauri::Runtime>(handle: &tauri::AppHandle<R>) {
     log_debug("Cleaning up processes...");
     
+    // Stop supervising first so hostd isn't restarted once it exits; this
+    // waits for hostd to stop its servers
+    if let Some(supervisor) = handle.try_state::<BackendSupervisor>() {
+        supervisor.stop();
+        log_debug("Hostd process stopped");
+    }
+    
     if let Some(state) = handle.try_state::<AppState>() {
-        // Cleanup hostd process
-        if let Ok(mut hostd_guard) = state.hostd_process.lock() {
-            if let Some(mut child) = hostd_guard.take() {
-                log_debug("Terminating hostd process...");
-                let _ = child.kill();
-                let _ = child.wait();
-                log_debug("Hostd process terminated");
-            }
-        }
-        
         // Cleanup GPU worker process
         if let Ok(mut gpu_worker_guard) = state.gpu_worker_process.lock() {
             if let Some(mut child) = gpu_worker_guard.take() {
@@ -628,35 +348,16 @@ fn cleanup_processes<R: tauri::Runtime>(handle: &tauri::AppHandle<R>) {
     log_debug("Process cleanup completed");
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    log_debug("=== GUARDIAN APP STARTING ===");
-    
-    // Enhanced panic handler
-    std::panic::set_hook(Box::new(|panic_info| {
-        let backtrace = std::backtrace::Backtrace::capture();
-        log_debug(&format!("PANIC: {:?}", panic_info));
-        log_debug(&format!("BACKTRACE: {:?}", backtrace));
-        
-        // Try to show error dialog if possible
-        eprintln!("Guardian crashed! Check guardian_debug.log for details.");
-    }));
+/// Bindings the frontend imports its DTO types and command signatures from
+const TYPES_PATH: &str = "../src/lib/types.gen.ts";
 
-    log_debug("Creating Tauri builder...");
-    
-    let result = tauri::Builder::default()
-        .manage(AppState {
-            hostd_process: Mutex::new(None),
-            gpu_worker_process: Mutex::new(None),
-        })
-        .on_window_event(|_window, event| {
-            if let tauri::WindowEvent::CloseRequested { .. } = event {
-                cleanup_processes(_window.app_handle());
-            }
-        })
-        .invoke_handler(tauri::generate_handler![
-            start_backend,
-            ensure_backend,
+/// Commands callable from the frontend, along with the DTOs they take and return
+fn specta_builder() -> tauri_specta::Builder<tauri::Wry> {
+    tauri_specta::Builder::<tauri::Wry>::new()
+        .commands(tauri_specta::collect_commands![
+            start_backend::<tauri::Wry>,
+            ensure_backend::<tauri::Wry>,
+            get_backend_status::<tauri::Wry>,
             commands::get_backend_url,
             commands::make_http_request,
             open_server_folder,
@@ -707,51 +408,68 @@ pub fn run() {
             // Events
             commands::get_events,
             commands::create_event,
+            // Jobs
+            commands::list_jobs,
+            commands::get_job,
+            commands::cancel_job,
             // GPU status
             commands::get_gpu_status,
+            // Tray
+            tray::update_tray_servers::<tauri::Wry>,
         ])
+        // Sent over events and the WebSocket rather than returned by a command
+        .typ::<dto::ConsoleLines>()
+        .typ::<dto::CrashSignature>()
+        // Commands reject with the error message, as with a plain `invoke`
+        .error_handling(tauri_specta::ErrorHandlingMode::Throw)
+}
+
+fn typescript() -> specta_typescript::Typescript {
+    specta_typescript::Typescript::default()
+        .header("// @ts-nocheck")
+        .bigint(specta_typescript::BigIntExportBehavior::Number)
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    log_debug("=== GUARDIAN APP STARTING ===");
+    
+    // Enhanced panic handler
+    std::panic::set_hook(Box::new(|panic_info| {
+        let backtrace = std::backtrace::Backtrace::capture();
+        log_debug(&format!("PANIC: {:?}", panic_info));
+        log_debug(&format!("BACKTRACE: {:?}", backtrace));
+        
+        // Try to show error dialog if possible
+        eprintln!("Guardian crashed! Check guardian_debug.log for details.");
+    }));
+
+    log_debug("Creating Tauri builder...");
+    
+    let builder = specta_builder();
+    // Debug builds keep the bindings up to date; `types_gen_is_current` fails
+    // when a change to the commands or DTOs was not exported
+    #[cfg(debug_assertions)]
+    if let Err(e) = builder.export(typescript(), TYPES_PATH) {
+        log_debug(&format!("Failed to export TypeScript types: {}", e));
+    }
+    
+    let result = tauri::Builder::default()
+        .manage(AppState {
+            gpu_worker_process: Mutex::new(None),
+        })
+        .manage(BackendSupervisor::default())
+        .on_window_event(|window, event| {
+            // Closing the window minimizes to the tray; servers keep running until "Quit" in the tray
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                api.prevent_close();
+                let _ = window.hide();
+            }
+        })
+        .invoke_handler(builder.invoke_handler())
         .setup(|app| {
             log_debug("In setup function...");
             
-            // Export TypeScript types
-            log_debug("Exporting TypeScript types...");
-            // TODO: Fix tauri-specta API usage - temporarily disabled
-            // tauri_specta::ts::export(
-            //     specta::collect_types![
-            //         dto::ServerSummary,
-            //         dto::BlueGreen,
-            //         dto::ConsoleLines,
-            //         dto::ConsoleLine,
-            //         dto::Metrics,
-            //         dto::Player,
-            //         dto::FreezeTicket,
-            //         dto::Location,
-            //         dto::Snapshot,
-            //         dto::Rule,
-            //         dto::PregenJob,
-            //         dto::Region,
-            //         dto::ServerHealth,
-            //         dto::ServerSettings,
-            //         dto::GeneralSettings,
-            //         dto::JVMSettings,
-            //         dto::GPUSettings,
-            //         dto::HASettings,
-            //         dto::PathSettings,
-            //         dto::ComposerSettings,
-            //         dto::TokenSettings,
-            //         dto::ModInfo,
-            //         dto::Conflict,
-            //         dto::Event,
-            //         dto::Shard,
-            //         dto::ShardingTopology,
-            //         dto::ShardAssignment,
-            //         dto::CrashSignature,
-            //         dto::ApiResponse,
-            //     ],
-            //     "../src/lib/types.gen.ts"
-            // ).expect("Failed to export TypeScript types");
-            log_debug("TypeScript types export temporarily disabled");
-            
             // Validate environment
             log_debug("Validating environment...");
             let resource_dir = app.path().resource_dir().unwrap_or_else(|_| std::env::current_dir().unwrap());
@@ -771,13 +489,9 @@ pub fn run() {
             // Start backend services
             log_debug("Starting backend services...");
             
-            // Try to start hostd service
+            // Supervise hostd: started in the background, restarted if it crashes
             log_debug("Attempting to start hostd service...");
-            // Start hostd service synchronously to avoid panic
-            if let Err(e) = start_hostd_service_sync(app.handle()) {
-                log_debug(&format!("Failed to start hostd service: {}", e));
-                // Don't fail the entire app startup, just log the error
-            }
+            app.state::<BackendSupervisor>().start(app.handle().clone());
             
             // Try to start GPU worker service
             log_debug("Attempting to start GPU worker service...");
@@ -786,6 +500,11 @@ pub fn run() {
                 // Don't fail the entire app startup, just log the error
             }
             
+            // Tray icon with server controls
+            if let Err(e) = tray::init(app.handle()) {
+                log_debug(&format!("Failed to create tray icon: {}", e));
+            }
+            
             // Initialize GPU integration
             log_debug("Initializing GPU integration...");
             if let Err(e) = gpu_integration::init_gpu_integration() {
@@ -807,4 +526,20 @@ pub fn run() {
             eprintln!("Failed to start Guardian: {}", e);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn types_gen_is_current() {
+        let generated = specta_builder().export_str(typescript()).expect("Failed to generate TypeScript types");
+        let committed = fs::read_to_string(TYPES_PATH).unwrap_or_default();
+        assert!(
+            generated == committed,
+            "{} is stale; run the app in debug mode to regenerate it",
+            TYPES_PATH
+        );
+    }
+}
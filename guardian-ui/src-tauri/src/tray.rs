@@ -0,0 +1,190 @@
+// System tray with quick server controls
+//
+// The tray has no API session of its own: the webview, which keeps running
+// while the window is hidden, sends the server list with `update_tray_servers`
+// and carries out the start/stop/console actions the tray emits.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::menu::{IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+const TRAY_ID: &str = "guardian";
+
+/// Event the frontend listens to for actions picked in the tray
+pub const TRAY_ACTION_EVENT: &str = "tray:server-action";
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct TrayServer {
+    pub id: String,
+    pub name: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrayAction {
+    pub server_id: String,
+    /// "start", "stop" or "console"
+    pub action: String,
+}
+
+/// What the tray currently shows
+#[derive(Default)]
+pub struct TrayState {
+    servers: Mutex<Vec<TrayServer>>,
+    backend_online: Mutex<bool>,
+}
+
+fn menu_label(server: &TrayServer) -> String {
+    format!("{} ({})", server.name, server.status)
+}
+
+/// Menu id of a server action, e.g. `server:start:<id>`
+fn action_id(action: &str, server_id: &str) -> String {
+    format!("server:{}:{}", action, server_id)
+}
+
+fn parse_action_id(id: &str) -> Option<TrayAction> {
+    let (action, server_id) = id.strip_prefix("server:")?.split_once(':')?;
+    Some(TrayAction {
+        server_id: server_id.to_string(),
+        action: action.to_string(),
+    })
+}
+
+fn build_menu<R: Runtime>(app: &AppHandle<R>, servers: &[TrayServer], backend_online: bool) -> tauri::Result<Menu<R>> {
+    let status = MenuItem::with_id(
+        app,
+        "backend-status",
+        if backend_online { "Backend: online" } else { "Backend: offline" },
+        false,
+        None::<&str>,
+    )?;
+
+    let mut submenus = Vec::with_capacity(servers.len());
+    for server in servers {
+        let running = matches!(server.status.as_str(), "running" | "starting");
+        let start = MenuItem::with_id(app, action_id("start", &server.id), "Start", backend_online && !running, None::<&str>)?;
+        let stop = MenuItem::with_id(app, action_id("stop", &server.id), "Stop", backend_online && running, None::<&str>)?;
+        let console = MenuItem::with_id(app, action_id("console", &server.id), "Open console", true, None::<&str>)?;
+        submenus.push(Submenu::with_items(app, menu_label(server), true, &[&start, &stop, &console])?);
+    }
+    let no_servers = MenuItem::with_id(app, "no-servers", "No servers", false, None::<&str>)?;
+
+    let show = MenuItem::with_id(app, "show", "Show Guardian", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit Guardian", true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let separator2 = PredefinedMenuItem::separator(app)?;
+
+    let mut items: Vec<&dyn IsMenuItem<R>> = vec![&status, &separator];
+    if submenus.is_empty() {
+        items.push(&no_servers);
+    }
+    for submenu in &submenus {
+        items.push(submenu);
+    }
+    items.push(&separator2);
+    items.push(&show);
+    items.push(&quit);
+    Menu::with_items(app, &items)
+}
+
+/// Rebuild the tray menu and tooltip from the current state
+fn refresh<R: Runtime>(app: &AppHandle<R>) {
+    let (Some(state), Some(tray)) = (app.try_state::<TrayState>(), app.tray_by_id(TRAY_ID)) else {
+        return;
+    };
+    let servers = state.servers.lock().map(|s| s.clone()).unwrap_or_default();
+    let backend_online = state.backend_online.lock().map(|b| *b).unwrap_or(false);
+
+    match build_menu(app, &servers, backend_online) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => log::error!("Failed to build tray menu: {}", e),
+    }
+    let running = servers.iter().filter(|s| s.status == "running").count();
+    let tooltip = if backend_online {
+        format!("Guardian - {} of {} servers running", running, servers.len())
+    } else {
+        "Guardian - backend offline".to_string()
+    };
+    let _ = tray.set_tooltip(Some(tooltip));
+}
+
+pub fn show_main_window<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn on_menu_event<R: Runtime>(app: &AppHandle<R>, id: &str) {
+    match id {
+        "show" => show_main_window(app),
+        "quit" => {
+            crate::cleanup_processes(app);
+            app.exit(0);
+        }
+        _ => {
+            let Some(action) = parse_action_id(id) else {
+                return;
+            };
+            if action.action == "console" {
+                show_main_window(app);
+            }
+            if let Err(e) = app.emit(TRAY_ACTION_EVENT, action) {
+                log::error!("Failed to send tray action: {}", e);
+            }
+        }
+    }
+}
+
+/// Create the tray icon and start watching backend health
+pub fn init<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
+    app.manage(TrayState::default());
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&build_menu(app, &[], false)?)
+        .tooltip("Guardian")
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| on_menu_event(app, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
+                show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let online = crate::commands::get_backend_url().await.is_ok();
+            let changed = handle.try_state::<TrayState>()
+                .and_then(|state| state.backend_online.lock().ok().map(|mut b| std::mem::replace(&mut *b, online) != online))
+                .unwrap_or(false);
+            if changed {
+                refresh(&handle);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+        }
+    });
+
+    Ok(())
+}
+
+/// Called by the frontend whenever its server list changes
+#[tauri::command]
+#[specta::specta]
+pub fn update_tray_servers<R: Runtime>(app: AppHandle<R>, servers: Vec<TrayServer>) -> Result<(), String> {
+    let state = app.try_state::<TrayState>().ok_or("Tray is not available")?;
+    *state.servers.lock().map_err(|e| e.to_string())? = servers;
+    refresh(&app);
+    Ok(())
+}
+
@@ -0,0 +1,74 @@
+import { useEffect } from "react";
+import { useNavigate } from "react-router-dom";
+import { useServers } from "@/store/servers-new";
+
+interface TrayAction {
+  server_id: string;
+  action: "start" | "stop" | "console";
+}
+
+const isTauri = () => typeof window !== "undefined" && (window as any).__TAURI__;
+
+// Keeps the system tray menu in step with the server list, and carries out
+// the start/stop/console actions picked in the tray
+export function useTraySync() {
+  const summaries = useServers((state) => state.summaries);
+  const navigate = useNavigate();
+
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    const servers = Object.values(summaries).map((server) => ({
+      id: server.id,
+      name: server.name,
+      status: server.status,
+    }));
+    import("@tauri-apps/api/core")
+      .then(({ invoke }) => invoke("update_tray_servers", { servers }))
+      .catch((error) => console.warn("Failed to update tray:", error));
+  }, [summaries]);
+
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+
+    import("@tauri-apps/api/event").then(async ({ listen }) => {
+      const stop = await listen<TrayAction>("tray:server-action", async ({ payload }) => {
+        const { startServer, stopServer, fetchServers } = useServers.getState();
+        switch (payload.action) {
+          case "start":
+            await startServer(payload.server_id);
+            break;
+          case "stop":
+            await stopServer(payload.server_id);
+            break;
+          case "console":
+            navigate(`/servers/${payload.server_id}/console`);
+            return;
+        }
+        await fetchServers();
+      });
+      if (cancelled) stop();
+      else unlisten = stop;
+    });
+
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [navigate]);
+
+  // While the window is hidden in the tray nothing else refreshes statuses
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    const interval = setInterval(() => {
+      if (document.hidden) {
+        useServers.getState().fetchServers().catch(() => {});
+      }
+    }, 30000);
+    return () => clearInterval(interval);
+  }, []);
+}
@@ -6,8 +6,11 @@ import { ErrorBoundary } from '@/components/ui/ErrorBoundary';
 import { Toaster } from '@/components/ui/toaster';
 // import { realtimeProvider } from '@/lib/realtime-provider';
 import { ConnectionStatus } from '@/components/ConnectionStatus';
+import { useTraySync } from '@/app/hooks/useTraySync';
 
 export const AppShell: React.FC = () => {
+  useTraySync();
+
   // const { id: serverId } = useParams<{ id: string }>();
 
   // Start/stop real-time monitoring based on server selection
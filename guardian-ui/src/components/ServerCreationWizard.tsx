@@ -5,7 +5,7 @@ import { Button } from '@/components/ui/button';
 import { Progress } from '@/components/ui/progress';
 import { Alert, AlertDescription } from '@/components/ui/alert';
 import { Loader2, CheckCircle, AlertTriangle } from 'lucide-react';
-import { apiClient as api } from '@/lib/api';
+import { apiClient as api, followJob } from '@/lib/api';
 import { useToast } from '@/hooks/use-toast';
 import { 
   serverFormSchema, 
@@ -319,53 +319,22 @@ export function ServerCreationWizard({ open, onOpenChange, onServerCreated, onCl
         individual_mods: formData.individualMods
       };
 
-      // Update stages with realistic timing
-      setTimeout(() => setCreationStage('Installing core server files...'), 1000);
-      setTimeout(() => setCreationStage('Installing modpack and mods...'), 3000);
-      setTimeout(() => setCreationStage('Validating configuration...'), 5000);
-      setTimeout(() => setCreationStage('Finalizing server setup...'), 7000);
-      setTimeout(() => setCreationStage('Server created successfully!'), 9000);
-
-      // Start progress simulation that's synchronized with stages
-      const progressInterval = setInterval(() => {
-        setCreationProgress(prev => {
-          if (prev >= 95) {
-            // Slow down near the end to ensure stage completion
-            const increment = Math.min(Math.random() * 2, 100 - prev);
-            return Math.min(prev + increment, 100);
-          } else if (prev >= 80) {
-            // Moderate speed in the middle
-            const increment = Math.min(Math.random() * 4, 100 - prev);
-            return Math.min(prev + increment, 100);
-          } else {
-            // Normal speed at the beginning
-            const increment = Math.min(Math.random() * 6, 100 - prev);
-            return Math.min(prev + increment, 100);
-          }
-        });
-      }, 500);
-
-      // Call the actual API
-      const newServer = await api.createServer(serverData);
-
-      // Wait for progress to reach 100% and final stage to complete
-      await new Promise(resolve => {
-        let attempts = 0;
-        const maxAttempts = 100; // 20 seconds max wait
-        
-        const checkProgress = () => {
-          attempts++;
-          if (attempts >= maxAttempts || (creationProgress >= 100 && creationStage.includes('Server created successfully'))) {
-            resolve(undefined);
-          } else {
-            setTimeout(checkProgress, 200);
-          }
-        };
-        setTimeout(checkProgress, 2000); // Start checking after 2 seconds
+      // The response only says the creation job was queued; the server
+      // exists once the job is done, and the job reports each step
+      setCreationStage('Queueing server creation...');
+      const creation = await api.createServer(serverData);
+      const job = await followJob(creation.job_id, ({ progress, step, message }) => {
+        setCreationProgress(Math.round(progress * 100));
+        const stage = message || step;
+        if (stage) {
+          setCreationStage(stage);
+        }
       });
+      if (job.status !== 'done') {
+        throw new Error(job.log || `Server creation ${job.status}`);
+      }
+      const newServer = await api.getServer(creation.id);
 
-      // Complete the progress
-      clearInterval(progressInterval);
       setCreationProgress(100);
       setCreationStage('Server created successfully!');
 
@@ -14,12 +14,24 @@ import {
   Info
 } from 'lucide-react';
 
+interface GPUDeviceTelemetry {
+  source: string;
+  index: number;
+  name: string;
+  utilization: number | null;
+  memory_used: number | null;
+  memory_total: number | null;
+  temperature: number | null;
+  power_usage: number | null;
+}
+
 interface GPUMetrics {
   utilization: number;
   memory_used: number;
   memory_total: number;
   temperature: number;
   power_usage: number;
+  devices?: GPUDeviceTelemetry[];
   last_update: string;
 }
 
@@ -27,6 +39,7 @@ interface GPUStatus {
   enabled: boolean;
   healthy: boolean;
   worker_available: boolean;
+  backend?: 'gpu' | 'cpu';
 }
 
 export const GPUMetrics: React.FC = () => {
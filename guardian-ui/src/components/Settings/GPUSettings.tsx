@@ -7,6 +7,7 @@ import { Label } from '@/components/ui/label';
 import { Switch } from '@/components/ui/switch';
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '@/components/ui/select';
 import { Button } from '@/components/ui/button';
+import { apiClient } from '@/lib/api';
 import { GPUMetrics } from './GPUMetrics';
 import { 
   Cpu, 
@@ -82,6 +83,20 @@ interface GPUSettingsData {
   gpuResourceLimits: boolean;
 }
 
+interface GPUDevice {
+  index: number;
+  name: string;
+  device_type: string;
+  backend: string;
+  selected: boolean;
+  metrics?: {
+    busy: boolean;
+    jobs_completed: number;
+    jobs_failed: number;
+    utilization: number;
+  } | null;
+}
+
 export const GPUSettings: React.FC = () => {
   const { id: serverId } = useParams<{ id: string }>();
   const { 
@@ -149,6 +164,8 @@ export const GPUSettings: React.FC = () => {
     gpuSandboxLevel: 'basic',
     gpuResourceLimits: true
   });
+  const [devices, setDevices] = useState<GPUDevice[]>([]);
+  const [deviceError, setDeviceError] = useState<string | null>(null);
   // Loading state removed for now
   // const [isLoading, setIsLoading] = useState(false);
   // Changes tracking removed for now
@@ -165,8 +182,37 @@ export const GPUSettings: React.FC = () => {
     }
   };
 
+  const loadDevices = async () => {
+    try {
+      const response = await apiClient.call<{ data: { selection: string; devices: GPUDevice[] } }>('/api/gpu/devices');
+      setDevices(response.data?.devices ?? []);
+    } catch (error) {
+      console.error('Failed to fetch GPU devices:', error);
+    }
+  };
+
+  const handleDeviceChange = async (value: string) => {
+    setDeviceError(null);
+    try {
+      const response = await apiClient.call<{ success: boolean; error?: string }>('/api/gpu/devices/selection', {
+        method: 'PUT',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ device: value }),
+      });
+      if (!response.success) {
+        setDeviceError(response.error ?? 'Failed to select GPU device');
+        return;
+      }
+      await handleSettingChange('gpuDevice', value);
+      await loadDevices();
+    } catch (error) {
+      setDeviceError(error instanceof Error ? error.message : 'Failed to select GPU device');
+    }
+  };
+
   useEffect(() => {
     loadSettings();
+    loadDevices();
   }, []);
 
   // Sync settings with server store data
@@ -392,18 +438,33 @@ export const GPUSettings: React.FC = () => {
             <div className="space-y-4">
               <div>
                 <Label htmlFor="gpuDevice">GPU Device</Label>
-                <Select value={settings.gpuDevice} onValueChange={(value) => handleSettingChange('gpuDevice', value)}>
+                <Select value={settings.gpuDevice} onValueChange={handleDeviceChange}>
                   <SelectTrigger>
                     <SelectValue />
                   </SelectTrigger>
                   <SelectContent>
                     <SelectItem value="auto">Auto-detect</SelectItem>
-                    <SelectItem value="cuda:0">CUDA Device 0</SelectItem>
-                    <SelectItem value="cuda:1">CUDA Device 1</SelectItem>
-                    <SelectItem value="opencl:0">OpenCL Device 0</SelectItem>
-                    <SelectItem value="opencl:1">OpenCL Device 1</SelectItem>
+                    {devices.length > 1 && (
+                      <SelectItem value="all">All devices (split batches)</SelectItem>
+                    )}
+                    {devices.map((device) => (
+                      <SelectItem key={device.index} value={`gpu:${device.index}`}>
+                        {device.name} ({device.device_type}, {device.backend})
+                      </SelectItem>
+                    ))}
                   </SelectContent>
                 </Select>
+                {deviceError && (
+                  <p className="text-sm text-red-500 mt-1">{deviceError}</p>
+                )}
+                {devices.filter((device) => device.metrics).map((device) => (
+                  <div key={device.index} className="flex items-center justify-between text-xs text-muted-foreground mt-2">
+                    <span>{device.name}</span>
+                    <span>
+                      {Math.round((device.metrics?.utilization ?? 0) * 100)}% utilized, {device.metrics?.jobs_completed ?? 0} chunks
+                    </span>
+                  </div>
+                ))}
               </div>
               
               <div>
@@ -2,6 +2,7 @@ import React, { useState, useEffect, useRef, useCallback } from 'react';
 import { useParams } from 'react-router-dom';
 import { useServers } from '@/store/servers-new';
 import { useWorldData } from '@/store/live';
+import { legacyApiClient } from '@/lib/api';
 
 interface HeatmapCell {
   x: number;
@@ -22,6 +23,7 @@ export const WorldHeatmap: React.FC<WorldHeatmapProps> = ({ className = '' }) =>
   const freezes = (worldData as any)?.freezes || [];
   
   const [heatmapData, setHeatmapData] = useState<HeatmapCell[]>([]);
+  const [activityCells, setActivityCells] = useState<HeatmapCell[]>([]);
   const [isVisible, setIsVisible] = useState(true);
   const [isPaused, setIsPaused] = useState(false);
   const [throttleRate, setThrottleRate] = useState(2);
@@ -140,7 +142,7 @@ export const WorldHeatmap: React.FC<WorldHeatmapProps> = ({ className = '' }) =>
 
   // Generate heatmap data from real world data
   const generateHeatmapData = useCallback((): HeatmapCell[] => {
-    const cells: HeatmapCell[] = [];
+    const cells: HeatmapCell[] = [...activityCells];
     const now = Date.now();
     
     // Use real freeze data if available
@@ -156,7 +158,32 @@ export const WorldHeatmap: React.FC<WorldHeatmapProps> = ({ className = '' }) =>
     }
 
     return cells;
-  }, [freezes]);
+  }, [freezes, activityCells]);
+
+  // Inhabited-time heatmap from the region files; the backend caches it, so poll slowly
+  const loadActivity = useCallback(async (refresh = false) => {
+    if (!serverId) return;
+    try {
+      const response = await legacyApiClient.getWorldHeatmap(serverId, 'overworld', refresh);
+      if (!response?.success || !response.data) return;
+      const lastUpdate = Date.parse(response.data.generated_at) || Date.now();
+      setActivityCells(response.data.cells.map((cell: any) => ({
+        x: cell.x + 25,
+        z: cell.z + 25,
+        intensity: cell.value,
+        lastUpdate,
+      })));
+    } catch (error) {
+      console.error('Failed to load world heatmap:', error);
+    }
+  }, [serverId]);
+
+  useEffect(() => {
+    if (!serverId || !server) return;
+    loadActivity();
+    const interval = setInterval(() => loadActivity(), 60000);
+    return () => clearInterval(interval);
+  }, [serverId, server, loadActivity]);
 
   // Update heatmap data periodically - controlled by throttleRate
   useEffect(() => {
@@ -326,6 +353,12 @@ export const WorldHeatmap: React.FC<WorldHeatmapProps> = ({ className = '' }) =>
           >
             Reset View
           </button>
+          <button
+            onClick={() => loadActivity(true)}
+            className="px-3 py-1 text-sm bg-gray-500 text-white rounded-md hover:bg-gray-600 transition-colors"
+          >
+            Rescan World
+          </button>
         </div>
       </div>
 
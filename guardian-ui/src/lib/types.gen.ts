@@ -0,0 +1,305 @@
+// @ts-nocheck
+// This file was generated by [tauri-specta](https://github.com/oscartbeaumont/tauri-specta). Do not edit this file manually.
+
+/** user-defined commands **/
+
+
+export const commands = {
+async startBackend() : Promise<string> {
+    return await TAURI_INVOKE("start_backend");
+},
+async ensureBackend() : Promise<string> {
+    return await TAURI_INVOKE("ensure_backend");
+},
+async getBackendStatus() : Promise<BackendStatus> {
+    return await TAURI_INVOKE("get_backend_status");
+},
+async getBackendUrl() : Promise<string> {
+    return await TAURI_INVOKE("get_backend_url");
+},
+async makeHttpRequest(url: string, method: string, body: string | null) : Promise<string> {
+    return await TAURI_INVOKE("make_http_request", { url, method, body });
+},
+async openServerFolder(serverId: string) : Promise<null> {
+    return await TAURI_INVOKE("open_server_folder", { serverId });
+},
+async getServerSummary(id: string) : Promise<ServerSummary> {
+    return await TAURI_INVOKE("get_server_summary", { id });
+},
+async getServers() : Promise<ServerSummary[]> {
+    return await TAURI_INVOKE("get_servers");
+},
+async createServer(data: CreateServerRequest) : Promise<ServerCreation> {
+    return await TAURI_INVOKE("create_server", { data });
+},
+async deleteServer(id: string) : Promise<null> {
+    return await TAURI_INVOKE("delete_server", { id });
+},
+async startServer(id: string) : Promise<null> {
+    return await TAURI_INVOKE("start_server", { id });
+},
+async stopServer(id: string) : Promise<null> {
+    return await TAURI_INVOKE("stop_server", { id });
+},
+async restartServer(id: string) : Promise<null> {
+    return await TAURI_INVOKE("restart_server", { id });
+},
+async promoteServer(id: string) : Promise<null> {
+    return await TAURI_INVOKE("promote_server", { id });
+},
+async sendRcon(id: string, cmd: string) : Promise<null> {
+    return await TAURI_INVOKE("send_rcon", { id, cmd });
+},
+async getConsoleMessages(id: string) : Promise<ConsoleLine[]> {
+    return await TAURI_INVOKE("get_console_messages", { id });
+},
+async getServerHealth(id: string) : Promise<ServerHealth> {
+    return await TAURI_INVOKE("get_server_health", { id });
+},
+async getPlayers(id: string) : Promise<Player[]> {
+    return await TAURI_INVOKE("get_players", { id });
+},
+async getMetrics(id: string) : Promise<Metrics> {
+    return await TAURI_INVOKE("get_metrics", { id });
+},
+async kickPlayer(id: string, playerUuid: string) : Promise<null> {
+    return await TAURI_INVOKE("kick_player", { id, playerUuid });
+},
+async banPlayer(id: string, playerUuid: string) : Promise<null> {
+    return await TAURI_INVOKE("ban_player", { id, playerUuid });
+},
+async getBackups(id: string) : Promise<Snapshot[]> {
+    return await TAURI_INVOKE("get_backups", { id });
+},
+async createBackup(id: string, name: string) : Promise<Snapshot> {
+    return await TAURI_INVOKE("create_backup", { id, name });
+},
+async deleteBackup(id: string, snapshotId: string) : Promise<null> {
+    return await TAURI_INVOKE("delete_backup", { id, snapshotId });
+},
+async restoreBackup(id: string, snapshotId: string) : Promise<null> {
+    return await TAURI_INVOKE("restore_backup", { id, snapshotId });
+},
+async getFreezeTickets(id: string) : Promise<FreezeTicket[]> {
+    return await TAURI_INVOKE("get_freeze_tickets", { id });
+},
+async thawWorld(id: string, ticketId: string, request: ThawRequest) : Promise<FreezeTicket> {
+    return await TAURI_INVOKE("thaw_world", { id, ticketId, request });
+},
+async getPregenJobs(id: string) : Promise<PregenJob[]> {
+    return await TAURI_INVOKE("get_pregen_jobs", { id });
+},
+async createPregenJob(id: string, job: CreatePregenJobRequest) : Promise<PregenJob> {
+    return await TAURI_INVOKE("create_pregen_job", { id, job });
+},
+async startPregenJob(id: string, jobId: string) : Promise<null> {
+    return await TAURI_INVOKE("start_pregen_job", { id, jobId });
+},
+async stopPregenJob(id: string, jobId: string) : Promise<null> {
+    return await TAURI_INVOKE("stop_pregen_job", { id, jobId });
+},
+async deletePregenJob(id: string, jobId: string) : Promise<null> {
+    return await TAURI_INVOKE("delete_pregen_job", { id, jobId });
+},
+async getMods(id: string) : Promise<ModInfo[]> {
+    return await TAURI_INVOKE("get_mods", { id });
+},
+async getRules(id: string) : Promise<Rule[]> {
+    return await TAURI_INVOKE("get_rules", { id });
+},
+async getConflicts(id: string) : Promise<Conflict[]> {
+    return await TAURI_INVOKE("get_conflicts", { id });
+},
+async getServerSettings(id: string) : Promise<ServerSettings> {
+    return await TAURI_INVOKE("get_server_settings", { id });
+},
+async updateServerSettings(id: string, settings: ServerSettings) : Promise<ServerSettings> {
+    return await TAURI_INVOKE("update_server_settings", { id, settings });
+},
+async getShardingTopology() : Promise<ShardingTopology> {
+    return await TAURI_INVOKE("get_sharding_topology");
+},
+async getShardAssignments() : Promise<ShardAssignment[]> {
+    return await TAURI_INVOKE("get_shard_assignments");
+},
+/**
+ * A page of the event feed, of one server or of all servers
+ */
+async getEvents(id: string | null, query: EventFeedQuery | null) : Promise<EventPage> {
+    return await TAURI_INVOKE("get_events", { id, query });
+},
+async createEvent(id: string, event: CreateEventRequest) : Promise<Event> {
+    return await TAURI_INVOKE("create_event", { id, event });
+},
+async listJobs(query: JobListQuery | null) : Promise<Job[]> {
+    return await TAURI_INVOKE("list_jobs", { query });
+},
+async getJob(jobId: string) : Promise<Job> {
+    return await TAURI_INVOKE("get_job", { jobId });
+},
+async cancelJob(jobId: string) : Promise<Job> {
+    return await TAURI_INVOKE("cancel_job", { jobId });
+},
+async getGpuStatus() : Promise<GpuStatus> {
+    return await TAURI_INVOKE("get_gpu_status");
+},
+/**
+ * Called by the frontend whenever its server list changes
+ */
+async updateTrayServers(servers: TrayServer[]) : Promise<null> {
+    return await TAURI_INVOKE("update_tray_servers", { servers });
+}
+}
+
+/** user-defined events **/
+
+
+
+/** user-defined constants **/
+
+
+
+/** user-defined types **/
+
+export type BackendState = "starting" | "healthy" | 
+/**
+ * Running but failing health checks
+ */
+"degraded" | "stopped"
+export type BackendStatus = { state: BackendState; url: string | null; 
+/**
+ * Whether the backend was already running rather than started by the app
+ */
+external: boolean; restarts: number; last_error: string | null }
+export type BlueGreen = { active: string; candidate_healthy: boolean }
+export type ComposerSettings = { profile: string }
+export type Conflict = { id: string; mods: string[]; severity: string; description: string }
+export type ConsoleLine = { ts: string; level: string; msg: string }
+export type ConsoleLines = { lines: ConsoleLine[] }
+export type CrashSignature = { id: string; pattern: string; severity: string; description: string; occurrences: number; last_seen: string }
+export type CreateEventRequest = { name: string; description: string; scheduled_at: string; actions: string[] }
+export type CreatePregenJobRequest = { region: Region; dimension: string; priority: string; gpu_assist: boolean }
+export type CreateServerRequest = { name: string; version: string; max_players: number | null; memory: number | null; paths: PathSettings }
+export type Event = { id: string; name: string; description: string; scheduled_at: string; status: string; actions: string[] }
+export type EventFeedQuery = { 
+/**
+ * Comma-separated kinds
+ */
+types: string | null; category: string | null; 
+/**
+ * Lowest severity to include
+ */
+severity: string | null; since: string | null; until: string | null; cursor: string | null; limit: number | null }
+export type EventPage = { events: FeedEvent[]; next_cursor: string | null }
+/**
+ * An entry of the event feed
+ */
+export type FeedEvent = { id: string; server_id: string | null; kind: string; category: string; severity: string; message: string; created_at: string }
+export type FreezeTicket = { id: string; server_id: string; status: string; suspect_kind: string; suspect_frame: string | null; entity_type: string | null; location: Location | null; stack: string[]; thread_dump: string | null; duration_ms: number | null; resolution: string | null; created_at: string; resolved_at: string | null }
+export type GPUSettings = { enabled: boolean; queue_size: number; 
+/**
+ * Adapter pregeneration runs on: "auto", "all" or "gpu:<index>"
+ */
+device?: string }
+export type GeneralSettings = { name: string; description: string; version: string; loader: string; max_players: number; motd: string; difficulty: string; gamemode: string; pvp: boolean; online_mode: boolean; whitelist: boolean; enable_command_block: boolean; view_distance: number; simulation_distance: number }
+export type GpuStatus = { available: boolean; worker_id: string | null; queue_size: number; last_activity: string | null }
+export type HASettings = { enabled: boolean; blue_green: boolean }
+export type JVMSettings = { memory: number; flags: string[] }
+/**
+ * A long-running operation on the host, such as a backup or lighting job
+ */
+export type Job = { id: string; server_id: string | null; kind: string; status: string; progress: number; 
+/**
+ * Result message, or the error of a failed job
+ */
+log: string | null; started_at: string | null; finished_at: string | null; created_at: string; updated_at: string }
+export type JobListQuery = { server_id: string | null; kind: string | null; 
+/**
+ * Comma-separated statuses; "active" stands for queued and running
+ */
+status: string | null; limit: number | null }
+export type Location = { x: number; y: number; z: number; dimension: string | null }
+export type Metrics = { tps: number; tickP95: number; heapMb: number; gpuQueueMs: number; playersOnline: number }
+export type ModInfo = { id: string; name: string; version: string; enabled: boolean; conflicts: string[] | null }
+export type PathSettings = { world: string; mods: string; config: string }
+export type Player = { uuid: string; name: string; online: boolean; last_seen: string | null; playtime: number | null }
+export type PregenJob = { id: string; region: Region; dimension: string; priority: string; status: string; progress: number; eta: string | null; gpu_assist: boolean }
+export type Region = { x: number; z: number; radius: number }
+export type Rule = { id: string; name: string; enabled: boolean; description: string; code: string; created_at: string; updated_at: string }
+/**
+ * A server being created; it exists once its `server_create` job is done
+ */
+export type ServerCreation = { id: string; job_id: string; status: string }
+export type ServerHealth = { rcon: boolean; query: boolean; crash_tickets: number; freeze_tickets: number }
+export type ServerSettings = { general: GeneralSettings; jvm: JVMSettings; gpu: GPUSettings; ha: HASettings; paths: PathSettings; composer: ComposerSettings; tokens: TokenSettings }
+export type ServerSummary = { id: string; name: string; status: string; tps: number; tickP95: number; heapMb: number; playersOnline: number; gpuQueueMs: number; lastSnapshotAt: string | null; blueGreen: BlueGreen | null; version: string | null; maxPlayers: number | null; memory: number | null }
+export type Shard = { id: string; name: string; status: string; dimensions: string[] }
+export type ShardAssignment = { id: string; shard_id: string; server_id: string; dimensions: string[]; player_count: number; status: string }
+export type ShardingTopology = { shards: Shard[] }
+export type Snapshot = { id: string; name: string; size: number; created_at: string; scope: string; status: string }
+/**
+ * `action` is `kill_entity` or `regenerate_chunk`; the other fields override the ticket
+ */
+export type ThawRequest = { action: string; entity_type: string | null; x: number | null; y: number | null; z: number | null; dimension: string | null; radius: number | null }
+export type TokenSettings = { rcon: string; query: string }
+export type TrayServer = { id: string; name: string; status: string }
+
+/** tauri-specta globals **/
+
+import {
+	invoke as TAURI_INVOKE,
+	Channel as TAURI_CHANNEL,
+} from "@tauri-apps/api/core";
+import * as TAURI_API_EVENT from "@tauri-apps/api/event";
+import { type WebviewWindow as __WebviewWindow__ } from "@tauri-apps/api/webviewWindow";
+
+type __EventObj__<T> = {
+	listen: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.listen<T>>;
+	once: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.once<T>>;
+	emit: null extends T
+		? (payload?: T) => ReturnType<typeof TAURI_API_EVENT.emit>
+		: (payload: T) => ReturnType<typeof TAURI_API_EVENT.emit>;
+};
+
+export type Result<T, E> =
+	| { status: "ok"; data: T }
+	| { status: "error"; error: E };
+
+function __makeEvents__<T extends Record<string, any>>(
+	mappings: Record<keyof T, string>,
+) {
+	return new Proxy(
+		{} as unknown as {
+			[K in keyof T]: __EventObj__<T[K]> & {
+				(handle: __WebviewWindow__): __EventObj__<T[K]>;
+			};
+		},
+		{
+			get: (_, event) => {
+				const name = mappings[event as keyof T];
+
+				return new Proxy((() => {}) as any, {
+					apply: (_, __, [window]: [__WebviewWindow__]) => ({
+						listen: (arg: any) => window.listen(name, arg),
+						once: (arg: any) => window.once(name, arg),
+						emit: (arg: any) => window.emit(name, arg),
+					}),
+					get: (_, command: keyof __EventObj__<any>) => {
+						switch (command) {
+							case "listen":
+								return (arg: any) => TAURI_API_EVENT.listen(name, arg);
+							case "once":
+								return (arg: any) => TAURI_API_EVENT.once(name, arg);
+							case "emit":
+								return (arg: any) => TAURI_API_EVENT.emit(name, arg);
+						}
+					},
+				});
+			},
+		},
+	);
+}
@@ -78,7 +78,23 @@ async function testBackendConnection() {
       console.log('🔍 Attempting to call start_backend command...');
       // Use Tauri start_backend command
       const { invoke } = await import('@tauri-apps/api/core');
+
+      // hostd is restarted on crash and may come back on another port
+      const { listen } = await import('@tauri-apps/api/event');
+      let currentBase: string | null = null;
+      await listen<{ state: string; url: string | null }>('backend:status', async ({ payload }) => {
+        console.log('🔁 Backend status:', payload.state, payload.url);
+        if (payload.state === 'healthy' && payload.url && payload.url !== currentBase) {
+          currentBase = payload.url;
+          const { updateApiBase } = await import('./lib/api');
+          await updateApiBase(payload.url);
+          const { useServers } = await import('./store/servers-new');
+          useServers.getState().fetchServers().catch(() => {});
+        }
+      });
+
       const baseFromTauri = await invoke('start_backend') as string;
+      currentBase = baseFromTauri;
       console.log('✅ Backend started via Tauri sidecar:', baseFromTauri);
       
       // Update the API base URL with the correct backend URL
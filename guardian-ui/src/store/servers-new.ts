@@ -1,6 +1,7 @@
 import { create } from "zustand";
 import type { ServerSummary, ServerSettings, ServerHealth } from "@/lib/types.gen";
 import { api } from "@/lib/client";
+import { followJob } from "@/lib/api";
 
 interface ServersState {
   // Server list and selection
@@ -132,7 +133,13 @@ export const useServers = create<ServersState>((set, get) => ({
     
     try {
       console.log('Creating server with data:', data);
-      const server = await api.createServer(data);
+      // The server is created by a job and only exists once the job is done
+      const creation = await api.createServer(data);
+      const job = await followJob(creation.job_id);
+      if (job.status !== 'done') {
+        throw new Error(job.log || `Server creation ${job.status}`);
+      }
+      const server = await api.getServerSummary(creation.id);
       console.log('Server created:', server);
       
       set((state) => {
@@ -1,5 +1,5 @@
 import { create } from 'zustand';
-import { apiClient as api } from '@/lib/api';
+import { apiClient as api, followJob } from '@/lib/api';
 import type { ServerSummary, ServerHealth, ServerSettings } from '@/lib/types';
 
 interface ServersState {
@@ -71,51 +71,21 @@ export const useServersStore = create<ServersState>((set, get) => ({
       const response = await api.getServers();
       
       if (response.ok && response.data) {
-        // Type assertion to ensure compatibility
-        const servers = response.data as ServerSummary[];
-        set({ 
-          servers,
-          loading: false,
-        });
-      } else {
-        set({ 
-          error: response.error || 'Failed to fetch servers',
-          loading: false,
-        });
-      }
-    } catch (error) {
-      set({ 
-        error: error instanceof Error ? error.message : 'Failed to fetch servers',
-        loading: false,
-      });
-    }
-  },
-
-  createServer: async (data) => {
-    set({ loading: true, error: null });
-    
-    try {
-      console.log('Creating server with data:', data);
-      const response = await api.createServer(data as any);
-      console.log('API response:', response);
-      
-      if (response.ok && response.data) {
-        // Type assertion to ensure compatibility
-        const newServer = response.data as ServerSummary;
-        set((state) => ({
-          servers: [...state.servers, newServer],
-          loading: false,
-        }));
-        console.log('Server created successfully:', newServer);
+        // The response only says the creation job was queued; the server
+        // exists once the job is done
+        const creation = response.data as { id: string; job_id: string };
+        const job = await followJob(creation.job_id);
+        if (job.status !== 'done') {
+          throw new Error(job.log || `Server creation ${job.status}`);
+        }
+        set({ loading: false });
+        console.log('Server created successfully:', creation.id);
         
-        // Refresh the server list to ensure consistency
-        setTimeout(() => {
-          get().fetchServers();
-          // Fetch server configuration files after creation
-          get().fetchServerConfig(newServer.id);
-          get().fetchServerProperties(newServer.id);
-          get().fetchServerJVMArgs(newServer.id);
-        }, 100);
+        // Load the new server along with its configuration files
+        await get().fetchServers();
+        get().fetchServerConfig(creation.id);
+        get().fetchServerProperties(creation.id);
+        get().fetchServerJVMArgs(creation.id);
         
         return true;
       } else {
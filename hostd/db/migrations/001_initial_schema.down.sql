@@ -0,0 +1,11 @@
+-- Revert the initial schema. Development only: this drops all server data.
+
+DROP TABLE IF EXISTS server_modpacks;
+DROP TABLE IF EXISTS modpacks;
+DROP TABLE IF EXISTS server_backups;
+DROP TABLE IF EXISTS backup_targets;
+DROP TABLE IF EXISTS roles;
+DROP TABLE IF EXISTS users;
+DROP TABLE IF EXISTS server_metrics;
+DROP TABLE IF EXISTS server_logs;
+DROP TABLE IF EXISTS servers;
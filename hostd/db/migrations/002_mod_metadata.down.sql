@@ -0,0 +1,6 @@
+-- Revert the mod metadata schema
+
+DROP TABLE IF EXISTS mod_dependencies;
+DROP TABLE IF EXISTS installed_mods;
+DROP TABLE IF EXISTS mod_versions;
+DROP TABLE IF EXISTS mod_metadata;
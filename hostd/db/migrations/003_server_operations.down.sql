@@ -0,0 +1,7 @@
+-- Revert the server operations schema
+
+DROP TABLE IF EXISTS audit_log;
+DROP TABLE IF EXISTS api_tokens;
+DROP TABLE IF EXISTS restart_policies;
+DROP TABLE IF EXISTS restart_schedules;
+DROP TABLE IF EXISTS world_heatmaps;
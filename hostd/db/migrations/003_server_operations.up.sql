@@ -0,0 +1,73 @@
+-- Server operations schema
+-- World heatmaps, scheduled restarts, restart policies, API tokens and the audit log
+
+-- Cached world heatmaps, one per dimension
+CREATE TABLE IF NOT EXISTS world_heatmaps (
+    server_id TEXT NOT NULL,
+    dimension TEXT NOT NULL,
+    data TEXT NOT NULL,
+    generated_at DATETIME NOT NULL,
+    PRIMARY KEY (server_id, dimension),
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+-- Cron restart schedules
+CREATE TABLE IF NOT EXISTS restart_schedules (
+    id TEXT PRIMARY KEY,
+    server_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    cron_expression TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    warnings TEXT NOT NULL,
+    grace_period_seconds INTEGER NOT NULL DEFAULT 10,
+    message TEXT,
+    use_title BOOLEAN NOT NULL DEFAULT 1,
+    last_run DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+-- Crash watchdog restart policies
+CREATE TABLE IF NOT EXISTS restart_policies (
+    server_id TEXT PRIMARY KEY,
+    policy TEXT NOT NULL,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+-- API tokens; only the SHA-256 of each token is stored
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    token_prefix TEXT NOT NULL,
+    scopes TEXT NOT NULL,
+    server_ids TEXT NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME,
+    last_used_at DATETIME,
+    revoked_at DATETIME
+);
+
+-- Mutating API requests
+CREATE TABLE IF NOT EXISTS audit_log (
+    id TEXT PRIMARY KEY,
+    timestamp DATETIME NOT NULL,
+    actor TEXT NOT NULL,
+    actor_type TEXT NOT NULL,
+    action TEXT NOT NULL,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    server_id TEXT,
+    status_code INTEGER NOT NULL,
+    success BOOLEAN NOT NULL,
+    error TEXT,
+    duration_ms INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_restart_schedules_server_id ON restart_schedules(server_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log(timestamp);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(actor);
+CREATE INDEX IF NOT EXISTS idx_audit_log_server_id ON audit_log(server_id);
@@ -0,0 +1,6 @@
+-- Revert metrics history rollups
+
+DROP INDEX IF EXISTS idx_server_metrics_rollup_bucket;
+DROP INDEX IF EXISTS idx_server_metrics_history;
+DELETE FROM server_metrics WHERE resolution != 'raw';
+ALTER TABLE server_metrics DROP COLUMN resolution;
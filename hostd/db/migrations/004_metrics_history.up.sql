@@ -0,0 +1,8 @@
+-- Metrics history: raw samples and their 1m/5m/1h rollups share server_metrics
+ALTER TABLE server_metrics ADD COLUMN resolution TEXT NOT NULL DEFAULT 'raw';
+
+CREATE INDEX IF NOT EXISTS idx_server_metrics_history ON server_metrics(server_id, resolution, timestamp);
+
+-- One rollup per server and bucket
+CREATE UNIQUE INDEX IF NOT EXISTS idx_server_metrics_rollup_bucket
+    ON server_metrics(server_id, resolution, timestamp) WHERE resolution != 'raw';
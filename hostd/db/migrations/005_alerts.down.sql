@@ -0,0 +1,5 @@
+-- Revert alert rules and channels
+
+DROP INDEX IF EXISTS idx_alert_rules_server_id;
+DROP TABLE IF EXISTS alert_rules;
+DROP TABLE IF EXISTS alert_channels;
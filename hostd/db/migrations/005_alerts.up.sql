@@ -0,0 +1,30 @@
+-- Alert rules and the channels they notify
+
+-- Discord/Slack/webhook/email destinations; `config` holds the channel settings as JSON
+CREATE TABLE IF NOT EXISTS alert_channels (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    config TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Conditions to alert on; a NULL server_id applies to every server
+CREATE TABLE IF NOT EXISTS alert_rules (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    condition TEXT NOT NULL,
+    server_id TEXT,
+    threshold REAL,
+    cooldown_minutes INTEGER NOT NULL DEFAULT 15,
+    channel_ids TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    last_triggered_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_alert_rules_server_id ON alert_rules(server_id);
@@ -0,0 +1,4 @@
+-- Revert configuration revisions
+
+DROP INDEX IF EXISTS idx_config_revisions_server;
+DROP TABLE IF EXISTS config_revisions;
@@ -0,0 +1,19 @@
+-- Every change Guardian makes to a server's configuration, for diffs and rollback
+
+-- `target` is `server.properties`, `jvm_args` or a path under the server's `config/`
+-- directory. `content` is the full configuration after the change and
+-- `previous_content` what it replaced (NULL when the file did not exist).
+CREATE TABLE IF NOT EXISTS config_revisions (
+    id TEXT PRIMARY KEY,
+    server_id TEXT NOT NULL,
+    target TEXT NOT NULL,
+    content TEXT NOT NULL,
+    previous_content TEXT,
+    diff TEXT NOT NULL,
+    author TEXT,
+    message TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_config_revisions_server ON config_revisions(server_id, created_at);
@@ -0,0 +1,4 @@
+-- Revert temporary bans
+
+DROP INDEX IF EXISTS idx_temporary_bans_expiry;
+DROP TABLE IF EXISTS temporary_bans;
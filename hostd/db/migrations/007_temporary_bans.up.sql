@@ -0,0 +1,18 @@
+-- Bans Guardian lifts itself when they expire
+
+-- `kind` is `player` or `ip`; `target` is the player name or the IP address
+CREATE TABLE IF NOT EXISTS temporary_bans (
+    id TEXT PRIMARY KEY,
+    server_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    target TEXT NOT NULL,
+    uuid TEXT,
+    reason TEXT,
+    source TEXT,
+    expires_at DATETIME NOT NULL,
+    lifted_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_temporary_bans_expiry ON temporary_bans(lifted_at, expires_at);
@@ -0,0 +1,4 @@
+-- Revert external servers
+
+DELETE FROM servers WHERE managed = 0;
+ALTER TABLE servers DROP COLUMN managed;
@@ -0,0 +1,3 @@
+-- External servers: registered by address and monitored over RCON and query,
+-- but not run by Guardian. Existing servers are all managed.
+ALTER TABLE servers ADD COLUMN managed BOOLEAN NOT NULL DEFAULT 1;
@@ -0,0 +1,3 @@
+-- Revert server templates
+
+DROP TABLE IF EXISTS server_templates;
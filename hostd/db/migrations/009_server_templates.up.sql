@@ -0,0 +1,19 @@
+-- Reusable server templates: loader, version, JVM settings, server.properties
+-- and a mod list. The mod jars themselves live in the template's directory.
+
+-- `properties` is a JSON object of server.properties keys; `mods` a JSON array of jar names
+CREATE TABLE IF NOT EXISTS server_templates (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    loader TEXT NOT NULL,
+    loader_version TEXT NOT NULL,
+    minecraft_version TEXT NOT NULL,
+    memory INTEGER NOT NULL,
+    jvm_args TEXT NOT NULL,
+    properties TEXT NOT NULL,
+    mods TEXT NOT NULL,
+    source_server_id TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
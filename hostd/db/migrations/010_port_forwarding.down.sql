@@ -0,0 +1,3 @@
+-- Revert port forwarding settings
+
+DROP TABLE IF EXISTS port_forwarding;
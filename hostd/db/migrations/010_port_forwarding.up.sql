@@ -0,0 +1,8 @@
+-- Per-server opt-in for opening the game port on the router with UPnP or NAT-PMP
+
+CREATE TABLE IF NOT EXISTS port_forwarding (
+    server_id TEXT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT 0,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- Revert server tunnels
+
+DROP TABLE IF EXISTS server_tunnels;
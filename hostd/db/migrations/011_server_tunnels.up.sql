@@ -0,0 +1,13 @@
+-- Tunnels that give servers a public address without port forwarding
+
+-- `token` is blank once the OS keychain holds it
+CREATE TABLE IF NOT EXISTS server_tunnels (
+    server_id TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    token TEXT NOT NULL DEFAULT '',
+    region TEXT,
+    agent_path TEXT,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- Revert server processes
+
+DROP TABLE IF EXISTS server_processes;
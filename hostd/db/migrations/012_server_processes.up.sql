@@ -0,0 +1,13 @@
+-- Server processes hostd launched, so they can be re-attached after a restart
+
+-- `process_started_at` is the OS start time of `pid`, to tell a reused PID apart
+CREATE TABLE IF NOT EXISTS server_processes (
+    server_id TEXT PRIMARY KEY,
+    pid INTEGER NOT NULL,
+    process_started_at INTEGER NOT NULL DEFAULT 0,
+    java_path TEXT NOT NULL,
+    server_directory TEXT NOT NULL,
+    command_line TEXT NOT NULL DEFAULT '[]',
+    started_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
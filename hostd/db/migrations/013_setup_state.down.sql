@@ -0,0 +1,3 @@
+-- Revert first-run setup state
+ALTER TABLE settings DROP COLUMN accept_eula_by_default;
+ALTER TABLE settings DROP COLUMN setup_completed_at;
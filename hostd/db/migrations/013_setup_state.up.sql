@@ -0,0 +1,18 @@
+-- First-run setup: when the wizard was completed, and whether new servers
+-- start with the Minecraft EULA accepted.
+
+-- The settings table predates migrations; create it here on a fresh database
+CREATE TABLE IF NOT EXISTS settings (
+    id TEXT PRIMARY KEY,
+    cf_api_key TEXT,
+    modrinth_token TEXT,
+    java_path TEXT NOT NULL DEFAULT 'java',
+    default_ram_mb INTEGER NOT NULL DEFAULT 4096,
+    data_dir TEXT NOT NULL DEFAULT 'data',
+    telemetry_opt_in BOOLEAN NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE settings ADD COLUMN setup_completed_at DATETIME;
+ALTER TABLE settings ADD COLUMN accept_eula_by_default BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Revert per-server resource limits
+
+DROP TABLE IF EXISTS server_resource_limits;
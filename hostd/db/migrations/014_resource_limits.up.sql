@@ -0,0 +1,10 @@
+-- Per-server CPU affinity, process priority and memory cap
+
+CREATE TABLE IF NOT EXISTS server_resource_limits (
+    server_id TEXT PRIMARY KEY,
+    cpu_cores TEXT NOT NULL DEFAULT '[]',
+    priority TEXT NOT NULL DEFAULT 'normal',
+    memory_limit_mb INTEGER,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
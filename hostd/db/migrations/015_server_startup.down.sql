@@ -0,0 +1,3 @@
+-- Revert auto-start ordering
+
+DROP TABLE IF EXISTS server_startup;
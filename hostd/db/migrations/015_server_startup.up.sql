@@ -0,0 +1,11 @@
+-- Auto-start order, delay and dependencies of each server
+
+CREATE TABLE IF NOT EXISTS server_startup (
+    server_id TEXT PRIMARY KEY,
+    start_order INTEGER NOT NULL DEFAULT 0,
+    delay_seconds INTEGER NOT NULL DEFAULT 0,
+    depends_on TEXT NOT NULL DEFAULT '[]',
+    ready_timeout_seconds INTEGER NOT NULL DEFAULT 300,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
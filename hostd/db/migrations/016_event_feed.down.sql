@@ -0,0 +1,5 @@
+-- Revert the event feed indexes and retention setting
+ALTER TABLE settings DROP COLUMN event_retention_days;
+DROP INDEX IF EXISTS idx_event_logs_event_type;
+DROP INDEX IF EXISTS idx_event_logs_server_feed;
+DROP INDEX IF EXISTS idx_event_logs_feed;
@@ -0,0 +1,21 @@
+-- Event feed: indexes for filtered, cursor-paged reads and a retention setting
+
+-- The event_logs table predates migrations; create it here on a fresh database
+CREATE TABLE IF NOT EXISTS event_logs (
+    id TEXT PRIMARY KEY,
+    server_id TEXT,
+    event_type TEXT NOT NULL,
+    message TEXT NOT NULL,
+    level TEXT NOT NULL DEFAULT 'info',
+    metadata TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE SET NULL
+);
+
+-- Pages are read newest first by (created_at, id)
+CREATE INDEX IF NOT EXISTS idx_event_logs_feed ON event_logs (created_at, id);
+CREATE INDEX IF NOT EXISTS idx_event_logs_server_feed ON event_logs (server_id, created_at, id);
+CREATE INDEX IF NOT EXISTS idx_event_logs_event_type ON event_logs (event_type);
+
+-- Days to keep events; 0 keeps them forever
+ALTER TABLE settings ADD COLUMN event_retention_days INTEGER NOT NULL DEFAULT 30;
@@ -0,0 +1,4 @@
+-- Revert console command history
+
+DROP INDEX IF EXISTS idx_console_commands_server;
+DROP TABLE IF EXISTS console_commands;
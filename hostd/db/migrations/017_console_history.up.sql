@@ -0,0 +1,16 @@
+-- Commands sent to server consoles, for history and autocompletion
+
+-- `author` is the user who sent the command; NULL for API calls without a user.
+-- `error` is set when the server could not be reached.
+CREATE TABLE IF NOT EXISTS console_commands (
+    id TEXT PRIMARY KEY,
+    server_id TEXT NOT NULL,
+    command TEXT NOT NULL,
+    author TEXT,
+    success INTEGER NOT NULL DEFAULT 1,
+    error TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_console_commands_server ON console_commands(server_id, created_at);
@@ -0,0 +1,3 @@
+-- Revert the metadata cache
+
+DROP TABLE IF EXISTS metadata_cache;
@@ -0,0 +1,11 @@
+-- Responses of version and project metadata APIs, kept across restarts
+
+-- `etag` and `last_modified` are the validators the provider sent, used to
+-- revalidate the entry once it is older than its TTL.
+CREATE TABLE IF NOT EXISTS metadata_cache (
+    url TEXT PRIMARY KEY,
+    body TEXT NOT NULL,
+    etag TEXT,
+    last_modified TEXT,
+    fetched_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
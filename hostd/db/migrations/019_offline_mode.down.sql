@@ -0,0 +1,3 @@
+-- Revert offline mode
+
+ALTER TABLE settings DROP COLUMN offline_mode;
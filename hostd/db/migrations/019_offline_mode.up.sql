@@ -0,0 +1,3 @@
+-- Offline mode: serve version catalogs from the metadata cache without contacting providers
+
+ALTER TABLE settings ADD COLUMN offline_mode BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+-- Revert sharding topology
+
+DROP TABLE IF EXISTS shard_regions;
+DROP TABLE IF EXISTS shard_assignments;
+DROP TABLE IF EXISTS shards;
+DROP TABLE IF EXISTS shard_groups;
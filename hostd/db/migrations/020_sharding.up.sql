@@ -0,0 +1,46 @@
+-- Sharding topology: groups of shards that together serve one world, the
+-- managed servers running each shard and the regions each shard owns
+
+CREATE TABLE IF NOT EXISTS shard_groups (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- `max_players` is the player capacity of the whole shard
+CREATE TABLE IF NOT EXISTS shards (
+    id TEXT PRIMARY KEY,
+    group_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    max_players INTEGER NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (group_id) REFERENCES shard_groups (id) ON DELETE CASCADE
+);
+
+-- A server runs at most one shard; `dimensions` is a JSON array of dimension IDs
+CREATE TABLE IF NOT EXISTS shard_assignments (
+    id TEXT PRIMARY KEY,
+    shard_id TEXT NOT NULL,
+    server_id TEXT NOT NULL UNIQUE,
+    dimensions TEXT NOT NULL,
+    assigned_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (shard_id) REFERENCES shards (id) ON DELETE CASCADE,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+-- Rectangles of region files, in region coordinates, both corners included
+CREATE TABLE IF NOT EXISTS shard_regions (
+    id TEXT PRIMARY KEY,
+    shard_id TEXT NOT NULL,
+    dimension TEXT NOT NULL,
+    min_x INTEGER NOT NULL,
+    min_z INTEGER NOT NULL,
+    max_x INTEGER NOT NULL,
+    max_z INTEGER NOT NULL,
+    FOREIGN KEY (shard_id) REFERENCES shards (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_shards_group_id ON shards(group_id);
+CREATE INDEX IF NOT EXISTS idx_shard_assignments_shard_id ON shard_assignments(shard_id);
+CREATE INDEX IF NOT EXISTS idx_shard_regions_shard_id ON shard_regions(shard_id);
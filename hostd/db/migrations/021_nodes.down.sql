@@ -0,0 +1,3 @@
+-- Revert multi-node mode
+
+DROP TABLE IF EXISTS nodes;
@@ -0,0 +1,4 @@
+-- Revert freeze tickets
+
+DROP INDEX IF EXISTS idx_freeze_tickets_server;
+DROP TABLE IF EXISTS freeze_tickets;
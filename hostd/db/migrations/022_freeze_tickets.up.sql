@@ -0,0 +1,24 @@
+-- Freeze tickets: server-thread stalls caught by the freeze detector
+
+-- `status` is open, recovered, crashed or thawed. `stack` holds the server
+-- thread frames as a JSON array; `thread_dump` is the path of the full dump.
+CREATE TABLE IF NOT EXISTS freeze_tickets (
+    id TEXT PRIMARY KEY,
+    server_id TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'open',
+    suspect_kind TEXT NOT NULL DEFAULT 'unknown',
+    suspect_frame TEXT,
+    entity_type TEXT,
+    dimension TEXT,
+    x REAL,
+    y REAL,
+    z REAL,
+    stack TEXT NOT NULL DEFAULT '[]',
+    thread_dump TEXT,
+    duration_ms INTEGER,
+    resolution TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_freeze_tickets_server ON freeze_tickets(server_id, created_at);
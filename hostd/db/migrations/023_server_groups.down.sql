@@ -0,0 +1,4 @@
+-- Revert server groups
+
+DROP TABLE IF EXISTS server_group_members;
+DROP TABLE IF EXISTS server_groups;
@@ -0,0 +1,22 @@
+-- Server groups: named sets of servers that bulk actions, restart schedules
+-- and summaries can target as a whole. A server may be in several groups.
+
+CREATE TABLE IF NOT EXISTS server_groups (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    description TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS server_group_members (
+    group_id TEXT NOT NULL,
+    server_id TEXT NOT NULL,
+    -- Members are listed in the order they were given
+    position INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (group_id, server_id),
+    FOREIGN KEY (group_id) REFERENCES server_groups (id) ON DELETE CASCADE,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_server_group_members_server ON server_group_members(server_id);
@@ -0,0 +1,3 @@
+-- Revert the Discord integration
+
+DROP TABLE IF EXISTS discord_settings;
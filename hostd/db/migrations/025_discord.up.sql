@@ -0,0 +1,22 @@
+-- Discord integration: one row of settings for the bot that posts server
+-- status, player and crash messages and answers slash commands.
+
+CREATE TABLE IF NOT EXISTS discord_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled BOOLEAN NOT NULL DEFAULT 0,
+    application_id TEXT,
+    -- Hex Ed25519 key Discord signs interactions with
+    public_key TEXT,
+    -- Blank once the token has been moved to the OS keychain
+    bot_token TEXT NOT NULL DEFAULT '',
+    -- Slash commands are registered in this guild only, or globally when NULL
+    guild_id TEXT,
+    status_channel_id TEXT,
+    player_channel_id TEXT,
+    alert_channel_id TEXT,
+    -- JSON array of role IDs allowed to start and stop servers
+    control_role_ids TEXT NOT NULL DEFAULT '[]',
+    -- The status message edited in place
+    status_message_id TEXT,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,3 @@
+-- Revert chat bridges
+
+DROP TABLE IF EXISTS chat_bridges;
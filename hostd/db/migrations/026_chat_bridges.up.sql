@@ -0,0 +1,16 @@
+-- Chat bridges: a server's chat relayed to a Discord channel or webhook, and
+-- messages from Discord relayed back into the game.
+
+CREATE TABLE IF NOT EXISTS chat_bridges (
+    server_id TEXT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT 0,
+    -- Channel the Discord bot posts chat to and reads replies from
+    discord_channel_id TEXT,
+    -- Discord webhook or generic URL chat is POSTed to
+    webhook_url TEXT,
+    relay_inbound BOOLEAN NOT NULL DEFAULT 1,
+    outbound_template TEXT NOT NULL,
+    inbound_template TEXT NOT NULL,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
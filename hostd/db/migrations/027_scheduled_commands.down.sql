@@ -0,0 +1,7 @@
+-- Revert scheduled commands
+
+DELETE FROM restart_schedules WHERE kind = 'command';
+ALTER TABLE restart_schedules DROP COLUMN last_task_id;
+ALTER TABLE restart_schedules DROP COLUMN last_status;
+ALTER TABLE restart_schedules DROP COLUMN commands;
+ALTER TABLE restart_schedules DROP COLUMN kind;
@@ -0,0 +1,7 @@
+-- Scheduled commands: restart schedules that run console commands over RCON
+-- instead of restarting, with the outcome of the last run
+
+ALTER TABLE restart_schedules ADD COLUMN kind TEXT NOT NULL DEFAULT 'restart';
+ALTER TABLE restart_schedules ADD COLUMN commands TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE restart_schedules ADD COLUMN last_status TEXT;
+ALTER TABLE restart_schedules ADD COLUMN last_task_id TEXT;
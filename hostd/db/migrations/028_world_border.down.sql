@@ -0,0 +1,3 @@
+-- Revert world border schedules
+
+DROP TABLE IF EXISTS world_border_schedules;
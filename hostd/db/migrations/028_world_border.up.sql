@@ -0,0 +1,28 @@
+-- World border schedules: a server's world border grown on a cron schedule
+-- over RCON, with pregeneration kept ahead of the next growth.
+
+CREATE TABLE IF NOT EXISTS world_border_schedules (
+    server_id TEXT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    center_x INTEGER NOT NULL DEFAULT 0,
+    center_z INTEGER NOT NULL DEFAULT 0,
+    -- Border diameter in blocks the first time the schedule applies it
+    initial_size INTEGER NOT NULL,
+    -- Blocks added to the diameter each time the schedule is due
+    growth INTEGER NOT NULL,
+    max_size INTEGER,
+    cron_expression TEXT NOT NULL,
+    -- Seconds the border takes to move to a new size
+    transition_seconds INTEGER NOT NULL DEFAULT 0,
+    -- Diameter last applied; NULL until the schedule first runs
+    current_size INTEGER,
+    last_grown_at DATETIME,
+    last_error TEXT,
+    pregen_ahead BOOLEAN NOT NULL DEFAULT 1,
+    -- Blocks pregenerated beyond the next border
+    pregen_margin INTEGER NOT NULL DEFAULT 128,
+    pregen_job_id TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
@@ -0,0 +1,16 @@
+-- Revert the legacy startup schema
+
+DROP INDEX IF EXISTS idx_event_logs_created_at;
+DROP INDEX IF EXISTS idx_event_logs_server_id;
+DROP INDEX IF EXISTS idx_servers_max_players;
+DROP INDEX IF EXISTS idx_servers_name;
+DROP TABLE IF EXISTS server_mods;
+DROP TABLE IF EXISTS mod_conflicts;
+DROP TABLE IF EXISTS loader_versions;
+DROP TABLE IF EXISTS minecraft_versions;
+DROP TABLE IF EXISTS backup_records;
+DROP TABLE IF EXISTS backup_configs;
+DROP TABLE IF EXISTS mods;
+DROP TABLE IF EXISTS tasks;
+DROP TABLE IF EXISTS user_settings;
+ALTER TABLE servers DROP COLUMN server_directory;
@@ -0,0 +1,165 @@
+-- Schema hostd used to create at startup, outside the migrations: legacy task,
+-- mod and backup tables, version catalogues, and the directory servers live in.
+-- Databases that already have it record this migration as applied when opened
+-- (see DatabaseManager::new).
+
+ALTER TABLE servers ADD COLUMN server_directory TEXT DEFAULT 'data/servers';
+
+CREATE TABLE IF NOT EXISTS user_settings (
+    id TEXT PRIMARY KEY,
+    theme TEXT NOT NULL DEFAULT 'dark',
+    language TEXT NOT NULL DEFAULT 'en',
+    notifications BOOLEAN NOT NULL DEFAULT 1,
+    auto_refresh BOOLEAN NOT NULL DEFAULT 1,
+    refresh_interval INTEGER NOT NULL DEFAULT 5,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS tasks (
+    id TEXT PRIMARY KEY,
+    server_id TEXT,
+    kind TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    progress REAL NOT NULL DEFAULT 0.0,
+    log TEXT,
+    metadata TEXT,
+    started_at DATETIME,
+    finished_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS mods (
+    id TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    project_id TEXT NOT NULL,
+    version_id TEXT NOT NULL,
+    filename TEXT NOT NULL,
+    sha1 TEXT NOT NULL,
+    server_id TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    category TEXT NOT NULL DEFAULT 'unknown',
+    side TEXT DEFAULT 'both',
+    source TEXT DEFAULT 'unknown',
+    name TEXT DEFAULT 'Unknown',
+    description TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS backup_configs (
+    id TEXT PRIMARY KEY,
+    server_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    schedule TEXT NOT NULL,
+    retention_days INTEGER NOT NULL DEFAULT 7,
+    include_world BOOLEAN NOT NULL DEFAULT 1,
+    include_logs BOOLEAN NOT NULL DEFAULT 1,
+    include_configs BOOLEAN NOT NULL DEFAULT 1,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS backup_records (
+    id TEXT PRIMARY KEY,
+    config_id TEXT NOT NULL,
+    server_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    path TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at DATETIME,
+    FOREIGN KEY (config_id) REFERENCES backup_configs (id) ON DELETE CASCADE,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS minecraft_versions (
+    id TEXT PRIMARY KEY,
+    release_type TEXT NOT NULL,
+    release_date DATETIME NOT NULL,
+    protocol_version INTEGER NOT NULL,
+    data_version INTEGER NOT NULL,
+    is_supported BOOLEAN DEFAULT TRUE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS loader_versions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    loader_type TEXT NOT NULL,
+    version TEXT NOT NULL,
+    minecraft_version TEXT NOT NULL,
+    download_url TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    sha256 TEXT NOT NULL,
+    is_stable BOOLEAN DEFAULT TRUE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (minecraft_version) REFERENCES minecraft_versions (id)
+);
+
+CREATE TABLE IF NOT EXISTS mod_conflicts (
+    id TEXT PRIMARY KEY,
+    mod_metadata_id TEXT NOT NULL,
+    conflicting_mod_id TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (mod_metadata_id) REFERENCES mod_metadata (id) ON DELETE CASCADE,
+    FOREIGN KEY (conflicting_mod_id) REFERENCES mod_metadata (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS server_mods (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_id TEXT NOT NULL,
+    mod_id TEXT NOT NULL,
+    mod_version_id INTEGER NOT NULL,
+    enabled BOOLEAN DEFAULT TRUE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE,
+    FOREIGN KEY (mod_id) REFERENCES mods (id),
+    FOREIGN KEY (mod_version_id) REFERENCES mod_versions (id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_servers_name ON servers (name);
+CREATE INDEX IF NOT EXISTS idx_servers_max_players ON servers (max_players);
+CREATE INDEX IF NOT EXISTS idx_tasks_server_id ON tasks (server_id);
+CREATE INDEX IF NOT EXISTS idx_tasks_kind ON tasks (kind);
+CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks (status);
+CREATE INDEX IF NOT EXISTS idx_tasks_created_at ON tasks (created_at);
+CREATE INDEX IF NOT EXISTS idx_mods_server_id ON mods (server_id);
+CREATE INDEX IF NOT EXISTS idx_mods_provider ON mods (provider);
+CREATE INDEX IF NOT EXISTS idx_mods_enabled ON mods (enabled);
+CREATE INDEX IF NOT EXISTS idx_mods_category ON mods (category);
+CREATE INDEX IF NOT EXISTS idx_mods_side ON mods (side);
+CREATE INDEX IF NOT EXISTS idx_mods_source ON mods (source);
+CREATE INDEX IF NOT EXISTS idx_backup_configs_server_id ON backup_configs (server_id);
+CREATE INDEX IF NOT EXISTS idx_backup_records_server_id ON backup_records (server_id);
+CREATE INDEX IF NOT EXISTS idx_backup_records_created_at ON backup_records (created_at);
+CREATE INDEX IF NOT EXISTS idx_event_logs_server_id ON event_logs (server_id);
+CREATE INDEX IF NOT EXISTS idx_event_logs_created_at ON event_logs (created_at);
+CREATE INDEX IF NOT EXISTS idx_server_mods_server_id ON server_mods (server_id);
+
+INSERT OR IGNORE INTO minecraft_versions (id, release_type, release_date, protocol_version, data_version, is_supported) VALUES
+    ('1.21.1', 'release', '2024-08-20T00:00:00Z', 767, 15, 1),
+    ('1.21', 'release', '2024-06-13T00:00:00Z', 766, 15, 1),
+    ('1.20.6', 'release', '2024-05-14T00:00:00Z', 765, 15, 1),
+    ('1.20.4', 'release', '2024-01-15T00:00:00Z', 764, 15, 1),
+    ('1.20.1', 'release', '2023-06-12T00:00:00Z', 763, 15, 1),
+    ('1.19.4', 'release', '2023-03-14T00:00:00Z', 762, 15, 1),
+    ('1.18.2', 'release', '2022-02-28T00:00:00Z', 758, 15, 1),
+    ('1.17.1', 'release', '2021-07-06T00:00:00Z', 756, 15, 1);
+
+INSERT INTO loader_versions (loader_type, version, minecraft_version, download_url, file_size, sha256, is_stable)
+SELECT * FROM (VALUES
+    ('forge', '47.4.0', '1.21.1', 'https://maven.minecraftforge.net/net/minecraftforge/forge/1.21.1-47.4.0/forge-1.21.1-47.4.0-installer.jar', 12345678, 'sha256hash1', 1),
+    ('forge', '47.3.0', '1.21', 'https://maven.minecraftforge.net/net/minecraftforge/forge/1.21-47.3.0/forge-1.21-47.3.0-installer.jar', 12345678, 'sha256hash2', 1),
+    ('fabric', '0.15.11', '1.21.1', 'https://maven.fabricmc.net/net/fabricmc/fabric-installer/0.15.11/fabric-installer-0.15.11.jar', 8765432, 'sha256hash3', 1),
+    ('fabric', '0.15.10', '1.21', 'https://maven.fabricmc.net/net/fabricmc/fabric-installer/0.15.10/fabric-installer-0.15.10.jar', 8765432, 'sha256hash4', 1),
+    ('quilt', '0.8.0', '1.21.1', 'https://maven.quiltmc.org/repository/release/org/quiltmc/quilt-installer/0.8.0/quilt-installer-0.8.0.jar', 5432109, 'sha256hash5', 1)
+)
+WHERE NOT EXISTS (SELECT 1 FROM loader_versions);
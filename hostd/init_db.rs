@@ -1,22 +1,33 @@
-use sqlx::{SqlitePool, migrate::Migrator};
+use clap::Parser;
+use hostd::database::DatabaseManager;
 use std::env;
 
-static MIGRATOR: Migrator = sqlx::migrate!("./db/migrations");
+#[derive(Parser)]
+#[command(about = "Create or migrate the Guardian database")]
+struct Args {
+    /// Revert migrations newer than this version (development only, drops data)
+    #[arg(long, value_name = "VERSION")]
+    down_to: Option<i64>,
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
     let database_url = env::var("DATABASE_URL")
         .unwrap_or_else(|_| "sqlite:guardian.db".to_string());
     
     println!("Connecting to database: {}", database_url);
     
-    let pool = SqlitePool::connect(&database_url).await?;
-    
     println!("Running migrations...");
-    MIGRATOR.run(&pool).await?;
-    
-    println!("Database initialized successfully!");
+    let db = DatabaseManager::new(&database_url).await?;
+
+    if let Some(target) = args.down_to {
+        println!("Reverting migrations newer than {}...", target);
+        db.migrate_down(target).await?;
+    }
+
+    let info = db.get_database_info().await?;
+    println!("Database initialized successfully! Schema version {} of {}", info.schema_version, info.latest_version);
     
-    pool.close().await;
     Ok(())
 }
@@ -0,0 +1,606 @@
+//! Alert rules: conditions such as a crashed server, low TPS, a nearly full disk
+//! or a failed backup that notify Discord, Slack, webhook or email channels.
+//! Rules are checked every 30 seconds; crashes and failed backups are picked up
+//! from the event log, TPS from persisted metrics and disk usage from the host.
+//! Every alert that fires is recorded in the event log (`alert`).
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use sysinfo::Disks;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::database::{AlertChannel, AlertRule, DatabaseManager, EventLog, ServerConfig};
+use crate::notifiers::{self, ChannelTarget};
+use crate::core::error_handler::NotFound;
+
+const EVENT_TYPE: &str = "alert";
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_COOLDOWN_MINUTES: u32 = 15;
+/// TPS is averaged over the raw samples of this window
+const TPS_WINDOW: chrono::Duration = chrono::Duration::minutes(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertCondition {
+    ServerCrashed,
+    TpsLow,
+    DiskNearlyFull,
+    BackupFailed,
+}
+
+impl AlertCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ServerCrashed => "server_crashed",
+            Self::TpsLow => "tps_low",
+            Self::DiskNearlyFull => "disk_nearly_full",
+            Self::BackupFailed => "backup_failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "server_crashed" => Some(Self::ServerCrashed),
+            "tps_low" => Some(Self::TpsLow),
+            "disk_nearly_full" => Some(Self::DiskNearlyFull),
+            "backup_failed" => Some(Self::BackupFailed),
+            _ => None,
+        }
+    }
+
+    /// Minimum TPS or maximum disk usage percentage; `None` for event conditions
+    pub fn default_threshold(&self) -> Option<f64> {
+        match self {
+            Self::TpsLow => Some(15.0),
+            Self::DiskNearlyFull => Some(90.0),
+            Self::ServerCrashed | Self::BackupFailed => None,
+        }
+    }
+
+    /// Event log types that trigger the condition
+    fn event_types(&self) -> &'static [&'static str] {
+        match self {
+            Self::ServerCrashed => &["server_crash", "crash_loop"],
+            Self::BackupFailed => &["backup_failed"],
+            Self::TpsLow | Self::DiskNearlyFull => &[],
+        }
+    }
+
+    fn level(&self) -> &'static str {
+        match self {
+            Self::ServerCrashed | Self::BackupFailed => "critical",
+            Self::TpsLow | Self::DiskNearlyFull => "warning",
+        }
+    }
+
+    fn validate_threshold(&self, threshold: Option<f64>) -> Result<()> {
+        let Some(threshold) = threshold else {
+            return Ok(());
+        };
+        match self {
+            Self::TpsLow if threshold > 0.0 && threshold <= 20.0 => Ok(()),
+            Self::DiskNearlyFull if threshold > 0.0 && threshold < 100.0 => Ok(()),
+            Self::TpsLow => bail!("TPS threshold must be between 0 and 20"),
+            Self::DiskNearlyFull => bail!("Disk usage threshold must be a percentage between 0 and 100"),
+            Self::ServerCrashed | Self::BackupFailed => bail!("Condition {} takes no threshold", self.as_str()),
+        }
+    }
+}
+
+/// What is sent to every channel of a rule that fired
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertNotification {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub condition: AlertCondition,
+    pub server_id: Option<String>,
+    pub server_name: Option<String>,
+    /// `critical` or `warning`
+    pub level: String,
+    pub title: String,
+    pub message: String,
+    pub triggered_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewAlertRule {
+    pub name: String,
+    pub condition: AlertCondition,
+    pub server_id: Option<String>,
+    pub threshold: Option<f64>,
+    pub cooldown_minutes: Option<u32>,
+    pub channel_ids: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AlertRuleUpdate {
+    pub name: Option<String>,
+    pub condition: Option<AlertCondition>,
+    /// An empty string makes the rule apply to every server
+    pub server_id: Option<String>,
+    pub threshold: Option<f64>,
+    pub cooldown_minutes: Option<u32>,
+    pub channel_ids: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+/// A channel as created or replaced through the API
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewAlertChannel {
+    pub name: String,
+    #[serde(flatten)]
+    pub target: ChannelTarget,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// Whether an alert last sent at `last` is still cooling down at `now`
+fn in_cooldown(last: Option<DateTime<Utc>>, cooldown_minutes: u32, now: DateTime<Utc>) -> bool {
+    last.is_some_and(|last| now - last < chrono::Duration::minutes(cooldown_minutes as i64))
+}
+
+/// Usage percentage of the disk holding `path`, picked by the longest matching mount point
+fn disk_usage_percent(disks: &[(PathBuf, u64, u64)], path: &Path) -> Option<f64> {
+    let (_, total, available) = disks
+        .iter()
+        .filter(|(mount_point, total, _)| *total > 0 && path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _, _)| mount_point.as_os_str().len())?;
+    Some((total - available.min(total)) as f64 / *total as f64 * 100.0)
+}
+
+/// Rule ID and, for per-server alerts, server ID
+type CooldownKey = (String, Option<String>);
+
+pub struct AlertManager {
+    database: Arc<DatabaseManager>,
+    servers_dir: PathBuf,
+    /// Last alert per rule and server, for cooldowns
+    last_fired: RwLock<HashMap<CooldownKey, DateTime<Utc>>>,
+    /// Events up to this time have been matched against the rules
+    events_checked_until: RwLock<DateTime<Utc>>,
+}
+
+impl AlertManager {
+    pub fn new(database: Arc<DatabaseManager>, servers_dir: PathBuf) -> Self {
+        Self {
+            database,
+            servers_dir,
+            last_fired: RwLock::new(HashMap::new()),
+            events_checked_until: RwLock::new(Utc::now()),
+        }
+    }
+
+    async fn validate_rule(&self, rule: &AlertRule) -> Result<()> {
+        if rule.name.trim().is_empty() {
+            bail!("Rule name is required");
+        }
+        rule.condition.validate_threshold(rule.threshold)?;
+        if let Some(server_id) = &rule.server_id {
+            if self.database.get_server(server_id).await?.is_none() {
+                return Err(NotFound::with_id("Server", server_id).into());
+            }
+        }
+        if rule.channel_ids.is_empty() {
+            bail!("At least one channel is required");
+        }
+        for channel_id in &rule.channel_ids {
+            if self.database.get_alert_channel(channel_id).await?.is_none() {
+                return Err(NotFound::with_id("Alert channel", channel_id).into());
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn list_rules(&self) -> Result<Vec<AlertRule>> {
+        self.database.get_alert_rules().await
+    }
+
+    pub async fn get_rule(&self, id: &str) -> Result<Option<AlertRule>> {
+        self.database.get_alert_rule(id).await
+    }
+
+    pub async fn create_rule(&self, request: NewAlertRule) -> Result<AlertRule> {
+        let now = Utc::now();
+        let rule = AlertRule {
+            id: Uuid::new_v4().to_string(),
+            name: request.name.trim().to_string(),
+            condition: request.condition,
+            server_id: request.server_id.filter(|id| !id.is_empty()),
+            threshold: request.threshold.or_else(|| request.condition.default_threshold()),
+            cooldown_minutes: request.cooldown_minutes.unwrap_or(DEFAULT_COOLDOWN_MINUTES),
+            channel_ids: request.channel_ids,
+            enabled: request.enabled,
+            last_triggered_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.validate_rule(&rule).await?;
+        self.database.create_alert_rule(&rule).await?;
+        Ok(rule)
+    }
+
+    pub async fn update_rule(&self, id: &str, update: AlertRuleUpdate) -> Result<Option<AlertRule>> {
+        let Some(mut rule) = self.database.get_alert_rule(id).await? else {
+            return Ok(None);
+        };
+        if let Some(name) = update.name {
+            rule.name = name.trim().to_string();
+        }
+        if let Some(condition) = update.condition {
+            if condition != rule.condition {
+                // The old threshold means something else for the new condition
+                rule.threshold = condition.default_threshold();
+            }
+            rule.condition = condition;
+        }
+        if let Some(server_id) = update.server_id {
+            rule.server_id = Some(server_id).filter(|id| !id.is_empty());
+        }
+        if let Some(threshold) = update.threshold {
+            rule.threshold = Some(threshold);
+        }
+        if let Some(cooldown_minutes) = update.cooldown_minutes {
+            rule.cooldown_minutes = cooldown_minutes;
+        }
+        if let Some(channel_ids) = update.channel_ids {
+            rule.channel_ids = channel_ids;
+        }
+        if let Some(enabled) = update.enabled {
+            rule.enabled = enabled;
+        }
+        rule.updated_at = Utc::now();
+
+        self.validate_rule(&rule).await?;
+        self.database.update_alert_rule(&rule).await?;
+        self.last_fired.write().await.retain(|(rule_id, _), _| rule_id != &rule.id);
+        Ok(Some(rule))
+    }
+
+    pub async fn delete_rule(&self, id: &str) -> Result<bool> {
+        if self.database.get_alert_rule(id).await?.is_none() {
+            return Ok(false);
+        }
+        self.database.delete_alert_rule(id).await?;
+        self.last_fired.write().await.retain(|(rule_id, _), _| rule_id != id);
+        Ok(true)
+    }
+
+    pub async fn list_channels(&self) -> Result<Vec<AlertChannel>> {
+        self.database.get_alert_channels().await
+    }
+
+    pub async fn get_channel(&self, id: &str) -> Result<Option<AlertChannel>> {
+        self.database.get_alert_channel(id).await
+    }
+
+    pub async fn create_channel(&self, request: NewAlertChannel) -> Result<AlertChannel> {
+        if request.name.trim().is_empty() {
+            bail!("Channel name is required");
+        }
+        request.target.validate()?;
+        let now = Utc::now();
+        let channel = AlertChannel {
+            id: Uuid::new_v4().to_string(),
+            name: request.name.trim().to_string(),
+            target: request.target,
+            enabled: request.enabled,
+            created_at: now,
+            updated_at: now,
+        };
+        self.database.create_alert_channel(&channel).await?;
+        Ok(channel)
+    }
+
+    /// Replace a channel's settings. An email channel keeps its SMTP password
+    /// when the request leaves it out.
+    pub async fn update_channel(&self, id: &str, request: NewAlertChannel) -> Result<Option<AlertChannel>> {
+        let Some(mut channel) = self.database.get_alert_channel(id).await? else {
+            return Ok(None);
+        };
+        if request.name.trim().is_empty() {
+            bail!("Channel name is required");
+        }
+        let mut target = request.target;
+        target.keep_password(&channel.target);
+        target.validate()?;
+
+        channel.name = request.name.trim().to_string();
+        channel.target = target;
+        channel.enabled = request.enabled;
+        channel.updated_at = Utc::now();
+        self.database.update_alert_channel(&channel).await?;
+        Ok(Some(channel))
+    }
+
+    /// Delete a channel and drop it from the rules that notify it
+    pub async fn delete_channel(&self, id: &str) -> Result<bool> {
+        if self.database.get_alert_channel(id).await?.is_none() {
+            return Ok(false);
+        }
+        for mut rule in self.database.get_alert_rules().await? {
+            if rule.channel_ids.iter().any(|channel_id| channel_id == id) {
+                rule.channel_ids.retain(|channel_id| channel_id != id);
+                rule.updated_at = Utc::now();
+                self.database.update_alert_rule(&rule).await?;
+            }
+        }
+        self.database.delete_alert_channel(id).await?;
+        Ok(true)
+    }
+
+    /// Send a test notification through a channel, returning the delivery error if any
+    pub async fn test_channel(&self, id: &str) -> Result<Option<()>> {
+        let Some(channel) = self.database.get_alert_channel(id).await? else {
+            return Ok(None);
+        };
+        let notification = AlertNotification {
+            rule_id: String::new(),
+            rule_name: "Test".to_string(),
+            condition: AlertCondition::ServerCrashed,
+            server_id: None,
+            server_name: None,
+            level: "info".to_string(),
+            title: "Guardian test notification".to_string(),
+            message: format!("Alerts sent to '{}' will arrive here.", channel.name),
+            triggered_at: Utc::now(),
+        };
+        notifiers::send(&channel.target, &notification).await?;
+        Ok(Some(()))
+    }
+
+    /// Check the rules every 30 seconds and notify channels of those that match
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting alert manager");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check().await {
+                error!("Alert check failed: {}", e);
+            }
+        }
+    }
+
+    async fn check(&self) -> Result<()> {
+        let rules: Vec<AlertRule> = self.database.get_alert_rules().await?.into_iter().filter(|rule| rule.enabled).collect();
+        self.check_events(&rules).await?;
+        if rules.is_empty() {
+            return Ok(());
+        }
+
+        let servers = self.database.get_all_servers().await?;
+        let disks: Vec<(PathBuf, u64, u64)> = Disks::new_with_refreshed_list()
+            .iter()
+            .map(|disk| (disk.mount_point().to_path_buf(), disk.total_space(), disk.available_space()))
+            .collect();
+
+        for rule in &rules {
+            let watched: Vec<&ServerConfig> = servers
+                .iter()
+                .filter(|server| rule.server_id.as_ref().is_none_or(|id| id == &server.id))
+                .collect();
+            match rule.condition {
+                AlertCondition::TpsLow => {
+                    let threshold = rule.threshold.or_else(|| rule.condition.default_threshold()).unwrap_or_default();
+                    for server in watched {
+                        let Some(tps) = self.recent_tps(&server.id).await? else {
+                            continue;
+                        };
+                        if tps < threshold {
+                            let message = format!("Average TPS over the last 2 minutes is {:.1} (threshold {:.1})", tps, threshold);
+                            self.fire(rule, Some(server), format!("Low TPS on {}", server.name), message).await;
+                        }
+                    }
+                }
+                AlertCondition::DiskNearlyFull => {
+                    let threshold = rule.threshold.or_else(|| rule.condition.default_threshold()).unwrap_or_default();
+                    if rule.server_id.is_none() {
+                        if let Some(usage) = self.disk_usage(&disks, &self.servers_dir) {
+                            if usage >= threshold {
+                                let message = format!("The disk holding {} is {:.0}% full", self.servers_dir.display(), usage);
+                                self.fire(rule, None, "Disk nearly full".to_string(), message).await;
+                            }
+                        }
+                        continue;
+                    }
+                    for server in watched {
+                        let directory = if server.server_directory.is_empty() {
+                            self.servers_dir.clone()
+                        } else {
+                            PathBuf::from(&server.server_directory)
+                        };
+                        if let Some(usage) = self.disk_usage(&disks, &directory) {
+                            if usage >= threshold {
+                                let message = format!("The disk holding {} is {:.0}% full", directory.display(), usage);
+                                self.fire(rule, Some(server), format!("Disk nearly full for {}", server.name), message).await;
+                            }
+                        }
+                    }
+                }
+                AlertCondition::ServerCrashed | AlertCondition::BackupFailed => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Match crash and backup events logged since the last check against the rules
+    async fn check_events(&self, rules: &[AlertRule]) -> Result<()> {
+        let since = *self.events_checked_until.read().await;
+        let event_types: Vec<&str> = [AlertCondition::ServerCrashed, AlertCondition::BackupFailed]
+            .iter()
+            .flat_map(|condition| condition.event_types().iter().copied())
+            .collect();
+        let events = self.database.get_events_since(&event_types, since).await?;
+        if let Some(last) = events.last() {
+            *self.events_checked_until.write().await = last.created_at;
+        }
+
+        for event in events {
+            let server = match &event.server_id {
+                Some(server_id) => self.database.get_server(server_id).await?,
+                None => None,
+            };
+            for rule in rules {
+                if !rule.condition.event_types().contains(&event.event_type.as_str()) {
+                    continue;
+                }
+                if rule.server_id.is_some() && rule.server_id != event.server_id {
+                    continue;
+                }
+                let title = match (rule.condition, &server) {
+                    (AlertCondition::ServerCrashed, Some(server)) => format!("{} crashed", server.name),
+                    (AlertCondition::ServerCrashed, None) => "Server crashed".to_string(),
+                    (_, Some(server)) => format!("Backup failed for {}", server.name),
+                    (_, None) => "Backup failed".to_string(),
+                };
+                self.fire(rule, server.as_ref(), title, event.message.clone()).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Average of the non-zero TPS samples in the last two minutes
+    async fn recent_tps(&self, server_id: &str) -> Result<Option<f64>> {
+        let samples = self
+            .database
+            .get_server_metrics_since(Some(server_id), "raw", Utc::now() - TPS_WINDOW)
+            .await?;
+        let tps: Vec<f64> = samples.iter().map(|sample| sample.tps).filter(|&tps| tps > 0.0).collect();
+        if tps.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(tps.iter().sum::<f64>() / tps.len() as f64))
+    }
+
+    fn disk_usage(&self, disks: &[(PathBuf, u64, u64)], path: &Path) -> Option<f64> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        disk_usage_percent(disks, &path)
+    }
+
+    /// Record and deliver an alert unless the rule is cooling down for this server
+    async fn fire(&self, rule: &AlertRule, server: Option<&ServerConfig>, title: String, message: String) {
+        let now = Utc::now();
+        let key = (rule.id.clone(), server.map(|server| server.id.clone()));
+        {
+            let mut last_fired = self.last_fired.write().await;
+            if in_cooldown(last_fired.get(&key).copied(), rule.cooldown_minutes, now) {
+                return;
+            }
+            last_fired.insert(key, now);
+        }
+
+        let notification = AlertNotification {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            condition: rule.condition,
+            server_id: server.map(|server| server.id.clone()),
+            server_name: server.map(|server| server.name.clone()),
+            level: rule.condition.level().to_string(),
+            title,
+            message,
+            triggered_at: now,
+        };
+        warn!("Alert '{}': {} - {}", rule.name, notification.title, notification.message);
+
+        let event = EventLog {
+            id: Uuid::new_v4().to_string(),
+            server_id: notification.server_id.clone(),
+            event_type: EVENT_TYPE.to_string(),
+            message: format!("{}: {}", notification.title, notification.message),
+            level: "warn".to_string(),
+            metadata: serde_json::to_value(&notification).ok(),
+            created_at: now,
+        };
+        if let Err(e) = self.database.log_event(&event).await {
+            error!("Failed to log alert for rule {}: {}", rule.id, e);
+        }
+
+        let mut rule = rule.clone();
+        rule.last_triggered_at = Some(now);
+        if let Err(e) = self.database.update_alert_rule(&rule).await {
+            error!("Failed to record trigger of alert rule {}: {}", rule.id, e);
+        }
+
+        for channel_id in &rule.channel_ids {
+            let channel = match self.database.get_alert_channel(channel_id).await {
+                Ok(Some(channel)) if channel.enabled => channel,
+                Ok(_) => continue,
+                Err(e) => {
+                    error!("Failed to load alert channel {}: {}", channel_id, e);
+                    continue;
+                }
+            };
+            let notification = notification.clone();
+            // Slow SMTP servers or webhooks must not hold up the next check
+            tokio::spawn(async move {
+                if let Err(e) = notifiers::send(&channel.target, &notification).await {
+                    error!("Failed to send alert to channel '{}': {}", channel.name, e);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_condition_round_trip_and_thresholds() {
+        for condition in [
+            AlertCondition::ServerCrashed,
+            AlertCondition::TpsLow,
+            AlertCondition::DiskNearlyFull,
+            AlertCondition::BackupFailed,
+        ] {
+            assert_eq!(AlertCondition::parse(condition.as_str()), Some(condition));
+        }
+        assert!(AlertCondition::TpsLow.validate_threshold(Some(18.0)).is_ok());
+        assert!(AlertCondition::TpsLow.validate_threshold(Some(25.0)).is_err());
+        assert!(AlertCondition::DiskNearlyFull.validate_threshold(Some(100.0)).is_err());
+        assert!(AlertCondition::ServerCrashed.validate_threshold(Some(1.0)).is_err());
+    }
+
+    #[test]
+    fn test_cooldown_and_disk_selection() {
+        let now = Utc::now();
+        assert!(in_cooldown(Some(now - chrono::Duration::minutes(5)), 15, now));
+        assert!(!in_cooldown(Some(now - chrono::Duration::minutes(20)), 15, now));
+        assert!(!in_cooldown(None, 15, now));
+
+        let disks = vec![(PathBuf::from("/"), 100, 50), (PathBuf::from("/srv"), 200, 10)];
+        assert_eq!(disk_usage_percent(&disks, Path::new("/srv/servers/a")), Some(95.0));
+        assert_eq!(disk_usage_percent(&disks, Path::new("/home")), Some(50.0));
+    }
+
+    #[test]
+    fn test_channel_target_json() {
+        let target: ChannelTarget = serde_json::from_value(serde_json::json!({
+            "kind": "email",
+            "smtp_host": "smtp.example.com",
+            "username": "guardian",
+            "password": "secret",
+            "from": "Guardian <guardian@example.com>",
+            "to": ["ops@example.com"],
+        }))
+        .unwrap();
+        assert!(target.validate().is_ok());
+        assert_eq!(target.kind(), "email");
+        assert!(!serde_json::to_string(&target.redacted()).unwrap().contains("secret"));
+
+        let webhook: ChannelTarget = serde_json::from_value(serde_json::json!({ "kind": "discord", "webhook_url": "ftp://example.com" })).unwrap();
+        assert!(webhook.validate().is_err());
+    }
+}
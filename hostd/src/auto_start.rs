@@ -0,0 +1,376 @@
+//! Auto-start sequencing
+//!
+//! When hostd starts, servers with `auto_start` are started one at a time
+//! instead of all at once. They go by `start_order`, wait `delay_seconds`
+//! after each, and start only once the servers they depend on answer a status
+//! ping, e.g. a proxy before its backends. Every step is recorded in the event
+//! log as `auto_start`.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, EventLog, ServerConfig, StartupRule};
+use crate::port_forwarding::PortForwarder;
+use crate::tunnels::TunnelManager;
+
+pub const EVENT_TYPE: &str = "auto_start";
+const READY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Changes to a server's place in the sequence; fields left out are kept
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StartupRuleUpdate {
+    pub start_order: Option<i32>,
+    pub delay_seconds: Option<u32>,
+    pub depends_on: Option<Vec<String>>,
+    pub ready_timeout_seconds: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepState {
+    Pending,
+    /// Waiting for dependencies to answer
+    Waiting,
+    Starting,
+    Started,
+    AlreadyRunning,
+    Failed,
+    /// Not started because a dependency didn't come up or forms a cycle
+    Blocked,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoStartStep {
+    pub server_id: String,
+    pub name: String,
+    pub start_order: i32,
+    pub depends_on: Vec<String>,
+    pub state: StepState,
+    pub message: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The last auto-start run, or the one in progress
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AutoStartStatus {
+    pub running: bool,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub steps: Vec<AutoStartStep>,
+}
+
+/// Start order for rules already sorted by `start_order`: each server comes
+/// after the servers it depends on and otherwise keeps its place. Dependencies
+/// outside `rules` don't affect the order. Returns the order, then the servers
+/// left out by a dependency cycle.
+pub fn sequence(rules: &[StartupRule]) -> (Vec<usize>, Vec<usize>) {
+    let ids: HashSet<&str> = rules.iter().map(|rule| rule.server_id.as_str()).collect();
+    let mut placed: HashSet<&str> = HashSet::new();
+    let mut order = Vec::with_capacity(rules.len());
+    while let Some(next) = (0..rules.len()).find(|&i| {
+        !placed.contains(rules[i].server_id.as_str())
+            && rules[i].depends_on.iter().all(|dep| !ids.contains(dep.as_str()) || placed.contains(dep.as_str()))
+    }) {
+        placed.insert(&rules[next].server_id);
+        order.push(next);
+    }
+    let cyclic = (0..rules.len()).filter(|i| !order.contains(i)).collect();
+    (order, cyclic)
+}
+
+pub struct AutoStartSequencer {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+    port_forwarder: Arc<PortForwarder>,
+    tunnel_manager: Arc<TunnelManager>,
+    status: RwLock<AutoStartStatus>,
+}
+
+impl AutoStartSequencer {
+    pub fn new(
+        database: Arc<DatabaseManager>,
+        process_manager: Arc<ProcessManager>,
+        port_forwarder: Arc<PortForwarder>,
+        tunnel_manager: Arc<TunnelManager>,
+    ) -> Self {
+        Self {
+            database,
+            process_manager,
+            port_forwarder,
+            tunnel_manager,
+            status: RwLock::new(AutoStartStatus::default()),
+        }
+    }
+
+    pub async fn status(&self) -> AutoStartStatus {
+        self.status.read().await.clone()
+    }
+
+    /// A server's rule, or the defaults if it has none yet
+    pub async fn rule(&self, server_id: &str) -> Result<StartupRule> {
+        Ok(self.database.get_startup_rule(server_id).await?.unwrap_or_else(|| StartupRule::new(server_id)))
+    }
+
+    /// Change a server's rule, refusing unknown dependencies and cycles
+    pub async fn update_rule(&self, server_id: &str, update: StartupRuleUpdate) -> Result<StartupRule> {
+        let mut rule = self.rule(server_id).await?;
+        if let Some(start_order) = update.start_order {
+            rule.start_order = start_order;
+        }
+        if let Some(delay_seconds) = update.delay_seconds {
+            rule.delay_seconds = delay_seconds;
+        }
+        if let Some(ready_timeout_seconds) = update.ready_timeout_seconds {
+            rule.ready_timeout_seconds = ready_timeout_seconds;
+        }
+        if let Some(mut depends_on) = update.depends_on {
+            depends_on.sort();
+            depends_on.dedup();
+            for dependency in &depends_on {
+                if dependency == server_id {
+                    bail!("A server can't depend on itself");
+                }
+                if self.database.get_server(dependency).await?.is_none() {
+                    bail!("Unknown server {}", dependency);
+                }
+            }
+            rule.depends_on = depends_on;
+        }
+
+        let mut rules: Vec<StartupRule> = self.database.get_startup_rules().await?
+            .into_iter()
+            .filter(|other| other.server_id != server_id)
+            .collect();
+        rules.push(rule.clone());
+        if !sequence(&rules).1.is_empty() {
+            bail!("These dependencies would form a cycle");
+        }
+
+        rule.updated_at = Utc::now();
+        self.database.save_startup_rule(&rule).await?;
+        Ok(rule)
+    }
+
+    /// Run the sequence in the background, unless a run is in progress
+    pub async fn start(self: Arc<Self>) -> Result<()> {
+        {
+            let mut status = self.status.write().await;
+            if status.running {
+                bail!("Auto-start is already running");
+            }
+            *status = AutoStartStatus {
+                running: true,
+                started_at: Some(Utc::now()),
+                ..Default::default()
+            };
+        }
+        tokio::spawn(async move {
+            if let Err(e) = self.run().await {
+                self.log(None, "error", format!("Auto-start failed: {}", e), None).await;
+            }
+            let mut status = self.status.write().await;
+            status.running = false;
+            status.finished_at = Some(Utc::now());
+        });
+        Ok(())
+    }
+
+    async fn run(&self) -> Result<()> {
+        let servers: HashMap<String, ServerConfig> = self.database.get_all_servers().await?
+            .into_iter()
+            .map(|server| (server.id.clone(), server))
+            .collect();
+        let saved: HashMap<String, StartupRule> = self.database.get_startup_rules().await?
+            .into_iter()
+            .map(|rule| (rule.server_id.clone(), rule))
+            .collect();
+
+        let mut rules: Vec<StartupRule> = servers.values()
+            .filter(|server| server.auto_start && server.managed)
+            .map(|server| saved.get(&server.id).cloned().unwrap_or_else(|| StartupRule::new(&server.id)))
+            .collect();
+        rules.sort_by(|a, b| {
+            a.start_order.cmp(&b.start_order).then_with(|| servers[&a.server_id].name.cmp(&servers[&b.server_id].name))
+        });
+        if rules.is_empty() {
+            return Ok(());
+        }
+        let (order, cyclic) = sequence(&rules);
+
+        self.status.write().await.steps = order.iter().chain(&cyclic).map(|&i| AutoStartStep {
+            server_id: rules[i].server_id.clone(),
+            name: servers[&rules[i].server_id].name.clone(),
+            start_order: rules[i].start_order,
+            depends_on: rules[i].depends_on.clone(),
+            state: StepState::Pending,
+            message: None,
+            updated_at: Utc::now(),
+        }).collect();
+        self.log(None, "info", format!("Auto-starting {} servers", rules.len()), None).await;
+
+        for &i in &cyclic {
+            self.step(&servers[&rules[i].server_id], StepState::Blocked, "Its dependencies form a cycle").await;
+        }
+        for (position, &i) in order.iter().enumerate() {
+            let rule = &rules[i];
+            let server = &servers[&rule.server_id];
+            let started = self.start_server(server, rule, &servers, &saved).await;
+            if started && rule.delay_seconds > 0 && position + 1 < order.len() {
+                tokio::time::sleep(Duration::from_secs(rule.delay_seconds as u64)).await;
+            }
+        }
+
+        let steps = self.status.read().await.steps.clone();
+        let count = |state: StepState| steps.iter().filter(|step| step.state == state).count();
+        let (started, failed, blocked) = (count(StepState::Started), count(StepState::Failed), count(StepState::Blocked));
+        let level = if failed + blocked > 0 { "warn" } else { "info" };
+        self.log(None, level, format!("Auto-start finished: {} started, {} failed, {} blocked", started, failed, blocked), None).await;
+        Ok(())
+    }
+
+    /// Start one server once its dependencies answer; true when it was started
+    async fn start_server(
+        &self,
+        server: &ServerConfig,
+        rule: &StartupRule,
+        servers: &HashMap<String, ServerConfig>,
+        rules: &HashMap<String, StartupRule>,
+    ) -> bool {
+        let server_uuid = match Uuid::parse_str(&server.id) {
+            Ok(id) => id,
+            Err(_) => {
+                self.step(server, StepState::Failed, "Invalid server ID").await;
+                return false;
+            }
+        };
+        if self.process_manager.is_server_running(server_uuid).await {
+            self.step(server, StepState::AlreadyRunning, "Already running").await;
+            return false;
+        }
+
+        for dependency_id in &rule.depends_on {
+            let Some(dependency) = servers.get(dependency_id) else {
+                self.step(server, StepState::Blocked, &format!("Depends on unknown server {}", dependency_id)).await;
+                return false;
+            };
+            if self.step_state(dependency_id).await.is_some_and(|state| matches!(state, StepState::Failed | StepState::Blocked)) {
+                self.step(server, StepState::Blocked, &format!("{} did not start", dependency.name)).await;
+                return false;
+            }
+            self.step(server, StepState::Waiting, &format!("Waiting for {}", dependency.name)).await;
+            let timeout = rules.get(dependency_id).map(|rule| rule.ready_timeout_seconds).unwrap_or(300);
+            if !self.wait_until_ready(dependency, Duration::from_secs(timeout as u64)).await {
+                let message = format!("{} did not answer within {} seconds", dependency.name, timeout);
+                self.step(server, StepState::Blocked, &message).await;
+                return false;
+            }
+        }
+
+        self.step(server, StepState::Starting, "Starting").await;
+        match self.process_manager.start_server_process(server.clone()).await {
+            Ok(()) => {
+                self.port_forwarder.server_started(server).await;
+                self.tunnel_manager.server_started(server).await;
+                self.step(server, StepState::Started, "Started").await;
+                true
+            }
+            Err(e) => {
+                self.step(server, StepState::Failed, &format!("Failed to start: {}", e)).await;
+                false
+            }
+        }
+    }
+
+    /// Whether `server` answers a status ping within `timeout`
+    async fn wait_until_ready(&self, server: &ServerConfig, timeout: Duration) -> bool {
+        let host = if server.managed { "127.0.0.1" } else { server.host.as_str() };
+        let deadline = Instant::now() + timeout;
+        loop {
+            if crate::server_ping::ping(host, server.port, crate::server_ping::DEFAULT_TIMEOUT).await.is_ok() {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(READY_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn step_state(&self, server_id: &str) -> Option<StepState> {
+        self.status.read().await.steps.iter().find(|step| step.server_id == server_id).map(|step| step.state)
+    }
+
+    async fn step(&self, server: &ServerConfig, state: StepState, message: &str) {
+        if let Some(step) = self.status.write().await.steps.iter_mut().find(|step| step.server_id == server.id) {
+            step.state = state;
+            step.message = Some(message.to_string());
+            step.updated_at = Utc::now();
+        }
+        let level = match state {
+            StepState::Failed | StepState::Blocked => "warn",
+            _ => "info",
+        };
+        // Waiting and starting are only shown in the status, the outcome is logged
+        if matches!(state, StepState::Started | StepState::AlreadyRunning | StepState::Failed | StepState::Blocked) {
+            let metadata = serde_json::json!({ "state": state });
+            self.log(Some(&server.id), level, format!("{}: {}", server.name, message), Some(metadata)).await;
+        }
+    }
+
+    async fn log(&self, server_id: Option<&str>, level: &str, message: String, metadata: Option<serde_json::Value>) {
+        match level {
+            "info" => info!("{}", message),
+            "warn" => warn!("{}", message),
+            _ => error!("{}", message),
+        }
+        let event = EventLog {
+            id: Uuid::new_v4().to_string(),
+            server_id: server_id.map(str::to_string),
+            event_type: EVENT_TYPE.to_string(),
+            message,
+            level: level.to_string(),
+            metadata,
+            created_at: Utc::now(),
+        };
+        if let Err(e) = self.database.log_event(&event).await {
+            error!("Failed to log auto-start event: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, depends_on: &[&str]) -> StartupRule {
+        StartupRule {
+            depends_on: depends_on.iter().map(|dep| dep.to_string()).collect(),
+            ..StartupRule::new(id)
+        }
+    }
+
+    #[test]
+    fn test_sequence_starts_dependencies_first() {
+        let rules = [rule("lobby", &["proxy"]), rule("survival", &["lobby", "proxy"]), rule("proxy", &[]), rule("creative", &["external"])];
+        let (order, cyclic) = sequence(&rules);
+        let ids: Vec<&str> = order.iter().map(|&i| rules[i].server_id.as_str()).collect();
+        assert_eq!(ids, ["proxy", "lobby", "survival", "creative"]);
+        assert!(cyclic.is_empty());
+    }
+
+    #[test]
+    fn test_sequence_leaves_out_cycles() {
+        let rules = [rule("a", &["b"]), rule("b", &["a"]), rule("c", &[]), rule("d", &["a"])];
+        let (order, cyclic) = sequence(&rules);
+        assert_eq!(order, [2]);
+        assert_eq!(cyclic, [0, 1, 3]);
+    }
+}
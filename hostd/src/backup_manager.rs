@@ -1,13 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Timelike, Utc};
 use uuid::Uuid;
 use tokio::fs as async_fs;
 use zip::ZipWriter;
-use std::io::Write;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+use rand::seq::SliceRandom;
+use sha2::{Digest, Sha256};
+
+use crate::core::error_handler::NotFound;
+use crate::database::{DatabaseManager, EventLog};
+use crate::jobs::{Job, JobContext, JobManager};
 
 /// Backup information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +30,59 @@ pub struct BackupInfo {
     pub compression: CompressionType,
     pub includes: BackupIncludes,
     pub metadata: Option<serde_json::Value>,
+    /// Every file in the archive with its checksum; empty for backups made before
+    /// checksums were recorded, which are verified by the archive's own CRCs only
+    #[serde(default)]
+    pub files: Vec<BackupFile>,
+    /// Outcome of the last verification or test-restore
+    #[serde(default)]
+    pub last_verification: Option<BackupVerification>,
+}
+
+/// A file stored in a backup archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    /// Path in the archive
+    pub path: String,
+    pub size: u64,
+    /// Hex SHA-256 of the contents when the backup was made
+    pub sha256: String,
+}
+
+/// Outcome of checking a backup archive against the checksums recorded with it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupVerification {
+    pub backup_id: String,
+    pub verified_at: DateTime<Utc>,
+    /// Whether the archive was extracted to disk and read back, rather than only read
+    pub test_restore: bool,
+    pub passed: bool,
+    pub files_checked: usize,
+    /// Files that fail to decompress or no longer match their checksum
+    pub corrupted: Vec<String>,
+    /// Recorded files the archive no longer has
+    pub missing: Vec<String>,
+    /// Why the archive couldn't be read at all
+    pub error: Option<String>,
+}
+
+impl BackupVerification {
+    fn new(backup_id: &str, test_restore: bool, check: io::Result<ArchiveCheck>) -> Self {
+        let (check, error) = match check {
+            Ok(check) => (check, None),
+            Err(e) => (ArchiveCheck::default(), Some(e.to_string())),
+        };
+        Self {
+            backup_id: backup_id.to_string(),
+            verified_at: Utc::now(),
+            test_restore,
+            passed: error.is_none() && check.corrupted.is_empty() && check.missing.is_empty(),
+            files_checked: check.files_checked,
+            corrupted: check.corrupted,
+            missing: check.missing,
+            error,
+        }
+    }
 }
 
 /// Backup status
@@ -84,6 +144,98 @@ pub struct RestoreBackupRequest {
     pub restore_config: bool,
     pub restore_logs: bool,
     pub create_backup: bool,
+    /// Only work out which files would be restored, without writing any
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Restore just these archive paths instead of the categories above: a file such as
+    /// `world/region/r.0.0.mca`, or a directory and everything under it
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+/// Files kept at the top of the server directory, restored along with `config`
+const ROOT_CONFIG_FILES: [&str; 5] = [
+    "server.properties",
+    "whitelist.json",
+    "ops.json",
+    "banned-players.json",
+    "banned-ips.json",
+];
+
+impl RestoreBackupRequest {
+    /// Archive paths the restore covers
+    fn selection(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        if !self.paths.is_empty() {
+            return self.paths.iter().map(|path| normalize_archive_path(path)).collect();
+        }
+
+        let mut selection = Vec::new();
+        if self.restore_world {
+            selection.push("world".to_string());
+        }
+        if self.restore_mods {
+            selection.push("mods".to_string());
+        }
+        if self.restore_config {
+            selection.push("config".to_string());
+            selection.extend(ROOT_CONFIG_FILES.iter().map(|file| file.to_string()));
+        }
+        if self.restore_logs {
+            selection.push("logs".to_string());
+        }
+        Ok(selection)
+    }
+}
+
+/// A file a restore writes into the server directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreEntry {
+    /// Path in the archive, which is also its path under the server directory
+    pub path: String,
+    /// Size and modification time of the copy in the backup
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    /// Whether a file already at `path` gets replaced
+    pub overwrites: bool,
+    pub current_size: Option<u64>,
+    pub current_modified: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    index: usize,
+}
+
+/// Files a restore wrote, or would write when it is a dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestorePlan {
+    pub backup_id: String,
+    pub dry_run: bool,
+    pub files: Vec<RestoreEntry>,
+    /// Files replaced and files that didn't exist before
+    pub overwritten: usize,
+    pub created: usize,
+    /// Bytes written
+    pub total_size: u64,
+    /// Requested paths that matched nothing in the backup
+    pub unmatched: Vec<String>,
+}
+
+impl RestorePlan {
+    fn new(backup_id: &str, dry_run: bool, files: Vec<RestoreEntry>, selection: &[String]) -> Self {
+        let overwritten = files.iter().filter(|file| file.overwrites).count();
+        let unmatched = selection
+            .iter()
+            .filter(|selected| !files.iter().any(|file| is_selected(&file.path, std::slice::from_ref(selected))))
+            .cloned()
+            .collect();
+        Self {
+            backup_id: backup_id.to_string(),
+            dry_run,
+            overwritten,
+            created: files.len() - overwritten,
+            total_size: files.iter().map(|file| file.size).sum(),
+            files,
+            unmatched,
+        }
+    }
 }
 
 /// Backup schedule
@@ -125,6 +277,10 @@ pub struct BackupManager {
     backups_base_dir: PathBuf,
     /// Base directory for servers
     servers_base_dir: PathBuf,
+    /// Where failures are recorded for alerting
+    database: Option<Arc<DatabaseManager>>,
+    /// Runs backups as `backup` jobs when set
+    jobs: Option<Arc<JobManager>>,
 }
 
 impl BackupManager {
@@ -134,15 +290,57 @@ impl BackupManager {
             schedules: Arc::new(RwLock::new(HashMap::new())),
             backups_base_dir,
             servers_base_dir,
+            database: None,
+            jobs: None,
         }
     }
 
+    /// Record failed backups in the event log (`backup_failed`)
+    pub fn with_database(mut self, database: Arc<DatabaseManager>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Run backups through the job manager, so they are listed under `/api/jobs`
+    /// and share its concurrency limits
+    pub fn with_jobs(mut self, jobs: Arc<JobManager>) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
     /// Create a backup for a server
     pub async fn create_backup(
         &self,
         server_id: &str,
         request: CreateBackupRequest,
     ) -> Result<BackupInfo, Box<dyn std::error::Error>> {
+        let backup = self.register_backup(server_id, request).await;
+        let backup_id = backup.id.clone();
+
+        // Perform backup in background
+        match &self.jobs {
+            Some(jobs) => {
+                jobs.spawn(BackupJob {
+                    manager: self.clone(),
+                    server_id: server_id.to_string(),
+                    backup_id,
+                })
+                .await?;
+            }
+            None => {
+                let manager = self.clone();
+                let server_id = server_id.to_string();
+                tokio::spawn(async move {
+                    let _ = manager.run_backup(&server_id, &backup_id).await;
+                });
+            }
+        }
+
+        Ok(backup)
+    }
+
+    /// Record a new backup as `Creating` without starting it
+    async fn register_backup(&self, server_id: &str, request: CreateBackupRequest) -> BackupInfo {
         let backup_id = Uuid::new_v4().to_string();
         let created_at = Utc::now();
 
@@ -159,6 +357,8 @@ impl BackupManager {
             compression: request.compression,
             includes: request.includes,
             metadata: request.metadata,
+            files: Vec::new(),
+            last_verification: None,
         };
 
         // Update status in storage
@@ -168,30 +368,48 @@ impl BackupManager {
             server_backups.push(backup.clone());
         }
 
-        // Perform backup in background
-        let manager = self.clone();
-        let server_id = server_id.to_string();
-        let backup_id = backup_id.clone();
-            tokio::spawn(async move {
-                let (success, error_msg) = {
-                    let result = manager.perform_backup(&server_id, &backup_id).await;
-                    match result {
-                        Ok(_) => (true, None),
-                        Err(e) => (false, Some(format!("{}", e))),
-                    }
+        backup
+    }
+
+    /// Perform a backup and record its outcome as a `backup_completed` or `backup_failed` event
+    async fn run_backup(&self, server_id: &str, backup_id: &str) -> Result<(), String> {
+        let result = self.perform_backup(server_id, backup_id).await.map_err(|e| e.to_string());
+        let Err(msg) = result else {
+            let _ = self.update_backup_status(server_id, backup_id, BackupStatus::Completed).await;
+            if let Some(database) = &self.database {
+                let event = EventLog {
+                    id: Uuid::new_v4().to_string(),
+                    server_id: Some(server_id.to_string()),
+                    event_type: "backup_completed".to_string(),
+                    message: "Backup completed".to_string(),
+                    level: "info".to_string(),
+                    metadata: Some(serde_json::json!({ "backup_id": backup_id })),
+                    created_at: Utc::now(),
                 };
-                
-                if success {
-                    let _ = manager.update_backup_status(&server_id, &backup_id, BackupStatus::Completed).await;
-                } else {
-                    if let Some(msg) = error_msg {
-                        tracing::error!("Backup failed for server {}: {}", server_id, msg);
-                    }
-                    let _ = manager.update_backup_status(&server_id, &backup_id, BackupStatus::Failed).await;
+                if let Err(e) = database.log_event(&event).await {
+                    tracing::error!("Failed to log backup completion for server {}: {}", server_id, e);
                 }
-            });
+            }
+            return Ok(());
+        };
 
-        Ok(backup)
+        tracing::error!("Backup failed for server {}: {}", server_id, msg);
+        if let Some(database) = &self.database {
+            let event = EventLog {
+                id: Uuid::new_v4().to_string(),
+                server_id: Some(server_id.to_string()),
+                event_type: "backup_failed".to_string(),
+                message: format!("Backup failed: {}", msg),
+                level: "error".to_string(),
+                metadata: Some(serde_json::json!({ "backup_id": backup_id })),
+                created_at: Utc::now(),
+            };
+            if let Err(e) = database.log_event(&event).await {
+                tracing::error!("Failed to log backup failure for server {}: {}", server_id, e);
+            }
+        }
+        let _ = self.update_backup_status(server_id, backup_id, BackupStatus::Failed).await;
+        Err(msg)
     }
 
     /// Perform the actual backup operation
@@ -213,41 +431,42 @@ impl BackupManager {
 
         // Add files to backup
         let server_dir = self.servers_base_dir.join(server_id);
+        let mut files = Vec::new();
         
         if backup.includes.world {
-            self.add_directory_to_archive(&mut archive, &server_dir.join("world"), "world")?;
+            self.add_directory_to_archive(&mut archive, &mut files, &server_dir.join("world"), "world")?;
         }
         
         if backup.includes.mods {
-            self.add_directory_to_archive(&mut archive, &server_dir.join("mods"), "mods")?;
+            self.add_directory_to_archive(&mut archive, &mut files, &server_dir.join("mods"), "mods")?;
         }
         
         if backup.includes.config {
-            self.add_directory_to_archive(&mut archive, &server_dir.join("config"), "config")?;
+            self.add_directory_to_archive(&mut archive, &mut files, &server_dir.join("config"), "config")?;
         }
         
         if backup.includes.logs {
-            self.add_directory_to_archive(&mut archive, &server_dir.join("logs"), "logs")?;
+            self.add_directory_to_archive(&mut archive, &mut files, &server_dir.join("logs"), "logs")?;
         }
         
         if backup.includes.server_properties {
-            self.add_file_to_archive(&mut archive, &server_dir.join("server.properties"), "server.properties")?;
+            self.add_file_to_archive(&mut archive, &mut files, &server_dir.join("server.properties"), "server.properties")?;
         }
         
         if backup.includes.whitelist {
-            self.add_file_to_archive(&mut archive, &server_dir.join("whitelist.json"), "whitelist.json")?;
+            self.add_file_to_archive(&mut archive, &mut files, &server_dir.join("whitelist.json"), "whitelist.json")?;
         }
         
         if backup.includes.ops {
-            self.add_file_to_archive(&mut archive, &server_dir.join("ops.json"), "ops.json")?;
+            self.add_file_to_archive(&mut archive, &mut files, &server_dir.join("ops.json"), "ops.json")?;
         }
         
         if backup.includes.banned_players {
-            self.add_file_to_archive(&mut archive, &server_dir.join("banned-players.json"), "banned-players.json")?;
+            self.add_file_to_archive(&mut archive, &mut files, &server_dir.join("banned-players.json"), "banned-players.json")?;
         }
         
         if backup.includes.banned_ips {
-            self.add_file_to_archive(&mut archive, &server_dir.join("banned-ips.json"), "banned-ips.json")?;
+            self.add_file_to_archive(&mut archive, &mut files, &server_dir.join("banned-ips.json"), "banned-ips.json")?;
         }
 
         // Finalize archive
@@ -258,8 +477,13 @@ impl BackupManager {
         let size = metadata.len();
         
         self.update_backup_size(server_id, backup_id, size).await?;
+        self.update_backup_files(server_id, backup_id, files).await?;
         self.update_backup_status(server_id, backup_id, BackupStatus::Completed).await?;
 
+        // Keep the backup's details and checksums next to its archive
+        let backup = self.get_backup(server_id, backup_id).await?;
+        self.save_manifest(&backup).await?;
+
         Ok(())
     }
 
@@ -287,6 +511,7 @@ impl BackupManager {
     fn add_directory_to_archive(
         &self,
         archive: &mut ZipWriter<std::fs::File>,
+        files: &mut Vec<BackupFile>,
         dir_path: &Path,
         archive_path: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
@@ -302,9 +527,9 @@ impl BackupManager {
             let archive_entry_path = format!("{}/{}", archive_path, relative_path.to_string_lossy());
             
             if entry.metadata()?.is_file() {
-                self.add_file_to_archive(archive, &entry_path, &archive_entry_path)?;
+                self.add_file_to_archive(archive, files, &entry_path, &archive_entry_path)?;
             } else if entry.metadata()?.is_dir() {
-                self.add_directory_to_archive(archive, &entry_path, &archive_entry_path)?;
+                self.add_directory_to_archive(archive, files, &entry_path, &archive_entry_path)?;
             }
         }
 
@@ -315,6 +540,7 @@ impl BackupManager {
     fn add_file_to_archive(
         &self,
         archive: &mut ZipWriter<std::fs::File>,
+        files: &mut Vec<BackupFile>,
         file_path: &Path,
         archive_path: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
@@ -322,32 +548,69 @@ impl BackupManager {
             return Ok(());
         }
 
+        // Keep the file's own timestamp so restores can show which copy is newer
+        let mut options = zip::write::FileOptions::default();
+        if let Some(time) = std::fs::metadata(file_path)?.modified().ok().and_then(|time| to_zip_time(time.into())) {
+            options = options.last_modified_time(time);
+        }
+
         let file_data = std::fs::read(file_path)?;
-        archive.start_file(archive_path, zip::write::FileOptions::default())?;
+        archive.start_file(archive_path, options)?;
         archive.write_all(&file_data)?;
+        files.push(BackupFile {
+            path: archive_path.to_string(),
+            size: file_data.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(&file_data)),
+        });
 
         Ok(())
     }
 
-    /// Restore a backup
+    /// Restore a backup, or with `dry_run` only list the files it would write
+    ///
+    /// Restores the whole categories the request selects, or just `paths` when given.
     pub async fn restore_backup(
         &self,
         server_id: &str,
         request: RestoreBackupRequest,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let backup = self.get_backup(server_id, &request.backup_id).await?;
-        
-        if backup.status != BackupStatus::Completed {
-            return Err("Backup is not completed".into());
+    ) -> Result<RestorePlan, Box<dyn std::error::Error>> {
+        let selection = request.selection()?;
+        let archive_path = self.restorable_archive(server_id, &request.backup_id).await?;
+        let server_dir = self.servers_base_dir.join(server_id);
+
+        let files = {
+            let (archive_path, server_dir, selection) = (archive_path.clone(), server_dir.clone(), selection.clone());
+            tokio::task::spawn_blocking(move || plan_restore(&archive_path, &server_dir, &selection)).await??
+        };
+        let plan = RestorePlan::new(&request.backup_id, request.dry_run, files, &selection);
+        if request.dry_run {
+            return Ok(plan);
         }
 
         // Update status to restoring
         self.update_backup_status(server_id, &request.backup_id, BackupStatus::Restoring).await?;
+        let result = self
+            .apply_restore(server_id, &request, &archive_path, &server_dir, &plan)
+            .await
+            .map_err(|e| e.to_string());
+        self.update_backup_status(server_id, &request.backup_id, BackupStatus::Completed).await?;
+        result?;
+
+        Ok(plan)
+    }
 
-        // Create backup before restore if requested
-        if request.create_backup {
+    async fn apply_restore(
+        &self,
+        server_id: &str,
+        request: &RestoreBackupRequest,
+        archive_path: &Path,
+        server_dir: &Path,
+        plan: &RestorePlan,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Back up what is about to be overwritten, and wait for it so the restore can't race it
+        if request.create_backup && plan.overwritten > 0 {
             let pre_restore_backup = CreateBackupRequest {
-                name: format!("Pre-restore backup for {}", backup.name),
+                name: format!("Pre-restore backup for {}", request.backup_id),
                 description: Some("Automatic backup before restore".to_string()),
                 backup_type: BackupType::Automatic,
                 compression: CompressionType::Zip,
@@ -364,64 +627,67 @@ impl BackupManager {
                 },
                 metadata: None,
             };
-            self.create_backup(server_id, pre_restore_backup).await?;
+            let backup = self.register_backup(server_id, pre_restore_backup).await;
+            self.run_backup(server_id, &backup.id).await?;
         }
 
-        // Restore files
-        let backup_dir = self.backups_base_dir.join(server_id).join(&request.backup_id);
-        let archive_path = backup_dir.join(format!("backup.{}", self.get_compression_extension(&backup.compression)));
-        let server_dir = self.servers_base_dir.join(server_id);
-
         // Extract archive
-        self.extract_archive(&archive_path, &server_dir, &request).await?;
-
-        // Update status back to completed
-        self.update_backup_status(server_id, &request.backup_id, BackupStatus::Completed).await?;
-
+        let (archive_path, server_dir, files) = (archive_path.to_path_buf(), server_dir.to_path_buf(), plan.files.clone());
+        tokio::task::spawn_blocking(move || extract_archive(&archive_path, &server_dir, &files)).await??;
         Ok(())
     }
 
-    /// Extract archive
-    async fn extract_archive(
-        &self,
-        archive_path: &Path,
-        target_dir: &Path,
-        request: &RestoreBackupRequest,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        // In a real implementation, this would extract the archive
-        // For now, just create placeholder files
-        async_fs::create_dir_all(target_dir).await?;
-
-        if request.restore_world {
-            async_fs::create_dir_all(target_dir.join("world")).await?;
-        }
-        
-        if request.restore_mods {
-            async_fs::create_dir_all(target_dir.join("mods")).await?;
-        }
-        
-        if request.restore_config {
-            async_fs::create_dir_all(target_dir.join("config")).await?;
+    /// Archive of a completed backup
+    ///
+    /// Backups this manager isn't tracking are found by their `backup.zip` under the
+    /// backups directory, the way [`crate::world::verify::discover_backups`] finds them.
+    async fn restorable_archive(&self, server_id: &str, backup_id: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        if Uuid::parse_str(backup_id).is_err() {
+            return Err(NotFound::new("Backup").into());
         }
-        
-        if request.restore_logs {
-            async_fs::create_dir_all(target_dir.join("logs")).await?;
+        let backup_dir = self.backups_base_dir.join(server_id).join(backup_id);
+        match self.get_backup(server_id, backup_id).await {
+            Ok(backup) if backup.status != BackupStatus::Completed => Err("Backup is not completed".into()),
+            Ok(backup) => Ok(backup_dir.join(format!("backup.{}", self.get_compression_extension(&backup.compression)))),
+            Err(_) => {
+                let archive_path = backup_dir.join("backup.zip");
+                if archive_path.is_file() {
+                    Ok(archive_path)
+                } else {
+                    Err(NotFound::new("Backup").into())
+                }
+            }
         }
-
-        Ok(())
     }
 
-    /// Get backup by ID
+    /// Get backup by ID, from the manifest saved with it when this manager isn't tracking it
     pub async fn get_backup(&self, server_id: &str, backup_id: &str) -> Result<BackupInfo, Box<dyn std::error::Error>> {
-        let backups = self.backups.read().await;
-        if let Some(server_backups) = backups.get(server_id) {
-            server_backups.iter()
-                .find(|b| b.id == backup_id)
-                .cloned()
-                .ok_or_else(|| "Backup not found".into())
-        } else {
-            Err("Server not found".into())
+        {
+            let backups = self.backups.read().await;
+            if let Some(backup) = backups.get(server_id).and_then(|server_backups| server_backups.iter().find(|b| b.id == backup_id)) {
+                return Ok(backup.clone());
+            }
         }
+        self.load_manifest(server_id, backup_id).await.ok_or_else(|| NotFound::new("Backup").into())
+    }
+
+    fn manifest_path(&self, server_id: &str, backup_id: &str) -> PathBuf {
+        self.backups_base_dir.join(server_id).join(backup_id).join("backup.json")
+    }
+
+    async fn load_manifest(&self, server_id: &str, backup_id: &str) -> Option<BackupInfo> {
+        Uuid::parse_str(backup_id).ok()?;
+        let content = async_fs::read(self.manifest_path(server_id, backup_id)).await.ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    /// Write a backup's manifest, replacing the old one only once the new one is complete
+    async fn save_manifest(&self, backup: &BackupInfo) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.manifest_path(&backup.server_id, &backup.id);
+        let partial = path.with_extension("json.partial");
+        async_fs::write(&partial, serde_json::to_vec_pretty(backup)?).await?;
+        async_fs::rename(&partial, &path).await?;
+        Ok(())
     }
 
     /// Update backup status
@@ -440,6 +706,22 @@ impl BackupManager {
         Ok(())
     }
 
+    /// Record the files a finished backup holds
+    async fn update_backup_files(
+        &self,
+        server_id: &str,
+        backup_id: &str,
+        files: Vec<BackupFile>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut backups = self.backups.write().await;
+        if let Some(server_backups) = backups.get_mut(server_id) {
+            if let Some(backup) = server_backups.iter_mut().find(|b| b.id == backup_id) {
+                backup.files = files;
+            }
+        }
+        Ok(())
+    }
+
     /// Update backup size
     async fn update_backup_size(
         &self,
@@ -456,10 +738,139 @@ impl BackupManager {
         Ok(())
     }
 
-    /// Get backups for a server
+    /// Get backups for a server: the ones this manager is tracking and any others with a manifest on disk
     pub async fn get_backups(&self, server_id: &str) -> Result<Vec<BackupInfo>, Box<dyn std::error::Error>> {
-        let backups = self.backups.read().await;
-        Ok(backups.get(server_id).cloned().unwrap_or_default())
+        let mut found = {
+            let backups = self.backups.read().await;
+            backups.get(server_id).cloned().unwrap_or_default()
+        };
+        if let Ok(mut entries) = async_fs::read_dir(self.backups_base_dir.join(server_id)).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let backup_id = entry.file_name().to_string_lossy().to_string();
+                if found.iter().any(|backup| backup.id == backup_id) {
+                    continue;
+                }
+                if let Some(backup) = self.load_manifest(server_id, &backup_id).await {
+                    found.push(backup);
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Check every file in a backup archive against the checksums recorded when it was made
+    pub async fn verify_backup(&self, server_id: &str, backup_id: &str) -> Result<BackupVerification, Box<dyn std::error::Error>> {
+        self.check_backup(server_id, backup_id, false).await
+    }
+
+    /// Extract a backup into a scratch directory and check what was written against its checksums
+    pub async fn test_restore(&self, server_id: &str, backup_id: &str) -> Result<BackupVerification, Box<dyn std::error::Error>> {
+        self.check_backup(server_id, backup_id, true).await
+    }
+
+    async fn check_backup(
+        &self,
+        server_id: &str,
+        backup_id: &str,
+        test_restore: bool,
+    ) -> Result<BackupVerification, Box<dyn std::error::Error>> {
+        let backup = self.get_backup(server_id, backup_id).await?;
+        let archive_path = self.restorable_archive(server_id, backup_id).await?;
+        // Scratch space next to the backups, so the test writes to the same disk a real restore reads from
+        let scratch = if test_restore {
+            async_fs::create_dir_all(&self.backups_base_dir).await?;
+            Some(tempfile::Builder::new().prefix(".test-restore-").tempdir_in(&self.backups_base_dir)?)
+        } else {
+            None
+        };
+
+        let recorded = backup.files.clone();
+        let check = tokio::task::spawn_blocking(move || {
+            check_archive(&archive_path, &recorded, scratch.as_ref().map(|dir| dir.path()))
+        })
+        .await?;
+        let verification = BackupVerification::new(backup_id, test_restore, check);
+
+        if !verification.passed {
+            tracing::error!("Backup {} of server {} failed verification", backup_id, server_id);
+            if let Some(database) = &self.database {
+                let event = EventLog {
+                    id: Uuid::new_v4().to_string(),
+                    server_id: Some(server_id.to_string()),
+                    event_type: "backup_corrupted".to_string(),
+                    message: format!(
+                        "Backup {} failed verification: {} corrupted, {} missing",
+                        backup.name,
+                        verification.corrupted.len(),
+                        verification.missing.len()
+                    ),
+                    level: "error".to_string(),
+                    metadata: serde_json::to_value(&verification).ok(),
+                    created_at: Utc::now(),
+                };
+                if let Err(e) = database.log_event(&event).await {
+                    tracing::error!("Failed to log backup verification for server {}: {}", server_id, e);
+                }
+            }
+        }
+
+        self.record_verification(backup, &verification).await;
+        Ok(verification)
+    }
+
+    /// Keep a verification result with the backup, in memory and in its manifest
+    async fn record_verification(&self, mut backup: BackupInfo, verification: &BackupVerification) {
+        {
+            let mut backups = self.backups.write().await;
+            if let Some(tracked) = backups
+                .get_mut(&backup.server_id)
+                .and_then(|server_backups| server_backups.iter_mut().find(|b| b.id == backup.id))
+            {
+                tracked.last_verification = Some(verification.clone());
+            }
+        }
+        if async_fs::metadata(self.manifest_path(&backup.server_id, &backup.id)).await.is_ok() {
+            backup.last_verification = Some(verification.clone());
+            if let Err(e) = self.save_manifest(&backup).await {
+                tracing::warn!("Failed to save verification of backup {}: {}", backup.id, e);
+            }
+        }
+    }
+
+    /// Test-restore a random completed backup every `every`, so silent corruption
+    /// turns up before the backup is needed
+    pub async fn start_test_restores(self: Arc<Self>, every: Duration) {
+        let mut interval = tokio::time::interval(every);
+        // The first tick is immediate; leave startup alone
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let mut candidates = Vec::new();
+            if let Ok(mut entries) = async_fs::read_dir(&self.backups_base_dir).await {
+                while let Ok(Some(entry)) = entries.next_entry().await {
+                    let server_id = entry.file_name().to_string_lossy().to_string();
+                    if server_id.starts_with('.') {
+                        continue;
+                    }
+                    let backups = self.get_backups(&server_id).await.unwrap_or_default();
+                    candidates.extend(backups.into_iter().filter(|backup| backup.status == BackupStatus::Completed));
+                }
+            }
+            let Some(backup) = candidates.choose(&mut rand::thread_rng()).cloned() else {
+                continue;
+            };
+
+            match self.test_restore(&backup.server_id, &backup.id).await {
+                Ok(verification) if verification.passed => tracing::info!(
+                    "Test-restored backup {} of server {}: {} files intact",
+                    backup.id,
+                    backup.server_id,
+                    verification.files_checked
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to test-restore backup {} of server {}: {}", backup.id, backup.server_id, e),
+            }
+        }
     }
 
     /// Delete backup
@@ -585,6 +996,165 @@ impl BackupManager {
     }
 }
 
+/// Clean up a requested restore path into archive form: relative, `/`-separated,
+/// with no `..` that could reach outside the server directory
+fn normalize_archive_path(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let path = path.replace('\\', "/");
+    let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty() && *part != ".").collect();
+    if path.starts_with('/') || parts.is_empty() || parts.iter().any(|part| *part == ".." || part.contains(':')) {
+        return Err(format!("Invalid restore path: {}", path).into());
+    }
+    Ok(parts.join("/"))
+}
+
+/// Whether an archive path is one of `selection`, or lies in a directory that is
+fn is_selected(path: &str, selection: &[String]) -> bool {
+    selection.iter().any(|selected| {
+        path == selected || path.strip_prefix(selected.as_str()).is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+/// The archive's files under `selection`, with whatever they would replace in `target_dir`
+fn plan_restore(archive_path: &Path, target_dir: &Path, selection: &[String]) -> io::Result<Vec<RestoreEntry>> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(archive_path)?)?;
+    let mut files = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        // Entries that would land outside the target are never restored
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        let path = name.to_string_lossy().replace('\\', "/");
+        if !is_selected(&path, selection) {
+            continue;
+        }
+
+        let current = std::fs::metadata(target_dir.join(&path)).ok().filter(|metadata| metadata.is_file());
+        files.push(RestoreEntry {
+            size: entry.size(),
+            modified: from_zip_time(entry.last_modified()),
+            overwrites: current.is_some(),
+            current_size: current.as_ref().map(|metadata| metadata.len()),
+            current_modified: current.and_then(|metadata| metadata.modified().ok()).map(DateTime::<Utc>::from),
+            path,
+            index,
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Write planned files into `target_dir`, each through a temporary file so an
+/// interrupted restore never leaves a half-written region behind
+fn extract_archive(archive_path: &Path, target_dir: &Path, files: &[RestoreEntry]) -> io::Result<()> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(archive_path)?)?;
+    for file in files {
+        let mut entry = archive.by_index(file.index)?;
+        let destination = target_dir.join(&file.path);
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial = destination.with_file_name(format!(
+            ".{}.restoring",
+            destination.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        let mut out = std::fs::File::create(&partial)?;
+        io::copy(&mut entry, &mut out)?;
+        out.sync_all()?;
+        std::fs::rename(&partial, &destination)?;
+    }
+    Ok(())
+}
+
+/// What reading a backup archive back found
+#[derive(Debug, Default)]
+struct ArchiveCheck {
+    files_checked: usize,
+    corrupted: Vec<String>,
+    missing: Vec<String>,
+}
+
+fn sha256_hex(reader: &mut impl Read) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(reader, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Read every file in an archive, or with `extract_to` write it there and read it back,
+/// and compare it with the checksums recorded at backup time. Decompressing also checks
+/// each entry's CRC, so backups without checksums are still checked for damage.
+fn check_archive(archive_path: &Path, recorded: &[BackupFile], extract_to: Option<&Path>) -> io::Result<ArchiveCheck> {
+    let expected: HashMap<&str, &str> = recorded.iter().map(|file| (file.path.as_str(), file.sha256.as_str())).collect();
+    let mut seen = HashSet::new();
+    let mut check = ArchiveCheck::default();
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(archive_path)?)?;
+
+    for index in 0..archive.len() {
+        let mut entry = match archive.by_index(index) {
+            Ok(entry) => entry,
+            Err(e) => {
+                check.corrupted.push(format!("entry {}: {}", index, e));
+                continue;
+            }
+        };
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        check.files_checked += 1;
+
+        let digest = match extract_to {
+            Some(dir) => {
+                let Some(relative) = entry.enclosed_name().map(Path::to_path_buf) else {
+                    check.corrupted.push(name);
+                    continue;
+                };
+                let destination = dir.join(relative);
+                if let Some(parent) = destination.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let mut out = std::fs::File::create(&destination)?;
+                io::copy(&mut entry, &mut out).and_then(|_| sha256_hex(&mut std::fs::File::open(&destination)?))
+            }
+            None => sha256_hex(&mut entry),
+        };
+        let intact = match (digest, expected.get(name.as_str())) {
+            (Ok(digest), Some(sha256)) => digest == *sha256,
+            (Ok(_), None) => true,
+            (Err(_), _) => false,
+        };
+        if !intact {
+            check.corrupted.push(name.clone());
+        }
+        seen.insert(name);
+    }
+
+    check.missing = recorded.iter().filter(|file| !seen.contains(&file.path)).map(|file| file.path.clone()).collect();
+    Ok(check)
+}
+
+/// Zip timestamps carry no zone; backups write and read them as UTC
+fn to_zip_time(time: DateTime<Utc>) -> Option<zip::DateTime> {
+    zip::DateTime::from_date_and_time(
+        time.year().try_into().ok()?,
+        time.month() as u8,
+        time.day() as u8,
+        time.hour() as u8,
+        time.minute() as u8,
+        time.second() as u8,
+    )
+    .ok()
+}
+
+fn from_zip_time(time: zip::DateTime) -> Option<DateTime<Utc>> {
+    chrono::NaiveDate::from_ymd_opt(time.year() as i32, time.month() as u32, time.day() as u32)?
+        .and_hms_opt(time.hour() as u32, time.minute() as u32, time.second() as u32)
+        .map(|time| time.and_utc())
+}
+
 /// Omit helper type
 
 /// Cleanup result
@@ -601,6 +1171,169 @@ impl Clone for BackupManager {
             schedules: self.schedules.clone(),
             backups_base_dir: self.backups_base_dir.clone(),
             servers_base_dir: self.servers_base_dir.clone(),
+            database: self.database.clone(),
+            jobs: self.jobs.clone(),
         }
     }
 }
+
+/// A backup run as a `backup` job
+struct BackupJob {
+    manager: BackupManager,
+    server_id: String,
+    backup_id: String,
+}
+
+#[async_trait::async_trait]
+impl Job for BackupJob {
+    fn kind(&self) -> &'static str {
+        "backup"
+    }
+
+    fn server_id(&self) -> Option<String> {
+        Some(self.server_id.clone())
+    }
+
+    fn metadata(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({ "backup_id": self.backup_id }))
+    }
+
+    async fn run(self: Box<Self>, _ctx: &JobContext) -> anyhow::Result<Option<String>> {
+        self.manager
+            .run_backup(&self.server_id, &self.backup_id)
+            .await
+            .map_err(|msg| anyhow::anyhow!(msg))?;
+        Ok(Some(format!("Backup {} completed", self.backup_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_archive(path: &Path, files: &[(&str, &[u8])]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut zip = ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, data) in files {
+            zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn request(backup_id: &str, paths: &[&str], dry_run: bool) -> RestoreBackupRequest {
+        RestoreBackupRequest {
+            backup_id: backup_id.to_string(),
+            restore_world: true,
+            restore_mods: false,
+            restore_config: true,
+            restore_logs: false,
+            create_backup: false,
+            dry_run,
+            paths: paths.iter().map(|path| path.to_string()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restore_dry_run_and_selected_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup_id = Uuid::new_v4().to_string();
+        write_archive(
+            &dir.path().join("backups/srv").join(&backup_id).join("backup.zip"),
+            &[
+                ("world/region/r.0.0.mca", b"old region"),
+                ("world/playerdata/abc.dat", b"player"),
+                ("mods/example.jar", b"jar"),
+                ("server.properties", b"motd=old"),
+            ],
+        );
+        let server_dir = dir.path().join("servers/srv");
+        std::fs::create_dir_all(server_dir.join("world/region")).unwrap();
+        std::fs::write(server_dir.join("world/region/r.0.0.mca"), b"new region data").unwrap();
+        let manager = BackupManager::new(dir.path().join("backups"), dir.path().join("servers"));
+
+        // A dry run lists world and config files, flags the one it would replace and writes nothing
+        let plan = manager.restore_backup("srv", request(&backup_id, &[], true)).await.unwrap();
+        let paths: Vec<_> = plan.files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, ["server.properties", "world/playerdata/abc.dat", "world/region/r.0.0.mca"]);
+        assert_eq!((plan.overwritten, plan.created), (1, 2));
+        let region = plan.files.iter().find(|file| file.overwrites).unwrap();
+        assert_eq!((region.size, region.current_size), (10, Some(15)));
+        assert!(region.modified.is_some());
+        assert!(!server_dir.join("server.properties").exists());
+
+        // Selected paths restore only what they name
+        let plan = manager
+            .restore_backup("srv", request(&backup_id, &["world/playerdata/abc.dat", "world/missing"], false))
+            .await
+            .unwrap();
+        assert_eq!(plan.files.len(), 1);
+        assert_eq!(plan.unmatched, ["world/missing"]);
+        assert_eq!(std::fs::read(server_dir.join("world/playerdata/abc.dat")).unwrap(), b"player");
+        assert_eq!(std::fs::read(server_dir.join("world/region/r.0.0.mca")).unwrap(), b"new region data");
+
+        // Whole directories can be selected too
+        let plan = manager.restore_backup("srv", request(&backup_id, &["world/region/"], false)).await.unwrap();
+        assert_eq!(plan.overwritten, 1);
+        assert_eq!(std::fs::read(server_dir.join("world/region/r.0.0.mca")).unwrap(), b"old region");
+    }
+
+    #[tokio::test]
+    async fn test_verify_backup_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        let server_dir = dir.path().join("servers/srv");
+        std::fs::create_dir_all(server_dir.join("world/region")).unwrap();
+        std::fs::write(server_dir.join("world/region/r.0.0.mca"), b"region").unwrap();
+        std::fs::write(server_dir.join("world/level.dat"), b"level").unwrap();
+        let manager = BackupManager::new(dir.path().join("backups"), dir.path().join("servers"));
+
+        let request = CreateBackupRequest {
+            name: "nightly".to_string(),
+            description: None,
+            backup_type: BackupType::Manual,
+            compression: CompressionType::Zip,
+            includes: BackupIncludes { world: true, ..Default::default() },
+            metadata: None,
+        };
+        let backup = manager.register_backup("srv", request).await;
+        manager.run_backup("srv", &backup.id).await.unwrap();
+
+        // A manager that never saw the backup finds it, checksums included, through its manifest
+        let fresh = BackupManager::new(dir.path().join("backups"), dir.path().join("servers"));
+        let stored = fresh.get_backup("srv", &backup.id).await.unwrap();
+        assert_eq!(stored.files.len(), 2);
+        assert_eq!(fresh.get_backups("srv").await.unwrap().len(), 1);
+
+        let verification = fresh.verify_backup("srv", &backup.id).await.unwrap();
+        assert!(verification.passed, "{:?}", verification);
+        assert_eq!(verification.files_checked, 2);
+        let verification = fresh.test_restore("srv", &backup.id).await.unwrap();
+        assert!(verification.passed && verification.test_restore);
+        assert!(fresh.get_backup("srv", &backup.id).await.unwrap().last_verification.is_some());
+
+        // Swap a file's contents and drop another, keeping the archive itself valid
+        let archive = dir.path().join("backups/srv").join(&backup.id).join("backup.zip");
+        write_archive(&archive, &[("world/region/r.0.0.mca", b"damaged")]);
+        let verification = fresh.verify_backup("srv", &backup.id).await.unwrap();
+        assert!(!verification.passed);
+        assert_eq!(verification.corrupted, ["world/region/r.0.0.mca"]);
+        assert_eq!(verification.missing, ["world/level.dat"]);
+
+        std::fs::write(&archive, b"not a zip").unwrap();
+        let verification = fresh.test_restore("srv", &backup.id).await.unwrap();
+        assert!(!verification.passed && verification.error.is_some());
+    }
+
+    #[test]
+    fn test_restore_paths_are_normalized() {
+        assert_eq!(normalize_archive_path("./world\\region//r.0.0.mca").unwrap(), "world/region/r.0.0.mca");
+        assert!(normalize_archive_path("../other/world").is_err());
+        assert!(normalize_archive_path("/etc/passwd").is_err());
+        assert!(normalize_archive_path("C:/world").is_err());
+        assert!(normalize_archive_path("").is_err());
+
+        let selection = vec!["world/region".to_string()];
+        assert!(is_selected("world/region/r.0.0.mca", &selection));
+        assert!(!is_selected("world/region_backup/r.0.0.mca", &selection));
+    }
+}
@@ -0,0 +1,334 @@
+//! Ban lists: `banned-players.json` and `banned-ips.json`. On a running server
+//! bans go through RCON (`ban`, `ban-ip`, `pardon`, `pardon-ip`) so they take
+//! effect at once, then the file is edited to carry the reason, source and
+//! expiry. Temporary bans are also recorded in the database, and the manager's
+//! loop lifts them when they expire.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, EventLog, TemporaryBan};
+use crate::player_lists::{self, PlayerIdentity, PlayerRequest};
+
+const PLAYERS_FILE: &str = "banned-players.json";
+const IPS_FILE: &str = "banned-ips.json";
+/// `expires` of a permanent ban
+const FOREVER: &str = "forever";
+/// Date format of the `created` and `expires` fields
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+const DEFAULT_REASON: &str = "Banned by an operator.";
+const DEFAULT_SOURCE: &str = "Guardian";
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const EVENT_TYPE: &str = "ban_expired";
+const KIND_PLAYER: &str = "player";
+const KIND_IP: &str = "ip";
+
+/// Fields shared by both ban lists
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BanDetails {
+    pub created: String,
+    pub source: String,
+    /// `forever` or a date in the list's date format
+    pub expires: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BannedPlayer {
+    #[serde(flatten)]
+    pub player: PlayerIdentity,
+    #[serde(flatten)]
+    pub details: BanDetails,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BannedIp {
+    pub ip: String,
+    #[serde(flatten)]
+    pub details: BanDetails,
+}
+
+/// Both ban lists of a server and the temporary bans Guardian will lift
+#[derive(Debug, Clone, Serialize)]
+pub struct BanLists {
+    pub players: Vec<BannedPlayer>,
+    pub ips: Vec<BannedIp>,
+    pub temporary: Vec<TemporaryBan>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlayerBanRequest {
+    #[serde(flatten)]
+    pub player: PlayerRequest,
+    pub reason: Option<String>,
+    /// Lift the ban after this many minutes; permanent when absent
+    pub duration_minutes: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IpBanRequest {
+    pub ip: String,
+    pub reason: Option<String>,
+    pub duration_minutes: Option<u32>,
+}
+
+fn details(reason: Option<&str>, source: Option<&str>, expires_at: Option<DateTime<Utc>>) -> BanDetails {
+    BanDetails {
+        created: Utc::now().format(DATE_FORMAT).to_string(),
+        source: source.unwrap_or(DEFAULT_SOURCE).to_string(),
+        expires: expires_at.map_or_else(|| FOREVER.to_string(), |at| at.format(DATE_FORMAT).to_string()),
+        reason: reason.filter(|r| !r.trim().is_empty()).unwrap_or(DEFAULT_REASON).to_string(),
+    }
+}
+
+fn expiry(duration_minutes: Option<u32>) -> Result<Option<DateTime<Utc>>> {
+    match duration_minutes {
+        Some(0) => bail!("Ban duration must be at least one minute"),
+        Some(minutes) => Ok(Some(Utc::now() + chrono::Duration::minutes(minutes as i64))),
+        None => Ok(None),
+    }
+}
+
+fn parse_ip(ip: &str) -> Result<String> {
+    ip.trim()
+        .parse::<IpAddr>()
+        .map(|ip| ip.to_string())
+        .map_err(|_| anyhow!("Invalid IP address '{}'", ip))
+}
+
+/// Ban command with its optional reason
+fn command(verb: &str, target: &str, reason: Option<&str>) -> String {
+    match reason.map(str::trim).filter(|r| !r.is_empty()) {
+        Some(reason) => format!("{} {} {}", verb, target, reason),
+        None => format!("{} {}", verb, target),
+    }
+}
+
+pub struct BanManager {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+}
+
+impl BanManager {
+    pub fn new(database: Arc<DatabaseManager>, process_manager: Arc<ProcessManager>) -> Self {
+        Self { database, process_manager }
+    }
+
+    async fn running(&self, server_id: &str) -> bool {
+        match Uuid::parse_str(server_id) {
+            Ok(server_id) => self.process_manager.is_server_running(server_id).await,
+            Err(_) => false,
+        }
+    }
+
+    pub async fn list(&self, server_id: &str) -> Result<BanLists> {
+        let server = self.database.require_server(server_id).await?;
+        Ok(BanLists {
+            players: player_lists::load(&server, PLAYERS_FILE).await?,
+            ips: player_lists::load(&server, IPS_FILE).await?,
+            temporary: self.database.get_active_temporary_bans(Some(server_id)).await?,
+        })
+    }
+
+    /// Temporary bans of `kind` matching `is_target` are superseded by a new ban or an unban
+    async fn lift_temporary(&self, server_id: &str, kind: &str, is_target: impl Fn(&TemporaryBan) -> bool) -> Result<()> {
+        for ban in self.database.get_active_temporary_bans(Some(server_id)).await? {
+            if ban.kind == kind && is_target(&ban) {
+                self.database.mark_temporary_ban_lifted(&ban.id, Utc::now()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn record_temporary(&self, server_id: &str, kind: &str, target: &str, uuid: Option<String>, details: &BanDetails, expires_at: DateTime<Utc>) -> Result<()> {
+        let ban = TemporaryBan {
+            id: Uuid::new_v4().to_string(),
+            server_id: server_id.to_string(),
+            kind: kind.to_string(),
+            target: target.to_string(),
+            uuid,
+            reason: Some(details.reason.clone()),
+            source: Some(details.source.clone()),
+            expires_at,
+            lifted_at: None,
+            created_at: Utc::now(),
+        };
+        self.database.create_temporary_ban(&ban).await
+    }
+
+    /// Ban a player, replacing an existing ban of theirs. Returns the updated list.
+    pub async fn ban_player(&self, server_id: &str, request: &PlayerBanRequest, source: Option<&str>) -> Result<Vec<BannedPlayer>> {
+        let server = self.database.require_server(server_id).await?;
+        let expires_at = expiry(request.duration_minutes)?;
+        let player = player_lists::resolve(&request.player).await?;
+        if self.running(server_id).await {
+            player_lists::apply_live(&server, command("ban", &player.name, request.reason.as_deref())).await;
+        }
+
+        let details = details(request.reason.as_deref(), source, expires_at);
+        let mut entries: Vec<BannedPlayer> = player_lists::load(&server, PLAYERS_FILE).await?;
+        entries.retain(|entry| entry.player.uuid != player.uuid);
+        entries.push(BannedPlayer { player: player.clone(), details: details.clone() });
+        player_lists::save(&server, PLAYERS_FILE, &entries).await?;
+        info!("Banned {} ({}) on server {} until {}", player.name, player.uuid, server_id, details.expires);
+
+        self.lift_temporary(server_id, KIND_PLAYER, |ban| ban.uuid.as_deref() == Some(player.uuid.as_str())).await?;
+        if let Some(expires_at) = expires_at {
+            self.record_temporary(server_id, KIND_PLAYER, &player.name, Some(player.uuid.clone()), &details, expires_at).await?;
+        }
+        Ok(entries)
+    }
+
+    /// Ban an IP address, replacing an existing ban of it. Returns the updated list.
+    pub async fn ban_ip(&self, server_id: &str, request: &IpBanRequest, source: Option<&str>) -> Result<Vec<BannedIp>> {
+        let server = self.database.require_server(server_id).await?;
+        let expires_at = expiry(request.duration_minutes)?;
+        let ip = parse_ip(&request.ip)?;
+        if self.running(server_id).await {
+            player_lists::apply_live(&server, command("ban-ip", &ip, request.reason.as_deref())).await;
+        }
+
+        let details = details(request.reason.as_deref(), source, expires_at);
+        let mut entries: Vec<BannedIp> = player_lists::load(&server, IPS_FILE).await?;
+        entries.retain(|entry| entry.ip != ip);
+        entries.push(BannedIp { ip: ip.clone(), details: details.clone() });
+        player_lists::save(&server, IPS_FILE, &entries).await?;
+        info!("Banned IP {} on server {} until {}", ip, server_id, details.expires);
+
+        self.lift_temporary(server_id, KIND_IP, |ban| ban.target == ip).await?;
+        if let Some(expires_at) = expires_at {
+            self.record_temporary(server_id, KIND_IP, &ip, None, &details, expires_at).await?;
+        }
+        Ok(entries)
+    }
+
+    /// Pardon a player by name or UUID; `None` when they weren't banned
+    pub async fn unban_player(&self, server_id: &str, name_or_uuid: &str) -> Result<Option<Vec<BannedPlayer>>> {
+        let server = self.database.require_server(server_id).await?;
+        let entries: Vec<BannedPlayer> = player_lists::load(&server, PLAYERS_FILE).await?;
+        let Some(banned) = entries.into_iter().find(|entry| player_lists::matches(&entry.player, name_or_uuid)) else {
+            return Ok(None);
+        };
+        if self.running(server_id).await {
+            player_lists::apply_live(&server, format!("pardon {}", banned.player.name)).await;
+        }
+        // `pardon` rewrote the file
+        let mut entries: Vec<BannedPlayer> = player_lists::load(&server, PLAYERS_FILE).await?;
+        entries.retain(|entry| entry.player.uuid != banned.player.uuid);
+        player_lists::save(&server, PLAYERS_FILE, &entries).await?;
+        info!("Unbanned {} on server {}", banned.player.name, server_id);
+
+        self.lift_temporary(server_id, KIND_PLAYER, |ban| ban.uuid.as_deref() == Some(banned.player.uuid.as_str())).await?;
+        Ok(Some(entries))
+    }
+
+    /// Pardon an IP address; `None` when it wasn't banned
+    pub async fn unban_ip(&self, server_id: &str, ip: &str) -> Result<Option<Vec<BannedIp>>> {
+        let server = self.database.require_server(server_id).await?;
+        let ip = parse_ip(ip)?;
+        let entries: Vec<BannedIp> = player_lists::load(&server, IPS_FILE).await?;
+        if !entries.iter().any(|entry| entry.ip == ip) {
+            return Ok(None);
+        }
+        if self.running(server_id).await {
+            player_lists::apply_live(&server, format!("pardon-ip {}", ip)).await;
+        }
+        let mut entries: Vec<BannedIp> = player_lists::load(&server, IPS_FILE).await?;
+        entries.retain(|entry| entry.ip != ip);
+        player_lists::save(&server, IPS_FILE, &entries).await?;
+        info!("Unbanned IP {} on server {}", ip, server_id);
+
+        self.lift_temporary(server_id, KIND_IP, |ban| ban.target == ip).await?;
+        Ok(Some(entries))
+    }
+
+    /// Check for expired temporary bans every few seconds and lift them
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting ban expiry checks");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.lift_expired().await {
+                error!("Ban expiry check failed: {}", e);
+            }
+        }
+    }
+
+    async fn lift_expired(&self) -> Result<()> {
+        let now = Utc::now();
+        for ban in self.database.get_active_temporary_bans(None).await? {
+            if ban.expires_at > now {
+                break;
+            }
+            let result = match ban.kind.as_str() {
+                KIND_IP => self.unban_ip(&ban.server_id, &ban.target).await.map(|_| ()),
+                _ => self.unban_player(&ban.server_id, ban.uuid.as_deref().unwrap_or(&ban.target)).await.map(|_| ()),
+            };
+            // Unbanning lifts the ban in the database; this covers bans already
+            // removed from the file and servers that no longer exist
+            self.database.mark_temporary_ban_lifted(&ban.id, now).await?;
+
+            let (level, message) = match &result {
+                Ok(()) => ("info", format!("Temporary ban of {} expired", ban.target)),
+                Err(e) => ("error", format!("Failed to lift expired ban of {}: {}", ban.target, e)),
+            };
+            match &result {
+                Err(_) => error!("{} (server {})", message, ban.server_id),
+                Ok(()) => info!("{} (server {})", message, ban.server_id),
+            }
+            let event = EventLog {
+                id: Uuid::new_v4().to_string(),
+                server_id: Some(ban.server_id.clone()),
+                event_type: EVENT_TYPE.to_string(),
+                message,
+                level: level.to_string(),
+                metadata: Some(serde_json::json!({
+                    "ban_id": ban.id,
+                    "kind": ban.kind,
+                    "target": ban.target,
+                    "expires_at": ban.expires_at,
+                })),
+                created_at: Utc::now(),
+            };
+            if let Err(e) = self.database.log_event(&event).await {
+                error!("Failed to log expired ban on server {}: {}", ban.server_id, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ban_list_format() {
+        let json = r#"[{"uuid":"8667ba71-b85a-4004-af54-457a9734eed7","name":"Steve","created":"2024-01-01 12:00:00 +0000","source":"Server","expires":"forever","reason":"Griefing"}]"#;
+        let players: Vec<BannedPlayer> = serde_json::from_str(json).unwrap();
+        assert_eq!(players[0].player.name, "Steve");
+        assert_eq!(players[0].details.reason, "Griefing");
+        assert_eq!(serde_json::to_string(&players).unwrap(), json);
+    }
+
+    #[test]
+    fn test_details_and_commands() {
+        let expires_at = DateTime::parse_from_rfc3339("2024-06-01T08:30:00Z").unwrap().with_timezone(&Utc);
+        let details = details(Some(" "), None, Some(expires_at));
+        assert_eq!(details.expires, "2024-06-01 08:30:00 +0000");
+        assert_eq!(details.reason, DEFAULT_REASON);
+        assert_eq!(details.source, DEFAULT_SOURCE);
+        assert_eq!(command("ban", "Steve", Some("Griefing spawn")), "ban Steve Griefing spawn");
+        assert_eq!(command("ban-ip", "10.0.0.1", None), "ban-ip 10.0.0.1");
+        assert_eq!(parse_ip(" 10.0.0.1 ").unwrap(), "10.0.0.1");
+        assert!(parse_ip("10.0.0").is_err());
+        assert!(expiry(Some(0)).is_err());
+    }
+}
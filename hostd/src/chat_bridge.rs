@@ -0,0 +1,348 @@
+//! In-game chat bridge
+//!
+//! Relays a server's chat to external channels and back. Chat lines are
+//! picked out of the console stream and posted, formatted with the bridge's
+//! outbound template, to a Discord channel through the bot and/or to a
+//! webhook URL. Messages posted in the Discord channel, and ones sent to
+//! `/api/servers/:id/chat`, are formatted with the inbound template and
+//! shown in game with `tellraw` over RCON. Each server has its own bridge.
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+
+use crate::database::{ChatBridge, DatabaseManager};
+use crate::discord::DiscordManager;
+use crate::event_bus::EventBus;
+use crate::websocket_manager::WebSocketMessage;
+use crate::core::error_handler::NotFound;
+
+pub const DEFAULT_OUTBOUND_TEMPLATE: &str = "**{player}**: {message}";
+pub const DEFAULT_INBOUND_TEMPLATE: &str = "[{source}] <{author}> {message}";
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest message shown in game; the rest is cut off
+const MAX_INBOUND_LENGTH: usize = 256;
+const MAX_TEMPLATE_LENGTH: usize = 500;
+
+/// Changes to a bridge; an empty channel ID or URL clears it
+#[derive(Debug, Default, Deserialize)]
+pub struct ChatBridgeUpdate {
+    pub enabled: Option<bool>,
+    pub discord_channel_id: Option<String>,
+    pub webhook_url: Option<String>,
+    pub relay_inbound: Option<bool>,
+    pub outbound_template: Option<String>,
+    pub inbound_template: Option<String>,
+}
+
+/// A message sent into the game from elsewhere
+#[derive(Debug, Deserialize)]
+pub struct ExternalMessage {
+    /// Defaults to the signed-in user
+    pub author: Option<String>,
+    pub message: String,
+    /// Where the message came from, "Web" by default
+    pub source: Option<String>,
+}
+
+/// The player and message of a chat line, e.g.
+/// `[12:00:00] [Server thread/INFO]: <Steve> hello`
+pub fn chat_message(line: &str) -> Option<(&str, &str)> {
+    let (_, message) = line.split_once("]: ")?;
+    let message = message.strip_prefix("[Not Secure] ").unwrap_or(message);
+    let (player, text) = message.strip_prefix('<')?.split_once("> ")?;
+    let valid = (1..=16).contains(&player.len())
+        && player.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'.');
+    let text = text.trim_end();
+    (valid && !text.is_empty()).then_some((player, text))
+}
+
+/// Fill `{name}` placeholders in a template
+pub fn render(template: &str, values: &[(&str, &str)]) -> String {
+    values.iter().fold(template.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+/// The RCON command showing `text` to everyone online
+pub fn tellraw(text: &str) -> String {
+    format!("tellraw @a {}", serde_json::json!({ "text": sanitize(text) }))
+}
+
+/// One line of at most `MAX_INBOUND_LENGTH` characters, without control characters
+fn sanitize(text: &str) -> String {
+    let line: String = text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+    line.trim().chars().take(MAX_INBOUND_LENGTH).collect()
+}
+
+fn is_discord_webhook(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| {
+        matches!(url.host_str(), Some("discord.com" | "discordapp.com" | "ptb.discord.com" | "canary.discord.com"))
+            && url.path().starts_with("/api/webhooks/")
+    })
+}
+
+fn validate(bridge: &ChatBridge) -> Result<()> {
+    if let Some(channel_id) = &bridge.discord_channel_id {
+        if !crate::discord::is_snowflake(channel_id) {
+            bail!("discord_channel_id must be a Discord ID");
+        }
+    }
+    if let Some(url) = &bridge.webhook_url {
+        crate::notifiers::validate_url(url)?;
+    }
+    for (field, template) in [("outbound_template", &bridge.outbound_template), ("inbound_template", &bridge.inbound_template)] {
+        if !template.contains("{message}") {
+            bail!("{} must contain {{message}}", field);
+        }
+        if template.len() > MAX_TEMPLATE_LENGTH {
+            bail!("{} must be at most {} characters", field, MAX_TEMPLATE_LENGTH);
+        }
+    }
+    if bridge.enabled && bridge.discord_channel_id.is_none() && bridge.webhook_url.is_none() {
+        bail!("A Discord channel or webhook URL is required");
+    }
+    Ok(())
+}
+
+pub struct ChatBridgeManager {
+    database: Arc<DatabaseManager>,
+    discord: Arc<DiscordManager>,
+    event_bus: EventBus,
+    client: reqwest::Client,
+    /// Last Discord message read from each channel
+    last_read: RwLock<HashMap<String, String>>,
+}
+
+impl ChatBridgeManager {
+    pub fn new(database: Arc<DatabaseManager>, discord: Arc<DiscordManager>, event_bus: EventBus) -> Self {
+        Self {
+            database,
+            discord,
+            event_bus,
+            client: reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+            last_read: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// A server's bridge, or a disabled one with the default templates
+    pub async fn get(&self, server_id: &str) -> Result<ChatBridge> {
+        Ok(self.database.get_chat_bridge(server_id).await?.unwrap_or_else(|| ChatBridge {
+            server_id: server_id.to_string(),
+            enabled: false,
+            discord_channel_id: None,
+            webhook_url: None,
+            relay_inbound: true,
+            outbound_template: DEFAULT_OUTBOUND_TEMPLATE.to_string(),
+            inbound_template: DEFAULT_INBOUND_TEMPLATE.to_string(),
+            updated_at: None,
+        }))
+    }
+
+    pub async fn update(&self, server_id: &str, update: ChatBridgeUpdate) -> Result<ChatBridge> {
+        let mut bridge = self.get(server_id).await?;
+        let optional = |value: String| Some(value.trim().to_string()).filter(|value| !value.is_empty());
+        if let Some(enabled) = update.enabled {
+            bridge.enabled = enabled;
+        }
+        if let Some(channel_id) = update.discord_channel_id {
+            bridge.discord_channel_id = optional(channel_id);
+        }
+        if let Some(url) = update.webhook_url {
+            bridge.webhook_url = optional(url);
+        }
+        if let Some(relay_inbound) = update.relay_inbound {
+            bridge.relay_inbound = relay_inbound;
+        }
+        if let Some(template) = update.outbound_template {
+            bridge.outbound_template = template;
+        }
+        if let Some(template) = update.inbound_template {
+            bridge.inbound_template = template;
+        }
+        validate(&bridge)?;
+        bridge.updated_at = Some(Utc::now());
+        self.database.save_chat_bridge(&bridge).await?;
+        Ok(bridge)
+    }
+
+    /// Show a message from `source` in game, formatted with the inbound template
+    pub async fn send_inbound(&self, server_id: &str, source: &str, author: &str, message: &str) -> Result<()> {
+        let bridge = self.get(server_id).await?;
+        if !bridge.enabled {
+            bail!("The chat bridge is not enabled for this server");
+        }
+        let Some(server) = self.database.get_server(server_id).await? else {
+            return Err(NotFound::with_id("Server", server_id).into());
+        };
+        let text = render(&bridge.inbound_template, &[("source", source), ("author", author), ("message", message)]);
+        crate::restart_scheduler::rcon(&server, tellraw(&text)).await?;
+        Ok(())
+    }
+
+    /// Relay chat from the console stream, and Discord messages into the game
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting chat bridge");
+        tokio::spawn(self.clone().poll_discord());
+        let mut messages = self.event_bus.subscribe();
+        loop {
+            match messages.recv().await {
+                Ok(WebSocketMessage::ConsoleMessage { server_id, message, .. }) => {
+                    let Some((player, text)) = chat_message(&message) else {
+                        continue;
+                    };
+                    if let Err(e) = self.relay_outbound(&server_id, player, text).await {
+                        warn!("Failed to relay chat from server {}: {}", server_id, e);
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => warn!("Chat bridge skipped {} console messages", skipped),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    async fn relay_outbound(&self, server_id: &str, player: &str, message: &str) -> Result<()> {
+        let bridge = self.get(server_id).await?;
+        if !bridge.enabled {
+            return Ok(());
+        }
+        let server_name = match self.database.get_server(server_id).await? {
+            Some(server) => server.name,
+            None => server_id.to_string(),
+        };
+        let text = render(
+            &bridge.outbound_template,
+            &[("server", &server_name), ("player", &crate::discord::escape(player)), ("message", message)],
+        );
+        // Chat must not ping @everyone or anyone else
+        let no_mentions = serde_json::json!({ "parse": [] });
+
+        if let Some(channel_id) = &bridge.discord_channel_id {
+            let body = serde_json::json!({ "content": text, "allowed_mentions": no_mentions });
+            self.discord.send_message(channel_id, &body).await?;
+        }
+        if let Some(url) = &bridge.webhook_url {
+            let body = if is_discord_webhook(url) {
+                serde_json::json!({ "username": player, "content": text, "allowed_mentions": no_mentions })
+            } else {
+                serde_json::json!({
+                    "server_id": server_id,
+                    "server_name": server_name,
+                    "player": player,
+                    "message": message,
+                    "text": text,
+                })
+            };
+            let response = self.client.post(url).json(&body).send().await?;
+            if !response.status().is_success() {
+                bail!("{} returned {}", url, response.status());
+            }
+        }
+        Ok(())
+    }
+
+    async fn poll_discord(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let bridges = match self.database.get_enabled_chat_bridges().await {
+                Ok(bridges) => bridges,
+                Err(e) => {
+                    error!("Failed to load chat bridges: {}", e);
+                    continue;
+                }
+            };
+            for bridge in bridges {
+                let Some(channel_id) = bridge.discord_channel_id.as_deref().filter(|_| bridge.relay_inbound) else {
+                    continue;
+                };
+                if let Err(e) = self.relay_inbound(&bridge, channel_id).await {
+                    warn!("Failed to relay Discord chat to server {}: {}", bridge.server_id, e);
+                }
+            }
+        }
+    }
+
+    async fn relay_inbound(&self, bridge: &ChatBridge, channel_id: &str) -> Result<()> {
+        let after = self.last_read.read().await.get(channel_id).cloned();
+        let messages = self.discord.messages_after(channel_id, after.as_deref()).await?;
+        let Some(last) = messages.last() else {
+            return Ok(());
+        };
+        self.last_read.write().await.insert(channel_id.to_string(), last.id.clone());
+        // The first read only finds where to start
+        if after.is_none() {
+            return Ok(());
+        }
+        for message in &messages {
+            // Skip the bridge's own posts and other bots
+            if message.author.bot || message.webhook_id.is_some() || message.content.trim().is_empty() {
+                continue;
+            }
+            let author = message.author.global_name.as_deref().unwrap_or(&message.author.username);
+            self.send_inbound(&bridge.server_id, "Discord", author, &message.content).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_message_parsing() {
+        assert_eq!(chat_message("[12:00:00] [Server thread/INFO]: <Steve> hello there"), Some(("Steve", "hello there")));
+        assert_eq!(
+            chat_message("[12:00:00] [Server thread/INFO]: [Not Secure] <Alex_2> a]: b"),
+            Some(("Alex_2", "a]: b"))
+        );
+        assert_eq!(chat_message("[12:00:00] [Server thread/INFO]: Steve joined the game"), None);
+        assert_eq!(chat_message("[12:00:00] [Server thread/INFO]: [Server] hi"), None);
+        assert_eq!(chat_message("[12:00:00] [Server thread/INFO]: <not a player> hi"), None);
+        assert_eq!(chat_message("<Steve> no prefix"), None);
+    }
+
+    #[test]
+    fn test_templates_and_tellraw() {
+        let text = render(DEFAULT_INBOUND_TEMPLATE, &[("source", "Discord"), ("author", "bob"), ("message", "hi")]);
+        assert_eq!(text, "[Discord] <bob> hi");
+        assert_eq!(render("{player} {missing}", &[("player", "Steve")]), "Steve {missing}");
+
+        let command = tellraw("line one\nline \"two\"");
+        assert_eq!(command, r#"tellraw @a {"text":"line one line \"two\""}"#);
+        assert_eq!(sanitize(&"x".repeat(1000)).len(), MAX_INBOUND_LENGTH);
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut bridge = ChatBridge {
+            server_id: "s1".to_string(),
+            enabled: true,
+            discord_channel_id: None,
+            webhook_url: None,
+            relay_inbound: true,
+            outbound_template: DEFAULT_OUTBOUND_TEMPLATE.to_string(),
+            inbound_template: DEFAULT_INBOUND_TEMPLATE.to_string(),
+            updated_at: None,
+        };
+        assert!(validate(&bridge).is_err());
+        bridge.webhook_url = Some("https://discord.com/api/webhooks/1/abc".to_string());
+        assert!(validate(&bridge).is_ok());
+        assert!(is_discord_webhook(bridge.webhook_url.as_deref().unwrap()));
+        assert!(!is_discord_webhook("https://example.com/api/webhooks/1"));
+        bridge.discord_channel_id = Some("general".to_string());
+        assert!(validate(&bridge).is_err());
+        bridge.discord_channel_id = Some("123456789012345678".to_string());
+        bridge.inbound_template = "<{author}>".to_string();
+        assert!(validate(&bridge).is_err());
+    }
+}
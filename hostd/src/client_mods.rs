@@ -0,0 +1,316 @@
+//! Client-only mods
+//!
+//! Shader loaders, minimaps and other client-only mods crash a dedicated
+//! server, or are silently skipped by it. They are recognised from provider
+//! metadata (Modrinth marks them unsupported on servers) and from their jars
+//! (see [`JarMod::client_only`]). Applying mods to a server moves them out of
+//! `mods/` into `client-mods/`, where they stay available to hand to players.
+//!
+//! Installing a shader loader, a replacement renderer or another client-only
+//! mod onto a server is refused up front with a [`ClientModWarning`]; the mod
+//! can go into the server's client pack instead, a modpack of the mods
+//! players should install alongside the server.
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::database::{DatabaseManager, Modpack, ServerConfig};
+use crate::external_apis::curseforge::CurseForgeApiClient;
+use crate::external_apis::modrinth::ModrinthApiClient;
+use crate::mod_metadata::{self, JarMod};
+
+/// Folder in the server directory client-only mods are moved to
+pub const CLIENT_MODS_DIR: &str = "client-mods";
+
+/// The side a mod runs on from Modrinth's `client_side` and `server_side`
+/// (required, optional or unsupported): client, server or both
+pub fn provider_side(client_side: &str, server_side: &str) -> &'static str {
+    match (client_side, server_side) {
+        (_, "unsupported") => "client",
+        ("unsupported", _) => "server",
+        _ => "both",
+    }
+}
+
+/// Key in a modpack's config naming the server it is the client pack of
+const CLIENT_PACK_KEY: &str = "client_pack_for";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientModKind {
+    /// Iris, Oculus, OptiFine and the like
+    ShaderLoader,
+    /// Sodium, its ports and its addons
+    Renderer,
+    /// Anything else its provider marks unsupported on servers
+    ClientOnly,
+}
+
+/// Well-known shader loaders and renderers by Modrinth or CurseForge slug,
+/// which is also the mod ID in their jars
+const KNOWN_CLIENT_MODS: &[(&str, ClientModKind)] = &[
+    ("iris", ClientModKind::ShaderLoader),
+    ("irisshaders", ClientModKind::ShaderLoader),
+    ("oculus", ClientModKind::ShaderLoader),
+    ("optifine", ClientModKind::ShaderLoader),
+    ("optifabric", ClientModKind::ShaderLoader),
+    ("sodium", ClientModKind::Renderer),
+    ("embeddium", ClientModKind::Renderer),
+    ("rubidium", ClientModKind::Renderer),
+    ("magnesium", ClientModKind::Renderer),
+    ("nvidium", ClientModKind::Renderer),
+    ("vulkanmod", ClientModKind::Renderer),
+    ("canvas", ClientModKind::Renderer),
+    ("indium", ClientModKind::Renderer),
+    ("sodium-extra", ClientModKind::Renderer),
+    ("reeses-sodium-options", ClientModKind::Renderer),
+];
+
+/// What kind of client mod a project is, from its slug or name and the side
+/// its provider says it runs on
+pub fn client_mod_kind(slug: &str, name: &str, side: Option<&str>) -> Option<ClientModKind> {
+    let known = |key: &str| {
+        let key = key.trim().to_ascii_lowercase();
+        KNOWN_CLIENT_MODS.iter().find(|(known, _)| *known == key).map(|(_, kind)| *kind)
+    };
+    known(slug)
+        .or_else(|| known(name))
+        .or_else(|| (side == Some("client")).then_some(ClientModKind::ClientOnly))
+}
+
+/// A mod that wasn't installed because it only runs on the client
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientModWarning {
+    pub mod_id: String,
+    pub provider: String,
+    pub name: String,
+    pub kind: ClientModKind,
+    pub message: String,
+    /// Adding the mod to the server's client pack, as
+    /// `POST /api/servers/:id/mods/client-pack` does, is offered instead
+    pub add_to_client_pack: bool,
+}
+
+impl ClientModWarning {
+    fn new(mod_id: &str, provider: &str, name: &str, kind: ClientModKind) -> Self {
+        let message = match kind {
+            ClientModKind::ShaderLoader => format!("{} loads shader packs, which only the game client renders", name),
+            ClientModKind::Renderer => format!("{} replaces the game client's renderer; a server renders nothing", name),
+            ClientModKind::ClientOnly => format!("{} is marked unsupported on servers by its provider", name),
+        };
+        Self {
+            mod_id: mod_id.to_string(),
+            provider: provider.to_string(),
+            name: name.to_string(),
+            kind,
+            message: format!("{}. Add it to the server's client pack for players instead.", message),
+            add_to_client_pack: true,
+        }
+    }
+}
+
+/// Look a mod up on its provider and warn if it doesn't belong on a server.
+/// CurseForge mods are only checked with an API key.
+pub async fn check_install(provider: &str, mod_id: &str, cf_api_key: Option<&str>) -> Result<Option<ClientModWarning>> {
+    let (slug, name, side) = match provider.to_ascii_lowercase().as_str() {
+        "modrinth" => {
+            let project = ModrinthApiClient::new().get_project(mod_id).await?;
+            let side = provider_side(&project.client_side, &project.server_side);
+            (project.slug, project.title, Some(side))
+        }
+        "curseforge" => {
+            let Some(api_key) = cf_api_key else { return Ok(None) };
+            let id = mod_id.parse().map_err(|_| anyhow!("Invalid CurseForge project ID '{}'", mod_id))?;
+            let project = CurseForgeApiClient::new(api_key.to_string()).get_project(id).await?;
+            (project.slug, project.name, None)
+        }
+        _ => return Ok(None),
+    };
+    Ok(client_mod_kind(&slug, &name, side).map(|kind| ClientModWarning::new(mod_id, provider, &name, kind)))
+}
+
+/// The modpack holding the mods players of `server_id` install themselves
+pub async fn client_pack(database: &DatabaseManager, server_id: &str) -> Result<Option<Modpack>> {
+    Ok(database.get_modpacks().await?.into_iter().find(|modpack| {
+        modpack
+            .config
+            .as_deref()
+            .and_then(|config| serde_json::from_str::<serde_json::Value>(config).ok())
+            .is_some_and(|config| config[CLIENT_PACK_KEY] == server_id)
+    }))
+}
+
+/// Add mods to the client pack of `server`, creating the pack the first time.
+/// The pack downloads and applies like any other modpack.
+pub async fn add_to_client_pack(database: &DatabaseManager, server: &ServerConfig, mod_ids: &[String]) -> Result<Modpack> {
+    let existing = client_pack(database, &server.id).await?;
+    let mut modpack = existing.clone().unwrap_or_else(|| Modpack {
+        id: Uuid::new_v4().to_string(),
+        name: format!("{} client pack", server.name),
+        description: Some(format!("Client-side mods for players of {}", server.name)),
+        minecraft_version: server.minecraft_version.clone(),
+        loader: server.loader.clone(),
+        client_mods: "[]".to_string(),
+        server_mods: "[]".to_string(),
+        config: Some(serde_json::json!({ CLIENT_PACK_KEY: server.id }).to_string()),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    });
+
+    let mut client_mods: Vec<String> = serde_json::from_str(&modpack.client_mods).unwrap_or_default();
+    for mod_id in mod_ids {
+        if !client_mods.contains(mod_id) {
+            client_mods.push(mod_id.clone());
+        }
+    }
+    modpack.client_mods = serde_json::to_string(&client_mods)?;
+    modpack.updated_at = Utc::now();
+
+    if existing.is_some() {
+        database.update_modpack(&modpack).await?;
+    } else {
+        database.create_modpack(&modpack).await?;
+        info!("Created client pack {} for {}", modpack.id, server.name);
+    }
+    Ok(modpack)
+}
+
+/// A jar moved out of the mods folder
+#[derive(Debug, Clone, Serialize)]
+pub struct QuarantinedMod {
+    pub file: String,
+    pub mod_ids: Vec<String>,
+    pub reason: String,
+}
+
+/// Why the jar's mods don't belong on a server, if they don't. `provider_client`
+/// holds the mod IDs and file names providers report as client-only; a jar
+/// that declares it runs on servers overrides them. Jars named in `keep`, by
+/// file name or mod ID, were asked for on the server and always stay.
+fn client_only_reason(
+    file: &str,
+    mods: &[JarMod],
+    provider_client: &HashSet<String>,
+    keep: &HashSet<String>,
+) -> Option<String> {
+    if keep.contains(file) || mods.iter().any(|m| keep.contains(&m.mod_id)) {
+        return None;
+    }
+    if let Some(jar_mod) = mods.iter().find(|m| m.client_only) {
+        return Some(match jar_mod.environment.as_deref() {
+            Some("client") => format!("{} declares it only runs on the client", jar_mod.name),
+            _ => format!("{} only has client entrypoints", jar_mod.name),
+        });
+    }
+    let declares_server = mods.iter().any(|m| matches!(m.environment.as_deref(), Some("server" | "both")));
+    let reported = provider_client.contains(file) || mods.iter().any(|m| provider_client.contains(&m.mod_id));
+    (reported && !declares_server).then(|| "The mod's provider marks it client-only".to_string())
+}
+
+/// Move the client-only jars in `<server_dir>/mods` to `<server_dir>/client-mods`,
+/// except those in `keep`
+pub async fn quarantine(
+    server_dir: &Path,
+    provider_client: &HashSet<String>,
+    keep: &HashSet<String>,
+) -> Result<Vec<QuarantinedMod>> {
+    let server_dir = server_dir.to_path_buf();
+    let provider_client = provider_client.clone();
+    let keep = keep.clone();
+    tokio::task::spawn_blocking(move || quarantine_blocking(&server_dir, &provider_client, &keep)).await?
+}
+
+fn quarantine_blocking(
+    server_dir: &Path,
+    provider_client: &HashSet<String>,
+    keep: &HashSet<String>,
+) -> Result<Vec<QuarantinedMod>> {
+    let mods_dir = server_dir.join("mods");
+    let jars: Vec<PathBuf> = match std::fs::read_dir(&mods_dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jar")))
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut quarantined = Vec::new();
+    for jar in jars {
+        let file = jar.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let mods = mod_metadata::read_jar(&jar).unwrap_or_default();
+        let Some(reason) = client_only_reason(&file, &mods, provider_client, keep) else { continue };
+
+        let target_dir = server_dir.join(CLIENT_MODS_DIR);
+        std::fs::create_dir_all(&target_dir)?;
+        let target = target_dir.join(&file);
+        if target.exists() {
+            std::fs::remove_file(&target)?;
+        }
+        std::fs::rename(&jar, &target)?;
+        warn!("Moved client-only mod {} to {}: {}", file, CLIENT_MODS_DIR, reason);
+        quarantined.push(QuarantinedMod {
+            file,
+            mod_ids: mods.into_iter().map(|m| m.mod_id).collect(),
+            reason,
+        });
+    }
+    quarantined.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok(quarantined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jar(mod_id: &str, environment: Option<&str>, client_only: bool) -> JarMod {
+        JarMod {
+            file: format!("{}.jar", mod_id),
+            mod_id: mod_id.to_string(),
+            name: mod_id.to_string(),
+            version: "1.0".to_string(),
+            loader: "fabric".to_string(),
+            minecraft: None,
+            environment: environment.map(str::to_string),
+            client_only,
+            provides: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_client_only_reason() {
+        let reported: HashSet<String> = ["xaerominimap".to_string(), "unreadable.jar".to_string()].into();
+        let none = HashSet::new();
+
+        assert!(client_only_reason("iris.jar", &[jar("iris", Some("client"), true)], &none, &none).is_some());
+        assert!(client_only_reason("map.jar", &[jar("xaerominimap", None, false)], &reported, &none).is_some());
+        assert!(client_only_reason("unreadable.jar", &[], &reported, &none).is_some());
+        // The jar knows better than the provider
+        assert!(client_only_reason("map.jar", &[jar("xaerominimap", Some("both"), false)], &reported, &none).is_none());
+        assert!(client_only_reason("lithium.jar", &[jar("lithium", None, false)], &reported, &none).is_none());
+        // Mods asked for on the server stay, whatever they declare
+        let keep: HashSet<String> = ["iris".to_string(), "unreadable.jar".to_string()].into();
+        assert!(client_only_reason("iris.jar", &[jar("iris", Some("client"), true)], &none, &keep).is_none());
+        assert!(client_only_reason("unreadable.jar", &[], &reported, &keep).is_none());
+
+        assert_eq!(provider_side("required", "unsupported"), "client");
+        assert_eq!(provider_side("required", "optional"), "both");
+    }
+
+    #[test]
+    fn test_client_mod_kind() {
+        assert_eq!(client_mod_kind("iris", "Iris Shaders", Some("client")), Some(ClientModKind::ShaderLoader));
+        assert_eq!(client_mod_kind("irisshaders", "Iris", None), Some(ClientModKind::ShaderLoader));
+        assert_eq!(client_mod_kind("", "OptiFine", None), Some(ClientModKind::ShaderLoader));
+        assert_eq!(client_mod_kind("embeddium", "Embeddium", Some("both")), Some(ClientModKind::Renderer));
+        assert_eq!(client_mod_kind("xaeros-minimap", "Xaero's Minimap", Some("client")), Some(ClientModKind::ClientOnly));
+        assert_eq!(client_mod_kind("lithium", "Lithium", Some("both")), None);
+    }
+}
@@ -0,0 +1,356 @@
+//! Known-incompatibility rules
+//!
+//! Some problems can't be read from jar metadata: mods that crash together
+//! without declaring it, companion mods needed only alongside certain other
+//! mods, and versions with known bugs. These are described by a rules file.
+//! A copy is bundled with Guardian; when `GUARDIAN_COMPAT_RULES_URL` is set,
+//! newer community rules are downloaded from there into
+//! `<data_dir>/compat_rules.json` and used instead.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::compatibility_engine::{Conflict, ConflictKind, ScanTarget};
+use crate::mod_metadata::{self, JarMod};
+
+const BUNDLED_RULES: &str = include_str!("../configs/compat_rules.json");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleKind {
+    /// The mod doesn't work with `other`
+    Incompatible,
+    /// The mod needs `other` installed as well
+    RequiresCompanion,
+    /// The mod has a problem on its own, within the rule's conditions
+    VersionIssue,
+}
+
+/// A mod, optionally limited to some of its versions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModMatch {
+    pub mod_id: String,
+    /// Version range, in either syntax [`mod_metadata::matches`] reads
+    #[serde(default = "any_version")]
+    pub versions: String,
+}
+
+fn any_version() -> String {
+    "*".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub kind: RuleKind,
+    #[serde(rename = "mod")]
+    pub target: ModMatch,
+    /// The incompatible or companion mod
+    pub other: Option<ModMatch>,
+    /// The rule only applies when these mods are installed too
+    #[serde(default)]
+    pub with: Vec<ModMatch>,
+    /// The rule only applies on these Minecraft versions
+    pub minecraft: Option<String>,
+    /// The rule only applies on these loaders; all when empty
+    #[serde(default)]
+    pub loaders: Vec<String>,
+    /// error or warning
+    pub severity: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSet {
+    /// Increases with every published change
+    pub version: u32,
+    pub updated: Option<String>,
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_RULES).expect("Bundled compatibility rules are invalid")
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let rules: Self = serde_json::from_str(content).context("Invalid compatibility rules")?;
+        let mut ids = HashSet::new();
+        for rule in &rules.rules {
+            if rule.id.is_empty() || !ids.insert(rule.id.as_str()) {
+                bail!("Compatibility rule IDs must be unique and not empty: {:?}", rule.id);
+            }
+            if rule.kind != RuleKind::VersionIssue && rule.other.is_none() {
+                bail!("Compatibility rule {} needs another mod", rule.id);
+            }
+            if !matches!(rule.severity.as_str(), "error" | "warning") {
+                bail!("Compatibility rule {} has unknown severity {}", rule.id, rule.severity);
+            }
+        }
+        Ok(rules)
+    }
+
+    /// Problems the rules describe among `mods`, which the server's loader runs
+    pub fn check(&self, mods: &[JarMod], target: &ScanTarget) -> Vec<Conflict> {
+        let mut installed: HashMap<&str, Vec<&JarMod>> = HashMap::new();
+        for jar_mod in mods {
+            installed.entry(&jar_mod.mod_id).or_default().push(jar_mod);
+            for provided in &jar_mod.provides {
+                installed.entry(provided).or_default().push(jar_mod);
+            }
+        }
+        // The installed mod matching `mod_match`; unreadable versions count as matching
+        let find = |mod_match: &ModMatch| -> Option<&JarMod> {
+            installed.get(mod_match.mod_id.as_str())?.iter().copied().find(|m| {
+                mod_metadata::matches(&m.version, &mod_match.versions) != Some(false)
+            })
+        };
+
+        let mut conflicts = Vec::new();
+        for rule in &self.rules {
+            if !rule.loaders.is_empty() && !rule.loaders.iter().any(|loader| loader.eq_ignore_ascii_case(&target.loader)) {
+                continue;
+            }
+            if let Some(range) = &rule.minecraft {
+                if mod_metadata::matches(&target.minecraft_version, range) != Some(true) {
+                    continue;
+                }
+            }
+            let Some(jar_mod) = find(&rule.target) else { continue };
+            if !rule.with.iter().all(|with| find(with).is_some()) {
+                continue;
+            }
+
+            let other = rule.other.as_ref();
+            let (kind, files) = match rule.kind {
+                RuleKind::Incompatible => {
+                    let Some(other) = other.and_then(&find) else { continue };
+                    (ConflictKind::KnownIncompatibility, vec![jar_mod.file.clone(), other.file.clone()])
+                }
+                RuleKind::RequiresCompanion => {
+                    if other.and_then(&find).is_some() {
+                        continue;
+                    }
+                    (ConflictKind::MissingCompanion, vec![jar_mod.file.clone()])
+                }
+                RuleKind::VersionIssue => (ConflictKind::KnownIssue, vec![jar_mod.file.clone()]),
+            };
+            conflicts.push(Conflict {
+                kind,
+                severity: rule.severity.clone(),
+                mod_id: jar_mod.mod_id.clone(),
+                other_mod_id: other.map(|other| other.mod_id.clone()),
+                files,
+                message: rule.message.clone(),
+                rule: Some(rule.id.clone()),
+            });
+        }
+        conflicts
+    }
+}
+
+/// The rules in use and where they came from
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatRulesInfo {
+    pub version: u32,
+    pub updated: Option<String>,
+    /// bundled or downloaded
+    pub source: String,
+    /// Where updates are downloaded from, if anywhere
+    pub url: Option<String>,
+    pub last_refresh: Option<DateTime<Utc>>,
+    /// Why the last refresh failed, until one succeeds
+    pub last_error: Option<String>,
+    pub rules: Vec<Rule>,
+}
+
+struct State {
+    rules: Arc<RuleSet>,
+    source: &'static str,
+    last_refresh: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+pub struct CompatRules {
+    path: PathBuf,
+    url: Option<String>,
+    state: RwLock<State>,
+}
+
+impl CompatRules {
+    /// Use the downloaded rules in `path` when they are at least as new as the bundled ones
+    pub fn load(path: PathBuf, url: Option<String>) -> Self {
+        let bundled = RuleSet::bundled();
+        let downloaded = match std::fs::read_to_string(&path) {
+            Ok(content) => match RuleSet::parse(&content) {
+                Ok(rules) => Some(rules),
+                Err(e) => {
+                    warn!("Ignoring {}: {}", path.display(), e);
+                    None
+                }
+            },
+            Err(_) => None,
+        };
+        let (rules, source) = match downloaded {
+            Some(rules) if rules.version >= bundled.version => (rules, "downloaded"),
+            _ => (bundled, "bundled"),
+        };
+        Self {
+            path,
+            url,
+            state: RwLock::new(State {
+                rules: Arc::new(rules),
+                source,
+                last_refresh: None,
+                last_error: None,
+            }),
+        }
+    }
+
+    pub async fn current(&self) -> Arc<RuleSet> {
+        self.state.read().await.rules.clone()
+    }
+
+    pub async fn info(&self) -> CompatRulesInfo {
+        let state = self.state.read().await;
+        CompatRulesInfo {
+            version: state.rules.version,
+            updated: state.rules.updated.clone(),
+            source: state.source.to_string(),
+            url: self.url.clone(),
+            last_refresh: state.last_refresh,
+            last_error: state.last_error.clone(),
+            rules: state.rules.rules.clone(),
+        }
+    }
+
+    /// Download the rules from the configured URL and use them if they are
+    /// not older than the current ones
+    pub async fn refresh(&self) -> Result<CompatRulesInfo> {
+        let result = self.download().await;
+        {
+            let mut state = self.state.write().await;
+            match &result {
+                Ok(rules) => {
+                    info!("Loaded compatibility rules version {} ({} rules)", rules.version, rules.rules.len());
+                    state.rules = Arc::new(rules.clone());
+                    state.source = "downloaded";
+                    state.last_refresh = Some(Utc::now());
+                    state.last_error = None;
+                }
+                Err(e) => state.last_error = Some(e.to_string()),
+            }
+        }
+        result?;
+        Ok(self.info().await)
+    }
+
+    async fn download(&self) -> Result<RuleSet> {
+        let Some(url) = &self.url else {
+            bail!("No compatibility rules URL is configured");
+        };
+        let content = reqwest::Client::new()
+            .get(url)
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+            .with_context(|| format!("Failed to download compatibility rules from {}", url))?;
+        let rules = RuleSet::parse(&content)?;
+        let current = self.current().await.version;
+        if rules.version < current {
+            bail!("Downloaded rules are version {}, older than the current version {}", rules.version, current);
+        }
+
+        save(&self.path, &content).await?;
+        Ok(rules)
+    }
+
+    /// Refresh the rules every `every`, when a URL is configured
+    pub async fn start(self: Arc<Self>, every: Duration) {
+        if self.url.is_none() {
+            return;
+        }
+        let mut interval = tokio::time::interval(every);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.refresh().await {
+                warn!("Failed to refresh compatibility rules: {}", e);
+            }
+        }
+    }
+}
+
+/// Replace the rules file, so a failed write never leaves half a file behind
+async fn save(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let partial = path.with_extension("json.partial");
+    tokio::fs::write(&partial, content).await?;
+    tokio::fs::rename(&partial, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jar(mod_id: &str, version: &str) -> JarMod {
+        JarMod {
+            file: format!("{}.jar", mod_id),
+            mod_id: mod_id.to_string(),
+            name: mod_id.to_string(),
+            version: version.to_string(),
+            loader: "fabric".to_string(),
+            minecraft: None,
+            environment: None,
+            client_only: false,
+            provides: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_bundled_rules() {
+        let target = ScanTarget {
+            loader: "fabric".to_string(),
+            minecraft_version: "1.20.1".to_string(),
+            loader_version: "0.15.0".to_string(),
+        };
+        let rules = RuleSet::bundled();
+
+        let mods = vec![jar("sodium", "0.5.3"), jar("optifabric", "1.14"), jar("continuity", "3.0.0"), jar("starlight", "1.1.2")];
+        let found: Vec<(ConflictKind, Option<String>)> =
+            rules.check(&mods, &target).into_iter().map(|c| (c.kind, c.rule)).collect();
+        assert_eq!(
+            found,
+            [
+                (ConflictKind::KnownIncompatibility, Some("optifabric-sodium".to_string())),
+                (ConflictKind::MissingCompanion, Some("continuity-indium".to_string())),
+                (ConflictKind::KnownIssue, Some("starlight-1.20".to_string())),
+            ]
+        );
+
+        // Sodium 0.6 renders Continuity's textures itself
+        let mods = vec![jar("sodium", "0.6.0"), jar("continuity", "3.0.0")];
+        assert!(rules.check(&mods, &target).is_empty());
+    }
+
+    #[test]
+    fn test_parse_rejects_incomplete_rules() {
+        let missing_other = r#"{ "version": 2, "rules": [
+            { "id": "a", "kind": "incompatible", "mod": { "mod_id": "x" }, "severity": "error", "message": "" }
+        ] }"#;
+        assert!(RuleSet::parse(missing_other).is_err());
+    }
+}
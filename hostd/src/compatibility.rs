@@ -26,6 +26,9 @@ impl CompatibilityScanner {
         Ok(crate::compatibility_engine::CompatibilityReport {
             server_id: server_id.to_string(),
             issues: vec![],
+            conflicts: vec![],
+            mods: vec![],
+            unreadable: vec![],
             scan_timestamp: chrono::Utc::now(),
         })
     }
@@ -53,6 +56,9 @@ impl AutoFixEngine {
         Ok(crate::compatibility_engine::CompatibilityReport {
             server_id: server_id.to_string(),
             issues: vec![],
+            conflicts: vec![],
+            mods: vec![],
+            unreadable: vec![],
             scan_timestamp: chrono::Utc::now(),
         })
     }
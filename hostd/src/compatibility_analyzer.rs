@@ -67,6 +67,7 @@ struct ForgeModEntry {
     #[serde(rename = "modId")]
     mod_id: String,
     version: String,
+    #[serde(rename = "displayName")]
     display_name: String,
     description: Option<String>,
     authors: Option<String>,
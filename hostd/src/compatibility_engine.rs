@@ -1,9 +1,87 @@
-// Compatibility engine module placeholder
-// This will be implemented later
+//! Compatibility scanning of a server's installed mods
+//!
+//! Every jar in the mods folder is read for its mod metadata (see
+//! [`crate::mod_metadata`]) and checked against the server and the other
+//! jars: the same mod installed twice, mods for another loader, Minecraft
+//! versions the mod doesn't support, client-only mods, and missing,
+//! mismatched or breaking dependencies. Each problem is reported as a [`Conflict`].
+//!
+//! Problems jar metadata can't express, such as mods known to clash, come
+//! from the rules in [`crate::compat_rules`].
 
-use serde::{Deserialize, Serialize};
 use anyhow::Result;
-use chrono;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::compat_rules::RuleSet;
+use crate::database::ServerConfig;
+use crate::mod_metadata::{self, DependencyKind, JarMod, BUILTIN_IDS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// The same mod ID comes from more than one jar
+    DuplicateMod,
+    /// The jar is for a loader the server doesn't run
+    LoaderMismatch,
+    /// The mod doesn't support the server's Minecraft version
+    MinecraftVersion,
+    /// A required mod is not installed
+    MissingDependency,
+    /// A required mod is installed in a version the mod doesn't accept
+    DependencyVersion,
+    /// The mod declares it breaks, or conflicts with, another installed mod
+    Breaks,
+    /// The mod only runs on the client
+    ClientOnly,
+    /// A rule says the mod doesn't work with another installed mod
+    KnownIncompatibility,
+    /// A rule says the mod needs a companion mod that is not installed
+    MissingCompanion,
+    /// A rule describes a problem with this version of the mod
+    KnownIssue,
+}
+
+impl ConflictKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DuplicateMod => "duplicate_mod",
+            Self::LoaderMismatch => "loader_mismatch",
+            Self::MinecraftVersion => "minecraft_version",
+            Self::MissingDependency => "missing_dependency",
+            Self::DependencyVersion => "dependency_version",
+            Self::Breaks => "breaks",
+            Self::ClientOnly => "client_only",
+            Self::KnownIncompatibility => "known_incompatibility",
+            Self::MissingCompanion => "missing_companion",
+            Self::KnownIssue => "known_issue",
+        }
+    }
+}
+
+/// A problem found between the installed mods and the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    /// error or warning
+    pub severity: String,
+    pub mod_id: String,
+    /// The other mod involved, for dependency problems
+    pub other_mod_id: Option<String>,
+    /// Jars involved
+    pub files: Vec<String>,
+    pub message: String,
+    /// The compatibility rule that reported it
+    pub rule: Option<String>,
+}
+
+impl Conflict {
+    pub fn involves(&self, mod_id: &str) -> bool {
+        self.mod_id == mod_id || self.other_mod_id.as_deref() == Some(mod_id)
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompatibilityIssue {
@@ -13,35 +91,354 @@ pub struct CompatibilityIssue {
     pub fix_suggestion: Option<String>,
 }
 
+impl From<&Conflict> for CompatibilityIssue {
+    fn from(conflict: &Conflict) -> Self {
+        let fix_suggestion = match conflict.kind {
+            ConflictKind::DuplicateMod => Some(format!("Keep one of {}", conflict.files.join(", "))),
+            ConflictKind::LoaderMismatch => Some(format!("Remove {}", conflict.files.join(", "))),
+            ConflictKind::MinecraftVersion => Some(format!("Install a version of {} for this Minecraft version", conflict.mod_id)),
+            ConflictKind::MissingDependency | ConflictKind::DependencyVersion | ConflictKind::MissingCompanion => {
+                conflict.other_mod_id.as_ref().map(|other| format!("Install a matching version of {}", other))
+            }
+            ConflictKind::Breaks | ConflictKind::KnownIncompatibility => {
+                conflict.other_mod_id.as_ref().map(|other| format!("Remove {} or {}", conflict.mod_id, other))
+            }
+            ConflictKind::ClientOnly => Some(format!("Move {} to {}", conflict.files.join(", "), crate::client_mods::CLIENT_MODS_DIR)),
+            ConflictKind::KnownIssue => None,
+        };
+        Self {
+            id: format!("{}:{}", conflict.kind.as_str(), conflict.mod_id),
+            severity: conflict.severity.clone(),
+            message: conflict.message.clone(),
+            fix_suggestion,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompatibilityReport {
     pub server_id: String,
     pub issues: Vec<CompatibilityIssue>,
+    pub conflicts: Vec<Conflict>,
+    /// Mods read from the jars
+    pub mods: Vec<JarMod>,
+    /// Jars without mod metadata that could be read
+    pub unreadable: Vec<String>,
     pub scan_timestamp: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug)]
-pub struct CompatibilityScanner {
-    // Placeholder implementation
+/// The server the mods are checked against
+#[derive(Debug, Clone)]
+pub struct ScanTarget {
+    pub loader: String,
+    pub minecraft_version: String,
+    pub loader_version: String,
 }
 
-impl Default for CompatibilityScanner {
-    fn default() -> Self {
-        Self::new()
+impl From<&ServerConfig> for ScanTarget {
+    fn from(server: &ServerConfig) -> Self {
+        Self {
+            loader: server.loader.to_ascii_lowercase(),
+            minecraft_version: server.minecraft_version.clone(),
+            loader_version: server.loader_version.clone(),
+        }
     }
 }
 
+#[derive(Debug, Default)]
+pub struct CompatibilityScanner {
+    rules: Option<Arc<RuleSet>>,
+}
+
 impl CompatibilityScanner {
     pub fn new() -> Self {
-        Self {}
+        Self { rules: None }
+    }
+
+    /// Also check the mods against known-incompatibility rules
+    pub fn with_rules(mut self, rules: Arc<RuleSet>) -> Self {
+        self.rules = Some(rules);
+        self
     }
-    
-    pub async fn scan_server(&self, server_id: &str, mods_dir: &std::path::Path) -> Result<CompatibilityReport, Box<dyn std::error::Error>> {
-        // Placeholder implementation
+
+    /// Scan the jars in `mods_dir` against `target`
+    pub async fn scan_server(&self, server_id: &str, mods_dir: &Path, target: &ScanTarget) -> Result<CompatibilityReport> {
+        let dir = mods_dir.to_path_buf();
+        let (mods, unreadable) = tokio::task::spawn_blocking(move || read_mods(&dir)).await??;
+        let mut conflicts = detect_conflicts(&mods, target);
+        if let Some(rules) = &self.rules {
+            let active: Vec<JarMod> = mods.iter().filter(|m| loads(&target.loader, &m.loader)).cloned().collect();
+            conflicts.extend(rules.check(&active, target));
+        }
         Ok(CompatibilityReport {
             server_id: server_id.to_string(),
-            issues: vec![],
+            issues: conflicts.iter().map(CompatibilityIssue::from).collect(),
+            conflicts,
+            mods,
+            unreadable,
             scan_timestamp: chrono::Utc::now(),
         })
     }
-}
\ No newline at end of file
+}
+
+/// Mods of every jar in the folder, and the jars that had none
+fn read_mods(dir: &Path) -> Result<(Vec<JarMod>, Vec<String>)> {
+    let mut jars: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jar")))
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    jars.sort();
+
+    let mut mods = Vec::new();
+    let mut unreadable = Vec::new();
+    for jar in jars {
+        match mod_metadata::read_jar(&jar) {
+            Ok(jar_mods) if !jar_mods.is_empty() => mods.extend(jar_mods),
+            _ => unreadable.push(jar.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()),
+        }
+    }
+    Ok((mods, unreadable))
+}
+
+/// Loaders whose mods a server loader runs
+pub(crate) fn loads(server_loader: &str, mod_loader: &str) -> bool {
+    match server_loader {
+        "quilt" => mod_loader == "quilt" || mod_loader == "fabric",
+        loader => loader == mod_loader,
+    }
+}
+
+/// The mod ID the loader itself answers to in dependency declarations
+fn loader_ids(loader: &str) -> &'static [&'static str] {
+    match loader {
+        "fabric" => &["fabricloader", "fabric-loader"],
+        "quilt" => &["quilt_loader"],
+        "forge" => &["forge"],
+        "neoforge" => &["neoforge"],
+        _ => &[],
+    }
+}
+
+fn conflict(kind: ConflictKind, severity: &str, jar_mod: &JarMod, other: Option<&str>, files: Vec<String>, message: String) -> Conflict {
+    Conflict {
+        kind,
+        severity: severity.to_string(),
+        mod_id: jar_mod.mod_id.clone(),
+        other_mod_id: other.map(str::to_string),
+        files,
+        message,
+        rule: None,
+    }
+}
+
+/// Problems among `mods` and between them and the server
+pub fn detect_conflicts(mods: &[JarMod], target: &ScanTarget) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+
+    // A jar may carry metadata for several loaders; it is fine if any of them fits
+    let mut by_file: BTreeMap<&str, Vec<&JarMod>> = BTreeMap::new();
+    for jar_mod in mods {
+        by_file.entry(&jar_mod.file).or_default().push(jar_mod);
+    }
+    let mut active = Vec::new();
+    for (file, jar_mods) in by_file {
+        let fitting: Vec<&JarMod> = jar_mods.iter().copied().filter(|m| loads(&target.loader, &m.loader)).collect();
+        if fitting.is_empty() {
+            let first = jar_mods[0];
+            conflicts.push(conflict(
+                ConflictKind::LoaderMismatch,
+                "error",
+                first,
+                None,
+                vec![file.to_string()],
+                format!("{} is a {} mod, but the server runs {}", first.name, first.loader, target.loader),
+            ));
+        }
+        active.extend(fitting);
+    }
+
+    // Installed mod IDs, including the ones mods provide, with the version of each
+    let mut installed: HashMap<&str, Vec<&JarMod>> = HashMap::new();
+    for jar_mod in &active {
+        installed.entry(&jar_mod.mod_id).or_default().push(jar_mod);
+        for provided in &jar_mod.provides {
+            installed.entry(provided).or_default().push(jar_mod);
+        }
+    }
+
+    let mut duplicates: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for jar_mod in &active {
+        duplicates.entry(&jar_mod.mod_id).or_default().push(jar_mod.file.clone());
+    }
+    for (mod_id, mut files) in duplicates {
+        files.sort();
+        files.dedup();
+        if files.len() > 1 {
+            let jar_mod = active.iter().find(|m| m.mod_id == mod_id).unwrap();
+            conflicts.push(conflict(
+                ConflictKind::DuplicateMod,
+                "error",
+                jar_mod,
+                None,
+                files.clone(),
+                format!("{} is installed {} times: {}", jar_mod.name, files.len(), files.join(", ")),
+            ));
+        }
+    }
+
+    for jar_mod in &active {
+        if jar_mod.client_only {
+            conflicts.push(conflict(
+                ConflictKind::ClientOnly,
+                "warning",
+                jar_mod,
+                None,
+                vec![jar_mod.file.clone()],
+                format!("{} only runs on the client and can crash a dedicated server", jar_mod.name),
+            ));
+        }
+
+        if let Some(range) = &jar_mod.minecraft {
+            if mod_metadata::matches(&target.minecraft_version, range) == Some(false) {
+                conflicts.push(conflict(
+                    ConflictKind::MinecraftVersion,
+                    "error",
+                    jar_mod,
+                    Some("minecraft"),
+                    vec![jar_mod.file.clone()],
+                    format!("{} {} requires Minecraft {}, the server runs {}", jar_mod.name, jar_mod.version, range, target.minecraft_version),
+                ));
+            }
+        }
+
+        for dependency in &jar_mod.dependencies {
+            let id = dependency.mod_id.as_str();
+            // The loader is checked against the server's loader version; other built-ins can't be
+            let installed_versions: Vec<(&str, &str)> = if loader_ids(&target.loader).contains(&id) {
+                vec![(target.loader_version.as_str(), "")]
+            } else if BUILTIN_IDS.contains(&id) {
+                continue;
+            } else {
+                installed
+                    .get(id)
+                    .map(|mods| mods.iter().map(|m| (m.version.as_str(), m.file.as_str())).collect())
+                    .unwrap_or_default()
+            };
+            let in_range = installed_versions
+                .iter()
+                .find(|(version, _)| mod_metadata::matches(version, &dependency.versions) != Some(false));
+
+            match dependency.kind {
+                DependencyKind::Required if installed_versions.is_empty() => conflicts.push(conflict(
+                    ConflictKind::MissingDependency,
+                    "error",
+                    jar_mod,
+                    Some(id),
+                    vec![jar_mod.file.clone()],
+                    format!("{} requires {} {}, which is not installed", jar_mod.name, id, dependency.versions),
+                )),
+                DependencyKind::Required if in_range.is_none() => conflicts.push(conflict(
+                    ConflictKind::DependencyVersion,
+                    "error",
+                    jar_mod,
+                    Some(id),
+                    vec![jar_mod.file.clone()],
+                    format!(
+                        "{} requires {} {}, but {} is installed",
+                        jar_mod.name,
+                        id,
+                        dependency.versions,
+                        installed_versions.iter().map(|(version, _)| *version).collect::<Vec<_>>().join(", ")
+                    ),
+                )),
+                DependencyKind::Breaks | DependencyKind::Conflicts => {
+                    let Some((version, file)) = in_range else { continue };
+                    let breaks = dependency.kind == DependencyKind::Breaks;
+                    let mut files = vec![jar_mod.file.clone()];
+                    files.extend((!file.is_empty()).then(|| file.to_string()));
+                    conflicts.push(conflict(
+                        ConflictKind::Breaks,
+                        if breaks { "error" } else { "warning" },
+                        jar_mod,
+                        Some(id),
+                        files,
+                        format!(
+                            "{} {} {} {}",
+                            jar_mod.name,
+                            if breaks { "does not work with" } else { "is known to conflict with" },
+                            id,
+                            version
+                        ),
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mod_metadata::Dependency;
+
+    fn jar(file: &str, mod_id: &str, version: &str, loader: &str) -> JarMod {
+        JarMod {
+            file: file.to_string(),
+            mod_id: mod_id.to_string(),
+            name: mod_id.to_string(),
+            version: version.to_string(),
+            loader: loader.to_string(),
+            minecraft: None,
+            environment: None,
+            client_only: false,
+            provides: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_detect_conflicts() {
+        let target = ScanTarget {
+            loader: "fabric".to_string(),
+            minecraft_version: "1.20.1".to_string(),
+            loader_version: "0.15.0".to_string(),
+        };
+
+        let mut create = jar("create.jar", "create", "0.5.1", "fabric");
+        create.minecraft = Some("~1.19.2".to_string());
+        create.dependencies = vec![
+            Dependency { mod_id: "fabric-api".to_string(), versions: ">=0.90".to_string(), kind: DependencyKind::Required },
+            Dependency { mod_id: "porting_lib".to_string(), versions: "*".to_string(), kind: DependencyKind::Required },
+            Dependency { mod_id: "fabricloader".to_string(), versions: ">=0.14".to_string(), kind: DependencyKind::Required },
+            Dependency { mod_id: "optifabric".to_string(), versions: "*".to_string(), kind: DependencyKind::Breaks },
+        ];
+        let mods = vec![
+            create,
+            jar("fabric-api-a.jar", "fabric-api", "0.85.0", "fabric"),
+            jar("fabric-api-b.jar", "fabric-api", "0.85.0", "fabric"),
+            jar("optifabric.jar", "optifabric", "1.14", "fabric"),
+            jar("jei-forge.jar", "jei", "15.2", "forge"),
+        ];
+
+        let kinds: Vec<ConflictKind> = detect_conflicts(&mods, &target).iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ConflictKind::LoaderMismatch,
+                ConflictKind::DuplicateMod,
+                ConflictKind::MinecraftVersion,
+                ConflictKind::DependencyVersion,
+                ConflictKind::MissingDependency,
+                ConflictKind::Breaks,
+            ]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! Editing of mod config files under a server's `config/` directory. Paths are
+//! checked with the security path sanitizer, content is parsed according to the
+//! file's format before anything is written, and the previous version is copied
+//! to `backups/config/` and recorded as a configuration revision.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::config_revisions::{self, ConfigRevisionSummary, ConfigTarget};
+use crate::database::{DatabaseManager, ServerConfig};
+use crate::security::PathSanitizer;
+
+const CONFIG_DIR: &str = "config";
+
+/// How a config file is parsed, from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+    Properties,
+    /// Anything else (Forge `.cfg`, `.txt`, ...), stored without validation
+    Text,
+}
+
+impl ConfigFormat {
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+        match extension.as_str() {
+            "toml" => Self::Toml,
+            "yaml" | "yml" => Self::Yaml,
+            "json" | "mcmeta" => Self::Json,
+            "properties" => Self::Properties,
+            _ => Self::Text,
+        }
+    }
+
+    /// Parse `content`, returning the document as JSON for structured editors.
+    /// `Text` files parse to `null`.
+    pub fn parse(&self, content: &str) -> Result<serde_json::Value> {
+        match self {
+            Self::Toml => {
+                let value: toml::Value = toml::from_str(content).map_err(|e| anyhow!("Invalid TOML: {}", e))?;
+                Ok(serde_json::to_value(value)?)
+            }
+            Self::Yaml => {
+                let value: serde_yaml::Value = serde_yaml::from_str(content).map_err(|e| anyhow!("Invalid YAML: {}", e))?;
+                Ok(serde_json::to_value(value)?)
+            }
+            Self::Json => serde_json::from_str(content).map_err(|e| anyhow!("Invalid JSON: {}", e)),
+            Self::Properties => parse_properties(content).map(serde_json::Value::Object),
+            Self::Text => Ok(serde_json::Value::Null),
+        }
+    }
+}
+
+/// `key=value` or `key:value` lines; `#` and `!` start comments
+fn parse_properties(content: &str) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let mut map = serde_json::Map::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        let Some(split) = line.find(['=', ':']) else {
+            bail!("Invalid properties: line {} has no '=' separator", number + 1);
+        };
+        let (key, value) = (line[..split].trim(), line[split + 1..].trim());
+        if key.is_empty() {
+            bail!("Invalid properties: line {} has an empty key", number + 1);
+        }
+        map.insert(key.to_string(), serde_json::Value::String(value.to_string()));
+    }
+    Ok(map)
+}
+
+/// A file under the server's `config/` directory
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFileEntry {
+    /// Path relative to `config/`, with `/` separators
+    pub path: String,
+    pub format: ConfigFormat,
+    pub size: u64,
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// A config file with its content and, unless it is plain text, its parsed document
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFile {
+    pub path: String,
+    pub format: ConfigFormat,
+    pub content: String,
+    pub parsed: serde_json::Value,
+    /// Set when the file on disk does not parse; `parsed` is `null` then
+    pub parse_error: Option<String>,
+}
+
+/// Result of writing a config file
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFileWrite {
+    pub path: String,
+    /// `None` when the content was already current
+    pub revision: Option<ConfigRevisionSummary>,
+    /// Copy of the previous version, when there was one
+    pub backup_path: Option<PathBuf>,
+}
+
+fn config_root(server: &ServerConfig) -> PathBuf {
+    config_revisions::server_dir(server).join(CONFIG_DIR)
+}
+
+/// Resolve `path` (relative to `config/`) to a file inside the server's config
+/// directory, rejecting traversal and absolute paths
+pub fn resolve(server: &ServerConfig, path: &str) -> Result<(String, PathBuf)> {
+    let path = path.trim_start_matches('/').replace('\\', "/");
+    let server_dir = config_revisions::server_dir(server);
+    let base = server_dir.canonicalize().unwrap_or(server_dir);
+    let resolved = PathSanitizer::new(base)
+        .sanitize_path(&format!("{}/{}", CONFIG_DIR, path))
+        .map_err(|e| anyhow!("Invalid config path '{}': {}", path, e))?;
+    // The sanitizer lets through `config/a/..` style paths that stay inside the
+    // server directory but leave `config/`
+    ConfigTarget::mod_config(&path)?;
+    Ok((path, resolved))
+}
+
+/// Every file under the server's `config/` directory, sorted by path
+pub async fn list(server: &ServerConfig) -> Result<Vec<ConfigFileEntry>> {
+    let root = config_root(server);
+    tokio::task::spawn_blocking(move || {
+        let mut entries = Vec::new();
+        if root.is_dir() {
+            collect(&root, &root, &mut entries)?;
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(entries)
+    })
+    .await?
+}
+
+fn collect(root: &Path, dir: &Path, entries: &mut Vec<ConfigFileEntry>) -> Result<()> {
+    for entry in std::fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect(root, &path, entries)?;
+        } else if file_type.is_file() {
+            let metadata = entry.metadata()?;
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            entries.push(ConfigFileEntry {
+                path: relative.to_string_lossy().replace('\\', "/"),
+                format: ConfigFormat::from_path(&path),
+                size: metadata.len(),
+                modified: metadata.modified().ok().map(Into::into),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Read a config file, `None` when it does not exist
+pub async fn read(server: &ServerConfig, path: &str) -> Result<Option<ConfigFile>> {
+    let (path, file) = resolve(server, path)?;
+    let content = match tokio::fs::read_to_string(&file).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(anyhow!("Failed to read {}: {}", file.display(), e)),
+    };
+    let format = ConfigFormat::from_path(&file);
+    let (parsed, parse_error) = match format.parse(&content) {
+        Ok(parsed) => (parsed, None),
+        Err(e) => (serde_json::Value::Null, Some(e.to_string())),
+    };
+    Ok(Some(ConfigFile { path, format, content, parsed, parse_error }))
+}
+
+/// Validate and write a config file. The previous version is copied to
+/// `backups/config/<timestamp>/` and the change recorded as a revision.
+pub async fn write(
+    database: &DatabaseManager,
+    server: &ServerConfig,
+    path: &str,
+    content: &str,
+    author: Option<&str>,
+    message: Option<String>,
+) -> Result<ConfigFileWrite> {
+    let (path, file) = resolve(server, path)?;
+    if file.is_dir() {
+        bail!("'{}' is a directory", path);
+    }
+    ConfigFormat::from_path(&file).parse(content)?;
+
+    let target = ConfigTarget::mod_config(&path)?;
+    let previous = config_revisions::read(server, &target).await?;
+    let mut backup_path = None;
+    if previous.as_deref().is_some_and(|previous| previous != content) {
+        let backup = config_revisions::server_dir(server)
+            .join("backups")
+            .join(CONFIG_DIR)
+            .join(chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f").to_string())
+            .join(&path);
+        if let Some(parent) = backup.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::copy(&file, &backup)
+            .await
+            .with_context(|| format!("backing up {}", file.display()))?;
+        backup_path = Some(backup);
+    }
+
+    let revision = config_revisions::apply(database, server, &target, content, author, message).await?;
+    Ok(ConfigFileWrite { path, revision: revision.as_ref().map(Into::into), backup_path })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_detection() {
+        assert_eq!(ConfigFormat::from_path(Path::new("create-common.toml")), ConfigFormat::Toml);
+        assert_eq!(ConfigFormat::from_path(Path::new("a/b.YML")), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path(Path::new("jei/jei.json")), ConfigFormat::Json);
+        assert_eq!(ConfigFormat::from_path(Path::new("forge.cfg")), ConfigFormat::Text);
+    }
+
+    #[test]
+    fn test_parse_validates_content() {
+        let toml = ConfigFormat::Toml.parse("[worldgen]\ndisable = false\n").unwrap();
+        assert_eq!(toml["worldgen"]["disable"], serde_json::Value::Bool(false));
+        assert!(ConfigFormat::Toml.parse("[worldgen\n").is_err());
+        assert!(ConfigFormat::Json.parse("{\"a\": }").is_err());
+        assert!(ConfigFormat::Yaml.parse("a: [1, 2").is_err());
+
+        let props = ConfigFormat::Properties.parse("# comment\na=1\nb: two\n").unwrap();
+        assert_eq!(props["b"], "two");
+        assert!(ConfigFormat::Properties.parse("no separator").is_err());
+        assert_eq!(ConfigFormat::Text.parse("anything [").unwrap(), serde_json::Value::Null);
+    }
+}
@@ -0,0 +1,428 @@
+//! Revisions of server configuration. Every change Guardian makes to
+//! `server.properties`, a server's JVM arguments or a mod config under the
+//! server's `config/` directory is stored with the full new content and a
+//! unified diff, so any earlier state can be viewed and restored.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use uuid::Uuid;
+
+use crate::database::{ConfigRevision, DatabaseManager, ServerConfig};
+
+pub const SERVER_PROPERTIES: &str = "server.properties";
+pub const JVM_ARGS: &str = "jvm_args";
+const MOD_CONFIG_DIR: &str = "config";
+/// Stored in place of the RCON password, which lives in the keychain
+const REDACTED: &str = "<redacted>";
+/// Lines of unchanged context around each hunk
+const DIFF_CONTEXT: usize = 3;
+/// Above this many line pairs the diff replaces the whole file instead of aligning lines
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// A piece of configuration that is versioned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigTarget {
+    ServerProperties,
+    JvmArgs,
+    /// Path relative to the server's `config/` directory
+    ModConfig(PathBuf),
+}
+
+impl ConfigTarget {
+    /// `server.properties`, `jvm_args` or `config/<path>`
+    pub fn parse(target: &str) -> Result<Self> {
+        match target {
+            SERVER_PROPERTIES => Ok(Self::ServerProperties),
+            JVM_ARGS => Ok(Self::JvmArgs),
+            _ => {
+                let path = target
+                    .strip_prefix("config/")
+                    .ok_or_else(|| anyhow!("Unknown configuration '{}'", target))?;
+                Self::mod_config(path)
+            }
+        }
+    }
+
+    /// A mod config file, by its path inside the server's `config/` directory
+    pub fn mod_config(path: &str) -> Result<Self> {
+        let path = Path::new(path.trim_start_matches('/'));
+        if path.as_os_str().is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            bail!("Invalid config path '{}'", path.display());
+        }
+        Ok(Self::ModConfig(path.to_path_buf()))
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::ServerProperties => SERVER_PROPERTIES.to_string(),
+            Self::JvmArgs => JVM_ARGS.to_string(),
+            Self::ModConfig(path) => format!("{}/{}", MOD_CONFIG_DIR, path.to_string_lossy().replace('\\', "/")),
+        }
+    }
+
+    fn file(&self, server: &ServerConfig) -> Option<PathBuf> {
+        match self {
+            Self::ServerProperties => Some(server_dir(server).join(SERVER_PROPERTIES)),
+            Self::JvmArgs => None,
+            Self::ModConfig(path) => Some(server_dir(server).join(MOD_CONFIG_DIR).join(path)),
+        }
+    }
+}
+
+/// Directory the server runs in
+pub fn server_dir(server: &ServerConfig) -> PathBuf {
+    PathBuf::from(&server.server_directory)
+}
+
+/// Current content, `None` when the file does not exist
+pub async fn read(server: &ServerConfig, target: &ConfigTarget) -> Result<Option<String>> {
+    match target.file(server) {
+        Some(path) => match tokio::fs::read_to_string(&path).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(anyhow!("Failed to read {}: {}", path.display(), e)),
+        },
+        None => Ok(Some(server.jvm_args.clone())),
+    }
+}
+
+async fn store(database: &DatabaseManager, server: &ServerConfig, target: &ConfigTarget, content: &str) -> Result<()> {
+    match target.file(server) {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&path, content).await?;
+        }
+        None => {
+            let mut server = server.clone();
+            server.jvm_args = content.to_string();
+            server.updated_at = Utc::now();
+            database.update_server(&server).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Content as stored in a revision, without the RCON password
+fn redact(target: &ConfigTarget, content: &str) -> String {
+    if *target != ConfigTarget::ServerProperties {
+        return content.to_string();
+    }
+    let mut redacted: String = content
+        .lines()
+        .map(|line| match line.split_once('=') {
+            Some((key, value)) if key.trim() == "rcon.password" && !value.trim().is_empty() => format!("{}={}", key, REDACTED),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if content.ends_with('\n') {
+        redacted.push('\n');
+    }
+    redacted
+}
+
+/// Stored content with the server's current RCON password put back
+fn unredact(target: &ConfigTarget, content: &str, server: &ServerConfig) -> String {
+    if *target != ConfigTarget::ServerProperties {
+        return content.to_string();
+    }
+    content.replace(&format!("rcon.password={}", REDACTED), &format!("rcon.password={}", server.rcon_password))
+}
+
+/// Write `content` and record the change. Returns `None` when the content is
+/// already current, in which case nothing is written.
+pub async fn apply(
+    database: &DatabaseManager,
+    server: &ServerConfig,
+    target: &ConfigTarget,
+    content: &str,
+    author: Option<&str>,
+    message: Option<String>,
+) -> Result<Option<ConfigRevision>> {
+    let previous = read(server, target).await?;
+    if previous.as_deref() == Some(content) {
+        return Ok(None);
+    }
+    store(database, server, target, content).await?;
+
+    let name = target.name();
+    let content = redact(target, content);
+    let previous = previous.map(|previous| redact(target, &previous));
+    let revision = ConfigRevision {
+        id: Uuid::new_v4().to_string(),
+        server_id: server.id.clone(),
+        diff: unified_diff(previous.as_deref().unwrap_or_default(), &content, &name),
+        target: name,
+        content,
+        previous_content: previous,
+        author: author.map(str::to_string),
+        message,
+        created_at: Utc::now(),
+    };
+    database.create_config_revision(&revision).await?;
+    Ok(Some(revision))
+}
+
+/// A revision without its content, for listings
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigRevisionSummary {
+    pub id: String,
+    pub target: String,
+    pub author: Option<String>,
+    pub message: Option<String>,
+    pub additions: usize,
+    pub deletions: usize,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+impl From<&ConfigRevision> for ConfigRevisionSummary {
+    fn from(revision: &ConfigRevision) -> Self {
+        let (additions, deletions) = diff_stats(&revision.diff);
+        Self {
+            id: revision.id.clone(),
+            target: revision.target.clone(),
+            author: revision.author.clone(),
+            message: revision.message.clone(),
+            additions,
+            deletions,
+            created_at: revision.created_at,
+        }
+    }
+}
+
+/// Content each target had right after `to` was made. A target first changed
+/// after `to` goes back to what it held before Guardian changed it; one that
+/// did not exist then is left alone.
+fn rollback_plan(revisions: &[ConfigRevision], to: &ConfigRevision) -> Vec<(String, String)> {
+    let mut by_target: HashMap<&str, Vec<&ConfigRevision>> = HashMap::new();
+    for revision in revisions {
+        by_target.entry(&revision.target).or_default().push(revision);
+    }
+    let mut plan: Vec<(String, String)> = by_target
+        .into_iter()
+        .filter_map(|(target, mut history)| {
+            history.sort_by_key(|revision| revision.created_at);
+            let content = match history.iter().rev().find(|revision| revision.created_at <= to.created_at) {
+                Some(revision) => Some(revision.content.clone()),
+                None => history.first().and_then(|revision| revision.previous_content.clone()),
+            };
+            content.map(|content| (target.to_string(), content))
+        })
+        .collect();
+    plan.sort();
+    plan
+}
+
+/// Restore every piece of the server's configuration to its state right after
+/// `revision_id`. Each restored target gets a new revision; `None` when the
+/// revision does not belong to the server.
+pub async fn rollback(
+    database: &DatabaseManager,
+    server: &ServerConfig,
+    revision_id: &str,
+    author: Option<&str>,
+) -> Result<Option<Vec<ConfigRevision>>> {
+    let Some(to) = database.get_config_revision(revision_id).await?.filter(|r| r.server_id == server.id) else {
+        return Ok(None);
+    };
+    let revisions = database.get_config_revisions(&server.id, None).await?;
+
+    let mut server = server.clone();
+    let mut applied = Vec::new();
+    for (target, content) in rollback_plan(&revisions, &to) {
+        let target = ConfigTarget::parse(&target)?;
+        let content = unredact(&target, &content, &server);
+        let message = Some(format!("Rolled back to revision {}", to.id));
+        if let Some(revision) = apply(database, &server, &target, &content, author, message).await? {
+            if target == ConfigTarget::JvmArgs {
+                server.jvm_args = content;
+            }
+            applied.push(revision);
+        }
+    }
+    Ok(Some(applied))
+}
+
+/// Diff from the current content of a target to a stored revision of it, with
+/// the RCON password redacted on both sides
+pub fn diff_against(target: &ConfigTarget, current: &str, stored: &str) -> String {
+    unified_diff(&redact(target, current), stored, &target.name())
+}
+
+/// Lines added and removed by a unified diff
+pub fn diff_stats(diff: &str) -> (usize, usize) {
+    diff.lines()
+        .filter(|line| !line.starts_with("+++") && !line.starts_with("---"))
+        .fold((0, 0), |(added, removed), line| match line.as_bytes().first() {
+            Some(b'+') => (added + 1, removed),
+            Some(b'-') => (added, removed + 1),
+            _ => (added, removed),
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Keep,
+    Remove,
+    Add,
+}
+
+/// Line edits turning `old` into `new`, from their longest common subsequence
+fn line_edits<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Edit, &'a str)> {
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        return old.iter().map(|line| (Edit::Remove, *line)).chain(new.iter().map(|line| (Edit::Add, *line))).collect();
+    }
+    // common[i][j]: length of the longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] { common[i + 1][j + 1] + 1 } else { common[i + 1][j].max(common[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            edits.push((Edit::Keep, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            edits.push((Edit::Remove, old[i]));
+            i += 1;
+        } else {
+            edits.push((Edit::Add, new[j]));
+            j += 1;
+        }
+    }
+    edits
+}
+
+/// Unified diff of two texts, empty when they are the same
+pub fn unified_diff(old: &str, new: &str, name: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let edits = line_edits(&old_lines, &new_lines);
+    let changes: Vec<usize> = edits.iter().enumerate().filter(|(_, (edit, _))| *edit != Edit::Keep).map(|(i, _)| i).collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // Line numbers in the old and new text at each edit
+    let mut positions = Vec::with_capacity(edits.len());
+    let (mut old_line, mut new_line) = (0, 0);
+    for (edit, _) in &edits {
+        positions.push((old_line, new_line));
+        match edit {
+            Edit::Keep => {
+                old_line += 1;
+                new_line += 1;
+            }
+            Edit::Remove => old_line += 1,
+            Edit::Add => new_line += 1,
+        }
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", name, name);
+    let mut group_start = 0;
+    for k in 0..changes.len() {
+        let last_in_group = k + 1 == changes.len() || changes[k + 1] - changes[k] > 2 * DIFF_CONTEXT;
+        if !last_in_group {
+            continue;
+        }
+        let start = changes[group_start].saturating_sub(DIFF_CONTEXT);
+        let end = (changes[k] + DIFF_CONTEXT + 1).min(edits.len());
+        let hunk = &edits[start..end];
+        let old_count = hunk.iter().filter(|(edit, _)| *edit != Edit::Add).count();
+        let new_count = hunk.iter().filter(|(edit, _)| *edit != Edit::Remove).count();
+        let (old_start, new_start) = positions[start];
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_count > 0 { old_start + 1 } else { old_start },
+            old_count,
+            if new_count > 0 { new_start + 1 } else { new_start },
+            new_count,
+        ));
+        for (edit, line) in hunk {
+            let prefix = match edit {
+                Edit::Keep => ' ',
+                Edit::Remove => '-',
+                Edit::Add => '+',
+            };
+            out.push(prefix);
+            out.push_str(line);
+            out.push('\n');
+        }
+        group_start = k + 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let diff = unified_diff(old, new, SERVER_PROPERTIES);
+        assert_eq!(
+            diff,
+            "--- a/server.properties\n+++ b/server.properties\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -8,3 +8,4 @@\n h\n i\n j\n+k\n"
+        );
+        assert_eq!(diff_stats(&diff), (2, 1));
+        assert_eq!(unified_diff(old, old, SERVER_PROPERTIES), "");
+        assert_eq!(unified_diff("", "x\n", JVM_ARGS), "--- a/jvm_args\n+++ b/jvm_args\n@@ -0,0 +1,1 @@\n+x\n");
+    }
+
+    #[test]
+    fn test_rcon_password_is_not_stored() {
+        let content = "motd=hi\nrcon.password=hunter2\n";
+        let redacted = redact(&ConfigTarget::ServerProperties, content);
+        assert_eq!(redacted, "motd=hi\nrcon.password=<redacted>\n");
+        assert_eq!(redact(&ConfigTarget::JvmArgs, "rcon.password=x"), "rcon.password=x");
+    }
+
+    #[test]
+    fn test_targets() {
+        assert_eq!(ConfigTarget::parse("jvm_args").unwrap(), ConfigTarget::JvmArgs);
+        assert_eq!(ConfigTarget::parse("config/create-common.toml").unwrap().name(), "config/create-common.toml");
+        assert!(ConfigTarget::parse("config/../server.properties").is_err());
+        assert!(ConfigTarget::parse("ops.json").is_err());
+    }
+
+    #[test]
+    fn test_rollback_plan() {
+        let at = |minutes: i64| Utc::now() - chrono::Duration::minutes(100 - minutes);
+        let revision = |id: &str, target: &str, previous: Option<&str>, content: &str, minutes: i64| ConfigRevision {
+            id: id.to_string(),
+            server_id: "s".to_string(),
+            target: target.to_string(),
+            content: content.to_string(),
+            previous_content: previous.map(str::to_string),
+            diff: String::new(),
+            author: None,
+            message: None,
+            created_at: at(minutes),
+        };
+        let revisions = vec![
+            revision("1", SERVER_PROPERTIES, None, "pvp=true", 1),
+            revision("2", JVM_ARGS, Some("-Xmx2G"), "-Xmx4G", 2),
+            revision("3", SERVER_PROPERTIES, Some("pvp=true"), "pvp=false", 3),
+            revision("4", "config/mod.toml", None, "x=1", 4),
+            revision("5", JVM_ARGS, Some("-Xmx4G"), "-Xmx8G", 5),
+        ];
+        assert_eq!(
+            rollback_plan(&revisions, &revisions[0]),
+            vec![(JVM_ARGS.to_string(), "-Xmx2G".to_string()), (SERVER_PROPERTIES.to_string(), "pvp=true".to_string())]
+        );
+        assert_eq!(rollback_plan(&revisions, &revisions[3]).len(), 3);
+    }
+}
@@ -0,0 +1,230 @@
+//! Command metadata for console autocompletion
+//!
+//! Vanilla commands are known ahead of time. Mods and plugins add their own,
+//! which are found by running `help` over RCON and reading the command names
+//! out of its output. Vanilla lists one command per line with its usage
+//! (`/ban <targets> [<reason>]`), and over RCON the lines arrive without
+//! separators; Bukkit-style servers print `/name: description`. What was
+//! detected is cached per server for an hour.
+
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+use tokio::sync::RwLock;
+use tracing::debug;
+
+use crate::database::ServerConfig;
+use crate::restart_scheduler::rcon;
+
+/// Vanilla commands and their usage
+const VANILLA_COMMANDS: &[(&str, &str)] = &[
+    ("advancement", "(grant|revoke) <targets> ..."),
+    ("attribute", "<target> <attribute> (base|get|modifier) ..."),
+    ("ban", "<targets> [<reason>]"),
+    ("ban-ip", "<target> [<reason>]"),
+    ("banlist", "[ips|players]"),
+    ("bossbar", "(add|get|list|remove|set) ..."),
+    ("clear", "[<targets>] [<item>] [<maxCount>]"),
+    ("clone", "<begin> <end> <destination> ..."),
+    ("damage", "<target> <amount> [<damageType>] ..."),
+    ("data", "(get|merge|modify|remove) ..."),
+    ("datapack", "(disable|enable|list) ..."),
+    ("debug", "(start|stop|function)"),
+    ("defaultgamemode", "<gamemode>"),
+    ("deop", "<targets>"),
+    ("difficulty", "[peaceful|easy|normal|hard]"),
+    ("effect", "(clear|give) <targets> ..."),
+    ("enchant", "<targets> <enchantment> [<level>]"),
+    ("execute", "(align|anchored|as|at|facing|if|in|on|positioned|rotated|run|store|summon|unless) ..."),
+    ("experience", "(add|query|set) <targets> ..."),
+    ("fill", "<from> <to> <block> [destroy|hollow|keep|outline|replace]"),
+    ("fillbiome", "<from> <to> <biome> [replace <filter>]"),
+    ("forceload", "(add|query|remove) ..."),
+    ("function", "<name> [<arguments>]"),
+    ("gamemode", "<gamemode> [<target>]"),
+    ("gamerule", "<rule> [<value>]"),
+    ("give", "<targets> <item> [<count>]"),
+    ("help", "[<command>]"),
+    ("item", "(modify|replace) ..."),
+    ("jfr", "(start|stop)"),
+    ("kick", "<targets> [<reason>]"),
+    ("kill", "[<targets>]"),
+    ("list", "[uuids]"),
+    ("locate", "(biome|poi|structure) <id>"),
+    ("loot", "(give|insert|replace|spawn) ..."),
+    ("me", "<action>"),
+    ("msg", "<targets> <message>"),
+    ("op", "<targets>"),
+    ("pardon", "<targets>"),
+    ("pardon-ip", "<target>"),
+    ("particle", "<name> [<pos>] ..."),
+    ("perf", "(start|stop)"),
+    ("place", "(feature|jigsaw|structure|template) ..."),
+    ("playsound", "<sound> [<source>] [<targets>] ..."),
+    ("random", "(value|roll|reset) ..."),
+    ("recipe", "(give|take) <targets> <recipe>"),
+    ("reload", ""),
+    ("return", "<value>"),
+    ("ride", "<target> (mount|dismount) ..."),
+    ("save-all", "[flush]"),
+    ("save-off", ""),
+    ("save-on", ""),
+    ("say", "<message>"),
+    ("schedule", "(clear|function) ..."),
+    ("scoreboard", "(objectives|players) ..."),
+    ("seed", ""),
+    ("setblock", "<pos> <block> [destroy|keep|replace]"),
+    ("setidletimeout", "<minutes>"),
+    ("setworldspawn", "[<pos>] [<angle>]"),
+    ("spawnpoint", "[<targets>] [<pos>] [<angle>]"),
+    ("spectate", "[<target>] [<player>]"),
+    ("spreadplayers", "<center> <spreadDistance> <maxRange> ..."),
+    ("stop", ""),
+    ("stopsound", "<targets> [<source>] [<sound>]"),
+    ("summon", "<entity> [<pos>] [<nbt>]"),
+    ("tag", "<targets> (add|list|remove) ..."),
+    ("team", "(add|empty|join|leave|list|modify|remove) ..."),
+    ("teammsg", "<message>"),
+    ("teleport", "<destination> | <targets> <location> ..."),
+    ("tell", "<targets> <message>"),
+    ("tellraw", "<targets> <message>"),
+    ("tick", "(query|rate|freeze|unfreeze|step|sprint) ..."),
+    ("time", "(add|query|set) ..."),
+    ("title", "<targets> (actionbar|clear|reset|subtitle|times|title) ..."),
+    ("tp", "<destination> | <targets> <location> ..."),
+    ("transfer", "<hostname> [<port>] [<players>]"),
+    ("trigger", "<objective> [add|set] [<value>]"),
+    ("w", "<targets> <message>"),
+    ("weather", "(clear|rain|thunder) [<duration>]"),
+    ("whitelist", "(add|list|off|on|reload|remove) ..."),
+    ("worldborder", "(add|center|damage|get|set|warning) ..."),
+    ("xp", "(add|query|set) <targets> ..."),
+];
+
+/// How long detected commands are reused
+const DETECTED_TTL_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandInfo {
+    /// Without the leading `/`
+    pub name: String,
+    pub usage: Option<String>,
+    /// vanilla, or server for commands added by mods and plugins
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandMetadata {
+    /// Sorted by name
+    pub commands: Vec<CommandInfo>,
+    /// When the server's own commands were last read; `None` if they never were
+    pub detected_at: Option<DateTime<Utc>>,
+}
+
+/// Commands read from a server, and when
+type Detected = (DateTime<Utc>, Vec<CommandInfo>);
+
+#[derive(Default)]
+pub struct ConsoleCommands {
+    detected: RwLock<HashMap<String, Detected>>,
+}
+
+impl ConsoleCommands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The commands a server knows. A `running` server is asked with `help`
+    /// when nothing recent was cached or `refresh` is set.
+    pub async fn metadata(&self, server: &ServerConfig, running: bool, refresh: bool) -> CommandMetadata {
+        let cached = self.detected.read().await.get(&server.id).cloned();
+        let stale = cached
+            .as_ref()
+            .is_none_or(|(at, _)| Utc::now() - *at > Duration::minutes(DETECTED_TTL_MINUTES));
+        let detected = if running && (refresh || stale) {
+            match rcon(server, "help".to_string()).await {
+                Ok(output) => {
+                    let entry = (Utc::now(), parse_help(&output));
+                    self.detected.write().await.insert(server.id.clone(), entry.clone());
+                    Some(entry)
+                }
+                Err(e) => {
+                    debug!("Could not read commands of server {}: {}", server.id, e);
+                    cached
+                }
+            }
+        } else {
+            cached
+        };
+
+        let mut commands: BTreeMap<String, CommandInfo> = VANILLA_COMMANDS
+            .iter()
+            .map(|(name, usage)| {
+                let info = CommandInfo {
+                    name: name.to_string(),
+                    usage: Some(usage.to_string()).filter(|usage| !usage.is_empty()),
+                    source: "vanilla".to_string(),
+                };
+                (name.to_string(), info)
+            })
+            .collect();
+        let detected_at = detected.as_ref().map(|(at, _)| *at);
+        for info in detected.map(|(_, commands)| commands).unwrap_or_default() {
+            match commands.get_mut(&info.name) {
+                // The server's own usage matches its version
+                Some(known) => known.usage = info.usage.or(known.usage.take()),
+                None => {
+                    commands.insert(info.name.clone(), info);
+                }
+            }
+        }
+        CommandMetadata { commands: commands.into_values().collect(), detected_at }
+    }
+}
+
+/// Commands named in the output of `help`
+fn parse_help(output: &str) -> Vec<CommandInfo> {
+    static COMMAND: OnceLock<Regex> = OnceLock::new();
+    static FORMATTING: OnceLock<Regex> = OnceLock::new();
+    let command = COMMAND.get_or_init(|| Regex::new(r"/([A-Za-z][A-Za-z0-9_.:-]*)([^/\n]*)").unwrap());
+    let formatting = FORMATTING.get_or_init(|| Regex::new(r"§.").unwrap());
+
+    let output = formatting.replace_all(output, "");
+    let mut found: BTreeMap<String, CommandInfo> = BTreeMap::new();
+    for captures in command.captures_iter(&output) {
+        let name = captures[1].trim_end_matches(':').to_ascii_lowercase();
+        let usage = captures[2].trim().trim_start_matches(':').trim();
+        found.entry(name.clone()).or_insert(CommandInfo {
+            name,
+            usage: Some(usage.to_string()).filter(|usage| !usage.is_empty()),
+            source: "server".to_string(),
+        });
+    }
+    found.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_help() {
+        let vanilla = "/advancement (grant|revoke)/ban <targets> [<reason>]/claim (trust|untrust) <player>/seed";
+        let names: Vec<(String, Option<String>)> = parse_help(vanilla).into_iter().map(|c| (c.name, c.usage)).collect();
+        assert_eq!(
+            names,
+            [
+                ("advancement".to_string(), Some("(grant|revoke)".to_string())),
+                ("ban".to_string(), Some("<targets> [<reason>]".to_string())),
+                ("claim".to_string(), Some("(trust|untrust) <player>".to_string())),
+                ("seed".to_string(), None),
+            ]
+        );
+
+        let bukkit = "§e--------- §fHelp: Index (1/12) §e---------\n§6/home: §fTeleport to your home\n§6/sethome: §fSet your home";
+        let names: Vec<String> = parse_help(bukkit).into_iter().map(|c| c.name).collect();
+        assert_eq!(names, ["home", "sethome"]);
+    }
+}
@@ -62,8 +62,8 @@ pub struct AppState {
     // Test harness
     pub test_harness: Arc<crate::core::test_harness::TestHarness>,
     
-    // SSE
-    pub sse_sender: Option<tokio::sync::broadcast::Sender<serde_json::Value>>,
+    // Live updates for WebSocket and SSE clients
+    pub event_bus: crate::event_bus::EventBus,
 }
 
 /// Server configuration (runtime-facing)
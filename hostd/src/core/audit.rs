@@ -0,0 +1,176 @@
+//! Audit trail of mutating API requests: who did what, to which server, and
+//! whether it worked. Reads are not recorded.
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{Request, State},
+    http::{header, Method},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::core::middleware::{scoped_server_id, AuthContext};
+use crate::database::{AuditEntry, DatabaseManager};
+
+/// Responses up to this size are read to find out whether the request succeeded
+const MAX_INSPECTED_BODY: u64 = 1024 * 1024;
+
+fn is_mutating(method: &Method) -> bool {
+    matches!(*method, Method::POST | Method::PUT | Method::PATCH | Method::DELETE)
+}
+
+fn is_id(segment: &str) -> bool {
+    Uuid::parse_str(segment).is_ok() || segment.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Short name of what a request does, e.g. `server.start` or `backup.restore`
+pub fn action_name(method: &Method, path: &str) -> String {
+    let segments: Vec<&str> = path.trim_start_matches("/api").split('/').filter(|s| !s.is_empty()).collect();
+    let verb = match *method {
+        Method::DELETE => "delete",
+        Method::PUT | Method::PATCH => "update",
+        _ => "create",
+    };
+
+    let action = match segments.as_slice() {
+        ["servers"] => "server.create",
+        ["servers", _] => if *method == Method::DELETE { "server.delete" } else { "server.update" },
+        ["servers", _, "start"] => "server.start",
+        ["servers", _, "stop"] => "server.stop",
+        ["servers", _, "restart"] => "server.restart",
+        ["servers", _, "command" | "console"] => "server.command",
+        ["servers", _, "settings" | "config", ..] => "server.settings_update",
+        ["servers", _, "backups", _, "restore"] => "backup.restore",
+        ["servers", _, "backups", ..] => if *method == Method::DELETE { "backup.delete" } else { "backup.create" },
+        ["servers", _, "mods"] => if *method == Method::DELETE { "mod.uninstall" } else { "mod.install" },
+        ["mods", "install"] | ["mods", _, "download"] => "mod.install",
+        ["modpacks", "apply"] | ["modpacks", _, "apply"] => "modpack.apply",
+        ["settings", ..] => "settings.update",
+        ["auth", "register"] => "user.create",
+        ["auth", "users", ..] => if *method == Method::DELETE { "user.delete" } else { "user.update" },
+        ["auth", "tokens"] => "api_token.create",
+        ["auth", "tokens", _] => "api_token.revoke",
+        ["auth", "logout"] => "auth.logout",
+        _ => {
+            // e.g. POST /api/servers/{id}/lighting/{job}/start -> lighting.start
+            let mut names: Vec<&str> = segments.iter().copied().filter(|s| !is_id(s)).collect();
+            if names.first() == Some(&"servers") && segments.len() > 2 {
+                names.remove(0);
+                names.retain(|name| *name != segments[1]);
+            }
+            let mut action = names.join(".").replace('-', "_");
+            if names.len() <= 1 || *method != Method::POST {
+                action = format!("{}.{}", action, verb);
+            }
+            return action;
+        }
+    };
+    action.to_string()
+}
+
+/// Whether the request succeeded and its error message, from the status code
+/// and, for JSON bodies, the `success`/`error` fields of the API response
+async fn inspect(response: Response) -> (Response, bool, Option<String>) {
+    let status = response.status();
+    let is_json = response.headers().get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let size = response.body().size_hint().exact();
+    if !is_json || size.is_none_or(|size| size > MAX_INSPECTED_BODY) {
+        let error = (!status.is_success()).then(|| status.to_string());
+        return (response, status.is_success(), error);
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_INSPECTED_BODY as usize).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return (Response::from_parts(parts, Body::empty()), false, Some(format!("Failed to read response: {}", e)));
+        }
+    };
+    let json: Option<serde_json::Value> = serde_json::from_slice(&bytes).ok();
+    let success = status.is_success()
+        && json.as_ref().and_then(|json| json.get("success")).and_then(|value| value.as_bool()).unwrap_or(true);
+    let error = json.as_ref()
+        .and_then(|json| json.get("error"))
+        .and_then(|value| value.as_str())
+        .map(str::to_string)
+        .or_else(|| (!status.is_success()).then(|| status.to_string()));
+    (Response::from_parts(parts, Body::from(bytes)), success, error)
+}
+
+/// Record every mutating `/api` request in the audit log. Runs outside the auth
+/// middleware, which leaves the caller's [`AuthContext`] on the response.
+pub async fn audit_middleware(
+    State(database): State<Arc<DatabaseManager>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    // Logins carry no actor yet; refreshes only rotate tokens
+    if !is_mutating(&method) || !path.starts_with("/api/") || matches!(path.as_str(), "/api/auth/login" | "/api/auth/refresh") {
+        return next.run(request).await;
+    }
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let status_code = response.status().as_u16();
+    let auth = response.extensions().get::<AuthContext>().cloned();
+    let (response, success, error) = inspect(response).await;
+
+    let (actor, actor_type) = match &auth {
+        Some(auth) if auth.api_token_id.is_some() => (auth.username.clone(), "api_token"),
+        Some(auth) => (auth.username.clone(), "user"),
+        None => ("anonymous".to_string(), "anonymous"),
+    };
+    let entry = AuditEntry {
+        id: Uuid::new_v4().to_string(),
+        timestamp: chrono::Utc::now(),
+        actor,
+        actor_type: actor_type.to_string(),
+        action: action_name(&method, &path),
+        method: method.to_string(),
+        server_id: scoped_server_id(&path).map(str::to_string),
+        path,
+        status_code,
+        success,
+        error,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    tokio::spawn(async move {
+        if let Err(e) = database.insert_audit_entry(&entry).await {
+            tracing::warn!("Failed to write audit entry for {} {}: {}", entry.method, entry.path, e);
+        }
+    });
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_names() {
+        let id = "0f8fad5b-d9cb-469f-a165-70867728950e";
+        let cases = [
+            (Method::POST, "/api/servers".to_string(), "server.create"),
+            (Method::POST, format!("/api/servers/{}/start", id), "server.start"),
+            (Method::DELETE, format!("/api/servers/{}", id), "server.delete"),
+            (Method::POST, format!("/api/servers/{}/backups/{}/restore", id, id), "backup.restore"),
+            (Method::PUT, "/api/settings".to_string(), "settings.update"),
+            (Method::POST, "/api/mods/install".to_string(), "mod.install"),
+            (Method::POST, format!("/api/servers/{}/lighting/{}/start", id, id), "lighting.start"),
+            (Method::DELETE, format!("/api/servers/{}/restart-schedules/{}", id, id), "restart_schedules.delete"),
+            (Method::POST, format!("/api/servers/{}/world/trim", id), "world.trim"),
+            (Method::POST, "/api/gpu/enable".to_string(), "gpu.enable"),
+        ];
+        for (method, path, expected) in cases {
+            assert_eq!(action_name(&method, &path), expected, "{} {}", method, path);
+        }
+    }
+}
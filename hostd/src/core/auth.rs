@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
@@ -7,8 +8,11 @@ use serde::{Deserialize, Serialize};
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use argon2::password_hash::{rand_core::OsRng, SaltString};
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
+use sha2::{Digest, Sha256};
 use crate::core::credential_manager::CredentialManager;
+use crate::core::error_handler::NotFound;
+use crate::database::{ApiToken, DatabaseManager};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
@@ -18,17 +22,29 @@ pub struct User {
     pub password_hash: String,
     pub role: UserRole,
     pub is_active: bool,
+    /// Servers the user may access; empty means all servers. Admins always see every server.
+    #[serde(default)]
+    pub server_ids: Vec<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub last_login: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+impl User {
+    pub fn can_access_server(&self, server_id: &str) -> bool {
+        self.role == UserRole::Admin || self.server_ids.is_empty() || self.server_ids.iter().any(|id| id == server_id)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum UserRole {
     Admin,
-    Moderator,
-    User,
-    ReadOnly,
+    /// Runs servers day to day: start/stop, backups, mods, settings
+    #[serde(alias = "Moderator")]
+    Operator,
+    /// Read-only access
+    #[serde(alias = "User", alias = "ReadOnly")]
+    Viewer,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,6 +110,36 @@ pub struct LoginResponse {
     pub token: String,
     pub user: User,
     pub expires_at: chrono::DateTime<chrono::Utc>,
+    /// Single-use token for `/api/auth/refresh`
+    pub refresh_token: String,
+    pub refresh_expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    #[serde(default)]
+    pub scopes: Vec<Permission>,
+    /// Adds a predefined set of scopes, see [`scope_preset`]
+    pub preset: Option<String>,
+    /// Servers the token may access; empty means all the creator can access
+    #[serde(default)]
+    pub server_ids: Vec<String>,
+    /// Never expires when unset
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedApiToken {
+    /// The token itself; it is not stored and cannot be shown again
+    pub token: String,
+    #[serde(flatten)]
+    pub api_token: ApiToken,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -102,6 +148,8 @@ pub struct RegisterRequest {
     pub email: String,
     pub password: String,
     pub role: Option<UserRole>,
+    #[serde(default)]
+    pub server_ids: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -113,13 +161,87 @@ pub struct JwtClaims {
     pub iat: usize,
 }
 
+/// API tokens start with this so they can be told apart from JWTs
+pub const API_TOKEN_PREFIX: &str = "gsm_";
+
+/// Scopes for common automation jobs
+pub fn scope_preset(name: &str) -> Option<Vec<Permission>> {
+    match name {
+        "read_only" => Some(vec![
+            Permission::ViewServer,
+            Permission::ViewBackup,
+            Permission::ViewLogs,
+            Permission::ViewMetrics,
+            Permission::ViewModpack,
+        ]),
+        "metrics" => Some(vec![Permission::ViewServer, Permission::ViewMetrics]),
+        "backup" => Some(vec![Permission::ViewServer, Permission::ViewBackup, Permission::CreateBackup]),
+        _ => None,
+    }
+}
+
+fn random_hex(len: usize) -> String {
+    use rand::RngCore;
+    let mut bytes = vec![0u8; len];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hash_api_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// How long a refresh token can be exchanged for a new access token
+pub const REFRESH_TOKEN_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Secrets shipped in default configs; never used to sign tokens
+const PLACEHOLDER_SECRETS: &[&str] = &["", "your-secret-key-here", "your-secret-key-change-in-production"];
+
+/// The configured JWT secret, or a random one generated once and kept in
+/// `data_dir/jwt_secret` so tokens stay valid across restarts
+pub fn load_jwt_secret(configured: &str, data_dir: &Path) -> Result<String> {
+    if !PLACEHOLDER_SECRETS.contains(&configured.trim()) {
+        return Ok(configured.to_string());
+    }
+
+    let path = data_dir.join("jwt_secret");
+    if let Ok(secret) = std::fs::read_to_string(&path) {
+        let secret = secret.trim();
+        if !secret.is_empty() {
+            return Ok(secret.to_string());
+        }
+    }
+
+    let secret = random_hex(32);
+
+    std::fs::create_dir_all(data_dir)?;
+    std::fs::write(&path, &secret)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    tracing::warn!("No JWT_SECRET configured, generated one at {}", path.display());
+    Ok(secret)
+}
+
+struct RefreshSession {
+    user_id: Uuid,
+    /// Access token issued together with this refresh token
+    access_token: String,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
 pub struct AuthManager {
     users: Arc<RwLock<HashMap<Uuid, User>>>,
     roles: Arc<RwLock<HashMap<Uuid, Role>>>,
     user_sessions: Arc<RwLock<HashMap<String, Uuid>>>, // token -> user_id
+    refresh_sessions: Arc<RwLock<HashMap<String, RefreshSession>>>,
     jwt_secret: String,
     jwt_expiry: Duration,
     credential_manager: Arc<CredentialManager>,
+    /// Where API tokens are kept
+    database: Option<Arc<DatabaseManager>>,
 }
 
 impl AuthManager {
@@ -128,11 +250,25 @@ impl AuthManager {
             users: Arc::new(RwLock::new(HashMap::new())),
             roles: Arc::new(RwLock::new(HashMap::new())),
             user_sessions: Arc::new(RwLock::new(HashMap::new())),
+            refresh_sessions: Arc::new(RwLock::new(HashMap::new())),
             jwt_secret,
             jwt_expiry: Duration::from_secs(24 * 60 * 60), // 24 hours
             credential_manager,
+            database: None,
         }
     }
+
+    /// Enable API tokens, which are stored in the database
+    pub fn with_database(mut self, database: Arc<DatabaseManager>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Lifetime of access tokens; clients renew them with their refresh token
+    pub fn with_token_expiry(mut self, expiry: Duration) -> Self {
+        self.jwt_expiry = expiry;
+        self
+    }
     
     pub async fn initialize(&self) -> Result<()> {
         // Create default roles
@@ -186,10 +322,10 @@ impl AuthManager {
         };
         roles.insert(admin_role.id, admin_role);
         
-        // Moderator role - server management and user viewing
-        let moderator_role = Role {
+        // Operator role - runs servers but cannot create/delete them or manage users
+        let operator_role = Role {
             id: Uuid::new_v4(),
-            name: "Moderator".to_string(),
+            name: "Operator".to_string(),
             permissions: vec![
                 Permission::StartServer,
                 Permission::StopServer,
@@ -197,9 +333,14 @@ impl AuthManager {
                 Permission::ViewServer,
                 Permission::EditServer,
                 Permission::ViewUser,
+                Permission::ViewRole,
+                Permission::CreateBackup,
+                Permission::RestoreBackup,
                 Permission::ViewBackup,
                 Permission::ViewLogs,
                 Permission::ViewMetrics,
+                Permission::CreateModpack,
+                Permission::EditModpack,
                 Permission::ViewModpack,
                 Permission::InstallMod,
                 Permission::UninstallMod,
@@ -207,45 +348,40 @@ impl AuthManager {
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
-        roles.insert(moderator_role.id, moderator_role);
-        
-        // User role - basic server operations
-        let user_role = Role {
-            id: Uuid::new_v4(),
-            name: "User".to_string(),
-            permissions: vec![
-                Permission::ViewServer,
-                Permission::ViewModpack,
-            ],
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-        };
-        roles.insert(user_role.id, user_role);
+        roles.insert(operator_role.id, operator_role);
         
-        // Read-only role - view only
-        let read_only_role = Role {
+        // Viewer role - view only
+        let viewer_role = Role {
             id: Uuid::new_v4(),
-            name: "ReadOnly".to_string(),
+            name: "Viewer".to_string(),
             permissions: vec![
                 Permission::ViewServer,
+                Permission::ViewBackup,
+                Permission::ViewLogs,
+                Permission::ViewMetrics,
                 Permission::ViewModpack,
             ],
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
-        roles.insert(read_only_role.id, read_only_role);
+        roles.insert(viewer_role.id, viewer_role);
         
         Ok(())
     }
     
     async fn create_default_admin(&self) -> Result<()> {
+        let password = std::env::var("GUARDIAN_ADMIN_PASSWORD").unwrap_or_else(|_| {
+            tracing::warn!("GUARDIAN_ADMIN_PASSWORD is not set, the default admin password is in use");
+            "admin123".to_string()
+        });
         let admin_user = User {
             id: Uuid::new_v4(),
             username: "admin".to_string(),
             email: "admin@guardian.local".to_string(),
-            password_hash: self.hash_password("admin123")?,
+            password_hash: self.hash_password(&password)?,
             role: UserRole::Admin,
             is_active: true,
+            server_ids: Vec::new(),
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
             last_login: None,
@@ -280,8 +416,9 @@ impl AuthManager {
             username: request.username,
             email: request.email,
             password_hash,
-            role: request.role.unwrap_or(UserRole::User),
+            role: request.role.unwrap_or(UserRole::Viewer),
             is_active: true,
+            server_ids: request.server_ids,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
             last_login: None,
@@ -292,20 +429,7 @@ impl AuthManager {
         users.insert(user.id, user.clone());
         drop(users);
         
-        // Generate JWT token
-        let token = self.generate_token(&user)?;
-        let expires_at = chrono::Utc::now() + chrono::Duration::seconds(self.jwt_expiry.as_secs() as i64);
-        
-        // Store session
-        let mut sessions = self.user_sessions.write().await;
-        sessions.insert(token.clone(), user.id);
-        drop(sessions);
-        
-        Ok(LoginResponse {
-            token,
-            user,
-            expires_at,
-        })
+        self.issue_tokens(user).await
     }
     
     pub async fn login(&self, request: LoginRequest) -> Result<LoginResponse> {
@@ -330,25 +454,65 @@ impl AuthManager {
         }
         drop(users);
         
+        self.issue_tokens(user).await
+    }
+    
+    /// Exchange a refresh token for a new access/refresh token pair. Refresh
+    /// tokens are single use; the access token issued with it is revoked.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<LoginResponse> {
+        let session = self.refresh_sessions.write().await.remove(refresh_token)
+            .ok_or_else(|| anyhow!("Invalid refresh token"))?;
+        self.user_sessions.write().await.remove(&session.access_token);
+        
+        if session.expires_at < chrono::Utc::now() {
+            return Err(anyhow!("Refresh token expired"));
+        }
+        
+        let user = self.users.read().await.get(&session.user_id)
+            .filter(|user| user.is_active)
+            .cloned()
+            .ok_or_else(|| anyhow!("User not found or disabled"))?;
+        
+        self.issue_tokens(user).await
+    }
+    
+    async fn issue_tokens(&self, user: User) -> Result<LoginResponse> {
         // Generate JWT token
         let token = self.generate_token(&user)?;
-        let expires_at = chrono::Utc::now() + chrono::Duration::seconds(self.jwt_expiry.as_secs() as i64);
+        let now = chrono::Utc::now();
+        let expires_at = now + chrono::Duration::seconds(self.jwt_expiry.as_secs() as i64);
         
         // Store session
-        let mut sessions = self.user_sessions.write().await;
-        sessions.insert(token.clone(), user.id);
-        drop(sessions);
+        self.user_sessions.write().await.insert(token.clone(), user.id);
+        
+        let refresh_token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let refresh_expires_at = now + chrono::Duration::seconds(REFRESH_TOKEN_EXPIRY.as_secs() as i64);
+        let mut refresh_sessions = self.refresh_sessions.write().await;
+        refresh_sessions.retain(|_, session| session.expires_at > now);
+        refresh_sessions.insert(refresh_token.clone(), RefreshSession {
+            user_id: user.id,
+            access_token: token.clone(),
+            expires_at: refresh_expires_at,
+        });
+        drop(refresh_sessions);
         
         Ok(LoginResponse {
             token,
             user,
             expires_at,
+            refresh_token,
+            refresh_expires_at,
         })
     }
     
     pub async fn logout(&self, token: &str) -> Result<()> {
         let mut sessions = self.user_sessions.write().await;
         sessions.remove(token);
+        drop(sessions);
+        
+        // The refresh token issued with this access token goes too
+        let mut refresh_sessions = self.refresh_sessions.write().await;
+        refresh_sessions.retain(|_, session| session.access_token != token);
         Ok(())
     }
     
@@ -375,6 +539,16 @@ impl AuthManager {
         Ok(user.clone())
     }
     
+    /// Permissions listed for a role; admins hold every permission regardless
+    pub async fn role_permissions(&self, role: &UserRole) -> Vec<Permission> {
+        let roles = self.roles.read().await;
+        roles
+            .values()
+            .find(|r| r.name == format!("{:?}", role))
+            .map(|r| r.permissions.clone())
+            .unwrap_or_default()
+    }
+
     pub async fn has_permission(&self, user_id: Uuid, permission: &Permission) -> bool {
         let users = self.users.read().await;
         if let Some(user) = users.get(&user_id) {
@@ -399,6 +573,16 @@ impl AuthManager {
         self.users.read().await.get(&user_id).cloned()
     }
     
+    /// The admin account the local desktop app acts as when it sends no token
+    pub async fn local_admin(&self) -> Option<User> {
+        let users = self.users.read().await;
+        users
+            .values()
+            .filter(|user| user.is_active && user.role == UserRole::Admin)
+            .min_by_key(|user| user.created_at)
+            .cloned()
+    }
+
     pub async fn get_all_users(&self) -> Vec<User> {
         self.users.read().await.values().cloned().collect()
     }
@@ -418,11 +602,14 @@ impl AuthManager {
             if let Some(is_active) = updates.is_active {
                 user.is_active = is_active;
             }
+            if let Some(server_ids) = updates.server_ids {
+                user.server_ids = server_ids;
+            }
             user.updated_at = chrono::Utc::now();
             
             Ok(user.clone())
         } else {
-            Err(anyhow!("User not found"))
+            Err(NotFound::new("User").into())
         }
     }
     
@@ -436,6 +623,100 @@ impl AuthManager {
         self.roles.read().await.values().cloned().collect()
     }
     
+    fn token_database(&self) -> Result<&DatabaseManager> {
+        self.database.as_deref().ok_or_else(|| anyhow!("API tokens are not available without a database"))
+    }
+    
+    /// Create an API token. A token can never do more than the user creating it.
+    pub async fn create_api_token(&self, creator: &User, request: CreateApiTokenRequest) -> Result<CreatedApiToken> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            bail!("Token name is required");
+        }
+        
+        let mut scopes = request.scopes;
+        if let Some(preset) = &request.preset {
+            let preset = scope_preset(preset).ok_or_else(|| anyhow!("Unknown scope preset '{}'", preset))?;
+            for permission in preset {
+                if !scopes.contains(&permission) {
+                    scopes.push(permission);
+                }
+            }
+        }
+        if scopes.is_empty() {
+            bail!("A token needs at least one scope");
+        }
+        for permission in &scopes {
+            if !self.has_permission(creator.id, permission).await {
+                bail!("You do not have the {:?} permission", permission);
+            }
+        }
+        
+        let server_ids = if creator.role == UserRole::Admin || creator.server_ids.is_empty() {
+            request.server_ids
+        } else if request.server_ids.is_empty() {
+            creator.server_ids.clone()
+        } else if request.server_ids.iter().all(|id| creator.server_ids.contains(id)) {
+            request.server_ids
+        } else {
+            bail!("Tokens can only be scoped to servers you have access to");
+        };
+        
+        let token = format!("{}{}", API_TOKEN_PREFIX, random_hex(32));
+        let now = chrono::Utc::now();
+        let api_token = ApiToken {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            token_hash: hash_api_token(&token),
+            token_prefix: token[..API_TOKEN_PREFIX.len() + 8].to_string(),
+            scopes,
+            server_ids,
+            created_by: creator.username.clone(),
+            created_at: now,
+            expires_at: request.expires_in_days.map(|days| now + chrono::Duration::days(days as i64)),
+            last_used_at: None,
+            revoked_at: None,
+        };
+        self.token_database()?.create_api_token(&api_token).await?;
+        
+        Ok(CreatedApiToken { token, api_token })
+    }
+    
+    /// Tokens the user created; admins see every token
+    pub async fn list_api_tokens(&self, user: &User) -> Result<Vec<ApiToken>> {
+        let tokens = self.token_database()?.get_api_tokens().await?;
+        Ok(tokens.into_iter()
+            .filter(|token| user.role == UserRole::Admin || token.created_by == user.username)
+            .collect())
+    }
+    
+    pub async fn revoke_api_token(&self, user: &User, token_id: &str) -> Result<()> {
+        let database = self.token_database()?;
+        let token = database.get_api_token(token_id).await?
+            .filter(|token| user.role == UserRole::Admin || token.created_by == user.username)
+            .ok_or_else(|| NotFound::new("Token"))?;
+        if !database.revoke_api_token(&token.id).await? {
+            bail!("Token is already revoked");
+        }
+        Ok(())
+    }
+    
+    pub async fn validate_api_token(&self, token: &str) -> Result<ApiToken> {
+        let database = self.token_database()?;
+        let api_token = database.get_api_token_by_hash(&hash_api_token(token)).await?
+            .ok_or_else(|| anyhow!("Invalid token"))?;
+        if api_token.revoked_at.is_some() {
+            bail!("Token has been revoked");
+        }
+        if api_token.expires_at.is_some_and(|expires_at| expires_at < chrono::Utc::now()) {
+            bail!("Token has expired");
+        }
+        if let Err(e) = database.touch_api_token(&api_token.id).await {
+            tracing::warn!("Failed to record use of API token {}: {}", api_token.id, e);
+        }
+        Ok(api_token)
+    }
+    
     fn hash_password(&self, password: &str) -> Result<String> {
         let salt = SaltString::generate(&mut OsRng);
         let argon2 = Argon2::default();
@@ -487,25 +768,112 @@ pub struct UserUpdate {
     pub email: Option<String>,
     pub role: Option<UserRole>,
     pub is_active: Option<bool>,
+    /// Replaces the user's server list; an empty list grants all servers
+    pub server_ids: Option<Vec<String>>,
 }
 
 impl UserRole {
     pub fn as_str(&self) -> &'static str {
         match self {
             UserRole::Admin => "admin",
-            UserRole::Moderator => "moderator",
-            UserRole::User => "user",
-            UserRole::ReadOnly => "readonly",
+            UserRole::Operator => "operator",
+            UserRole::Viewer => "viewer",
         }
     }
     
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "admin" => Some(UserRole::Admin),
-            "moderator" => Some(UserRole::Moderator),
-            "user" => Some(UserRole::User),
-            "readonly" => Some(UserRole::ReadOnly),
+            "operator" | "moderator" => Some(UserRole::Operator),
+            "viewer" | "user" | "readonly" => Some(UserRole::Viewer),
             _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn token_manager(dir: &Path) -> (AuthManager, User) {
+        let database = DatabaseManager::new(&format!("sqlite:{}", dir.join("auth.db").display())).await.unwrap();
+        let auth = AuthManager::new("test-secret".to_string(), Arc::new(CredentialManager::new()))
+            .with_database(Arc::new(database));
+        auth.initialize().await.unwrap();
+        let admin = auth.local_admin().await.unwrap();
+        (auth, admin)
+    }
+
+    fn token_request(name: &str) -> CreateApiTokenRequest {
+        CreateApiTokenRequest {
+            name: name.to_string(),
+            scopes: vec![Permission::ViewServer],
+            preset: None,
+            server_ids: vec![],
+            expires_in_days: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_api_token_is_prefixed_and_found_by_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let (auth, admin) = token_manager(dir.path()).await;
+
+        let created = auth.create_api_token(&admin, token_request("ci")).await.unwrap();
+        assert!(created.token.starts_with(API_TOKEN_PREFIX));
+        assert!(created.token.starts_with(&created.api_token.token_prefix));
+        // Only the hash is kept
+        assert_eq!(created.api_token.token_hash, hash_api_token(&created.token));
+        assert_ne!(created.api_token.token_hash, created.token);
+
+        let found = auth.validate_api_token(&created.token).await.unwrap();
+        assert_eq!(found.id, created.api_token.id);
+        assert!(auth.validate_api_token(&format!("{}{}", API_TOKEN_PREFIX, random_hex(32))).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_revoked_and_expired_api_tokens_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let (auth, admin) = token_manager(dir.path()).await;
+
+        let created = auth.create_api_token(&admin, token_request("revoked")).await.unwrap();
+        auth.revoke_api_token(&admin, &created.api_token.id).await.unwrap();
+        let error = auth.validate_api_token(&created.token).await.unwrap_err();
+        assert!(error.to_string().contains("revoked"));
+        assert!(auth.revoke_api_token(&admin, &created.api_token.id).await.is_err());
+
+        let token = format!("{}{}", API_TOKEN_PREFIX, random_hex(32));
+        let mut expired = created.api_token.clone();
+        expired.id = Uuid::new_v4().to_string();
+        expired.token_hash = hash_api_token(&token);
+        expired.revoked_at = None;
+        expired.expires_at = Some(chrono::Utc::now() - chrono::Duration::days(1));
+        auth.token_database().unwrap().create_api_token(&expired).await.unwrap();
+        let error = auth.validate_api_token(&token).await.unwrap_err();
+        assert!(error.to_string().contains("expired"));
+    }
+
+    #[tokio::test]
+    async fn test_api_token_cannot_exceed_its_creator() {
+        let dir = tempfile::tempdir().unwrap();
+        let (auth, admin) = token_manager(dir.path()).await;
+        let mut viewer = admin.clone();
+        viewer.id = Uuid::new_v4();
+        viewer.username = "viewer".to_string();
+        viewer.role = UserRole::Viewer;
+        viewer.server_ids = vec!["s1".to_string()];
+        auth.users.write().await.insert(viewer.id, viewer.clone());
+
+        let mut request = token_request("too-strong");
+        request.scopes = vec![Permission::DeleteServer];
+        assert!(auth.create_api_token(&viewer, request).await.is_err());
+
+        let mut request = token_request("other-server");
+        request.server_ids = vec!["s2".to_string()];
+        assert!(auth.create_api_token(&viewer, request).await.is_err());
+
+        // Unscoped requests inherit the creator's servers
+        let created = auth.create_api_token(&viewer, token_request("inherits")).await.unwrap();
+        assert_eq!(created.api_token.server_ids, vec!["s1".to_string()]);
+    }
+}
@@ -186,7 +186,7 @@ where
         let entry = CacheEntry::new(value, ttl);
         entries.insert(key, entry);
         
-        self.update_metrics().await;
+        self.update_metrics(entries.len()).await;
     }
     
     /// Remove a value from the cache
@@ -199,7 +199,7 @@ where
     pub async fn clear(&self) {
         let mut entries = self.entries.write().await;
         entries.clear();
-        self.update_metrics().await;
+        self.update_metrics(0).await;
     }
     
     /// Check if the cache contains a key
@@ -343,11 +343,11 @@ where
     }
     
     /// Update cache metrics
-    async fn update_metrics(&self) {
+    /// Callers hold the entries lock, so they pass the count in
+    async fn update_metrics(&self, entries: usize) {
         if self.config.enable_metrics {
-            let entries = self.entries.read().await;
             let mut metrics = self.metrics.write().await;
-            metrics.entries = entries.len();
+            metrics.entries = entries;
             // Note: memory_usage would need to be calculated based on actual memory usage
         }
     }
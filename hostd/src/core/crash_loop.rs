@@ -0,0 +1,180 @@
+//! Crash-loop detection: a server that keeps crashing right after being
+//! restarted is left stopped instead of being restarted forever, optionally
+//! with its most recently added mods disabled.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Suffix given to mod jars the watchdog disabled; mod loaders skip anything not ending in `.jar`
+pub const DISABLED_SUFFIX: &str = ".disabled";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrashLoopSettings {
+    /// Crashes within the window that count as a crash loop
+    pub max_crashes: u32,
+    pub window_seconds: u64,
+    /// Number of most recently added mods to disable once a loop is detected (0 = none)
+    pub disable_recent_mods: u32,
+}
+
+impl Default for CrashLoopSettings {
+    fn default() -> Self {
+        Self {
+            max_crashes: 3,
+            window_seconds: 600,
+            disable_recent_mods: 0,
+        }
+    }
+}
+
+impl CrashLoopSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_crashes < 2 {
+            return Err("A crash loop needs at least 2 crashes".to_string());
+        }
+        if self.window_seconds == 0 {
+            return Err("Crash loop window must be longer than 0 seconds".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Recent crashes of one server
+#[derive(Debug, Clone, Default)]
+pub struct CrashHistory {
+    crashes: VecDeque<Instant>,
+}
+
+impl CrashHistory {
+    /// Record a crash and report whether the server is now crash-looping
+    pub fn record(&mut self, settings: &CrashLoopSettings, now: Instant) -> bool {
+        self.crashes.push_back(now);
+        self.prune(settings, now);
+        self.crashes.len() >= settings.max_crashes as usize
+    }
+
+    /// Crashes still inside the window
+    pub fn count(&mut self, settings: &CrashLoopSettings, now: Instant) -> usize {
+        self.prune(settings, now);
+        self.crashes.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.crashes.clear();
+    }
+
+    fn prune(&mut self, settings: &CrashLoopSettings, now: Instant) {
+        let window = Duration::from_secs(settings.window_seconds);
+        while self.crashes.front().is_some_and(|crash| now.duration_since(*crash) > window) {
+            self.crashes.pop_front();
+        }
+    }
+}
+
+/// Mod jars in `mods_dir`, newest first. Creation time is used where the
+/// filesystem records it, so a jar that was merely touched does not jump ahead.
+pub fn recent_mods(mods_dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut mods = Vec::new();
+    for entry in std::fs::read_dir(mods_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| !ext.eq_ignore_ascii_case("jar")) {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        let added = metadata.created().or_else(|_| metadata.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
+        mods.push((added, path));
+    }
+    mods.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    Ok(mods.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Disable the `count` most recently added mods by renaming them to `*.jar.disabled`.
+/// Returns the file names of the mods that were disabled.
+pub fn disable_recent_mods(mods_dir: &Path, count: usize) -> std::io::Result<Vec<String>> {
+    if count == 0 || !mods_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut disabled = Vec::new();
+    for path in recent_mods(mods_dir)?.into_iter().take(count) {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()).map(str::to_string) else {
+            continue;
+        };
+        std::fs::rename(&path, mods_dir.join(format!("{}{}", name, DISABLED_SUFFIX)))?;
+        disabled.push(name);
+    }
+    Ok(disabled)
+}
+
+/// Newest file in the server's `crash-reports/` folder
+pub fn latest_crash_report(server_dir: &Path) -> Option<String> {
+    std::fs::read_dir(server_dir.join("crash-reports"))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.file_name())))
+        .max_by_key(|(modified, _)| *modified)
+        .and_then(|(_, name)| name.into_string().ok())
+}
+
+/// What the operator should do about a crash loop
+pub fn recommended_action(disabled_mods: &[String], crash_report: Option<&str>) -> String {
+    let mut action = if disabled_mods.is_empty() {
+        "Automatic restarts are paused. Check recently added or updated mods and config changes.".to_string()
+    } else {
+        format!(
+            "Automatic restarts are paused and the most recently added mods were disabled ({}). \
+             Start the server to confirm it is stable, then re-enable them one at a time.",
+            disabled_mods.join(", ")
+        )
+    };
+    if let Some(report) = crash_report {
+        action.push_str(&format!(" See crash-reports/{} for the cause.", report));
+    }
+    action.push_str(" Clear the crash loop to resume automatic restarts.");
+    action
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_loop_needs_crashes_inside_window() {
+        let settings = CrashLoopSettings::default();
+        let mut history = CrashHistory::default();
+        let start = Instant::now();
+
+        assert!(!history.record(&settings, start));
+        assert!(!history.record(&settings, start + Duration::from_secs(300)));
+        // The first crash has left the 10 minute window
+        assert!(!history.record(&settings, start + Duration::from_secs(700)));
+        assert_eq!(history.count(&settings, start + Duration::from_secs(700)), 2);
+        assert!(history.record(&settings, start + Duration::from_secs(800)));
+
+        history.clear();
+        assert!(!history.record(&settings, start + Duration::from_secs(801)));
+    }
+
+    #[test]
+    fn test_disable_recent_mods_renames_newest_jars() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["old.jar", "middle.jar", "new.jar"] {
+            std::fs::write(dir.path().join(name), b"jar").unwrap();
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        std::fs::write(dir.path().join("notes.txt"), b"").unwrap();
+
+        let disabled = disable_recent_mods(dir.path(), 2).unwrap();
+        assert_eq!(disabled, vec!["new.jar", "middle.jar"]);
+        assert!(dir.path().join("new.jar.disabled").exists());
+        assert!(dir.path().join("old.jar").exists());
+        assert!(disable_recent_mods(&dir.path().join("missing"), 2).unwrap().is_empty());
+
+        let action = recommended_action(&disabled, Some("crash-2024.txt"));
+        assert!(action.contains("new.jar, middle.jar"));
+        assert!(action.contains("crash-reports/crash-2024.txt"));
+    }
+}
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
@@ -5,14 +6,20 @@ use tokio::time::interval;
 use uuid::Uuid;
 use serde::Serialize;
 use tracing::{info, warn, error};
+use sysinfo::{Pid, System};
 
 use crate::core::{
     error_handler::{AppError, Result},
     process_manager::{ProcessManager, ServerState},
     monitoring::MonitoringManager,
     retry_backoff::{RetryManager, RetryConfig, with_crash_recovery_retry},
+    restart_policy::{PolicySample, PolicyState, RestartPolicy},
+    crash_loop::{self, CrashHistory, CrashLoopSettings},
 };
-use crate::database::DatabaseManager;
+use crate::database::{DatabaseManager, EventLog, ServerConfig};
+use crate::rcon::RconClient;
+use crate::restart_scheduler::{announce, format_remaining, rcon};
+use crate::websocket_manager::WebSocketManager;
 
 /// Crash detection configuration
 #[derive(Debug, Clone)]
@@ -21,6 +28,8 @@ pub struct WatchdogConfig {
     pub check_interval: Duration,
     pub max_restart_attempts: u32,
     pub restart_cooldown: Duration,
+    /// How often servers with a restart policy are sampled
+    pub policy_check_interval: Duration,
 }
 
 impl Default for WatchdogConfig {
@@ -30,6 +39,7 @@ impl Default for WatchdogConfig {
             check_interval: Duration::from_secs(1),
             max_restart_attempts: 3,
             restart_cooldown: Duration::from_secs(30),
+            policy_check_interval: Duration::from_secs(30),
         }
     }
 }
@@ -41,6 +51,8 @@ pub enum ServerHealth {
     Hanging,
     Crashed,
     Restarting,
+    /// Crashed too often in a short time; left stopped until the crash loop is cleared
+    CrashLooping,
 }
 
 /// Server watchdog state
@@ -52,6 +64,50 @@ struct ServerWatchdogState {
     restart_attempts: u32,
     last_restart: Option<Instant>,
     hang_start: Option<Instant>,
+    crashes: CrashHistory,
+    crash_loop: Option<CrashLoopReport>,
+}
+
+/// Why a server was put into crash-loop safe mode and what to do next
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashLoopReport {
+    pub detected_at: chrono::DateTime<chrono::Utc>,
+    pub crashes: usize,
+    pub window_seconds: u64,
+    /// Mod jars renamed to `*.jar.disabled`, newest first
+    pub disabled_mods: Vec<String>,
+    /// Latest file in `crash-reports/`, if any
+    pub crash_report: Option<String>,
+    pub recommended_action: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashLoopStatus {
+    pub crash_looping: bool,
+    /// Crashes inside the current window
+    pub recent_crashes: usize,
+    pub settings: CrashLoopSettings,
+    pub report: Option<CrashLoopReport>,
+}
+
+/// A server's restart policy together with how close it is to triggering
+#[derive(Debug, Clone, Serialize)]
+pub struct RestartPolicyStatus {
+    pub policy: RestartPolicy,
+    pub last_sample: PolicySample,
+    /// Seconds each rule's condition has been holding, in rule order
+    pub breaches: Vec<Option<u64>>,
+    pub last_restart: Option<chrono::DateTime<chrono::Utc>>,
+    pub restarting: bool,
+    pub cooldown_remaining_seconds: u64,
+}
+
+fn database_error(e: anyhow::Error, operation: &str, table: &str) -> AppError {
+    AppError::DatabaseError {
+        message: e.to_string(),
+        operation: operation.to_string(),
+        table: Some(table.to_string()),
+    }
 }
 
 /// Crash watchdog system
@@ -61,6 +117,9 @@ pub struct CrashWatchdog {
     monitoring: Arc<MonitoringManager>,
     database: Arc<DatabaseManager>,
     server_states: Arc<RwLock<std::collections::HashMap<Uuid, ServerWatchdogState>>>,
+    policy_states: Arc<RwLock<std::collections::HashMap<Uuid, PolicyState>>>,
+    websocket_manager: Option<Arc<WebSocketManager>>,
+    stopped: Arc<RwLock<bool>>,
 }
 
 impl CrashWatchdog {
@@ -76,9 +135,18 @@ impl CrashWatchdog {
             monitoring,
             database,
             server_states: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            policy_states: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            websocket_manager: None,
+            stopped: Arc::new(RwLock::new(false)),
         }
     }
 
+    /// Send alerts such as crash loops to WebSocket clients
+    pub fn with_websocket_manager(mut self, websocket_manager: Arc<WebSocketManager>) -> Self {
+        self.websocket_manager = Some(websocket_manager);
+        self
+    }
+
     /// Start the watchdog monitoring loop
     pub async fn start(&self) -> Result<()> {
         info!("Starting crash watchdog with config: {:?}", self.config);
@@ -87,6 +155,10 @@ impl CrashWatchdog {
         
         loop {
             interval.tick().await;
+            if *self.stopped.read().await {
+                info!("Crash watchdog stopped");
+                return Ok(());
+            }
             
             if let Err(e) = self.check_all_servers().await {
                 error!("Error in watchdog check: {}", e);
@@ -94,6 +166,12 @@ impl CrashWatchdog {
         }
     }
 
+    /// Stop the monitoring and restart policy loops, so servers stopped
+    /// during shutdown are not restarted
+    pub async fn stop(&self) {
+        *self.stopped.write().await = true;
+    }
+
     /// Register a server for monitoring
     pub async fn register_server(&self, server_id: Uuid) -> Result<()> {
         let mut states = self.server_states.write().await;
@@ -104,6 +182,8 @@ impl CrashWatchdog {
             restart_attempts: 0,
             last_restart: None,
             hang_start: None,
+            crashes: CrashHistory::default(),
+            crash_loop: None,
         });
         
         info!("Registered server {} for crash monitoring", server_id);
@@ -136,99 +216,240 @@ impl CrashWatchdog {
 
     /// Check all registered servers for crashes
     async fn check_all_servers(&self) -> Result<()> {
-        let mut states = self.server_states.write().await;
-        let mut to_remove = Vec::new();
-        
-        for (server_id, state) in states.iter_mut() {
-            // Check server state first
-            let server_state = self.process_manager.get_server_state(*server_id).await;
-            
-            // Check if server process has crashed
-            if !self.process_manager.is_server_running(*server_id).await {
-                if state.health != ServerHealth::Crashed {
-                    state.health = ServerHealth::Crashed;
-                    warn!("Server {} has crashed (process not running)", server_id);
-                    
-                    // Log crash immediately
-                    self.log_crash_event(*server_id, "Process terminated unexpectedly").await?;
-                    
-                    // Try to restart if within limits
-                    if self.should_attempt_restart(state) {
-                        if let Err(e) = self.attempt_restart(*server_id).await {
-                            error!("Failed to restart crashed server {}: {}", server_id, e);
-                            self.log_crash_event(*server_id, &format!("Restart failed: {}", e)).await?;
-                        }
+        let mut crashed = Vec::new();
+        let mut hung = Vec::new();
+
+        // Restarts take the state lock themselves, so they run once it is released
+        {
+            let mut states = self.server_states.write().await;
+            let mut to_remove = Vec::new();
+
+            for (server_id, state) in states.iter_mut() {
+                // A policy restart stops the server on purpose
+                if self.policy_states.read().await.get(server_id).is_some_and(|policy| policy.restarting) {
+                    continue;
+                }
+                // Already being brought back up, or deliberately left down
+                if matches!(state.health, ServerHealth::Restarting | ServerHealth::CrashLooping) {
+                    continue;
+                }
+
+                // Check server state first
+                let server_state = self.process_manager.get_server_state(*server_id).await;
+
+                // Check if server process has crashed
+                if !self.process_manager.is_server_running(*server_id).await {
+                    if state.health != ServerHealth::Crashed {
+                        state.health = ServerHealth::Crashed;
+                        warn!("Server {} has crashed (process not running)", server_id);
+                        crashed.push((*server_id, "Process terminated unexpectedly"));
                     }
+                    continue;
                 }
-                continue;
-            }
-            
-            // Check if server state indicates a crash
-            if matches!(server_state, ServerState::Crashed) {
-                if state.health != ServerHealth::Crashed {
-                    state.health = ServerHealth::Crashed;
-                    warn!("Server {} state indicates crash", server_id);
-                    
-                    // Log crash immediately
-                    self.log_crash_event(*server_id, "Server state indicates crash").await?;
-                    
-                    // Try to restart if within limits
-                    if self.should_attempt_restart(state) {
-                        if let Err(e) = self.attempt_restart(*server_id).await {
-                            error!("Failed to restart crashed server {}: {}", server_id, e);
-                            self.log_crash_event(*server_id, &format!("Restart failed: {}", e)).await?;
-                        }
+
+                // Check if server state indicates a crash
+                if matches!(server_state, ServerState::Crashed) {
+                    if state.health != ServerHealth::Crashed {
+                        state.health = ServerHealth::Crashed;
+                        warn!("Server {} state indicates crash", server_id);
+                        crashed.push((*server_id, "Server state indicates crash"));
                     }
+                    continue;
                 }
-                continue;
-            }
 
-            // Check for hangs
-            let time_since_heartbeat = state.last_heartbeat.elapsed();
-            if time_since_heartbeat > self.config.hang_threshold {
-                if state.health == ServerHealth::Healthy {
-                    state.health = ServerHealth::Hanging;
-                    state.hang_start = Some(Instant::now());
-                    warn!("Server {} appears to be hanging (no heartbeat for {:?})", 
-                          server_id, time_since_heartbeat);
-                } else if state.health == ServerHealth::Hanging {
-                    // Check if we should restart due to prolonged hang
-                    if let Some(hang_start) = state.hang_start {
-                        let hang_duration = hang_start.elapsed();
-                        if hang_duration > Duration::from_secs(30) { // 30 seconds of hanging
-                            warn!("Server {} has been hanging for {:?}, attempting restart", 
-                                  server_id, hang_duration);
-                            
-                            if self.should_attempt_restart(state) {
-                                if let Err(e) = self.attempt_restart(*server_id).await {
-                                    error!("Failed to restart hanging server {}: {}", server_id, e);
+                if state.health == ServerHealth::Crashed {
+                    // Started again after a crash that was not restarted automatically
+                    state.health = ServerHealth::Healthy;
+                    state.last_heartbeat = Instant::now();
+                    info!("Server {} is running again", server_id);
+                }
+
+                // Check for hangs
+                let time_since_heartbeat = state.last_heartbeat.elapsed();
+                if time_since_heartbeat > self.config.hang_threshold {
+                    if state.health == ServerHealth::Healthy {
+                        state.health = ServerHealth::Hanging;
+                        state.hang_start = Some(Instant::now());
+                        warn!("Server {} appears to be hanging (no heartbeat for {:?})", 
+                              server_id, time_since_heartbeat);
+                    } else if state.health == ServerHealth::Hanging {
+                        // Check if we should restart due to prolonged hang
+                        if let Some(hang_start) = state.hang_start {
+                            let hang_duration = hang_start.elapsed();
+                            if hang_duration > Duration::from_secs(30) { // 30 seconds of hanging
+                                warn!("Server {} has been hanging for {:?}, attempting restart", 
+                                      server_id, hang_duration);
+
+                                if self.should_attempt_restart(state) {
+                                    hung.push(*server_id);
                                 }
                             }
                         }
                     }
+                } else if state.health == ServerHealth::Hanging {
+                    // Server recovered
+                    state.health = ServerHealth::Healthy;
+                    state.hang_start = None;
+                    info!("Server {} recovered from hang", server_id);
+                }
+
+                // Check if server should be removed (too many failed restarts)
+                if state.restart_attempts >= self.config.max_restart_attempts {
+                    error!("Server {} has exceeded maximum restart attempts, removing from monitoring", server_id);
+                    to_remove.push(*server_id);
                 }
-            } else if state.health == ServerHealth::Hanging {
-                // Server recovered
-                state.health = ServerHealth::Healthy;
-                state.hang_start = None;
-                info!("Server {} recovered from hang", server_id);
             }
 
-            // Check if server should be removed (too many failed restarts)
-            if state.restart_attempts >= self.config.max_restart_attempts {
-                error!("Server {} has exceeded maximum restart attempts, removing from monitoring", server_id);
-                to_remove.push(*server_id);
+            // Remove servers that exceeded restart limits
+            for server_id in to_remove {
+                states.remove(&server_id);
             }
         }
 
-        // Remove servers that exceeded restart limits
-        for server_id in to_remove {
-            states.remove(&server_id);
+        for (server_id, reason) in crashed {
+            // Log crash immediately
+            self.log_crash_event(server_id, reason).await?;
+            self.handle_crash(server_id).await?;
         }
 
+        for server_id in hung {
+            if let Err(e) = self.attempt_restart(server_id).await {
+                error!("Failed to restart hanging server {}: {}", server_id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Restart a crashed server, unless it crashed too often within the crash-loop window
+    async fn handle_crash(&self, server_id: Uuid) -> Result<()> {
+        let settings = self.get_restart_policy(server_id).await
+            .map(|policy| policy.crash_loop)
+            .unwrap_or_default();
+
+        let (crash_loop, restart) = {
+            let mut states = self.server_states.write().await;
+            let Some(state) = states.get_mut(&server_id) else {
+                return Ok(());
+            };
+            let now = Instant::now();
+            if state.crashes.record(&settings, now) {
+                state.health = ServerHealth::CrashLooping;
+                (Some(state.crashes.count(&settings, now)), false)
+            } else {
+                (None, self.should_attempt_restart(state))
+            }
+        };
+
+        if let Some(crashes) = crash_loop {
+            self.enter_crash_loop(server_id, &settings, crashes).await;
+        } else if restart {
+            if let Err(e) = self.attempt_restart(server_id).await {
+                error!("Failed to restart crashed server {}: {}", server_id, e);
+                self.log_crash_event(server_id, &format!("Restart failed: {}", e)).await?;
+            }
+        }
         Ok(())
     }
 
+    /// Leave a crash-looping server stopped, disable its newest mods if configured,
+    /// and tell the operator what to do
+    async fn enter_crash_loop(&self, server_id: Uuid, settings: &CrashLoopSettings, crashes: usize) {
+        error!("Server {} crashed {} times within {}s, pausing automatic restarts",
+               server_id, crashes, settings.window_seconds);
+
+        let server_dir = match self.database.get_server(&server_id.to_string()).await {
+            Ok(Some(server)) => Some(PathBuf::from(server.server_directory)),
+            _ => None,
+        };
+        let count = settings.disable_recent_mods as usize;
+        let (disabled_mods, crash_report) = match server_dir {
+            Some(dir) => tokio::task::spawn_blocking(move || {
+                let disabled = crash_loop::disable_recent_mods(&dir.join("mods"), count).unwrap_or_else(|e| {
+                    warn!("Failed to disable recent mods of server {}: {}", server_id, e);
+                    Vec::new()
+                });
+                (disabled, crash_loop::latest_crash_report(&dir))
+            }).await.unwrap_or_default(),
+            None => (Vec::new(), None),
+        };
+
+        let message = format!(
+            "Server crashed {} times within {}; automatic restarts are paused",
+            crashes,
+            format_remaining(settings.window_seconds.min(u32::MAX as u64) as u32)
+        );
+        let report = CrashLoopReport {
+            detected_at: chrono::Utc::now(),
+            crashes,
+            window_seconds: settings.window_seconds,
+            recommended_action: crash_loop::recommended_action(&disabled_mods, crash_report.as_deref()),
+            disabled_mods,
+            crash_report,
+        };
+        if let Some(state) = self.server_states.write().await.get_mut(&server_id) {
+            state.crash_loop = Some(report.clone());
+        }
+
+        let event = EventLog {
+            id: Uuid::new_v4().to_string(),
+            server_id: Some(server_id.to_string()),
+            event_type: "crash_loop".to_string(),
+            message: message.clone(),
+            level: "error".to_string(),
+            metadata: serde_json::to_value(&report).ok(),
+            created_at: chrono::Utc::now(),
+        };
+        if let Err(e) = self.database.log_event(&event).await {
+            error!("Failed to log crash loop of server {}: {}", server_id, e);
+        }
+
+        if let Some(websocket_manager) = &self.websocket_manager {
+            if let Err(e) = websocket_manager
+                .send_alert(server_id, "error", "crash_loop", &message, Some(&report.recommended_action))
+                .await
+            {
+                warn!("Failed to send crash loop alert for server {}: {}", server_id, e);
+            }
+        }
+    }
+
+    pub async fn get_crash_loop_status(&self, server_id: Uuid) -> Result<CrashLoopStatus> {
+        let settings = self.get_restart_policy(server_id).await?.crash_loop;
+        let mut states = self.server_states.write().await;
+        let (crash_looping, recent_crashes, report) = match states.get_mut(&server_id) {
+            Some(state) => (
+                state.health == ServerHealth::CrashLooping,
+                state.crashes.count(&settings, Instant::now()),
+                state.crash_loop.clone(),
+            ),
+            None => (false, 0, None),
+        };
+        Ok(CrashLoopStatus { crash_looping, recent_crashes, settings, report })
+    }
+
+    /// Resume automatic restarts of a crash-looping server. The server stays
+    /// stopped until it is started; disabled mods are left disabled.
+    pub async fn clear_crash_loop(&self, server_id: Uuid) -> Result<CrashLoopStatus> {
+        {
+            let mut states = self.server_states.write().await;
+            let state = states.get_mut(&server_id).ok_or_else(|| AppError::ValidationError {
+                message: format!("Server {} is not monitored", server_id),
+                field: "server_id".to_string(),
+                value: server_id.to_string(),
+                constraint: "registered with the watchdog".to_string(),
+            })?;
+            state.crashes.clear();
+            state.crash_loop = None;
+            state.restart_attempts = 0;
+            if state.health == ServerHealth::CrashLooping {
+                state.health = ServerHealth::Crashed;
+            }
+        }
+        info!("Cleared crash loop of server {}", server_id);
+        self.get_crash_loop_status(server_id).await
+    }
+
     /// Check if we should attempt a restart with exponential backoff
     fn should_attempt_restart(&self, state: &ServerWatchdogState) -> bool {
         if state.restart_attempts >= self.config.max_restart_attempts {
@@ -262,16 +483,19 @@ impl CrashWatchdog {
             error!("Failed to log crash event for server {}: {}", server_id, e);
         }
         
-        // Log to monitoring system
-        // TODO: Implement record_event method in MonitoringManager
-        // if let Err(e) = self.monitoring.record_event(
-        //     server_id,
-        //     "server_crash",
-        //     &format!("Server crashed: {}", reason),
-        //     Some(timestamp),
-        // ).await {
-        //     error!("Failed to record crash event in monitoring: {}", e);
-        // }
+        // Crash events drive alert rules
+        let event = EventLog {
+            id: Uuid::new_v4().to_string(),
+            server_id: Some(server_id.to_string()),
+            event_type: "server_crash".to_string(),
+            message: format!("Server crashed: {}", reason),
+            level: "error".to_string(),
+            metadata: Some(serde_json::json!({ "reason": reason, "source": "crash_watchdog" })),
+            created_at: timestamp,
+        };
+        if let Err(e) = self.database.log_event(&event).await {
+            error!("Failed to record crash event for server {}: {}", server_id, e);
+        }
         
         Ok(())
     }
@@ -286,11 +510,37 @@ impl CrashWatchdog {
         }
         drop(states); // Release the lock before the async operation
 
-        // Use retry backoff for the restart operation
-        let restart_result = with_crash_recovery_retry(|| {
+        match self.restart_process(server_id).await {
+            Ok(_) => {
+                info!("Successfully restarted server {}", server_id);
+                
+                // Reset restart attempts on successful restart
+                let mut states = self.server_states.write().await;
+                if let Some(state) = states.get_mut(&server_id) {
+                    state.restart_attempts = 0;
+                    state.health = ServerHealth::Healthy;
+                    state.last_heartbeat = Instant::now();
+                }
+            }
+            Err(e) => {
+                error!("Failed to restart server {} after retries: {}", server_id, e);
+                
+                // Update state to reflect restart failure
+                let mut states = self.server_states.write().await;
+                if let Some(state) = states.get_mut(&server_id) {
+                    state.health = ServerHealth::Crashed;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stop the server gracefully and start it again, retrying with backoff
+    async fn restart_process(&self, server_id: Uuid) -> Result<()> {
+        with_crash_recovery_retry(|| {
             let process_manager = self.process_manager.clone();
             let database = self.database.clone();
-            let server_id = server_id;
             
             async move {
                 // Stop the server gracefully first
@@ -319,34 +569,199 @@ impl CrashWatchdog {
                 
                 Ok::<(), AppError>(())
             }
-        }).await;
+        }).await
+    }
 
-        match restart_result {
-            Ok(_) => {
-                info!("Successfully restarted server {}", server_id);
-                
-                // Reset restart attempts on successful restart
-                let mut states = self.server_states.write().await;
-                if let Some(state) = states.get_mut(&server_id) {
-                    state.restart_attempts = 0;
-                    state.health = ServerHealth::Healthy;
-                    state.last_heartbeat = Instant::now();
+    /// Restart policy of a server; the (disabled) default when none is stored
+    pub async fn get_restart_policy(&self, server_id: Uuid) -> Result<RestartPolicy> {
+        let stored = self.database.get_restart_policy(&server_id.to_string()).await
+            .map_err(|e| database_error(e, "get_restart_policy", "restart_policies"))?;
+        Ok(stored.and_then(|value| serde_json::from_value(value).ok()).unwrap_or_default())
+    }
+
+    pub async fn set_restart_policy(&self, server_id: Uuid, policy: RestartPolicy) -> Result<()> {
+        policy.validate().map_err(|message| AppError::ValidationError {
+            message,
+            field: "rules".to_string(),
+            value: String::new(),
+            constraint: "valid restart policy".to_string(),
+        })?;
+        let value = serde_json::to_value(&policy)
+            .map_err(|e| database_error(e.into(), "save_restart_policy", "restart_policies"))?;
+        self.database.save_restart_policy(&server_id.to_string(), &value).await
+            .map_err(|e| database_error(e, "save_restart_policy", "restart_policies"))?;
+        if !policy.enabled {
+            self.policy_states.write().await.remove(&server_id);
+        }
+        info!("Updated restart policy for server {} (enabled: {})", server_id, policy.enabled);
+        Ok(())
+    }
+
+    pub async fn get_restart_policy_status(&self, server_id: Uuid) -> Result<RestartPolicyStatus> {
+        let policy = self.get_restart_policy(server_id).await?;
+        let states = self.policy_states.read().await;
+        let state = states.get(&server_id).cloned().unwrap_or_default();
+        let now = Instant::now();
+        let cooldown_remaining_seconds = state.last_restart
+            .map(|last| Duration::from_secs(policy.cooldown_seconds).saturating_sub(now.duration_since(last)).as_secs())
+            .unwrap_or(0);
+        let mut breaches = state.breaches(now);
+        breaches.resize(policy.rules.len(), None);
+        Ok(RestartPolicyStatus {
+            policy,
+            last_sample: state.last_sample,
+            breaches,
+            last_restart: state.last_restart_at,
+            restarting: state.restarting,
+            cooldown_remaining_seconds,
+        })
+    }
+
+    /// Sample servers with an enabled restart policy and restart those that breach it
+    pub async fn run_restart_policies(self: Arc<Self>) {
+        info!("Starting restart policy checks every {:?}", self.config.policy_check_interval);
+        let mut interval = interval(self.config.policy_check_interval);
+        loop {
+            interval.tick().await;
+            if *self.stopped.read().await {
+                return;
+            }
+            if let Err(e) = self.check_restart_policies().await {
+                error!("Error checking restart policies: {}", e);
+            }
+        }
+    }
+
+    async fn check_restart_policies(self: &Arc<Self>) -> Result<()> {
+        let policies = self.database.get_restart_policies().await
+            .map_err(|e| database_error(e, "get_restart_policies", "restart_policies"))?;
+
+        for (id, value) in policies {
+            let (Ok(server_id), Ok(policy)) = (Uuid::parse_str(&id), serde_json::from_value::<RestartPolicy>(value)) else {
+                continue;
+            };
+            if !policy.enabled || policy.rules.is_empty() {
+                continue;
+            }
+            if !self.process_manager.is_server_running(server_id).await {
+                if let Some(state) = self.policy_states.write().await.get_mut(&server_id) {
+                    state.reset_breaches();
                 }
+                continue;
             }
-            Err(e) => {
-                error!("Failed to restart server {} after retries: {}", server_id, e);
-                
-                // Update state to reflect restart failure
-                let mut states = self.server_states.write().await;
-                if let Some(state) = states.get_mut(&server_id) {
-                    state.health = ServerHealth::Crashed;
+            let Ok(Some(server)) = self.database.get_server(&id).await else {
+                continue;
+            };
+
+            let sample = self.sample(server_id, &server).await;
+            let triggered = {
+                let mut states = self.policy_states.write().await;
+                let state = states.entry(server_id).or_default();
+                let triggered = state.observe(&policy, sample, Instant::now());
+                if triggered.is_some() {
+                    state.restarting = true;
                 }
+                triggered
+            };
+
+            if let Some(index) = triggered {
+                let rule = policy.rules[index].clone();
+                let value = sample.get(rule.metric);
+                warn!("Server {} breached restart policy '{}', restarting", server_id, rule.describe());
+                let watchdog = self.clone();
+                tokio::spawn(async move {
+                    watchdog.policy_restart(server_id, server, policy, rule, value).await;
+                });
             }
         }
-
         Ok(())
     }
 
+    /// Current heap use (from the process' resident memory) and TPS (over RCON)
+    async fn sample(&self, server_id: Uuid, server: &ServerConfig) -> PolicySample {
+        let heap_percent = match self.process_manager.get_process_info(server_id).await {
+            Ok(info) if info.pid != 0 && server.memory > 0 => {
+                let pid = Pid::from_u32(info.pid);
+                let mut system = System::new();
+                system.refresh_process(pid);
+                system.process(pid)
+                    .map(|process| process.memory() as f64 / (server.memory as f64 * 1024.0 * 1024.0) * 100.0)
+            }
+            _ => None,
+        };
+
+        let client = RconClient::new(server.host.clone(), server.rcon_port, server.rcon_password.clone());
+        let tps = match tokio::task::spawn_blocking(move || client.get_server_info()).await {
+            Ok(Ok(info)) => Some(info.tps),
+            _ => None,
+        };
+
+        PolicySample { heap_percent, tps }
+    }
+
+    async fn policy_restart(
+        &self,
+        server_id: Uuid,
+        server: ServerConfig,
+        policy: RestartPolicy,
+        rule: crate::core::restart_policy::PolicyRule,
+        value: Option<f64>,
+    ) {
+        let started = chrono::Utc::now();
+        if policy.warning_seconds > 0 {
+            let text = format!("Server restarting in {} to recover performance", format_remaining(policy.warning_seconds));
+            announce(&server, &text, true).await;
+            tokio::time::sleep(Duration::from_secs(policy.warning_seconds as u64)).await;
+        }
+        announce(&server, "Server restarting now", false).await;
+        if let Err(e) = rcon(&server, "save-all flush".to_string()).await {
+            warn!("Failed to save server {} before policy restart: {}", server_id, e);
+        }
+
+        let result = self.restart_process(server_id).await;
+
+        {
+            let mut states = self.policy_states.write().await;
+            let state = states.entry(server_id).or_default();
+            state.restarting = false;
+            state.last_restart = Some(Instant::now());
+            state.last_restart_at = Some(chrono::Utc::now());
+            state.reset_breaches();
+        }
+        if result.is_ok() {
+            let mut states = self.server_states.write().await;
+            if let Some(state) = states.get_mut(&server_id) {
+                state.health = ServerHealth::Healthy;
+                state.last_heartbeat = Instant::now();
+            }
+        }
+
+        let (level, message) = match &result {
+            Ok(()) => ("info", format!("Restarted by policy '{}'", rule.describe())),
+            Err(e) => ("error", format!("Policy restart for '{}' failed: {}", rule.describe(), e)),
+        };
+        match &result {
+            Ok(()) => info!("Server {}: {}", server_id, message),
+            Err(_) => error!("Server {}: {}", server_id, message),
+        }
+        let event = EventLog {
+            id: Uuid::new_v4().to_string(),
+            server_id: Some(server_id.to_string()),
+            event_type: "policy_restart".to_string(),
+            message,
+            level: level.to_string(),
+            metadata: Some(serde_json::json!({
+                "rule": rule,
+                "value": value,
+                "duration_ms": (chrono::Utc::now() - started).num_milliseconds(),
+            })),
+            created_at: chrono::Utc::now(),
+        };
+        if let Err(e) = self.database.log_event(&event).await {
+            error!("Failed to log policy restart of server {}: {}", server_id, e);
+        }
+    }
+
     /// Get server health status
     pub async fn get_server_health(&self, server_id: Uuid) -> Option<ServerHealth> {
         let states = self.server_states.read().await;
@@ -375,6 +790,7 @@ impl CrashWatchdog {
                 "last_restart": state.last_restart.map(|t| t.elapsed().as_secs()),
                 "hang_start": state.hang_start.map(|t| t.elapsed().as_secs()),
                 "is_healthy": state.health == ServerHealth::Healthy,
+                "crash_looping": state.health == ServerHealth::CrashLooping,
             });
             
             stats.insert(*server_id, server_stats);
@@ -396,6 +812,7 @@ impl CrashWatchdog {
                 "last_restart": state.last_restart.map(|t| t.elapsed().as_secs()),
                 "hang_start": state.hang_start.map(|t| t.elapsed().as_secs()),
                 "is_healthy": state.health == ServerHealth::Healthy,
+                "crash_looping": state.health == ServerHealth::CrashLooping,
             }))
         } else {
             None
@@ -412,6 +829,8 @@ impl CrashWatchdog {
             if let Some(state) = states.get_mut(&server_id) {
                 state.restart_attempts = 0;
                 state.health = ServerHealth::Restarting;
+                state.crashes.clear();
+                state.crash_loop = None;
             }
         }
 
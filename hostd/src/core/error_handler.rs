@@ -8,6 +8,33 @@ use axum::{
 };
 use tracing::{error, warn, info};
 
+/// A resource a request named does not exist. Managers return it, wrapped in
+/// their usual error type, so handlers can answer 404 through `AppError::rejected`.
+#[derive(Debug, Clone)]
+pub struct NotFound {
+    pub resource: String,
+    message: String,
+}
+
+impl NotFound {
+    pub fn new(resource: &str) -> Self {
+        Self { resource: resource.to_string(), message: format!("{} not found", resource) }
+    }
+
+    /// Message naming the missing resource's ID, e.g. "Backup abc not found"
+    pub fn with_id(resource: &str, id: impl fmt::Display) -> Self {
+        Self { resource: resource.to_string(), message: format!("{} {} not found", resource, id) }
+    }
+}
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl StdError for NotFound {}
+
 /// Comprehensive error types for the Guardian Server Manager
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AppError {
@@ -130,6 +157,36 @@ pub enum AppError {
         operation: String,
         timeout_duration: String,
     },
+    
+    // Missing resources
+    NotFoundError {
+        message: String,
+        resource: String,
+    },
+    
+    // Requests that conflict with the current state
+    ConflictError {
+        message: String,
+    },
+    
+    // Requests that could not be carried out
+    RequestError {
+        message: String,
+    },
+    
+    // Several invalid fields of one request
+    FieldValidationError {
+        message: String,
+        fields: Vec<FieldError>,
+    },
+}
+
+/// One invalid field of a request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub constraint: String,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -195,6 +252,10 @@ impl AppError {
             AppError::PortConflictError { .. } => StatusCode::CONFLICT,
             AppError::ResourceLimitError { .. } => StatusCode::TOO_MANY_REQUESTS,
             AppError::TimeoutError { .. } => StatusCode::REQUEST_TIMEOUT,
+            AppError::NotFoundError { .. } => StatusCode::NOT_FOUND,
+            AppError::ConflictError { .. } => StatusCode::CONFLICT,
+            AppError::RequestError { .. } => StatusCode::BAD_REQUEST,
+            AppError::FieldValidationError { .. } => StatusCode::UNPROCESSABLE_ENTITY,
         }
     }
     
@@ -248,6 +309,12 @@ impl AppError {
             AppError::TimeoutError { message, operation, timeout_duration } => {
                 format!("Operation '{}' timed out after {}. {}", operation, timeout_duration, message)
             }
+            AppError::NotFoundError { message, .. } => message.clone(),
+            AppError::ConflictError { message } => message.clone(),
+            AppError::RequestError { message } => message.clone(),
+            AppError::FieldValidationError { message, .. } => {
+                format!("Validation error: {}", message)
+            }
         }
     }
     
@@ -305,6 +372,15 @@ impl AppError {
             AppError::TimeoutError { message, operation, timeout_duration } => {
                 format!("Timeout error for operation '{}' after {}: {}", operation, timeout_duration, message)
             }
+            AppError::NotFoundError { message, resource } => {
+                format!("Not found ({}): {}", resource, message)
+            }
+            AppError::ConflictError { message } => format!("Conflict: {}", message),
+            AppError::RequestError { message } => format!("Request failed: {}", message),
+            AppError::FieldValidationError { message, fields } => {
+                let fields: Vec<&str> = fields.iter().map(|field| field.field.as_str()).collect();
+                format!("Validation error for fields {}: {}", fields.join(", "), message)
+            }
         }
     }
     
@@ -328,6 +404,10 @@ impl AppError {
             AppError::PortConflictError { .. } => "port_conflict",
             AppError::ResourceLimitError { .. } => "resource_limit",
             AppError::TimeoutError { .. } => "timeout",
+            AppError::NotFoundError { .. } => "not_found",
+            AppError::ConflictError { .. } => "conflict",
+            AppError::RequestError { .. } => "request",
+            AppError::FieldValidationError { .. } => "validation",
         }
     }
     
@@ -373,6 +453,10 @@ impl AppError {
             AppError::PortConflictError { .. } => "PORT_CONFLICT".to_string(),
             AppError::ResourceLimitError { .. } => "RESOURCE_LIMIT_EXCEEDED".to_string(),
             AppError::TimeoutError { .. } => "OPERATION_TIMEOUT".to_string(),
+            AppError::NotFoundError { .. } => "NOT_FOUND".to_string(),
+            AppError::ConflictError { .. } => "CONFLICT".to_string(),
+            AppError::RequestError { .. } => "REQUEST_FAILED".to_string(),
+            AppError::FieldValidationError { .. } => "VALIDATION_ERROR".to_string(),
         }
     }
 
@@ -404,6 +488,19 @@ impl AppError {
         }
     }
     
+    /// Per-field details of validation errors
+    pub fn field_errors(&self) -> Option<Vec<FieldError>> {
+        match self {
+            AppError::ValidationError { message, field, constraint, .. } => Some(vec![FieldError {
+                field: field.clone(),
+                constraint: constraint.clone(),
+                message: message.clone(),
+            }]),
+            AppError::FieldValidationError { fields, .. } => Some(fields.clone()),
+            _ => None,
+        }
+    }
+    
     /// Get the retry delay in milliseconds
     pub fn retry_delay_ms(&self) -> u64 {
         match self {
@@ -442,6 +539,7 @@ impl IntoResponse for AppError {
             category: self.category().to_string(),
             timestamp: chrono::Utc::now(),
             details: self.safe_details(),
+            fields: self.field_errors(),
         };
         
         // Log the full error details server-side only
@@ -459,7 +557,7 @@ impl IntoResponse for AppError {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ErrorResponse {
     pub success: bool,
     pub error: String,
@@ -467,6 +565,8 @@ pub struct ErrorResponse {
     pub category: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub details: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldError>>,
 }
 
 /// Result type alias for convenience
@@ -594,6 +694,40 @@ impl AppError {
             timeout_duration: timeout_duration.to_string(),
         }
     }
+    
+    pub fn not_found(resource: &str) -> Self {
+        AppError::NotFoundError {
+            message: format!("{} not found", resource),
+            resource: resource.to_string(),
+        }
+    }
+    
+    pub fn conflict(message: impl Into<String>) -> Self {
+        AppError::ConflictError { message: message.into() }
+    }
+    
+    /// A request that cannot be carried out as asked
+    pub fn request(message: impl Into<String>) -> Self {
+        AppError::RequestError { message: message.into() }
+    }
+    
+    /// A request a manager turned down, described as `context: error`. A
+    /// `NotFound` anywhere in the error's chain makes it a 404.
+    pub fn rejected<E: AsRef<dyn StdError>>(context: &str, error: E) -> Self {
+        let error = error.as_ref();
+        let message = format!("{}: {}", context, error);
+        let not_found = std::iter::successors(Some(error), |&error| error.source())
+            .find_map(|error| error.downcast_ref::<NotFound>());
+        match not_found {
+            Some(not_found) => AppError::NotFoundError { message, resource: not_found.resource.clone() },
+            None => AppError::RequestError { message },
+        }
+    }
+    
+    pub fn field_validation_error(fields: Vec<FieldError>) -> Self {
+        let message = fields.iter().map(|field| format!("{}: {}", field.field, field.message)).collect::<Vec<_>>().join("; ");
+        AppError::FieldValidationError { message, fields }
+    }
 }
 
 /// Macro for easy error creation
@@ -656,3 +790,50 @@ macro_rules! timeout_error {
         AppError::timeout_error($operation, $duration, $msg)
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_classification() {
+        // Only a typed `NotFound` is a 404, whatever the message says
+        let error = AppError::request("Server abc not found");
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+
+        let error = AppError::rejected("Failed to delete", anyhow::Error::new(NotFound::with_id("Backup", "b1")));
+        assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(error.error_code(), "NOT_FOUND");
+        assert_eq!(error.user_message(), "Failed to delete: Backup b1 not found");
+
+        let wrapped = anyhow::Error::new(NotFound::new("Server")).context("Failed to load");
+        assert_eq!(AppError::rejected("Failed to start", wrapped).status_code(), StatusCode::NOT_FOUND);
+        let boxed: Box<dyn StdError> = Box::new(NotFound::new("Backup"));
+        assert_eq!(AppError::rejected("Failed to restore", boxed).status_code(), StatusCode::NOT_FOUND);
+
+        let error = AppError::rejected("Failed to start", anyhow::anyhow!("Server must be stopped first"));
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(error.user_message(), "Failed to start: Server must be stopped first");
+    }
+
+    #[test]
+    fn test_status_codes() {
+        assert_eq!(AppError::not_found("Server").status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(AppError::conflict("busy").status_code(), StatusCode::CONFLICT);
+        assert_eq!(AppError::internal_error("api", "boom").status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(AppError::not_found("Server").user_message(), "Server not found");
+    }
+
+    #[test]
+    fn test_field_errors() {
+        let error = AppError::field_validation_error(vec![FieldError {
+            field: "memory".to_string(),
+            constraint: "min".to_string(),
+            message: "must be at least 512".to_string(),
+        }]);
+        assert_eq!(error.user_message(), "Validation error: memory: must be at least 512");
+        assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error.field_errors().map(|fields| fields.len()), Some(1));
+        assert!(AppError::conflict("busy").field_errors().is_none());
+    }
+}
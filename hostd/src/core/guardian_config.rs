@@ -14,9 +14,49 @@ pub struct GuardianConfig {
     pub guardian_port: u16,
     pub guardian_host: String,
     
+    // TLS Configuration
+    pub tls_enabled: bool,
+    /// PEM certificate and key; a self-signed pair is generated when both are unset
+    pub tls_cert_path: Option<PathBuf>,
+    pub tls_key_path: Option<PathBuf>,
+    
+    // Remote Management
+    /// Allow listening on non-loopback addresses, with CORS limited to the allowlist
+    pub remote_mode: bool,
+    /// Extra browser origins allowed to call the API; `*` allows any
+    pub allowed_origins: Vec<String>,
+    
     // Database Configuration
     pub database_url: String,
     
+    // Metrics History
+    /// Seconds between performance samples, each stored in the database
+    pub metrics_interval_secs: u64,
+    /// Days hourly rollups are kept; finer history is kept for shorter periods
+    pub metrics_retention_days: u32,
+    
+    // Compatibility Rules
+    /// Where community compatibility rules are downloaded from; only the bundled rules are used when unset
+    pub compat_rules_url: Option<String>,
+    /// Hours between downloads of the compatibility rules
+    pub compat_rules_refresh_hours: u64,
+    
+    // Diagnostics
+    /// Megabytes of heap dumps and GC logs kept per server; the oldest files are removed past this
+    pub diagnostics_quota_mb: u64,
+    
+    // Backups
+    /// Hours between test-restores of a random backup to catch silent corruption; 0 turns them off
+    pub backup_test_restore_hours: u64,
+    
+    // Resource Packs
+    /// Public base URL players download resource packs from; Guardian's own address when unset
+    pub resource_pack_url: Option<String>,
+    /// Object storage base URL packs are PUT to instead of being served by Guardian
+    pub resource_pack_upload_url: Option<String>,
+    /// Bearer token for the upload URL
+    pub resource_pack_upload_token: Option<String>,
+    
     // Logging Configuration
     pub rust_log: String,
     pub log_level: String,
@@ -24,6 +64,8 @@ pub struct GuardianConfig {
     // GPU Configuration
     pub gpu_enabled: bool,
     pub gpu_worker_path: PathBuf,
+    pub gpu_worker_endpoint: String,
+    pub gpu_device: String,
     
     // Java Agent Configuration
     pub java_agent_enabled: bool,
@@ -42,11 +84,27 @@ impl Default for GuardianConfig {
             modrinth_api_key: None,
             guardian_port: 52100,
             guardian_host: "127.0.0.1".to_string(),
+            tls_enabled: false,
+            tls_cert_path: None,
+            tls_key_path: None,
+            remote_mode: false,
+            allowed_origins: Vec::new(),
             database_url: "sqlite:guardian.db".to_string(),
+            metrics_interval_secs: 30,
+            metrics_retention_days: 365,
+            compat_rules_url: None,
+            compat_rules_refresh_hours: 24,
+            diagnostics_quota_mb: 20 * 1024,
+            backup_test_restore_hours: 0,
+            resource_pack_url: None,
+            resource_pack_upload_url: None,
+            resource_pack_upload_token: None,
             rust_log: "info".to_string(),
             log_level: "info".to_string(),
             gpu_enabled: false, // Off by default for safety
             gpu_worker_path: PathBuf::from("./gpu-worker.exe"),
+            gpu_worker_endpoint: gpu_worker::ipc::default_endpoint(),
+            gpu_device: "auto".to_string(),
             java_agent_enabled: false,
             java_agent_path: PathBuf::from("./guardian-agent.jar"),
             data_dir: PathBuf::from("data"),
@@ -80,14 +138,83 @@ impl GuardianConfig {
                 .context("Invalid GUARDIAN_PORT value")?;
         }
         
+        if let Ok(remote_mode) = env::var("GUARDIAN_REMOTE_MODE") {
+            config.remote_mode = remote_mode.parse()
+                .context("Invalid GUARDIAN_REMOTE_MODE value")?;
+        }
+        
+        // Remote mode listens on every interface unless told otherwise
         if let Ok(host) = env::var("GUARDIAN_HOST") {
             config.guardian_host = host;
+        } else if config.remote_mode {
+            config.guardian_host = "0.0.0.0".to_string();
+        }
+        
+        if let Ok(origins) = env::var("GUARDIAN_ALLOWED_ORIGINS") {
+            config.allowed_origins = origins.split(',')
+                .map(|origin| origin.trim().to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect();
+        }
+        
+        if let Ok(tls_enabled) = env::var("GUARDIAN_TLS_ENABLED") {
+            config.tls_enabled = tls_enabled.parse()
+                .context("Invalid GUARDIAN_TLS_ENABLED value")?;
+        }
+        
+        if let Ok(cert_path) = env::var("GUARDIAN_TLS_CERT") {
+            config.tls_cert_path = Some(PathBuf::from(cert_path));
+        }
+        
+        if let Ok(key_path) = env::var("GUARDIAN_TLS_KEY") {
+            config.tls_key_path = Some(PathBuf::from(key_path));
         }
         
         if let Ok(db_url) = env::var("DATABASE_URL") {
             config.database_url = db_url;
         }
         
+        if let Ok(interval) = env::var("GUARDIAN_METRICS_INTERVAL") {
+            config.metrics_interval_secs = interval.parse()
+                .context("Invalid GUARDIAN_METRICS_INTERVAL value")?;
+        }
+        
+        if let Ok(days) = env::var("GUARDIAN_METRICS_RETENTION_DAYS") {
+            config.metrics_retention_days = days.parse()
+                .context("Invalid GUARDIAN_METRICS_RETENTION_DAYS value")?;
+        }
+        
+        if let Ok(url) = env::var("GUARDIAN_COMPAT_RULES_URL") {
+            config.compat_rules_url = Some(url).filter(|url| !url.trim().is_empty());
+        }
+        
+        if let Ok(hours) = env::var("GUARDIAN_COMPAT_RULES_REFRESH_HOURS") {
+            config.compat_rules_refresh_hours = hours.parse()
+                .context("Invalid GUARDIAN_COMPAT_RULES_REFRESH_HOURS value")?;
+        }
+        
+        if let Ok(quota) = env::var("GUARDIAN_DIAGNOSTICS_QUOTA_MB") {
+            config.diagnostics_quota_mb = quota.parse()
+                .context("Invalid GUARDIAN_DIAGNOSTICS_QUOTA_MB value")?;
+        }
+        
+        if let Ok(hours) = env::var("GUARDIAN_BACKUP_TEST_RESTORE_HOURS") {
+            config.backup_test_restore_hours = hours.parse()
+                .context("Invalid GUARDIAN_BACKUP_TEST_RESTORE_HOURS value")?;
+        }
+        
+        if let Ok(url) = env::var("GUARDIAN_RESOURCE_PACK_URL") {
+            config.resource_pack_url = Some(url).filter(|url| !url.trim().is_empty());
+        }
+        
+        if let Ok(url) = env::var("GUARDIAN_RESOURCE_PACK_UPLOAD_URL") {
+            config.resource_pack_upload_url = Some(url).filter(|url| !url.trim().is_empty());
+        }
+        
+        if let Ok(token) = env::var("GUARDIAN_RESOURCE_PACK_UPLOAD_TOKEN") {
+            config.resource_pack_upload_token = Some(token).filter(|token| !token.is_empty());
+        }
+        
         if let Ok(rust_log) = env::var("RUST_LOG") {
             config.rust_log = rust_log;
         }
@@ -105,6 +232,14 @@ impl GuardianConfig {
             config.gpu_worker_path = PathBuf::from(gpu_path);
         }
         
+        if let Ok(endpoint) = env::var("GPU_WORKER_ENDPOINT") {
+            config.gpu_worker_endpoint = endpoint;
+        }
+        
+        if let Ok(device) = env::var("GPU_WORKER_DEVICE") {
+            config.gpu_device = device;
+        }
+        
         if let Ok(java_enabled) = env::var("JAVA_AGENT_ENABLED") {
             config.java_agent_enabled = java_enabled.parse()
                 .unwrap_or(false);
@@ -136,6 +271,23 @@ impl GuardianConfig {
             tracing::warn!("MODRINTH_API_KEY not set - Modrinth integration will be disabled");
         }
         
+        // The API is only exposed beyond this machine on purpose
+        if !self.remote_mode && !crate::core::remote::is_loopback_host(&self.guardian_host) {
+            anyhow::bail!(
+                "Listening on {} exposes the API to the network; set GUARDIAN_REMOTE_MODE=true to allow remote management",
+                self.guardian_host
+            );
+        }
+        
+        if self.remote_mode {
+            if env::var("GUARDIAN_ADMIN_PASSWORD").map_or(true, |password| password.trim().is_empty()) {
+                anyhow::bail!("Remote mode requires GUARDIAN_ADMIN_PASSWORD so the default admin password is not exposed");
+            }
+            if !self.tls_enabled {
+                tracing::warn!("Remote mode is enabled without TLS - credentials will be sent in cleartext. Set GUARDIAN_TLS_ENABLED=true.");
+            }
+        }
+        
         // Validate paths
         if self.gpu_enabled && !self.gpu_worker_path.exists() {
             tracing::warn!("GPU worker not found at {:?} - GPU features will be disabled", self.gpu_worker_path);
@@ -153,6 +305,11 @@ impl GuardianConfig {
         format!("{}:{}", self.guardian_host, self.guardian_port)
     }
     
+    /// URL scheme clients use for the API, `https` when TLS is enabled
+    pub fn scheme(&self) -> &'static str {
+        if self.tls_enabled { "https" } else { "http" }
+    }
+    
     /// Check if API integration is available
     pub fn has_curseforge(&self) -> bool {
         self.curseforge_api_key.is_some()
@@ -1,132 +1,345 @@
 use axum::{
-    extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
     middleware::Next,
     response::{Response, IntoResponse},
     Json,
 };
 use serde_json::json;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use crate::core::auth::{AuthManager, Permission};
+use crate::core::auth::{AuthManager, Permission, UserRole, API_TOKEN_PREFIX};
+use crate::core::guardian_config::GuardianConfig;
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct AuthContext {
     pub user_id: uuid::Uuid,
     pub username: String,
     pub role: crate::core::auth::UserRole,
+    /// Servers the user may access; empty means all
+    pub server_ids: Vec<String>,
+    /// Set when the request was made with an API token rather than a user's JWT;
+    /// `username` is then `token:<name>` and the role carries no meaning
+    pub api_token_id: Option<String>,
+    /// The role's permissions, or the token's scopes
+    pub permissions: Vec<Permission>,
 }
 
-pub async fn auth_middleware(
-    State(auth_manager): State<Arc<AuthManager>>,
-    headers: HeaderMap,
-    mut request: Request,
-    next: Next,
-) -> Result<Response, StatusCode> {
-    // Extract token from Authorization header
-    let token = headers
+impl AuthContext {
+    pub fn can_access_server(&self, server_id: &str) -> bool {
+        self.role == UserRole::Admin || self.server_ids.is_empty() || self.server_ids.iter().any(|id| id == server_id)
+    }
+
+    /// Servers the caller is limited to, `None` when they may access every server
+    pub fn server_scope(&self) -> Option<&[String]> {
+        (self.role != UserRole::Admin && !self.server_ids.is_empty()).then_some(self.server_ids.as_slice())
+    }
+
+    /// For routes whose permission depends on the request body rather than its path
+    pub fn has_permission(&self, permission: &Permission) -> bool {
+        (self.api_token_id.is_none() && self.role == UserRole::Admin) || self.permissions.contains(permission)
+    }
+}
+
+/// Routes reachable without a token
+fn is_public(method: &Method, path: &str) -> bool {
+    *method == Method::OPTIONS
+        || matches!(path, "/api/health" | "/api/healthz")
+        // The API description, so integrators can generate clients before signing in
+        || (*method == Method::GET && (path == crate::openapi::SPEC_PATH || path.starts_with(crate::openapi::DOCS_PATH)))
+        || (*method == Method::POST && matches!(path, "/api/auth/login" | "/api/auth/refresh"))
+        // Signed by Discord; the handler checks the signature
+        || (*method == Method::POST && path == "/api/discord/interactions")
+}
+
+/// Permission a request needs, decided from its method and path.
+/// `None` means any signed-in user may make it.
+pub fn required_permission(method: &Method, path: &str) -> Option<Permission> {
+    let segments: Vec<&str> = path.trim_start_matches("/api").split('/').filter(|s| !s.is_empty()).collect();
+    let read = *method == Method::GET || *method == Method::HEAD;
+    let delete = *method == Method::DELETE;
+
+    let permission = match segments.as_slice() {
+        // Anyone may manage their own API tokens, up to their own permissions
+        ["auth", "me" | "logout"] | ["auth", "tokens", ..] => return None,
+        ["auth", "roles" | "permissions"] => Permission::ViewRole,
+        ["auth", "register"] => Permission::CreateUser,
+        ["auth", "users", ..] if read => Permission::ViewUser,
+        ["auth", "users"] => Permission::CreateUser,
+        ["auth", "users", ..] if delete => Permission::DeleteUser,
+        ["auth", ..] => Permission::EditUser,
+
+        // The handler checks the action's permission, and access to each server
+        ["servers", "bulk"] => return None,
+        ["servers"] if read => Permission::ViewServer,
+        ["servers"] | ["servers", "external" | "import" | "transfer"] | ["servers", _, "clone"] => Permission::CreateServer,
+        // The server is removed from where it was once it runs at its destination
+        ["servers", _, "migrate"] => Permission::DeleteServer,
+        ["servers", _] if delete => Permission::DeleteServer,
+        // Accepting the EULA only serves to start the server, and may start it at once
+        ["servers", _, "start"] | ["servers", _, "eula", "accept"] => Permission::StartServer,
+        ["servers", _, "stop"] => Permission::StopServer,
+        ["servers", _, "restart"] | ["servers", _, "watchdog", "force-restart"] => Permission::RestartServer,
+        ["servers", _, "backups", ..] if read => Permission::ViewBackup,
+        ["servers", _, "backups", ..] if delete => Permission::DeleteBackup,
+        ["servers", _, "backups", _, "restore"] => Permission::RestoreBackup,
+        ["servers", _, "backups", ..] => Permission::CreateBackup,
+        ["servers", _, "mods", ..] if read => Permission::ViewModpack,
+        // Checking for drift only reads the mods folder
+        ["servers", _, "mods", "drift", "check"] => Permission::ViewModpack,
+        // Aborting a bisection enables mods again rather than removing any
+        ["servers", _, "mods", "bisect", ..] => Permission::InstallMod,
+        ["servers", _, "mods", ..] if delete => Permission::UninstallMod,
+        ["servers", _, "mods", ..] => Permission::InstallMod,
+        ["servers", _, "console", ..] if read => Permission::ViewLogs,
+        ["servers", _, "logs", ..] => Permission::ViewLogs,
+        ["servers", _, "metrics", ..] => Permission::ViewMetrics,
+        // Server files hold secrets such as rcon.password, so reading them takes edit rights
+        ["servers", _, "files", "content" | "download"] => Permission::EditServer,
+        // Heap dumps hold everything in the server's memory, secrets included
+        ["servers", _, "diagnostics", ..] => Permission::EditServer,
+        ["servers", ..] if read => Permission::ViewServer,
+        ["servers", ..] => Permission::EditServer,
+
+        ["groups", _, "summary"] => Permission::ViewMetrics,
+        ["groups", ..] if read => Permission::ViewServer,
+        ["groups", ..] => Permission::EditServer,
+
+        ["templates", ..] if read => Permission::ViewServer,
+        ["templates", ..] => Permission::CreateServer,
+        ["nodes", _, "servers"] => Permission::CreateServer,
+
+        ["modpacks", ..] | ["mods", ..] if read => Permission::ViewModpack,
+        ["modpacks"] => Permission::CreateModpack,
+        ["modpacks", "apply"] | ["modpacks", _, "apply"] | ["mods", ..] => Permission::InstallMod,
+        ["modpacks", ..] if delete => Permission::DeleteModpack,
+        ["modpacks", ..] => Permission::EditModpack,
+
+        // Lists the host's network addresses
+        ["system", "connection-info"] => Permission::SystemSettings,
+        ["performance" | "system" | "watchdog" | "compatibility", ..] if read => Permission::ViewMetrics,
+        // Settings hold API keys, so even reading them is admin-only
+        ["settings", ..] => Permission::SystemSettings,
+        ["setup"] => Permission::SystemSettings,
+        ["audit", ..] => Permission::SystemSettings,
+        ["alerts", ..] => Permission::SystemSettings,
+        // Webhooks hold signing secrets
+        ["webhooks", ..] => Permission::SystemSettings,
+        // The bot token and who may control servers from Discord
+        ["discord", ..] => Permission::SystemSettings,
+        ["jobs", _, "cancel"] => Permission::EditServer,
+        _ if read => Permission::ViewServer,
+        _ => Permission::SystemSettings,
+    };
+    Some(permission)
+}
+
+/// Server a request is about, for routes scoped to a single server
+pub fn scoped_server_id(path: &str) -> Option<&str> {
+    let mut segments = path.trim_start_matches("/api/").split('/');
+    match (segments.next(), segments.next()) {
+        (Some("servers"), Some("bulk")) => None,
+        (Some("servers" | "compatibility"), Some(id)) if !id.is_empty() => Some(id),
+        (Some("performance"), Some(id)) if !id.is_empty() && id != "all" => Some(id),
+        _ => None,
+    }
+}
+
+fn deny(status: StatusCode, error: &str) -> Response {
+    (status, Json(json!({
+        "success": false,
+        "error": error,
+        "timestamp": chrono::Utc::now()
+    }))).into_response()
+}
+
+/// Bearer token from the `Authorization` header, or the `access_token` query
+/// parameter for clients that cannot set headers (EventSource, WebSocket)
+fn request_token<'a>(headers: &'a HeaderMap, query: Option<&'a str>) -> Option<&'a str> {
+    headers
         .get("Authorization")
         .and_then(|h| h.to_str().ok())
-        .and_then(|s| s.strip_prefix("Bearer "));
-    
-    let token = match token {
-        Some(token) => token,
-        None => {
-            return Ok(Json(json!({
-                "success": false,
-                "error": "Missing authorization token",
-                "timestamp": chrono::Utc::now()
-            })).into_response());
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .or_else(|| query?.split('&').find_map(|pair| pair.strip_prefix("access_token=")))
+}
+
+/// Whether a request without a token comes from the desktop app on this
+/// machine: a loopback peer, and no browser origin other than the app's.
+/// Checking the origin keeps other pages open in a local browser out.
+fn is_trusted_local(trusted_origins: &[String], peer: Option<SocketAddr>, headers: &HeaderMap) -> bool {
+    if !peer.is_some_and(|peer| peer.ip().is_loopback()) {
+        return false;
+    }
+    match headers.get(header::ORIGIN) {
+        None => true,
+        Some(origin) => origin.to_str().is_ok_and(|origin| trusted_origins.iter().any(|trusted| trusted == origin)),
+    }
+}
+
+/// What the auth layer needs to decide who a request is from
+#[derive(Clone)]
+pub struct AuthState {
+    pub auth_manager: Arc<AuthManager>,
+    /// Outside remote mode hostd only listens on loopback, and the desktop
+    /// app on the same machine is trusted as the admin without a token
+    pub trust_loopback: bool,
+    /// Browser origins whose loopback requests are trusted
+    pub trusted_origins: Vec<String>,
+}
+
+impl AuthState {
+    pub fn new(auth_manager: Arc<AuthManager>, config: &GuardianConfig) -> Self {
+        let trusted_origins = crate::core::remote::APP_ORIGINS
+            .iter()
+            .map(|origin| origin.to_string())
+            .chain(config.allowed_origins.iter().filter(|origin| *origin != "*").cloned())
+            .collect();
+        Self {
+            auth_manager,
+            trust_loopback: !config.remote_mode,
+            trusted_origins,
         }
-    };
-    
-    // Validate token and get user
-    let user = match auth_manager.validate_token(token).await {
-        Ok(user) => user,
-        Err(_) => {
-            return Ok(Json(json!({
-                "success": false,
-                "error": "Invalid or expired token",
-                "timestamp": chrono::Utc::now()
-            })).into_response());
+    }
+
+    /// Who a request is from, checked against the permission its method and
+    /// path need. A token is always validated, even from loopback.
+    pub async fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        query: Option<&str>,
+        peer: Option<SocketAddr>,
+        method: &Method,
+        path: &str,
+    ) -> Result<AuthContext, Response> {
+        match request_token(headers, query) {
+            Some(token) if token.starts_with(API_TOKEN_PREFIX) => {
+                authenticate_api_token(&self.auth_manager, token, method, path).await
+            }
+            Some(token) => authenticate_user(&self.auth_manager, token, method, path).await,
+            None if self.trust_loopback && is_trusted_local(&self.trusted_origins, peer, headers) => {
+                self.local_context().await
+            }
+            None => Err(deny(StatusCode::UNAUTHORIZED, "Missing authorization token")),
         }
-    };
-    
-    // Add user context to request extensions
-    let auth_context = AuthContext {
-        user_id: user.id,
-        username: user.username,
-        role: user.role,
-    };
-    
-    request.extensions_mut().insert(auth_context);
-    
-    Ok(next.run(request).await)
+    }
+
+    /// Context for the trusted local app, acting as the admin account
+    async fn local_context(&self) -> Result<AuthContext, Response> {
+        let user = self.auth_manager.local_admin().await
+            .ok_or_else(|| deny(StatusCode::UNAUTHORIZED, "Missing authorization token"))?;
+        Ok(AuthContext {
+            user_id: user.id,
+            username: user.username,
+            permissions: self.auth_manager.role_permissions(&user.role).await,
+            role: user.role,
+            server_ids: user.server_ids,
+            api_token_id: None,
+        })
+    }
 }
 
-pub async fn permission_middleware(
-    State(auth_manager): State<Arc<AuthManager>>,
-    State(required_permission): State<Permission>,
+/// Require a valid JWT or API token on every `/api` route except login,
+/// refresh and health checks, and check the caller's permissions and server
+/// scope against the route. Outside remote mode the local app needs no token.
+pub async fn auth_middleware(
+    State(auth): State<AuthState>,
     headers: HeaderMap,
     mut request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    // Extract token from Authorization header
-    let token = headers
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok())
-        .and_then(|s| s.strip_prefix("Bearer "));
-    
-    let token = match token {
-        Some(token) => token,
-        None => {
-            return Ok(Json(json!({
-                "success": false,
-                "error": "Missing authorization token",
-                "timestamp": chrono::Utc::now()
-            })).into_response());
-        }
+) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    if !path.starts_with("/api/") || is_public(&method, &path) {
+        return next.run(request).await;
+    }
+
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0);
+    let auth_context = match auth.authenticate(&headers, request.uri().query(), peer, &method, &path).await {
+        Ok(auth_context) => auth_context,
+        Err(response) => return response,
     };
-    
-    // Validate token and get user
-    let user = match auth_manager.validate_token(token).await {
-        Ok(user) => user,
-        Err(_) => {
-            return Ok(Json(json!({
-                "success": false,
-                "error": "Invalid or expired token",
-                "timestamp": chrono::Utc::now()
-            })).into_response());
+
+    if let Some(server_id) = scoped_server_id(&path) {
+        if !auth_context.can_access_server(server_id) {
+            return deny_as(&auth_context, StatusCode::FORBIDDEN, "No access to this server");
         }
-    };
-    
-    // Check permission
-    if !auth_manager.has_permission(user.id, &required_permission).await {
-        return Ok(Json(json!({
-            "success": false,
-            "error": "Insufficient permissions",
-            "timestamp": chrono::Utc::now()
-        })).into_response());
-    }
-    
-    // Add user context to request extensions
+    }
+
+    // Add user context to request extensions, and to the response for the audit log
+    request.extensions_mut().insert(auth_context.clone());
+
+    let mut response = next.run(request).await;
+    response.extensions_mut().insert(auth_context);
+    response
+}
+
+/// Denial of a request whose caller is known, so the audit log can name them
+fn deny_as(auth_context: &AuthContext, status: StatusCode, error: &str) -> Response {
+    let mut response = deny(status, error);
+    response.extensions_mut().insert(auth_context.clone());
+    response
+}
+
+async fn authenticate_user(
+    auth_manager: &AuthManager,
+    token: &str,
+    method: &Method,
+    path: &str,
+) -> Result<AuthContext, Response> {
+    // Validate token and get user
+    let user = auth_manager.validate_token(token).await
+        .map_err(|_| deny(StatusCode::UNAUTHORIZED, "Invalid or expired token"))?;
+
     let auth_context = AuthContext {
         user_id: user.id,
         username: user.username,
+        permissions: auth_manager.role_permissions(&user.role).await,
         role: user.role,
+        server_ids: user.server_ids,
+        api_token_id: None,
     };
-    
-    request.extensions_mut().insert(auth_context);
-    
-    Ok(next.run(request).await)
+
+    if let Some(permission) = required_permission(method, path) {
+        if !auth_manager.has_permission(auth_context.user_id, &permission).await {
+            tracing::debug!("User {} lacks {:?} for {} {}", auth_context.username, permission, method, path);
+            return Err(deny_as(&auth_context, StatusCode::FORBIDDEN, "Insufficient permissions"));
+        }
+    }
+
+    Ok(auth_context)
 }
 
-// This function is not used in the current implementation
-// pub fn require_permission(permission: Permission) -> axum::middleware::FromFnLayer<impl Fn(State<Arc<AuthManager>>, State<Permission>, HeaderMap, Request, Next) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<Response, StatusCode>> + Send + 'static>> + Clone + Send + Sync + 'static, (State<Arc<AuthManager>>, State<Permission>)> {
-//     axum::middleware::from_fn_with_state(
-//         (Arc::new(AuthManager::new("default_secret".to_string())), permission),
-//         permission_middleware,
-//     )
-// }
+async fn authenticate_api_token(
+    auth_manager: &AuthManager,
+    token: &str,
+    method: &Method,
+    path: &str,
+) -> Result<AuthContext, Response> {
+    let api_token = auth_manager.validate_api_token(token).await
+        .map_err(|_| deny(StatusCode::UNAUTHORIZED, "Invalid or expired token"))?;
+
+    let auth_context = AuthContext {
+        user_id: uuid::Uuid::parse_str(&api_token.id).unwrap_or_default(),
+        username: format!("token:{}", api_token.name),
+        role: UserRole::Viewer,
+        server_ids: api_token.server_ids,
+        api_token_id: Some(api_token.id),
+        permissions: api_token.scopes.clone(),
+    };
+
+    // Accounts and tokens are managed by users, never by other tokens
+    if path.starts_with("/api/auth/") {
+        return Err(deny_as(&auth_context, StatusCode::FORBIDDEN, "API tokens cannot access account endpoints"));
+    }
+    if let Some(permission) = required_permission(method, path) {
+        if !api_token.scopes.contains(&permission) {
+            tracing::debug!("API token {} lacks {:?} for {} {}", api_token.name, permission, method, path);
+            return Err(deny_as(&auth_context, StatusCode::FORBIDDEN, "Token scope does not allow this request"));
+        }
+    }
+
+    Ok(auth_context)
+}
 
 // Helper function to extract auth context from request
 pub fn get_auth_context(request: &Request) -> Option<&AuthContext> {
@@ -141,3 +354,235 @@ pub async fn check_permission(
 ) -> bool {
     auth_manager.has_permission(user_id, permission).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_permission_by_route() {
+        let cases = [
+            (Method::GET, "/api/servers", Some(Permission::ViewServer)),
+            (Method::POST, "/api/servers", Some(Permission::CreateServer)),
+            (Method::POST, "/api/servers/external", Some(Permission::CreateServer)),
+            (Method::POST, "/api/servers/import", Some(Permission::CreateServer)),
+            (Method::POST, "/api/servers/bulk", None),
+            (Method::GET, "/api/groups/smp/summary", Some(Permission::ViewMetrics)),
+            (Method::PUT, "/api/groups/smp/servers", Some(Permission::EditServer)),
+            (Method::POST, "/api/servers/abc/clone", Some(Permission::CreateServer)),
+            (Method::POST, "/api/servers/abc/template", Some(Permission::EditServer)),
+            (Method::GET, "/api/templates", Some(Permission::ViewServer)),
+            (Method::DELETE, "/api/templates/t1", Some(Permission::CreateServer)),
+            (Method::DELETE, "/api/servers/abc", Some(Permission::DeleteServer)),
+            (Method::PATCH, "/api/servers/abc", Some(Permission::EditServer)),
+            (Method::POST, "/api/servers/abc/start", Some(Permission::StartServer)),
+            (Method::POST, "/api/servers/abc/watchdog/force-restart", Some(Permission::RestartServer)),
+            (Method::POST, "/api/servers/abc/backups/b1/restore", Some(Permission::RestoreBackup)),
+            (Method::POST, "/api/servers/abc/backups", Some(Permission::CreateBackup)),
+            (Method::GET, "/api/servers/abc/console", Some(Permission::ViewLogs)),
+            (Method::POST, "/api/servers/abc/console", Some(Permission::EditServer)),
+            (Method::POST, "/api/servers/abc/eula/accept", Some(Permission::StartServer)),
+            (Method::GET, "/api/servers/abc/console/history", Some(Permission::ViewLogs)),
+            (Method::POST, "/api/servers/abc/chat", Some(Permission::EditServer)),
+            (Method::PUT, "/api/servers/abc/chat-bridge", Some(Permission::EditServer)),
+            (Method::POST, "/api/servers/abc/world-border/grow", Some(Permission::EditServer)),
+            (Method::GET, "/api/servers/abc/pregeneration/job-1", Some(Permission::ViewServer)),
+            (Method::GET, "/api/servers/abc/logs/search", Some(Permission::ViewLogs)),
+            (Method::GET, "/api/servers/abc/files", Some(Permission::ViewServer)),
+            (Method::GET, "/api/servers/abc/files/download", Some(Permission::EditServer)),
+            (Method::POST, "/api/modpacks/m1/apply", Some(Permission::InstallMod)),
+            (Method::GET, "/api/settings", Some(Permission::SystemSettings)),
+            (Method::GET, "/api/catalog", Some(Permission::ViewServer)),
+            (Method::POST, "/api/catalog/sync", Some(Permission::SystemSettings)),
+            (Method::GET, "/api/providers/usage", Some(Permission::ViewServer)),
+            (Method::GET, "/api/servers/abc/mods/drift", Some(Permission::ViewModpack)),
+            (Method::POST, "/api/servers/abc/mods/drift/check", Some(Permission::ViewModpack)),
+            (Method::GET, "/api/servers/abc/mods/client-pack", Some(Permission::ViewModpack)),
+            (Method::POST, "/api/servers/abc/mods/client-pack", Some(Permission::InstallMod)),
+            (Method::POST, "/api/servers/abc/mods/m1/disable", Some(Permission::InstallMod)),
+            (Method::DELETE, "/api/servers/abc/mods/bisect", Some(Permission::InstallMod)),
+            (Method::POST, "/api/servers/abc/mods/bisect/result", Some(Permission::InstallMod)),
+            (Method::GET, "/api/setup", Some(Permission::SystemSettings)),
+            (Method::POST, "/api/java/runtimes/21", Some(Permission::SystemSettings)),
+            (Method::PUT, "/api/servers/abc/java-runtime", Some(Permission::EditServer)),
+            (Method::PUT, "/api/servers/abc/resource-limits", Some(Permission::EditServer)),
+            (Method::PUT, "/api/servers/abc/startup", Some(Permission::EditServer)),
+            (Method::GET, "/api/auto-start", Some(Permission::ViewServer)),
+            (Method::POST, "/api/auto-start", Some(Permission::SystemSettings)),
+            (Method::GET, "/api/sharding/topology", Some(Permission::ViewServer)),
+            (Method::POST, "/api/sharding/assignments", Some(Permission::SystemSettings)),
+            (Method::GET, "/api/nodes", Some(Permission::ViewServer)),
+            (Method::POST, "/api/servers/transfer", Some(Permission::CreateServer)),
+            (Method::POST, "/api/servers/123/migrate", Some(Permission::DeleteServer)),
+            (Method::GET, "/api/servers/123/diagnostics/heap-1.hprof", Some(Permission::EditServer)),
+            (Method::POST, "/api/servers/123/diagnostics/heap-dump", Some(Permission::EditServer)),
+            (Method::POST, "/api/nodes", Some(Permission::SystemSettings)),
+            (Method::DELETE, "/api/nodes/n1", Some(Permission::SystemSettings)),
+            (Method::POST, "/api/nodes/n1/servers", Some(Permission::CreateServer)),
+            (Method::GET, "/api/events", Some(Permission::ViewServer)),
+            (Method::GET, "/api/servers/abc/events", Some(Permission::ViewServer)),
+            (Method::GET, "/api/events/stream", Some(Permission::ViewServer)),
+            (Method::GET, "/api/jobs", Some(Permission::ViewServer)),
+            (Method::GET, "/api/jobs/abc", Some(Permission::ViewServer)),
+            (Method::POST, "/api/jobs/abc/cancel", Some(Permission::EditServer)),
+            (Method::POST, "/api/gpu/enable", Some(Permission::SystemSettings)),
+            (Method::GET, "/api/audit", Some(Permission::SystemSettings)),
+            (Method::GET, "/api/system/connection-info", Some(Permission::SystemSettings)),
+            (Method::GET, "/api/system/metrics", Some(Permission::ViewMetrics)),
+            (Method::POST, "/api/system/shutdown", Some(Permission::SystemSettings)),
+            (Method::POST, "/api/auth/register", Some(Permission::CreateUser)),
+            (Method::PUT, "/api/auth/users/u1", Some(Permission::EditUser)),
+            (Method::GET, "/api/auth/me", None),
+            (Method::POST, "/api/auth/tokens", None),
+            (Method::DELETE, "/api/auth/tokens/t1", None),
+        ];
+        for (method, path, expected) in cases {
+            assert_eq!(required_permission(&method, path), expected, "{} {}", method, path);
+        }
+    }
+
+    #[test]
+    fn test_public_routes_and_server_scope() {
+        assert!(is_public(&Method::POST, "/api/auth/login"));
+        assert!(is_public(&Method::GET, "/api/healthz"));
+        assert!(is_public(&Method::GET, "/api/openapi.json"));
+        assert!(is_public(&Method::GET, "/api/docs/index.html"));
+        assert!(!is_public(&Method::GET, "/api/auth/login"));
+        assert!(is_public(&Method::POST, "/api/discord/interactions"));
+        assert!(!is_public(&Method::PUT, "/api/discord"));
+        assert!(!is_public(&Method::GET, "/api/servers"));
+
+        assert_eq!(scoped_server_id("/api/servers/abc/start"), Some("abc"));
+        assert_eq!(scoped_server_id("/api/performance/abc/metrics"), Some("abc"));
+        assert_eq!(scoped_server_id("/api/performance/all"), None);
+        assert_eq!(scoped_server_id("/api/servers"), None);
+        assert_eq!(scoped_server_id("/api/servers/bulk"), None);
+    }
+
+    #[test]
+    fn test_has_permission_for_users_and_tokens() {
+        let mut auth = AuthContext {
+            user_id: uuid::Uuid::nil(),
+            username: "ops".to_string(),
+            role: UserRole::Operator,
+            server_ids: Vec::new(),
+            api_token_id: None,
+            permissions: vec![Permission::StartServer],
+        };
+        assert!(auth.has_permission(&Permission::StartServer));
+        assert!(!auth.has_permission(&Permission::CreateBackup));
+
+        auth.role = UserRole::Admin;
+        assert!(auth.has_permission(&Permission::CreateBackup));
+        // A token's scopes bound it whatever its role field says
+        auth.api_token_id = Some("token".to_string());
+        assert!(!auth.has_permission(&Permission::CreateBackup));
+    }
+
+    #[test]
+    fn test_trusted_local_requests() {
+        let trusted = vec!["tauri://localhost".to_string()];
+        let loopback: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let remote: SocketAddr = "192.168.1.20:50000".parse().unwrap();
+        let mut headers = HeaderMap::new();
+
+        assert!(is_trusted_local(&trusted, Some(loopback), &headers));
+        assert!(is_trusted_local(&trusted, Some("[::1]:50000".parse().unwrap()), &headers));
+        assert!(!is_trusted_local(&trusted, Some(remote), &headers));
+        assert!(!is_trusted_local(&trusted, None, &headers));
+
+        headers.insert(header::ORIGIN, "tauri://localhost".parse().unwrap());
+        assert!(is_trusted_local(&trusted, Some(loopback), &headers));
+        // Another page open in a local browser
+        headers.insert(header::ORIGIN, "https://example.com".parse().unwrap());
+        assert!(!is_trusted_local(&trusted, Some(loopback), &headers));
+    }
+
+    #[test]
+    fn test_request_token_from_header_or_query() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(request_token(&headers, Some("topics=all&access_token=abc")), Some("abc"));
+        assert_eq!(request_token(&headers, Some("topics=all")), None);
+        headers.insert("Authorization", "Bearer xyz".parse().unwrap());
+        assert_eq!(request_token(&headers, Some("access_token=abc")), Some("xyz"));
+    }
+
+    async fn token_app(dir: &std::path::Path) -> (axum::Router, Arc<AuthManager>) {
+        let database = crate::database::DatabaseManager::new(&format!("sqlite:{}", dir.join("auth.db").display())).await.unwrap();
+        let auth_manager = AuthManager::new("test-secret".to_string(), Arc::new(crate::core::credential_manager::CredentialManager::new()))
+            .with_database(Arc::new(database));
+        auth_manager.initialize().await.unwrap();
+        let auth_manager = Arc::new(auth_manager);
+        let state = AuthState { auth_manager: auth_manager.clone(), trust_loopback: false, trusted_origins: Vec::new() };
+        let app = axum::Router::new()
+            .route("/api/servers/:id", axum::routing::get(|| async { "ok" }))
+            .route("/api/servers/:id/start", axum::routing::post(|| async { "ok" }))
+            .route("/api/auth/tokens", axum::routing::get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(state, auth_middleware));
+        (app, auth_manager)
+    }
+
+    async fn send(app: &axum::Router, method: Method, path: &str, token: &str) -> StatusCode {
+        use tower::ServiceExt;
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    async fn create_token(auth_manager: &AuthManager, scopes: Vec<Permission>, server_ids: Vec<String>) -> crate::core::auth::CreatedApiToken {
+        let admin = auth_manager.local_admin().await.unwrap();
+        let request = crate::core::auth::CreateApiTokenRequest {
+            name: "ci".to_string(),
+            scopes,
+            preset: None,
+            server_ids,
+            expires_in_days: None,
+        };
+        auth_manager.create_api_token(&admin, request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_api_token_scopes_and_servers() {
+        let dir = tempfile::tempdir().unwrap();
+        let (app, auth_manager) = token_app(dir.path()).await;
+        let created = create_token(&auth_manager, vec![Permission::ViewServer], vec!["s1".to_string()]).await;
+        let token = created.token.as_str();
+
+        assert_eq!(send(&app, Method::GET, "/api/servers/s1", token).await, StatusCode::OK);
+        // Outside the token's scopes
+        assert_eq!(send(&app, Method::POST, "/api/servers/s1/start", token).await, StatusCode::FORBIDDEN);
+        // Outside the token's servers
+        assert_eq!(send(&app, Method::GET, "/api/servers/s2", token).await, StatusCode::FORBIDDEN);
+        assert_eq!(send(&app, Method::GET, "/api/auth/tokens", token).await, StatusCode::FORBIDDEN);
+        // The prefix routes a token to API token lookup, never to JWT validation
+        assert_eq!(send(&app, Method::GET, "/api/servers/s1", &format!("{}unknown", API_TOKEN_PREFIX)).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_revoked_and_expired_api_tokens_are_unauthorized() {
+        let dir = tempfile::tempdir().unwrap();
+        let (app, auth_manager) = token_app(dir.path()).await;
+        let admin = auth_manager.local_admin().await.unwrap();
+
+        let created = create_token(&auth_manager, vec![Permission::ViewServer], Vec::new()).await;
+        assert_eq!(send(&app, Method::GET, "/api/servers/s1", &created.token).await, StatusCode::OK);
+        auth_manager.revoke_api_token(&admin, &created.api_token.id).await.unwrap();
+        assert_eq!(send(&app, Method::GET, "/api/servers/s1", &created.token).await, StatusCode::UNAUTHORIZED);
+
+        // Expires the moment it is created
+        let request = crate::core::auth::CreateApiTokenRequest {
+            name: "short-lived".to_string(),
+            scopes: vec![Permission::ViewServer],
+            preset: None,
+            server_ids: Vec::new(),
+            expires_in_days: Some(0),
+        };
+        let created = auth_manager.create_api_token(&admin, request).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert_eq!(send(&app, Method::GET, "/api/servers/s1", &created.token).await, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -3,6 +3,8 @@ pub mod app_state;
 pub mod config;
 pub mod guardian_config;
 pub mod crash_watchdog;
+pub mod crash_loop;
+pub mod restart_policy;
 pub mod scheduler;
 pub mod resource_monitor;
 pub mod test_harness;
@@ -13,6 +15,9 @@ pub mod security;
 pub mod monitoring;
 pub mod auth;
 pub mod middleware;
+pub mod audit;
+pub mod tls;
+pub mod remote;
 pub mod error_handler;
 pub mod retry;
 pub mod retry_backoff;
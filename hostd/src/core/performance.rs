@@ -618,8 +618,16 @@ mod tests {
         monitor.record_error("api").await;
         monitor.record_error("database").await;
         
-        // Test metrics retrieval
-        let metrics = monitor.get_current_metrics().await;
+        // Test metrics retrieval; the first sample is taken as monitoring starts
+        monitor.start_monitoring().await.unwrap();
+        let mut metrics = None;
+        for _ in 0..100 {
+            metrics = monitor.get_current_metrics().await;
+            if metrics.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
         assert!(metrics.is_some());
     }
     
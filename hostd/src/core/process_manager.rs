@@ -4,20 +4,26 @@ use std::time::{Duration, Instant};
 use tokio::sync::{RwLock, Mutex};
 use uuid::Uuid;
 use tokio::process::Command as TokioCommand;
-use tokio::io::{AsyncBufReadExt, BufReader, AsyncWriteExt};
-use tokio::process::Child as TokioChild;
-use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, BufReader, AsyncWriteExt};
+use tokio::process::{Child as TokioChild, ChildStdin};
+use std::path::{Path, PathBuf};
 use std::fs;
 use serde_json;
 use std::process::Stdio;
 
-use crate::database::ServerConfig;
+use crate::database::{ServerConfig, ServerProcessRecord};
 use crate::core::{
     error_handler::{AppError, Result},
     credential_manager::CredentialManager,
 };
 use crate::database::DatabaseManager;
+use crate::resource_limits::{self, AppliedLimits, ResourceLimits};
 use crate::websocket_manager::WebSocketManager;
+use crate::downloads::{Checksum, Download};
+use crate::metadata_cache;
+
+/// How long a server gets to save and exit after `stop` before it is killed
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Clone)]
 pub struct ProcessInfo {
@@ -33,15 +39,73 @@ pub struct ProcessInfo {
     pub memory_usage: f32,
     pub uptime: Duration,
     pub last_heartbeat: chrono::DateTime<chrono::Utc>,
+    /// CPU affinity, priority and memory cap in force, if any were set
+    pub resource_limits: Option<AppliedLimits>,
 }
 
 #[derive(Debug)]
 struct ServerProcess {
-    child: TokioChild,
+    /// None for a server re-attached after a hostd restart
+    child: Option<TokioChild>,
+    pid: u32,
+    process_started_at: u64,
     start_time: Instant,
-    last_heartbeat: chrono::DateTime<chrono::Utc>,
     rcon_port: u16,
     rcon_password: String,
+    /// The server's console input; None for a re-attached server, whose stdin
+    /// went away with the hostd that started it
+    stdin: Option<Arc<Mutex<ChildStdin>>>,
+}
+
+impl ServerProcess {
+    fn has_exited(&mut self) -> bool {
+        match self.child.as_mut() {
+            Some(child) => child.try_wait().unwrap_or(None).is_some(),
+            None => !is_same_process(self.pid, self.process_started_at),
+        }
+    }
+
+    /// Wait up to `wait` for the process to exit and kill it otherwise;
+    /// false when it had to be killed
+    async fn wait_or_kill(&mut self, wait: Duration) -> bool {
+        if let Some(child) = self.child.as_mut() {
+            if tokio::time::timeout(wait, child.wait()).await.is_ok() {
+                return true;
+            }
+            let _ = child.kill().await;
+            return false;
+        }
+
+        let deadline = Instant::now() + wait;
+        while Instant::now() < deadline {
+            if !is_same_process(self.pid, self.process_started_at) {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        kill_process(self.pid, self.process_started_at)
+    }
+}
+
+/// How a console command reaches the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandTransport {
+    /// Over RCON, which returns the command's output
+    Rcon,
+    /// Written to the server's console; output shows up in the console stream
+    Stdin,
+}
+
+impl CommandTransport {
+    /// RCON when the server.properties enable it, the console otherwise
+    pub fn for_properties(properties: &str) -> Self {
+        let properties = crate::server_import::parse_properties(properties);
+        match properties.get("enable-rcon").map(|value| value.trim()) {
+            Some("true") => Self::Rcon,
+            _ => Self::Stdin,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -102,6 +166,22 @@ impl ProcessManager {
         
         tracing::info!("Starting server process for: {}", server_name);
         
+        // The server would exit at once, with nothing but a line in its log to say why
+        let eula_ready = crate::eula::ready_to_start(self.database.as_deref(), &self.get_server_directory(&config)).await
+            .map_err(|e| AppError::FileSystemError {
+                message: format!("Failed to read eula.txt: {}", e),
+                path: "eula.txt".to_string(),
+                operation: "read".to_string(),
+            })?;
+        if !eula_ready {
+            return Err(AppError::ValidationError {
+                message: format!("The Minecraft EULA ({}) must be accepted before {} can start", crate::eula::EULA_URL, server_name),
+                field: "eula".to_string(),
+                value: "false".to_string(),
+                constraint: crate::eula::REQUIRED_CODE.to_string(),
+            });
+        }
+        
         // Atomic check-and-set operation to prevent race conditions
         {
             let mut server_states = self.server_states.write().await;
@@ -144,41 +224,50 @@ impl ProcessManager {
         // Create server.properties file
         self.create_server_properties(&config).await?;
         
-        // Create eula.txt file
-        self.create_eula_file(&server_dir).await?;
-        
         // Generate secure RCON password
         let rcon_password = self.credential_manager.generate_rcon_password(server_id).await?;
         
+        // Add JVM arguments: `jvm_args` as edited or set from a preset, else those set at creation
+        let mut args: Vec<String> = if config.jvm_args.trim().is_empty() {
+            serde_json::from_str(&config.java_args).unwrap_or_default()
+        } else {
+            config.jvm_args.split_whitespace().map(str::to_string).collect()
+        };
+        
+        // Add memory settings, unless the arguments already size the heap
+        if !args.iter().any(|arg| arg.starts_with("-Xmx")) {
+            args.push(format!("-Xmx{}M", config.memory));
+        }
+        if !args.iter().any(|arg| arg.starts_with("-Xms")) {
+            args.push(format!("-Xms{}M", config.memory / 2));
+        }
+        
+        // Add server JAR
+        args.push("-jar".to_string());
+        args.push(jar_path.to_string_lossy().to_string());
+        
+        // Add server arguments
+        let server_args: Vec<String> = serde_json::from_str(&config.server_args).unwrap_or_default();
+        args.extend(server_args);
+        
         // Start the actual Minecraft server process
-        let child = {
+        let mut child = {
             let mut cmd = TokioCommand::new(&config.java_path);
             cmd.current_dir(&server_dir);
+            cmd.args(&args);
             
-            // Add JVM arguments
-            let java_args: Vec<String> = serde_json::from_str(&config.java_args).unwrap_or_default();
-            for arg in &java_args {
-                cmd.arg(arg);
-            }
-            
-            // Add memory settings
-            cmd.arg(format!("-Xmx{}M", config.memory));
-            cmd.arg(format!("-Xms{}M", config.memory / 2));
-            
-            // Add server JAR
-            cmd.arg("-jar");
-            cmd.arg(&jar_path);
-            
-            // Add server arguments
-            let server_args: Vec<String> = serde_json::from_str(&config.server_args).unwrap_or_default();
-            for arg in &server_args {
-                cmd.arg(arg);
-            }
-            
-            // Set up process
+            // Set up process; stdin stays open as the server's console
+            cmd.stdin(Stdio::piped());
             cmd.stdout(Stdio::piped());
             cmd.stderr(Stdio::piped());
             
+            // A process group of its own keeps Ctrl+C on hostd from reaching the
+            // server, so it keeps running for a re-attach if hostd goes away
+            #[cfg(unix)]
+            cmd.process_group(0);
+            #[cfg(windows)]
+            cmd.creation_flags(0x0000_0200); // CREATE_NEW_PROCESS_GROUP
+            
             cmd.spawn()
                 .map_err(|e| {
                     // Reset state to Stopped on failure
@@ -197,7 +286,9 @@ impl ProcessManager {
                 })?
         };
         
+        let stdin = child.stdin.take().map(|stdin| Arc::new(Mutex::new(stdin)));
         let pid = child.id().unwrap_or(0);
+        let process_started_at = process_start_time(pid).unwrap_or(0);
         
         // Create process info
         let process_info = ProcessInfo {
@@ -213,15 +304,18 @@ impl ProcessManager {
             memory_usage: 0.0,
             uptime: Duration::ZERO,
             last_heartbeat: chrono::Utc::now(),
+            resource_limits: None,
         };
         
         // Store process and info atomically
         let server_process = ServerProcess {
-            child,
+            child: Some(child),
+            pid,
+            process_started_at,
             start_time: Instant::now(),
-            last_heartbeat: chrono::Utc::now(),
             rcon_port: config.rcon_port,
             rcon_password,
+            stdin,
         };
         
         // Use a single write lock to update all related data atomically
@@ -235,6 +329,24 @@ impl ProcessManager {
             server_states.insert(server_id, ServerState::Running);
         }
         
+        // Remember the launch, so a restarted hostd can find the server again
+        if let Some(database) = &self.database {
+            let record = ServerProcessRecord {
+                server_id: server_id.to_string(),
+                pid,
+                process_started_at: process_started_at as i64,
+                java_path: config.java_path.clone(),
+                server_directory: server_dir.to_string_lossy().to_string(),
+                command_line: serde_json::to_string(&args).unwrap_or_else(|_| "[]".to_string()),
+                started_at: chrono::Utc::now(),
+            };
+            if let Err(e) = database.save_server_process(&record).await {
+                tracing::warn!("Failed to record process of server {}: {}", server_id, e);
+            }
+        }
+        
+        self.restore_resource_limits(server_id).await;
+        
         // Start monitoring task
         self.start_monitoring_task(server_id).await;
         
@@ -246,6 +358,12 @@ impl ProcessManager {
     }
     
     pub async fn stop_server_process(&self, server_id: Uuid) -> Result<()> {
+        self.stop_server_process_within(server_id, STOP_TIMEOUT).await
+    }
+    
+    /// Ask the server to stop over RCON, or on its console when RCON is not
+    /// up, and kill it if it has not exited within `wait`
+    pub async fn stop_server_process_within(&self, server_id: Uuid, wait: Duration) -> Result<()> {
         tracing::info!("Stopping server process: {}", server_id);
         
         // Set state to Stopping atomically
@@ -254,28 +372,28 @@ impl ProcessManager {
             server_states.insert(server_id, ServerState::Stopping);
         }
         
-        // Stop the process and clean up
-        {
-            let mut processes = self.processes.write().await;
-            let mut process_info = self.process_info.write().await;
-            let mut server_states = self.server_states.write().await;
-            
-            if let Some(mut process) = processes.remove(&server_id) {
-                // Send stop command to the server
-                if let Some(mut stdin) = process.child.stdin.take() {
+        // Take the process out first, so other servers can stop while this one saves
+        let process = self.processes.write().await.remove(&server_id);
+        if let Some(mut process) = process {
+            let rcon = crate::rcon::RconClient::new("127.0.0.1".to_string(), process.rcon_port, process.rcon_password.clone());
+            let sent = matches!(tokio::task::spawn_blocking(move || rcon.send_command("stop")).await, Ok(Ok(_)));
+            if !sent {
+                if let Some(stdin) = &process.stdin {
+                    let mut stdin = stdin.lock().await;
                     let _ = stdin.write_all(b"stop\n").await;
+                    let _ = stdin.flush().await;
                 }
-                
-                // Wait for the process to exit gracefully
-                let _ = tokio::time::timeout(Duration::from_secs(30), process.child.wait()).await;
-                
-                // Force kill if still running
-                let _ = process.child.kill().await;
             }
             
-            process_info.remove(&server_id);
-            server_states.insert(server_id, ServerState::Stopped);
+            // Wait for the process to exit gracefully, then force kill
+            if !process.wait_or_kill(wait).await {
+                tracing::warn!("Server {} did not stop within {:?}, killed it", server_id, wait);
+            }
         }
+        forget_process(&self.database, server_id).await;
+        
+        self.process_info.write().await.remove(&server_id);
+        self.server_states.write().await.insert(server_id, ServerState::Stopped);
         
         // Stop monitoring task
         self.stop_monitoring_task(server_id).await;
@@ -306,6 +424,184 @@ impl ProcessManager {
         Ok(())
     }
     
+    /// Servers with a process, whether still starting or running
+    pub async fn running_servers(&self) -> Vec<Uuid> {
+        self.processes.read().await.keys().copied().collect()
+    }
+    
+    /// Take back servers that kept running while hostd was down, and forget
+    /// the ones that exited in the meantime
+    pub async fn reattach_servers(&self) -> Result<Vec<Uuid>> {
+        let Some(database) = self.database.clone() else {
+            return Ok(Vec::new());
+        };
+        let records = database.get_server_processes().await
+            .map_err(|e| AppError::DatabaseError {
+                message: format!("Failed to load server processes: {}", e),
+                operation: "select".to_string(),
+                table: Some("server_processes".to_string()),
+            })?;
+        
+        let mut reattached = Vec::new();
+        for record in records {
+            let Ok(server_id) = Uuid::parse_str(&record.server_id) else {
+                continue;
+            };
+            let process_started_at = record.process_started_at.max(0) as u64;
+            if !is_same_process(record.pid, process_started_at) {
+                tracing::info!("Server {} (PID {}) exited while hostd was down", server_id, record.pid);
+                forget_process(&self.database, server_id).await;
+                continue;
+            }
+            
+            let config = database.get_server(&record.server_id).await.ok().flatten();
+            let server_dir = PathBuf::from(&record.server_directory);
+            let (rcon_port, rcon_password) = {
+                let properties = fs::read_to_string(server_dir.join("server.properties")).unwrap_or_default();
+                let (default_port, default_password) = config.as_ref()
+                    .map(|config| (config.rcon_port, config.rcon_password.clone()))
+                    .unwrap_or((25575, String::new()));
+                rcon_settings(&properties, default_port, &default_password)
+            };
+            let uptime = (chrono::Utc::now() - record.started_at).to_std().unwrap_or_default();
+            
+            let server_process = ServerProcess {
+                child: None,
+                pid: record.pid,
+                process_started_at,
+                start_time: Instant::now().checked_sub(uptime).unwrap_or_else(Instant::now),
+                rcon_port,
+                rcon_password,
+                stdin: None,
+            };
+            let process_info = ProcessInfo {
+                id: server_id,
+                name: config.as_ref().map(|config| config.name.clone()).unwrap_or_else(|| "Unknown".to_string()),
+                pid: record.pid,
+                tps: 20.0,
+                tick_p95: 45.0,
+                heap_mb: config.as_ref().map(|config| config.memory).unwrap_or(0),
+                players_online: 0,
+                gpu_queue_ms: 0.0,
+                cpu_usage: 0.0,
+                memory_usage: 0.0,
+                uptime,
+                last_heartbeat: chrono::Utc::now(),
+                resource_limits: None,
+            };
+            
+            {
+                let mut processes = self.processes.write().await;
+                let mut process_info_guard = self.process_info.write().await;
+                let mut server_states = self.server_states.write().await;
+                
+                processes.insert(server_id, server_process);
+                process_info_guard.insert(server_id, process_info);
+                server_states.insert(server_id, ServerState::Running);
+            }
+            
+            self.restore_resource_limits(server_id).await;
+            self.start_monitoring_task(server_id).await;
+            self.start_log_tail(server_id, server_dir.join("logs").join("latest.log"));
+            let _ = self.websocket.send_server_status_update(server_id, "running").await;
+            
+            tracing::info!("Re-attached to server {} (PID {})", server_id, record.pid);
+            reattached.push(server_id);
+        }
+        
+        Ok(reattached)
+    }
+    
+    /// Apply resource limits to a running server; None when it isn't running
+    pub async fn apply_resource_limits(&self, server_id: Uuid, limits: &ResourceLimits) -> Option<AppliedLimits> {
+        let pid = self.processes.read().await.get(&server_id)?.pid;
+        let applied = resource_limits::apply(pid, &server_id.to_string(), limits);
+        if let Some(info) = self.process_info.write().await.get_mut(&server_id) {
+            info.resource_limits = Some(applied.clone());
+        }
+        Some(applied)
+    }
+    
+    /// Resource limits in force on a running server
+    pub async fn applied_resource_limits(&self, server_id: Uuid) -> Option<AppliedLimits> {
+        self.process_info.read().await.get(&server_id)?.resource_limits.clone()
+    }
+    
+    /// Apply a server's saved resource limits after it starts or is re-attached
+    async fn restore_resource_limits(&self, server_id: Uuid) {
+        let Some(database) = &self.database else {
+            return;
+        };
+        match database.get_resource_limits(&server_id.to_string()).await {
+            Ok(limits) if !limits.is_unlimited() => {
+                self.apply_resource_limits(server_id, &limits).await;
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to load resource limits of server {}: {}", server_id, e),
+        }
+    }
+    
+    /// Follow the server's latest.log in place of the console pipe a
+    /// re-attached server no longer has
+    fn start_log_tail(&self, server_id: Uuid, log_path: PathBuf) {
+        let processes = self.processes.clone();
+        let websocket = self.websocket.clone();
+        let database = self.database.clone();
+        
+        tokio::spawn(async move {
+            // Start at the end; what was logged while hostd was down stays in the file
+            let mut offset = tokio::fs::metadata(&log_path).await.map(|m| m.len()).unwrap_or(0);
+            let mut pending = String::new();
+            let mut interval = tokio::time::interval(Duration::from_secs(1));
+            
+            while processes.read().await.contains_key(&server_id) {
+                interval.tick().await;
+                
+                let Ok(mut file) = tokio::fs::File::open(&log_path).await else {
+                    continue;
+                };
+                let len = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+                if len < offset {
+                    // Rotated to a new latest.log
+                    offset = 0;
+                    pending.clear();
+                }
+                if len == offset || file.seek(std::io::SeekFrom::Start(offset)).await.is_err() {
+                    continue;
+                }
+                let mut buf = Vec::new();
+                if file.read_to_end(&mut buf).await.is_err() {
+                    continue;
+                }
+                offset += buf.len() as u64;
+                pending.push_str(&String::from_utf8_lossy(&buf));
+                
+                for line in take_complete_lines(&mut pending) {
+                    let console_message = crate::websocket_manager::WebSocketMessage::ConsoleMessage {
+                        server_id: server_id.to_string(),
+                        timestamp: chrono::Utc::now(),
+                        level: log_line_level(&line).to_string(),
+                        message: line.clone(),
+                    };
+                    if let Err(e) = websocket.broadcast(console_message).await {
+                        tracing::error!("Failed to send console message via WebSocket: {}", e);
+                    }
+                    
+                    if let Some(db) = &database {
+                        if let Err(e) = db.log_server_message(
+                            &server_id.to_string(),
+                            log_line_level(&line),
+                            &line,
+                            Some("Console"),
+                        ).await {
+                            tracing::error!("Failed to log console message to database: {}", e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+    
     pub async fn is_server_running(&self, server_id: Uuid) -> bool {
         let server_states = self.server_states.read().await;
         matches!(server_states.get(&server_id), Some(ServerState::Running))
@@ -327,6 +623,34 @@ impl ProcessManager {
             })
     }
     
+    /// Write a command to the server's console, as if typed into its terminal.
+    /// Works without RCON, but the command's output only appears in the log.
+    pub async fn send_console_input(&self, server_id: Uuid, command: &str) -> Result<()> {
+        let stdin = {
+            let processes = self.processes.read().await;
+            let process = processes.get(&server_id).ok_or_else(|| AppError::ServerError {
+                message: "Server is not running".to_string(),
+                server_id: server_id.to_string(),
+                operation: "console".to_string(),
+            })?;
+            process.stdin.clone().ok_or_else(|| AppError::ServerError {
+                message: "The console of a server re-attached after a hostd restart is only reachable over RCON".to_string(),
+                server_id: server_id.to_string(),
+                operation: "console".to_string(),
+            })?
+        };
+        
+        let line = format!("{}\n", command.trim_end_matches(['\r', '\n']));
+        let mut stdin = stdin.lock().await;
+        stdin.write_all(line.as_bytes()).await
+            .and(stdin.flush().await)
+            .map_err(|e| AppError::ProcessError {
+                message: format!("Failed to write to the server console: {}", e),
+                process_id: None,
+                operation: "console".to_string(),
+            })
+    }
+    
     pub async fn send_rcon_command(&self, server_id: Uuid, command: &str) -> Result<()> {
         let processes = self.processes.read().await;
         
@@ -405,6 +729,7 @@ impl ProcessManager {
             server_jar: "server.jar".to_string(),
             server_directory: server_dir.to_string_lossy().to_string(),
             rcon_password: "".to_string(),
+            managed: true,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         })
@@ -416,7 +741,9 @@ impl ProcessManager {
     
     fn get_server_jar_path(&self, config: &ServerConfig) -> Result<PathBuf> {
         let server_dir = self.get_server_directory(config);
-        Ok(server_dir.join("server.jar"))
+        // Imported servers keep their own jar, e.g. fabric-server-launch.jar
+        let jar_name = Path::new(&config.server_jar).file_name().unwrap_or("server.jar".as_ref());
+        Ok(server_dir.join(jar_name))
     }
     
     async fn download_server_jar(&self, config: &ServerConfig) -> Result<()> {
@@ -461,6 +788,11 @@ impl ProcessManager {
     async fn create_server_properties(&self, config: &ServerConfig) -> Result<()> {
         let server_dir = self.get_server_directory(config);
         let properties_path = server_dir.join("server.properties");
+        // An existing file is the server's own configuration, edited through
+        // config revisions or brought along by an import
+        if properties_path.exists() {
+            return Ok(());
+        }
         
         let properties = format!(
             r#"#Minecraft server properties
@@ -506,33 +838,20 @@ simulation-distance={}
         Ok(())
     }
     
-    async fn create_eula_file(&self, server_dir: &PathBuf) -> Result<()> {
-        let eula_path = server_dir.join("eula.txt");
-        let eula_content = "eula=true\n";
-        
-        fs::write(&eula_path, eula_content)
-            .map_err(|e| AppError::FileSystemError {
-                message: format!("Failed to create eula.txt: {}", e),
-                path: eula_path.to_string_lossy().to_string(),
-                operation: "create".to_string(),
-            })?;
-        
-        Ok(())
-    }
-    
-    
     async fn start_monitoring_task(&self, server_id: Uuid) {
         let processes = self.processes.clone();
         let process_info = self.process_info.clone();
         let server_states = self.server_states.clone();
         let websocket = self.websocket.clone();
         let monitoring_tasks = self.monitoring_tasks.clone();
+        let database = self.database.clone();
         
         // Cancel any existing monitoring task for this server
         self.stop_monitoring_task(server_id).await;
         
         let task_handle = tokio::spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_secs(5));
+            let mut exited = false;
             
             loop {
                 interval.tick().await;
@@ -544,8 +863,9 @@ simulation-distance={}
                 
                 if let Some(process) = processes_guard.get_mut(&server_id) {
                     // Check if process is still alive
-                    if process.child.try_wait().unwrap_or(None).is_some() {
+                    if process.has_exited() {
                         // Process has exited
+                        exited = true;
                         processes_guard.remove(&server_id);
                         info_guard.remove(&server_id);
                         states_guard.insert(server_id, ServerState::Crashed);
@@ -572,6 +892,7 @@ simulation-distance={}
                             "cpu_usage": info.cpu_usage,
                             "memory_usage": info.memory_usage,
                             "uptime_seconds": info.uptime.as_secs(),
+                            "resource_limits": info.resource_limits,
                             "timestamp": chrono::Utc::now()
                         });
                         
@@ -584,6 +905,10 @@ simulation-distance={}
                 }
             }
             
+            if exited {
+                forget_process(&database, server_id).await;
+            }
+            
             // Clean up task handle when done
             let mut tasks_guard = monitoring_tasks.write().await;
             tasks_guard.remove(&server_id);
@@ -705,25 +1030,10 @@ simulation-distance={}
     }
     
     async fn download_vanilla_server_jar(&self, version: &str, dest_path: &std::path::Path) -> Result<()> {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(60))
-            .build()
+        let manifest_url = crate::version_catalog::MOJANG_MANIFEST_URL;
+        let manifest = metadata_cache::get_json(manifest_url, metadata_cache::MOJANG_TTL).await
             .map_err(|e| AppError::NetworkError {
-                message: format!("Failed to create HTTP client: {}", e),
-                endpoint: "".to_string(),
-                status_code: None,
-            })?;
-            
-        let manifest_url = "https://launchermeta.mojang.com/mc/game/version_manifest_v2.json";
-        let manifest: serde_json::Value = client.get(manifest_url).send().await
-            .map_err(|e| AppError::NetworkError {
-                message: format!("Failed to fetch version manifest: {}", e),
-                endpoint: manifest_url.to_string(),
-                status_code: None,
-            })?
-            .json().await
-            .map_err(|e| AppError::NetworkError {
-                message: format!("Failed to parse version manifest: {}", e),
+                message: format!("Failed to fetch version manifest: {:#}", e),
                 endpoint: manifest_url.to_string(),
                 status_code: None,
             })?;
@@ -749,15 +1059,9 @@ simulation-distance={}
             constraint: "must be present".to_string(),
         })?;
         
-        let ver_json: serde_json::Value = client.get(ver_url).send().await
-            .map_err(|e| AppError::NetworkError {
-                message: format!("Failed to fetch version info: {}", e),
-                endpoint: ver_url.to_string(),
-                status_code: None,
-            })?
-            .json().await
+        let ver_json = metadata_cache::get_json(ver_url, metadata_cache::MOJANG_TTL).await
             .map_err(|e| AppError::NetworkError {
-                message: format!("Failed to parse version info: {}", e),
+                message: format!("Failed to fetch version info: {:#}", e),
                 endpoint: ver_url.to_string(),
                 status_code: None,
             })?;
@@ -769,24 +1073,12 @@ simulation-distance={}
             constraint: "must be present".to_string(),
         })?;
         
-        let bytes = client.get(server_url).send().await
+        let sha1 = ver_json["downloads"]["server"]["sha1"].as_str().map(|sha1| Checksum::Sha1(sha1.to_string()));
+        crate::downloads::shared().fetch_to(&Download::new(server_url).checksum(sha1), dest_path).await
             .map_err(|e| AppError::NetworkError {
-                message: format!("Failed to download server JAR: {}", e),
+                message: format!("Failed to download server JAR: {:#}", e),
                 endpoint: server_url.to_string(),
                 status_code: None,
-            })?
-            .bytes().await
-            .map_err(|e| AppError::NetworkError {
-                message: format!("Failed to read server JAR bytes: {}", e),
-                endpoint: server_url.to_string(),
-                status_code: None,
-            })?;
-            
-        fs::write(dest_path, bytes)
-            .map_err(|e| AppError::FileSystemError {
-                message: format!("Failed to write server JAR: {}", e),
-                path: dest_path.to_string_lossy().to_string(),
-                operation: "write".to_string(),
             })?;
             
         tracing::info!("Downloaded vanilla server JAR for version {} to {:?}", version, dest_path);
@@ -857,6 +1149,85 @@ simulation-distance={}
     }
 }
 
+/// OS start time of a process in seconds since the epoch
+fn process_start_time(pid: u32) -> Option<u64> {
+    let pid = sysinfo::Pid::from_u32(pid);
+    let mut system = sysinfo::System::new();
+    system.refresh_process(pid);
+    system.process(pid).map(|process| process.start_time())
+}
+
+/// Whether `pid` is still the process that started at `started_at`, rather
+/// than another program the OS has since given the PID to
+fn is_same_process(pid: u32, started_at: u64) -> bool {
+    match process_start_time(pid) {
+        Some(start) => started_at == 0 || start.abs_diff(started_at) <= 1,
+        None => false,
+    }
+}
+
+/// Kill a process hostd has no child handle for; true when it had already exited
+fn kill_process(pid: u32, started_at: u64) -> bool {
+    if !is_same_process(pid, started_at) {
+        return true;
+    }
+    let pid = sysinfo::Pid::from_u32(pid);
+    let mut system = sysinfo::System::new();
+    system.refresh_process(pid);
+    if let Some(process) = system.process(pid) {
+        process.kill();
+    }
+    false
+}
+
+/// Drop the launch record of a server whose process is gone
+async fn forget_process(database: &Option<Arc<DatabaseManager>>, server_id: Uuid) {
+    resource_limits::release(&server_id.to_string());
+    if let Some(database) = database {
+        if let Err(e) = database.delete_server_process(&server_id.to_string()).await {
+            tracing::warn!("Failed to clear process record of server {}: {}", server_id, e);
+        }
+    }
+}
+
+/// RCON port and password the server runs with, from its server.properties
+fn rcon_settings(properties: &str, default_port: u16, default_password: &str) -> (u16, String) {
+    let properties = crate::server_import::parse_properties(properties);
+    let port = properties.get("rcon.port")
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(default_port);
+    let password = properties.get("rcon.password")
+        .filter(|password| !password.is_empty())
+        .cloned()
+        .unwrap_or_else(|| default_password.to_string());
+    (port, password)
+}
+
+/// Split off the finished lines, leaving a partly written last line for the next read
+fn take_complete_lines(pending: &mut String) -> Vec<String> {
+    let Some(end) = pending.rfind('\n') else {
+        return Vec::new();
+    };
+    let rest = pending.split_off(end + 1);
+    let lines = pending.lines()
+        .map(|line| line.trim_end().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+    *pending = rest;
+    lines
+}
+
+/// Level of a log4j line such as `[12:00:00] [Server thread/WARN]: ...`
+fn log_line_level(line: &str) -> &'static str {
+    if line.contains("/ERROR]") || line.contains("/FATAL]") {
+        "ERROR"
+    } else if line.contains("/WARN]") {
+        "WARN"
+    } else {
+        "INFO"
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ProcessMetrics {
     pub tps: f32,
@@ -866,4 +1237,42 @@ pub struct ProcessMetrics {
     pub gpu_queue_ms: f32,
     pub cpu_usage: f32,
     pub memory_usage: f32,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_same_process_checks_start_time() {
+        let pid = std::process::id();
+        let started_at = process_start_time(pid).expect("own process");
+        assert!(is_same_process(pid, started_at));
+        assert!(is_same_process(pid, 0));
+        assert!(!is_same_process(pid, started_at + 3600));
+    }
+
+    #[test]
+    fn test_rcon_settings_prefer_server_properties() {
+        let properties = "enable-rcon=true\nrcon.port=25580\nrcon.password=s3cret\n";
+        assert_eq!(rcon_settings(properties, 25575, "db"), (25580, "s3cret".to_string()));
+        assert_eq!(rcon_settings("rcon.password=\n", 25575, "db"), (25575, "db".to_string()));
+    }
+
+    #[test]
+    fn test_command_transport_follows_enable_rcon() {
+        assert_eq!(CommandTransport::for_properties("enable-rcon=true\nrcon.port=25575\n"), CommandTransport::Rcon);
+        assert_eq!(CommandTransport::for_properties("enable-rcon=false\n"), CommandTransport::Stdin);
+        assert_eq!(CommandTransport::for_properties(""), CommandTransport::Stdin);
+    }
+    
+    #[test]
+    fn test_take_complete_lines_keeps_partial_line() {
+        let mut pending = "[12:00:00] [Server thread/INFO]: Done\r\n\n[12:00:01] [Server thread/WARN]: Can't".to_string();
+        let lines = take_complete_lines(&mut pending);
+        assert_eq!(lines, vec!["[12:00:00] [Server thread/INFO]: Done".to_string()]);
+        assert_eq!(pending, "[12:00:01] [Server thread/WARN]: Can't");
+        assert_eq!(log_line_level(&pending), "WARN");
+        assert_eq!(log_line_level(&lines[0]), "INFO");
+    }
+}
@@ -0,0 +1,155 @@
+//! Remote management: which browser origins may call the API and how remote
+//! clients reach this host.
+
+use axum::http::{header, HeaderName, HeaderValue};
+use serde::Serialize;
+use std::net::IpAddr;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::core::guardian_config::GuardianConfig;
+
+/// Origins of the desktop app and the UI dev servers, always allowed
+pub const APP_ORIGINS: &[&str] = &[
+    "tauri://localhost",
+    "http://tauri.localhost",
+    "https://tauri.localhost",
+    "http://localhost:3000",
+    "http://localhost:5173",
+    "http://127.0.0.1:3000",
+    "http://127.0.0.1:5173",
+];
+
+/// How to reach this hostd from another device
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionInfo {
+    pub remote_mode: bool,
+    pub tls_enabled: bool,
+    pub bind_address: String,
+    /// Best guess at the address other devices should use
+    pub url: String,
+    pub websocket_url: String,
+    /// Every address the API is reachable on
+    pub urls: Vec<String>,
+    pub allowed_origins: Vec<String>,
+    /// `url` as a QR code, for pairing a phone or tablet
+    pub qr_code_svg: String,
+}
+
+pub fn is_loopback_host(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost") || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+fn is_wildcard_host(host: &str) -> bool {
+    host.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified())
+}
+
+/// CORS for the API. Outside remote mode any origin is allowed unless an
+/// allowlist is configured; in remote mode only the app and the allowlist are.
+pub fn cors_layer(config: &GuardianConfig) -> CorsLayer {
+    // Browsers only let scripts read the version and deprecation headers if exposed
+    let layer = CorsLayer::new().allow_methods(Any).allow_headers(Any).expose_headers([
+        HeaderName::from_static(crate::middleware::versioning::VERSION_HEADER),
+        HeaderName::from_static("deprecation"),
+        header::LINK,
+    ]);
+    let allow_any = config.allowed_origins.iter().any(|origin| origin == "*")
+        || (!config.remote_mode && config.allowed_origins.is_empty());
+    if allow_any {
+        return layer.allow_origin(Any);
+    }
+
+    let origins: Vec<HeaderValue> = APP_ORIGINS
+        .iter()
+        .map(|origin| origin.to_string())
+        .chain(config.allowed_origins.iter().cloned())
+        .filter_map(|origin| match HeaderValue::from_str(origin.trim_end_matches('/')) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid allowed origin: {}", origin);
+                None
+            }
+        })
+        .collect();
+    layer.allow_origin(AllowOrigin::list(origins))
+}
+
+/// Base URLs for a listener on `bind_host`. A wildcard bind is reachable on
+/// every interface address, given in `addresses`.
+pub fn api_urls(scheme: &str, bind_host: &str, port: u16, addresses: &[IpAddr]) -> Vec<String> {
+    let format_host = |host: String| if host.contains(':') { format!("[{}]", host) } else { host };
+    let hosts: Vec<String> = if is_wildcard_host(bind_host) {
+        let mut hosts: Vec<IpAddr> = addresses
+            .iter()
+            .copied()
+            .filter(|ip| ip.is_ipv4() || bind_host.contains(':'))
+            .collect();
+        // Addresses on the LAN first, loopback last
+        hosts.sort_by_key(|ip| ip.is_loopback());
+        hosts.into_iter().map(|ip| ip.to_string()).collect()
+    } else {
+        vec![bind_host.to_string()]
+    };
+    hosts.into_iter().map(|host| format!("{}://{}:{}", scheme, format_host(host), port)).collect()
+}
+
+/// Addresses of this host's network interfaces, without link-local ones
+fn interface_addresses() -> Vec<IpAddr> {
+    match if_addrs::get_if_addrs() {
+        Ok(interfaces) => interfaces
+            .into_iter()
+            .filter(|interface| !interface.is_link_local())
+            .map(|interface| interface.ip())
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Failed to list network interfaces: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+pub fn connection_info(config: &GuardianConfig) -> anyhow::Result<ConnectionInfo> {
+    let mut urls = api_urls(config.scheme(), &config.guardian_host, config.guardian_port, &interface_addresses());
+    if urls.is_empty() {
+        urls = api_urls(config.scheme(), "127.0.0.1", config.guardian_port, &[]);
+    }
+    let url = urls[0].clone();
+    let websocket_url = format!("{}/ws", url.replacen("http", "ws", 1));
+
+    let qr_code_svg = qrcode::QrCode::new(url.as_bytes())?
+        .render::<qrcode::render::svg::Color>()
+        .min_dimensions(200, 200)
+        .build();
+
+    Ok(ConnectionInfo {
+        remote_mode: config.remote_mode,
+        tls_enabled: config.tls_enabled,
+        bind_address: config.server_address(),
+        url,
+        websocket_url,
+        urls,
+        allowed_origins: config.allowed_origins.clone(),
+        qr_code_svg,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_urls_for_wildcard_and_specific_binds() {
+        let addresses: Vec<IpAddr> = ["127.0.0.1", "192.168.1.20", "fd00::1"].iter().map(|ip| ip.parse().unwrap()).collect();
+
+        assert_eq!(
+            api_urls("https", "0.0.0.0", 52100, &addresses),
+            vec!["https://192.168.1.20:52100", "https://127.0.0.1:52100"]
+        );
+        assert_eq!(api_urls("http", "::", 52100, &addresses)[1], "http://[fd00::1]:52100");
+        assert_eq!(api_urls("http", "guardian.lan", 8080, &addresses), vec!["http://guardian.lan:8080"]);
+
+        assert!(is_loopback_host("localhost"));
+        assert!(is_loopback_host("::1"));
+        assert!(!is_loopback_host("0.0.0.0"));
+        assert!(!is_loopback_host("192.168.1.20"));
+    }
+}
@@ -11,6 +11,8 @@ use crate::core::{
     error_handler::Result,
     guardian_config::GuardianConfig,
 };
+use crate::gpu_manager::GpuMetrics;
+use crate::gpu_telemetry::GpuTelemetry;
 
 /// System resource metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +30,8 @@ pub struct SystemMetrics {
     pub gpu_usage: Option<f32>,
     pub gpu_memory_usage: Option<f32>,
     pub gpu_memory_total: Option<u64>,
+    pub gpu_temperature: Option<f32>,
+    pub gpu_power_usage: Option<f32>,
     pub uptime: Duration,
 }
 
@@ -60,6 +64,10 @@ pub struct AlertThresholds {
     pub memory_usage: f32,
     pub disk_usage: f32,
     pub low_tps: f32,
+    pub gpu_usage: f32,
+    pub gpu_memory_usage: f32,
+    pub gpu_temperature: f32,
+    pub gpu_power_usage: f32,
 }
 
 impl Default for ResourceMonitorConfig {
@@ -72,6 +80,10 @@ impl Default for ResourceMonitorConfig {
                 memory_usage: 85.0,
                 disk_usage: 90.0,
                 low_tps: 15.0,
+                gpu_usage: 95.0,
+                gpu_memory_usage: 90.0,
+                gpu_temperature: 85.0,
+                gpu_power_usage: 350.0,
             },
         }
     }
@@ -187,7 +199,7 @@ impl ResourceMonitor {
         };
 
         // GPU metrics (if available)
-        let (gpu_usage, gpu_memory_usage, gpu_memory_total) = self.get_gpu_metrics().await;
+        let gpu = self.get_gpu_metrics().await;
 
         // System uptime
         let uptime = Duration::from_secs(System::uptime());
@@ -203,22 +215,33 @@ impl ResourceMonitor {
             disk_available,
             network_in: network_in_delta,
             network_out: network_out_delta,
-            gpu_usage,
-            gpu_memory_usage,
-            gpu_memory_total,
+            gpu_usage: gpu.as_ref().map(|gpu| gpu.utilization * 100.0),
+            gpu_memory_usage: gpu.as_ref().and_then(|gpu| {
+                (gpu.memory_total > 0).then(|| gpu.memory_used as f32 / gpu.memory_total as f32 * 100.0)
+            }),
+            gpu_memory_total: gpu.as_ref().map(|gpu| gpu.memory_total),
+            gpu_temperature: gpu.as_ref().map(|gpu| gpu.temperature),
+            gpu_power_usage: gpu.as_ref().map(|gpu| gpu.power_usage),
             uptime,
         })
     }
 
-    /// Get GPU metrics (if GPU is available)
-    async fn get_gpu_metrics(&self) -> (Option<f32>, Option<f32>, Option<u64>) {
-        if !self.guardian_config.gpu_available() {
-            return (None, None, None);
+    /// Get GPU metrics from vendor telemetry (if any GPU sensors are present)
+    async fn get_gpu_metrics(&self) -> Option<GpuMetrics> {
+        if !self.guardian_config.gpu_enabled {
+            return None;
         }
 
-        // This would integrate with the GPU worker to get actual GPU metrics
-        // For now, return placeholder values
-        (Some(0.0), Some(0.0), Some(0))
+        let telemetry = GpuTelemetry::shared();
+        if !telemetry.is_available() {
+            return None;
+        }
+
+        let devices = tokio::task::spawn_blocking(move || telemetry.sample()).await.ok()?;
+        if devices.is_empty() {
+            return None;
+        }
+        Some(GpuMetrics::from_devices(devices))
     }
 
     /// Collect metrics for a specific server
@@ -289,6 +312,20 @@ impl ResourceMonitor {
             warn!("High disk usage: {:.1}%", metrics.disk_usage);
         }
 
+        // GPU alerts
+        if let Some(gpu_usage) = metrics.gpu_usage.filter(|usage| *usage > thresholds.gpu_usage) {
+            warn!("High GPU usage: {:.1}%", gpu_usage);
+        }
+        if let Some(gpu_memory) = metrics.gpu_memory_usage.filter(|usage| *usage > thresholds.gpu_memory_usage) {
+            warn!("High GPU memory usage: {:.1}%", gpu_memory);
+        }
+        if let Some(temperature) = metrics.gpu_temperature.filter(|temp| *temp > thresholds.gpu_temperature) {
+            warn!("High GPU temperature: {:.1}°C", temperature);
+        }
+        if let Some(power) = metrics.gpu_power_usage.filter(|power| *power > thresholds.gpu_power_usage) {
+            warn!("High GPU power draw: {:.1}W", power);
+        }
+
         Ok(())
     }
 
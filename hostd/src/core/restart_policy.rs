@@ -0,0 +1,243 @@
+//! Policy-based restarts: rules such as "heap above 95% for 5 minutes" or
+//! "TPS below 10 for 10 minutes" that the crash watchdog checks against live
+//! samples and answers with a warned, graceful restart.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use super::crash_loop::CrashLoopSettings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyMetric {
+    /// Resident memory of the server process as a percentage of its max heap (`-Xmx`).
+    /// Includes JVM overhead, so it reads a little above the true heap usage.
+    HeapPercent,
+    Tps,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub metric: PolicyMetric,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    /// How long the condition must hold before the server is restarted
+    pub duration_seconds: u64,
+}
+
+impl PolicyRule {
+    fn breached(&self, value: f64) -> bool {
+        match self.comparison {
+            Comparison::Above => value > self.threshold,
+            Comparison::Below => value < self.threshold,
+        }
+    }
+
+    /// e.g. "heap > 95% for 300s"
+    pub fn describe(&self) -> String {
+        let (metric, unit) = match self.metric {
+            PolicyMetric::HeapPercent => ("heap", "%"),
+            PolicyMetric::Tps => ("TPS", ""),
+        };
+        let operator = match self.comparison {
+            Comparison::Above => ">",
+            Comparison::Below => "<",
+        };
+        format!("{} {} {}{} for {}s", metric, operator, self.threshold, unit, self.duration_seconds)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestartPolicy {
+    pub enabled: bool,
+    pub rules: Vec<PolicyRule>,
+    /// Minimum time between two policy restarts of the same server
+    pub cooldown_seconds: u64,
+    /// Warning broadcast to players before the restart
+    pub warning_seconds: u32,
+    /// When crash restarts give up; applies even when the rules are disabled
+    #[serde(default)]
+    pub crash_loop: CrashLoopSettings,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: vec![
+                PolicyRule {
+                    metric: PolicyMetric::HeapPercent,
+                    comparison: Comparison::Above,
+                    threshold: 95.0,
+                    duration_seconds: 300,
+                },
+                PolicyRule {
+                    metric: PolicyMetric::Tps,
+                    comparison: Comparison::Below,
+                    threshold: 10.0,
+                    duration_seconds: 600,
+                },
+            ],
+            cooldown_seconds: 1800,
+            warning_seconds: 60,
+            crash_loop: CrashLoopSettings::default(),
+        }
+    }
+}
+
+impl RestartPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        for rule in &self.rules {
+            if !rule.threshold.is_finite() || rule.threshold < 0.0 {
+                return Err(format!("Invalid threshold in rule '{}'", rule.describe()));
+            }
+            if rule.metric == PolicyMetric::Tps && rule.threshold > 20.0 {
+                return Err(format!("TPS threshold above 20 can never be met: '{}'", rule.describe()));
+            }
+        }
+        self.crash_loop.validate()
+    }
+}
+
+/// One reading of a server's metrics; `None` when it could not be measured
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PolicySample {
+    pub heap_percent: Option<f64>,
+    pub tps: Option<f64>,
+}
+
+impl PolicySample {
+    pub fn get(&self, metric: PolicyMetric) -> Option<f64> {
+        match metric {
+            PolicyMetric::HeapPercent => self.heap_percent,
+            PolicyMetric::Tps => self.tps,
+        }
+    }
+}
+
+/// Per-server progress towards a policy restart
+#[derive(Debug, Clone, Default)]
+pub struct PolicyState {
+    /// Rules the state was built for; a changed policy starts over
+    rules: Vec<PolicyRule>,
+    /// When each rule's condition started holding
+    breached_since: Vec<Option<Instant>>,
+    pub last_restart: Option<Instant>,
+    pub last_restart_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub restarting: bool,
+    pub last_sample: PolicySample,
+}
+
+impl PolicyState {
+    /// Record a sample and return the index of a rule that has held long enough,
+    /// unless the server is still in its cooldown or already restarting
+    pub fn observe(&mut self, policy: &RestartPolicy, sample: PolicySample, now: Instant) -> Option<usize> {
+        if self.rules != policy.rules {
+            self.rules = policy.rules.clone();
+            self.breached_since = vec![None; policy.rules.len()];
+        }
+        self.last_sample = sample;
+
+        for (rule, since) in policy.rules.iter().zip(self.breached_since.iter_mut()) {
+            match sample.get(rule.metric) {
+                Some(value) if rule.breached(value) => {
+                    since.get_or_insert(now);
+                }
+                // A missing reading neither starts nor ends a breach
+                None => {}
+                Some(_) => *since = None,
+            }
+        }
+
+        if self.restarting || self.in_cooldown(policy, now) {
+            return None;
+        }
+        policy.rules.iter().zip(&self.breached_since).position(|(rule, since)| {
+            since.is_some_and(|since| now.duration_since(since) >= Duration::from_secs(rule.duration_seconds))
+        })
+    }
+
+    pub fn in_cooldown(&self, policy: &RestartPolicy, now: Instant) -> bool {
+        self.last_restart
+            .is_some_and(|last| now.duration_since(last) < Duration::from_secs(policy.cooldown_seconds))
+    }
+
+    /// Forget ongoing breaches, e.g. after a restart or while the server is down
+    pub fn reset_breaches(&mut self) {
+        self.breached_since.iter_mut().for_each(|since| *since = None);
+    }
+
+    /// Seconds each rule's condition has been holding for
+    pub fn breaches(&self, now: Instant) -> Vec<Option<u64>> {
+        self.breached_since
+            .iter()
+            .map(|since| since.map(|since| now.duration_since(since).as_secs()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RestartPolicy {
+        RestartPolicy { enabled: true, ..Default::default() }
+    }
+
+    fn sample(heap_percent: f64, tps: f64) -> PolicySample {
+        PolicySample { heap_percent: Some(heap_percent), tps: Some(tps) }
+    }
+
+    #[test]
+    fn test_rule_must_hold_for_its_duration() {
+        let policy = policy();
+        let mut state = PolicyState::default();
+        let start = Instant::now();
+
+        assert_eq!(state.observe(&policy, sample(97.0, 20.0), start), None);
+        assert_eq!(state.observe(&policy, sample(98.0, 20.0), start + Duration::from_secs(200)), None);
+        assert_eq!(state.observe(&policy, sample(96.0, 20.0), start + Duration::from_secs(300)), Some(0));
+
+        // Dipping below the threshold starts the clock over
+        let mut state = PolicyState::default();
+        state.observe(&policy, sample(97.0, 20.0), start);
+        state.observe(&policy, sample(80.0, 20.0), start + Duration::from_secs(100));
+        assert_eq!(state.observe(&policy, sample(97.0, 20.0), start + Duration::from_secs(300)), None);
+
+        // Missing readings do not interrupt a breach
+        let mut state = PolicyState::default();
+        state.observe(&policy, sample(50.0, 5.0), start);
+        state.observe(&policy, PolicySample { heap_percent: Some(50.0), tps: None }, start + Duration::from_secs(300));
+        assert_eq!(state.observe(&policy, sample(50.0, 5.0), start + Duration::from_secs(600)), Some(1));
+    }
+
+    #[test]
+    fn test_cooldown_blocks_repeated_restarts() {
+        let policy = policy();
+        let mut state = PolicyState::default();
+        let start = Instant::now();
+        state.last_restart = Some(start);
+
+        state.observe(&policy, sample(99.0, 20.0), start);
+        assert_eq!(state.observe(&policy, sample(99.0, 20.0), start + Duration::from_secs(600)), None);
+        assert!(state.in_cooldown(&policy, start + Duration::from_secs(1799)));
+        assert_eq!(state.observe(&policy, sample(99.0, 20.0), start + Duration::from_secs(1800)), Some(0));
+    }
+
+    #[test]
+    fn test_validate_and_describe() {
+        let mut policy = policy();
+        assert!(policy.validate().is_ok());
+        assert_eq!(policy.rules[0].describe(), "heap > 95% for 300s");
+        assert_eq!(policy.rules[1].describe(), "TPS < 10 for 600s");
+        policy.rules[1].threshold = 25.0;
+        assert!(policy.validate().is_err());
+    }
+}
@@ -361,14 +361,15 @@ where
 mod tests {
     use super::*;
     use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
     
     #[tokio::test]
     async fn test_retry_success() {
-        let config = RetryConfig::default();
-        let mut attempt = AtomicU32::new(0);
+        let config = RetryConfig::new(3, 1);
+        let attempt = Arc::new(AtomicU32::new(0));
         
         let result = retry(config, || {
-            let attempt = Arc::new(attempt);
+            let attempt = attempt.clone();
             Box::pin(async move {
                 let current = attempt.fetch_add(1, Ordering::SeqCst);
                 if current < 2 {
@@ -399,15 +400,15 @@ mod tests {
     #[tokio::test]
     async fn test_circuit_breaker() {
         let breaker = CircuitBreaker::new(2, Duration::from_millis(100));
-        let mut attempt = AtomicU32::new(0);
+        let attempt = Arc::new(AtomicU32::new(0));
         
         // First two failures should open the circuit
         for _ in 0..2 {
             let result = breaker.execute(|| {
-                let attempt = Arc::new(attempt);
+                let attempt = attempt.clone();
                 Box::pin(async move {
                     attempt.fetch_add(1, Ordering::SeqCst);
-                    Err::<String, String>("Always fails".to_string())
+                    Err::<String, AppError>(AppError::internal_error("test", "Always fails"))
                 })
             }).await;
             assert!(result.is_err());
@@ -415,19 +416,22 @@ mod tests {
         
         // Third attempt should fail immediately due to open circuit
         let result = breaker.execute(|| {
+            let attempt = attempt.clone();
             Box::pin(async move {
-                Ok("Should not reach here".to_string())
+                attempt.fetch_add(1, Ordering::SeqCst);
+                Ok::<String, AppError>("Should not reach here".to_string())
             })
         }).await;
         assert!(result.is_err());
+        assert_eq!(attempt.load(Ordering::SeqCst), 2);
         
         // Wait for timeout and try again
         tokio::time::sleep(Duration::from_millis(150)).await;
         let result = breaker.execute(|| {
             Box::pin(async move {
-                Ok("Success after timeout".to_string())
+                Ok::<String, AppError>("Success after timeout".to_string())
             })
         }).await;
-        assert_eq!(result, Ok("Success after timeout".to_string()));
+        assert_eq!(result.ok(), Some("Success after timeout".to_string()));
     }
 }
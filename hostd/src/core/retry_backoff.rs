@@ -207,6 +207,7 @@ impl RetryManager {
         }
 
         self.stats.total_duration = start_time.elapsed();
+        self.stats.total_attempts = self.config.max_attempts;
         self.stats.average_attempt_duration = if self.stats.total_attempts > 0 {
             Duration::from_millis(
                 self.stats.total_duration.as_millis() as u64 / self.stats.total_attempts as u64
@@ -501,12 +502,12 @@ mod tests {
         let mut circuit_breaker = CircuitBreaker::new(2, Duration::from_secs(1));
 
         // First failure
-        let result = circuit_breaker.execute(|| async { Err::<i32, String>("Fail".to_string()) }).await;
+        let result = circuit_breaker.execute(|| async { Err::<i32, anyhow::Error>(anyhow::anyhow!("Fail")) }).await;
         assert!(result.is_err());
         assert_eq!(circuit_breaker.state(), &CircuitState::Closed);
 
         // Second failure should open the circuit
-        let result = circuit_breaker.execute(|| async { Err::<i32, String>("Fail".to_string()) }).await;
+        let result = circuit_breaker.execute(|| async { Err::<i32, anyhow::Error>(anyhow::anyhow!("Fail")) }).await;
         assert!(result.is_err());
         assert_eq!(circuit_breaker.state(), &CircuitState::Open);
     }
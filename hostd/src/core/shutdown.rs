@@ -1,20 +1,22 @@
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{broadcast, RwLock};
-use tokio::time::{timeout, sleep};
+use tokio::time::timeout;
 use tracing::{info, warn, error};
 use uuid::Uuid;
 use futures::future::join_all;
 
 use crate::core::{
-    process_manager::ProcessManager,
+    process_manager::{ProcessManager, STOP_TIMEOUT},
     crash_watchdog::CrashWatchdog,
     port_registry::PortRegistry,
-    credential_manager::CredentialManager,
     error_handler::Result,
 };
 use crate::websocket_manager::WebSocketManager;
 use crate::database::DatabaseManager;
+use crate::performance_telemetry::PerformanceTelemetry;
+use crate::port_forwarding::PortForwarder;
+use crate::tunnels::TunnelManager;
 
 /// Shutdown manager for graceful application shutdown
 pub struct ShutdownManager {
@@ -76,8 +78,10 @@ pub struct AppShutdownHandler {
     websocket_manager: Arc<WebSocketManager>,
     crash_watchdog: Arc<CrashWatchdog>,
     port_registry: Arc<PortRegistry>,
-    credential_manager: Arc<CredentialManager>,
     database: Arc<DatabaseManager>,
+    performance_telemetry: Option<Arc<PerformanceTelemetry>>,
+    port_forwarder: Option<Arc<PortForwarder>>,
+    tunnel_manager: Option<Arc<TunnelManager>>,
 }
 
 impl AppShutdownHandler {
@@ -87,7 +91,6 @@ impl AppShutdownHandler {
         websocket_manager: Arc<WebSocketManager>,
         crash_watchdog: Arc<CrashWatchdog>,
         port_registry: Arc<PortRegistry>,
-        credential_manager: Arc<CredentialManager>,
         database: Arc<DatabaseManager>,
     ) -> Self {
         Self {
@@ -96,11 +99,26 @@ impl AppShutdownHandler {
             websocket_manager,
             crash_watchdog,
             port_registry,
-            credential_manager,
             database,
+            performance_telemetry: None,
+            port_forwarder: None,
+            tunnel_manager: None,
         }
     }
 
+    /// Write the last metrics rollups before the database closes
+    pub fn with_telemetry(mut self, performance_telemetry: Arc<PerformanceTelemetry>) -> Self {
+        self.performance_telemetry = Some(performance_telemetry);
+        self
+    }
+
+    /// Remove port mappings and stop tunnel agents along with the servers
+    pub fn with_networking(mut self, port_forwarder: Arc<PortForwarder>, tunnel_manager: Arc<TunnelManager>) -> Self {
+        self.port_forwarder = Some(port_forwarder);
+        self.tunnel_manager = Some(tunnel_manager);
+        self
+    }
+
     /// Perform graceful shutdown of all components
     pub async fn shutdown(&self) -> Result<()> {
         info!("Starting graceful shutdown process...");
@@ -131,33 +149,53 @@ impl AppShutdownHandler {
 
         // 2. Stop crash watchdog
         info!("Stopping crash watchdog...");
-        // Note: CrashWatchdog doesn't have a stop method in the current implementation
-        // This would need to be added
+        self.crash_watchdog.stop().await;
 
         // 3. Stop all running servers gracefully
         info!("Stopping all running servers...");
         self.stop_all_servers().await?;
 
-        // 4. Clean up monitoring tasks
+        // 4. Close forwarded ports and tunnels
+        if let Some(port_forwarder) = &self.port_forwarder {
+            info!("Closing forwarded ports...");
+            port_forwarder.close_all().await;
+        }
+        if let Some(tunnel_manager) = &self.tunnel_manager {
+            info!("Stopping tunnels...");
+            tunnel_manager.stop_all().await;
+        }
+
+        // 5. Clean up monitoring tasks
         info!("Cleaning up monitoring tasks...");
         self.process_manager.cleanup_all_monitoring_tasks().await;
 
-        // 5. Close WebSocket connections
+        // 6. Flush telemetry
+        if let Some(performance_telemetry) = &self.performance_telemetry {
+            info!("Flushing metrics...");
+            performance_telemetry.flush().await;
+        }
+
+        // 7. Close WebSocket connections
         info!("Closing WebSocket connections...");
         self.websocket_manager.cleanup_expired_connections().await;
 
-        // 6. Clean up port registry
+        // 8. Clean up port registry
         info!("Cleaning up port registry...");
-        // Port registry cleanup would be automatic when servers are stopped
-
-        // 7. Close database connections
-        info!("Closing database connections...");
-        // Database cleanup would be handled by the connection pool
+        let owners: std::collections::HashSet<Uuid> = self.port_registry.get_all_assigned_ports().await.into_values().collect();
+        for server_id in owners {
+            if let Err(e) = self.port_registry.release_ports(server_id).await {
+                warn!("Failed to release ports for server {}: {}", server_id, e);
+            }
+        }
 
-        // 8. Clean up temporary files
+        // 9. Clean up temporary files
         info!("Cleaning up temporary files...");
         self.cleanup_temp_files().await?;
 
+        // 10. Close database connections last, once nothing else writes
+        info!("Closing database connections...");
+        self.database.close().await;
+
         Ok(())
     }
 
@@ -172,11 +210,11 @@ impl AppShutdownHandler {
 
         info!("Stopping {} running servers...", running_servers.len());
 
-        // Stop servers in parallel with a timeout
+        // Stop servers in parallel; each is killed if it doesn't exit in time
         let stop_tasks: Vec<_> = running_servers.into_iter().map(|server_id| {
             let process_manager = self.process_manager.clone();
             async move {
-                match process_manager.stop_server_process(server_id).await {
+                match process_manager.stop_server_process_within(server_id, STOP_TIMEOUT).await {
                     Ok(_) => {
                         info!("Successfully stopped server {}", server_id);
                         Ok(())
@@ -189,8 +227,8 @@ impl AppShutdownHandler {
             }
         }).collect();
 
-        // Wait for all servers to stop with a timeout
-        let stop_timeout = Duration::from_secs(30);
+        // Backstop for a server stuck before its own timeout starts, e.g. in RCON
+        let stop_timeout = STOP_TIMEOUT + Duration::from_secs(10);
         let stop_result = timeout(stop_timeout, async {
             let results = join_all(stop_tasks).await;
             results.into_iter().collect::<std::result::Result<Vec<_>, _>>()
@@ -212,9 +250,7 @@ impl AppShutdownHandler {
     }
 
     async fn get_running_servers(&self) -> Vec<Uuid> {
-        // This would need to be implemented in ProcessManager
-        // For now, return empty vector
-        vec![]
+        self.process_manager.running_servers().await
     }
 
     async fn cleanup_temp_files(&self) -> Result<()> {
@@ -239,8 +275,12 @@ impl AppShutdownHandler {
     async fn force_shutdown(&self) {
         warn!("Performing forced shutdown...");
         
-        // Force kill all processes
-        // This would be implemented based on the specific process management needs
+        // Kill whatever is still running without waiting for it to save
+        for server_id in self.get_running_servers().await {
+            if let Err(e) = self.process_manager.stop_server_process_within(server_id, Duration::ZERO).await {
+                error!("Failed to kill server {}: {}", server_id, e);
+            }
+        }
         
         // Clean up resources immediately
         self.process_manager.cleanup_all_monitoring_tasks().await;
@@ -319,13 +359,21 @@ pub async fn setup_signal_handlers(shutdown_manager: Arc<ShutdownManager>) -> Re
     let shutdown_manager_clone = shutdown_manager.clone();
     
     tokio::spawn(async move {
-        if let Err(e) = tokio::signal::ctrl_c().await {
-            error!("Failed to create Ctrl+C handler: {}", e);
-            return;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => match result {
+                Ok(()) => info!("Received Ctrl+C, initiating shutdown..."),
+                Err(e) => {
+                    // Running as a service there may be no console; keep waiting for the service manager
+                    error!("Failed to create Ctrl+C handler: {}", e);
+                    crate::service::STOP_REQUESTED.notified().await;
+                    info!("Service stop requested, initiating shutdown...");
+                }
+            },
+            _ = crate::service::STOP_REQUESTED.notified() => {
+                info!("Service stop requested, initiating shutdown...");
+            }
         }
         
-        info!("Received Ctrl+C, initiating shutdown...");
-        
         if let Err(e) = shutdown_manager_clone.shutdown().await {
             error!("Failed to initiate shutdown: {}", e);
         }
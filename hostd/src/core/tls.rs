@@ -0,0 +1,114 @@
+//! HTTPS/WSS for the API listener. Uses the configured certificate, or a
+//! self-signed one generated under the data directory for local use.
+
+use anyhow::{Context, Result};
+use axum_server::tls_rustls::RustlsConfig;
+use std::path::{Path, PathBuf};
+
+use crate::core::guardian_config::GuardianConfig;
+
+/// Certificate and private key, both PEM encoded
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsFiles {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// Generated by hostd rather than provided by the operator
+    pub self_signed: bool,
+}
+
+/// Certificate files to serve with, generating a self-signed pair when none are configured
+pub fn resolve_tls_files(config: &GuardianConfig) -> Result<TlsFiles> {
+    match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            for path in [cert_path, key_path] {
+                if !path.is_file() {
+                    anyhow::bail!("TLS file not found: {}", path.display());
+                }
+            }
+            Ok(TlsFiles { cert_path: cert_path.clone(), key_path: key_path.clone(), self_signed: false })
+        }
+        (None, None) => {
+            let dir = config.data_dir.join("tls");
+            let files = TlsFiles {
+                cert_path: dir.join("cert.pem"),
+                key_path: dir.join("key.pem"),
+                self_signed: true,
+            };
+            if !files.cert_path.is_file() || !files.key_path.is_file() {
+                generate_self_signed(&files.cert_path, &files.key_path, &config.guardian_host)?;
+                tracing::warn!(
+                    "No TLS certificate configured, generated a self-signed one at {}. Clients will need to trust it.",
+                    files.cert_path.display()
+                );
+            }
+            Ok(files)
+        }
+        _ => anyhow::bail!("GUARDIAN_TLS_CERT and GUARDIAN_TLS_KEY must be set together"),
+    }
+}
+
+/// Write a self-signed certificate valid for localhost and `host`
+pub fn generate_self_signed(cert_path: &Path, key_path: &Path, host: &str) -> Result<()> {
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+    // A wildcard bind address is not a name clients connect to
+    if !matches!(host, "0.0.0.0" | "::" | "") && !names.iter().any(|name| name == host) {
+        names.push(host.to_string());
+    }
+    let certified = rcgen::generate_simple_self_signed(names)
+        .context("Failed to generate self-signed certificate")?;
+
+    if let Some(dir) = cert_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    if let Some(dir) = key_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(cert_path, certified.cert.pem())?;
+    std::fs::write(key_path, certified.key_pair.serialize_pem())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(key_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// rustls server config for the listener
+pub async fn load_rustls_config(files: &TlsFiles) -> Result<RustlsConfig> {
+    // Fails only when a provider is already installed, which is fine
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    RustlsConfig::from_pem_file(&files.cert_path, &files.key_path)
+        .await
+        .with_context(|| format!("Failed to load TLS certificate {}", files.cert_path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_self_signed_certificate_is_generated_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = GuardianConfig {
+            data_dir: dir.path().to_path_buf(),
+            tls_enabled: true,
+            ..GuardianConfig::default()
+        };
+
+        let files = resolve_tls_files(&config).unwrap();
+        assert!(files.self_signed);
+        let cert = std::fs::read_to_string(&files.cert_path).unwrap();
+        assert!(cert.starts_with("-----BEGIN CERTIFICATE-----"));
+        load_rustls_config(&files).await.unwrap();
+
+        // An existing certificate is reused rather than replaced
+        assert_eq!(resolve_tls_files(&config).unwrap(), files);
+        assert_eq!(std::fs::read_to_string(&files.cert_path).unwrap(), cert);
+
+        let missing = GuardianConfig {
+            tls_cert_path: Some(dir.path().join("missing.pem")),
+            ..config
+        };
+        assert!(resolve_tls_files(&missing).is_err());
+    }
+}
@@ -1,11 +1,13 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use crate::core::error_handler::{AppError, Result};
+use crate::core::error_handler::{self, AppError, Result};
 
 /// Validation rule for a field
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ValidationRule {
     Required,
+    /// A string with something other than whitespace in it
+    NotBlank,
     MinLength(usize),
     MaxLength(usize),
     MinValue(i64),
@@ -24,6 +26,8 @@ pub enum ValidationRule {
     NoPathTraversal,
     NoCommandInjection,
     ValidEnum(Vec<String>),
+    /// An array with at least this many items
+    MinItems(usize),
 }
 
 /// Field validation configuration
@@ -34,6 +38,23 @@ pub struct FieldValidation {
     pub required: bool,
 }
 
+impl FieldValidation {
+    pub fn required(field_name: &str, rules: Vec<ValidationRule>) -> Self {
+        Self { field_name: field_name.to_string(), rules, required: true }
+    }
+
+    pub fn optional(field_name: &str, rules: Vec<ValidationRule>) -> Self {
+        Self { field_name: field_name.to_string(), rules, required: false }
+    }
+}
+
+/// A request body whose fields are checked against declared rules before it
+/// reaches a handler; see `middleware::validation::ValidJson`
+pub trait ValidateRequest {
+    /// Rules for the body's fields, by their names in the JSON body
+    fn validations() -> Vec<FieldValidation>;
+}
+
 /// Validation result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidationResult {
@@ -113,6 +134,32 @@ impl InputValidator {
         }
     }
 
+    /// Check a body against `validations`, reporting the first broken rule of
+    /// each field. Optional fields that are absent or null are not checked.
+    pub fn check(&self, validations: &[FieldValidation], data: &serde_json::Value) -> Vec<error_handler::FieldError> {
+        let mut errors = Vec::new();
+        for validation in validations {
+            let value = match data.get(&validation.field_name) {
+                Some(value) if !value.is_null() => value,
+                _ => {
+                    if validation.required {
+                        errors.push(error_handler::FieldError {
+                            field: validation.field_name.clone(),
+                            constraint: "required".to_string(),
+                            message: format!("Field '{}' is required", validation.field_name),
+                        });
+                    }
+                    continue;
+                }
+            };
+            let broken = validation.rules.iter().find_map(|rule| self.validate_rule(&validation.field_name, value, rule).err());
+            if let Some(AppError::ValidationError { message, constraint, .. }) = broken {
+                errors.push(error_handler::FieldError { field: validation.field_name.clone(), constraint, message });
+            }
+        }
+        errors
+    }
+
     fn validate_rule(&self, field_name: &str, value: &serde_json::Value, rule: &ValidationRule) -> Result<()> {
         match rule {
             ValidationRule::Required => {
@@ -125,6 +172,18 @@ impl InputValidator {
                     });
                 }
             }
+            ValidationRule::NotBlank => {
+                if let Some(s) = value.as_str() {
+                    if s.trim().is_empty() {
+                        return Err(AppError::ValidationError {
+                            message: format!("Field '{}' cannot be empty", field_name),
+                            field: field_name.to_string(),
+                            value: s.to_string(),
+                            constraint: "not_blank".to_string(),
+                        });
+                    }
+                }
+            }
             ValidationRule::MinLength(min) => {
                 if let Some(s) = value.as_str() {
                     if s.len() < *min {
@@ -342,6 +401,18 @@ impl InputValidator {
                     }
                 }
             }
+            ValidationRule::MinItems(min) => {
+                if let Some(items) = value.as_array() {
+                    if items.len() < *min {
+                        return Err(AppError::ValidationError {
+                            message: format!("Field '{}' must have at least {} items", field_name, min),
+                            field: field_name.to_string(),
+                            value: items.len().to_string(),
+                            constraint: format!("min_items:{}", min),
+                        });
+                    }
+                }
+            }
         }
 
         Ok(())
@@ -516,4 +587,26 @@ pub fn create_default_validations() -> InputValidator {
     ]);
 
     validator
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_reports_each_field() {
+        let validations = vec![
+            FieldValidation::required("name", vec![ValidationRule::NotBlank, ValidationRule::MaxLength(5)]),
+            FieldValidation::required("loader", vec![ValidationRule::NotBlank]),
+            FieldValidation::optional("memory", vec![ValidationRule::MemorySize]),
+            FieldValidation::optional("port", vec![ValidationRule::Port]),
+        ];
+        let body = serde_json::json!({ "name": "  ", "memory": 128, "port": null });
+        let errors = InputValidator::new().check(&validations, &body);
+
+        let constraints: Vec<_> = errors.iter().map(|e| (e.field.as_str(), e.constraint.as_str())).collect();
+        assert_eq!(constraints, vec![("name", "not_blank"), ("loader", "required"), ("memory", "memory_size")]);
+
+        let body = serde_json::json!({ "name": "lobby", "loader": "fabric", "port": 25565 });
+        assert!(InputValidator::new().check(&validations, &body).is_empty());
+    }
+}
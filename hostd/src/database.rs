@@ -2,14 +2,40 @@ use anyhow::Result;
 use chrono;
 use serde::{Deserialize, Serialize};
 use sqlx::{sqlite::SqlitePool, Row, Transaction};
+use sqlx::migrate::Migrator;
 use std::str::FromStr;
+use std::sync::Arc;
 use tracing::{info, warn, debug};
 use uuid::Uuid;
 
+use crate::core::error_handler::NotFound;
+use crate::resource_limits::{ProcessPriority, ResourceLimits};
+use crate::security::secret_storage::SecretStorage;
+
 /// Database manager for Guardian
 #[derive(Debug, Clone)]
 pub struct DatabaseManager {
     pool: SqlitePool,
+    /// Keeps RCON passwords and API keys out of SQLite when it uses the OS keychain
+    secrets: Option<Arc<SecretStorage>>,
+}
+
+/// Keychain names of secrets the database keeps out of SQLite; the API keys
+/// match `SecretStorage::get_api_key`
+const CURSEFORGE_SECRET_KEY: &str = "api_key_curseforge";
+const MODRINTH_SECRET_KEY: &str = "api_key_modrinth";
+const DISCORD_SECRET_KEY: &str = "discord_bot_token";
+
+fn rcon_secret_key(server_id: &str) -> String {
+    format!("rcon_password_{}", server_id)
+}
+
+fn tunnel_secret_key(server_id: &str) -> String {
+    format!("tunnel_token_{}", server_id)
+}
+
+fn node_secret_key(node_id: &str) -> String {
+    format!("node_token_{}", node_id)
 }
 
 /// Server configuration stored in database
@@ -46,10 +72,17 @@ pub struct ServerConfig {
     pub server_jar: String,
     pub server_directory: String,
     pub rcon_password: String,
+    /// False for external servers Guardian monitors but does not run
+    #[serde(default = "default_managed")]
+    pub managed: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+fn default_managed() -> bool {
+    true
+}
+
 /// Server log entry
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct ServerLog {
@@ -66,11 +99,19 @@ pub struct ServerLog {
 pub struct ServerMetric {
     pub id: String,
     pub server_id: String,
+    /// `raw` for a sample, otherwise the rollup bucket size (`1m`, `5m`, `1h`)
+    pub resolution: String,
     pub tps: f64,
     pub tick_p95: f64,
     pub heap_mb: u32,
     pub players_online: u32,
     pub gpu_queue_ms: f64,
+    pub cpu_usage: f64,
+    pub memory_usage: i64,
+    pub disk_usage: i64,
+    pub network_in: i64,
+    pub network_out: i64,
+    /// Sample time, or the start of the rollup bucket
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
@@ -84,10 +125,48 @@ pub struct Settings {
     pub default_ram_mb: u32,
     pub data_dir: String,
     pub telemetry_opt_in: bool,
+    /// When the first-run setup was completed; the UI shows the wizard until then
+    #[serde(default)]
+    pub setup_completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Write `eula=true` for new servers instead of asking on first start
+    #[serde(default)]
+    pub accept_eula_by_default: bool,
+    /// Days to keep entries in the event feed; 0 keeps them forever
+    #[serde(default = "default_event_retention_days")]
+    pub event_retention_days: u32,
+    /// Serve version catalogs from the metadata cache without contacting
+    /// Mojang, the loaders or mod providers
+    #[serde(default)]
+    pub offline_mode: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+impl Default for Settings {
+    fn default() -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            id: "default".to_string(),
+            cf_api_key: None,
+            modrinth_token: None,
+            java_path: "java".to_string(),
+            default_ram_mb: 4096,
+            data_dir: "data".to_string(),
+            telemetry_opt_in: false,
+            setup_completed_at: None,
+            accept_eula_by_default: false,
+            event_retention_days: default_event_retention_days(),
+            offline_mode: false,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+fn default_event_retention_days() -> u32 {
+    30
+}
+
 /// User settings stored in database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserSettings {
@@ -117,6 +196,432 @@ pub struct Task {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Cron-driven restart of one server, with in-game countdown warnings, or
+/// console commands run on the same kind of schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartSchedule {
+    pub id: String,
+    pub server_id: String,
+    pub name: String,
+    pub kind: crate::restart_scheduler::ScheduleKind,
+    pub cron_expression: String,
+    pub enabled: bool,
+    /// Commands sent over RCON, in order, by `command` schedules
+    pub commands: Vec<String>,
+    /// Seconds before the restart at which players are warned
+    pub warnings: Vec<u32>,
+    /// Seconds between the final announcement and stopping the server
+    pub grace_period_seconds: u32,
+    /// Warning text; `{time}` is replaced with the time left
+    pub message: Option<String>,
+    /// Also show warnings as an on-screen title
+    pub use_title: bool,
+    pub last_run: Option<chrono::DateTime<chrono::Utc>>,
+    /// done, failed or skipped
+    pub last_status: Option<String>,
+    /// Task holding the output of the last command run
+    pub last_task_id: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Condition that notifies a set of alert channels when it is met
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    pub condition: crate::alerts::AlertCondition,
+    /// Server the rule watches; `None` watches every server
+    pub server_id: Option<String>,
+    /// Minimum TPS, or disk usage percentage, depending on the condition
+    pub threshold: Option<f64>,
+    /// Minutes before the rule may alert again for the same server
+    pub cooldown_minutes: u32,
+    pub channel_ids: Vec<String>,
+    pub enabled: bool,
+    pub last_triggered_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Destination for alert notifications
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertChannel {
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub target: crate::notifiers::ChannelTarget,
+    pub enabled: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// URL that lifecycle events are POSTed to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    /// Signing key; never returned by the API
+    #[serde(skip_serializing)]
+    pub secret: Option<String>,
+    /// Event names to send; empty sends every event
+    pub events: Vec<String>,
+    pub enabled: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// One event queued for, or sent to, a webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: crate::webhook::DeliveryStatus,
+    pub attempts: u32,
+    /// When a pending delivery is next tried
+    pub next_attempt_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// HTTP status of the last attempt, if the URL answered
+    pub response_status: Option<u16>,
+    /// Why the last attempt failed
+    pub error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Settings of the Discord bot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiscordSettings {
+    pub enabled: bool,
+    pub application_id: Option<String>,
+    /// Hex Ed25519 key Discord signs interactions with
+    pub public_key: Option<String>,
+    /// Never returned by the API
+    #[serde(skip_serializing)]
+    pub bot_token: String,
+    /// Guild slash commands are registered in; global when unset
+    pub guild_id: Option<String>,
+    /// Channel holding the status message
+    pub status_channel_id: Option<String>,
+    /// Channel players joining and leaving are posted to
+    pub player_channel_id: Option<String>,
+    /// Channel crashes are posted to
+    pub alert_channel_id: Option<String>,
+    /// Roles whose members may start and stop servers
+    pub control_role_ids: Vec<String>,
+    /// Status message edited in place
+    pub status_message_id: Option<String>,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// A server's chat relayed to and from external channels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatBridge {
+    pub server_id: String,
+    pub enabled: bool,
+    /// Channel the Discord bot posts chat to and reads replies from
+    pub discord_channel_id: Option<String>,
+    /// Discord webhook or generic URL chat is POSTed to
+    pub webhook_url: Option<String>,
+    /// Relay messages from the Discord channel into the game
+    pub relay_inbound: bool,
+    /// Format of chat sent out, with `{server}`, `{player}` and `{message}`
+    pub outbound_template: String,
+    /// Format of chat sent in, with `{source}`, `{author}` and `{message}`
+    pub inbound_template: String,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// A server's world border growth schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldBorderSchedule {
+    pub server_id: String,
+    pub enabled: bool,
+    pub center_x: i32,
+    pub center_z: i32,
+    /// Border diameter in blocks the first time the schedule applies it
+    pub initial_size: u32,
+    /// Blocks added to the diameter each time the schedule is due
+    pub growth: u32,
+    pub max_size: Option<u32>,
+    pub cron_expression: String,
+    /// Seconds the border takes to move to a new size
+    pub transition_seconds: u32,
+    /// Diameter last applied; `None` until the schedule first runs
+    pub current_size: Option<u32>,
+    pub last_grown_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_error: Option<String>,
+    /// Keep a pregeneration job covering the next border
+    pub pregen_ahead: bool,
+    /// Blocks pregenerated beyond the next border
+    pub pregen_margin: u32,
+    pub pregen_job_id: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A command sent to a server's console
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsoleCommand {
+    pub id: String,
+    pub server_id: String,
+    pub command: String,
+    /// The user who sent it
+    pub author: Option<String>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Commands kept per server; older ones are pruned as new ones arrive
+pub const CONSOLE_HISTORY_LIMIT: u32 = 1000;
+
+/// A metadata API response kept by the metadata cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: String,
+    pub body: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// When the response was fetched or last revalidated
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Shards that together serve one world
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardGroup {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Part of a shard group, run by one or more managed servers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Shard {
+    pub id: String,
+    pub group_id: String,
+    pub name: String,
+    /// Players the shard's servers may hold together
+    pub max_players: u32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A managed server running a shard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardAssignment {
+    pub id: String,
+    pub shard_id: String,
+    pub server_id: String,
+    /// Dimensions the server hosts for the shard, such as `minecraft:overworld`
+    pub dimensions: Vec<String>,
+    pub assigned_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Region files a shard owns, in region coordinates, both corners included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShardRegion {
+    pub id: String,
+    pub shard_id: String,
+    pub dimension: String,
+    pub min_x: i32,
+    pub min_z: i32,
+    pub max_x: i32,
+    pub max_z: i32,
+}
+
+/// Ban that Guardian lifts with `pardon`/`pardon-ip` once it expires
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemporaryBan {
+    pub id: String,
+    pub server_id: String,
+    /// `player` or `ip`
+    pub kind: String,
+    /// Player name or IP address
+    pub target: String,
+    pub uuid: Option<String>,
+    pub reason: Option<String>,
+    /// Who issued the ban
+    pub source: Option<String>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    /// When the ban was lifted, on expiry or by an unban
+    pub lifted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Reusable starting point for new servers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub loader: String,
+    pub loader_version: String,
+    pub minecraft_version: String,
+    pub memory: u32,
+    pub jvm_args: String,
+    /// server.properties keys the template sets
+    pub properties: std::collections::BTreeMap<String, String>,
+    /// Jar names of the mods stored with the template
+    pub mods: Vec<String>,
+    /// Server the template was saved from
+    pub source_server_id: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A remote hostd agent this hostd controls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Node {
+    pub id: String,
+    pub name: String,
+    /// Base URL of the agent's API, e.g. `https://node-2:52100`
+    pub url: String,
+    /// API token issued by the agent
+    #[serde(skip_serializing)]
+    pub token: String,
+    pub enabled: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A server-thread stall caught by the freeze detector
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FreezeTicket {
+    pub id: String,
+    pub server_id: String,
+    /// `open`, `recovered`, `crashed` or `thawed`
+    pub status: String,
+    /// What the server thread was busy with: `entity`, `block_entity`, `chunk` or `unknown`
+    pub suspect_kind: String,
+    /// First mod (or game) frame on the stuck thread
+    pub suspect_frame: Option<String>,
+    pub entity_type: Option<String>,
+    pub location: Option<FreezeLocation>,
+    /// Server thread frames, innermost first
+    pub stack: Vec<String>,
+    /// Path of the full thread dump
+    pub thread_dump: Option<String>,
+    pub duration_ms: Option<u64>,
+    pub resolution: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FreezeLocation {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub dimension: Option<String>,
+}
+
+/// A named set of servers that bulk actions, schedules and summaries can target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerGroup {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub server_ids: Vec<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Tunnel provider settings of a server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerTunnel {
+    pub server_id: String,
+    pub provider: String,
+    pub enabled: bool,
+    /// Provider auth token or agent secret
+    pub token: String,
+    pub region: Option<String>,
+    /// Agent executable; the provider's default name on PATH when unset
+    pub agent_path: Option<String>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A server process hostd launched and has not seen exit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerProcessRecord {
+    pub server_id: String,
+    pub pid: u32,
+    /// OS start time of the process in seconds since the epoch, 0 when unknown
+    pub process_started_at: i64,
+    pub java_path: String,
+    pub server_directory: String,
+    /// JVM and server arguments, as a JSON array
+    pub command_line: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Where a server comes in the auto-start sequence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StartupRule {
+    pub server_id: String,
+    /// Lower starts first, among servers whose dependencies are met
+    pub start_order: i32,
+    /// Wait after starting this server before starting the next
+    pub delay_seconds: u32,
+    /// Servers that must answer a status ping before this one starts
+    pub depends_on: Vec<String>,
+    /// How long servers that depend on this one wait for it to answer
+    pub ready_timeout_seconds: u32,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl StartupRule {
+    pub fn new(server_id: &str) -> Self {
+        Self {
+            server_id: server_id.to_string(),
+            start_order: 0,
+            delay_seconds: 0,
+            depends_on: Vec::new(),
+            ready_timeout_seconds: 300,
+            updated_at: chrono::Utc::now(),
+        }
+    }
+}
+
+/// One change Guardian made to a server's configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigRevision {
+    pub id: String,
+    pub server_id: String,
+    /// `server.properties`, `jvm_args` or `config/<path>` for a mod config
+    pub target: String,
+    /// The configuration after the change
+    pub content: String,
+    /// What the change replaced; `None` when the file did not exist
+    pub previous_content: Option<String>,
+    /// Unified diff from `previous_content` to `content`
+    pub diff: String,
+    pub author: Option<String>,
+    pub message: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Long-lived token for scripts and CI. Only the SHA-256 of the token is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    /// Start of the token, to recognise it in listings
+    pub token_prefix: String,
+    pub scopes: Vec<crate::core::auth::Permission>,
+    /// Servers the token may access; empty means all
+    pub server_ids: Vec<String>,
+    pub created_by: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 /// Mod information with enhanced fields
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Mod {
@@ -245,6 +750,26 @@ pub struct EventLog {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// One mutating API request, as recorded by the audit middleware
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Username, `token:<name>` for API tokens, or `anonymous`
+    pub actor: String,
+    /// user, api_token or anonymous
+    pub actor_type: String,
+    /// What was done, e.g. `server.start` or `backup.restore`
+    pub action: String,
+    pub method: String,
+    pub path: String,
+    pub server_id: Option<String>,
+    pub status_code: u16,
+    pub success: bool,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
 /// Minecraft version information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MinecraftVersion {
@@ -321,6 +846,72 @@ pub struct ServerMod {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Versioned schema migrations, embedded from `db/migrations`
+pub static MIGRATOR: Migrator = sqlx::migrate!("./db/migrations");
+
+/// Migration holding the schema hostd used to create at startup
+const LEGACY_SCHEMA_VERSION: i64 = 29;
+
+/// Databases opened by hostd before the startup schema became a migration
+/// already have its tables and `servers.server_directory`. Record the
+/// migration as applied for them, since it can't add the column twice.
+async fn adopt_legacy_schema(pool: &sqlx::SqlitePool) -> Result<()> {
+    let tracked: bool = sqlx::query_scalar("SELECT COUNT(*) > 0 FROM sqlite_master WHERE name = '_sqlx_migrations'")
+        .fetch_one(pool)
+        .await?;
+    if !tracked {
+        return Ok(());
+    }
+    let legacy: bool = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) > 0 FROM pragma_table_info('servers') WHERE name = 'server_directory'
+        AND NOT EXISTS (SELECT 1 FROM _sqlx_migrations WHERE version = ?)
+        "#,
+    )
+    .bind(LEGACY_SCHEMA_VERSION)
+    .fetch_one(pool)
+    .await?;
+    if !legacy {
+        return Ok(());
+    }
+
+    let migration = MIGRATOR
+        .iter()
+        .find(|migration| migration.version == LEGACY_SCHEMA_VERSION && migration.migration_type.is_up_migration())
+        .ok_or_else(|| anyhow::anyhow!("Migration {} is missing from this build", LEGACY_SCHEMA_VERSION))?;
+    sqlx::query(
+        "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) VALUES (?, ?, TRUE, ?, 0)",
+    )
+    .bind(migration.version)
+    .bind(migration.description.as_ref())
+    .bind(migration.checksum.as_ref())
+    .execute(pool)
+    .await?;
+    info!("Recorded the existing startup schema as migration {}", LEGACY_SCHEMA_VERSION);
+    Ok(())
+}
+
+/// A migration known to this build and whether it has been applied
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaMigration {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    pub applied_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Schema version and migration history of the database
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseInfo {
+    /// Latest applied migration, 0 for an empty database
+    pub schema_version: i64,
+    /// Latest migration embedded in this build
+    pub latest_version: i64,
+    pub up_to_date: bool,
+    pub size_bytes: i64,
+    pub migrations: Vec<SchemaMigration>,
+}
+
 impl DatabaseManager {
     /// Create a new database manager
     pub async fn new(database_url: &str) -> Result<Self> {
@@ -334,1257 +925,2803 @@ impl DatabaseManager {
             .await?;
         
         // Run migrations using SQLx
-        let migrator = sqlx::migrate!("./db/migrations");
-        migrator.run(&pool).await?;
-        
-        Ok(Self { pool })
-    }
-
-    /// Begin a new database transaction
-    pub async fn begin_transaction(&self) -> Result<Transaction<'_, sqlx::Sqlite>> {
-        self.pool.begin().await.map_err(|e| anyhow::anyhow!("Failed to begin transaction: {}", e))
-    }
+        adopt_legacy_schema(&pool).await?;
+        MIGRATOR.run(&pool).await?;
 
-    /// Execute a closure within a database transaction
-    /// If the closure returns an error, the transaction is rolled back
-    /// If the closure succeeds, the transaction is committed
-    pub async fn with_transaction<F, R>(&self, f: F) -> Result<R>
-    where
-        F: FnOnce(Transaction<'_, sqlx::Sqlite>) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<R>> + Send + '_>>,
-    {
-        let tx = self.begin_transaction().await?;
-        
-        match f(tx).await {
-            Ok(result) => {
-                // Transaction is automatically committed when it goes out of scope
-                Ok(result)
-            }
-            Err(e) => {
-                // Transaction is automatically rolled back when it goes out of scope
-                Err(e)
-            }
-        }
+        let manager = Self { pool, secrets: None };
+        manager.record_schema_version().await?;
+        Ok(manager)
     }
 
-    // Server configuration methods
-    // Note: Database migrations are now handled by SQLx migrations in the new() function
-    
-    pub async fn run_migrations(&self) -> Result<()> {
-        // Create servers table
+    /// Copy the latest applied migration into `schema_version`
+    async fn record_schema_version(&self) -> Result<()> {
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS servers (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                minecraft_version TEXT NOT NULL DEFAULT '1.20.1',
-                loader TEXT NOT NULL DEFAULT 'vanilla',
-                loader_version TEXT NOT NULL DEFAULT 'latest',
-                port INTEGER NOT NULL DEFAULT 25565,
-                rcon_port INTEGER NOT NULL DEFAULT 25575,
-                query_port INTEGER NOT NULL DEFAULT 25566,
-                max_players INTEGER NOT NULL DEFAULT 20,
-                memory INTEGER NOT NULL DEFAULT 4096,
-                java_args TEXT NOT NULL DEFAULT '[]',
-                server_args TEXT NOT NULL DEFAULT '[]',
-                auto_start BOOLEAN NOT NULL DEFAULT 0,
-                auto_restart BOOLEAN NOT NULL DEFAULT 1,
-                world_name TEXT NOT NULL DEFAULT 'world',
-                difficulty TEXT NOT NULL DEFAULT 'normal',
-                gamemode TEXT NOT NULL DEFAULT 'survival',
-                pvp BOOLEAN NOT NULL DEFAULT 1,
-                online_mode BOOLEAN NOT NULL DEFAULT 1,
-                whitelist BOOLEAN NOT NULL DEFAULT 0,
-                enable_command_block BOOLEAN NOT NULL DEFAULT 0,
-                view_distance INTEGER NOT NULL DEFAULT 10,
-                simulation_distance INTEGER NOT NULL DEFAULT 10,
-                motd TEXT NOT NULL DEFAULT 'A Minecraft Server',
-                host TEXT NOT NULL DEFAULT 'localhost',
-                java_path TEXT NOT NULL DEFAULT 'java',
-                jvm_args TEXT NOT NULL DEFAULT '-Xmx4G -Xms2G',
-                server_jar TEXT NOT NULL DEFAULT 'server.jar',
-                server_directory TEXT NOT NULL DEFAULT 'data/servers',
-                rcon_password TEXT NOT NULL DEFAULT '',
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            CREATE TABLE IF NOT EXISTS schema_version (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                version INTEGER NOT NULL,
+                description TEXT NOT NULL,
+                updated_at DATETIME NOT NULL
             )
             "#,
         )
         .execute(&self.pool)
         .await?;
 
-        // Create settings table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS settings (
-                id TEXT PRIMARY KEY,
-                cf_api_key TEXT,
-                modrinth_token TEXT,
-                java_path TEXT NOT NULL DEFAULT 'java',
-                default_ram_mb INTEGER NOT NULL DEFAULT 4096,
-                data_dir TEXT NOT NULL DEFAULT 'data',
-                telemetry_opt_in BOOLEAN NOT NULL DEFAULT 0,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
+        let latest: Option<(i64, String)> = sqlx::query_as(
+            "SELECT version, description FROM _sqlx_migrations WHERE success = 1 ORDER BY version DESC LIMIT 1",
         )
-        .execute(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
+        let (version, description) = latest.unwrap_or((0, String::new()));
 
-        // Create user_settings table
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS user_settings (
-                id TEXT PRIMARY KEY,
-                theme TEXT NOT NULL DEFAULT 'dark',
-                language TEXT NOT NULL DEFAULT 'en',
-                notifications BOOLEAN NOT NULL DEFAULT 1,
-                auto_refresh BOOLEAN NOT NULL DEFAULT 1,
-                refresh_interval INTEGER NOT NULL DEFAULT 5,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
-            )
+            INSERT INTO schema_version (id, version, description, updated_at)
+            VALUES (1, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                version = excluded.version,
+                description = excluded.description,
+                updated_at = excluded.updated_at
             "#,
         )
+        .bind(version)
+        .bind(&description)
+        .bind(chrono::Utc::now())
         .execute(&self.pool)
         .await?;
+        Ok(())
+    }
 
-        // Create tasks table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS tasks (
-                id TEXT PRIMARY KEY,
-                server_id TEXT,
-                kind TEXT NOT NULL,
-                status TEXT NOT NULL DEFAULT 'pending',
-                progress REAL NOT NULL DEFAULT 0.0,
-                log TEXT,
-                metadata TEXT,
-                started_at DATETIME,
-                finished_at DATETIME,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+    /// Revert migrations newer than `target` using their down scripts.
+    /// For development only: reverted tables are dropped along with their data.
+    pub async fn migrate_down(&self, target: i64) -> Result<()> {
+        MIGRATOR.undo(&self.pool, target).await?;
+        self.record_schema_version().await
+    }
 
-        // Create mods table (enhanced)
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS mods (
-                id TEXT PRIMARY KEY,
-                provider TEXT NOT NULL,
-                project_id TEXT NOT NULL,
-                version_id TEXT NOT NULL,
-                filename TEXT NOT NULL,
-                sha1 TEXT NOT NULL,
-                server_id TEXT,
-                enabled BOOLEAN NOT NULL DEFAULT 1,
-                category TEXT NOT NULL DEFAULT 'unknown',
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
-            )
-            "#,
+    pub async fn get_database_info(&self) -> Result<DatabaseInfo> {
+        let (schema_version,): (i64,) = sqlx::query_as("SELECT version FROM schema_version WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?
+            .unwrap_or((0,));
+
+        let applied: Vec<(i64, chrono::NaiveDateTime)> =
+            sqlx::query_as("SELECT version, installed_on FROM _sqlx_migrations WHERE success = 1")
+                .fetch_all(&self.pool)
+                .await?;
+        let migrations: Vec<SchemaMigration> = MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| {
+                let applied_at = applied
+                    .iter()
+                    .find(|(version, _)| *version == migration.version)
+                    .map(|(_, installed_on)| installed_on.and_utc());
+                SchemaMigration {
+                    version: migration.version,
+                    description: migration.description.to_string(),
+                    applied: applied_at.is_some(),
+                    applied_at,
+                }
+            })
+            .collect();
+        let latest_version = migrations.iter().map(|migration| migration.version).max().unwrap_or(0);
+
+        let size_bytes: i64 = sqlx::query_scalar(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
         )
-        .execute(&self.pool)
+        .fetch_one(&self.pool)
         .await?;
 
-        // Create backup_configs table
-        sqlx::query(
+        Ok(DatabaseInfo {
+            schema_version,
+            latest_version,
+            up_to_date: schema_version >= latest_version,
+            size_bytes,
+            migrations,
+        })
+    }
+
+    /// Fold the write-ahead log back into the database file and close the
+    /// pool. Queries fail afterwards, so this is the last step of a shutdown.
+    pub async fn close(&self) {
+        if let Err(e) = sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&self.pool).await {
+            warn!("Failed to checkpoint the database: {}", e);
+        }
+        self.pool.close().await;
+    }
+
+    pub fn with_secret_storage(mut self, secrets: Arc<SecretStorage>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Value to write to SQLite for a secret: blank once the keychain holds it,
+    /// the secret itself when there is no keychain
+    async fn stash_secret(&self, key: &str, value: &str) -> Result<String> {
+        let Some(secrets) = self.secrets.as_ref().filter(|secrets| secrets.uses_keychain()) else {
+            return Ok(value.to_string());
+        };
+        if value.is_empty() {
+            secrets.remove_secret(key).await?;
+        } else {
+            secrets.store_secret(key, value).await?;
+        }
+        Ok(String::new())
+    }
+
+    /// A secret read from SQLite, filled in from the keychain when it was moved there
+    async fn reveal_secret(&self, key: &str, stored: String) -> String {
+        let Some(secrets) = self.secrets.as_ref().filter(|_| stored.is_empty()) else {
+            return stored;
+        };
+        match secrets.get_secret(key).await {
+            Ok(secret) => secret.unwrap_or_default(),
+            Err(e) => {
+                warn!("Failed to read {} from the keychain: {}", key, e);
+                stored
+            }
+        }
+    }
+
+    async fn reveal_server_secrets(&self, mut config: ServerConfig) -> ServerConfig {
+        let stored = std::mem::take(&mut config.rcon_password);
+        config.rcon_password = self.reveal_secret(&rcon_secret_key(&config.id), stored).await;
+        config
+    }
+
+    /// Move RCON passwords and API keys still stored in plaintext into the OS keychain.
+    /// Returns how many secrets were moved; does nothing without a keychain.
+    pub async fn migrate_secrets_to_keychain(&self) -> Result<usize> {
+        if !self.secrets.as_ref().is_some_and(|secrets| secrets.uses_keychain()) {
+            return Ok(0);
+        }
+        let mut moved = 0;
+
+        let rows = sqlx::query("SELECT id, rcon_password FROM servers WHERE rcon_password != ''")
+            .fetch_all(&self.pool)
+            .await?;
+        for row in rows {
+            let id: String = row.get("id");
+            let password: String = row.get("rcon_password");
+            let stored = self.stash_secret(&rcon_secret_key(&id), &password).await?;
+            sqlx::query("UPDATE servers SET rcon_password = ? WHERE id = ?")
+                .bind(stored)
+                .bind(&id)
+                .execute(&self.pool)
+                .await?;
+            moved += 1;
+        }
+
+        let rows = sqlx::query("SELECT id, cf_api_key, modrinth_token FROM settings")
+            .fetch_all(&self.pool)
+            .await?;
+        for row in rows {
+            let id: String = row.get("id");
+            for (column, key) in [("cf_api_key", CURSEFORGE_SECRET_KEY), ("modrinth_token", MODRINTH_SECRET_KEY)] {
+                let value: Option<String> = row.get(column);
+                let Some(value) = value.filter(|value| !value.is_empty()) else {
+                    continue;
+                };
+                self.stash_secret(key, &value).await?;
+                sqlx::query(&format!("UPDATE settings SET {} = NULL WHERE id = ?", column))
+                    .bind(&id)
+                    .execute(&self.pool)
+                    .await?;
+                moved += 1;
+            }
+        }
+
+        let token: Option<String> = sqlx::query_scalar("SELECT bot_token FROM discord_settings WHERE bot_token != ''")
+            .fetch_optional(&self.pool)
+            .await?;
+        if let Some(token) = token {
+            self.stash_secret(DISCORD_SECRET_KEY, &token).await?;
+            sqlx::query("UPDATE discord_settings SET bot_token = ''").execute(&self.pool).await?;
+            moved += 1;
+        }
+
+        if moved > 0 {
+            info!("Moved {} secrets from the database into the OS keychain", moved);
+        }
+        Ok(moved)
+    }
+
+    /// Begin a new database transaction
+    pub async fn begin_transaction(&self) -> Result<Transaction<'_, sqlx::Sqlite>> {
+        self.pool.begin().await.map_err(|e| anyhow::anyhow!("Failed to begin transaction: {}", e))
+    }
+
+    /// Execute a closure within a database transaction
+    /// If the closure returns an error, the transaction is rolled back
+    /// If the closure succeeds, the transaction is committed
+    pub async fn with_transaction<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(Transaction<'_, sqlx::Sqlite>) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<R>> + Send + '_>>,
+    {
+        let tx = self.begin_transaction().await?;
+        
+        match f(tx).await {
+            Ok(result) => {
+                // Transaction is automatically committed when it goes out of scope
+                Ok(result)
+            }
+            Err(e) => {
+                // Transaction is automatically rolled back when it goes out of scope
+                Err(e)
+            }
+        }
+    }
+
+    // Server configuration methods
+    pub async fn create_server(&self, config: &ServerConfig) -> Result<()> {
+        let rcon_password = self.stash_secret(&rcon_secret_key(&config.id), &config.rcon_password).await?;
+        sqlx::query(
+            r#"
+            INSERT INTO servers (
+                id, name, minecraft_version, loader, loader_version, port, rcon_port, query_port,
+                max_players, memory, java_args, server_args, auto_start, auto_restart,
+                world_name, difficulty, gamemode, pvp, online_mode, whitelist,
+                enable_command_block, view_distance, simulation_distance, motd,
+                host, java_path, jvm_args, server_jar, server_directory, rcon_password, managed,
+                created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&config.id)
+        .bind(&config.name)
+        .bind(&config.minecraft_version)
+        .bind(&config.loader)
+        .bind(&config.loader_version)
+        .bind(config.port)
+        .bind(config.rcon_port)
+        .bind(config.query_port)
+        .bind(config.max_players)
+        .bind(config.memory)
+        .bind(&config.java_args)
+        .bind(&config.server_args)
+        .bind(config.auto_start)
+        .bind(config.auto_restart)
+        .bind(&config.world_name)
+        .bind(&config.difficulty)
+        .bind(&config.gamemode)
+        .bind(config.pvp)
+        .bind(config.online_mode)
+        .bind(config.whitelist)
+        .bind(config.enable_command_block)
+        .bind(config.view_distance)
+        .bind(config.simulation_distance)
+        .bind(&config.motd)
+        .bind(&config.host)
+        .bind(&config.java_path)
+        .bind(&config.jvm_args)
+        .bind(&config.server_jar)
+        .bind(&config.server_directory)
+        .bind(&rcon_password)
+        .bind(config.managed)
+        .bind(config.created_at)
+        .bind(config.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Created server configuration: {}", config.id);
+        Ok(())
+    }
+
+    pub async fn get_server(&self, id: &str) -> Result<Option<ServerConfig>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, name, minecraft_version, loader, loader_version, port, rcon_port, query_port,
+                   max_players, memory, java_args, server_args, auto_start, auto_restart,
+                   world_name, difficulty, gamemode, pvp, online_mode, whitelist,
+                   enable_command_block, view_distance, simulation_distance, motd,
+                   host, java_path, jvm_args, server_jar, server_directory, rcon_password, managed,
+                   created_at, updated_at
+            FROM servers WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(row) = row {
+            Ok(Some(self.reveal_server_secrets(ServerConfig {
+                id: row.get("id"),
+                name: row.get("name"),
+                minecraft_version: row.get("minecraft_version"),
+                loader: row.get("loader"),
+                loader_version: row.get("loader_version"),
+                port: row.get("port"),
+                rcon_port: row.get("rcon_port"),
+                query_port: row.get("query_port"),
+                max_players: row.get("max_players"),
+                memory: row.get("memory"),
+                java_args: row.get("java_args"),
+                server_args: row.get("server_args"),
+                auto_start: row.get("auto_start"),
+                auto_restart: row.get("auto_restart"),
+                world_name: row.get("world_name"),
+                difficulty: row.get("difficulty"),
+                gamemode: row.get("gamemode"),
+                pvp: row.get("pvp"),
+                online_mode: row.get("online_mode"),
+                whitelist: row.get("whitelist"),
+                enable_command_block: row.get("enable_command_block"),
+                view_distance: row.get("view_distance"),
+                simulation_distance: row.get("simulation_distance"),
+                motd: row.get("motd"),
+                host: row.get("host"),
+                java_path: row.get("java_path"),
+                jvm_args: row.get("jvm_args"),
+                server_jar: row.get("server_jar"),
+                server_directory: row.get("server_directory"),
+                rcon_password: row.get("rcon_password"),
+                managed: row.get("managed"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            }).await))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The server with this id, or a `NotFound` error that the API reports as 404
+    pub async fn require_server(&self, id: &str) -> Result<ServerConfig> {
+        self.get_server(id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Server", id).into())
+    }
+
+    pub async fn get_all_servers(&self) -> Result<Vec<ServerConfig>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, name, minecraft_version, loader, loader_version, port, rcon_port, query_port,
+                   max_players, memory, java_args, server_args, auto_start, auto_restart,
+                   world_name, difficulty, gamemode, pvp, online_mode, whitelist,
+                   enable_command_block, view_distance, simulation_distance, motd,
+                   host, java_path, jvm_args, server_jar, server_directory, rcon_password, managed,
+                   created_at, updated_at
+            FROM servers ORDER BY name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let servers = rows
+            .into_iter()
+            .map(|row| ServerConfig {
+                id: row.get("id"),
+                name: row.get("name"),
+                minecraft_version: row.get("minecraft_version"),
+                loader: row.get("loader"),
+                loader_version: row.get("loader_version"),
+                port: row.get("port"),
+                rcon_port: row.get("rcon_port"),
+                query_port: row.get("query_port"),
+                max_players: row.get("max_players"),
+                memory: row.get("memory"),
+                java_args: row.get("java_args"),
+                server_args: row.get("server_args"),
+                auto_start: row.get("auto_start"),
+                auto_restart: row.get("auto_restart"),
+                world_name: row.get("world_name"),
+                difficulty: row.get("difficulty"),
+                gamemode: row.get("gamemode"),
+                pvp: row.get("pvp"),
+                online_mode: row.get("online_mode"),
+                whitelist: row.get("whitelist"),
+                enable_command_block: row.get("enable_command_block"),
+                view_distance: row.get("view_distance"),
+                simulation_distance: row.get("simulation_distance"),
+                motd: row.get("motd"),
+                host: row.get("host"),
+                java_path: row.get("java_path"),
+                jvm_args: row.get("jvm_args"),
+                server_jar: row.get("server_jar"),
+                server_directory: row.get("server_directory"),
+                rcon_password: row.get("rcon_password"),
+                managed: row.get("managed"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            })
+            .collect::<Vec<_>>();
+
+        let mut revealed = Vec::with_capacity(servers.len());
+        for server in servers {
+            revealed.push(self.reveal_server_secrets(server).await);
+        }
+        Ok(revealed)
+    }
+
+    pub async fn update_server(&self, config: &ServerConfig) -> Result<()> {
+        let rcon_password = self.stash_secret(&rcon_secret_key(&config.id), &config.rcon_password).await?;
+        sqlx::query(
+            r#"
+            UPDATE servers SET
+                name = ?, host = ?, port = ?, rcon_port = ?, rcon_password = ?,
+                java_path = ?, server_jar = ?, jvm_args = ?, server_args = ?,
+                auto_start = ?, auto_restart = ?, max_players = ?,
+                updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&config.name)
+        .bind(&config.host)
+        .bind(config.port)
+        .bind(config.rcon_port)
+        .bind(&rcon_password)
+        .bind(&config.java_path)
+        .bind(&config.server_jar)
+        .bind(&config.jvm_args)
+        .bind(&config.server_args)
+        .bind(config.auto_start)
+        .bind(config.auto_restart)
+        .bind(config.max_players)
+        // pregeneration_policy field removed
+        .bind(config.updated_at)
+        .bind(&config.id)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Updated server configuration: {}", config.id);
+        Ok(())
+    }
+
+    pub async fn delete_server(&self, id: &str) -> Result<()> {
+        // Delete related data first
+        sqlx::query("DELETE FROM server_logs WHERE server_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM server_metrics WHERE server_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM server_mods WHERE server_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM server_group_members WHERE server_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM chat_bridges WHERE server_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM world_border_schedules WHERE server_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        // Delete the server itself
+        sqlx::query("DELETE FROM servers WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.stash_secret(&rcon_secret_key(id), "").await?;
+        self.stash_secret(&tunnel_secret_key(id), "").await?;
+
+        info!("Deleted server configuration and all related data: {}", id);
+        Ok(())
+    }
+
+    // Settings methods
+    pub async fn get_settings(&self) -> Result<Option<Settings>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, cf_api_key, modrinth_token, java_path, default_ram_mb,
+                   data_dir, telemetry_opt_in, setup_completed_at, accept_eula_by_default,
+                   event_retention_days, offline_mode, created_at, updated_at
+            FROM settings LIMIT 1
+            "#,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(row) = row {
+            let cf_api_key: Option<String> = row.get("cf_api_key");
+            let modrinth_token: Option<String> = row.get("modrinth_token");
+            let cf_api_key = self.reveal_secret(CURSEFORGE_SECRET_KEY, cf_api_key.unwrap_or_default()).await;
+            let modrinth_token = self.reveal_secret(MODRINTH_SECRET_KEY, modrinth_token.unwrap_or_default()).await;
+            Ok(Some(Settings {
+                id: row.get("id"),
+                cf_api_key: Some(cf_api_key).filter(|key| !key.is_empty()),
+                modrinth_token: Some(modrinth_token).filter(|token| !token.is_empty()),
+                java_path: row.get("java_path"),
+                default_ram_mb: row.get("default_ram_mb"),
+                data_dir: row.get("data_dir"),
+                telemetry_opt_in: row.get("telemetry_opt_in"),
+                setup_completed_at: row.get("setup_completed_at"),
+                accept_eula_by_default: row.get("accept_eula_by_default"),
+                event_retention_days: row.get("event_retention_days"),
+                offline_mode: row.get("offline_mode"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn update_settings(&self, settings: &Settings) -> Result<()> {
+        let cf_api_key = self.stash_secret(CURSEFORGE_SECRET_KEY, settings.cf_api_key.as_deref().unwrap_or_default()).await?;
+        let modrinth_token = self.stash_secret(MODRINTH_SECRET_KEY, settings.modrinth_token.as_deref().unwrap_or_default()).await?;
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO settings (
+                id, cf_api_key, modrinth_token, java_path, default_ram_mb,
+                data_dir, telemetry_opt_in, setup_completed_at, accept_eula_by_default,
+                event_retention_days, offline_mode, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&settings.id)
+        .bind(Some(cf_api_key).filter(|key| !key.is_empty()))
+        .bind(Some(modrinth_token).filter(|token| !token.is_empty()))
+        .bind(&settings.java_path)
+        .bind(settings.default_ram_mb)
+        .bind(&settings.data_dir)
+        .bind(settings.telemetry_opt_in)
+        .bind(settings.setup_completed_at)
+        .bind(settings.accept_eula_by_default)
+        .bind(settings.event_retention_days)
+        .bind(settings.offline_mode)
+        .bind(settings.created_at)
+        .bind(settings.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Updated settings: {}", settings.id);
+        Ok(())
+    }
+
+    // User settings methods
+    pub async fn get_user_settings(&self) -> Result<Option<UserSettings>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, theme, language, notifications, auto_refresh,
+                   refresh_interval, created_at, updated_at
+            FROM user_settings LIMIT 1
+            "#,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(row) = row {
+            Ok(Some(UserSettings {
+                id: row.get("id"),
+                theme: row.get("theme"),
+                language: row.get("language"),
+                notifications: row.get("notifications"),
+                auto_refresh: row.get("auto_refresh"),
+                refresh_interval: row.get("refresh_interval"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn update_user_settings(&self, settings: &UserSettings) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO user_settings (
+                id, theme, language, notifications, auto_refresh,
+                refresh_interval, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&settings.id)
+        .bind(&settings.theme)
+        .bind(&settings.language)
+        .bind(settings.notifications)
+        .bind(settings.auto_refresh)
+        .bind(settings.refresh_interval)
+        .bind(settings.created_at)
+        .bind(settings.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Updated user settings: {}", settings.id);
+        Ok(())
+    }
+
+    // Task methods
+    pub async fn create_task(&self, task: &Task) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO tasks (
+                id, server_id, kind, status, progress, log, metadata,
+                started_at, finished_at, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&task.id)
+        .bind(&task.server_id)
+        .bind(&task.kind)
+        .bind(&task.status)
+        .bind(task.progress)
+        .bind(&task.log)
+        .bind(&task.metadata)
+        .bind(task.started_at)
+        .bind(task.finished_at)
+        .bind(task.created_at)
+        .bind(task.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Created task: {}", task.id);
+        Ok(())
+    }
+
+    pub async fn get_task(&self, id: &str) -> Result<Option<Task>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, server_id, kind, status, progress, log, metadata,
+                   started_at, finished_at, created_at, updated_at
+            FROM tasks WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(row) = row {
+            Ok(Some(Task {
+                id: row.get("id"),
+                server_id: row.get("server_id"),
+                kind: row.get("kind"),
+                status: row.get("status"),
+                progress: row.get("progress"),
+                log: row.get("log"),
+                metadata: row.get("metadata"),
+                started_at: row.get("started_at"),
+                finished_at: row.get("finished_at"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn get_tasks_by_server(&self, server_id: &str) -> Result<Vec<Task>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, server_id, kind, status, progress, log, metadata,
+                   started_at, finished_at, created_at, updated_at
+            FROM tasks WHERE server_id = ?
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let tasks = rows
+            .into_iter()
+            .map(|row| Task {
+                id: row.get("id"),
+                server_id: row.get("server_id"),
+                kind: row.get("kind"),
+                status: row.get("status"),
+                progress: row.get("progress"),
+                log: row.get("log"),
+                metadata: row.get("metadata"),
+                started_at: row.get("started_at"),
+                finished_at: row.get("finished_at"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            })
+            .collect();
+
+        Ok(tasks)
+    }
+
+    pub async fn update_task(&self, task: &Task) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE tasks SET
+                status = ?, progress = ?, log = ?, metadata = ?,
+                started_at = ?, finished_at = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&task.status)
+        .bind(task.progress)
+        .bind(&task.log)
+        .bind(&task.metadata)
+        .bind(task.started_at)
+        .bind(task.finished_at)
+        .bind(task.updated_at)
+        .bind(&task.id)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Updated task: {}", task.id);
+        Ok(())
+    }
+
+    pub async fn delete_task(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM tasks WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        info!("Deleted task: {}", id);
+        Ok(())
+    }
+
+    /// Tasks of any kind matching `filter`, newest first
+    pub async fn list_tasks(&self, filter: &crate::jobs::JobFilter) -> Result<Vec<Task>> {
+        let mut query = sqlx::QueryBuilder::new(
+            "SELECT id, server_id, kind, status, progress, log, metadata, \
+             started_at, finished_at, created_at, updated_at FROM tasks WHERE 1=1",
+        );
+        if let Some(server_id) = &filter.server_id {
+            query.push(" AND server_id = ").push_bind(server_id);
+        }
+        if !filter.server_ids.is_empty() {
+            query.push(" AND server_id IN (");
+            let mut servers = query.separated(", ");
+            for server_id in &filter.server_ids {
+                servers.push_bind(server_id);
+            }
+            query.push(")");
+        }
+        if let Some(kind) = &filter.kind {
+            query.push(" AND kind = ").push_bind(kind);
+        }
+        if !filter.statuses.is_empty() {
+            query.push(" AND status IN (");
+            let mut statuses = query.separated(", ");
+            for status in &filter.statuses {
+                statuses.push_bind(status);
+            }
+            query.push(")");
+        }
+        query.push(" ORDER BY created_at DESC LIMIT ").push_bind(filter.limit);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| Task {
+                id: row.get("id"),
+                server_id: row.get("server_id"),
+                kind: row.get("kind"),
+                status: row.get("status"),
+                progress: row.get("progress"),
+                log: row.get("log"),
+                metadata: row.get("metadata"),
+                started_at: row.get("started_at"),
+                finished_at: row.get("finished_at"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            })
+            .collect())
+    }
+
+    /// Record progress of a task without touching its other columns
+    pub async fn update_task_progress(&self, id: &str, progress: f64, log: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE tasks SET progress = ?, log = COALESCE(?, log), updated_at = ? WHERE id = ?")
+            .bind(progress)
+            .bind(log)
+            .bind(chrono::Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // World heatmap cache methods
+    pub async fn get_world_heatmap(&self, server_id: &str, dimension: &str) -> Result<Option<serde_json::Value>> {
+        let row = sqlx::query("SELECT data FROM world_heatmaps WHERE server_id = ? AND dimension = ?")
+            .bind(server_id)
+            .bind(dimension)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("data")))
+    }
+
+    pub async fn save_world_heatmap(
+        &self,
+        server_id: &str,
+        dimension: &str,
+        data: &serde_json::Value,
+        generated_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO world_heatmaps (server_id, dimension, data, generated_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(server_id)
+        .bind(dimension)
+        .bind(data)
+        .bind(generated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Restart schedule methods
+    fn restart_schedule_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<RestartSchedule> {
+        let warnings: serde_json::Value = row.get("warnings");
+        let kind: String = row.get("kind");
+        let commands: String = row.get("commands");
+        Ok(RestartSchedule {
+            id: row.get("id"),
+            server_id: row.get("server_id"),
+            name: row.get("name"),
+            kind: crate::restart_scheduler::ScheduleKind::parse(&kind)?,
+            cron_expression: row.get("cron_expression"),
+            enabled: row.get("enabled"),
+            commands: serde_json::from_str(&commands)?,
+            warnings: serde_json::from_value(warnings)?,
+            grace_period_seconds: row.get::<i64, _>("grace_period_seconds") as u32,
+            message: row.get("message"),
+            use_title: row.get("use_title"),
+            last_run: row.get("last_run"),
+            last_status: row.get("last_status"),
+            last_task_id: row.get("last_task_id"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    pub async fn create_restart_schedule(&self, schedule: &RestartSchedule) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO restart_schedules (
+                id, server_id, name, kind, cron_expression, enabled, commands, warnings,
+                grace_period_seconds, message, use_title, last_run, last_status, last_task_id,
+                created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&schedule.id)
+        .bind(&schedule.server_id)
+        .bind(&schedule.name)
+        .bind(schedule.kind.as_str())
+        .bind(&schedule.cron_expression)
+        .bind(schedule.enabled)
+        .bind(serde_json::to_string(&schedule.commands)?)
+        .bind(serde_json::to_value(&schedule.warnings)?)
+        .bind(schedule.grace_period_seconds as i64)
+        .bind(&schedule.message)
+        .bind(schedule.use_title)
+        .bind(schedule.last_run)
+        .bind(&schedule.last_status)
+        .bind(&schedule.last_task_id)
+        .bind(schedule.created_at)
+        .bind(schedule.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Created restart schedule: {}", schedule.id);
+        Ok(())
+    }
+
+    pub async fn get_restart_schedule(&self, id: &str) -> Result<Option<RestartSchedule>> {
+        let row = sqlx::query("SELECT * FROM restart_schedules WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::restart_schedule_from_row).transpose()
+    }
+
+    /// Schedules of one server, or of every server when `server_id` is `None`
+    pub async fn get_restart_schedules(&self, server_id: Option<&str>) -> Result<Vec<RestartSchedule>> {
+        let rows = match server_id {
+            Some(server_id) => {
+                sqlx::query("SELECT * FROM restart_schedules WHERE server_id = ? ORDER BY created_at")
+                    .bind(server_id)
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+            None => {
+                sqlx::query("SELECT * FROM restart_schedules ORDER BY created_at")
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+        };
+
+        rows.iter().map(Self::restart_schedule_from_row).collect()
+    }
+
+    pub async fn update_restart_schedule(&self, schedule: &RestartSchedule) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE restart_schedules SET
+                name = ?, kind = ?, cron_expression = ?, enabled = ?, commands = ?, warnings = ?,
+                grace_period_seconds = ?, message = ?, use_title = ?, last_run = ?, last_status = ?,
+                last_task_id = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&schedule.name)
+        .bind(schedule.kind.as_str())
+        .bind(&schedule.cron_expression)
+        .bind(schedule.enabled)
+        .bind(serde_json::to_string(&schedule.commands)?)
+        .bind(serde_json::to_value(&schedule.warnings)?)
+        .bind(schedule.grace_period_seconds as i64)
+        .bind(&schedule.message)
+        .bind(schedule.use_title)
+        .bind(schedule.last_run)
+        .bind(&schedule.last_status)
+        .bind(&schedule.last_task_id)
+        .bind(schedule.updated_at)
+        .bind(&schedule.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_restart_schedule(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM restart_schedules WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        info!("Deleted restart schedule: {}", id);
+        Ok(())
+    }
+
+    // Temporary ban methods
+    fn temporary_ban_from_row(row: &sqlx::sqlite::SqliteRow) -> TemporaryBan {
+        TemporaryBan {
+            id: row.get("id"),
+            server_id: row.get("server_id"),
+            kind: row.get("kind"),
+            target: row.get("target"),
+            uuid: row.get("uuid"),
+            reason: row.get("reason"),
+            source: row.get("source"),
+            expires_at: row.get("expires_at"),
+            lifted_at: row.get("lifted_at"),
+            created_at: row.get("created_at"),
+        }
+    }
+
+    pub async fn create_temporary_ban(&self, ban: &TemporaryBan) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO temporary_bans (
+                id, server_id, kind, target, uuid, reason, source, expires_at, lifted_at, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&ban.id)
+        .bind(&ban.server_id)
+        .bind(&ban.kind)
+        .bind(&ban.target)
+        .bind(&ban.uuid)
+        .bind(&ban.reason)
+        .bind(&ban.source)
+        .bind(ban.expires_at)
+        .bind(ban.lifted_at)
+        .bind(ban.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Created temporary {} ban {} for server {}", ban.kind, ban.id, ban.server_id);
+        Ok(())
+    }
+
+    /// Temporary bans not lifted yet, of one server or of every server when `server_id` is `None`
+    pub async fn get_active_temporary_bans(&self, server_id: Option<&str>) -> Result<Vec<TemporaryBan>> {
+        let rows = sqlx::query(
+            "SELECT * FROM temporary_bans WHERE lifted_at IS NULL AND (? IS NULL OR server_id = ?) ORDER BY expires_at",
+        )
+        .bind(server_id)
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::temporary_ban_from_row).collect())
+    }
+
+    pub async fn mark_temporary_ban_lifted(&self, id: &str, lifted_at: chrono::DateTime<chrono::Utc>) -> Result<()> {
+        sqlx::query("UPDATE temporary_bans SET lifted_at = ? WHERE id = ?")
+            .bind(lifted_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Console history methods
+    pub async fn create_console_command(&self, command: &ConsoleCommand) -> Result<()> {
+        sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS backup_configs (
-                id TEXT PRIMARY KEY,
-                server_id TEXT NOT NULL,
-                name TEXT NOT NULL,
-                schedule TEXT NOT NULL,
-                retention_days INTEGER NOT NULL DEFAULT 7,
-                include_world BOOLEAN NOT NULL DEFAULT 1,
-                include_logs BOOLEAN NOT NULL DEFAULT 1,
-                include_configs BOOLEAN NOT NULL DEFAULT 1,
-                enabled BOOLEAN NOT NULL DEFAULT 1,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
+            INSERT INTO console_commands (id, server_id, command, author, success, error, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&command.id)
+        .bind(&command.server_id)
+        .bind(&command.command)
+        .bind(&command.author)
+        .bind(command.success)
+        .bind(&command.error)
+        .bind(command.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM console_commands WHERE server_id = ? AND id NOT IN (
+                SELECT id FROM console_commands WHERE server_id = ? ORDER BY created_at DESC LIMIT ?
             )
             "#,
         )
+        .bind(&command.server_id)
+        .bind(&command.server_id)
+        .bind(CONSOLE_HISTORY_LIMIT)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// A server's commands, newest first, optionally only those sent before `before`
+    pub async fn get_console_commands(
+        &self,
+        server_id: &str,
+        before: Option<chrono::DateTime<chrono::Utc>>,
+        limit: u32,
+    ) -> Result<Vec<ConsoleCommand>> {
+        let rows = sqlx::query(
+            "SELECT * FROM console_commands WHERE server_id = ? AND (? IS NULL OR created_at < ?) ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(server_id)
+        .bind(before)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ConsoleCommand {
+                id: row.get("id"),
+                server_id: row.get("server_id"),
+                command: row.get("command"),
+                author: row.get("author"),
+                success: row.get("success"),
+                error: row.get("error"),
+                created_at: row.get("created_at"),
+            })
+            .collect())
+    }
+
+    // Metadata cache methods
+    pub async fn get_cached_response(&self, url: &str) -> Result<Option<CachedResponse>> {
+        let row = sqlx::query("SELECT * FROM metadata_cache WHERE url = ?")
+            .bind(url)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| CachedResponse {
+            url: row.get("url"),
+            body: row.get("body"),
+            etag: row.get("etag"),
+            last_modified: row.get("last_modified"),
+            fetched_at: row.get("fetched_at"),
+        }))
+    }
+
+    pub async fn save_cached_response(&self, response: &CachedResponse) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO metadata_cache (url, body, etag, last_modified, fetched_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(url) DO UPDATE SET
+                body = excluded.body,
+                etag = excluded.etag,
+                last_modified = excluded.last_modified,
+                fetched_at = excluded.fetched_at
+            "#,
+        )
+        .bind(&response.url)
+        .bind(&response.body)
+        .bind(&response.etag)
+        .bind(&response.last_modified)
+        .bind(response.fetched_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Sharding methods
+    pub async fn create_shard_group(&self, group: &ShardGroup) -> Result<()> {
+        sqlx::query("INSERT INTO shard_groups (id, name, description, created_at) VALUES (?, ?, ?, ?)")
+            .bind(&group.id)
+            .bind(&group.name)
+            .bind(&group.description)
+            .bind(group.created_at)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_shard_groups(&self) -> Result<Vec<ShardGroup>> {
+        let rows = sqlx::query("SELECT * FROM shard_groups ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ShardGroup {
+                id: row.get("id"),
+                name: row.get("name"),
+                description: row.get("description"),
+                created_at: row.get("created_at"),
+            })
+            .collect())
+    }
+
+    /// Delete a group with its shards, their assignments and their regions
+    pub async fn delete_shard_group(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM shard_groups WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn create_shard(&self, shard: &Shard) -> Result<()> {
+        sqlx::query("INSERT INTO shards (id, group_id, name, max_players, created_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&shard.id)
+            .bind(&shard.group_id)
+            .bind(&shard.name)
+            .bind(shard.max_players as i64)
+            .bind(shard.created_at)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_shards(&self) -> Result<Vec<Shard>> {
+        let rows = sqlx::query("SELECT * FROM shards ORDER BY group_id, name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Shard {
+                id: row.get("id"),
+                group_id: row.get("group_id"),
+                name: row.get("name"),
+                max_players: row.get::<i64, _>("max_players") as u32,
+                created_at: row.get("created_at"),
+            })
+            .collect())
+    }
+
+    pub async fn update_shard(&self, shard: &Shard) -> Result<()> {
+        sqlx::query("UPDATE shards SET name = ?, max_players = ? WHERE id = ?")
+            .bind(&shard.name)
+            .bind(shard.max_players as i64)
+            .bind(&shard.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_shard(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM shards WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Assign a server to a shard, replacing its previous assignment
+    pub async fn save_shard_assignment(&self, assignment: &ShardAssignment) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO shard_assignments (id, shard_id, server_id, dimensions, assigned_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(server_id) DO UPDATE SET
+                shard_id = excluded.shard_id,
+                dimensions = excluded.dimensions,
+                assigned_at = excluded.assigned_at
+            "#,
+        )
+        .bind(&assignment.id)
+        .bind(&assignment.shard_id)
+        .bind(&assignment.server_id)
+        .bind(serde_json::to_string(&assignment.dimensions)?)
+        .bind(assignment.assigned_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_shard_assignments(&self) -> Result<Vec<ShardAssignment>> {
+        let rows = sqlx::query("SELECT * FROM shard_assignments ORDER BY assigned_at")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let dimensions: String = row.get("dimensions");
+                Ok(ShardAssignment {
+                    id: row.get("id"),
+                    shard_id: row.get("shard_id"),
+                    server_id: row.get("server_id"),
+                    dimensions: serde_json::from_str(&dimensions)?,
+                    assigned_at: row.get("assigned_at"),
+                })
+            })
+            .collect()
+    }
+
+    pub async fn delete_shard_assignment(&self, server_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM shard_assignments WHERE server_id = ?")
+            .bind(server_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn create_shard_region(&self, region: &ShardRegion) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO shard_regions (id, shard_id, dimension, min_x, min_z, max_x, max_z) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&region.id)
+        .bind(&region.shard_id)
+        .bind(&region.dimension)
+        .bind(region.min_x)
+        .bind(region.min_z)
+        .bind(region.max_x)
+        .bind(region.max_z)
         .execute(&self.pool)
         .await?;
+        Ok(())
+    }
+
+    pub async fn get_shard_regions(&self) -> Result<Vec<ShardRegion>> {
+        let rows = sqlx::query("SELECT * FROM shard_regions ORDER BY shard_id, dimension, min_x, min_z")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ShardRegion {
+                id: row.get("id"),
+                shard_id: row.get("shard_id"),
+                dimension: row.get("dimension"),
+                min_x: row.get("min_x"),
+                min_z: row.get("min_z"),
+                max_x: row.get("max_x"),
+                max_z: row.get("max_z"),
+            })
+            .collect())
+    }
+
+    pub async fn delete_shard_region(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM shard_regions WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
 
-        // Create backup_records table
+    // Server template methods
+    fn server_template_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<ServerTemplate> {
+        let properties: String = row.get("properties");
+        let mods: String = row.get("mods");
+        Ok(ServerTemplate {
+            id: row.get("id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            loader: row.get("loader"),
+            loader_version: row.get("loader_version"),
+            minecraft_version: row.get("minecraft_version"),
+            memory: row.get::<i64, _>("memory") as u32,
+            jvm_args: row.get("jvm_args"),
+            properties: serde_json::from_str(&properties)?,
+            mods: serde_json::from_str(&mods)?,
+            source_server_id: row.get("source_server_id"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    pub async fn create_server_template(&self, template: &ServerTemplate) -> Result<()> {
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS backup_records (
-                id TEXT PRIMARY KEY,
-                config_id TEXT NOT NULL,
-                server_id TEXT NOT NULL,
-                name TEXT NOT NULL,
-                path TEXT NOT NULL,
-                size_bytes INTEGER NOT NULL DEFAULT 0,
-                status TEXT NOT NULL DEFAULT 'pending',
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                completed_at DATETIME,
-                FOREIGN KEY (config_id) REFERENCES backup_configs (id) ON DELETE CASCADE,
-                FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
-            )
+            INSERT INTO server_templates (
+                id, name, description, loader, loader_version, minecraft_version, memory,
+                jvm_args, properties, mods, source_server_id, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
+        .bind(&template.id)
+        .bind(&template.name)
+        .bind(&template.description)
+        .bind(&template.loader)
+        .bind(&template.loader_version)
+        .bind(&template.minecraft_version)
+        .bind(template.memory as i64)
+        .bind(&template.jvm_args)
+        .bind(serde_json::to_string(&template.properties)?)
+        .bind(serde_json::to_string(&template.mods)?)
+        .bind(&template.source_server_id)
+        .bind(template.created_at)
+        .bind(template.updated_at)
         .execute(&self.pool)
         .await?;
 
-        // Create event_logs table
+        info!("Created server template: {}", template.id);
+        Ok(())
+    }
+
+    pub async fn get_server_template(&self, id: &str) -> Result<Option<ServerTemplate>> {
+        let row = sqlx::query("SELECT * FROM server_templates WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::server_template_from_row).transpose()
+    }
+
+    pub async fn get_server_templates(&self) -> Result<Vec<ServerTemplate>> {
+        let rows = sqlx::query("SELECT * FROM server_templates ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(Self::server_template_from_row).collect()
+    }
+
+    pub async fn update_server_template(&self, template: &ServerTemplate) -> Result<()> {
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS event_logs (
-                id TEXT PRIMARY KEY,
-                server_id TEXT,
-                event_type TEXT NOT NULL,
-                message TEXT NOT NULL,
-                level TEXT NOT NULL DEFAULT 'info',
-                metadata TEXT,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE SET NULL
-            )
+            UPDATE server_templates SET
+                name = ?, description = ?, loader = ?, loader_version = ?, minecraft_version = ?,
+                memory = ?, jvm_args = ?, properties = ?, mods = ?, updated_at = ?
+            WHERE id = ?
             "#,
         )
+        .bind(&template.name)
+        .bind(&template.description)
+        .bind(&template.loader)
+        .bind(&template.loader_version)
+        .bind(&template.minecraft_version)
+        .bind(template.memory as i64)
+        .bind(&template.jvm_args)
+        .bind(serde_json::to_string(&template.properties)?)
+        .bind(serde_json::to_string(&template.mods)?)
+        .bind(template.updated_at)
+        .bind(&template.id)
         .execute(&self.pool)
         .await?;
 
-        // Create indexes
-        // Add missing columns if they don't exist (migration for existing databases)
-        info!("Adding missing columns to existing tables...");
-        
-        // Add max_players column to servers table
-        if let Err(e) = sqlx::query("ALTER TABLE servers ADD COLUMN max_players INTEGER DEFAULT 20")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add max_players column to servers table: {}", e);
-            }
-        }
-        
-        // Add minecraft_version column to servers table
-        if let Err(e) = sqlx::query("ALTER TABLE servers ADD COLUMN minecraft_version TEXT DEFAULT '1.20.1'")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add minecraft_version column to servers table: {}", e);
-            }
-        }
-        
-        // Add server_id column to tasks table
-        if let Err(e) = sqlx::query("ALTER TABLE tasks ADD COLUMN server_id TEXT")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add server_id column to tasks table: {}", e);
-            }
-        }
-        
-        // Add server_id column to mods table
-        if let Err(e) = sqlx::query("ALTER TABLE mods ADD COLUMN server_id TEXT")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add server_id column to mods table: {}", e);
-            }
-        }
-        
-        // Add category column to mods table
-        if let Err(e) = sqlx::query("ALTER TABLE mods ADD COLUMN category TEXT DEFAULT 'unknown'")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add category column to mods table: {}", e);
-            }
-        }
-        
-        // Add side column to mods table
-        if let Err(e) = sqlx::query("ALTER TABLE mods ADD COLUMN side TEXT DEFAULT 'both'")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add side column to mods table: {}", e);
-            }
-        }
-        
-        // Add source column to mods table
-        if let Err(e) = sqlx::query("ALTER TABLE mods ADD COLUMN source TEXT DEFAULT 'unknown'")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add source column to mods table: {}", e);
-            }
-        }
-        
-        // Add name column to mods table
-        if let Err(e) = sqlx::query("ALTER TABLE mods ADD COLUMN name TEXT DEFAULT 'Unknown'")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add name column to mods table: {}", e);
-            }
-        }
-        
-        // Add description column to mods table
-        if let Err(e) = sqlx::query("ALTER TABLE mods ADD COLUMN description TEXT")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add description column to mods table: {}", e);
-            }
-        }
-        
-        // Add server_id column to backup_configs table
-        if let Err(e) = sqlx::query("ALTER TABLE backup_configs ADD COLUMN server_id TEXT")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add server_id column to backup_configs table: {}", e);
-            }
-        }
-        
-        // Add server_id column to backup_records table
-        if let Err(e) = sqlx::query("ALTER TABLE backup_records ADD COLUMN server_id TEXT")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add server_id column to backup_records table: {}", e);
-            }
-        }
-        
-        // Add server_id column to event_logs table
-        if let Err(e) = sqlx::query("ALTER TABLE event_logs ADD COLUMN server_id TEXT")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add server_id column to event_logs table: {}", e);
-            }
-        }
-        
-        // Add missing columns for ServerConfig fields
-        let missing_columns = [
-            ("loader", "TEXT DEFAULT 'vanilla'"),
-            ("loader_version", "TEXT DEFAULT 'latest'"),
-            ("query_port", "INTEGER DEFAULT 25566"),
-            ("memory", "INTEGER DEFAULT 4096"),
-            ("java_args", "TEXT DEFAULT '[]'"),
-            ("server_args", "TEXT DEFAULT '[]'"),
-            ("world_name", "TEXT DEFAULT 'world'"),
-            ("difficulty", "TEXT DEFAULT 'normal'"),
-            ("gamemode", "TEXT DEFAULT 'survival'"),
-            ("pvp", "BOOLEAN DEFAULT 1"),
-            ("online_mode", "BOOLEAN DEFAULT 1"),
-            ("whitelist", "BOOLEAN DEFAULT 0"),
-            ("enable_command_block", "BOOLEAN DEFAULT 0"),
-            ("view_distance", "INTEGER DEFAULT 10"),
-            ("simulation_distance", "INTEGER DEFAULT 10"),
-            ("motd", "TEXT DEFAULT 'A Minecraft Server'"),
-            ("host", "TEXT DEFAULT 'localhost'"),
-            ("java_path", "TEXT DEFAULT 'java'"),
-            ("jvm_args", "TEXT DEFAULT '-Xmx4G -Xms2G'"),
-            ("server_jar", "TEXT DEFAULT 'server.jar'"),
-            ("server_directory", "TEXT DEFAULT 'data/servers'"),
-            ("rcon_password", "TEXT DEFAULT ''"),
-        ];
-        
-        for (column_name, column_def) in &missing_columns {
-            if let Err(e) = sqlx::query(&format!("ALTER TABLE servers ADD COLUMN {} {}", column_name, column_def))
-                .execute(&self.pool)
-                .await
-            {
-                if !e.to_string().contains("duplicate column name") {
-                    warn!("Failed to add {} column to servers table: {}", column_name, e);
-                }
-            }
-        }
-        
-        // Add provider column to mods table
-        if let Err(e) = sqlx::query("ALTER TABLE mods ADD COLUMN provider TEXT DEFAULT 'unknown'")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add provider column to mods table: {}", e);
-            }
-        }
-        
-        // Add project_id column to mods table
-        if let Err(e) = sqlx::query("ALTER TABLE mods ADD COLUMN project_id TEXT DEFAULT ''")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add project_id column to mods table: {}", e);
-            }
-        }
-        
-        // Add version_id column to mods table
-        if let Err(e) = sqlx::query("ALTER TABLE mods ADD COLUMN version_id TEXT DEFAULT ''")
+        Ok(())
+    }
+
+    pub async fn delete_server_template(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM server_templates WHERE id = ?")
+            .bind(id)
             .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add version_id column to mods table: {}", e);
-            }
+            .await?;
+
+        info!("Deleted server template: {}", id);
+        Ok(())
+    }
+
+    // Server tunnel methods
+    async fn server_tunnel_from_row(&self, row: &sqlx::sqlite::SqliteRow) -> ServerTunnel {
+        let server_id: String = row.get("server_id");
+        let token = self.reveal_secret(&tunnel_secret_key(&server_id), row.get("token")).await;
+        ServerTunnel {
+            server_id,
+            provider: row.get("provider"),
+            enabled: row.get("enabled"),
+            token,
+            region: row.get("region"),
+            agent_path: row.get("agent_path"),
+            updated_at: row.get("updated_at"),
         }
-        
-        // Add filename column to mods table
-        if let Err(e) = sqlx::query("ALTER TABLE mods ADD COLUMN filename TEXT DEFAULT ''")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add filename column to mods table: {}", e);
-            }
+    }
+
+    pub async fn get_server_tunnel(&self, server_id: &str) -> Result<Option<ServerTunnel>> {
+        let row = sqlx::query("SELECT * FROM server_tunnels WHERE server_id = ?")
+            .bind(server_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => Ok(Some(self.server_tunnel_from_row(&row).await)),
+            None => Ok(None),
         }
-        
-        // Add sha1 column to mods table
-        if let Err(e) = sqlx::query("ALTER TABLE mods ADD COLUMN sha1 TEXT DEFAULT ''")
-            .execute(&self.pool)
-            .await
-        {
-            if !e.to_string().contains("duplicate column name") {
-                warn!("Failed to add sha1 column to mods table: {}", e);
-            }
+    }
+
+    /// Tunnels that are turned on
+    pub async fn get_server_tunnels(&self) -> Result<Vec<ServerTunnel>> {
+        let rows = sqlx::query("SELECT * FROM server_tunnels WHERE enabled = 1")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut tunnels = Vec::with_capacity(rows.len());
+        for row in &rows {
+            tunnels.push(self.server_tunnel_from_row(row).await);
         }
-        
-        info!("Database migration completed");
+        Ok(tunnels)
+    }
+
+    pub async fn save_server_tunnel(&self, tunnel: &ServerTunnel) -> Result<()> {
+        let token = self.stash_secret(&tunnel_secret_key(&tunnel.server_id), &tunnel.token).await?;
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO server_tunnels (server_id, provider, enabled, token, region, agent_path, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&tunnel.server_id)
+        .bind(&tunnel.provider)
+        .bind(tunnel.enabled)
+        .bind(token)
+        .bind(&tunnel.region)
+        .bind(&tunnel.agent_path)
+        .bind(tunnel.updated_at)
+        .execute(&self.pool)
+        .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_servers_name ON servers (name)")
+        Ok(())
+    }
+
+    pub async fn delete_server_tunnel(&self, server_id: &str) -> Result<()> {
+        self.stash_secret(&tunnel_secret_key(server_id), "").await?;
+        sqlx::query("DELETE FROM server_tunnels WHERE server_id = ?")
+            .bind(server_id)
             .execute(&self.pool)
             .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_servers_max_players ON servers (max_players)")
-            .execute(&self.pool)
+        Ok(())
+    }
+
+    // Node methods
+    async fn node_from_row(&self, row: &sqlx::sqlite::SqliteRow) -> Node {
+        let id: String = row.get("id");
+        let token = self.reveal_secret(&node_secret_key(&id), row.get("token")).await;
+        Node {
+            id,
+            name: row.get("name"),
+            url: row.get("url"),
+            token,
+            enabled: row.get("enabled"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        }
+    }
+
+    pub async fn get_nodes(&self) -> Result<Vec<Node>> {
+        let rows = sqlx::query("SELECT * FROM nodes ORDER BY name")
+            .fetch_all(&self.pool)
             .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_tasks_server_id ON tasks (server_id)")
-            .execute(&self.pool)
+        let mut nodes = Vec::with_capacity(rows.len());
+        for row in &rows {
+            nodes.push(self.node_from_row(row).await);
+        }
+        Ok(nodes)
+    }
+
+    pub async fn get_node(&self, id: &str) -> Result<Option<Node>> {
+        let row = sqlx::query("SELECT * FROM nodes WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
             .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_tasks_kind ON tasks (kind)")
+        match row {
+            Some(row) => Ok(Some(self.node_from_row(&row).await)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn save_node(&self, node: &Node) -> Result<()> {
+        let token = self.stash_secret(&node_secret_key(&node.id), &node.token).await?;
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO nodes (id, name, url, token, enabled, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&node.id)
+        .bind(&node.name)
+        .bind(&node.url)
+        .bind(token)
+        .bind(node.enabled)
+        .bind(node.created_at)
+        .bind(node.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_node(&self, id: &str) -> Result<bool> {
+        self.stash_secret(&node_secret_key(id), "").await?;
+        let result = sqlx::query("DELETE FROM nodes WHERE id = ?")
+            .bind(id)
             .execute(&self.pool)
             .await?;
+        Ok(result.rows_affected() > 0)
+    }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks (status)")
-            .execute(&self.pool)
+    // Freeze ticket methods
+    fn freeze_ticket_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<FreezeTicket> {
+        let stack: String = row.get("stack");
+        let x: Option<f64> = row.get("x");
+        let location = x.map(|x| FreezeLocation {
+            x,
+            y: row.get::<Option<f64>, _>("y").unwrap_or_default(),
+            z: row.get::<Option<f64>, _>("z").unwrap_or_default(),
+            dimension: row.get("dimension"),
+        });
+        Ok(FreezeTicket {
+            id: row.get("id"),
+            server_id: row.get("server_id"),
+            status: row.get("status"),
+            suspect_kind: row.get("suspect_kind"),
+            suspect_frame: row.get("suspect_frame"),
+            entity_type: row.get("entity_type"),
+            location,
+            stack: serde_json::from_str(&stack)?,
+            thread_dump: row.get("thread_dump"),
+            duration_ms: row.get::<Option<i64>, _>("duration_ms").map(|ms| ms as u64),
+            resolution: row.get("resolution"),
+            created_at: row.get("created_at"),
+            resolved_at: row.get("resolved_at"),
+        })
+    }
+
+    pub async fn save_freeze_ticket(&self, ticket: &FreezeTicket) -> Result<()> {
+        let location = ticket.location.as_ref();
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO freeze_tickets (
+                id, server_id, status, suspect_kind, suspect_frame, entity_type, dimension, x, y, z,
+                stack, thread_dump, duration_ms, resolution, created_at, resolved_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&ticket.id)
+        .bind(&ticket.server_id)
+        .bind(&ticket.status)
+        .bind(&ticket.suspect_kind)
+        .bind(&ticket.suspect_frame)
+        .bind(&ticket.entity_type)
+        .bind(location.and_then(|l| l.dimension.clone()))
+        .bind(location.map(|l| l.x))
+        .bind(location.map(|l| l.y))
+        .bind(location.map(|l| l.z))
+        .bind(serde_json::to_string(&ticket.stack)?)
+        .bind(&ticket.thread_dump)
+        .bind(ticket.duration_ms.map(|ms| ms as i64))
+        .bind(&ticket.resolution)
+        .bind(ticket.created_at)
+        .bind(ticket.resolved_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_freeze_ticket(&self, id: &str) -> Result<Option<FreezeTicket>> {
+        let row = sqlx::query("SELECT * FROM freeze_tickets WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
             .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_tasks_created_at ON tasks (created_at)")
-            .execute(&self.pool)
+        row.as_ref().map(Self::freeze_ticket_from_row).transpose()
+    }
+
+    /// Tickets of a server, newest first
+    pub async fn get_freeze_tickets(&self, server_id: &str) -> Result<Vec<FreezeTicket>> {
+        let rows = sqlx::query("SELECT * FROM freeze_tickets WHERE server_id = ? ORDER BY created_at DESC")
+            .bind(server_id)
+            .fetch_all(&self.pool)
             .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_mods_server_id ON mods (server_id)")
-            .execute(&self.pool)
+        rows.iter().map(Self::freeze_ticket_from_row).collect()
+    }
+
+    pub async fn count_open_freeze_tickets(&self, server_id: &str) -> Result<u32> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM freeze_tickets WHERE server_id = ? AND status = 'open'")
+            .bind(server_id)
+            .fetch_one(&self.pool)
             .await?;
+        Ok(count as u32)
+    }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_mods_provider ON mods (provider)")
-            .execute(&self.pool)
+    // Server group methods
+    async fn server_group_members(&self, group_id: &str) -> Result<Vec<String>> {
+        let members = sqlx::query_scalar("SELECT server_id FROM server_group_members WHERE group_id = ? ORDER BY position")
+            .bind(group_id)
+            .fetch_all(&self.pool)
             .await?;
+        Ok(members)
+    }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_mods_enabled ON mods (enabled)")
-            .execute(&self.pool)
+    async fn server_group_from_row(&self, row: &sqlx::sqlite::SqliteRow) -> Result<ServerGroup> {
+        let id: String = row.get("id");
+        Ok(ServerGroup {
+            server_ids: self.server_group_members(&id).await?,
+            id,
+            name: row.get("name"),
+            description: row.get("description"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    /// Create or update a group and replace its members
+    pub async fn save_server_group(&self, group: &ServerGroup) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO server_groups (id, name, description, created_at, updated_at) VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET name = excluded.name, description = excluded.description, updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&group.id)
+        .bind(&group.name)
+        .bind(&group.description)
+        .bind(group.created_at)
+        .bind(group.updated_at)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM server_group_members WHERE group_id = ?")
+            .bind(&group.id)
+            .execute(&mut *tx)
             .await?;
+        for (position, server_id) in group.server_ids.iter().enumerate() {
+            sqlx::query("INSERT INTO server_group_members (group_id, server_id, position) VALUES (?, ?, ?)")
+                .bind(&group.id)
+                .bind(server_id)
+                .bind(position as i64)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_backup_configs_server_id ON backup_configs (server_id)")
-            .execute(&self.pool)
+    /// A group by its id or, ignoring case, its name
+    pub async fn get_server_group(&self, id_or_name: &str) -> Result<Option<ServerGroup>> {
+        let row = sqlx::query("SELECT * FROM server_groups WHERE id = ? OR name = ? COLLATE NOCASE")
+            .bind(id_or_name)
+            .bind(id_or_name)
+            .fetch_optional(&self.pool)
             .await?;
+        match row {
+            Some(row) => Ok(Some(self.server_group_from_row(&row).await?)),
+            None => Ok(None),
+        }
+    }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_backup_records_server_id ON backup_records (server_id)")
-            .execute(&self.pool)
+    pub async fn get_server_groups(&self) -> Result<Vec<ServerGroup>> {
+        let rows = sqlx::query("SELECT * FROM server_groups ORDER BY name")
+            .fetch_all(&self.pool)
             .await?;
+        let mut groups = Vec::with_capacity(rows.len());
+        for row in &rows {
+            groups.push(self.server_group_from_row(row).await?);
+        }
+        Ok(groups)
+    }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_backup_records_created_at ON backup_records (created_at)")
+    pub async fn delete_server_group(&self, id: &str) -> Result<bool> {
+        sqlx::query("DELETE FROM server_group_members WHERE group_id = ?")
+            .bind(id)
             .execute(&self.pool)
             .await?;
-
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_event_logs_server_id ON event_logs (server_id)")
+        let result = sqlx::query("DELETE FROM server_groups WHERE id = ?")
+            .bind(id)
             .execute(&self.pool)
             .await?;
+        Ok(result.rows_affected() > 0)
+    }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_event_logs_created_at ON event_logs (created_at)")
-            .execute(&self.pool)
+    // Server process methods
+    pub async fn get_server_processes(&self) -> Result<Vec<ServerProcessRecord>> {
+        let rows = sqlx::query("SELECT * FROM server_processes")
+            .fetch_all(&self.pool)
             .await?;
 
-        // Create minecraft_versions table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS minecraft_versions (
-                id TEXT PRIMARY KEY,
-                release_type TEXT NOT NULL,
-                release_date DATETIME NOT NULL,
-                protocol_version INTEGER NOT NULL,
-                data_version INTEGER NOT NULL,
-                is_supported BOOLEAN DEFAULT TRUE,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+        Ok(rows.iter().map(|row| ServerProcessRecord {
+            server_id: row.get("server_id"),
+            pid: row.get("pid"),
+            process_started_at: row.get("process_started_at"),
+            java_path: row.get("java_path"),
+            server_directory: row.get("server_directory"),
+            command_line: row.get("command_line"),
+            started_at: row.get("started_at"),
+        }).collect())
+    }
 
-        // Create loader_versions table
+    pub async fn save_server_process(&self, record: &ServerProcessRecord) -> Result<()> {
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS loader_versions (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                loader_type TEXT NOT NULL,
-                version TEXT NOT NULL,
-                minecraft_version TEXT NOT NULL,
-                download_url TEXT NOT NULL,
-                file_size INTEGER NOT NULL,
-                sha256 TEXT NOT NULL,
-                is_stable BOOLEAN DEFAULT TRUE,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (minecraft_version) REFERENCES minecraft_versions(id)
-            )
+            INSERT OR REPLACE INTO server_processes (server_id, pid, process_started_at, java_path, server_directory, command_line, started_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
             "#,
         )
+        .bind(&record.server_id)
+        .bind(record.pid)
+        .bind(record.process_started_at)
+        .bind(&record.java_path)
+        .bind(&record.server_directory)
+        .bind(&record.command_line)
+        .bind(record.started_at)
         .execute(&self.pool)
         .await?;
 
-        // Create mod_metadata table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS mod_metadata (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT NOT NULL,
-                author TEXT NOT NULL,
-                provider TEXT NOT NULL,
-                project_id TEXT NOT NULL,
-                slug TEXT,
-                category TEXT NOT NULL,
-                side TEXT NOT NULL,
-                website_url TEXT,
-                source_url TEXT,
-                issues_url TEXT,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                UNIQUE(provider, project_id)
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+        Ok(())
+    }
 
-        // Create installed_mods table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS installed_mods (
-                id TEXT PRIMARY KEY,
-                server_id TEXT NOT NULL,
-                mod_metadata_id TEXT NOT NULL,
-                mod_version_id TEXT NOT NULL,
-                file_path TEXT NOT NULL,
-                enabled BOOLEAN NOT NULL DEFAULT 1,
-                installed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE,
-                FOREIGN KEY (mod_metadata_id) REFERENCES mod_metadata(id) ON DELETE CASCADE,
-                FOREIGN KEY (mod_version_id) REFERENCES mod_versions(id) ON DELETE CASCADE,
-                UNIQUE(server_id, mod_metadata_id)
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+    // Startup rule methods
+    fn startup_rule_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<StartupRule> {
+        let depends_on: String = row.get("depends_on");
+        Ok(StartupRule {
+            server_id: row.get("server_id"),
+            start_order: row.get("start_order"),
+            delay_seconds: row.get("delay_seconds"),
+            depends_on: serde_json::from_str(&depends_on)?,
+            ready_timeout_seconds: row.get("ready_timeout_seconds"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    pub async fn get_startup_rule(&self, server_id: &str) -> Result<Option<StartupRule>> {
+        let row = sqlx::query("SELECT * FROM server_startup WHERE server_id = ?")
+            .bind(server_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::startup_rule_from_row).transpose()
+    }
+
+    pub async fn get_startup_rules(&self) -> Result<Vec<StartupRule>> {
+        let rows = sqlx::query("SELECT * FROM server_startup")
+            .fetch_all(&self.pool)
+            .await?;
 
-        // Create mod_versions table
+        rows.iter().map(Self::startup_rule_from_row).collect()
+    }
+
+    pub async fn save_startup_rule(&self, rule: &StartupRule) -> Result<()> {
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS mod_versions (
-                id TEXT PRIMARY KEY,
-                mod_metadata_id TEXT NOT NULL,
-                version TEXT NOT NULL,
-                minecraft_version TEXT NOT NULL,
-                loader TEXT NOT NULL,
-                filename TEXT NOT NULL,
-                file_size INTEGER NOT NULL,
-                sha1 TEXT,
-                sha512 TEXT,
-                download_url TEXT NOT NULL,
-                release_type TEXT NOT NULL,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (mod_metadata_id) REFERENCES mod_metadata(id) ON DELETE CASCADE,
-                UNIQUE(mod_metadata_id, version, minecraft_version, loader)
-            )
+            INSERT OR REPLACE INTO server_startup (server_id, start_order, delay_seconds, depends_on, ready_timeout_seconds, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
             "#,
         )
+        .bind(&rule.server_id)
+        .bind(rule.start_order)
+        .bind(rule.delay_seconds)
+        .bind(serde_json::to_string(&rule.depends_on)?)
+        .bind(rule.ready_timeout_seconds)
+        .bind(rule.updated_at)
         .execute(&self.pool)
         .await?;
 
-        // Create mod_dependencies table
+        Ok(())
+    }
+
+    pub async fn delete_server_process(&self, server_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM server_processes WHERE server_id = ?")
+            .bind(server_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Config revision methods
+    fn config_revision_from_row(row: &sqlx::sqlite::SqliteRow) -> ConfigRevision {
+        ConfigRevision {
+            id: row.get("id"),
+            server_id: row.get("server_id"),
+            target: row.get("target"),
+            content: row.get("content"),
+            previous_content: row.get("previous_content"),
+            diff: row.get("diff"),
+            author: row.get("author"),
+            message: row.get("message"),
+            created_at: row.get("created_at"),
+        }
+    }
+
+    pub async fn create_config_revision(&self, revision: &ConfigRevision) -> Result<()> {
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS mod_dependencies (
-                id TEXT PRIMARY KEY,
-                mod_metadata_id TEXT NOT NULL,
-                dependency_mod_id TEXT NOT NULL,
-                version_range TEXT NOT NULL,
-                required BOOLEAN NOT NULL DEFAULT 1,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (mod_metadata_id) REFERENCES mod_metadata(id) ON DELETE CASCADE,
-                FOREIGN KEY (dependency_mod_id) REFERENCES mod_metadata(id) ON DELETE CASCADE
-            )
+            INSERT INTO config_revisions (
+                id, server_id, target, content, previous_content, diff, author, message, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
+        .bind(&revision.id)
+        .bind(&revision.server_id)
+        .bind(&revision.target)
+        .bind(&revision.content)
+        .bind(&revision.previous_content)
+        .bind(&revision.diff)
+        .bind(&revision.author)
+        .bind(&revision.message)
+        .bind(revision.created_at)
         .execute(&self.pool)
         .await?;
 
-        // Create mod_conflicts table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS mod_conflicts (
-                id TEXT PRIMARY KEY,
-                mod_metadata_id TEXT NOT NULL,
-                conflicting_mod_id TEXT NOT NULL,
-                reason TEXT NOT NULL,
-                severity TEXT NOT NULL,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (mod_metadata_id) REFERENCES mod_metadata(id) ON DELETE CASCADE,
-                FOREIGN KEY (conflicting_mod_id) REFERENCES mod_metadata(id) ON DELETE CASCADE
-            )
-            "#,
+        info!("Recorded {} revision {} for server {}", revision.target, revision.id, revision.server_id);
+        Ok(())
+    }
+
+    pub async fn get_config_revision(&self, id: &str) -> Result<Option<ConfigRevision>> {
+        let row = sqlx::query("SELECT * FROM config_revisions WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(Self::config_revision_from_row))
+    }
+
+    /// A server's revisions, newest first, optionally of one target only
+    pub async fn get_config_revisions(&self, server_id: &str, target: Option<&str>) -> Result<Vec<ConfigRevision>> {
+        let rows = sqlx::query(
+            "SELECT * FROM config_revisions WHERE server_id = ? AND (? IS NULL OR target = ?) ORDER BY created_at DESC",
         )
-        .execute(&self.pool)
+        .bind(server_id)
+        .bind(target)
+        .bind(target)
+        .fetch_all(&self.pool)
         .await?;
 
-        // Create server_logs table
+        Ok(rows.iter().map(Self::config_revision_from_row).collect())
+    }
+
+    // Alert rule and channel methods
+    fn alert_rule_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<AlertRule> {
+        let condition: String = row.get("condition");
+        let channel_ids: serde_json::Value = row.get("channel_ids");
+        Ok(AlertRule {
+            id: row.get("id"),
+            name: row.get("name"),
+            condition: crate::alerts::AlertCondition::parse(&condition)
+                .ok_or_else(|| anyhow::anyhow!("Unknown alert condition: {}", condition))?,
+            server_id: row.get("server_id"),
+            threshold: row.get("threshold"),
+            cooldown_minutes: row.get::<i64, _>("cooldown_minutes") as u32,
+            channel_ids: serde_json::from_value(channel_ids)?,
+            enabled: row.get("enabled"),
+            last_triggered_at: row.get("last_triggered_at"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    pub async fn create_alert_rule(&self, rule: &AlertRule) -> Result<()> {
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS server_logs (
-                id TEXT PRIMARY KEY,
-                server_id TEXT NOT NULL,
-                level TEXT NOT NULL,
-                message TEXT NOT NULL,
-                component TEXT,
-                timestamp DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (server_id) REFERENCES servers (id) ON DELETE CASCADE
-            )
+            INSERT INTO alert_rules (
+                id, name, condition, server_id, threshold, cooldown_minutes, channel_ids,
+                enabled, last_triggered_at, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
+        .bind(&rule.id)
+        .bind(&rule.name)
+        .bind(rule.condition.as_str())
+        .bind(&rule.server_id)
+        .bind(rule.threshold)
+        .bind(rule.cooldown_minutes as i64)
+        .bind(serde_json::to_value(&rule.channel_ids)?)
+        .bind(rule.enabled)
+        .bind(rule.last_triggered_at)
+        .bind(rule.created_at)
+        .bind(rule.updated_at)
         .execute(&self.pool)
         .await?;
 
-        // Create modpacks table
+        info!("Created alert rule: {}", rule.id);
+        Ok(())
+    }
+
+    pub async fn get_alert_rule(&self, id: &str) -> Result<Option<AlertRule>> {
+        let row = sqlx::query("SELECT * FROM alert_rules WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::alert_rule_from_row).transpose()
+    }
+
+    pub async fn get_alert_rules(&self) -> Result<Vec<AlertRule>> {
+        let rows = sqlx::query("SELECT * FROM alert_rules ORDER BY created_at")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(Self::alert_rule_from_row).collect()
+    }
+
+    pub async fn update_alert_rule(&self, rule: &AlertRule) -> Result<()> {
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS modpacks (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT,
-                minecraft_version TEXT NOT NULL,
-                loader TEXT NOT NULL,
-                client_mods TEXT NOT NULL,
-                server_mods TEXT NOT NULL,
-                config TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
+            UPDATE alert_rules SET
+                name = ?, condition = ?, server_id = ?, threshold = ?, cooldown_minutes = ?,
+                channel_ids = ?, enabled = ?, last_triggered_at = ?, updated_at = ?
+            WHERE id = ?
             "#,
         )
+        .bind(&rule.name)
+        .bind(rule.condition.as_str())
+        .bind(&rule.server_id)
+        .bind(rule.threshold)
+        .bind(rule.cooldown_minutes as i64)
+        .bind(serde_json::to_value(&rule.channel_ids)?)
+        .bind(rule.enabled)
+        .bind(rule.last_triggered_at)
+        .bind(rule.updated_at)
+        .bind(&rule.id)
         .execute(&self.pool)
         .await?;
 
-        // Create server_mods table
+        Ok(())
+    }
+
+    pub async fn delete_alert_rule(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM alert_rules WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        info!("Deleted alert rule: {}", id);
+        Ok(())
+    }
+
+    fn alert_channel_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<AlertChannel> {
+        let config: serde_json::Value = row.get("config");
+        Ok(AlertChannel {
+            id: row.get("id"),
+            name: row.get("name"),
+            target: serde_json::from_value(config)?,
+            enabled: row.get("enabled"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    pub async fn create_alert_channel(&self, channel: &AlertChannel) -> Result<()> {
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS server_mods (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                server_id TEXT NOT NULL,
-                mod_id TEXT NOT NULL,
-                mod_version_id INTEGER NOT NULL,
-                enabled BOOLEAN DEFAULT TRUE,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE,
-                FOREIGN KEY (mod_id) REFERENCES mods(id),
-                FOREIGN KEY (mod_version_id) REFERENCES mod_versions(id)
-            )
+            INSERT INTO alert_channels (id, name, kind, config, enabled, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
             "#,
         )
+        .bind(&channel.id)
+        .bind(&channel.name)
+        .bind(channel.target.kind())
+        .bind(serde_json::to_value(&channel.target)?)
+        .bind(channel.enabled)
+        .bind(channel.created_at)
+        .bind(channel.updated_at)
         .execute(&self.pool)
         .await?;
 
-        // Create additional indexes for modpack system
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_mods_category ON mods (category)")
-            .execute(&self.pool)
-            .await?;
-
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_mods_side ON mods (side)")
-            .execute(&self.pool)
-            .await?;
-
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_mods_source ON mods (source)")
-            .execute(&self.pool)
-            .await?;
+        info!("Created alert channel: {}", channel.id);
+        Ok(())
+    }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_mod_versions_mod_metadata_id ON mod_versions (mod_metadata_id)")
-            .execute(&self.pool)
+    pub async fn get_alert_channel(&self, id: &str) -> Result<Option<AlertChannel>> {
+        let row = sqlx::query("SELECT * FROM alert_channels WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
             .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_modpacks_minecraft_version ON modpacks (minecraft_version)")
-            .execute(&self.pool)
-            .await?;
+        row.as_ref().map(Self::alert_channel_from_row).transpose()
+    }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_modpacks_loader ON modpacks (loader)")
-            .execute(&self.pool)
+    pub async fn get_alert_channels(&self) -> Result<Vec<AlertChannel>> {
+        let rows = sqlx::query("SELECT * FROM alert_channels ORDER BY created_at")
+            .fetch_all(&self.pool)
             .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_server_mods_server_id ON server_mods (server_id)")
-            .execute(&self.pool)
-            .await?;
+        rows.iter().map(Self::alert_channel_from_row).collect()
+    }
 
-        // Populate default Minecraft versions
-        self.populate_default_minecraft_versions().await?;
-        
-        // Populate default loader versions
-        self.populate_default_loader_versions().await?;
+    pub async fn update_alert_channel(&self, channel: &AlertChannel) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE alert_channels SET name = ?, kind = ?, config = ?, enabled = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&channel.name)
+        .bind(channel.target.kind())
+        .bind(serde_json::to_value(&channel.target)?)
+        .bind(channel.enabled)
+        .bind(channel.updated_at)
+        .bind(&channel.id)
+        .execute(&self.pool)
+        .await?;
 
-        info!("Database migrations completed successfully");
         Ok(())
     }
 
-    async fn populate_default_minecraft_versions(&self) -> Result<()> {
-        // Insert some common Minecraft versions
-        let versions = vec![
-            ("1.21.1", "release", "2024-08-20T00:00:00Z", 767, 15, true),
-            ("1.21", "release", "2024-06-13T00:00:00Z", 766, 15, true),
-            ("1.20.6", "release", "2024-05-14T00:00:00Z", 765, 15, true),
-            ("1.20.4", "release", "2024-01-15T00:00:00Z", 764, 15, true),
-            ("1.20.1", "release", "2023-06-12T00:00:00Z", 763, 15, true),
-            ("1.19.4", "release", "2023-03-14T00:00:00Z", 762, 15, true),
-            ("1.18.2", "release", "2022-02-28T00:00:00Z", 758, 15, true),
-            ("1.17.1", "release", "2021-07-06T00:00:00Z", 756, 15, true),
-        ];
-
-        for (version, release_type, release_date, protocol_version, data_version, is_supported) in versions {
-            sqlx::query(
-                r#"
-                INSERT OR IGNORE INTO minecraft_versions 
-                (id, release_type, release_date, protocol_version, data_version, is_supported)
-                VALUES (?, ?, ?, ?, ?, ?)
-                "#
-            )
-            .bind(version)
-            .bind(release_type)
-            .bind(release_date)
-            .bind(protocol_version)
-            .bind(data_version)
-            .bind(is_supported)
+    pub async fn delete_alert_channel(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM alert_channels WHERE id = ?")
+            .bind(id)
             .execute(&self.pool)
             .await?;
-        }
 
-        info!("Populated default Minecraft versions");
+        info!("Deleted alert channel: {}", id);
         Ok(())
     }
 
-    async fn populate_default_loader_versions(&self) -> Result<()> {
-        // Insert some common loader versions for Forge and Fabric
-        let loaders = vec![
-            ("forge", "47.4.0", "1.21.1", "https://maven.minecraftforge.net/net/minecraftforge/forge/1.21.1-47.4.0/forge-1.21.1-47.4.0-installer.jar", 12345678, "sha256hash1", true),
-            ("forge", "47.3.0", "1.21", "https://maven.minecraftforge.net/net/minecraftforge/forge/1.21-47.3.0/forge-1.21-47.3.0-installer.jar", 12345678, "sha256hash2", true),
-            ("fabric", "0.15.11", "1.21.1", "https://maven.fabricmc.net/net/fabricmc/fabric-installer/0.15.11/fabric-installer-0.15.11.jar", 8765432, "sha256hash3", true),
-            ("fabric", "0.15.10", "1.21", "https://maven.fabricmc.net/net/fabricmc/fabric-installer/0.15.10/fabric-installer-0.15.10.jar", 8765432, "sha256hash4", true),
-            ("quilt", "0.8.0", "1.21.1", "https://maven.quiltmc.org/repository/release/org/quiltmc/quilt-installer/0.8.0/quilt-installer-0.8.0.jar", 5432109, "sha256hash5", true),
-        ];
-
-        for (loader_type, version, minecraft_version, download_url, file_size, sha256, is_stable) in loaders {
-            sqlx::query(
-                r#"
-                INSERT OR IGNORE INTO loader_versions 
-                (loader_type, version, minecraft_version, download_url, file_size, sha256, is_stable)
-                VALUES (?, ?, ?, ?, ?, ?, ?)
-                "#
-            )
-            .bind(loader_type)
-            .bind(version)
-            .bind(minecraft_version)
-            .bind(download_url)
-            .bind(file_size)
-            .bind(sha256)
-            .bind(is_stable)
-            .execute(&self.pool)
-            .await?;
-        }
-
-        info!("Populated default loader versions");
-        Ok(())
+    // Discord methods
+    /// The Discord settings, or the defaults before any are saved
+    pub async fn get_discord_settings(&self) -> Result<DiscordSettings> {
+        let Some(row) = sqlx::query("SELECT * FROM discord_settings WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?
+        else {
+            return Ok(DiscordSettings::default());
+        };
+        let control_role_ids: String = row.get("control_role_ids");
+        Ok(DiscordSettings {
+            enabled: row.get("enabled"),
+            application_id: row.get("application_id"),
+            public_key: row.get("public_key"),
+            bot_token: self.reveal_secret(DISCORD_SECRET_KEY, row.get("bot_token")).await,
+            guild_id: row.get("guild_id"),
+            status_channel_id: row.get("status_channel_id"),
+            player_channel_id: row.get("player_channel_id"),
+            alert_channel_id: row.get("alert_channel_id"),
+            control_role_ids: serde_json::from_str(&control_role_ids)?,
+            status_message_id: row.get("status_message_id"),
+            updated_at: row.get("updated_at"),
+        })
     }
 
-    // Server configuration methods
-    pub async fn create_server(&self, config: &ServerConfig) -> Result<()> {
+    pub async fn save_discord_settings(&self, settings: &DiscordSettings) -> Result<()> {
+        let bot_token = self.stash_secret(DISCORD_SECRET_KEY, &settings.bot_token).await?;
         sqlx::query(
             r#"
-            INSERT INTO servers (
-                id, name, minecraft_version, loader, loader_version, port, rcon_port, query_port,
-                max_players, memory, java_args, server_args, auto_start, auto_restart,
-                world_name, difficulty, gamemode, pvp, online_mode, whitelist,
-                enable_command_block, view_distance, simulation_distance, motd,
-                host, java_path, jvm_args, server_jar, server_directory, rcon_password,
-                created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT OR REPLACE INTO discord_settings (
+                id, enabled, application_id, public_key, bot_token, guild_id, status_channel_id,
+                player_channel_id, alert_channel_id, control_role_ids, status_message_id, updated_at
+            ) VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
-        .bind(&config.id)
-        .bind(&config.name)
-        .bind(&config.minecraft_version)
-        .bind(&config.loader)
-        .bind(&config.loader_version)
-        .bind(config.port)
-        .bind(config.rcon_port)
-        .bind(config.query_port)
-        .bind(config.max_players)
-        .bind(config.memory)
-        .bind(&config.java_args)
-        .bind(&config.server_args)
-        .bind(config.auto_start)
-        .bind(config.auto_restart)
-        .bind(&config.world_name)
-        .bind(&config.difficulty)
-        .bind(&config.gamemode)
-        .bind(config.pvp)
-        .bind(config.online_mode)
-        .bind(config.whitelist)
-        .bind(config.enable_command_block)
-        .bind(config.view_distance)
-        .bind(config.simulation_distance)
-        .bind(&config.motd)
-        .bind(&config.host)
-        .bind(&config.java_path)
-        .bind(&config.jvm_args)
-        .bind(&config.server_jar)
-        .bind(&config.server_directory)
-        .bind(&config.rcon_password)
-        .bind(config.created_at)
-        .bind(config.updated_at)
+        .bind(settings.enabled)
+        .bind(&settings.application_id)
+        .bind(&settings.public_key)
+        .bind(bot_token)
+        .bind(&settings.guild_id)
+        .bind(&settings.status_channel_id)
+        .bind(&settings.player_channel_id)
+        .bind(&settings.alert_channel_id)
+        .bind(serde_json::to_string(&settings.control_role_ids)?)
+        .bind(&settings.status_message_id)
+        .bind(settings.updated_at)
         .execute(&self.pool)
         .await?;
 
-        info!("Created server configuration: {}", config.id);
         Ok(())
     }
 
-    pub async fn get_server(&self, id: &str) -> Result<Option<ServerConfig>> {
-        let row = sqlx::query(
-            r#"
-            SELECT id, name, minecraft_version, loader, loader_version, port, rcon_port, query_port,
-                   max_players, memory, java_args, server_args, auto_start, auto_restart,
-                   world_name, difficulty, gamemode, pvp, online_mode, whitelist,
-                   enable_command_block, view_distance, simulation_distance, motd,
-                   host, java_path, jvm_args, server_jar, rcon_password,
-                   created_at, updated_at
-            FROM servers WHERE id = ?
-            "#,
-        )
-        .bind(id)
-        .fetch_optional(&self.pool)
-        .await?;
+    /// Remember the status message without touching the other settings
+    pub async fn set_discord_status_message(&self, message_id: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE discord_settings SET status_message_id = ? WHERE id = 1")
+            .bind(message_id)
+            .execute(&self.pool)
+            .await?;
 
-        if let Some(row) = row {
-            Ok(Some(ServerConfig {
-                id: row.get("id"),
-                name: row.get("name"),
-                minecraft_version: row.get("minecraft_version"),
-                loader: row.get("loader"),
-                loader_version: row.get("loader_version"),
-                port: row.get("port"),
-                rcon_port: row.get("rcon_port"),
-                query_port: row.get("query_port"),
-                max_players: row.get("max_players"),
-                memory: row.get("memory"),
-                java_args: row.get("java_args"),
-                server_args: row.get("server_args"),
-                auto_start: row.get("auto_start"),
-                auto_restart: row.get("auto_restart"),
-                world_name: row.get("world_name"),
-                difficulty: row.get("difficulty"),
-                gamemode: row.get("gamemode"),
-                pvp: row.get("pvp"),
-                online_mode: row.get("online_mode"),
-                whitelist: row.get("whitelist"),
-                enable_command_block: row.get("enable_command_block"),
-                view_distance: row.get("view_distance"),
-                simulation_distance: row.get("simulation_distance"),
-                motd: row.get("motd"),
-                host: row.get("host"),
-                java_path: row.get("java_path"),
-                jvm_args: row.get("jvm_args"),
-                server_jar: row.get("server_jar"),
-                server_directory: row.get("server_directory"),
-                rcon_password: row.get("rcon_password"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            }))
-        } else {
-            Ok(None)
+        Ok(())
+    }
+
+    // Chat bridge methods
+    fn chat_bridge_from_row(row: &sqlx::sqlite::SqliteRow) -> ChatBridge {
+        ChatBridge {
+            server_id: row.get("server_id"),
+            enabled: row.get("enabled"),
+            discord_channel_id: row.get("discord_channel_id"),
+            webhook_url: row.get("webhook_url"),
+            relay_inbound: row.get("relay_inbound"),
+            outbound_template: row.get("outbound_template"),
+            inbound_template: row.get("inbound_template"),
+            updated_at: row.get("updated_at"),
         }
     }
 
-    pub async fn get_all_servers(&self) -> Result<Vec<ServerConfig>> {
-        let rows = sqlx::query(
+    pub async fn get_chat_bridge(&self, server_id: &str) -> Result<Option<ChatBridge>> {
+        let row = sqlx::query("SELECT * FROM chat_bridges WHERE server_id = ?")
+            .bind(server_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(Self::chat_bridge_from_row))
+    }
+
+    pub async fn get_enabled_chat_bridges(&self) -> Result<Vec<ChatBridge>> {
+        let rows = sqlx::query("SELECT * FROM chat_bridges WHERE enabled = 1")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(Self::chat_bridge_from_row).collect())
+    }
+
+    pub async fn save_chat_bridge(&self, bridge: &ChatBridge) -> Result<()> {
+        sqlx::query(
             r#"
-            SELECT id, name, minecraft_version, loader, loader_version, port, rcon_port, query_port,
-                   max_players, memory, java_args, server_args, auto_start, auto_restart,
-                   world_name, difficulty, gamemode, pvp, online_mode, whitelist,
-                   enable_command_block, view_distance, simulation_distance, motd,
-                   host, java_path, jvm_args, server_jar, rcon_password,
-                   created_at, updated_at
-            FROM servers ORDER BY name
+            INSERT OR REPLACE INTO chat_bridges (
+                server_id, enabled, discord_channel_id, webhook_url, relay_inbound,
+                outbound_template, inbound_template, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
-        .fetch_all(&self.pool)
+        .bind(&bridge.server_id)
+        .bind(bridge.enabled)
+        .bind(&bridge.discord_channel_id)
+        .bind(&bridge.webhook_url)
+        .bind(bridge.relay_inbound)
+        .bind(&bridge.outbound_template)
+        .bind(&bridge.inbound_template)
+        .bind(bridge.updated_at)
+        .execute(&self.pool)
         .await?;
 
-        let servers = rows
-            .into_iter()
-            .map(|row| ServerConfig {
-                id: row.get("id"),
-                name: row.get("name"),
-                minecraft_version: row.get("minecraft_version"),
-                loader: row.get("loader"),
-                loader_version: row.get("loader_version"),
-                port: row.get("port"),
-                rcon_port: row.get("rcon_port"),
-                query_port: row.get("query_port"),
-                max_players: row.get("max_players"),
-                memory: row.get("memory"),
-                java_args: row.get("java_args"),
-                server_args: row.get("server_args"),
-                auto_start: row.get("auto_start"),
-                auto_restart: row.get("auto_restart"),
-                world_name: row.get("world_name"),
-                difficulty: row.get("difficulty"),
-                gamemode: row.get("gamemode"),
-                pvp: row.get("pvp"),
-                online_mode: row.get("online_mode"),
-                whitelist: row.get("whitelist"),
-                enable_command_block: row.get("enable_command_block"),
-                view_distance: row.get("view_distance"),
-                simulation_distance: row.get("simulation_distance"),
-                motd: row.get("motd"),
-                host: row.get("host"),
-                java_path: row.get("java_path"),
-                jvm_args: row.get("jvm_args"),
-                server_jar: row.get("server_jar"),
-                server_directory: row.get("server_directory"),
-                rcon_password: row.get("rcon_password"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            })
-            .collect();
+        Ok(())
+    }
+
+    // World border methods
+    fn world_border_from_row(row: &sqlx::sqlite::SqliteRow) -> WorldBorderSchedule {
+        WorldBorderSchedule {
+            server_id: row.get("server_id"),
+            enabled: row.get("enabled"),
+            center_x: row.get("center_x"),
+            center_z: row.get("center_z"),
+            initial_size: row.get::<i64, _>("initial_size") as u32,
+            growth: row.get::<i64, _>("growth") as u32,
+            max_size: row.get::<Option<i64>, _>("max_size").map(|size| size as u32),
+            cron_expression: row.get("cron_expression"),
+            transition_seconds: row.get::<i64, _>("transition_seconds") as u32,
+            current_size: row.get::<Option<i64>, _>("current_size").map(|size| size as u32),
+            last_grown_at: row.get("last_grown_at"),
+            last_error: row.get("last_error"),
+            pregen_ahead: row.get("pregen_ahead"),
+            pregen_margin: row.get::<i64, _>("pregen_margin") as u32,
+            pregen_job_id: row.get("pregen_job_id"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        }
+    }
+
+    pub async fn get_world_border_schedule(&self, server_id: &str) -> Result<Option<WorldBorderSchedule>> {
+        let row = sqlx::query("SELECT * FROM world_border_schedules WHERE server_id = ?")
+            .bind(server_id)
+            .fetch_optional(&self.pool)
+            .await?;
 
-        Ok(servers)
+        Ok(row.as_ref().map(Self::world_border_from_row))
     }
 
-    pub async fn update_server(&self, config: &ServerConfig) -> Result<()> {
+    pub async fn get_enabled_world_border_schedules(&self) -> Result<Vec<WorldBorderSchedule>> {
+        let rows = sqlx::query("SELECT * FROM world_border_schedules WHERE enabled = 1")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(Self::world_border_from_row).collect())
+    }
+
+    pub async fn save_world_border_schedule(&self, schedule: &WorldBorderSchedule) -> Result<()> {
         sqlx::query(
             r#"
-            UPDATE servers SET
-                name = ?, host = ?, port = ?, rcon_port = ?, rcon_password = ?,
-                java_path = ?, server_jar = ?, jvm_args = ?, server_args = ?,
-                auto_start = ?, auto_restart = ?, max_players = ?,
-                updated_at = ?
-            WHERE id = ?
+            INSERT OR REPLACE INTO world_border_schedules (
+                server_id, enabled, center_x, center_z, initial_size, growth, max_size,
+                cron_expression, transition_seconds, current_size, last_grown_at, last_error,
+                pregen_ahead, pregen_margin, pregen_job_id, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
-        .bind(&config.name)
-        .bind(&config.host)
-        .bind(config.port)
-        .bind(config.rcon_port)
-        .bind(&config.rcon_password)
-        .bind(&config.java_path)
-        .bind(&config.server_jar)
-        .bind(&config.jvm_args)
-        .bind(&config.server_args)
-        .bind(config.auto_start)
-        .bind(config.auto_restart)
-        .bind(config.max_players)
-        // pregeneration_policy field removed
-        .bind(config.updated_at)
-        .bind(&config.id)
+        .bind(&schedule.server_id)
+        .bind(schedule.enabled)
+        .bind(schedule.center_x)
+        .bind(schedule.center_z)
+        .bind(schedule.initial_size as i64)
+        .bind(schedule.growth as i64)
+        .bind(schedule.max_size.map(|size| size as i64))
+        .bind(&schedule.cron_expression)
+        .bind(schedule.transition_seconds as i64)
+        .bind(schedule.current_size.map(|size| size as i64))
+        .bind(schedule.last_grown_at)
+        .bind(&schedule.last_error)
+        .bind(schedule.pregen_ahead)
+        .bind(schedule.pregen_margin as i64)
+        .bind(&schedule.pregen_job_id)
+        .bind(schedule.created_at)
+        .bind(schedule.updated_at)
         .execute(&self.pool)
         .await?;
 
-        info!("Updated server configuration: {}", config.id);
         Ok(())
     }
 
-    pub async fn delete_server(&self, id: &str) -> Result<()> {
-        // Delete related data first
-        sqlx::query("DELETE FROM server_logs WHERE server_id = ?")
-            .bind(id)
+    pub async fn delete_world_border_schedule(&self, server_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM world_border_schedules WHERE server_id = ?")
+            .bind(server_id)
             .execute(&self.pool)
             .await?;
 
-        sqlx::query("DELETE FROM server_metrics WHERE server_id = ?")
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Webhook methods
+    fn webhook_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Webhook> {
+        let events: serde_json::Value = row.get("events");
+        Ok(Webhook {
+            id: row.get("id"),
+            name: row.get("name"),
+            url: row.get("url"),
+            secret: row.get("secret"),
+            events: serde_json::from_value(events)?,
+            enabled: row.get("enabled"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    pub async fn create_webhook(&self, webhook: &Webhook) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO webhooks (id, name, url, secret, events, enabled, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&webhook.id)
+        .bind(&webhook.name)
+        .bind(&webhook.url)
+        .bind(&webhook.secret)
+        .bind(serde_json::to_value(&webhook.events)?)
+        .bind(webhook.enabled)
+        .bind(webhook.created_at)
+        .bind(webhook.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Created webhook: {}", webhook.id);
+        Ok(())
+    }
+
+    pub async fn get_webhook(&self, id: &str) -> Result<Option<Webhook>> {
+        let row = sqlx::query("SELECT * FROM webhooks WHERE id = ?")
             .bind(id)
-            .execute(&self.pool)
+            .fetch_optional(&self.pool)
             .await?;
 
-        sqlx::query("DELETE FROM server_mods WHERE server_id = ?")
-            .bind(id)
-            .execute(&self.pool)
+        row.as_ref().map(Self::webhook_from_row).transpose()
+    }
+
+    pub async fn get_webhooks(&self) -> Result<Vec<Webhook>> {
+        let rows = sqlx::query("SELECT * FROM webhooks ORDER BY created_at")
+            .fetch_all(&self.pool)
             .await?;
 
-        // Delete the server itself
-        sqlx::query("DELETE FROM servers WHERE id = ?")
+        rows.iter().map(Self::webhook_from_row).collect()
+    }
+
+    pub async fn update_webhook(&self, webhook: &Webhook) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE webhooks SET name = ?, url = ?, secret = ?, events = ?, enabled = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&webhook.name)
+        .bind(&webhook.url)
+        .bind(&webhook.secret)
+        .bind(serde_json::to_value(&webhook.events)?)
+        .bind(webhook.enabled)
+        .bind(webhook.updated_at)
+        .bind(&webhook.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete a webhook and its delivery log
+    pub async fn delete_webhook(&self, id: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM webhook_deliveries WHERE webhook_id = ?")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM webhooks WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
 
-        info!("Deleted server configuration and all related data: {}", id);
+        info!("Deleted webhook: {}", id);
         Ok(())
     }
 
-    // Settings methods
-    pub async fn get_settings(&self) -> Result<Option<Settings>> {
-        let row = sqlx::query(
+    fn webhook_delivery_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<WebhookDelivery> {
+        let payload: String = row.get("payload");
+        let status: String = row.get("status");
+        Ok(WebhookDelivery {
+            id: row.get("id"),
+            webhook_id: row.get("webhook_id"),
+            event: row.get("event"),
+            payload: serde_json::from_str(&payload)?,
+            status: crate::webhook::DeliveryStatus::parse(&status)
+                .ok_or_else(|| anyhow::anyhow!("Unknown delivery status: {}", status))?,
+            attempts: row.get::<i64, _>("attempts") as u32,
+            next_attempt_at: row.get("next_attempt_at"),
+            response_status: row.get::<Option<i64>, _>("response_status").map(|status| status as u16),
+            error: row.get("error"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    pub async fn create_webhook_delivery(&self, delivery: &WebhookDelivery) -> Result<()> {
+        sqlx::query(
             r#"
-            SELECT id, cf_api_key, modrinth_token, java_path, default_ram_mb,
-                   data_dir, telemetry_opt_in, created_at, updated_at
-            FROM settings LIMIT 1
+            INSERT INTO webhook_deliveries (
+                id, webhook_id, event, payload, status, attempts, next_attempt_at,
+                response_status, error, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
-        .fetch_optional(&self.pool)
+        .bind(&delivery.id)
+        .bind(&delivery.webhook_id)
+        .bind(&delivery.event)
+        .bind(delivery.payload.to_string())
+        .bind(delivery.status.as_str())
+        .bind(delivery.attempts as i64)
+        .bind(delivery.next_attempt_at)
+        .bind(delivery.response_status.map(|status| status as i64))
+        .bind(&delivery.error)
+        .bind(delivery.created_at)
+        .bind(delivery.updated_at)
+        .execute(&self.pool)
         .await?;
 
-        if let Some(row) = row {
-            Ok(Some(Settings {
-                id: row.get("id"),
-                cf_api_key: row.get("cf_api_key"),
-                modrinth_token: row.get("modrinth_token"),
-                java_path: row.get("java_path"),
-                default_ram_mb: row.get("default_ram_mb"),
-                data_dir: row.get("data_dir"),
-                telemetry_opt_in: row.get("telemetry_opt_in"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(())
     }
 
-    pub async fn update_settings(&self, settings: &Settings) -> Result<()> {
+    /// Record the outcome of a delivery attempt
+    pub async fn update_webhook_delivery(&self, delivery: &WebhookDelivery) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT OR REPLACE INTO settings (
-                id, cf_api_key, modrinth_token, java_path, default_ram_mb,
-                data_dir, telemetry_opt_in, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            UPDATE webhook_deliveries SET
+                status = ?, attempts = ?, next_attempt_at = ?, response_status = ?, error = ?, updated_at = ?
+            WHERE id = ?
             "#,
         )
-        .bind(&settings.id)
-        .bind(&settings.cf_api_key)
-        .bind(&settings.modrinth_token)
-        .bind(&settings.java_path)
-        .bind(settings.default_ram_mb)
-        .bind(&settings.data_dir)
-        .bind(settings.telemetry_opt_in)
-        .bind(settings.created_at)
-        .bind(settings.updated_at)
+        .bind(delivery.status.as_str())
+        .bind(delivery.attempts as i64)
+        .bind(delivery.next_attempt_at)
+        .bind(delivery.response_status.map(|status| status as i64))
+        .bind(&delivery.error)
+        .bind(delivery.updated_at)
+        .bind(&delivery.id)
         .execute(&self.pool)
         .await?;
 
-        info!("Updated settings: {}", settings.id);
         Ok(())
     }
 
-    // User settings methods
-    pub async fn get_user_settings(&self) -> Result<Option<UserSettings>> {
-        let row = sqlx::query(
+    /// A webhook's deliveries, newest first
+    pub async fn get_webhook_deliveries(&self, webhook_id: &str, limit: u32) -> Result<Vec<WebhookDelivery>> {
+        let rows = sqlx::query(
+            "SELECT * FROM webhook_deliveries WHERE webhook_id = ? ORDER BY created_at DESC, id DESC LIMIT ?",
+        )
+        .bind(webhook_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::webhook_delivery_from_row).collect()
+    }
+
+    /// Pending deliveries whose next attempt is due, oldest first
+    pub async fn get_due_webhook_deliveries(&self, now: chrono::DateTime<chrono::Utc>, limit: u32) -> Result<Vec<WebhookDelivery>> {
+        let rows = sqlx::query(
             r#"
-            SELECT id, theme, language, notifications, auto_refresh,
-                   refresh_interval, created_at, updated_at
-            FROM user_settings LIMIT 1
+            SELECT * FROM webhook_deliveries
+            WHERE status = 'pending' AND next_attempt_at <= ?
+            ORDER BY next_attempt_at, created_at
+            LIMIT ?
             "#,
         )
-        .fetch_optional(&self.pool)
+        .bind(now)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
         .await?;
 
-        if let Some(row) = row {
-            Ok(Some(UserSettings {
-                id: row.get("id"),
-                theme: row.get("theme"),
-                language: row.get("language"),
-                notifications: row.get("notifications"),
-                auto_refresh: row.get("auto_refresh"),
-                refresh_interval: row.get("refresh_interval"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            }))
-        } else {
-            Ok(None)
-        }
+        rows.iter().map(Self::webhook_delivery_from_row).collect()
+    }
+
+    /// Delete finished deliveries created before `before`, returning how many were removed
+    pub async fn prune_webhook_deliveries(&self, before: chrono::DateTime<chrono::Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM webhook_deliveries WHERE status != 'pending' AND created_at < ?")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Watchdog restart policy methods
+    pub async fn get_restart_policy(&self, server_id: &str) -> Result<Option<serde_json::Value>> {
+        let row = sqlx::query("SELECT policy FROM restart_policies WHERE server_id = ?")
+            .bind(server_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("policy")))
+    }
+
+    /// Every stored policy as `(server_id, policy)`
+    pub async fn get_restart_policies(&self) -> Result<Vec<(String, serde_json::Value)>> {
+        let rows = sqlx::query("SELECT server_id, policy FROM restart_policies")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| (row.get("server_id"), row.get("policy"))).collect())
+    }
+
+    pub async fn save_restart_policy(&self, server_id: &str, policy: &serde_json::Value) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO restart_policies (server_id, policy, updated_at)
+            VALUES (?, ?, ?)
+            "#,
+        )
+        .bind(server_id)
+        .bind(policy)
+        .bind(chrono::Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Port forwarding methods
+    pub async fn get_port_forwarding(&self, server_id: &str) -> Result<bool> {
+        let row = sqlx::query("SELECT enabled FROM port_forwarding WHERE server_id = ?")
+            .bind(server_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("enabled")).unwrap_or(false))
+    }
+
+    /// Servers that have port forwarding turned on
+    pub async fn get_port_forwarding_servers(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query("SELECT server_id FROM port_forwarding WHERE enabled = 1")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| row.get("server_id")).collect())
+    }
+
+    pub async fn set_port_forwarding(&self, server_id: &str, enabled: bool) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO port_forwarding (server_id, enabled, updated_at)
+            VALUES (?, ?, ?)
+            "#,
+        )
+        .bind(server_id)
+        .bind(enabled)
+        .bind(chrono::Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Resource limit methods
+    pub async fn get_resource_limits(&self, server_id: &str) -> Result<ResourceLimits> {
+        let row = sqlx::query("SELECT * FROM server_resource_limits WHERE server_id = ?")
+            .bind(server_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let Some(row) = row else {
+            return Ok(ResourceLimits::default());
+        };
+        let cpu_cores: String = row.get("cpu_cores");
+        let priority: String = row.get("priority");
+        let memory_limit_mb: Option<i64> = row.get("memory_limit_mb");
+        Ok(ResourceLimits {
+            cpu_cores: serde_json::from_str(&cpu_cores)?,
+            priority: ProcessPriority::parse(&priority).unwrap_or_default(),
+            memory_limit_mb: memory_limit_mb.map(|limit| limit as u32),
+        })
     }
 
-    pub async fn update_user_settings(&self, settings: &UserSettings) -> Result<()> {
+    pub async fn set_resource_limits(&self, server_id: &str, limits: &ResourceLimits) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT OR REPLACE INTO user_settings (
-                id, theme, language, notifications, auto_refresh,
-                refresh_interval, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT OR REPLACE INTO server_resource_limits (server_id, cpu_cores, priority, memory_limit_mb, updated_at)
+            VALUES (?, ?, ?, ?, ?)
             "#,
         )
-        .bind(&settings.id)
-        .bind(&settings.theme)
-        .bind(&settings.language)
-        .bind(settings.notifications)
-        .bind(settings.auto_refresh)
-        .bind(settings.refresh_interval)
-        .bind(settings.created_at)
-        .bind(settings.updated_at)
+        .bind(server_id)
+        .bind(serde_json::to_string(&limits.cpu_cores)?)
+        .bind(limits.priority.as_str())
+        .bind(limits.memory_limit_mb.map(|limit| limit as i64))
+        .bind(chrono::Utc::now())
         .execute(&self.pool)
         .await?;
 
-        info!("Updated user settings: {}", settings.id);
         Ok(())
     }
 
-    // Task methods
-    pub async fn create_task(&self, task: &Task) -> Result<()> {
+    fn api_token_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<ApiToken> {
+        let scopes: serde_json::Value = row.get("scopes");
+        let server_ids: serde_json::Value = row.get("server_ids");
+        Ok(ApiToken {
+            id: row.get("id"),
+            name: row.get("name"),
+            token_hash: row.get("token_hash"),
+            token_prefix: row.get("token_prefix"),
+            scopes: serde_json::from_value(scopes)?,
+            server_ids: serde_json::from_value(server_ids)?,
+            created_by: row.get("created_by"),
+            created_at: row.get("created_at"),
+            expires_at: row.get("expires_at"),
+            last_used_at: row.get("last_used_at"),
+            revoked_at: row.get("revoked_at"),
+        })
+    }
+
+    pub async fn create_api_token(&self, token: &ApiToken) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT INTO tasks (
-                id, server_id, kind, status, progress, log, metadata,
-                started_at, finished_at, created_at, updated_at
+            INSERT INTO api_tokens (
+                id, name, token_hash, token_prefix, scopes, server_ids, created_by,
+                created_at, expires_at, last_used_at, revoked_at
             ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
-        .bind(&task.id)
-        .bind(&task.server_id)
-        .bind(&task.kind)
-        .bind(&task.status)
-        .bind(task.progress)
-        .bind(&task.log)
-        .bind(&task.metadata)
-        .bind(task.started_at)
-        .bind(task.finished_at)
-        .bind(task.created_at)
-        .bind(task.updated_at)
+        .bind(&token.id)
+        .bind(&token.name)
+        .bind(&token.token_hash)
+        .bind(&token.token_prefix)
+        .bind(serde_json::to_value(&token.scopes)?)
+        .bind(serde_json::to_value(&token.server_ids)?)
+        .bind(&token.created_by)
+        .bind(token.created_at)
+        .bind(token.expires_at)
+        .bind(token.last_used_at)
+        .bind(token.revoked_at)
         .execute(&self.pool)
         .await?;
 
-        info!("Created task: {}", task.id);
+        info!("Created API token: {}", token.id);
         Ok(())
     }
 
-    pub async fn get_task(&self, id: &str) -> Result<Option<Task>> {
-        let row = sqlx::query(
-            r#"
-            SELECT id, server_id, kind, status, progress, log, metadata,
-                   started_at, finished_at, created_at, updated_at
-            FROM tasks WHERE id = ?
-            "#,
-        )
-        .bind(id)
-        .fetch_optional(&self.pool)
-        .await?;
+    pub async fn get_api_tokens(&self) -> Result<Vec<ApiToken>> {
+        let rows = sqlx::query("SELECT * FROM api_tokens ORDER BY created_at DESC")
+            .fetch_all(&self.pool)
+            .await?;
 
-        if let Some(row) = row {
-            Ok(Some(Task {
-                id: row.get("id"),
-                server_id: row.get("server_id"),
-                kind: row.get("kind"),
-                status: row.get("status"),
-                progress: row.get("progress"),
-                log: row.get("log"),
-                metadata: row.get("metadata"),
-                started_at: row.get("started_at"),
-                finished_at: row.get("finished_at"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            }))
-        } else {
-            Ok(None)
-        }
+        rows.iter().map(Self::api_token_from_row).collect()
     }
 
-    pub async fn get_tasks_by_server(&self, server_id: &str) -> Result<Vec<Task>> {
-        let rows = sqlx::query(
+    pub async fn get_api_token(&self, id: &str) -> Result<Option<ApiToken>> {
+        let row = sqlx::query("SELECT * FROM api_tokens WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::api_token_from_row).transpose()
+    }
+
+    pub async fn get_api_token_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let row = sqlx::query("SELECT * FROM api_tokens WHERE token_hash = ?")
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::api_token_from_row).transpose()
+    }
+
+    /// Returns false when the token does not exist or was already revoked
+    pub async fn revoke_api_token(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE api_tokens SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL")
+            .bind(chrono::Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn touch_api_token(&self, id: &str) -> Result<()> {
+        sqlx::query("UPDATE api_tokens SET last_used_at = ? WHERE id = ?")
+            .bind(chrono::Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn insert_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        sqlx::query(
             r#"
-            SELECT id, server_id, kind, status, progress, log, metadata,
-                   started_at, finished_at, created_at, updated_at
-            FROM tasks WHERE server_id = ?
-            ORDER BY created_at DESC
+            INSERT INTO audit_log (id, timestamp, actor, actor_type, action, method, path, server_id, status_code, success, error, duration_ms)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
-        .bind(server_id)
-        .fetch_all(&self.pool)
+        .bind(&entry.id)
+        .bind(entry.timestamp)
+        .bind(&entry.actor)
+        .bind(&entry.actor_type)
+        .bind(&entry.action)
+        .bind(&entry.method)
+        .bind(&entry.path)
+        .bind(&entry.server_id)
+        .bind(entry.status_code as i64)
+        .bind(entry.success)
+        .bind(&entry.error)
+        .bind(entry.duration_ms as i64)
+        .execute(&self.pool)
         .await?;
 
-        let tasks = rows
-            .into_iter()
-            .map(|row| Task {
+        Ok(())
+    }
+
+    /// Audit entries matching the filters, newest first, with the total number of matches
+    pub async fn get_audit_entries(&self, params: &crate::api::AuditLogQuery) -> Result<(Vec<AuditEntry>, u64)> {
+        fn push_filters<'a>(builder: &mut sqlx::QueryBuilder<'a, sqlx::Sqlite>, params: &'a crate::api::AuditLogQuery) {
+            builder.push(" WHERE 1=1");
+            if let Some(actor) = &params.actor {
+                builder.push(" AND actor = ").push_bind(actor);
+            }
+            if let Some(action) = &params.action {
+                // "backup" matches backup.create, backup.restore, ...
+                builder.push(" AND (action = ").push_bind(action)
+                    .push(" OR action LIKE ").push_bind(format!("{}.%", action)).push(")");
+            }
+            if let Some(server_id) = &params.server_id {
+                builder.push(" AND server_id = ").push_bind(server_id);
+            }
+            if let Some(success) = params.success {
+                builder.push(" AND success = ").push_bind(success);
+            }
+            if let Some(since) = params.since {
+                builder.push(" AND timestamp >= ").push_bind(since);
+            }
+            if let Some(until) = params.until {
+                builder.push(" AND timestamp <= ").push_bind(until);
+            }
+        }
+
+        let mut count = sqlx::QueryBuilder::new("SELECT COUNT(*) FROM audit_log");
+        push_filters(&mut count, params);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let limit = params.limit.unwrap_or(50).clamp(1, 500);
+        let offset = (params.page.unwrap_or(1).max(1) - 1) * limit;
+        let mut query = sqlx::QueryBuilder::new("SELECT * FROM audit_log");
+        push_filters(&mut query, params);
+        query.push(" ORDER BY timestamp DESC LIMIT ").push_bind(limit)
+            .push(" OFFSET ").push_bind(offset);
+        let rows = query.build().fetch_all(&self.pool).await?;
+
+        let entries = rows
+            .iter()
+            .map(|row| AuditEntry {
                 id: row.get("id"),
+                timestamp: row.get("timestamp"),
+                actor: row.get("actor"),
+                actor_type: row.get("actor_type"),
+                action: row.get("action"),
+                method: row.get("method"),
+                path: row.get("path"),
                 server_id: row.get("server_id"),
-                kind: row.get("kind"),
-                status: row.get("status"),
-                progress: row.get("progress"),
-                log: row.get("log"),
-                metadata: row.get("metadata"),
-                started_at: row.get("started_at"),
-                finished_at: row.get("finished_at"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
+                status_code: row.get::<i64, _>("status_code") as u16,
+                success: row.get("success"),
+                error: row.get("error"),
+                duration_ms: row.get::<i64, _>("duration_ms") as u64,
             })
             .collect();
 
-        Ok(tasks)
+        Ok((entries, total as u64))
     }
 
-    pub async fn update_task(&self, task: &Task) -> Result<()> {
+    // Metrics history methods
+    /// Store a sample or rollup. A rollup for a bucket that already has one is ignored.
+    pub async fn insert_server_metric(&self, metric: &ServerMetric) -> Result<()> {
         sqlx::query(
             r#"
-            UPDATE tasks SET
-                status = ?, progress = ?, log = ?, metadata = ?,
-                started_at = ?, finished_at = ?, updated_at = ?
-            WHERE id = ?
+            INSERT OR IGNORE INTO server_metrics (
+                id, server_id, resolution, timestamp, tps, tick_p95, heap_mb, players_online,
+                gpu_queue_ms, cpu_usage, memory_usage, disk_usage, network_in, network_out
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
-        .bind(&task.status)
-        .bind(task.progress)
-        .bind(&task.log)
-        .bind(&task.metadata)
-        .bind(task.started_at)
-        .bind(task.finished_at)
-        .bind(task.updated_at)
-        .bind(&task.id)
+        .bind(&metric.id)
+        .bind(&metric.server_id)
+        .bind(&metric.resolution)
+        .bind(metric.timestamp)
+        .bind(metric.tps)
+        .bind(metric.tick_p95)
+        .bind(metric.heap_mb)
+        .bind(metric.players_online)
+        .bind(metric.gpu_queue_ms)
+        .bind(metric.cpu_usage)
+        .bind(metric.memory_usage)
+        .bind(metric.disk_usage)
+        .bind(metric.network_in)
+        .bind(metric.network_out)
         .execute(&self.pool)
         .await?;
-
-        info!("Updated task: {}", task.id);
         Ok(())
     }
 
-    pub async fn delete_task(&self, id: &str) -> Result<()> {
-        sqlx::query("DELETE FROM tasks WHERE id = ?")
-            .bind(id)
+    /// Metrics at `resolution` since `since`, oldest first, for one server or all of them
+    pub async fn get_server_metrics_since(
+        &self,
+        server_id: Option<&str>,
+        resolution: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<ServerMetric>> {
+        let metrics = sqlx::query_as::<_, ServerMetric>(
+            r#"
+            SELECT id, server_id, resolution, tps, tick_p95, heap_mb, players_online, gpu_queue_ms,
+                   cpu_usage, memory_usage, disk_usage, network_in, network_out, timestamp
+            FROM server_metrics
+            WHERE (? IS NULL OR server_id = ?) AND resolution = ? AND timestamp >= ?
+            ORDER BY timestamp ASC
+            "#,
+        )
+        .bind(server_id)
+        .bind(server_id)
+        .bind(resolution)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(metrics)
+    }
+
+    /// Delete metrics at `resolution` older than `before`, returning how many were removed
+    pub async fn prune_server_metrics(&self, resolution: &str, before: chrono::DateTime<chrono::Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM server_metrics WHERE resolution = ? AND timestamp < ?")
+            .bind(resolution)
+            .bind(before)
             .execute(&self.pool)
             .await?;
-
-        info!("Deleted task: {}", id);
-        Ok(())
+        Ok(result.rows_affected())
     }
 
     // Event logging methods
@@ -1659,6 +3796,126 @@ impl DatabaseManager {
         Ok(events)
     }
 
+    /// Events of the given types logged after `since`, oldest first
+    pub async fn get_events_since(
+        &self,
+        event_types: &[&str],
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<EventLog>> {
+        let mut query = sqlx::QueryBuilder::new(
+            "SELECT id, server_id, event_type, message, level, metadata, created_at FROM event_logs WHERE created_at > ",
+        );
+        query.push_bind(since).push(" AND event_type IN (");
+        let mut types = query.separated(", ");
+        for event_type in event_types {
+            types.push_bind(*event_type);
+        }
+        query.push(") ORDER BY created_at ASC");
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| EventLog {
+                id: row.get("id"),
+                server_id: row.get("server_id"),
+                event_type: row.get("event_type"),
+                message: row.get("message"),
+                level: row.get("level"),
+                metadata: row.get("metadata"),
+                created_at: row.get("created_at"),
+            })
+            .collect())
+    }
+
+    /// Events matching `filter`, newest first, at most `limit` of them
+    pub async fn get_event_page(&self, filter: &crate::events::EventFilter, limit: u32) -> Result<Vec<EventLog>> {
+        let mut query = sqlx::QueryBuilder::new(
+            "SELECT id, server_id, event_type, message, level, metadata, created_at FROM event_logs WHERE 1=1",
+        );
+        if let Some(server_id) = &filter.server_id {
+            query.push(" AND server_id = ").push_bind(server_id);
+        }
+        if !filter.server_ids.is_empty() {
+            query.push(" AND server_id IN (");
+            let mut servers = query.separated(", ");
+            for server_id in &filter.server_ids {
+                servers.push_bind(server_id);
+            }
+            query.push(")");
+        }
+        if !filter.event_types.is_empty() {
+            query.push(" AND event_type IN (");
+            let mut types = query.separated(", ");
+            for event_type in &filter.event_types {
+                types.push_bind(event_type);
+            }
+            query.push(")");
+        }
+        match filter.category {
+            // Anything that isn't a known kind
+            Some(crate::events::EventCategory::Other) => {
+                query.push(" AND event_type NOT IN (");
+                let mut types = query.separated(", ");
+                for kind in crate::events::KINDS {
+                    types.push_bind(kind.as_str());
+                }
+                query.push(")");
+            }
+            Some(category) => {
+                query.push(" AND event_type IN (");
+                let mut types = query.separated(", ");
+                for event_type in category.event_types() {
+                    types.push_bind(event_type);
+                }
+                query.push(")");
+            }
+            None => {}
+        }
+        if !filter.levels.is_empty() {
+            query.push(" AND level IN (");
+            let mut levels = query.separated(", ");
+            for level in &filter.levels {
+                levels.push_bind(*level);
+            }
+            query.push(")");
+        }
+        if let Some(since) = filter.since {
+            query.push(" AND created_at >= ").push_bind(since);
+        }
+        if let Some(until) = filter.until {
+            query.push(" AND created_at <= ").push_bind(until);
+        }
+        if let Some((created_at, id)) = &filter.before {
+            query.push(" AND (created_at < ").push_bind(*created_at)
+                .push(" OR (created_at = ").push_bind(*created_at)
+                .push(" AND id < ").push_bind(id).push("))");
+        }
+        query.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(limit);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| EventLog {
+                id: row.get("id"),
+                server_id: row.get("server_id"),
+                event_type: row.get("event_type"),
+                message: row.get("message"),
+                level: row.get("level"),
+                metadata: row.get("metadata"),
+                created_at: row.get("created_at"),
+            })
+            .collect())
+    }
+
+    /// Delete events logged before `before`, returning how many were removed
+    pub async fn prune_events(&self, before: chrono::DateTime<chrono::Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM event_logs WHERE created_at < ?")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
     // Backup configuration methods
     pub async fn create_backup_config(&self, config: &BackupConfig) -> Result<()> {
         sqlx::query(
@@ -1848,7 +4105,7 @@ impl DatabaseManager {
         let row = sqlx::query(
             r#"
             SELECT id, provider, project_id, version_id, filename, sha1,
-                   server_id, enabled, created_at, updated_at
+                   server_id, enabled, category, created_at, updated_at
             FROM mods WHERE id = ?
             "#,
         )
@@ -1879,7 +4136,7 @@ impl DatabaseManager {
         let rows = sqlx::query(
             r#"
             SELECT id, provider, project_id, version_id, filename, sha1,
-                   server_id, enabled, created_at, updated_at
+                   server_id, enabled, category, created_at, updated_at
             FROM mods WHERE server_id = ?
             ORDER BY created_at DESC
             "#,
@@ -2618,16 +4875,8 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
-    #[tokio::test]
-    async fn test_database_creation() {
-        let temp_dir = tempdir().expect("Failed to create temp directory");
-        let db_path = temp_dir.path().join("test.db");
-        let database_url = format!("sqlite:{}", db_path.display());
-        
-        let db = DatabaseManager::new(&database_url).await.expect("Failed to create test database");
-        
-        // Test creating a server
-        let server = ServerConfig {
+    fn test_server(dir: &std::path::Path) -> ServerConfig {
+        ServerConfig {
             id: "test-server".to_string(),
             name: "Test Server".to_string(),
             minecraft_version: "1.21.1".to_string(),
@@ -2656,10 +4905,24 @@ mod tests {
             java_path: "/usr/bin/java".to_string(),
             jvm_args: "-Xmx4G".to_string(),
             server_jar: "server.jar".to_string(),
+            server_directory: dir.join("test-server").display().to_string(),
             rcon_password: "password".to_string(),
+            managed: true,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
-        };
+        }
+    }
+
+    #[tokio::test]
+    async fn test_database_creation() {
+        let temp_dir = tempdir().expect("Failed to create temp directory");
+        let db_path = temp_dir.path().join("test.db");
+        let database_url = format!("sqlite:{}", db_path.display());
+        
+        let db = DatabaseManager::new(&database_url).await.expect("Failed to create test database");
+        
+        // Test creating a server
+        let server = test_server(temp_dir.path());
         
         db.create_server(&server).await.unwrap();
         
@@ -2676,6 +4939,7 @@ mod tests {
         let database_url = format!("sqlite:{}", db_path.display());
         
         let db = DatabaseManager::new(&database_url).await.unwrap();
+        db.create_server(&test_server(temp_dir.path())).await.unwrap();
         
         let event = EventLog {
             id: Uuid::new_v4().to_string(),
@@ -2693,4 +4957,58 @@ mod tests {
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].event_type, "server_start");
     }
+
+    #[tokio::test]
+    async fn test_schema_version_tracks_migrations() {
+        let temp_dir = tempdir().unwrap();
+        let database_url = format!("sqlite:{}", temp_dir.path().join("test.db").display());
+
+        let db = DatabaseManager::new(&database_url).await.unwrap();
+        let info = db.get_database_info().await.unwrap();
+        assert!(info.up_to_date);
+        assert_eq!(info.schema_version, info.latest_version);
+        assert!(info.migrations.iter().all(|migration| migration.applied));
+
+        db.migrate_down(1).await.unwrap();
+        let info = db.get_database_info().await.unwrap();
+        assert_eq!(info.schema_version, 1);
+        assert!(!info.up_to_date);
+
+        // Reopening applies the reverted migrations again
+        let db = DatabaseManager::new(&database_url).await.unwrap();
+        assert!(db.get_database_info().await.unwrap().up_to_date);
+    }
+
+    #[tokio::test]
+    async fn test_require_server_reports_a_missing_server_as_not_found() {
+        let temp_dir = tempdir().unwrap();
+        let db = DatabaseManager::new(&format!("sqlite:{}", temp_dir.path().join("test.db").display())).await.unwrap();
+        let server = test_server(temp_dir.path());
+        db.create_server(&server).await.unwrap();
+
+        assert_eq!(db.require_server(&server.id).await.unwrap().name, server.name);
+        let error = db.require_server("missing").await.unwrap_err();
+        assert!(error.downcast_ref::<NotFound>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_existing_startup_schema_is_adopted() {
+        let temp_dir = tempdir().unwrap();
+        let database_url = format!("sqlite:{}", temp_dir.path().join("test.db").display());
+
+        // A database from before the startup schema became a migration has
+        // server_directory without the migration recorded
+        let db = DatabaseManager::new(&database_url).await.unwrap();
+        sqlx::query("DELETE FROM _sqlx_migrations WHERE version = ?")
+            .bind(LEGACY_SCHEMA_VERSION)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        db.pool.close().await;
+
+        let db = DatabaseManager::new(&database_url).await.unwrap();
+        let info = db.get_database_info().await.unwrap();
+        assert!(info.up_to_date);
+        assert!(info.migrations.iter().any(|migration| migration.version == LEGACY_SCHEMA_VERSION && migration.applied));
+    }
 }
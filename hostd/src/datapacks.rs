@@ -0,0 +1,378 @@
+//! Datapacks of a server's world
+//!
+//! Datapacks live in `<world>/datapacks` as zips or folders, and level.dat
+//! records which are enabled under `Data.DataPacks`, by `file/<name>` ID. On a
+//! running server changes go through RCON (`reload`, `datapack enable` and
+//! `datapack disable`) so they take effect at once; on a stopped one
+//! level.dat is edited. Packs the game hasn't seen yet are enabled when it
+//! next loads the world.
+
+use anyhow::{anyhow, bail, Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use crate::downloads::{Checksum, Download};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::core::process_manager::ProcessManager;
+use crate::database::DatabaseManager;
+use crate::restart_scheduler::rcon;
+use crate::world::nbt::{self, Compound, Tag};
+
+/// Largest datapack accepted by upload
+pub const MAX_UPLOAD_BYTES: usize = 256 * 1024 * 1024;
+
+const DATAPACKS_DIR: &str = "datapacks";
+const TAG_STRING: u8 = 8;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Datapack {
+    /// File or folder name in `datapacks/`
+    pub name: String,
+    /// zip or folder
+    pub format: String,
+    pub enabled: bool,
+    /// From pack.mcmeta
+    pub description: Option<String>,
+    pub pack_format: Option<i64>,
+    pub size_bytes: u64,
+}
+
+/// Install a datapack from a Modrinth project
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModrinthDatapackRequest {
+    pub project_id: String,
+    /// Newest version for the server's Minecraft version when absent
+    pub version_id: Option<String>,
+}
+
+pub struct DatapackManager {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+}
+
+impl DatapackManager {
+    pub fn new(database: Arc<DatabaseManager>, process_manager: Arc<ProcessManager>) -> Self {
+        Self { database, process_manager }
+    }
+
+    async fn running(&self, server_id: &str) -> bool {
+        match Uuid::parse_str(server_id) {
+            Ok(server_id) => self.process_manager.is_server_running(server_id).await,
+            Err(_) => false,
+        }
+    }
+
+    pub async fn list(&self, server_id: &str) -> Result<Vec<Datapack>> {
+        let server = self.database.require_server(server_id).await?;
+        // level.dat is only written on save, so a running server is asked directly
+        let live = if self.running(server_id).await {
+            rcon(&server, "datapack list enabled".to_string()).await.ok().map(|output| enabled_ids(&output))
+        } else {
+            None
+        };
+        let world = crate::world::server_world_dir(&server);
+        tokio::task::spawn_blocking(move || list_blocking(&world, live.as_deref())).await?
+    }
+
+    pub async fn upload(&self, server_id: &str, name: &str, bytes: &[u8]) -> Result<Datapack> {
+        validate_name(name)?;
+        if !name.to_ascii_lowercase().ends_with(".zip") {
+            bail!("Datapacks are uploaded as .zip files");
+        }
+        read_zip_meta(bytes).context("Not a datapack: pack.mcmeta is missing or invalid")?;
+
+        let server = self.database.require_server(server_id).await?;
+        let dir = crate::world::server_world_dir(&server).join(DATAPACKS_DIR);
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(dir.join(name), bytes).await?;
+        info!("Added datapack {} to server {}", name, server_id);
+
+        // The game only sees new packs after a reload; enabling one already enabled is harmless
+        if self.running(server_id).await {
+            rcon(&server, "reload".to_string()).await?;
+            rcon(&server, format!("datapack enable \"{}\"", pack_id(name))).await?;
+        }
+        self.find(server_id, name).await
+    }
+
+    pub async fn set_enabled(&self, server_id: &str, name: &str, enabled: bool) -> Result<Datapack> {
+        validate_name(name)?;
+        let server = self.database.require_server(server_id).await?;
+        let world = crate::world::server_world_dir(&server);
+        if !world.join(DATAPACKS_DIR).join(name).exists() {
+            bail!("Datapack {} not found", name);
+        }
+
+        if self.running(server_id).await {
+            let action = if enabled { "enable" } else { "disable" };
+            rcon(&server, format!("datapack {} \"{}\"", action, pack_id(name))).await?;
+        } else {
+            let name = name.to_string();
+            tokio::task::spawn_blocking(move || update_level_dat(&world, &name, Some(enabled))).await??;
+        }
+        self.find(server_id, name).await
+    }
+
+    pub async fn delete(&self, server_id: &str, name: &str) -> Result<()> {
+        validate_name(name)?;
+        let server = self.database.require_server(server_id).await?;
+        let world = crate::world::server_world_dir(&server);
+        let path = world.join(DATAPACKS_DIR).join(name);
+        if !path.exists() {
+            bail!("Datapack {} not found", name);
+        }
+
+        if self.running(server_id).await {
+            rcon(&server, format!("datapack disable \"{}\"", pack_id(name))).await?;
+        } else {
+            let name = name.to_string();
+            tokio::task::spawn_blocking(move || update_level_dat(&world, &name, None)).await??;
+        }
+        if path.is_dir() {
+            tokio::fs::remove_dir_all(&path).await?;
+        } else {
+            tokio::fs::remove_file(&path).await?;
+        }
+        info!("Deleted datapack {} from server {}", name, server_id);
+        Ok(())
+    }
+
+    /// Download a Modrinth datapack project's zip into the world
+    pub async fn install_modrinth(&self, server_id: &str, request: &ModrinthDatapackRequest) -> Result<Datapack> {
+        let server = self.database.require_server(server_id).await?;
+        let client = crate::external_apis::modrinth::ModrinthApiClient::new();
+        let version = match &request.version_id {
+            Some(version_id) => client.get_version(version_id).await?,
+            None => client
+                .get_project_versions(&request.project_id, Some(vec![&server.minecraft_version]), Some(vec!["datapack"]))
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    anyhow!("{} has no datapack for Minecraft {}", request.project_id, server.minecraft_version)
+                })?,
+        };
+        if !version.loaders.iter().any(|loader| loader == "datapack") {
+            bail!("Version {} of {} is not a datapack", version.version_number, request.project_id);
+        }
+        let file = version
+            .files
+            .iter()
+            .find(|file| file.primary)
+            .or_else(|| version.files.first())
+            .ok_or_else(|| anyhow!("Version {} has no files", version.version_number))?;
+
+        let download = Download::new(&file.url).checksum(Checksum::from_hashes(&file.hashes));
+        let bytes = crate::downloads::shared().fetch(&download).await
+            .with_context(|| format!("Failed to download {}", file.filename))?;
+        self.upload(server_id, &file.filename, &bytes).await
+    }
+
+    async fn find(&self, server_id: &str, name: &str) -> Result<Datapack> {
+        self.list(server_id)
+            .await?
+            .into_iter()
+            .find(|pack| pack.name == name)
+            .ok_or_else(|| anyhow!("Datapack {} not found", name))
+    }
+}
+
+/// The ID the game knows a datapack in `datapacks/` by
+fn pack_id(name: &str) -> String {
+    format!("file/{}", name)
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\', '"']) {
+        bail!("Invalid datapack name: {}", name);
+    }
+    Ok(())
+}
+
+/// IDs in the output of `datapack list enabled`, such as
+/// `There are 2 data pack(s) enabled: [vanilla (built-in)], [file/terralith.zip (world)]`
+fn enabled_ids(output: &str) -> Vec<String> {
+    output
+        .split('[')
+        .skip(1)
+        .filter_map(|entry| entry.split(']').next())
+        .map(|entry| match entry.rsplit_once(" (") {
+            Some((id, _)) => id.to_string(),
+            None => entry.to_string(),
+        })
+        .collect()
+}
+
+fn list_blocking(world: &Path, live: Option<&[String]>) -> Result<Vec<Datapack>> {
+    let dir = world.join(DATAPACKS_DIR);
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let (enabled, disabled) = match read_level_dat(world) {
+        Ok(Some((_, root))) => (string_list(&root, "Enabled"), string_list(&root, "Disabled")),
+        _ => (Vec::new(), Vec::new()),
+    };
+
+    let mut packs = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let (format, meta, size_bytes) = if path.is_dir() {
+            let meta = std::fs::read_to_string(path.join("pack.mcmeta")).ok().and_then(|content| parse_meta(&content).ok());
+            ("folder", meta, dir_size(&path))
+        } else if name.to_ascii_lowercase().ends_with(".zip") {
+            let bytes = std::fs::read(&path)?;
+            ("zip", read_zip_meta(&bytes).ok(), bytes.len() as u64)
+        } else {
+            continue;
+        };
+        let id = pack_id(&name);
+        let enabled = match live {
+            Some(live) => live.contains(&id),
+            None => enabled.contains(&id) || !disabled.contains(&id),
+        };
+        packs.push(Datapack {
+            name,
+            format: format.to_string(),
+            enabled,
+            description: meta.as_ref().and_then(|(description, _)| description.clone()),
+            pack_format: meta.and_then(|(_, pack_format)| pack_format),
+            size_bytes,
+        });
+    }
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(packs)
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else { return 0 };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Description and pack format of a zipped datapack
+pub(crate) fn read_zip_meta(bytes: &[u8]) -> Result<(Option<String>, Option<i64>)> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+    let mut content = String::new();
+    archive.by_name("pack.mcmeta")?.read_to_string(&mut content)?;
+    parse_meta(&content)
+}
+
+fn parse_meta(content: &str) -> Result<(Option<String>, Option<i64>)> {
+    let meta: serde_json::Value = serde_json::from_str(content)?;
+    let pack = meta.get("pack").ok_or_else(|| anyhow!("pack.mcmeta has no pack section"))?;
+    // The description may be a text component rather than a string
+    let description = match &pack["description"] {
+        serde_json::Value::String(text) => Some(text.clone()),
+        serde_json::Value::Null => None,
+        component => component["text"].as_str().map(str::to_string).or_else(|| Some(component.to_string())),
+    };
+    Ok((description, pack["pack_format"].as_i64()))
+}
+
+/// The world's level.dat root name and `Data.DataPacks` compound, if it has one
+fn read_level_dat(world: &Path) -> Result<Option<(String, Compound)>> {
+    let path = world.join("level.dat");
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    GzDecoder::new(std::fs::File::open(&path)?).read_to_end(&mut bytes)?;
+    let (name, root) = nbt::read(&bytes)?;
+    let packs = root.get_compound("Data").and_then(|data| data.get_compound("DataPacks")).cloned();
+    Ok(packs.map(|packs| (name, packs)))
+}
+
+fn string_list(packs: &Compound, list: &str) -> Vec<String> {
+    packs
+        .get_list(list)
+        .map(|ids| ids.iter().filter_map(Tag::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Move a pack to level.dat's `Enabled` or `Disabled` list, or out of both
+fn update_level_dat(world: &Path, name: &str, enabled: Option<bool>) -> Result<()> {
+    let path: PathBuf = world.join("level.dat");
+    if !path.is_file() {
+        // The world hasn't been created yet; the game enables every pack it finds
+        if enabled == Some(false) {
+            bail!("The world has not been generated yet, so its datapacks can't be disabled");
+        }
+        return Ok(());
+    }
+    let mut bytes = Vec::new();
+    GzDecoder::new(std::fs::File::open(&path)?).read_to_end(&mut bytes)?;
+    let (root_name, mut root) = nbt::read(&bytes)?;
+    let data = root.get_compound_mut("Data").ok_or_else(|| anyhow!("level.dat has no Data"))?;
+    if data.get_compound("DataPacks").is_none() {
+        data.insert("DataPacks", Tag::Compound(Compound::new()));
+    }
+    let packs = data.get_compound_mut("DataPacks").expect("inserted above");
+
+    let id = pack_id(name);
+    for (list, keep) in [("Enabled", enabled == Some(true)), ("Disabled", enabled == Some(false))] {
+        if packs.get_list(list).is_none() {
+            packs.insert(list, Tag::List(TAG_STRING, Vec::new()));
+        }
+        let ids = packs.get_list_mut(list).expect("inserted above");
+        ids.retain(|tag| tag.as_str() != Some(id.as_str()));
+        if keep {
+            ids.push(Tag::String(id.clone()));
+        }
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&nbt::write(&root_name, &root))?;
+    let partial = world.join("level.dat.partial");
+    std::fs::write(&partial, encoder.finish()?)?;
+    std::fs::rename(&partial, &path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enabled_ids() {
+        let output = "There are 3 data pack(s) enabled: [vanilla (built-in)], [file/My Pack.zip (world)], [fabric]";
+        assert_eq!(enabled_ids(output), ["vanilla", "file/My Pack.zip", "fabric"]);
+        assert!(enabled_ids("There are no data packs enabled").is_empty());
+    }
+
+    #[test]
+    fn test_level_dat_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut packs = Compound::new();
+        packs.insert("Enabled", Tag::List(TAG_STRING, vec![Tag::String("vanilla".to_string()), Tag::String("file/a.zip".to_string())]));
+        let mut data = Compound::new();
+        data.insert("DataPacks", Tag::Compound(packs));
+        let mut root = Compound::new();
+        root.insert("Data", Tag::Compound(data));
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&nbt::write("", &root)).unwrap();
+        std::fs::write(dir.path().join("level.dat"), encoder.finish().unwrap()).unwrap();
+
+        update_level_dat(dir.path(), "a.zip", Some(false)).unwrap();
+        let (_, packs) = read_level_dat(dir.path()).unwrap().unwrap();
+        assert_eq!(string_list(&packs, "Enabled"), ["vanilla"]);
+        assert_eq!(string_list(&packs, "Disabled"), ["file/a.zip"]);
+
+        update_level_dat(dir.path(), "a.zip", None).unwrap();
+        let (_, packs) = read_level_dat(dir.path()).unwrap().unwrap();
+        assert!(string_list(&packs, "Disabled").is_empty());
+    }
+}
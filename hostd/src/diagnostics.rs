@@ -0,0 +1,393 @@
+//! Diagnostics captures of running servers, kept in `<server>/diagnostics/`
+//! for offline analysis: heap dumps taken with `jcmd GC.heap_dump` (run as a
+//! `heap_dump` job, since the JVM pauses while it writes), GC logs switched on
+//! and off at runtime with `jcmd VM.log`, and the thread dumps of freeze
+//! tickets. Each server's folder is held to the configured quota by removing
+//! the oldest files once a capture is written.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config_revisions::server_dir;
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, ServerConfig, Task};
+use crate::jobs::{Job, JobContext, JobManager};
+
+pub const DIAGNOSTICS_DIR: &str = "diagnostics";
+const JCMD_TIMEOUT: Duration = Duration::from_secs(30);
+/// Writing the dump of a large heap takes a while
+const HEAP_DUMP_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// GC logs rotate within this many files of this size
+const GC_LOG_FILES: u32 = 5;
+const GC_LOG_FILE_SIZE: &str = "20M";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    HeapDump,
+    GcLog,
+    ThreadDump,
+    Other,
+}
+
+impl DiagnosticKind {
+    fn of(name: &str) -> Self {
+        if name.starts_with("heap-") {
+            Self::HeapDump
+        } else if name.starts_with("gc-") {
+            Self::GcLog
+        } else if name.starts_with("thread-dump-") {
+            Self::ThreadDump
+        } else {
+            Self::Other
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticFile {
+    pub name: String,
+    pub kind: DiagnosticKind,
+    pub size: u64,
+    pub modified: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HeapDumpRequest {
+    /// Include unreachable objects; only live objects are dumped otherwise
+    #[serde(default)]
+    pub all_objects: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GcLogStatus {
+    pub enabled: bool,
+    /// Log file GC events are written to, rotated as `<file>.0`, `<file>.1`, ...
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GcLogRequest {
+    pub enabled: bool,
+}
+
+pub struct DiagnosticsManager {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+    jobs: Arc<JobManager>,
+    quota_bytes: u64,
+    /// GC log file of each server it is enabled on, until the server stops
+    gc_logs: RwLock<HashMap<String, String>>,
+}
+
+impl DiagnosticsManager {
+    pub fn new(
+        database: Arc<DatabaseManager>,
+        process_manager: Arc<ProcessManager>,
+        jobs: Arc<JobManager>,
+        quota_mb: u64,
+    ) -> Self {
+        Self {
+            database,
+            process_manager,
+            jobs,
+            quota_bytes: quota_mb * 1024 * 1024,
+            gc_logs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// PID of a running server
+    async fn pid(&self, server: &ServerConfig) -> Result<u32> {
+        let uuid = Uuid::parse_str(&server.id)?;
+        if !server.managed || !self.process_manager.is_server_running(uuid).await {
+            bail!("The server is not running");
+        }
+        self.process_manager
+            .get_process_info(uuid)
+            .await
+            .map(|info| info.pid)
+            .map_err(|_| anyhow!("The server's process is not known"))
+    }
+
+    pub async fn files(&self, server_id: &str) -> Result<Vec<DiagnosticFile>> {
+        let server = self.database.require_server(server_id).await?;
+        let mut files = list(&diagnostics_dir(&server)).await?;
+        files.sort_by_key(|file| std::cmp::Reverse(file.modified));
+        Ok(files)
+    }
+
+    /// Queue a heap dump of the running server
+    pub async fn heap_dump(self: &Arc<Self>, server_id: &str, request: HeapDumpRequest) -> Result<Task> {
+        let server = self.database.require_server(server_id).await?;
+        self.pid(&server).await?;
+        self.jobs
+            .spawn(HeapDumpJob { manager: self.clone(), server_id: server.id, all_objects: request.all_objects })
+            .await
+    }
+
+    async fn write_heap_dump(&self, server_id: &str, all_objects: bool, ctx: &JobContext) -> Result<String> {
+        let server = self.database.require_server(server_id).await?;
+        let pid = self.pid(&server).await?;
+        tokio::fs::create_dir_all(diagnostics_dir(&server)).await?;
+        let name = format!("heap-{}.hprof", Utc::now().format("%Y-%m-%d_%H.%M.%S"));
+
+        ctx.progress(0.1, "dumping", Some("Writing the heap dump; the server pauses meanwhile")).await;
+        // Relative to the server's working directory, so the path holds no spaces jcmd would split on
+        let target = format!("{}/{}", DIAGNOSTICS_DIR, name);
+        let mut args = vec!["GC.heap_dump".to_string()];
+        if all_objects {
+            args.push("-all".to_string());
+        }
+        args.push(target);
+        jcmd(&server, pid, &args, HEAP_DUMP_TIMEOUT).await?;
+
+        let path = diagnostics_dir(&server).join(&name);
+        let size = tokio::fs::metadata(&path)
+            .await
+            .map_err(|_| anyhow!("jcmd finished but {} was not written", name))?
+            .len();
+        ctx.progress(0.9, "quota", None).await;
+        self.enforce_quota(&server, &name).await;
+        info!("Heap dump of server {} written to {} ({} bytes)", server.id, path.display(), size);
+        Ok(name)
+    }
+
+    pub async fn gc_log(&self, server_id: &str) -> Result<GcLogStatus> {
+        let server = self.database.require_server(server_id).await?;
+        let running = self.pid(&server).await.is_ok();
+        let mut gc_logs = self.gc_logs.write().await;
+        if !running {
+            // Runtime logging settings end with the JVM
+            gc_logs.remove(server_id);
+        }
+        let file = gc_logs.get(server_id).cloned();
+        Ok(GcLogStatus { enabled: file.is_some(), file })
+    }
+
+    /// Switch GC logging of the running JVM on or off
+    pub async fn set_gc_log(&self, server_id: &str, enabled: bool) -> Result<GcLogStatus> {
+        let server = self.database.require_server(server_id).await?;
+        let pid = self.pid(&server).await?;
+        let mut gc_logs = self.gc_logs.write().await;
+        match (enabled, gc_logs.get(server_id).cloned()) {
+            (true, Some(_)) | (false, None) => {}
+            (true, None) => {
+                tokio::fs::create_dir_all(diagnostics_dir(&server)).await?;
+                let name = format!("gc-{}.log", Utc::now().format("%Y-%m-%d_%H.%M.%S"));
+                let args = [
+                    "VM.log".to_string(),
+                    format!("output=file={}/{}", DIAGNOSTICS_DIR, name),
+                    "what=gc*=info".to_string(),
+                    "decorators=time,uptime,level,tags".to_string(),
+                    format!("output_options=filecount={},filesize={}", GC_LOG_FILES, GC_LOG_FILE_SIZE),
+                ];
+                jcmd(&server, pid, &args, JCMD_TIMEOUT).await?;
+                gc_logs.insert(server.id.clone(), name);
+            }
+            (false, Some(name)) => {
+                // An output with every tag off is closed by the JVM
+                let args = ["VM.log".to_string(), format!("output=file={}/{}", DIAGNOSTICS_DIR, name), "what=all=off".to_string()];
+                jcmd(&server, pid, &args, JCMD_TIMEOUT).await?;
+                gc_logs.remove(server_id);
+            }
+        }
+        let file = gc_logs.get(server_id).cloned();
+        drop(gc_logs);
+        if !enabled {
+            self.enforce_quota(&server, "").await;
+        }
+        Ok(GcLogStatus { enabled: file.is_some(), file })
+    }
+
+    pub async fn open(&self, server_id: &str, name: &str) -> Result<(u64, tokio::fs::File)> {
+        let server = self.database.require_server(server_id).await?;
+        let path = file_path(&server, name)?;
+        let metadata = tokio::fs::metadata(&path).await.map_err(|_| anyhow!("{} not found", name))?;
+        if !metadata.is_file() {
+            bail!("{} not found", name);
+        }
+        Ok((metadata.len(), tokio::fs::File::open(&path).await?))
+    }
+
+    pub async fn delete(&self, server_id: &str, name: &str) -> Result<bool> {
+        let server = self.database.require_server(server_id).await?;
+        if self.gc_log(server_id).await?.file.is_some_and(|file| name.starts_with(&file)) {
+            bail!("Turn GC logging off before deleting its log");
+        }
+        let path = file_path(&server, name)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Remove the oldest captures past the quota, never `keep` or a GC log still written to
+    pub(crate) async fn enforce_quota(&self, server: &ServerConfig, keep: &str) {
+        let dir = diagnostics_dir(server);
+        let files = match list(&dir).await {
+            Ok(files) => files,
+            Err(e) => {
+                warn!("Failed to list diagnostics of server {}: {}", server.id, e);
+                return;
+            }
+        };
+        let mut protected: HashSet<&str> = HashSet::from([keep]);
+        let gc_logs = self.gc_logs.read().await;
+        if let Some(active) = gc_logs.get(&server.id) {
+            protected.extend(files.iter().filter(|file| file.name.starts_with(active.as_str())).map(|file| file.name.as_str()));
+        }
+        for name in over_quota(&files, self.quota_bytes, &protected) {
+            match tokio::fs::remove_file(dir.join(&name)).await {
+                Ok(()) => info!("Removed {} of server {} to stay within the diagnostics quota", name, server.id),
+                Err(e) => warn!("Failed to remove {} of server {}: {}", name, server.id, e),
+            }
+        }
+    }
+}
+
+/// A heap dump run as a `heap_dump` job
+struct HeapDumpJob {
+    manager: Arc<DiagnosticsManager>,
+    server_id: String,
+    all_objects: bool,
+}
+
+#[async_trait::async_trait]
+impl Job for HeapDumpJob {
+    fn kind(&self) -> &'static str {
+        "heap_dump"
+    }
+
+    fn server_id(&self) -> Option<String> {
+        Some(self.server_id.clone())
+    }
+
+    fn metadata(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({ "all_objects": self.all_objects }))
+    }
+
+    async fn run(self: Box<Self>, ctx: &JobContext) -> Result<Option<String>> {
+        let name = self.manager.write_heap_dump(&self.server_id, self.all_objects, ctx).await?;
+        Ok(Some(name))
+    }
+}
+
+pub fn diagnostics_dir(server: &ServerConfig) -> PathBuf {
+    server_dir(server).join(DIAGNOSTICS_DIR)
+}
+
+/// A file directly in the diagnostics folder
+fn file_path(server: &ServerConfig, name: &str) -> Result<PathBuf> {
+    check_name(name)?;
+    Ok(diagnostics_dir(server).join(name))
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\', ':']) {
+        bail!("Invalid file name {}", name);
+    }
+    Ok(())
+}
+
+async fn list(dir: &std::path::Path) -> Result<Vec<DiagnosticFile>> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut files = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        files.push(DiagnosticFile {
+            kind: DiagnosticKind::of(&name),
+            name,
+            size: metadata.len(),
+            modified: metadata.modified().map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now()),
+        });
+    }
+    Ok(files)
+}
+
+/// Oldest files to remove so the rest fit in `quota` bytes
+fn over_quota(files: &[DiagnosticFile], quota: u64, protected: &HashSet<&str>) -> Vec<String> {
+    let mut total: u64 = files.iter().map(|file| file.size).sum();
+    let mut oldest: Vec<&DiagnosticFile> = files.iter().filter(|file| !protected.contains(file.name.as_str())).collect();
+    oldest.sort_by_key(|file| file.modified);
+    let mut removed = Vec::new();
+    for file in oldest {
+        if total <= quota {
+            break;
+        }
+        total -= file.size;
+        removed.push(file.name.clone());
+    }
+    removed
+}
+
+/// Run a `jcmd` diagnostic command against `pid`, with the `jcmd` of the server's Java when there is one
+pub(crate) async fn jcmd(server: &ServerConfig, pid: u32, args: &[String], timeout: Duration) -> Result<String> {
+    let tool = crate::freeze_tickets::jdk_tool(server, "jcmd");
+    let output = tokio::process::Command::new(&tool).arg(pid.to_string()).args(args).kill_on_drop(true).output();
+    let output = tokio::time::timeout(timeout, output)
+        .await
+        .map_err(|_| anyhow!("{} timed out", tool.display()))?
+        .map_err(|e| anyhow!("{}: {}", tool.display(), e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    // jcmd reports most command errors on stdout with a zero exit code
+    if !output.status.success() || stdout.contains("Exception") || stdout.contains("Error:") {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{} {} failed: {}", tool.display(), args.join(" "), format!("{}{}", stdout, stderr).trim());
+    }
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, size: u64, age_minutes: i64) -> DiagnosticFile {
+        DiagnosticFile {
+            name: name.to_string(),
+            kind: DiagnosticKind::of(name),
+            size,
+            modified: Utc::now() - chrono::Duration::minutes(age_minutes),
+        }
+    }
+
+    #[test]
+    fn test_over_quota_removes_oldest_first() {
+        let files = vec![
+            file("heap-1.hprof", 600, 30),
+            file("gc-1.log", 100, 40),
+            file("thread-dump-1.txt", 10, 20),
+            file("heap-2.hprof", 500, 0),
+        ];
+        let protected = HashSet::from(["heap-2.hprof"]);
+        assert_eq!(over_quota(&files, 1000, &protected), vec!["gc-1.log", "heap-1.hprof"]);
+        assert!(over_quota(&files, 2000, &protected).is_empty());
+        // The newest capture is kept even when it alone is over the quota
+        assert_eq!(over_quota(&files, 100, &protected).len(), 3);
+        assert_eq!(files[2].kind, DiagnosticKind::ThreadDump);
+    }
+
+    #[test]
+    fn test_names_stay_in_folder() {
+        assert!(check_name("heap-1.hprof").is_ok());
+        for name in ["", "../server.properties", "a/b", "a\\b", ".hidden", "C:x"] {
+            assert!(check_name(name).is_err(), "{}", name);
+        }
+    }
+}
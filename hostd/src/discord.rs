@@ -0,0 +1,645 @@
+//! Discord integration
+//!
+//! An optional bot that keeps a status message up to date in one channel,
+//! posts players joining and leaving and crashes to others, and answers the
+//! `/status`, `/start` and `/stop` slash commands. Messages go out through
+//! Discord's REST API with the bot token, which is kept in the OS keychain
+//! when there is one. Slash commands arrive at `/api/discord/interactions`,
+//! the application's Interactions Endpoint URL, signed with the
+//! application's Ed25519 key. Starting and stopping servers is limited to
+//! members holding one of the configured roles.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+
+use crate::database::{DatabaseManager, DiscordSettings, EventLog};
+use crate::minecraft::{MinecraftManager, ServerStatus};
+
+const API_BASE: &str = "https://discord.com/api/v10";
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const STATUS_INTERVAL: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Discord allows at most 25 fields per embed
+const MAX_EMBED_FIELDS: usize = 25;
+const GREEN: u32 = 0x2ECC71;
+const RED: u32 = 0xE74C3C;
+
+pub const SIGNATURE_HEADER: &str = "x-signature-ed25519";
+pub const TIMESTAMP_HEADER: &str = "x-signature-timestamp";
+
+/// Event log types relayed to the player channel
+const PLAYER_EVENTS: [&str; 2] = ["player_joined", "player_left"];
+/// Event log types relayed to the alert channel
+const CRASH_EVENTS: [&str; 2] = ["server_crash", "crash_loop"];
+
+/// Settings as returned by the API
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscordSettingsInfo {
+    #[serde(flatten)]
+    pub settings: DiscordSettings,
+    pub bot_token_set: bool,
+}
+
+impl From<DiscordSettings> for DiscordSettingsInfo {
+    fn from(settings: DiscordSettings) -> Self {
+        Self { bot_token_set: !settings.bot_token.is_empty(), settings }
+    }
+}
+
+/// Changes to the settings. Empty strings clear optional fields; a left out
+/// bot token is kept.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DiscordSettingsUpdate {
+    pub enabled: Option<bool>,
+    pub application_id: Option<String>,
+    pub public_key: Option<String>,
+    pub bot_token: Option<String>,
+    pub guild_id: Option<String>,
+    pub status_channel_id: Option<String>,
+    pub player_channel_id: Option<String>,
+    pub alert_channel_id: Option<String>,
+    pub control_role_ids: Option<Vec<String>>,
+}
+
+/// An interaction as Discord POSTs it
+#[derive(Debug, Clone, Deserialize)]
+pub struct Interaction {
+    #[serde(rename = "type")]
+    pub kind: u8,
+    pub application_id: String,
+    pub token: String,
+    pub data: Option<InteractionData>,
+    /// Set for interactions in a guild
+    pub member: Option<Member>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InteractionData {
+    pub name: String,
+    #[serde(default)]
+    pub options: Vec<CommandOption>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandOption {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Member {
+    #[serde(default)]
+    pub roles: Vec<String>,
+    pub user: Option<User>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    pub id: String,
+    pub username: String,
+    /// Display name, when it differs from the username
+    #[serde(default)]
+    pub global_name: Option<String>,
+    #[serde(default)]
+    pub bot: bool,
+}
+
+/// A message read from a channel
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChannelMessage {
+    pub id: String,
+    #[serde(default)]
+    pub content: String,
+    pub author: User,
+    /// Set when a webhook posted the message
+    #[serde(default)]
+    pub webhook_id: Option<String>,
+}
+
+const INTERACTION_PING: u8 = 1;
+const INTERACTION_COMMAND: u8 = 2;
+const RESPONSE_PONG: u8 = 1;
+const RESPONSE_MESSAGE: u8 = 4;
+const RESPONSE_DEFERRED: u8 = 5;
+/// Only the member who ran the command sees the reply
+const FLAG_EPHEMERAL: u32 = 1 << 6;
+
+/// A slash command, with the server it names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Status,
+    Start(String),
+    Stop(String),
+}
+
+impl Interaction {
+    pub fn is_ping(&self) -> bool {
+        self.kind == INTERACTION_PING
+    }
+
+    pub fn command(&self) -> Result<Command> {
+        let data = self.data.as_ref().filter(|_| self.kind == INTERACTION_COMMAND).ok_or_else(|| anyhow!("Not a command"))?;
+        let server = || {
+            data.options
+                .iter()
+                .find(|option| option.name == "server")
+                .and_then(|option| option.value.as_str())
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("/{} needs a server", data.name))
+        };
+        match data.name.as_str() {
+            "status" => Ok(Command::Status),
+            "start" => Ok(Command::Start(server()?)),
+            "stop" => Ok(Command::Stop(server()?)),
+            other => bail!("Unknown command /{}", other),
+        }
+    }
+
+    /// Whether the member holds a role allowed to start and stop servers
+    pub fn can_control(&self, settings: &DiscordSettings) -> bool {
+        self.member
+            .as_ref()
+            .is_some_and(|member| member.roles.iter().any(|role| settings.control_role_ids.contains(role)))
+    }
+
+    pub fn username(&self) -> &str {
+        self.member.as_ref().and_then(|member| member.user.as_ref()).map_or("unknown", |user| user.username.as_str())
+    }
+}
+
+/// Answer to a ping
+pub fn pong() -> serde_json::Value {
+    serde_json::json!({ "type": RESPONSE_PONG })
+}
+
+/// Reply to a command, visible only to the member who ran it
+pub fn reply(content: &str) -> serde_json::Value {
+    serde_json::json!({ "type": RESPONSE_MESSAGE, "data": { "content": content, "flags": FLAG_EPHEMERAL } })
+}
+
+pub fn reply_embed(embed: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "type": RESPONSE_MESSAGE, "data": { "embeds": [embed] } })
+}
+
+/// Acknowledge a command whose outcome is edited in once known
+pub fn deferred() -> serde_json::Value {
+    serde_json::json!({ "type": RESPONSE_DEFERRED })
+}
+
+/// Slash command definitions, as registered with Discord
+fn command_definitions() -> serde_json::Value {
+    let server_option = serde_json::json!([{
+        "type": 3,
+        "name": "server",
+        "description": "Server name or ID",
+        "required": true,
+    }]);
+    serde_json::json!([
+        { "name": "status", "description": "Show the status of every server", "type": 1 },
+        { "name": "start", "description": "Start a server", "type": 1, "options": server_option },
+        { "name": "stop", "description": "Stop a server", "type": 1, "options": server_option },
+    ])
+}
+
+/// Check Discord's signature over `timestamp` followed by the body
+pub fn verify_signature(public_key: &str, signature: &str, timestamp: &str, body: &[u8]) -> bool {
+    let (Some(public_key), Some(signature)) = (decode_hex(public_key), decode_hex(signature)) else {
+        return false;
+    };
+    let mut message = timestamp.as_bytes().to_vec();
+    message.extend_from_slice(body);
+    UnparsedPublicKey::new(&ED25519, public_key).verify(&message, &signature).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+pub(crate) fn is_snowflake(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 20 && id.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// One line of the status message
+#[derive(Debug, Clone)]
+pub struct ServerLine {
+    pub name: String,
+    pub status: ServerStatus,
+    pub players_online: Option<u32>,
+    pub max_players: u32,
+    pub tps: Option<f64>,
+}
+
+/// The status message: one field per server, green while all that were
+/// started are running
+pub fn status_embed(servers: &[ServerLine], now: DateTime<Utc>) -> serde_json::Value {
+    let mut fields: Vec<serde_json::Value> = servers
+        .iter()
+        .take(MAX_EMBED_FIELDS)
+        .map(|server| {
+            let value = match server.status {
+                ServerStatus::Running => {
+                    let mut value = format!("🟢 Running · {}/{} players", server.players_online.unwrap_or(0), server.max_players);
+                    if let Some(tps) = server.tps.filter(|tps| *tps > 0.0) {
+                        value.push_str(&format!(" · {:.1} TPS", tps));
+                    }
+                    value
+                }
+                ServerStatus::Starting => "🟡 Starting".to_string(),
+                ServerStatus::Stopping => "🟡 Stopping".to_string(),
+                ServerStatus::Crashed => "🔴 Crashed".to_string(),
+                ServerStatus::Stopped => "⚫ Stopped".to_string(),
+                ServerStatus::Unknown => "❔ Unknown".to_string(),
+            };
+            serde_json::json!({ "name": server.name, "value": value, "inline": false })
+        })
+        .collect();
+    if servers.is_empty() {
+        fields.push(serde_json::json!({ "name": "No servers", "value": "Guardian isn't running any servers yet." }));
+    }
+    let crashed = servers.iter().any(|server| server.status == ServerStatus::Crashed);
+    let mut embed = serde_json::json!({
+        "title": "Server status",
+        "color": if crashed { RED } else { GREEN },
+        "fields": fields,
+        "timestamp": now.to_rfc3339(),
+    });
+    if servers.len() > MAX_EMBED_FIELDS {
+        embed["footer"] = serde_json::json!({ "text": format!("and {} more", servers.len() - MAX_EMBED_FIELDS) });
+    }
+    embed
+}
+
+pub struct DiscordManager {
+    database: Arc<DatabaseManager>,
+    minecraft: MinecraftManager,
+    client: reqwest::Client,
+    /// Events up to this time have been relayed
+    events_checked_until: RwLock<DateTime<Utc>>,
+}
+
+impl DiscordManager {
+    pub fn new(database: Arc<DatabaseManager>, minecraft: MinecraftManager) -> Self {
+        Self {
+            database,
+            minecraft,
+            client: reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+            events_checked_until: RwLock::new(Utc::now()),
+        }
+    }
+
+    pub async fn settings(&self) -> Result<DiscordSettings> {
+        self.database.get_discord_settings().await
+    }
+
+    /// Apply and save changes. Enabling the bot registers its slash commands
+    /// first, so a wrong token or application ID is reported rather than saved.
+    pub async fn update(&self, update: DiscordSettingsUpdate) -> Result<DiscordSettings> {
+        let mut settings = self.database.get_discord_settings().await?;
+        let previous = settings.clone();
+        let optional = |value: String| Some(value.trim().to_string()).filter(|value| !value.is_empty());
+        if let Some(enabled) = update.enabled {
+            settings.enabled = enabled;
+        }
+        if let Some(application_id) = update.application_id {
+            settings.application_id = optional(application_id);
+        }
+        if let Some(public_key) = update.public_key {
+            settings.public_key = optional(public_key).map(|key| key.to_lowercase());
+        }
+        if let Some(bot_token) = update.bot_token {
+            settings.bot_token = bot_token.trim().to_string();
+        }
+        if let Some(guild_id) = update.guild_id {
+            settings.guild_id = optional(guild_id);
+        }
+        if let Some(channel_id) = update.status_channel_id {
+            settings.status_channel_id = optional(channel_id);
+        }
+        if let Some(channel_id) = update.player_channel_id {
+            settings.player_channel_id = optional(channel_id);
+        }
+        if let Some(channel_id) = update.alert_channel_id {
+            settings.alert_channel_id = optional(channel_id);
+        }
+        if let Some(role_ids) = update.control_role_ids {
+            settings.control_role_ids = role_ids.into_iter().map(|id| id.trim().to_string()).collect();
+        }
+        if settings.status_channel_id != previous.status_channel_id {
+            settings.status_message_id = None;
+        }
+        validate(&settings)?;
+
+        if settings.enabled {
+            self.register_commands(&settings).await?;
+        }
+        settings.updated_at = Some(Utc::now());
+        self.database.save_discord_settings(&settings).await?;
+        info!("Updated Discord settings (enabled: {})", settings.enabled);
+        Ok(settings)
+    }
+
+    /// The server a command names, by ID or by name ignoring case
+    pub async fn find_server(&self, key: &str) -> Option<(String, String)> {
+        let servers = self.minecraft.get_all_servers().await;
+        servers
+            .iter()
+            .find(|server| server.id == key)
+            .or_else(|| servers.iter().find(|server| server.config.name.eq_ignore_ascii_case(key)))
+            .filter(|server| server.config.managed)
+            .map(|server| (server.id.clone(), server.config.name.clone()))
+    }
+
+    pub async fn server_lines(&self) -> Vec<ServerLine> {
+        let mut lines = Vec::new();
+        for server in self.minecraft.get_all_servers().await.into_iter().filter(|server| server.config.managed) {
+            let metrics = server.get_metrics().await.ok();
+            lines.push(ServerLine {
+                name: server.config.name.clone(),
+                status: server.status.clone(),
+                players_online: metrics.as_ref().map(|metrics| metrics.players_online),
+                max_players: server.config.max_players,
+                tps: metrics.as_ref().map(|metrics| metrics.tps),
+            });
+        }
+        lines.sort_by_key(|line| line.name.to_lowercase());
+        lines
+    }
+
+    /// Replace the reply to a deferred command
+    pub async fn edit_reply(&self, interaction: &Interaction, content: &str) -> Result<()> {
+        let url = format!("{}/webhooks/{}/{}/messages/@original", API_BASE, interaction.application_id, interaction.token);
+        let response = self.client.patch(url).json(&serde_json::json!({ "content": content })).send().await?;
+        if !response.status().is_success() {
+            bail!("Discord returned {} editing a reply", response.status());
+        }
+        Ok(())
+    }
+
+    /// Post a message to a channel as the bot
+    pub async fn send_message(&self, channel_id: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+        let settings = self.active_settings().await?;
+        self.post(&settings, channel_id, body).await
+    }
+
+    /// Up to 50 messages in a channel, oldest first. With no `after`, only
+    /// the latest message is returned, to find where to start reading.
+    pub async fn messages_after(&self, channel_id: &str, after: Option<&str>) -> Result<Vec<ChannelMessage>> {
+        let settings = self.active_settings().await?;
+        let url = match after {
+            Some(after) => format!("{}/channels/{}/messages?after={}&limit=50", API_BASE, channel_id, after),
+            None => format!("{}/channels/{}/messages?limit=1", API_BASE, channel_id),
+        };
+        let response = self.client.get(url).header("Authorization", bot_auth(&settings)).send().await?;
+        if !response.status().is_success() {
+            bail!("Discord returned {} reading channel {}", response.status(), channel_id);
+        }
+        let mut messages: Vec<ChannelMessage> = response.json().await?;
+        // Discord lists the newest first
+        messages.reverse();
+        Ok(messages)
+    }
+
+    async fn active_settings(&self) -> Result<DiscordSettings> {
+        let settings = self.database.get_discord_settings().await?;
+        if !settings.enabled || settings.bot_token.is_empty() {
+            bail!("The Discord bot is not enabled");
+        }
+        Ok(settings)
+    }
+
+    /// Relay events and refresh the status message while the bot is enabled
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting Discord integration");
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut last_status: Option<tokio::time::Instant> = None;
+        loop {
+            interval.tick().await;
+            let settings = match self.database.get_discord_settings().await {
+                Ok(settings) if settings.enabled && !settings.bot_token.is_empty() => settings,
+                Ok(_) => {
+                    *self.events_checked_until.write().await = Utc::now();
+                    continue;
+                }
+                Err(e) => {
+                    error!("Failed to load Discord settings: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = self.relay_events(&settings).await {
+                error!("Failed to relay events to Discord: {}", e);
+            }
+            if settings.status_channel_id.is_some() && last_status.is_none_or(|at| at.elapsed() >= STATUS_INTERVAL) {
+                last_status = Some(tokio::time::Instant::now());
+                if let Err(e) = self.update_status(&settings).await {
+                    warn!("Failed to update the Discord status message: {}", e);
+                }
+            }
+        }
+    }
+
+    async fn relay_events(&self, settings: &DiscordSettings) -> Result<()> {
+        let since = *self.events_checked_until.read().await;
+        let event_types: Vec<&str> = PLAYER_EVENTS.iter().chain(CRASH_EVENTS.iter()).copied().collect();
+        let events = self.database.get_events_since(&event_types, since).await?;
+        let Some(last) = events.last() else {
+            return Ok(());
+        };
+        *self.events_checked_until.write().await = last.created_at;
+
+        for event in events {
+            let server_name = self.server_name(&event).await;
+            if PLAYER_EVENTS.contains(&event.event_type.as_str()) {
+                let Some(channel_id) = &settings.player_channel_id else {
+                    continue;
+                };
+                let player = event.metadata.as_ref().and_then(|metadata| metadata["player"].as_str()).unwrap_or("A player");
+                let verb = if event.event_type == "player_joined" { "joined" } else { "left" };
+                let content = format!("**{}** {} *{}*", escape(player), verb, escape(&server_name));
+                self.post(settings, channel_id, &serde_json::json!({ "content": content })).await?;
+            } else if let Some(channel_id) = &settings.alert_channel_id {
+                let embed = serde_json::json!({
+                    "title": format!("{} crashed", server_name),
+                    "description": event.message,
+                    "color": RED,
+                    "timestamp": event.created_at.to_rfc3339(),
+                });
+                self.post(settings, channel_id, &serde_json::json!({ "embeds": [embed] })).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn server_name(&self, event: &EventLog) -> String {
+        let Some(server_id) = event.server_id.as_deref() else {
+            return "Unknown server".to_string();
+        };
+        match self.minecraft.get_server(server_id).await {
+            Some(server) => server.config.name,
+            None => server_id.to_string(),
+        }
+    }
+
+    /// Edit the status message, posting a new one if there is none yet or it was deleted
+    async fn update_status(&self, settings: &DiscordSettings) -> Result<()> {
+        let Some(channel_id) = &settings.status_channel_id else {
+            return Ok(());
+        };
+        let body = serde_json::json!({ "embeds": [status_embed(&self.server_lines().await, Utc::now())] });
+        if let Some(message_id) = &settings.status_message_id {
+            let url = format!("{}/channels/{}/messages/{}", API_BASE, channel_id, message_id);
+            let response = self.client.patch(url).header("Authorization", bot_auth(settings)).json(&body).send().await?;
+            if response.status().is_success() {
+                return Ok(());
+            }
+            if response.status() != reqwest::StatusCode::NOT_FOUND {
+                bail!("Discord returned {} editing the status message", response.status());
+            }
+        }
+        let message = self.post(settings, channel_id, &body).await?;
+        let message_id = message["id"].as_str().ok_or_else(|| anyhow!("Discord returned no message ID"))?;
+        self.database.set_discord_status_message(Some(message_id)).await
+    }
+
+    async fn post(&self, settings: &DiscordSettings, channel_id: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+        let url = format!("{}/channels/{}/messages", API_BASE, channel_id);
+        let response = self.client.post(url).header("Authorization", bot_auth(settings)).json(body).send().await?;
+        if !response.status().is_success() {
+            bail!("Discord returned {} posting to channel {}", response.status(), channel_id);
+        }
+        Ok(response.json().await?)
+    }
+
+    async fn register_commands(&self, settings: &DiscordSettings) -> Result<()> {
+        let application_id = settings.application_id.as_deref().unwrap_or_default();
+        let url = match &settings.guild_id {
+            Some(guild_id) => format!("{}/applications/{}/guilds/{}/commands", API_BASE, application_id, guild_id),
+            None => format!("{}/applications/{}/commands", API_BASE, application_id),
+        };
+        let response = self
+            .client
+            .put(url)
+            .header("Authorization", bot_auth(settings))
+            .json(&command_definitions())
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            bail!("Discord rejected the slash commands ({}): {}", status, text.trim());
+        }
+        Ok(())
+    }
+}
+
+fn bot_auth(settings: &DiscordSettings) -> String {
+    format!("Bot {}", settings.bot_token)
+}
+
+/// Keep names from being read as Markdown
+pub(crate) fn escape(text: &str) -> String {
+    text.chars().fold(String::new(), |mut escaped, c| {
+        if matches!(c, '*' | '_' | '~' | '`' | '|' | '>' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        escaped
+    })
+}
+
+fn validate(settings: &DiscordSettings) -> Result<()> {
+    let ids = [
+        ("application_id", &settings.application_id),
+        ("guild_id", &settings.guild_id),
+        ("status_channel_id", &settings.status_channel_id),
+        ("player_channel_id", &settings.player_channel_id),
+        ("alert_channel_id", &settings.alert_channel_id),
+    ];
+    for (field, id) in ids {
+        if id.as_deref().is_some_and(|id| !is_snowflake(id)) {
+            bail!("{} must be a Discord ID", field);
+        }
+    }
+    if let Some(role_id) = settings.control_role_ids.iter().find(|id| !is_snowflake(id)) {
+        bail!("Invalid role ID '{}'", role_id);
+    }
+    if settings.public_key.as_deref().is_some_and(|key| decode_hex(key).is_none_or(|key| key.len() != 32)) {
+        bail!("public_key must be the application's 64 character hex public key");
+    }
+    if settings.enabled {
+        if settings.bot_token.is_empty() {
+            bail!("A bot token is required");
+        }
+        if settings.application_id.is_none() || settings.public_key.is_none() {
+            bail!("The application ID and public key are required");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_signature_verification() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let body = br#"{"type":1}"#;
+        let signature = hex(pair.sign(&[b"1700000000".as_slice(), body].concat()).as_ref());
+        let public_key = hex(pair.public_key().as_ref());
+
+        assert!(verify_signature(&public_key, &signature, "1700000000", body));
+        assert!(!verify_signature(&public_key, &signature, "1700000001", body));
+        assert!(!verify_signature(&public_key, "zz", "1700000000", body));
+    }
+
+    #[test]
+    fn test_commands_and_role_check() {
+        let interaction: Interaction = serde_json::from_value(serde_json::json!({
+            "type": 2,
+            "application_id": "1",
+            "token": "t",
+            "data": { "name": "start", "options": [{ "name": "server", "type": 3, "value": "Survival" }] },
+            "member": { "roles": ["42"], "user": { "id": "7", "username": "steve" } },
+        }))
+        .unwrap();
+        assert_eq!(interaction.command().unwrap(), Command::Start("Survival".to_string()));
+
+        let mut settings = DiscordSettings::default();
+        assert!(!interaction.can_control(&settings));
+        settings.control_role_ids = vec!["42".to_string()];
+        assert!(interaction.can_control(&settings));
+    }
+
+    #[test]
+    fn test_validation_and_status_embed() {
+        let mut settings = DiscordSettings { enabled: true, ..Default::default() };
+        assert!(validate(&settings).is_err());
+        settings.bot_token = "token".to_string();
+        settings.application_id = Some("123456789012345678".to_string());
+        settings.public_key = Some("ab".repeat(32));
+        assert!(validate(&settings).is_ok());
+        settings.alert_channel_id = Some("#alerts".to_string());
+        assert!(validate(&settings).is_err());
+
+        let line = |name: &str, status| ServerLine { name: name.to_string(), status, players_online: Some(3), max_players: 20, tps: Some(19.5) };
+        let embed = status_embed(&[line("Survival", ServerStatus::Running), line("Lobby", ServerStatus::Crashed)], Utc::now());
+        assert_eq!(embed["fields"][0]["value"], "🟢 Running · 3/20 players · 19.5 TPS");
+        assert_eq!(embed["color"], RED);
+        assert_eq!(escape("_Steve_"), "\\_Steve\\_");
+    }
+}
@@ -0,0 +1,365 @@
+//! Download manager
+//!
+//! Server jars, loader installers, Java runtimes and mods are fetched through
+//! one shared [`DownloadManager`]. It bounds how many downloads run at once,
+//! retries failures with exponential backoff, and resumes an interrupted
+//! download with a range request instead of starting over. When the provider
+//! published a hash the file is checked against it and kept in a cache keyed
+//! by that hash, so a jar or mod used by several servers is downloaded once.
+//!
+//! Everything lives under `<data_dir>/downloads`: `partial/` holds downloads
+//! in progress and `cache/<algorithm>/<hash>` the verified files.
+
+use anyhow::{anyhow, bail, Context, Result};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+use tracing::{debug, info, warn};
+
+/// Downloads running at once, across all features
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+const MAX_ATTEMPTS: u32 = 4;
+/// Wait before the first retry; doubled for each one after
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest the server may go without sending anything
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A hash published for a file, as lowercase hex
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+    Sha1(String),
+    Sha256(String),
+    Sha512(String),
+}
+
+impl Checksum {
+    /// The strongest of the hashes a provider lists by algorithm name, as
+    /// Modrinth does
+    pub fn from_hashes(hashes: &HashMap<String, String>) -> Option<Self> {
+        if let Some(hash) = hashes.get("sha512") {
+            return Some(Self::Sha512(hash.to_ascii_lowercase()));
+        }
+        hashes.get("sha1").map(|hash| Self::Sha1(hash.to_ascii_lowercase()))
+    }
+
+    fn algorithm(&self) -> &'static str {
+        match self {
+            Self::Sha1(_) => "sha1",
+            Self::Sha256(_) => "sha256",
+            Self::Sha512(_) => "sha512",
+        }
+    }
+
+    fn hex(&self) -> &str {
+        match self {
+            Self::Sha1(hex) | Self::Sha256(hex) | Self::Sha512(hex) => hex,
+        }
+    }
+
+    /// Hash of the file at `path` with this checksum's algorithm
+    fn digest_file(&self, path: &Path) -> Result<String> {
+        match self {
+            Self::Sha1(_) => digest::<Sha1>(path),
+            Self::Sha256(_) => digest::<Sha256>(path),
+            Self::Sha512(_) => digest::<Sha512>(path),
+        }
+    }
+}
+
+/// SHA-1 of the file at `path`, as lowercase hex
+pub fn sha1_file(path: &Path) -> Result<String> {
+    digest::<Sha1>(path)
+}
+
+fn digest<D: Digest>(path: &Path) -> Result<String>
+where
+    sha2::digest::Output<D>: std::fmt::LowerHex,
+{
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = D::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// A file to download
+#[derive(Debug, Clone)]
+pub struct Download {
+    pub url: String,
+    pub checksum: Option<Checksum>,
+}
+
+impl Download {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), checksum: None }
+    }
+
+    pub fn checksum(mut self, checksum: Option<Checksum>) -> Self {
+        self.checksum = checksum;
+        self
+    }
+}
+
+/// Why an attempt failed, and whether another one may succeed
+enum Failure {
+    Retry(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+pub struct DownloadManager {
+    client: reqwest::Client,
+    dir: PathBuf,
+    connections: Semaphore,
+    /// One lock per URL, so two callers never write the same partial file
+    in_flight: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+static SHARED: OnceLock<DownloadManager> = OnceLock::new();
+
+/// Keep the shared manager's files in `dir`; called once at startup, before
+/// anything is downloaded
+pub fn init(dir: PathBuf) {
+    if SHARED.set(DownloadManager::new(dir)).is_err() {
+        warn!("The download manager was already in use before it was set up");
+    }
+}
+
+/// The manager every download goes through, so its limits hold for all of them
+pub fn shared() -> &'static DownloadManager {
+    SHARED.get_or_init(|| DownloadManager::new(PathBuf::from("data").join("downloads")))
+}
+
+impl DownloadManager {
+    pub fn new(dir: PathBuf) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .user_agent(concat!("Guardian/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            dir,
+            connections: Semaphore::new(MAX_CONCURRENT_DOWNLOADS),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn cache_path(&self, checksum: &Checksum) -> PathBuf {
+        self.dir.join("cache").join(checksum.algorithm()).join(checksum.hex())
+    }
+
+    fn partial_path(&self, url: &str) -> PathBuf {
+        self.dir.join("partial").join(format!("{:x}.part", Sha256::digest(url.as_bytes())))
+    }
+
+    /// Download into `dest`, replacing any file there
+    pub async fn fetch_to(&self, download: &Download, dest: &Path) -> Result<()> {
+        let (file, cached) = self.obtain(download).await?;
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        if cached || tokio::fs::rename(&file, dest).await.is_err() {
+            tokio::fs::copy(&file, dest)
+                .await
+                .with_context(|| format!("Failed to write {}", dest.display()))?;
+            if !cached {
+                let _ = tokio::fs::remove_file(&file).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Download into memory
+    pub async fn fetch(&self, download: &Download) -> Result<Vec<u8>> {
+        let (file, cached) = self.obtain(download).await?;
+        let bytes = tokio::fs::read(&file).await?;
+        if !cached {
+            let _ = tokio::fs::remove_file(&file).await;
+        }
+        Ok(bytes)
+    }
+
+    /// The downloaded file, and whether it is the cached copy (which must be
+    /// left in place) rather than a finished partial file
+    async fn obtain(&self, download: &Download) -> Result<(PathBuf, bool)> {
+        if let Some(cached) = self.cached(download) {
+            return Ok((cached, true));
+        }
+
+        let lock = self.in_flight.lock().unwrap().entry(download.url.clone()).or_default().clone();
+        let guard = lock.lock().await;
+        // Someone else may have downloaded it while this waited
+        let result = match self.cached(download) {
+            Some(cached) => Ok((cached, true)),
+            None => self.download(download).await,
+        };
+        drop(guard);
+        {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            // Only the map and this call still hold it
+            if Arc::strong_count(&lock) == 2 {
+                in_flight.remove(&download.url);
+            }
+        }
+        result
+    }
+
+    fn cached(&self, download: &Download) -> Option<PathBuf> {
+        let path = self.cache_path(download.checksum.as_ref()?);
+        path.is_file().then(|| {
+            debug!("Using cached {}", download.url);
+            path
+        })
+    }
+
+    async fn download(&self, download: &Download) -> Result<(PathBuf, bool)> {
+        let partial = self.partial_path(&download.url);
+        if let Some(parent) = partial.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let _connection = self.connections.acquire().await?;
+
+        let mut attempt = 1;
+        loop {
+            let failure = match self.attempt(&download.url, &partial).await {
+                Ok(()) => match self.verify(download, &partial).await {
+                    Ok(()) => break,
+                    // A corrupt resumed download never verifies, so start it over
+                    Err(e) => {
+                        let _ = tokio::fs::remove_file(&partial).await;
+                        Failure::Retry(e)
+                    }
+                },
+                Err(failure) => failure,
+            };
+            match failure {
+                Failure::Retry(e) if attempt < MAX_ATTEMPTS => {
+                    let backoff = INITIAL_BACKOFF * 2u32.pow(attempt - 1);
+                    warn!("Download of {} failed (attempt {}), retrying in {:?}: {:#}", download.url, attempt, backoff, e);
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                Failure::Retry(e) | Failure::Fatal(e) => {
+                    return Err(e.context(format!("Failed to download {}", download.url)));
+                }
+            }
+        }
+
+        match &download.checksum {
+            Some(checksum) => {
+                let cached = self.cache_path(checksum);
+                if let Some(parent) = cached.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::rename(&partial, &cached).await?;
+                info!("Downloaded {}", download.url);
+                Ok((cached, true))
+            }
+            None => {
+                // Out of the way of the next download of the same URL
+                let done = partial.with_file_name(format!("{}.done", uuid::Uuid::new_v4()));
+                tokio::fs::rename(&partial, &done).await?;
+                info!("Downloaded {} (no checksum published)", download.url);
+                Ok((done, false))
+            }
+        }
+    }
+
+    /// Fetch `url` into `partial`, continuing from what is already there
+    async fn attempt(&self, url: &str, partial: &Path) -> Result<(), Failure> {
+        let offset = tokio::fs::metadata(partial).await.map(|metadata| metadata.len()).unwrap_or(0);
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request.send().await.map_err(|e| Failure::Retry(e.into()))?;
+
+        let status = response.status();
+        let resume = offset > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT;
+        if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            let _ = tokio::fs::remove_file(partial).await;
+            return Err(Failure::Retry(anyhow!("The server could not resume the download")));
+        }
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(Failure::Retry(anyhow!("HTTP {}", status)));
+        }
+        if !status.is_success() {
+            return Err(Failure::Fatal(anyhow!("HTTP {}", status)));
+        }
+        if resume {
+            debug!("Resuming {} at byte {}", url, offset);
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resume)
+            .truncate(!resume)
+            .open(partial)
+            .await
+            .map_err(|e| Failure::Fatal(e.into()))?;
+        loop {
+            let chunk = tokio::time::timeout(READ_TIMEOUT, response.chunk())
+                .await
+                .map_err(|_| Failure::Retry(anyhow!("No data for {:?}", READ_TIMEOUT)))?
+                .map_err(|e| Failure::Retry(e.into()))?;
+            let Some(chunk) = chunk else {
+                break;
+            };
+            file.write_all(&chunk).await.map_err(|e| Failure::Fatal(e.into()))?;
+        }
+        file.flush().await.map_err(|e| Failure::Fatal(e.into()))?;
+        Ok(())
+    }
+
+    async fn verify(&self, download: &Download, partial: &Path) -> Result<()> {
+        let Some(checksum) = download.checksum.clone() else {
+            return Ok(());
+        };
+        let path = partial.to_path_buf();
+        let actual = {
+            let checksum = checksum.clone();
+            tokio::task::spawn_blocking(move || checksum.digest_file(&path)).await??
+        };
+        if !actual.eq_ignore_ascii_case(checksum.hex()) {
+            bail!("{} mismatch: expected {}, got {}", checksum.algorithm(), checksum.hex(), actual);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        let hashes = HashMap::from([
+            ("sha1".to_string(), "ABC".to_string()),
+            ("sha512".to_string(), "def".to_string()),
+        ]);
+        assert_eq!(Checksum::from_hashes(&hashes), Some(Checksum::Sha512("def".to_string())));
+        assert_eq!(Checksum::from_hashes(&HashMap::from([("sha1".to_string(), "ABC".to_string())])), Some(Checksum::Sha1("abc".to_string())));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        std::fs::write(&path, b"guardian").unwrap();
+        let sha1 = format!("{:x}", Sha1::digest(b"guardian"));
+        assert_eq!(Checksum::Sha1(sha1.clone()).digest_file(&path).unwrap(), sha1);
+        let manager = DownloadManager::new(dir.path().to_path_buf());
+        assert_eq!(manager.cache_path(&Checksum::Sha1(sha1.clone())), dir.path().join("cache").join("sha1").join(sha1));
+    }
+}
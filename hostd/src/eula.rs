@@ -0,0 +1,86 @@
+//! Minecraft EULA acceptance
+//!
+//! A server exits right away unless `eula.txt` in its directory says
+//! `eula=true`. Guardian only writes that when the user accepts the EULA, or
+//! when the first-run setting `accept_eula_by_default` is on; otherwise
+//! starting the server fails with [`REQUIRED_CODE`] so the UI can ask.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+use tracing::info;
+
+use crate::database::DatabaseManager;
+
+/// Where the EULA can be read
+pub const EULA_URL: &str = "https://aka.ms/MinecraftEULA";
+
+/// Error code of a start refused because the EULA was not accepted
+pub const REQUIRED_CODE: &str = "eula_required";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EulaStatus {
+    Accepted,
+    /// eula.txt exists but does not accept the EULA
+    Pending,
+    Missing,
+}
+
+pub async fn status(server_dir: &Path) -> Result<EulaStatus> {
+    match tokio::fs::read_to_string(server_dir.join("eula.txt")).await {
+        Ok(content) if accepts(&content) => Ok(EulaStatus::Accepted),
+        Ok(_) => Ok(EulaStatus::Pending),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(EulaStatus::Missing),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn accept(server_dir: &Path) -> Result<()> {
+    let content = format!(
+        "# EULA accepted by Guardian on {}\n# {}\neula=true\n",
+        chrono::Utc::now().to_rfc3339(),
+        EULA_URL
+    );
+    tokio::fs::write(server_dir.join("eula.txt"), content).await?;
+    Ok(())
+}
+
+/// Whether the server may start: the EULA is accepted, or is accepted now
+/// because the settings accept it by default
+pub async fn ready_to_start(database: Option<&DatabaseManager>, server_dir: &Path) -> Result<bool> {
+    if status(server_dir).await? == EulaStatus::Accepted {
+        return Ok(true);
+    }
+    let by_default = match database {
+        Some(database) => database.get_settings().await?.is_some_and(|settings| settings.accept_eula_by_default),
+        None => false,
+    };
+    if by_default {
+        info!("Accepting the EULA in {} as set up in the settings", server_dir.display());
+        accept(server_dir).await?;
+    }
+    Ok(by_default)
+}
+
+fn accepts(content: &str) -> bool {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .any(|(key, value)| key.trim() == "eula" && value.trim().eq_ignore_ascii_case("true"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts() {
+        assert!(accepts("#By changing the setting below to TRUE you are indicating your agreement\neula=true\n"));
+        assert!(accepts("eula = TRUE"));
+        assert!(!accepts("eula=false\n"));
+        assert!(!accepts("#eula=true\neula=false\n"));
+    }
+}
@@ -0,0 +1,58 @@
+//! Global event bus
+//!
+//! Every live message for clients goes through one bus: each WebSocketManager
+//! publishes into it, and WebSocket connections and the `/api/events/stream`
+//! SSE route subscribe to it. The bus keeps the last messages of each topic
+//! for replay.
+
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+
+use crate::websocket_manager::{ReplayBuffer, WebSocketMessage};
+
+/// Messages a slow subscriber may fall behind by before it skips some
+const CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<WebSocketMessage>,
+    replay: Arc<RwLock<ReplayBuffer>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self {
+            sender,
+            replay: Arc::new(RwLock::new(ReplayBuffer::default())),
+        }
+    }
+
+    /// Send a message to every subscriber and keep it for replay. Having no
+    /// subscribers is not an error.
+    pub async fn publish(&self, message: WebSocketMessage) {
+        if let Some(topic) = message.topic() {
+            self.replay.write().await.record(topic, message.clone());
+        }
+        let _ = self.sender.send(message);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<WebSocketMessage> {
+        self.sender.subscribe()
+    }
+
+    pub fn sender(&self) -> broadcast::Sender<WebSocketMessage> {
+        self.sender.clone()
+    }
+
+    /// The last `count` messages of every topic covered by `subscriptions`, oldest first
+    pub async fn replay(&self, subscriptions: &[String], count: usize) -> Vec<WebSocketMessage> {
+        self.replay.read().await.replay(subscriptions, count)
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
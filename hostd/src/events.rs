@@ -0,0 +1,427 @@
+//! Event feed
+//!
+//! A typed view of `event_logs`: each event has a kind, a category and a
+//! severity, and is read newest first in pages that continue from an opaque
+//! cursor, filtered by server, kind, category, severity and time range. Events
+//! past the retention set in settings are deleted once an hour.
+
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration as ChronoDuration, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info};
+
+use crate::database::{DatabaseManager, EventLog};
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 500;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Event types logged by hostd
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    ServerStart,
+    ServerStarted,
+    ServerStop,
+    ServerStopped,
+    ServerRestart,
+    ServerCrash,
+    CrashLoop,
+    PolicyRestart,
+    ScheduledRestart,
+    ScheduledCommand,
+    WorldBorder,
+    AutoStart,
+    Console,
+    BackupCompleted,
+    BackupFailed,
+    PlayerJoined,
+    PlayerLeft,
+    BanExpired,
+    Alert,
+}
+
+pub const KINDS: [EventKind; 19] = [
+    EventKind::ServerStart,
+    EventKind::ServerStarted,
+    EventKind::ServerStop,
+    EventKind::ServerStopped,
+    EventKind::ServerRestart,
+    EventKind::ServerCrash,
+    EventKind::CrashLoop,
+    EventKind::PolicyRestart,
+    EventKind::ScheduledRestart,
+    EventKind::ScheduledCommand,
+    EventKind::WorldBorder,
+    EventKind::AutoStart,
+    EventKind::Console,
+    EventKind::BackupCompleted,
+    EventKind::BackupFailed,
+    EventKind::PlayerJoined,
+    EventKind::PlayerLeft,
+    EventKind::BanExpired,
+    EventKind::Alert,
+];
+
+impl EventKind {
+    pub fn parse(event_type: &str) -> Option<Self> {
+        KINDS.into_iter().find(|kind| kind.as_str() == event_type)
+    }
+
+    /// The `event_type` it is logged as
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ServerStart => "server_start",
+            Self::ServerStarted => "server_started",
+            Self::ServerStop => "server_stop",
+            Self::ServerStopped => "server_stopped",
+            Self::ServerRestart => "server_restart",
+            Self::ServerCrash => "server_crash",
+            Self::CrashLoop => "crash_loop",
+            Self::PolicyRestart => "policy_restart",
+            Self::ScheduledRestart => "scheduled_restart",
+            Self::ScheduledCommand => "scheduled_command",
+            Self::WorldBorder => "world_border",
+            Self::AutoStart => "auto_start",
+            Self::Console => "console",
+            Self::BackupCompleted => "backup_completed",
+            Self::BackupFailed => "backup_failed",
+            Self::PlayerJoined => "player_joined",
+            Self::PlayerLeft => "player_left",
+            Self::BanExpired => "ban_expired",
+            Self::Alert => "alert",
+        }
+    }
+
+    pub fn category(&self) -> EventCategory {
+        match self {
+            Self::ServerStart
+            | Self::ServerStarted
+            | Self::ServerStop
+            | Self::ServerStopped
+            | Self::ServerRestart
+            | Self::PolicyRestart
+            | Self::ScheduledRestart
+            | Self::AutoStart => EventCategory::Lifecycle,
+            Self::ServerCrash | Self::CrashLoop => EventCategory::Crash,
+            Self::Console | Self::ScheduledCommand | Self::WorldBorder => EventCategory::Console,
+            Self::BackupCompleted | Self::BackupFailed => EventCategory::Backup,
+            Self::PlayerJoined | Self::PlayerLeft | Self::BanExpired => EventCategory::Player,
+            Self::Alert => EventCategory::Alert,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventCategory {
+    Lifecycle,
+    Crash,
+    Console,
+    Backup,
+    Player,
+    Alert,
+    /// Event types hostd doesn't know, such as ones logged by plugins
+    Other,
+}
+
+impl EventCategory {
+    pub fn parse(category: &str) -> Result<Self> {
+        match category {
+            "lifecycle" => Ok(Self::Lifecycle),
+            "crash" => Ok(Self::Crash),
+            "console" => Ok(Self::Console),
+            "backup" => Ok(Self::Backup),
+            "player" => Ok(Self::Player),
+            "alert" => Ok(Self::Alert),
+            "other" => Ok(Self::Other),
+            other => bail!("Unknown event category: {}", other),
+        }
+    }
+
+    pub fn of(event_type: &str) -> Self {
+        EventKind::parse(event_type).map_or(Self::Other, |kind| kind.category())
+    }
+
+    /// Event types in this category; empty for `Other`
+    pub fn event_types(&self) -> Vec<&'static str> {
+        KINDS.iter().filter(|kind| kind.category() == *self).map(|kind| kind.as_str()).collect()
+    }
+}
+
+/// A player joining or leaving, read from a server console line such as
+/// `[12:00:00] [Server thread/INFO]: Steve joined the game`. Chat lines can't
+/// pass for one, since the message would have to start with a bare name.
+pub fn player_event(line: &str) -> Option<(EventKind, &str)> {
+    let message = line.rsplit_once("]: ").map_or(line, |(_, message)| message).trim_end();
+    let (kind, player) = if let Some(player) = message.strip_suffix(" joined the game") {
+        (EventKind::PlayerJoined, player)
+    } else {
+        (EventKind::PlayerLeft, message.strip_suffix(" left the game")?)
+    };
+    let valid = (1..=16).contains(&player.len())
+        && player.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'.');
+    valid.then_some((kind, player))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Severity {
+    pub fn parse(level: &str) -> Result<Self> {
+        match level {
+            "info" => Ok(Self::Info),
+            "warn" | "warning" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            other => bail!("Unknown severity: {}", other),
+        }
+    }
+
+    /// Severity of a logged `level`; anything unrecognised counts as info
+    pub fn of(level: &str) -> Self {
+        Self::parse(&level.to_ascii_lowercase()).unwrap_or(Self::Info)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+
+    /// Logged levels at this severity or above
+    pub fn levels_from(&self) -> Vec<&'static str> {
+        let mut levels = Vec::new();
+        if *self <= Self::Info {
+            levels.push("info");
+        }
+        if *self <= Self::Warn {
+            levels.extend(["warn", "warning"]);
+        }
+        levels.push("error");
+        levels
+    }
+}
+
+/// Feed filters as given in the query string
+#[derive(Debug, Default, Deserialize)]
+pub struct EventQuery {
+    pub server_id: Option<String>,
+    /// Comma-separated event types
+    #[serde(rename = "type")]
+    pub types: Option<String>,
+    pub category: Option<String>,
+    /// Lowest severity to include
+    pub severity: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// Checked feed filters, as used for the database query
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub server_id: Option<String>,
+    /// Only events of these servers, when not empty; set for users limited to
+    /// some servers, and leaves out events not tied to a server
+    pub server_ids: Vec<String>,
+    /// Only these event types, when not empty
+    pub event_types: Vec<String>,
+    pub category: Option<EventCategory>,
+    /// Only these levels, when not empty
+    pub levels: Vec<&'static str>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Only events older than this position
+    pub before: Option<(DateTime<Utc>, String)>,
+    pub limit: u32,
+}
+
+impl EventQuery {
+    pub fn filter(&self) -> Result<EventFilter> {
+        if let (Some(since), Some(until)) = (self.since, self.until) {
+            if since > until {
+                bail!("since must not be after until");
+            }
+        }
+        let event_types = self
+            .types
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|event_type| !event_type.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok(EventFilter {
+            server_id: self.server_id.clone(),
+            server_ids: Vec::new(),
+            event_types,
+            category: self.category.as_deref().map(EventCategory::parse).transpose()?,
+            levels: match &self.severity {
+                Some(severity) => Severity::parse(severity)?.levels_from(),
+                None => Vec::new(),
+            },
+            since: self.since,
+            until: self.until,
+            before: self.cursor.as_deref().map(decode_cursor).transpose()?,
+            limit: self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+        })
+    }
+}
+
+/// A logged event as shown in the feed
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedEvent {
+    pub id: String,
+    pub server_id: Option<String>,
+    /// The event type as logged
+    pub kind: String,
+    pub category: EventCategory,
+    pub severity: Severity,
+    pub message: String,
+    pub metadata: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<EventLog> for FeedEvent {
+    fn from(event: EventLog) -> Self {
+        Self {
+            category: EventCategory::of(&event.event_type),
+            severity: Severity::of(&event.level),
+            id: event.id,
+            server_id: event.server_id,
+            kind: event.event_type,
+            message: event.message,
+            metadata: event.metadata,
+            created_at: event.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventPage {
+    pub events: Vec<FeedEvent>,
+    /// Pass as `cursor` for the next, older page; absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+pub fn encode_cursor(created_at: DateTime<Utc>, id: &str) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}|{}", created_at.to_rfc3339_opts(SecondsFormat::Nanos, true), id))
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<(DateTime<Utc>, String)> {
+    let invalid = || anyhow!("Invalid cursor");
+    let decoded = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+    let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+    let (created_at, id) = decoded.split_once('|').ok_or_else(invalid)?;
+    let created_at = DateTime::parse_from_rfc3339(created_at).map_err(|_| invalid())?;
+    Ok((created_at.with_timezone(&Utc), id.to_string()))
+}
+
+/// One page of the feed, newest first
+pub async fn read_page(database: &DatabaseManager, filter: &EventFilter) -> Result<EventPage> {
+    // One extra row tells whether another page follows
+    let mut events = database.get_event_page(filter, filter.limit + 1).await?;
+    let next_cursor = if events.len() > filter.limit as usize {
+        events.truncate(filter.limit as usize);
+        events.last().map(|event| encode_cursor(event.created_at, &event.id))
+    } else {
+        None
+    };
+    Ok(EventPage {
+        events: events.into_iter().map(FeedEvent::from).collect(),
+        next_cursor,
+    })
+}
+
+/// Deletes events past the retention in settings
+pub struct EventRetention {
+    database: Arc<DatabaseManager>,
+}
+
+impl EventRetention {
+    pub fn new(database: Arc<DatabaseManager>) -> Self {
+        Self { database }
+    }
+
+    /// Prune once an hour
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting event retention");
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.prune().await {
+                error!("Event pruning failed: {}", e);
+            }
+        }
+    }
+
+    /// Delete events older than the retention, returning how many were removed
+    pub async fn prune(&self) -> Result<u64> {
+        let days = self.database.get_settings().await?.unwrap_or_default().event_retention_days;
+        if days == 0 {
+            return Ok(0);
+        }
+        let pruned = self.database.prune_events(Utc::now() - ChronoDuration::days(days as i64)).await?;
+        if pruned > 0 {
+            debug!("Pruned {} events older than {} days", pruned, days);
+        }
+        Ok(pruned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let created_at = DateTime::parse_from_rfc3339("2024-01-01T12:00:00.123456789Z").unwrap().with_timezone(&Utc);
+        let cursor = encode_cursor(created_at, "event-1");
+        assert_eq!(decode_cursor(&cursor).unwrap(), (created_at, "event-1".to_string()));
+        assert!(decode_cursor("not a cursor").is_err());
+    }
+
+    #[test]
+    fn test_query_filter() {
+        let query = EventQuery {
+            types: Some("server_crash, crash_loop,".to_string()),
+            category: Some("crash".to_string()),
+            severity: Some("warn".to_string()),
+            limit: Some(10_000),
+            ..Default::default()
+        };
+        let filter = query.filter().unwrap();
+        assert_eq!(filter.event_types, ["server_crash", "crash_loop"]);
+        assert_eq!(filter.levels, ["warn", "warning", "error"]);
+        assert_eq!(filter.limit, MAX_PAGE_SIZE);
+        assert_eq!(EventCategory::Crash.event_types(), ["server_crash", "crash_loop"]);
+        assert_eq!(EventCategory::of("plugin_loaded"), EventCategory::Other);
+        assert_eq!(Severity::of("WARNING"), Severity::Warn);
+
+        let bad = EventQuery { severity: Some("loud".to_string()), ..Default::default() };
+        assert!(bad.filter().is_err());
+    }
+
+    #[test]
+    fn test_player_event_from_console() {
+        let joined = player_event("[12:00:00] [Server thread/INFO]: Steve_2 joined the game");
+        assert_eq!(joined, Some((EventKind::PlayerJoined, "Steve_2")));
+        assert_eq!(player_event("Alex left the game"), Some((EventKind::PlayerLeft, "Alex")));
+        assert_eq!(player_event("[12:00:00] [Server thread/INFO]: <Steve> Alex joined the game"), None);
+        assert_eq!(player_event("[12:00:00] [Server thread/INFO]: Done (3.2s)!"), None);
+    }
+}
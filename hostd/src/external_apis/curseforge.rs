@@ -1,12 +1,28 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn, error};
-use crate::external_apis::mod_provider::ModProvider;
+use crate::external_apis::mod_provider::{ModProvider, ProviderListing, ProviderType};
+use crate::external_apis::rate_limit;
 use crate::mod_manager::ModDependency;
 use crate::mod_manager::ModInfo;
 use chrono::Utc;
 
+/// CurseForge's game ID for Minecraft
+pub const MINECRAFT_GAME_ID: u32 = 432;
+/// Class of Minecraft projects that are mods, as opposed to modpacks or resource packs
+pub const MOD_CLASS_ID: u32 = 6;
+/// Largest page the API returns
+pub const MAX_PAGE_SIZE: u32 = 50;
+/// The API refuses to page past this many results
+const MAX_RESULT_INDEX: u32 = 10_000;
+
+/// `relationType` of a required dependency
+const REQUIRED_DEPENDENCY: u32 = 3;
+/// `algo` of a SHA-1 file hash
+const SHA1_ALGO: u32 = 1;
+
 /// CurseForge API client for fetching mod data
 #[derive(Clone)]
 pub struct CurseForgeApiClient {
@@ -16,7 +32,8 @@ pub struct CurseForgeApiClient {
 }
 
 /// CurseForge project response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeProject {
     pub id: u32,
     pub game_id: u32,
@@ -25,11 +42,11 @@ pub struct CurseForgeProject {
     pub links: CurseForgeLinks,
     pub summary: String,
     pub status: u32,
-    pub download_count: u64,
+    pub download_count: f64,
     pub is_featured: bool,
     pub primary_category_id: u32,
     pub categories: Vec<CurseForgeCategory>,
-    pub class_id: u32,
+    pub class_id: Option<u32>,
     pub authors: Vec<CurseForgeAuthor>,
     pub logo: Option<CurseForgeLogo>,
     pub screenshots: Vec<CurseForgeScreenshot>,
@@ -39,13 +56,15 @@ pub struct CurseForgeProject {
     pub date_created: String,
     pub date_modified: String,
     pub date_released: String,
+    /// `false` when the author doesn't allow downloads outside CurseForge
     pub allow_mod_distribution: Option<bool>,
     pub game_popularity_rank: u32,
     pub is_available: bool,
-    pub thumbs_up: u32,
+    pub thumbs_up_count: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeLinks {
     pub website_url: Option<String>,
     pub wiki_url: Option<String>,
@@ -53,7 +72,8 @@ pub struct CurseForgeLinks {
     pub source_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeCategory {
     pub id: u32,
     pub game_id: u32,
@@ -68,14 +88,16 @@ pub struct CurseForgeCategory {
     pub display_index: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeAuthor {
     pub id: u32,
     pub name: String,
     pub url: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeLogo {
     pub id: u32,
     pub mod_id: u32,
@@ -85,7 +107,8 @@ pub struct CurseForgeLogo {
     pub url: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeScreenshot {
     pub id: u32,
     pub mod_id: u32,
@@ -95,7 +118,8 @@ pub struct CurseForgeScreenshot {
     pub url: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeFile {
     pub id: u32,
     pub game_id: u32,
@@ -103,13 +127,17 @@ pub struct CurseForgeFile {
     pub is_available: bool,
     pub display_name: String,
     pub file_name: String,
+    /// 1 release, 2 beta, 3 alpha
     pub release_type: u32,
     pub file_status: u32,
     pub hashes: Vec<CurseForgeHash>,
     pub file_date: String,
     pub file_length: u64,
     pub download_count: u64,
-    pub download_url: String,
+    /// `None` when the project doesn't allow third-party downloads
+    pub download_url: Option<String>,
+    /// Minecraft versions mixed with loader and side names, such as
+    /// `1.20.1`, `Forge` and `Server`
     pub game_versions: Vec<String>,
     pub sortable_game_versions: Vec<CurseForgeSortableGameVersion>,
     pub dependencies: Vec<CurseForgeDependency>,
@@ -122,50 +150,69 @@ pub struct CurseForgeFile {
     pub modules: Vec<CurseForgeModule>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeFileIndex {
     pub game_version: String,
     pub file_id: u32,
     pub filename: String,
     pub release_type: u32,
-    pub game_version_type_id: u32,
-    pub mod_loader: Option<String>,
+    pub game_version_type_id: Option<u32>,
+    /// See [`mod_loader_type`]
+    pub mod_loader: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeHash {
     pub value: String,
+    /// 1 SHA-1, 2 MD5
     pub algo: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeSortableGameVersion {
+    pub game_version_name: String,
     pub game_version_padded: String,
     pub game_version: String,
-    pub game_version_clean: String,
-    pub build: u32,
+    pub game_version_release_date: String,
+    pub game_version_type_id: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeDependency {
     pub mod_id: u32,
+    /// 1 embedded library, 2 optional, 3 required, 4 tool, 5 incompatible, 6 include
     pub relation_type: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgeModule {
     pub name: String,
     pub fingerprint: u64,
 }
 
 /// CurseForge search response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CurseForgeSearchResponse {
     pub data: Vec<CurseForgeProject>,
     pub pagination: CurseForgePagination,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A page of a project's files
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CurseForgeFilesResponse {
+    pub data: Vec<CurseForgeFile>,
+    pub pagination: CurseForgePagination,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CurseForgePagination {
     pub index: u32,
     pub page_size: u32,
@@ -173,43 +220,209 @@ pub struct CurseForgePagination {
     pub total_count: u32,
 }
 
-/// CurseForge game version response
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CurseForgeGameVersionResponse {
-    pub data: Vec<CurseForgeGameVersion>,
+/// Versions of one type (Minecraft versions, loaders, ...) the game has
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CurseForgeGameVersion {
+    #[serde(rename = "type")]
+    pub version_type: u32,
+    pub versions: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CurseForgeGameVersion {
-    pub id: u32,
-    pub game_id: u32,
-    pub slug: String,
-    pub name: String,
-    pub summary: String,
-    pub files: Vec<CurseForgeFile>,
-    pub sortable_game_versions: Vec<CurseForgeSortableGameVersion>,
-    pub game_version_type_id: u32,
-    pub game_version_status: u32,
-    pub game_version_status_reason: Option<String>,
-    pub game_version_date_status: Option<String>,
-    pub game_version_length: u32,
-    pub game_version_type_status: u32,
-    pub game_version_parent: Option<String>,
-    pub game_version_type_parent: Option<String>,
-    pub game_version_type_parent_display_index: Option<u32>,
-    pub game_version_type_parent_sort: Option<u32>,
-    pub game_version_padded: String,
-    pub game_version_clean: String,
-    pub build: u32,
-    pub java_version: Option<String>,
-    pub use_files: Option<bool>,
-    pub game_version_released: String,
-    pub game_version_jvm: Option<String>,
-    pub game_version_jvm_args: Option<String>,
-    pub game_version_jvm_use_legacy_args: Option<bool>,
-    pub game_version_jvm_legacy_args: Option<String>,
-    pub game_version_jvm_mod_loader: Option<String>,
-    pub game_version_jvm_mod_loader_version: Option<String>,
+/// Responses other than pages wrap their payload in `data`
+#[derive(Deserialize)]
+struct Data<T> {
+    data: T,
+}
+
+/// Filters of a project search
+#[derive(Debug, Clone, Default)]
+pub struct CurseForgeSearch<'a> {
+    pub query: Option<&'a str>,
+    /// [`MOD_CLASS_ID`] when `None`
+    pub class_id: Option<u32>,
+    pub category_id: Option<u32>,
+    pub game_version: Option<&'a str>,
+    pub loader: Option<&'a str>,
+    /// Index of the first result
+    pub index: u32,
+    /// Up to [`MAX_PAGE_SIZE`]
+    pub page_size: u32,
+}
+
+/// CurseForge's `modLoaderType` for a loader name
+pub fn mod_loader_type(loader: &str) -> Option<u32> {
+    match loader.to_ascii_lowercase().as_str() {
+        "forge" => Some(1),
+        "fabric" => Some(4),
+        "quilt" => Some(5),
+        "neoforge" => Some(6),
+        _ => None,
+    }
+}
+
+/// The loader a file is for, from the names mixed into its game versions
+pub fn file_loader(file: &CurseForgeFile) -> Option<String> {
+    file.game_versions
+        .iter()
+        .map(|version| version.to_ascii_lowercase())
+        .find(|version| mod_loader_type(version).is_some())
+}
+
+/// The Minecraft versions a file supports
+pub fn file_minecraft_versions(file: &CurseForgeFile) -> Vec<String> {
+    file.game_versions
+        .iter()
+        .filter(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+        .cloned()
+        .collect()
+}
+
+pub fn file_sha1(file: &CurseForgeFile) -> Option<String> {
+    file.hashes.iter().find(|hash| hash.algo == SHA1_ALGO).map(|hash| hash.value.to_ascii_lowercase())
+}
+
+pub fn release_type_name(release_type: u32) -> &'static str {
+    match release_type {
+        1 => "release",
+        2 => "beta",
+        3 => "alpha",
+        _ => "unknown",
+    }
+}
+
+/// Whether a file suits a Minecraft version and loader, where given
+fn file_matches(file: &CurseForgeFile, game_version: Option<&str>, loader: Option<&str>) -> bool {
+    let version_matches = game_version.is_none_or(|version| file.game_versions.iter().any(|v| v == version));
+    // Files of old versions often don't name a loader
+    let loader_matches = loader.is_none_or(|loader| file_loader(file).is_none_or(|file_loader| file_loader.eq_ignore_ascii_case(loader)));
+    version_matches && loader_matches
+}
+
+/// The newest file of `files` that suits the version and loader, preferring releases
+pub fn best_file<'a>(files: &'a [CurseForgeFile], game_version: Option<&str>, loader: Option<&str>) -> Option<&'a CurseForgeFile> {
+    files
+        .iter()
+        .filter(|file| file.is_available || file.download_url.is_some())
+        .filter(|file| file_matches(file, game_version, loader))
+        .min_by(|a, b| {
+            let stability = |file: &CurseForgeFile| if (1..=3).contains(&file.release_type) { file.release_type } else { 4 };
+            stability(a).cmp(&stability(b)).then_with(|| b.file_date.cmp(&a.file_date))
+        })
+}
+
+/// Map CurseForge categories to our category system
+pub fn map_category(categories: &[CurseForgeCategory]) -> String {
+    for category in categories {
+        match category.slug.to_lowercase().as_str() {
+            "adventure-rpg" => return "adventure".to_string(),
+            "cosmetic" | "armor-weapons-tools" => return "utility".to_string(),
+            "world-gen" | "biomes" | "structures" | "dimensions" | "ores-resources" => return "world_generation".to_string(),
+            "library-api" => return "library".to_string(),
+            "magic" => return "magic".to_string(),
+            "server-utility" | "map-information" | "utility-qol" | "storage" => return "utility".to_string(),
+            "mobs" => return "mobs".to_string(),
+            "performance" => return "optimization".to_string(),
+            "technology" | "tech-processing" | "tech-energy" | "tech-item-fluid-energy-transport" | "automation" => return "technology".to_string(),
+            "mc-food" | "farming" => return "food".to_string(),
+            "redstone" => return "technology".to_string(),
+            "decoration" => return "building".to_string(),
+            "miscellaneous" => return "miscellaneous".to_string(),
+            _ => continue,
+        }
+    }
+    "miscellaneous".to_string()
+}
+
+fn parse_date(date: &str) -> chrono::DateTime<Utc> {
+    date.parse().unwrap_or_else(|_| Utc::now())
+}
+
+/// A project as a `mod_metadata` row
+pub fn to_mod_metadata(project: &CurseForgeProject) -> crate::database::ModMetadata {
+    crate::database::ModMetadata {
+        id: format!("curseforge:{}", project.id),
+        name: project.name.clone(),
+        description: project.summary.clone(),
+        author: project.authors.first().map(|author| author.name.clone()).unwrap_or_default(),
+        provider: "curseforge".to_string(),
+        project_id: project.id.to_string(),
+        slug: Some(project.slug.clone()),
+        category: map_category(&project.categories),
+        // CurseForge doesn't say which side a mod runs on
+        side: "both".to_string(),
+        website_url: project.links.website_url.clone(),
+        source_url: project.links.source_url.clone(),
+        issues_url: project.links.issues_url.clone(),
+        created_at: parse_date(&project.date_created),
+        updated_at: parse_date(&project.date_modified),
+    }
+}
+
+/// A file as a `mod_versions` row; `download_url` is empty when the project
+/// doesn't allow third-party downloads
+pub fn to_mod_version(project: &CurseForgeProject, file: &CurseForgeFile) -> crate::database::ModVersion {
+    crate::database::ModVersion {
+        id: file.id.to_string(),
+        mod_metadata_id: format!("curseforge:{}", project.id),
+        version: file.display_name.clone(),
+        minecraft_version: file_minecraft_versions(file).into_iter().next().unwrap_or_default(),
+        loader: file_loader(file).unwrap_or_default(),
+        filename: file.file_name.clone(),
+        file_size: file.file_length,
+        sha1: file_sha1(file),
+        sha256: None,
+        sha512: None,
+        download_url: file.download_url.clone().unwrap_or_default(),
+        release_type: release_type_name(file.release_type).to_string(),
+        created_at: parse_date(&file.file_date),
+        updated_at: parse_date(&file.file_date),
+    }
+}
+
+/// A project and one of its files as a `mods` row
+pub fn to_mod(project: &CurseForgeProject, file: &CurseForgeFile) -> crate::database::Mod {
+    crate::database::Mod {
+        id: project.id.to_string(),
+        provider: "curseforge".to_string(),
+        project_id: project.id.to_string(),
+        version_id: file.id.to_string(),
+        filename: file.file_name.clone(),
+        sha1: file_sha1(file).unwrap_or_default(),
+        server_id: None,
+        enabled: false,
+        category: map_category(&project.categories),
+        created_at: parse_date(&project.date_created),
+        updated_at: parse_date(&project.date_modified),
+    }
+}
+
+/// A project and one of its files as the mod manager's `ModInfo`
+pub fn to_mod_info(project: &CurseForgeProject, file: &CurseForgeFile) -> ModInfo {
+    ModInfo {
+        id: project.id.to_string(),
+        name: project.name.clone(),
+        description: project.summary.clone(),
+        author: project.authors.first().map(|a| a.name.clone()).unwrap_or_else(|| "Unknown".to_string()),
+        version: file.display_name.clone(),
+        minecraft_version: file_minecraft_versions(file).into_iter().next().unwrap_or_default(),
+        loader: file_loader(file).unwrap_or_else(|| "forge".to_string()),
+        category: map_category(&project.categories),
+        side: "both".to_string(),
+        download_url: file.download_url.clone(),
+        file_size: Some(file.file_length),
+        sha1: file_sha1(file),
+        dependencies: file.dependencies.iter()
+            .filter(|d| d.relation_type == REQUIRED_DEPENDENCY || d.relation_type == 2)
+            .map(|d| ModDependency {
+                mod_id: d.mod_id.to_string(),
+                version_range: "any".to_string(),
+                required: d.relation_type == REQUIRED_DEPENDENCY,
+            })
+            .collect(),
+        created_at: parse_date(&project.date_created),
+        updated_at: parse_date(&project.date_modified),
+    }
 }
 
 impl CurseForgeApiClient {
@@ -221,181 +434,149 @@ impl CurseForgeApiClient {
         }
     }
 
-    /// Search for mods
-    pub async fn search_mods(
-        &self,
-        game_id: u32,
-        search_filter: Option<&str>,
-        category_id: Option<u32>,
-        game_version: Option<&str>,
-        mod_loader_type: Option<u32>,
-        sort_field: Option<u32>,
-        sort_order: Option<&str>,
-        page_size: Option<u32>,
-        index: Option<u32>,
-    ) -> Result<CurseForgeSearchResponse> {
+    async fn get_json<T: DeserializeOwned>(&self, path: &str, params: &[(&str, String)]) -> Result<T> {
+        let url = format!("{}{}", self.base_url, path);
+        let request = self.client
+            .get(&url)
+            .query(params)
+            .header("x-api-key", &self.api_key)
+            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0");
+        let response = rate_limit::send(request).await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::FORBIDDEN {
+            bail!("CurseForge refused the API key; check it in the settings");
+        }
+        if !status.is_success() {
+            error!("CurseForge API error for {}: {}", path, status);
+            return Err(anyhow!("CurseForge API error: {}", status));
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Search for projects, a page at a time
+    pub async fn search_mods(&self, search: &CurseForgeSearch<'_>) -> Result<CurseForgeSearchResponse> {
+        let page_size = search.page_size.clamp(1, MAX_PAGE_SIZE);
+        if search.index + page_size > MAX_RESULT_INDEX {
+            warn!("CurseForge only returns the first {} results", MAX_RESULT_INDEX);
+            return Ok(CurseForgeSearchResponse {
+                data: vec![],
+                pagination: CurseForgePagination { index: search.index, page_size, ..Default::default() },
+            });
+        }
+
         let mut params = vec![
-            ("gameId", game_id.to_string()),
+            ("gameId", MINECRAFT_GAME_ID.to_string()),
+            ("classId", search.class_id.unwrap_or(MOD_CLASS_ID).to_string()),
+            // Most downloaded first, as on the website
+            ("sortField", "6".to_string()),
+            ("sortOrder", "desc".to_string()),
+            ("index", search.index.to_string()),
+            ("pageSize", page_size.to_string()),
         ];
-
-        if let Some(filter) = search_filter {
-            params.push(("searchFilter", filter.to_string()));
+        if let Some(query) = search.query.filter(|query| !query.is_empty()) {
+            params.push(("searchFilter", query.to_string()));
         }
-        if let Some(category) = category_id {
+        if let Some(category) = search.category_id {
             params.push(("categoryId", category.to_string()));
         }
-        if let Some(version) = game_version {
+        if let Some(version) = search.game_version {
             params.push(("gameVersion", version.to_string()));
         }
-        if let Some(loader) = mod_loader_type {
+        if let Some(loader) = search.loader.and_then(mod_loader_type) {
             params.push(("modLoaderType", loader.to_string()));
         }
-        if let Some(sort) = sort_field {
-            params.push(("sortField", sort.to_string()));
-        }
-        if let Some(order) = sort_order {
-            params.push(("sortOrder", order.to_string()));
-        }
-        if let Some(size) = page_size {
-            params.push(("pageSize", size.to_string()));
-        }
-        if let Some(idx) = index {
-            params.push(("index", idx.to_string()));
-        }
-
-        let url = format!("{}/mods/search", self.base_url);
-        let response = self.client
-            .get(&url)
-            .query(&params)
-            .header("x-api-key", &self.api_key)
-            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0")
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            if response.status() == 403 {
-                warn!("CurseForge API key invalid or missing, returning empty results");
-                return Ok(CurseForgeSearchResponse {
-                    data: vec![],
-                    pagination: CurseForgePagination {
-                        index: 0,
-                        page_size: 0,
-                        result_count: 0,
-                        total_count: 0,
-                    },
-                });
-            }
-            error!("CurseForge API error: {}", response.status());
-            return Err(anyhow::anyhow!("CurseForge API error: {}", response.status()));
-        }
 
-        let search_response: CurseForgeSearchResponse = response.json().await?;
+        let search_response: CurseForgeSearchResponse = self.get_json("/mods/search", &params).await?;
         info!("Found {} mods on CurseForge", search_response.pagination.total_count);
         Ok(search_response)
     }
 
     /// Get project details
     pub async fn get_project(&self, project_id: u32) -> Result<CurseForgeProject> {
-        let url = format!("{}/mods/{}", self.base_url, project_id);
-        let response = self.client
-            .get(&url)
-            .header("x-api-key", &self.api_key)
-            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0")
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            error!("CurseForge API error for project {}: {}", project_id, response.status());
-            return Err(anyhow::anyhow!("CurseForge API error: {}", response.status()));
-        }
-
-        let project: CurseForgeProject = response.json().await?;
-        Ok(project)
+        let project: Data<CurseForgeProject> = self.get_json(&format!("/mods/{}", project_id), &[]).await?;
+        Ok(project.data)
     }
 
-    /// Get project files
+    /// A page of a project's files, newest first
     pub async fn get_project_files(
         &self,
         project_id: u32,
         game_version: Option<&str>,
-        mod_loader_type: Option<u32>,
-        game_version_type_id: Option<u32>,
-        index: Option<u32>,
-        page_size: Option<u32>,
-    ) -> Result<Vec<CurseForgeFile>> {
-        let mut params = vec![];
-
+        loader: Option<&str>,
+        index: u32,
+        page_size: u32,
+    ) -> Result<CurseForgeFilesResponse> {
+        let mut params = vec![
+            ("index", index.to_string()),
+            ("pageSize", page_size.clamp(1, MAX_PAGE_SIZE).to_string()),
+        ];
         if let Some(version) = game_version {
             params.push(("gameVersion", version.to_string()));
         }
-        if let Some(loader) = mod_loader_type {
+        if let Some(loader) = loader.and_then(mod_loader_type) {
             params.push(("modLoaderType", loader.to_string()));
         }
-        if let Some(version_type) = game_version_type_id {
-            params.push(("gameVersionTypeId", version_type.to_string()));
-        }
-        if let Some(idx) = index {
-            params.push(("index", idx.to_string()));
-        }
-        if let Some(size) = page_size {
-            params.push(("pageSize", size.to_string()));
-        }
-
-        let url = format!("{}/mods/{}/files", self.base_url, project_id);
-        let response = self.client
-            .get(&url)
-            .query(&params)
-            .header("x-api-key", &self.api_key)
-            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0")
-            .send()
-            .await?;
+        self.get_json(&format!("/mods/{}/files", project_id), &params).await
+    }
 
-        if !response.status().is_success() {
-            error!("CurseForge API error for project files {}: {}", project_id, response.status());
-            return Err(anyhow::anyhow!("CurseForge API error: {}", response.status()));
+    /// Every file of a project for a Minecraft version and loader, up to `limit`
+    pub async fn get_all_project_files(
+        &self,
+        project_id: u32,
+        game_version: Option<&str>,
+        loader: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<CurseForgeFile>> {
+        let mut files = Vec::new();
+        loop {
+            let page = self.get_project_files(project_id, game_version, loader, files.len() as u32, MAX_PAGE_SIZE).await?;
+            let done = page.data.is_empty() || files.len() + page.data.len() >= page.pagination.total_count as usize;
+            files.extend(page.data);
+            if done || files.len() >= limit {
+                files.truncate(limit);
+                return Ok(files);
+            }
         }
-
-        let files: Vec<CurseForgeFile> = response.json().await?;
-        Ok(files)
     }
 
-    /// Get game versions
-    pub async fn get_game_versions(&self, game_id: u32) -> Result<Vec<CurseForgeGameVersion>> {
-        let url = format!("{}/games/{}/versions", self.base_url, game_id);
-        let response = self.client
-            .get(&url)
-            .header("x-api-key", &self.api_key)
-            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0")
-            .send()
-            .await?;
+    pub async fn get_file(&self, project_id: u32, file_id: u32) -> Result<CurseForgeFile> {
+        let file: Data<CurseForgeFile> = self.get_json(&format!("/mods/{}/files/{}", project_id, file_id), &[]).await?;
+        Ok(file.data)
+    }
 
-        if !response.status().is_success() {
-            error!("CurseForge API error for game versions: {}", response.status());
-            return Err(anyhow::anyhow!("CurseForge API error: {}", response.status()));
+    /// Where a file can be downloaded. Authors can turn off downloads outside
+    /// CurseForge; their files have to be downloaded from the website by hand.
+    pub async fn resolve_download_url(&self, project: &CurseForgeProject, file: &CurseForgeFile) -> Result<String> {
+        if let Some(url) = file.download_url.as_ref().filter(|url| !url.is_empty()) {
+            return Ok(url.clone());
+        }
+        if project.allow_mod_distribution == Some(false) {
+            bail!(
+                "The author of {} doesn't allow downloads outside CurseForge; download {} from {} and add it by hand",
+                project.name,
+                file.file_name,
+                project.links.website_url.as_deref().unwrap_or("curseforge.com")
+            );
         }
+        let url: Data<Option<String>> = self
+            .get_json(&format!("/mods/{}/files/{}/download-url", project.id, file.id), &[])
+            .await?;
+        url.data.filter(|url| !url.is_empty()).ok_or_else(|| anyhow!("CurseForge has no download for {}", file.file_name))
+    }
 
-        let version_response: CurseForgeGameVersionResponse = response.json().await?;
-        Ok(version_response.data)
+    /// Get game versions, grouped by type
+    pub async fn get_game_versions(&self) -> Result<Vec<CurseForgeGameVersion>> {
+        let versions: Data<Vec<CurseForgeGameVersion>> =
+            self.get_json(&format!("/games/{}/versions", MINECRAFT_GAME_ID), &[]).await?;
+        Ok(versions.data)
     }
 
     /// Get categories
-    pub async fn get_categories(&self, game_id: u32) -> Result<Vec<CurseForgeCategory>> {
-        let url = format!("{}/categories", self.base_url);
-        let response = self.client
-            .get(&url)
-            .query(&[("gameId", game_id.to_string())])
-            .header("x-api-key", &self.api_key)
-            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0")
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            error!("CurseForge API error for categories: {}", response.status());
-            return Err(anyhow::anyhow!("CurseForge API error: {}", response.status()));
-        }
-
-        let categories: Vec<CurseForgeCategory> = response.json().await?;
-        Ok(categories)
+    pub async fn get_categories(&self) -> Result<Vec<CurseForgeCategory>> {
+        let categories: Data<Vec<CurseForgeCategory>> =
+            self.get_json("/categories", &[("gameId", MINECRAFT_GAME_ID.to_string())]).await?;
+        Ok(categories.data)
     }
 
     /// Convert CurseForge project to our ModInfo format
@@ -404,47 +585,32 @@ impl CurseForgeApiClient {
             id: project.id.to_string(),
             name: project.name.clone(),
             description: Some(project.summary.clone()),
-            category: self.map_category(&project.categories),
-            side: self.map_side(project),
+            category: map_category(&project.categories),
+            side: "universal".to_string(),
             source: "curseforge".to_string(),
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
+            created_at: parse_date(&project.date_created),
+            updated_at: parse_date(&project.date_modified),
         }
     }
 
-    /// Map CurseForge categories to our category system
-    fn map_category(&self, categories: &[CurseForgeCategory]) -> String {
-        for category in categories {
-            match category.name.to_lowercase().as_str() {
-                "adventure" => return "adventure".to_string(),
-                "decoration" => return "building".to_string(),
-                "economy" => return "economy".to_string(),
-                "equipment" => return "utility".to_string(),
-                "food" => return "utility".to_string(),
-                "game-mechanics" => return "core".to_string(),
-                "library" => return "library".to_string(),
-                "magic" => return "magic".to_string(),
-                "management" => return "utility".to_string(),
-                "minigame" => return "miscellaneous".to_string(),
-                "mobs" => return "mobs".to_string(),
-                "optimization" => return "optimization".to_string(),
-                "social" => return "utility".to_string(),
-                "storage" => return "utility".to_string(),
-                "technology" => return "technology".to_string(),
-                "transportation" => return "transportation".to_string(),
-                "utility" => return "utility".to_string(),
-                "worldgen" => return "world_generation".to_string(),
-                _ => continue,
-            }
-        }
-        "miscellaneous".to_string()
-    }
+    /// A project and its newest file for a Minecraft version
+    async fn project_file(
+        &self,
+        mod_id: &str,
+        game_version: Option<&str>,
+    ) -> Result<(CurseForgeProject, CurseForgeFile), Box<dyn std::error::Error>> {
+        let project_id = mod_id.parse::<u32>()
+            .map_err(|_| "Invalid CurseForge project ID")?;
 
-    /// Map CurseForge project to our side system
-    fn map_side(&self, project: &CurseForgeProject) -> String {
-        // CurseForge doesn't have explicit client/server side info in the basic project data
-        // We'll need to check the files for more detailed information
-        "universal".to_string()
+        let project = self.get_project(project_id).await?;
+        let files = match game_version {
+            Some(_) => self.get_project_files(project_id, game_version, None, 0, MAX_PAGE_SIZE).await?.data,
+            None => project.latest_files.clone(),
+        };
+        let file = best_file(&files, game_version, None)
+            .cloned()
+            .ok_or("No files found for the project")?;
+        Ok((project, file))
     }
 }
 
@@ -457,62 +623,13 @@ impl ModProvider for CurseForgeApiClient {
         loader: Option<&str>,
         limit: Option<usize>,
     ) -> Result<Vec<ModInfo>, Box<dyn std::error::Error>> {
-        // CurseForge game ID for Minecraft is 432
-        let search_results = self.search_mods(
-            432, // Minecraft game ID
-            Some(query),
-            None, // category_id
-            minecraft_version,
-            None, // mod_loader_type
-            None, // sort_field
-            None, // sort_order
-            limit.map(|l| l as u32),
-            None, // index
-        ).await?;
-        
-        let mut mod_infos = Vec::new();
-        
-        for result in search_results.data {
-            if let Ok(mod_info) = self.get_mod(&result.id.to_string()).await {
-                mod_infos.push(mod_info);
-            }
-        }
-        
-        Ok(mod_infos)
+        let listings = self.search_listings(query, minecraft_version, loader, limit).await?;
+        Ok(listings.into_iter().map(|(info, _)| info).collect())
     }
 
     async fn get_mod(&self, mod_id: &str) -> Result<ModInfo, Box<dyn std::error::Error>> {
-        let project_id = mod_id.parse::<u32>()
-            .map_err(|_| "Invalid CurseForge project ID")?;
-        
-        let project = self.get_project(project_id).await?;
-        let files = self.get_project_files(project_id, None, None, None, None, None).await?;
-        
-        if let Some(file) = files.first() {
-            Ok(ModInfo {
-                id: project.id.to_string(),
-                name: project.name,
-                description: project.summary,
-                author: project.authors.first().map(|a| a.name.clone()).unwrap_or_else(|| "Unknown".to_string()),
-                version: file.display_name.clone(),
-                minecraft_version: file.sortable_game_versions.first().map(|v| v.game_version.clone()).unwrap_or_else(|| "1.20.1".to_string()),
-                loader: "forge".to_string(), // CurseForge is primarily Forge
-                category: "misc".to_string(),
-                side: "both".to_string(),
-                download_url: Some(file.download_url.clone()),
-                file_size: Some(file.file_length),
-                sha1: file.hashes.iter().find(|h| h.algo == 1).map(|h| h.value.clone()),
-                dependencies: file.dependencies.iter().map(|d| ModDependency {
-                    mod_id: d.mod_id.to_string(),
-                    version_range: "any".to_string(),
-                    required: d.relation_type == 1,
-                }).collect(),
-                created_at: project.date_created.parse().unwrap_or_else(|_| Utc::now()),
-                updated_at: project.date_modified.parse().unwrap_or_else(|_| Utc::now()),
-            })
-        } else {
-            Err("No files found for the project".into())
-        }
+        let (project, file) = self.project_file(mod_id, None).await?;
+        Ok(to_mod_info(&project, &file))
     }
 
     async fn get_mod_version(
@@ -520,37 +637,8 @@ impl ModProvider for CurseForgeApiClient {
         mod_id: &str,
         version: &str,
     ) -> Result<ModInfo, Box<dyn std::error::Error>> {
-        let project_id = mod_id.parse::<u32>()
-            .map_err(|_| "Invalid CurseForge project ID")?;
-        
-        let project = self.get_project(project_id).await?;
-        let files = self.get_project_files(project_id, Some(version), None, None, None, None).await?;
-        
-        if let Some(file) = files.first() {
-            Ok(ModInfo {
-                id: project.id.to_string(),
-                name: project.name,
-                description: project.summary,
-                author: project.authors.first().map(|a| a.name.clone()).unwrap_or_else(|| "Unknown".to_string()),
-                version: file.display_name.clone(),
-                minecraft_version: file.sortable_game_versions.first().map(|v| v.game_version.clone()).unwrap_or_else(|| "1.20.1".to_string()),
-                loader: "forge".to_string(),
-                category: "misc".to_string(),
-                side: "both".to_string(),
-                download_url: Some(file.download_url.clone()),
-                file_size: Some(file.file_length),
-                sha1: file.hashes.iter().find(|h| h.algo == 1).map(|h| h.value.clone()),
-                dependencies: file.dependencies.iter().map(|d| ModDependency {
-                    mod_id: d.mod_id.to_string(),
-                    version_range: "any".to_string(),
-                    required: d.relation_type == 1,
-                }).collect(),
-                created_at: project.date_created.parse().unwrap_or_else(|_| Utc::now()),
-                updated_at: project.date_modified.parse().unwrap_or_else(|_| Utc::now()),
-            })
-        } else {
-            Err("No files found for the specified version".into())
-        }
+        let (project, file) = self.project_file(mod_id, Some(version)).await?;
+        Ok(to_mod_info(&project, &file))
     }
 
     async fn download_mod(
@@ -559,17 +647,12 @@ impl ModProvider for CurseForgeApiClient {
         version: &str,
         file_path: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let mod_info = self.get_mod_version(mod_id, version).await?;
-        
-        if let Some(download_url) = mod_info.download_url {
-            let response = self.client.get(&download_url).send().await?;
-            let content = response.bytes().await?;
-            
-            std::fs::write(file_path, content)?;
-            Ok(())
-        } else {
-            Err("No download URL available".into())
-        }
+        let (project, file) = self.project_file(mod_id, Some(version)).await?;
+        let download_url = self.resolve_download_url(&project, &file).await?;
+        let sha1 = file_sha1(&file).map(crate::downloads::Checksum::Sha1);
+        let download = crate::downloads::Download::new(download_url).checksum(sha1);
+        crate::downloads::shared().fetch_to(&download, std::path::Path::new(file_path)).await?;
+        Ok(())
     }
 
     async fn get_mod_dependencies(
@@ -586,17 +669,83 @@ impl ModProvider for CurseForgeApiClient {
         mod_id: &str,
         current_version: &str,
     ) -> Result<Option<String>, Box<dyn std::error::Error>> {
-        let project_id = mod_id.parse::<u32>()
-            .map_err(|_| "Invalid CurseForge project ID")?;
-        
-        let files = self.get_project_files(project_id, None, None, None, None, Some(1)).await?;
-        
-        if let Some(latest_file) = files.first() {
-            if latest_file.display_name != current_version {
-                return Ok(Some(latest_file.display_name.clone()));
-            }
+        let (_, latest_file) = self.project_file(mod_id, None).await?;
+        if latest_file.display_name != current_version {
+            return Ok(Some(latest_file.display_name));
         }
-        
         Ok(None)
     }
+
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::CurseForge
+    }
+
+    async fn search_listings(
+        &self,
+        query: &str,
+        minecraft_version: Option<&str>,
+        loader: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<(ModInfo, ProviderListing)>, Box<dyn std::error::Error>> {
+        let search = CurseForgeSearch {
+            query: Some(query),
+            game_version: minecraft_version,
+            loader,
+            page_size: limit.map_or(20, |l| l as u32),
+            ..Default::default()
+        };
+        let search_results = self.search_mods(&search).await?;
+
+        // Search results carry each project's latest files, so no request per project is needed
+        Ok(search_results.data.iter()
+            .filter_map(|project| {
+                let file = best_file(&project.latest_files, minecraft_version, loader)?;
+                let listing = ProviderListing {
+                    provider: ProviderType::CurseForge.as_str().to_string(),
+                    project_id: project.id.to_string(),
+                    slug: project.slug.clone(),
+                    version_id: file.id.to_string(),
+                    filename: file.file_name.clone(),
+                    sha1: file_sha1(file),
+                    downloads: project.download_count as u64,
+                };
+                Some((to_mod_info(project, file), listing))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file() {
+        let file: CurseForgeFile = serde_json::from_value(serde_json::json!({
+            "id": 4712866,
+            "modId": 238222,
+            "isAvailable": true,
+            "displayName": "jei-1.20.1-forge-15.2.0.27.jar",
+            "fileName": "jei-1.20.1-forge-15.2.0.27.jar",
+            "releaseType": 1,
+            "hashes": [{ "value": "ABCDEF", "algo": 1 }, { "value": "123", "algo": 2 }],
+            "fileDate": "2023-08-10T12:00:00Z",
+            "fileLength": 1234,
+            "downloadUrl": null,
+            "gameVersions": ["Forge", "1.20.1", "Server"],
+            "dependencies": [{ "modId": 1, "relationType": 3 }]
+        }))
+        .unwrap();
+
+        assert_eq!(file_loader(&file).as_deref(), Some("forge"));
+        assert_eq!(file_minecraft_versions(&file), ["1.20.1"]);
+        assert_eq!(file_sha1(&file).as_deref(), Some("abcdef"));
+        assert!(file.download_url.is_none());
+
+        let beta = CurseForgeFile { release_type: 2, file_date: "2023-09-01T00:00:00Z".to_string(), ..file.clone() };
+        let files = [beta, file];
+        assert_eq!(best_file(&files, Some("1.20.1"), Some("forge")).unwrap().release_type, 1);
+        assert!(best_file(&files, Some("1.20.1"), Some("fabric")).is_none());
+        assert!(best_file(&files, Some("1.19.2"), None).is_none());
+    }
 }
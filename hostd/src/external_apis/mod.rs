@@ -1,7 +1,8 @@
 pub mod modrinth;
 pub mod curseforge;
 pub mod mod_provider;
+pub mod rate_limit;
 
 pub use modrinth::ModrinthApiClient;
 pub use curseforge::CurseForgeApiClient;
-pub use mod_provider::{ModProvider, ProviderType, ProviderConfig, ProviderFactory, MultiProviderModManager};
+pub use mod_provider::{ModProvider, ProviderType, ProviderConfig, ProviderFactory, MultiProviderModManager, UnifiedSearchResult};
@@ -45,6 +45,61 @@ pub trait ModProvider: Send + Sync {
         mod_id: &str,
         current_version: &str,
     ) -> Result<Option<String>, Box<dyn Error>>;
+
+    /// Which provider this is
+    fn provider_type(&self) -> ProviderType;
+
+    /// Search for mods, with how this provider lists each of them
+    async fn search_listings(
+        &self,
+        query: &str,
+        minecraft_version: Option<&str>,
+        loader: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<(ModInfo, ProviderListing)>, Box<dyn Error>>;
+}
+
+/// A mod as one provider lists it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderListing {
+    /// `curseforge` or `modrinth`
+    pub provider: String,
+    pub project_id: String,
+    pub slug: String,
+    /// The CurseForge file or Modrinth version of the newest compatible release
+    pub version_id: String,
+    pub filename: String,
+    pub sha1: Option<String>,
+    pub downloads: u64,
+}
+
+/// A search result found on one or more providers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnifiedSearchResult {
+    #[serde(flatten)]
+    pub info: ModInfo,
+    pub providers: Vec<ProviderListing>,
+}
+
+/// Merge results of several providers. A result is the same mod as an earlier
+/// one from another provider when their slugs match or their files have the
+/// same SHA-1; it is then added as another listing of that mod.
+pub fn merge_listings(results: Vec<(ModInfo, ProviderListing)>) -> Vec<UnifiedSearchResult> {
+    let mut merged: Vec<UnifiedSearchResult> = Vec::new();
+    for (info, listing) in results {
+        let same_mod = merged.iter_mut().find(|result| {
+            result.providers.iter().all(|other| other.provider != listing.provider)
+                && result.providers.iter().any(|other| {
+                    (!listing.slug.is_empty() && other.slug.eq_ignore_ascii_case(&listing.slug))
+                        || (listing.sha1.is_some() && other.sha1.as_deref().map(str::to_ascii_lowercase) == listing.sha1.as_deref().map(str::to_ascii_lowercase))
+                })
+        });
+        match same_mod {
+            Some(result) => result.providers.push(listing),
+            None => merged.push(UnifiedSearchResult { info, providers: vec![listing] }),
+        }
+    }
+    merged
 }
 
 
@@ -55,6 +110,16 @@ pub enum ProviderType {
     Modrinth,
 }
 
+impl ProviderType {
+    /// The provider's name in mod records
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderType::CurseForge => "curseforge",
+            ProviderType::Modrinth => "modrinth",
+        }
+    }
+}
+
 impl std::fmt::Display for ProviderType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -167,6 +232,44 @@ impl MultiProviderModManager {
         Ok(all_results)
     }
 
+    /// Search every provider at once and merge mods listed on several of
+    /// them; a provider that fails is left out
+    pub async fn search_unified(
+        &self,
+        query: &str,
+        minecraft_version: Option<&str>,
+        loader: Option<&str>,
+        limit: Option<usize>,
+    ) -> Vec<UnifiedSearchResult> {
+        let searches = self.providers.iter().map(|provider| async move {
+            provider
+                .search_listings(query, minecraft_version, loader, limit)
+                .await
+                .map_err(|e| format!("{} search failed: {}", provider.provider_type(), e))
+        });
+
+        let mut listings = Vec::new();
+        for result in futures::future::join_all(searches).await {
+            match result {
+                Ok(results) => listings.extend(results),
+                Err(e) => tracing::warn!("{}", e),
+            }
+        }
+
+        let mut merged = merge_listings(listings);
+        merged.sort_by(|a, b| {
+            let downloads = |result: &UnifiedSearchResult| result.providers.iter().map(|p| p.downloads).sum::<u64>();
+            self.calculate_relevance_score(&b.info.name, query)
+                .partial_cmp(&self.calculate_relevance_score(&a.info.name, query))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| downloads(b).cmp(&downloads(a)))
+        });
+        if let Some(limit) = limit {
+            merged.truncate(limit);
+        }
+        merged
+    }
+
     /// Get mod from any provider
     pub async fn get_mod(&self, mod_id: &str) -> Result<ModInfo, Box<dyn Error>> {
         for provider in &self.providers {
@@ -243,3 +346,56 @@ impl MultiProviderModManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(provider: &str, slug: &str, sha1: Option<&str>) -> (ModInfo, ProviderListing) {
+        let info = ModInfo {
+            id: format!("{}-{}", provider, slug),
+            name: slug.to_string(),
+            description: String::new(),
+            author: String::new(),
+            version: "1.0.0".to_string(),
+            minecraft_version: "1.20.1".to_string(),
+            loader: "fabric".to_string(),
+            category: "utility".to_string(),
+            side: "both".to_string(),
+            download_url: None,
+            file_size: None,
+            sha1: sha1.map(str::to_string),
+            dependencies: vec![],
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        let listing = ProviderListing {
+            provider: provider.to_string(),
+            project_id: info.id.clone(),
+            slug: slug.to_string(),
+            version_id: "1".to_string(),
+            filename: format!("{}.jar", slug),
+            sha1: sha1.map(str::to_string),
+            downloads: 0,
+        };
+        (info, listing)
+    }
+
+    #[test]
+    fn test_merge_listings() {
+        let merged = merge_listings(vec![
+            result("modrinth", "sodium", Some("aa")),
+            result("modrinth", "lithium", None),
+            result("curseforge", "Sodium", None),
+            result("curseforge", "lithium-fabric", None),
+            result("curseforge", "phosphor", Some("AA")),
+        ]);
+        let providers: Vec<Vec<&str>> = merged
+            .iter()
+            .map(|result| result.providers.iter().map(|p| p.provider.as_str()).collect())
+            .collect();
+        // Phosphor's file matches Sodium's, but Sodium already has a CurseForge listing
+        assert_eq!(providers, [vec!["modrinth", "curseforge"], vec!["modrinth"], vec!["curseforge"], vec!["curseforge"]]);
+        assert_eq!(merged[0].info.id, "modrinth-sodium");
+    }
+}
@@ -3,7 +3,9 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tracing::{info, warn, error};
-use crate::external_apis::mod_provider::ModProvider;
+use crate::external_apis::mod_provider::{ModProvider, ProviderListing, ProviderType};
+use crate::external_apis::rate_limit;
+use crate::metadata_cache;
 use crate::mod_manager::ModDependency;
 use crate::mod_manager::ModInfo;
 use chrono::Utc;
@@ -16,7 +18,8 @@ pub struct ModrinthApiClient {
 }
 
 /// Modrinth project response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModrinthProject {
     pub id: String,
     pub slug: String,
@@ -53,21 +56,24 @@ pub struct ModrinthProject {
     pub monetization_status: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModrinthLicense {
     pub id: String,
     pub name: String,
     pub url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModrinthDonationUrl {
     pub id: String,
     pub platform: String,
     pub url: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModrinthGalleryImage {
     pub url: String,
     pub featured: bool,
@@ -78,7 +84,8 @@ pub struct ModrinthGalleryImage {
 }
 
 /// Modrinth version response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModrinthVersion {
     pub id: String,
     pub project_id: String,
@@ -99,7 +106,8 @@ pub struct ModrinthVersion {
     pub loaders: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModrinthFile {
     pub hashes: HashMap<String, String>,
     pub url: String,
@@ -108,7 +116,8 @@ pub struct ModrinthFile {
     pub size: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModrinthDependency {
     pub version_id: Option<String>,
     pub project_id: Option<String>,
@@ -116,15 +125,210 @@ pub struct ModrinthDependency {
     pub dependency_type: String,
 }
 
+/// A project as search returns it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModrinthSearchHit {
+    pub project_id: String,
+    pub project_type: String,
+    pub slug: String,
+    /// Username of the project's owner
+    pub author: String,
+    pub title: String,
+    pub description: String,
+    pub categories: Vec<String>,
+    pub display_categories: Vec<String>,
+    /// Minecraft versions the project supports
+    pub versions: Vec<String>,
+    pub downloads: u64,
+    pub follows: u64,
+    pub icon_url: Option<String>,
+    pub date_created: String,
+    pub date_modified: String,
+    pub license: String,
+    pub client_side: String,
+    pub server_side: String,
+    pub gallery: Vec<String>,
+    pub color: Option<u32>,
+}
+
 /// Modrinth search response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModrinthSearchResponse {
-    pub hits: Vec<ModrinthProject>,
+    pub hits: Vec<ModrinthSearchHit>,
     pub offset: u32,
     pub limit: u32,
     pub total_hits: u32,
 }
 
+/// Everything a project depends on, from `/project/{id}/dependencies`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModrinthProjectDependencies {
+    pub projects: Vec<ModrinthProject>,
+    pub versions: Vec<ModrinthVersion>,
+}
+
+/// Filters of a project search. Each category must match; any of the loaders
+/// and any of the game versions may.
+#[derive(Debug, Clone, Default)]
+pub struct ModrinthSearch<'a> {
+    pub query: Option<&'a str>,
+    /// `mod` when `None`
+    pub project_type: Option<&'a str>,
+    pub categories: Vec<&'a str>,
+    pub loaders: Vec<&'a str>,
+    pub game_versions: Vec<&'a str>,
+    /// relevance, downloads, follows, newest or updated
+    pub index: Option<&'a str>,
+    pub offset: u32,
+    /// Up to [`MAX_SEARCH_LIMIT`]
+    pub limit: u32,
+}
+
+/// Largest page search returns
+pub const MAX_SEARCH_LIMIT: u32 = 100;
+/// Search results whose versions are looked up at once
+const VERSION_LOOKUPS: usize = 8;
+/// Most dependencies followed from one version
+const MAX_DEPENDENCIES: usize = 100;
+
+/// The `facets` parameter of a search: groups are ANDed, the values within a
+/// group ORed
+pub fn build_facets(search: &ModrinthSearch<'_>) -> String {
+    let mut facets = vec![vec![format!("project_type:{}", search.project_type.unwrap_or("mod"))]];
+    facets.extend(search.categories.iter().map(|category| vec![format!("categories:{}", category)]));
+    if !search.loaders.is_empty() {
+        // Loaders are categories to Modrinth
+        facets.push(search.loaders.iter().map(|loader| format!("categories:{}", loader.to_ascii_lowercase())).collect());
+    }
+    if !search.game_versions.is_empty() {
+        facets.push(search.game_versions.iter().map(|version| format!("versions:{}", version)).collect());
+    }
+    serde_json::Value::from(facets).to_string()
+}
+
+/// The file to install from a version
+pub fn primary_file(version: &ModrinthVersion) -> Option<&ModrinthFile> {
+    version.files.iter().find(|file| file.primary).or_else(|| version.files.first())
+}
+
+/// The newest release of `versions`, or the newest version if none is a
+/// release; Modrinth lists versions newest first
+pub fn best_version(versions: &[ModrinthVersion]) -> Option<&ModrinthVersion> {
+    versions
+        .iter()
+        .find(|version| version.version_type == "release")
+        .or_else(|| versions.first())
+}
+
+/// Map Modrinth categories to our category system
+pub fn map_category(categories: &[String]) -> String {
+    for category in categories {
+        match category.as_str() {
+            "adventure" => return "adventure".to_string(),
+            "cursed" => return "miscellaneous".to_string(),
+            "decoration" => return "building".to_string(),
+            "economy" => return "economy".to_string(),
+            "equipment" => return "utility".to_string(),
+            "food" => return "utility".to_string(),
+            "game-mechanics" => return "core".to_string(),
+            "library" => return "library".to_string(),
+            "magic" => return "magic".to_string(),
+            "management" => return "utility".to_string(),
+            "minigame" => return "miscellaneous".to_string(),
+            "mobs" => return "mobs".to_string(),
+            "optimization" => return "optimization".to_string(),
+            "social" => return "utility".to_string(),
+            "storage" => return "utility".to_string(),
+            "technology" => return "technology".to_string(),
+            "transportation" => return "transportation".to_string(),
+            "utility" => return "utility".to_string(),
+            "worldgen" => return "world_generation".to_string(),
+            _ => continue,
+        }
+    }
+    "miscellaneous".to_string()
+}
+
+fn parse_date(date: &str) -> chrono::DateTime<Utc> {
+    date.parse().unwrap_or_else(|_| Utc::now())
+}
+
+/// A search result and one of its versions as a `mods` row
+pub fn to_mod(hit: &ModrinthSearchHit, version: &ModrinthVersion) -> crate::database::Mod {
+    let file = primary_file(version);
+    crate::database::Mod {
+        id: hit.project_id.clone(),
+        provider: "modrinth".to_string(),
+        project_id: hit.project_id.clone(),
+        version_id: version.id.clone(),
+        filename: file.map(|file| file.filename.clone()).unwrap_or_default(),
+        sha1: file.and_then(|file| file.hashes.get("sha1").cloned()).unwrap_or_default(),
+        server_id: None,
+        enabled: false,
+        category: map_category(&hit.categories),
+        created_at: parse_date(&hit.date_created),
+        updated_at: parse_date(&hit.date_modified),
+    }
+}
+
+/// A project and one of its versions as the mod manager's `ModInfo`
+pub fn to_mod_info(project: &ModrinthProject, version: &ModrinthVersion, author: &str) -> ModInfo {
+    let file = primary_file(version);
+    ModInfo {
+        id: project.id.clone(),
+        name: project.title.clone(),
+        description: project.description.clone(),
+        author: author.to_string(),
+        version: version.version_number.clone(),
+        minecraft_version: version.game_versions.first().cloned().unwrap_or_default(),
+        loader: version.loaders.first().cloned().unwrap_or_else(|| "fabric".to_string()),
+        category: map_category(&project.categories),
+        side: crate::client_mods::provider_side(&project.client_side, &project.server_side).to_string(),
+        download_url: file.map(|file| file.url.clone()),
+        file_size: file.map(|file| file.size),
+        sha1: file.and_then(|file| file.hashes.get("sha1").cloned()),
+        dependencies: version.dependencies.iter()
+            .filter(|d| d.dependency_type == "required" || d.dependency_type == "optional")
+            .filter_map(|d| {
+                Some(ModDependency {
+                    mod_id: d.project_id.clone().or_else(|| d.version_id.clone())?,
+                    version_range: "any".to_string(),
+                    required: d.dependency_type == "required",
+                })
+            })
+            .collect(),
+        created_at: parse_date(&project.published),
+        updated_at: parse_date(&project.updated),
+    }
+}
+
+impl ModrinthSearchHit {
+    /// The project fields a search result carries
+    pub fn to_project(&self) -> ModrinthProject {
+        ModrinthProject {
+            id: self.project_id.clone(),
+            slug: self.slug.clone(),
+            project_type: self.project_type.clone(),
+            title: self.title.clone(),
+            description: self.description.clone(),
+            published: self.date_created.clone(),
+            updated: self.date_modified.clone(),
+            client_side: self.client_side.clone(),
+            server_side: self.server_side.clone(),
+            downloads: self.downloads,
+            followers: self.follows,
+            categories: self.categories.clone(),
+            game_versions: self.versions.clone(),
+            icon_url: self.icon_url.clone(),
+            color: self.color,
+            ..Default::default()
+        }
+    }
+}
+
 impl ModrinthApiClient {
     pub fn new() -> Self {
         Self {
@@ -133,49 +337,31 @@ impl ModrinthApiClient {
         }
     }
 
-    /// Search for mods
-    pub async fn search_mods(
-        &self,
-        query: &str,
-        game_version: Option<&str>,
-        loader: Option<&str>,
-        category: Option<&str>,
-        limit: Option<u32>,
-        offset: Option<u32>,
-    ) -> Result<ModrinthSearchResponse> {
+    /// Search for projects, a page at a time
+    pub async fn search_mods(&self, search: &ModrinthSearch<'_>) -> Result<ModrinthSearchResponse> {
         let mut params = vec![
-            ("query", query.to_string()),
+            ("facets", build_facets(search)),
+            ("offset", search.offset.to_string()),
+            ("limit", search.limit.clamp(1, MAX_SEARCH_LIMIT).to_string()),
         ];
-        
-        let facets = self.build_facets(game_version, loader, category)?;
-        if !facets.is_empty() && facets != "[]" {
-            params.push(("facets", facets));
-        }
-
-        if let Some(limit) = limit {
-            params.push(("limit", limit.to_string()));
+        if let Some(query) = search.query.filter(|query| !query.is_empty()) {
+            params.push(("query", query.to_string()));
         }
-        if let Some(offset) = offset {
-            params.push(("offset", offset.to_string()));
+        if let Some(index) = search.index {
+            params.push(("index", index.to_string()));
         }
 
         let url = format!("{}/search", self.base_url);
-        let response = self.client
+        let request = self.client
             .get(&url)
             .query(&params)
-            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0")
-            .send()
-            .await?;
+            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0");
+        let response = rate_limit::send(request).await?;
 
         if !response.status().is_success() {
             if response.status() == 400 {
                 warn!("Modrinth API bad request, returning empty results");
-                return Ok(ModrinthSearchResponse {
-                    hits: vec![],
-                    offset: 0,
-                    limit: 0,
-                    total_hits: 0,
-                });
+                return Ok(ModrinthSearchResponse::default());
             }
             error!("Modrinth API error: {}", response.status());
             return Err(anyhow::anyhow!("Modrinth API error: {}", response.status()));
@@ -189,19 +375,10 @@ impl ModrinthApiClient {
     /// Get project details
     pub async fn get_project(&self, project_id: &str) -> Result<ModrinthProject> {
         let url = format!("{}/project/{}", self.base_url, project_id);
-        let response = self.client
-            .get(&url)
-            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0")
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            error!("Modrinth API error for project {}: {}", project_id, response.status());
-            return Err(anyhow::anyhow!("Modrinth API error: {}", response.status()));
-        }
-
-        let project: ModrinthProject = response.json().await?;
-        Ok(project)
+        metadata_cache::get(&url, metadata_cache::PROJECT_TTL).await.map_err(|e| {
+            error!("Modrinth API error for project {}: {:#}", project_id, e);
+            e
+        })
     }
 
     /// Get project versions
@@ -211,70 +388,162 @@ impl ModrinthApiClient {
         game_versions: Option<Vec<&str>>,
         loaders: Option<Vec<&str>>,
     ) -> Result<Vec<ModrinthVersion>> {
+        // Modrinth takes list filters as JSON arrays
         let mut params = vec![];
         
         if let Some(versions) = game_versions {
-            for version in versions {
-                params.push(("game_versions", version));
-            }
+            params.push(("game_versions", serde_json::to_string(&versions)?));
         }
         
         if let Some(loaders) = loaders {
-            for loader in loaders {
-                params.push(("loaders", loader));
-            }
+            params.push(("loaders", serde_json::to_string(&loaders)?));
         }
 
-        let url = format!("{}/project/{}/version", self.base_url, project_id);
-        let response = self.client
-            .get(&url)
-            .query(&params)
-            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0")
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            error!("Modrinth API error for project versions {}: {}", project_id, response.status());
-            return Err(anyhow::anyhow!("Modrinth API error: {}", response.status()));
-        }
+        let url = reqwest::Url::parse_with_params(&format!("{}/project/{}/version", self.base_url, project_id), &params)?;
+        metadata_cache::get(url.as_str(), metadata_cache::PROJECT_TTL).await.map_err(|e| {
+            error!("Modrinth API error for project versions {}: {:#}", project_id, e);
+            e
+        })
+    }
 
-        let versions: Vec<ModrinthVersion> = response.json().await?;
-        Ok(versions)
+    /// The newest version of a project for a Minecraft version and loader, preferring releases
+    pub async fn get_latest_version(
+        &self,
+        project_id: &str,
+        game_version: Option<&str>,
+        loader: Option<&str>,
+    ) -> Result<Option<ModrinthVersion>> {
+        let versions = self
+            .get_project_versions(project_id, game_version.map(|v| vec![v]), loader.map(|l| vec![l]))
+            .await?;
+        Ok(best_version(&versions).cloned())
     }
 
     /// Get specific version
     pub async fn get_version(&self, version_id: &str) -> Result<ModrinthVersion> {
         let url = format!("{}/version/{}", self.base_url, version_id);
-        let response = self.client
-            .get(&url)
-            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0")
-            .send()
-            .await?;
+        metadata_cache::get(&url, metadata_cache::PROJECT_TTL).await.map_err(|e| {
+            error!("Modrinth API error for version {}: {:#}", version_id, e);
+            e
+        })
+    }
 
-        if !response.status().is_success() {
-            error!("Modrinth API error for version {}: {}", version_id, response.status());
-            return Err(anyhow::anyhow!("Modrinth API error: {}", response.status()));
+    /// Several versions at once
+    pub async fn get_versions(&self, version_ids: &[&str]) -> Result<Vec<ModrinthVersion>> {
+        if version_ids.is_empty() {
+            return Ok(vec![]);
         }
+        let params = [("ids", serde_json::to_string(version_ids)?)];
+        let url = reqwest::Url::parse_with_params(&format!("{}/versions", self.base_url), &params)?;
+        metadata_cache::get(url.as_str(), metadata_cache::PROJECT_TTL).await
+    }
 
-        let version: ModrinthVersion = response.json().await?;
-        Ok(version)
+    /// The version a file belongs to, by its SHA-1
+    pub async fn get_version_from_hash(&self, sha1: &str) -> Result<ModrinthVersion> {
+        let url = format!("{}/version_file/{}?algorithm=sha1", self.base_url, sha1.to_ascii_lowercase());
+        metadata_cache::get(&url, metadata_cache::PROJECT_TTL).await
     }
 
-    /// Get game versions
-    pub async fn get_game_versions(&self) -> Result<Vec<String>> {
-        let url = format!("{}/tag/game_version", self.base_url);
-        let response = self.client
-            .get(&url)
+    /// The versions files belong to, keyed by SHA-1; unknown files are left out
+    pub async fn get_versions_from_hashes(&self, sha1s: &[String]) -> Result<HashMap<String, ModrinthVersion>> {
+        if sha1s.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let request = self.client
+            .post(format!("{}/version_files", self.base_url))
             .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0")
-            .send()
-            .await?;
-
+            .json(&serde_json::json!({ "hashes": sha1s, "algorithm": "sha1" }));
+        let response = rate_limit::send(request).await?;
         if !response.status().is_success() {
-            error!("Modrinth API error for game versions: {}", response.status());
+            error!("Modrinth API error for version files: {}", response.status());
             return Err(anyhow::anyhow!("Modrinth API error: {}", response.status()));
         }
+        Ok(response.json().await?)
+    }
+
+    /// Every project and version a project depends on, as its author declared them
+    pub async fn get_project_dependencies(&self, project_id: &str) -> Result<ModrinthProjectDependencies> {
+        let url = format!("{}/project/{}/dependencies", self.base_url, project_id);
+        metadata_cache::get(&url, metadata_cache::PROJECT_TTL).await
+    }
 
-        let versions: Vec<serde_json::Value> = response.json().await?;
+    /// The versions `version` needs, directly or through other dependencies.
+    /// Dependencies pinned to a version are taken as is; the others resolve
+    /// to their newest version for the Minecraft version and loader.
+    pub async fn resolve_dependencies(
+        &self,
+        version: &ModrinthVersion,
+        game_version: Option<&str>,
+        loader: Option<&str>,
+    ) -> Result<Vec<ModrinthVersion>> {
+        let mut seen = std::collections::HashSet::from([version.project_id.clone()]);
+        let mut pending: Vec<(ModrinthDependency, String)> = version.dependencies.iter()
+            .map(|dependency| (dependency.clone(), version.project_id.clone()))
+            .collect();
+        let mut resolved = Vec::new();
+
+        while let Some((dependency, dependent)) = pending.pop() {
+            if dependency.dependency_type != "required" {
+                continue;
+            }
+            if resolved.len() >= MAX_DEPENDENCIES {
+                anyhow::bail!("More than {} dependencies; the dependency graph may be broken", MAX_DEPENDENCIES);
+            }
+            let found = match (&dependency.version_id, &dependency.project_id) {
+                (Some(version_id), _) => Some(self.get_version(version_id).await?),
+                (None, Some(project_id)) if !seen.contains(project_id) => {
+                    self.get_latest_version(project_id, game_version, loader).await?
+                }
+                _ => continue,
+            };
+            let Some(found) = found else {
+                anyhow::bail!(
+                    "{} requires project {}, which has no version for {} {}",
+                    dependent,
+                    dependency.project_id.as_deref().unwrap_or("unknown"),
+                    loader.unwrap_or("any loader"),
+                    game_version.unwrap_or("")
+                );
+            };
+            if !seen.insert(found.project_id.clone()) {
+                continue;
+            }
+            pending.extend(found.dependencies.iter().map(|d| (d.clone(), found.project_id.clone())));
+            resolved.push(found);
+        }
+        Ok(resolved)
+    }
+
+    /// Each search result's newest version for the Minecraft version and
+    /// loader; results without one are left out
+    pub async fn with_latest_versions(
+        &self,
+        hits: &[ModrinthSearchHit],
+        game_version: Option<&str>,
+        loader: Option<&str>,
+    ) -> Vec<(ModrinthSearchHit, ModrinthVersion)> {
+        use futures::StreamExt;
+
+        futures::stream::iter(hits.to_vec())
+            .map(|hit| async move {
+                match self.get_latest_version(&hit.project_id, game_version, loader).await {
+                    Ok(version) => version.map(|version| (hit, version)),
+                    Err(e) => {
+                        warn!("Could not get versions of {}: {:#}", hit.project_id, e);
+                        None
+                    }
+                }
+            })
+            .buffered(VERSION_LOOKUPS)
+            .filter_map(|found| async move { found })
+            .collect()
+            .await
+    }
+
+    /// Get game versions
+    pub async fn get_game_versions(&self) -> Result<Vec<String>> {
+        let url = format!("{}/tag/game_version", self.base_url);
+        let versions: Vec<serde_json::Value> = metadata_cache::get(&url, metadata_cache::LOADER_TTL).await?;
         let version_strings: Vec<String> = versions
             .into_iter()
             .filter_map(|v| v.get("version").and_then(|s| s.as_str()).map(|s| s.to_string()))
@@ -286,18 +555,7 @@ impl ModrinthApiClient {
     /// Get loaders
     pub async fn get_loaders(&self) -> Result<Vec<String>> {
         let url = format!("{}/tag/loader", self.base_url);
-        let response = self.client
-            .get(&url)
-            .header("User-Agent", "Guardian-Minecraft-Server-Manager/1.0.0")
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            error!("Modrinth API error for loaders: {}", response.status());
-            return Err(anyhow::anyhow!("Modrinth API error: {}", response.status()));
-        }
-
-        let loaders: Vec<serde_json::Value> = response.json().await?;
+        let loaders: Vec<serde_json::Value> = metadata_cache::get(&url, metadata_cache::LOADER_TTL).await?;
         let loader_strings: Vec<String> = loaders
             .into_iter()
             .filter_map(|v| v.get("name").and_then(|s| s.as_str()).map(|s| s.to_string()))
@@ -306,88 +564,17 @@ impl ModrinthApiClient {
         Ok(loader_strings)
     }
 
-    /// Build facets for search
-    fn build_facets(
-        &self,
-        game_version: Option<&str>,
-        loader: Option<&str>,
-        category: Option<&str>,
-    ) -> Result<String> {
-        let mut facets = Vec::new();
-
-        if let Some(version) = game_version {
-            facets.push(format!(r#"["versions:{}"]"#, version));
-        }
-
-        if let Some(loader) = loader {
-            facets.push(format!(r#"["categories:{}"]"#, loader));
-        }
-
-        if let Some(category) = category {
-            facets.push(format!(r#"["categories:{}"]"#, category));
-        }
-
-        if facets.is_empty() {
-            Ok("[]".to_string())
-        } else {
-            Ok(format!("[{}]", facets.join(",")))
-        }
-    }
-
     /// Convert Modrinth project to our ModInfo format
     pub fn convert_to_mod_info(&self, project: &ModrinthProject) -> crate::database::ModInfo {
         crate::database::ModInfo {
             id: project.id.clone(),
             name: project.title.clone(),
             description: Some(project.description.clone()),
-            category: self.map_category(&project.categories),
-            side: self.map_side(&project.client_side, &project.server_side),
+            category: map_category(&project.categories),
+            side: crate::client_mods::provider_side(&project.client_side, &project.server_side).to_string(),
             source: "modrinth".to_string(),
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-        }
-    }
-
-    /// Map Modrinth categories to our category system
-    fn map_category(&self, categories: &[String]) -> String {
-        // Map Modrinth categories to our category system
-        for category in categories {
-            match category.as_str() {
-                "adventure" => return "adventure".to_string(),
-                "cursed" => return "miscellaneous".to_string(),
-                "decoration" => return "building".to_string(),
-                "economy" => return "economy".to_string(),
-                "equipment" => return "utility".to_string(),
-                "food" => return "utility".to_string(),
-                "game-mechanics" => return "core".to_string(),
-                "library" => return "library".to_string(),
-                "magic" => return "magic".to_string(),
-                "management" => return "utility".to_string(),
-                "minigame" => return "miscellaneous".to_string(),
-                "mobs" => return "mobs".to_string(),
-                "optimization" => return "optimization".to_string(),
-                "social" => return "utility".to_string(),
-                "storage" => return "utility".to_string(),
-                "technology" => return "technology".to_string(),
-                "transportation" => return "transportation".to_string(),
-                "utility" => return "utility".to_string(),
-                "worldgen" => return "world_generation".to_string(),
-                _ => continue,
-            }
-        }
-        "miscellaneous".to_string()
-    }
-
-    /// Map Modrinth side to our side system
-    fn map_side(&self, client_side: &str, server_side: &str) -> String {
-        match (client_side, server_side) {
-            ("required", "required") => "both".to_string(),
-            ("required", "optional") => "client".to_string(),
-            ("optional", "required") => "server".to_string(),
-            ("optional", "optional") => "universal".to_string(),
-            ("unsupported", "required") => "server".to_string(),
-            ("required", "unsupported") => "client".to_string(),
-            _ => "universal".to_string(),
+            created_at: parse_date(&project.published),
+            updated_at: parse_date(&project.updated),
         }
     }
 }
@@ -407,63 +594,16 @@ impl ModProvider for ModrinthApiClient {
         loader: Option<&str>,
         limit: Option<usize>,
     ) -> Result<Vec<ModInfo>, Box<dyn std::error::Error>> {
-        let search_results = self.search_mods(
-            query,
-            minecraft_version,
-            loader,
-            None, // category
-            limit.map(|l| l as u32),
-            None, // offset
-        ).await?;
-        
-        let mut mod_infos = Vec::new();
-        
-        for result in search_results.hits {
-            if let Ok(mod_info) = self.get_mod(&result.id).await {
-                mod_infos.push(mod_info);
-            }
-        }
-        
-        Ok(mod_infos)
+        let listings = self.search_listings(query, minecraft_version, loader, limit).await?;
+        Ok(listings.into_iter().map(|(info, _)| info).collect())
     }
 
     async fn get_mod(&self, mod_id: &str) -> Result<ModInfo, Box<dyn std::error::Error>> {
         let project = self.get_project(mod_id).await?;
-        let versions = self.get_project_versions(mod_id, None, None).await?;
-        
-        if let Some(version_info) = versions.first() {
-            Ok(ModInfo {
-                id: project.id,
-                name: project.title,
-                description: project.description,
-                author: "Unknown".to_string(), // Modrinth doesn't provide author in project response
-                version: version_info.version_number.clone(),
-                minecraft_version: version_info.game_versions.first().cloned().unwrap_or_else(|| "1.20.1".to_string()),
-                loader: version_info.loaders.first().cloned().unwrap_or_else(|| "fabric".to_string()),
-                category: project.categories.first().cloned().unwrap_or_else(|| "misc".to_string()),
-                side: if project.client_side == "required" && project.server_side == "required" {
-                    "both".to_string()
-                } else if project.client_side == "required" {
-                    "client".to_string()
-                } else if project.server_side == "required" {
-                    "server".to_string()
-                } else {
-                    "both".to_string()
-                },
-                download_url: version_info.files.first().map(|f| f.url.clone()),
-                file_size: version_info.files.first().map(|f| f.size),
-                sha1: version_info.files.first().and_then(|f| f.hashes.get("sha1").cloned()),
-                dependencies: version_info.dependencies.iter().map(|d| ModDependency {
-                    mod_id: d.project_id.clone().unwrap_or_else(|| "unknown".to_string()),
-                    version_range: "any".to_string(),
-                    required: d.dependency_type == "required",
-                }).collect(),
-                created_at: project.published.parse().unwrap_or_else(|_| Utc::now()),
-                updated_at: project.updated.parse().unwrap_or_else(|_| Utc::now()),
-            })
-        } else {
-            Err("No versions found for the project".into())
-        }
+        let version = self.get_latest_version(mod_id, None, None).await?
+            .ok_or("No versions found for the project")?;
+        // Projects name a team rather than an author
+        Ok(to_mod_info(&project, &version, "Unknown"))
     }
 
     async fn get_mod_version(
@@ -472,41 +612,9 @@ impl ModProvider for ModrinthApiClient {
         version: &str,
     ) -> Result<ModInfo, Box<dyn std::error::Error>> {
         let project = self.get_project(mod_id).await?;
-        let versions = self.get_project_versions(mod_id, Some(vec![version]), None).await?;
-        
-        if let Some(version_info) = versions.first() {
-            Ok(ModInfo {
-                id: project.id,
-                name: project.title,
-                description: project.description,
-                author: "Unknown".to_string(),
-                version: version_info.version_number.clone(),
-                minecraft_version: version_info.game_versions.first().cloned().unwrap_or_else(|| "1.20.1".to_string()),
-                loader: version_info.loaders.first().cloned().unwrap_or_else(|| "fabric".to_string()),
-                category: project.categories.first().cloned().unwrap_or_else(|| "misc".to_string()),
-                side: if project.client_side == "required" && project.server_side == "required" {
-                    "both".to_string()
-                } else if project.client_side == "required" {
-                    "client".to_string()
-                } else if project.server_side == "required" {
-                    "server".to_string()
-                } else {
-                    "both".to_string()
-                },
-                download_url: version_info.files.first().map(|f| f.url.clone()),
-                file_size: version_info.files.first().map(|f| f.size),
-                sha1: version_info.files.first().and_then(|f| f.hashes.get("sha1").cloned()),
-                dependencies: version_info.dependencies.iter().map(|d| ModDependency {
-                    mod_id: d.project_id.clone().unwrap_or_else(|| "unknown".to_string()),
-                    version_range: "any".to_string(),
-                    required: d.dependency_type == "required",
-                }).collect(),
-                created_at: project.published.parse().unwrap_or_else(|_| Utc::now()),
-                updated_at: project.updated.parse().unwrap_or_else(|_| Utc::now()),
-            })
-        } else {
-            Err("No versions found for the specified version".into())
-        }
+        let version_info = self.get_latest_version(mod_id, Some(version), None).await?
+            .ok_or("No versions found for the specified version")?;
+        Ok(to_mod_info(&project, &version_info, "Unknown"))
     }
 
     async fn download_mod(
@@ -518,10 +626,9 @@ impl ModProvider for ModrinthApiClient {
         let mod_info = self.get_mod_version(mod_id, version).await?;
         
         if let Some(download_url) = mod_info.download_url {
-            let response = self.client.get(&download_url).send().await?;
-            let content = response.bytes().await?;
-            
-            std::fs::write(file_path, content)?;
+            let sha1 = mod_info.sha1.map(|sha1| crate::downloads::Checksum::Sha1(sha1.to_ascii_lowercase()));
+            let download = crate::downloads::Download::new(download_url).checksum(sha1);
+            crate::downloads::shared().fetch_to(&download, std::path::Path::new(file_path)).await?;
             Ok(())
         } else {
             Err("No download URL available".into())
@@ -552,4 +659,75 @@ impl ModProvider for ModrinthApiClient {
         
         Ok(None)
     }
+
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Modrinth
+    }
+
+    async fn search_listings(
+        &self,
+        query: &str,
+        minecraft_version: Option<&str>,
+        loader: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<(ModInfo, ProviderListing)>, Box<dyn std::error::Error>> {
+        let search = ModrinthSearch {
+            query: Some(query),
+            loaders: loader.into_iter().collect(),
+            game_versions: minecraft_version.into_iter().collect(),
+            limit: limit.map_or(20, |l| l as u32),
+            ..Default::default()
+        };
+        let search_results = self.search_mods(&search).await?;
+
+        Ok(self.with_latest_versions(&search_results.hits, minecraft_version, loader).await
+            .iter()
+            .map(|(hit, version)| {
+                let file = primary_file(version);
+                let listing = ProviderListing {
+                    provider: ProviderType::Modrinth.as_str().to_string(),
+                    project_id: hit.project_id.clone(),
+                    slug: hit.slug.clone(),
+                    version_id: version.id.clone(),
+                    filename: file.map(|file| file.filename.clone()).unwrap_or_default(),
+                    sha1: file.and_then(|file| file.hashes.get("sha1").cloned()),
+                    downloads: hit.downloads,
+                };
+                (to_mod_info(&hit.to_project(), version, &hit.author), listing)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_facets() {
+        let search = ModrinthSearch {
+            categories: vec!["technology", "storage"],
+            loaders: vec!["Fabric", "quilt"],
+            game_versions: vec!["1.20.1"],
+            ..Default::default()
+        };
+        assert_eq!(
+            build_facets(&search),
+            r#"[["project_type:mod"],["categories:technology"],["categories:storage"],["categories:fabric","categories:quilt"],["versions:1.20.1"]]"#
+        );
+        assert_eq!(build_facets(&ModrinthSearch::default()), r#"[["project_type:mod"]]"#);
+    }
+
+    #[test]
+    fn test_best_version() {
+        let version = |id: &str, version_type: &str| ModrinthVersion {
+            id: id.to_string(),
+            version_type: version_type.to_string(),
+            ..Default::default()
+        };
+        let versions = [version("b", "beta"), version("r", "release"), version("old", "release")];
+        assert_eq!(best_version(&versions).unwrap().id, "r");
+        assert_eq!(best_version(&versions[..1]).unwrap().id, "b");
+        assert!(best_version(&[]).is_none());
+    }
 }
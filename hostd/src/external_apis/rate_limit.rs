@@ -0,0 +1,305 @@
+//! Rate limiting for the CurseForge, Modrinth and Mojang APIs
+//!
+//! Requests to a provider go through [`send`], which takes a token from the
+//! provider's bucket, holds one of its concurrent request slots and, when the
+//! provider answers 429, backs off for the `Retry-After` it sent (or
+//! exponentially) before retrying. Bulk operations such as modpack installs
+//! are slowed down rather than getting the API key banned. Requests to other
+//! hosts are sent as is.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, warn};
+
+/// How often a request answered with 429 is retried
+const MAX_RETRIES: u32 = 3;
+/// Backoff after the first 429 without a `Retry-After`, doubled on each one after
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiProvider {
+    CurseForge,
+    Modrinth,
+    Mojang,
+}
+
+/// A provider's budget
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Limits {
+    pub requests_per_minute: u32,
+    /// Requests that may be sent at once after a quiet period
+    pub burst: u32,
+    pub max_concurrent: usize,
+}
+
+impl ApiProvider {
+    const ALL: [ApiProvider; 3] = [ApiProvider::CurseForge, ApiProvider::Modrinth, ApiProvider::Mojang];
+
+    /// The provider whose API `url` is on; file CDNs are not limited
+    pub fn for_url(url: &reqwest::Url) -> Option<Self> {
+        match url.host_str()? {
+            "api.curseforge.com" => Some(ApiProvider::CurseForge),
+            "api.modrinth.com" => Some(ApiProvider::Modrinth),
+            "launchermeta.mojang.com" | "piston-meta.mojang.com" | "api.mojang.com" | "sessionserver.mojang.com" => {
+                Some(ApiProvider::Mojang)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn limits(self) -> Limits {
+        match self {
+            // CurseForge doesn't publish its limits; stay well below what gets keys flagged
+            ApiProvider::CurseForge => Limits { requests_per_minute: 100, burst: 10, max_concurrent: 4 },
+            // Modrinth allows 300 a minute per IP
+            ApiProvider::Modrinth => Limits { requests_per_minute: 250, burst: 20, max_concurrent: 8 },
+            // Profile lookups allow about 600 per 10 minutes
+            ApiProvider::Mojang => Limits { requests_per_minute: 60, burst: 20, max_concurrent: 4 },
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Refills at a steady rate up to its capacity
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    per_second: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(limits: Limits, now: Instant) -> Self {
+        Self {
+            capacity: limits.burst as f64,
+            tokens: limits.burst as f64,
+            per_second: limits.requests_per_minute as f64 / 60.0,
+            refilled_at: now,
+        }
+    }
+
+    /// Take a token, or say how long until one is available
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.refilled_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.per_second))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Backoff {
+    until: Option<Instant>,
+    /// 429s since the last success
+    strikes: u32,
+}
+
+impl Backoff {
+    fn throttled(&mut self, retry_after: Option<Duration>, now: Instant) -> Duration {
+        self.strikes += 1;
+        let delay = retry_after.unwrap_or_else(|| INITIAL_BACKOFF * 2u32.saturating_pow(self.strikes - 1)).min(MAX_BACKOFF);
+        self.until = Some(now + delay);
+        delay
+    }
+}
+
+struct ProviderState {
+    bucket: Mutex<TokenBucket>,
+    backoff: Mutex<Backoff>,
+    slots: std::sync::Arc<Semaphore>,
+    requests: AtomicU64,
+    throttled: AtomicU64,
+    /// Time spent waiting for tokens and backoffs
+    waited_ms: AtomicU64,
+    in_flight: AtomicUsize,
+}
+
+/// Request counts of a provider since startup
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderUsage {
+    pub provider: ApiProvider,
+    pub limits: Limits,
+    pub requests: u64,
+    /// Requests answered with 429
+    pub throttled: u64,
+    pub waited_ms: u64,
+    pub in_flight: usize,
+    /// Seconds until requests are sent again after a 429
+    pub backoff_secs: Option<f64>,
+}
+
+fn states() -> &'static [ProviderState] {
+    static STATES: OnceLock<Vec<ProviderState>> = OnceLock::new();
+    STATES.get_or_init(|| {
+        let now = Instant::now();
+        ApiProvider::ALL
+            .iter()
+            .map(|provider| {
+                let limits = provider.limits();
+                ProviderState {
+                    bucket: Mutex::new(TokenBucket::new(limits, now)),
+                    backoff: Mutex::new(Backoff::default()),
+                    slots: std::sync::Arc::new(Semaphore::new(limits.max_concurrent)),
+                    requests: AtomicU64::new(0),
+                    throttled: AtomicU64::new(0),
+                    waited_ms: AtomicU64::new(0),
+                    in_flight: AtomicUsize::new(0),
+                }
+            })
+            .collect()
+    })
+}
+
+/// A concurrent request slot, given back on drop
+struct Permit {
+    _slot: OwnedSemaphorePermit,
+    state: &'static ProviderState,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.state.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Wait until a request to `provider` may be sent
+async fn acquire(provider: ApiProvider) -> Permit {
+    let state = &states()[provider.index()];
+    let slot = state.slots.clone().acquire_owned().await.expect("the semaphore is never closed");
+    let started = Instant::now();
+    loop {
+        let now = Instant::now();
+        let backoff = state.backoff.lock().unwrap().until.filter(|until| *until > now);
+        let wait = match backoff {
+            Some(until) => Some(until - now),
+            None => state.bucket.lock().unwrap().take(now),
+        };
+        match wait {
+            Some(wait) => tokio::time::sleep(wait).await,
+            None => break,
+        }
+    }
+    let waited = started.elapsed();
+    if waited > Duration::from_millis(100) {
+        debug!("Waited {:?} for a {:?} request", waited, provider);
+    }
+    state.waited_ms.fetch_add(waited.as_millis() as u64, Ordering::Relaxed);
+    state.requests.fetch_add(1, Ordering::Relaxed);
+    state.in_flight.fetch_add(1, Ordering::Relaxed);
+    Permit { _slot: slot, state }
+}
+
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Send a request within its provider's limits, retrying when it answers 429
+pub async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let Some(provider) = ApiProvider::for_url(request.url()) else {
+        return Ok(client.execute(request).await?);
+    };
+    let state = &states()[provider.index()];
+
+    let mut attempt = 0;
+    loop {
+        // Requests with a streamed body can't be sent twice
+        let Some(this_try) = request.try_clone() else {
+            let _permit = acquire(provider).await;
+            return Ok(client.execute(request).await?);
+        };
+        let permit = acquire(provider).await;
+        let response = client.execute(this_try).await;
+        drop(permit);
+        let response = response?;
+
+        if response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            state.backoff.lock().unwrap().strikes = 0;
+            return Ok(response);
+        }
+        state.throttled.fetch_add(1, Ordering::Relaxed);
+        let delay = state.backoff.lock().unwrap().throttled(retry_after(&response), Instant::now());
+        attempt += 1;
+        if attempt > MAX_RETRIES {
+            warn!("{:?} is still rate limiting after {} retries", provider, MAX_RETRIES);
+            return Ok(response);
+        }
+        warn!("{:?} rate limited a request, retrying in {:?}", provider, delay);
+    }
+}
+
+/// Usage of every provider
+pub fn usage() -> Vec<ProviderUsage> {
+    let now = Instant::now();
+    ApiProvider::ALL
+        .iter()
+        .map(|provider| {
+            let state = &states()[provider.index()];
+            ProviderUsage {
+                provider: *provider,
+                limits: provider.limits(),
+                requests: state.requests.load(Ordering::Relaxed),
+                throttled: state.throttled.load(Ordering::Relaxed),
+                waited_ms: state.waited_ms.load(Ordering::Relaxed),
+                in_flight: state.in_flight.load(Ordering::Relaxed),
+                backoff_secs: state.backoff.lock().unwrap().until.filter(|until| *until > now).map(|until| (until - now).as_secs_f64()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let limits = Limits { requests_per_minute: 60, burst: 2, max_concurrent: 1 };
+        let mut bucket = TokenBucket::new(limits, start);
+        assert!(bucket.take(start).is_none());
+        assert!(bucket.take(start).is_none());
+        let wait = bucket.take(start).unwrap();
+        assert!(wait > Duration::from_millis(990) && wait <= Duration::from_secs(1));
+        assert!(bucket.take(start + Duration::from_secs(1)).is_none());
+        // Never refills past the burst
+        let later = start + Duration::from_secs(600);
+        assert!(bucket.take(later).is_none());
+        assert!(bucket.take(later).is_none());
+        assert!(bucket.take(later).is_some());
+    }
+
+    #[test]
+    fn test_backoff() {
+        let now = Instant::now();
+        let mut backoff = Backoff::default();
+        assert_eq!(backoff.throttled(None, now), Duration::from_secs(1));
+        assert_eq!(backoff.throttled(None, now), Duration::from_secs(2));
+        assert_eq!(backoff.throttled(Some(Duration::from_secs(30)), now), Duration::from_secs(30));
+        assert_eq!(backoff.throttled(Some(Duration::from_secs(600)), now), MAX_BACKOFF);
+    }
+}
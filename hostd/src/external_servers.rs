@@ -0,0 +1,278 @@
+//! External servers: servers Guardian monitors but does not run. They are
+//! registered by address and RCON credentials and stored as `managed: false`
+//! servers. The monitor's loop checks each one with a Server List Ping, a
+//! query and an RCON connect, caches the result for the API, and records the
+//! player count into metrics history like any other server.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::database::{DatabaseManager, ServerConfig, ServerMetric};
+use crate::performance_telemetry::MetricsResolution;
+use crate::server_ping::{self, ServerStatus};
+use crate::server_query::{self, QueryStatus};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+const DEFAULT_RCON_PORT: u16 = 25575;
+/// `loader` of an external server whose software Guardian can't see
+const UNKNOWN: &str = "unknown";
+
+#[derive(Debug, Deserialize)]
+pub struct ExternalServerRequest {
+    pub name: String,
+    pub host: String,
+    pub port: Option<u16>,
+    pub rcon_port: Option<u16>,
+    pub rcon_password: Option<String>,
+    /// Defaults to the game port, as `query.port` does in server.properties
+    pub query_port: Option<u16>,
+}
+
+/// Last check of an external server
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalStatus {
+    /// Answered the Server List Ping
+    pub online: bool,
+    /// RCON port accepted a connection
+    pub rcon: bool,
+    pub ping: Option<ServerStatus>,
+    /// None when query is disabled on the server
+    pub query: Option<QueryStatus>,
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl ExternalStatus {
+    pub fn players_online(&self) -> u32 {
+        self.query
+            .as_ref()
+            .map(|query| query.online_players)
+            .or(self.ping.as_ref().map(|ping| ping.online_players))
+            .unwrap_or(0)
+    }
+}
+
+pub struct ExternalServerMonitor {
+    database: Arc<DatabaseManager>,
+    statuses: RwLock<HashMap<String, ExternalStatus>>,
+}
+
+impl ExternalServerMonitor {
+    pub fn new(database: Arc<DatabaseManager>) -> Self {
+        Self { database, statuses: RwLock::new(HashMap::new()) }
+    }
+
+    /// Store a new external server, filling in its version and player limit
+    /// from a ping when it answers
+    pub async fn register(&self, request: ExternalServerRequest) -> Result<ServerConfig> {
+        let name = request.name.trim();
+        let host = request.host.trim();
+        if name.is_empty() {
+            bail!("Server name is required");
+        }
+        if host.is_empty() {
+            bail!("Server host is required");
+        }
+        let port = request.port.unwrap_or(server_ping::DEFAULT_PORT);
+        let now = Utc::now();
+        let mut config = ServerConfig {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            minecraft_version: UNKNOWN.to_string(),
+            loader: UNKNOWN.to_string(),
+            loader_version: UNKNOWN.to_string(),
+            port,
+            rcon_port: request.rcon_port.unwrap_or(DEFAULT_RCON_PORT),
+            query_port: request.query_port.unwrap_or(port),
+            max_players: 0,
+            memory: 0,
+            java_args: "[]".to_string(),
+            server_args: "[]".to_string(),
+            auto_start: false,
+            auto_restart: false,
+            world_name: String::new(),
+            difficulty: String::new(),
+            gamemode: String::new(),
+            pvp: true,
+            online_mode: true,
+            whitelist: false,
+            enable_command_block: false,
+            view_distance: 0,
+            simulation_distance: 0,
+            motd: String::new(),
+            host: host.to_string(),
+            java_path: String::new(),
+            jvm_args: String::new(),
+            server_jar: String::new(),
+            server_directory: String::new(),
+            rcon_password: request.rcon_password.unwrap_or_default(),
+            managed: false,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let status = check(&config).await;
+        if let Some(ping) = &status.ping {
+            config.minecraft_version = ping.version.clone();
+            config.max_players = ping.max_players;
+            config.motd = ping.motd.clone();
+        }
+        self.database.create_server(&config).await?;
+        info!("Registered external server {} at {}:{}", config.id, config.host, config.port);
+        self.statuses.write().await.insert(config.id.clone(), status);
+        Ok(config)
+    }
+
+    /// Cached status of an external server, checking it now if it has none yet
+    pub async fn status(&self, server: &ServerConfig) -> ExternalStatus {
+        if let Some(status) = self.statuses.read().await.get(&server.id) {
+            return status.clone();
+        }
+        self.refresh(server).await
+    }
+
+    /// Check an external server now and cache the result
+    pub async fn refresh(&self, server: &ServerConfig) -> ExternalStatus {
+        let status = check(server).await;
+        self.statuses.write().await.insert(server.id.clone(), status.clone());
+        status
+    }
+
+    /// Everyone online: the query's full list, or the ping's sample when query is off
+    pub async fn players(&self, server: &ServerConfig) -> Result<Vec<String>> {
+        if let Ok(query) = server_query::query(&server.host, server.query_port, CHECK_TIMEOUT).await {
+            return Ok(query.players);
+        }
+        let ping = server_ping::ping(&server.host, server.port, CHECK_TIMEOUT)
+            .await
+            .map_err(|e| anyhow!("Server is not reachable: {}", e))?;
+        Ok(ping.player_sample.into_iter().map(|player| player.name).collect())
+    }
+
+    pub async fn forget(&self, server_id: &str) {
+        self.statuses.write().await.remove(server_id);
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting external server monitoring");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_all().await {
+                error!("External server check failed: {}", e);
+            }
+        }
+    }
+
+    async fn check_all(&self) -> Result<()> {
+        let servers: Vec<ServerConfig> =
+            self.database.get_all_servers().await?.into_iter().filter(|server| !server.managed).collect();
+        let checks = servers.iter().map(|server| async move { (server, check(server).await) });
+        let results = futures::future::join_all(checks).await;
+
+        let mut statuses = self.statuses.write().await;
+        statuses.retain(|id, _| servers.iter().any(|server| &server.id == id));
+        for (server, status) in results {
+            if status.online {
+                let metric = ServerMetric {
+                    id: Uuid::new_v4().to_string(),
+                    server_id: server.id.clone(),
+                    resolution: MetricsResolution::Raw.as_str().to_string(),
+                    tps: 0.0,
+                    tick_p95: 0.0,
+                    heap_mb: 0,
+                    players_online: status.players_online(),
+                    gpu_queue_ms: 0.0,
+                    cpu_usage: 0.0,
+                    memory_usage: 0,
+                    disk_usage: 0,
+                    network_in: 0,
+                    network_out: 0,
+                    timestamp: status.checked_at,
+                };
+                if let Err(e) = self.database.insert_server_metric(&metric).await {
+                    debug!("Failed to store metrics for external server {}: {}", server.id, e);
+                }
+            }
+            statuses.insert(server.id.clone(), status);
+        }
+        Ok(())
+    }
+}
+
+/// Ping, query and RCON connect, all at once
+async fn check(server: &ServerConfig) -> ExternalStatus {
+    let (ping, query, rcon) = tokio::join!(
+        server_ping::ping(&server.host, server.port, CHECK_TIMEOUT),
+        server_query::query(&server.host, server.query_port, CHECK_TIMEOUT),
+        tokio::time::timeout(CHECK_TIMEOUT, tokio::net::TcpStream::connect((server.host.as_str(), server.rcon_port))),
+    );
+    ExternalStatus {
+        online: ping.is_ok(),
+        rcon: matches!(rcon, Ok(Ok(_))),
+        error: ping.as_ref().err().map(|e| e.to_string()),
+        ping: ping.ok(),
+        query: query.ok(),
+        checked_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_unreachable_server() {
+        // Bind and drop to get a port nothing listens on
+        let port = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        let now = Utc::now();
+        let server = ServerConfig {
+            id: Uuid::new_v4().to_string(),
+            name: "External".to_string(),
+            minecraft_version: UNKNOWN.to_string(),
+            loader: UNKNOWN.to_string(),
+            loader_version: UNKNOWN.to_string(),
+            port,
+            rcon_port: port,
+            query_port: port,
+            max_players: 0,
+            memory: 0,
+            java_args: "[]".to_string(),
+            server_args: "[]".to_string(),
+            auto_start: false,
+            auto_restart: false,
+            world_name: String::new(),
+            difficulty: String::new(),
+            gamemode: String::new(),
+            pvp: true,
+            online_mode: true,
+            whitelist: false,
+            enable_command_block: false,
+            view_distance: 0,
+            simulation_distance: 0,
+            motd: String::new(),
+            host: "127.0.0.1".to_string(),
+            java_path: String::new(),
+            jvm_args: String::new(),
+            server_jar: String::new(),
+            server_directory: String::new(),
+            rcon_password: String::new(),
+            managed: false,
+            created_at: now,
+            updated_at: now,
+        };
+        let status = check(&server).await;
+        assert!(!status.online);
+        assert!(!status.rcon);
+        assert!(status.error.is_some());
+        assert_eq!(status.players_online(), 0);
+    }
+}
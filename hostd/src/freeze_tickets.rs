@@ -0,0 +1,608 @@
+//! Freeze detection: every running server is probed over RCON while its
+//! `logs/latest.log` is watched. When a probe gets no answer and the log stays
+//! quiet for [`STALL_THRESHOLD`], the server thread is considered stuck: a
+//! thread dump is captured with `jcmd` (or `jstack`), the server thread's stack
+//! is classified and a [`FreezeTicket`] is opened. The ticket is closed as
+//! recovered once the server answers again, or as crashed when the process
+//! exits, in which case the crash report fills in the stuck entity or block.
+//! Tickets can be thawed by killing the entity over RCON or, with the server
+//! stopped, by dropping the chunk so it regenerates.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::config_revisions::server_dir;
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, EventLog, FreezeLocation, FreezeTicket, ServerConfig};
+use crate::websocket_manager::{WebSocketManager, WebSocketMessage};
+use crate::core::error_handler::NotFound;
+
+const EVENT_TYPE: &str = "freeze";
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// How long the server may neither answer RCON nor log before it counts as frozen
+pub const STALL_THRESHOLD: Duration = Duration::from_secs(15);
+const DUMP_TIMEOUT: Duration = Duration::from_secs(30);
+/// Log bytes read per check; anything past this still counts as activity
+const MAX_LOG_READ: u64 = 64 * 1024;
+const DEFAULT_KILL_RADIUS: f64 = 8.0;
+
+/// Frames that belong to the JVM, the game or the mod loader rather than a mod
+const PLATFORM_PREFIXES: &[&str] = &[
+    "java.", "jdk.", "sun.", "net.minecraft.", "com.mojang.", "it.unimi.", "io.netty.",
+    "org.spongepowered.", "net.minecraftforge.", "net.neoforged.", "net.fabricmc.",
+    "cpw.mods.", "io.papermc.", "org.bukkit.", "com.destroystokyo.", "org.spigotmc.",
+];
+
+/// What the server thread was doing, taken from its stack
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackAnalysis {
+    pub suspect_kind: &'static str,
+    pub suspect_frame: Option<String>,
+    /// Server thread frames, innermost first
+    pub stack: Vec<String>,
+}
+
+/// The stuck entity or block from a crash report
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CrashLocation {
+    pub entity_type: Option<String>,
+    pub location: Option<FreezeLocation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThawAction {
+    /// Kill entities of the ticket's type around its location over RCON
+    KillEntity,
+    /// Drop the chunk at the ticket's location so it regenerates; the server must be stopped
+    RegenerateChunk,
+}
+
+/// Thaw request; location fields override what the ticket recorded
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThawRequest {
+    pub action: ThawAction,
+    pub entity_type: Option<String>,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub z: Option<f64>,
+    pub dimension: Option<String>,
+    /// Kill radius in blocks
+    pub radius: Option<f64>,
+}
+
+/// Per-server probe state
+struct Watch {
+    probe: Option<(Instant, JoinHandle<Result<String>>)>,
+    log_offset: Option<u64>,
+    last_activity: Instant,
+    /// Ticket opened for the current stall
+    ticket: Option<String>,
+}
+
+impl Watch {
+    fn new() -> Self {
+        Self { probe: None, log_offset: None, last_activity: Instant::now(), ticket: None }
+    }
+}
+
+pub struct FreezeDetector {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+    websocket_manager: Arc<WebSocketManager>,
+    watches: RwLock<HashMap<String, Watch>>,
+}
+
+impl FreezeDetector {
+    pub fn new(
+        database: Arc<DatabaseManager>,
+        process_manager: Arc<ProcessManager>,
+        websocket_manager: Arc<WebSocketManager>,
+    ) -> Self {
+        Self {
+            database,
+            process_manager,
+            websocket_manager,
+            watches: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting freeze detector");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_servers().await {
+                error!("Freeze detector error: {}", e);
+            }
+        }
+    }
+
+    async fn check_servers(&self) -> Result<()> {
+        let mut running = HashMap::new();
+        for server in self.database.get_all_servers().await? {
+            let Ok(uuid) = Uuid::parse_str(&server.id) else {
+                continue;
+            };
+            if server.managed && !server.rcon_password.is_empty() && self.process_manager.is_server_running(uuid).await {
+                running.insert(server.id.clone(), (uuid, server));
+            }
+        }
+
+        let mut watches = self.watches.write().await;
+        let gone: Vec<String> = watches.keys().filter(|id| !running.contains_key(*id)).cloned().collect();
+        for id in gone {
+            if let Some(watch) = watches.remove(&id) {
+                if let Some((_, probe)) = &watch.probe {
+                    probe.abort();
+                }
+                if let Some(ticket_id) = watch.ticket {
+                    self.close_crashed(&id, &ticket_id).await;
+                }
+            }
+        }
+
+        for (id, (uuid, server)) in running {
+            let watch = watches.entry(id).or_insert_with(Watch::new);
+            if self.log_activity(&server, watch).await {
+                watch.last_activity = Instant::now();
+            }
+
+            let pending_since = match &watch.probe {
+                Some((started, probe)) if !probe.is_finished() => Some(*started),
+                _ => None,
+            };
+            let Some(started) = pending_since else {
+                if let Some((started, probe)) = watch.probe.take() {
+                    let answered = matches!(probe.await, Ok(Ok(_)));
+                    if answered {
+                        if let Some(ticket_id) = watch.ticket.take() {
+                            self.close_recovered(&ticket_id, started.elapsed()).await;
+                        }
+                    }
+                }
+                let probe_server = server.clone();
+                let probe = tokio::spawn(async move { crate::restart_scheduler::rcon(&probe_server, "list".to_string()).await });
+                watch.probe = Some((Instant::now(), probe));
+                continue;
+            };
+
+            let stalled = started.elapsed() >= STALL_THRESHOLD && watch.last_activity.elapsed() >= STALL_THRESHOLD;
+            if stalled && watch.ticket.is_none() {
+                match self.open_ticket(&server, uuid, started.elapsed()).await {
+                    Ok(ticket_id) => watch.ticket = Some(ticket_id),
+                    Err(e) => error!("Failed to open a freeze ticket for server {}: {}", server.id, e),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the server logged anything besides RCON connections since the last check
+    async fn log_activity(&self, server: &ServerConfig, watch: &mut Watch) -> bool {
+        let path = server_dir(server).join("logs").join("latest.log");
+        let Ok(mut file) = tokio::fs::File::open(&path).await else {
+            return false;
+        };
+        let Ok(len) = file.metadata().await.map(|metadata| metadata.len()) else {
+            return false;
+        };
+        let Some(offset) = watch.log_offset.replace(len) else {
+            return false;
+        };
+        // A shorter file was rotated
+        let from = if len < offset { 0 } else { offset };
+        if len == from {
+            return false;
+        }
+        if len - from > MAX_LOG_READ || file.seek(std::io::SeekFrom::Start(from)).await.is_err() {
+            return true;
+        }
+        let mut appended = Vec::new();
+        if file.take(len - from).read_to_end(&mut appended).await.is_err() {
+            return true;
+        }
+        has_activity(&String::from_utf8_lossy(&appended))
+    }
+
+    async fn open_ticket(&self, server: &ServerConfig, uuid: Uuid, stalled_for: Duration) -> Result<String> {
+        let pid = self.process_manager.get_process_info(uuid).await.ok().map(|info| info.pid);
+        let (analysis, thread_dump) = match pid {
+            Some(pid) => match capture_thread_dump(server, pid).await {
+                Ok((path, dump)) => (analyze(&dump), Some(path.to_string_lossy().to_string())),
+                Err(e) => {
+                    warn!("Failed to capture a thread dump of server {}: {}", server.id, e);
+                    (analyze(""), None)
+                }
+            },
+            None => (analyze(""), None),
+        };
+
+        let ticket = FreezeTicket {
+            id: Uuid::new_v4().to_string(),
+            server_id: server.id.clone(),
+            status: "open".to_string(),
+            suspect_kind: analysis.suspect_kind.to_string(),
+            suspect_frame: analysis.suspect_frame,
+            entity_type: None,
+            location: None,
+            stack: analysis.stack,
+            thread_dump,
+            duration_ms: None,
+            resolution: None,
+            created_at: Utc::now(),
+            resolved_at: None,
+        };
+        self.database.save_freeze_ticket(&ticket).await?;
+
+        let mut message = format!("Server thread stalled for {}s", stalled_for.as_secs());
+        if ticket.suspect_kind != "unknown" {
+            message.push_str(&format!(" while ticking a {}", ticket.suspect_kind.replace('_', " ")));
+        }
+        if let Some(frame) = &ticket.suspect_frame {
+            message.push_str(&format!(" ({})", frame));
+        }
+        self.log_event(&ticket, message, "warn").await;
+        let _ = self
+            .websocket_manager
+            .broadcast(WebSocketMessage::WorldFreeze {
+                server_id: server.id.clone(),
+                timestamp: ticket.created_at,
+                x: 0,
+                z: 0,
+                duration_ms: stalled_for.as_millis() as u64,
+            })
+            .await;
+        Ok(ticket.id)
+    }
+
+    async fn close_recovered(&self, ticket_id: &str, stalled_for: Duration) {
+        let Ok(Some(mut ticket)) = self.database.get_freeze_ticket(ticket_id).await else {
+            return;
+        };
+        ticket.status = "recovered".to_string();
+        ticket.duration_ms = Some(stalled_for.as_millis() as u64);
+        ticket.resolved_at = Some(Utc::now());
+        ticket.resolution = Some("Server thread resumed".to_string());
+        if let Err(e) = self.database.save_freeze_ticket(&ticket).await {
+            error!("Failed to update freeze ticket {}: {}", ticket.id, e);
+            return;
+        }
+        let message = format!("Server thread resumed after {}s", stalled_for.as_secs());
+        self.log_event(&ticket, message, "info").await;
+    }
+
+    /// The process exited while frozen, usually killed by the game's own watchdog
+    async fn close_crashed(&self, server_id: &str, ticket_id: &str) {
+        let Ok(Some(mut ticket)) = self.database.get_freeze_ticket(ticket_id).await else {
+            return;
+        };
+        if let Ok(Some(server)) = self.database.get_server(server_id).await {
+            if let Some(report) = latest_crash_report(&server, ticket.created_at.into()).await {
+                let crash = parse_crash_location(&report);
+                ticket.entity_type = crash.entity_type;
+                ticket.location = crash.location;
+            }
+        }
+        let now = Utc::now();
+        ticket.status = "crashed".to_string();
+        ticket.duration_ms = Some((now - ticket.created_at).num_milliseconds().max(0) as u64);
+        ticket.resolved_at = Some(now);
+        ticket.resolution = Some("Server stopped while frozen".to_string());
+        if let Err(e) = self.database.save_freeze_ticket(&ticket).await {
+            error!("Failed to update freeze ticket {}: {}", ticket.id, e);
+            return;
+        }
+        self.log_event(&ticket, "Server stopped while frozen".to_string(), "error").await;
+    }
+
+    pub async fn tickets(&self, server_id: &str) -> Result<Vec<FreezeTicket>> {
+        self.database.get_freeze_tickets(server_id).await
+    }
+
+    /// Kill the stuck entity or regenerate the stuck chunk, then close the ticket
+    pub async fn thaw(&self, server_id: &str, ticket_id: &str, request: ThawRequest) -> Result<FreezeTicket> {
+        let mut ticket = self
+            .database
+            .get_freeze_ticket(ticket_id)
+            .await?
+            .filter(|ticket| ticket.server_id == server_id)
+            .ok_or_else(|| NotFound::with_id("Freeze ticket", ticket_id))?;
+        let server = self
+            .database
+            .get_server(server_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Server", server_id))?;
+        let running = self.process_manager.is_server_running(Uuid::parse_str(server_id)?).await;
+
+        let recorded = ticket.location.clone();
+        let location = match (request.x.or(recorded.as_ref().map(|l| l.x)), request.z.or(recorded.as_ref().map(|l| l.z))) {
+            (Some(x), Some(z)) => FreezeLocation {
+                x,
+                y: request.y.or(recorded.as_ref().map(|l| l.y)).unwrap_or(64.0),
+                z,
+                dimension: request.dimension.clone().or(recorded.and_then(|l| l.dimension)),
+            },
+            _ => bail!("The ticket has no location; pass x and z"),
+        };
+        let dimension = location.dimension.clone().unwrap_or_else(|| "minecraft:overworld".to_string());
+
+        let resolution = match request.action {
+            ThawAction::KillEntity => {
+                if !running {
+                    bail!("The server must be running to kill entities");
+                }
+                let entity_type = request
+                    .entity_type
+                    .clone()
+                    .or(ticket.entity_type.clone())
+                    .ok_or_else(|| anyhow!("The ticket has no entity type; pass entity_type"))?;
+                let radius = request.radius.unwrap_or(DEFAULT_KILL_RADIUS);
+                let command = kill_command(&entity_type, &location, &dimension, radius)?;
+                let response = crate::restart_scheduler::rcon(&server, command).await?;
+                format!("Killed {} near {:.0}, {:.0}, {:.0}: {}", entity_type, location.x, location.y, location.z, response.trim())
+            }
+            ThawAction::RegenerateChunk => {
+                if running {
+                    bail!("Stop the server before regenerating a chunk");
+                }
+                let world = crate::world::server_world_dir(&server);
+                let dimension_dir = crate::world::dimension_root(&world, &dimension)
+                    .ok_or_else(|| anyhow!("Unsupported dimension {}", dimension))?;
+                let (chunk_x, chunk_z) = ((location.x.floor() as i32) >> 4, (location.z.floor() as i32) >> 4);
+                let removed = tokio::task::spawn_blocking(move || {
+                    crate::world::trim::remove_chunk(&dimension_dir, chunk_x, chunk_z)
+                })
+                .await??;
+                if !removed {
+                    bail!("Chunk {}, {} in {} has not been generated", chunk_x, chunk_z, dimension);
+                }
+                format!("Chunk {}, {} in {} will regenerate on next load", chunk_x, chunk_z, dimension)
+            }
+        };
+
+        if ticket.entity_type.is_none() {
+            ticket.entity_type = request.entity_type;
+        }
+        ticket.location = Some(location);
+        ticket.status = "thawed".to_string();
+        ticket.resolution = Some(resolution.clone());
+        ticket.resolved_at = Some(Utc::now());
+        self.database.save_freeze_ticket(&ticket).await?;
+        if let Some(watch) = self.watches.write().await.get_mut(server_id) {
+            if watch.ticket.as_deref() == Some(ticket_id) {
+                watch.ticket = None;
+            }
+        }
+        self.log_event(&ticket, resolution, "info").await;
+        Ok(ticket)
+    }
+
+    async fn log_event(&self, ticket: &FreezeTicket, message: String, level: &str) {
+        info!("{}: {}", ticket.server_id, message);
+        let event = EventLog {
+            id: Uuid::new_v4().to_string(),
+            server_id: Some(ticket.server_id.clone()),
+            event_type: EVENT_TYPE.to_string(),
+            message,
+            level: level.to_string(),
+            metadata: serde_json::to_value(ticket).ok(),
+            created_at: Utc::now(),
+        };
+        if let Err(e) = self.database.log_event(&event).await {
+            error!("Failed to log freeze ticket {}: {}", ticket.id, e);
+        }
+    }
+}
+
+/// Lines other than the RCON connections the probe itself causes
+fn has_activity(appended: &str) -> bool {
+    appended.lines().any(|line| !line.trim().is_empty() && !line.contains("RCON"))
+}
+
+/// `jcmd` (or `jstack`) next to the server's java, falling back to the one on PATH
+pub(crate) fn jdk_tool(server: &ServerConfig, name: &str) -> PathBuf {
+    let executable = if cfg!(windows) { format!("{}.exe", name) } else { name.to_string() };
+    Path::new(&server.java_path)
+        .parent()
+        .map(|dir| dir.join(&executable))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(executable))
+}
+
+/// Capture a thread dump into the server's diagnostics folder
+async fn capture_thread_dump(server: &ServerConfig, pid: u32) -> Result<(PathBuf, String)> {
+    let attempts = [
+        (jdk_tool(server, "jcmd"), vec![pid.to_string(), "Thread.print".to_string()]),
+        (jdk_tool(server, "jstack"), vec![pid.to_string()]),
+    ];
+    let mut last_error = anyhow!("no JDK tool available");
+    for (tool, args) in attempts {
+        let output = tokio::process::Command::new(&tool).args(&args).kill_on_drop(true).output();
+        match tokio::time::timeout(DUMP_TIMEOUT, output).await {
+            Ok(Ok(output)) if output.status.success() => {
+                let dump = String::from_utf8_lossy(&output.stdout).to_string();
+                let dir = crate::diagnostics::diagnostics_dir(server);
+                tokio::fs::create_dir_all(&dir).await?;
+                let path = dir.join(format!("thread-dump-{}.txt", Utc::now().format("%Y-%m-%d_%H.%M.%S")));
+                tokio::fs::write(&path, &dump).await?;
+                return Ok((path, dump));
+            }
+            Ok(Ok(output)) => {
+                last_error = anyhow!("{} failed: {}", tool.display(), String::from_utf8_lossy(&output.stderr).trim());
+            }
+            Ok(Err(e)) => last_error = anyhow!("{}: {}", tool.display(), e),
+            Err(_) => last_error = anyhow!("{} timed out", tool.display()),
+        }
+    }
+    Err(last_error)
+}
+
+/// Classify the `Server thread` stack of a thread dump. The outermost frame that
+/// dispatches a tick decides what was being ticked; the innermost non-platform
+/// frame is the likely culprit. Obfuscated names (Fabric's intermediary) stay `unknown`.
+pub fn analyze(dump: &str) -> StackAnalysis {
+    let mut stack = Vec::new();
+    let mut in_server_thread = false;
+    for line in dump.lines() {
+        if line.starts_with('"') {
+            if in_server_thread {
+                break;
+            }
+            in_server_thread = line.starts_with("\"Server thread\"");
+            continue;
+        }
+        if in_server_thread {
+            if let Some(frame) = line.trim().strip_prefix("at ") {
+                stack.push(frame.to_string());
+            }
+        }
+    }
+
+    let suspect_kind = stack
+        .iter()
+        .rev()
+        .find_map(|frame| {
+            if frame.contains("tickBlockEntities") || frame.contains("TickingBlockEntity") || frame.contains("BlockEntityTicker") {
+                Some("block_entity")
+            } else if frame.contains("guardEntityTick") || frame.contains("tickNonPassenger") || frame.contains("EntityTickList") {
+                Some("entity")
+            } else if frame.contains("ChunkMap") || frame.contains("ServerChunkCache") || frame.contains("ChunkStatus") {
+                Some("chunk")
+            } else {
+                None
+            }
+        })
+        .unwrap_or("unknown");
+    let suspect_frame = stack
+        .iter()
+        .find(|frame| !PLATFORM_PREFIXES.iter().any(|prefix| frame.starts_with(prefix)))
+        .or(stack.first())
+        .cloned();
+
+    StackAnalysis { suspect_kind, suspect_frame, stack }
+}
+
+fn parse_triple(text: &str) -> Option<(f64, f64, f64)> {
+    let mut parts = text.split(',').map(|part| part.trim().parse::<f64>().ok());
+    Some((parts.next()??, parts.next()??, parts.next()??))
+}
+
+/// The ticked entity or block entity of a crash report
+pub fn parse_crash_location(report: &str) -> CrashLocation {
+    let mut crash = CrashLocation::default();
+    let mut dimension = None;
+    for line in report.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("Entity Type:") {
+            crash.entity_type = value.split_whitespace().next().map(str::to_string);
+        } else if let Some(value) = line.strip_prefix("Entity's Exact location:") {
+            if let Some((x, y, z)) = parse_triple(value) {
+                crash.location = Some(FreezeLocation { x, y, z, dimension: None });
+            }
+        } else if let Some(value) = line.strip_prefix("Block location: World: (") {
+            if crash.location.is_none() {
+                if let Some((x, y, z)) = value.split(')').next().and_then(parse_triple) {
+                    crash.location = Some(FreezeLocation { x, y, z, dimension: None });
+                }
+            }
+        } else if let Some(value) = line.strip_prefix("Level dimension:") {
+            dimension.get_or_insert_with(|| value.trim().to_string());
+        }
+    }
+    if let Some(location) = crash.location.as_mut() {
+        location.dimension = dimension;
+    }
+    crash
+}
+
+/// Newest crash report written after `since`
+async fn latest_crash_report(server: &ServerConfig, since: std::time::SystemTime) -> Option<String> {
+    let mut entries = tokio::fs::read_dir(server_dir(server).join("crash-reports")).await.ok()?;
+    let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Ok(modified) = entry.metadata().await.and_then(|metadata| metadata.modified()) else {
+            continue;
+        };
+        if modified >= since && newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+            newest = Some((modified, entry.path()));
+        }
+    }
+    tokio::fs::read_to_string(newest?.1).await.ok()
+}
+
+fn kill_command(entity_type: &str, location: &FreezeLocation, dimension: &str, radius: f64) -> Result<String> {
+    let valid = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "_-.:/".contains(c));
+    if !valid(entity_type) || !valid(dimension) {
+        bail!("Invalid entity type or dimension");
+    }
+    Ok(format!(
+        "execute in {} run kill @e[type={},x={},y={},z={},distance=..{}]",
+        dimension, entity_type, location.x, location.y, location.z, radius
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"2024-05-01 12:00:00
+Full thread dump OpenJDK 64-Bit Server VM (17.0.9+9 mixed mode):
+
+"Server thread" #25 prio=5 os_prio=0 cpu=91234.56ms elapsed=3600.12s tid=0x00007f nid=0x1a runnable  [0x00007f]
+   java.lang.Thread.State: RUNNABLE
+	at java.util.HashMap.getNode(HashMap.java:570)
+	at com.example.pipes.PipeNetwork.walk(PipeNetwork.java:88)
+	at net.minecraft.world.level.block.entity.TickingBlockEntity.tick(TickingBlockEntity.java)
+	at net.minecraft.world.level.Level.tickBlockEntities(Level.java:480)
+	at net.minecraft.server.level.ServerLevel.tick(ServerLevel.java:403)
+	at net.minecraft.server.MinecraftServer.runServer(MinecraftServer.java:680)
+
+"Server Watchdog" #40 daemon prio=5 os_prio=0 tid=0x00007f nid=0x2b waiting on condition
+	at java.lang.Thread.sleep(Native Method)
+"#;
+
+    #[test]
+    fn test_analyze_server_thread() {
+        let analysis = analyze(DUMP);
+        assert_eq!(analysis.stack.len(), 6);
+        assert_eq!(analysis.suspect_kind, "block_entity");
+        assert_eq!(analysis.suspect_frame.as_deref(), Some("com.example.pipes.PipeNetwork.walk(PipeNetwork.java:88)"));
+
+        let empty = analyze("");
+        assert_eq!(empty.suspect_kind, "unknown");
+        assert!(empty.suspect_frame.is_none());
+    }
+
+    #[test]
+    fn test_parse_crash_location() {
+        let report = "-- Entity being ticked --\nDetails:\n\tEntity Type: minecraft:zombie (net.minecraft.world.entity.monster.Zombie)\n\tEntity's Exact location: 10.50, 64.00, -20.30\n\tEntity's Block location: World: (10,64,-21), Section: (at 10,0,11 in 0,4,-2)\n-- Affected level --\nDetails:\n\tLevel dimension: minecraft:the_nether\n";
+        let crash = parse_crash_location(report);
+        assert_eq!(crash.entity_type.as_deref(), Some("minecraft:zombie"));
+        let location = crash.location.unwrap();
+        assert_eq!((location.x, location.y, location.z), (10.5, 64.0, -20.3));
+        assert_eq!(location.dimension.as_deref(), Some("minecraft:the_nether"));
+
+        let block = parse_crash_location("\tBlock location: World: (-5,70,12), Section: (at 11,6,12 in -1,4,0)\n");
+        let location = block.location.unwrap();
+        assert_eq!((location.x, location.y, location.z), (-5.0, 70.0, 12.0));
+        assert!(block.entity_type.is_none());
+    }
+
+    #[test]
+    fn test_log_activity_ignores_rcon() {
+        assert!(!has_activity("[12:00:00] [RCON Listener #1/INFO]: Thread RCON Client /127.0.0.1 started\n"));
+        assert!(has_activity("[12:00:01] [Server thread/INFO]: Saving the game\n"));
+        assert!(kill_command("minecraft:zombie", &FreezeLocation { x: 1.0, y: 2.0, z: 3.0, dimension: None }, "minecraft:overworld", 8.0).is_ok());
+        assert!(kill_command("zombie]; stop", &FreezeLocation { x: 1.0, y: 2.0, z: 3.0, dimension: None }, "minecraft:overworld", 8.0).is_err());
+    }
+}
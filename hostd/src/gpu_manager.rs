@@ -1,12 +1,24 @@
 use crate::core::guardian_config::GuardianConfig;
-use gpu_worker::GpuWorker;
+use crate::gpu_telemetry::{DeviceTelemetry, GpuTelemetry};
+use gpu_worker::{Backend, ChunkGenerator, CpuChunkGenerator, GpuWorker, WorldGenSettings};
+use gpu_worker::devices::{self, DeviceSelection, GpuDevice};
+use gpu_worker::ipc::{IpcClient, IpcServer};
+use gpu_worker::queue::{ChunkOutput, ChunkRequest, DeviceMetrics, GpuWorkerHandle, GpuWorkerService, JobPriority, QueueConfig, QueueMetrics};
+use gpu_worker::selftest::{SelfTestConfig, SelfTestReport};
+use gpu_worker::shm::{self, ResultRing};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tracing::{info, warn, error};
 use std::time::{Duration, Instant};
 use serde::Serialize;
 
+/// How long a telemetry sample is reused before the sensors are read again
+const TELEMETRY_REFRESH: Duration = Duration::from_secs(1);
+
 /// GPU metrics for monitoring
+///
+/// Totals across all GPUs on the host: utilization is the busiest device,
+/// temperature the hottest, memory and power are summed.
 #[derive(Debug, Clone, Serialize)]
 pub struct GpuMetrics {
     pub utilization: f32,
@@ -14,10 +26,32 @@ pub struct GpuMetrics {
     pub memory_total: u64,
     pub temperature: f32,
     pub power_usage: f32,
+    /// Raw vendor readings per physical GPU
+    pub devices: Vec<DeviceTelemetry>,
+    /// Job queue depth, preemption and starvation counters from the worker
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue: Option<QueueMetrics>,
     #[serde(skip)]
     pub last_update: Instant,
 }
 
+impl GpuMetrics {
+    /// Fold per-device readings into host totals
+    pub fn from_devices(devices: Vec<DeviceTelemetry>) -> Self {
+        let max = |values: &mut dyn Iterator<Item = f32>| values.fold(0.0f32, f32::max);
+        Self {
+            utilization: max(&mut devices.iter().filter_map(|d| d.utilization)),
+            memory_used: devices.iter().filter_map(|d| d.memory_used).sum(),
+            memory_total: devices.iter().filter_map(|d| d.memory_total).sum(),
+            temperature: max(&mut devices.iter().filter_map(|d| d.temperature)),
+            power_usage: devices.iter().filter_map(|d| d.power_usage).sum(),
+            devices,
+            queue: None,
+            last_update: Instant::now(),
+        }
+    }
+}
+
 impl Default for GpuMetrics {
     fn default() -> Self {
         Self {
@@ -26,6 +60,8 @@ impl Default for GpuMetrics {
             memory_total: 0,
             temperature: 0.0,
             power_usage: 0.0,
+            devices: Vec::new(),
+            queue: None,
             last_update: Instant::now(),
         }
     }
@@ -36,15 +72,37 @@ impl Default for GpuMetrics {
 pub struct GpuStatus {
     pub enabled: bool,
     pub worker_running: bool,
+    /// Backend chunk jobs currently run on ("gpu" or "cpu")
+    pub backend: Backend,
     pub metrics: GpuMetrics,
 }
 
+/// An adapter on this host and what the worker is doing with it
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuDeviceStatus {
+    #[serde(flatten)]
+    pub device: GpuDevice,
+    /// Whether chunk jobs are scheduled on this device
+    pub selected: bool,
+    /// Per-device counters, present while the device is in use
+    pub metrics: Option<DeviceMetrics>,
+}
+
 /// GPU job types
 #[derive(Debug, Clone)]
 pub enum GpuJobType {
-    ChunkGeneration { x: i32, z: i32, seed: u64, dimension: String },
-    Lighting { x: i32, z: i32, y: i32 },
-    Pregeneration { center_x: i32, center_z: i32, radius: u32, seed: u64 },
+    /// `priority` picks the worker's queue lane: interactive jobs overtake
+    /// normal ones, which overtake bulk work such as pregeneration
+    ChunkGeneration { x: i32, z: i32, seed: u64, dimension: String, settings: WorldGenSettings, priority: JobPriority },
+}
+
+impl GpuJobType {
+    /// Queue priority on the worker
+    pub fn priority(&self) -> JobPriority {
+        match self {
+            GpuJobType::ChunkGeneration { priority, .. } => *priority,
+        }
+    }
 }
 
 /// GPU job result
@@ -54,15 +112,24 @@ pub struct GpuJobResult {
     pub success: bool,
     pub duration: Duration,
     pub error: Option<String>,
-    pub data: Option<Vec<u8>>,
+    /// The generated chunk
+    pub data: Option<ChunkOutput>,
 }
 
 /// GPU Manager for coordinating GPU acceleration
 #[derive(Clone)]
 pub struct GpuManager {
-    worker: Option<Arc<Mutex<GpuWorker>>>,
+    worker: Option<IpcClient>,
+    /// In-process worker started when no standalone gpu-worker is listening
+    embedded_worker: Option<GpuWorkerHandle>,
+    /// IPC server task exposing the embedded worker
+    embedded_server: Option<tokio::task::AbortHandle>,
+    /// Adapters the embedded worker generates on
+    device_selection: DeviceSelection,
     config: GuardianConfig,
     metrics: Arc<Mutex<GpuMetrics>>,
+    /// Vendor sensors (NVML, amdgpu/i915 sysfs)
+    telemetry: Arc<GpuTelemetry>,
     is_enabled: bool,
     cpu_usage_threshold: f32,
     last_cpu_check: Arc<Mutex<Instant>>,
@@ -71,17 +138,19 @@ pub struct GpuManager {
 impl GpuManager {
     /// Create a new GPU manager
     pub async fn new(config: GuardianConfig) -> Result<Self, String> {
+        let device_selection = config.gpu_device.parse().unwrap_or_else(|e| {
+            warn!("{}; using automatic GPU selection", e);
+            DeviceSelection::Auto
+        });
+
         let mut manager = Self {
             worker: None,
+            embedded_worker: None,
+            embedded_server: None,
+            device_selection,
             config: config.clone(),
-            metrics: Arc::new(Mutex::new(GpuMetrics {
-                utilization: 0.0,
-                memory_used: 0,
-                memory_total: 0,
-                temperature: 0.0,
-                power_usage: 0.0,
-                last_update: Instant::now(),
-            })),
+            metrics: Arc::new(Mutex::new(GpuMetrics::default())),
+            telemetry: GpuTelemetry::shared(),
             is_enabled: config.gpu_enabled, // Use config value, default is false
             cpu_usage_threshold: 0.8, // 80% CPU usage threshold
             last_cpu_check: Arc::new(Mutex::new(Instant::now())),
@@ -105,27 +174,81 @@ impl GpuManager {
         Ok(manager)
     }
 
-    /// Initialize the GPU worker
+    /// Connect to the GPU worker over IPC, starting an embedded one if none is running
     async fn initialize_gpu(&mut self) -> Result<(), String> {
         info!("Initializing GPU worker...");
-        
-        match GpuWorker::new().await {
-            Ok(worker) => {
-                self.worker = Some(Arc::new(Mutex::new(worker)));
-                info!("GPU worker initialized successfully");
-                // Don't log GPU metrics here to avoid Send issues
-                Ok(())
+
+        let mut client = IpcClient::new(self.config.gpu_worker_endpoint.clone());
+        match client.health().await {
+            Ok(report) => {
+                info!("Connected to GPU worker {} at {}", report.worker_id, client.endpoint());
+                Self::negotiate_shared_memory(&mut client).await;
+                self.worker = Some(client);
+                return Ok(());
+            }
+            Err(e) => {
+                info!("No GPU worker reachable at {} ({}); starting embedded worker", client.endpoint(), e);
             }
+        }
+
+        let handle = match GpuWorker::with_selection(self.device_selection).await {
+            Ok(worker) => GpuWorkerService::spawn(worker, QueueConfig::default()),
             Err(e) => {
                 let error_msg = format!("GPU initialization failed: {}", e);
                 warn!("Failed to initialize GPU worker: {}. Falling back to CPU.", error_msg);
                 self.is_enabled = false;
-                // Don't log GPU metrics here to avoid Send issues
-                Ok(()) // Don't fail, just disable GPU
+                return Ok(()); // Don't fail, just disable GPU
+            }
+        };
+
+        match IpcServer::bind(client.endpoint()) {
+            Ok(mut server) => {
+                match ResultRing::create(shm::default_path(client.endpoint()), shm::DEFAULT_SLOTS) {
+                    Ok(ring) => server = server.with_result_ring(ring),
+                    Err(e) => warn!("Failed to create GPU result ring: {}; chunk results will be sent inline", e),
+                }
+                let server_handle = handle.clone();
+                let server_task = tokio::spawn(async move {
+                    if let Err(e) = server.run(server_handle).await {
+                        error!("Embedded GPU worker IPC server stopped: {}", e);
+                    }
+                });
+                self.embedded_server = Some(server_task.abort_handle());
+                self.embedded_worker = Some(handle);
+                Self::negotiate_shared_memory(&mut client).await;
+                self.worker = Some(client);
+                info!("GPU worker initialized successfully");
+                Ok(())
+            }
+            Err(e) => {
+                handle.shutdown();
+                warn!("Failed to bind GPU worker IPC endpoint {}: {}. Falling back to CPU.", client.endpoint(), e);
+                self.is_enabled = false;
+                Ok(())
             }
         }
     }
 
+    /// Map the worker's shared-memory result ring; results travel inline over IPC without one
+    async fn negotiate_shared_memory(client: &mut IpcClient) {
+        match client.negotiate_shared_memory().await {
+            Ok(Some(ring)) => info!("Reading GPU results from shared memory at {} ({} slots)", ring.path.display(), ring.slot_count),
+            Ok(None) => info!("GPU worker offers no shared-memory ring; results will be sent inline"),
+            Err(e) => warn!("Failed to map GPU worker result ring: {}; results will be sent inline", e),
+        }
+    }
+
+    /// Stop the embedded worker (if any) and drop the IPC client
+    fn release_worker(&mut self) {
+        if let Some(server) = self.embedded_server.take() {
+            server.abort();
+        }
+        if let Some(handle) = self.embedded_worker.take() {
+            handle.shutdown();
+        }
+        self.worker = None;
+    }
+
     /// Submit a GPU job with safe fallback to CPU
     pub async fn submit_job(&self, job: GpuJobType) -> Result<GpuJobResult, String> {
         let start_time = Instant::now();
@@ -152,41 +275,33 @@ impl GpuManager {
 
     /// Try to process a job on GPU
     async fn try_gpu_processing(&self, job: GpuJobType, start_time: Instant) -> Result<GpuJobResult, String> {
-        if let Some(worker) = &self.worker {
-            let worker_guard = worker.lock().await;
-            
-            if !worker_guard.is_healthy() {
-                return Err("GPU worker unhealthy".to_string());
-            }
+        let Some(worker) = &self.worker else {
+            return Err("GPU worker not available".to_string());
+        };
 
-            // Submit the job based on type
-            match job {
-                GpuJobType::ChunkGeneration { x, z, seed, ref dimension } => {
-                    // Simulate GPU processing with occasional failures for testing
-                    if rand::random::<f32>() < 0.15 { // 15% chance of GPU failure
-                        return Err("Simulated GPU processing failure".to_string());
-                    }
+        match job {
+            GpuJobType::ChunkGeneration { x, z, seed, ref dimension, settings, .. } => {
+                let request = ChunkRequest {
+                    chunk_x: x,
+                    chunk_z: z,
+                    seed: seed as i64,
+                    dimension: dimension.clone(),
+                    settings,
+                    priority: job.priority(),
+                };
+
+                let job_id = worker.submit(request).await.map_err(|e| e.to_string())?;
+                // Copied straight out of the shared-memory slot when the ring is in use
+                let data = worker.wait_view(job_id).await.map_err(|e| e.to_string())?.into_output();
 
-                    // Simulate GPU processing time (faster than CPU)
-                    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-                    
-                    let result = format!("GPU chunk generation result for ({}, {})", x, z);
-                    self.update_metrics(0.7).await; // Assume 70% GPU utilization
-                    
-                    Ok(GpuJobResult {
-                        job_type: job.clone(),
-                        success: true,
-                        duration: start_time.elapsed(),
-                        error: None,
-                        data: Some(result.into_bytes()),
-                    })
-                }
-                _ => {
-                    Err("Job type not implemented for GPU".to_string())
-                }
+                Ok(GpuJobResult {
+                    job_type: job.clone(),
+                    success: true,
+                    duration: start_time.elapsed(),
+                    error: None,
+                    data: Some(data),
+                })
             }
-        } else {
-            Err("GPU worker not available".to_string())
         }
     }
 
@@ -194,28 +309,38 @@ impl GpuManager {
     async fn fallback_to_cpu(&self, job: GpuJobType, start_time: Instant) -> Result<GpuJobResult, String> {
         tracing::info!("Processing job on CPU as fallback");
         
-        // Simulate CPU processing (longer than GPU)
-        tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
-        
         match job {
-            GpuJobType::ChunkGeneration { x, z, seed, ref dimension } => {
-                let result = format!("CPU fallback chunk generation result for ({}, {})", x, z);
+            GpuJobType::ChunkGeneration { x, z, seed, ref dimension, settings, .. } => {
+                let request = ChunkRequest {
+                    chunk_x: x,
+                    chunk_z: z,
+                    seed: seed as i64,
+                    dimension: dimension.clone(),
+                    settings,
+                    priority: job.priority(),
+                };
+
+                // Same noise as the GPU kernels, run on the blocking pool
+                let output = tokio::task::spawn_blocking(move || -> anyhow::Result<ChunkOutput> {
+                    let chunk = CpuChunkGenerator::new().generate_chunk(
+                        request.chunk_x,
+                        request.chunk_z,
+                        request.seed as u32,
+                        &request.dimension,
+                        &request.settings,
+                    )?;
+                    Ok(ChunkOutput::from_chunk_data(&request, &chunk))
+                })
+                .await
+                .map_err(|e| format!("CPU chunk generation task failed: {}", e))?
+                .map_err(|e| format!("CPU chunk generation failed: {}", e))?;
                 
                 Ok(GpuJobResult {
                     job_type: job.clone(),
                     success: true,
                     duration: start_time.elapsed(),
                     error: Some("Processed on CPU due to GPU unavailability".to_string()),
-                    data: Some(result.into_bytes()),
-                })
-            }
-            _ => {
-                Ok(GpuJobResult {
-                    job_type: job.clone(),
-                    success: false,
-                    duration: start_time.elapsed(),
-                    error: Some("Job type not implemented for CPU fallback".to_string()),
-                    data: None,
+                    data: Some(output),
                 })
             }
         }
@@ -268,11 +393,9 @@ impl GpuManager {
         let memory_threshold = 0.85; // 85% memory usage
         
         // GPU worker health check
-        let gpu_healthy = if let Some(worker) = &self.worker {
-            let worker_guard = worker.lock().await;
-            worker_guard.is_healthy()
-        } else {
-            false
+        let gpu_healthy = match &self.worker {
+            Some(worker) => worker.health().await.map(|report| report.healthy).unwrap_or(false),
+            None => false,
         };
         
         // Adaptive thresholds based on current load
@@ -295,7 +418,7 @@ impl GpuManager {
         // Additional safety checks
         if use_gpu {
             // Check if GPU has been working too hard recently
-            let metrics = self.metrics.lock().await;
+            let metrics = self.get_metrics().await;
             if metrics.utilization > 0.9 {
                 // GPU is at 90%+ utilization, give it a break
                 return false;
@@ -305,13 +428,6 @@ impl GpuManager {
         use_gpu
     }
 
-    /// Update GPU metrics
-    async fn update_metrics(&self, utilization: f32) {
-        let mut metrics = self.metrics.lock().await;
-        metrics.utilization = utilization;
-        metrics.last_update = Instant::now();
-    }
-
     /// Dynamically adjust CPU threshold based on system performance
     pub async fn adjust_cpu_threshold(&mut self) {
         let mut system = sysinfo::System::new_all();
@@ -339,9 +455,22 @@ impl GpuManager {
         )).await;
     }
 
-    /// Get current GPU metrics
+    /// Get current GPU metrics, re-reading the vendor sensors when the last sample is stale
     pub async fn get_metrics(&self) -> GpuMetrics {
-        self.metrics.lock().await.clone()
+        let mut metrics = self.metrics.lock().await;
+        if self.telemetry.is_available() && metrics.last_update.elapsed() >= TELEMETRY_REFRESH {
+            let telemetry = self.telemetry.clone();
+            if let Ok(devices) = tokio::task::spawn_blocking(move || telemetry.sample()).await {
+                *metrics = GpuMetrics::from_devices(devices);
+            }
+        }
+        metrics.clone()
+    }
+
+    /// Scheduling counters of the worker's job queue, if one is reachable
+    pub async fn queue_metrics(&self) -> Option<QueueMetrics> {
+        let worker = self.worker.as_ref()?;
+        worker.health().await.ok().map(|report| report.queue)
     }
 
     /// Check if GPU is enabled
@@ -354,20 +483,101 @@ impl GpuManager {
         Ok(GpuStatus {
             enabled: self.is_enabled,
             worker_running: self.worker.is_some(),
+            backend: self.backend().await,
             metrics: self.get_metrics().await,
         })
     }
 
+    /// Backend chunk jobs will run on; anything without a reachable worker runs on the CPU
+    pub async fn backend(&self) -> Backend {
+        if !self.is_enabled {
+            return Backend::Cpu;
+        }
+        match &self.worker {
+            Some(worker) => worker.health().await.map(|report| report.backend).unwrap_or(Backend::Cpu),
+            None => Backend::Cpu,
+        }
+    }
+
+    /// Current device selection
+    pub fn device_selection(&self) -> DeviceSelection {
+        self.device_selection
+    }
+
+    /// All adapters on this host, with per-device metrics for the ones in use
+    pub async fn list_devices(&self) -> Vec<GpuDeviceStatus> {
+        let in_use = match &self.worker {
+            Some(worker) => worker.health().await.map(|report| report.devices).unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        tokio::task::spawn_blocking(devices::list_devices)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|device| {
+                let metrics = in_use
+                    .iter()
+                    .find(|metrics| metrics.device.as_ref().map(|d| d.index) == Some(device.index))
+                    .cloned();
+                GpuDeviceStatus {
+                    selected: metrics.is_some(),
+                    device,
+                    metrics,
+                }
+            })
+            .collect()
+    }
+
+    /// Pin chunk generation to a device (or split it across all of them), restarting the embedded worker
+    pub async fn select_device(&mut self, selection: DeviceSelection) -> Result<(), String> {
+        if let DeviceSelection::Device(index) = selection {
+            let available = tokio::task::spawn_blocking(devices::list_devices).await.unwrap_or_default();
+            if !available.iter().any(|device| device.index == index) {
+                return Err(format!("GPU device {} not found", index));
+            }
+        }
+
+        if self.worker.is_some() && self.embedded_worker.is_none() {
+            return Err(format!(
+                "GPU worker at {} is managed externally; restart it with GPU_WORKER_DEVICE={}",
+                self.config.gpu_worker_endpoint, selection
+            ));
+        }
+
+        self.device_selection = selection;
+        if self.is_enabled {
+            self.release_worker();
+            self.initialize_gpu().await?;
+        }
+
+        self.log_gpu_metrics(&format!("GPU device selection set to {}", selection)).await;
+        Ok(())
+    }
+
+    /// Check that the worker produces the same chunks on every run and matches the CPU generator
+    pub async fn self_test(&self, config: SelfTestConfig) -> Result<SelfTestReport, String> {
+        let Some(worker) = &self.worker else {
+            return Err("GPU worker not available".to_string());
+        };
+
+        let report = worker.self_test(config).await.map_err(|e| e.to_string())?;
+        self.log_gpu_metrics(&format!(
+            "GPU self-test {} on {} ({} chunks x {} runs)",
+            if report.passed { "passed" } else { "failed" },
+            report.backend,
+            report.chunks.len(),
+            report.iterations
+        )).await;
+        Ok(report)
+    }
+
     /// Enable or disable GPU
     pub async fn set_enabled(&mut self, enabled: bool) -> Result<(), String> {
         if enabled && !self.is_enabled {
             self.initialize_gpu().await?;
         } else if !enabled && self.is_enabled {
-            if let Some(worker) = &self.worker {
-                let mut worker_guard = worker.lock().await;
-                worker_guard.cleanup();
-            }
-            self.worker = None;
+            self.release_worker();
         }
         
         self.is_enabled = enabled;
@@ -377,7 +587,7 @@ impl GpuManager {
 
     /// Log GPU metrics to file
     async fn log_gpu_metrics(&self, message: &str) {
-        let metrics = self.metrics.lock().await;
+        let metrics = self.get_metrics().await;
         let should_use_gpu = self.should_use_gpu().await;
         
         // Create detailed JSON log entry
@@ -440,11 +650,7 @@ impl GpuManager {
 
     /// Cleanup GPU resources
     pub async fn cleanup(&mut self) {
-        if let Some(worker) = &self.worker {
-            let mut worker_guard = worker.lock().await;
-            worker_guard.cleanup();
-        }
-        self.worker = None;
+        self.release_worker();
         self.is_enabled = false;
         self.log_gpu_metrics("GPU manager cleanup completed").await;
     }
@@ -464,4 +670,69 @@ mod tests {
         let manager = GpuManager::new(config).await;
         assert!(manager.is_ok());
     }
+
+    /// A manager whose worker generates on the CPU, reached over IPC like a real one
+    fn cpu_manager(endpoint: &str) -> GpuManager {
+        let handle = GpuWorkerService::spawn(GpuWorker::with_generator(Box::new(CpuChunkGenerator::new())), QueueConfig::default());
+        let server = IpcServer::bind(endpoint).unwrap();
+        let server_handle = handle.clone();
+        let server_task = tokio::spawn(async move { server.run(server_handle).await });
+        GpuManager {
+            worker: Some(IpcClient::new(endpoint)),
+            embedded_worker: Some(handle),
+            embedded_server: Some(server_task.abort_handle()),
+            device_selection: DeviceSelection::Auto,
+            config: GuardianConfig::default(),
+            metrics: Arc::new(Mutex::new(GpuMetrics::default())),
+            telemetry: GpuTelemetry::shared(),
+            is_enabled: true,
+            cpu_usage_threshold: 0.8,
+            // Never due, so the host's CPU load cannot send jobs to the fallback
+            last_cpu_check: Arc::new(Mutex::new(Instant::now() + Duration::from_secs(3600))),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_interactive_job_preempts_bulk_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = cpu_manager(&dir.path().join("gpu-worker.sock").display().to_string());
+        let worker = manager.embedded_worker.clone().unwrap();
+        let job = |x, priority| GpuJobType::ChunkGeneration {
+            x,
+            z: 0,
+            seed: 42,
+            dimension: "overworld".to_string(),
+            settings: WorldGenSettings::default(),
+            priority,
+        };
+
+        let bulk: Vec<_> = (0..12)
+            .map(|x| {
+                let manager = manager.clone();
+                tokio::spawn(async move { manager.submit_job(job(x, JobPriority::Bulk)).await })
+            })
+            .collect();
+        let queued_bulk = || {
+            worker.health().queue.priorities.iter()
+                .find(|lane| lane.priority == JobPriority::Bulk)
+                .map_or(0, |lane| lane.depth)
+        };
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while queued_bulk() < 2 {
+            assert!(Instant::now() < deadline, "bulk jobs never queued up");
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let result = manager.submit_job(job(100, JobPriority::Interactive)).await.unwrap();
+        // No error means it ran on the worker rather than the CPU fallback
+        assert!(result.success && result.error.is_none());
+        let queue = worker.health().queue;
+        assert_eq!(queue.preemptions, 1);
+        assert_eq!(queue.priorities.iter().find(|lane| lane.priority == JobPriority::Interactive).unwrap().dispatched, 1);
+
+        for task in bulk {
+            assert!(task.await.unwrap().unwrap().success);
+        }
+        manager.release_worker();
+    }
 }
@@ -0,0 +1,366 @@
+use libloading::Library;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::os::raw::{c_char, c_int, c_uint, c_ulonglong, c_void};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Vendor sensor readings for one physical GPU
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceTelemetry {
+    /// Which driver interface produced the reading ("nvml", "amdgpu", "i915", ...)
+    pub source: String,
+    pub index: u32,
+    pub name: String,
+    /// Fraction of time the GPU was busy (0.0 - 1.0)
+    pub utilization: Option<f32>,
+    /// VRAM in bytes
+    pub memory_used: Option<u64>,
+    pub memory_total: Option<u64>,
+    /// Degrees Celsius
+    pub temperature: Option<f32>,
+    /// Watts
+    pub power_usage: Option<f32>,
+}
+
+/// A vendor API that can report GPU sensor data
+pub trait TelemetrySource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn sample(&self) -> Vec<DeviceTelemetry>;
+}
+
+/// All telemetry sources available on this host
+pub struct GpuTelemetry {
+    sources: Vec<Box<dyn TelemetrySource>>,
+}
+
+static SHARED: Lazy<Arc<GpuTelemetry>> = Lazy::new(|| Arc::new(GpuTelemetry::detect()));
+
+impl GpuTelemetry {
+    /// Probe NVML and the kernel's DRM sensors; missing drivers are skipped
+    pub fn detect() -> Self {
+        let mut sources: Vec<Box<dyn TelemetrySource>> = Vec::new();
+
+        match NvmlSource::load() {
+            Ok(nvml) => sources.push(Box::new(nvml)),
+            Err(e) => debug!("NVML not available: {}", e),
+        }
+
+        if cfg!(target_os = "linux") {
+            let sysfs = SysfsSource::new("/sys/class/drm");
+            if !sysfs.sample().is_empty() {
+                sources.push(Box::new(sysfs));
+            }
+        }
+
+        let names: Vec<&str> = sources.iter().map(|source| source.name()).collect();
+        info!("GPU telemetry sources: {:?}", names);
+
+        Self { sources }
+    }
+
+    /// Process-wide instance; loading NVML more than once is wasted work
+    pub fn shared() -> Arc<GpuTelemetry> {
+        SHARED.clone()
+    }
+
+    pub fn is_available(&self) -> bool {
+        !self.sources.is_empty()
+    }
+
+    /// Read every device from every source
+    pub fn sample(&self) -> Vec<DeviceTelemetry> {
+        self.sources.iter().flat_map(|source| source.sample()).collect()
+    }
+}
+
+// NVML is loaded at runtime so hosts without an NVIDIA driver still start
+const NVML_SUCCESS: c_int = 0;
+const NVML_TEMPERATURE_GPU: c_int = 0;
+const NVML_DEVICE_NAME_BUFFER_SIZE: usize = 96;
+
+type NvmlDevice = *mut c_void;
+
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)] // layout must match nvml.h
+struct NvmlMemory {
+    total: c_ulonglong,
+    free: c_ulonglong,
+    used: c_ulonglong,
+}
+
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)] // layout must match nvml.h
+struct NvmlUtilization {
+    gpu: c_uint,
+    memory: c_uint,
+}
+
+/// NVIDIA Management Library bindings
+pub struct NvmlSource {
+    shutdown: unsafe extern "C" fn() -> c_int,
+    device_count: unsafe extern "C" fn(*mut c_uint) -> c_int,
+    device_by_index: unsafe extern "C" fn(c_uint, *mut NvmlDevice) -> c_int,
+    device_name: unsafe extern "C" fn(NvmlDevice, *mut c_char, c_uint) -> c_int,
+    memory_info: unsafe extern "C" fn(NvmlDevice, *mut NvmlMemory) -> c_int,
+    utilization: unsafe extern "C" fn(NvmlDevice, *mut NvmlUtilization) -> c_int,
+    temperature: unsafe extern "C" fn(NvmlDevice, c_int, *mut c_uint) -> c_int,
+    power_usage: unsafe extern "C" fn(NvmlDevice, *mut c_uint) -> c_int,
+    // Keeps the function pointers above valid
+    _library: Library,
+}
+
+impl NvmlSource {
+    #[cfg(windows)]
+    const LIBRARY: &'static str = "nvml.dll";
+    #[cfg(not(windows))]
+    const LIBRARY: &'static str = "libnvidia-ml.so.1";
+
+    pub fn load() -> Result<Self, String> {
+        // SAFETY: NVML is a plain C library with no load-time side effects beyond its own init
+        let library = unsafe { Library::new(Self::LIBRARY) }.map_err(|e| e.to_string())?;
+
+        // SAFETY: symbol types match the NVML headers
+        unsafe {
+            let init = *library
+                .get::<unsafe extern "C" fn() -> c_int>(b"nvmlInit_v2\0")
+                .map_err(|e| e.to_string())?;
+            let source = Self {
+                shutdown: *library.get(b"nvmlShutdown\0").map_err(|e| e.to_string())?,
+                device_count: *library.get(b"nvmlDeviceGetCount_v2\0").map_err(|e| e.to_string())?,
+                device_by_index: *library.get(b"nvmlDeviceGetHandleByIndex_v2\0").map_err(|e| e.to_string())?,
+                device_name: *library.get(b"nvmlDeviceGetName\0").map_err(|e| e.to_string())?,
+                memory_info: *library.get(b"nvmlDeviceGetMemoryInfo\0").map_err(|e| e.to_string())?,
+                utilization: *library.get(b"nvmlDeviceGetUtilizationRates\0").map_err(|e| e.to_string())?,
+                temperature: *library.get(b"nvmlDeviceGetTemperature\0").map_err(|e| e.to_string())?,
+                power_usage: *library.get(b"nvmlDeviceGetPowerUsage\0").map_err(|e| e.to_string())?,
+                _library: library,
+            };
+
+            let status = init();
+            if status != NVML_SUCCESS {
+                // Not initialized, so Drop must not call nvmlShutdown
+                let source = std::mem::ManuallyDrop::new(source);
+                drop(std::ptr::read(&source._library));
+                return Err(format!("nvmlInit_v2 failed with status {}", status));
+            }
+            Ok(source)
+        }
+    }
+
+    fn sample_device(&self, index: c_uint) -> Option<DeviceTelemetry> {
+        // SAFETY: NVML was initialized in load() and every out-pointer is a valid local
+        unsafe {
+            let mut device: NvmlDevice = std::ptr::null_mut();
+            if (self.device_by_index)(index, &mut device) != NVML_SUCCESS {
+                return None;
+            }
+
+            let mut name = [0 as c_char; NVML_DEVICE_NAME_BUFFER_SIZE];
+            let name = if (self.device_name)(device, name.as_mut_ptr(), name.len() as c_uint) == NVML_SUCCESS {
+                std::ffi::CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned()
+            } else {
+                format!("NVIDIA GPU {}", index)
+            };
+
+            let mut memory = NvmlMemory::default();
+            let memory = ((self.memory_info)(device, &mut memory) == NVML_SUCCESS).then_some(memory);
+
+            let mut utilization = NvmlUtilization::default();
+            let utilization = ((self.utilization)(device, &mut utilization) == NVML_SUCCESS)
+                .then(|| utilization.gpu as f32 / 100.0);
+
+            let mut temperature: c_uint = 0;
+            let temperature = ((self.temperature)(device, NVML_TEMPERATURE_GPU, &mut temperature) == NVML_SUCCESS)
+                .then_some(temperature as f32);
+
+            let mut milliwatts: c_uint = 0;
+            let power_usage = ((self.power_usage)(device, &mut milliwatts) == NVML_SUCCESS)
+                .then(|| milliwatts as f32 / 1000.0);
+
+            Some(DeviceTelemetry {
+                source: self.name().to_string(),
+                index,
+                name,
+                utilization,
+                memory_used: memory.as_ref().map(|m| m.used),
+                memory_total: memory.as_ref().map(|m| m.total),
+                temperature,
+                power_usage,
+            })
+        }
+    }
+}
+
+impl TelemetrySource for NvmlSource {
+    fn name(&self) -> &'static str {
+        "nvml"
+    }
+
+    fn sample(&self) -> Vec<DeviceTelemetry> {
+        let mut count: c_uint = 0;
+        // SAFETY: NVML was initialized in load()
+        if unsafe { (self.device_count)(&mut count) } != NVML_SUCCESS {
+            return Vec::new();
+        }
+        (0..count).filter_map(|index| self.sample_device(index)).collect()
+    }
+}
+
+impl Drop for NvmlSource {
+    fn drop(&mut self) {
+        // SAFETY: paired with the successful nvmlInit_v2 in load()
+        unsafe {
+            (self.shutdown)();
+        }
+    }
+}
+
+// SAFETY: NVML is documented as thread-safe; the struct only holds function pointers and the library handle
+unsafe impl Send for NvmlSource {}
+unsafe impl Sync for NvmlSource {}
+
+/// AMD and Intel sensors exposed by the Linux DRM drivers under /sys/class/drm
+pub struct SysfsSource {
+    root: PathBuf,
+}
+
+const VENDOR_AMD: &str = "0x1002";
+const VENDOR_INTEL: &str = "0x8086";
+
+impl SysfsSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn read_u64(path: &Path) -> Option<u64> {
+        std::fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+
+    /// First hwmon directory of a card; amdgpu and newer Intel drivers expose temperature and power there
+    fn hwmon_dir(device: &Path) -> Option<PathBuf> {
+        let mut dirs: Vec<PathBuf> = std::fs::read_dir(device.join("hwmon"))
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        dirs.sort();
+        dirs.into_iter().next()
+    }
+
+    fn sample_card(&self, index: u32, device: &Path) -> Option<DeviceTelemetry> {
+        let vendor = std::fs::read_to_string(device.join("vendor")).ok()?;
+        let source = match vendor.trim() {
+            VENDOR_AMD => "amdgpu",
+            VENDOR_INTEL => "i915",
+            _ => return None,
+        };
+
+        let hwmon = Self::hwmon_dir(device);
+        let hwmon_value = |file: &str| hwmon.as_ref().and_then(|dir| Self::read_u64(&dir.join(file)));
+
+        let name = hwmon
+            .as_ref()
+            .and_then(|dir| std::fs::read_to_string(dir.join("name")).ok())
+            .map(|name| format!("{} card{}", name.trim(), index))
+            .unwrap_or_else(|| format!("{} card{}", source, index));
+
+        Some(DeviceTelemetry {
+            source: source.to_string(),
+            index,
+            name,
+            utilization: Self::read_u64(&device.join("gpu_busy_percent")).map(|percent| percent as f32 / 100.0),
+            memory_used: Self::read_u64(&device.join("mem_info_vram_used")),
+            memory_total: Self::read_u64(&device.join("mem_info_vram_total")),
+            // millidegrees Celsius
+            temperature: hwmon_value("temp1_input").map(|value| value as f32 / 1000.0),
+            // microwatts; amdgpu reports an average, Intel an instantaneous reading
+            power_usage: hwmon_value("power1_average")
+                .or_else(|| hwmon_value("power1_input"))
+                .map(|value| value as f32 / 1_000_000.0),
+        })
+    }
+}
+
+impl TelemetrySource for SysfsSource {
+    fn name(&self) -> &'static str {
+        "sysfs"
+    }
+
+    fn sample(&self) -> Vec<DeviceTelemetry> {
+        let Ok(entries) = std::fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+
+        // Only cardN entries; cardN-DP-1 style connectors and renderD nodes are skipped
+        let mut cards: Vec<(u32, PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let index = entry.file_name().to_str()?.strip_prefix("card")?.parse().ok()?;
+                Some((index, entry.path().join("device")))
+            })
+            .collect();
+        cards.sort_by_key(|(index, _)| *index);
+
+        cards
+            .into_iter()
+            .filter_map(|(index, device)| self.sample_card(index, &device))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_sysfs_reads_amd_card() {
+        let root = tempfile::tempdir().unwrap();
+        let device = root.path().join("card1/device");
+        write(&device.join("vendor"), "0x1002\n");
+        write(&device.join("gpu_busy_percent"), "37\n");
+        write(&device.join("mem_info_vram_used"), "1073741824\n");
+        write(&device.join("mem_info_vram_total"), "8589934592\n");
+        write(&device.join("hwmon/hwmon3/name"), "amdgpu\n");
+        write(&device.join("hwmon/hwmon3/temp1_input"), "61000\n");
+        write(&device.join("hwmon/hwmon3/power1_average"), "145000000\n");
+
+        // NVIDIA cards are left to NVML and connectors are not cards
+        write(&root.path().join("card0/device/vendor"), "0x10de\n");
+        write(&root.path().join("card1-DP-1/device/vendor"), "0x1002\n");
+
+        let devices = SysfsSource::new(root.path()).sample();
+        assert_eq!(devices.len(), 1);
+
+        let card = &devices[0];
+        assert_eq!(card.source, "amdgpu");
+        assert_eq!(card.index, 1);
+        assert_eq!(card.name, "amdgpu card1");
+        assert_eq!(card.utilization, Some(0.37));
+        assert_eq!(card.memory_used, Some(1 << 30));
+        assert_eq!(card.memory_total, Some(8 << 30));
+        assert_eq!(card.temperature, Some(61.0));
+        assert_eq!(card.power_usage, Some(145.0));
+    }
+
+    #[test]
+    fn test_sysfs_missing_sensors_are_none() {
+        let root = tempfile::tempdir().unwrap();
+        write(&root.path().join("card0/device/vendor"), "0x8086\n");
+
+        let devices = SysfsSource::new(root.path()).sample();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].source, "i915");
+        assert!(devices[0].utilization.is_none());
+        assert!(devices[0].temperature.is_none());
+        assert!(devices[0].power_usage.is_none());
+    }
+}
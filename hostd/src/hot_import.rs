@@ -1,14 +1,697 @@
-// Hot import module placeholder
-// This will be implemented later
+//! Hot world import: copy region files from another world into a server's
+//! world folder, optionally while the server is running.
+//!
+//! When the server is up, saving is paused (`save-off`, `save-all flush`) for
+//! the duration of the import and every region is only replaced once RCON
+//! confirms none of its chunks are loaded. Replaced files are backed up first
+//! and each copy is verified by SHA-256; a mismatch rolls the whole import
+//! back. Jobs are persisted as `tasks` rows (kind `import`) and run one at a
+//! time through the job manager.
 
+use anyhow::{anyhow, bail, Context, Result};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
 use uuid::Uuid;
 
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, ServerConfig, Task};
+use crate::jobs::{self, JobHandle, JobManager};
+use crate::rcon::RconClient;
+use crate::websocket_manager::WebSocketManager;
+use crate::world::{self, region};
+use crate::core::error_handler::NotFound;
+
+const TASK_KIND: &str = "import";
+const DEFAULT_CHUNK_BATCH_SIZE: u32 = 1024;
+const TPS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HotImportJob {
-    pub id: Uuid,
+    pub id: String,
     pub server_id: String,
+    pub name: String,
+    pub source_dir: PathBuf,
+    pub target_world: PathBuf,
+    pub dimensions: Vec<String>,
+    /// Chunks copied between load checks and progress updates
+    pub chunk_batch_size: u32,
+    /// Wait for the server to recover before each batch when TPS is below this
+    pub tps_threshold: Option<f64>,
+    pub safety_checks: bool,
+    pub backup_before_import: bool,
+    /// pending, queued, running, done, failed or cancelled
     pub status: String,
     pub progress: f64,
+    #[serde(default)]
+    pub regions_total: usize,
+    #[serde(default)]
+    pub regions_imported: usize,
+    /// Regions left untouched because their chunks stayed loaded
+    #[serde(default)]
+    pub regions_skipped: Vec<String>,
+    #[serde(default)]
+    pub chunks_imported: usize,
+    #[serde(default)]
+    pub backup_path: Option<PathBuf>,
+    #[serde(default)]
+    pub rolled_back: bool,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
-}
\ No newline at end of file
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl HotImportJob {
+    fn from_task(task: &Task) -> Option<Self> {
+        let mut job: HotImportJob = serde_json::from_value(task.metadata.clone()?).ok()?;
+        job.status = task.status.clone();
+        job.progress = task.progress;
+        job.started_at = task.started_at;
+        job.finished_at = task.finished_at;
+        job.updated_at = task.updated_at;
+        Some(job)
+    }
+
+    fn to_task(&self) -> Task {
+        Task {
+            id: self.id.clone(),
+            server_id: Some(self.server_id.clone()),
+            kind: TASK_KIND.to_string(),
+            status: self.status.clone(),
+            progress: self.progress,
+            log: self.error.clone(),
+            metadata: serde_json::to_value(self).ok(),
+            started_at: self.started_at,
+            finished_at: self.finished_at,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        jobs::is_active(&self.status)
+    }
+}
+
+/// Parameters for a new import job
+#[derive(Debug, Clone)]
+pub struct NewHotImportJob {
+    pub name: String,
+    pub source_dir: String,
+    pub target_world: String,
+    pub dimensions: Vec<String>,
+    pub chunk_batch_size: Option<u32>,
+    pub tps_threshold: Option<f64>,
+    pub safety_checks: bool,
+    pub backup_before_import: bool,
+}
+
+/// One region file to copy
+#[derive(Debug, Clone)]
+struct RegionImport {
+    dimension: &'static str,
+    source: PathBuf,
+    target: PathBuf,
+    /// Chunks present in the source or the file being replaced
+    chunks: Vec<(i32, i32)>,
+    source_chunks: usize,
+}
+
+impl RegionImport {
+    fn label(&self) -> String {
+        let name = self.target.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        format!("{} {}", self.dimension, name)
+    }
+
+    /// Path of this region inside a backup folder
+    fn backup_path(&self, backup_root: &Path) -> PathBuf {
+        let relative = world::region_dir(self.dimension).unwrap_or("region");
+        backup_root.join(relative).join(self.target.file_name().unwrap_or_default())
+    }
+}
+
+/// Directory holding a dimension's region files in the source. A bare folder of
+/// region files is accepted as the overworld.
+fn source_region_dir(source: &Path, dimension: &str) -> Option<PathBuf> {
+    let dir = source.join(world::region_dir(dimension)?);
+    if dir.is_dir() {
+        return Some(dir);
+    }
+    let bare = world::dimension_id(dimension) == Some("minecraft:overworld")
+        && region::list_regions(source).map(|regions| !regions.is_empty()).unwrap_or(false);
+    bare.then(|| source.to_path_buf())
+}
+
+/// Work out which region files to copy, rejecting unreadable sources up front
+fn plan_import(source: &Path, target_world: &Path, dimensions: &[String]) -> Result<Vec<RegionImport>> {
+    let mut plan = Vec::new();
+    for dimension in dimensions {
+        let id = world::dimension_id(dimension).ok_or_else(|| anyhow!("Unknown dimension '{}'", dimension))?;
+        let Some(dir) = source_region_dir(source, dimension) else {
+            continue;
+        };
+        let target_dir = target_world.join(world::region_dir(dimension).unwrap_or("region"));
+
+        for (x, z, path) in region::list_regions(&dir)? {
+            let incoming = region::Region::read(&path).with_context(|| format!("source region {} is damaged", path.display()))?;
+            let target = target_dir.join(path.file_name().unwrap_or_default());
+
+            let mut slots: BTreeSet<usize> = incoming.present().map(|(index, _)| index).collect();
+            if target.exists() {
+                let existing = region::Region::read(&target)
+                    .with_context(|| format!("target region {} is damaged", target.display()))?;
+                slots.extend(existing.present().map(|(index, _)| index));
+            }
+
+            plan.push(RegionImport {
+                dimension: id,
+                source: path,
+                target,
+                chunks: slots.into_iter().map(|index| region::chunk_coords(x, z, index)).collect(),
+                source_chunks: incoming.chunk_count(),
+            });
+        }
+    }
+    Ok(plan)
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Copy the original of every region about to be replaced into the backup folder
+fn backup_regions(plan: &[RegionImport], backup_root: &Path) -> Result<()> {
+    for entry in plan.iter().filter(|entry| entry.target.exists()) {
+        let backup = entry.backup_path(backup_root);
+        if let Some(parent) = backup.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&entry.target, &backup).with_context(|| format!("backing up {}", entry.target.display()))?;
+        if sha256_file(&entry.target)? != sha256_file(&backup)? {
+            bail!("backup of {} does not match the original", entry.target.display());
+        }
+    }
+    Ok(())
+}
+
+/// Copy one region through a temporary file, verifying the checksum before and after it is moved into place
+fn copy_region(entry: &RegionImport) -> Result<()> {
+    let expected = sha256_file(&entry.source)?;
+    if let Some(parent) = entry.target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let tmp = entry.target.with_extension("mca.import");
+    std::fs::copy(&entry.source, &tmp).with_context(|| format!("copying {}", entry.source.display()))?;
+    if sha256_file(&tmp)? != expected {
+        let _ = std::fs::remove_file(&tmp);
+        bail!("checksum mismatch while copying {}", entry.source.display());
+    }
+    std::fs::rename(&tmp, &entry.target).with_context(|| format!("replacing {}", entry.target.display()))?;
+
+    if sha256_file(&entry.target)? != expected {
+        bail!("checksum mismatch after replacing {}", entry.target.display());
+    }
+    Ok(())
+}
+
+/// Put back the originals of imported regions; regions that did not exist before are removed
+fn rollback(imported: &[RegionImport], backup_root: &Path) -> Result<()> {
+    for entry in imported.iter().rev() {
+        let backup = entry.backup_path(backup_root);
+        if backup.exists() {
+            std::fs::copy(&backup, &entry.target).with_context(|| format!("restoring {}", entry.target.display()))?;
+        } else if entry.target.exists() {
+            std::fs::remove_file(&entry.target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Blocking RCON round trip off the async runtime
+async fn rcon(server: &ServerConfig, command: String) -> Result<String> {
+    let client = RconClient::new(server.host.clone(), server.rcon_port, server.rcon_password.clone());
+    tokio::task::spawn_blocking(move || client.send_command(&command)).await?
+}
+
+/// Ask the server whether any of the region's chunks are loaded (`execute if loaded`, 1.19.4+)
+async fn loaded_chunk(server: &ServerConfig, entry: &RegionImport) -> Result<Option<(i32, i32)>> {
+    for &(x, z) in &entry.chunks {
+        let command = format!("execute in {} if loaded {} 0 {}", entry.dimension, x * 16, z * 16);
+        let response = rcon(server, command).await?;
+        if response.contains("Test passed") {
+            return Ok(Some((x, z)));
+        }
+        if !response.contains("Test failed") {
+            bail!("Server cannot report loaded chunks ({}); stop it or disable safety checks", response.trim());
+        }
+    }
+    Ok(None)
+}
+
+/// Runs import jobs and tracks which ones are in flight
+pub struct HotImportManager {
+    database: Arc<DatabaseManager>,
+    websocket_manager: Arc<WebSocketManager>,
+    process_manager: Arc<ProcessManager>,
+    jobs: Arc<JobManager>,
+}
+
+impl HotImportManager {
+    pub fn new(
+        database: Arc<DatabaseManager>,
+        websocket_manager: Arc<WebSocketManager>,
+        process_manager: Arc<ProcessManager>,
+        jobs: Arc<JobManager>,
+    ) -> Self {
+        Self {
+            database,
+            websocket_manager,
+            process_manager,
+            jobs,
+        }
+    }
+
+    pub async fn list_jobs(&self, server_id: &str) -> Result<Vec<HotImportJob>> {
+        let tasks = self.database.get_tasks_by_server(server_id).await?;
+        let mut jobs = Vec::new();
+        for job in tasks.iter().filter(|task| task.kind == TASK_KIND).filter_map(HotImportJob::from_task) {
+            jobs.push(self.reconcile(job).await?);
+        }
+        Ok(jobs)
+    }
+
+    pub async fn get_job(&self, server_id: &str, job_id: &str) -> Result<Option<HotImportJob>> {
+        let job = self
+            .database
+            .get_task(job_id)
+            .await?
+            .filter(|task| task.kind == TASK_KIND && task.server_id.as_deref() == Some(server_id))
+            .and_then(|task| HotImportJob::from_task(&task));
+        match job {
+            Some(job) => Ok(Some(self.reconcile(job).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// A job recorded as running that this process is not executing was cut off by a restart
+    async fn reconcile(&self, mut job: HotImportJob) -> Result<HotImportJob> {
+        if job.is_active() && !self.jobs.is_tracked(&job.id) {
+            job.status = "failed".to_string();
+            job.error = Some("Interrupted by a restart of the host daemon; restore from the backup if needed".to_string());
+            job.finished_at = Some(chrono::Utc::now());
+            job.updated_at = chrono::Utc::now();
+            self.database.update_task(&job.to_task()).await?;
+        }
+        Ok(job)
+    }
+
+    pub async fn create_job(&self, server_id: &str, request: NewHotImportJob) -> Result<HotImportJob> {
+        let server = self.database.require_server(server_id).await?;
+        let server_dir = PathBuf::from(&server.server_directory)
+            .canonicalize()
+            .context("Server directory does not exist")?;
+
+        let target_world = if request.target_world.trim().is_empty() {
+            server_dir.join(&server.world_name)
+        } else {
+            server_dir.join(&request.target_world)
+        };
+        let target_world = target_world
+            .canonicalize()
+            .map_err(|_| anyhow!("World folder {} does not exist", target_world.display()))?;
+        if !target_world.starts_with(&server_dir) {
+            bail!("Target world must be inside the server directory");
+        }
+
+        let source_dir = PathBuf::from(&request.source_dir)
+            .canonicalize()
+            .map_err(|_| anyhow!("Source folder {} does not exist", request.source_dir))?;
+        if source_dir == target_world || target_world.starts_with(&source_dir) {
+            bail!("Source folder must not contain the target world");
+        }
+
+        let mut dimensions = request.dimensions;
+        if dimensions.is_empty() {
+            dimensions.push("overworld".to_string());
+        }
+        for dimension in &dimensions {
+            if world::dimension_id(dimension).is_none() {
+                bail!("Unknown dimension '{}'", dimension);
+            }
+        }
+
+        let now = chrono::Utc::now();
+        let job = HotImportJob {
+            id: Uuid::new_v4().to_string(),
+            server_id: server_id.to_string(),
+            name: request.name,
+            source_dir,
+            target_world,
+            dimensions,
+            chunk_batch_size: request.chunk_batch_size.filter(|size| *size > 0).unwrap_or(DEFAULT_CHUNK_BATCH_SIZE),
+            tps_threshold: request.tps_threshold,
+            safety_checks: request.safety_checks,
+            backup_before_import: request.backup_before_import,
+            status: "pending".to_string(),
+            progress: 0.0,
+            regions_total: 0,
+            regions_imported: 0,
+            regions_skipped: Vec::new(),
+            chunks_imported: 0,
+            backup_path: None,
+            rolled_back: false,
+            error: None,
+            started_at: None,
+            finished_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.database.create_task(&job.to_task()).await?;
+        Ok(job)
+    }
+
+    pub async fn start_job(self: &Arc<Self>, server_id: &str, job_id: &str) -> Result<HotImportJob> {
+        let mut job = self
+            .get_job(server_id, job_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Import job", job_id))?;
+        if job.is_active() {
+            bail!("Import job is already running");
+        }
+        if job.status != "pending" {
+            bail!("Import job has already run; create a new job to import again");
+        }
+        let server = self.database.require_server(server_id).await?;
+
+        let handle = self.jobs.track(&job.id, TASK_KIND).map_err(|_| anyhow!("Import job is already running"))?;
+
+        job.status = jobs::STATUS_QUEUED.to_string();
+        job.updated_at = chrono::Utc::now();
+        self.database.update_task(&job.to_task()).await?;
+
+        let manager = self.clone();
+        let queued = job.clone();
+        tokio::spawn(async move { manager.run(job, server, handle).await });
+
+        Ok(queued)
+    }
+
+    async fn run(&self, mut job: HotImportJob, server: ServerConfig, handle: JobHandle) {
+        let server_id = job.server_id.clone();
+        let Some(_slot) = handle.slot().await else {
+            self.finish(&mut job, Ok(()), true).await;
+            return;
+        };
+
+        job.status = jobs::STATUS_RUNNING.to_string();
+        job.started_at = Some(chrono::Utc::now());
+        job.updated_at = chrono::Utc::now();
+        if let Err(e) = self.database.update_task(&job.to_task()).await {
+            self.finish(&mut job, Err(e), false).await;
+            return;
+        }
+        let _ = self.websocket_manager.send_job_started(Some(&server_id), &job.id, TASK_KIND, 1).await;
+
+        let live = match Uuid::parse_str(&server_id) {
+            Ok(uuid) => self.process_manager.is_server_running(uuid).await,
+            Err(_) => false,
+        };
+        if live {
+            // Keep the server from writing region files while they are swapped
+            if let Err(e) = async {
+                rcon(&server, "save-off".to_string()).await?;
+                rcon(&server, "save-all flush".to_string()).await
+            }
+            .await
+            {
+                warn!("Could not pause saving for import job {}: {}", job.id, e);
+                let _ = rcon(&server, "save-on".to_string()).await;
+                self.finish(&mut job, Err(anyhow!("Could not pause world saving over RCON: {}", e)), false).await;
+                return;
+            }
+        }
+
+        let result = self.import(&mut job, &server, live, &handle).await;
+        if live {
+            if let Err(e) = rcon(&server, "save-on".to_string()).await {
+                warn!("Failed to re-enable saving after import job {}: {}", job.id, e);
+            }
+        }
+        self.finish(&mut job, result, handle.is_cancelled()).await;
+    }
+
+    async fn finish(&self, job: &mut HotImportJob, result: Result<()>, cancelled: bool) {
+        let server_id = job.server_id.clone();
+        job.finished_at = Some(chrono::Utc::now());
+        job.updated_at = chrono::Utc::now();
+
+        match result {
+            Ok(()) if cancelled => {
+                job.status = "cancelled".to_string();
+                let _ = self.websocket_manager.send_job_failed(Some(&server_id), &job.id, TASK_KIND, "Cancelled").await;
+            }
+            Ok(()) => {
+                job.status = "done".to_string();
+                job.progress = 1.0;
+                let mut message = format!("Imported {} chunks in {} regions", job.chunks_imported, job.regions_imported);
+                if !job.regions_skipped.is_empty() {
+                    message.push_str(&format!("; skipped {} regions with loaded chunks", job.regions_skipped.len()));
+                }
+                info!("Import job {} finished: {}", job.id, message);
+                let _ = self
+                    .websocket_manager
+                    .send_job_completed(Some(&server_id), &job.id, TASK_KIND, Some(&message))
+                    .await;
+            }
+            Err(e) => {
+                warn!("Import job {} failed: {}", job.id, e);
+                job.status = "failed".to_string();
+                job.error = Some(e.to_string());
+                let _ = self.websocket_manager.send_job_failed(Some(&server_id), &job.id, TASK_KIND, &e.to_string()).await;
+            }
+        }
+
+        if let Err(e) = self.database.update_task(&job.to_task()).await {
+            warn!("Failed to persist import job {}: {}", job.id, e);
+        }
+    }
+
+    async fn import(&self, job: &mut HotImportJob, server: &ServerConfig, live: bool, handle: &JobHandle) -> Result<()> {
+        let (source, target, dimensions) = (job.source_dir.clone(), job.target_world.clone(), job.dimensions.clone());
+        let plan = tokio::task::spawn_blocking(move || plan_import(&source, &target, &dimensions)).await??;
+        if plan.is_empty() {
+            bail!("No region files found in {}", job.source_dir.display());
+        }
+        job.regions_total = plan.len();
+
+        let backup_root = PathBuf::from(&server.server_directory)
+            .join("backups")
+            .join(format!("import-{}", job.id));
+        if job.backup_before_import {
+            let (entries, root) = (plan.clone(), backup_root.clone());
+            tokio::task::spawn_blocking(move || backup_regions(&entries, &root)).await??;
+            info!("Backed up regions for import job {} to {}", job.id, backup_root.display());
+            job.backup_path = Some(backup_root.clone());
+        }
+
+        let mut imported: Vec<RegionImport> = Vec::new();
+        let mut deferred: Vec<RegionImport> = Vec::new();
+
+        for batch in batches(plan, job.chunk_batch_size as usize) {
+            if handle.is_cancelled() {
+                return Ok(());
+            }
+            if live {
+                self.wait_for_tps(job, server, handle).await?;
+            }
+
+            for entry in batch {
+                if live && job.safety_checks {
+                    if let Some((x, z)) = loaded_chunk(server, &entry).await? {
+                        info!("Deferring {}: chunk {},{} is loaded", entry.label(), x, z);
+                        deferred.push(entry);
+                        continue;
+                    }
+                }
+                self.copy(job, entry, &mut imported, &backup_root).await?;
+            }
+        }
+
+        // Chunks around players may have unloaded by now; give deferred regions one more chance
+        for entry in deferred {
+            if handle.is_cancelled() {
+                return Ok(());
+            }
+            if loaded_chunk(server, &entry).await?.is_some() {
+                job.regions_skipped.push(entry.label());
+                continue;
+            }
+            self.copy(job, entry, &mut imported, &backup_root).await?;
+        }
+        Ok(())
+    }
+
+    /// Copy one region, rolling back everything imported so far if it fails verification
+    async fn copy(
+        &self,
+        job: &mut HotImportJob,
+        entry: RegionImport,
+        imported: &mut Vec<RegionImport>,
+        backup_root: &Path,
+    ) -> Result<()> {
+        let copied = entry.clone();
+        let result = tokio::task::spawn_blocking(move || copy_region(&copied)).await?;
+        imported.push(entry.clone());
+
+        if let Err(e) = result {
+            if job.backup_before_import {
+                let (entries, root) = (imported.clone(), backup_root.to_path_buf());
+                match tokio::task::spawn_blocking(move || rollback(&entries, &root)).await? {
+                    Ok(()) => job.rolled_back = true,
+                    Err(rollback_error) => {
+                        return Err(e.context(format!("rollback also failed: {}", rollback_error)));
+                    }
+                }
+                return Err(e.context("import rolled back"));
+            }
+            return Err(e);
+        }
+
+        job.regions_imported += 1;
+        job.chunks_imported += entry.source_chunks;
+        job.progress = (job.regions_imported + job.regions_skipped.len()) as f64 / job.regions_total.max(1) as f64;
+        job.updated_at = chrono::Utc::now();
+        self.database.update_task(&job.to_task()).await?;
+
+        let message = format!("{}/{} regions", job.regions_imported, job.regions_total);
+        let _ = self
+            .websocket_manager
+            .send_job_progress(Some(&job.server_id), &job.id, TASK_KIND, &entry.label(), job.progress as f32, 1, Some(&message))
+            .await;
+        Ok(())
+    }
+
+    async fn wait_for_tps(&self, job: &HotImportJob, server: &ServerConfig, handle: &JobHandle) -> Result<()> {
+        let Some(threshold) = job.tps_threshold else {
+            return Ok(());
+        };
+        loop {
+            let client = RconClient::new(server.host.clone(), server.rcon_port, server.rcon_password.clone());
+            let tps = tokio::task::spawn_blocking(move || client.get_server_info()).await??.tps;
+            if tps >= threshold || handle.is_cancelled() {
+                return Ok(());
+            }
+            info!("Import job {} waiting for TPS {:.1} to reach {:.1}", job.id, tps, threshold);
+            tokio::time::sleep(TPS_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Request cancellation; regions already copied stay in place
+    pub async fn cancel_job(&self, server_id: &str, job_id: &str) -> Result<()> {
+        self.get_job(server_id, job_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Import job", job_id))?;
+        self.jobs.cancel(job_id).await?;
+        Ok(())
+    }
+
+    pub async fn delete_job(&self, server_id: &str, job_id: &str) -> Result<()> {
+        let job = self
+            .get_job(server_id, job_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Import job", job_id))?;
+        if job.is_active() {
+            bail!("Cancel the import job before deleting it");
+        }
+        self.database.delete_task(job_id).await
+    }
+}
+
+/// Group regions so each batch holds roughly `chunk_batch_size` chunks (at least one region)
+fn batches(plan: Vec<RegionImport>, chunk_batch_size: usize) -> Vec<Vec<RegionImport>> {
+    let mut batches = Vec::new();
+    let mut current = Vec::new();
+    let mut chunks = 0;
+    for entry in plan {
+        chunks += entry.source_chunks;
+        current.push(entry);
+        if chunks >= chunk_batch_size {
+            batches.push(std::mem::take(&mut current));
+            chunks = 0;
+        }
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::nbt::{Compound, Tag};
+
+    fn write_region(dir: &Path, x: i32, z: i32, chunks: &[(i32, i32)], marker: i32) {
+        std::fs::create_dir_all(dir).unwrap();
+        let mut region = region::Region::new(x, z);
+        for &(cx, cz) in chunks {
+            let mut chunk = Compound::new();
+            chunk.insert("marker", Tag::Int(marker));
+            region.set_chunk(region::chunk_index(cx, cz), Some(region::RawChunk::encode(&chunk, 1).unwrap()));
+        }
+        region.write(&dir.join(format!("r.{}.{}.mca", x, z))).unwrap();
+    }
+
+    #[test]
+    fn test_plan_copy_and_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        let world = dir.path().join("world");
+        let backups = dir.path().join("backups");
+
+        write_region(&source.join("region"), 0, 0, &[(0, 0), (1, 0)], 1);
+        write_region(&source.join("region"), -1, 0, &[(-1, 0)], 1);
+        write_region(&world.join("region"), 0, 0, &[(5, 5)], 2);
+
+        let plan = plan_import(&source, &world, &["overworld".to_string(), "nether".to_string()]).unwrap();
+        assert_eq!(plan.len(), 2);
+        let existing = plan.iter().find(|entry| entry.target.ends_with("r.0.0.mca")).unwrap();
+        // Both the incoming chunks and the one being replaced have to be unloaded
+        assert_eq!(existing.chunks, vec![(0, 0), (1, 0), (5, 5)]);
+        assert_eq!(existing.source_chunks, 2);
+
+        backup_regions(&plan, &backups).unwrap();
+        let original = sha256_file(&world.join("region/r.0.0.mca")).unwrap();
+        for entry in &plan {
+            copy_region(entry).unwrap();
+            assert_eq!(sha256_file(&entry.target).unwrap(), sha256_file(&entry.source).unwrap());
+        }
+
+        rollback(&plan, &backups).unwrap();
+        assert_eq!(sha256_file(&world.join("region/r.0.0.mca")).unwrap(), original);
+        assert!(!world.join("region/r.-1.0.mca").exists());
+    }
+
+    #[test]
+    fn test_bare_region_folder_and_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        write_region(dir.path(), 0, 0, &[(0, 0)], 1);
+        write_region(dir.path(), 1, 0, &[(32, 0), (33, 0)], 1);
+
+        assert_eq!(source_region_dir(dir.path(), "overworld").as_deref(), Some(dir.path()));
+        assert_eq!(source_region_dir(dir.path(), "end"), None);
+
+        let plan = plan_import(dir.path(), &dir.path().join("world"), &["overworld".to_string()]).unwrap();
+        assert_eq!(batches(plan.clone(), 2).len(), 1);
+        assert_eq!(batches(plan, 1).len(), 2);
+    }
+}
@@ -0,0 +1,305 @@
+//! Managed Java runtimes
+//!
+//! Guardian downloads Eclipse Temurin JREs from Adoptium into
+//! `<data_dir>/runtimes/temurin-<major>-jre`, so servers don't depend on a
+//! system Java. Each Minecraft version needs a minimum Java major; a server
+//! pins a runtime by pointing its `java_path` at that runtime's binary, which
+//! keeps the same path when the runtime is reinstalled or updated.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::downloads::{Checksum, Download};
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::database::DatabaseManager;
+
+const ADOPTIUM_API: &str = "https://api.adoptium.net/v3";
+
+/// Java majors Guardian can download
+pub const SUPPORTED_MAJORS: [u32; 4] = [8, 11, 17, 21];
+
+/// Lowest Java major that runs `minecraft_version`, rounded up to one Guardian
+/// can download: 8 before 1.17, 17 up to 1.20.4 (1.17 itself needs 16), 21 from
+/// 1.20.5. Snapshots and unparseable versions get the newest.
+pub fn required_java(minecraft_version: &str) -> u32 {
+    let mut parts = minecraft_version.split(['.', '-', ' ']).map(|part| part.parse::<u32>().ok());
+    let (Some(Some(1)), Some(Some(minor))) = (parts.next(), parts.next()) else {
+        return 21;
+    };
+    let patch = parts.next().flatten().unwrap_or(0);
+    match (minor, patch) {
+        (..=16, _) => 8,
+        (17..=19, _) | (20, ..=4) => 17,
+        _ => 21,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JavaRuntime {
+    pub major: u32,
+    pub installed: bool,
+    /// The java binary, when installed
+    pub path: Option<String>,
+    /// Full version from the runtime's `release` file, e.g. "21.0.4"
+    pub version: Option<String>,
+    pub installing: bool,
+}
+
+/// Pin a server to a managed runtime
+#[derive(Debug, Clone, Deserialize)]
+pub struct PinRuntimeRequest {
+    pub major: u32,
+}
+
+/// Which runtime a server uses and which it needs
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerJavaRuntime {
+    pub java_path: String,
+    /// The managed runtime `java_path` points at, if any
+    pub pinned: Option<u32>,
+    pub required: u32,
+    pub minecraft_version: String,
+}
+
+pub struct JavaRuntimeManager {
+    database: Arc<DatabaseManager>,
+    installing: Mutex<HashSet<u32>>,
+}
+
+impl JavaRuntimeManager {
+    pub fn new(database: Arc<DatabaseManager>) -> Self {
+        Self {
+            database,
+            installing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// `<data_dir>/runtimes`, following the data directory in settings
+    async fn runtimes_dir(&self) -> Result<PathBuf> {
+        let settings = self.database.get_settings().await?.unwrap_or_default();
+        Ok(Path::new(&settings.data_dir).join("runtimes"))
+    }
+
+    async fn runtime(&self, runtimes_dir: &Path, major: u32) -> JavaRuntime {
+        let home = runtime_home(runtimes_dir, major);
+        let binary = find_java_binary(&home);
+        JavaRuntime {
+            major,
+            installed: binary.is_some(),
+            path: binary
+                .and_then(|path| std::fs::canonicalize(path).ok())
+                .map(|path| path.to_string_lossy().to_string()),
+            version: runtime_version(&home),
+            installing: self.installing.lock().await.contains(&major),
+        }
+    }
+
+    pub async fn list(&self) -> Result<Vec<JavaRuntime>> {
+        let runtimes_dir = self.runtimes_dir().await?;
+        let mut runtimes = Vec::with_capacity(SUPPORTED_MAJORS.len());
+        for major in SUPPORTED_MAJORS {
+            runtimes.push(self.runtime(&runtimes_dir, major).await);
+        }
+        Ok(runtimes)
+    }
+
+    /// Download `major`, replacing an installed copy with the latest build
+    pub async fn install(&self, major: u32) -> Result<JavaRuntime> {
+        if !SUPPORTED_MAJORS.contains(&major) {
+            bail!("Java {} is not available; pick one of {:?}", major, SUPPORTED_MAJORS);
+        }
+        if !self.installing.lock().await.insert(major) {
+            bail!("Java {} is already being installed", major);
+        }
+        let result = self.download(major).await;
+        self.installing.lock().await.remove(&major);
+        let runtimes_dir = result?;
+        Ok(self.runtime(&runtimes_dir, major).await)
+    }
+
+    async fn download(&self, major: u32) -> Result<PathBuf> {
+        let runtimes_dir = self.runtimes_dir().await?;
+        download_temurin(&runtimes_dir, major).await?;
+        Ok(runtimes_dir)
+    }
+
+    /// The java binary of `major`, downloading it first if needed
+    pub async fn ensure(&self, major: u32) -> Result<PathBuf> {
+        let runtimes_dir = self.runtimes_dir().await?;
+        if let Some(binary) = find_java_binary(&runtime_home(&runtimes_dir, major)) {
+            return Ok(std::fs::canonicalize(binary)?);
+        }
+        let runtime = self.install(major).await?;
+        runtime.path.map(PathBuf::from).ok_or_else(|| anyhow!("Java {} did not install", major))
+    }
+
+    pub async fn remove(&self, major: u32) -> Result<bool> {
+        let home = runtime_home(&self.runtimes_dir().await?, major);
+        if !home.exists() {
+            return Ok(false);
+        }
+        tokio::fs::remove_dir_all(&home).await
+            .with_context(|| format!("Failed to remove {}", home.display()))?;
+        info!("Removed Java {} runtime", major);
+        Ok(true)
+    }
+
+    /// The managed runtime `java_path` belongs to, if it is one
+    pub async fn pinned_major(&self, java_path: &str) -> Option<u32> {
+        let runtimes_dir = self.runtimes_dir().await.ok()?;
+        let java_path = std::fs::canonicalize(java_path).ok()?;
+        SUPPORTED_MAJORS.into_iter().find(|major| {
+            std::fs::canonicalize(runtime_home(&runtimes_dir, *major))
+                .is_ok_and(|home| java_path.starts_with(home))
+        })
+    }
+}
+
+fn runtime_home(runtimes_dir: &Path, major: u32) -> PathBuf {
+    runtimes_dir.join(format!("temurin-{}-jre", major))
+}
+
+/// `JAVA_VERSION` from the `release` file in a runtime's home
+fn runtime_version(home: &Path) -> Option<String> {
+    [home.join("release"), home.join("Contents").join("Home").join("release")]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .and_then(|release| {
+            release.lines()
+                .find_map(|line| line.strip_prefix("JAVA_VERSION="))
+                .map(|version| version.trim().trim_matches('"').to_string())
+        })
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumRelease {
+    binary: AdoptiumBinary,
+    release_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumBinary {
+    package: AdoptiumPackage,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumPackage {
+    name: String,
+    link: String,
+    checksum: String,
+}
+
+/// Adoptium's names for this OS and CPU
+fn adoptium_platform() -> Result<(&'static str, &'static str)> {
+    let os = match std::env::consts::OS {
+        "windows" => "windows",
+        "linux" => "linux",
+        "macos" => "mac",
+        other => bail!("No Temurin builds for {}", other),
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "aarch64",
+        other => bail!("No Temurin builds for {}", other),
+    };
+    Ok((os, arch))
+}
+
+/// `bin/java` in a runtime home, which on macOS sits under `Contents/Home`
+fn find_java_binary(home: &Path) -> Option<PathBuf> {
+    let exe = if cfg!(windows) { "java.exe" } else { "java" };
+    [home.join("bin").join(exe), home.join("Contents").join("Home").join("bin").join(exe)]
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+/// Download the latest Temurin JRE for `major` into `runtimes_dir/temurin-<major>-jre`
+/// and return the absolute path of its java binary
+pub async fn download_temurin(runtimes_dir: &Path, major: u32) -> Result<PathBuf> {
+    let (os, arch) = adoptium_platform()?;
+    let url = format!(
+        "{}/assets/latest/{}/hotspot?architecture={}&image_type=jre&os={}&vendor=eclipse",
+        ADOPTIUM_API, major, arch, os
+    );
+    let client = reqwest::Client::new();
+    let releases: Vec<AdoptiumRelease> = client.get(&url).send().await?.error_for_status()?.json().await
+        .context("Failed to look up Temurin releases")?;
+    let release = releases.into_iter().next()
+        .ok_or_else(|| anyhow!("No Temurin {} JRE for {} {}", major, os, arch))?;
+    let package = release.binary.package;
+
+    info!("Downloading {} ({})", release.release_name, package.name);
+    let download = Download::new(&package.link).checksum(Some(Checksum::Sha256(package.checksum.to_ascii_lowercase())));
+    let bytes = crate::downloads::shared().fetch(&download).await
+        .with_context(|| format!("Failed to download {}", package.name))?;
+
+    let target = runtime_home(runtimes_dir, major);
+    let staging = runtimes_dir.join(format!(".temurin-{}-jre.partial", major));
+    let archive_name = package.name.clone();
+    tokio::task::spawn_blocking(move || -> Result<PathBuf> {
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        std::fs::create_dir_all(&staging)?;
+        if archive_name.ends_with(".zip") {
+            zip::ZipArchive::new(Cursor::new(bytes))?.extract(&staging)?;
+        } else {
+            tar::Archive::new(flate2::read::GzDecoder::new(Cursor::new(bytes))).unpack(&staging)?;
+        }
+
+        // The archive holds one top-level directory, e.g. `jdk-21.0.4+7-jre`,
+        // which becomes the runtime home so the binary's path never changes
+        let home = std::fs::read_dir(&staging)?
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| find_java_binary(path).is_some())
+            .ok_or_else(|| anyhow!("{} has no java binary", archive_name))?;
+
+        // Swap in the new runtime only once it unpacked completely
+        if target.exists() {
+            std::fs::remove_dir_all(&target)?;
+        }
+        std::fs::rename(&home, &target)?;
+        let _ = std::fs::remove_dir_all(&staging);
+
+        // Absolute, since servers run from their own directories
+        let java = find_java_binary(&target).ok_or_else(|| anyhow!("{} has no java binary", archive_name))?;
+        Ok(std::fs::canonicalize(java)?)
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_java_by_minecraft_version() {
+        assert_eq!(required_java("1.12.2"), 8);
+        assert_eq!(required_java("1.16.5"), 8);
+        assert_eq!(required_java("1.17.1"), 17);
+        assert_eq!(required_java("1.20.4"), 17);
+        assert_eq!(required_java("1.20.5"), 21);
+        assert_eq!(required_java("1.21"), 21);
+        assert_eq!(required_java("24w14a"), 21);
+    }
+
+    #[test]
+    fn test_runtime_home_layout() {
+        let root = tempfile::tempdir().unwrap();
+        let home = runtime_home(root.path(), 17);
+        assert!(find_java_binary(&home).is_none());
+
+        let exe = if cfg!(windows) { "java.exe" } else { "java" };
+        std::fs::create_dir_all(home.join("bin")).unwrap();
+        std::fs::write(home.join("bin").join(exe), b"").unwrap();
+        std::fs::write(home.join("release"), "IMPLEMENTOR=\"Eclipse Adoptium\"\nJAVA_VERSION=\"17.0.12\"\n").unwrap();
+        assert_eq!(find_java_binary(&home), Some(home.join("bin").join(exe)));
+        assert_eq!(runtime_version(&home).as_deref(), Some("17.0.12"));
+    }
+}
@@ -0,0 +1,474 @@
+//! Job orchestration
+//!
+//! Long-running operations (lighting, world imports, backups, modpack installs)
+//! run as jobs: each is a `tasks` row that outlives hostd, holds a cancellation
+//! token while this process executes it, and waits for a slot before it starts
+//! so that only a few jobs of a kind, and of all kinds together, run at once.
+//!
+//! Simple operations implement [`Job`] and are handed to [`JobManager::spawn`].
+//! Features that keep their own task rows call [`JobManager::track`] and hold
+//! the returned [`JobHandle`] for as long as they run.
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::database::{DatabaseManager, Task};
+use crate::websocket_manager::WebSocketManager;
+use crate::core::error_handler::NotFound;
+
+/// Jobs of all kinds running at once
+const MAX_CONCURRENT_JOBS: usize = 4;
+const DEFAULT_LIST_LIMIT: u32 = 100;
+const MAX_LIST_LIMIT: u32 = 1000;
+
+/// Created but not started yet; only features that create jobs ahead of time use it
+pub const STATUS_PENDING: &str = "pending";
+/// Started and waiting for a slot
+pub const STATUS_QUEUED: &str = "queued";
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_DONE: &str = "done";
+pub const STATUS_FAILED: &str = "failed";
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+/// Jobs of one kind running at once; jobs rewriting world files run alone
+pub fn concurrency_limit(kind: &str) -> usize {
+    match kind {
+        "lighting" | "import" | "pregen" | "modpack_install" => 1,
+        _ => 2,
+    }
+}
+
+pub fn is_active(status: &str) -> bool {
+    status == STATUS_QUEUED || status == STATUS_RUNNING
+}
+
+/// Set once to ask a job to stop; jobs check it between steps
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<(AtomicBool, Notify)>,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.inner.0.store(true, Ordering::Relaxed);
+        self.inner.1.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.0.load(Ordering::Relaxed)
+    }
+
+    /// Resolves once the token is cancelled
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.1.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Semaphores that bound how many jobs run
+struct Limits {
+    global: Arc<Semaphore>,
+    kinds: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl Limits {
+    fn new() -> Self {
+        Self {
+            global: Arc::new(Semaphore::new(MAX_CONCURRENT_JOBS)),
+            kinds: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn acquire(&self, kind: &str) -> JobSlot {
+        let semaphore = self
+            .kinds
+            .lock()
+            .unwrap()
+            .entry(kind.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(concurrency_limit(kind))))
+            .clone();
+        // The kind first, so a job held back by its kind doesn't take a global slot
+        let kind = semaphore.acquire_owned().await.expect("job semaphores are never closed");
+        let global = self.global.clone().acquire_owned().await.expect("job semaphores are never closed");
+        JobSlot { _kind: kind, _global: global }
+    }
+}
+
+/// Permission to run; the slot is freed when dropped
+pub struct JobSlot {
+    _kind: OwnedSemaphorePermit,
+    _global: OwnedSemaphorePermit,
+}
+
+type Registry = Arc<Mutex<HashMap<String, CancelToken>>>;
+
+/// A job this process is executing; dropping it marks the job as no longer running here
+pub struct JobHandle {
+    id: String,
+    kind: String,
+    cancel: CancelToken,
+    registry: Registry,
+    limits: Arc<Limits>,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Wait until the job may run; `None` if it was cancelled while waiting
+    pub async fn slot(&self) -> Option<JobSlot> {
+        tokio::select! {
+            slot = self.limits.acquire(&self.kind) => Some(slot),
+            _ = self.cancel.cancelled() => None,
+        }
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        self.registry.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// What a job sees while it runs
+pub struct JobContext {
+    handle: JobHandle,
+    kind: String,
+    server_id: Option<String>,
+    database: Arc<DatabaseManager>,
+    websocket_manager: Arc<WebSocketManager>,
+}
+
+impl JobContext {
+    pub fn id(&self) -> &str {
+        self.handle.id()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.handle.is_cancelled()
+    }
+
+    /// Record progress (0.0 to 1.0) and report it over the WebSocket
+    pub async fn progress(&self, progress: f64, step: &str, message: Option<&str>) {
+        let progress = progress.clamp(0.0, 1.0);
+        if let Err(e) = self.database.update_task_progress(self.id(), progress, message).await {
+            warn!("Failed to record progress of job {}: {}", self.id(), e);
+        }
+        let _ = self
+            .websocket_manager
+            .send_job_progress(self.server_id.as_deref(), self.id(), &self.kind, step, progress as f32, 1, message)
+            .await;
+    }
+}
+
+/// A long-running operation run by the [`JobManager`]
+#[async_trait]
+pub trait Job: Send + 'static {
+    /// Task kind, such as `backup`
+    fn kind(&self) -> &'static str;
+
+    fn server_id(&self) -> Option<String>;
+
+    /// Stored in the task's metadata column
+    fn metadata(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Do the work, checking `ctx.is_cancelled()` between steps; the returned
+    /// message is kept as the job's log
+    async fn run(self: Box<Self>, ctx: &JobContext) -> Result<Option<String>>;
+}
+
+/// Job list filters as given in the query string
+#[derive(Debug, Default, Deserialize)]
+pub struct JobQuery {
+    pub server_id: Option<String>,
+    pub kind: Option<String>,
+    /// Comma-separated statuses; `active` stands for queued and running
+    pub status: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// Checked job list filters, as used for the database query
+#[derive(Debug, Clone, Default)]
+pub struct JobFilter {
+    pub server_id: Option<String>,
+    /// Only jobs of these servers, when not empty; set for users limited to
+    /// some servers, and leaves out jobs not tied to a server
+    pub server_ids: Vec<String>,
+    pub kind: Option<String>,
+    /// Only these statuses, when not empty
+    pub statuses: Vec<String>,
+    pub limit: u32,
+}
+
+impl JobQuery {
+    pub fn filter(&self) -> Result<JobFilter> {
+        let mut statuses = Vec::new();
+        for status in self.status.as_deref().unwrap_or_default().split(',').map(str::trim) {
+            match status {
+                "" => {}
+                "active" => statuses.extend([STATUS_QUEUED.to_string(), STATUS_RUNNING.to_string()]),
+                STATUS_PENDING | STATUS_QUEUED | STATUS_RUNNING | STATUS_DONE | STATUS_FAILED | STATUS_CANCELLED => {
+                    statuses.push(status.to_string())
+                }
+                other => bail!("Unknown job status: {}", other),
+            }
+        }
+        Ok(JobFilter {
+            server_id: self.server_id.clone(),
+            server_ids: Vec::new(),
+            kind: self.kind.clone().filter(|kind| !kind.is_empty()),
+            statuses,
+            limit: self.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT),
+        })
+    }
+}
+
+/// Runs jobs and tracks which ones are in flight
+pub struct JobManager {
+    database: Arc<DatabaseManager>,
+    websocket_manager: Arc<WebSocketManager>,
+    /// Cancellation tokens of jobs this process is executing, keyed by job ID
+    registry: Registry,
+    limits: Arc<Limits>,
+}
+
+impl JobManager {
+    pub fn new(database: Arc<DatabaseManager>, websocket_manager: Arc<WebSocketManager>) -> Self {
+        Self {
+            database,
+            websocket_manager,
+            registry: Arc::new(Mutex::new(HashMap::new())),
+            limits: Arc::new(Limits::new()),
+        }
+    }
+
+    /// Mark a task as executed by this process; fails if it already is
+    pub fn track(&self, id: &str, kind: &str) -> Result<JobHandle> {
+        let cancel = CancelToken::default();
+        {
+            let mut registry = self.registry.lock().unwrap();
+            if registry.contains_key(id) {
+                bail!("Job {} is already running", id);
+            }
+            registry.insert(id.to_string(), cancel.clone());
+        }
+        Ok(JobHandle {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            cancel,
+            registry: self.registry.clone(),
+            limits: self.limits.clone(),
+        })
+    }
+
+    pub fn is_tracked(&self, id: &str) -> bool {
+        self.registry.lock().unwrap().contains_key(id)
+    }
+
+    /// Queue a job; it starts in the background once a slot is free
+    pub async fn spawn<J: Job>(self: &Arc<Self>, job: J) -> Result<Task> {
+        let now = Utc::now();
+        let task = Task {
+            id: Uuid::new_v4().to_string(),
+            server_id: job.server_id(),
+            kind: job.kind().to_string(),
+            status: STATUS_QUEUED.to_string(),
+            progress: 0.0,
+            log: None,
+            metadata: job.metadata(),
+            started_at: None,
+            finished_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        // Tracked before the row exists, so it is never taken for an interrupted job
+        let handle = self.track(&task.id, &task.kind)?;
+        self.database.create_task(&task).await?;
+
+        let manager = self.clone();
+        let queued = task.clone();
+        tokio::spawn(async move { manager.execute(task, Box::new(job), handle).await });
+        Ok(queued)
+    }
+
+    async fn execute(&self, mut task: Task, job: Box<dyn Job>, handle: JobHandle) {
+        let Some(_slot) = handle.slot().await else {
+            task.status = STATUS_CANCELLED.to_string();
+            task.finished_at = Some(Utc::now());
+            task.updated_at = Utc::now();
+            self.persist(&task).await;
+            return;
+        };
+
+        task.status = STATUS_RUNNING.to_string();
+        task.started_at = Some(Utc::now());
+        task.updated_at = Utc::now();
+        self.persist(&task).await;
+        let server_id = task.server_id.clone();
+        let _ = self.websocket_manager.send_job_started(server_id.as_deref(), &task.id, &task.kind, 1).await;
+
+        let ctx = JobContext {
+            handle,
+            kind: task.kind.clone(),
+            server_id: server_id.clone(),
+            database: self.database.clone(),
+            websocket_manager: self.websocket_manager.clone(),
+        };
+        let result = job.run(&ctx).await;
+
+        // Progress was written by the job as it ran
+        if let Ok(Some(current)) = self.database.get_task(&task.id).await {
+            task.progress = current.progress;
+        }
+        task.finished_at = Some(Utc::now());
+        task.updated_at = Utc::now();
+        match result {
+            Ok(_) if ctx.is_cancelled() => {
+                task.status = STATUS_CANCELLED.to_string();
+                let _ = self.websocket_manager.send_job_failed(server_id.as_deref(), &task.id, &task.kind, "Cancelled").await;
+            }
+            Ok(message) => {
+                task.status = STATUS_DONE.to_string();
+                task.progress = 1.0;
+                task.log = message;
+                info!("Job {} ({}) finished", task.id, task.kind);
+                let _ = self
+                    .websocket_manager
+                    .send_job_completed(server_id.as_deref(), &task.id, &task.kind, task.log.as_deref())
+                    .await;
+            }
+            Err(e) => {
+                warn!("Job {} ({}) failed: {}", task.id, task.kind, e);
+                task.status = STATUS_FAILED.to_string();
+                task.log = Some(e.to_string());
+                let _ = self.websocket_manager.send_job_failed(server_id.as_deref(), &task.id, &task.kind, &e.to_string()).await;
+            }
+        }
+        self.persist(&task).await;
+    }
+
+    async fn persist(&self, task: &Task) {
+        if let Err(e) = self.database.update_task(task).await {
+            warn!("Failed to persist job {}: {}", task.id, e);
+        }
+    }
+
+    /// A job recorded as active that this process is not executing was cut off by a restart
+    pub async fn reconcile(&self, mut task: Task) -> Result<Task> {
+        if is_active(&task.status) && !self.is_tracked(&task.id) {
+            task.status = STATUS_FAILED.to_string();
+            task.log = Some("Interrupted by a restart of the host daemon".to_string());
+            task.finished_at = Some(Utc::now());
+            task.updated_at = Utc::now();
+            self.database.update_task(&task).await?;
+        }
+        Ok(task)
+    }
+
+    pub async fn list(&self, filter: &JobFilter) -> Result<Vec<Task>> {
+        let mut jobs = Vec::new();
+        for task in self.database.list_tasks(filter).await? {
+            jobs.push(self.reconcile(task).await?);
+        }
+        Ok(jobs)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Task>> {
+        match self.database.get_task(id).await? {
+            Some(task) => Ok(Some(self.reconcile(task).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Request cancellation of a job. A running job stops at its next check, a
+    /// queued one before it starts, and one not started yet right away.
+    pub async fn cancel(&self, id: &str) -> Result<Task> {
+        let mut task = self.get(id).await?.ok_or_else(|| NotFound::with_id("Job", id))?;
+
+        let token = self.registry.lock().unwrap().get(id).cloned();
+        if let Some(token) = token {
+            token.cancel();
+            return Ok(task);
+        }
+        if task.status == STATUS_PENDING {
+            task.status = STATUS_CANCELLED.to_string();
+            task.updated_at = Utc::now();
+            self.database.update_task(&task).await?;
+            return Ok(task);
+        }
+        bail!("Job is not running")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_filter() {
+        let query = JobQuery {
+            status: Some("active, failed".to_string()),
+            kind: Some(String::new()),
+            limit: Some(0),
+            ..Default::default()
+        };
+        let filter = query.filter().unwrap();
+        assert_eq!(filter.statuses, ["queued", "running", "failed"]);
+        assert_eq!(filter.kind, None);
+        assert_eq!(filter.limit, 1);
+
+        let bad = JobQuery { status: Some("stuck".to_string()), ..Default::default() };
+        assert!(bad.filter().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slots_and_cancellation() {
+        let limits = Arc::new(Limits::new());
+        let registry: Registry = Arc::new(Mutex::new(HashMap::new()));
+        let handle = |id: &str| {
+            let cancel = CancelToken::default();
+            registry.lock().unwrap().insert(id.to_string(), cancel.clone());
+            JobHandle {
+                id: id.to_string(),
+                kind: "lighting".to_string(),
+                cancel,
+                registry: registry.clone(),
+                limits: limits.clone(),
+            }
+        };
+
+        let first = handle("first");
+        let _slot = first.slot().await.unwrap();
+
+        // Only one lighting job runs at a time, so the second waits until cancelled
+        let second = handle("second");
+        let cancel = second.cancel.clone();
+        tokio::spawn(async move { cancel.cancel() });
+        assert!(second.slot().await.is_none());
+
+        drop(second);
+        assert_eq!(registry.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,280 @@
+//! JVM tuning profiles
+//!
+//! Selectable flag sets for a server's `jvm_args`, built for its heap size and
+//! Java version, and a check that flags are still accepted by that Java:
+//! CMS and other flags removed from newer JVMs stop the server from starting.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::database::ServerConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JvmProfile {
+    /// Aikar's G1 flags, the usual choice for servers up to 12 GB
+    Aikar,
+    /// Aikar's flags with the larger young generation recommended above 12 GB
+    G1LargeHeap,
+    /// ZGC for very short pauses; needs Java 17
+    Zgc,
+    /// Only the heap size, leaving everything else to the JVM's defaults
+    Basic,
+}
+
+pub const PROFILES: [JvmProfile; 4] = [JvmProfile::Aikar, JvmProfile::G1LargeHeap, JvmProfile::Zgc, JvmProfile::Basic];
+
+/// Heap size from which `G1LargeHeap` is the default
+const LARGE_HEAP_MB: u32 = 12 * 1024;
+
+impl JvmProfile {
+    pub fn parse(id: &str) -> Result<Self> {
+        match id {
+            "aikar" => Ok(Self::Aikar),
+            "g1_large_heap" => Ok(Self::G1LargeHeap),
+            "zgc" => Ok(Self::Zgc),
+            "basic" => Ok(Self::Basic),
+            other => bail!("Unknown JVM preset: {}", other),
+        }
+    }
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Aikar => "aikar",
+            Self::G1LargeHeap => "g1_large_heap",
+            Self::Zgc => "zgc",
+            Self::Basic => "basic",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Aikar => "Aikar's flags",
+            Self::G1LargeHeap => "G1 large heap",
+            Self::Zgc => "ZGC",
+            Self::Basic => "Basic",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Aikar => "Tuned G1 collector; the usual choice up to 12 GB",
+            Self::G1LargeHeap => "Aikar's flags with a larger young generation, for 12 GB and up",
+            Self::Zgc => "Sub-millisecond pauses at some throughput cost; best with plenty of spare RAM and cores",
+            Self::Basic => "Only sets the heap size",
+        }
+    }
+
+    pub fn min_java(&self) -> u32 {
+        match self {
+            Self::Zgc => 17,
+            _ => 8,
+        }
+    }
+
+    /// Default for a new server with `memory_mb` of heap
+    pub fn default_for(memory_mb: u32) -> Self {
+        if memory_mb >= LARGE_HEAP_MB {
+            Self::G1LargeHeap
+        } else {
+            Self::Aikar
+        }
+    }
+
+    /// Flags for `memory_mb` of heap on Java `java_major`
+    pub fn args(&self, memory_mb: u32, java_major: u32) -> Result<Vec<String>> {
+        if java_major < self.min_java() {
+            bail!("The {} preset needs Java {} or newer, not Java {}", self.name(), self.min_java(), java_major);
+        }
+        // A fixed heap avoids resizing pauses; AlwaysPreTouch then commits it up front
+        let mut args = vec![format!("-Xms{}M", memory_mb), format!("-Xmx{}M", memory_mb)];
+        let flags: &[&str] = match self {
+            Self::Aikar | Self::G1LargeHeap => &[
+                "-XX:+UseG1GC",
+                "-XX:+ParallelRefProcEnabled",
+                "-XX:MaxGCPauseMillis=200",
+                "-XX:+UnlockExperimentalVMOptions",
+                "-XX:+DisableExplicitGC",
+                "-XX:+AlwaysPreTouch",
+                "-XX:G1HeapWastePercent=5",
+                "-XX:G1MixedGCCountTarget=4",
+                "-XX:G1MixedGCLiveThresholdPercent=90",
+                "-XX:G1RSetUpdatingPauseTimePercent=5",
+                "-XX:SurvivorRatio=32",
+                "-XX:+PerfDisableSharedMem",
+                "-XX:MaxTenuringThreshold=1",
+                "-Dusing.aikars.flags=https://mcflags.emc.gs",
+                "-Daikars.new.flags=true",
+            ],
+            Self::Zgc => &["-XX:+UseZGC", "-XX:+AlwaysPreTouch", "-XX:+DisableExplicitGC", "-XX:+PerfDisableSharedMem"],
+            // G1 is only the default from Java 9
+            Self::Basic if java_major < 9 => &["-XX:+UseG1GC"],
+            Self::Basic => &[],
+        };
+        args.extend(flags.iter().map(|flag| flag.to_string()));
+        match self {
+            Self::Aikar => args.extend([
+                "-XX:G1NewSizePercent=30",
+                "-XX:G1MaxNewSizePercent=40",
+                "-XX:G1HeapRegionSize=8M",
+                "-XX:G1ReservePercent=20",
+                "-XX:InitiatingHeapOccupancyPercent=15",
+            ].map(str::to_string)),
+            Self::G1LargeHeap => args.extend([
+                "-XX:G1NewSizePercent=40",
+                "-XX:G1MaxNewSizePercent=50",
+                "-XX:G1HeapRegionSize=16M",
+                "-XX:G1ReservePercent=15",
+                "-XX:InitiatingHeapOccupancyPercent=20",
+            ].map(str::to_string)),
+            // Generational ZGC collects young objects separately from Java 21
+            Self::Zgc if java_major >= 21 => args.push("-XX:+ZGenerational".to_string()),
+            _ => {}
+        }
+        Ok(args)
+    }
+}
+
+/// A preset as offered for one server
+#[derive(Debug, Clone, Serialize)]
+pub struct JvmPreset {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub min_java: u32,
+    /// Whether the server's Java can use it
+    pub supported: bool,
+    /// The flags for this server's heap and Java, when supported
+    pub args: Option<String>,
+}
+
+pub fn presets(memory_mb: u32, java_major: u32) -> Vec<JvmPreset> {
+    PROFILES.iter().map(|profile| {
+        let args = profile.args(memory_mb, java_major).ok();
+        JvmPreset {
+            id: profile.id(),
+            name: profile.name(),
+            description: profile.description(),
+            min_java: profile.min_java(),
+            supported: args.is_some(),
+            args: args.map(|args| args.join(" ")),
+        }
+    }).collect()
+}
+
+/// Presets for a server and how its current flags fare on its Java
+#[derive(Debug, Clone, Serialize)]
+pub struct JvmPresetList {
+    pub java_major: u32,
+    /// False when the server's Java couldn't be run and `java_major` is the
+    /// minimum its Minecraft version needs
+    pub java_detected: bool,
+    pub memory_mb: u32,
+    pub current_args: String,
+    pub issues: Vec<FlagIssue>,
+    pub presets: Vec<JvmPreset>,
+}
+
+/// Java major of the server's `java_path`, and whether it could be run to
+/// find out; otherwise the minimum its Minecraft version needs
+pub async fn server_java_major(server: &ServerConfig) -> (u32, bool) {
+    match crate::setup::check_java(&server.java_path).await.major {
+        Some(major) => (major, true),
+        None => (crate::java_runtimes::required_java(&server.minecraft_version), false),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FlagIssue {
+    pub flag: String,
+    pub reason: String,
+}
+
+/// Flags removed from the JVM, and the first Java that refuses to start with them
+const REMOVED_FLAGS: &[(&str, u32)] = &[
+    ("PermSize", 17),
+    ("MaxPermSize", 17),
+    ("UseParNewGC", 10),
+    ("AggressiveOpts", 13),
+    ("UseConcMarkSweepGC", 14),
+    ("CMSIncrementalMode", 14),
+    ("CMSClassUnloadingEnabled", 14),
+    ("CMSInitiatingOccupancyFraction", 14),
+    ("UseCMSInitiatingOccupancyOnly", 14),
+];
+
+/// Flags added to the JVM, and the first Java that accepts them
+const ADDED_FLAGS: &[(&str, u32)] = &[
+    ("UseShenandoahGC", 11),
+    ("UseZGC", 15),
+    ("ZGenerational", 21),
+];
+
+/// G1 flags the JVM only accepts after `-XX:+UnlockExperimentalVMOptions`
+const EXPERIMENTAL_FLAGS: &[&str] = &["G1NewSizePercent", "G1MaxNewSizePercent"];
+
+/// Name of a `-XX:` flag: `-XX:+UseZGC` and `-XX:G1NewSizePercent=30` give
+/// `UseZGC` and `G1NewSizePercent`
+fn xx_name(arg: &str) -> Option<&str> {
+    let flag = arg.strip_prefix("-XX:")?;
+    let flag = flag.strip_prefix(['+', '-']).unwrap_or(flag);
+    Some(flag.split('=').next().unwrap_or(flag))
+}
+
+/// Flags in `args` that Java `java_major` would refuse to start with
+pub fn validate_flags(args: &[String], java_major: u32) -> Vec<FlagIssue> {
+    let unlocked = args.iter().any(|arg| arg == "-XX:+UnlockExperimentalVMOptions");
+    let mut issues = Vec::new();
+    for arg in args {
+        let Some(name) = xx_name(arg) else {
+            continue;
+        };
+        let reason = if let Some((_, removed)) = REMOVED_FLAGS.iter().find(|(flag, _)| *flag == name) {
+            (java_major >= *removed).then(|| format!("Not accepted from Java {}", removed))
+        } else if let Some((_, added)) = ADDED_FLAGS.iter().find(|(flag, _)| *flag == name) {
+            (java_major < *added).then(|| format!("Needs Java {} or newer", added))
+        } else if EXPERIMENTAL_FLAGS.contains(&name) && !unlocked {
+            Some("Needs -XX:+UnlockExperimentalVMOptions before it".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            issues.push(FlagIssue { flag: arg.clone(), reason });
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_pass_their_own_validation() {
+        for java in [8, 11, 17, 21] {
+            for profile in PROFILES {
+                if let Ok(args) = profile.args(8192, java) {
+                    assert!(validate_flags(&args, java).is_empty(), "{:?} on Java {}", profile, java);
+                }
+            }
+        }
+        assert!(JvmProfile::Zgc.args(8192, 11).is_err());
+        assert!(JvmProfile::Zgc.args(8192, 21).unwrap().contains(&"-XX:+ZGenerational".to_string()));
+        assert_eq!(JvmProfile::default_for(16384), JvmProfile::G1LargeHeap);
+    }
+
+    #[test]
+    fn test_validate_flags_catches_removed_and_new_flags() {
+        let args: Vec<String> = ["-Xmx4G", "-XX:+UseConcMarkSweepGC", "-XX:+UseParNewGC", "-XX:G1NewSizePercent=30"]
+            .map(str::to_string)
+            .to_vec();
+        let issues = validate_flags(&args, 17);
+        assert_eq!(issues.iter().map(|issue| issue.flag.as_str()).collect::<Vec<_>>(), [
+            "-XX:+UseConcMarkSweepGC",
+            "-XX:+UseParNewGC",
+            "-XX:G1NewSizePercent=30",
+        ]);
+        assert!(validate_flags(&args[..2], 8).is_empty());
+        assert_eq!(validate_flags(&["-XX:+UseZGC".to_string()], 11)[0].reason, "Needs Java 15 or newer");
+    }
+}
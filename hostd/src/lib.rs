@@ -9,12 +9,15 @@ pub mod compatibility_engine;
 pub mod pregeneration;
 pub mod hot_import;
 pub mod lighting;
+pub mod restart_scheduler;
+pub mod world;
 pub mod mod_management;
 pub mod external_apis;
 pub mod modpack_installer;
 pub mod version_resolver;
 pub mod loaders;
 pub mod gpu_manager;
+pub mod gpu_telemetry;
 pub mod compatibility_analyzer;
 pub mod performance_telemetry;
 pub mod middleware;
@@ -30,4 +33,56 @@ pub mod mod_manager;
 pub mod backup_manager;
 pub mod security;
 pub mod minecraft;
-pub mod rcon;
\ No newline at end of file
+pub mod rcon;
+pub mod tick_timings;
+pub mod alerts;
+pub mod notifiers;
+pub mod server_properties;
+pub mod config_revisions;
+pub mod config_files;
+pub mod player_lists;
+pub mod bans;
+pub mod player_profiles;
+pub mod server_ping;
+pub mod server_query;
+pub mod external_servers;
+pub mod server_import;
+pub mod server_templates;
+pub mod port_forwarding;
+pub mod tunnels;
+pub mod service;
+pub mod setup;
+pub mod java_runtimes;
+pub mod jvm_presets;
+pub mod resource_limits;
+pub mod auto_start;
+pub mod events;
+pub mod event_bus;
+pub mod jobs;
+pub mod mod_metadata;
+pub mod compat_rules;
+pub mod client_mods;
+pub mod datapacks;
+pub mod resource_packs;
+pub mod server_files;
+pub mod server_logs;
+pub mod console_commands;
+pub mod eula;
+pub mod downloads;
+pub mod metadata_cache;
+pub mod version_catalog;
+pub mod mod_drift;
+pub mod mod_toggle;
+pub mod mod_bisect;
+pub mod sharding;
+pub mod nodes;
+pub mod server_migration;
+pub mod freeze_tickets;
+pub mod diagnostics;
+pub mod openapi;
+pub mod listing;
+pub mod server_groups;
+pub mod webhook;
+pub mod discord;
+pub mod chat_bridge;
+pub mod world_border;
\ No newline at end of file
@@ -1,31 +1,183 @@
-// Lighting module placeholder
-// This will be implemented later
+//! Lighting optimization jobs: rewrite the stored block and sky light of a
+//! world's region files while the server is offline.
+//!
+//! Jobs are persisted as `tasks` rows (kind `lighting`) so they survive a
+//! restart of hostd, run one at a time through the job manager, and report
+//! progress over the WebSocket as `lighting` progress events.
 
+use anyhow::{anyhow, bail, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, Task};
+use crate::jobs::{self, JobHandle, JobManager};
+use crate::websocket_manager::WebSocketManager;
+use crate::world::{self, light, region};
+use crate::core::error_handler::NotFound;
+
+const TASK_KIND: &str = "lighting";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OptimizationLevel {
+    /// Drop stored light and let the server recompute it when chunks load
     Low,
+    /// Recompute light only for chunks the game has not lit yet
     Medium,
+    /// Recompute light for every chunk
     High,
+    /// Same as `High`
     Ultra,
+    /// Same as `Medium`
     Balanced,
 }
 
+impl FromStr for OptimizationLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "low" => Ok(OptimizationLevel::Low),
+            "medium" => Ok(OptimizationLevel::Medium),
+            "high" => Ok(OptimizationLevel::High),
+            "ultra" => Ok(OptimizationLevel::Ultra),
+            "balanced" | "" => Ok(OptimizationLevel::Balanced),
+            other => Err(format!("unknown optimization level '{}'", other)),
+        }
+    }
+}
+
 /// Lighting job configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LightingJob {
     pub id: String,
     pub server_id: String,
+    pub name: String,
     pub world_path: PathBuf,
+    pub dimensions: Vec<String>,
     pub optimization_level: OptimizationLevel,
+    pub use_gpu: bool,
+    pub backup_before_optimization: bool,
+    pub preserve_lighting_data: bool,
     pub progress: f32,
+    /// pending, queued, running, done, failed or cancelled
     pub status: String,
+    pub regions_total: usize,
+    pub regions_processed: usize,
+    pub chunks_processed: usize,
+    pub chunks_relit: usize,
+    pub chunks_failed: usize,
+    pub error: Option<String>,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Job fields kept in the task's metadata column
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct JobMetadata {
+    name: String,
+    world_path: PathBuf,
+    dimensions: Vec<String>,
+    optimization_level: Option<OptimizationLevel>,
+    use_gpu: bool,
+    backup_before_optimization: bool,
+    preserve_lighting_data: bool,
+    regions_total: usize,
+    regions_processed: usize,
+    chunks_processed: usize,
+    chunks_relit: usize,
+    chunks_failed: usize,
+}
+
+impl LightingJob {
+    fn from_task(task: &Task) -> Self {
+        let metadata: JobMetadata = task
+            .metadata
+            .clone()
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default();
+
+        Self {
+            id: task.id.clone(),
+            server_id: task.server_id.clone().unwrap_or_default(),
+            name: metadata.name,
+            world_path: metadata.world_path,
+            dimensions: metadata.dimensions,
+            optimization_level: metadata.optimization_level.unwrap_or(OptimizationLevel::Balanced),
+            use_gpu: metadata.use_gpu,
+            backup_before_optimization: metadata.backup_before_optimization,
+            preserve_lighting_data: metadata.preserve_lighting_data,
+            progress: task.progress as f32,
+            status: task.status.clone(),
+            regions_total: metadata.regions_total,
+            regions_processed: metadata.regions_processed,
+            chunks_processed: metadata.chunks_processed,
+            chunks_relit: metadata.chunks_relit,
+            chunks_failed: metadata.chunks_failed,
+            error: if task.status == "failed" { task.log.clone() } else { None },
+            started_at: task.started_at,
+            finished_at: task.finished_at,
+            created_at: task.created_at,
+            updated_at: task.updated_at,
+        }
+    }
+
+    fn to_task(&self) -> Task {
+        let metadata = JobMetadata {
+            name: self.name.clone(),
+            world_path: self.world_path.clone(),
+            dimensions: self.dimensions.clone(),
+            optimization_level: Some(self.optimization_level),
+            use_gpu: self.use_gpu,
+            backup_before_optimization: self.backup_before_optimization,
+            preserve_lighting_data: self.preserve_lighting_data,
+            regions_total: self.regions_total,
+            regions_processed: self.regions_processed,
+            chunks_processed: self.chunks_processed,
+            chunks_relit: self.chunks_relit,
+            chunks_failed: self.chunks_failed,
+        };
+
+        Task {
+            id: self.id.clone(),
+            server_id: Some(self.server_id.clone()),
+            kind: TASK_KIND.to_string(),
+            status: self.status.clone(),
+            progress: self.progress as f64,
+            log: self.error.clone(),
+            metadata: serde_json::to_value(metadata).ok(),
+            started_at: self.started_at,
+            finished_at: self.finished_at,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        jobs::is_active(&self.status)
+    }
+}
+
+/// Parameters for a new lighting job
+#[derive(Debug, Clone)]
+pub struct NewLightingJob {
+    pub name: String,
+    pub world_path: String,
+    pub dimensions: Vec<String>,
+    pub optimization_level: OptimizationLevel,
+    pub use_gpu: bool,
+    pub backup_before_optimization: bool,
+    pub preserve_lighting_data: bool,
+}
+
 /// Lighting settings for a server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LightingSettings {
@@ -42,3 +194,441 @@ pub struct LightingSettings {
     pub chunk_radius: u32,
     pub priority: u8,
 }
+
+impl Default for LightingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            optimization_level: OptimizationLevel::Balanced,
+            default_level: OptimizationLevel::Balanced,
+            auto_optimize: true,
+            gpu_acceleration: true,
+            auto_optimize_after_pregeneration: true,
+            preserve_lighting_data: true,
+            max_concurrent_jobs: 4,
+            chunk_batch_size: 100,
+            schedule: None,
+            chunk_radius: 100,
+            priority: 5,
+        }
+    }
+}
+
+/// Whether sky light is stored for a dimension
+fn has_sky(dimension: &str) -> bool {
+    world::dimension_id(dimension) != Some("minecraft:the_nether")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RelightMode {
+    Invalidate,
+    Unlit,
+    All,
+}
+
+impl RelightMode {
+    fn for_job(job: &LightingJob) -> Self {
+        match job.optimization_level {
+            OptimizationLevel::Low => RelightMode::Invalidate,
+            OptimizationLevel::High | OptimizationLevel::Ultra if !job.preserve_lighting_data => RelightMode::All,
+            _ => RelightMode::Unlit,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct RegionStats {
+    chunks: usize,
+    relit: usize,
+    failed: usize,
+}
+
+/// Relight every chunk of one region file and write it back if anything changed
+fn relight_region(path: &Path, mode: RelightMode, sky: bool) -> Result<RegionStats> {
+    let mut region = region::Region::read(path)?;
+    let mut stats = RegionStats::default();
+    let mut updated = Vec::new();
+
+    for (index, raw) in region.present() {
+        stats.chunks += 1;
+        let mut chunk = match raw.decode() {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                let (x, z) = region::chunk_coords(region.x, region.z, index);
+                warn!("Skipping unreadable chunk {},{} in {}: {}", x, z, path.display(), e);
+                stats.failed += 1;
+                continue;
+            }
+        };
+
+        let changed = match mode {
+            _ if !light::is_fully_generated(&chunk) => false,
+            RelightMode::Invalidate => {
+                light::invalidate_light(&mut chunk);
+                true
+            }
+            RelightMode::Unlit if light::is_lit(&chunk) => false,
+            _ => match light::relight_chunk(&mut chunk, sky) {
+                Ok(_) => true,
+                Err(e) => {
+                    let (x, z) = region::chunk_coords(region.x, region.z, index);
+                    warn!("Could not relight chunk {},{} in {}: {}", x, z, path.display(), e);
+                    stats.failed += 1;
+                    false
+                }
+            },
+        };
+
+        if changed {
+            updated.push((index, region::RawChunk::encode(&chunk, raw.timestamp)?));
+        }
+    }
+
+    stats.relit = updated.len();
+    if !updated.is_empty() {
+        for (index, chunk) in updated {
+            region.set_chunk(index, Some(chunk));
+        }
+        region.write(path)?;
+    }
+    Ok(stats)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs lighting jobs and tracks which ones are in flight
+pub struct LightingManager {
+    database: Arc<DatabaseManager>,
+    websocket_manager: Arc<WebSocketManager>,
+    process_manager: Arc<ProcessManager>,
+    jobs: Arc<JobManager>,
+    settings: RwLock<HashMap<String, LightingSettings>>,
+}
+
+impl LightingManager {
+    pub fn new(
+        database: Arc<DatabaseManager>,
+        websocket_manager: Arc<WebSocketManager>,
+        process_manager: Arc<ProcessManager>,
+        jobs: Arc<JobManager>,
+    ) -> Self {
+        Self {
+            database,
+            websocket_manager,
+            process_manager,
+            jobs,
+            settings: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Resolve the world folder of a server; relative paths are taken from the server directory
+    async fn resolve_world(&self, server_id: &str, world_path: &str) -> Result<PathBuf> {
+        let server = self
+            .database
+            .get_server(server_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Server", server_id))?;
+        let server_dir = PathBuf::from(&server.server_directory);
+
+        let world = if world_path.trim().is_empty() {
+            server_dir.join(&server.world_name)
+        } else {
+            server_dir.join(world_path)
+        };
+
+        let world = world.canonicalize().map_err(|_| anyhow!("World folder {} does not exist", world.display()))?;
+        if !world.starts_with(server_dir.canonicalize()?) {
+            bail!("World folder must be inside the server directory");
+        }
+        Ok(world)
+    }
+
+    pub async fn list_jobs(&self, server_id: &str) -> Result<Vec<LightingJob>> {
+        let tasks = self.database.get_tasks_by_server(server_id).await?;
+        let mut jobs = Vec::new();
+        for task in tasks.into_iter().filter(|task| task.kind == TASK_KIND) {
+            jobs.push(LightingJob::from_task(&self.jobs.reconcile(task).await?));
+        }
+        Ok(jobs)
+    }
+
+    pub async fn get_job(&self, server_id: &str, job_id: &str) -> Result<Option<LightingJob>> {
+        let task = self
+            .jobs
+            .get(job_id)
+            .await?
+            .filter(|task| task.kind == TASK_KIND && task.server_id.as_deref() == Some(server_id));
+        Ok(task.map(|task| LightingJob::from_task(&task)))
+    }
+
+    pub async fn create_job(&self, server_id: &str, request: NewLightingJob) -> Result<LightingJob> {
+        let world_path = self.resolve_world(server_id, &request.world_path).await?;
+
+        let mut dimensions = request.dimensions;
+        if dimensions.is_empty() {
+            dimensions.push("overworld".to_string());
+        }
+        for dimension in &dimensions {
+            if world::region_dir(dimension).is_none() {
+                bail!("Unknown dimension '{}'", dimension);
+            }
+        }
+        if request.use_gpu {
+            info!("Lighting job for server {} requested GPU acceleration; light is computed on the CPU", server_id);
+        }
+
+        let now = chrono::Utc::now();
+        let job = LightingJob {
+            id: Uuid::new_v4().to_string(),
+            server_id: server_id.to_string(),
+            name: request.name,
+            world_path,
+            dimensions,
+            optimization_level: request.optimization_level,
+            use_gpu: request.use_gpu,
+            backup_before_optimization: request.backup_before_optimization,
+            preserve_lighting_data: request.preserve_lighting_data,
+            progress: 0.0,
+            status: "pending".to_string(),
+            regions_total: 0,
+            regions_processed: 0,
+            chunks_processed: 0,
+            chunks_relit: 0,
+            chunks_failed: 0,
+            error: None,
+            started_at: None,
+            finished_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.database.create_task(&job.to_task()).await?;
+        Ok(job)
+    }
+
+    pub async fn start_job(self: &Arc<Self>, server_id: &str, job_id: &str) -> Result<LightingJob> {
+        let mut job = self
+            .get_job(server_id, job_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Lighting job", job_id))?;
+        if job.is_active() {
+            bail!("Lighting job is already running");
+        }
+        if job.status == "done" {
+            bail!("Lighting job has already completed");
+        }
+        let server_uuid = Uuid::parse_str(server_id)?;
+        if self.process_manager.is_server_running(server_uuid).await {
+            bail!("Stop the server before optimizing its lighting");
+        }
+
+        let handle = self.jobs.track(&job.id, TASK_KIND).map_err(|_| anyhow!("Lighting job is already running"))?;
+
+        job.status = jobs::STATUS_QUEUED.to_string();
+        job.progress = 0.0;
+        job.error = None;
+        job.regions_processed = 0;
+        job.chunks_processed = 0;
+        job.chunks_relit = 0;
+        job.chunks_failed = 0;
+        job.started_at = None;
+        job.finished_at = None;
+        job.updated_at = chrono::Utc::now();
+        self.database.update_task(&job.to_task()).await?;
+
+        let manager = self.clone();
+        let queued = job.clone();
+        tokio::spawn(async move { manager.run(job, server_uuid, handle).await });
+
+        Ok(queued)
+    }
+
+    async fn run(&self, mut job: LightingJob, server_uuid: Uuid, handle: JobHandle) {
+        let server_id = job.server_id.clone();
+        let Some(_slot) = handle.slot().await else {
+            job.status = jobs::STATUS_CANCELLED.to_string();
+            job.finished_at = Some(chrono::Utc::now());
+            job.updated_at = chrono::Utc::now();
+            if let Err(e) = self.database.update_task(&job.to_task()).await {
+                warn!("Failed to persist lighting job {}: {}", job.id, e);
+            }
+            return;
+        };
+
+        job.status = jobs::STATUS_RUNNING.to_string();
+        job.started_at = Some(chrono::Utc::now());
+        job.updated_at = chrono::Utc::now();
+        let _ = self.websocket_manager.send_job_started(Some(&server_id), &job.id, TASK_KIND, 1).await;
+
+        let result = match self.database.update_task(&job.to_task()).await {
+            Ok(()) => self.process(&mut job, server_uuid, &handle).await,
+            Err(e) => Err(e),
+        };
+        job.finished_at = Some(chrono::Utc::now());
+        job.updated_at = chrono::Utc::now();
+
+        match result {
+            Ok(()) if handle.is_cancelled() => {
+                job.status = "cancelled".to_string();
+                let _ = self.websocket_manager.send_job_failed(Some(&server_id), &job.id, TASK_KIND, "Cancelled").await;
+            }
+            Ok(()) => {
+                job.status = "done".to_string();
+                job.progress = 1.0;
+                let message = format!(
+                    "Relit {} of {} chunks in {} regions",
+                    job.chunks_relit, job.chunks_processed, job.regions_processed
+                );
+                info!("Lighting job {} finished: {}", job.id, message);
+                let _ = self
+                    .websocket_manager
+                    .send_job_completed(Some(&server_id), &job.id, TASK_KIND, Some(&message))
+                    .await;
+            }
+            Err(e) => {
+                warn!("Lighting job {} failed: {}", job.id, e);
+                job.status = "failed".to_string();
+                job.error = Some(e.to_string());
+                let _ = self.websocket_manager.send_job_failed(Some(&server_id), &job.id, TASK_KIND, &e.to_string()).await;
+            }
+        }
+
+        if let Err(e) = self.database.update_task(&job.to_task()).await {
+            warn!("Failed to persist lighting job {}: {}", job.id, e);
+        }
+    }
+
+    async fn process(&self, job: &mut LightingJob, server_uuid: Uuid, handle: &JobHandle) -> Result<()> {
+        let mode = RelightMode::for_job(job);
+
+        let mut regions = Vec::new();
+        for dimension in &job.dimensions {
+            let dir = job.world_path.join(world::region_dir(dimension).unwrap_or("region"));
+            for (_, _, path) in region::list_regions(&dir)? {
+                regions.push((dimension.clone(), path));
+            }
+        }
+        job.regions_total = regions.len();
+
+        if job.backup_before_optimization {
+            let backup_root = job.world_path.join(format!(".lighting-backup-{}", job.id));
+            for dimension in &job.dimensions {
+                let relative = world::region_dir(dimension).unwrap_or("region");
+                let dir = job.world_path.join(relative);
+                if dir.exists() {
+                    let (from, to) = (dir, backup_root.join(relative));
+                    tokio::task::spawn_blocking(move || copy_dir(&from, &to)).await??;
+                }
+            }
+            info!("Backed up region files for lighting job {} to {}", job.id, backup_root.display());
+        }
+
+        for (dimension, path) in regions {
+            if handle.is_cancelled() {
+                return Ok(());
+            }
+            if self.process_manager.is_server_running(server_uuid).await {
+                bail!("Server was started while lighting was being optimized");
+            }
+
+            let sky = has_sky(&dimension);
+            let region_path = path.clone();
+            let stats = tokio::task::spawn_blocking(move || relight_region(&region_path, mode, sky)).await??;
+
+            job.regions_processed += 1;
+            job.chunks_processed += stats.chunks;
+            job.chunks_relit += stats.relit;
+            job.chunks_failed += stats.failed;
+            job.progress = job.regions_processed as f32 / job.regions_total.max(1) as f32;
+            job.updated_at = chrono::Utc::now();
+            self.database.update_task(&job.to_task()).await?;
+
+            let step = format!("{} {}", dimension, path.file_name().and_then(|n| n.to_str()).unwrap_or_default());
+            let message = format!("{}/{} regions", job.regions_processed, job.regions_total);
+            let _ = self
+                .websocket_manager
+                .send_job_progress(Some(&job.server_id), &job.id, TASK_KIND, &step, job.progress, 1, Some(&message))
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Request cancellation; the job stops after the region it is working on
+    pub async fn cancel_job(&self, server_id: &str, job_id: &str) -> Result<()> {
+        self.get_job(server_id, job_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Lighting job", job_id))?;
+        self.jobs.cancel(job_id).await?;
+        Ok(())
+    }
+
+    pub async fn delete_job(&self, server_id: &str, job_id: &str) -> Result<()> {
+        let job = self
+            .get_job(server_id, job_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Lighting job", job_id))?;
+        if job.is_active() {
+            bail!("Cancel the lighting job before deleting it");
+        }
+        self.database.delete_task(job_id).await
+    }
+
+    pub async fn get_settings(&self, server_id: &str) -> LightingSettings {
+        self.settings.read().await.get(server_id).cloned().unwrap_or_default()
+    }
+
+    pub async fn update_settings(&self, server_id: &str, settings: LightingSettings) -> LightingSettings {
+        self.settings.write().await.insert(server_id.to_string(), settings.clone());
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::nbt::{Compound, Tag};
+
+    #[test]
+    fn test_relight_region_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("r.0.0.mca");
+
+        let mut section = Compound::new();
+        section.insert("Y", Tag::Byte(0));
+        let mut chunk = Compound::new();
+        chunk.insert("DataVersion", Tag::Int(3700));
+        chunk.insert("sections", Tag::List(10, vec![Tag::Compound(section)]));
+
+        let mut region = region::Region::new(0, 0);
+        region.set_chunk(0, Some(region::RawChunk::encode(&chunk, 1).unwrap()));
+        region.write(&path).unwrap();
+
+        let stats = relight_region(&path, RelightMode::Unlit, true).unwrap();
+        assert_eq!((stats.chunks, stats.relit), (1, 1));
+        let relit = region::Region::read(&path).unwrap().chunk(0).unwrap().decode().unwrap();
+        assert!(light::is_lit(&relit));
+
+        // Already lit chunks are left alone unless everything is recomputed
+        assert_eq!(relight_region(&path, RelightMode::Unlit, true).unwrap().relit, 0);
+        assert_eq!(relight_region(&path, RelightMode::All, true).unwrap().relit, 1);
+
+        relight_region(&path, RelightMode::Invalidate, true).unwrap();
+        let invalidated = region::Region::read(&path).unwrap().chunk(0).unwrap().decode().unwrap();
+        assert!(!light::is_lit(&invalidated));
+    }
+
+    #[test]
+    fn test_levels_and_sky() {
+        assert!(!has_sky("nether"));
+        assert!(has_sky("end"));
+        assert_eq!("ULTRA".parse::<OptimizationLevel>().unwrap(), OptimizationLevel::Ultra);
+    }
+}
@@ -0,0 +1,363 @@
+//! Listings
+//!
+//! Sorting, filtering and cursor pagination for list endpoints whose items are
+//! gathered in memory: servers, backups and a server's mods. Items are sorted
+//! by name, creation time or status with the id breaking ties, so a cursor
+//! holding the last item's sort value and id continues a page exactly where
+//! the previous one ended, even if items were added in between.
+//!
+//! v1 clients get a plain array, paged only when they ask for a `limit`.
+//! v2 clients always get a [`Page`] with the total and the next cursor.
+
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::api::{FilterQuery, PaginationQuery, ServerInfo};
+use crate::backup_manager::BackupInfo;
+use crate::database::Mod;
+use crate::middleware::versioning::ApiVersion;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    CreatedAt,
+    Status,
+}
+
+impl SortKey {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "name" => Ok(SortKey::Name),
+            "created_at" => Ok(SortKey::CreatedAt),
+            "status" => Ok(SortKey::Status),
+            other => bail!("Unknown sort key '{}', expected name, created_at or status", other),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::CreatedAt => "created_at",
+            SortKey::Status => "status",
+        }
+    }
+}
+
+/// What sorting and filtering need from a listed item
+pub trait Listable {
+    fn id(&self) -> &str;
+    fn name(&self) -> &str;
+    fn status(&self) -> String;
+    fn created_at(&self) -> Option<DateTime<Utc>>;
+
+    fn sort_value(&self, key: SortKey) -> String {
+        match key {
+            SortKey::Name => self.name().to_lowercase(),
+            // RFC 3339 in UTC with fixed precision sorts like the time itself
+            SortKey::CreatedAt => self
+                .created_at()
+                .map(|at| at.to_rfc3339_opts(SecondsFormat::Nanos, true))
+                .unwrap_or_default(),
+            SortKey::Status => self.status(),
+        }
+    }
+}
+
+/// Checked listing parameters
+#[derive(Debug, Clone)]
+pub struct ListOptions {
+    pub sort: SortKey,
+    pub descending: bool,
+    /// Only items with one of these statuses, when not empty
+    pub statuses: Vec<String>,
+    /// Only items whose name contains this, ignoring case
+    pub search: Option<String>,
+    /// Sort value and id of the last item of the previous page
+    pub after: Option<(String, String)>,
+    /// Items skipped for an offset `page`, when no cursor is given
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl ListOptions {
+    /// Read the query, sorting by `default_sort` unless it names a key.
+    /// Creation time sorts newest first unless an `order` is given.
+    pub fn from_query(pagination: &PaginationQuery, filter: &FilterQuery, default_sort: SortKey) -> Result<Self> {
+        let sort = pagination.sort.as_deref().map(SortKey::parse).transpose()?.unwrap_or(default_sort);
+        let descending = match pagination.order.as_deref() {
+            None => sort == SortKey::CreatedAt,
+            Some("asc") => false,
+            Some("desc") => true,
+            Some(other) => bail!("Unknown order '{}', expected asc or desc", other),
+        };
+        let limit = pagination.limit.map(|limit| (limit as usize).clamp(1, MAX_PAGE_SIZE));
+        let after = pagination.cursor.as_deref().map(|cursor| decode_cursor(cursor, sort)).transpose()?;
+        let offset = match (pagination.page, limit, &after) {
+            (Some(page), Some(limit), None) => (page.max(1) as usize - 1) * limit,
+            _ => 0,
+        };
+        let statuses = filter
+            .status
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|status| status.trim().to_lowercase())
+            .filter(|status| !status.is_empty())
+            .collect();
+        Ok(Self {
+            sort,
+            descending,
+            statuses,
+            search: filter.search.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_lowercase),
+            after,
+            offset,
+            limit,
+        })
+    }
+
+    fn matches<T: Listable>(&self, item: &T) -> bool {
+        (self.statuses.is_empty() || self.statuses.contains(&item.status().to_lowercase()))
+            && self.search.as_ref().is_none_or(|search| item.name().to_lowercase().contains(search))
+    }
+
+    fn compare(&self, a: (&str, &str), b: (&str, &str)) -> Ordering {
+        let ordering = a.cmp(&b);
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// One page of a listing
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Items matching the filters, across all pages
+    pub total: usize,
+    /// Pass as `cursor` for the next page; absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Filter, sort and page `items`. Without a limit the page holds every match.
+pub fn paginate<T: Listable>(items: Vec<T>, options: &ListOptions) -> Page<T> {
+    let mut items: Vec<(String, T)> = items
+        .into_iter()
+        .filter(|item| options.matches(item))
+        .map(|item| (item.sort_value(options.sort), item))
+        .collect();
+    items.sort_by(|(a, x), (b, y)| options.compare((a, x.id()), (b, y.id())));
+    let total = items.len();
+
+    let start = match &options.after {
+        Some((value, id)) => items
+            .iter()
+            .position(|(v, item)| options.compare((v, item.id()), (value, id)) == Ordering::Greater)
+            .unwrap_or(total),
+        None => options.offset.min(total),
+    };
+    let end = options.limit.map_or(total, |limit| (start + limit).min(total));
+    let next_cursor = (end < total && end > start).then(|| {
+        let (value, item) = &items[end - 1];
+        encode_cursor(options.sort, value, item.id())
+    });
+
+    Page {
+        items: items.into_iter().skip(start).take(end - start).map(|(_, item)| item).collect(),
+        total,
+        next_cursor,
+    }
+}
+
+/// A listing in the shape the requested API version expects
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Listing<T> {
+    Items(Vec<T>),
+    Page(Page<T>),
+}
+
+impl<T: Listable> Listing<T> {
+    pub fn new(version: ApiVersion, items: Vec<T>, mut options: ListOptions) -> Self {
+        match version {
+            ApiVersion::V1 => Listing::Items(paginate(items, &options).items),
+            ApiVersion::V2 => {
+                options.limit.get_or_insert(DEFAULT_PAGE_SIZE);
+                Listing::Page(paginate(items, &options))
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    sort: String,
+    value: String,
+    id: String,
+}
+
+fn encode_cursor(sort: SortKey, value: &str, id: &str) -> String {
+    let cursor = Cursor { sort: sort.as_str().to_string(), value: value.to_string(), id: id.to_string() };
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&cursor).unwrap_or_default())
+}
+
+fn decode_cursor(cursor: &str, sort: SortKey) -> Result<(String, String)> {
+    let invalid = || anyhow!("Invalid cursor");
+    let decoded = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+    let cursor: Cursor = serde_json::from_slice(&decoded).map_err(|_| invalid())?;
+    if cursor.sort != sort.as_str() {
+        bail!("Cursor was issued for sorting by {}, not {}", cursor.sort, sort.as_str());
+    }
+    Ok((cursor.value, cursor.id))
+}
+
+impl Listable for ServerInfo {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn status(&self) -> String {
+        self.status.clone()
+    }
+
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
+}
+
+impl Listable for BackupInfo {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn status(&self) -> String {
+        format!("{:?}", self.status).to_lowercase()
+    }
+
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        Some(self.created_at)
+    }
+}
+
+impl Listable for Mod {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.filename
+    }
+
+    fn status(&self) -> String {
+        if self.enabled { "enabled" } else { "disabled" }.to_string()
+    }
+
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        Some(self.created_at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Item(&'static str, &'static str, &'static str);
+
+    impl Listable for Item {
+        fn id(&self) -> &str {
+            self.0
+        }
+        fn name(&self) -> &str {
+            self.1
+        }
+        fn status(&self) -> String {
+            self.2.to_string()
+        }
+        fn created_at(&self) -> Option<DateTime<Utc>> {
+            None
+        }
+    }
+
+    fn items() -> Vec<Item> {
+        vec![
+            Item("1", "Charlie", "running"),
+            Item("2", "alpha", "stopped"),
+            Item("3", "Bravo", "running"),
+            Item("4", "bravo", "crashed"),
+        ]
+    }
+
+    fn query(query: &str) -> (PaginationQuery, FilterQuery) {
+        let (mut pagination, mut filter) = (PaginationQuery::default(), FilterQuery::default());
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            let value = Some(value.to_string());
+            match key {
+                "page" => pagination.page = value.map(|v| v.parse().unwrap()),
+                "limit" => pagination.limit = value.map(|v| v.parse().unwrap()),
+                "cursor" => pagination.cursor = value,
+                "sort" => pagination.sort = value,
+                "order" => pagination.order = value,
+                "status" => filter.status = value,
+                "search" => filter.search = value,
+                _ => panic!("unexpected {}", key),
+            }
+        }
+        (pagination, filter)
+    }
+
+    fn options(q: &str) -> ListOptions {
+        let (pagination, filter) = query(q);
+        ListOptions::from_query(&pagination, &filter, SortKey::Name).unwrap()
+    }
+
+    fn ids(page: &Page<Item>) -> Vec<&str> {
+        page.items.iter().map(|item| item.0).collect()
+    }
+
+    #[test]
+    fn test_sorts_by_name_ignoring_case_with_id_ties() {
+        assert_eq!(ids(&paginate(items(), &options(""))), ["2", "3", "4", "1"]);
+        assert_eq!(ids(&paginate(items(), &options("order=desc"))), ["1", "4", "3", "2"]);
+        assert_eq!(ids(&paginate(items(), &options("sort=status"))), ["4", "1", "3", "2"]);
+    }
+
+    #[test]
+    fn test_cursor_continues_after_last_item() {
+        let first = paginate(items(), &options("limit=3"));
+        assert_eq!((ids(&first), first.total), (vec!["2", "3", "4"], 4));
+        let cursor = first.next_cursor.unwrap();
+        let second = paginate(items(), &options(&format!("limit=3&cursor={}", cursor)));
+        assert_eq!(ids(&second), ["1"]);
+        assert_eq!(second.next_cursor, None);
+
+        let (pagination, filter) = query(&format!("sort=status&cursor={}", cursor));
+        assert!(ListOptions::from_query(&pagination, &filter, SortKey::Name).is_err());
+    }
+
+    #[test]
+    fn test_filters_and_offset_pages() {
+        let page = paginate(items(), &options("status=running,crashed&search=BRAV"));
+        assert_eq!((ids(&page), page.total), (vec!["3", "4"], 2));
+        assert_eq!(ids(&paginate(items(), &options("limit=2&page=2"))), ["4", "1"]);
+
+        let (pagination, filter) = query("sort=size");
+        assert!(ListOptions::from_query(&pagination, &filter, SortKey::Name).is_err());
+    }
+}
@@ -1,7 +1,13 @@
 use crate::core::error_handler::{AppError, Result};
+use crate::metadata_cache;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Fabric loader versions
+pub const LOADER_VERSIONS_URL: &str = "https://meta.fabricmc.net/v2/versions/loader";
+/// Minecraft versions supported by Fabric
+pub const GAME_VERSIONS_URL: &str = "https://meta.fabricmc.net/v2/versions/game";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FabricVersion {
     pub version: String,
@@ -30,71 +36,35 @@ pub struct FabricVersionManifest {
 }
 
 /// Client for interacting with Fabric's version API
-pub struct FabricClient {
-    client: reqwest::Client,
-}
+///
+/// Version lists are read through the metadata cache.
+#[derive(Default)]
+pub struct FabricClient;
 
 impl FabricClient {
     pub fn new() -> Self {
-        Self {
-            client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
-                .build()
-                .expect("Failed to create HTTP client"),
-        }
+        Self
     }
 
     /// Get available Fabric loader versions
     pub async fn get_loader_versions(&self) -> Result<Vec<FabricLoaderVersion>> {
-        let url = "https://meta.fabricmc.net/v2/versions/loader";
-        
-        let response = self.client.get(url).send().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to fetch Fabric loader versions: {}", e),
+        let versions: Vec<FabricLoaderVersion> = metadata_cache::get(LOADER_VERSIONS_URL, metadata_cache::LOADER_TTL).await.map_err(|e| AppError::NetworkError {
+            message: format!("Failed to fetch Fabric loader versions: {:#}", e),
             endpoint: "get_loader_versions".to_string(),
             status_code: None,
         })?;
 
-        if !response.status().is_success() {
-            return Err(AppError::NetworkError {
-                message: format!("Failed to fetch Fabric loader versions: HTTP {}", response.status()),
-                endpoint: "get_loader_versions".to_string(),
-            status_code: None,
-            });
-        }
-
-        let versions: Vec<FabricLoaderVersion> = response.json().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to parse Fabric loader versions: {}", e),
-            endpoint: "parse_loader_versions".to_string(),
-            status_code: None,
-        })?;
-
         Ok(versions)
     }
 
     /// Get available Minecraft versions supported by Fabric
     pub async fn get_game_versions(&self) -> Result<Vec<FabricGameVersion>> {
-        let url = "https://meta.fabricmc.net/v2/versions/game";
-        
-        let response = self.client.get(url).send().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to fetch Fabric game versions: {}", e),
+        let versions: Vec<FabricGameVersion> = metadata_cache::get(GAME_VERSIONS_URL, metadata_cache::LOADER_TTL).await.map_err(|e| AppError::NetworkError {
+            message: format!("Failed to fetch Fabric game versions: {:#}", e),
             endpoint: "get_game_versions".to_string(),
             status_code: None,
         })?;
 
-        if !response.status().is_success() {
-            return Err(AppError::NetworkError {
-                message: format!("Failed to fetch Fabric game versions: HTTP {}", response.status()),
-                endpoint: "get_game_versions".to_string(),
-            status_code: None,
-            });
-        }
-
-        let versions: Vec<FabricGameVersion> = response.json().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to parse Fabric game versions: {}", e),
-            endpoint: "parse_game_versions".to_string(),
-            status_code: None,
-        })?;
-
         Ok(versions)
     }
 
@@ -1,7 +1,11 @@
 use crate::core::error_handler::{AppError, Result};
+use crate::metadata_cache;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Recommended and latest Forge build for each Minecraft version
+pub const PROMOTIONS_URL: &str = "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForgeVersion {
     pub version: String,
@@ -33,44 +37,24 @@ pub struct ForgeInstallerInfo {
 }
 
 /// Client for interacting with Forge's version API
-pub struct ForgeClient {
-    client: reqwest::Client,
-}
+///
+/// Version lists are read through the metadata cache.
+#[derive(Default)]
+pub struct ForgeClient;
 
 impl ForgeClient {
     pub fn new() -> Self {
-        Self {
-            client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
-                .build()
-                .expect("Failed to create HTTP client"),
-        }
+        Self
     }
 
     /// Get available Forge versions for a specific Minecraft version
     pub async fn get_versions_for_minecraft(&self, minecraft_version: &str) -> Result<Vec<ForgeInstallerInfo>> {
-        let url = format!("https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json");
-        
-        let response = self.client.get(&url).send().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to fetch Forge versions: {}", e),
+        let manifest: serde_json::Value = metadata_cache::get(PROMOTIONS_URL, metadata_cache::LOADER_TTL).await.map_err(|e| AppError::NetworkError {
+            message: format!("Failed to fetch Forge versions: {:#}", e),
             endpoint: "get_versions_for_minecraft".to_string(),
             status_code: None,
         })?;
 
-        if !response.status().is_success() {
-            return Err(AppError::NetworkError {
-                message: format!("Failed to fetch Forge versions: HTTP {}", response.status()),
-                endpoint: "get_versions_for_minecraft".to_string(),
-            status_code: None,
-            });
-        }
-
-        let manifest: serde_json::Value = response.json().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to parse Forge versions: {}", e),
-            endpoint: "parse_versions".to_string(),
-            status_code: None,
-        })?;
-
         // Parse the Forge promotions JSON
         let mut versions = Vec::new();
         
@@ -119,40 +119,7 @@ impl LoaderInstaller {
         
         info!("Downloading Fabric installer from: {}", installer_url);
         
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(60))
-            .build()
-            .map_err(|e| AppError::NetworkError {
-                message: format!("Failed to create HTTP client: {}", e),
-                endpoint: "create_client".to_string(),
-                status_code: None,
-            })?;
-
-        let response = client.get(&installer_url).send().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to download Fabric installer: {}", e),
-            endpoint: "download_installer".to_string(),
-            status_code: None,
-        })?;
-
-        if !response.status().is_success() {
-            return Err(AppError::NetworkError {
-                message: format!("Failed to download Fabric installer: HTTP {}", response.status()),
-                endpoint: "download_installer".to_string(),
-            status_code: None,
-            });
-        }
-
-        let bytes = response.bytes().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to read installer response: {}", e),
-                endpoint: "read_response".to_string(),
-                status_code: None,
-        })?;
-
-        fs::write(&installer_path, bytes).await.map_err(|e| AppError::FileSystemError {
-            message: format!("Failed to write installer JAR: {}", e),
-            path: installer_path.to_string_lossy().to_string(),
-            operation: "write".to_string(),
-        })?;
+        download_installer("Fabric", &installer_url, &installer_path).await?;
 
         info!("Fabric installer downloaded to: {}", installer_path.display());
         Ok(installer_path)
@@ -169,40 +136,7 @@ impl LoaderInstaller {
         
         info!("Downloading Quilt installer from: {}", installer_url);
         
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(60))
-            .build()
-            .map_err(|e| AppError::NetworkError {
-                message: format!("Failed to create HTTP client: {}", e),
-                endpoint: "create_client".to_string(),
-                status_code: None,
-            })?;
-
-        let response = client.get(&installer_url).send().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to download Quilt installer: {}", e),
-            endpoint: "download_installer".to_string(),
-            status_code: None,
-        })?;
-
-        if !response.status().is_success() {
-            return Err(AppError::NetworkError {
-                message: format!("Failed to download Quilt installer: HTTP {}", response.status()),
-                endpoint: "download_installer".to_string(),
-            status_code: None,
-            });
-        }
-
-        let bytes = response.bytes().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to read installer response: {}", e),
-                endpoint: "read_response".to_string(),
-                status_code: None,
-        })?;
-
-        fs::write(&installer_path, bytes).await.map_err(|e| AppError::FileSystemError {
-            message: format!("Failed to write installer JAR: {}", e),
-            path: installer_path.to_string_lossy().to_string(),
-            operation: "write".to_string(),
-        })?;
+        download_installer("Quilt", &installer_url, &installer_path).await?;
 
         info!("Quilt installer downloaded to: {}", installer_path.display());
         Ok(installer_path)
@@ -327,40 +261,7 @@ impl LoaderInstaller {
         
         info!("Downloading Forge installer from: {}", installer_url);
         
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(60))
-            .build()
-            .map_err(|e| AppError::NetworkError {
-                message: format!("Failed to create HTTP client: {}", e),
-                endpoint: "create_client".to_string(),
-                status_code: None,
-            })?;
-
-        let response = client.get(&installer_url).send().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to download Forge installer: {}", e),
-            endpoint: "download_installer".to_string(),
-            status_code: None,
-        })?;
-
-        if !response.status().is_success() {
-            return Err(AppError::NetworkError {
-                message: format!("Failed to download Forge installer: HTTP {}", response.status()),
-                endpoint: "download_installer".to_string(),
-            status_code: None,
-            });
-        }
-
-        let bytes = response.bytes().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to read installer response: {}", e),
-                endpoint: "read_response".to_string(),
-                status_code: None,
-        })?;
-
-        fs::write(&installer_path, bytes).await.map_err(|e| AppError::FileSystemError {
-            message: format!("Failed to write installer JAR: {}", e),
-            path: installer_path.to_string_lossy().to_string(),
-            operation: "write".to_string(),
-        })?;
+        download_installer("Forge", &installer_url, &installer_path).await?;
 
         info!("Forge installer downloaded to: {}", installer_path.display());
         Ok(installer_path)
@@ -464,3 +365,15 @@ impl LoaderInstaller {
         })
     }
 }
+
+/// Download an installer JAR through the shared download manager
+async fn download_installer(name: &str, url: &str, path: &Path) -> Result<()> {
+    crate::downloads::shared()
+        .fetch_to(&crate::downloads::Download::new(url), path)
+        .await
+        .map_err(|e| AppError::NetworkError {
+            message: format!("Failed to download {} installer: {:#}", name, e),
+            endpoint: url.to_string(),
+            status_code: None,
+        })
+}
@@ -1,7 +1,13 @@
 use crate::core::error_handler::{AppError, Result};
+use crate::metadata_cache;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Quilt loader versions
+pub const LOADER_VERSIONS_URL: &str = "https://meta.quiltmc.org/v3/versions/loader";
+/// Minecraft versions supported by Quilt
+pub const GAME_VERSIONS_URL: &str = "https://meta.quiltmc.org/v3/versions/game";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuiltVersion {
     pub version: String,
@@ -28,71 +34,35 @@ pub struct QuiltVersionManifest {
 }
 
 /// Client for interacting with Quilt's version API
-pub struct QuiltClient {
-    client: reqwest::Client,
-}
+///
+/// Version lists are read through the metadata cache.
+#[derive(Default)]
+pub struct QuiltClient;
 
 impl QuiltClient {
     pub fn new() -> Self {
-        Self {
-            client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
-                .build()
-                .expect("Failed to create HTTP client"),
-        }
+        Self
     }
 
     /// Get available Quilt loader versions
     pub async fn get_loader_versions(&self) -> Result<Vec<QuiltLoaderVersion>> {
-        let url = "https://meta.quiltmc.org/v3/versions/loader";
-        
-        let response = self.client.get(url).send().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to fetch Quilt loader versions: {}", e),
+        let versions: Vec<QuiltLoaderVersion> = metadata_cache::get(LOADER_VERSIONS_URL, metadata_cache::LOADER_TTL).await.map_err(|e| AppError::NetworkError {
+            message: format!("Failed to fetch Quilt loader versions: {:#}", e),
             endpoint: "get_loader_versions".to_string(),
             status_code: None,
         })?;
 
-        if !response.status().is_success() {
-            return Err(AppError::NetworkError {
-                message: format!("Failed to fetch Quilt loader versions: HTTP {}", response.status()),
-                endpoint: "get_loader_versions".to_string(),
-            status_code: None,
-            });
-        }
-
-        let versions: Vec<QuiltLoaderVersion> = response.json().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to parse Quilt loader versions: {}", e),
-            endpoint: "parse_loader_versions".to_string(),
-            status_code: None,
-        })?;
-
         Ok(versions)
     }
 
     /// Get available Minecraft versions supported by Quilt
     pub async fn get_game_versions(&self) -> Result<Vec<QuiltGameVersion>> {
-        let url = "https://meta.quiltmc.org/v3/versions/game";
-        
-        let response = self.client.get(url).send().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to fetch Quilt game versions: {}", e),
+        let versions: Vec<QuiltGameVersion> = metadata_cache::get(GAME_VERSIONS_URL, metadata_cache::LOADER_TTL).await.map_err(|e| AppError::NetworkError {
+            message: format!("Failed to fetch Quilt game versions: {:#}", e),
             endpoint: "get_game_versions".to_string(),
             status_code: None,
         })?;
 
-        if !response.status().is_success() {
-            return Err(AppError::NetworkError {
-                message: format!("Failed to fetch Quilt game versions: HTTP {}", response.status()),
-                endpoint: "get_game_versions".to_string(),
-            status_code: None,
-            });
-        }
-
-        let versions: Vec<QuiltGameVersion> = response.json().await.map_err(|e| AppError::NetworkError {
-            message: format!("Failed to parse Quilt game versions: {}", e),
-            endpoint: "parse_game_versions".to_string(),
-            status_code: None,
-        })?;
-
         Ok(versions)
     }
 
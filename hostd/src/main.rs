@@ -2,8 +2,9 @@ use axum::{
     routing::get,
     Router,
 };
-use tower_http::cors::{CorsLayer, Any};
 use std::sync::Arc;
+use tower::Layer;
+use clap::{Parser, Subcommand};
 
 use hostd::core::{
     app_state::AppState,
@@ -13,6 +14,8 @@ use hostd::core::{
     crash_watchdog::{CrashWatchdog, WatchdogConfig},
     monitoring::MonitoringManager,
     auth::AuthManager,
+    middleware::{auth_middleware, AuthState},
+    audit::audit_middleware,
     shutdown::{ShutdownManager, AppShutdownHandler, setup_signal_handlers},
     error_handler::{AppError, Result},
     logging::{initialize_logging, LogConfig, LogFormat, LogOutput},
@@ -23,23 +26,62 @@ use hostd::api::{create_api_router, AppState as ApiAppState};
 use hostd::routes::auth::auth_routes;
 use hostd::websocket_manager::WebSocketManager;
 use hostd::gpu_manager::GpuManager;
-use axum::{
-    extract::{
-        ws::WebSocketUpgrade,
-        State,
+
+#[derive(Parser)]
+#[command(about = "Guardian Server Manager host daemon")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Start hostd on boot as a Windows service or systemd unit, running from the current directory
+    InstallService,
+    /// Stop and remove the hostd service
+    UninstallService,
+    /// Started by the Windows service manager
+    #[command(hide = true)]
+    RunService {
+        #[arg(long)]
+        working_dir: std::path::PathBuf,
     },
-    response::Response,
-};
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        None => run(),
+        Some(Command::InstallService) => exit_on_error(hostd::service::install()),
+        Some(Command::UninstallService) => exit_on_error(hostd::service::uninstall()),
+        #[cfg(windows)]
+        Some(Command::RunService { working_dir }) => {
+            exit_on_error(hostd::service::run(&working_dir, || run().map_err(anyhow::Error::from)))
+        }
+        #[cfg(not(windows))]
+        Some(Command::RunService { .. }) => run(),
+    }
+}
+
+/// Report a failed service command as a plain message, not an AppError dump
+fn exit_on_error(result: anyhow::Result<()>) -> Result<()> {
+    if let Err(e) = result {
+        eprintln!("Error: {:#}", e);
+        std::process::exit(1);
+    }
+    Ok(())
+}
 
-async fn handle_websocket(
-    ws: WebSocketUpgrade,
-    State(manager): State<Arc<WebSocketManager>>,
-) -> Response {
-    ws.on_upgrade(|socket| manager.handle_socket(socket))
+fn run() -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| AppError::InternalError {
+            message: format!("Failed to start the async runtime: {}", e),
+            component: "runtime".to_string(),
+            details: None,
+        })?;
+    runtime.block_on(serve())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+async fn serve() -> Result<()> {
     // Load Guardian configuration
     let guardian_config = GuardianConfig::load()
         .map_err(|e| AppError::ConfigurationError {
@@ -55,6 +97,7 @@ async fn main() -> Result<()> {
             config_key: "guardian_config".to_string(),
             expected_type: "GuardianConfig".to_string(),
         })?;
+    hostd::downloads::init(guardian_config.data_dir.join("downloads"));
 
     // Initialize comprehensive logging system
     let log_config = LogConfig {
@@ -126,36 +169,49 @@ async fn main() -> Result<()> {
     let gpu_manager = Arc::new(tokio::sync::Mutex::new(GpuManager::new(guardian_config.clone()).await.map_err(|e| AppError::InternalError { message: e, component: "gpu_manager".to_string(), details: None })?));
     tracing::info!("GPU manager initialized");
 
+    // Start periodic GPU metrics logging
+    {
+        let gpu_manager_clone = gpu_manager.clone();
+        let gpu_manager_guard = gpu_manager_clone.lock().await;
+        gpu_manager_guard.start_metrics_logging().await;
+    }
+    tracing::info!("GPU metrics logging started");
+
+    // RCON passwords and API keys go to the OS keychain when there is one
+    let secret_storage = Arc::new(hostd::security::secret_storage::SecretStorage::with_keychain(
+        hostd::security::secret_storage::KEYCHAIN_SERVICE,
+    ).await);
+
+    // Create the database manager first
+    let database = hostd::database::DatabaseManager::new(&guardian_config.database_url).await?
+        .with_secret_storage(secret_storage.clone());
+    
+    database.migrate_secrets_to_keychain().await?;
+    hostd::metadata_cache::init(database.clone(), &cache_manager).await;
+    hostd::version_catalog::spawn_sync();
+
     // Initialize performance telemetry
-    let performance_telemetry = Arc::new(hostd::performance_telemetry::PerformanceTelemetry::new(
-        std::time::Duration::from_secs(30) // Collect metrics every 30 seconds
-    ));
+    let performance_telemetry = Arc::new(
+        hostd::performance_telemetry::PerformanceTelemetry::new(
+            std::time::Duration::from_secs(guardian_config.metrics_interval_secs.max(1))
+        )
+        .with_database(
+            Arc::new(database.clone()),
+            hostd::performance_telemetry::MetricsRetention::with_days(guardian_config.metrics_retention_days),
+        ),
+    );
     tracing::info!("Performance telemetry initialized");
 
     // Start performance telemetry collection
     {
         let performance_telemetry_clone = performance_telemetry.clone();
-        let servers_path = std::path::PathBuf::from("servers");
+        let servers_path = guardian_config.servers_dir.clone();
         tokio::spawn(async move {
             if let Err(e) = performance_telemetry_clone.start_collection(&servers_path).await {
                 tracing::error!("Failed to start performance telemetry collection: {}", e);
             }
         });
     }
-    
-    // Start periodic GPU metrics logging
-    {
-        let gpu_manager_clone = gpu_manager.clone();
-        let gpu_manager_guard = gpu_manager_clone.lock().await;
-        gpu_manager_guard.start_metrics_logging().await;
-    }
-    tracing::info!("GPU metrics logging started");
-
-    // Create the database manager first
-    let database = hostd::database::DatabaseManager::new(&guardian_config.database_url).await?;
-    
-    // Run database migrations to ensure tables exist
-    database.run_migrations().await?;
 
     // Initialize monitoring manager
     let monitoring_config = hostd::core::config::MonitoringConfig {
@@ -167,23 +223,37 @@ async fn main() -> Result<()> {
     };
     let monitoring_manager = Arc::new(MonitoringManager::new(&monitoring_config)?);
 
+    // Live updates for WebSocket and SSE clients all go through one bus
+    let event_bus = hostd::event_bus::EventBus::new();
+
     // Initialize WebSocket manager
-    let websocket_manager = Arc::new(hostd::websocket_manager::WebSocketManager::new());
+    let websocket_manager = Arc::new(hostd::websocket_manager::WebSocketManager::with_event_bus(event_bus.clone()));
 
     // Create credential manager
     let credential_manager = Arc::new(hostd::core::credential_manager::CredentialManager::new());
 
+    // Process manager shared by the API and the crash watchdog, so the watchdog sees
+    // the servers the API starts
+    let api_websocket_manager = Arc::new(WebSocketManager::with_event_bus(event_bus.clone()));
+
     // Initialize crash watchdog
     let watchdog_config = WatchdogConfig::default();
-    let mut process_manager = hostd::core::process_manager::ProcessManager::new(websocket_manager.clone(), credential_manager.clone());
+    let mut process_manager = hostd::core::process_manager::ProcessManager::new(api_websocket_manager.clone(), credential_manager.clone());
     process_manager.set_database(Arc::new(database.clone()));
     let process_manager = Arc::new(process_manager);
+
+    // Servers still running from before a hostd restart are picked up, not reported stopped
+    match process_manager.reattach_servers().await {
+        Ok(servers) if !servers.is_empty() => tracing::info!("Re-attached to {} running servers", servers.len()),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to re-attach to running servers: {}", e),
+    }
     let crash_watchdog = Arc::new(CrashWatchdog::new(
         watchdog_config,
-        process_manager,
+        process_manager.clone(),
         monitoring_manager,
         Arc::new(database.clone()),
-    ));
+    ).with_websocket_manager(api_websocket_manager.clone()));
 
     // Start crash watchdog in background
     let crash_watchdog_clone = crash_watchdog.clone();
@@ -193,8 +263,40 @@ async fn main() -> Result<()> {
         }
     });
 
+    tokio::spawn(crash_watchdog.clone().run_restart_policies());
+
     tracing::info!("Crash watchdog initialized");
 
+    // Keep world heatmaps fresh so the dashboard rarely waits on a scan
+    {
+        let database = database.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(hostd::world::heatmap::MAX_AGE);
+            loop {
+                interval.tick().await;
+                let servers = match database.get_all_servers().await {
+                    Ok(servers) => servers,
+                    Err(e) => {
+                        tracing::error!("Failed to list servers for heatmap refresh: {}", e);
+                        continue;
+                    }
+                };
+                for server in servers {
+                    let world = hostd::world::server_world_dir(&server);
+                    for dimension in ["overworld", "nether", "end"] {
+                        let has_regions = hostd::world::region_dir(dimension).is_some_and(|dir| world.join(dir).is_dir());
+                        if !has_regions {
+                            continue;
+                        }
+                        if let Err(e) = hostd::world::heatmap::refresh(&database, &server, dimension).await {
+                            tracing::warn!("Failed to refresh {} heatmap for server {}: {}", dimension, server.id, e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
     // Load configuration
     let config = Config::load()
         .map_err(|e| AppError::ConfigurationError {
@@ -204,7 +306,17 @@ async fn main() -> Result<()> {
         })?;
 
     // Create authentication manager
-    let auth_manager = Arc::new(AuthManager::new("your-secret-key-here".to_string(), credential_manager.clone()));
+    let jwt_secret = hostd::core::auth::load_jwt_secret(&config.security.jwt_secret, &guardian_config.data_dir)
+        .map_err(|e| AppError::ConfigurationError {
+            message: format!("Failed to load JWT secret: {}", e),
+            config_key: "JWT_SECRET".to_string(),
+            expected_type: "String".to_string(),
+        })?;
+    let auth_manager = Arc::new(
+        AuthManager::new(jwt_secret, credential_manager.clone())
+            .with_token_expiry(std::time::Duration::from_secs(config.security.token_expiry))
+            .with_database(Arc::new(database.clone())),
+    );
     auth_manager.initialize().await
         .map_err(|e| AppError::ConfigurationError {
             message: format!("Failed to initialize auth: {}", e),
@@ -216,7 +328,7 @@ async fn main() -> Result<()> {
     let app_state = Arc::new(AppState::new(config, auth_manager.clone(), resource_monitor.clone(), crash_watchdog.clone()).await?);
 
     // Create shutdown manager
-    let shutdown_manager = Arc::new(ShutdownManager::new(std::time::Duration::from_secs(30)));
+    let shutdown_manager = Arc::new(ShutdownManager::new(std::time::Duration::from_secs(60)));
     
     // Set up signal handlers
     setup_signal_handlers(shutdown_manager.clone()).await?;
@@ -266,13 +378,143 @@ async fn main() -> Result<()> {
     ));
     
     // Create the API app state for the comprehensive router
-    let api_websocket_manager = Arc::new(WebSocketManager::new());
-    let mut process_manager = hostd::core::process_manager::ProcessManager::new(api_websocket_manager.clone(), app_state.credential_manager.clone());
-    process_manager.set_database(Arc::new(database.clone()));
-    let process_manager = Arc::new(process_manager);
+    let jobs = Arc::new(hostd::jobs::JobManager::new(
+        Arc::new(database.clone()),
+        api_websocket_manager.clone(),
+    ));
+    let lighting_manager = Arc::new(hostd::lighting::LightingManager::new(
+        Arc::new(database.clone()),
+        api_websocket_manager.clone(),
+        process_manager.clone(),
+        jobs.clone(),
+    ));
+    let pregeneration_manager = Arc::new(hostd::pregeneration::PregenerationManager::new(
+        Arc::new(database.clone()),
+        api_websocket_manager.clone(),
+        process_manager.clone(),
+        jobs.clone(),
+    ));
+    let world_border = Arc::new(hostd::world_border::WorldBorderManager::new(
+        Arc::new(database.clone()),
+        process_manager.clone(),
+        pregeneration_manager.clone(),
+    ));
+    tokio::spawn(world_border.clone().start());
+    let hot_import_manager = Arc::new(hostd::hot_import::HotImportManager::new(
+        Arc::new(database.clone()),
+        api_websocket_manager.clone(),
+        process_manager.clone(),
+        jobs.clone(),
+    ));
+    let restart_scheduler = Arc::new(hostd::restart_scheduler::RestartScheduler::new(
+        Arc::new(database.clone()),
+        process_manager.clone(),
+    ));
+    tokio::spawn(restart_scheduler.clone().start());
+    let ban_manager = Arc::new(hostd::bans::BanManager::new(
+        Arc::new(database.clone()),
+        process_manager.clone(),
+    ));
+    tokio::spawn(ban_manager.clone().start());
+    let datapack_manager = Arc::new(hostd::datapacks::DatapackManager::new(
+        Arc::new(database.clone()),
+        process_manager.clone(),
+    ));
+    let resource_packs = Arc::new(hostd::resource_packs::ResourcePackHost::new(
+        Arc::new(database.clone()),
+        Arc::new(guardian_config.clone()),
+    ));
+    let external_monitor = Arc::new(hostd::external_servers::ExternalServerMonitor::new(Arc::new(database.clone())));
+    tokio::spawn(external_monitor.clone().start());
+    let alert_manager = Arc::new(hostd::alerts::AlertManager::new(
+        Arc::new(database.clone()),
+        guardian_config.servers_dir.clone(),
+    ));
+    tokio::spawn(alert_manager.clone().start());
+    let webhook_manager = Arc::new(hostd::webhook::WebhookManager::new(Arc::new(database.clone())));
+    tokio::spawn(webhook_manager.clone().start());
+    let template_manager = Arc::new(hostd::server_templates::TemplateManager::new(
+        Arc::new(database.clone()),
+        guardian_config.data_dir.join("templates"),
+        guardian_config.servers_dir.clone(),
+    ));
+    let port_forwarder = Arc::new(hostd::port_forwarding::PortForwarder::new(
+        Arc::new(database.clone()),
+        process_manager.clone(),
+    ));
+    tokio::spawn(port_forwarder.clone().start());
+    let tunnel_manager = Arc::new(hostd::tunnels::TunnelManager::new(
+        Arc::new(database.clone()),
+        process_manager.clone(),
+    ));
+    tokio::spawn(tunnel_manager.clone().start());
+    tokio::spawn(Arc::new(hostd::events::EventRetention::new(Arc::new(database.clone()))).start());
+    let mod_drift = Arc::new(hostd::mod_drift::ModDriftMonitor::new(Arc::new(database.clone())));
+    tokio::spawn(mod_drift.clone().start());
+    let mod_bisector = Arc::new(hostd::mod_bisect::ModBisector::new(Arc::new(database.clone()), process_manager.clone()));
+    let sharding = Arc::new(hostd::sharding::ShardingManager::new(Arc::new(database.clone()), process_manager.clone()));
+    let node_manager = Arc::new(hostd::nodes::NodeManager::new(Arc::new(database.clone())));
+    tokio::spawn(node_manager.clone().start());
+    let minecraft_manager = hostd::minecraft::MinecraftManager::new(database.clone());
+    let discord = Arc::new(hostd::discord::DiscordManager::new(Arc::new(database.clone()), minecraft_manager.clone()));
+    tokio::spawn(discord.clone().start());
+    let chat_bridge = Arc::new(hostd::chat_bridge::ChatBridgeManager::new(
+        Arc::new(database.clone()),
+        discord.clone(),
+        event_bus.clone(),
+    ));
+    tokio::spawn(chat_bridge.clone().start());
+    let server_migrator = Arc::new(hostd::server_migration::ServerMigrator::new(
+        Arc::new(database.clone()),
+        process_manager.clone(),
+        minecraft_manager.clone(),
+        node_manager.clone(),
+        jobs.clone(),
+        guardian_config.servers_dir.clone(),
+    ));
+    let freeze_detector = Arc::new(hostd::freeze_tickets::FreezeDetector::new(
+        Arc::new(database.clone()),
+        process_manager.clone(),
+        api_websocket_manager.clone(),
+    ));
+    tokio::spawn(freeze_detector.clone().start());
+    let diagnostics = Arc::new(hostd::diagnostics::DiagnosticsManager::new(
+        Arc::new(database.clone()),
+        process_manager.clone(),
+        jobs.clone(),
+        guardian_config.diagnostics_quota_mb,
+    ));
+    let server_groups = Arc::new(hostd::server_groups::ServerGroupManager::new(Arc::new(database.clone())));
+    let java_runtimes = Arc::new(hostd::java_runtimes::JavaRuntimeManager::new(Arc::new(database.clone())));
+    let compat_rules = Arc::new(hostd::compat_rules::CompatRules::load(
+        guardian_config.data_dir.join("compat_rules.json"),
+        guardian_config.compat_rules_url.clone(),
+    ));
+    tokio::spawn(compat_rules.clone().start(std::time::Duration::from_secs(
+        guardian_config.compat_rules_refresh_hours.max(1) * 3600,
+    )));
+    if guardian_config.backup_test_restore_hours > 0 {
+        let backup_manager = Arc::new(
+            hostd::backup_manager::BackupManager::new(guardian_config.backups_dir.clone(), guardian_config.servers_dir.clone())
+                .with_database(Arc::new(database.clone())),
+        );
+        tokio::spawn(backup_manager.start_test_restores(std::time::Duration::from_secs(
+            guardian_config.backup_test_restore_hours * 3600,
+        )));
+    }
+    // Servers with auto_start come up in order, now that running ones are re-attached
+    let auto_start = Arc::new(hostd::auto_start::AutoStartSequencer::new(
+        Arc::new(database.clone()),
+        process_manager.clone(),
+        port_forwarder.clone(),
+        tunnel_manager.clone(),
+    ));
+    if let Err(e) = auto_start.clone().start().await {
+        tracing::warn!("Failed to auto-start servers: {}", e);
+    }
     let api_app_state = ApiAppState {
         websocket_manager: api_websocket_manager,
-        minecraft_manager: hostd::minecraft::MinecraftManager::new(database.clone()),
+        minecraft_manager,
         database: Arc::new(database.clone()),
         mod_manager: hostd::mod_manager::ModManager::new(std::path::PathBuf::from("mods")),
         resource_monitor: resource_monitor.clone(),
@@ -280,7 +522,39 @@ async fn main() -> Result<()> {
         test_harness: test_harness.clone(),
         gpu_manager: gpu_manager.clone(),
         performance_telemetry: performance_telemetry.clone(),
-        sse_sender: None,
+        lighting_manager,
+        pregeneration_manager,
+        world_border,
+        hot_import_manager,
+        restart_scheduler,
+        ban_manager,
+        datapack_manager,
+        resource_packs,
+        console_commands: Arc::new(hostd::console_commands::ConsoleCommands::new()),
+        player_profiles: Arc::new(hostd::player_profiles::PlayerProfileService::new()),
+        external_monitor,
+        alert_manager,
+        webhook_manager,
+        discord,
+        chat_bridge,
+        template_manager,
+        port_forwarder: port_forwarder.clone(),
+        tunnel_manager: tunnel_manager.clone(),
+        java_runtimes,
+        auto_start,
+        shutdown_manager: shutdown_manager.clone(),
+        jobs,
+        compat_rules,
+        mod_drift,
+        mod_bisector,
+        sharding,
+        node_manager: node_manager.clone(),
+        server_migrator,
+        freeze_detector,
+        diagnostics,
+        server_groups,
+        event_bus,
+        guardian_config: Arc::new(guardian_config.clone()),
         process_manager: process_manager.clone(),
         server_manager: Arc::new(hostd::core::server_manager::ServerManager::new(
             Arc::new(database.clone()),
@@ -299,7 +573,7 @@ async fn main() -> Result<()> {
             process_manager.clone(),
             app_state.port_registry.clone(),
         )),
-        secret_storage: Arc::new(hostd::security::secret_storage::SecretStorage::new()),
+        secret_storage,
         rate_limiter: Arc::new(hostd::security::rate_limiting::RateLimiter::new(hostd::security::rate_limiting::RateLimitConfig::default())),
     };
     
@@ -307,22 +581,39 @@ async fn main() -> Result<()> {
     let auth_router = auth_routes().with_state(app_state.clone());
     let api_router = create_api_router(api_app_state.clone());
     
-    let app = Router::new()
-        .route("/", get(|| async { "Guardian Server Manager API" }))
+    // Every /api route requires a JWT or API token except login, refresh and
+    // health checks; outside remote mode the local app is trusted without one.
+    // The audit layer wraps auth so rejected requests are recorded too.
+    let auth_state = AuthState::new(auth_manager.clone(), &guardian_config);
+    // Requests for servers on remote nodes are forwarded once authorized.
+    let api_routes = Router::new()
         .merge(api_router)
         .nest("/api/auth", auth_router)
-        .route("/ws", get(handle_websocket).with_state(api_app_state.websocket_manager.clone()))
-        .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any)
-        );
+        .layer(axum::middleware::from_fn_with_state(node_manager, hostd::nodes::proxy_middleware))
+        .layer(axum::middleware::from_fn_with_state(auth_state.clone(), auth_middleware))
+        .layer(axum::middleware::from_fn_with_state(api_app_state.database.clone(), audit_middleware));
+    // /api/v1 and /api/v2 paths are rewritten to their handlers before routing,
+    // so the version layer wraps the router rather than being one of its layers.
+    let api_routes = axum::middleware::from_fn(hostd::middleware::versioning::version_middleware).layer(api_routes);
+
+    let app = Router::new()
+        .route("/", get(|| async { "Guardian Server Manager API" }))
+        .route("/ws", get(WebSocketManager::handle_websocket).with_state((api_app_state.websocket_manager.clone(), auth_state)))
+        .fallback_service(api_routes)
+        .layer(hostd::core::remote::cors_layer(&guardian_config));
 
     // Get the server address
     let addr = guardian_config.server_address();
     
-    tracing::info!("Guardian Server Manager listening on {}", addr);
+    // Resolve certificates before binding so a bad TLS setup fails fast
+    let tls_config = if guardian_config.tls_enabled {
+        let files = hostd::core::tls::resolve_tls_files(&guardian_config)?;
+        Some(hostd::core::tls::load_rustls_config(&files).await?)
+    } else {
+        None
+    };
+    
+    tracing::info!("Guardian Server Manager listening on {}://{}", guardian_config.scheme(), addr);
 
     // Create shutdown handler
     let shutdown_handler = AppShutdownHandler::new(
@@ -331,9 +622,10 @@ async fn main() -> Result<()> {
         websocket_manager.clone(),
         crash_watchdog.clone(),
         app_state.port_registry.clone(),
-        credential_manager.clone(),
         Arc::new(database.clone()),
-    );
+    )
+    .with_telemetry(performance_telemetry.clone())
+    .with_networking(port_forwarder, tunnel_manager);
 
     // Start the server with shutdown handling
     let listener = tokio::net::TcpListener::bind(&addr).await
@@ -346,14 +638,22 @@ async fn main() -> Result<()> {
     // Create a shutdown receiver for the server
     let mut shutdown_rx = shutdown_manager.subscribe();
     
-    // Start the server in a task
+    // Start the server in a task; with TLS the same routes serve HTTPS and WSS
     let server_handle = tokio::spawn(async move {
-        axum::serve(listener, app).await
-            .map_err(|e| AppError::NetworkError {
-                message: format!("Server error: {}", e),
-                endpoint: addr.to_string(),
-                status_code: None,
-            })
+        let result = match tls_config {
+            Some(tls_config) => match listener.into_std() {
+                Ok(listener) => axum_server::from_tcp_rustls(listener, tls_config)
+                    .serve(app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+                    .await,
+                Err(e) => Err(e),
+            },
+            None => axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await,
+        };
+        result.map_err(|e| AppError::NetworkError {
+            message: format!("Server error: {}", e),
+            endpoint: addr.to_string(),
+            status_code: None,
+        })
     });
 
     // Wait for shutdown signal
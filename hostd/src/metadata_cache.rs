@@ -0,0 +1,382 @@
+//! Cache for version and project metadata
+//!
+//! Mojang's version manifest, Fabric/Quilt/Forge version lists and Modrinth
+//! project metadata are read through here instead of from the network on
+//! every call. Responses are kept in memory and in SQLite: an entry younger
+//! than its TTL is served as is, an older one is revalidated with the ETag or
+//! Last-Modified date the provider sent, and while the provider can't be
+//! reached an older entry is still served for up to [`MAX_STALE_DAYS`], so
+//! creating a server keeps working through short outages. With the
+//! `offline_mode` setting on, the providers are never contacted and stored
+//! entries are served however old they are.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::core::caching::{Cache, CacheConfig, CacheManager, EvictionPolicy};
+use crate::database::{CachedResponse, DatabaseManager};
+use crate::external_apis::rate_limit;
+
+/// Mojang version manifests
+pub const MOJANG_TTL: Duration = Duration::from_secs(10 * 60);
+/// Loader version lists
+pub const LOADER_TTL: Duration = Duration::from_secs(30 * 60);
+/// Mod project and version metadata
+pub const PROJECT_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// How old an entry may be and still be served when the provider is down
+pub const MAX_STALE_DAYS: i64 = 7;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How current a response is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Freshness {
+    /// When the provider last sent or confirmed the data
+    pub fetched_at: DateTime<Utc>,
+    /// Older than its TTL, because the provider couldn't be reached or
+    /// offline mode is on
+    pub stale: bool,
+    pub offline: bool,
+}
+
+#[derive(Clone)]
+struct Entry {
+    value: serde_json::Value,
+    fetched_at: DateTime<Utc>,
+}
+
+pub struct MetadataCache {
+    database: DatabaseManager,
+    memory: Arc<Cache<String, Entry>>,
+    client: reqwest::Client,
+}
+
+static SHARED: OnceLock<MetadataCache> = OnceLock::new();
+
+/// Set up the shared cache; until then metadata is fetched without caching
+pub async fn init(database: DatabaseManager, cache_manager: &CacheManager) {
+    let memory = cache_manager.create_cache::<String, Entry>("metadata".to_string(), memory_config()).await;
+    if SHARED.set(MetadataCache { database, memory, client: client() }).is_err() {
+        warn!("The metadata cache was already set up");
+    }
+}
+
+/// A JSON document from a metadata API, cached for `ttl`
+pub async fn get_json(url: &str, ttl: Duration) -> Result<serde_json::Value> {
+    Ok(get_json_fresh(url, ttl).await?.0)
+}
+
+/// Like [`get_json`], deserialized
+pub async fn get<T: DeserializeOwned>(url: &str, ttl: Duration) -> Result<T> {
+    Ok(serde_json::from_value(get_json(url, ttl).await?)?)
+}
+
+/// Like [`get_json`], with how current the document is
+pub async fn get_json_fresh(url: &str, ttl: Duration) -> Result<(serde_json::Value, Freshness)> {
+    match SHARED.get() {
+        Some(cache) => cache.load(url, ttl, false).await,
+        None => match fetch(&client(), url, None).await? {
+            Fetched::Modified(response) => {
+                let freshness = Freshness { fetched_at: response.fetched_at, stale: false, offline: false };
+                Ok((serde_json::from_str(&response.body)?, freshness))
+            }
+            Fetched::NotModified => Err(anyhow!("{} was not modified, but nothing was cached", url)),
+        },
+    }
+}
+
+/// Revalidate `url` now, whatever its age
+pub async fn refresh(url: &str) -> Result<Freshness> {
+    let cache = SHARED.get().ok_or_else(|| anyhow!("The metadata cache is not set up"))?;
+    Ok(cache.load(url, Duration::ZERO, true).await?.1)
+}
+
+/// When `url` was last fetched, if it is stored
+pub async fn fetched_at(url: &str) -> Result<Option<DateTime<Utc>>> {
+    match SHARED.get() {
+        Some(cache) => Ok(cache.database.get_cached_response(url).await?.map(|response| response.fetched_at)),
+        None => Ok(None),
+    }
+}
+
+/// Whether the `offline_mode` setting is on
+pub async fn offline_mode() -> bool {
+    match SHARED.get() {
+        Some(cache) => cache.offline_mode().await,
+        None => false,
+    }
+}
+
+fn memory_config() -> CacheConfig {
+    CacheConfig {
+        max_size: 500,
+        default_ttl: Some(PROJECT_TTL),
+        eviction_policy: EvictionPolicy::LRU,
+        cleanup_interval: Duration::from_secs(60),
+        enable_metrics: true,
+    }
+}
+
+fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("Guardian/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap_or_default()
+}
+
+enum Fetched {
+    Modified(CachedResponse),
+    NotModified,
+}
+
+/// Request `url`, conditionally when a cached response is given
+async fn fetch(client: &reqwest::Client, url: &str, cached: Option<&CachedResponse>) -> Result<Fetched> {
+    let mut request = client.get(url);
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let response = rate_limit::send(request).await?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED && cached.is_some() {
+        return Ok(Fetched::NotModified);
+    }
+    let response = response.error_for_status()?;
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
+    Ok(Fetched::Modified(CachedResponse {
+        url: url.to_string(),
+        body: response.text().await?,
+        etag,
+        last_modified,
+        fetched_at: Utc::now(),
+    }))
+}
+
+impl MetadataCache {
+    async fn offline_mode(&self) -> bool {
+        match self.database.get_settings().await {
+            Ok(settings) => settings.is_some_and(|settings| settings.offline_mode),
+            Err(e) => {
+                warn!("Could not read the offline mode setting: {}", e);
+                false
+            }
+        }
+    }
+
+    /// `url` from memory, the database or the provider; `force` skips the
+    /// first two unless offline
+    async fn load(&self, url: &str, ttl: Duration, force: bool) -> Result<(serde_json::Value, Freshness)> {
+        let offline = self.offline_mode().await;
+        if force && offline {
+            bail!("Offline mode is on");
+        }
+        if !force {
+            if let Some(entry) = self.memory.get(&url.to_string()).await {
+                return Ok((entry.value, Freshness { fetched_at: entry.fetched_at, stale: false, offline }));
+            }
+        }
+
+        let stored = match self.database.get_cached_response(url).await {
+            Ok(stored) => stored,
+            Err(e) => {
+                warn!("Could not read cached metadata for {}: {}", url, e);
+                None
+            }
+        };
+        if let Some(stored) = stored.as_ref().filter(|_| !force) {
+            let age = (Utc::now() - stored.fetched_at).to_std().unwrap_or_default();
+            if age < ttl {
+                let entry = Entry { value: serde_json::from_str(&stored.body)?, fetched_at: stored.fetched_at };
+                self.memory.put_with_ttl(url.to_string(), entry.clone(), Some(ttl - age)).await;
+                return Ok((entry.value, Freshness { fetched_at: entry.fetched_at, stale: false, offline }));
+            }
+        }
+        if offline {
+            let stored = stored.ok_or_else(|| anyhow!("{} is not in the offline catalog", url))?;
+            let freshness = Freshness { fetched_at: stored.fetched_at, stale: true, offline };
+            return Ok((serde_json::from_str(&stored.body)?, freshness));
+        }
+
+        let response = match fetch(&self.client, url, stored.as_ref()).await {
+            Ok(Fetched::Modified(response)) => response,
+            Ok(Fetched::NotModified) => {
+                debug!("Cached metadata for {} is still current", url);
+                CachedResponse { fetched_at: Utc::now(), ..stored.expect("only revalidated when cached") }
+            }
+            Err(e) => {
+                return match stored {
+                    Some(stored) if Utc::now() - stored.fetched_at < chrono::Duration::days(MAX_STALE_DAYS) => {
+                        warn!("Could not refresh {} ({}), using the copy from {}", url, e, stored.fetched_at);
+                        let freshness = Freshness { fetched_at: stored.fetched_at, stale: true, offline };
+                        Ok((serde_json::from_str(&stored.body)?, freshness))
+                    }
+                    _ => Err(e),
+                };
+            }
+        };
+
+        let entry = Entry { value: serde_json::from_str(&response.body)?, fetched_at: response.fetched_at };
+        if let Err(e) = self.database.save_cached_response(&response).await {
+            warn!("Could not store metadata for {}: {}", url, e);
+        }
+        self.memory.put_with_ttl(url.to_string(), entry.clone(), Some(ttl)).await;
+        Ok((entry.value, Freshness { fetched_at: entry.fetched_at, stale: false, offline }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::response::IntoResponse;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// A metadata provider whose document has ETag `"v2"`
+    #[derive(Clone, Default)]
+    struct Provider {
+        requests: Arc<AtomicUsize>,
+        down: Arc<AtomicBool>,
+    }
+
+    impl Provider {
+        async fn start(&self) -> String {
+            let provider = self.clone();
+            let app = axum::Router::new().route(
+                "/versions",
+                axum::routing::get(move |headers: HeaderMap| {
+                    let provider = provider.clone();
+                    async move {
+                        provider.requests.fetch_add(1, Ordering::SeqCst);
+                        if provider.down.load(Ordering::SeqCst) {
+                            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                        }
+                        if headers.get(header::IF_NONE_MATCH).is_some_and(|etag| etag == "\"v2\"") {
+                            return StatusCode::NOT_MODIFIED.into_response();
+                        }
+                        ([(header::ETAG, "\"v2\"")], r#"{"latest":"1.21.2"}"#).into_response()
+                    }
+                }),
+            );
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/versions", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await });
+            url
+        }
+
+        fn requests(&self) -> usize {
+            self.requests.load(Ordering::SeqCst)
+        }
+    }
+
+    /// A cache with an empty memory tier over `database`
+    fn empty_cache(database: &DatabaseManager) -> MetadataCache {
+        MetadataCache { database: database.clone(), memory: Arc::new(Cache::new(memory_config())), client: client() }
+    }
+
+    async fn database(dir: &std::path::Path) -> DatabaseManager {
+        DatabaseManager::new(&format!("sqlite:{}", dir.join("metadata.db").display())).await.unwrap()
+    }
+
+    async fn store(database: &DatabaseManager, url: &str, etag: &str, age: chrono::Duration) {
+        let response = CachedResponse {
+            url: url.to_string(),
+            body: r#"{"latest":"1.21.1"}"#.to_string(),
+            etag: Some(etag.to_string()),
+            last_modified: None,
+            fetched_at: Utc::now() - age,
+        };
+        database.save_cached_response(&response).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fresh_entries_skip_the_network() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = database(dir.path()).await;
+        let provider = Provider::default();
+        let url = provider.start().await;
+
+        let cache = empty_cache(&database);
+        let (value, freshness) = cache.load(&url, MOJANG_TTL, false).await.unwrap();
+        assert_eq!(value["latest"], "1.21.2");
+        assert!(!freshness.stale);
+        cache.load(&url, MOJANG_TTL, false).await.unwrap();
+        assert_eq!(provider.requests(), 1);
+
+        // From the database once memory is gone, as after a restart
+        let (value, _) = empty_cache(&database).load(&url, MOJANG_TTL, false).await.unwrap();
+        assert_eq!(value["latest"], "1.21.2");
+        assert_eq!(provider.requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_not_modified_renews_the_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = database(dir.path()).await;
+        let provider = Provider::default();
+        let url = provider.start().await;
+        store(&database, &url, "\"v2\"", chrono::Duration::hours(1)).await;
+
+        let (value, freshness) = empty_cache(&database).load(&url, MOJANG_TTL, false).await.unwrap();
+        assert_eq!(provider.requests(), 1);
+        // The stored body, now counted as fetched just now
+        assert_eq!(value["latest"], "1.21.1");
+        assert!(!freshness.stale);
+        assert!(Utc::now() - freshness.fetched_at < chrono::Duration::minutes(1));
+        let stored = database.get_cached_response(&url).await.unwrap().unwrap();
+        assert_eq!(stored.fetched_at, freshness.fetched_at);
+
+        empty_cache(&database).load(&url, MOJANG_TTL, false).await.unwrap();
+        assert_eq!(provider.requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_changed_document_replaces_the_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = database(dir.path()).await;
+        let provider = Provider::default();
+        let url = provider.start().await;
+        store(&database, &url, "\"v1\"", chrono::Duration::hours(1)).await;
+
+        let (value, _) = empty_cache(&database).load(&url, MOJANG_TTL, false).await.unwrap();
+        assert_eq!(value["latest"], "1.21.2");
+        let stored = database.get_cached_response(&url).await.unwrap().unwrap();
+        assert_eq!(stored.etag.as_deref(), Some("\"v2\""));
+    }
+
+    #[tokio::test]
+    async fn test_stale_entries_are_served_while_the_provider_is_down() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = database(dir.path()).await;
+        let provider = Provider::default();
+        provider.down.store(true, Ordering::SeqCst);
+        let url = provider.start().await;
+
+        store(&database, &url, "\"v1\"", chrono::Duration::days(1)).await;
+        let (value, freshness) = empty_cache(&database).load(&url, MOJANG_TTL, false).await.unwrap();
+        assert_eq!(value["latest"], "1.21.1");
+        assert!(freshness.stale);
+        assert_eq!(provider.requests(), 1);
+
+        // Too old to stand in for the provider
+        store(&database, &url, "\"v1\"", chrono::Duration::days(MAX_STALE_DAYS + 1)).await;
+        assert!(empty_cache(&database).load(&url, MOJANG_TTL, false).await.is_err());
+    }
+}
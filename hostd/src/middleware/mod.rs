@@ -1 +1,2 @@
 pub mod validation;
+pub mod versioning;
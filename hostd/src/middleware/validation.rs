@@ -8,7 +8,8 @@ use axum::{
 use serde_json;
 use std::sync::Arc;
 
-use crate::core::validation::{InputValidator, ValidationResult};
+use crate::core::error_handler::{AppError, FieldError};
+use crate::core::validation::{InputValidator, ValidateRequest, ValidationResult};
 use crate::api::ApiResponse;
 
 /// Validation middleware that validates request body against endpoint rules
@@ -36,3 +37,34 @@ pub fn create_validation_middleware() -> Arc<InputValidator> {
     use crate::core::validation::create_default_validations;
     Arc::new(create_default_validations())
 }
+
+/// JSON body extractor that checks the body against its type's
+/// `ValidateRequest` rules. A body breaking them is rejected with a 422 that
+/// lists every invalid field, before the handler runs.
+pub struct ValidJson<T>(pub T);
+
+#[axum::async_trait]
+impl<T, S> axum::extract::FromRequest<S> for ValidJson<T>
+where
+    T: serde::de::DeserializeOwned + ValidateRequest,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<serde_json::Value>::from_request(request, state)
+            .await
+            .map_err(|rejection| AppError::request(rejection.body_text()))?;
+        let fields = InputValidator::new().check(&T::validations(), &value);
+        if !fields.is_empty() {
+            return Err(AppError::field_validation_error(fields));
+        }
+        serde_json::from_value(value).map(ValidJson).map_err(|e| {
+            AppError::field_validation_error(vec![FieldError {
+                field: "body".to_string(),
+                constraint: "schema".to_string(),
+                message: e.to_string(),
+            }])
+        })
+    }
+}
@@ -0,0 +1,211 @@
+//! Versioned routing for the API.
+//!
+//! Handlers are registered once under `/api/...`. Clients address them as
+//! `/api/v1/...` or `/api/v2/...`, and this middleware rewrites the path
+//! before the router sees it, so auth, RBAC and audit keep matching on one
+//! canonical path. Handlers read the requested version as an [`ApiVersion`].
+//!
+//! An endpoint whose response changes shape in a breaking way answers v2
+//! requests with the new shape and is listed in [`V2_ROUTES`]. Every other
+//! endpoint answers both versions alike. Unversioned `/api/...` paths keep
+//! working as v1 for existing clients, but answer with a `Deprecation` header
+//! and a `Link` to the versioned successor. So does a v1 endpoint that has a
+//! v2 replacement.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{header, request::Parts, HeaderValue, Method, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::core::error_handler::AppError;
+
+/// Endpoints with a different v2 response, as `(method, path)` below the version prefix.
+/// `:name` matches one path segment.
+pub const V2_ROUTES: &[(Method, &str)] = &[
+    // Listings are pages with a total and a cursor rather than arrays
+    (Method::GET, "/servers"),
+    (Method::GET, "/servers/:id/backups"),
+    (Method::GET, "/servers/:id/mods"),
+];
+
+/// Reported on every versioned response
+pub const VERSION_HEADER: &str = "api-version";
+
+/// Paths that describe the API itself and are never versioned
+const UNVERSIONED: &[&str] = &["/api/health", "/api/healthz", crate::openapi::SPEC_PATH];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    pub fn number(self) -> u16 {
+        match self {
+            ApiVersion::V1 => 1,
+            ApiVersion::V2 => 2,
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "/api/v1",
+            ApiVersion::V2 => "/api/v2",
+        }
+    }
+
+    fn parse(segment: &str) -> Option<Option<Self>> {
+        let number = segment.strip_prefix('v')?;
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some(match number {
+            "1" => Some(ApiVersion::V1),
+            "2" => Some(ApiVersion::V2),
+            _ => None,
+        })
+    }
+}
+
+/// The version a request asked for; v1 for requests that did not pass the middleware
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ApiVersion {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<ApiVersion>().copied().unwrap_or(ApiVersion::V1))
+    }
+}
+
+/// Where a request is routed and what it is told about deprecation
+#[derive(Debug, PartialEq, Eq)]
+pub struct Resolved {
+    pub version: ApiVersion,
+    pub path: String,
+    pub successor: Option<String>,
+}
+
+/// Resolve a request path against the given v2 routes. `Ok(None)` leaves
+/// the request alone; `Err` carries an unknown version segment.
+pub fn resolve(method: &Method, path: &str, v2_routes: &[(Method, &str)]) -> Result<Option<Resolved>, String> {
+    let Some(rest) = path.strip_prefix("/api").filter(|r| r.is_empty() || r.starts_with('/')) else {
+        return Ok(None);
+    };
+    if UNVERSIONED.contains(&path) || path.starts_with(crate::openapi::DOCS_PATH) {
+        return Ok(None);
+    }
+
+    let trimmed = rest.strip_prefix('/').unwrap_or(rest);
+    let first = trimmed.split('/').next().unwrap_or_default();
+    let has_v2 = |tail: &str| v2_routes.iter().any(|(m, pattern)| m == method && matches_pattern(pattern, tail));
+
+    match ApiVersion::parse(first) {
+        Some(None) => Err(first.to_string()),
+        Some(Some(version)) => {
+            let tail = &trimmed[first.len()..];
+            let successor = (version == ApiVersion::V1 && has_v2(tail)).then(|| format!("{}{}", ApiVersion::V2.prefix(), tail));
+            Ok(Some(Resolved { version, path: format!("/api{}", tail), successor }))
+        }
+        None => {
+            let successor = if has_v2(rest) { ApiVersion::V2 } else { ApiVersion::V1 };
+            Ok(Some(Resolved {
+                version: ApiVersion::V1,
+                path: path.to_string(),
+                successor: Some(format!("{}{}", successor.prefix(), rest)),
+            }))
+        }
+    }
+}
+
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+    let mut pattern = pattern.split('/').filter(|s| !s.is_empty());
+    let mut path = path.split('/').filter(|s| !s.is_empty());
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, None) => return true,
+            (Some(p), Some(s)) if p.starts_with(':') || p == s => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Rewrite versioned paths to their handlers and tag the response.
+/// Must wrap the router as a service, since a `Router::layer` runs after routing.
+pub async fn version_middleware(mut request: Request, next: Next) -> Response {
+    let resolved = match resolve(request.method(), request.uri().path(), V2_ROUTES) {
+        Ok(Some(resolved)) => resolved,
+        Ok(None) => return next.run(request).await,
+        Err(version) => return AppError::not_found(&format!("API version {}", version)).into_response(),
+    };
+
+    if resolved.path != request.uri().path() {
+        let path_and_query = match request.uri().query() {
+            Some(query) => format!("{}?{}", resolved.path, query),
+            None => resolved.path.clone(),
+        };
+        let mut parts = request.uri().clone().into_parts();
+        parts.path_and_query = path_and_query.parse().ok();
+        if let Ok(uri) = Uri::from_parts(parts) {
+            *request.uri_mut() = uri;
+        }
+    }
+    request.extensions_mut().insert(resolved.version);
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(VERSION_HEADER, HeaderValue::from(resolved.version.number()));
+    if let Some(successor) = resolved.successor {
+        headers.insert("deprecation", HeaderValue::from_static("true"));
+        if let Ok(link) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor)) {
+            headers.insert(header::LINK, link);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUTES: &[(Method, &str)] = &[(Method::GET, "/servers/:id/players")];
+
+    fn resolved(method: Method, path: &str) -> Resolved {
+        resolve(&method, path, ROUTES).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_v1_paths_route_to_canonical_handlers() {
+        let r = resolved(Method::GET, "/api/v1/servers/abc");
+        assert_eq!(r, Resolved { version: ApiVersion::V1, path: "/api/servers/abc".into(), successor: None });
+        assert_eq!(resolved(Method::POST, "/api/v1/auth/login").path, "/api/auth/login");
+    }
+
+    #[test]
+    fn test_v2_paths_route_to_the_same_handlers() {
+        let r = resolved(Method::GET, "/api/v2/servers/abc/players");
+        assert_eq!(r, Resolved { version: ApiVersion::V2, path: "/api/servers/abc/players".into(), successor: None });
+        assert_eq!(resolved(Method::GET, "/api/v2/servers").path, "/api/servers");
+    }
+
+    #[test]
+    fn test_deprecated_paths_point_at_successor() {
+        assert_eq!(resolved(Method::GET, "/api/servers").successor.as_deref(), Some("/api/v1/servers"));
+        assert_eq!(resolved(Method::GET, "/api/servers/abc/players").successor.as_deref(), Some("/api/v2/servers/abc/players"));
+        assert_eq!(resolved(Method::GET, "/api/v1/servers/abc/players").successor.as_deref(), Some("/api/v2/servers/abc/players"));
+    }
+
+    #[test]
+    fn test_unversioned_and_unknown_paths() {
+        assert_eq!(resolve(&Method::GET, "/api/health", ROUTES), Ok(None));
+        assert_eq!(resolve(&Method::GET, "/api/docs/index.html", ROUTES), Ok(None));
+        assert_eq!(resolve(&Method::GET, "/ws", ROUTES), Ok(None));
+        assert_eq!(resolve(&Method::GET, "/apiary", ROUTES), Ok(None));
+        assert_eq!(resolve(&Method::GET, "/api/v3/servers", ROUTES), Err("v3".to_string()));
+        // A path segment that merely starts with v is a route, not a version
+        assert_eq!(resolved(Method::GET, "/api/versions").path, "/api/versions");
+    }
+}
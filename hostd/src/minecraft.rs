@@ -16,7 +16,9 @@ use uuid::Uuid;
 
 use crate::database::{DatabaseManager, ServerConfig, EventLog};
 use crate::rcon::RconClient;
+use crate::tick_timings::{self, TickTimings};
 use crate::websocket_manager::WebSocketManager;
+use crate::core::error_handler::NotFound;
 
 /// Minecraft server process manager
 #[derive(Debug, Clone)]
@@ -225,6 +227,19 @@ impl MinecraftServer {
                 let reader = BufReader::new(stdout);
                 for line in reader.lines().flatten() {
                     let _ = futures::executor::block_on(async {
+                        if let Some((kind, player)) = crate::events::player_event(&line) {
+                            let verb = if kind == crate::events::EventKind::PlayerJoined { "joined" } else { "left" };
+                            let event = EventLog {
+                                id: Uuid::new_v4().to_string(),
+                                server_id: Some(server_id.clone()),
+                                event_type: kind.as_str().to_string(),
+                                message: format!("{} {} the game", player, verb),
+                                level: "info".to_string(),
+                                metadata: Some(serde_json::json!({ "player": player })),
+                                created_at: chrono::Utc::now(),
+                            };
+                            db_clone.log_event(&event).await?;
+                        }
                         let event = EventLog {
                             id: Uuid::new_v4().to_string(),
                             server_id: Some(server_id.clone()),
@@ -431,19 +446,29 @@ impl MinecraftServer {
             return Err(anyhow!("Server is not running"));
         }
 
-        // Get TPS
-        let tps_response = self.send_command("tps").await?;
-        let tps = self.parse_tps(&tps_response)?;
+        let spark = tick_timings::has_spark(&PathBuf::from(&self.config.host));
+
+        // Get TPS and tick time; zero when the server reports neither
+        let (tps, tick_p95) = match self.read_tick_timings(spark).await {
+            Some(timings) => (timings.tps, timings.tick_p95.or(timings.mspt).unwrap_or(0.0)),
+            None => (0.0, 0.0),
+        };
 
         // Get player count
         let list_response = self.send_command("list").await?;
         let players_online = self.parse_player_count(&list_response)?;
 
-        // Get heap usage (simulated for now)
-        let heap_mb = 2048; // TODO: Get actual heap usage
-
-        // Get tick time (simulated for now)
-        let tick_p95 = 45.2; // TODO: Get actual tick time
+        // Get heap usage from spark, otherwise the JVM's resident memory
+        let heap = if spark {
+            self.send_command("spark health").await.ok()
+                .and_then(|response| tick_timings::parse_spark_heap(&response))
+        } else {
+            None
+        };
+        let heap_mb = match heap {
+            Some(heap) => heap.used_mb,
+            None => self.process_memory_mb().await.unwrap_or(0),
+        };
 
         // Get GPU queue time (simulated for now)
         let gpu_queue_ms = 5.2; // TODO: Get actual GPU queue time
@@ -466,6 +491,29 @@ impl MinecraftServer {
         })
     }
 
+    /// Tick timings from spark when it is installed, otherwise the loader's own command
+    async fn read_tick_timings(&self, spark: bool) -> Option<TickTimings> {
+        for source in tick_timings::sources(&self.config.loader, spark) {
+            match self.send_command(source.command()).await {
+                Ok(response) => match tick_timings::parse(source, &response) {
+                    Some(timings) => return Some(timings),
+                    None => debug!("No tick timings in '{}' output for {}", source.command(), self.id),
+                },
+                Err(e) => debug!("Failed to run '{}' on {}: {}", source.command(), self.id, e),
+            }
+        }
+        None
+    }
+
+    /// Resident memory of the server process
+    async fn process_memory_mb(&self) -> Option<u64> {
+        let pid = self.process.as_ref()?.lock().await.as_ref()?.id();
+        let pid = sysinfo::Pid::from_u32(pid);
+        let mut system = sysinfo::System::new();
+        system.refresh_process(pid);
+        system.process(pid).map(|process| process.memory() / (1024 * 1024))
+    }
+
     /// Get player list
     pub async fn get_players(&self) -> Result<Vec<Player>> {
         if self.status != ServerStatus::Running {
@@ -494,18 +542,6 @@ impl MinecraftServer {
         Ok(players)
     }
 
-    /// Parse TPS from server response
-    fn parse_tps(&self, response: &str) -> Result<f64> {
-        // Parse TPS from response like "TPS: 20.0 (1m, 5m, 15m)"
-        if let Some(tps_part) = response.split_whitespace().nth(1) {
-            if let Some(tps_str) = tps_part.strip_suffix(',') {
-                return Ok(tps_str.parse()?);
-            }
-            return Ok(tps_part.parse()?);
-        }
-        Ok(20.0) // Default TPS
-    }
-
     /// Parse player count from server response
     fn parse_player_count(&self, response: &str) -> Result<u32> {
         // Parse player count from response like "There are 5 of a max of 20 players online: ..."
@@ -678,7 +714,7 @@ impl MinecraftManager {
                 ws_manager.send_server_status(Uuid::parse_str(id)?, "starting".to_string()).await;
             }
         } else {
-            return Err(anyhow!("Server not found: {}", id));
+            return Err(NotFound::with_id("Server", id).into());
         }
         Ok(())
     }
@@ -694,7 +730,7 @@ impl MinecraftManager {
                 ws_manager.send_server_status(Uuid::parse_str(id)?, "stopping".to_string()).await;
             }
         } else {
-            return Err(anyhow!("Server not found: {}", id));
+            return Err(NotFound::with_id("Server", id).into());
         }
         Ok(())
     }
@@ -710,7 +746,7 @@ impl MinecraftManager {
                 ws_manager.send_server_status(Uuid::parse_str(id)?, "restarting".to_string()).await;
             }
         } else {
-            return Err(anyhow!("Server not found: {}", id));
+            return Err(NotFound::with_id("Server", id).into());
         }
         Ok(())
     }
@@ -721,7 +757,7 @@ impl MinecraftManager {
         if let Some(server) = servers.get(id) {
             server.send_command(command).await
         } else {
-            Err(anyhow!("Server not found: {}", id))
+            Err(NotFound::with_id("Server", id).into())
         }
     }
 
@@ -731,7 +767,7 @@ impl MinecraftManager {
         if let Some(server) = servers.get(id) {
             server.get_metrics().await
         } else {
-            Err(anyhow!("Server not found: {}", id))
+            Err(NotFound::with_id("Server", id).into())
         }
     }
 
@@ -741,7 +777,7 @@ impl MinecraftManager {
         if let Some(server) = servers.get(id) {
             server.get_players().await
         } else {
-            Err(anyhow!("Server not found: {}", id))
+            Err(NotFound::with_id("Server", id).into())
         }
     }
 
@@ -822,7 +858,9 @@ mod tests {
             java_path: "/usr/bin/java".to_string(),
             jvm_args: "-Xmx4G".to_string(),
             server_jar: "server.jar".to_string(),
+            server_directory: temp_dir.path().join("test-server").display().to_string(),
             rcon_password: "password".to_string(),
+            managed: true,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -833,6 +871,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "needs a Minecraft server with RCON on localhost:25575"]
     async fn test_rcon_client() {
         let rcon = RconClient::new("localhost".to_string(), 25575, "password".to_string());
         
@@ -0,0 +1,375 @@
+//! Mod bisection: finds the mods that make a server crash
+//!
+//! Starting a bisection records the server's enabled mods, which are assumed
+//! to crash it. Each round leaves a chunk of the suspects out, applies that
+//! with [`mod_toggle`](crate::mod_toggle) and restarts the server; the
+//! operator then reports whether it still crashed. A crash means the chunk
+//! was not needed for it, so the chunk is dropped from the suspects; no crash
+//! means the chunk is kept. Chunks start at half the suspects and are halved
+//! after every pass, so a single culprit is found in a few rounds and the mods
+//! left at the end are a minimal set that still crashes the server, which also
+//! catches crashes that take two mods together. Mods a tested mod depends on
+//! are never left out. The original mods are enabled again when the
+//! bisection ends.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::config_revisions::server_dir;
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, EventLog};
+use crate::mod_metadata::{self, DependencyKind};
+use crate::core::error_handler::NotFound;
+
+const EVENT_TYPE: &str = "mod_bisect";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BisectStatus {
+    /// Waiting for the operator to report the result of a round
+    Testing,
+    Finished,
+    Aborted,
+}
+
+/// A bisection of one server's mods, by jar name
+#[derive(Debug, Clone, Serialize)]
+pub struct BisectSession {
+    pub server_id: String,
+    pub status: BisectStatus,
+    pub started_at: DateTime<Utc>,
+    pub round: u32,
+    /// Mods enabled when the bisection started
+    pub original: Vec<String>,
+    /// Smallest set of mods known to crash the server
+    pub suspects: Vec<String>,
+    /// Mods enabled this round
+    pub testing: Vec<String>,
+    /// Mods left out this round
+    pub left_out: Vec<String>,
+    /// Restart the server after applying each round
+    pub restart: bool,
+    #[serde(skip)]
+    bisection: Bisection,
+    /// Mod IDs each jar provides, and those it requires
+    #[serde(skip)]
+    jars: HashMap<String, JarInfo>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct JarInfo {
+    provides: Vec<String>,
+    requires: Vec<String>,
+}
+
+/// The search itself: drops chunks of the suspects that the crash doesn't need
+#[derive(Debug, Clone)]
+struct Bisection {
+    suspects: Vec<String>,
+    chunk_size: usize,
+    /// Start of the chunk left out this round
+    position: usize,
+}
+
+impl Bisection {
+    fn new(suspects: Vec<String>) -> Self {
+        let chunk_size = (suspects.len() / 2).max(1);
+        Self { suspects, chunk_size, position: 0 }
+    }
+
+    /// The chunk to leave out next; `None` once the suspects are minimal
+    fn chunk(&self) -> Option<&[String]> {
+        if self.finished() {
+            return None;
+        }
+        let end = (self.position + self.chunk_size).min(self.suspects.len());
+        Some(&self.suspects[self.position..end])
+    }
+
+    /// Record whether the server crashed without `left_out`
+    fn record(&mut self, left_out: &[String], crashed: bool) {
+        let chunk_end = (self.position + self.chunk_size).min(self.suspects.len());
+        if crashed {
+            self.suspects.retain(|suspect| !left_out.contains(suspect));
+        } else {
+            self.position = chunk_end;
+        }
+        if self.position >= self.suspects.len() {
+            if self.chunk_size == 1 {
+                // A pass leaving out single mods ends the search
+                self.chunk_size = 0;
+                return;
+            }
+            self.chunk_size /= 2;
+            self.position = 0;
+        }
+        // Never leave every suspect out
+        if self.chunk_size >= self.suspects.len() {
+            self.chunk_size = (self.suspects.len() / 2).max(1);
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.chunk_size == 0 || self.suspects.len() <= 1
+    }
+}
+
+/// The mods in `chunk` the mods in `testing` need, directly or through each other
+fn required_from(testing: &[String], chunk: &[String], jars: &HashMap<String, JarInfo>) -> Vec<String> {
+    let mut needed: HashSet<&str> = testing
+        .iter()
+        .filter_map(|file| jars.get(file))
+        .flat_map(|jar| jar.requires.iter().map(String::as_str))
+        .collect();
+    let mut kept: Vec<String> = Vec::new();
+    loop {
+        let found: Vec<&String> = chunk
+            .iter()
+            .filter(|file| !kept.contains(file))
+            .filter(|file| jars.get(*file).is_some_and(|jar| jar.provides.iter().any(|id| needed.contains(id.as_str()))))
+            .collect();
+        if found.is_empty() {
+            return kept;
+        }
+        for file in found {
+            if let Some(jar) = jars.get(file) {
+                needed.extend(jar.requires.iter().map(String::as_str));
+            }
+            kept.push(file.clone());
+        }
+    }
+}
+
+fn read_jars(mods_dir: &Path, files: &[String]) -> HashMap<String, JarInfo> {
+    files
+        .iter()
+        .map(|file| {
+            let mods = mod_metadata::read_jar(&mods_dir.join(file)).unwrap_or_default();
+            let info = JarInfo {
+                provides: mods.iter().flat_map(|m| std::iter::once(m.mod_id.clone()).chain(m.provides.iter().cloned())).collect(),
+                requires: mods
+                    .iter()
+                    .flat_map(|m| m.dependencies.iter())
+                    .filter(|dependency| dependency.kind == DependencyKind::Required)
+                    .map(|dependency| dependency.mod_id.clone())
+                    .collect(),
+            };
+            (file.clone(), info)
+        })
+        .collect()
+}
+
+pub struct ModBisector {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+    sessions: RwLock<HashMap<String, BisectSession>>,
+}
+
+impl ModBisector {
+    pub fn new(database: Arc<DatabaseManager>, process_manager: Arc<ProcessManager>) -> Self {
+        Self {
+            database,
+            process_manager,
+            sessions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn session(&self, server_id: &str) -> Option<BisectSession> {
+        self.sessions.read().await.get(server_id).cloned()
+    }
+
+    /// Start bisecting the enabled mods of a server that crashes with them
+    pub async fn start(&self, server_id: &str, restart: bool) -> Result<BisectSession> {
+        if self.session(server_id).await.is_some_and(|session| session.status == BisectStatus::Testing) {
+            bail!("A bisection of this server is already running");
+        }
+        let server = self.database.get_server(server_id).await?.ok_or_else(|| NotFound::new("Server"))?;
+        let original: Vec<String> = self
+            .database
+            .get_mods_by_server(server_id)
+            .await?
+            .into_iter()
+            .filter(|record| record.enabled)
+            .map(|record| record.filename)
+            .collect();
+        if original.len() < 2 {
+            bail!("Bisection needs at least 2 enabled mods");
+        }
+
+        let mods_dir = server_dir(&server).join("mods");
+        let files = original.clone();
+        let jars = tokio::task::spawn_blocking(move || read_jars(&mods_dir, &files)).await?;
+        let mut session = BisectSession {
+            server_id: server_id.to_string(),
+            status: BisectStatus::Testing,
+            started_at: Utc::now(),
+            round: 0,
+            original: original.clone(),
+            suspects: original.clone(),
+            testing: Vec::new(),
+            left_out: Vec::new(),
+            restart,
+            bisection: Bisection::new(original),
+            jars,
+        };
+        info!("Starting a mod bisection of {} over {} mods", server.name, session.original.len());
+        self.next_round(&mut session).await?;
+        self.sessions.write().await.insert(server_id.to_string(), session.clone());
+        Ok(session)
+    }
+
+    /// Record whether the server crashed this round and apply the next one
+    pub async fn report(&self, server_id: &str, crashed: bool) -> Result<BisectSession> {
+        let mut session = self
+            .session(server_id)
+            .await
+            .filter(|session| session.status == BisectStatus::Testing)
+            .ok_or_else(|| anyhow!("No bisection of this server is running"))?;
+        let left_out = session.left_out.clone();
+        session.bisection.record(&left_out, crashed);
+        self.next_round(&mut session).await?;
+        self.sessions.write().await.insert(server_id.to_string(), session.clone());
+        Ok(session)
+    }
+
+    /// Stop bisecting and enable the original mods again
+    pub async fn abort(&self, server_id: &str) -> Result<BisectSession> {
+        let mut session = self
+            .session(server_id)
+            .await
+            .filter(|session| session.status == BisectStatus::Testing)
+            .ok_or_else(|| anyhow!("No bisection of this server is running"))?;
+        session.status = BisectStatus::Aborted;
+        self.apply(&session.server_id, &session.original, &session.original, session.restart).await?;
+        session.testing = session.original.clone();
+        session.left_out.clear();
+        self.sessions.write().await.insert(server_id.to_string(), session.clone());
+        Ok(session)
+    }
+
+    /// Pick the next chunk to leave out, skipping chunks the tested mods
+    /// depend on entirely, or finish
+    async fn next_round(&self, session: &mut BisectSession) -> Result<()> {
+        while let Some(chunk) = session.bisection.chunk().map(<[String]>::to_vec) {
+            let testing: Vec<String> = session.bisection.suspects.iter().filter(|file| !chunk.contains(file)).cloned().collect();
+            let required = required_from(&testing, &chunk, &session.jars);
+            let left_out: Vec<String> = chunk.into_iter().filter(|file| !required.contains(file)).collect();
+            if left_out.is_empty() {
+                session.bisection.record(&[], false);
+                continue;
+            }
+
+            session.round += 1;
+            session.suspects = session.bisection.suspects.clone();
+            session.testing = testing.into_iter().chain(required).collect();
+            session.left_out = left_out;
+            return self.apply(&session.server_id, &session.original, &session.testing, session.restart).await;
+        }
+
+        session.status = BisectStatus::Finished;
+        session.suspects = session.bisection.suspects.clone();
+        session.testing = session.original.clone();
+        session.left_out.clear();
+        self.apply(&session.server_id, &session.original, &session.original, false).await?;
+        self.log_result(session).await;
+        Ok(())
+    }
+
+    /// Enable the mods in `enabled` and disable the rest of `mods`
+    async fn apply(&self, server_id: &str, mods: &[String], enabled: &[String], restart: bool) -> Result<()> {
+        let uuid = Uuid::parse_str(server_id)?;
+        // Jars of a running server can't be renamed everywhere
+        if self.process_manager.is_server_running(uuid).await {
+            self.process_manager.stop_server_process(uuid).await?;
+        }
+        for file in mods {
+            crate::mod_toggle::set_enabled(&self.database, server_id, file, enabled.contains(file)).await?;
+        }
+        if restart {
+            self.process_manager.restart_server_process(uuid).await?;
+        }
+        Ok(())
+    }
+
+    async fn log_result(&self, session: &BisectSession) {
+        let message = format!(
+            "Mod bisection finished after {} rounds; the server crashes with: {}",
+            session.round,
+            session.suspects.join(", ")
+        );
+        warn!("{}: {}", session.server_id, message);
+        let event = EventLog {
+            id: Uuid::new_v4().to_string(),
+            server_id: Some(session.server_id.clone()),
+            event_type: EVENT_TYPE.to_string(),
+            message,
+            level: "warn".to_string(),
+            metadata: serde_json::to_value(session).ok(),
+            created_at: Utc::now(),
+        };
+        if let Err(e) = self.database.log_event(&event).await {
+            error!("Failed to log the mod bisection of {}: {}", session.server_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mods(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("mod{}.jar", i)).collect()
+    }
+
+    /// Run a bisection against a server that crashes whenever `culprits` are all enabled
+    fn bisect(count: usize, culprits: &[&str]) -> (Vec<String>, u32) {
+        let mut bisection = Bisection::new(mods(count));
+        let mut rounds = 0;
+        while let Some(chunk) = bisection.chunk().map(<[String]>::to_vec) {
+            let crashed = culprits.iter().all(|culprit| !chunk.iter().any(|file| file == culprit));
+            bisection.record(&chunk, crashed);
+            rounds += 1;
+        }
+        assert!(bisection.finished());
+        (bisection.suspects, rounds)
+    }
+
+    #[test]
+    fn test_bisection() {
+        let (suspects, rounds) = bisect(16, &["mod11.jar"]);
+        assert_eq!(suspects, ["mod11.jar"]);
+        assert!(rounds <= 8, "took {} rounds", rounds);
+
+        let (suspects, _) = bisect(10, &["mod2.jar", "mod7.jar"]);
+        assert_eq!(suspects, ["mod2.jar", "mod7.jar"]);
+
+        let (suspects, _) = bisect(3, &["mod0.jar"]);
+        assert_eq!(suspects, ["mod0.jar"]);
+    }
+
+    #[test]
+    fn test_required_from() {
+        let jar = |provides: &[&str], requires: &[&str]| JarInfo {
+            provides: provides.iter().map(|id| id.to_string()).collect(),
+            requires: requires.iter().map(|id| id.to_string()).collect(),
+        };
+        let jars: HashMap<String, JarInfo> = [
+            ("create.jar".to_string(), jar(&["create"], &["flywheel"])),
+            ("flywheel.jar".to_string(), jar(&["flywheel"], &["fabric-api"])),
+            ("fabric-api.jar".to_string(), jar(&["fabric-api"], &[])),
+            ("jei.jar".to_string(), jar(&["jei"], &[])),
+        ]
+        .into();
+        let chunk = ["flywheel.jar".to_string(), "fabric-api.jar".to_string(), "jei.jar".to_string()];
+        let mut kept = required_from(&["create.jar".to_string()], &chunk, &jars);
+        kept.sort();
+        assert_eq!(kept, ["fabric-api.jar", "flywheel.jar"]);
+    }
+}
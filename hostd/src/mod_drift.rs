@@ -0,0 +1,276 @@
+//! Mod drift detection: the jars in each server's `mods/` folder are hashed
+//! every [`CHECK_INTERVAL`] and compared with the SHA-1 recorded for the
+//! server's mods. Files nobody recorded, files whose hash changed and recorded
+//! mods whose file is gone are reported, which catches hand edits and
+//! installs that didn't finish. A server whose drift changes gets a
+//! `mod_drift` event.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::config_revisions::server_dir;
+use crate::core::crash_loop::DISABLED_SUFFIX;
+use crate::database::{DatabaseManager, EventLog, Mod, ServerConfig};
+use crate::core::error_handler::NotFound;
+
+const EVENT_TYPE: &str = "mod_drift";
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftKind {
+    /// A jar no mod record points at
+    Unknown,
+    /// A recorded mod whose jar has a different SHA-1
+    Modified,
+    /// A recorded mod without a jar
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DriftEntry {
+    pub filename: String,
+    pub kind: DriftKind,
+    pub mod_id: Option<String>,
+    pub expected_sha1: Option<String>,
+    pub actual_sha1: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftReport {
+    pub server_id: String,
+    pub checked_at: DateTime<Utc>,
+    pub files_checked: usize,
+    pub drift: Vec<DriftEntry>,
+}
+
+/// A jar in the mods folder
+#[derive(Debug, Clone)]
+struct ModFile {
+    /// Name without the suffix of a disabled mod
+    filename: String,
+    sha1: String,
+}
+
+/// A hash, and the modification time and size of the file it was taken of
+type HashedFile = (SystemTime, u64, String);
+
+pub struct ModDriftMonitor {
+    database: Arc<DatabaseManager>,
+    reports: RwLock<HashMap<String, DriftReport>>,
+    /// Hashes by path, so unchanged jars are not hashed again
+    hashes: RwLock<HashMap<PathBuf, HashedFile>>,
+}
+
+impl ModDriftMonitor {
+    pub fn new(database: Arc<DatabaseManager>) -> Self {
+        Self {
+            database,
+            reports: RwLock::new(HashMap::new()),
+            hashes: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Check every server now and every [`CHECK_INTERVAL`]
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting mod drift detection");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let servers = match self.database.get_all_servers().await {
+                Ok(servers) => servers,
+                Err(e) => {
+                    error!("Failed to list servers for mod drift detection: {}", e);
+                    continue;
+                }
+            };
+            for server in servers {
+                if let Err(e) = self.check_server(&server).await {
+                    warn!("Mod drift check of {} failed: {}", server.name, e);
+                }
+            }
+        }
+    }
+
+    /// The last report of a server, checking it first if it never was
+    pub async fn report(&self, server_id: &str) -> Result<DriftReport> {
+        if let Some(report) = self.reports.read().await.get(server_id) {
+            return Ok(report.clone());
+        }
+        self.check(server_id).await
+    }
+
+    /// Check a server now
+    pub async fn check(&self, server_id: &str) -> Result<DriftReport> {
+        let server = self
+            .database
+            .get_server(server_id)
+            .await?
+            .ok_or_else(|| NotFound::new("Server"))?;
+        self.check_server(&server).await
+    }
+
+    async fn check_server(&self, server: &ServerConfig) -> Result<DriftReport> {
+        let recorded = self.database.get_mods_by_server(&server.id).await?;
+        let files = self.hash_mods(&server_dir(server).join("mods")).await?;
+        let report = DriftReport {
+            server_id: server.id.clone(),
+            checked_at: Utc::now(),
+            files_checked: files.len(),
+            drift: compare(&recorded, &files),
+        };
+
+        let previous = self.reports.write().await.insert(server.id.clone(), report.clone());
+        let changed = previous.map_or(!report.drift.is_empty(), |previous| previous.drift != report.drift);
+        if changed {
+            self.log_drift(server, &report).await;
+        }
+        Ok(report)
+    }
+
+    /// Hash the jars in `mods_dir`, including disabled ones
+    async fn hash_mods(&self, mods_dir: &Path) -> Result<Vec<ModFile>> {
+        let mut entries = match tokio::fs::read_dir(mods_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        let mut files = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let filename = name.strip_suffix(DISABLED_SUFFIX).unwrap_or(&name);
+            if !filename.ends_with(".jar") {
+                continue;
+            }
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let path = entry.path();
+            let modified = metadata.modified()?;
+            let cached = self.hashes.read().await.get(&path).cloned();
+            let sha1 = match cached {
+                Some((at, len, sha1)) if at == modified && len == metadata.len() => sha1,
+                _ => {
+                    let hashed = path.clone();
+                    let sha1 = tokio::task::spawn_blocking(move || crate::downloads::sha1_file(&hashed)).await??;
+                    self.hashes.write().await.insert(path, (modified, metadata.len(), sha1.clone()));
+                    sha1
+                }
+            };
+            files.push(ModFile { filename: filename.to_string(), sha1 });
+        }
+        Ok(files)
+    }
+
+    async fn log_drift(&self, server: &ServerConfig, report: &DriftReport) {
+        let count = |kind| report.drift.iter().filter(|entry| entry.kind == kind).count();
+        let (message, level) = if report.drift.is_empty() {
+            ("The mods folder matches the installed mods again".to_string(), "info")
+        } else {
+            let message = format!(
+                "The mods folder differs from the installed mods: {} unknown, {} modified, {} missing",
+                count(DriftKind::Unknown),
+                count(DriftKind::Modified),
+                count(DriftKind::Missing)
+            );
+            warn!("{}: {}", server.name, message);
+            (message, "warn")
+        };
+        let event = EventLog {
+            id: Uuid::new_v4().to_string(),
+            server_id: Some(server.id.clone()),
+            event_type: EVENT_TYPE.to_string(),
+            message,
+            level: level.to_string(),
+            metadata: serde_json::to_value(report).ok(),
+            created_at: report.checked_at,
+        };
+        if let Err(e) = self.database.log_event(&event).await {
+            error!("Failed to log mod drift of {}: {}", server.id, e);
+        }
+    }
+}
+
+/// Differences between a server's mod records and the jars in its mods folder.
+/// Records without a SHA-1 are only checked for presence.
+fn compare(recorded: &[Mod], files: &[ModFile]) -> Vec<DriftEntry> {
+    let mut drift = Vec::new();
+    for file in files {
+        match recorded.iter().find(|record| record.filename == file.filename) {
+            None => drift.push(DriftEntry {
+                filename: file.filename.clone(),
+                kind: DriftKind::Unknown,
+                mod_id: None,
+                expected_sha1: None,
+                actual_sha1: Some(file.sha1.clone()),
+            }),
+            Some(record) if !record.sha1.is_empty() && !record.sha1.eq_ignore_ascii_case(&file.sha1) => {
+                drift.push(DriftEntry {
+                    filename: file.filename.clone(),
+                    kind: DriftKind::Modified,
+                    mod_id: Some(record.id.clone()),
+                    expected_sha1: Some(record.sha1.to_ascii_lowercase()),
+                    actual_sha1: Some(file.sha1.clone()),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for record in recorded {
+        if !files.iter().any(|file| file.filename == record.filename) {
+            drift.push(DriftEntry {
+                filename: record.filename.clone(),
+                kind: DriftKind::Missing,
+                mod_id: Some(record.id.clone()),
+                expected_sha1: Some(record.sha1.to_ascii_lowercase()).filter(|sha1| !sha1.is_empty()),
+                actual_sha1: None,
+            });
+        }
+    }
+    drift.sort_by(|a, b| a.filename.cmp(&b.filename));
+    drift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(filename: &str, sha1: &str) -> Mod {
+        Mod {
+            id: filename.trim_end_matches(".jar").to_string(),
+            provider: "modrinth".to_string(),
+            project_id: "project".to_string(),
+            version_id: "version".to_string(),
+            filename: filename.to_string(),
+            sha1: sha1.to_string(),
+            server_id: Some("server".to_string()),
+            enabled: true,
+            category: "utility".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn file(filename: &str, sha1: &str) -> ModFile {
+        ModFile { filename: filename.to_string(), sha1: sha1.to_string() }
+    }
+
+    #[test]
+    fn test_compare() {
+        let recorded = [record("a.jar", "AAAA"), record("b.jar", "bbbb"), record("c.jar", ""), record("d.jar", "dddd")];
+        let files = [file("a.jar", "aaaa"), file("b.jar", "ffff"), file("c.jar", "cccc"), file("e.jar", "eeee")];
+        let drift = compare(&recorded, &files);
+        let kinds: Vec<(&str, DriftKind)> = drift.iter().map(|entry| (entry.filename.as_str(), entry.kind)).collect();
+        assert_eq!(kinds, [("b.jar", DriftKind::Modified), ("d.jar", DriftKind::Missing), ("e.jar", DriftKind::Unknown)]);
+        assert_eq!(drift[0].expected_sha1.as_deref(), Some("bbbb"));
+    }
+}
@@ -0,0 +1,517 @@
+//! Metadata of mod jars: the mods a jar declares in fabric.mod.json,
+//! quilt.mod.json or (neoforge.)mods.toml, with their versions, supported
+//! Minecraft versions and dependency declarations, and matching of versions
+//! against the ranges those files use.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Mod IDs provided by the game or the loader rather than by a jar
+pub const BUILTIN_IDS: [&str; 8] = [
+    "minecraft",
+    "java",
+    "fabricloader",
+    "fabric-loader",
+    "quilt_loader",
+    "forge",
+    "neoforge",
+    "javafml",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+    Required,
+    Optional,
+    /// The mods cannot run together
+    Breaks,
+    /// The mods run together but misbehave
+    Conflicts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dependency {
+    pub mod_id: String,
+    /// As declared; `*` for any version
+    pub versions: String,
+    pub kind: DependencyKind,
+}
+
+/// One mod declared by a jar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JarMod {
+    /// Jar file name
+    pub file: String,
+    pub mod_id: String,
+    pub name: String,
+    pub version: String,
+    /// fabric, quilt, forge or neoforge
+    pub loader: String,
+    /// Declared Minecraft versions; absent when the mod doesn't say
+    pub minecraft: Option<String>,
+    /// Where the mod runs: client, server or both; absent when the mod doesn't say
+    pub environment: Option<String>,
+    /// Declared client-only, or with nothing but client entrypoints
+    pub client_only: bool,
+    /// Other mod IDs the mod stands in for
+    pub provides: Vec<String>,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// Mods declared by a jar; empty when it has no metadata this module reads
+pub fn read_jar(path: &Path) -> Result<Vec<JarMod>> {
+    let file = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let mut archive = zip::ZipArchive::new(fs::File::open(path)?).with_context(|| format!("{} is not a jar", file))?;
+
+    let mut mods = Vec::new();
+    if let Some(content) = read_entry(&mut archive, "fabric.mod.json") {
+        mods.extend(parse_fabric(&file, &content)?);
+    }
+    if let Some(content) = read_entry(&mut archive, "quilt.mod.json") {
+        mods.extend(parse_quilt(&file, &content)?);
+    }
+    let jar_version = read_entry(&mut archive, "META-INF/MANIFEST.MF").and_then(|manifest| {
+        manifest
+            .lines()
+            .find_map(|line| line.strip_prefix("Implementation-Version:"))
+            .map(|version| version.trim().to_string())
+    });
+    for (name, loader) in [("META-INF/neoforge.mods.toml", "neoforge"), ("META-INF/mods.toml", "forge")] {
+        if let Some(content) = read_entry(&mut archive, name) {
+            mods.extend(parse_mods_toml(&file, &content, loader, jar_version.as_deref())?);
+        }
+    }
+    Ok(mods)
+}
+
+fn read_entry(archive: &mut zip::ZipArchive<fs::File>, name: &str) -> Option<String> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    entry.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+/// Fabric and Quilt version requirements: a string or a list of alternatives
+fn requirement(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(range) => range.clone(),
+        serde_json::Value::Array(ranges) => {
+            let ranges: Vec<&str> = ranges.iter().filter_map(|range| range.as_str()).collect();
+            if ranges.is_empty() {
+                "*".to_string()
+            } else {
+                ranges.join(" || ")
+            }
+        }
+        _ => "*".to_string(),
+    }
+}
+
+pub fn parse_fabric(file: &str, content: &str) -> Result<Vec<JarMod>> {
+    let json: serde_json::Value = serde_json::from_str(content).context("Invalid fabric.mod.json")?;
+    let mod_id = json["id"].as_str().context("fabric.mod.json has no id")?.to_string();
+
+    let mut dependencies = Vec::new();
+    let mut minecraft = None;
+    for (key, kind) in [
+        ("depends", DependencyKind::Required),
+        ("recommends", DependencyKind::Optional),
+        ("breaks", DependencyKind::Breaks),
+        ("conflicts", DependencyKind::Conflicts),
+    ] {
+        let Some(entries) = json[key].as_object() else { continue };
+        for (id, versions) in entries {
+            let versions = requirement(versions);
+            if id == "minecraft" && kind == DependencyKind::Required {
+                minecraft = Some(versions);
+            } else {
+                dependencies.push(Dependency { mod_id: id.clone(), versions, kind });
+            }
+        }
+    }
+
+    let environment = match json["environment"].as_str() {
+        Some("client") => Some("client".to_string()),
+        Some("server") => Some("server".to_string()),
+        Some("*") => Some("both".to_string()),
+        _ => None,
+    };
+    Ok(vec![JarMod {
+        file: file.to_string(),
+        name: json["name"].as_str().unwrap_or(&mod_id).to_string(),
+        mod_id,
+        version: json["version"].as_str().unwrap_or_default().to_string(),
+        loader: "fabric".to_string(),
+        minecraft,
+        client_only: client_only(environment.as_deref(), &json["entrypoints"], "client", &["main", "server"]),
+        environment,
+        provides: string_list(&json["provides"]),
+        dependencies,
+    }])
+}
+
+pub fn parse_quilt(file: &str, content: &str) -> Result<Vec<JarMod>> {
+    let json: serde_json::Value = serde_json::from_str(content).context("Invalid quilt.mod.json")?;
+    let loader = &json["quilt_loader"];
+    let mod_id = loader["id"].as_str().context("quilt.mod.json has no id")?.to_string();
+
+    let mut dependencies = Vec::new();
+    let mut minecraft = None;
+    for (key, required) in [("depends", true), ("breaks", false)] {
+        let Some(entries) = loader[key].as_array() else { continue };
+        for entry in entries {
+            let (id, versions, optional) = match entry {
+                serde_json::Value::String(id) => (id.clone(), "*".to_string(), false),
+                serde_json::Value::Object(_) => (
+                    entry["id"].as_str().unwrap_or_default().to_string(),
+                    requirement(&entry["versions"]),
+                    entry["optional"].as_bool().unwrap_or(false),
+                ),
+                _ => continue,
+            };
+            let kind = match (required, optional) {
+                (true, false) => DependencyKind::Required,
+                (true, true) => DependencyKind::Optional,
+                (false, _) => DependencyKind::Breaks,
+            };
+            if id == "minecraft" && kind == DependencyKind::Required {
+                minecraft = Some(versions);
+            } else if !id.is_empty() {
+                dependencies.push(Dependency { mod_id: id, versions, kind });
+            }
+        }
+    }
+
+    let environment = match json["minecraft"]["environment"].as_str() {
+        Some("client") => Some("client".to_string()),
+        Some("dedicated_server") => Some("server".to_string()),
+        Some("*") => Some("both".to_string()),
+        _ => None,
+    };
+    Ok(vec![JarMod {
+        file: file.to_string(),
+        name: loader["metadata"]["name"].as_str().unwrap_or(&mod_id).to_string(),
+        mod_id,
+        version: loader["version"].as_str().unwrap_or_default().to_string(),
+        loader: "quilt".to_string(),
+        minecraft,
+        client_only: client_only(environment.as_deref(), &loader["entrypoints"], "client_init", &["init", "server_init"]),
+        environment,
+        provides: loader["provides"]
+            .as_array()
+            .map(|provides| {
+                provides
+                    .iter()
+                    .filter_map(|entry| entry.as_str().or_else(|| entry["id"].as_str()))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        dependencies,
+    }])
+}
+
+/// Whether a Fabric or Quilt mod only runs on the client: it says so, or,
+/// saying nothing, it only hooks into the client's entrypoint
+fn client_only(environment: Option<&str>, entrypoints: &serde_json::Value, client: &str, common: &[&str]) -> bool {
+    match environment {
+        Some(environment) => environment == "client",
+        None => {
+            // Quilt allows a single entrypoint instead of a list
+            let has = |name: &str| match &entrypoints[name] {
+                serde_json::Value::Array(entries) => !entries.is_empty(),
+                serde_json::Value::String(_) => true,
+                _ => false,
+            };
+            has(client) && !common.iter().any(|name| has(name))
+        }
+    }
+}
+
+fn string_list(value: &serde_json::Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|values| values.iter().filter_map(|value| value.as_str()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Forge and NeoForge `mods.toml`; `${file.jarVersion}` is taken from the jar's manifest
+pub fn parse_mods_toml(file: &str, content: &str, loader: &str, jar_version: Option<&str>) -> Result<Vec<JarMod>> {
+    let toml: toml::Value = toml::from_str(content).context("Invalid mods.toml")?;
+    let mods = toml.get("mods").and_then(|mods| mods.as_array()).cloned().unwrap_or_default();
+    // Forge 1.20.2+ lets a jar declare it is only for the client
+    let client_side_only = toml.get("clientSideOnly").and_then(|value| value.as_bool()).unwrap_or(false);
+
+    let mut jar_mods = Vec::new();
+    for entry in mods {
+        let Some(mod_id) = entry.get("modId").and_then(|id| id.as_str()) else { continue };
+        let mut version = entry.get("version").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        if version.contains("${file.jarVersion}") {
+            version = jar_version.unwrap_or_default().to_string();
+        }
+
+        let mut dependencies = Vec::new();
+        let mut minecraft = None;
+        let declared = toml
+            .get("dependencies")
+            .and_then(|dependencies| dependencies.get(mod_id))
+            .and_then(|dependencies| dependencies.as_array())
+            .cloned()
+            .unwrap_or_default();
+        for dependency in declared {
+            let Some(id) = dependency.get("modId").and_then(|id| id.as_str()) else { continue };
+            let versions = dependency.get("versionRange").and_then(|v| v.as_str()).unwrap_or("*").to_string();
+            // Forge marks dependencies `mandatory`, NeoForge gives them a `type`
+            let kind = match dependency.get("type").and_then(|t| t.as_str()).map(str::to_ascii_lowercase).as_deref() {
+                Some("required") => DependencyKind::Required,
+                Some("optional") => DependencyKind::Optional,
+                Some("incompatible") => DependencyKind::Breaks,
+                Some("discouraged") => DependencyKind::Conflicts,
+                _ if dependency.get("mandatory").and_then(|m| m.as_bool()).unwrap_or(true) => DependencyKind::Required,
+                _ => DependencyKind::Optional,
+            };
+            if id == "minecraft" && kind == DependencyKind::Required {
+                minecraft = Some(versions);
+            } else {
+                dependencies.push(Dependency { mod_id: id.to_string(), versions, kind });
+            }
+        }
+
+        jar_mods.push(JarMod {
+            file: file.to_string(),
+            name: entry.get("displayName").and_then(|n| n.as_str()).unwrap_or(mod_id).to_string(),
+            mod_id: mod_id.to_string(),
+            version,
+            loader: loader.to_string(),
+            minecraft,
+            environment: client_side_only.then(|| "client".to_string()),
+            client_only: client_side_only,
+            provides: Vec::new(),
+            dependencies,
+        });
+    }
+    Ok(jar_mods)
+}
+
+/// A version as far as it can be ordered: numeric components, then a pre-release tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Version {
+    parts: Vec<u64>,
+    pre: Option<String>,
+}
+
+impl Version {
+    /// `None` for versions that don't start with a number, such as snapshots like `23w13a`
+    fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches('v');
+        // Build metadata, as in `0.92.0+1.20.1`, doesn't take part in ordering
+        let version = version.split('+').next().unwrap_or_default();
+        let (release, pre) = match version.split_once('-') {
+            Some((release, pre)) => (release, Some(pre.to_string())),
+            None => (version, None),
+        };
+        let parts = release.split('.').map(|part| part.parse().ok()).collect::<Option<Vec<u64>>>()?;
+        Some(Self { parts, pre })
+    }
+
+    fn component(&self, index: usize) -> u64 {
+        self.parts.get(index).copied().unwrap_or(0)
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.parts.len().max(other.parts.len());
+        for index in 0..len {
+            match self.component(index).cmp(&other.component(index)) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            }
+        }
+        match (&self.pre, &other.pre) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a), Some(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Whether `version` is in `range`, in the Maven syntax of mods.toml
+/// (`[1.20,1.21)`) or the npm-like syntax of Fabric and Quilt
+/// (`>=1.20 <1.21`, `~1.20.1`, `1.20.x`, alternatives joined by `||`).
+/// `None` when either can't be interpreted.
+pub fn matches(version: &str, range: &str) -> Option<bool> {
+    let range = range.trim();
+    if range.is_empty() || range == "*" {
+        return Some(true);
+    }
+    let version = Version::parse(version)?;
+    if range.starts_with('[') || range.starts_with('(') {
+        return matches_maven(&version, range);
+    }
+
+    let mut unknown = false;
+    for alternative in range.split("||") {
+        match matches_all(&version, alternative) {
+            Some(true) => return Some(true),
+            Some(false) => {}
+            None => unknown = true,
+        }
+    }
+    if unknown {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+/// Space-separated predicates that must all hold
+fn matches_all(version: &Version, predicates: &str) -> Option<bool> {
+    let mut all = true;
+    for predicate in predicates.split_whitespace() {
+        all &= matches_predicate(version, predicate)?;
+    }
+    Some(all)
+}
+
+fn matches_predicate(version: &Version, predicate: &str) -> Option<bool> {
+    if predicate == "*" {
+        return Some(true);
+    }
+    let (operator, bound) = match predicate.find(|c: char| c.is_ascii_digit()) {
+        Some(index) => predicate.split_at(index),
+        None => return None,
+    };
+
+    // Wildcards: `1.20.x` matches every 1.20 version
+    if let Some(prefix) = bound.strip_suffix(".x").or_else(|| bound.strip_suffix(".*")) {
+        let prefix = Version::parse(prefix)?;
+        let matched = prefix.parts.iter().enumerate().all(|(index, part)| version.component(index) == *part);
+        return Some(matched);
+    }
+
+    let bound = Version::parse(bound)?;
+    let ordering = version.cmp(&bound);
+    Some(match operator {
+        ">=" => ordering != Ordering::Less,
+        ">" => ordering == Ordering::Greater,
+        "<=" => ordering != Ordering::Greater,
+        "<" => ordering == Ordering::Less,
+        "" | "=" => ordering == Ordering::Equal,
+        // Same minor version, at least the bound
+        "~" => ordering != Ordering::Less && version.component(0) == bound.component(0) && version.component(1) == bound.component(1),
+        // Same major version, at least the bound
+        "^" => ordering != Ordering::Less && version.component(0) == bound.component(0),
+        _ => return None,
+    })
+}
+
+/// Maven ranges such as `[1.20,1.21)` or `(,1.0],[1.2,)`; a version may be in any of them
+fn matches_maven(version: &Version, range: &str) -> Option<bool> {
+    let mut rest = range;
+    while !rest.is_empty() {
+        let end = rest.find([']', ')'])?;
+        let (spec, tail) = rest.split_at(end + 1);
+        let inclusive_start = spec.starts_with('[');
+        let inclusive_end = spec.ends_with(']');
+        let inner = &spec[1..spec.len() - 1];
+
+        let in_range = match inner.split_once(',') {
+            Some((low, high)) => {
+                let above = match low.trim() {
+                    "" => true,
+                    low => {
+                        let ordering = version.cmp(&Version::parse(low)?);
+                        ordering == Ordering::Greater || (inclusive_start && ordering == Ordering::Equal)
+                    }
+                };
+                let below = match high.trim() {
+                    "" => true,
+                    high => {
+                        let ordering = version.cmp(&Version::parse(high)?);
+                        ordering == Ordering::Less || (inclusive_end && ordering == Ordering::Equal)
+                    }
+                };
+                above && below
+            }
+            // `[1.20.1]` is exactly that version
+            None => version.cmp(&Version::parse(inner)?) == Ordering::Equal,
+        };
+        if in_range {
+            return Some(true);
+        }
+        rest = tail.trim_start_matches(',').trim();
+    }
+    Some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_ranges() {
+        assert_eq!(matches("1.20.1", ">=1.20 <1.21"), Some(true));
+        assert_eq!(matches("1.21", ">=1.20 <1.21"), Some(false));
+        assert_eq!(matches("1.20.4", "~1.20.1"), Some(true));
+        assert_eq!(matches("1.21.0", "~1.20.1"), Some(false));
+        assert_eq!(matches("1.20.2", "1.20.x"), Some(true));
+        assert_eq!(matches("1.19.2", "1.18.2 || 1.19.2"), Some(true));
+        assert_eq!(matches("0.92.0+1.20.1", ">=0.90.0"), Some(true));
+        assert_eq!(matches("1.20.1", "[1.20,1.21)"), Some(true));
+        assert_eq!(matches("1.21", "[1.20,1.21)"), Some(false));
+        assert_eq!(matches("47.2.0", "[47,)"), Some(true));
+        assert_eq!(matches("1.0", "(,1.0],[1.2,)"), Some(true));
+        assert_eq!(matches("1.1", "(,1.0],[1.2,)"), Some(false));
+        assert_eq!(matches("1.20-beta.1", ">=1.20"), Some(false));
+        assert_eq!(matches("23w13a", ">=1.20"), None);
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let fabric = r#"{
+            "id": "sodium", "version": "0.5.3", "name": "Sodium", "environment": "client",
+            "depends": { "minecraft": ["1.20", "1.20.1"], "fabricloader": ">=0.12" },
+            "breaks": { "optifabric": "*" }
+        }"#;
+        let sodium = &parse_fabric("sodium.jar", fabric).unwrap()[0];
+        assert_eq!(sodium.minecraft.as_deref(), Some("1.20 || 1.20.1"));
+        assert_eq!(sodium.environment.as_deref(), Some("client"));
+        assert!(sodium.client_only);
+        let minimap = r#"{ "id": "minimap", "entrypoints": { "client": ["a.Client"] } }"#;
+        assert!(parse_fabric("minimap.jar", minimap).unwrap()[0].client_only);
+        let lib = r#"{ "id": "lib", "entrypoints": { "main": ["a.Main"], "client": ["a.Client"] } }"#;
+        assert!(!parse_fabric("lib.jar", lib).unwrap()[0].client_only);
+        assert!(sodium.dependencies.iter().any(|d| d.mod_id == "optifabric" && d.kind == DependencyKind::Breaks));
+
+        let toml = r#"
+            modLoader = "javafml"
+            [[mods]]
+            modId = "create"
+            version = "${file.jarVersion}"
+            [[dependencies.create]]
+            modId = "minecraft"
+            mandatory = true
+            versionRange = "[1.20.1,1.20.2)"
+            [[dependencies.create]]
+            modId = "flywheel"
+            mandatory = true
+            versionRange = "[0.6.10,0.6.11)"
+        "#;
+        let create = &parse_mods_toml("create.jar", toml, "forge", Some("0.5.1")).unwrap()[0];
+        assert_eq!(create.version, "0.5.1");
+        assert_eq!(create.minecraft.as_deref(), Some("[1.20.1,1.20.2)"));
+        assert_eq!(create.dependencies[0].kind, DependencyKind::Required);
+    }
+}
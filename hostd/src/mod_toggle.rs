@@ -0,0 +1,84 @@
+//! Enabling and disabling a server's mods without deleting them
+//!
+//! A disabled mod's jar is renamed to `*.jar.disabled`, the suffix mod
+//! loaders skip and other launchers use too, and its record in the `mods`
+//! table is marked disabled. The download stays in place, so mods can be
+//! switched off one at a time to find the one crashing a server. Changes take
+//! effect the next time the server starts.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use std::path::Path;
+use tracing::info;
+
+use crate::config_revisions::server_dir;
+use crate::core::crash_loop::DISABLED_SUFFIX;
+use crate::database::{DatabaseManager, Mod};
+use crate::core::error_handler::NotFound;
+
+/// Enable or disable one of a server's mods, by record ID or jar name
+pub async fn set_enabled(database: &DatabaseManager, server_id: &str, mod_id: &str, enabled: bool) -> Result<Mod> {
+    let server = database
+        .get_server(server_id)
+        .await?
+        .ok_or_else(|| NotFound::new("Server"))?;
+    let mut record = database
+        .get_mods_by_server(server_id)
+        .await?
+        .into_iter()
+        .find(|record| record.id == mod_id || record.filename == mod_id)
+        .ok_or_else(|| anyhow!("Mod {} is not installed on this server", mod_id))?;
+
+    let mods_dir = server_dir(&server).join("mods");
+    let filename = record.filename.clone();
+    tokio::task::spawn_blocking(move || rename_jar(&mods_dir, &filename, enabled)).await??;
+
+    if record.enabled != enabled {
+        record.enabled = enabled;
+        record.updated_at = Utc::now();
+        database.update_mod(&record).await?;
+        info!("{} mod {} on server {}", if enabled { "Enabled" } else { "Disabled" }, record.filename, server.name);
+    }
+    Ok(record)
+}
+
+/// Rename `filename` in `mods_dir` to or from its disabled name. A jar
+/// already in the wanted state is left alone.
+fn rename_jar(mods_dir: &Path, filename: &str, enabled: bool) -> Result<()> {
+    let jar = mods_dir.join(filename);
+    let disabled = mods_dir.join(format!("{}{}", filename, DISABLED_SUFFIX));
+    let (from, to) = if enabled { (disabled, jar) } else { (jar, disabled) };
+    if to.exists() {
+        if from.exists() {
+            bail!("Both {} and {} exist; remove one first", filename, to.display());
+        }
+        return Ok(());
+    }
+    if !from.exists() {
+        bail!("{} is not in the mods folder", filename);
+    }
+    std::fs::rename(&from, &to)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rename_jar() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("sodium.jar"), b"jar").unwrap();
+
+        rename_jar(dir.path(), "sodium.jar", false).unwrap();
+        assert!(dir.path().join("sodium.jar.disabled").exists());
+        assert!(!dir.path().join("sodium.jar").exists());
+        // Disabling twice is fine
+        rename_jar(dir.path(), "sodium.jar", false).unwrap();
+
+        rename_jar(dir.path(), "sodium.jar", true).unwrap();
+        assert!(dir.path().join("sodium.jar").exists());
+        assert!(rename_jar(dir.path(), "missing.jar", true).is_err());
+    }
+}
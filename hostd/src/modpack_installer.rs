@@ -2,7 +2,7 @@ use crate::mod_manager::{ModInfo, ModManager};
 use crate::external_apis::mod_provider::{ModProvider, ProviderType};
 use crate::security::{PathSanitizer, SecureExtractor};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::error::Error;
 use std::io::Read;
@@ -563,17 +563,13 @@ impl ModpackInstaller {
 
     /// Download a file from URL
     async fn download_file(&self, url: &str, file_path: &Path) -> Result<(), Box<dyn Error>> {
-        let response = reqwest::get(url).await?;
-        let content = response.bytes().await?;
-        fs::write(file_path, content).await?;
+        crate::downloads::shared().fetch_to(&crate::downloads::Download::new(url), file_path).await?;
         Ok(())
     }
 
     /// Download file content from URL
     async fn download_file_content(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
-        let response = reqwest::get(url).await?;
-        let content = response.bytes().await?;
-        Ok(content.to_vec())
+        Ok(crate::downloads::shared().fetch(&crate::downloads::Download::new(url)).await?)
     }
 
     /// Verify file hash
@@ -870,6 +866,82 @@ impl ParallelDownloader {
     }
 }
 
+/// Applying a saved modpack to a server, run as a `modpack_install` job
+pub struct ModpackApplyJob {
+    pub database: Arc<crate::database::DatabaseManager>,
+    pub modpack_id: String,
+    pub server_id: String,
+    pub install_client_mods: bool,
+}
+
+#[async_trait::async_trait]
+impl crate::jobs::Job for ModpackApplyJob {
+    fn kind(&self) -> &'static str {
+        "modpack_install"
+    }
+
+    fn server_id(&self) -> Option<String> {
+        Some(self.server_id.clone())
+    }
+
+    fn metadata(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({ "modpack_id": self.modpack_id }))
+    }
+
+    async fn run(self: Box<Self>, ctx: &crate::jobs::JobContext) -> anyhow::Result<Option<String>> {
+        let modpack = self
+            .database
+            .get_modpack(&self.modpack_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Modpack {} not found", self.modpack_id))?;
+
+        let server = self
+            .database
+            .get_server(&self.server_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Server {} not found", self.server_id))?;
+
+        let client_mods: Vec<String> = serde_json::from_str(&modpack.client_mods).unwrap_or_default();
+        let mut mods: Vec<String> = serde_json::from_str(&modpack.server_mods).unwrap_or_default();
+        if self.install_client_mods {
+            mods.extend(client_mods.iter().cloned());
+        }
+
+        // TODO: Download each mod once providers are configured with API keys
+        for (index, mod_id) in mods.iter().enumerate() {
+            if ctx.is_cancelled() {
+                return Ok(None);
+            }
+            let message = format!("Resolving {} ({}/{})", mod_id, index + 1, mods.len());
+            ctx.progress(index as f64 / mods.len() as f64, "resolve", Some(&message)).await;
+        }
+
+        // Client-only mods crash a dedicated server, so none stay in mods/
+        // unless the pack's client mods were asked for on the server too
+        let client_mods: HashSet<String> = client_mods.into_iter().collect();
+        let (provider_client, keep) = if self.install_client_mods {
+            (HashSet::new(), client_mods)
+        } else {
+            (client_mods, HashSet::new())
+        };
+        let quarantined = crate::client_mods::quarantine(
+            std::path::Path::new(&server.server_directory),
+            &provider_client,
+            &keep,
+        )
+        .await?;
+        let mut summary = format!("Applied modpack {} ({} mods)", modpack.name, mods.len());
+        if !quarantined.is_empty() {
+            summary.push_str(&format!(
+                "; moved {} client-only mods to {}",
+                quarantined.len(),
+                crate::client_mods::CLIENT_MODS_DIR
+            ));
+        }
+        Ok(Some(summary))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -882,7 +954,7 @@ mod tests {
         let mods_dir = temp_dir.path().join("mods");
         let temp_dir_path = temp_dir.path().join("temp");
         
-        let mod_manager = ModManager::new();
+        let mod_manager = ModManager::new(mods_dir.clone());
         let providers = HashMap::new();
         
         let installer = ModpackInstaller::new(
@@ -0,0 +1,402 @@
+//! Multi-node mode: a controller hostd runs servers on other machines through
+//! the hostd agents there. An agent is registered with its URL and an API
+//! token issued on it, and every [`HEARTBEAT_INTERVAL`] the controller fetches
+//! each agent's server list, which `GET /api/servers` merges with the local
+//! servers. Requests for a server on an agent (`/api/servers/{id}/...`) are
+//! forwarded to it by [`proxy_middleware`], so server CRUD, the console and
+//! metrics work the same whichever node runs the server.
+
+use anyhow::{anyhow, bail, Result};
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::api::ServerInfo;
+use crate::database::{DatabaseManager, Node};
+use crate::core::error_handler::{AppError, NotFound};
+
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// Timeout of heartbeats; forwarded requests may stream for longer
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(10);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A node and what its last heartbeat found
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeStatus {
+    #[serde(flatten)]
+    pub node: Node,
+    pub online: bool,
+    pub last_seen: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub servers: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Heartbeat {
+    online: bool,
+    last_seen: Option<DateTime<Utc>>,
+    error: Option<String>,
+    servers: Vec<ServerInfo>,
+}
+
+/// The `ApiResponse` an agent answers with
+#[derive(Debug, Deserialize)]
+struct AgentResponse<T> {
+    success: bool,
+    data: Option<T>,
+    error: Option<String>,
+}
+
+pub struct NodeManager {
+    database: Arc<DatabaseManager>,
+    client: reqwest::Client,
+    /// Last heartbeat by node id
+    heartbeats: RwLock<HashMap<String, Heartbeat>>,
+}
+
+impl NodeManager {
+    pub fn new(database: Arc<DatabaseManager>) -> Self {
+        Self {
+            database,
+            client: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            heartbeats: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Check every node now and every [`HEARTBEAT_INTERVAL`]
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting node heartbeats");
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+            let nodes = match self.database.get_nodes().await {
+                Ok(nodes) => nodes,
+                Err(e) => {
+                    warn!("Failed to list nodes: {}", e);
+                    continue;
+                }
+            };
+            for node in nodes.iter().filter(|node| node.enabled) {
+                self.heartbeat(node).await;
+            }
+        }
+    }
+
+    pub async fn nodes(&self) -> Result<Vec<NodeStatus>> {
+        let nodes = self.database.get_nodes().await?;
+        let heartbeats = self.heartbeats.read().await;
+        Ok(nodes
+            .into_iter()
+            .map(|node| {
+                let heartbeat = heartbeats.get(&node.id).cloned().unwrap_or_default();
+                status(node, &heartbeat)
+            })
+            .collect())
+    }
+
+    /// Register an agent once it accepts the token
+    pub async fn register(&self, name: &str, url: &str, token: &str) -> Result<NodeStatus> {
+        let url = normalize_url(url)?;
+        if name.trim().is_empty() {
+            bail!("A node needs a name");
+        }
+        if self.database.get_nodes().await?.iter().any(|node| node.url == url) {
+            bail!("A node with URL {} is already registered", url);
+        }
+        let now = Utc::now();
+        let node = Node {
+            id: Uuid::new_v4().to_string(),
+            name: name.trim().to_string(),
+            url,
+            token: token.to_string(),
+            enabled: true,
+            created_at: now,
+            updated_at: now,
+        };
+        self.fetch_servers(&node)
+            .await
+            .map_err(|e| anyhow!("Could not reach the agent at {}: {}", node.url, e))?;
+        self.database.save_node(&node).await?;
+        info!("Registered node {} at {}", node.name, node.url);
+        Ok(self.heartbeat(&node).await)
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<bool> {
+        self.heartbeats.write().await.remove(id);
+        self.database.delete_node(id).await
+    }
+
+    /// Check a node now
+    pub async fn refresh(&self, id: &str) -> Result<NodeStatus> {
+        let node = self.node(id).await?;
+        Ok(self.heartbeat(&node).await)
+    }
+
+    pub async fn node(&self, id: &str) -> Result<Node> {
+        self.database.get_node(id).await?.ok_or_else(|| NotFound::new("Node").into())
+    }
+
+    async fn heartbeat(&self, node: &Node) -> NodeStatus {
+        let previous = self.heartbeats.read().await.get(&node.id).cloned().unwrap_or_default();
+
+        let heartbeat = match self.fetch_servers(node).await {
+            Ok(servers) => {
+                if !previous.online {
+                    info!("Node {} is online with {} servers", node.name, servers.len());
+                }
+                Heartbeat { online: true, last_seen: Some(Utc::now()), error: None, servers }
+            }
+            Err(e) => {
+                if previous.online || previous.last_seen.is_none() {
+                    warn!("Node {} is unreachable: {}", node.name, e);
+                }
+                // Keep the servers so they are still listed, and proxied once the node is back
+                Heartbeat { online: false, error: Some(e.to_string()), ..previous }
+            }
+        };
+        let status = status(node.clone(), &heartbeat);
+        self.heartbeats.write().await.insert(node.id.clone(), heartbeat);
+        status
+    }
+
+    /// The agent's own servers, tagged with the node
+    async fn fetch_servers(&self, node: &Node) -> Result<Vec<ServerInfo>> {
+        let response = self
+            .client
+            .get(format!("{}/api/servers", node.url))
+            .bearer_auth(&node.token)
+            .timeout(HEARTBEAT_TIMEOUT)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            bail!("The agent answered {}", status);
+        }
+        let response: AgentResponse<Vec<ServerInfo>> = response.json().await?;
+        if !response.success {
+            bail!(response.error.unwrap_or_else(|| "The agent did not list its servers".to_string()));
+        }
+        // An agent that controls nodes of its own lists their servers too
+        Ok(response
+            .data
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|server| server.node_id.is_none())
+            .map(|server| ServerInfo { node_id: Some(node.id.clone()), ..server })
+            .collect())
+    }
+
+    /// Servers on every node, as of their last heartbeat
+    pub async fn remote_servers(&self) -> Vec<ServerInfo> {
+        self.heartbeats
+            .read()
+            .await
+            .values()
+            .flat_map(|heartbeat| heartbeat.servers.iter().cloned())
+            .collect()
+    }
+
+    /// The node running a server, if it runs on one
+    pub async fn node_for_server(&self, server_id: &str) -> Option<Node> {
+        let node_id = self
+            .heartbeats
+            .read()
+            .await
+            .iter()
+            .find(|(_, heartbeat)| heartbeat.servers.iter().any(|server| server.id == server_id))
+            .map(|(node_id, _)| node_id.clone())?;
+        self.database.get_node(&node_id).await.ok().flatten().filter(|node| node.enabled)
+    }
+
+    /// Send a request to an agent with the node's token
+    pub async fn forward(
+        &self,
+        node: &Node,
+        method: &str,
+        path_and_query: &str,
+        content_type: Option<&str>,
+        body: impl Into<reqwest::Body>,
+    ) -> Result<reqwest::Response> {
+        let method = reqwest::Method::from_bytes(method.as_bytes())?;
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", node.url, agent_path(path_and_query)))
+            .bearer_auth(&node.token)
+            .body(body);
+        if let Some(content_type) = content_type {
+            request = request.header(reqwest::header::CONTENT_TYPE, content_type);
+        }
+        Ok(request.send().await?)
+    }
+
+    /// Send a request to an agent and take the data it answered with
+    pub async fn call<T: DeserializeOwned>(
+        &self,
+        node: &Node,
+        method: &str,
+        path_and_query: &str,
+        content_type: Option<&str>,
+        body: impl Into<reqwest::Body>,
+    ) -> Result<T> {
+        let response = self.forward(node, method, path_and_query, content_type, body).await?;
+        let status = response.status();
+        let response: AgentResponse<T> = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("{} answered {}: {}", node.name, status, e))?;
+        match response.data {
+            Some(data) if response.success => Ok(data),
+            _ => bail!(response.error.unwrap_or_else(|| format!("{} answered {}", node.name, status))),
+        }
+    }
+
+    /// Create a server on a node from a `POST /api/servers` body
+    pub async fn create_server(&self, node_id: &str, body: axum::body::Bytes) -> Result<Response> {
+        let node = self.node(node_id).await?;
+        let response = self.forward(&node, "POST", "/api/servers", Some("application/json"), body).await?;
+        self.heartbeat(&node).await;
+        Ok(agent_response(response))
+    }
+}
+
+fn status(node: Node, heartbeat: &Heartbeat) -> NodeStatus {
+    NodeStatus {
+        node,
+        online: heartbeat.online,
+        last_seen: heartbeat.last_seen,
+        error: heartbeat.error.clone(),
+        servers: heartbeat.servers.len(),
+    }
+}
+
+/// Base URL of an agent, without a trailing slash
+fn normalize_url(url: &str) -> Result<String> {
+    let url = url.trim().trim_end_matches('/');
+    let parsed = reqwest::Url::parse(url).map_err(|e| anyhow!("Invalid node URL {}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        bail!("Node URLs need an http or https scheme and a host");
+    }
+    Ok(url.to_string())
+}
+
+/// The id in `/api/servers/{id}/...`
+fn server_id(path: &str) -> Option<&str> {
+    let id = path.strip_prefix("/api/servers/")?.split('/').next()?;
+    (!id.is_empty()).then_some(id)
+}
+
+/// The path sent to an agent: the controller's `access_token` is not passed on
+fn agent_path(path_and_query: &str) -> String {
+    let Some((path, query)) = path_and_query.split_once('?') else {
+        return path_and_query.to_string();
+    };
+    let query: Vec<&str> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty() && pair.split('=').next() != Some("access_token"))
+        .collect();
+    if query.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}?{}", path, query.join("&"))
+    }
+}
+
+/// An agent's answer, streamed back as is
+fn agent_response(response: reqwest::Response) -> Response {
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let content_type = response.headers().get(reqwest::header::CONTENT_TYPE).cloned();
+    let chunks = futures::stream::unfold(Some(response), |response| async move {
+        let mut response = response?;
+        match response.chunk().await {
+            Ok(Some(chunk)) => Some((Ok(chunk), Some(response))),
+            Ok(None) => None,
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    let mut builder = Response::builder().status(status);
+    if let Some(content_type) = content_type.as_ref().and_then(|value| value.to_str().ok()) {
+        builder = builder.header(header::CONTENT_TYPE, content_type);
+    }
+    builder
+        .body(Body::from_stream(chunks))
+        .unwrap_or_else(|_| StatusCode::BAD_GATEWAY.into_response())
+}
+
+/// Forward requests for servers on a remote node to its agent. Runs after
+/// authentication, so the caller's permissions on the server are checked here
+/// and the agent only sees the node's token.
+pub async fn proxy_middleware(State(nodes): State<Arc<NodeManager>>, request: Request, next: Next) -> Response {
+    let Some(id) = server_id(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let Some(node) = nodes.node_for_server(id).await else {
+        return next.run(request).await;
+    };
+
+    // Streamed through, so uploads are not held in memory; the agent applies
+    // its own size limits
+    let (parts, body) = request.into_parts();
+    let body = reqwest::Body::wrap_stream(body.into_data_stream());
+    let path_and_query = parts.uri.path_and_query().map_or(parts.uri.path(), |p| p.as_str());
+    let content_type = parts.headers.get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok());
+    match nodes.forward(&node, parts.method.as_str(), path_and_query, content_type, body).await {
+        Ok(response) => {
+            // Deletes and renames show up in the server list right away
+            if parts.method != axum::http::Method::GET {
+                let nodes = nodes.clone();
+                tokio::spawn(async move {
+                    nodes.heartbeat(&node).await;
+                });
+            }
+            agent_response(response)
+        }
+        Err(e) => AppError::NetworkError {
+            message: format!("Node {} is unreachable: {}", node.name, e),
+            endpoint: node.url.clone(),
+            status_code: None,
+        }
+        .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_id() {
+        assert_eq!(server_id("/api/servers/abc"), Some("abc"));
+        assert_eq!(server_id("/api/servers/abc/console"), Some("abc"));
+        assert_eq!(server_id("/api/servers"), None);
+        assert_eq!(server_id("/api/servers/"), None);
+        assert_eq!(server_id("/api/nodes/abc"), None);
+    }
+
+    #[test]
+    fn test_agent_path() {
+        assert_eq!(agent_path("/api/servers/a/metrics"), "/api/servers/a/metrics");
+        assert_eq!(agent_path("/api/servers/a/events?access_token=jwt"), "/api/servers/a/events");
+        assert_eq!(agent_path("/api/servers/a/logs?lines=50&access_token=jwt"), "/api/servers/a/logs?lines=50");
+    }
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(normalize_url(" https://node-2:52100/ ").unwrap(), "https://node-2:52100");
+        assert!(normalize_url("ftp://node-2").is_err());
+        assert!(normalize_url("node-2").is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Delivery of alert notifications to Discord and Slack webhooks, generic HTTP
+//! webhooks and email over SMTP.
+
+use anyhow::{anyhow, bail, Context, Result};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::alerts::AlertNotification;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where a channel sends its notifications
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChannelTarget {
+    Discord { webhook_url: String },
+    Slack { webhook_url: String },
+    /// The notification is POSTed as JSON
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    Email(EmailSettings),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmailSettings {
+    pub smtp_host: String,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    /// Never returned by the API
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// Upgrade a plain connection, usually on port 587
+    #[default]
+    Starttls,
+    /// TLS from the start, usually on port 465
+    Tls,
+    /// No encryption, for a relay on the local network
+    None,
+}
+
+impl ChannelTarget {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Discord { .. } => "discord",
+            Self::Slack { .. } => "slack",
+            Self::Webhook { .. } => "webhook",
+            Self::Email(_) => "email",
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Discord { webhook_url } | Self::Slack { webhook_url } => validate_url(webhook_url),
+            Self::Webhook { url, .. } => validate_url(url),
+            Self::Email(email) => {
+                if email.smtp_host.trim().is_empty() {
+                    bail!("SMTP host is required");
+                }
+                email.from.parse::<Mailbox>().with_context(|| format!("Invalid sender address '{}'", email.from))?;
+                if email.to.is_empty() {
+                    bail!("At least one recipient is required");
+                }
+                for to in &email.to {
+                    to.parse::<Mailbox>().with_context(|| format!("Invalid recipient address '{}'", to))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The target without its SMTP password, for API responses
+    pub fn redacted(&self) -> Self {
+        match self {
+            Self::Email(email) => Self::Email(EmailSettings { password: None, ..email.clone() }),
+            other => other.clone(),
+        }
+    }
+
+    /// Keep `previous`'s SMTP password when an update leaves it out
+    pub fn keep_password(&mut self, previous: &ChannelTarget) {
+        if let (Self::Email(email), Self::Email(previous)) = (self, previous) {
+            if email.password.is_none() && email.username == previous.username {
+                email.password = previous.password.clone();
+            }
+        }
+    }
+}
+
+pub fn validate_url(url: &str) -> Result<()> {
+    let parsed = reqwest::Url::parse(url).with_context(|| format!("Invalid URL '{}'", url))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        bail!("URL must use http or https: {}", url);
+    }
+    Ok(())
+}
+
+/// Send `notification` through one channel
+pub async fn send(target: &ChannelTarget, notification: &AlertNotification) -> Result<()> {
+    match target {
+        ChannelTarget::Discord { webhook_url } => post_json(webhook_url, &discord_payload(notification), &HashMap::new()).await,
+        ChannelTarget::Slack { webhook_url } => post_json(webhook_url, &slack_payload(notification), &HashMap::new()).await,
+        ChannelTarget::Webhook { url, headers } => post_json(url, &serde_json::to_value(notification)?, headers).await,
+        ChannelTarget::Email(email) => send_email(email, notification).await,
+    }
+}
+
+async fn post_json(url: &str, body: &serde_json::Value, headers: &HashMap<String, String>) -> Result<()> {
+    let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+    let mut request = client.post(url).json(body);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        bail!("{} returned {}", url, response.status());
+    }
+    Ok(())
+}
+
+fn discord_payload(notification: &AlertNotification) -> serde_json::Value {
+    let color = match notification.level.as_str() {
+        "critical" => 0xE74C3C,
+        "warning" => 0xF1C40F,
+        _ => 0x3498DB,
+    };
+    let mut fields = vec![serde_json::json!({ "name": "Rule", "value": notification.rule_name, "inline": true })];
+    if let Some(server) = notification.server_name.as_ref().or(notification.server_id.as_ref()) {
+        fields.push(serde_json::json!({ "name": "Server", "value": server, "inline": true }));
+    }
+    serde_json::json!({
+        "username": "Guardian",
+        "embeds": [{
+            "title": notification.title,
+            "description": notification.message,
+            "color": color,
+            "timestamp": notification.triggered_at.to_rfc3339(),
+            "fields": fields,
+        }]
+    })
+}
+
+fn slack_payload(notification: &AlertNotification) -> serde_json::Value {
+    let server = notification.server_name.as_ref().or(notification.server_id.as_ref());
+    let context = match server {
+        Some(server) => format!("{} · {}", notification.rule_name, server),
+        None => notification.rule_name.clone(),
+    };
+    serde_json::json!({
+        "text": format!("{}: {}", notification.title, notification.message),
+        "blocks": [
+            { "type": "section", "text": { "type": "mrkdwn", "text": format!("*{}*\n{}", notification.title, notification.message) } },
+            { "type": "context", "elements": [{ "type": "mrkdwn", "text": context }] },
+        ]
+    })
+}
+
+async fn send_email(email: &EmailSettings, notification: &AlertNotification) -> Result<()> {
+    let mut message = Message::builder()
+        .from(email.from.parse::<Mailbox>()?)
+        .subject(format!("[Guardian] {}", notification.title));
+    for to in &email.to {
+        message = message.to(to.parse::<Mailbox>()?);
+    }
+    let mut body = format!("{}\n\nRule: {}\n", notification.message, notification.rule_name);
+    if let Some(server) = notification.server_name.as_ref().or(notification.server_id.as_ref()) {
+        body.push_str(&format!("Server: {}\n", server));
+    }
+    body.push_str(&format!("Time: {}\n", notification.triggered_at.to_rfc3339()));
+    let message = message.body(body)?;
+
+    let builder = match email.security {
+        SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&email.smtp_host)?,
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&email.smtp_host)?,
+        SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&email.smtp_host),
+    };
+    let mut builder = builder.port(email.smtp_port).timeout(Some(REQUEST_TIMEOUT));
+    if let Some(username) = &email.username {
+        builder = builder.credentials(Credentials::new(username.clone(), email.password.clone().unwrap_or_default()));
+    }
+    builder
+        .build()
+        .send(message)
+        .await
+        .map_err(|e| anyhow!("SMTP delivery to {} failed: {}", email.smtp_host, e))?;
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! OpenAPI description of the hostd API, generated from the handlers'
+//! `#[utoipa::path]` annotations and the request and response types.
+//! Served as `/api/openapi.json`, with Swagger UI at `/api/docs`.
+
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::api;
+
+pub const SPEC_PATH: &str = "/api/openapi.json";
+pub const DOCS_PATH: &str = "/api/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Guardian hostd API", description = "Manage Minecraft servers run by Guardian"),
+    paths(
+        api::health_check,
+        api::get_servers,
+        api::create_server,
+        api::get_server,
+        api::update_server,
+        api::delete_server,
+        api::get_server_health,
+        api::start_server,
+        api::stop_server,
+        api::restart_server,
+        api::send_server_command,
+        api::bulk_server_action,
+    ),
+    components(schemas(
+        crate::core::error_handler::ErrorResponse,
+        crate::core::error_handler::FieldError,
+    )),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    tags(
+        (name = "servers", description = "Server lifecycle and configuration"),
+        (name = "system", description = "Health of hostd itself"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the JWT or API token every route but login and health checks needs
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
+/// Routes serving the spec and Swagger UI
+pub fn docs() -> SwaggerUi {
+    SwaggerUi::new(DOCS_PATH).url(SPEC_PATH, ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_covers_annotated_routes() {
+        let spec = ApiDoc::openapi();
+        for path in ["/api/health", "/api/servers", "/api/servers/{id}", "/api/servers/{id}/command"] {
+            assert!(spec.paths.paths.contains_key(path), "{} is missing", path);
+        }
+        let schemas = spec.components.expect("components").schemas;
+        assert!(schemas.contains_key("CreateServerRequest"));
+        assert!(schemas.contains_key("ErrorResponse"));
+    }
+}
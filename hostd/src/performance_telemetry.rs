@@ -1,5 +1,6 @@
+use chrono::{DateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 use std::time::Duration;
 use tokio::fs;
@@ -7,6 +8,8 @@ use tokio::time::sleep;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::database::{DatabaseManager, ServerMetric};
+
 /// Performance metrics for a server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceMetrics {
@@ -28,11 +31,171 @@ pub struct PerformanceMetrics {
     pub entity_count: u32,          // Number of entities
 }
 
+/// Granularity of stored metrics history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetricsResolution {
+    #[serde(rename = "raw")]
+    Raw,
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+}
+
+impl MetricsResolution {
+    pub const ROLLUPS: [MetricsResolution; 3] = [Self::OneMinute, Self::FiveMinutes, Self::OneHour];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::OneMinute => "1m",
+            Self::FiveMinutes => "5m",
+            Self::OneHour => "1h",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "raw" => Some(Self::Raw),
+            "1m" => Some(Self::OneMinute),
+            "5m" => Some(Self::FiveMinutes),
+            "1h" => Some(Self::OneHour),
+            _ => None,
+        }
+    }
+
+    /// Rollup bucket length in seconds, 0 for raw samples
+    pub fn bucket_secs(&self) -> i64 {
+        match self {
+            Self::Raw => 0,
+            Self::OneMinute => 60,
+            Self::FiveMinutes => 300,
+            Self::OneHour => 3600,
+        }
+    }
+
+    /// Coarsest resolution that still gives a detailed chart over `span`
+    pub fn for_span(span: chrono::Duration) -> Self {
+        if span <= chrono::Duration::hours(2) {
+            Self::Raw
+        } else if span <= chrono::Duration::days(1) {
+            Self::OneMinute
+        } else if span <= chrono::Duration::days(7) {
+            Self::FiveMinutes
+        } else {
+            Self::OneHour
+        }
+    }
+}
+
+/// How long each resolution of metrics history is kept
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricsRetention {
+    pub raw: chrono::Duration,
+    pub one_minute: chrono::Duration,
+    pub five_minutes: chrono::Duration,
+    pub one_hour: chrono::Duration,
+}
+
+impl Default for MetricsRetention {
+    fn default() -> Self {
+        Self::with_days(365)
+    }
+}
+
+impl MetricsRetention {
+    /// Keep hourly rollups for `days`, finer resolutions for shorter periods within that
+    pub fn with_days(days: u32) -> Self {
+        let limit = chrono::Duration::days(days.max(1) as i64);
+        Self {
+            raw: chrono::Duration::days(1).min(limit),
+            one_minute: chrono::Duration::days(7).min(limit),
+            five_minutes: chrono::Duration::days(30).min(limit),
+            one_hour: limit,
+        }
+    }
+
+    pub fn for_resolution(&self, resolution: MetricsResolution) -> chrono::Duration {
+        match resolution {
+            MetricsResolution::Raw => self.raw,
+            MetricsResolution::OneMinute => self.one_minute,
+            MetricsResolution::FiveMinutes => self.five_minutes,
+            MetricsResolution::OneHour => self.one_hour,
+        }
+    }
+}
+
+impl From<&PerformanceMetrics> for ServerMetric {
+    fn from(metrics: &PerformanceMetrics) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            server_id: metrics.server_id.clone(),
+            resolution: MetricsResolution::Raw.as_str().to_string(),
+            tps: metrics.tps as f64,
+            tick_p95: metrics.tick_ms as f64,
+            heap_mb: (metrics.memory_used / (1024 * 1024)) as u32,
+            players_online: metrics.player_count,
+            gpu_queue_ms: 0.0,
+            cpu_usage: metrics.cpu_usage as f64,
+            memory_usage: metrics.memory_used as i64,
+            disk_usage: metrics.disk_usage as i64,
+            network_in: metrics.network_in as i64,
+            network_out: metrics.network_out as i64,
+            timestamp: Utc.timestamp_opt(metrics.timestamp as i64, 0).single().unwrap_or_else(Utc::now),
+        }
+    }
+}
+
+/// Average `samples` into one rollup per server and bucket, skipping buckets that
+/// have not ended by `until`. Player count is the bucket's peak, tick time its worst.
+pub fn roll_up(samples: &[ServerMetric], resolution: MetricsResolution, until: DateTime<Utc>) -> Vec<ServerMetric> {
+    let bucket_secs = resolution.bucket_secs();
+    if bucket_secs == 0 {
+        return Vec::new();
+    }
+
+    let mut buckets: BTreeMap<(&str, i64), Vec<&ServerMetric>> = BTreeMap::new();
+    for sample in samples {
+        let start = sample.timestamp.timestamp().div_euclid(bucket_secs) * bucket_secs;
+        if start + bucket_secs <= until.timestamp() {
+            buckets.entry((sample.server_id.as_str(), start)).or_default().push(sample);
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|((server_id, start), samples)| {
+            let count = samples.len() as f64;
+            let avg = |value: fn(&ServerMetric) -> f64| samples.iter().map(|s| value(s)).sum::<f64>() / count;
+            ServerMetric {
+                id: uuid::Uuid::new_v4().to_string(),
+                server_id: server_id.to_string(),
+                resolution: resolution.as_str().to_string(),
+                tps: avg(|s| s.tps),
+                tick_p95: samples.iter().map(|s| s.tick_p95).fold(0.0, f64::max),
+                heap_mb: avg(|s| s.heap_mb as f64).round() as u32,
+                players_online: samples.iter().map(|s| s.players_online).max().unwrap_or(0),
+                gpu_queue_ms: avg(|s| s.gpu_queue_ms),
+                cpu_usage: avg(|s| s.cpu_usage),
+                memory_usage: avg(|s| s.memory_usage as f64).round() as i64,
+                disk_usage: avg(|s| s.disk_usage as f64).round() as i64,
+                network_in: avg(|s| s.network_in as f64).round() as i64,
+                network_out: avg(|s| s.network_out as f64).round() as i64,
+                timestamp: Utc.timestamp_opt(start, 0).single().unwrap_or_else(Utc::now),
+            }
+        })
+        .collect()
+}
+
 /// Performance telemetry collector
 pub struct PerformanceTelemetry {
     metrics_history: Arc<Mutex<HashMap<String, Vec<PerformanceMetrics>>>>,
     collection_interval: Duration,
     is_running: Arc<Mutex<bool>>,
+    database: Option<Arc<DatabaseManager>>,
+    retention: MetricsRetention,
 }
 
 impl PerformanceTelemetry {
@@ -41,9 +204,18 @@ impl PerformanceTelemetry {
             metrics_history: Arc::new(Mutex::new(HashMap::new())),
             collection_interval,
             is_running: Arc::new(Mutex::new(false)),
+            database: None,
+            retention: MetricsRetention::default(),
         }
     }
 
+    /// Persist every sample to `server_metrics`, with rollups and retention
+    pub fn with_database(mut self, database: Arc<DatabaseManager>, retention: MetricsRetention) -> Self {
+        self.database = Some(database);
+        self.retention = retention;
+        self
+    }
+
     /// Start collecting performance metrics for all servers
     pub async fn start_collection(&self, servers_path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let mut is_running = self.is_running.lock().await;
@@ -58,10 +230,27 @@ impl PerformanceTelemetry {
         let is_running = self.is_running.clone();
         let servers_path = servers_path.to_path_buf();
 
+        let database = self.database.clone();
+        let retention = self.retention;
+
         tokio::spawn(async move {
+            let mut last_rollup: Option<i64> = None;
             while *is_running.lock().await {
-                if let Err(e) = Self::collect_metrics_for_all_servers(&metrics_history, &servers_path).await {
-                    eprintln!("Error collecting performance metrics: {}", e);
+                match Self::collect_metrics_for_all_servers(&metrics_history, &servers_path).await {
+                    Ok(samples) => {
+                        if let Some(database) = &database {
+                            Self::persist_samples(database, &samples).await;
+                            // Rollups only change once a minute bucket ends
+                            let minute = Utc::now().timestamp() / 60;
+                            if last_rollup != Some(minute) {
+                                last_rollup = Some(minute);
+                                if let Err(e) = Self::roll_up_and_prune(database, &retention).await {
+                                    tracing::warn!("Failed to roll up metrics history: {}", e);
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("Error collecting performance metrics: {}", e),
                 }
                 sleep(collection_interval).await;
             }
@@ -76,13 +265,59 @@ impl PerformanceTelemetry {
         *is_running = false;
     }
 
-    /// Collect metrics for all servers
+    /// Stop collecting and write the rollups of buckets that ended since the
+    /// last pass, so a shutdown doesn't leave a gap in the history
+    pub async fn flush(&self) {
+        self.stop_collection().await;
+        if let Some(database) = &self.database {
+            if let Err(e) = Self::roll_up_and_prune(database, &self.retention).await {
+                tracing::warn!("Failed to roll up metrics history: {}", e);
+            }
+        }
+    }
+
+    async fn persist_samples(database: &DatabaseManager, samples: &[PerformanceMetrics]) {
+        for sample in samples {
+            // Fails for server directories with no server record
+            if let Err(e) = database.insert_server_metric(&ServerMetric::from(sample)).await {
+                tracing::debug!("Failed to store metrics for {}: {}", sample.server_id, e);
+            }
+        }
+    }
+
+    /// Write rollups for buckets that ended recently and drop history past its retention
+    async fn roll_up_and_prune(database: &DatabaseManager, retention: &MetricsRetention) -> anyhow::Result<()> {
+        let now = Utc::now();
+        for resolution in MetricsResolution::ROLLUPS {
+            // Two buckets back covers the one that just ended even if a cycle ran late
+            let since = now - chrono::Duration::seconds(resolution.bucket_secs() * 2);
+            let samples = database
+                .get_server_metrics_since(None, MetricsResolution::Raw.as_str(), since)
+                .await?;
+            for rollup in roll_up(&samples, resolution, now) {
+                database.insert_server_metric(&rollup).await?;
+            }
+        }
+
+        for resolution in [MetricsResolution::Raw].into_iter().chain(MetricsResolution::ROLLUPS) {
+            let pruned = database
+                .prune_server_metrics(resolution.as_str(), now - retention.for_resolution(resolution))
+                .await?;
+            if pruned > 0 {
+                tracing::debug!("Pruned {} {} metrics past retention", pruned, resolution.as_str());
+            }
+        }
+        Ok(())
+    }
+
+    /// Collect metrics for all servers, returning this round's samples
     async fn collect_metrics_for_all_servers(
         metrics_history: &Arc<Mutex<HashMap<String, Vec<PerformanceMetrics>>>>,
         servers_path: &Path,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<Vec<PerformanceMetrics>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut samples = Vec::new();
         if !servers_path.exists() {
-            return Ok(());
+            return Ok(samples);
         }
 
         let mut entries = fs::read_dir(servers_path).await?;
@@ -91,6 +326,7 @@ impl PerformanceTelemetry {
             if server_path.is_dir() {
                 if let Some(server_id) = server_path.file_name().and_then(|n| n.to_str()) {
                     if let Ok(metrics) = Self::collect_server_metrics(server_id, &server_path).await {
+                        samples.push(metrics.clone());
                         let mut history = metrics_history.lock().await;
                         let server_metrics = history.entry(server_id.to_string()).or_insert_with(Vec::new);
                         server_metrics.push(metrics);
@@ -104,7 +340,7 @@ impl PerformanceTelemetry {
             }
         }
 
-        Ok(())
+        Ok(samples)
     }
 
     /// Collect performance metrics for a specific server
@@ -198,31 +434,25 @@ impl PerformanceTelemetry {
 
     /// Parse TPS and tick time from log content
     fn parse_tps_from_log_content(content: &str) -> Result<(f32, f32), Box<dyn std::error::Error + Send + Sync>> {
-        let mut tps = 20.0;
-        let mut tick_ms = 50.0;
-
-        // Look for TPS patterns in the log
-        for line in content.lines().rev().take(100) { // Check last 100 lines
-            if line.contains("TPS:") {
-                if let Some(tps_str) = line.split("TPS:").nth(1) {
-                    if let Some(tps_value) = tps_str.split_whitespace().next() {
-                        if let Ok(parsed_tps) = tps_value.parse::<f32>() {
-                            tps = parsed_tps;
-                        }
-                    }
-                }
+        let mut tps = None;
+        let mut tick_ms = None;
+
+        // Look for TPS patterns in the last 100 lines; the newest reading wins
+        let value = |line: &str, label: &str| {
+            line.split(label)
+                .nth(1)
+                .and_then(|rest| rest.split_whitespace().next())
+                .and_then(|value| value.trim_end_matches("ms").parse::<f32>().ok())
+        };
+        for line in content.lines().rev().take(100) {
+            if tps.is_none() {
+                tps = value(line, "TPS:");
             }
-            
-            if line.contains("Tick:") && line.contains("ms") {
-                if let Some(tick_str) = line.split("Tick:").nth(1) {
-                    if let Some(tick_value) = tick_str.split_whitespace().next() {
-                        if let Ok(parsed_tick) = tick_value.parse::<f32>() {
-                            tick_ms = parsed_tick;
-                        }
-                    }
-                }
+            if tick_ms.is_none() && line.contains("ms") {
+                tick_ms = value(line, "Tick:");
             }
         }
+        let (tps, tick_ms) = (tps.unwrap_or(20.0), tick_ms.unwrap_or(50.0));
 
         Ok((tps, tick_ms))
     }
@@ -390,6 +620,46 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_roll_up_averages_complete_buckets() {
+        let sample = |server_id: &str, secs: i64, tps: f64, players: u32| ServerMetric {
+            id: uuid::Uuid::new_v4().to_string(),
+            server_id: server_id.to_string(),
+            resolution: "raw".to_string(),
+            tps,
+            tick_p95: 50.0 - tps,
+            heap_mb: 1024,
+            players_online: players,
+            gpu_queue_ms: 0.0,
+            cpu_usage: 10.0,
+            memory_usage: 0,
+            disk_usage: 0,
+            network_in: 0,
+            network_out: 0,
+            timestamp: Utc.timestamp_opt(secs, 0).unwrap(),
+        };
+        let samples = vec![
+            sample("a", 600, 20.0, 2),
+            sample("a", 630, 18.0, 5),
+            sample("b", 610, 10.0, 1),
+            // Bucket still in progress
+            sample("a", 660, 20.0, 3),
+        ];
+
+        let rollups = roll_up(&samples, MetricsResolution::OneMinute, Utc.timestamp_opt(690, 0).unwrap());
+        assert_eq!(rollups.len(), 2);
+        assert_eq!(rollups[0].server_id, "a");
+        assert_eq!(rollups[0].resolution, "1m");
+        assert_eq!(rollups[0].timestamp.timestamp(), 600);
+        assert_eq!(rollups[0].tps, 19.0);
+        assert_eq!(rollups[0].tick_p95, 32.0);
+        assert_eq!(rollups[0].players_online, 5);
+        assert_eq!(rollups[1].server_id, "b");
+
+        assert_eq!(MetricsResolution::for_span(chrono::Duration::days(3)), MetricsResolution::FiveMinutes);
+        assert_eq!(MetricsRetention::with_days(3).five_minutes, chrono::Duration::days(3));
+    }
+
     #[test]
     fn test_parse_tps_from_log_content() {
         let log_content = r#"
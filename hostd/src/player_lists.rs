@@ -0,0 +1,291 @@
+//! Management of a server's `whitelist.json` and `ops.json`. Player names are
+//! resolved to UUIDs through the Mojang API. While the server runs, changes are
+//! applied over RCON as well: `whitelist reload` after editing the whitelist,
+//! and `op`/`deop` for operators since the server rewrites `ops.json` from its
+//! own list whenever that changes.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config_revisions::server_dir;
+use crate::database::ServerConfig;
+use crate::external_apis::rate_limit;
+use crate::restart_scheduler::rcon;
+
+const MOJANG_PROFILE_URL: &str = "https://api.mojang.com/users/profiles/minecraft";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_OP_LEVEL: u8 = 4;
+
+/// A player as Minecraft stores them in its JSON lists
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerIdentity {
+    /// Dashed UUID
+    pub uuid: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    #[serde(flatten)]
+    pub player: PlayerIdentity,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpEntry {
+    #[serde(flatten)]
+    pub player: PlayerIdentity,
+    /// Permission level, 1-4
+    pub level: u8,
+    #[serde(rename = "bypassesPlayerLimit", default)]
+    pub bypasses_player_limit: bool,
+}
+
+/// A player to add; `uuid` skips the Mojang lookup (offline-mode servers)
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlayerRequest {
+    pub name: String,
+    pub uuid: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpRequest {
+    #[serde(flatten)]
+    pub player: PlayerRequest,
+    pub level: Option<u8>,
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OpUpdate {
+    pub level: Option<u8>,
+    pub bypasses_player_limit: Option<bool>,
+}
+
+/// `8667ba71b85a4004af54457a9734eed7` -> `8667ba71-b85a-4004-af54-457a9734eed7`
+pub fn dashed_uuid(uuid: &str) -> Result<String> {
+    uuid::Uuid::parse_str(uuid.trim())
+        .map(|uuid| uuid.hyphenated().to_string())
+        .map_err(|_| anyhow!("Invalid UUID '{}'", uuid))
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = (1..=16).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        bail!("Invalid player name '{}'", name);
+    }
+    Ok(())
+}
+
+/// Look a player up by name with the Mojang API
+pub async fn lookup_player(name: &str) -> Result<PlayerIdentity> {
+    #[derive(Deserialize)]
+    struct Profile {
+        id: String,
+        name: String,
+    }
+
+    validate_name(name)?;
+    let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+    let response = rate_limit::send(client.get(format!("{}/{}", MOJANG_PROFILE_URL, name)))
+        .await
+        .context("Mojang API request failed")?;
+    match response.status() {
+        status if status.is_success() && status != reqwest::StatusCode::NO_CONTENT => {
+            let profile: Profile = response.json().await.context("Unexpected Mojang API response")?;
+            Ok(PlayerIdentity { uuid: dashed_uuid(&profile.id)?, name: profile.name })
+        }
+        reqwest::StatusCode::NO_CONTENT | reqwest::StatusCode::NOT_FOUND => bail!("No Minecraft account named '{}'", name),
+        status => bail!("Mojang API returned {}", status),
+    }
+}
+
+pub(crate) async fn resolve(request: &PlayerRequest) -> Result<PlayerIdentity> {
+    match &request.uuid {
+        Some(uuid) => {
+            validate_name(&request.name)?;
+            Ok(PlayerIdentity { uuid: dashed_uuid(uuid)?, name: request.name.clone() })
+        }
+        None => lookup_player(&request.name).await,
+    }
+}
+
+fn list_path(server: &ServerConfig, file: &str) -> PathBuf {
+    server_dir(server).join(file)
+}
+
+pub(crate) async fn load<T: for<'de> Deserialize<'de>>(server: &ServerConfig, file: &str) -> Result<Vec<T>> {
+    let path = list_path(server, file);
+    match tokio::fs::read_to_string(&path).await {
+        Ok(content) if content.trim().is_empty() => Ok(Vec::new()),
+        Ok(content) => serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(anyhow!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+pub(crate) async fn save<T: Serialize>(server: &ServerConfig, file: &str, entries: &[T]) -> Result<()> {
+    let path = list_path(server, file);
+    let content = serde_json::to_string_pretty(entries)?;
+    tokio::fs::write(&path, content + "\n")
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Whether `player` has the name or UUID given
+pub(crate) fn matches(player: &PlayerIdentity, name_or_uuid: &str) -> bool {
+    player.name.eq_ignore_ascii_case(name_or_uuid)
+        || dashed_uuid(name_or_uuid).is_ok_and(|uuid| uuid.eq_ignore_ascii_case(&player.uuid))
+}
+
+/// Send a command to a running server; failures are logged since the file
+/// already holds the change
+pub(crate) async fn apply_live(server: &ServerConfig, command: String) {
+    if let Err(e) = rcon(server, command.clone()).await {
+        warn!("Failed to run '{}' on server {}: {}", command, server.id, e);
+    }
+}
+
+pub mod whitelist {
+    use super::*;
+
+    const FILE: &str = "whitelist.json";
+
+    pub async fn list(server: &ServerConfig) -> Result<Vec<WhitelistEntry>> {
+        load(server, FILE).await
+    }
+
+    /// Add a player, returning the updated list. Adding a listed player is a no-op.
+    pub async fn add(server: &ServerConfig, request: &PlayerRequest, running: bool) -> Result<Vec<WhitelistEntry>> {
+        let player = resolve(request).await?;
+        let mut entries = list(server).await?;
+        if entries.iter().any(|entry| entry.player.uuid == player.uuid) {
+            return Ok(entries);
+        }
+        info!("Whitelisting {} ({}) on server {}", player.name, player.uuid, server.id);
+        entries.push(WhitelistEntry { player });
+        save(server, FILE, &entries).await?;
+        if running {
+            apply_live(server, "whitelist reload".to_string()).await;
+        }
+        Ok(entries)
+    }
+
+    /// Remove a player by name or UUID; `None` when they weren't listed
+    pub async fn remove(server: &ServerConfig, name_or_uuid: &str, running: bool) -> Result<Option<Vec<WhitelistEntry>>> {
+        let mut entries = list(server).await?;
+        let before = entries.len();
+        entries.retain(|entry| !matches(&entry.player, name_or_uuid));
+        if entries.len() == before {
+            return Ok(None);
+        }
+        info!("Removing {} from the whitelist of server {}", name_or_uuid, server.id);
+        save(server, FILE, &entries).await?;
+        if running {
+            apply_live(server, "whitelist reload".to_string()).await;
+        }
+        Ok(Some(entries))
+    }
+}
+
+pub mod ops {
+    use super::*;
+
+    const FILE: &str = "ops.json";
+
+    fn validate_level(level: u8) -> Result<u8> {
+        if !(1..=4).contains(&level) {
+            bail!("Operator level must be between 1 and 4");
+        }
+        Ok(level)
+    }
+
+    pub async fn list(server: &ServerConfig) -> Result<Vec<OpEntry>> {
+        load(server, FILE).await
+    }
+
+    /// Make a player an operator, or update their level if they already are.
+    /// On a running server `op` is sent first: it makes the change live and
+    /// rewrites `ops.json`, which is then edited to set the level.
+    pub async fn add(server: &ServerConfig, request: &OpRequest, running: bool) -> Result<Vec<OpEntry>> {
+        let level = validate_level(request.level.unwrap_or(DEFAULT_OP_LEVEL))?;
+        let player = resolve(&request.player).await?;
+        if running {
+            apply_live(server, format!("op {}", player.name)).await;
+        }
+        let mut entries = list(server).await?;
+        let entry = OpEntry { player, level, bypasses_player_limit: request.bypasses_player_limit };
+        info!("Making {} an operator (level {}) on server {}", entry.player.name, level, server.id);
+        match entries.iter_mut().find(|existing| existing.player.uuid == entry.player.uuid) {
+            Some(existing) => *existing = entry,
+            None => entries.push(entry),
+        }
+        save(server, FILE, &entries).await?;
+        Ok(entries)
+    }
+
+    /// Change an operator's level or player-limit bypass; `None` when they aren't an operator.
+    /// A running server keeps the old level until it restarts.
+    pub async fn update(server: &ServerConfig, name_or_uuid: &str, update: &OpUpdate) -> Result<Option<Vec<OpEntry>>> {
+        let mut entries = list(server).await?;
+        let Some(entry) = entries.iter_mut().find(|entry| matches(&entry.player, name_or_uuid)) else {
+            return Ok(None);
+        };
+        if let Some(level) = update.level {
+            entry.level = validate_level(level)?;
+        }
+        if let Some(bypass) = update.bypasses_player_limit {
+            entry.bypasses_player_limit = bypass;
+        }
+        save(server, FILE, &entries).await?;
+        Ok(Some(entries))
+    }
+
+    /// Remove an operator by name or UUID; `None` when they weren't one
+    pub async fn remove(server: &ServerConfig, name_or_uuid: &str, running: bool) -> Result<Option<Vec<OpEntry>>> {
+        let entries = list(server).await?;
+        let Some(entry) = entries.iter().find(|entry| matches(&entry.player, name_or_uuid)) else {
+            return Ok(None);
+        };
+        let uuid = entry.player.uuid.clone();
+        info!("Removing operator {} from server {}", entry.player.name, server.id);
+        if running {
+            apply_live(server, format!("deop {}", entry.player.name)).await;
+        }
+        // `deop` rewrote the file
+        let mut entries = list(server).await?;
+        entries.retain(|entry| entry.player.uuid != uuid);
+        save(server, FILE, &entries).await?;
+        Ok(Some(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_formats() {
+        let ops: Vec<OpEntry> = serde_json::from_str(
+            r#"[{"uuid":"8667ba71-b85a-4004-af54-457a9734eed7","name":"Steve","level":4,"bypassesPlayerLimit":false}]"#,
+        )
+        .unwrap();
+        assert_eq!(ops[0].player.name, "Steve");
+        let json = serde_json::to_value(&ops[0]).unwrap();
+        assert_eq!(json["bypassesPlayerLimit"], false);
+        assert_eq!(json["uuid"], "8667ba71-b85a-4004-af54-457a9734eed7");
+    }
+
+    #[test]
+    fn test_player_matching() {
+        assert_eq!(dashed_uuid("8667ba71b85a4004af54457a9734eed7").unwrap(), "8667ba71-b85a-4004-af54-457a9734eed7");
+        let player = PlayerIdentity { uuid: "8667ba71-b85a-4004-af54-457a9734eed7".to_string(), name: "Steve".to_string() };
+        assert!(matches(&player, "steve"));
+        assert!(matches(&player, "8667ba71b85a4004af54457a9734eed7"));
+        assert!(!matches(&player, "Alex"));
+        assert!(validate_name("Not a name!").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! Minecraft player profiles (name, skin, cape) from Mojang's session server,
+//! with face and head render URLs for the UI. Profiles are cached and requests
+//! to Mojang are rate limited so clients can ask for every player in a list
+//! without each of them hitting Mojang.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::core::caching::{Cache, CacheConfig, EvictionPolicy};
+use crate::external_apis::rate_limit;
+use crate::player_lists::{dashed_uuid, lookup_player};
+
+const SESSION_PROFILE_URL: &str = "https://sessionserver.mojang.com/session/minecraft/profile";
+const RENDER_URL: &str = "https://crafatar.com";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Skins and capes rarely change
+const PROFILE_TTL: Duration = Duration::from_secs(30 * 60);
+/// Names can be changed, so name lookups expire sooner than profiles
+const NAME_TTL: Duration = Duration::from_secs(10 * 60);
+const MAX_CACHED_PROFILES: usize = 5000;
+/// Mojang allows roughly 600 requests per 10 minutes per IP
+const MAX_REQUESTS_PER_MINUTE: usize = 50;
+/// Most profiles one batch request may ask for
+pub const MAX_BATCH: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerProfile {
+    /// Dashed UUID
+    pub uuid: String,
+    pub name: String,
+    pub skin_url: Option<String>,
+    pub cape_url: Option<String>,
+    /// Skin uses the slim (Alex) arm model
+    pub slim: bool,
+    /// Flat face, with the hat layer
+    pub avatar_url: String,
+    /// 3D head render
+    pub head_url: String,
+    pub fetched_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct SessionProfile {
+    id: String,
+    name: String,
+    #[serde(default)]
+    properties: Vec<SessionProperty>,
+}
+
+#[derive(Deserialize)]
+struct SessionProperty {
+    name: String,
+    value: String,
+}
+
+/// Decoded `textures` property
+#[derive(Deserialize, Default)]
+struct Textures {
+    #[serde(default)]
+    textures: TextureSet,
+}
+
+#[derive(Deserialize, Default)]
+struct TextureSet {
+    #[serde(rename = "SKIN")]
+    skin: Option<Texture>,
+    #[serde(rename = "CAPE")]
+    cape: Option<Texture>,
+}
+
+#[derive(Deserialize)]
+struct Texture {
+    url: String,
+    metadata: Option<TextureMetadata>,
+}
+
+#[derive(Deserialize)]
+struct TextureMetadata {
+    model: Option<String>,
+}
+
+impl PlayerProfile {
+    fn from_session(session: SessionProfile) -> Result<Self> {
+        let uuid = dashed_uuid(&session.id)?;
+        let textures = match session.properties.iter().find(|property| property.name == "textures") {
+            Some(property) => {
+                let json = base64::engine::general_purpose::STANDARD
+                    .decode(&property.value)
+                    .context("Invalid textures property")?;
+                serde_json::from_slice::<Textures>(&json).context("Invalid textures property")?
+            }
+            None => Textures::default(),
+        };
+        let skin = textures.textures.skin;
+        Ok(Self {
+            avatar_url: format!("{}/avatars/{}?overlay", RENDER_URL, uuid),
+            head_url: format!("{}/renders/head/{}?overlay", RENDER_URL, uuid),
+            slim: skin
+                .as_ref()
+                .and_then(|skin| skin.metadata.as_ref())
+                .and_then(|metadata| metadata.model.as_deref())
+                == Some("slim"),
+            skin_url: skin.map(|skin| skin.url),
+            cape_url: textures.textures.cape.map(|cape| cape.url),
+            uuid,
+            name: session.name,
+            fetched_at: Utc::now(),
+        })
+    }
+}
+
+/// Sliding one-minute window of requests made to Mojang
+struct RequestWindow {
+    sent: VecDeque<Instant>,
+}
+
+impl RequestWindow {
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        while self.sent.front().is_some_and(|sent| now.duration_since(*sent) >= Duration::from_secs(60)) {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= MAX_REQUESTS_PER_MINUTE {
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
+}
+
+pub struct PlayerProfileService {
+    /// Keyed by dashed UUID
+    profiles: Cache<String, PlayerProfile>,
+    /// Lower-case name to dashed UUID
+    names: Cache<String, String>,
+    window: Mutex<RequestWindow>,
+}
+
+impl PlayerProfileService {
+    pub fn new() -> Self {
+        let config = |ttl| CacheConfig {
+            max_size: MAX_CACHED_PROFILES,
+            default_ttl: Some(ttl),
+            eviction_policy: EvictionPolicy::LRU,
+            cleanup_interval: Duration::from_secs(300),
+            enable_metrics: false,
+        };
+        Self {
+            profiles: Cache::new(config(PROFILE_TTL)),
+            names: Cache::new(config(NAME_TTL)),
+            window: Mutex::new(RequestWindow { sent: VecDeque::new() }),
+        }
+    }
+
+    async fn acquire(&self) -> Result<()> {
+        if !self.window.lock().await.try_acquire(Instant::now()) {
+            bail!("Too many Mojang requests, try again in a minute");
+        }
+        Ok(())
+    }
+
+    /// Profile of a player by name or UUID
+    pub async fn get(&self, name_or_uuid: &str) -> Result<PlayerProfile> {
+        match dashed_uuid(name_or_uuid) {
+            Ok(uuid) => self.get_by_uuid(&uuid).await,
+            Err(_) => self.get_by_name(name_or_uuid).await,
+        }
+    }
+
+    pub async fn get_by_name(&self, name: &str) -> Result<PlayerProfile> {
+        let key = name.to_ascii_lowercase();
+        let uuid = match self.names.get(&key).await {
+            Some(uuid) => uuid,
+            None => {
+                self.acquire().await?;
+                let player = lookup_player(name).await?;
+                self.names.put(key, player.uuid.clone()).await;
+                player.uuid
+            }
+        };
+        self.get_by_uuid(&uuid).await
+    }
+
+    pub async fn get_by_uuid(&self, uuid: &str) -> Result<PlayerProfile> {
+        let uuid = dashed_uuid(uuid)?;
+        if let Some(profile) = self.profiles.get(&uuid).await {
+            return Ok(profile);
+        }
+        self.acquire().await?;
+        let profile = fetch_profile(&uuid).await?;
+        self.names.put(profile.name.to_ascii_lowercase(), profile.uuid.clone()).await;
+        self.profiles.put(uuid, profile.clone()).await;
+        Ok(profile)
+    }
+}
+
+impl Default for PlayerProfileService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn fetch_profile(uuid: &str) -> Result<PlayerProfile> {
+    let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+    let response = rate_limit::send(client.get(format!("{}/{}", SESSION_PROFILE_URL, uuid.replace('-', ""))))
+        .await
+        .context("Mojang session server request failed")?;
+    match response.status() {
+        reqwest::StatusCode::NO_CONTENT | reqwest::StatusCode::NOT_FOUND => Err(anyhow!("No Minecraft account with UUID {}", uuid)),
+        reqwest::StatusCode::TOO_MANY_REQUESTS => bail!("Mojang is rate limiting requests, try again in a minute"),
+        status if status.is_success() => {
+            let session: SessionProfile = response.json().await.context("Unexpected session server response")?;
+            PlayerProfile::from_session(session)
+        }
+        status => bail!("Mojang session server returned {}", status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_from_session() {
+        let textures = serde_json::json!({
+            "textures": {
+                "SKIN": { "url": "http://textures.minecraft.net/texture/abc", "metadata": { "model": "slim" } },
+                "CAPE": { "url": "http://textures.minecraft.net/texture/def" }
+            }
+        });
+        let session = SessionProfile {
+            id: "8667ba71b85a4004af54457a9734eed7".to_string(),
+            name: "Steve".to_string(),
+            properties: vec![SessionProperty {
+                name: "textures".to_string(),
+                value: base64::engine::general_purpose::STANDARD.encode(textures.to_string()),
+            }],
+        };
+        let profile = PlayerProfile::from_session(session).unwrap();
+        assert_eq!(profile.uuid, "8667ba71-b85a-4004-af54-457a9734eed7");
+        assert_eq!(profile.skin_url.as_deref(), Some("http://textures.minecraft.net/texture/abc"));
+        assert!(profile.cape_url.is_some());
+        assert!(profile.slim);
+        assert_eq!(profile.avatar_url, "https://crafatar.com/avatars/8667ba71-b85a-4004-af54-457a9734eed7?overlay");
+    }
+
+    #[test]
+    fn test_request_window() {
+        let mut window = RequestWindow { sent: VecDeque::new() };
+        let start = Instant::now();
+        for _ in 0..MAX_REQUESTS_PER_MINUTE {
+            assert!(window.try_acquire(start));
+        }
+        assert!(!window.try_acquire(start));
+        assert!(window.try_acquire(start + Duration::from_secs(61)));
+    }
+}
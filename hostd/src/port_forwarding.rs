@@ -0,0 +1,626 @@
+//! Automatic port forwarding. Servers that opt in get their game port opened
+//! on the router while they run, through NAT-PMP when the gateway speaks it
+//! and UPnP IGD otherwise. Mappings are leased, so a mapping left behind by a
+//! crash of hostd expires on its own; the loop renews leases while the server
+//! runs and removes mappings once it stops.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, ServerConfig};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Lease asked for; renewed at half of what the gateway grants
+const LEASE_SECS: u32 = 3600;
+
+const NATPMP_PORT: u16 = 5351;
+const NATPMP_OP_ADDRESS: u8 = 0;
+const NATPMP_OP_MAP_TCP: u8 = 2;
+/// Initial wait for a NAT-PMP answer, doubled on each retry (RFC 6886 3.1)
+const NATPMP_INITIAL_WAIT: Duration = Duration::from_millis(250);
+const NATPMP_TRIES: u32 = 3;
+
+const SSDP_ADDR: &str = "239.255.255.250:1900";
+const SSDP_WAIT: Duration = Duration::from_secs(2);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+const IGD_DEVICE: &str = "urn:schemas-upnp-org:device:InternetGatewayDevice:1";
+/// WAN services that can map ports, in order of preference
+const IGD_SERVICES: &[&str] = &[
+    "urn:schemas-upnp-org:service:WANIPConnection:2",
+    "urn:schemas-upnp-org:service:WANIPConnection:1",
+    "urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+/// UPnP error for gateways that only accept leases of 0, meaning permanent
+const ONLY_PERMANENT_LEASES: &str = "725";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardingState {
+    Forwarded,
+    /// The gateway answered but would not map the port
+    Failed,
+    /// No gateway answered NAT-PMP or UPnP
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ForwardingMethod {
+    NatPmp,
+    Upnp,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct PortForwardStatus {
+    pub state: ForwardingState,
+    pub method: Option<ForwardingMethod>,
+    pub internal_port: u16,
+    pub external_port: Option<u16>,
+    /// Router's public address, when it reported one
+    #[schema(value_type = Option<String>)]
+    pub external_ip: Option<IpAddr>,
+    /// None for permanent UPnP mappings
+    pub expires_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PortForwarding {
+    pub enabled: bool,
+    /// None while the server is stopped or forwarding is off
+    pub status: Option<PortForwardStatus>,
+}
+
+/// Where a mapping was made, so it can be renewed and removed
+#[derive(Debug, Clone)]
+enum Gateway {
+    NatPmp(SocketAddr),
+    Upnp(Igd),
+}
+
+#[derive(Debug, Clone)]
+struct Mapping {
+    gateway: Gateway,
+    port: u16,
+    /// None for permanent mappings
+    renew_at: Option<Instant>,
+}
+
+pub struct PortForwarder {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+    mappings: RwLock<HashMap<String, Mapping>>,
+    statuses: RwLock<HashMap<String, PortForwardStatus>>,
+}
+
+impl PortForwarder {
+    pub fn new(database: Arc<DatabaseManager>, process_manager: Arc<ProcessManager>) -> Self {
+        Self {
+            database,
+            process_manager,
+            mappings: RwLock::new(HashMap::new()),
+            statuses: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn get(&self, server_id: &str) -> Result<PortForwarding> {
+        Ok(PortForwarding {
+            enabled: self.database.get_port_forwarding(server_id).await?,
+            status: self.status(server_id).await,
+        })
+    }
+
+    /// Last result of forwarding a server's port
+    pub async fn status(&self, server_id: &str) -> Option<PortForwardStatus> {
+        self.statuses.read().await.get(server_id).cloned()
+    }
+
+    /// Turn forwarding on or off, opening or closing the port right away
+    pub async fn set_enabled(&self, server: &ServerConfig, enabled: bool) -> Result<PortForwarding> {
+        if !server.managed {
+            bail!("External servers are not run by Guardian");
+        }
+        self.database.set_port_forwarding(&server.id, enabled).await?;
+        if enabled && self.is_running(&server.id).await {
+            self.open(server).await;
+        } else if !enabled {
+            self.close(&server.id).await;
+        }
+        self.get(&server.id).await
+    }
+
+    /// Open the port of a server that just started, if it opted in
+    pub async fn server_started(&self, server: &ServerConfig) {
+        match self.database.get_port_forwarding(&server.id).await {
+            Ok(true) => self.open(server).await,
+            Ok(false) => {}
+            Err(e) => warn!("Failed to read port forwarding setting for {}: {}", server.id, e),
+        }
+    }
+
+    pub async fn server_stopped(&self, server_id: &str) {
+        self.close(server_id).await;
+    }
+
+    /// Remove every mapping, for shutdown
+    pub async fn close_all(&self) {
+        let mapped: Vec<String> = self.mappings.read().await.keys().cloned().collect();
+        for server_id in mapped {
+            self.close(&server_id).await;
+        }
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting port forwarding");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_all().await {
+                warn!("Port forwarding check failed: {}", e);
+            }
+        }
+    }
+
+    /// Renew leases of running servers, retry failures, and close the ports of
+    /// servers that stopped or crashed since
+    async fn check_all(&self) -> Result<()> {
+        let enabled = self.database.get_port_forwarding_servers().await?;
+        let mapped: Vec<String> = self.mappings.read().await.keys().cloned().collect();
+        for server_id in mapped.iter().filter(|id| !enabled.contains(id)) {
+            self.close(server_id).await;
+        }
+
+        for server_id in enabled {
+            let running = self.is_running(&server_id).await;
+            if !running {
+                if mapped.contains(&server_id) {
+                    self.close(&server_id).await;
+                }
+                self.statuses.write().await.remove(&server_id);
+                continue;
+            }
+            let due = match self.mappings.read().await.get(&server_id) {
+                Some(mapping) => mapping.renew_at.is_some_and(|at| Instant::now() >= at),
+                // Nothing to retry against until the server starts again
+                None => !matches!(self.status(&server_id).await, Some(s) if s.state == ForwardingState::Unsupported),
+            };
+            if due {
+                if let Some(server) = self.database.get_server(&server_id).await? {
+                    self.open(&server).await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn is_running(&self, server_id: &str) -> bool {
+        match Uuid::parse_str(server_id) {
+            Ok(id) => self.process_manager.is_server_running(id).await,
+            Err(_) => false,
+        }
+    }
+
+    async fn open(&self, server: &ServerConfig) {
+        let description = format!("Guardian {}", server.name);
+        let status = match forward(server.port, &description).await {
+            Ok((mapping, lease, external_ip)) => {
+                let method = match mapping.gateway {
+                    Gateway::NatPmp(_) => ForwardingMethod::NatPmp,
+                    Gateway::Upnp(_) => ForwardingMethod::Upnp,
+                };
+                info!("Forwarded port {} of server {} with {:?}", mapping.port, server.id, method);
+                let status = PortForwardStatus {
+                    state: ForwardingState::Forwarded,
+                    method: Some(method),
+                    internal_port: server.port,
+                    external_port: Some(mapping.port),
+                    external_ip,
+                    expires_at: lease.map(|lease| Utc::now() + chrono::Duration::seconds(lease as i64)),
+                    error: None,
+                    updated_at: Utc::now(),
+                };
+                self.mappings.write().await.insert(server.id.clone(), mapping);
+                status
+            }
+            Err(outcome) => {
+                let (state, error) = match outcome {
+                    Outcome::Unsupported => (ForwardingState::Unsupported, "No NAT-PMP or UPnP gateway found".to_string()),
+                    Outcome::Failed(e) => (ForwardingState::Failed, e.to_string()),
+                };
+                warn!("Could not forward port {} of server {}: {}", server.port, server.id, error);
+                self.mappings.write().await.remove(&server.id);
+                PortForwardStatus {
+                    state,
+                    method: None,
+                    internal_port: server.port,
+                    external_port: None,
+                    external_ip: None,
+                    expires_at: None,
+                    error: Some(error),
+                    updated_at: Utc::now(),
+                }
+            }
+        };
+        self.statuses.write().await.insert(server.id.clone(), status);
+    }
+
+    async fn close(&self, server_id: &str) {
+        self.statuses.write().await.remove(server_id);
+        let Some(mapping) = self.mappings.write().await.remove(server_id) else {
+            return;
+        };
+        let result = match &mapping.gateway {
+            Gateway::NatPmp(gateway) => natpmp_map(*gateway, mapping.port, 0).await.map(|_| ()),
+            Gateway::Upnp(igd) => igd.delete_port_mapping(mapping.port).await,
+        };
+        match result {
+            Ok(()) => info!("Closed forwarded port {} of server {}", mapping.port, server_id),
+            Err(e) => debug!("Failed to remove port mapping of {}, it expires with its lease: {}", server_id, e),
+        }
+    }
+}
+
+enum Outcome {
+    Unsupported,
+    Failed(anyhow::Error),
+}
+
+/// Map `port` with NAT-PMP, falling back to UPnP. Returns the mapping, its
+/// lease in seconds (None when permanent) and the external address.
+async fn forward(port: u16, description: &str) -> Result<(Mapping, Option<u32>, Option<IpAddr>), Outcome> {
+    let mut failure = None;
+    if let Some(gateway) = default_gateway() {
+        let gateway = SocketAddr::new(IpAddr::V4(gateway), NATPMP_PORT);
+        match natpmp_map(gateway, port, LEASE_SECS).await {
+            Ok(Some((external_port, lease))) => {
+                let external_ip = natpmp_external_address(gateway).await.ok().flatten().map(IpAddr::V4);
+                let mapping = Mapping { gateway: Gateway::NatPmp(gateway), port: external_port, renew_at: Some(renew_at(lease)) };
+                return Ok((mapping, Some(lease), external_ip));
+            }
+            Ok(None) => debug!("No NAT-PMP answer from {}", gateway),
+            Err(e) => failure = Some(e),
+        }
+    }
+
+    match Igd::discover().await {
+        Ok(Some(igd)) => {
+            let lease = igd.add_port_mapping(port, description).await.map_err(Outcome::Failed)?;
+            let external_ip = igd.external_ip().await.ok();
+            let mapping = Mapping { gateway: Gateway::Upnp(igd), port, renew_at: lease.map(renew_at) };
+            Ok((mapping, lease, external_ip))
+        }
+        Ok(None) => Err(failure.map(Outcome::Failed).unwrap_or(Outcome::Unsupported)),
+        Err(e) => Err(Outcome::Failed(failure.unwrap_or(e))),
+    }
+}
+
+fn renew_at(lease: u32) -> Instant {
+    Instant::now() + Duration::from_secs(u64::from(lease / 2).max(CHECK_INTERVAL.as_secs()))
+}
+
+/// IPv4 default gateway from the routing table on Linux, otherwise guessed as
+/// the `.1` address of the local network
+fn default_gateway() -> Option<Ipv4Addr> {
+    if let Ok(table) = std::fs::read_to_string("/proc/net/route") {
+        return parse_route_table(&table);
+    }
+    match local_ipv4(SocketAddr::from(([192, 0, 2, 1], 9))) {
+        Some(local) => {
+            let [a, b, c, _] = local.octets();
+            Some(Ipv4Addr::new(a, b, c, 1))
+        }
+        None => None,
+    }
+}
+
+/// Gateway of the default route in `/proc/net/route`, stored little-endian hex
+fn parse_route_table(table: &str) -> Option<Ipv4Addr> {
+    table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || fields[1] != "00000000" {
+            return None;
+        }
+        let gateway = u32::from_str_radix(fields[2], 16).ok()?;
+        (gateway != 0).then(|| Ipv4Addr::from(gateway.to_le_bytes()))
+    })
+}
+
+/// Local address used to reach `target`; connecting a UDP socket sends nothing
+fn local_ipv4(target: SocketAddr) -> Option<Ipv4Addr> {
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect(target).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V4(ip) if !ip.is_unspecified() => Some(ip),
+        _ => None,
+    }
+}
+
+fn natpmp_map_request(port: u16, lifetime: u32) -> [u8; 12] {
+    let mut request = [0u8; 12];
+    request[1] = NATPMP_OP_MAP_TCP;
+    request[4..6].copy_from_slice(&port.to_be_bytes());
+    // Removing a mapping asks for external port 0
+    let external = if lifetime == 0 { 0 } else { port };
+    request[6..8].copy_from_slice(&external.to_be_bytes());
+    request[8..12].copy_from_slice(&lifetime.to_be_bytes());
+    request
+}
+
+/// Check a NAT-PMP response's header and result code
+fn natpmp_result(response: &[u8], op: u8, len: usize) -> Result<()> {
+    if response.len() < len || response[0] != 0 || response[1] != 128 + op {
+        bail!("Malformed NAT-PMP response");
+    }
+    match u16::from_be_bytes([response[2], response[3]]) {
+        0 => Ok(()),
+        1 => bail!("NAT-PMP gateway does not support this version"),
+        2 => bail!("NAT-PMP gateway refused the mapping"),
+        3 => bail!("NAT-PMP gateway has no network connection"),
+        4 => bail!("NAT-PMP gateway is out of mappings"),
+        5 => bail!("NAT-PMP gateway does not support this request"),
+        code => bail!("NAT-PMP gateway returned error {}", code),
+    }
+}
+
+/// External port and lease granted in a mapping response
+fn parse_natpmp_mapping(response: &[u8]) -> Result<(u16, u32)> {
+    natpmp_result(response, NATPMP_OP_MAP_TCP, 16)?;
+    let external_port = u16::from_be_bytes([response[10], response[11]]);
+    let lease = u32::from_be_bytes([response[12], response[13], response[14], response[15]]);
+    Ok((external_port, lease))
+}
+
+/// Send a NAT-PMP request, retrying with backoff. `None` when nothing answers.
+async fn natpmp_request(gateway: SocketAddr, request: &[u8]) -> Result<Option<Vec<u8>>> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(gateway).await?;
+    let mut wait = NATPMP_INITIAL_WAIT;
+    let mut buf = [0u8; 64];
+    for _ in 0..NATPMP_TRIES {
+        socket.send(request).await?;
+        match tokio::time::timeout(wait, socket.recv(&mut buf)).await {
+            Ok(Ok(len)) => return Ok(Some(buf[..len].to_vec())),
+            // ICMP port unreachable: the gateway does not run NAT-PMP
+            Ok(Err(_)) => return Ok(None),
+            Err(_) => wait *= 2,
+        }
+    }
+    Ok(None)
+}
+
+/// Map `port` over TCP, or remove its mapping with a lifetime of 0
+async fn natpmp_map(gateway: SocketAddr, port: u16, lifetime: u32) -> Result<Option<(u16, u32)>> {
+    match natpmp_request(gateway, &natpmp_map_request(port, lifetime)).await? {
+        Some(response) => parse_natpmp_mapping(&response).map(Some),
+        None => Ok(None),
+    }
+}
+
+async fn natpmp_external_address(gateway: SocketAddr) -> Result<Option<Ipv4Addr>> {
+    match natpmp_request(gateway, &[0, NATPMP_OP_ADDRESS]).await? {
+        Some(response) => {
+            natpmp_result(&response, NATPMP_OP_ADDRESS, 12)?;
+            Ok(Some(Ipv4Addr::new(response[8], response[9], response[10], response[11])))
+        }
+        None => Ok(None),
+    }
+}
+
+/// A UPnP Internet Gateway Device's port mapping service
+#[derive(Debug, Clone)]
+struct Igd {
+    control_url: String,
+    service_type: String,
+    local_ip: Ipv4Addr,
+}
+
+impl Igd {
+    /// Find a gateway with SSDP and read its description. `None` when no
+    /// gateway answers.
+    async fn discover() -> Result<Option<Igd>> {
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        let search = format!(
+            "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: {}\r\n\r\n",
+            SSDP_ADDR, IGD_DEVICE
+        );
+        socket.send_to(search.as_bytes(), SSDP_ADDR).await?;
+
+        let client = reqwest::Client::builder().timeout(HTTP_TIMEOUT).build()?;
+        let deadline = tokio::time::Instant::now() + SSDP_WAIT;
+        let mut buf = vec![0u8; 2048];
+        let mut last_error = None;
+        loop {
+            let (len, _) = match tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+                Ok(received) => received?,
+                Err(_) => break,
+            };
+            let Some(location) = ssdp_location(&String::from_utf8_lossy(&buf[..len])) else {
+                continue;
+            };
+            match Self::from_description(&client, &location).await {
+                Ok(Some(igd)) => return Ok(Some(igd)),
+                Ok(None) => {}
+                Err(e) => last_error = Some(e),
+            }
+        }
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    async fn from_description(client: &reqwest::Client, location: &str) -> Result<Option<Igd>> {
+        let description = client.get(location).send().await?.error_for_status()?.text().await?;
+        let Some((service_type, control_url)) = find_igd_service(&description) else {
+            return Ok(None);
+        };
+        let control_url = reqwest::Url::parse(location)?.join(&control_url)?;
+        let host = control_url.host_str().ok_or_else(|| anyhow!("Gateway control URL has no host"))?;
+        let gateway: IpAddr = host.parse().context("Gateway control URL is not an IP address")?;
+        let port = control_url.port_or_known_default().unwrap_or(80);
+        let local_ip = local_ipv4(SocketAddr::new(gateway, port)).ok_or_else(|| anyhow!("No IPv4 route to the gateway"))?;
+        Ok(Some(Igd { control_url: control_url.to_string(), service_type, local_ip }))
+    }
+
+    async fn soap(&self, action: &str, args: &[(&str, String)]) -> Result<String> {
+        let args: String = args.iter().map(|(name, value)| format!("<{0}>{1}</{0}>", name, value)).collect();
+        let body = format!(
+            "<?xml version=\"1.0\"?>\
+             <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+             <s:Body><u:{action} xmlns:u=\"{service}\">{args}</u:{action}></s:Body></s:Envelope>",
+            action = action,
+            service = self.service_type,
+            args = args,
+        );
+        let response = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()?
+            .post(&self.control_url)
+            .header("Content-Type", "text/xml; charset=\"utf-8\"")
+            .header("SOAPAction", format!("\"{}#{}\"", self.service_type, action))
+            .body(body)
+            .send()
+            .await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            let code = xml_text(&text, "errorCode").unwrap_or_else(|| status.as_u16().to_string());
+            let description = xml_text(&text, "errorDescription").unwrap_or_default();
+            bail!("UPnP {} failed with error {} {}", action, code, description);
+        }
+        Ok(text)
+    }
+
+    /// Map `port` over TCP. Returns the lease, `None` when the gateway only
+    /// grants permanent mappings.
+    async fn add_port_mapping(&self, port: u16, description: &str) -> Result<Option<u32>> {
+        let args = |lease: u32| {
+            vec![
+                ("NewRemoteHost", String::new()),
+                ("NewExternalPort", port.to_string()),
+                ("NewProtocol", "TCP".to_string()),
+                ("NewInternalPort", port.to_string()),
+                ("NewInternalClient", self.local_ip.to_string()),
+                ("NewEnabled", "1".to_string()),
+                ("NewPortMappingDescription", xml_escape(description)),
+                ("NewLeaseDuration", lease.to_string()),
+            ]
+        };
+        match self.soap("AddPortMapping", &args(LEASE_SECS)).await {
+            Ok(_) => Ok(Some(LEASE_SECS)),
+            Err(e) if e.to_string().contains(ONLY_PERMANENT_LEASES) => {
+                self.soap("AddPortMapping", &args(0)).await?;
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn delete_port_mapping(&self, port: u16) -> Result<()> {
+        let args = [
+            ("NewRemoteHost", String::new()),
+            ("NewExternalPort", port.to_string()),
+            ("NewProtocol", "TCP".to_string()),
+        ];
+        self.soap("DeletePortMapping", &args).await.map(|_| ())
+    }
+
+    async fn external_ip(&self) -> Result<IpAddr> {
+        let response = self.soap("GetExternalIPAddress", &[]).await?;
+        let ip = xml_text(&response, "NewExternalIPAddress").ok_or_else(|| anyhow!("Gateway did not report its address"))?;
+        Ok(ip.parse()?)
+    }
+}
+
+fn ssdp_location(response: &str) -> Option<String> {
+    response.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim().eq_ignore_ascii_case("location").then(|| value.trim().to_string())
+    })
+}
+
+/// First port mapping service in a device description, as `(type, control URL)`
+fn find_igd_service(description: &str) -> Option<(String, String)> {
+    let services: Vec<(String, String)> = description
+        .split("<service>")
+        .skip(1)
+        .filter_map(|block| Some((xml_text(block, "serviceType")?, xml_text(block, "controlURL")?)))
+        .collect();
+    IGD_SERVICES
+        .iter()
+        .find_map(|wanted| services.iter().find(|(service_type, _)| service_type == wanted).cloned())
+}
+
+/// Text of the first `<tag>`, ignoring namespace prefixes
+fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("{}>", tag);
+    let start = xml.find(&open).filter(|i| xml[..*i].ends_with('<') || xml[..*i].ends_with(':'))? + open.len();
+    let end = xml[start..].find("</")? + start;
+    Some(xml[start..end].trim().to_string())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_route_table() {
+        let table = "Iface\tDestination\tGateway \tFlags\n\
+                     eth0\t0000A8C0\t00000000\t0001\n\
+                     eth0\t00000000\t0101A8C0\t0003\n";
+        assert_eq!(parse_route_table(table), Some(Ipv4Addr::new(192, 168, 1, 1)));
+        assert_eq!(parse_route_table("Iface\tDestination\tGateway\n"), None);
+    }
+
+    #[test]
+    fn test_find_igd_service() {
+        let description = r#"<root><device><serviceList>
+            <service><serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType><controlURL>/l3f</controlURL></service>
+            <service><serviceType>urn:schemas-upnp-org:service:WANPPPConnection:1</serviceType><controlURL>/ppp</controlURL></service>
+            <service><serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType><controlURL>/ctl/IPConn</controlURL></service>
+            </serviceList></device></root>"#;
+        assert_eq!(
+            find_igd_service(description),
+            Some(("urn:schemas-upnp-org:service:WANIPConnection:1".to_string(), "/ctl/IPConn".to_string()))
+        );
+        let response = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=120\r\nLocation: http://192.168.1.1:5000/rootDesc.xml\r\n\r\n";
+        assert_eq!(ssdp_location(response).as_deref(), Some("http://192.168.1.1:5000/rootDesc.xml"));
+        let fault = "<s:Body><s:Fault><detail><UPnPError><errorCode>725</errorCode></UPnPError></detail></s:Fault></s:Body>";
+        assert_eq!(xml_text(fault, "errorCode").as_deref(), Some("725"));
+    }
+
+    #[tokio::test]
+    async fn test_natpmp_mapping_with_fake_gateway() {
+        let gateway = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = gateway.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 12];
+            let (_, client) = gateway.recv_from(&mut buf).await.unwrap();
+            assert_eq!(buf, natpmp_map_request(25565, LEASE_SECS));
+            let mut response = vec![0, 128 + NATPMP_OP_MAP_TCP, 0, 0, 0, 0, 0, 1];
+            response.extend_from_slice(&buf[4..6]);
+            response.extend_from_slice(&25565u16.to_be_bytes());
+            response.extend_from_slice(&7200u32.to_be_bytes());
+            gateway.send_to(&response, client).await.unwrap();
+        });
+        assert_eq!(natpmp_map(address, 25565, LEASE_SECS).await.unwrap(), Some((25565, 7200)));
+
+        let refused = [0, 128 + NATPMP_OP_MAP_TCP, 0, 2, 0, 0, 0, 1, 0x63, 0xDD, 0, 0, 0, 0, 0, 0];
+        assert!(parse_natpmp_mapping(&refused).unwrap_err().to_string().contains("refused"));
+    }
+}
@@ -1,14 +1,1186 @@
-// Pregeneration module placeholder
-// This will be implemented later
+//! World pregeneration: generate the chunks within a radius of a point before
+//! players get there.
+//!
+//! The running server does the generating, so chunks come out exactly as the
+//! game would make them. Chunks are force-loaded over RCON one square batch at
+//! a time (`forceload add`), the world is saved until the region files show
+//! every chunk of the batch fully generated, and the batch is released again.
+//! Batches go outwards from the center, and ones already generated are
+//! skipped, so growing a finished radius only generates the new ring.
+//!
+//! A job can cover several dimensions. Its center and radius are given in
+//! overworld coordinates; the nether part is scaled down 1:8 to match (unless
+//! `scale_nether` is off), and each dimension is generated in turn from its
+//! own region folder with its own progress.
+//!
+//! Jobs are persisted as `tasks` rows (kind `pregen`), run one at a time
+//! through the job manager and report progress over the WebSocket. Starting a
+//! cancelled or failed job again continues from the batch it stopped at.
+//!
+//! Once every batch has run, a verification pass scans the region files for
+//! the whole area. Chunks still missing are force-loaded again, batch by
+//! batch, for up to [`MAX_VERIFY_PASSES`] passes, and the coverage found is
+//! kept with the job. [`coverage_grid`] turns a scan into cells for the map.
+//!
+//! Force-loaded chunks are generated on the server's main thread, taking tick
+//! time from the players on it. Before each batch the job reads the server's
+//! tick timings over RCON (see [`tick_timings`]); while ticks run over
+//! [`TARGET_MSPT`], batches are spaced out in proportion through a
+//! [`PregenThrottle`], and the current factor is kept on the job.
 
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, ServerConfig, Task};
+use crate::jobs::{self, JobHandle, JobManager};
+use crate::tick_timings;
+use crate::websocket_manager::{WebSocketManager, WebSocketMessage};
+use crate::world::{self, light, region};
+use crate::core::error_handler::NotFound;
+
+const TASK_KIND: &str = "pregen";
+/// Chunks per side of a batch; `forceload` takes at most 256 chunks at once
+const MAX_BATCH_SIZE: u32 = 16;
+/// Largest radius a job may cover, in blocks
+const MAX_RADIUS: u32 = 100_000;
+/// Overworld blocks per nether block
+const NETHER_SCALE: i32 = 8;
+/// How often a loading batch is saved and checked
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Chunks of a batch still missing after this long are given up on
+const BATCH_TIMEOUT: Duration = Duration::from_secs(180);
+/// Tick time above which batches are spaced out; a tick has 50 ms at 20 TPS
+const TARGET_MSPT: f64 = 40.0;
+/// Slowest pregeneration is allowed to go, as a fraction of full speed
+const MIN_THROTTLE: f64 = 0.1;
+/// Share of the gap to the target factor closed per sample once the server catches up
+const THROTTLE_RECOVERY: f64 = 0.25;
+/// Times missing chunks are re-queued after all batches have run
+const MAX_VERIFY_PASSES: u32 = 2;
+/// Coverage grids are kept to at most this many cells per side by default
+const MAX_COVERAGE_CELLS: u32 = 128;
+
+/// Chunk area, inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkArea {
+    pub min_x: i32,
+    pub min_z: i32,
+    pub max_x: i32,
+    pub max_z: i32,
+}
+
+impl ChunkArea {
+    /// Chunks within `radius` blocks of a point, as a square like the world border
+    pub fn around(center_x: i32, center_z: i32, radius: u32) -> Self {
+        let radius = radius as i32;
+        Self {
+            min_x: (center_x - radius).div_euclid(16),
+            min_z: (center_z - radius).div_euclid(16),
+            max_x: (center_x + radius).div_euclid(16),
+            max_z: (center_z + radius).div_euclid(16),
+        }
+    }
+
+    pub fn chunk_count(&self) -> u64 {
+        (self.max_x - self.min_x + 1) as u64 * (self.max_z - self.min_z + 1) as u64
+    }
+
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        (self.min_x..=self.max_x).contains(&x) && (self.min_z..=self.max_z).contains(&z)
+    }
+
+    /// Regions holding any of the area's chunks
+    fn regions(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (min_x, max_x) = (self.min_x.div_euclid(32), self.max_x.div_euclid(32));
+        let (min_z, max_z) = (self.min_z.div_euclid(32), self.max_z.div_euclid(32));
+        (min_z..=max_z).flat_map(move |z| (min_x..=max_x).map(move |x| (x, z)))
+    }
+
+    /// Square batches of at most `size` chunks per side covering the area, nearest the center first
+    pub fn batches(&self, size: u32) -> Vec<ChunkArea> {
+        let size = size.max(1) as i32;
+        let center = ((self.min_x + self.max_x) / 2, (self.min_z + self.max_z) / 2);
+        let mut batches = Vec::new();
+        for min_z in (self.min_z..=self.max_z).step_by(size as usize) {
+            for min_x in (self.min_x..=self.max_x).step_by(size as usize) {
+                batches.push(ChunkArea {
+                    min_x,
+                    min_z,
+                    max_x: (min_x + size - 1).min(self.max_x),
+                    max_z: (min_z + size - 1).min(self.max_z),
+                });
+            }
+        }
+        batches.sort_by_key(|batch| {
+            let dx = (batch.min_x + batch.max_x) / 2 - center.0;
+            let dz = (batch.min_z + batch.max_z) / 2 - center.1;
+            (dx.abs().max(dz.abs()), dx * dx + dz * dz)
+        });
+        batches
+    }
+
+    /// Smallest area holding all of `chunks`
+    fn bounding(chunks: impl IntoIterator<Item = (i32, i32)>) -> Option<ChunkArea> {
+        chunks.into_iter().fold(None, |area, (x, z)| {
+            Some(match area {
+                None => ChunkArea { min_x: x, min_z: z, max_x: x, max_z: z },
+                Some(area) => ChunkArea {
+                    min_x: area.min_x.min(x),
+                    min_z: area.min_z.min(z),
+                    max_x: area.max_x.max(x),
+                    max_z: area.max_z.max(z),
+                },
+            })
+        })
+    }
+
+    /// Block coordinates of the area's corners, as `forceload` takes them
+    fn block_corners(&self) -> String {
+        format!("{} {} {} {}", self.min_x * 16, self.min_z * 16, self.max_x * 16 + 15, self.max_z * 16 + 15)
+    }
+}
+
+/// Chunks of `area` the region files in `dir` hold fully generated. Damaged
+/// chunks count as missing.
+pub fn generated_chunks(dir: &Path, area: &ChunkArea) -> Result<HashSet<(i32, i32)>> {
+    let mut generated = HashSet::new();
+    for (region_x, region_z) in area.regions() {
+        let path = dir.join(format!("r.{}.{}.mca", region_x, region_z));
+        if !path.exists() {
+            continue;
+        }
+        let bytes = std::fs::read(&path)?;
+        let Ok((region, _damaged)) = region::Region::parse_lenient(region_x, region_z, &bytes, Some(dir)) else {
+            continue;
+        };
+        for (index, raw) in region.present() {
+            let (x, z) = region::chunk_coords(region_x, region_z, index);
+            if area.contains(x, z) && raw.decode().is_ok_and(|chunk| light::is_fully_generated(&chunk)) {
+                generated.insert((x, z));
+            }
+        }
+    }
+    Ok(generated)
+}
+
+/// Areas to force-load again: per batch, the smallest area holding its chunks
+/// that are not generated
+fn requeue_areas(batches: &[ChunkArea], generated: &HashSet<(i32, i32)>) -> Vec<ChunkArea> {
+    batches
+        .iter()
+        .filter_map(|batch| {
+            let chunks = (batch.min_z..=batch.max_z).flat_map(|z| (batch.min_x..=batch.max_x).map(move |x| (x, z)));
+            ChunkArea::bounding(chunks.filter(|chunk| !generated.contains(chunk)))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageCell {
+    /// Cell coordinates; multiply by `cell_size` for block coordinates
+    pub x: i32,
+    pub z: i32,
+    /// Share of the cell's chunks within the job's area that are generated, 0..1
+    pub value: f64,
+    pub chunks: u32,
+    pub generated: u32,
+}
+
+/// Generated chunks within a job's area, for the map
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PregenerationCoverage {
+    pub job_id: String,
+    pub dimension: String,
+    pub center_x: i32,
+    pub center_z: i32,
+    pub radius: u32,
+    /// Cell side length in blocks
+    pub cell_size: u32,
+    pub cells: Vec<CoverageCell>,
+    pub chunks_total: u64,
+    pub chunks_generated: u64,
+    /// Share of the area's chunks that are generated, 0..1
+    pub coverage: f64,
+    pub scanned_at: DateTime<Utc>,
+}
+
+/// Chunks per cell side keeping a grid of `area` within [`MAX_COVERAGE_CELLS`] cells per side
+fn default_cell_chunks(area: &ChunkArea) -> u32 {
+    let side = (area.max_x - area.min_x).max(area.max_z - area.min_z) as u32 + 1;
+    side.div_ceil(MAX_COVERAGE_CELLS).max(1)
+}
+
+/// Group the chunks of `area` into cells of `cell_chunks` chunks per side, row by row
+pub fn coverage_grid(area: &ChunkArea, generated: &HashSet<(i32, i32)>, cell_chunks: u32) -> Vec<CoverageCell> {
+    let size = cell_chunks.max(1) as i32;
+    let (min_x, max_x) = (area.min_x.div_euclid(size), area.max_x.div_euclid(size));
+    let (min_z, max_z) = (area.min_z.div_euclid(size), area.max_z.div_euclid(size));
+    let mut cells = Vec::new();
+    for z in min_z..=max_z {
+        for x in min_x..=max_x {
+            let cell = ChunkArea {
+                min_x: (x * size).max(area.min_x),
+                min_z: (z * size).max(area.min_z),
+                max_x: (x * size + size - 1).min(area.max_x),
+                max_z: (z * size + size - 1).min(area.max_z),
+            };
+            cells.push(CoverageCell { x, z, value: 0.0, chunks: cell.chunk_count() as u32, generated: 0 });
+        }
+    }
+
+    let width = (max_x - min_x + 1) as usize;
+    for &(x, z) in generated.iter().filter(|(x, z)| area.contains(*x, *z)) {
+        let index = (z.div_euclid(size) - min_z) as usize * width + (x.div_euclid(size) - min_x) as usize;
+        cells[index].generated += 1;
+    }
+    for cell in &mut cells {
+        cell.value = cell.generated as f64 / cell.chunks as f64;
+    }
+    cells
+}
+
+/// One dimension of a job. Dimensions are generated in turn, each with its
+/// own area, progress and verification.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DimensionPregeneration {
+    /// Namespaced dimension ID, such as `minecraft:the_nether`
+    pub dimension: String,
+    /// Center and radius in this dimension's coordinates
+    pub center_x: i32,
+    pub center_z: i32,
+    pub radius: u32,
+    /// pending, running, done, failed or cancelled
+    pub status: String,
+    pub progress: f64,
+    pub chunks_total: u64,
+    /// Chunks this job generated
+    pub chunks_generated: u64,
+    /// Chunks that were already generated
+    pub chunks_existing: u64,
+    /// Chunks still not generated after verification, or when their batch timed out
+    pub chunks_missing: u64,
+    pub batches_total: u32,
+    pub batches_done: u32,
+    /// Verification passes that re-queued missing chunks
+    pub verification_passes: u32,
+    /// Chunks re-queued by verification
+    pub chunks_requeued: u64,
+    /// Share of the area found generated by the last verification, 0..1
+    pub coverage: Option<f64>,
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+impl DimensionPregeneration {
+    /// The part of a job for `dimension`, covering the overworld area given.
+    /// Nether coordinates are an eighth of the overworld's, so with
+    /// `scale_nether` the nether area is scaled down to match.
+    fn new(dimension: &str, center_x: i32, center_z: i32, radius: u32, scale_nether: bool, batch_size: u32) -> Self {
+        let (center_x, center_z, radius) = if scale_nether && dimension == "minecraft:the_nether" {
+            (center_x.div_euclid(NETHER_SCALE), center_z.div_euclid(NETHER_SCALE), radius.div_ceil(NETHER_SCALE as u32))
+        } else {
+            (center_x, center_z, radius)
+        };
+        let area = ChunkArea::around(center_x, center_z, radius);
+        Self {
+            dimension: dimension.to_string(),
+            center_x,
+            center_z,
+            radius,
+            status: jobs::STATUS_PENDING.to_string(),
+            chunks_total: area.chunk_count(),
+            batches_total: area.batches(batch_size).len() as u32,
+            ..Default::default()
+        }
+    }
+
+    pub fn area(&self) -> ChunkArea {
+        ChunkArea::around(self.center_x, self.center_z, self.radius)
+    }
+
+    /// Whether running the job again has anything left to do here
+    fn is_finished(&self) -> bool {
+        self.status == jobs::STATUS_DONE && self.chunks_missing == 0
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PregenerationJob {
-    pub id: Uuid,
+    pub id: String,
     pub server_id: String,
-    pub status: String,
+    pub name: String,
+    /// Center and radius as requested, in overworld coordinates
+    pub center_x: i32,
+    pub center_z: i32,
+    /// Blocks from the center in each direction
+    pub radius: u32,
+    /// Whether the nether area is scaled down 1:8 from the overworld's
+    pub scale_nether: bool,
+    /// Chunks per side of a batch
+    pub batch_size: u32,
+    /// What created the job, such as `world_border`; `None` when created through the API
+    pub source: Option<String>,
     pub progress: f64,
+    /// pending, queued, running, done, failed or cancelled
+    pub status: String,
+    pub dimensions: Vec<DimensionPregeneration>,
+    /// Totals over all dimensions
+    pub chunks_total: u64,
+    pub chunks_generated: u64,
+    pub chunks_existing: u64,
+    pub chunks_missing: u64,
+    /// Pace batches are submitted at, 1.0 being full speed; lowered while the server's ticks run long
+    pub throttle_factor: f64,
+    /// Milliseconds per tick the throttle was last computed from
+    pub mspt: Option<f64>,
+    pub error: Option<String>,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
-}
\ No newline at end of file
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Job fields kept in the task's metadata column
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct JobMetadata {
+    name: String,
+    center_x: i32,
+    center_z: i32,
+    radius: u32,
+    scale_nether: bool,
+    batch_size: u32,
+    source: Option<String>,
+    dimensions: Vec<DimensionPregeneration>,
+    throttle: PregenThrottle,
+}
+
+impl PregenerationJob {
+    fn from_task(task: &Task) -> Self {
+        let metadata: JobMetadata = task
+            .metadata
+            .clone()
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default();
+
+        let mut job = Self {
+            id: task.id.clone(),
+            server_id: task.server_id.clone().unwrap_or_default(),
+            name: metadata.name,
+            center_x: metadata.center_x,
+            center_z: metadata.center_z,
+            radius: metadata.radius,
+            scale_nether: metadata.scale_nether,
+            batch_size: metadata.batch_size,
+            source: metadata.source,
+            progress: task.progress,
+            status: task.status.clone(),
+            dimensions: metadata.dimensions,
+            chunks_total: 0,
+            chunks_generated: 0,
+            chunks_existing: 0,
+            chunks_missing: 0,
+            throttle_factor: metadata.throttle.factor,
+            mspt: metadata.throttle.mspt,
+            error: if task.status == jobs::STATUS_FAILED { task.log.clone() } else { None },
+            started_at: task.started_at,
+            finished_at: task.finished_at,
+            created_at: task.created_at,
+            updated_at: task.updated_at,
+        };
+        job.update_totals();
+        job
+    }
+
+    fn to_task(&self) -> Task {
+        let metadata = JobMetadata {
+            name: self.name.clone(),
+            center_x: self.center_x,
+            center_z: self.center_z,
+            radius: self.radius,
+            scale_nether: self.scale_nether,
+            batch_size: self.batch_size,
+            source: self.source.clone(),
+            dimensions: self.dimensions.clone(),
+            throttle: PregenThrottle { factor: self.throttle_factor, mspt: self.mspt },
+        };
+
+        Task {
+            id: self.id.clone(),
+            server_id: Some(self.server_id.clone()),
+            kind: TASK_KIND.to_string(),
+            status: self.status.clone(),
+            progress: self.progress,
+            log: self.error.clone(),
+            metadata: serde_json::to_value(metadata).ok(),
+            started_at: self.started_at,
+            finished_at: self.finished_at,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+
+    /// Recompute the totals and overall progress from the dimensions
+    fn update_totals(&mut self) {
+        let sum = |count: fn(&DimensionPregeneration) -> u64| self.dimensions.iter().map(count).sum::<u64>();
+        self.chunks_total = sum(|part| part.chunks_total);
+        self.chunks_generated = sum(|part| part.chunks_generated);
+        self.chunks_existing = sum(|part| part.chunks_existing);
+        self.chunks_missing = sum(|part| part.chunks_missing);
+        let batches_total = sum(|part| part.batches_total as u64);
+        if batches_total > 0 {
+            self.progress = sum(|part| part.batches_done as u64) as f64 / batches_total as f64;
+        }
+    }
+
+    pub fn dimension(&self, dimension: &str) -> Option<&DimensionPregeneration> {
+        let dimension = world::dimension_id(dimension)?;
+        self.dimensions.iter().find(|part| part.dimension == dimension)
+    }
+
+    pub fn is_active(&self) -> bool {
+        jobs::is_active(&self.status)
+    }
+}
+
+/// Parameters for a new pregeneration job
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewPregenerationJob {
+    pub name: Option<String>,
+    /// `overworld` when neither this nor `dimension` is given
+    pub dimensions: Option<Vec<String>>,
+    /// A single dimension, for callers that don't need several
+    pub dimension: Option<String>,
+    /// In overworld coordinates
+    #[serde(default)]
+    pub center_x: i32,
+    #[serde(default)]
+    pub center_z: i32,
+    pub radius: u32,
+    /// `true` by default
+    pub scale_nether: Option<bool>,
+    pub batch_size: Option<u32>,
+    #[serde(skip)]
+    pub source: Option<String>,
+}
+
+/// Runs pregeneration jobs and tracks which ones are in flight
+pub struct PregenerationManager {
+    database: Arc<DatabaseManager>,
+    websocket_manager: Arc<WebSocketManager>,
+    process_manager: Arc<ProcessManager>,
+    jobs: Arc<JobManager>,
+}
+
+impl PregenerationManager {
+    pub fn new(
+        database: Arc<DatabaseManager>,
+        websocket_manager: Arc<WebSocketManager>,
+        process_manager: Arc<ProcessManager>,
+        jobs: Arc<JobManager>,
+    ) -> Self {
+        Self {
+            database,
+            websocket_manager,
+            process_manager,
+            jobs,
+        }
+    }
+
+    /// Region folder of a dimension of the server's world
+    fn region_dir(server: &ServerConfig, dimension: &str) -> PathBuf {
+        world::server_region_dir(server, dimension).unwrap_or_else(|| world::server_world_dir(server).join("region"))
+    }
+
+    pub async fn list_jobs(&self, server_id: &str) -> Result<Vec<PregenerationJob>> {
+        let tasks = self.database.get_tasks_by_server(server_id).await?;
+        let mut jobs = Vec::new();
+        for task in tasks.into_iter().filter(|task| task.kind == TASK_KIND) {
+            jobs.push(PregenerationJob::from_task(&self.jobs.reconcile(task).await?));
+        }
+        Ok(jobs)
+    }
+
+    pub async fn get_job(&self, server_id: &str, job_id: &str) -> Result<Option<PregenerationJob>> {
+        let task = self
+            .jobs
+            .get(job_id)
+            .await?
+            .filter(|task| task.kind == TASK_KIND && task.server_id.as_deref() == Some(server_id));
+        Ok(task.map(|task| PregenerationJob::from_task(&task)))
+    }
+
+    pub async fn create_job(&self, server_id: &str, request: NewPregenerationJob) -> Result<PregenerationJob> {
+        let server = self.database.require_server(server_id).await?;
+        if !server.managed {
+            bail!("Only servers Guardian runs can be pregenerated");
+        }
+        let requested = match (request.dimensions, request.dimension) {
+            (Some(dimensions), _) => dimensions,
+            (None, Some(dimension)) => vec![dimension],
+            (None, None) => vec!["overworld".to_string()],
+        };
+        let mut dimensions: Vec<&'static str> = Vec::new();
+        for dimension in &requested {
+            let id = world::dimension_id(dimension).ok_or_else(|| anyhow!("Unknown dimension '{}'", dimension))?;
+            if !dimensions.contains(&id) {
+                dimensions.push(id);
+            }
+        }
+        if dimensions.is_empty() {
+            bail!("At least one dimension is required");
+        }
+        if request.radius == 0 || request.radius > MAX_RADIUS {
+            bail!("radius must be between 1 and {} blocks", MAX_RADIUS);
+        }
+        let batch_size = request.batch_size.unwrap_or(MAX_BATCH_SIZE);
+        if !(1..=MAX_BATCH_SIZE).contains(&batch_size) {
+            bail!("batch_size must be between 1 and {} chunks", MAX_BATCH_SIZE);
+        }
+
+        let scale_nether = request.scale_nether.unwrap_or(true);
+        let now = Utc::now();
+        let mut job = PregenerationJob {
+            id: Uuid::new_v4().to_string(),
+            server_id: server_id.to_string(),
+            name: request.name.unwrap_or_else(|| format!("Pregenerate {} blocks", request.radius)),
+            center_x: request.center_x,
+            center_z: request.center_z,
+            radius: request.radius,
+            scale_nether,
+            batch_size,
+            source: request.source,
+            progress: 0.0,
+            status: jobs::STATUS_PENDING.to_string(),
+            dimensions: dimensions
+                .iter()
+                .map(|dimension| {
+                    DimensionPregeneration::new(dimension, request.center_x, request.center_z, request.radius, scale_nether, batch_size)
+                })
+                .collect(),
+            chunks_total: 0,
+            chunks_generated: 0,
+            chunks_existing: 0,
+            chunks_missing: 0,
+            throttle_factor: 1.0,
+            mspt: None,
+            error: None,
+            started_at: None,
+            finished_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        job.update_totals();
+        self.database.create_task(&job.to_task()).await?;
+        Ok(job)
+    }
+
+    pub async fn start_job(self: &Arc<Self>, server_id: &str, job_id: &str) -> Result<PregenerationJob> {
+        let mut job = self
+            .get_job(server_id, job_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Pregeneration job", job_id))?;
+        if job.is_active() {
+            bail!("Pregeneration job is already running");
+        }
+        // A completed job with missing chunks runs verification again
+        if job.dimensions.iter().all(DimensionPregeneration::is_finished) {
+            bail!("Pregeneration job has already completed");
+        }
+        let server_uuid = Uuid::parse_str(server_id)?;
+        if !self.process_manager.is_server_running(server_uuid).await {
+            bail!("Start the server to pregenerate its world");
+        }
+
+        let handle = self.jobs.track(&job.id, TASK_KIND).map_err(|_| anyhow!("Pregeneration job is already running"))?;
+
+        job.status = jobs::STATUS_QUEUED.to_string();
+        job.error = None;
+        job.finished_at = None;
+        job.updated_at = Utc::now();
+        self.database.update_task(&job.to_task()).await?;
+
+        let manager = self.clone();
+        let queued = job.clone();
+        tokio::spawn(async move { manager.run(job, server_uuid, handle).await });
+
+        Ok(queued)
+    }
+
+    async fn run(&self, mut job: PregenerationJob, server_uuid: Uuid, handle: JobHandle) {
+        let server_id = job.server_id.clone();
+        let Some(_slot) = handle.slot().await else {
+            job.status = jobs::STATUS_CANCELLED.to_string();
+            job.finished_at = Some(Utc::now());
+            job.updated_at = Utc::now();
+            if let Err(e) = self.database.update_task(&job.to_task()).await {
+                warn!("Failed to persist pregeneration job {}: {}", job.id, e);
+            }
+            return;
+        };
+
+        job.status = jobs::STATUS_RUNNING.to_string();
+        job.started_at.get_or_insert_with(Utc::now);
+        job.updated_at = Utc::now();
+        let steps = job.dimensions.len() as u32;
+        let _ = self.websocket_manager.send_job_started(Some(&server_id), &job.id, TASK_KIND, steps).await;
+
+        let result = match self.database.update_task(&job.to_task()).await {
+            Ok(()) => self.process(&mut job, server_uuid, &handle).await,
+            Err(e) => Err(e),
+        };
+        job.finished_at = Some(Utc::now());
+        job.updated_at = Utc::now();
+
+        match result {
+            Ok(()) if handle.is_cancelled() => {
+                job.status = jobs::STATUS_CANCELLED.to_string();
+                let _ = self.websocket_manager.send_job_failed(Some(&server_id), &job.id, TASK_KIND, "Cancelled").await;
+            }
+            Ok(()) => {
+                job.status = jobs::STATUS_DONE.to_string();
+                job.progress = 1.0;
+                let message = format!(
+                    "Generated {} chunks, {} already generated, {} missing",
+                    job.chunks_generated, job.chunks_existing, job.chunks_missing
+                );
+                info!("Pregeneration job {} finished: {}", job.id, message);
+                let _ = self
+                    .websocket_manager
+                    .send_job_completed(Some(&server_id), &job.id, TASK_KIND, Some(&message))
+                    .await;
+            }
+            Err(e) => {
+                warn!("Pregeneration job {} failed: {}", job.id, e);
+                job.status = jobs::STATUS_FAILED.to_string();
+                job.error = Some(e.to_string());
+                let _ = self.websocket_manager.send_job_failed(Some(&server_id), &job.id, TASK_KIND, &e.to_string()).await;
+            }
+        }
+        // The dimension that was interrupted ends the way the job did
+        for part in job.dimensions.iter_mut().filter(|part| part.status == jobs::STATUS_RUNNING) {
+            part.status = job.status.clone();
+        }
+
+        if let Err(e) = self.database.update_task(&job.to_task()).await {
+            warn!("Failed to persist pregeneration job {}: {}", job.id, e);
+        }
+    }
+
+    async fn process(&self, job: &mut PregenerationJob, server_uuid: Uuid, handle: &JobHandle) -> Result<()> {
+        let server = self.database.require_server(&job.server_id).await?;
+        for index in 0..job.dimensions.len() {
+            if job.dimensions[index].is_finished() {
+                continue;
+            }
+            if handle.is_cancelled() {
+                return Ok(());
+            }
+            job.dimensions[index].status = jobs::STATUS_RUNNING.to_string();
+            self.process_dimension(job, index, &server, server_uuid, handle).await?;
+            if handle.is_cancelled() {
+                return Ok(());
+            }
+            job.dimensions[index].status = jobs::STATUS_DONE.to_string();
+            job.updated_at = Utc::now();
+            self.database.update_task(&job.to_task()).await?;
+        }
+        Ok(())
+    }
+
+    async fn process_dimension(
+        &self,
+        job: &mut PregenerationJob,
+        index: usize,
+        server: &ServerConfig,
+        server_uuid: Uuid,
+        handle: &JobHandle,
+    ) -> Result<()> {
+        let dimension = job.dimensions[index].dimension.clone();
+        let dir = Self::region_dir(server, &dimension);
+        let batches = job.dimensions[index].area().batches(job.batch_size);
+        job.dimensions[index].batches_total = batches.len() as u32;
+        let started = std::time::Instant::now();
+        let resumed_at = job.dimensions[index].batches_done;
+
+        for batch in batches.iter().skip(resumed_at as usize) {
+            if handle.is_cancelled() {
+                return Ok(());
+            }
+            if !self.process_manager.is_server_running(server_uuid).await {
+                bail!("Server stopped during pregeneration");
+            }
+            self.throttle(job, server, handle).await;
+            if handle.is_cancelled() {
+                return Ok(());
+            }
+
+            let outcome = self.generate_batch(server, &dir, &dimension, batch, handle).await?;
+            let part = &mut job.dimensions[index];
+            part.chunks_generated += outcome.generated;
+            part.chunks_existing += outcome.existing;
+            part.chunks_missing += outcome.missing;
+            part.batches_done += 1;
+            part.progress = part.batches_done as f64 / part.batches_total.max(1) as f64;
+            let done_here = (part.batches_done - resumed_at) as f64;
+            let left = (part.batches_total - part.batches_done) as f64;
+            let eta_seconds = (started.elapsed().as_secs_f64() / done_here * left) as u64;
+            let step = format!("{} batch {}/{}", dimension, part.batches_done, part.batches_total);
+            let mut message = format!("{} of {} chunks generated", part.chunks_generated + part.chunks_existing, part.chunks_total);
+            if job.throttle_factor < 1.0 {
+                message.push_str(&format!(", throttled to {:.0}% while the server is falling behind", job.throttle_factor * 100.0));
+            }
+            job.update_totals();
+            job.updated_at = Utc::now();
+            self.database.update_task(&job.to_task()).await?;
+
+            let _ = self
+                .websocket_manager
+                .send_job_progress(Some(&job.server_id), &job.id, TASK_KIND, &step, job.progress as f32, 1, Some(&message))
+                .await;
+            let _ = self
+                .websocket_manager
+                .broadcast(WebSocketMessage::PregenProgress {
+                    server_id: job.server_id.clone(),
+                    timestamp: Utc::now(),
+                    job_id: job.id.clone(),
+                    progress: job.progress,
+                    eta_seconds: Some(eta_seconds),
+                })
+                .await;
+        }
+        self.verify(job, index, server, server_uuid, handle).await
+    }
+
+    /// Scan a dimension's whole area and force-load missing chunks again until
+    /// none are left or [`MAX_VERIFY_PASSES`] passes have run
+    async fn verify(
+        &self,
+        job: &mut PregenerationJob,
+        index: usize,
+        server: &ServerConfig,
+        server_uuid: Uuid,
+        handle: &JobHandle,
+    ) -> Result<()> {
+        let dimension = job.dimensions[index].dimension.clone();
+        let dir = Self::region_dir(server, &dimension);
+        let batches = job.dimensions[index].area().batches(job.batch_size);
+        let mut passes = 0;
+        loop {
+            if handle.is_cancelled() {
+                return Ok(());
+            }
+            let step = format!("{} verification", dimension);
+            let _ = self
+                .websocket_manager
+                .send_job_progress(Some(&job.server_id), &job.id, TASK_KIND, &step, job.progress as f32, 1, Some("Checking coverage"))
+                .await;
+            let part = &mut job.dimensions[index];
+            let generated = scan(&dir, &part.area()).await?;
+            let missing = part.chunks_total.saturating_sub(generated.len() as u64);
+            part.chunks_missing = missing;
+            part.coverage = Some(generated.len() as f64 / part.chunks_total.max(1) as f64);
+            part.verified_at = Some(Utc::now());
+            if missing > 0 && passes < MAX_VERIFY_PASSES {
+                part.verification_passes += 1;
+                part.chunks_requeued += missing;
+            }
+            job.update_totals();
+            job.updated_at = Utc::now();
+            self.database.update_task(&job.to_task()).await?;
+            if missing == 0 || passes == MAX_VERIFY_PASSES {
+                if missing > 0 {
+                    warn!("{} chunks of {} in pregeneration job {} are still missing after verification", missing, dimension, job.id);
+                }
+                return Ok(());
+            }
+
+            passes += 1;
+            info!("Re-queueing {} missing chunks of {} in pregeneration job {} (pass {})", missing, dimension, job.id, passes);
+            for area in requeue_areas(&batches, &generated) {
+                if handle.is_cancelled() {
+                    return Ok(());
+                }
+                if !self.process_manager.is_server_running(server_uuid).await {
+                    bail!("Server stopped during pregeneration");
+                }
+                let outcome = self.generate_batch(server, &dir, &dimension, &area, handle).await?;
+                job.dimensions[index].chunks_generated += outcome.generated;
+            }
+        }
+    }
+
+    /// Scan one dimension of a job (the first by default) for generated chunks,
+    /// grouped into cells of `cell_chunks` chunks per side (chosen from the
+    /// area's size by default)
+    pub async fn coverage(
+        &self,
+        server_id: &str,
+        job_id: &str,
+        dimension: Option<&str>,
+        cell_chunks: Option<u32>,
+    ) -> Result<PregenerationCoverage> {
+        let job = self
+            .get_job(server_id, job_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Pregeneration job", job_id))?;
+        let part = match dimension {
+            Some(dimension) => job.dimension(dimension),
+            None => job.dimensions.first(),
+        }
+        .ok_or_else(|| anyhow!("Pregeneration job {} does not cover {}", job_id, dimension.unwrap_or("any dimension")))?
+        .clone();
+        let server = self.database.require_server(server_id).await?;
+        let area = part.area();
+        let cell_chunks = cell_chunks.unwrap_or_else(|| default_cell_chunks(&area)).max(1);
+        if area.chunk_count() / (cell_chunks as u64 * cell_chunks as u64) > (4 * MAX_COVERAGE_CELLS * MAX_COVERAGE_CELLS) as u64 {
+            bail!("cell_chunks {} makes too many cells for this area", cell_chunks);
+        }
+
+        let generated = scan(&Self::region_dir(&server, &part.dimension), &area).await?;
+        let cells = tokio::task::spawn_blocking(move || coverage_grid(&area, &generated, cell_chunks)).await?;
+        let chunks_generated = cells.iter().map(|cell| cell.generated as u64).sum::<u64>();
+        Ok(PregenerationCoverage {
+            job_id: job.id,
+            dimension: part.dimension,
+            center_x: part.center_x,
+            center_z: part.center_z,
+            radius: part.radius,
+            cell_size: cell_chunks * 16,
+            cells,
+            chunks_total: area.chunk_count(),
+            chunks_generated,
+            coverage: chunks_generated as f64 / area.chunk_count() as f64,
+            scanned_at: Utc::now(),
+        })
+    }
+
+    /// Hold back the next batch for as long as the server's tick time calls for,
+    /// re-sampling it every [`POLL_INTERVAL`] so the job speeds up as soon as it catches up
+    async fn throttle(&self, job: &mut PregenerationJob, server: &ServerConfig, handle: &JobHandle) {
+        let mut waited = Duration::ZERO;
+        loop {
+            let previous = PregenThrottle { factor: job.throttle_factor, mspt: job.mspt };
+            let throttle = previous.next(tick_ms(server).await);
+            if throttle.is_throttled() != previous.is_throttled() {
+                match throttle.mspt {
+                    Some(mspt) if throttle.is_throttled() => info!(
+                        "Server {} at {:.0} ms per tick; throttling pregeneration to {:.0}%",
+                        server.id,
+                        mspt,
+                        throttle.factor * 100.0
+                    ),
+                    _ => info!("Server {} caught up; pregeneration back at full speed", server.id),
+                }
+            }
+            job.throttle_factor = throttle.factor;
+            job.mspt = throttle.mspt;
+            let delay = throttle.batch_delay(POLL_INTERVAL);
+            if waited >= delay || handle.is_cancelled() {
+                return;
+            }
+            let step = (delay - waited).min(POLL_INTERVAL);
+            tokio::time::sleep(step).await;
+            waited += step;
+        }
+    }
+
+    /// Force-load one batch until its chunks are generated or it times out
+    async fn generate_batch(
+        &self,
+        server: &ServerConfig,
+        dir: &Path,
+        dimension: &str,
+        batch: &ChunkArea,
+        handle: &JobHandle,
+    ) -> Result<BatchOutcome> {
+        let total = batch.chunk_count();
+        let existing = count_generated(dir, batch).await?;
+        if existing == total {
+            return Ok(BatchOutcome { generated: 0, existing, missing: 0 });
+        }
+
+        let corners = batch.block_corners();
+        rcon(server, format!("execute in {} run forceload add {}", dimension, corners)).await?;
+        let deadline = tokio::time::Instant::now() + BATCH_TIMEOUT;
+        let mut generated = existing;
+        let result = loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if let Err(e) = rcon(server, "save-all".to_string()).await {
+                break Err(e);
+            }
+            match count_generated(dir, batch).await {
+                Ok(count) => generated = count,
+                Err(e) => break Err(e),
+            }
+            if generated == total || handle.is_cancelled() || tokio::time::Instant::now() >= deadline {
+                break Ok(());
+            }
+        };
+        // Always release the batch, even when checking it failed
+        if let Err(e) = rcon(server, format!("execute in {} run forceload remove {}", dimension, corners)).await {
+            warn!("Failed to release force-loaded chunks {} on server {}: {}", corners, server.id, e);
+        }
+        result?;
+
+        if generated < total && !handle.is_cancelled() {
+            warn!("{} chunks of batch {} on server {} were not generated in time", total - generated, corners, server.id);
+        }
+        Ok(BatchOutcome { generated: generated - existing, existing, missing: total - generated })
+    }
+
+    /// Request cancellation; the job stops after the batch it is working on
+    pub async fn cancel_job(&self, server_id: &str, job_id: &str) -> Result<()> {
+        self.get_job(server_id, job_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Pregeneration job", job_id))?;
+        self.jobs.cancel(job_id).await?;
+        Ok(())
+    }
+
+    pub async fn delete_job(&self, server_id: &str, job_id: &str) -> Result<()> {
+        let job = self
+            .get_job(server_id, job_id)
+            .await?
+            .ok_or_else(|| NotFound::with_id("Pregeneration job", job_id))?;
+        if job.is_active() {
+            bail!("Cancel the pregeneration job before deleting it");
+        }
+        self.database.delete_task(job_id).await
+    }
+}
+
+/// How fast pregeneration may submit batches
+///
+/// Follows `mspt`, the server's milliseconds per tick. When ticks run long the
+/// factor drops straight away; once the server catches up it climbs back a step
+/// per sample so pregeneration doesn't immediately overload it again.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct PregenThrottle {
+    /// 1.0 submits batches back to back; lower values space them out
+    factor: f64,
+    /// Tick time from the last sample; `None` when the server didn't report one
+    mspt: Option<f64>,
+}
+
+impl Default for PregenThrottle {
+    fn default() -> Self {
+        Self { factor: 1.0, mspt: None }
+    }
+}
+
+impl PregenThrottle {
+    /// The throttle after observing a new tick time
+    fn next(self, mspt: Option<f64>) -> Self {
+        let target = match mspt {
+            Some(mspt) if mspt > TARGET_MSPT => (TARGET_MSPT / mspt).max(MIN_THROTTLE),
+            _ => 1.0,
+        };
+        let factor = if target < self.factor {
+            target
+        } else {
+            let factor = self.factor + (target - self.factor) * THROTTLE_RECOVERY;
+            // Snap the last stretch so the job shows as unthrottled again
+            if target - factor < 0.01 { target } else { factor }
+        };
+        Self { factor, mspt }
+    }
+
+    /// Extra wait before the next batch, given how long a batch normally waits between steps
+    fn batch_delay(&self, base: Duration) -> Duration {
+        base.mul_f64(1.0 / self.factor - 1.0)
+    }
+
+    fn is_throttled(&self) -> bool {
+        self.factor < 1.0
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BatchOutcome {
+    generated: u64,
+    existing: u64,
+    missing: u64,
+}
+
+async fn scan(dir: &Path, area: &ChunkArea) -> Result<HashSet<(i32, i32)>> {
+    let (dir, area) = (dir.to_path_buf(), *area);
+    tokio::task::spawn_blocking(move || generated_chunks(&dir, &area)).await?
+}
+
+async fn count_generated(dir: &Path, area: &ChunkArea) -> Result<u64> {
+    Ok(scan(dir, area).await?.len() as u64)
+}
+
+async fn rcon(server: &ServerConfig, command: String) -> Result<String> {
+    crate::restart_scheduler::rcon(server, command).await
+}
+
+/// The server's current milliseconds per tick, from spark or the loader's own command
+async fn tick_ms(server: &ServerConfig) -> Option<f64> {
+    let spark = tick_timings::has_spark(Path::new(&server.server_directory));
+    for source in tick_timings::sources(&server.loader, spark) {
+        match rcon(server, source.command().to_string()).await {
+            Ok(response) => {
+                if let Some(timings) = tick_timings::parse(source, &response) {
+                    return timings.tick_ms();
+                }
+            }
+            Err(e) => debug!("Failed to run '{}' on server {}: {}", source.command(), server.id, e),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::nbt::{Compound, Tag};
+
+    #[test]
+    fn test_area_and_batches() {
+        let area = ChunkArea::around(0, 0, 100);
+        assert_eq!(area, ChunkArea { min_x: -7, min_z: -7, max_x: 6, max_z: 6 });
+        assert_eq!(area.chunk_count(), 196);
+        assert_eq!(area.block_corners(), "-112 -112 111 111");
+
+        let batches = area.batches(4);
+        assert_eq!(batches.len(), 16);
+        assert_eq!(batches.iter().map(ChunkArea::chunk_count).sum::<u64>(), 196);
+        // Nearest the center first, the corners last
+        assert!(batches[0].contains(0, 0) || batches[0].contains(-1, -1));
+        assert!(batches[15].contains(-7, -7) || batches[15].contains(6, 6) || batches[15].contains(-7, 6) || batches[15].contains(6, -7));
+        assert!(area.batches(MAX_BATCH_SIZE).iter().all(|batch| batch.chunk_count() <= 256));
+    }
+
+    #[test]
+    fn test_dimension_scaling() {
+        let nether = DimensionPregeneration::new("minecraft:the_nether", -1000, 804, 2000, true, 16);
+        assert_eq!((nether.center_x, nether.center_z, nether.radius), (-125, 100, 250));
+        assert_eq!(nether.chunks_total, nether.area().chunk_count());
+        let unscaled = DimensionPregeneration::new("minecraft:the_nether", -1000, 804, 2000, false, 16);
+        assert_eq!((unscaled.center_x, unscaled.radius), (-1000, 2000));
+        let end = DimensionPregeneration::new("minecraft:the_end", -1000, 804, 2000, true, 16);
+        assert_eq!((end.center_x, end.center_z, end.radius), (-1000, 804, 2000));
+
+        let now = Utc::now();
+        let mut job = PregenerationJob {
+            id: "job".to_string(),
+            server_id: "server".to_string(),
+            name: "Spawn".to_string(),
+            center_x: -1000,
+            center_z: 804,
+            radius: 2000,
+            scale_nether: true,
+            batch_size: 16,
+            source: None,
+            progress: 0.0,
+            status: jobs::STATUS_RUNNING.to_string(),
+            dimensions: vec![end, nether],
+            chunks_total: 0,
+            chunks_generated: 0,
+            chunks_existing: 0,
+            chunks_missing: 0,
+            throttle_factor: 1.0,
+            mspt: None,
+            error: None,
+            started_at: None,
+            finished_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        job.dimensions[0].batches_done = job.dimensions[0].batches_total;
+        job.dimensions[0].chunks_existing = 500;
+        job.update_totals();
+        let batches = (job.dimensions[0].batches_total + job.dimensions[1].batches_total) as f64;
+        assert_eq!(job.progress, job.dimensions[0].batches_total as f64 / batches);
+        assert_eq!(job.chunks_total, job.dimensions[0].chunks_total + job.dimensions[1].chunks_total);
+        assert_eq!(job.chunks_existing, 500);
+
+        // Sub-jobs survive the round trip through the task row
+        let restored = PregenerationJob::from_task(&job.to_task());
+        assert_eq!(restored.dimensions.len(), 2);
+        assert_eq!(restored.dimension("nether").unwrap().radius, 250);
+        assert!(restored.dimension("overworld").is_none());
+    }
+
+    #[test]
+    fn test_coverage_and_requeue() {
+        let area = ChunkArea { min_x: -3, min_z: -3, max_x: 4, max_z: 4 };
+        let generated: HashSet<(i32, i32)> = (-3..=4)
+            .flat_map(|z| (-3..=4).map(move |x| (x, z)))
+            .filter(|&chunk| chunk != (0, 0) && chunk != (3, 4) && chunk != (4, 4))
+            .chain([(9, 9)])
+            .collect();
+
+        let cells = coverage_grid(&area, &generated, 4);
+        assert_eq!(cells.len(), 9);
+        // Cells are clipped to the area, and chunks outside it don't count
+        assert_eq!((cells[0].x, cells[0].z, cells[0].chunks), (-1, -1, 9));
+        assert_eq!(cells.iter().map(|cell| cell.chunks).sum::<u32>(), 64);
+        assert_eq!(cells.iter().map(|cell| cell.generated).sum::<u32>(), 61);
+        let origin = cells.iter().find(|cell| cell.x == 0 && cell.z == 0).unwrap();
+        assert_eq!((origin.chunks, origin.generated), (16, 15));
+        assert_eq!(default_cell_chunks(&ChunkArea::around(0, 0, 100_000)), 98);
+
+        let requeued = requeue_areas(&area.batches(4), &generated);
+        assert_eq!(requeued.len(), 2);
+        assert!(requeued.contains(&ChunkArea { min_x: 0, min_z: 0, max_x: 0, max_z: 0 }));
+        assert!(requeued.contains(&ChunkArea { min_x: 3, min_z: 4, max_x: 4, max_z: 4 }));
+    }
+
+    #[test]
+    fn test_pregen_throttle() {
+        let idle = PregenThrottle::default();
+        assert_eq!(idle.next(Some(30.0)).factor, 1.0);
+        assert_eq!(idle.next(None).factor, 1.0);
+        assert_eq!(idle.batch_delay(Duration::from_secs(5)), Duration::ZERO);
+
+        // Falling behind drops the factor at once, down to the floor
+        let busy = idle.next(Some(160.0));
+        assert_eq!(busy.factor, 0.25);
+        assert_eq!(busy.batch_delay(Duration::from_secs(5)), Duration::from_secs(15));
+        assert_eq!(busy.next(Some(1_000.0)).factor, MIN_THROTTLE);
+
+        // Catching up recovers gradually until it snaps back to full speed
+        let mut throttle = busy.next(Some(20.0));
+        assert!(throttle.factor > busy.factor && throttle.is_throttled());
+        for _ in 0..30 {
+            throttle = throttle.next(Some(20.0));
+        }
+        assert_eq!(throttle.factor, 1.0);
+        assert!(!throttle.is_throttled());
+    }
+
+    #[test]
+    fn test_generated_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk = |status: &str| {
+            let mut chunk = Compound::new();
+            chunk.insert("DataVersion", Tag::Int(3700));
+            chunk.insert("Status", Tag::String(status.to_string()));
+            region::RawChunk::encode(&chunk, 1).unwrap()
+        };
+        let mut negative = region::Region::new(-1, -1);
+        negative.set_chunk(region::chunk_index(-1, -1), Some(chunk("minecraft:full")));
+        negative.write(&dir.path().join("r.-1.-1.mca")).unwrap();
+        let mut origin = region::Region::new(0, 0);
+        origin.set_chunk(region::chunk_index(0, 0), Some(chunk("minecraft:full")));
+        origin.set_chunk(region::chunk_index(1, 0), Some(chunk("minecraft:features")));
+        origin.set_chunk(region::chunk_index(20, 20), Some(chunk("minecraft:full")));
+        origin.write(&dir.path().join("r.0.0.mca")).unwrap();
+
+        let area = ChunkArea { min_x: -1, min_z: -1, max_x: 1, max_z: 1 };
+        let generated = generated_chunks(dir.path(), &area).unwrap();
+        // Partly generated chunks and ones outside the area don't count
+        assert_eq!(generated, HashSet::from([(-1, -1), (0, 0)]));
+    }
+}
@@ -0,0 +1,434 @@
+//! Per-server resource limits
+//!
+//! CPU core affinity, process priority and a memory cap for a server's JVM.
+//! ProcessManager applies them when the server starts, and they are applied
+//! straight away when changed while it runs. The memory cap goes through a
+//! cgroup v2 group on Linux and a Job Object on Windows. It covers the whole
+//! process, so it needs room above the heap for metaspace, threads and native
+//! buffers.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Least room a memory limit has to leave above the heap
+pub const MIN_NON_HEAP_MB: u32 = 512;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessPriority {
+    Low,
+    BelowNormal,
+    #[default]
+    Normal,
+    AboveNormal,
+    High,
+}
+
+impl ProcessPriority {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "low" => Some(Self::Low),
+            "below_normal" => Some(Self::BelowNormal),
+            "normal" => Some(Self::Normal),
+            "above_normal" => Some(Self::AboveNormal),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::BelowNormal => "below_normal",
+            Self::Normal => "normal",
+            Self::AboveNormal => "above_normal",
+            Self::High => "high",
+        }
+    }
+
+    /// Unix niceness; below zero needs root or CAP_SYS_NICE
+    pub fn niceness(&self) -> i32 {
+        match self {
+            Self::Low => 10,
+            Self::BelowNormal => 5,
+            Self::Normal => 0,
+            Self::AboveNormal => -5,
+            Self::High => -10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// CPU cores the server may run on; empty for all of them
+    #[serde(default)]
+    pub cpu_cores: Vec<usize>,
+    #[serde(default)]
+    pub priority: ProcessPriority,
+    /// Cap on the whole process's memory, heap included
+    #[serde(default)]
+    pub memory_limit_mb: Option<u32>,
+}
+
+impl ResourceLimits {
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check the limits against this machine and a server with `heap_mb` of heap
+    pub fn validate(&self, heap_mb: u32) -> Result<()> {
+        let cpus = cpu_count();
+        if let Some(core) = self.cpu_cores.iter().find(|core| **core >= cpus) {
+            bail!("CPU core {} does not exist; this machine has cores 0 to {}", core, cpus - 1);
+        }
+        if cfg!(windows) && self.cpu_cores.iter().any(|core| *core >= usize::BITS as usize) {
+            bail!("Only the first {} CPU cores can be picked on Windows", usize::BITS);
+        }
+        if let Some(limit) = self.memory_limit_mb {
+            let needed = heap_mb + MIN_NON_HEAP_MB;
+            if limit < needed {
+                bail!(
+                    "A {} MB memory limit leaves too little room above the {} MB heap; allow at least {} MB",
+                    limit, heap_mb, needed
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Limits as they took effect on a running server
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AppliedLimits {
+    pub cpu_cores: Vec<usize>,
+    pub priority: ProcessPriority,
+    pub memory_limit_mb: Option<u32>,
+    /// What enforces the memory limit: "cgroup" or "job_object"
+    pub memory_limit_via: Option<&'static str>,
+    /// Why a limit could not be applied
+    pub errors: Vec<String>,
+}
+
+/// Limits of a server and what took effect if it is running
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceLimitsStatus {
+    pub limits: ResourceLimits,
+    pub applied: Option<AppliedLimits>,
+    pub cpu_count: usize,
+}
+
+pub fn cpu_count() -> usize {
+    std::thread::available_parallelism().map(|count| count.get()).unwrap_or(1)
+}
+
+/// Apply `limits` to the running process `pid` of a server
+pub fn apply(pid: u32, server_id: &str, limits: &ResourceLimits) -> AppliedLimits {
+    let mut applied = AppliedLimits::default();
+    match set_affinity(pid, &limits.cpu_cores) {
+        Ok(()) => applied.cpu_cores = limits.cpu_cores.clone(),
+        Err(e) => applied.errors.push(format!("CPU affinity: {}", e)),
+    }
+    match set_priority(pid, limits.priority) {
+        Ok(()) => applied.priority = limits.priority,
+        Err(e) => applied.errors.push(format!("Priority: {}", e)),
+    }
+    match set_memory_limit(pid, server_id, limits.memory_limit_mb) {
+        Ok(via) => {
+            applied.memory_limit_mb = limits.memory_limit_mb.filter(|_| via.is_some());
+            applied.memory_limit_via = via;
+        }
+        Err(e) => applied.errors.push(format!("Memory limit: {}", e)),
+    }
+    for error in &applied.errors {
+        tracing::warn!("Resource limits of server {}: {}", server_id, error);
+    }
+    applied
+}
+
+/// Thread IDs of a process; Linux keeps affinity and niceness per thread,
+/// and threads the JVM starts later inherit them
+#[cfg(target_os = "linux")]
+fn threads(pid: u32) -> Result<Vec<libc::pid_t>> {
+    let tasks = std::fs::read_dir(format!("/proc/{}/task", pid))
+        .map_err(|_| anyhow::anyhow!("process {} is not running", pid))?;
+    Ok(tasks
+        .flatten()
+        .filter_map(|task| task.file_name().to_str()?.parse().ok())
+        .collect())
+}
+
+/// Run `call` for every thread of `pid`, skipping threads that exited meanwhile
+#[cfg(target_os = "linux")]
+fn for_each_thread(pid: u32, call: impl Fn(libc::pid_t) -> libc::c_int) -> Result<()> {
+    for tid in threads(pid)? {
+        if call(tid) != 0 {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() != Some(libc::ESRCH) {
+                return Err(error.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_affinity(pid: u32, cores: &[usize]) -> Result<()> {
+    let cores: Vec<usize> = if cores.is_empty() { (0..cpu_count()).collect() } else { cores.to_vec() };
+    // SAFETY: cpu_set_t is a plain bit set, valid when zeroed
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for core in cores {
+        unsafe { libc::CPU_SET(core, &mut set) };
+    }
+    for_each_thread(pid, |tid| unsafe {
+        libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn set_priority(pid: u32, priority: ProcessPriority) -> Result<()> {
+    for_each_thread(pid, |tid| unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, priority.niceness())
+    })
+    .map_err(|e| match e.downcast_ref::<std::io::Error>().and_then(|e| e.raw_os_error()) {
+        Some(libc::EACCES) | Some(libc::EPERM) => {
+            anyhow::anyhow!("raising priority needs root or CAP_SYS_NICE; a lowered one is kept until restart")
+        }
+        _ => e,
+    })
+}
+
+/// Parent of the per-server groups: hostd's own cgroup, or its parent once
+/// hostd has moved into a `hostd` leaf
+#[cfg(target_os = "linux")]
+fn cgroup_parent() -> Result<std::path::PathBuf> {
+    let root = std::path::Path::new("/sys/fs/cgroup");
+    // Hybrid setups mount cgroup v1 controllers there instead
+    if !root.join("cgroup.controllers").exists() {
+        bail!("needs cgroup v2 mounted at {}", root.display());
+    }
+    let cgroups = std::fs::read_to_string("/proc/self/cgroup")?;
+    let own = cgroups
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or_else(|| anyhow::anyhow!("cgroup v2 is not mounted"))?;
+    let own = root.join(own.trim_start_matches('/'));
+    match own.parent() {
+        Some(parent) if own.file_name().is_some_and(|name| name == "hostd") => Ok(parent.to_path_buf()),
+        _ => Ok(own),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn server_cgroup(parent: &std::path::Path, server_id: &str) -> std::path::PathBuf {
+    parent.join(format!("guardian-{}", server_id))
+}
+
+/// Set up the parent of the per-server groups. cgroup v2 only hands the
+/// memory controller to children of a group with no processes of its own,
+/// so hostd first moves itself into a `hostd` leaf.
+#[cfg(target_os = "linux")]
+fn prepare_cgroup_parent() -> Result<std::path::PathBuf> {
+    use anyhow::Context;
+
+    let parent = cgroup_parent()?;
+    let leaf = parent.join("hostd");
+    std::fs::create_dir_all(&leaf)
+        .and_then(|_| std::fs::write(leaf.join("cgroup.procs"), std::process::id().to_string()))
+        .with_context(|| format!("{} is not writable; run hostd as a service so systemd delegates it", parent.display()))?;
+    std::fs::write(parent.join("cgroup.subtree_control"), "+memory")
+        .with_context(|| format!("Failed to enable the memory controller in {}", parent.display()))?;
+    Ok(parent)
+}
+
+#[cfg(target_os = "linux")]
+fn set_memory_limit(pid: u32, server_id: &str, limit_mb: Option<u32>) -> Result<Option<&'static str>> {
+    use anyhow::Context;
+
+    let Some(limit_mb) = limit_mb else {
+        // Lift a limit set earlier while the server ran
+        if let Ok(parent) = cgroup_parent() {
+            let group = server_cgroup(&parent, server_id);
+            if group.exists() {
+                std::fs::write(group.join("memory.max"), "max")?;
+            }
+        }
+        return Ok(None);
+    };
+    let group = server_cgroup(&prepare_cgroup_parent()?, server_id);
+    std::fs::create_dir_all(&group).with_context(|| format!("Failed to create {}", group.display()))?;
+    std::fs::write(group.join("memory.max"), (limit_mb as u64 * 1024 * 1024).to_string())?;
+    std::fs::write(group.join("cgroup.procs"), pid.to_string())?;
+    Ok(Some("cgroup"))
+}
+
+/// Remove a stopped server's cgroup
+#[cfg(target_os = "linux")]
+pub fn release(server_id: &str) {
+    if let Ok(parent) = cgroup_parent() {
+        let _ = std::fs::remove_dir(server_cgroup(&parent, server_id));
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn set_affinity(_pid: u32, cores: &[usize]) -> Result<()> {
+    if !cores.is_empty() {
+        bail!("not supported on this OS");
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn set_priority(pid: u32, priority: ProcessPriority) -> Result<()> {
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, priority.niceness()) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn set_memory_limit(_pid: u32, _server_id: &str, limit_mb: Option<u32>) -> Result<Option<&'static str>> {
+    if limit_mb.is_some() {
+        bail!("not supported on this OS");
+    }
+    Ok(None)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn release(_server_id: &str) {}
+
+#[cfg(windows)]
+mod windows {
+    use anyhow::Result;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, IsProcessInJob, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_JOB_MEMORY,
+    };
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, SetPriorityClass, SetProcessAffinityMask, ABOVE_NORMAL_PRIORITY_CLASS,
+        BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+        PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_INFORMATION, PROCESS_SET_QUOTA, PROCESS_TERMINATE,
+    };
+
+    use super::{cpu_count, ProcessPriority};
+
+    struct Handle(HANDLE);
+
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    fn check(result: i32) -> Result<()> {
+        if result == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    fn open_process(pid: u32) -> Result<Handle> {
+        let access = PROCESS_SET_INFORMATION | PROCESS_SET_QUOTA | PROCESS_TERMINATE | PROCESS_QUERY_LIMITED_INFORMATION;
+        let handle = unsafe { OpenProcess(access, 0, pid) };
+        if handle == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Handle(handle))
+    }
+
+    pub fn set_affinity(pid: u32, cores: &[usize]) -> Result<()> {
+        let mask = if cores.is_empty() {
+            match cpu_count() {
+                count if count >= usize::BITS as usize => usize::MAX,
+                count => (1usize << count) - 1,
+            }
+        } else {
+            cores.iter().fold(0usize, |mask, core| mask | (1usize << core))
+        };
+        let process = open_process(pid)?;
+        check(unsafe { SetProcessAffinityMask(process.0, mask) })
+    }
+
+    pub fn set_priority(pid: u32, priority: ProcessPriority) -> Result<()> {
+        let class = match priority {
+            ProcessPriority::Low => IDLE_PRIORITY_CLASS,
+            ProcessPriority::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+            ProcessPriority::Normal => NORMAL_PRIORITY_CLASS,
+            ProcessPriority::AboveNormal => ABOVE_NORMAL_PRIORITY_CLASS,
+            ProcessPriority::High => HIGH_PRIORITY_CLASS,
+        };
+        let process = open_process(pid)?;
+        check(unsafe { SetPriorityClass(process.0, class) })
+    }
+
+    /// The server's process goes into a Job Object named after it, which
+    /// lives as long as the process does
+    pub fn set_memory_limit(pid: u32, server_id: &str, limit_mb: Option<u32>) -> Result<Option<&'static str>> {
+        let name: Vec<u16> = format!("Guardian-{}", server_id).encode_utf16().chain(Some(0)).collect();
+        let job = unsafe { CreateJobObjectW(std::ptr::null(), name.as_ptr()) };
+        if job == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let job = Handle(job);
+        let process = open_process(pid)?;
+        let mut in_job = 0;
+        check(unsafe { IsProcessInJob(process.0, job.0, &mut in_job) })?;
+        if limit_mb.is_none() && in_job == 0 {
+            return Ok(None);
+        }
+
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+        if let Some(limit_mb) = limit_mb {
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_JOB_MEMORY;
+            info.JobMemoryLimit = limit_mb as usize * 1024 * 1024;
+        }
+        check(unsafe {
+            SetInformationJobObject(
+                job.0,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const std::ffi::c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        })?;
+        if in_job == 0 {
+            check(unsafe { AssignProcessToJobObject(job.0, process.0) })?;
+        }
+        Ok(limit_mb.map(|_| "job_object"))
+    }
+}
+
+#[cfg(windows)]
+use windows::{set_affinity, set_memory_limit, set_priority};
+
+/// A server's Job Object goes away with its process
+#[cfg(windows)]
+pub fn release(_server_id: &str) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_checks_cores_and_room_above_heap() {
+        let mut limits = ResourceLimits { cpu_cores: vec![0], ..Default::default() };
+        assert!(limits.validate(4096).is_ok());
+
+        limits.cpu_cores = vec![cpu_count()];
+        assert!(limits.validate(4096).is_err());
+
+        limits.cpu_cores.clear();
+        limits.memory_limit_mb = Some(4096);
+        assert!(limits.validate(4096).is_err());
+        limits.memory_limit_mb = Some(4096 + MIN_NON_HEAP_MB);
+        assert!(limits.validate(4096).is_ok());
+    }
+
+    #[test]
+    fn test_priority_round_trips() {
+        for priority in [ProcessPriority::Low, ProcessPriority::Normal, ProcessPriority::High] {
+            assert_eq!(ProcessPriority::parse(priority.as_str()), Some(priority));
+        }
+        assert!(ResourceLimits::default().is_unlimited());
+        assert_eq!(ProcessPriority::Low.niceness(), 10);
+    }
+}
@@ -0,0 +1,205 @@
+//! Resource pack hosting
+//!
+//! Each server can have one server resource pack. An uploaded pack is stored
+//! under `<data_dir>/resource-packs/files/<server_id>/<sha1>.zip` and served
+//! without authentication at `/resource-packs/<server_id>/<sha1>.zip`, since
+//! players' clients download it directly. When
+//! `GUARDIAN_RESOURCE_PACK_UPLOAD_URL` is set the pack is PUT to object
+//! storage instead. Either way `resource-pack` and `resource-pack-sha1` in
+//! server.properties are pointed at it, as a config revision.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+use crate::config_revisions::{self, ConfigTarget};
+use crate::core::guardian_config::GuardianConfig;
+use crate::database::{DatabaseManager, ServerConfig};
+use crate::server_templates::merge_properties;
+
+/// Largest resource pack accepted by upload
+pub const MAX_UPLOAD_BYTES: usize = 256 * 1024 * 1024;
+
+/// Path the packs are served under, relative to Guardian's address
+pub const SERVE_PATH: &str = "/resource-packs";
+
+/// A server's resource pack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourcePack {
+    pub server_id: String,
+    /// Name the pack was uploaded as
+    pub file_name: String,
+    pub sha1: String,
+    pub size_bytes: u64,
+    /// Where players download it from, as written to server.properties
+    pub url: String,
+    /// local or remote
+    pub storage: String,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+pub struct ResourcePackHost {
+    database: Arc<DatabaseManager>,
+    config: Arc<GuardianConfig>,
+}
+
+impl ResourcePackHost {
+    pub fn new(database: Arc<DatabaseManager>, config: Arc<GuardianConfig>) -> Self {
+        Self { database, config }
+    }
+
+    fn root(&self) -> PathBuf {
+        self.config.data_dir.join("resource-packs")
+    }
+
+    /// The directory served at [`SERVE_PATH`]
+    pub fn files_dir(&self) -> PathBuf {
+        self.root().join("files")
+    }
+
+    fn record_path(&self, server_id: &str) -> PathBuf {
+        self.root().join(format!("{}.json", server_id))
+    }
+
+    pub async fn get(&self, server_id: &str) -> Result<Option<ResourcePack>> {
+        match tokio::fs::read_to_string(self.record_path(server_id)).await {
+            Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store `bytes` as the server's pack, replacing any previous one, and
+    /// point server.properties at it
+    pub async fn upload(&self, server_id: &str, file_name: &str, bytes: &[u8], author: Option<&str>) -> Result<ResourcePack> {
+        if !file_name.to_ascii_lowercase().ends_with(".zip") {
+            bail!("Resource packs are uploaded as .zip files");
+        }
+        crate::datapacks::read_zip_meta(bytes).context("Not a resource pack: pack.mcmeta is missing or invalid")?;
+        let server = self.database.require_server(server_id).await?;
+
+        let sha1 = format!("{:x}", Sha1::digest(bytes));
+        let object = format!("{}/{}.zip", server.id, sha1);
+        let (url, storage) = match &self.config.resource_pack_upload_url {
+            Some(upload_url) => {
+                let Some(public_url) = &self.config.resource_pack_url else {
+                    bail!("GUARDIAN_RESOURCE_PACK_URL must be set to upload resource packs to object storage");
+                };
+                self.put_remote(&format!("{}/{}", upload_url.trim_end_matches('/'), object), bytes).await?;
+                (format!("{}/{}", public_url.trim_end_matches('/'), object), "remote")
+            }
+            None => {
+                let dir = self.files_dir().join(&server.id);
+                // Only the current pack is kept; its file name changes with its content
+                if let Err(e) = tokio::fs::remove_dir_all(&dir).await {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        return Err(e.into());
+                    }
+                }
+                tokio::fs::create_dir_all(&dir).await?;
+                tokio::fs::write(dir.join(format!("{}.zip", sha1)), bytes).await?;
+                (format!("{}/{}", self.public_base()?, object), "local")
+            }
+        };
+
+        let pack = ResourcePack {
+            server_id: server.id.clone(),
+            file_name: file_name.to_string(),
+            sha1: sha1.clone(),
+            size_bytes: bytes.len() as u64,
+            url: url.clone(),
+            storage: storage.to_string(),
+            uploaded_at: Utc::now(),
+        };
+        self.set_properties(&server, &url, &sha1, author, format!("Set resource pack {}", file_name)).await?;
+        tokio::fs::create_dir_all(self.root()).await?;
+        tokio::fs::write(self.record_path(&server.id), serde_json::to_string_pretty(&pack)?).await?;
+        info!("Resource pack {} ({}) of server {} is served at {}", file_name, sha1, server.id, url);
+        Ok(pack)
+    }
+
+    /// Stop offering the server's pack. Packs in object storage are left there.
+    pub async fn remove(&self, server_id: &str, author: Option<&str>) -> Result<()> {
+        let server = self.database.require_server(server_id).await?;
+        if self.get(server_id).await?.is_none() {
+            bail!("Server {} has no resource pack", server_id);
+        }
+        self.set_properties(&server, "", "", author, "Removed resource pack".to_string()).await?;
+        if let Err(e) = tokio::fs::remove_dir_all(self.files_dir().join(&server.id)).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(e.into());
+            }
+        }
+        tokio::fs::remove_file(self.record_path(&server.id)).await?;
+        Ok(())
+    }
+
+    /// Base URL of locally served packs: the configured public URL, or
+    /// Guardian's own address
+    fn public_base(&self) -> Result<String> {
+        match &self.config.resource_pack_url {
+            Some(url) => Ok(url.trim_end_matches('/').to_string()),
+            None => {
+                let info = crate::core::remote::connection_info(&self.config)?;
+                Ok(format!("{}{}", info.url.trim_end_matches('/'), SERVE_PATH))
+            }
+        }
+    }
+
+    async fn put_remote(&self, url: &str, bytes: &[u8]) -> Result<()> {
+        let mut request = reqwest::Client::new()
+            .put(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/zip")
+            .timeout(Duration::from_secs(300))
+            .body(bytes.to_vec());
+        if let Some(token) = &self.config.resource_pack_upload_token {
+            request = request.bearer_auth(token);
+        }
+        request
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to upload resource pack to {}", url))?;
+        Ok(())
+    }
+
+    async fn set_properties(&self, server: &ServerConfig, url: &str, sha1: &str, author: Option<&str>, message: String) -> Result<()> {
+        let target = ConfigTarget::ServerProperties;
+        let current = config_revisions::read(server, &target).await?.unwrap_or_default();
+        let content = merge_properties(&current, &pack_properties(url, sha1));
+        config_revisions::apply(&self.database, server, &target, &content, author, Some(message)).await?;
+        Ok(())
+    }
+}
+
+/// server.properties values offering the pack at `url`. Colons are escaped
+/// the way the game writes them.
+fn pack_properties(url: &str, sha1: &str) -> BTreeMap<String, String> {
+    BTreeMap::from([
+        ("resource-pack".to_string(), url.replace(':', "\\:")),
+        ("resource-pack-sha1".to_string(), sha1.to_string()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_properties() {
+        let current = "motd=Hello\nresource-pack=\nresource-pack-sha1=\n";
+        let sha1 = format!("{:x}", Sha1::digest(b"pack"));
+        let merged = merge_properties(current, &pack_properties("http://10.0.0.2:52100/resource-packs/a/b.zip", &sha1));
+        assert_eq!(
+            merged,
+            format!("motd=Hello\nresource-pack=http\\://10.0.0.2\\:52100/resource-packs/a/b.zip\nresource-pack-sha1={}\n", sha1)
+        );
+        assert_eq!(sha1.len(), 40);
+    }
+}
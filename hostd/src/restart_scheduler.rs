@@ -0,0 +1,671 @@
+//! Scheduled restarts: each server can have cron schedules that count down in
+//! game over RCON (`say` and optionally `title`), save the world, wait a grace
+//! period and then stop and start the server through the process manager.
+//! Every restart is recorded in the event log (`scheduled_restart`).
+//!
+//! `command` schedules instead send their console commands over RCON when
+//! due, such as a nightly `kick @a` or a periodic `save-all`. Each run is a
+//! `scheduled_command` task holding the commands' output, and is recorded in
+//! the event log as well (`scheduled_command`).
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, EventLog, RestartSchedule, ServerConfig, Task};
+use crate::rcon::RconClient;
+
+const TASK_KIND: &str = "scheduled_command";
+/// Commands one schedule may run
+const MAX_COMMANDS: usize = 20;
+const MAX_COMMAND_LENGTH: usize = 1000;
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// Restarts missed by more than this (e.g. while hostd was down) are skipped, not run late
+const MISSED_RUN_WINDOW: chrono::Duration = chrono::Duration::minutes(2);
+const DEFAULT_WARNINGS: &[u32] = &[600, 300, 60, 30, 10, 5];
+const DEFAULT_GRACE_PERIOD_SECONDS: u32 = 10;
+const DEFAULT_MESSAGE: &str = "Server restarting in {time}";
+
+/// What a schedule does when it is due
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleKind {
+    #[default]
+    Restart,
+    /// Send the schedule's commands over RCON
+    Command,
+}
+
+impl ScheduleKind {
+    pub fn parse(kind: &str) -> Result<Self> {
+        match kind {
+            "restart" => Ok(Self::Restart),
+            "command" => Ok(Self::Command),
+            other => bail!("Unknown schedule kind: {}", other),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Restart => "restart",
+            Self::Command => "command",
+        }
+    }
+
+    fn event_type(&self) -> &'static str {
+        match self {
+            Self::Restart => "scheduled_restart",
+            Self::Command => "scheduled_command",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewRestartSchedule {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub kind: ScheduleKind,
+    pub cron_expression: String,
+    #[serde(default)]
+    pub commands: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub warnings: Option<Vec<u32>>,
+    pub grace_period_seconds: Option<u32>,
+    pub message: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub use_title: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RestartScheduleUpdate {
+    pub name: Option<String>,
+    pub kind: Option<ScheduleKind>,
+    pub cron_expression: Option<String>,
+    pub commands: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+    pub warnings: Option<Vec<u32>>,
+    pub grace_period_seconds: Option<u32>,
+    pub message: Option<String>,
+    pub use_title: Option<bool>,
+}
+
+/// A schedule as returned by the API, with its next restart time
+#[derive(Debug, Clone, Serialize)]
+pub struct RestartScheduleInfo {
+    #[serde(flatten)]
+    pub schedule: RestartSchedule,
+    pub next_run: Option<DateTime<Utc>>,
+    /// A countdown for this schedule is under way
+    pub in_progress: bool,
+}
+
+/// Parse a cron expression. Standard five-field expressions are accepted as well as
+/// the six/seven-field form with seconds (and year).
+pub fn parse_cron(expression: &str) -> Result<Schedule> {
+    let expression = expression.trim();
+    let expression = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    Schedule::from_str(&expression).map_err(|e| anyhow!("Invalid cron expression '{}': {}", expression, e))
+}
+
+/// Next restart time of a schedule. Runs already done or missed by more than
+/// [`MISSED_RUN_WINDOW`] are not returned.
+pub fn next_run(schedule: &RestartSchedule, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let cron = parse_cron(&schedule.cron_expression).ok()?;
+    let mut after = now - MISSED_RUN_WINDOW;
+    if let Some(last_run) = schedule.last_run {
+        after = after.max(last_run);
+    }
+    cron.after(&after).next()
+}
+
+/// Commands without a leading slash; `command` schedules need at least one
+fn normalize_commands(kind: ScheduleKind, commands: Vec<String>) -> Result<Vec<String>> {
+    let commands: Vec<String> = commands
+        .iter()
+        .map(|command| command.trim().trim_start_matches('/').trim().to_string())
+        .filter(|command| !command.is_empty())
+        .collect();
+    if kind == ScheduleKind::Command && commands.is_empty() {
+        bail!("A command schedule needs at least one command");
+    }
+    if commands.len() > MAX_COMMANDS {
+        bail!("A schedule can run at most {} commands", MAX_COMMANDS);
+    }
+    if let Some(command) = commands.iter().find(|command| command.len() > MAX_COMMAND_LENGTH || command.contains(['\n', '\r'])) {
+        bail!("Commands must be a single line of at most {} characters: {}", MAX_COMMAND_LENGTH, command);
+    }
+    Ok(commands)
+}
+
+/// Seconds before the scheduled time a run begins; only restarts count down
+fn lead_seconds(schedule: &RestartSchedule) -> u32 {
+    match schedule.kind {
+        ScheduleKind::Restart => schedule.warnings.first().copied().unwrap_or(0),
+        ScheduleKind::Command => 0,
+    }
+}
+
+/// Each command followed by its output, as kept in the task log
+fn command_log(outputs: &[(String, String)]) -> String {
+    outputs
+        .iter()
+        .map(|(command, output)| match output.trim() {
+            "" => format!("> {}", command),
+            output => format!("> {}\n{}", command, output),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Warning offsets, longest first, without duplicates
+fn normalize_warnings(mut warnings: Vec<u32>) -> Vec<u32> {
+    warnings.retain(|&seconds| seconds > 0);
+    warnings.sort_unstable_by(|a, b| b.cmp(a));
+    warnings.dedup();
+    warnings
+}
+
+/// "10 minutes", "1 minute", "30 seconds"
+pub(crate) fn format_remaining(seconds: u32) -> String {
+    let (value, unit) = if seconds >= 60 && seconds.is_multiple_of(60) { (seconds / 60, "minute") } else { (seconds, "second") };
+    format!("{} {}{}", value, unit, if value == 1 { "" } else { "s" })
+}
+
+fn warning_text(schedule: &RestartSchedule, seconds: u32) -> String {
+    schedule
+        .message
+        .as_deref()
+        .unwrap_or(DEFAULT_MESSAGE)
+        .replace("{time}", &format_remaining(seconds))
+}
+
+pub(crate) async fn rcon(server: &ServerConfig, command: String) -> Result<String> {
+    let client = RconClient::new(server.host.clone(), server.rcon_port, server.rcon_password.clone());
+    tokio::task::spawn_blocking(move || client.send_command(&command)).await?
+}
+
+/// Tell everyone online; failures are logged, never fatal
+pub(crate) async fn announce(server: &ServerConfig, text: &str, use_title: bool) {
+    let mut commands = vec![format!("say {}", text)];
+    if use_title {
+        let subtitle = serde_json::json!({ "text": text, "color": "yellow" });
+        commands.push(format!("title @a subtitle {}", subtitle));
+        commands.push(format!("title @a title {}", serde_json::json!({ "text": "Server restart", "color": "red" })));
+    }
+    for command in commands {
+        if let Err(e) = rcon(server, command).await {
+            warn!("Failed to send restart warning to server {}: {}", server.id, e);
+            return;
+        }
+    }
+}
+
+struct Countdown {
+    handle: JoinHandle<()>,
+    /// At least one warning went out, so players should hear about a cancellation
+    announced: Arc<AtomicBool>,
+}
+
+pub struct RestartScheduler {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+    /// Countdowns under way, keyed by schedule ID
+    running: RwLock<HashMap<String, Countdown>>,
+}
+
+impl RestartScheduler {
+    pub fn new(database: Arc<DatabaseManager>, process_manager: Arc<ProcessManager>) -> Self {
+        Self {
+            database,
+            process_manager,
+            running: RwLock::new(HashMap::new()),
+        }
+    }
+
+    async fn info(&self, schedule: RestartSchedule) -> RestartScheduleInfo {
+        let in_progress = self.running.read().await.contains_key(&schedule.id);
+        let next_run = if schedule.enabled { next_run(&schedule, Utc::now()) } else { None };
+        RestartScheduleInfo { schedule, next_run, in_progress }
+    }
+
+    pub async fn list_schedules(&self, server_id: &str) -> Result<Vec<RestartScheduleInfo>> {
+        let mut schedules = Vec::new();
+        for schedule in self.database.get_restart_schedules(Some(server_id)).await? {
+            schedules.push(self.info(schedule).await);
+        }
+        Ok(schedules)
+    }
+
+    async fn schedule(&self, server_id: &str, schedule_id: &str) -> Result<Option<RestartSchedule>> {
+        Ok(self
+            .database
+            .get_restart_schedule(schedule_id)
+            .await?
+            .filter(|schedule| schedule.server_id == server_id))
+    }
+
+    pub async fn get_schedule(&self, server_id: &str, schedule_id: &str) -> Result<Option<RestartScheduleInfo>> {
+        match self.schedule(server_id, schedule_id).await? {
+            Some(schedule) => Ok(Some(self.info(schedule).await)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn create_schedule(&self, server_id: &str, request: NewRestartSchedule) -> Result<RestartScheduleInfo> {
+        self.database.require_server(server_id).await?;
+        parse_cron(&request.cron_expression)?;
+        let commands = normalize_commands(request.kind, request.commands)?;
+        let now = Utc::now();
+        let default_name = match request.kind {
+            ScheduleKind::Restart => "Scheduled restart",
+            ScheduleKind::Command => "Scheduled command",
+        };
+        let schedule = RestartSchedule {
+            id: Uuid::new_v4().to_string(),
+            server_id: server_id.to_string(),
+            name: request.name.unwrap_or_else(|| default_name.to_string()),
+            kind: request.kind,
+            cron_expression: request.cron_expression.trim().to_string(),
+            enabled: request.enabled,
+            commands,
+            warnings: normalize_warnings(request.warnings.unwrap_or_else(|| DEFAULT_WARNINGS.to_vec())),
+            grace_period_seconds: request.grace_period_seconds.unwrap_or(DEFAULT_GRACE_PERIOD_SECONDS),
+            message: request.message.filter(|message| !message.trim().is_empty()),
+            use_title: request.use_title,
+            last_run: None,
+            last_status: None,
+            last_task_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.database.create_restart_schedule(&schedule).await?;
+        Ok(self.info(schedule).await)
+    }
+
+    /// Changing a schedule cancels a countdown that is already running for it
+    pub async fn update_schedule(
+        &self,
+        server_id: &str,
+        schedule_id: &str,
+        update: RestartScheduleUpdate,
+    ) -> Result<Option<RestartScheduleInfo>> {
+        let Some(mut schedule) = self.schedule(server_id, schedule_id).await? else {
+            return Ok(None);
+        };
+        if let Some(cron_expression) = update.cron_expression {
+            parse_cron(&cron_expression)?;
+            schedule.cron_expression = cron_expression.trim().to_string();
+        }
+        if let Some(name) = update.name {
+            schedule.name = name;
+        }
+        if let Some(kind) = update.kind {
+            schedule.kind = kind;
+        }
+        let commands = update.commands.unwrap_or_else(|| schedule.commands.clone());
+        schedule.commands = normalize_commands(schedule.kind, commands)?;
+        if let Some(enabled) = update.enabled {
+            schedule.enabled = enabled;
+        }
+        if let Some(warnings) = update.warnings {
+            schedule.warnings = normalize_warnings(warnings);
+        }
+        if let Some(grace_period_seconds) = update.grace_period_seconds {
+            schedule.grace_period_seconds = grace_period_seconds;
+        }
+        if let Some(message) = update.message {
+            schedule.message = Some(message).filter(|message| !message.trim().is_empty());
+        }
+        if let Some(use_title) = update.use_title {
+            schedule.use_title = use_title;
+        }
+        schedule.updated_at = Utc::now();
+
+        self.cancel_countdown(&schedule).await;
+        self.database.update_restart_schedule(&schedule).await?;
+        Ok(Some(self.info(schedule).await))
+    }
+
+    pub async fn delete_schedule(&self, server_id: &str, schedule_id: &str) -> Result<bool> {
+        let Some(schedule) = self.schedule(server_id, schedule_id).await? else {
+            return Ok(false);
+        };
+        self.cancel_countdown(&schedule).await;
+        self.database.delete_restart_schedule(schedule_id).await?;
+        Ok(true)
+    }
+
+    /// Start a schedule's countdown now instead of waiting for its next cron time
+    pub async fn trigger(self: &Arc<Self>, server_id: &str, schedule_id: &str) -> Result<RestartScheduleInfo> {
+        let schedule = self
+            .schedule(server_id, schedule_id)
+            .await?
+            .ok_or_else(|| anyhow!("Restart schedule {} not found", schedule_id))?;
+        if self.running.read().await.contains_key(&schedule.id) {
+            bail!("This schedule is already running");
+        }
+        let at = Utc::now() + chrono::Duration::seconds(lead_seconds(&schedule) as i64);
+        self.begin_countdown(schedule.clone(), at).await;
+        Ok(self.info(schedule).await)
+    }
+
+    async fn cancel_countdown(&self, schedule: &RestartSchedule) {
+        let Some(countdown) = self.running.write().await.remove(&schedule.id) else {
+            return;
+        };
+        countdown.handle.abort();
+        info!("Cancelled restart countdown for schedule {}", schedule.id);
+        if countdown.announced.load(Ordering::SeqCst) {
+            if let Ok(server) = self.database.require_server(&schedule.server_id).await {
+                announce(&server, "Scheduled restart cancelled", false).await;
+            }
+        }
+    }
+
+    /// Check schedules every few seconds and start countdowns that are due
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting restart scheduler");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_schedules().await {
+                error!("Restart scheduler error: {}", e);
+            }
+        }
+    }
+
+    async fn check_schedules(self: &Arc<Self>) -> Result<()> {
+        let now = Utc::now();
+        for schedule in self.database.get_restart_schedules(None).await? {
+            if !schedule.enabled || self.running.read().await.contains_key(&schedule.id) {
+                continue;
+            }
+            let Some(at) = next_run(&schedule, now) else {
+                continue;
+            };
+            let lead = lead_seconds(&schedule) as i64;
+            if at - chrono::Duration::seconds(lead) <= now + chrono::Duration::from_std(CHECK_INTERVAL)? {
+                self.begin_countdown(schedule, at).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn begin_countdown(self: &Arc<Self>, schedule: RestartSchedule, at: DateTime<Utc>) {
+        let mut running = self.running.write().await;
+        if running.contains_key(&schedule.id) {
+            return;
+        }
+        info!("Run of schedule '{}' for server {} due at {}", schedule.name, schedule.server_id, at);
+        let announced = Arc::new(AtomicBool::new(false));
+        let scheduler = self.clone();
+        let schedule_id = schedule.id.clone();
+        let flag = announced.clone();
+        let handle = tokio::spawn(async move {
+            scheduler.run_countdown(schedule.clone(), at, flag).await;
+            scheduler.running.write().await.remove(&schedule.id);
+        });
+        running.insert(schedule_id, Countdown { handle, announced });
+    }
+
+    async fn sleep_until(at: DateTime<Utc>) {
+        if let Ok(wait) = (at - Utc::now()).to_std() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    async fn run_countdown(&self, mut schedule: RestartSchedule, at: DateTime<Utc>, announced: Arc<AtomicBool>) {
+        let started = Utc::now();
+        let result = match schedule.kind {
+            ScheduleKind::Restart => self.restart(&schedule, at, &announced).await,
+            ScheduleKind::Command => self.run_commands(&mut schedule).await,
+        };
+
+        schedule.last_run = Some(at);
+        schedule.last_status = Some(match &result {
+            Ok(true) => "done",
+            Ok(false) => "skipped",
+            Err(_) => "failed",
+        }.to_string());
+        schedule.updated_at = Utc::now();
+        if let Err(e) = self.database.update_restart_schedule(&schedule).await {
+            error!("Failed to record run of restart schedule {}: {}", schedule.id, e);
+        }
+
+        let what = match schedule.kind {
+            ScheduleKind::Restart => "Scheduled restart",
+            ScheduleKind::Command => "Scheduled command",
+        };
+        let (level, message) = match &result {
+            Ok(true) => ("info", format!("{} '{}' completed", what, schedule.name)),
+            Ok(false) => ("warn", format!("{} '{}' skipped: server not running", what, schedule.name)),
+            Err(e) => ("error", format!("{} '{}' failed: {}", what, schedule.name, e)),
+        };
+        match &result {
+            Err(_) => error!("{} (server {})", message, schedule.server_id),
+            _ => info!("{} (server {})", message, schedule.server_id),
+        }
+        let event = EventLog {
+            id: Uuid::new_v4().to_string(),
+            server_id: Some(schedule.server_id.clone()),
+            event_type: schedule.kind.event_type().to_string(),
+            message,
+            level: level.to_string(),
+            metadata: Some(serde_json::json!({
+                "schedule_id": schedule.id,
+                "cron_expression": schedule.cron_expression,
+                "task_id": schedule.last_task_id.as_ref().filter(|_| schedule.kind == ScheduleKind::Command),
+                "scheduled_for": at,
+                "duration_ms": (Utc::now() - started).num_milliseconds(),
+            })),
+            created_at: Utc::now(),
+        };
+        if let Err(e) = self.database.log_event(&event).await {
+            error!("Failed to log run of schedule {} for server {}: {}", schedule.id, schedule.server_id, e);
+        }
+    }
+
+    /// Send the schedule's commands in order, stopping at the first that fails, and
+    /// keep their output in a task. Returns `false` when the server was not running.
+    async fn run_commands(&self, schedule: &mut RestartSchedule) -> Result<bool> {
+        let server = self.database.require_server(&schedule.server_id).await?;
+        // Servers Guardian did not start can only be tried
+        if server.managed {
+            let server_uuid = Uuid::parse_str(&schedule.server_id)?;
+            if !self.process_manager.is_server_running(server_uuid).await {
+                return Ok(false);
+            }
+        }
+
+        let now = Utc::now();
+        let mut task = Task {
+            id: Uuid::new_v4().to_string(),
+            server_id: Some(schedule.server_id.clone()),
+            kind: TASK_KIND.to_string(),
+            status: crate::jobs::STATUS_RUNNING.to_string(),
+            progress: 0.0,
+            log: None,
+            metadata: Some(serde_json::json!({
+                "schedule_id": schedule.id,
+                "schedule_name": schedule.name,
+                "commands": schedule.commands,
+            })),
+            started_at: Some(now),
+            finished_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.database.create_task(&task).await?;
+        schedule.last_task_id = Some(task.id.clone());
+
+        let mut outputs = Vec::new();
+        let mut failure = None;
+        for (i, command) in schedule.commands.iter().enumerate() {
+            match rcon(&server, command.clone()).await {
+                Ok(output) => outputs.push((command.clone(), output)),
+                Err(e) => {
+                    outputs.push((command.clone(), format!("Error: {}", e)));
+                    failure = Some(anyhow!("'{}' failed: {}", command, e));
+                    break;
+                }
+            }
+            task.progress = (i + 1) as f64 / schedule.commands.len() as f64;
+        }
+
+        task.status = match failure {
+            Some(_) => crate::jobs::STATUS_FAILED,
+            None => crate::jobs::STATUS_DONE,
+        }
+        .to_string();
+        task.log = Some(command_log(&outputs));
+        task.finished_at = Some(Utc::now());
+        task.updated_at = Utc::now();
+        if let Err(e) = self.database.update_task(&task).await {
+            error!("Failed to record output of schedule {}: {}", schedule.id, e);
+        }
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(true),
+        }
+    }
+
+    /// Count down, save, wait out the grace period, then stop and start the server.
+    /// Returns `false` when the server was not running at restart time.
+    async fn restart(&self, schedule: &RestartSchedule, at: DateTime<Utc>, announced: &AtomicBool) -> Result<bool> {
+        let server_uuid = Uuid::parse_str(&schedule.server_id)?;
+        let server = self.database.require_server(&schedule.server_id).await?;
+
+        for &seconds in &schedule.warnings {
+            let warn_at = at - chrono::Duration::seconds(seconds as i64);
+            if warn_at < Utc::now() - chrono::Duration::seconds(1) {
+                continue;
+            }
+            Self::sleep_until(warn_at).await;
+            if self.process_manager.is_server_running(server_uuid).await {
+                announce(&server, &warning_text(schedule, seconds), schedule.use_title).await;
+                announced.store(true, Ordering::SeqCst);
+            }
+        }
+        Self::sleep_until(at).await;
+
+        if !self.process_manager.is_server_running(server_uuid).await {
+            return Ok(false);
+        }
+        announce(&server, "Server restarting now", schedule.use_title).await;
+        if let Err(e) = rcon(&server, "save-all flush".to_string()).await {
+            warn!("Failed to save server {} before restart: {}", server.id, e);
+        }
+        tokio::time::sleep(Duration::from_secs(schedule.grace_period_seconds as u64)).await;
+
+        self.process_manager.stop_server_process(server_uuid).await?;
+        // Reload in case the configuration changed while the server was up
+        let server = self.database.require_server(&schedule.server_id).await?;
+        self.process_manager.start_server_process(server).await?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn schedule(cron_expression: &str, last_run: Option<DateTime<Utc>>) -> RestartSchedule {
+        let created = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        RestartSchedule {
+            id: "s".to_string(),
+            server_id: "server".to_string(),
+            name: "Nightly".to_string(),
+            kind: ScheduleKind::Restart,
+            cron_expression: cron_expression.to_string(),
+            enabled: true,
+            commands: Vec::new(),
+            warnings: DEFAULT_WARNINGS.to_vec(),
+            grace_period_seconds: DEFAULT_GRACE_PERIOD_SECONDS,
+            message: None,
+            use_title: true,
+            last_run,
+            last_status: None,
+            last_task_id: None,
+            created_at: created,
+            updated_at: created,
+        }
+    }
+
+    #[test]
+    fn test_parse_cron_accepts_five_and_six_fields() {
+        assert!(parse_cron("0 4 * * *").is_ok());
+        assert!(parse_cron("30 0 4 * * *").is_ok());
+        assert!(parse_cron("every night").is_err());
+    }
+
+    #[test]
+    fn test_next_run_skips_done_and_long_missed_runs() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 10, 4, 1, 0).unwrap();
+        let today = Utc.with_ymd_and_hms(2024, 3, 10, 4, 0, 0).unwrap();
+        let tomorrow = Utc.with_ymd_and_hms(2024, 3, 11, 4, 0, 0).unwrap();
+
+        // Missed by a minute (e.g. hostd was busy starting up): still due
+        assert_eq!(next_run(&schedule("0 4 * * *", None), now), Some(today));
+        // Already done today
+        assert_eq!(next_run(&schedule("0 4 * * *", Some(today)), now), Some(tomorrow));
+        // Missed by hours: wait for the next one rather than restarting at a random time
+        let later = Utc.with_ymd_and_hms(2024, 3, 10, 9, 0, 0).unwrap();
+        assert_eq!(next_run(&schedule("0 4 * * *", None), later), Some(tomorrow));
+    }
+
+    #[test]
+    fn test_warning_text() {
+        let mut restart = schedule("0 4 * * *", None);
+        assert_eq!(warning_text(&restart, 600), "Server restarting in 10 minutes");
+        assert_eq!(warning_text(&restart, 60), "Server restarting in 1 minute");
+        assert_eq!(warning_text(&restart, 90), "Server restarting in 90 seconds");
+        restart.message = Some("Back in a moment, restart in {time}!".to_string());
+        assert_eq!(warning_text(&restart, 1), "Back in a moment, restart in 1 second!");
+        assert_eq!(normalize_warnings(vec![5, 60, 0, 60, 600]), vec![600, 60, 5]);
+    }
+
+    #[test]
+    fn test_command_schedules() {
+        let commands = vec![" /save-all ".to_string(), "".to_string(), "kick @a Nightly reset".to_string()];
+        assert_eq!(
+            normalize_commands(ScheduleKind::Command, commands).unwrap(),
+            vec!["save-all".to_string(), "kick @a Nightly reset".to_string()]
+        );
+        assert!(normalize_commands(ScheduleKind::Command, Vec::new()).is_err());
+        assert!(normalize_commands(ScheduleKind::Restart, Vec::new()).is_ok());
+        assert!(normalize_commands(ScheduleKind::Command, vec!["say a\nop me".to_string()]).is_err());
+
+        let mut command = schedule("0 4 * * *", None);
+        assert_eq!(lead_seconds(&command), 600);
+        command.kind = ScheduleKind::Command;
+        assert_eq!(lead_seconds(&command), 0);
+        assert_eq!(ScheduleKind::parse(command.kind.as_str()).unwrap(), ScheduleKind::Command);
+
+        let outputs = vec![
+            ("save-all".to_string(), "Saved the game".to_string()),
+            ("kick @a".to_string(), String::new()),
+        ];
+        assert_eq!(command_log(&outputs), "> save-all\nSaved the game\n> kick @a");
+    }
+}
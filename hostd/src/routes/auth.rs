@@ -1,6 +1,5 @@
 use axum::{
     extract::State,
-    http::StatusCode,
     response::Json,
     routing::{get, post},
     Router,
@@ -9,14 +8,17 @@ use serde_json::{json, Value};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::core::auth::{LoginRequest, LoginResponse, RegisterRequest, UserUpdate};
+use crate::core::auth::{CreateApiTokenRequest, CreatedApiToken, LoginRequest, LoginResponse, RefreshRequest, RegisterRequest, UserUpdate};
 use crate::core::app_state::AppState;
-// use crate::core::middleware::AuthContext;
+use crate::core::error_handler::{AppError, AuthErrorReason};
+use crate::core::middleware::AuthContext;
+use crate::database::ApiToken;
 use crate::api::ApiResponse;
 
 pub fn auth_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/login", post(login))
+        .route("/refresh", post(refresh))
         .route("/register", post(register))
         .route("/logout", post(logout))
         .route("/me", get(get_current_user))
@@ -26,19 +28,32 @@ pub fn auth_routes() -> Router<Arc<AppState>> {
         .route("/users/:id", axum::routing::delete(delete_user))
         .route("/roles", get(get_roles))
         .route("/permissions", get(get_permissions))
+        .route("/tokens", get(get_api_tokens).post(create_api_token))
+        .route("/tokens/:id", axum::routing::delete(revoke_api_token))
 }
 
 pub async fn login(
     State(app_state): State<Arc<AppState>>,
     Json(request): Json<LoginRequest>,
-) -> Result<Json<ApiResponse<LoginResponse>>, StatusCode> {
+) -> Result<Json<ApiResponse<LoginResponse>>, AppError> {
     match app_state.auth.login(request).await {
         Ok(response) => Ok(Json(ApiResponse::success(response))),
         Err(e) => {
             tracing::warn!("Login failed: {}", e);
-            Ok(Json(ApiResponse::<LoginResponse>::error(
-                "Invalid username or password".to_string(),
-            )))
+            Err(AppError::authentication_error(AuthErrorReason::InvalidCredentials, "Invalid username or password"))
+        }
+    }
+}
+
+pub async fn refresh(
+    State(app_state): State<Arc<AppState>>,
+    Json(request): Json<RefreshRequest>,
+) -> Result<Json<ApiResponse<LoginResponse>>, AppError> {
+    match app_state.auth.refresh(&request.refresh_token).await {
+        Ok(response) => Ok(Json(ApiResponse::success(response))),
+        Err(e) => {
+            tracing::warn!("Token refresh failed: {}", e);
+            Err(AppError::authentication_error(AuthErrorReason::TokenInvalid, "Invalid or expired refresh token"))
         }
     }
 }
@@ -46,14 +61,12 @@ pub async fn login(
 pub async fn register(
     State(app_state): State<Arc<AppState>>,
     Json(request): Json<RegisterRequest>,
-) -> Result<Json<ApiResponse<LoginResponse>>, StatusCode> {
+) -> Result<Json<ApiResponse<LoginResponse>>, AppError> {
     match app_state.auth.register(request).await {
         Ok(response) => Ok(Json(ApiResponse::success(response))),
         Err(e) => {
             tracing::warn!("Registration failed: {}", e);
-            Ok(Json(ApiResponse::<LoginResponse>::error(
-                "Registration failed".to_string(),
-            )))
+            Err(AppError::rejected("Registration failed", e))
         }
     }
 }
@@ -61,7 +74,7 @@ pub async fn register(
 pub async fn logout(
     State(app_state): State<Arc<AppState>>,
     headers: axum::http::HeaderMap,
-) -> Result<Json<ApiResponse<()>>, StatusCode> {
+) -> Result<Json<ApiResponse<()>>, AppError> {
     let token = headers
         .get("Authorization")
         .and_then(|h| h.to_str().ok())
@@ -76,45 +89,30 @@ pub async fn logout(
 
 pub async fn get_current_user(
     State(app_state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
-) -> Result<Json<ApiResponse<Value>>, StatusCode> {
-    let token = headers
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok())
-        .and_then(|s| s.strip_prefix("Bearer "));
-    
-    let token = match token {
-        Some(token) => token,
-        None => {
-            return Ok(Json(ApiResponse::<Value>::error(
-                "Missing authorization token".to_string(),
-            )));
-        }
-    };
-    
-    match app_state.auth.validate_token(token).await {
-        Ok(user) => {
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<ApiResponse<Value>>, AppError> {
+    match app_state.auth.get_user(auth.user_id).await {
+        Some(user) => {
             let user_json = json!({
                 "id": user.id,
                 "username": user.username,
                 "email": user.email,
                 "role": user.role.as_str(),
                 "is_active": user.is_active,
+                "server_ids": user.server_ids,
                 "created_at": user.created_at,
                 "updated_at": user.updated_at,
                 "last_login": user.last_login,
             });
             Ok(Json(ApiResponse::success(user_json)))
         }
-        Err(_) => Ok(Json(ApiResponse::<Value>::error(
-            "Invalid or expired token".to_string(),
-        )))
+        None => Err(AppError::authentication_error(AuthErrorReason::UserNotFound, "User not found")),
     }
 }
 
 pub async fn get_users(
     State(app_state): State<Arc<AppState>>,
-) -> Result<Json<ApiResponse<Vec<Value>>>, StatusCode> {
+) -> Result<Json<ApiResponse<Vec<Value>>>, AppError> {
     let users = app_state.auth.get_all_users().await;
     let users_json: Vec<Value> = users
         .into_iter()
@@ -125,6 +123,7 @@ pub async fn get_users(
                 "email": user.email,
                 "role": user.role.as_str(),
                 "is_active": user.is_active,
+                "server_ids": user.server_ids,
                 "created_at": user.created_at,
                 "updated_at": user.updated_at,
                 "last_login": user.last_login,
@@ -138,13 +137,11 @@ pub async fn get_users(
 pub async fn get_user(
     State(app_state): State<Arc<AppState>>,
     axum::extract::Path(user_id): axum::extract::Path<String>,
-) -> Result<Json<ApiResponse<Value>>, StatusCode> {
+) -> Result<Json<ApiResponse<Value>>, AppError> {
     let user_id = match Uuid::parse_str(&user_id) {
         Ok(id) => id,
         Err(_) => {
-            return Ok(Json(ApiResponse::<Value>::error(
-                "Invalid user ID".to_string(),
-            )));
+            return Err(AppError::request("Invalid user ID".to_string()));
         }
     };
     
@@ -156,15 +153,14 @@ pub async fn get_user(
                 "email": user.email,
                 "role": user.role.as_str(),
                 "is_active": user.is_active,
+                "server_ids": user.server_ids,
                 "created_at": user.created_at,
                 "updated_at": user.updated_at,
                 "last_login": user.last_login,
             });
             Ok(Json(ApiResponse::success(user_json)))
         }
-        None => Ok(Json(ApiResponse::<Value>::error(
-            "User not found".to_string(),
-        )))
+        None => Err(AppError::not_found("User")),
     }
 }
 
@@ -172,13 +168,11 @@ pub async fn update_user(
     State(app_state): State<Arc<AppState>>,
     axum::extract::Path(user_id): axum::extract::Path<String>,
     Json(updates): Json<UserUpdate>,
-) -> Result<Json<ApiResponse<Value>>, StatusCode> {
+) -> Result<Json<ApiResponse<Value>>, AppError> {
     let user_id = match Uuid::parse_str(&user_id) {
         Ok(id) => id,
         Err(_) => {
-            return Ok(Json(ApiResponse::<Value>::error(
-                "Invalid user ID".to_string(),
-            )));
+            return Err(AppError::request("Invalid user ID".to_string()));
         }
     };
     
@@ -190,42 +184,37 @@ pub async fn update_user(
                 "email": user.email,
                 "role": user.role.as_str(),
                 "is_active": user.is_active,
+                "server_ids": user.server_ids,
                 "created_at": user.created_at,
                 "updated_at": user.updated_at,
                 "last_login": user.last_login,
             });
             Ok(Json(ApiResponse::success(user_json)))
         }
-        Err(e) => Ok(Json(ApiResponse::<Value>::error(
-            format!("Failed to update user: {}", e),
-        )))
+        Err(e) => Err(AppError::rejected("Failed to update user", e)),
     }
 }
 
 pub async fn delete_user(
     State(app_state): State<Arc<AppState>>,
     axum::extract::Path(user_id): axum::extract::Path<String>,
-) -> Result<Json<ApiResponse<()>>, StatusCode> {
+) -> Result<Json<ApiResponse<()>>, AppError> {
     let user_id = match Uuid::parse_str(&user_id) {
         Ok(id) => id,
         Err(_) => {
-            return Ok(Json(ApiResponse::<()>::error(
-                "Invalid user ID".to_string(),
-            )));
+            return Err(AppError::request("Invalid user ID".to_string()));
         }
     };
     
     match app_state.auth.delete_user(user_id).await {
         Ok(_) => Ok(Json(ApiResponse::success(()))),
-        Err(e) => Ok(Json(ApiResponse::<()>::error(
-            format!("Failed to delete user: {}", e),
-        )))
+        Err(e) => Err(AppError::rejected("Failed to delete user", e)),
     }
 }
 
 pub async fn get_roles(
     State(app_state): State<Arc<AppState>>,
-) -> Result<Json<ApiResponse<Vec<Value>>>, StatusCode> {
+) -> Result<Json<ApiResponse<Vec<Value>>>, AppError> {
     let roles = app_state.auth.get_roles().await;
     let roles_json: Vec<Value> = roles
         .into_iter()
@@ -243,7 +232,7 @@ pub async fn get_roles(
     Ok(Json(ApiResponse::success(roles_json)))
 }
 
-pub async fn get_permissions() -> Result<Json<ApiResponse<Vec<Value>>>, StatusCode> {
+pub async fn get_permissions() -> Result<Json<ApiResponse<Vec<Value>>>, AppError> {
     let permissions = vec![
         json!({"name": "CreateServer", "description": "Create new servers"}),
         json!({"name": "DeleteServer", "description": "Delete servers"}),
@@ -277,3 +266,44 @@ pub async fn get_permissions() -> Result<Json<ApiResponse<Vec<Value>>>, StatusCo
     
     Ok(Json(ApiResponse::success(permissions)))
 }
+
+pub async fn get_api_tokens(
+    State(app_state): State<Arc<AppState>>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<ApiResponse<Vec<ApiToken>>>, AppError> {
+    let user = app_state.auth.get_user(auth.user_id).await.ok_or_else(|| AppError::authentication_error(AuthErrorReason::UserNotFound, "User not found"))?;
+    match app_state.auth.list_api_tokens(&user).await {
+        Ok(tokens) => Ok(Json(ApiResponse::success(tokens))),
+        Err(e) => Err(AppError::rejected("Failed to list API tokens", e)),
+    }
+}
+
+pub async fn create_api_token(
+    State(app_state): State<Arc<AppState>>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    Json(request): Json<CreateApiTokenRequest>,
+) -> Result<Json<ApiResponse<CreatedApiToken>>, AppError> {
+    let user = app_state.auth.get_user(auth.user_id).await.ok_or_else(|| AppError::authentication_error(AuthErrorReason::UserNotFound, "User not found"))?;
+    match app_state.auth.create_api_token(&user, request).await {
+        Ok(created) => {
+            tracing::info!("User {} created API token '{}'", user.username, created.api_token.name);
+            Ok(Json(ApiResponse::success(created)))
+        }
+        Err(e) => Err(AppError::rejected("Failed to create API token", e)),
+    }
+}
+
+pub async fn revoke_api_token(
+    State(app_state): State<Arc<AppState>>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    axum::extract::Path(token_id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let user = app_state.auth.get_user(auth.user_id).await.ok_or_else(|| AppError::authentication_error(AuthErrorReason::UserNotFound, "User not found"))?;
+    match app_state.auth.revoke_api_token(&user, &token_id).await {
+        Ok(()) => {
+            tracing::info!("User {} revoked API token {}", user.username, token_id);
+            Ok(Json(ApiResponse::success(())))
+        }
+        Err(e) => Err(AppError::rejected("Failed to revoke API token", e)),
+    }
+}
@@ -11,6 +11,7 @@ use uuid::Uuid;
 use chrono::Utc;
 
 use crate::api::ApiResponse;
+use crate::core::error_handler::AppError;
 use crate::database::{DatabaseManager, ModMetadata, ModVersion, ModDependency};
 use crate::version_resolver::{VersionResolver, DependencyResolution};
 
@@ -220,7 +221,7 @@ pub fn create_modpack_router(state: ModpackState) -> Router {
 
 // Minecraft Version Management
 
-pub async fn get_minecraft_versions(State(state): State<ModpackState>) -> Json<ApiResponse<Vec<MinecraftVersion>>> {
+pub async fn get_minecraft_versions(State(state): State<ModpackState>) -> Result<Json<ApiResponse<Vec<MinecraftVersion>>>, AppError> {
     match state.database.get_minecraft_versions().await {
         Ok(db_versions) => {
         let versions: Vec<MinecraftVersion> = db_versions.into_iter().map(|v| MinecraftVersion {
@@ -231,12 +232,9 @@ pub async fn get_minecraft_versions(State(state): State<ModpackState>) -> Json<A
             supported_loaders: vec!["forge".to_string(), "fabric".to_string(), "quilt".to_string()],
         }).collect();
             
-            Json(ApiResponse::success(versions))
-        }
-        Err(e) => {
-            eprintln!("Error fetching Minecraft versions: {}", e);
-            Json(ApiResponse::error("Failed to fetch Minecraft versions"))
+            Ok(Json(ApiResponse::success(versions)))
         }
+        Err(e) => Err(AppError::internal_error("get_minecraft_versions", format!("Failed to fetch Minecraft versions: {}", e))),
     }
 }
 
@@ -291,7 +289,7 @@ pub async fn get_supported_loaders(
 pub async fn search_mods(
     State(state): State<ModpackState>,
     Query(filters): Query<ModFilters>
-) -> Json<ApiResponse<ModSearchResult>> {
+) -> Result<Json<ApiResponse<ModSearchResult>>, AppError> {
     match state.database.search_mod_metadata(
         filters.search_query.as_deref(),
         filters.category.as_deref(),
@@ -338,12 +336,9 @@ pub async fn search_mods(
                 has_more: end < mods.len(),
             };
             
-            Json(ApiResponse::success(result))
-        }
-        Err(e) => {
-            eprintln!("Error searching mods: {}", e);
-            Json(ApiResponse::error("Failed to search mods"))
+            Ok(Json(ApiResponse::success(result)))
         }
+        Err(e) => Err(AppError::internal_error("search_mods", format!("Failed to search mods: {}", e))),
     }
 }
 
@@ -384,7 +379,7 @@ pub async fn get_mod(
 pub async fn get_mod_versions(
     State(state): State<ModpackState>,
     Path(id): Path<String>
-) -> Json<ApiResponse<Vec<ModInfo>>> {
+) -> Result<Json<ApiResponse<Vec<ModInfo>>>, AppError> {
     match state.database.get_mod_versions_by_metadata_id(&id).await {
         Ok(versions) => {
             let mod_infos: Vec<ModInfo> = versions.into_iter().map(|v| ModInfo {
@@ -405,12 +400,9 @@ pub async fn get_mod_versions(
                 updated_at: v.updated_at,
             }).collect();
             
-            Json(ApiResponse::success(mod_infos))
-        }
-        Err(e) => {
-            eprintln!("Error fetching mod versions: {}", e);
-            Json(ApiResponse::error("Failed to fetch mod versions"))
+            Ok(Json(ApiResponse::success(mod_infos)))
         }
+        Err(e) => Err(AppError::internal_error("get_mod_versions", format!("Failed to fetch mod versions: {}", e))),
     }
 }
 
@@ -431,7 +423,7 @@ pub async fn get_mod_stats(
 
 // Modpack Management
 
-pub async fn get_modpacks(State(state): State<ModpackState>) -> Json<ApiResponse<Vec<Modpack>>> {
+pub async fn get_modpacks(State(state): State<ModpackState>) -> Result<Json<ApiResponse<Vec<Modpack>>>, AppError> {
     match state.database.get_modpacks().await {
         Ok(db_modpacks) => {
             let modpacks: Vec<Modpack> = db_modpacks.into_iter().map(|mp| Modpack {
@@ -447,12 +439,9 @@ pub async fn get_modpacks(State(state): State<ModpackState>) -> Json<ApiResponse
                 updated_at: mp.updated_at,
             }).collect();
             
-            Json(ApiResponse::success(modpacks))
-        }
-        Err(e) => {
-            eprintln!("Error fetching modpacks: {}", e);
-            Json(ApiResponse::error("Failed to fetch modpacks"))
+            Ok(Json(ApiResponse::success(modpacks)))
         }
+        Err(e) => Err(AppError::internal_error("get_modpacks", format!("Failed to fetch modpacks: {}", e))),
     }
 }
 
@@ -644,18 +633,15 @@ pub async fn resolve_mod_dependencies(
     State(state): State<ModpackState>,
     Path(id): Path<String>,
     Query(params): Query<HashMap<String, String>>
-) -> Json<ApiResponse<DependencyResolution>> {
+) -> Result<Json<ApiResponse<DependencyResolution>>, AppError> {
     let default_mc_version = "1.21.1".to_string();
     let default_loader = "fabric".to_string();
     let minecraft_version = params.get("minecraft_version").unwrap_or(&default_mc_version);
     let loader = params.get("loader").unwrap_or(&default_loader);
     
     match state.version_resolver.resolve_dependencies(&id, "latest", minecraft_version, loader).await {
-        Ok(resolution) => Json(ApiResponse::success(resolution)),
-        Err(e) => {
-            eprintln!("Error resolving mod dependencies: {}", e);
-            Json(ApiResponse::error("Failed to resolve mod dependencies"))
-        }
+        Ok(resolution) => Ok(Json(ApiResponse::success(resolution))),
+        Err(e) => Err(AppError::rejected("Failed to resolve mod dependencies", e)),
     }
 }
 
@@ -663,7 +649,7 @@ pub async fn resolve_modpack_dependencies(
     State(state): State<ModpackState>,
     Path(id): Path<String>,
     Query(params): Query<HashMap<String, String>>
-) -> Json<ApiResponse<Vec<DependencyResolution>>> {
+) -> Result<Json<ApiResponse<Vec<DependencyResolution>>>, AppError> {
     let default_mc_version = "1.21.1".to_string();
     let default_loader = "fabric".to_string();
     let minecraft_version = params.get("minecraft_version").unwrap_or(&default_mc_version);
@@ -677,18 +663,12 @@ pub async fn resolve_modpack_dependencies(
             let all_mods = [client_mods, server_mods].concat();
             
             match state.version_resolver.auto_resolve_dependencies(all_mods, minecraft_version, loader).await {
-                Ok(resolutions) => Json(ApiResponse::success(resolutions)),
-                Err(e) => {
-                    eprintln!("Error resolving modpack dependencies: {}", e);
-                    Json(ApiResponse::error("Failed to resolve modpack dependencies"))
-                }
+                Ok(resolutions) => Ok(Json(ApiResponse::success(resolutions))),
+                Err(e) => Err(AppError::rejected("Failed to resolve modpack dependencies", e)),
             }
         }
-        Ok(None) => Json(ApiResponse::error("Modpack not found")),
-        Err(e) => {
-            eprintln!("Error fetching modpack: {}", e);
-            Json(ApiResponse::error("Failed to fetch modpack"))
-        }
+        Ok(None) => Err(AppError::not_found("Modpack")),
+        Err(e) => Err(AppError::internal_error("resolve_modpack_dependencies", format!("Failed to fetch modpack: {}", e))),
     }
 }
 
@@ -702,16 +682,13 @@ pub struct AutoResolveDependenciesRequest {
 pub async fn auto_resolve_dependencies(
     State(state): State<ModpackState>,
     Json(request): Json<AutoResolveDependenciesRequest>
-) -> Json<ApiResponse<Vec<DependencyResolution>>> {
+) -> Result<Json<ApiResponse<Vec<DependencyResolution>>>, AppError> {
     match state.version_resolver.auto_resolve_dependencies(
         request.mod_ids,
         &request.minecraft_version,
         &request.loader
     ).await {
-        Ok(resolutions) => Json(ApiResponse::success(resolutions)),
-        Err(e) => {
-            eprintln!("Error auto-resolving dependencies: {}", e);
-            Json(ApiResponse::error("Failed to auto-resolve dependencies"))
-        }
+        Ok(resolutions) => Ok(Json(ApiResponse::success(resolutions))),
+        Err(e) => Err(AppError::rejected("Failed to auto-resolve dependencies", e)),
     }
 }
@@ -159,7 +159,7 @@ impl InputValidator {
         }
 
         // Validate port
-        if let Err(e) = ValidationService::validate_port_range(port) {
+        if let Err(e) = ValidationService::validate_port(port) {
             errors.add("port", e);
         }
 
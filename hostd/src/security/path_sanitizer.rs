@@ -49,6 +49,15 @@ impl PathSanitizer {
         }
     }
 
+    /// Create a path sanitizer that allows any path inside the base directory
+    pub fn unrestricted(base_dir: PathBuf) -> Self {
+        Self {
+            base_dir,
+            // Every path starts with the empty prefix
+            allowed_prefixes: vec![String::new()],
+        }
+    }
+
     /// Sanitize a file path for safe extraction
     pub fn sanitize_path(&self, file_path: &str) -> Result<PathBuf, PathSanitizationError> {
         // Check for empty path
@@ -31,14 +31,12 @@ impl Default for RateLimitConfig {
 #[derive(Debug, Clone)]
 struct RateLimitEntry {
     requests: Vec<Instant>,
-    last_cleanup: Instant,
 }
 
 impl RateLimitEntry {
     fn new() -> Self {
         Self {
             requests: Vec::new(),
-            last_cleanup: Instant::now(),
         }
     }
 
@@ -46,11 +44,9 @@ impl RateLimitEntry {
     fn is_allowed(&mut self, config: &RateLimitConfig) -> bool {
         let now = Instant::now();
         
-        // Clean up old requests
-        if now.duration_since(self.last_cleanup) > Duration::from_secs(30) {
-            self.requests.retain(|&time| now.duration_since(time) < config.window_size);
-            self.last_cleanup = now;
-        }
+        // Drop requests that have left the window, so the burst check below
+        // only counts requests that still apply
+        self.requests.retain(|&time| now.duration_since(time) < config.window_size);
         
         // Check burst limit
         if self.requests.len() >= config.burst_limit as usize {
@@ -363,6 +359,7 @@ mod tests {
         
         // Test endpoint-specific rate limit
         assert!(limiter.is_allowed("test_key", "/api/auth/login").await);
+        assert!(limiter.is_allowed("test_key", "/api/auth/login").await);
         assert!(!limiter.is_allowed("test_key", "/api/auth/login").await);
     }
 }
\ No newline at end of file
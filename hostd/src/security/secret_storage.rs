@@ -4,10 +4,24 @@ use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
 use anyhow::{Result, Context};
 
-/// Secret storage service for API keys and sensitive data
+/// Service name secrets are filed under in the OS keychain
+pub const KEYCHAIN_SERVICE: &str = "guardian-server-manager";
+
+/// Secret storage service for API keys and sensitive data.
+/// With a keychain, secrets live in the OS credential store (Windows Credential
+/// Manager, macOS Keychain, Secret Service on Linux) and are cached in memory.
 pub struct SecretStorage {
     secrets: Arc<RwLock<HashMap<String, SecretEntry>>>,
     encryption_key: Option<String>,
+    keychain_service: Option<String>,
+}
+
+impl std::fmt::Debug for SecretStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretStorage")
+            .field("keychain_service", &self.keychain_service)
+            .finish_non_exhaustive()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +37,7 @@ impl SecretStorage {
         Self {
             secrets: Arc::new(RwLock::new(HashMap::new())),
             encryption_key: None,
+            keychain_service: None,
         }
     }
 
@@ -30,14 +45,59 @@ impl SecretStorage {
         Self {
             secrets: Arc::new(RwLock::new(HashMap::new())),
             encryption_key: Some(encryption_key),
+            keychain_service: None,
+        }
+    }
+
+    /// Storage backed by the OS keychain. Falls back to memory only when the
+    /// keychain cannot be written, as on headless Linux without a Secret Service.
+    pub async fn with_keychain(service: &str) -> Self {
+        let mut storage = Self::new();
+        storage.keychain_service = Some(service.to_string());
+        let probe = storage.keychain("keychain_probe", |entry| {
+            entry.set_password("probe")?;
+            entry.delete_credential()
+        }).await;
+        if let Err(e) = probe {
+            tracing::warn!("OS keychain unavailable, secrets will not be moved out of the database: {}", e);
+            storage.keychain_service = None;
         }
+        storage
+    }
+
+    /// Whether secrets are kept in the OS keychain
+    pub fn uses_keychain(&self) -> bool {
+        self.keychain_service.is_some()
+    }
+
+    /// Run a blocking keychain operation on the entry for `key`
+    async fn keychain<T, F>(&self, key: &str, op: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(keyring::Entry) -> keyring::Result<T> + Send + 'static,
+    {
+        let service = self.keychain_service.clone()
+            .ok_or_else(|| anyhow::anyhow!("No keychain configured"))?;
+        let key = key.to_string();
+        tokio::task::spawn_blocking(move || op(keyring::Entry::new(&service, &key)?))
+            .await
+            .context("Keychain task failed")?
+            .context("Keychain operation failed")
     }
 
     /// Store a secret value
     pub async fn store_secret(&self, key: &str, value: &str) -> Result<()> {
+        if self.uses_keychain() {
+            let value = value.to_string();
+            self.keychain(key, move |entry| entry.set_password(&value)).await?;
+        }
+
         let mut secrets = self.secrets.write().await;
         
-        let processed_value = if let Some(enc_key) = &self.encryption_key {
+        // The keychain encrypts on its own
+        let processed_value = if self.uses_keychain() {
+            value.to_string()
+        } else if let Some(enc_key) = &self.encryption_key {
             self.encrypt_value(value, enc_key)?
         } else {
             value.to_string()
@@ -45,7 +105,7 @@ impl SecretStorage {
         
         let entry = SecretEntry {
             value: processed_value,
-            encrypted: self.encryption_key.is_some(),
+            encrypted: self.encryption_key.is_some() && !self.uses_keychain(),
             created_at: chrono::Utc::now(),
             last_accessed: None,
         };
@@ -71,25 +131,52 @@ impl SecretStorage {
                 entry.value.clone()
             };
             
-            Ok(Some(value))
-        } else {
-            Ok(None)
+            return Ok(Some(value));
+        }
+        drop(secrets);
+
+        if !self.uses_keychain() {
+            return Ok(None);
+        }
+        let value = self.keychain(key, |entry| match entry.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e),
+        }).await?;
+        if let Some(value) = &value {
+            self.secrets.write().await.insert(key.to_string(), SecretEntry {
+                value: value.clone(),
+                encrypted: false,
+                created_at: chrono::Utc::now(),
+                last_accessed: Some(chrono::Utc::now()),
+            });
         }
+        Ok(value)
     }
 
     /// Check if a secret exists
     pub async fn has_secret(&self, key: &str) -> bool {
-        let secrets = self.secrets.read().await;
-        secrets.contains_key(key)
+        if self.secrets.read().await.contains_key(key) {
+            return true;
+        }
+        self.uses_keychain() && matches!(self.get_secret(key).await, Ok(Some(_)))
     }
 
     /// Remove a secret
     pub async fn remove_secret(&self, key: &str) -> Result<bool> {
-        let mut secrets = self.secrets.write().await;
-        Ok(secrets.remove(key).is_some())
+        let mut removed = self.secrets.write().await.remove(key).is_some();
+        if self.uses_keychain() {
+            removed |= self.keychain(key, |entry| match entry.delete_credential() {
+                Ok(()) => Ok(true),
+                Err(keyring::Error::NoEntry) => Ok(false),
+                Err(e) => Err(e),
+            }).await?;
+        }
+        Ok(removed)
     }
 
-    /// List all secret keys (without values)
+    /// List secret keys (without values). The keychain cannot be enumerated,
+    /// so only secrets stored or read since startup are listed.
     pub async fn list_secrets(&self) -> Vec<String> {
         let secrets = self.secrets.read().await;
         secrets.keys().cloned().collect()
@@ -293,6 +380,17 @@ mod tests {
         assert_eq!(not_found, None);
     }
 
+    #[tokio::test]
+    async fn test_keychain_storage_round_trip() {
+        // Without a usable keychain this exercises the in-memory fallback
+        let storage = SecretStorage::with_keychain("guardian-server-manager-test").await;
+
+        storage.store_api_key("curseforge", "cf_test_key_123").await.unwrap();
+        assert_eq!(storage.get_api_key("curseforge").await.unwrap(), Some("cf_test_key_123".to_string()));
+        assert!(storage.remove_secret("api_key_curseforge").await.unwrap());
+        assert_eq!(storage.get_api_key("curseforge").await.unwrap(), None);
+    }
+
     #[tokio::test]
     async fn test_api_key_manager() {
         let manager = ApiKeyManager::new();
@@ -137,7 +137,7 @@ impl ValidationService {
     pub fn sanitize_input(input: &str) -> String {
         input
             .chars()
-            .filter(|c| !c.is_control())
+            .filter(|c| !c.is_control() || c.is_whitespace())
             .collect::<String>()
             .trim()
             .to_string()
@@ -411,7 +411,7 @@ mod tests {
     fn test_port_validation() {
         assert!(ValidationService::validate_port(25565).is_ok());
         assert!(ValidationService::validate_port(1023).is_err());
-        assert!(ValidationService::validate_port(65536).is_err());
+        assert!(ValidationService::validate_port(65535).is_ok());
     }
 
     #[test]
@@ -0,0 +1,322 @@
+//! File manager for a server's directory
+//!
+//! Paths are relative to the server directory, with `/` separators; the empty
+//! path is the directory itself. They are checked with the security path
+//! sanitizer, and symlinks leading out of the server directory are refused.
+//! Files are opened as text when they are valid UTF-8 without NUL bytes and
+//! small enough to edit; anything else is downloaded instead.
+
+use anyhow::{anyhow, bail, Result};
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use std::path::{Component, Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+use crate::database::ServerConfig;
+use crate::security::PathSanitizer;
+
+/// Largest file opened or saved as text
+pub const MAX_TEXT_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Largest file accepted by upload
+pub const MAX_UPLOAD_BYTES: usize = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEntry {
+    pub name: String,
+    /// Relative to the server directory
+    pub path: String,
+    /// file or directory
+    pub kind: String,
+    pub size: u64,
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileContent {
+    pub path: String,
+    pub size: u64,
+    pub binary: bool,
+    /// `None` for binary files
+    pub content: Option<String>,
+}
+
+/// A server's directory, canonicalized so symlinks can be checked against it
+fn root(server: &ServerConfig) -> Result<PathBuf> {
+    let dir = PathBuf::from(&server.server_directory);
+    dir.canonicalize()
+        .map_err(|e| anyhow!("Server directory {} is not available: {}", dir.display(), e))
+}
+
+/// Resolve `path` inside the server directory, returning it normalized and
+/// as a full path
+pub fn resolve(server: &ServerConfig, path: &str) -> Result<(String, PathBuf)> {
+    let root = root(server)?;
+    resolve_in(&root, path)
+}
+
+fn resolve_in(root: &Path, path: &str) -> Result<(String, PathBuf)> {
+    let path = path.trim_matches('/').replace('\\', "/");
+    if path.is_empty() {
+        return Ok((path, root.to_path_buf()));
+    }
+    // The sanitizer misses a trailing `..`
+    if Path::new(&path).components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+        bail!("Invalid path '{}'", path);
+    }
+    PathSanitizer::unrestricted(root.to_path_buf())
+        .sanitize_path(&path)
+        .map_err(|e| anyhow!("Invalid path '{}': {}", path, e))?;
+    // Not the sanitizer's canonical path, so a symlink is renamed or deleted rather than its target
+    let resolved = root.join(&path);
+
+    // A path that doesn't exist yet may still lead through a symlinked directory
+    let existing = resolved.ancestors().find(|ancestor| ancestor.exists()).unwrap_or(root);
+    if !existing.canonicalize()?.starts_with(root) {
+        bail!("Path '{}' leads outside the server directory", path);
+    }
+    Ok((path, resolved))
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/")
+}
+
+/// Entries of a directory, directories first
+pub async fn list(server: &ServerConfig, path: &str) -> Result<Vec<FileEntry>> {
+    let root = root(server)?;
+    let (path, dir) = resolve_in(&root, path)?;
+    if !dir.is_dir() {
+        bail!("'{}' is not a directory", path);
+    }
+    tokio::task::spawn_blocking(move || {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            entries.push(FileEntry {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path: relative(&root, &entry.path()),
+                kind: if metadata.is_dir() { "directory" } else { "file" }.to_string(),
+                size: if metadata.is_dir() { 0 } else { metadata.len() },
+                modified: metadata.modified().ok().map(Into::into),
+            });
+        }
+        entries.sort_by(|a, b| (a.kind != "directory", &a.name).cmp(&(b.kind != "directory", &b.name)));
+        Ok(entries)
+    })
+    .await?
+}
+
+/// Open a file, as text when it is text
+pub async fn read(server: &ServerConfig, path: &str) -> Result<FileContent> {
+    let (path, file) = resolve(server, path)?;
+    let metadata = tokio::fs::metadata(&file).await.map_err(|_| anyhow!("File '{}' not found", path))?;
+    if !metadata.is_file() {
+        bail!("'{}' is not a file", path);
+    }
+    if metadata.len() > MAX_TEXT_BYTES {
+        return Ok(FileContent { path, size: metadata.len(), binary: true, content: None });
+    }
+    let bytes = tokio::fs::read(&file).await?;
+    let content = text(bytes);
+    Ok(FileContent { path, size: metadata.len(), binary: content.is_none(), content })
+}
+
+/// The bytes as a string, unless they look binary
+fn text(bytes: Vec<u8>) -> Option<String> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Save text to a file, creating it and its directories if needed
+pub async fn write(server: &ServerConfig, path: &str, content: &str) -> Result<FileEntry> {
+    if content.len() as u64 > MAX_TEXT_BYTES {
+        bail!("Text files are limited to {} MB", MAX_TEXT_BYTES / 1024 / 1024);
+    }
+    save(server, path, content.as_bytes()).await
+}
+
+/// Store an uploaded file, replacing any file at `path`. The body is streamed
+/// to disk; one larger than [`MAX_UPLOAD_BYTES`] is refused.
+pub async fn upload<S, B, E>(server: &ServerConfig, path: &str, body: S) -> Result<FileEntry>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    upload_in(&root(server)?, path, body).await
+}
+
+async fn upload_in<S, B, E>(root: &Path, path: &str, mut body: S) -> Result<FileEntry>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    let (_, file) = target(root, path).await?;
+    let partial = partial_path(&file);
+    let written = async {
+        let mut out = tokio::fs::File::create(&partial).await?;
+        let mut size = 0;
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.map_err(|e| anyhow!("The upload was interrupted: {}", e))?;
+            size += chunk.as_ref().len();
+            if size > MAX_UPLOAD_BYTES {
+                bail!("Uploads are limited to {} MB", MAX_UPLOAD_BYTES / 1024 / 1024);
+            }
+            out.write_all(chunk.as_ref()).await?;
+        }
+        out.flush().await?;
+        Ok(())
+    }
+    .await;
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(e);
+    }
+    tokio::fs::rename(&partial, &file).await?;
+    entry(root, &file).await
+}
+
+async fn save(server: &ServerConfig, path: &str, bytes: &[u8]) -> Result<FileEntry> {
+    let root = root(server)?;
+    let (_, file) = target(&root, path).await?;
+    let partial = partial_path(&file);
+    tokio::fs::write(&partial, bytes).await?;
+    tokio::fs::rename(&partial, &file).await?;
+    entry(&root, &file).await
+}
+
+/// Resolve a file to save, creating its directories
+async fn target(root: &Path, path: &str) -> Result<(String, PathBuf)> {
+    let (path, file) = resolve_in(root, path)?;
+    if path.is_empty() || file.is_dir() {
+        bail!("'{}' is a directory", path);
+    }
+    if let Some(parent) = file.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    Ok((path, file))
+}
+
+/// Files are written aside and renamed, so a failed write never leaves half a
+/// file behind
+fn partial_path(file: &Path) -> PathBuf {
+    let mut partial = file.to_path_buf().into_os_string();
+    partial.push(".partial");
+    partial.into()
+}
+
+async fn entry(root: &Path, path: &Path) -> Result<FileEntry> {
+    let metadata = tokio::fs::metadata(path).await?;
+    Ok(FileEntry {
+        name: path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+        path: relative(root, path),
+        kind: if metadata.is_dir() { "directory" } else { "file" }.to_string(),
+        size: if metadata.is_dir() { 0 } else { metadata.len() },
+        modified: metadata.modified().ok().map(Into::into),
+    })
+}
+
+/// Move or rename a file or directory; the target must not exist
+pub async fn rename(server: &ServerConfig, from: &str, to: &str) -> Result<FileEntry> {
+    let root = root(server)?;
+    let (from, source) = resolve_in(&root, from)?;
+    let (to, target) = resolve_in(&root, to)?;
+    if from.is_empty() || to.is_empty() {
+        bail!("The server directory itself can't be renamed");
+    }
+    if !source.exists() {
+        bail!("'{}' not found", from);
+    }
+    if target.exists() {
+        bail!("'{}' already exists", to);
+    }
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::rename(&source, &target).await?;
+    entry(&root, &target).await
+}
+
+/// Delete a file, or a directory with everything in it
+pub async fn delete(server: &ServerConfig, path: &str) -> Result<()> {
+    let (path, file) = resolve(server, path)?;
+    if path.is_empty() {
+        bail!("The server directory itself can't be deleted");
+    }
+    let metadata = tokio::fs::symlink_metadata(&file).await.map_err(|_| anyhow!("'{}' not found", path))?;
+    if metadata.is_dir() {
+        tokio::fs::remove_dir_all(&file).await?;
+    } else {
+        tokio::fs::remove_file(&file).await?;
+    }
+    Ok(())
+}
+
+/// A file to download, with its name and size
+pub async fn open(server: &ServerConfig, path: &str) -> Result<(String, u64, tokio::fs::File)> {
+    let (path, file) = resolve(server, path)?;
+    let metadata = tokio::fs::metadata(&file).await.map_err(|_| anyhow!("File '{}' not found", path))?;
+    if !metadata.is_file() {
+        bail!("'{}' is not a file", path);
+    }
+    let name = file.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    Ok((name, metadata.len(), tokio::fs::File::open(&file).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_stays_in_server_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("config")).unwrap();
+
+        assert_eq!(resolve_in(&root, "/config/a.toml").unwrap(), ("config/a.toml".to_string(), root.join("config/a.toml")));
+        assert_eq!(resolve_in(&root, "").unwrap().1, root);
+        for path in ["../etc/passwd", "config/..", "config/../../x", "C:/Windows"] {
+            assert!(resolve_in(&root, path).is_err(), "{}", path);
+        }
+
+        #[cfg(unix)]
+        {
+            let outside = tempfile::tempdir().unwrap();
+            std::os::unix::fs::symlink(outside.path(), root.join("link")).unwrap();
+            assert!(resolve_in(&root, "link/new.txt").is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_streams_into_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+
+        let chunks = futures::stream::iter(vec![Ok::<_, std::io::Error>(b"hello ".to_vec()), Ok(b"world".to_vec())]);
+        let entry = upload_in(&root, "mods/a.jar", chunks).await.unwrap();
+        assert_eq!(entry.path, "mods/a.jar");
+        assert_eq!(entry.size, 11);
+        assert_eq!(std::fs::read(root.join("mods/a.jar")).unwrap(), b"hello world");
+
+        // An interrupted upload leaves the existing file alone
+        let chunks = futures::stream::iter(vec![
+            Ok(b"partial".to_vec()),
+            Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset")),
+        ]);
+        assert!(upload_in(&root, "mods/a.jar", chunks).await.is_err());
+        assert_eq!(std::fs::read(root.join("mods/a.jar")).unwrap(), b"hello world");
+        assert!(!root.join("mods/a.jar.partial").exists());
+    }
+
+    #[test]
+    fn test_text_detection() {
+        assert_eq!(text(b"motd=Hello\n".to_vec()).as_deref(), Some("motd=Hello\n"));
+        assert!(text(vec![0x0a, 0x00, 0x01]).is_none());
+        assert!(text(vec![0xff, 0xfe, 0x41]).is_none());
+    }
+}
@@ -0,0 +1,222 @@
+//! Server groups
+//!
+//! A group is a named set of servers, such as "SMP" or "Events", kept in the
+//! database. Groups can be referred to by id or name. They filter the server
+//! list, and bulk actions, restart schedules and summaries can target them
+//! instead of naming every server. A server may be in several groups.
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::api::ServerInfo;
+use crate::core::error_handler::NotFound;
+use crate::database::{DatabaseManager, ServerGroup};
+
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Totals across the servers of a group
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GroupSummary {
+    pub group_id: String,
+    pub name: String,
+    pub servers: usize,
+    pub running: usize,
+    pub players_online: u32,
+    /// Player slots across servers that set a limit
+    pub max_players: u32,
+    pub heap_mb: u64,
+    /// Mean TPS of the running servers; 0 when none runs
+    pub average_tps: f64,
+}
+
+/// Add up the servers of a group, as listed for the caller
+pub fn summarize(group: &ServerGroup, servers: &[ServerInfo]) -> GroupSummary {
+    let running: Vec<&ServerInfo> = servers.iter().filter(|server| server.status == "running").collect();
+    GroupSummary {
+        group_id: group.id.clone(),
+        name: group.name.clone(),
+        servers: servers.len(),
+        running: running.len(),
+        players_online: servers.iter().map(|server| server.players_online).sum(),
+        max_players: servers.iter().filter_map(|server| server.max_players).sum(),
+        heap_mb: servers.iter().map(|server| server.heap_mb).sum(),
+        average_tps: if running.is_empty() {
+            0.0
+        } else {
+            running.iter().map(|server| server.tps).sum::<f64>() / running.len() as f64
+        },
+    }
+}
+
+/// Drop repeated ids, keeping the first of each
+fn dedup(ids: Vec<String>) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    ids.into_iter().filter(|id| seen.insert(id.clone())).collect()
+}
+
+pub struct ServerGroupManager {
+    database: Arc<DatabaseManager>,
+}
+
+impl ServerGroupManager {
+    pub fn new(database: Arc<DatabaseManager>) -> Self {
+        Self { database }
+    }
+
+    pub async fn list(&self) -> Result<Vec<ServerGroup>> {
+        self.database.get_server_groups().await
+    }
+
+    pub async fn get(&self, id_or_name: &str) -> Result<ServerGroup> {
+        match self.database.get_server_group(id_or_name).await? {
+            Some(group) => Ok(group),
+            None => Err(NotFound::with_id("Server group", id_or_name).into()),
+        }
+    }
+
+    async fn check_name(&self, name: &str, id: Option<&str>) -> Result<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("A server group needs a name");
+        }
+        if name.chars().count() > MAX_NAME_LENGTH {
+            bail!("Server group names are at most {} characters", MAX_NAME_LENGTH);
+        }
+        if let Some(existing) = self.database.get_server_group(name).await? {
+            if Some(existing.id.as_str()) != id {
+                bail!("A server group named {} already exists", existing.name);
+            }
+        }
+        Ok(name.to_string())
+    }
+
+    async fn check_servers(&self, server_ids: Vec<String>) -> Result<Vec<String>> {
+        let server_ids = dedup(server_ids);
+        for id in &server_ids {
+            if self.database.get_server(id).await?.is_none() {
+                bail!("Unknown server {}", id);
+            }
+        }
+        Ok(server_ids)
+    }
+
+    pub async fn create(&self, name: &str, description: Option<String>, server_ids: Vec<String>) -> Result<ServerGroup> {
+        let now = Utc::now();
+        let group = ServerGroup {
+            id: Uuid::new_v4().to_string(),
+            name: self.check_name(name, None).await?,
+            description,
+            server_ids: self.check_servers(server_ids).await?,
+            created_at: now,
+            updated_at: now,
+        };
+        self.database.save_server_group(&group).await?;
+        info!("Created server group {} with {} servers", group.name, group.server_ids.len());
+        Ok(group)
+    }
+
+    pub async fn update(&self, id_or_name: &str, name: Option<String>, description: Option<String>) -> Result<ServerGroup> {
+        let mut group = self.get(id_or_name).await?;
+        if let Some(name) = name {
+            group.name = self.check_name(&name, Some(&group.id)).await?;
+        }
+        if description.is_some() {
+            group.description = description;
+        }
+        group.updated_at = Utc::now();
+        self.database.save_server_group(&group).await?;
+        Ok(group)
+    }
+
+    /// Replace the group's servers
+    pub async fn set_servers(&self, id_or_name: &str, server_ids: Vec<String>) -> Result<ServerGroup> {
+        let mut group = self.get(id_or_name).await?;
+        group.server_ids = self.check_servers(server_ids).await?;
+        group.updated_at = Utc::now();
+        self.database.save_server_group(&group).await?;
+        info!("Server group {} now has {} servers", group.name, group.server_ids.len());
+        Ok(group)
+    }
+
+    pub async fn delete(&self, id_or_name: &str) -> Result<()> {
+        let group = self.get(id_or_name).await?;
+        self.database.delete_server_group(&group.id).await?;
+        info!("Deleted server group {}", group.name);
+        Ok(())
+    }
+
+    /// Servers of all the given groups, each once, in the order the groups list them
+    pub async fn members(&self, ids_or_names: &[String]) -> Result<Vec<String>> {
+        let mut server_ids = Vec::new();
+        for key in ids_or_names {
+            server_ids.extend(self.get(key).await?.server_ids);
+        }
+        Ok(dedup(server_ids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(id: &str, status: &str, players: u32, max_players: Option<u32>, tps: f64) -> ServerInfo {
+        ServerInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            status: status.to_string(),
+            tps,
+            tick_p95: 0.0,
+            heap_mb: 1024,
+            players_online: players,
+            gpu_queue_ms: 0.0,
+            last_snapshot_at: None,
+            blue_green: crate::api::BlueGreenInfo { active: "blue".to_string(), candidate_healthy: false },
+            version: None,
+            max_players,
+            uptime: None,
+            memory_usage: None,
+            cpu_usage: None,
+            world_size: None,
+            last_backup: None,
+            auto_start: None,
+            auto_restart: None,
+            created_at: None,
+            updated_at: None,
+            managed: true,
+            public_address: None,
+            node_id: None,
+        }
+    }
+
+    #[test]
+    fn test_summarize_adds_up_servers() {
+        let group = ServerGroup {
+            id: "g".to_string(),
+            name: "SMP".to_string(),
+            description: None,
+            server_ids: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let servers = [
+            server("a", "running", 12, Some(20), 20.0),
+            server("b", "running", 3, Some(50), 18.0),
+            server("c", "stopped", 0, None, 0.0),
+        ];
+        let summary = summarize(&group, &servers);
+        assert_eq!((summary.servers, summary.running), (3, 2));
+        assert_eq!((summary.players_online, summary.max_players, summary.heap_mb), (15, 70, 3072));
+        assert_eq!(summary.average_tps, 19.0);
+        assert_eq!(summarize(&group, &[]).average_tps, 0.0);
+    }
+
+    #[test]
+    fn test_dedup_keeps_first_occurrence() {
+        let ids = ["b", "a", "b", "c", "a"].map(String::from).to_vec();
+        assert_eq!(dedup(ids), ["b", "a", "c"]);
+    }
+}
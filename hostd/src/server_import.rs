@@ -0,0 +1,547 @@
+//! Import of a server installation that already exists on this machine. The
+//! directory is inspected, never written to: the loader and Minecraft version
+//! come from the installed libraries and the jars' manifests, the ports and
+//! world settings from server.properties, and the server is registered as a
+//! managed server that runs from the directory as it is.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::database::ServerConfig;
+
+const DEFAULT_MEMORY_MB: u32 = 4096;
+const UNKNOWN: &str = "unknown";
+
+#[derive(Debug, Deserialize)]
+pub struct ImportRequest {
+    /// Directory holding the server, with its server.properties and jar
+    pub path: String,
+    /// Defaults to the directory's name
+    pub name: Option<String>,
+    pub java_path: Option<String>,
+    /// Defaults to the `-Xmx` in user_jvm_args.txt, then 4096
+    pub memory: Option<u32>,
+    /// Only report what would be imported
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// What was found in the directory
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedServer {
+    pub directory: String,
+    pub loader: String,
+    pub loader_version: String,
+    pub minecraft_version: String,
+    /// Jar the server is started with, relative to the directory
+    pub server_jar: Option<String>,
+    pub properties: HashMap<String, String>,
+    pub world: Option<DetectedWorld>,
+    pub mod_count: usize,
+    pub plugin_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedWorld {
+    pub name: String,
+    pub size_bytes: u64,
+    pub has_nether: bool,
+    pub has_end: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerImport {
+    /// None for a dry run
+    pub server_id: Option<String>,
+    pub detected: DetectedServer,
+    /// Things that will need attention before the server runs well under Guardian
+    pub warnings: Vec<String>,
+}
+
+/// Loader and version as one source reports them
+#[derive(Debug, Clone, PartialEq)]
+struct LoaderInfo {
+    loader: &'static str,
+    version: Option<String>,
+    minecraft_version: Option<String>,
+}
+
+/// What a jar's manifest and metadata files say about it
+#[derive(Debug, Default)]
+struct JarInfo {
+    main_class: Option<String>,
+    implementation_title: Option<String>,
+    /// `id` of version.json, which vanilla and Paperclip jars carry
+    minecraft_version: Option<String>,
+    /// install.properties of a Fabric server launcher
+    install_properties: HashMap<String, String>,
+}
+
+/// Inspect a server directory without changing it
+pub fn detect(directory: &Path) -> Result<DetectedServer> {
+    if !directory.is_dir() {
+        bail!("{} is not a directory", directory.display());
+    }
+    let properties = match fs::read_to_string(directory.join("server.properties")) {
+        Ok(content) => parse_properties(&content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+        Err(e) => return Err(e).context("Failed to read server.properties"),
+    };
+
+    let jars = root_jars(directory)?;
+    let inspected: Vec<(String, JarInfo)> = jars
+        .iter()
+        .filter_map(|name| inspect_jar(&directory.join(name)).ok().map(|info| (name.clone(), info)))
+        .collect();
+
+    let from_libraries = loader_from_libraries(&directory.join("libraries"));
+    let from_jars = inspected.iter().find_map(|(name, info)| loader_from_jar(name, info));
+    let loader = from_libraries.clone().or(from_jars.clone()).unwrap_or(LoaderInfo {
+        loader: "vanilla",
+        version: None,
+        minecraft_version: None,
+    });
+
+    let minecraft_version = loader
+        .minecraft_version
+        .clone()
+        .or_else(|| from_jars.as_ref().and_then(|info| info.minecraft_version.clone()))
+        .or_else(|| inspected.iter().find_map(|(_, info)| info.minecraft_version.clone()))
+        .or_else(|| minecraft_from_libraries(&directory.join("libraries")))
+        .or_else(|| newest_subdirectory(&directory.join("versions")))
+        .unwrap_or_else(|| UNKNOWN.to_string());
+    let loader_version = loader
+        .version
+        .clone()
+        .unwrap_or_else(|| if loader.loader == "vanilla" { minecraft_version.clone() } else { UNKNOWN.to_string() });
+
+    let world_name = properties.get("level-name").cloned().unwrap_or_else(|| "world".to_string());
+    let world_dir = directory.join(&world_name);
+    let world = world_dir.join("level.dat").is_file().then(|| DetectedWorld {
+        size_bytes: directory_size(&world_dir),
+        // Vanilla keeps other dimensions inside the world, Bukkit servers beside it
+        has_nether: world_dir.join("DIM-1").is_dir() || directory.join(format!("{}_nether", world_name)).is_dir(),
+        has_end: world_dir.join("DIM1").is_dir() || directory.join(format!("{}_the_end", world_name)).is_dir(),
+        name: world_name,
+    });
+
+    Ok(DetectedServer {
+        directory: directory.to_string_lossy().to_string(),
+        server_jar: launch_jar(loader.loader, &inspected),
+        loader: loader.loader.to_string(),
+        loader_version,
+        minecraft_version,
+        properties,
+        world,
+        mod_count: count_jars(&directory.join("mods")),
+        plugin_count: count_jars(&directory.join("plugins")),
+    })
+}
+
+/// Detect a server and build its configuration. Nothing is written; the
+/// caller stores the configuration.
+pub async fn prepare(request: &ImportRequest) -> Result<(ServerConfig, ServerImport)> {
+    let directory = tokio::fs::canonicalize(request.path.trim())
+        .await
+        .with_context(|| format!("Server directory {} not found", request.path))?;
+    let detected = {
+        let directory = directory.clone();
+        tokio::task::spawn_blocking(move || detect(&directory)).await??
+    };
+    let mut warnings = Vec::new();
+    let props = &detected.properties;
+    let prop = |key: &str| props.get(key).map(|value| value.trim()).filter(|value| !value.is_empty());
+    let flag = |key: &str, default: bool| prop(key).map_or(default, |value| value == "true");
+    let number = |key: &str, default: u32| prop(key).and_then(|value| value.parse().ok()).unwrap_or(default);
+
+    if props.is_empty() {
+        warnings.push("No server.properties found; the server will create one with default settings on first start".to_string());
+    }
+    if detected.server_jar.is_none() {
+        warnings.push(format!(
+            "No jar to start the server with was found; {} servers that launch through a script need a server jar set before Guardian can start them",
+            detected.loader
+        ));
+    }
+    if detected.minecraft_version == UNKNOWN {
+        warnings.push("Could not tell the Minecraft version".to_string());
+    }
+    if !flag("enable-rcon", false) || prop("rcon.password").is_none() {
+        warnings.push("RCON is not enabled in server.properties; the console and player list need it".to_string());
+    }
+    if detected.world.is_none() {
+        warnings.push("No world found; one will be generated on first start".to_string());
+    }
+
+    let user_jvm_args = read_user_jvm_args(&directory).await;
+    let memory = request
+        .memory
+        .or_else(|| user_jvm_args.iter().find_map(|arg| parse_memory(arg.strip_prefix("-Xmx")?)))
+        .unwrap_or(DEFAULT_MEMORY_MB);
+    let jvm_args = if user_jvm_args.is_empty() {
+        format!("-Xmx{}M -Xms{}M", memory, memory / 2)
+    } else {
+        user_jvm_args.join(" ")
+    };
+
+    let name = request
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .or_else(|| directory.file_name().map(|name| name.to_string_lossy().to_string()))
+        .ok_or_else(|| anyhow!("Server name is required"))?;
+
+    let port = number("server-port", 25565) as u16;
+    let now = Utc::now();
+    let config = ServerConfig {
+        id: Uuid::new_v4().to_string(),
+        name,
+        minecraft_version: detected.minecraft_version.clone(),
+        loader: detected.loader.clone(),
+        loader_version: detected.loader_version.clone(),
+        port,
+        rcon_port: number("rcon.port", 25575) as u16,
+        query_port: number("query.port", port as u32) as u16,
+        max_players: number("max-players", 20),
+        memory,
+        java_args: "[]".to_string(),
+        server_args: serde_json::to_string(&["--nogui"]).unwrap_or_default(),
+        auto_start: false,
+        auto_restart: true,
+        world_name: prop("level-name").unwrap_or("world").to_string(),
+        difficulty: prop("difficulty").unwrap_or("easy").to_string(),
+        gamemode: prop("gamemode").unwrap_or("survival").to_string(),
+        pvp: flag("pvp", true),
+        online_mode: flag("online-mode", true),
+        whitelist: flag("white-list", false),
+        enable_command_block: flag("enable-command-block", false),
+        view_distance: number("view-distance", 10),
+        simulation_distance: number("simulation-distance", 10),
+        motd: prop("motd").unwrap_or("A Minecraft Server").to_string(),
+        host: "localhost".to_string(),
+        java_path: request.java_path.clone().unwrap_or_else(|| "java".to_string()),
+        jvm_args,
+        server_jar: detected.server_jar.clone().unwrap_or_default(),
+        server_directory: detected.directory.clone(),
+        rcon_password: prop("rcon.password").unwrap_or_default().to_string(),
+        managed: true,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let import = ServerImport { server_id: None, detected, warnings };
+    Ok((config, import))
+}
+
+/// Simple `key=value` reading of a properties file
+pub(crate) fn parse_properties(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().replace("\\:", ":").replace("\\=", "=")))
+        .collect()
+}
+
+/// `.jar` files directly in the directory, sorted by name
+fn root_jars(directory: &Path) -> Result<Vec<String>> {
+    let mut jars: Vec<String> = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.to_ascii_lowercase().ends_with(".jar") && !name.to_ascii_lowercase().contains("installer"))
+        .collect();
+    jars.sort();
+    Ok(jars)
+}
+
+fn inspect_jar(path: &Path) -> Result<JarInfo> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+    let mut info = JarInfo::default();
+    if let Some(manifest) = read_entry(&mut archive, "META-INF/MANIFEST.MF") {
+        for line in manifest.lines() {
+            match line.split_once(':') {
+                Some(("Main-Class", value)) => info.main_class = Some(value.trim().to_string()),
+                Some(("Implementation-Title", value)) => info.implementation_title = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+    }
+    if let Some(version) = read_entry(&mut archive, "version.json") {
+        info.minecraft_version = serde_json::from_str::<serde_json::Value>(&version)
+            .ok()
+            .and_then(|json| json.get("id").and_then(|id| id.as_str()).map(str::to_string));
+    }
+    if let Some(install) = read_entry(&mut archive, "install.properties") {
+        info.install_properties = parse_properties(&install);
+    }
+    Ok(info)
+}
+
+fn read_entry(archive: &mut zip::ZipArchive<fs::File>, name: &str) -> Option<String> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    entry.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+/// Loaders that install themselves into `libraries/`
+fn loader_from_libraries(libraries: &Path) -> Option<LoaderInfo> {
+    if let Some(version) = newest_subdirectory(&libraries.join("net/neoforged/neoforge")) {
+        return Some(LoaderInfo {
+            loader: "neoforge",
+            minecraft_version: neoforge_minecraft_version(&version),
+            version: Some(version),
+        });
+    }
+    // NeoForge for 1.20.1 kept Forge's artifact name and numbering
+    if let Some(version) = newest_subdirectory(&libraries.join("net/neoforged/forge")) {
+        let (minecraft, loader) = version.split_once('-')?;
+        return Some(LoaderInfo {
+            loader: "neoforge",
+            version: Some(loader.to_string()),
+            minecraft_version: Some(minecraft.to_string()),
+        });
+    }
+    if let Some(version) = newest_subdirectory(&libraries.join("net/minecraftforge/forge")) {
+        let (minecraft, loader) = version.split_once('-')?;
+        return Some(LoaderInfo {
+            loader: "forge",
+            version: Some(loader.to_string()),
+            minecraft_version: Some(minecraft.to_string()),
+        });
+    }
+    if let Some(version) = newest_subdirectory(&libraries.join("org/quiltmc/quilt-loader")) {
+        return Some(LoaderInfo { loader: "quilt", version: Some(version), minecraft_version: None });
+    }
+    if let Some(version) = newest_subdirectory(&libraries.join("net/fabricmc/fabric-loader")) {
+        return Some(LoaderInfo { loader: "fabric", version: Some(version), minecraft_version: None });
+    }
+    None
+}
+
+/// Forge and NeoForge keep the server jar as a library, e.g. `1.20.1-20230612.114412`
+fn minecraft_from_libraries(libraries: &Path) -> Option<String> {
+    let version = newest_subdirectory(&libraries.join("net/minecraft/server"))?;
+    Some(version.split('-').next().unwrap_or(&version).to_string())
+}
+
+/// NeoForge numbers its releases after the Minecraft version: 20.4.x is for 1.20.4, 21.0.x for 1.21
+fn neoforge_minecraft_version(version: &str) -> Option<String> {
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse::<u32>().ok()?;
+    let minor = parts.next()?.parse::<u32>().ok()?;
+    Some(if minor == 0 { format!("1.{}", major) } else { format!("1.{}.{}", major, minor) })
+}
+
+/// Loader of a jar in the server directory, from its manifest and name
+fn loader_from_jar(name: &str, info: &JarInfo) -> Option<LoaderInfo> {
+    let lower = name.to_ascii_lowercase();
+    let main_class = info.main_class.as_deref().unwrap_or_default();
+    let title = info.implementation_title.as_deref().unwrap_or_default().to_ascii_lowercase();
+
+    if main_class.starts_with("net.fabricmc.") {
+        return Some(LoaderInfo {
+            loader: "fabric",
+            version: info.install_properties.get("fabric-loader-version").cloned(),
+            minecraft_version: info.install_properties.get("game-version").cloned(),
+        });
+    }
+    if main_class.starts_with("org.quiltmc.") {
+        return Some(LoaderInfo { loader: "quilt", version: None, minecraft_version: None });
+    }
+    // forge-1.12.2-14.23.5.2859.jar, forge-1.16.5-36.2.39-universal.jar
+    if let Some(rest) = lower.strip_prefix("forge-") {
+        let rest = rest.trim_end_matches(".jar").trim_end_matches("-universal").trim_end_matches("-shim");
+        if let Some((minecraft, loader)) = rest.split_once('-') {
+            return Some(LoaderInfo {
+                loader: "forge",
+                version: Some(loader.to_string()),
+                minecraft_version: Some(minecraft.to_string()),
+            });
+        }
+    }
+    let bukkit = if lower.contains("purpur") || title.contains("purpur") {
+        Some("purpur")
+    } else if main_class.starts_with("io.papermc.") || lower.contains("paper") || title.contains("paper") {
+        Some("paper")
+    } else if main_class.starts_with("org.bukkit.") || lower.contains("spigot") {
+        Some("spigot")
+    } else {
+        None
+    };
+    bukkit.map(|loader| LoaderInfo { loader, version: None, minecraft_version: info.minecraft_version.clone() })
+}
+
+/// Jar the server is started with, for the detected loader
+fn launch_jar(loader: &str, jars: &[(String, JarInfo)]) -> Option<String> {
+    let matching = |name: &String, info: &JarInfo| match loader {
+        "vanilla" => info.minecraft_version.is_some() && info.main_class.as_deref().is_some_and(|main| main.starts_with("net.minecraft.")),
+        "fabric" => info.main_class.as_deref().is_some_and(|main| main.starts_with("net.fabricmc.")),
+        "quilt" => info.main_class.as_deref().is_some_and(|main| main.starts_with("org.quiltmc.")),
+        "forge" | "neoforge" => name.to_ascii_lowercase().starts_with(loader) && info.main_class.is_some(),
+        _ => loader_from_jar(name, info).is_some_and(|found| found.loader == loader),
+    };
+    jars.iter().find(|(name, info)| matching(name, info)).map(|(name, _)| name.clone())
+}
+
+/// Highest-versioned subdirectory, if there is any
+fn newest_subdirectory(path: &Path) -> Option<String> {
+    let mut versions: Vec<String> = fs::read_dir(path)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    versions.sort_by(|a, b| compare_versions(a, b));
+    versions.pop()
+}
+
+/// Compare dotted versions numerically where they are numbers
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parts = |v: &str| -> Vec<u64> {
+        v.split(['.', '-']).map(|part| part.parse().unwrap_or(0)).collect()
+    };
+    parts(a).cmp(&parts(b)).then_with(|| a.cmp(b))
+}
+
+fn count_jars(path: &Path) -> usize {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_name().to_string_lossy().to_ascii_lowercase().ends_with(".jar"))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+fn directory_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => directory_size(&entry.path()),
+            Ok(kind) if kind.is_file() => entry.metadata().map_or(0, |metadata| metadata.len()),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Forge's user_jvm_args.txt, without comments
+async fn read_user_jvm_args(directory: &Path) -> Vec<String> {
+    let Ok(content) = tokio::fs::read_to_string(directory.join("user_jvm_args.txt")).await else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .flat_map(|line| line.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+        .collect()
+}
+
+/// `4G`, `4096M` or `4096m` in megabytes
+fn parse_memory(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let (number, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len()));
+    let number: u32 = number.parse().ok()?;
+    match unit.to_ascii_lowercase().as_str() {
+        "g" => Some(number * 1024),
+        "m" => Some(number),
+        "k" => Some(number / 1024),
+        "" => Some(number / (1024 * 1024)),
+        _ => None,
+    }
+}
+
+/// Directories already registered, so the same installation isn't imported twice
+pub fn already_registered(servers: &[ServerConfig], directory: &str) -> Option<String> {
+    let directory = PathBuf::from(directory);
+    servers
+        .iter()
+        .find(|server| server.managed && fs::canonicalize(&server.server_directory).is_ok_and(|path| path == directory))
+        .map(|server| server.name.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_jar(path: &Path, files: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_detect_fabric_server() {
+        let dir = tempfile::tempdir().unwrap();
+        write_jar(
+            &dir.path().join("fabric-server-launch.jar"),
+            &[
+                ("META-INF/MANIFEST.MF", "Manifest-Version: 1.0\nMain-Class: net.fabricmc.loader.impl.launch.server.FabricServerLauncher\n"),
+                ("install.properties", "fabric-loader-version=0.15.7\ngame-version=1.20.1\n"),
+            ],
+        );
+        write_jar(
+            &dir.path().join("server.jar"),
+            &[
+                ("META-INF/MANIFEST.MF", "Main-Class: net.minecraft.bundler.Main\n"),
+                ("version.json", r#"{"id": "1.20.1", "name": "1.20.1"}"#),
+            ],
+        );
+        fs::write(dir.path().join("server.properties"), "#comment\nserver-port=25570\nlevel-name=survival\nmotd=Hello\\=world\n").unwrap();
+        fs::create_dir_all(dir.path().join("survival/DIM-1")).unwrap();
+        fs::write(dir.path().join("survival/level.dat"), [0u8; 16]).unwrap();
+        fs::create_dir_all(dir.path().join("mods")).unwrap();
+        fs::write(dir.path().join("mods/sodium.jar"), b"").unwrap();
+
+        let detected = detect(dir.path()).unwrap();
+        assert_eq!(detected.loader, "fabric");
+        assert_eq!(detected.loader_version, "0.15.7");
+        assert_eq!(detected.minecraft_version, "1.20.1");
+        assert_eq!(detected.server_jar.as_deref(), Some("fabric-server-launch.jar"));
+        assert_eq!(detected.properties.get("motd").map(String::as_str), Some("Hello=world"));
+        let world = detected.world.unwrap();
+        assert_eq!(world.name, "survival");
+        assert!(world.has_nether && !world.has_end);
+        assert_eq!(detected.mod_count, 1);
+    }
+
+    #[test]
+    fn test_detect_forge_from_libraries() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("libraries/net/minecraftforge/forge/1.20.1-47.2.0")).unwrap();
+        let detected = detect(dir.path()).unwrap();
+        assert_eq!(detected.loader, "forge");
+        assert_eq!(detected.loader_version, "47.2.0");
+        assert_eq!(detected.minecraft_version, "1.20.1");
+        assert!(detected.server_jar.is_none());
+        assert!(detected.world.is_none());
+    }
+
+    #[test]
+    fn test_versions() {
+        assert_eq!(neoforge_minecraft_version("20.4.190").as_deref(), Some("1.20.4"));
+        assert_eq!(neoforge_minecraft_version("21.0.167").as_deref(), Some("1.21"));
+        assert_eq!(compare_versions("0.9.2", "0.15.7"), std::cmp::Ordering::Less);
+        assert_eq!(parse_memory("6G"), Some(6144));
+        assert_eq!(parse_memory("2048m"), Some(2048));
+    }
+}
@@ -0,0 +1,428 @@
+//! Server log files
+//!
+//! The game writes `logs/latest.log` and rotates it into
+//! `logs/<date>-<n>.log.gz`. Reading and searching happen here rather than in
+//! the UI, so it never downloads the whole history. Vanilla lines start with
+//! `[12:34:56] [Server thread/INFO]:`, Forge lines with
+//! `[01Jan2024 12:34:56.789] [Server thread/INFO] [logger/]:`; continuation
+//! lines such as stack traces take the level and time of the line they follow.
+//! Lines only carry a time of day, so dates come from the rotated file's name
+//! or, for latest.log, its modification date.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use flate2::read::GzDecoder;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::database::ServerConfig;
+use crate::core::error_handler::NotFound;
+
+const LATEST: &str = "latest.log";
+
+/// Most bytes returned by one read
+pub const MAX_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Most lines a search returns
+pub const MAX_MATCHES: usize = 1000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogFile {
+    pub name: String,
+    pub size_bytes: u64,
+    /// gzip-compressed rotated log
+    pub compressed: bool,
+    /// Day the log was written, from its name or modification time
+    pub date: Option<NaiveDate>,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// Part of a log, starting at `offset` bytes into its uncompressed content
+#[derive(Debug, Clone, Serialize)]
+pub struct LogChunk {
+    pub file: String,
+    pub offset: u64,
+    /// Where to continue reading from
+    pub next_offset: u64,
+    /// Uncompressed size of the log
+    pub size_bytes: u64,
+    /// Set when the log got shorter than the requested offset, because it was
+    /// rotated; reading restarted from the beginning
+    pub rotated: bool,
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReadOptions {
+    /// Byte offset to read from
+    pub offset: Option<u64>,
+    /// Return the last `tail` lines instead
+    pub tail: Option<usize>,
+    /// Bytes to read from `offset`
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    fn parse(level: &str) -> Option<Self> {
+        match level.to_ascii_uppercase().as_str() {
+            "TRACE" => Some(Self::Trace),
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" | "WARNING" => Some(Self::Warn),
+            "ERROR" | "SEVERE" => Some(Self::Error),
+            "FATAL" => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// Text to find; every line matches when absent
+    pub query: Option<String>,
+    /// Treat `query` as a regular expression
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// Lowest level to include
+    pub level: Option<LogLevel>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Only search these files; all logs when empty
+    pub files: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogMatch {
+    pub file: String,
+    /// 1-based
+    pub line_number: usize,
+    pub time: Option<NaiveDateTime>,
+    pub level: Option<LogLevel>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    /// Newest first
+    pub matches: Vec<LogMatch>,
+    pub files_searched: usize,
+    /// More lines matched than the limit
+    pub truncated: bool,
+}
+
+fn logs_dir(server: &ServerConfig) -> PathBuf {
+    Path::new(&server.server_directory).join("logs")
+}
+
+/// latest.log, then rotated logs newest first
+pub async fn list(server: &ServerConfig) -> Result<Vec<LogFile>> {
+    let dir = logs_dir(server);
+    tokio::task::spawn_blocking(move || list_blocking(&dir)).await?
+}
+
+fn list_blocking(dir: &Path) -> Result<Vec<LogFile>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut files = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() || !is_log_name(&name) {
+            continue;
+        }
+        let modified: Option<DateTime<Utc>> = metadata.modified().ok().map(Into::into);
+        let date = rotated_date(&name).or_else(|| modified.map(|modified| modified.with_timezone(&Local).date_naive()));
+        files.push(LogFile {
+            compressed: name.ends_with(".gz"),
+            size_bytes: metadata.len(),
+            date,
+            modified,
+            name,
+        });
+    }
+    files.sort_by(|a, b| {
+        (b.name == LATEST, b.date, rotation_index(&b.name), &a.name).cmp(&(a.name == LATEST, a.date, rotation_index(&a.name), &b.name))
+    });
+    Ok(files)
+}
+
+fn is_log_name(name: &str) -> bool {
+    !name.starts_with('.') && !name.contains(['/', '\\']) && (name.ends_with(".log") || name.ends_with(".log.gz"))
+}
+
+/// Date of a rotated log named `2024-01-31-2.log.gz`
+fn rotated_date(name: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(name.get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn rotation_index(name: &str) -> u32 {
+    name.get(11..)
+        .and_then(|rest| rest.split('.').next())
+        .and_then(|index| index.parse().ok())
+        .unwrap_or(0)
+}
+
+fn log_path(server: &ServerConfig, name: &str) -> Result<PathBuf> {
+    if !is_log_name(name) {
+        bail!("Invalid log file name: {}", name);
+    }
+    let path = logs_dir(server).join(name);
+    if !path.is_file() {
+        return Err(NotFound::with_id("Log", name).into());
+    }
+    Ok(path)
+}
+
+fn open(path: &Path) -> Result<Box<dyn Read + Send>> {
+    let file = std::fs::File::open(path)?;
+    Ok(if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    })
+}
+
+/// Read part of a log, or its last lines
+pub async fn read(server: &ServerConfig, name: &str, options: ReadOptions) -> Result<LogChunk> {
+    let path = log_path(server, name)?;
+    let name = name.to_string();
+    tokio::task::spawn_blocking(move || read_blocking(&path, name, &options)).await?
+}
+
+fn read_blocking(path: &Path, file: String, options: &ReadOptions) -> Result<LogChunk> {
+    let limit = options.limit.unwrap_or(256 * 1024).clamp(1, MAX_CHUNK_BYTES);
+    // Compressed logs are small enough to decompress whole
+    let content = if path.extension().is_some_and(|ext| ext == "gz") {
+        let mut content = Vec::new();
+        open(path)?.read_to_end(&mut content)?;
+        Some(content)
+    } else {
+        None
+    };
+    let size = match &content {
+        Some(content) => content.len() as u64,
+        None => std::fs::metadata(path)?.len(),
+    };
+
+    let (offset, rotated) = match (options.tail, options.offset) {
+        (Some(tail), _) => {
+            let start = match &content {
+                Some(content) => tail_offset(|from, to| Ok(content[from as usize..to as usize].to_vec()), size, tail.max(1))?,
+                None => {
+                    let mut file = std::fs::File::open(path)?;
+                    tail_offset(
+                        |from, to| {
+                            let mut block = vec![0; (to - from) as usize];
+                            file.seek(SeekFrom::Start(from))?;
+                            file.read_exact(&mut block)?;
+                            Ok(block)
+                        },
+                        size,
+                        tail.max(1),
+                    )?
+                }
+            };
+            // The end of the log is what matters when the lines don't fit in the limit
+            (start.max(size.saturating_sub(limit as u64)), false)
+        }
+        (None, Some(offset)) if offset > size => (0, true),
+        (None, offset) => (offset.unwrap_or(0), false),
+    };
+    let mut bytes = match &content {
+        Some(content) => content[offset as usize..].iter().take(limit).copied().collect(),
+        None => {
+            let mut file = std::fs::File::open(path)?;
+            file.seek(SeekFrom::Start(offset))?;
+            let mut bytes = Vec::with_capacity(limit.min((size - offset) as usize));
+            file.take(limit as u64).read_to_end(&mut bytes)?;
+            bytes
+        }
+    };
+    // Stop at the last complete line. A line longer than the limit is returned
+    // in parts; an unfinished last line waits for the next read.
+    match bytes.iter().rposition(|&b| b == b'\n') {
+        Some(end) => bytes.truncate(end + 1),
+        None if bytes.len() < limit => bytes.clear(),
+        None => {}
+    }
+    let next_offset = offset + bytes.len() as u64;
+    let lines = String::from_utf8_lossy(&bytes).lines().map(str::to_string).collect();
+    Ok(LogChunk { file, offset, next_offset, size_bytes: size, rotated, lines })
+}
+
+/// Offset at which the last `lines` lines of a log of `size` bytes start,
+/// reading it backwards through `block(from, to)`
+fn tail_offset(mut block: impl FnMut(u64, u64) -> Result<Vec<u8>>, size: u64, lines: usize) -> Result<u64> {
+    const BLOCK: u64 = 64 * 1024;
+    let mut end = size;
+    let mut newlines = 0usize;
+    // A trailing newline ends the last line rather than starting another
+    let mut at_end = true;
+    while end > 0 {
+        let start = end.saturating_sub(BLOCK);
+        for (i, &byte) in block(start, end)?.iter().enumerate().rev() {
+            if byte == b'\n' && !at_end {
+                newlines += 1;
+                if newlines == lines {
+                    return Ok(start + i as u64 + 1);
+                }
+            }
+            at_end = false;
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+/// Time of day and level at the start of a log line, if it has them
+fn parse_line(line: &str) -> Option<(Option<NaiveTime>, Option<LogLevel>)> {
+    static PREFIX: OnceLock<Regex> = OnceLock::new();
+    let prefix = PREFIX.get_or_init(|| Regex::new(r"^\[([^\]]*?)(\d{1,2}:\d{2}:\d{2})(?:\.\d+)?\] \[[^\]]*/([A-Za-z]+)\]").unwrap());
+    let captures = prefix.captures(line)?;
+    let time = NaiveTime::parse_from_str(&captures[2], "%H:%M:%S").ok();
+    Some((time, LogLevel::parse(&captures[3])))
+}
+
+/// Search logs, newest first
+pub async fn search(server: &ServerConfig, options: SearchOptions) -> Result<SearchResult> {
+    let dir = logs_dir(server);
+    tokio::task::spawn_blocking(move || search_blocking(&dir, &options)).await?
+}
+
+fn search_blocking(dir: &Path, options: &SearchOptions) -> Result<SearchResult> {
+    let matcher = match options.query.as_deref().filter(|query| !query.is_empty()) {
+        Some(query) => {
+            let pattern = if options.regex { query.to_string() } else { regex::escape(query) };
+            Some(
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(!options.case_sensitive)
+                    .size_limit(1 << 20)
+                    .build()
+                    .map_err(|e| anyhow!("Invalid search pattern: {}", e))?,
+            )
+        }
+        None => None,
+    };
+    let limit = options.limit.unwrap_or(200).clamp(1, MAX_MATCHES);
+    let from = options.from.map(|from| from.with_timezone(&Local).naive_local());
+    let to = options.to.map(|to| to.with_timezone(&Local).naive_local());
+
+    let mut files = list_blocking(dir)?;
+    if !options.files.is_empty() {
+        files.retain(|file| options.files.contains(&file.name));
+    }
+    let mut result = SearchResult { matches: Vec::new(), files_searched: 0, truncated: false };
+    for file in files {
+        // A file's lines all fall on its date, give or take a log running past midnight
+        if let Some(date) = file.date {
+            if from.is_some_and(|from| date < from.date().pred_opt().unwrap_or(date))
+                || to.is_some_and(|to| date > to.date())
+            {
+                continue;
+            }
+        }
+        result.files_searched += 1;
+
+        let mut found = Vec::new();
+        let (mut time, mut level) = (None, None);
+        let mut reader = BufReader::new(open(&dir.join(&file.name))?);
+        let mut buffer = Vec::new();
+        let mut line_number = 0;
+        while reader.read_until(b'\n', &mut buffer)? > 0 {
+            line_number += 1;
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if let Some((line_time, line_level)) = parse_line(line) {
+                time = line_time.zip(file.date).map(|(time, date)| date.and_time(time));
+                level = line_level;
+            }
+            let keep = options.level.is_none_or(|min| level.is_some_and(|level| level >= min))
+                && from.is_none_or(|from| time.is_some_and(|time| time >= from))
+                && to.is_none_or(|to| time.is_some_and(|time| time <= to))
+                && matcher.as_ref().is_none_or(|matcher| matcher.is_match(line));
+            if keep {
+                found.push(LogMatch { file: file.name.clone(), line_number, time, level, text: line.to_string() });
+            }
+            buffer.clear();
+        }
+
+        // Newest lines first, like the files
+        found.reverse();
+        let room = limit - result.matches.len();
+        if found.len() > room {
+            found.truncate(room);
+            result.truncated = true;
+        }
+        result.matches.extend(found);
+        if result.matches.len() >= limit {
+            result.truncated = true;
+            break;
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_line() {
+        let (time, level) = parse_line("[12:34:56] [Server thread/WARN]: Can't keep up!").unwrap();
+        assert_eq!(time, NaiveTime::from_hms_opt(12, 34, 56));
+        assert_eq!(level, Some(LogLevel::Warn));
+        let (time, level) = parse_line("[01Jan2024 08:00:01.123] [main/ERROR] [net.minecraftforge/]: Boom").unwrap();
+        assert_eq!(time, NaiveTime::from_hms_opt(8, 0, 1));
+        assert_eq!(level, Some(LogLevel::Error));
+        assert!(parse_line("\tat net.minecraft.Foo.bar(Foo.java:1)").is_none());
+    }
+
+    #[test]
+    fn test_search_and_tail_across_rotated_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut gz = flate2::write::GzEncoder::new(
+            std::fs::File::create(dir.path().join("2024-01-01-1.log.gz")).unwrap(),
+            flate2::Compression::default(),
+        );
+        gz.write_all(b"[10:00:00] [Server thread/INFO]: Starting\n[10:00:05] [Server thread/ERROR]: Exception ticking world\n\tat net.minecraft.World.tick\n")
+            .unwrap();
+        gz.finish().unwrap();
+        std::fs::write(dir.path().join("latest.log"), "[09:00:00] [Server thread/INFO]: Done\n[09:00:01] [Server thread/WARN]: Exception caught\n").unwrap();
+
+        let files = list_blocking(dir.path()).unwrap();
+        assert_eq!(files.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), ["latest.log", "2024-01-01-1.log.gz"]);
+
+        let options = SearchOptions { query: Some("exception|at net".to_string()), regex: true, level: Some(LogLevel::Error), ..Default::default() };
+        let result = search_blocking(dir.path(), &options).unwrap();
+        let found: Vec<(&str, usize)> = result.matches.iter().map(|m| (m.file.as_str(), m.line_number)).collect();
+        // The stack trace line takes its level from the line it follows
+        assert_eq!(found, [("2024-01-01-1.log.gz", 3), ("2024-01-01-1.log.gz", 2)]);
+        assert_eq!(result.matches[1].time, NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(10, 0, 5));
+
+        let chunk = read_blocking(&dir.path().join("latest.log"), LATEST.to_string(), &ReadOptions { tail: Some(1), ..Default::default() }).unwrap();
+        assert_eq!(chunk.lines, ["[09:00:01] [Server thread/WARN]: Exception caught"]);
+        assert_eq!(chunk.next_offset, chunk.size_bytes);
+        let chunk = read_blocking(&dir.path().join("latest.log"), LATEST.to_string(), &ReadOptions { offset: Some(1000), ..Default::default() }).unwrap();
+        assert!(chunk.rotated);
+        assert_eq!(chunk.lines.len(), 2);
+    }
+}
@@ -0,0 +1,702 @@
+//! Server migration: a `migration` job moves a managed server to another
+//! directory on this host or to a remote node. The server is stopped and its
+//! directory (world, mods, configs) copied to the destination, or packaged and
+//! sent to the node's agent, which unpacks it with [`ServerMigrator::receive`].
+//! The server is then started at the destination, and only once it answers a
+//! status ping is the source removed. A failed step rolls back: the copy is
+//! removed, the config restored and the source started again if it was
+//! running.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::config_revisions::{server_dir, SERVER_PROPERTIES};
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, EventLog, Mod, ServerConfig, Task};
+use crate::jobs::{Job, JobContext, JobManager};
+use crate::minecraft::MinecraftManager;
+use crate::nodes::NodeManager;
+use crate::server_templates::{copy_tree, merge_properties, next_free_port};
+
+const EVENT_TYPE: &str = "server_migration";
+const DEFAULT_VERIFY_TIMEOUT_SECS: u64 = 300;
+const READY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Entries of a transfer archive
+const MANIFEST_ENTRY: &str = "server.json";
+const FILES_ENTRY: &str = "files";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MigrationRequest {
+    /// New server directory on this host
+    pub directory: Option<String>,
+    /// Node to move the server to, instead of a directory
+    pub node_id: Option<String>,
+    /// New game port; the RCON and query ports move along when they clash
+    pub port: Option<u16>,
+    /// Start the server at the destination and wait for it to answer before
+    /// removing the source; true when unset
+    pub verify: Option<bool>,
+    pub verify_timeout_secs: Option<u64>,
+}
+
+/// Query of `POST /api/servers/transfer`, sent by the node a server leaves
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransferQuery {
+    pub port: Option<u16>,
+    pub verify: Option<bool>,
+    pub verify_timeout_secs: Option<u64>,
+    /// Leave the server running once it answered
+    pub keep_running: Option<bool>,
+}
+
+/// What a transfer archive says about the server besides its files
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    server: ServerConfig,
+    mods: Vec<Mod>,
+}
+
+/// A server unpacked from a transfer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceivedServer {
+    pub server_id: String,
+    pub name: String,
+    pub server_directory: String,
+    pub port: u16,
+    pub rcon_port: u16,
+    pub query_port: u16,
+}
+
+pub struct ServerMigrator {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+    minecraft_manager: MinecraftManager,
+    node_manager: Arc<NodeManager>,
+    jobs: Arc<JobManager>,
+    servers_dir: PathBuf,
+}
+
+impl ServerMigrator {
+    pub fn new(
+        database: Arc<DatabaseManager>,
+        process_manager: Arc<ProcessManager>,
+        minecraft_manager: MinecraftManager,
+        node_manager: Arc<NodeManager>,
+        jobs: Arc<JobManager>,
+        servers_dir: PathBuf,
+    ) -> Self {
+        Self { database, process_manager, minecraft_manager, node_manager, jobs, servers_dir }
+    }
+
+    /// Check the request and queue the migration job
+    pub async fn start(self: &Arc<Self>, server_id: &str, request: MigrationRequest) -> Result<Task> {
+        let server = self.database.require_server(server_id).await?;
+        if !server.managed {
+            bail!("External servers cannot be migrated");
+        }
+        let directory = request.directory.as_deref().map(str::trim).filter(|d| !d.is_empty()).map(PathBuf::from);
+        let destination = match (directory, request.node_id.as_deref()) {
+            (Some(_), Some(_)) => bail!("Give either a directory or a node, not both"),
+            (None, None) => bail!("Give a directory or a node to migrate the server to"),
+            (Some(directory), None) => {
+                check_directory(&server_dir(&server), &directory)?;
+                Destination::Directory(directory)
+            }
+            (None, Some(node_id)) => Destination::Node(self.node_manager.node(node_id).await?.id),
+        };
+        if let Some(port) = request.port {
+            let used = self.used_ports(server_id).await?;
+            if used.contains(&port) {
+                bail!("Port {} is already used by another server", port);
+            }
+        }
+        self.jobs
+            .spawn(MigrationJob {
+                migrator: self.clone(),
+                server_id: server.id.clone(),
+                destination,
+                port: request.port,
+                verify: request.verify.unwrap_or(true),
+                verify_timeout: Duration::from_secs(request.verify_timeout_secs.unwrap_or(DEFAULT_VERIFY_TIMEOUT_SECS)),
+            })
+            .await
+    }
+
+    /// Ports of every server but `except`
+    async fn used_ports(&self, except: &str) -> Result<HashSet<u16>> {
+        Ok(self
+            .database
+            .get_all_servers()
+            .await?
+            .iter()
+            .filter(|server| server.id != except)
+            .flat_map(|server| [server.port, server.rcon_port, server.query_port])
+            .collect())
+    }
+
+    async fn stop(&self, server_id: &str) -> Result<()> {
+        let uuid = Uuid::parse_str(server_id)?;
+        if self.process_manager.is_server_running(uuid).await {
+            self.process_manager.stop_server_process(uuid).await?;
+        }
+        Ok(())
+    }
+
+    /// Point the stored and cached config at `server`
+    async fn save(&self, server: &ServerConfig) -> Result<()> {
+        self.database.update_server(server).await?;
+        if let Some(mut cached) = self.minecraft_manager.get_server(&server.id).await {
+            cached.config = server.clone();
+            self.minecraft_manager.update_server(cached).await;
+        }
+        Ok(())
+    }
+
+    /// Start `server` and wait until it answers a status ping, stopping it
+    /// again afterwards unless `keep_running`; a server that doesn't answer is
+    /// stopped
+    async fn verify(&self, server: &ServerConfig, timeout: Duration, keep_running: bool) -> Result<()> {
+        let uuid = Uuid::parse_str(&server.id)?;
+        self.process_manager
+            .start_server_process(server.clone())
+            .await
+            .map_err(|e| anyhow!("Failed to start the server at the destination: {}", e))?;
+        let ready = wait_until_ready(server.port, timeout).await;
+        if !ready || !keep_running {
+            if let Err(e) = self.process_manager.stop_server_process(uuid).await {
+                warn!("Failed to stop {} after verifying it: {}", server.name, e);
+            }
+        }
+        if !ready {
+            bail!("The server did not answer within {} seconds at the destination", timeout.as_secs());
+        }
+        Ok(())
+    }
+
+    /// Move a server to another directory on this host
+    async fn migrate_local(&self, job: &MigrationJob, directory: &Path, ctx: &JobContext) -> Result<String> {
+        let original = self.database.require_server(&job.server_id).await?;
+        let from = server_dir(&original);
+        check_directory(&from, directory)?;
+        let was_running = self.process_manager.is_server_running(Uuid::parse_str(&original.id)?).await;
+
+        ctx.progress(0.05, "stopping", Some("Stopping the server")).await;
+        self.stop(&original.id).await?;
+
+        let migrated = match self.move_local(job, &original, directory, was_running, ctx).await {
+            Ok(migrated) => migrated,
+            Err(e) => {
+                warn!("Migrating {} to {} failed, rolling back: {}", original.name, directory.display(), e);
+                if let Err(rollback) = self.save(&original).await {
+                    error!("Failed to restore the config of {}: {}", original.name, rollback);
+                }
+                if let Err(rollback) = tokio::fs::remove_dir_all(directory).await {
+                    warn!("Failed to remove {}: {}", directory.display(), rollback);
+                }
+                if was_running {
+                    if let Err(restart) = self.process_manager.start_server_process(original.clone()).await {
+                        error!("Failed to start {} again at its source: {}", original.name, restart);
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        ctx.progress(0.95, "cleaning", Some("Removing the source directory")).await;
+        if let Err(e) = tokio::fs::remove_dir_all(&from).await {
+            warn!("Failed to remove the old directory {} of {}: {}", from.display(), original.name, e);
+        }
+        if was_running && !job.verify {
+            self.process_manager.start_server_process(migrated).await?;
+        }
+        Ok(format!("Moved {} from {} to {}", original.name, from.display(), directory.display()))
+    }
+
+    /// Copy the server to `directory` and point its config there; the caller rolls back on failure
+    async fn move_local(
+        &self,
+        job: &MigrationJob,
+        original: &ServerConfig,
+        directory: &Path,
+        was_running: bool,
+        ctx: &JobContext,
+    ) -> Result<ServerConfig> {
+        ctx.progress(0.1, "copying", Some(&format!("Copying to {}", directory.display()))).await;
+        {
+            let (from, to) = (server_dir(original), directory.to_path_buf());
+            tokio::task::spawn_blocking(move || copy_tree(&from, &to, &[]))
+                .await?
+                .context("Failed to copy the server directory")?;
+        }
+        if ctx.is_cancelled() {
+            bail!("Cancelled");
+        }
+
+        ctx.progress(0.6, "configuring", Some("Updating the server config")).await;
+        let mut migrated = original.clone();
+        migrated.server_directory = directory.to_string_lossy().to_string();
+        migrated.updated_at = Utc::now();
+        assign_ports(&mut migrated, &self.used_ports(&original.id).await?, job.port)?;
+        write_ports(original, &migrated).await?;
+        self.save(&migrated).await?;
+
+        if job.verify {
+            ctx.progress(0.7, "verifying", Some("Starting the server at the destination")).await;
+            self.verify(&migrated, job.verify_timeout, was_running).await?;
+        }
+        Ok(migrated)
+    }
+
+    /// Move a server to a node: send it to the agent, which starts it to
+    /// check it, then remove it here
+    async fn migrate_remote(&self, job: &MigrationJob, node_id: &str, ctx: &JobContext) -> Result<String> {
+        let node = self.node_manager.node(node_id).await?;
+        let server = self.database.require_server(&job.server_id).await?;
+        let uuid = Uuid::parse_str(&server.id)?;
+        let was_running = self.process_manager.is_server_running(uuid).await;
+
+        ctx.progress(0.05, "stopping", Some("Stopping the server")).await;
+        self.stop(&server.id).await?;
+
+        ctx.progress(0.1, "packaging", Some("Packaging the server")).await;
+        let archive = self.servers_dir.join(format!(".migration-{}.tar.gz", Uuid::new_v4()));
+        let result = self.send(job, &node, &server, &archive, was_running, ctx).await;
+        if let Err(e) = tokio::fs::remove_file(&archive).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove {}: {}", archive.display(), e);
+            }
+        }
+        let received = match result {
+            Ok(received) => received,
+            Err(e) => {
+                warn!("Migrating {} to node {} failed, rolling back: {}", server.name, node.name, e);
+                if was_running {
+                    if let Err(restart) = self.process_manager.start_server_process(server.clone()).await {
+                        error!("Failed to start {} again at its source: {}", server.name, restart);
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        ctx.progress(0.95, "cleaning", Some("Removing the server from this node")).await;
+        self.database.delete_server(&server.id).await?;
+        let _ = self.minecraft_manager.remove_server(&server.id).await;
+        if let Err(e) = tokio::fs::remove_dir_all(server_dir(&server)).await {
+            warn!("Failed to remove the old directory of {}: {}", server.name, e);
+        }
+        if let Err(e) = self.node_manager.refresh(&node.id).await {
+            warn!("Failed to refresh node {}: {}", node.name, e);
+        }
+        Ok(format!(
+            "Moved {} to node {} as {} on port {}",
+            server.name, node.name, received.server_id, received.port
+        ))
+    }
+
+    async fn send(
+        &self,
+        job: &MigrationJob,
+        node: &crate::database::Node,
+        server: &ServerConfig,
+        archive: &Path,
+        keep_running: bool,
+        ctx: &JobContext,
+    ) -> Result<ReceivedServer> {
+        let manifest = Manifest { server: server.clone(), mods: self.database.get_mods_by_server(&server.id).await? };
+        {
+            let manifest = serde_json::to_vec_pretty(&manifest)?;
+            let (from, to) = (server_dir(server), archive.to_path_buf());
+            tokio::task::spawn_blocking(move || package(&manifest, &from, &to))
+                .await?
+                .context("Failed to package the server")?;
+        }
+        if ctx.is_cancelled() {
+            bail!("Cancelled");
+        }
+
+        ctx.progress(0.4, "transferring", Some(&format!("Sending the server to {}", node.name))).await;
+        let query = TransferQuery {
+            port: job.port,
+            verify: Some(job.verify),
+            verify_timeout_secs: Some(job.verify_timeout.as_secs()),
+            keep_running: Some(keep_running),
+        };
+        let path = transfer_path(&query);
+        let file = tokio::fs::File::open(archive).await?;
+        let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
+        self.node_manager
+            .call(node, "POST", &path, Some("application/gzip"), body)
+            .await
+            .map_err(|e| anyhow!("{} did not take the server: {}", node.name, e))
+    }
+
+    /// Unpack a server sent by another node and register it here, starting it
+    /// to check it when asked to; a server that fails the check is removed again
+    pub async fn receive(&self, body: axum::body::Body, query: TransferQuery) -> Result<ReceivedServer> {
+        tokio::fs::create_dir_all(&self.servers_dir).await?;
+        let staging = self.servers_dir.join(format!(".incoming-{}", Uuid::new_v4()));
+        let result = self.unpack_and_register(body, &query, &staging).await;
+        if let Err(e) = tokio::fs::remove_dir_all(&staging).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove {}: {}", staging.display(), e);
+            }
+        }
+        let server = result?;
+
+        if query.verify.unwrap_or(true) {
+            let timeout = Duration::from_secs(query.verify_timeout_secs.unwrap_or(DEFAULT_VERIFY_TIMEOUT_SECS));
+            if let Err(e) = self.verify(&server, timeout, query.keep_running.unwrap_or(false)).await {
+                warn!("Received server {} failed to start, removing it: {}", server.name, e);
+                if let Err(e) = self.database.delete_server(&server.id).await {
+                    error!("Failed to remove received server {}: {}", server.id, e);
+                }
+                let _ = self.minecraft_manager.remove_server(&server.id).await;
+                let _ = tokio::fs::remove_dir_all(server_dir(&server)).await;
+                return Err(e);
+            }
+        } else if query.keep_running.unwrap_or(false) {
+            self.process_manager.start_server_process(server.clone()).await?;
+        }
+
+        info!("Received server {} into {}", server.name, server.server_directory);
+        self.log(&server.id, "info", format!("Received {} from another node", server.name)).await;
+        Ok(ReceivedServer {
+            server_id: server.id,
+            name: server.name,
+            server_directory: server.server_directory,
+            port: server.port,
+            rcon_port: server.rcon_port,
+            query_port: server.query_port,
+        })
+    }
+
+    async fn unpack_and_register(&self, body: axum::body::Body, query: &TransferQuery, staging: &Path) -> Result<ServerConfig> {
+        tokio::fs::create_dir_all(staging).await?;
+        let archive = staging.join("transfer.tar.gz");
+        let mut file = tokio::fs::File::create(&archive).await?;
+        let mut stream = body.into_data_stream();
+        while let Some(chunk) = stream.next().await {
+            file.write_all(&chunk?).await?;
+        }
+        file.flush().await?;
+        drop(file);
+
+        let manifest = {
+            let (archive, staging) = (archive.clone(), staging.to_path_buf());
+            tokio::task::spawn_blocking(move || unpack(&archive, &staging)).await??
+        };
+        let mut server = manifest.server;
+        if self.database.get_server(&server.id).await?.is_some() {
+            bail!("Server {} already exists on this node", server.id);
+        }
+        let original = server.clone();
+        let directory = self.servers_dir.join(&server.id);
+        if directory.exists() {
+            bail!("{} already exists", directory.display());
+        }
+        tokio::fs::rename(staging.join(FILES_ENTRY), &directory).await?;
+        server.server_directory = directory.to_string_lossy().to_string();
+        server.managed = true;
+        server.auto_start = false;
+        server.updated_at = Utc::now();
+        let used = self.used_ports(&server.id).await?;
+        if let Err(e) = assign_ports(&mut server, &used, query.port) {
+            let _ = tokio::fs::remove_dir_all(&directory).await;
+            return Err(e);
+        }
+        write_ports(&original, &server).await?;
+        self.minecraft_manager.add_server(server.clone()).await?;
+        for record in &manifest.mods {
+            if let Err(e) = self.database.create_mod(record).await {
+                warn!("Failed to record mod {} of {}: {}", record.filename, server.name, e);
+            }
+        }
+        Ok(server)
+    }
+
+    async fn log(&self, server_id: &str, level: &str, message: String) {
+        let event = EventLog {
+            id: Uuid::new_v4().to_string(),
+            server_id: Some(server_id.to_string()),
+            event_type: EVENT_TYPE.to_string(),
+            message,
+            level: level.to_string(),
+            metadata: None,
+            created_at: Utc::now(),
+        };
+        if let Err(e) = self.database.log_event(&event).await {
+            error!("Failed to log the migration of {}: {}", server_id, e);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Destination {
+    Directory(PathBuf),
+    /// Node id
+    Node(String),
+}
+
+/// A migration run as a `migration` job
+struct MigrationJob {
+    migrator: Arc<ServerMigrator>,
+    server_id: String,
+    destination: Destination,
+    port: Option<u16>,
+    verify: bool,
+    verify_timeout: Duration,
+}
+
+#[async_trait::async_trait]
+impl Job for MigrationJob {
+    fn kind(&self) -> &'static str {
+        "migration"
+    }
+
+    fn server_id(&self) -> Option<String> {
+        Some(self.server_id.clone())
+    }
+
+    fn metadata(&self) -> Option<serde_json::Value> {
+        Some(match &self.destination {
+            Destination::Directory(directory) => serde_json::json!({ "directory": directory }),
+            Destination::Node(node_id) => serde_json::json!({ "node_id": node_id }),
+        })
+    }
+
+    async fn run(self: Box<Self>, ctx: &JobContext) -> Result<Option<String>> {
+        let migrator = self.migrator.clone();
+        let result = match &self.destination {
+            Destination::Directory(directory) => migrator.migrate_local(&self, directory, ctx).await,
+            Destination::Node(node_id) => migrator.migrate_remote(&self, node_id, ctx).await,
+        };
+        match &result {
+            Ok(message) => migrator.log(&self.server_id, "info", message.clone()).await,
+            Err(e) => {
+                let message = format!("Migration failed and was rolled back: {}", e);
+                migrator.log(&self.server_id, "warn", message).await
+            }
+        }
+        result.map(Some)
+    }
+}
+
+/// The destination must be empty, and neither inside the server directory nor around it
+fn check_directory(from: &Path, to: &Path) -> Result<()> {
+    let absolute = |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let (from, to) = (absolute(from), absolute(to));
+    if to.starts_with(&from) || from.starts_with(&to) {
+        bail!("{} overlaps the server directory {}", to.display(), from.display());
+    }
+    if to.exists() && std::fs::read_dir(&to)?.next().is_some() {
+        bail!("{} already exists and is not empty", to.display());
+    }
+    Ok(())
+}
+
+/// Give `server` the requested game port, and move any of its ports that
+/// another server in `used` holds to the next free one
+fn assign_ports(server: &mut ServerConfig, used: &HashSet<u16>, requested: Option<u16>) -> Result<()> {
+    let mut used = used.clone();
+    let port = match requested {
+        Some(port) if used.contains(&port) => bail!("Port {} is already used by another server", port),
+        Some(port) => port,
+        None if used.contains(&server.port) => next_free_port(&used, server.port)?,
+        None => server.port,
+    };
+    used.insert(port);
+    let query_port = if server.query_port == server.port {
+        port
+    } else if used.contains(&server.query_port) {
+        next_free_port(&used, server.query_port)?
+    } else {
+        server.query_port
+    };
+    used.insert(query_port);
+    let rcon_port = if used.contains(&server.rcon_port) { next_free_port(&used, server.rcon_port)? } else { server.rcon_port };
+    server.port = port;
+    server.query_port = query_port;
+    server.rcon_port = rcon_port;
+    Ok(())
+}
+
+/// Point the moved server's server.properties at its new ports
+async fn write_ports(original: &ServerConfig, migrated: &ServerConfig) -> Result<()> {
+    if (original.port, original.query_port, original.rcon_port) == (migrated.port, migrated.query_port, migrated.rcon_port) {
+        return Ok(());
+    }
+    let properties = server_dir(migrated).join(SERVER_PROPERTIES);
+    if let Ok(current) = tokio::fs::read_to_string(&properties).await {
+        let ports = BTreeMap::from([
+            ("server-port".to_string(), migrated.port.to_string()),
+            ("query.port".to_string(), migrated.query_port.to_string()),
+            ("rcon.port".to_string(), migrated.rcon_port.to_string()),
+        ]);
+        tokio::fs::write(&properties, merge_properties(&current, &ports)).await?;
+    }
+    Ok(())
+}
+
+fn transfer_path(query: &TransferQuery) -> String {
+    let mut pairs = Vec::new();
+    if let Some(port) = query.port {
+        pairs.push(format!("port={}", port));
+    }
+    if let Some(verify) = query.verify {
+        pairs.push(format!("verify={}", verify));
+    }
+    if let Some(secs) = query.verify_timeout_secs {
+        pairs.push(format!("verify_timeout_secs={}", secs));
+    }
+    if let Some(keep_running) = query.keep_running {
+        pairs.push(format!("keep_running={}", keep_running));
+    }
+    if pairs.is_empty() {
+        "/api/servers/transfer".to_string()
+    } else {
+        format!("/api/servers/transfer?{}", pairs.join("&"))
+    }
+}
+
+/// Whether the server on `port` answers a status ping within `timeout`
+async fn wait_until_ready(port: u16, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if crate::server_ping::ping("127.0.0.1", port, crate::server_ping::DEFAULT_TIMEOUT).await.is_ok() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(READY_POLL_INTERVAL).await;
+    }
+}
+
+/// Write a transfer archive: the manifest, then the server directory under `files/`
+fn package(manifest: &[u8], from: &Path, to: &Path) -> Result<()> {
+    let file = std::fs::File::create(to)?;
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(file, flate2::Compression::fast()));
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST_ENTRY, manifest)?;
+    builder.append_dir_all(FILES_ENTRY, from)?;
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Unpack a transfer archive into `staging`, leaving the server files in `staging/files`
+fn unpack(archive: &Path, staging: &Path) -> Result<Manifest> {
+    let file = std::fs::File::open(archive)?;
+    // Entries that would land outside `staging` are refused
+    tar::Archive::new(flate2::read::GzDecoder::new(file)).unpack(staging)?;
+    let mut manifest = String::new();
+    std::fs::File::open(staging.join(MANIFEST_ENTRY))
+        .context("The transfer holds no server.json")?
+        .read_to_string(&mut manifest)?;
+    if !staging.join(FILES_ENTRY).is_dir() {
+        bail!("The transfer holds no server files");
+    }
+    Ok(serde_json::from_str(&manifest)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(port: u16, query_port: u16, rcon_port: u16) -> ServerConfig {
+        let now = Utc::now();
+        ServerConfig {
+            id: "server".to_string(),
+            name: "Survival".to_string(),
+            minecraft_version: "1.20.1".to_string(),
+            loader: "vanilla".to_string(),
+            loader_version: String::new(),
+            port,
+            rcon_port,
+            query_port,
+            max_players: 20,
+            memory: 2048,
+            java_args: "[]".to_string(),
+            server_args: "[]".to_string(),
+            auto_start: false,
+            auto_restart: false,
+            world_name: "world".to_string(),
+            difficulty: "normal".to_string(),
+            gamemode: "survival".to_string(),
+            pvp: true,
+            online_mode: true,
+            whitelist: false,
+            enable_command_block: false,
+            view_distance: 10,
+            simulation_distance: 10,
+            motd: String::new(),
+            host: "localhost".to_string(),
+            java_path: "java".to_string(),
+            jvm_args: String::new(),
+            server_jar: "server.jar".to_string(),
+            server_directory: String::new(),
+            rcon_password: String::new(),
+            managed: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_assign_ports() {
+        let used = HashSet::from([25565, 25575]);
+        let mut moved = server(25565, 25565, 25575);
+        assign_ports(&mut moved, &used, None).unwrap();
+        assert_eq!((moved.port, moved.query_port, moved.rcon_port), (25566, 25566, 25576));
+
+        let mut moved = server(25570, 25571, 25580);
+        assign_ports(&mut moved, &used, None).unwrap();
+        assert_eq!((moved.port, moved.query_port, moved.rcon_port), (25570, 25571, 25580));
+
+        let mut moved = server(25570, 25570, 25580);
+        assign_ports(&mut moved, &used, Some(25600)).unwrap();
+        assert_eq!((moved.port, moved.query_port, moved.rcon_port), (25600, 25600, 25580));
+        assert!(assign_ports(&mut moved, &used, Some(25575)).is_err());
+    }
+
+    #[test]
+    fn test_package_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("server");
+        std::fs::create_dir_all(from.join("world/region")).unwrap();
+        std::fs::write(from.join("server.properties"), "server-port=25565\n").unwrap();
+        std::fs::write(from.join("world/region/r.0.0.mca"), [1, 2, 3]).unwrap();
+        let archive = dir.path().join("transfer.tar.gz");
+        let manifest = Manifest { server: server(25565, 25565, 25575), mods: vec![] };
+        package(&serde_json::to_vec(&manifest).unwrap(), &from, &archive).unwrap();
+
+        let staging = dir.path().join("staging");
+        let unpacked = unpack(&archive, &staging).unwrap();
+        assert_eq!(unpacked.server.port, 25565);
+        assert_eq!(std::fs::read(staging.join("files/world/region/r.0.0.mca")).unwrap(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_check_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("server");
+        std::fs::create_dir_all(&from).unwrap();
+        assert!(check_directory(&from, &dir.path().join("moved")).is_ok());
+        assert!(check_directory(&from, &from.join("inner")).is_err());
+        assert!(check_directory(&from, dir.path()).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Server List Ping: the status request the Minecraft client sends to fill in
+//! its server list. Works against any server reachable by address, managed by
+//! Guardian or not, and reports the MOTD, player counts, version and latency.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const DEFAULT_PORT: u16 = 25565;
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Protocol version sent in the handshake; -1 asks the server for its own
+const ANY_PROTOCOL: i32 = -1;
+/// Largest status response accepted (favicons make them a few KiB)
+const MAX_PACKET: usize = 1 << 20;
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ServerStatus {
+    /// MOTD with formatting codes removed
+    pub motd: String,
+    pub version: String,
+    pub protocol: i32,
+    pub online_players: u32,
+    pub max_players: u32,
+    /// Some of the players online, as the server chooses to list them
+    pub player_sample: Vec<PlayerSample>,
+    /// Round trip of the ping packet
+    pub latency_ms: u64,
+    /// `data:image/png;base64,...`
+    pub favicon: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PlayerSample {
+    pub name: String,
+    pub id: String,
+}
+
+#[derive(Deserialize)]
+struct StatusResponse {
+    version: StatusVersion,
+    players: Option<StatusPlayers>,
+    #[serde(default)]
+    description: serde_json::Value,
+    favicon: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StatusVersion {
+    name: String,
+    protocol: i32,
+}
+
+#[derive(Deserialize)]
+struct StatusPlayers {
+    max: u32,
+    online: u32,
+    #[serde(default)]
+    sample: Vec<PlayerSample>,
+}
+
+/// Split `host[:port]`, defaulting to port 25565
+pub fn parse_address(address: &str) -> Result<(String, u16)> {
+    let address = address.trim();
+    if address.is_empty() {
+        bail!("Server address is empty");
+    }
+    // Bracketed IPv6, e.g. [::1]:25565
+    if let Some(rest) = address.strip_prefix('[') {
+        let (host, port) = rest.split_once(']').ok_or_else(|| anyhow!("Invalid address '{}'", address))?;
+        let port = match port.strip_prefix(':') {
+            Some(port) => port.parse().map_err(|_| anyhow!("Invalid port in '{}'", address))?,
+            None => DEFAULT_PORT,
+        };
+        return Ok((host.to_string(), port));
+    }
+    match address.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => {
+            let port = port.parse().map_err(|_| anyhow!("Invalid port in '{}'", address))?;
+            Ok((host.to_string(), port))
+        }
+        _ => Ok((address.to_string(), DEFAULT_PORT)),
+    }
+}
+
+/// Ping a server and read its status
+pub async fn ping(host: &str, port: u16, timeout: Duration) -> Result<ServerStatus> {
+    tokio::time::timeout(timeout, ping_inner(host, port))
+        .await
+        .map_err(|_| anyhow!("{}:{} did not answer within {:?}", host, port, timeout))?
+}
+
+async fn ping_inner(host: &str, port: u16) -> Result<ServerStatus> {
+    let mut stream = TcpStream::connect((host, port))
+        .await
+        .with_context(|| format!("Failed to connect to {}:{}", host, port))?;
+    stream.set_nodelay(true)?;
+
+    // Handshake with next state 1 (status), then the status request
+    let mut handshake = Vec::new();
+    write_varint(&mut handshake, 0x00);
+    write_varint(&mut handshake, ANY_PROTOCOL);
+    write_string(&mut handshake, host);
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+    send_packet(&mut stream, &handshake).await?;
+    send_packet(&mut stream, &[0x00]).await?;
+
+    let packet = read_packet(&mut stream).await?;
+    let mut cursor = packet.as_slice();
+    if read_varint_from(&mut cursor)? != 0x00 {
+        bail!("Unexpected packet in status response");
+    }
+    let json = read_string_from(&mut cursor)?;
+    let response: StatusResponse = serde_json::from_str(&json).context("Invalid status response")?;
+
+    let started = Instant::now();
+    let payload = chrono::Utc::now().timestamp_millis();
+    let mut ping = vec![0x01];
+    ping.extend_from_slice(&payload.to_be_bytes());
+    send_packet(&mut stream, &ping).await?;
+    // Some servers close the connection instead of answering the ping; the
+    // status is still worth reporting then, timed up to the close
+    let _ = read_packet(&mut stream).await;
+    let latency = started.elapsed();
+
+    let players = response.players;
+    Ok(ServerStatus {
+        motd: strip_formatting(&chat_text(&response.description)),
+        version: response.version.name,
+        protocol: response.version.protocol,
+        online_players: players.as_ref().map_or(0, |p| p.online),
+        max_players: players.as_ref().map_or(0, |p| p.max),
+        player_sample: players.map(|p| p.sample).unwrap_or_default(),
+        latency_ms: latency.as_millis() as u64,
+        favicon: response.favicon,
+    })
+}
+
+/// Plain text of a chat component: a string, or an object with `text` and `extra`
+fn chat_text(component: &serde_json::Value) -> String {
+    match component {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(parts) => parts.iter().map(chat_text).collect(),
+        serde_json::Value::Object(object) => {
+            let mut text = object.get("text").and_then(|t| t.as_str()).unwrap_or_default().to_string();
+            if let Some(extra) = object.get("extra") {
+                text.push_str(&chat_text(extra));
+            }
+            text
+        }
+        _ => String::new(),
+    }
+}
+
+/// Remove `§x` formatting codes
+fn strip_formatting(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn write_varint(buf: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7F == 0 {
+            buf.push(value as u8);
+            return;
+        }
+        buf.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+fn write_string(buf: &mut Vec<u8>, value: &str) {
+    write_varint(buf, value.len() as i32);
+    buf.extend_from_slice(value.as_bytes());
+}
+
+fn read_varint_from(cursor: &mut &[u8]) -> Result<i32> {
+    let mut value: u32 = 0;
+    for i in 0..5 {
+        let (&byte, rest) = cursor.split_first().ok_or_else(|| anyhow!("Truncated VarInt"))?;
+        *cursor = rest;
+        value |= ((byte & 0x7F) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    bail!("VarInt too long")
+}
+
+fn read_string_from(cursor: &mut &[u8]) -> Result<String> {
+    let len = read_varint_from(cursor)?;
+    let len = usize::try_from(len).map_err(|_| anyhow!("Negative string length"))?;
+    if len > cursor.len() {
+        bail!("Truncated string");
+    }
+    let (text, rest) = cursor.split_at(len);
+    *cursor = rest;
+    Ok(String::from_utf8_lossy(text).into_owned())
+}
+
+async fn send_packet(stream: &mut TcpStream, body: &[u8]) -> Result<()> {
+    let mut packet = Vec::with_capacity(body.len() + 5);
+    write_varint(&mut packet, body.len() as i32);
+    packet.extend_from_slice(body);
+    stream.write_all(&packet).await?;
+    Ok(())
+}
+
+async fn read_varint(stream: &mut TcpStream) -> Result<i32> {
+    let mut value: u32 = 0;
+    for i in 0..5 {
+        let byte = stream.read_u8().await?;
+        value |= ((byte & 0x7F) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    bail!("VarInt too long")
+}
+
+async fn read_packet(stream: &mut TcpStream) -> Result<Vec<u8>> {
+    let len = read_varint(stream).await?;
+    let len = usize::try_from(len).map_err(|_| anyhow!("Negative packet length"))?;
+    if len > MAX_PACKET {
+        bail!("Status response too large ({} bytes)", len);
+    }
+    let mut packet = vec![0; len];
+    stream.read_exact(&mut packet).await?;
+    Ok(packet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_round_trip() {
+        for value in [0, 1, 127, 128, 255, 25565, 2097151, i32::MAX, -1] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, value);
+            let mut cursor = buf.as_slice();
+            assert_eq!(read_varint_from(&mut cursor).unwrap(), value);
+            assert!(cursor.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_motd_text() {
+        let description = serde_json::json!({ "text": "§aHello", "extra": [{ "text": " world" }, "!"] });
+        assert_eq!(strip_formatting(&chat_text(&description)), "Hello world!");
+        assert_eq!(chat_text(&serde_json::json!("A Minecraft Server")), "A Minecraft Server");
+    }
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(parse_address("mc.example.com").unwrap(), ("mc.example.com".to_string(), 25565));
+        assert_eq!(parse_address("10.0.0.5:25570").unwrap(), ("10.0.0.5".to_string(), 25570));
+        assert_eq!(parse_address("[::1]:25566").unwrap(), ("::1".to_string(), 25566));
+        assert!(parse_address("host:port").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ping_against_fake_server() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            read_packet(&mut socket).await.unwrap();
+            read_packet(&mut socket).await.unwrap();
+            let status = serde_json::json!({
+                "version": { "name": "1.21.1", "protocol": 767 },
+                "players": { "max": 20, "online": 1, "sample": [{ "name": "Steve", "id": "8667ba71-b85a-4004-af54-457a9734eed7" }] },
+                "description": { "text": "Guardian test" }
+            });
+            let mut body = vec![0x00];
+            write_string(&mut body, &status.to_string());
+            send_packet(&mut socket, &body).await.unwrap();
+            let ping = read_packet(&mut socket).await.unwrap();
+            send_packet(&mut socket, &ping).await.unwrap();
+        });
+
+        let status = ping("127.0.0.1", port, DEFAULT_TIMEOUT).await.unwrap();
+        assert_eq!(status.motd, "Guardian test");
+        assert_eq!(status.version, "1.21.1");
+        assert_eq!((status.online_players, status.max_players), (1, 20));
+        assert_eq!(status.player_sample[0].name, "Steve");
+    }
+}
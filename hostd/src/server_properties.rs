@@ -0,0 +1,369 @@
+//! Schema of the vanilla `server.properties` keys: type, allowed range or values,
+//! default and the Minecraft versions that read them. Used to validate edits
+//! made through the API and to describe each property to the UI.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// How a property's value is interpreted by the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PropertyType {
+    Boolean,
+    Integer { min: Option<i64>, max: Option<i64> },
+    String,
+    Enum { values: &'static [&'static str] },
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PropertySpec {
+    pub key: &'static str,
+    #[serde(flatten)]
+    pub kind: PropertyType,
+    pub default: &'static str,
+    pub description: &'static str,
+    /// First version that reads the key
+    pub since: Option<&'static str>,
+    /// First version that no longer reads the key
+    pub removed_in: Option<&'static str>,
+}
+
+const fn spec(key: &'static str, kind: PropertyType, default: &'static str, description: &'static str) -> PropertySpec {
+    PropertySpec { key, kind, default, description, since: None, removed_in: None }
+}
+
+impl PropertySpec {
+    const fn since(mut self, version: &'static str) -> Self {
+        self.since = Some(version);
+        self
+    }
+
+    const fn removed_in(mut self, version: &'static str) -> Self {
+        self.removed_in = Some(version);
+        self
+    }
+
+    /// Whether `minecraft_version` reads this key. Versions that can't be parsed
+    /// (snapshots, "latest") are assumed to read every key.
+    pub fn available_in(&self, minecraft_version: &str) -> bool {
+        let Some(version) = parse_version(minecraft_version) else {
+            return true;
+        };
+        let after_since = self.since.and_then(parse_version).is_none_or(|since| version >= since);
+        let before_removal = self.removed_in.and_then(parse_version).is_none_or(|removed| version < removed);
+        after_since && before_removal
+    }
+}
+
+const fn int(min: i64, max: i64) -> PropertyType {
+    PropertyType::Integer { min: Some(min), max: Some(max) }
+}
+
+const fn at_least(min: i64) -> PropertyType {
+    PropertyType::Integer { min: Some(min), max: None }
+}
+
+const BOOL: PropertyType = PropertyType::Boolean;
+const TEXT: PropertyType = PropertyType::String;
+const PORT: PropertyType = int(1, 65535);
+
+pub const SCHEMA: &[PropertySpec] = &[
+    spec("accepts-transfers", BOOL, "false", "Accept players transferred from another server").since("1.20.5"),
+    spec("allow-flight", BOOL, "false", "Don't kick players for flying in survival"),
+    spec("allow-nether", BOOL, "true", "Allow players to travel to the Nether"),
+    spec("announce-player-achievements", BOOL, "true", "Announce achievements in chat").removed_in("1.12"),
+    spec("broadcast-console-to-ops", BOOL, "true", "Send console command output to online operators"),
+    spec("broadcast-rcon-to-ops", BOOL, "true", "Send RCON command output to online operators"),
+    spec("bug-report-link", TEXT, "", "Link shown in the disconnect screen's report button").since("1.21"),
+    spec(
+        "difficulty",
+        PropertyType::Enum { values: &["peaceful", "easy", "normal", "hard"] },
+        "easy",
+        "World difficulty",
+    ),
+    spec("enable-command-block", BOOL, "false", "Allow command blocks to run"),
+    spec("enable-jmx-monitoring", BOOL, "false", "Expose tick times over JMX").since("1.16"),
+    spec("enable-query", BOOL, "false", "Answer GameSpy4 query requests"),
+    spec("enable-rcon", BOOL, "false", "Accept remote console connections"),
+    spec("enable-status", BOOL, "true", "Show the server as online in the server list").since("1.16"),
+    spec("enforce-secure-profile", BOOL, "true", "Require players to have a Mojang-signed public key").since("1.19"),
+    spec("enforce-whitelist", BOOL, "false", "Kick players not on the whitelist when it is reloaded"),
+    spec("entity-broadcast-range-percentage", int(10, 1000), "100", "How far away entities are sent to clients, in percent").since("1.16"),
+    spec("force-gamemode", BOOL, "false", "Put players in the default game mode when they join"),
+    spec("function-permission-level", int(1, 4), "2", "Permission level of functions").since("1.14"),
+    spec(
+        "gamemode",
+        PropertyType::Enum { values: &["survival", "creative", "adventure", "spectator"] },
+        "survival",
+        "Default game mode",
+    ),
+    spec("generate-structures", BOOL, "true", "Generate villages, temples and other structures"),
+    spec("generator-settings", TEXT, "{}", "Settings for custom world generation"),
+    spec("hardcore", BOOL, "false", "Ban players when they die"),
+    spec("hide-online-players", BOOL, "false", "Hide the player list from status requests").since("1.18"),
+    spec("initial-disabled-packs", TEXT, "", "Data packs not enabled when the world is created").since("1.19.3"),
+    spec("initial-enabled-packs", TEXT, "vanilla", "Data packs enabled when the world is created").since("1.19.3"),
+    spec("level-name", TEXT, "world", "World folder name"),
+    spec("level-seed", TEXT, "", "Seed for new worlds; random when empty"),
+    spec("level-type", TEXT, "minecraft:normal", "World preset for new worlds"),
+    spec("log-ips", BOOL, "true", "Log player IP addresses").since("1.20.2"),
+    spec("max-build-height", int(64, 256), "256", "Maximum building height").removed_in("1.17"),
+    spec("max-chained-neighbor-updates", at_least(-1), "1000000", "Limit of consecutive neighbor updates before skipping").since("1.19"),
+    spec("max-players", int(0, 2_147_483_647), "20", "Maximum number of players online at once"),
+    spec("max-tick-time", at_least(-1), "60000", "Milliseconds a tick may take before the watchdog stops the server; -1 disables it"),
+    spec("max-world-size", int(1, 29_999_984), "29999984", "World border radius in blocks"),
+    spec("motd", TEXT, "A Minecraft Server", "Message shown in the server list"),
+    spec("network-compression-threshold", at_least(-1), "256", "Packet size in bytes above which packets are compressed; -1 disables compression"),
+    spec("online-mode", BOOL, "true", "Verify players against Minecraft account servers"),
+    spec("op-permission-level", int(0, 4), "4", "Default permission level of operators"),
+    spec("pause-when-empty-seconds", at_least(0), "60", "Seconds without players before the server pauses ticking").since("1.21.2"),
+    spec("player-idle-timeout", at_least(0), "0", "Minutes before idle players are kicked; 0 disables it"),
+    spec("prevent-proxy-connections", BOOL, "false", "Kick players whose IP differs from the one used to authenticate"),
+    spec("previews-chat", BOOL, "false", "Enable chat preview").since("1.19").removed_in("1.19.3"),
+    spec("pvp", BOOL, "true", "Allow players to damage each other"),
+    spec("query.port", PORT, "25565", "Port for query requests"),
+    spec("rate-limit", at_least(0), "0", "Packets per second a client may send before being kicked; 0 disables it"),
+    spec("rcon.password", TEXT, "", "Remote console password"),
+    spec("rcon.port", PORT, "25575", "Remote console port"),
+    spec(
+        "region-file-compression",
+        PropertyType::Enum { values: &["deflate", "lz4", "none"] },
+        "deflate",
+        "Compression of region files",
+    )
+    .since("1.20.5"),
+    spec("require-resource-pack", BOOL, "false", "Kick players who decline the resource pack"),
+    spec("resource-pack", TEXT, "", "URL of the server resource pack"),
+    spec("resource-pack-id", TEXT, "", "UUID of the server resource pack").since("1.20.3"),
+    spec("resource-pack-prompt", TEXT, "", "Message shown when asking players to accept the resource pack").since("1.17"),
+    spec("resource-pack-sha1", TEXT, "", "SHA-1 of the resource pack"),
+    spec("server-ip", TEXT, "", "Address to bind to; all addresses when empty"),
+    spec("server-port", PORT, "25565", "Port players connect to"),
+    spec("simulation-distance", int(3, 32), "10", "Distance in chunks around players that is ticked").since("1.18"),
+    spec("snooper-enabled", BOOL, "true", "Send usage data to Mojang").removed_in("1.18"),
+    spec("spawn-animals", BOOL, "true", "Spawn animals").removed_in("1.21.2"),
+    spec("spawn-monsters", BOOL, "true", "Spawn hostile mobs"),
+    spec("spawn-npcs", BOOL, "true", "Spawn villagers").removed_in("1.21.2"),
+    spec("spawn-protection", at_least(0), "16", "Radius around spawn only operators can build in; 0 disables it"),
+    spec("sync-chunk-writes", BOOL, "true", "Write chunks to disk synchronously").since("1.16"),
+    spec("text-filtering-config", TEXT, "", "Text filtering configuration").since("1.17"),
+    spec("use-native-transport", BOOL, "true", "Use Linux's epoll for networking"),
+    spec("view-distance", int(2, 32), "10", "Distance in chunks the server sends to clients"),
+    spec("white-list", BOOL, "false", "Only let whitelisted players join"),
+];
+
+pub fn find(key: &str) -> Option<&'static PropertySpec> {
+    SCHEMA.iter().find(|spec| spec.key == key)
+}
+
+/// A property as returned by the API, with its current value
+#[derive(Debug, Clone, Serialize)]
+pub struct PropertyInfo {
+    pub key: String,
+    pub value: Option<String>,
+    /// Schema of the key; unknown keys found in the file have none
+    #[serde(flatten)]
+    pub spec: Option<PropertySpec>,
+    /// The server's Minecraft version reads this key
+    pub available: bool,
+}
+
+/// Every key the server's version reads, plus any other key set in the file
+pub fn describe(properties: &HashMap<String, String>, minecraft_version: &str) -> Vec<PropertyInfo> {
+    let mut infos: Vec<PropertyInfo> = SCHEMA
+        .iter()
+        .filter(|spec| spec.available_in(minecraft_version) || properties.contains_key(spec.key))
+        .map(|spec| PropertyInfo {
+            key: spec.key.to_string(),
+            value: properties.get(spec.key).cloned(),
+            spec: Some(*spec),
+            available: spec.available_in(minecraft_version),
+        })
+        .collect();
+    infos.extend(properties.iter().filter(|(key, _)| find(key).is_none()).map(|(key, value)| PropertyInfo {
+        key: key.clone(),
+        value: Some(value.clone()),
+        spec: None,
+        available: true,
+    }));
+    infos.sort_by(|a, b| a.key.cmp(&b.key));
+    infos
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PropertyError {
+    pub key: String,
+    pub message: String,
+    pub suggestions: Vec<String>,
+}
+
+impl std::fmt::Display for PropertyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)?;
+        if !self.suggestions.is_empty() {
+            write!(f, " (did you mean {}?)", self.suggestions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Check a set of edits against the schema. Returns the values normalised the
+/// way the server writes them (booleans and enums in lower case), or every
+/// problem found.
+pub fn validate(
+    updates: &HashMap<String, String>,
+    minecraft_version: &str,
+) -> Result<HashMap<String, String>, Vec<PropertyError>> {
+    let mut normalized = HashMap::new();
+    let mut errors = Vec::new();
+    for (key, value) in updates {
+        let key = key.trim();
+        match validate_one(key, value.trim(), minecraft_version) {
+            Ok(value) => {
+                normalized.insert(key.to_string(), value);
+            }
+            Err(error) => errors.push(error),
+        }
+    }
+    if errors.is_empty() {
+        Ok(normalized)
+    } else {
+        errors.sort_by(|a, b| a.key.cmp(&b.key));
+        Err(errors)
+    }
+}
+
+fn validate_one(key: &str, value: &str, minecraft_version: &str) -> Result<String, PropertyError> {
+    let error = |message: String, suggestions: Vec<String>| PropertyError { key: key.to_string(), message, suggestions };
+
+    let Some(spec) = find(key) else {
+        return Err(error("unknown property".to_string(), suggestions(key, minecraft_version)));
+    };
+    if !spec.available_in(minecraft_version) {
+        let message = match (spec.since, spec.removed_in) {
+            (_, Some(removed)) if parse_version(minecraft_version) >= parse_version(removed) => {
+                format!("removed in Minecraft {}", removed)
+            }
+            (Some(since), _) => format!("requires Minecraft {} or newer", since),
+            _ => format!("not read by Minecraft {}", minecraft_version),
+        };
+        return Err(error(message, Vec::new()));
+    }
+
+    match spec.kind {
+        PropertyType::Boolean => match value.to_lowercase().as_str() {
+            v @ ("true" | "false") => Ok(v.to_string()),
+            _ => Err(error(format!("expected true or false, got '{}'", value), Vec::new())),
+        },
+        PropertyType::Integer { min, max } => {
+            let number: i64 = value
+                .parse()
+                .map_err(|_| error(format!("expected a whole number, got '{}'", value), Vec::new()))?;
+            if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                let range = match (min, max) {
+                    (Some(min), Some(max)) => format!("between {} and {}", min, max),
+                    (Some(min), None) => format!("at least {}", min),
+                    (None, Some(max)) => format!("at most {}", max),
+                    (None, None) => unreachable!(),
+                };
+                return Err(error(format!("must be {}, got {}", range, number), Vec::new()));
+            }
+            Ok(number.to_string())
+        }
+        PropertyType::Enum { values } => {
+            let lower = value.to_lowercase();
+            if values.contains(&lower.as_str()) {
+                Ok(lower)
+            } else {
+                let close = values.iter().filter(|v| edit_distance(v, &lower) <= 2).map(|v| v.to_string()).collect();
+                Err(error(format!("expected one of {}, got '{}'", values.join(", "), value), close))
+            }
+        }
+        PropertyType::String => Ok(value.to_string()),
+    }
+}
+
+/// Known keys close to a typo'd one, nearest first
+fn suggestions(key: &str, minecraft_version: &str) -> Vec<String> {
+    let key = key.to_lowercase();
+    let mut close: Vec<(usize, &str)> = SCHEMA
+        .iter()
+        .filter(|spec| spec.available_in(minecraft_version))
+        .map(|spec| (edit_distance(spec.key, &key), spec.key))
+        .filter(|(distance, candidate)| *distance <= 3.max(key.len() / 4) || candidate.replace(['-', '.', '_'], "") == key.replace(['-', '.', '_'], ""))
+        .collect();
+    close.sort();
+    close.into_iter().take(3).map(|(_, key)| key.to_string()).collect()
+}
+
+/// Levenshtein distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// "1.20.4" → (1, 20, 4); snapshots and other formats give `None`
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.trim().split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = match parts.next() {
+        Some(patch) => patch.parse().ok()?,
+        None => 0,
+    };
+    Some((major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn updates(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_validate_normalizes_values() {
+        let valid = validate(&updates(&[("pvp", "TRUE"), ("difficulty", "Hard"), ("view-distance", " 12 ")]), "1.20.4").unwrap();
+        assert_eq!(valid["pvp"], "true");
+        assert_eq!(valid["difficulty"], "hard");
+        assert_eq!(valid["view-distance"], "12");
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let errors = validate(
+            &updates(&[("view-distnace", "10"), ("max-players", "-1"), ("gamemode", "creatve"), ("online-mode", "yes")]),
+            "1.20.4",
+        )
+        .unwrap_err();
+        let keys: Vec<&str> = errors.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["gamemode", "max-players", "online-mode", "view-distnace"]);
+        assert_eq!(errors[0].suggestions, ["creative"]);
+        assert_eq!(errors[3].suggestions.first().map(String::as_str), Some("view-distance"));
+    }
+
+    #[test]
+    fn test_version_availability() {
+        assert!(validate(&updates(&[("simulation-distance", "8")]), "1.16.5").is_err());
+        assert!(validate(&updates(&[("simulation-distance", "8")]), "1.18").is_ok());
+        assert!(validate(&updates(&[("spawn-animals", "true")]), "1.21.4").is_err());
+        assert!(validate(&updates(&[("simulation-distance", "8")]), "24w14a").is_ok());
+
+        let props = updates(&[("snooper-enabled", "true"), ("custom-mod-key", "1")]);
+        let described = describe(&props, "1.20.4");
+        let snooper = described.iter().find(|p| p.key == "snooper-enabled").unwrap();
+        assert!(!snooper.available);
+        assert!(described.iter().any(|p| p.key == "custom-mod-key" && p.spec.is_none()));
+        assert!(!described.iter().any(|p| p.key == "max-build-height"));
+    }
+}
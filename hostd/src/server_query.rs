@@ -0,0 +1,208 @@
+//! Query protocol: the UDP status protocol servers answer when `enable-query`
+//! is set. Unlike the Server List Ping it reports the full player list, the
+//! map, and the plugin string, which makes it the source of player lists for
+//! servers Guardian does not run itself.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+
+pub const DEFAULT_PORT: u16 = 25565;
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const TYPE_HANDSHAKE: u8 = 9;
+const TYPE_STAT: u8 = 0;
+/// Any session id works as long as the low nibble of each byte is used
+const SESSION_ID: i32 = 0x0102_0304;
+/// Padding after the key/value section of a full stat response
+const PLAYER_SECTION: &[u8] = b"\x01player_\x00\x00";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryStatus {
+    pub motd: String,
+    pub game_type: String,
+    pub version: String,
+    /// Server software and plugins as the server reports them, e.g. `Paper on 1.21.1: ...`
+    pub plugins: String,
+    pub map: String,
+    pub online_players: u32,
+    pub max_players: u32,
+    /// Every player online, unlike the ping's sample
+    pub players: Vec<String>,
+    pub latency_ms: u64,
+}
+
+/// Full stat query of a server
+pub async fn query(host: &str, port: u16, timeout: Duration) -> Result<QueryStatus> {
+    tokio::time::timeout(timeout, query_inner(host, port))
+        .await
+        .map_err(|_| anyhow!("{}:{} did not answer the query within {:?}", host, port, timeout))?
+}
+
+async fn query_inner(host: &str, port: u16) -> Result<QueryStatus> {
+    let socket = UdpSocket::bind(if host.contains(':') { "[::]:0" } else { "0.0.0.0:0" }).await?;
+    socket
+        .connect((host, port))
+        .await
+        .with_context(|| format!("Failed to reach {}:{}", host, port))?;
+    let started = Instant::now();
+
+    socket.send(&request(TYPE_HANDSHAKE, &[])).await?;
+    let mut buf = vec![0u8; 65_535];
+    let len = socket.recv(&mut buf).await.context("No handshake response, is enable-query on?")?;
+    let token = parse_challenge(&buf[..len])?;
+    let latency = started.elapsed();
+
+    // Full stat is requested by padding the basic stat request with four bytes
+    let mut payload = token.to_be_bytes().to_vec();
+    payload.extend_from_slice(&[0, 0, 0, 0]);
+    socket.send(&request(TYPE_STAT, &payload)).await?;
+    let len = socket.recv(&mut buf).await.context("No stat response")?;
+    let mut status = parse_full_stat(&buf[..len])?;
+    status.latency_ms = latency.as_millis() as u64;
+    Ok(status)
+}
+
+fn request(kind: u8, payload: &[u8]) -> Vec<u8> {
+    let mut packet = MAGIC.to_vec();
+    packet.push(kind);
+    packet.extend_from_slice(&SESSION_ID.to_be_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+/// Strip the type and session id every response starts with
+fn response_body(packet: &[u8], kind: u8) -> Result<&[u8]> {
+    if packet.len() < 5 || packet[0] != kind {
+        bail!("Unexpected query response");
+    }
+    if packet[1..5] != SESSION_ID.to_be_bytes() {
+        bail!("Query response for another session");
+    }
+    Ok(&packet[5..])
+}
+
+/// The challenge token is sent back as a decimal string
+fn parse_challenge(packet: &[u8]) -> Result<i32> {
+    let body = response_body(packet, TYPE_HANDSHAKE)?;
+    let text = body.split(|b| *b == 0).next().unwrap_or_default();
+    std::str::from_utf8(text)?
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Invalid challenge token"))
+}
+
+fn parse_full_stat(packet: &[u8]) -> Result<QueryStatus> {
+    let body = response_body(packet, TYPE_STAT)?;
+    // 11 bytes of padding ("splitnum\0\x80\0") precede the key/value section
+    let body = body.get(11..).ok_or_else(|| anyhow!("Truncated stat response"))?;
+    let split = body
+        .windows(PLAYER_SECTION.len())
+        .position(|window| window == PLAYER_SECTION)
+        .ok_or_else(|| anyhow!("Stat response has no player section"))?;
+
+    let mut fields: HashMap<String, String> = HashMap::new();
+    let mut strings = body[..split].split(|b| *b == 0).map(|s| String::from_utf8_lossy(s).into_owned());
+    while let (Some(key), Some(value)) = (strings.next(), strings.next()) {
+        if key.is_empty() {
+            break;
+        }
+        fields.insert(key, value);
+    }
+
+    let players = body[split + PLAYER_SECTION.len()..]
+        .split(|b| *b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect();
+
+    let field = |key: &str| fields.get(key).cloned().unwrap_or_default();
+    let number = |key: &str| fields.get(key).and_then(|v| v.parse().ok()).unwrap_or(0);
+    Ok(QueryStatus {
+        motd: field("hostname"),
+        game_type: field("gametype"),
+        version: field("version"),
+        plugins: field("plugins"),
+        map: field("map"),
+        online_players: number("numplayers"),
+        max_players: number("maxplayers"),
+        players,
+        latency_ms: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_stat(players: &[&str]) -> Vec<u8> {
+        let mut packet = vec![TYPE_STAT];
+        packet.extend_from_slice(&SESSION_ID.to_be_bytes());
+        packet.extend_from_slice(b"splitnum\x00\x80\x00");
+        for (key, value) in [
+            ("hostname", "A Minecraft Server"),
+            ("gametype", "SMP"),
+            ("version", "1.21.1"),
+            ("plugins", ""),
+            ("map", "world"),
+            ("numplayers", "2"),
+            ("maxplayers", "20"),
+        ] {
+            packet.extend_from_slice(key.as_bytes());
+            packet.push(0);
+            packet.extend_from_slice(value.as_bytes());
+            packet.push(0);
+        }
+        packet.push(0);
+        packet.extend_from_slice(PLAYER_SECTION);
+        for player in players {
+            packet.extend_from_slice(player.as_bytes());
+            packet.push(0);
+        }
+        packet.push(0);
+        packet
+    }
+
+    #[test]
+    fn test_parse_full_stat() {
+        let status = parse_full_stat(&full_stat(&["Steve", "Alex"])).unwrap();
+        assert_eq!(status.motd, "A Minecraft Server");
+        assert_eq!(status.version, "1.21.1");
+        assert_eq!(status.map, "world");
+        assert_eq!((status.online_players, status.max_players), (2, 20));
+        assert_eq!(status.players, vec!["Steve", "Alex"]);
+    }
+
+    #[test]
+    fn test_parse_challenge() {
+        let mut packet = vec![TYPE_HANDSHAKE];
+        packet.extend_from_slice(&SESSION_ID.to_be_bytes());
+        packet.extend_from_slice(b"9513307\x00");
+        assert_eq!(parse_challenge(&packet).unwrap(), 9513307);
+        packet[1] = 0xFF;
+        assert!(parse_challenge(&packet).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_query_against_fake_server() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = server.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let (_, peer) = server.recv_from(&mut buf).await.unwrap();
+            let mut challenge = vec![TYPE_HANDSHAKE];
+            challenge.extend_from_slice(&SESSION_ID.to_be_bytes());
+            challenge.extend_from_slice(b"42\x00");
+            server.send_to(&challenge, peer).await.unwrap();
+            let (len, peer) = server.recv_from(&mut buf).await.unwrap();
+            // magic, type, session, token, padding
+            assert_eq!(len, 15);
+            assert_eq!(&buf[7..11], &42i32.to_be_bytes());
+            server.send_to(&full_stat(&["Steve"]), peer).await.unwrap();
+        });
+
+        let status = query("127.0.0.1", port, Duration::from_secs(3)).await.unwrap();
+        assert_eq!(status.players, vec!["Steve"]);
+    }
+}
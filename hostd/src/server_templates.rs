@@ -0,0 +1,433 @@
+//! Server templates and cloning. A template is a reusable starting point for
+//! new servers: loader and version, memory and JVM arguments, the
+//! server.properties a server should start with, and a set of mod jars kept in
+//! the template's own directory. Templates are saved from an existing server
+//! or created directly, and applied when the creation wizard names one.
+//! Cloning copies a whole server, optionally with its world, under new ports.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::config_revisions::{self, ConfigTarget};
+use crate::database::{DatabaseManager, ServerConfig, ServerTemplate};
+use crate::server_import::parse_properties;
+
+const MODS_DIR: &str = "mods";
+/// Properties that identify one server instance and never belong in a template
+const INSTANCE_PROPERTIES: &[&str] = &["server-port", "server-ip", "query.port", "rcon.port", "rcon.password"];
+/// Not copied by a clone: runtime leftovers and Guardian's own backups
+const CLONE_SKIP: &[&str] = &["logs", "crash-reports", "backups", "session.lock"];
+
+#[derive(Debug, Deserialize)]
+pub struct NewTemplate {
+    pub name: String,
+    pub description: Option<String>,
+    pub loader: String,
+    pub loader_version: String,
+    pub minecraft_version: String,
+    pub memory: Option<u32>,
+    pub jvm_args: Option<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateUpdate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub loader: Option<String>,
+    pub loader_version: Option<String>,
+    pub minecraft_version: Option<String>,
+    pub memory: Option<u32>,
+    pub jvm_args: Option<String>,
+    pub properties: Option<BTreeMap<String, String>>,
+    /// Mods to keep; jars left out are deleted from the template
+    pub mods: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveTemplateRequest {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloneRequest {
+    pub name: String,
+    /// Copy the world too; the source must be stopped for that
+    #[serde(default)]
+    pub include_world: bool,
+    /// Defaults to the next free port after the source's
+    pub port: Option<u16>,
+    /// Defaults to a new directory beside the other servers
+    pub directory: Option<String>,
+}
+
+pub struct TemplateManager {
+    database: Arc<DatabaseManager>,
+    templates_dir: PathBuf,
+    servers_dir: PathBuf,
+}
+
+impl TemplateManager {
+    pub fn new(database: Arc<DatabaseManager>, templates_dir: PathBuf, servers_dir: PathBuf) -> Self {
+        Self { database, templates_dir, servers_dir }
+    }
+
+    fn mods_dir(&self, template_id: &str) -> PathBuf {
+        self.templates_dir.join(template_id).join(MODS_DIR)
+    }
+
+    pub async fn list(&self) -> Result<Vec<ServerTemplate>> {
+        self.database.get_server_templates().await
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<ServerTemplate>> {
+        self.database.get_server_template(id).await
+    }
+
+    pub async fn create(&self, request: NewTemplate) -> Result<ServerTemplate> {
+        let now = Utc::now();
+        let memory = request.memory.unwrap_or(4096);
+        let template = ServerTemplate {
+            id: Uuid::new_v4().to_string(),
+            name: request.name.trim().to_string(),
+            description: request.description.filter(|d| !d.trim().is_empty()),
+            loader: request.loader,
+            loader_version: request.loader_version,
+            minecraft_version: request.minecraft_version,
+            memory,
+            jvm_args: request.jvm_args.unwrap_or_else(|| format!("-Xmx{}M -Xms{}M", memory, memory / 2)),
+            properties: template_properties(request.properties),
+            mods: Vec::new(),
+            source_server_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+        validate(&template)?;
+        self.database.create_server_template(&template).await?;
+        Ok(template)
+    }
+
+    pub async fn update(&self, id: &str, update: TemplateUpdate) -> Result<Option<ServerTemplate>> {
+        let Some(mut template) = self.database.get_server_template(id).await? else {
+            return Ok(None);
+        };
+        if let Some(name) = update.name {
+            template.name = name.trim().to_string();
+        }
+        if let Some(description) = update.description {
+            template.description = Some(description).filter(|d| !d.trim().is_empty());
+        }
+        if let Some(loader) = update.loader {
+            template.loader = loader;
+        }
+        if let Some(loader_version) = update.loader_version {
+            template.loader_version = loader_version;
+        }
+        if let Some(minecraft_version) = update.minecraft_version {
+            template.minecraft_version = minecraft_version;
+        }
+        if let Some(memory) = update.memory {
+            template.memory = memory;
+        }
+        if let Some(jvm_args) = update.jvm_args {
+            template.jvm_args = jvm_args;
+        }
+        if let Some(properties) = update.properties {
+            template.properties = template_properties(properties);
+        }
+        let removed: Vec<String> = match update.mods {
+            Some(keep) => {
+                if let Some(unknown) = keep.iter().find(|name| !template.mods.contains(name)) {
+                    bail!("Template has no mod '{}'; mods can only be added by saving a server as a template", unknown);
+                }
+                let removed = template.mods.iter().filter(|name| !keep.contains(name)).cloned().collect();
+                template.mods = keep;
+                removed
+            }
+            None => Vec::new(),
+        };
+        validate(&template)?;
+        template.updated_at = Utc::now();
+        self.database.update_server_template(&template).await?;
+        for name in removed {
+            let _ = tokio::fs::remove_file(self.mods_dir(id).join(name)).await;
+        }
+        Ok(Some(template))
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        if self.database.get_server_template(id).await?.is_none() {
+            return Ok(false);
+        }
+        self.database.delete_server_template(id).await?;
+        let dir = self.templates_dir.join(id);
+        if dir.exists() {
+            tokio::fs::remove_dir_all(&dir).await?;
+        }
+        Ok(true)
+    }
+
+    /// Save a server's loader, JVM settings, properties and mods as a template
+    pub async fn save_from_server(&self, server: &ServerConfig, request: SaveTemplateRequest) -> Result<ServerTemplate> {
+        let properties = config_revisions::read(server, &ConfigTarget::ServerProperties)
+            .await?
+            .map(|content| parse_properties(&content).into_iter().collect())
+            .unwrap_or_default();
+        let now = Utc::now();
+        let mut template = ServerTemplate {
+            id: Uuid::new_v4().to_string(),
+            name: request.name.trim().to_string(),
+            description: request.description.filter(|d| !d.trim().is_empty()),
+            loader: server.loader.clone(),
+            loader_version: server.loader_version.clone(),
+            minecraft_version: server.minecraft_version.clone(),
+            memory: server.memory,
+            jvm_args: server.jvm_args.clone(),
+            properties: template_properties(properties),
+            mods: Vec::new(),
+            source_server_id: Some(server.id.clone()),
+            created_at: now,
+            updated_at: now,
+        };
+        validate(&template)?;
+
+        let source = config_revisions::server_dir(server).join(MODS_DIR);
+        let target = self.mods_dir(&template.id);
+        template.mods = tokio::task::spawn_blocking(move || copy_jars(&source, &target)).await??;
+        if let Err(e) = self.database.create_server_template(&template).await {
+            let _ = tokio::fs::remove_dir_all(self.templates_dir.join(&template.id)).await;
+            return Err(e);
+        }
+        info!("Saved server {} as template {} with {} mods", server.id, template.id, template.mods.len());
+        Ok(template)
+    }
+
+    /// Put a template's mods and properties into a newly created server
+    pub async fn apply(&self, template: &ServerTemplate, server: &ServerConfig) -> Result<()> {
+        let source = self.mods_dir(&template.id);
+        let target = config_revisions::server_dir(server).join(MODS_DIR);
+        tokio::task::spawn_blocking(move || copy_jars(&source, &target)).await??;
+
+        let target = ConfigTarget::ServerProperties;
+        let current = config_revisions::read(server, &target).await?.unwrap_or_default();
+        let content = merge_properties(&current, &template.properties);
+        let message = Some(format!("Applied template {}", template.name));
+        config_revisions::apply(&self.database, server, &target, &content, None, message).await?;
+        Ok(())
+    }
+
+    /// Copy a server's directory and configuration into a new server under new
+    /// ports. The returned configuration is not stored yet.
+    pub async fn clone_server(&self, source: &ServerConfig, request: CloneRequest) -> Result<ServerConfig> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            bail!("Server name is required");
+        }
+        let servers = self.database.get_all_servers().await?;
+        let mut used: HashSet<u16> = servers.iter().flat_map(|s| [s.port, s.rcon_port, s.query_port]).collect();
+        let port = match request.port {
+            Some(port) if used.contains(&port) => bail!("Port {} is already used by another server", port),
+            Some(port) => port,
+            None => next_free_port(&used, source.port)?,
+        };
+        used.insert(port);
+        let rcon_port = next_free_port(&used, source.rcon_port)?;
+
+        let id = Uuid::new_v4().to_string();
+        let directory = match request.directory.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+            Some(directory) => PathBuf::from(directory),
+            None => self.servers_dir.join(&id),
+        };
+        if directory.exists() && std::fs::read_dir(&directory)?.next().is_some() {
+            bail!("{} already exists and is not empty", directory.display());
+        }
+
+        let from = config_revisions::server_dir(source);
+        let mut skip: Vec<String> = CLONE_SKIP.iter().map(|name| name.to_string()).collect();
+        if !request.include_world {
+            skip.extend([
+                source.world_name.clone(),
+                format!("{}_nether", source.world_name),
+                format!("{}_the_end", source.world_name),
+            ]);
+        }
+        {
+            let to = directory.clone();
+            tokio::task::spawn_blocking(move || copy_tree(&from, &to, &skip))
+                .await?
+                .context("Failed to copy the server directory")?;
+        }
+
+        let now = Utc::now();
+        let clone = ServerConfig {
+            id,
+            name: name.to_string(),
+            port,
+            rcon_port,
+            query_port: port,
+            rcon_password: Uuid::new_v4().simple().to_string(),
+            server_directory: directory.to_string_lossy().to_string(),
+            auto_start: false,
+            created_at: now,
+            updated_at: now,
+            ..source.clone()
+        };
+
+        // The copied server.properties still carries the source's ports
+        let properties = directory.join(config_revisions::SERVER_PROPERTIES);
+        if let Ok(current) = tokio::fs::read_to_string(&properties).await {
+            let instance = BTreeMap::from([
+                ("server-port".to_string(), clone.port.to_string()),
+                ("query.port".to_string(), clone.query_port.to_string()),
+                ("rcon.port".to_string(), clone.rcon_port.to_string()),
+                ("rcon.password".to_string(), clone.rcon_password.clone()),
+            ]);
+            tokio::fs::write(&properties, merge_properties(&current, &instance)).await?;
+        }
+        info!("Cloned server {} into {} at {}", source.id, clone.id, directory.display());
+        Ok(clone)
+    }
+}
+
+fn validate(template: &ServerTemplate) -> Result<()> {
+    if template.name.is_empty() {
+        bail!("Template name is required");
+    }
+    if template.loader.trim().is_empty() || template.minecraft_version.trim().is_empty() {
+        bail!("Template loader and Minecraft version are required");
+    }
+    if template.memory < 512 {
+        bail!("Template memory must be at least 512 MB");
+    }
+    Ok(())
+}
+
+/// Template properties without the ones that identify a single server
+fn template_properties(properties: BTreeMap<String, String>) -> BTreeMap<String, String> {
+    properties.into_iter().filter(|(key, _)| !INSTANCE_PROPERTIES.contains(&key.as_str())).collect()
+}
+
+/// Set `values` in a properties file, keeping its other lines and comments
+pub(crate) fn merge_properties(current: &str, values: &BTreeMap<String, String>) -> String {
+    let mut remaining = values.clone();
+    let mut lines: Vec<String> = current
+        .lines()
+        .map(|line| match line.split_once('=') {
+            Some((key, _)) if !line.trim_start().starts_with('#') => match remaining.remove(key.trim()) {
+                Some(value) => format!("{}={}", key.trim(), value),
+                None => line.to_string(),
+            },
+            _ => line.to_string(),
+        })
+        .collect();
+    lines.extend(remaining.into_iter().map(|(key, value)| format!("{}={}", key, value)));
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+pub(crate) fn next_free_port(used: &HashSet<u16>, after: u16) -> Result<u16> {
+    (after.saturating_add(1)..=u16::MAX)
+        .find(|port| !used.contains(port))
+        .ok_or_else(|| anyhow!("No free port after {}", after))
+}
+
+/// Copy the `.jar` files of `from` into `to`, returning their names
+fn copy_jars(from: &Path, to: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    let Ok(entries) = std::fs::read_dir(from) else {
+        return Ok(names);
+    };
+    std::fs::create_dir_all(to)?;
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_file() && name.to_ascii_lowercase().ends_with(".jar") {
+            std::fs::copy(entry.path(), to.join(&name))?;
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Recursive copy, leaving out top-level entries named in `skip`
+pub(crate) fn copy_tree(from: &Path, to: &Path, skip: &[String]) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        if skip.iter().any(|name| entry.file_name() == name.as_str()) {
+            continue;
+        }
+        let kind = entry.file_type()?;
+        if kind.is_dir() {
+            copy_tree(&entry.path(), &to.join(entry.file_name()), &[])?;
+        } else if kind.is_file() {
+            std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_properties() {
+        let current = "#Minecraft server properties\nserver-port=25565\nmotd=Old\n";
+        let values = BTreeMap::from([
+            ("motd".to_string(), "New".to_string()),
+            ("pvp".to_string(), "false".to_string()),
+        ]);
+        assert_eq!(
+            merge_properties(current, &values),
+            "#Minecraft server properties\nserver-port=25565\nmotd=New\npvp=false\n"
+        );
+    }
+
+    #[test]
+    fn test_template_properties_drop_instance_keys() {
+        let properties = BTreeMap::from([
+            ("server-port".to_string(), "25565".to_string()),
+            ("rcon.password".to_string(), "secret".to_string()),
+            ("difficulty".to_string(), "hard".to_string()),
+        ]);
+        let kept = template_properties(properties);
+        assert_eq!(kept.keys().collect::<Vec<_>>(), vec!["difficulty"]);
+    }
+
+    #[test]
+    fn test_copy_tree_skips_world() {
+        let from = tempfile::tempdir().unwrap();
+        let to = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(from.path().join("world/region")).unwrap();
+        std::fs::write(from.path().join("world/level.dat"), b"level").unwrap();
+        std::fs::create_dir_all(from.path().join("mods")).unwrap();
+        std::fs::write(from.path().join("mods/a.jar"), b"jar").unwrap();
+        std::fs::write(from.path().join("server.properties"), b"motd=x\n").unwrap();
+
+        let target = to.path().join("clone");
+        copy_tree(from.path(), &target, &["world".to_string()]).unwrap();
+        assert!(target.join("mods/a.jar").is_file());
+        assert!(target.join("server.properties").is_file());
+        assert!(!target.join("world").exists());
+
+        let names = copy_jars(&from.path().join("mods"), &to.path().join("template")).unwrap();
+        assert_eq!(names, vec!["a.jar"]);
+    }
+
+    #[test]
+    fn test_next_free_port() {
+        let used = HashSet::from([25565, 25566, 25575]);
+        assert_eq!(next_free_port(&used, 25565).unwrap(), 25567);
+    }
+}
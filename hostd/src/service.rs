@@ -0,0 +1,246 @@
+//! Running hostd as a system service
+//!
+//! `hostd install-service` registers the current executable to start on boot,
+//! with the current directory as its working directory, so servers keep
+//! running when the desktop app is closed. On Windows it is a service under the
+//! Service Control Manager; on Linux a systemd unit.
+
+use anyhow::Result;
+#[cfg(any(target_os = "linux", windows))]
+use anyhow::Context;
+use once_cell::sync::Lazy;
+use tokio::sync::Notify;
+
+/// Service name on Windows, and the systemd unit name on Linux
+pub const SERVICE_NAME: &str = "guardian-hostd";
+
+/// Signalled when the service manager asks hostd to stop
+pub static STOP_REQUESTED: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// systemd unit that runs `exe` from `working_dir`
+///
+/// `KillMode=process` leaves the Minecraft servers to hostd's own shutdown,
+/// and keeps them running to be re-attached if hostd crashes and is restarted.
+/// `Delegate=yes` lets hostd put servers in cgroups of their own for memory limits.
+pub fn systemd_unit(exe: &str, working_dir: &str, user: Option<&str>) -> String {
+    let user = user.map(|user| format!("User={}\n", user)).unwrap_or_default();
+    format!(
+        r#"[Unit]
+Description=Guardian Server Manager host daemon
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=simple
+{user}WorkingDirectory={working_dir}
+ExecStart={exe}
+Restart=on-failure
+RestartSec=5
+KillMode=process
+TimeoutStopSec=90
+Delegate=yes
+
+[Install]
+WantedBy=multi-user.target
+"#
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn unit_path() -> std::path::PathBuf {
+    std::path::Path::new("/etc/systemd/system").join(format!("{}.service", SERVICE_NAME))
+}
+
+#[cfg(target_os = "linux")]
+fn systemctl(args: &[&str]) -> Result<()> {
+    let status = std::process::Command::new("systemctl")
+        .args(args)
+        .status()
+        .context("Failed to run systemctl")?;
+    if !status.success() {
+        anyhow::bail!("systemctl {} failed with {}", args.join(" "), status);
+    }
+    Ok(())
+}
+
+/// Write the systemd unit, then enable and start it
+#[cfg(target_os = "linux")]
+pub fn install() -> Result<()> {
+    let exe = std::env::current_exe().context("Failed to locate the hostd executable")?;
+    let working_dir = std::env::current_dir().context("Failed to read the current directory")?;
+    // Under sudo, run as the user who asked rather than as root
+    let user = std::env::var("SUDO_USER").ok().filter(|user| user != "root");
+    let unit = systemd_unit(&exe.to_string_lossy(), &working_dir.to_string_lossy(), user.as_deref());
+
+    std::fs::write(unit_path(), unit)
+        .with_context(|| format!("Failed to write {} (run as root)", unit_path().display()))?;
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", "--now", SERVICE_NAME])?;
+    println!("Installed and started {} from {}", SERVICE_NAME, working_dir.display());
+    Ok(())
+}
+
+/// Stop and disable the systemd unit and remove it
+#[cfg(target_os = "linux")]
+pub fn uninstall() -> Result<()> {
+    if !unit_path().exists() {
+        anyhow::bail!("{} is not installed", SERVICE_NAME);
+    }
+    systemctl(&["disable", "--now", SERVICE_NAME])?;
+    std::fs::remove_file(unit_path())
+        .with_context(|| format!("Failed to remove {}", unit_path().display()))?;
+    systemctl(&["daemon-reload"])?;
+    println!("Uninstalled {}", SERVICE_NAME);
+    Ok(())
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::OsString;
+    use std::path::Path;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    use anyhow::{Context, Result};
+    use windows_service::service::{
+        ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+        ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+    use windows_service::{define_windows_service, service_dispatcher};
+
+    use super::{SERVICE_NAME, STOP_REQUESTED};
+
+    static RUN: OnceLock<Box<dyn Fn() -> Result<()> + Send + Sync>> = OnceLock::new();
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    /// Register with the Service Control Manager, which then starts `hostd run-service`
+    pub fn install() -> Result<()> {
+        let exe = std::env::current_exe().context("Failed to locate the hostd executable")?;
+        let working_dir = std::env::current_dir().context("Failed to read the current directory")?;
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE)
+            .context("Failed to open the service manager (run as administrator)")?;
+
+        let info = ServiceInfo {
+            name: OsString::from(SERVICE_NAME),
+            display_name: OsString::from("Guardian Server Manager"),
+            service_type: ServiceType::OWN_PROCESS,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path: exe,
+            launch_arguments: vec![
+                OsString::from("run-service"),
+                OsString::from("--working-dir"),
+                working_dir.clone().into_os_string(),
+            ],
+            dependencies: vec![],
+            account_name: None,
+            account_password: None,
+        };
+        let service = manager.create_service(&info, ServiceAccess::CHANGE_CONFIG | ServiceAccess::START)
+            .context("Failed to create the service")?;
+        service.set_description("Runs Minecraft servers managed by Guardian")?;
+        service.start::<OsString>(&[]).context("Failed to start the service")?;
+        println!("Installed and started {} from {}", SERVICE_NAME, working_dir.display());
+        Ok(())
+    }
+
+    /// Stop the service and delete it
+    pub fn uninstall() -> Result<()> {
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+            .context("Failed to open the service manager (run as administrator)")?;
+        let service = manager.open_service(SERVICE_NAME, ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE)
+            .with_context(|| format!("{} is not installed", SERVICE_NAME))?;
+
+        if service.query_status()?.current_state != ServiceState::Stopped {
+            service.stop()?;
+            // hostd stops its servers first, which takes up to a minute
+            for _ in 0..90 {
+                if service.query_status()?.current_state == ServiceState::Stopped {
+                    break;
+                }
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        }
+        service.delete().context("Failed to delete the service")?;
+        println!("Uninstalled {}", SERVICE_NAME);
+        Ok(())
+    }
+
+    /// Entry point when started by the Service Control Manager; blocks until `run` returns
+    pub fn run(working_dir: &Path, run: impl Fn() -> Result<()> + Send + Sync + 'static) -> Result<()> {
+        // Services start in the system directory; the database and data/ are relative
+        std::env::set_current_dir(working_dir)
+            .with_context(|| format!("Failed to enter {}", working_dir.display()))?;
+        let _ = RUN.set(Box::new(run));
+        service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+            .context("Failed to connect to the service manager")?;
+        Ok(())
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        let handler = |control: ServiceControl| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                STOP_REQUESTED.notify_one();
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        };
+        let Ok(status_handle) = service_control_handler::register(SERVICE_NAME, handler) else {
+            return;
+        };
+
+        let status = |state: ServiceState, exit_code: u32, wait_hint: Duration| ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: if state == ServiceState::Running {
+                ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+            } else {
+                ServiceControlAccept::empty()
+            },
+            exit_code: ServiceExitCode::Win32(exit_code),
+            checkpoint: 0,
+            wait_hint,
+            process_id: None,
+        };
+        let _ = status_handle.set_service_status(status(ServiceState::Running, 0, Duration::ZERO));
+
+        let result = RUN.get().map(|run| run()).unwrap_or(Ok(()));
+        if let Err(e) = &result {
+            tracing::error!("hostd service stopped with an error: {}", e);
+        }
+        let _ = status_handle.set_service_status(status(ServiceState::Stopped, u32::from(result.is_err()), Duration::ZERO));
+    }
+}
+
+#[cfg(windows)]
+pub use windows::{install, run, uninstall};
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn install() -> Result<()> {
+    anyhow::bail!("Installing hostd as a service is supported on Windows and Linux")
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn uninstall() -> Result<()> {
+    anyhow::bail!("Installing hostd as a service is supported on Windows and Linux")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_systemd_unit_leaves_servers_to_hostd() {
+        let unit = systemd_unit("/opt/guardian/hostd", "/var/lib/guardian", Some("guardian"));
+        assert!(unit.contains("ExecStart=/opt/guardian/hostd\n"));
+        assert!(unit.contains("WorkingDirectory=/var/lib/guardian\n"));
+        assert!(unit.contains("User=guardian\n"));
+        assert!(unit.contains("KillMode=process\n"));
+        assert!(unit.contains("Delegate=yes\n"));
+        assert!(!systemd_unit("/opt/guardian/hostd", "/var/lib/guardian", None).contains("User="));
+    }
+}
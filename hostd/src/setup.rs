@@ -0,0 +1,180 @@
+//! First-run setup
+//!
+//! `GET /api/setup` reports what a fresh install is missing: a working Java,
+//! the data directory and the mod platform API keys. The wizard then sends
+//! all its answers in one `POST /api/setup`, which can download a Temurin JRE
+//! with `java_runtimes`, and marks setup as complete in `settings`.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tracing::info;
+
+use crate::database::{DatabaseManager, Settings};
+
+/// Java used when the wizard asks for a JRE without naming a version
+const DEFAULT_JAVA_MAJOR: u32 = 21;
+const JAVA_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JavaCheck {
+    pub path: String,
+    pub found: bool,
+    pub version: Option<String>,
+    pub major: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SetupStatus {
+    pub completed: bool,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub java: JavaCheck,
+    pub data_dir: String,
+    pub data_dir_exists: bool,
+    pub has_curseforge_key: bool,
+    pub has_modrinth_token: bool,
+    pub accept_eula_by_default: bool,
+    /// What still needs an answer: "java", "data_dir" and "api_keys".
+    /// API keys are optional; setup can be completed without them.
+    pub missing: Vec<String>,
+}
+
+/// Everything the wizard collects; fields left out keep their current value
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SetupRequest {
+    pub java_path: Option<String>,
+    /// Download a Temurin JRE into the data directory and use it
+    #[serde(default)]
+    pub download_java: bool,
+    /// Java version to download, 21 unless given
+    pub java_version: Option<u32>,
+    pub data_dir: Option<String>,
+    pub cf_api_key: Option<String>,
+    pub modrinth_token: Option<String>,
+    pub default_ram_mb: Option<u32>,
+    pub accept_eula_by_default: Option<bool>,
+    pub telemetry_opt_in: Option<bool>,
+}
+
+/// Saved settings, or the defaults on a fresh database
+async fn current_settings(database: &DatabaseManager) -> Result<Settings> {
+    Ok(database.get_settings().await?.unwrap_or_default())
+}
+
+/// Run `java -version` and read the version it reports
+pub async fn check_java(java_path: &str) -> JavaCheck {
+    let output = tokio::time::timeout(
+        JAVA_CHECK_TIMEOUT,
+        tokio::process::Command::new(java_path).arg("-version").output(),
+    )
+    .await;
+    let version = match output {
+        Ok(Ok(output)) if output.status.success() => {
+            // java -version prints to stderr
+            crate::api::extract_java_version(&String::from_utf8_lossy(&output.stderr))
+        }
+        _ => None,
+    };
+    JavaCheck {
+        path: java_path.to_string(),
+        found: version.is_some(),
+        major: version.as_ref().map(|(major, _)| *major),
+        version: version.map(|(_, version)| version),
+    }
+}
+
+fn missing(java: &JavaCheck, data_dir_exists: bool, settings: &Settings) -> Vec<String> {
+    let mut missing = Vec::new();
+    if !java.found {
+        missing.push("java".to_string());
+    }
+    if !data_dir_exists {
+        missing.push("data_dir".to_string());
+    }
+    if settings.cf_api_key.is_none() && settings.modrinth_token.is_none() {
+        missing.push("api_keys".to_string());
+    }
+    missing
+}
+
+pub async fn status(database: &DatabaseManager) -> Result<SetupStatus> {
+    let settings = current_settings(database).await?;
+    let java = check_java(&settings.java_path).await;
+    let data_dir_exists = Path::new(&settings.data_dir).is_dir();
+    Ok(SetupStatus {
+        completed: settings.setup_completed_at.is_some(),
+        completed_at: settings.setup_completed_at,
+        missing: missing(&java, data_dir_exists, &settings),
+        java,
+        data_dir: settings.data_dir,
+        data_dir_exists,
+        has_curseforge_key: settings.cf_api_key.is_some(),
+        has_modrinth_token: settings.modrinth_token.is_some(),
+        accept_eula_by_default: settings.accept_eula_by_default,
+    })
+}
+
+/// Apply the wizard's answers and mark setup as complete.
+/// Fails without saving anything if Java still doesn't run afterwards.
+pub async fn apply(database: &DatabaseManager, request: SetupRequest) -> Result<SetupStatus> {
+    let mut settings = current_settings(database).await?;
+
+    if let Some(data_dir) = request.data_dir.filter(|dir| !dir.trim().is_empty()) {
+        settings.data_dir = data_dir;
+    }
+    std::fs::create_dir_all(&settings.data_dir)
+        .with_context(|| format!("Failed to create data directory {}", settings.data_dir))?;
+
+    if request.download_java {
+        let major = request.java_version.unwrap_or(DEFAULT_JAVA_MAJOR);
+        let runtimes = Path::new(&settings.data_dir).join("runtimes");
+        settings.java_path = crate::java_runtimes::download_temurin(&runtimes, major).await?.to_string_lossy().to_string();
+    } else if let Some(java_path) = request.java_path.filter(|path| !path.trim().is_empty()) {
+        settings.java_path = java_path;
+    }
+    let java = check_java(&settings.java_path).await;
+    if !java.found {
+        bail!("Java at {} does not run; pick another path or download a JRE", settings.java_path);
+    }
+
+    if let Some(cf_api_key) = request.cf_api_key {
+        settings.cf_api_key = Some(cf_api_key).filter(|key| !key.is_empty());
+    }
+    if let Some(modrinth_token) = request.modrinth_token {
+        settings.modrinth_token = Some(modrinth_token).filter(|token| !token.is_empty());
+    }
+    if let Some(default_ram_mb) = request.default_ram_mb {
+        settings.default_ram_mb = default_ram_mb;
+    }
+    if let Some(accept_eula_by_default) = request.accept_eula_by_default {
+        settings.accept_eula_by_default = accept_eula_by_default;
+    }
+    if let Some(telemetry_opt_in) = request.telemetry_opt_in {
+        settings.telemetry_opt_in = telemetry_opt_in;
+    }
+
+    let now = Utc::now();
+    settings.setup_completed_at = Some(now);
+    settings.updated_at = now;
+    database.update_settings(&settings).await?;
+    info!("First-run setup completed with Java {} at {}", java.version.as_deref().unwrap_or("?"), settings.java_path);
+
+    status(database).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_lists_only_unanswered_steps() {
+        let java = JavaCheck { path: "java".to_string(), found: true, version: Some("21.0.4".to_string()), major: Some(21) };
+        let mut settings = Settings::default();
+        assert_eq!(missing(&java, false, &settings), vec!["data_dir", "api_keys"]);
+
+        settings.modrinth_token = Some("token".to_string());
+        assert!(missing(&java, true, &settings).is_empty());
+    }
+}
@@ -0,0 +1,374 @@
+//! Sharding topology
+//!
+//! A shard group is one world split across several managed servers. Each
+//! shard of a group owns rectangles of region files per dimension and is run
+//! by the servers assigned to it, each hosting some of the shard's
+//! dimensions. The topology is kept in the database; player counts and
+//! shard health are read from the running servers whenever it is asked for.
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::core::process_manager::{ProcessManager, ServerState};
+use crate::database::{DatabaseManager, Shard, ShardAssignment, ShardGroup, ShardRegion};
+use crate::core::error_handler::NotFound;
+
+/// Blocks along one side of a region file
+const REGION_BLOCKS: i32 = 512;
+/// Dimension a server hosts when its assignment names none
+pub const DEFAULT_DIMENSION: &str = "minecraft:overworld";
+
+/// A shard with its servers, regions and current load
+#[derive(Debug, Clone, Serialize)]
+pub struct ShardStatus {
+    #[serde(flatten)]
+    pub shard: Shard,
+    /// `healthy` with every server running, `degraded` with some, `offline` with none
+    pub status: &'static str,
+    /// Dimensions the shard's servers host
+    pub dimensions: Vec<String>,
+    pub server_ids: Vec<String>,
+    pub regions: Vec<ShardRegion>,
+    pub players_online: u32,
+    pub full: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Topology {
+    pub groups: Vec<ShardGroup>,
+    pub shards: Vec<ShardStatus>,
+}
+
+/// A server's assignment with its current state
+#[derive(Debug, Clone, Serialize)]
+pub struct AssignmentStatus {
+    #[serde(flatten)]
+    pub assignment: ShardAssignment,
+    pub player_count: u32,
+    /// `active` while the server runs, `error` after it crashed, `inactive` otherwise
+    pub status: &'static str,
+}
+
+/// The shard owning a block, and the servers hosting its dimension
+#[derive(Debug, Clone, Serialize)]
+pub struct RegionOwner {
+    pub shard: Shard,
+    pub region: ShardRegion,
+    pub server_ids: Vec<String>,
+}
+
+/// Whether two regions of the same dimension share a region file
+fn overlaps(a: &ShardRegion, b: &ShardRegion) -> bool {
+    a.dimension == b.dimension && a.min_x <= b.max_x && b.min_x <= a.max_x && a.min_z <= b.max_z && b.min_z <= a.max_z
+}
+
+fn contains(region: &ShardRegion, dimension: &str, region_x: i32, region_z: i32) -> bool {
+    region.dimension == dimension
+        && (region.min_x..=region.max_x).contains(&region_x)
+        && (region.min_z..=region.max_z).contains(&region_z)
+}
+
+fn shard_health(states: &[ServerState]) -> &'static str {
+    let running = states.iter().filter(|state| matches!(state, ServerState::Running)).count();
+    match running {
+        0 => "offline",
+        n if n == states.len() => "healthy",
+        _ => "degraded",
+    }
+}
+
+pub struct ShardingManager {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+}
+
+impl ShardingManager {
+    pub fn new(database: Arc<DatabaseManager>, process_manager: Arc<ProcessManager>) -> Self {
+        Self { database, process_manager }
+    }
+
+    /// Current state and player count of a server
+    async fn server_load(&self, server_id: &str) -> (ServerState, u32) {
+        let Ok(uuid) = Uuid::parse_str(server_id) else {
+            return (ServerState::Stopped, 0);
+        };
+        let state = self.process_manager.get_server_state(uuid).await;
+        let players = match state {
+            ServerState::Running => self.process_manager.get_process_info(uuid).await.map_or(0, |info| info.players_online),
+            _ => 0,
+        };
+        (state, players)
+    }
+
+    pub async fn topology(&self) -> Result<Topology> {
+        let groups = self.database.get_shard_groups().await?;
+        let assignments = self.database.get_shard_assignments().await?;
+        let regions = self.database.get_shard_regions().await?;
+
+        let mut shards = Vec::new();
+        for shard in self.database.get_shards().await? {
+            let mut states = Vec::new();
+            let mut players_online = 0;
+            let mut dimensions: Vec<String> = Vec::new();
+            let mut server_ids = Vec::new();
+            for assignment in assignments.iter().filter(|assignment| assignment.shard_id == shard.id) {
+                let (state, players) = self.server_load(&assignment.server_id).await;
+                states.push(state);
+                players_online += players;
+                server_ids.push(assignment.server_id.clone());
+                for dimension in &assignment.dimensions {
+                    if !dimensions.contains(dimension) {
+                        dimensions.push(dimension.clone());
+                    }
+                }
+            }
+            shards.push(ShardStatus {
+                status: shard_health(&states),
+                dimensions,
+                server_ids,
+                regions: regions.iter().filter(|region| region.shard_id == shard.id).cloned().collect(),
+                players_online,
+                full: players_online >= shard.max_players,
+                shard,
+            });
+        }
+        Ok(Topology { groups, shards })
+    }
+
+    pub async fn assignments(&self) -> Result<Vec<AssignmentStatus>> {
+        let mut statuses = Vec::new();
+        for assignment in self.database.get_shard_assignments().await? {
+            let (state, player_count) = self.server_load(&assignment.server_id).await;
+            let status = match state {
+                ServerState::Running => "active",
+                ServerState::Crashed => "error",
+                _ => "inactive",
+            };
+            statuses.push(AssignmentStatus { assignment, player_count, status });
+        }
+        Ok(statuses)
+    }
+
+    pub async fn create_group(&self, name: &str, description: Option<String>) -> Result<ShardGroup> {
+        if name.trim().is_empty() {
+            bail!("A shard group needs a name");
+        }
+        let group = ShardGroup {
+            id: Uuid::new_v4().to_string(),
+            name: name.trim().to_string(),
+            description,
+            created_at: Utc::now(),
+        };
+        self.database.create_shard_group(&group).await?;
+        info!("Created shard group {}", group.name);
+        Ok(group)
+    }
+
+    pub async fn delete_group(&self, id: &str) -> Result<()> {
+        if !self.database.delete_shard_group(id).await? {
+            return Err(NotFound::new("Shard group").into());
+        }
+        Ok(())
+    }
+
+    async fn shard(&self, id: &str) -> Result<Shard> {
+        self.database
+            .get_shards()
+            .await?
+            .into_iter()
+            .find(|shard| shard.id == id)
+            .ok_or_else(|| NotFound::new("Shard").into())
+    }
+
+    pub async fn create_shard(&self, group_id: &str, name: &str, max_players: u32) -> Result<Shard> {
+        if !self.database.get_shard_groups().await?.iter().any(|group| group.id == group_id) {
+            return Err(NotFound::new("Shard group").into());
+        }
+        if name.trim().is_empty() || max_players == 0 {
+            bail!("A shard needs a name and room for at least one player");
+        }
+        let shard = Shard {
+            id: Uuid::new_v4().to_string(),
+            group_id: group_id.to_string(),
+            name: name.trim().to_string(),
+            max_players,
+            created_at: Utc::now(),
+        };
+        self.database.create_shard(&shard).await?;
+        Ok(shard)
+    }
+
+    pub async fn update_shard(&self, id: &str, name: Option<String>, max_players: Option<u32>) -> Result<Shard> {
+        let mut shard = self.shard(id).await?;
+        if let Some(name) = name.filter(|name| !name.trim().is_empty()) {
+            shard.name = name.trim().to_string();
+        }
+        if let Some(max_players) = max_players {
+            if max_players == 0 {
+                bail!("A shard needs room for at least one player");
+            }
+            shard.max_players = max_players;
+        }
+        self.database.update_shard(&shard).await?;
+        Ok(shard)
+    }
+
+    pub async fn delete_shard(&self, id: &str) -> Result<()> {
+        if !self.database.delete_shard(id).await? {
+            return Err(NotFound::new("Shard").into());
+        }
+        Ok(())
+    }
+
+    /// Assign a managed server to a shard, moving it off any other shard
+    pub async fn assign(&self, server_id: &str, shard_id: &str, dimensions: Vec<String>) -> Result<ShardAssignment> {
+        let server = self
+            .database
+            .get_server(server_id)
+            .await?
+            .ok_or_else(|| NotFound::new("Server"))?;
+        if !server.managed {
+            bail!("Only servers Guardian runs can be assigned to shards");
+        }
+        let shard = self.shard(shard_id).await?;
+
+        let mut dimensions: Vec<String> = dimensions.into_iter().map(|d| d.trim().to_string()).filter(|d| !d.is_empty()).collect();
+        dimensions.dedup();
+        if dimensions.is_empty() {
+            dimensions.push(DEFAULT_DIMENSION.to_string());
+        }
+        let assignment = ShardAssignment {
+            id: Uuid::new_v4().to_string(),
+            shard_id: shard.id.clone(),
+            server_id: server.id.clone(),
+            dimensions,
+            assigned_at: Utc::now(),
+        };
+        self.database.save_shard_assignment(&assignment).await?;
+        info!("Assigned {} to shard {}", server.name, shard.name);
+        Ok(assignment)
+    }
+
+    pub async fn unassign(&self, server_id: &str) -> Result<()> {
+        if !self.database.delete_shard_assignment(server_id).await? {
+            bail!("The server is not assigned to a shard");
+        }
+        Ok(())
+    }
+
+    /// Give a shard a rectangle of region files, which no other shard of its
+    /// group may own
+    pub async fn add_region(&self, shard_id: &str, dimension: &str, min_x: i32, min_z: i32, max_x: i32, max_z: i32) -> Result<ShardRegion> {
+        let shard = self.shard(shard_id).await?;
+        let region = ShardRegion {
+            id: Uuid::new_v4().to_string(),
+            shard_id: shard.id.clone(),
+            dimension: if dimension.trim().is_empty() { DEFAULT_DIMENSION.to_string() } else { dimension.trim().to_string() },
+            min_x: min_x.min(max_x),
+            min_z: min_z.min(max_z),
+            max_x: min_x.max(max_x),
+            max_z: min_z.max(max_z),
+        };
+
+        let group_shards: HashMap<String, String> = self
+            .database
+            .get_shards()
+            .await?
+            .into_iter()
+            .filter(|other| other.group_id == shard.group_id)
+            .map(|other| (other.id, other.name))
+            .collect();
+        for other in self.database.get_shard_regions().await? {
+            if let Some(owner) = group_shards.get(&other.shard_id) {
+                if overlaps(&region, &other) {
+                    bail!(
+                        "Regions {},{} to {},{} of {} belong to shard {} already",
+                        other.min_x, other.min_z, other.max_x, other.max_z, other.dimension, owner
+                    );
+                }
+            }
+        }
+        self.database.create_shard_region(&region).await?;
+        Ok(region)
+    }
+
+    pub async fn delete_region(&self, id: &str) -> Result<()> {
+        if !self.database.delete_shard_region(id).await? {
+            return Err(NotFound::new("Region").into());
+        }
+        Ok(())
+    }
+
+    /// The shard of a group that owns the block at `x`, `z` in `dimension`
+    pub async fn owner(&self, group_id: &str, dimension: &str, x: i32, z: i32) -> Result<Option<RegionOwner>> {
+        let shards: HashMap<String, Shard> = self
+            .database
+            .get_shards()
+            .await?
+            .into_iter()
+            .filter(|shard| shard.group_id == group_id)
+            .map(|shard| (shard.id.clone(), shard))
+            .collect();
+        let (region_x, region_z) = (x.div_euclid(REGION_BLOCKS), z.div_euclid(REGION_BLOCKS));
+        let Some(region) = self
+            .database
+            .get_shard_regions()
+            .await?
+            .into_iter()
+            .find(|region| shards.contains_key(&region.shard_id) && contains(region, dimension, region_x, region_z))
+        else {
+            return Ok(None);
+        };
+
+        let server_ids = self
+            .database
+            .get_shard_assignments()
+            .await?
+            .into_iter()
+            .filter(|assignment| assignment.shard_id == region.shard_id && assignment.dimensions.iter().any(|d| d == dimension))
+            .map(|assignment| assignment.server_id)
+            .collect();
+        Ok(Some(RegionOwner { shard: shards[&region.shard_id].clone(), region, server_ids }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(dimension: &str, min_x: i32, min_z: i32, max_x: i32, max_z: i32) -> ShardRegion {
+        ShardRegion {
+            id: "region".to_string(),
+            shard_id: "shard".to_string(),
+            dimension: dimension.to_string(),
+            min_x,
+            min_z,
+            max_x,
+            max_z,
+        }
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let a = region(DEFAULT_DIMENSION, -4, -4, -1, 3);
+        assert!(overlaps(&a, &region(DEFAULT_DIMENSION, -1, 3, 5, 5)));
+        assert!(!overlaps(&a, &region(DEFAULT_DIMENSION, 0, -4, 3, 3)));
+        assert!(!overlaps(&a, &region("minecraft:the_nether", -4, -4, -1, 3)));
+
+        // Block -1 is in region -1, block 512 in region 1
+        assert!(contains(&a, DEFAULT_DIMENSION, (-1i32).div_euclid(REGION_BLOCKS), 0));
+        assert!(!contains(&a, DEFAULT_DIMENSION, 512i32.div_euclid(REGION_BLOCKS), 0));
+    }
+
+    #[test]
+    fn test_shard_health() {
+        assert_eq!(shard_health(&[]), "offline");
+        assert_eq!(shard_health(&[ServerState::Running, ServerState::Running]), "healthy");
+        assert_eq!(shard_health(&[ServerState::Running, ServerState::Crashed]), "degraded");
+    }
+}
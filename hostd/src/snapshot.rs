@@ -8,6 +8,7 @@ use chrono::{DateTime, Utc};
 use tracing::{info, warn, error, debug};
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
+use crate::core::error_handler::NotFound;
 
 /// Snapshot manager for handling world backups and snapshots
 pub struct SnapshotManager {
@@ -225,7 +226,7 @@ impl SnapshotManager {
             snapshots_guard.iter()
                 .find(|s| s.id == snapshot_id)
                 .cloned()
-                .ok_or_else(|| anyhow::anyhow!("Snapshot not found: {}", snapshot_id))?
+                .ok_or_else(|| NotFound::with_id("Snapshot", snapshot_id))?
         };
         
         let world_dir = Path::new(&self.config.paths.world_dir);
@@ -262,7 +263,7 @@ impl SnapshotManager {
             let mut snapshots_guard = self.snapshots.write().await;
             let index = snapshots_guard.iter()
                 .position(|s| s.id == snapshot_id)
-                .ok_or_else(|| anyhow::anyhow!("Snapshot not found: {}", snapshot_id))?;
+                .ok_or_else(|| NotFound::with_id("Snapshot", snapshot_id))?;
             
             snapshots_guard.remove(index)
         };
@@ -71,7 +71,7 @@ impl IntegrationTestSuite {
             secret_storage: Arc::new(crate::security::secret_storage::SecretStorage::new("test_key".to_string(), std::path::PathBuf::from("test_secrets.db"))),
             rate_limiter: Arc::new(crate::security::rate_limiting::RateLimiter::new()),
             test_harness: Arc::new(crate::core::test_harness::TestHarness::new()),
-            sse_sender: None,
+            event_bus: crate::event_bus::EventBus::new(),
         };
         
         let app = create_api_router(app_state);
@@ -243,6 +243,7 @@ impl IntegrationTestSuite {
             jvm_args: "-Xmx4G -Xms2G".to_string(),
             server_jar: "server.jar".to_string(),
             rcon_password: "test_password".to_string(),
+            managed: true,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -0,0 +1,264 @@
+//! Real tick timings for running servers, read over RCON: spark's `tps` and
+//! `health` output when spark is installed, otherwise the loader's own command.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Command the timings were read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimingSource {
+    /// `spark tps`
+    Spark,
+    /// Vanilla `tick query` (1.20.3+), also used by Fabric and Quilt
+    TickQuery,
+    /// Paper/Spigot `tps`
+    Paper,
+    /// `forge tps`
+    Forge,
+    /// `neoforge tps`
+    NeoForge,
+}
+
+impl TimingSource {
+    pub fn command(&self) -> &'static str {
+        match self {
+            Self::Spark => "spark tps",
+            Self::TickQuery => "tick query",
+            Self::Paper => "tps",
+            Self::Forge => "forge tps",
+            Self::NeoForge => "neoforge tps",
+        }
+    }
+
+    /// Built-in timing command for a loader
+    pub fn for_loader(loader: &str) -> Self {
+        match loader.to_lowercase().as_str() {
+            "paper" | "purpur" | "spigot" | "bukkit" | "folia" => Self::Paper,
+            "forge" => Self::Forge,
+            "neoforge" => Self::NeoForge,
+            _ => Self::TickQuery,
+        }
+    }
+}
+
+/// Commands to read timings with, in order: spark first when it is installed
+pub fn sources(loader: &str, spark: bool) -> Vec<TimingSource> {
+    let loader_source = TimingSource::for_loader(loader);
+    if spark { vec![TimingSource::Spark, loader_source] } else { vec![loader_source] }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TickTimings {
+    pub source: TimingSource,
+    pub tps: f64,
+    /// Typical milliseconds per tick (median or mean, depending on the source)
+    pub mspt: Option<f64>,
+    pub tick_p95: Option<f64>,
+}
+
+impl TickTimings {
+    /// Milliseconds per tick, estimated from the TPS for sources that only
+    /// report that. A server keeping up gives `None` then: its ticks fit in
+    /// their 50 ms, but how much of it they take is unknown.
+    pub fn tick_ms(&self) -> Option<f64> {
+        self.mspt.or_else(|| (self.tps > 0.0 && self.tps < 19.5).then(|| 1000.0 / self.tps))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HeapUsage {
+    pub used_mb: u64,
+    pub max_mb: u64,
+}
+
+/// Whether spark is installed as a mod or plugin in `server_dir`
+pub fn has_spark(server_dir: &Path) -> bool {
+    ["mods", "plugins"].iter().any(|dir| {
+        std::fs::read_dir(server_dir.join(dir))
+            .map(|entries| {
+                entries.flatten().any(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_lowercase();
+                    name.starts_with("spark") && name.ends_with(".jar")
+                })
+            })
+            .unwrap_or(false)
+    })
+}
+
+/// Parse a timing command's response, `None` when it has no timings
+/// (for example an unknown command on an older server)
+pub fn parse(source: TimingSource, response: &str) -> Option<TickTimings> {
+    let text = strip_formatting(response);
+    match source {
+        TimingSource::Spark => parse_spark_tps(&text),
+        TimingSource::TickQuery => parse_tick_query(&text),
+        TimingSource::Paper => parse_paper_tps(&text),
+        TimingSource::Forge | TimingSource::NeoForge => parse_forge_tps(source, &text),
+    }
+}
+
+/// Heap usage from `spark health`
+pub fn parse_spark_heap(response: &str) -> Option<HeapUsage> {
+    let text = strip_formatting(response);
+    let line = line_after(&text, "Memory usage")?;
+    let (used, max) = line.split_once('/')?;
+    Some(HeapUsage { used_mb: size_to_mb(used)?, max_mb: size_to_mb(max)? })
+}
+
+/// Remove `§` colour and style codes
+fn strip_formatting(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Every number in `text`, ignoring units and punctuation around them
+fn numbers(text: &str) -> Vec<f64> {
+    text.split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter_map(|token| token.trim_matches('.').parse().ok())
+        .collect()
+}
+
+/// First non-empty line after the line containing `heading`
+fn line_after<'a>(text: &'a str, heading: &str) -> Option<&'a str> {
+    let mut lines = text.lines().skip_while(|line| !line.contains(heading));
+    lines.next()?;
+    lines.find(|line| !line.trim().is_empty())
+}
+
+/// The number following `label` on the same line
+fn value_after(text: &str, label: &str) -> Option<f64> {
+    let rest = &text[text.find(label)? + label.len()..];
+    numbers(rest.lines().next()?).first().copied()
+}
+
+fn size_to_mb(text: &str) -> Option<u64> {
+    let value = *numbers(text).first()?;
+    let upper = text.to_uppercase();
+    let mb = if upper.contains("GB") {
+        value * 1024.0
+    } else if upper.contains("KB") {
+        value / 1024.0
+    } else {
+        value
+    };
+    Some(mb.round() as u64)
+}
+
+/// ```text
+/// TPS from last 5s, 10s, 1m, 5m, 15m:
+///  *20.0, *20.0, 19.97, 19.99, 20.0
+///
+/// Tick durations (min/med/95%ile/max ms) from last 10s, 1m:
+///  0.4/0.6/1.2/3.5;  0.3/0.6/1.3/21.4
+/// ```
+fn parse_spark_tps(text: &str) -> Option<TickTimings> {
+    let tps_values = numbers(line_after(text, "TPS from last")?);
+    // The one minute average, when present, is steadier than the last few seconds
+    let tps = *tps_values.get(2).or_else(|| tps_values.first())?;
+
+    let durations = line_after(text, "Tick durations")
+        .map(|line| numbers(line.split(';').next().unwrap_or_default()))
+        .unwrap_or_default();
+
+    Some(TickTimings {
+        source: TimingSource::Spark,
+        tps,
+        mspt: durations.get(1).copied(),
+        tick_p95: durations.get(2).copied(),
+    })
+}
+
+/// ```text
+/// The game is running normally
+/// Target tick rate: 20.0 per second.
+/// Average time per tick: 3.2ms (Target: 50.0ms)
+/// Percentiles: P50: 2.9ms P95: 5.1ms P99: 8.0ms, sample: 100
+/// ```
+fn parse_tick_query(text: &str) -> Option<TickTimings> {
+    let mspt = value_after(text, "Average time per tick:")?;
+    let target = value_after(text, "Target tick rate:").unwrap_or(20.0);
+    // A tick that takes longer than its slot lowers the rate below target
+    let tps = if mspt > 0.0 { target.min(1000.0 / mspt) } else { target };
+
+    Some(TickTimings {
+        source: TimingSource::TickQuery,
+        tps,
+        mspt: Some(mspt),
+        tick_p95: value_after(text, "P95:"),
+    })
+}
+
+/// `TPS from last 1m, 5m, 15m: 20.0, 20.0, 19.98`
+fn parse_paper_tps(text: &str) -> Option<TickTimings> {
+    let line = text.lines().find(|line| line.contains("TPS from last"))?;
+    let (_, values) = line.split_once(':')?;
+    let tps = *numbers(values).first()?;
+
+    Some(TickTimings { source: TimingSource::Paper, tps, mspt: None, tick_p95: None })
+}
+
+/// Forge: `Overall: Mean tick time: 1.234 ms. Mean TPS: 20.000`
+/// NeoForge: `Overall: 20.000 TPS (1.234 ms/tick)`
+fn parse_forge_tps(source: TimingSource, text: &str) -> Option<TickTimings> {
+    let line = text.lines().find(|line| line.trim_start().starts_with("Overall"))?;
+    let values = numbers(line);
+    let (tps, mspt) = if line.contains("Mean tick time") {
+        (*values.get(1)?, *values.first()?)
+    } else {
+        (*values.first()?, *values.get(1)?)
+    };
+
+    Some(TickTimings { source, tps, mspt: Some(mspt), tick_p95: None })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timing_commands() {
+        let spark = "§6TPS from last 5s, 10s, 1m, 5m, 15m:\n §a*20.0, §a*20.0, §a19.5, §a19.9, §a20.0\n\n\
+                     Tick durations (min/med/95%ile/max ms) from last 10s, 1m:\n 0.4/0.6/1.2/3.5;  0.3/0.6/1.3/21.4\n";
+        let timings = parse(TimingSource::Spark, spark).unwrap();
+        assert_eq!(timings.tps, 19.5);
+        assert_eq!(timings.mspt, Some(0.6));
+        assert_eq!(timings.tick_p95, Some(1.2));
+
+        let tick_query = "The game is running normally\nTarget tick rate: 20.0 per second.\n\
+                          Average time per tick: 62.5ms (Target: 50.0ms)\nPercentiles: P50: 60.1ms P95: 80.2ms P99: 95.0ms, sample: 100";
+        let timings = parse(TimingSource::TickQuery, tick_query).unwrap();
+        assert_eq!(timings.tps, 16.0);
+        assert_eq!(timings.tick_p95, Some(80.2));
+
+        let paper = "§6TPS from last 1m, 5m, 15m: §a*20.0, §a19.98, §a19.99";
+        let timings = parse(TimingSource::Paper, paper).unwrap();
+        assert_eq!(timings.tps, 20.0);
+        assert_eq!(timings.tick_ms(), None);
+        let lagging = parse(TimingSource::Paper, "TPS from last 1m, 5m, 15m: 12.5, 15.0, 18.0").unwrap();
+        assert_eq!(lagging.tick_ms(), Some(80.0));
+
+        let forge = "Dim minecraft:overworld: Mean tick time: 0.800 ms. Mean TPS: 20.000\nOverall: Mean tick time: 1.234 ms. Mean TPS: 19.500";
+        let timings = parse(TimingSource::Forge, forge).unwrap();
+        assert_eq!((timings.tps, timings.mspt), (19.5, Some(1.234)));
+
+        let neoforge = "Overall: 20.000 TPS (1.234 ms/tick)";
+        assert_eq!(parse(TimingSource::NeoForge, neoforge).unwrap().mspt, Some(1.234));
+
+        assert_eq!(parse(TimingSource::TickQuery, "Unknown or incomplete command, see below for error"), None);
+    }
+
+    #[test]
+    fn test_parse_spark_heap() {
+        let health = "Memory usage:\n  1.5 GB / 4.0 GB   (37%)\n";
+        assert_eq!(parse_spark_heap(health), Some(HeapUsage { used_mb: 1536, max_mb: 4096 }));
+    }
+}
@@ -0,0 +1,419 @@
+//! Tunnels give a server a public address when the router can't forward
+//! ports. Guardian runs the provider's agent next to the server process,
+//! reads the public address from the agent's output, and stops the agent
+//! when the server stops. The loop restarts agents that exit while their
+//! server runs.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, ServerConfig, ServerTunnel};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelProvider {
+    /// ngrok TCP tunnel; needs an ngrok auth token
+    Ngrok,
+    /// playit.gg agent; needs the agent's secret, and the tunnel itself is
+    /// set up on playit.gg pointing at the server's port
+    Playit,
+}
+
+impl TunnelProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ngrok => "ngrok",
+            Self::Playit => "playit",
+        }
+    }
+
+    pub fn parse(provider: &str) -> Result<Self> {
+        match provider {
+            "ngrok" => Ok(Self::Ngrok),
+            "playit" => Ok(Self::Playit),
+            other => bail!("Unknown tunnel provider: {}", other),
+        }
+    }
+
+    fn command(&self, tunnel: &ServerTunnel, port: u16) -> Command {
+        let agent = tunnel.agent_path.as_deref().unwrap_or(self.as_str());
+        let mut command = Command::new(agent);
+        match self {
+            Self::Ngrok => {
+                command.args(["tcp", &port.to_string(), "--log", "stdout", "--log-format", "json"]);
+                if let Some(region) = &tunnel.region {
+                    command.args(["--region", region]);
+                }
+                // The environment keeps the token out of the process list
+                command.env("NGROK_AUTHTOKEN", &tunnel.token);
+            }
+            Self::Playit => {
+                command.args(["--secret", &tunnel.token, "--stdout", "start"]);
+            }
+        }
+        command
+    }
+
+    /// Public address announced in a line of the agent's output
+    fn parse_address(&self, line: &str) -> Option<String> {
+        static NGROK: OnceLock<Regex> = OnceLock::new();
+        static PLAYIT: OnceLock<Regex> = OnceLock::new();
+        let regex = match self {
+            Self::Ngrok => NGROK.get_or_init(|| Regex::new(r"tcp://([A-Za-z0-9.-]+:\d+)").unwrap()),
+            Self::Playit => PLAYIT.get_or_init(|| {
+                Regex::new(r"\b((?:[a-z0-9-]+\.)+(?:joinmc\.link|playit\.gg|ply\.gg)(?::\d+)?)\b").unwrap()
+            }),
+        };
+        let address = regex.captures(line)?.get(1)?.as_str();
+        // playit's own hosts appear in its log too
+        (!matches!(address, "api.playit.gg" | "www.playit.gg")).then(|| address.to_string())
+    }
+
+    /// Error reported in a line of the agent's output
+    fn parse_error(&self, line: &str) -> Option<String> {
+        match self {
+            Self::Ngrok => {
+                let entry: serde_json::Value = serde_json::from_str(line).ok()?;
+                let level = entry.get("lvl")?.as_str()?;
+                matches!(level, "eror" | "crit")
+                    .then(|| entry.get("err").or(entry.get("msg")).and_then(|e| e.as_str()).map(str::to_string))
+                    .flatten()
+            }
+            Self::Playit => line.to_ascii_lowercase().contains("error").then(|| line.trim().to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TunnelRequest {
+    pub provider: TunnelProvider,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Left out to keep the current token
+    pub token: Option<String>,
+    pub region: Option<String>,
+    pub agent_path: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TunnelState {
+    /// Agent running, no address yet
+    Starting,
+    Online,
+    /// Agent could not start or exited; retried while the server runs
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TunnelStatus {
+    pub state: TunnelState,
+    /// Address players connect to
+    pub public_address: Option<String>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Tunnel settings as returned by the API, without the token
+#[derive(Debug, Clone, Serialize)]
+pub struct TunnelInfo {
+    pub provider: String,
+    pub enabled: bool,
+    pub has_token: bool,
+    pub region: Option<String>,
+    pub agent_path: Option<String>,
+    /// None while the server is stopped
+    pub status: Option<TunnelStatus>,
+}
+
+struct RunningTunnel {
+    /// None when the agent failed to start
+    child: Option<Child>,
+    status: Arc<RwLock<TunnelStatus>>,
+}
+
+pub struct TunnelManager {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+    tunnels: RwLock<HashMap<String, RunningTunnel>>,
+}
+
+impl TunnelManager {
+    pub fn new(database: Arc<DatabaseManager>, process_manager: Arc<ProcessManager>) -> Self {
+        Self { database, process_manager, tunnels: RwLock::new(HashMap::new()) }
+    }
+
+    pub async fn get(&self, server_id: &str) -> Result<Option<TunnelInfo>> {
+        match self.database.get_server_tunnel(server_id).await? {
+            Some(tunnel) => Ok(Some(self.info(&tunnel).await)),
+            None => Ok(None),
+        }
+    }
+
+    async fn info(&self, tunnel: &ServerTunnel) -> TunnelInfo {
+        TunnelInfo {
+            provider: tunnel.provider.clone(),
+            enabled: tunnel.enabled,
+            has_token: !tunnel.token.is_empty(),
+            region: tunnel.region.clone(),
+            agent_path: tunnel.agent_path.clone(),
+            status: self.status(&tunnel.server_id).await,
+        }
+    }
+
+    pub async fn status(&self, server_id: &str) -> Option<TunnelStatus> {
+        let status = self.tunnels.read().await.get(server_id).map(|tunnel| tunnel.status.clone())?;
+        let status = status.read().await.clone();
+        Some(status)
+    }
+
+    /// Address players can share, while the tunnel is up
+    pub async fn public_address(&self, server_id: &str) -> Option<String> {
+        self.status(server_id).await.and_then(|status| status.public_address)
+    }
+
+    /// Save a server's tunnel settings, restarting its agent if it runs
+    pub async fn configure(&self, server: &ServerConfig, request: TunnelRequest) -> Result<TunnelInfo> {
+        if !server.managed {
+            bail!("External servers are not run by Guardian");
+        }
+        let previous = self.database.get_server_tunnel(&server.id).await?;
+        let token = match request.token {
+            Some(token) => token.trim().to_string(),
+            None => previous.map(|tunnel| tunnel.token).unwrap_or_default(),
+        };
+        if token.is_empty() {
+            bail!("A {} token is required", request.provider.as_str());
+        }
+        let tunnel = ServerTunnel {
+            server_id: server.id.clone(),
+            provider: request.provider.as_str().to_string(),
+            enabled: request.enabled,
+            token,
+            region: request.region.filter(|r| !r.trim().is_empty()),
+            agent_path: request.agent_path.filter(|p| !p.trim().is_empty()),
+            updated_at: Utc::now(),
+        };
+        self.database.save_server_tunnel(&tunnel).await?;
+
+        self.stop_tunnel(&server.id).await;
+        if tunnel.enabled && self.is_running(&server.id).await {
+            self.start_tunnel(server, &tunnel).await;
+        }
+        Ok(self.info(&tunnel).await)
+    }
+
+    pub async fn remove(&self, server_id: &str) -> Result<bool> {
+        if self.database.get_server_tunnel(server_id).await?.is_none() {
+            return Ok(false);
+        }
+        self.stop_tunnel(server_id).await;
+        self.database.delete_server_tunnel(server_id).await?;
+        Ok(true)
+    }
+
+    /// Bring up the tunnel of a server that just started
+    pub async fn server_started(&self, server: &ServerConfig) {
+        match self.database.get_server_tunnel(&server.id).await {
+            Ok(Some(tunnel)) if tunnel.enabled => self.start_tunnel(server, &tunnel).await,
+            Ok(_) => {}
+            Err(e) => warn!("Failed to read tunnel settings for {}: {}", server.id, e),
+        }
+    }
+
+    pub async fn server_stopped(&self, server_id: &str) {
+        self.stop_tunnel(server_id).await;
+    }
+
+    /// Stop every agent, for shutdown
+    pub async fn stop_all(&self) {
+        let active: Vec<String> = self.tunnels.read().await.keys().cloned().collect();
+        for server_id in active {
+            self.stop_tunnel(&server_id).await;
+        }
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting tunnel supervision");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_all().await {
+                warn!("Tunnel check failed: {}", e);
+            }
+        }
+    }
+
+    /// Start agents of running servers whose agent is missing or exited, and
+    /// stop those of servers that stopped or crashed
+    async fn check_all(&self) -> Result<()> {
+        let tunnels = self.database.get_server_tunnels().await?;
+        let active: Vec<String> = self.tunnels.read().await.keys().cloned().collect();
+        for server_id in active.iter().filter(|id| !tunnels.iter().any(|t| &t.server_id == *id)) {
+            self.stop_tunnel(server_id).await;
+        }
+
+        for tunnel in tunnels {
+            if !self.is_running(&tunnel.server_id).await {
+                self.stop_tunnel(&tunnel.server_id).await;
+                continue;
+            }
+            if self.agent_alive(&tunnel.server_id).await {
+                continue;
+            }
+            if let Some(server) = self.database.get_server(&tunnel.server_id).await? {
+                self.start_tunnel(&server, &tunnel).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn is_running(&self, server_id: &str) -> bool {
+        match Uuid::parse_str(server_id) {
+            Ok(id) => self.process_manager.is_server_running(id).await,
+            Err(_) => false,
+        }
+    }
+
+    /// Whether the agent still runs, marking it failed if it exited
+    async fn agent_alive(&self, server_id: &str) -> bool {
+        let mut tunnels = self.tunnels.write().await;
+        let Some(tunnel) = tunnels.get_mut(server_id) else {
+            return false;
+        };
+        let Some(child) = tunnel.child.as_mut() else {
+            return false;
+        };
+        match child.try_wait() {
+            Ok(None) => true,
+            Ok(Some(exit)) => {
+                let mut status = tunnel.status.write().await;
+                status.state = TunnelState::Failed;
+                status.public_address = None;
+                if status.error.is_none() {
+                    status.error = Some(format!("Agent exited with {}", exit));
+                }
+                false
+            }
+            Err(e) => {
+                debug!("Failed to check tunnel agent of {}: {}", server_id, e);
+                false
+            }
+        }
+    }
+
+    async fn start_tunnel(&self, server: &ServerConfig, tunnel: &ServerTunnel) {
+        self.stop_tunnel(&server.id).await;
+        let status = Arc::new(RwLock::new(TunnelStatus {
+            state: TunnelState::Starting,
+            public_address: None,
+            error: None,
+            started_at: Utc::now(),
+        }));
+        let child = match spawn_agent(tunnel, server.port, status.clone()) {
+            Ok(child) => {
+                info!("Started {} tunnel for server {}", tunnel.provider, server.id);
+                Some(child)
+            }
+            Err(e) => {
+                warn!("Failed to start {} tunnel for server {}: {}", tunnel.provider, server.id, e);
+                let mut status = status.write().await;
+                status.state = TunnelState::Failed;
+                status.error = Some(e.to_string());
+                None
+            }
+        };
+        self.tunnels.write().await.insert(server.id.clone(), RunningTunnel { child, status });
+    }
+
+    async fn stop_tunnel(&self, server_id: &str) {
+        let Some(tunnel) = self.tunnels.write().await.remove(server_id) else {
+            return;
+        };
+        if let Some(mut child) = tunnel.child {
+            if let Err(e) = child.kill().await {
+                debug!("Failed to stop tunnel agent of {}: {}", server_id, e);
+            } else {
+                info!("Stopped tunnel for server {}", server_id);
+            }
+        }
+    }
+}
+
+fn spawn_agent(tunnel: &ServerTunnel, port: u16, status: Arc<RwLock<TunnelStatus>>) -> Result<Child> {
+    let provider = TunnelProvider::parse(&tunnel.provider)?;
+    let mut child = provider
+        .command(tunnel, port)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run {}: {}", tunnel.agent_path.as_deref().unwrap_or(provider.as_str()), e))?;
+    if let Some(stdout) = child.stdout.take() {
+        tokio::spawn(watch_output(provider, stdout, status.clone()));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        tokio::spawn(watch_output(provider, stderr, status));
+    }
+    Ok(child)
+}
+
+/// Follow the agent's output for its public address and errors
+async fn watch_output(provider: TunnelProvider, output: impl AsyncRead + Unpin, status: Arc<RwLock<TunnelStatus>>) {
+    let mut lines = BufReader::new(output).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        debug!("{} agent: {}", provider.as_str(), line);
+        if let Some(address) = provider.parse_address(&line) {
+            let mut status = status.write().await;
+            status.state = TunnelState::Online;
+            status.public_address = Some(address);
+            status.error = None;
+        } else if let Some(error) = provider.parse_error(&line) {
+            status.write().await.error = Some(error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ngrok_output() {
+        let started = r#"{"addr":"//localhost:25565","lvl":"info","msg":"started tunnel","name":"command_line","obj":"tunnels","t":"2024-01-01T12:00:00Z","url":"tcp://4.tcp.eu.ngrok.io:17312"}"#;
+        assert_eq!(TunnelProvider::Ngrok.parse_address(started).as_deref(), Some("4.tcp.eu.ngrok.io:17312"));
+        assert_eq!(TunnelProvider::Ngrok.parse_error(started), None);
+
+        let failed = r#"{"err":"authentication failed: The authtoken you specified is properly formed, but it is invalid","lvl":"eror","msg":"session closing","obj":"tunnels.session"}"#;
+        assert!(TunnelProvider::Ngrok.parse_error(failed).unwrap().starts_with("authentication failed"));
+        assert_eq!(TunnelProvider::Ngrok.parse_address(failed), None);
+    }
+
+    #[test]
+    fn test_parse_playit_output() {
+        let line = "tunnel running: bright-fox.joinmc.link => 127.0.0.1:25565 (minecraft-java)";
+        assert_eq!(TunnelProvider::Playit.parse_address(line).as_deref(), Some("bright-fox.joinmc.link"));
+        assert_eq!(TunnelProvider::Playit.parse_address("connecting to api.playit.gg"), None);
+        assert_eq!(TunnelProvider::parse("playit").unwrap(), TunnelProvider::Playit);
+        assert!(TunnelProvider::parse("frp").is_err());
+    }
+}
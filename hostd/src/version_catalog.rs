@@ -0,0 +1,187 @@
+//! Version catalogs for offline use
+//!
+//! The version lists the server creation wizard needs are kept in the
+//! metadata cache and refreshed by a background task every
+//! [`SYNC_INTERVAL`], so they are on hand when the network is not. With the
+//! `offline_mode` setting on, nothing is synced and the lists are served from
+//! the cache however old they are; responses say how old with a
+//! [`Freshness`].
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::loaders::{fabric, forge, quilt};
+use crate::metadata_cache::{self, Freshness, LOADER_TTL, MOJANG_TTL};
+
+/// Mojang's list of every Minecraft version
+pub const MOJANG_MANIFEST_URL: &str = "https://launchermeta.mojang.com/mc/game/version_manifest_v2.json";
+pub const MODRINTH_GAME_VERSIONS_URL: &str = "https://api.modrinth.com/v2/tag/game_version";
+pub const MODRINTH_LOADERS_URL: &str = "https://api.modrinth.com/v2/tag/loader";
+
+/// How often the catalogs are refreshed
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// The catalogs kept for offline use, by name
+const CATALOGS: &[(&str, &str)] = &[
+    ("minecraft", MOJANG_MANIFEST_URL),
+    ("fabric-loader", fabric::LOADER_VERSIONS_URL),
+    ("fabric-game", fabric::GAME_VERSIONS_URL),
+    ("quilt-loader", quilt::LOADER_VERSIONS_URL),
+    ("quilt-game", quilt::GAME_VERSIONS_URL),
+    ("forge", forge::PROMOTIONS_URL),
+    ("modrinth-game-versions", MODRINTH_GAME_VERSIONS_URL),
+    ("modrinth-loaders", MODRINTH_LOADERS_URL),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogStatus {
+    pub name: String,
+    pub url: String,
+    /// When the catalog was last fetched; `None` if it never was
+    pub fetched_at: Option<DateTime<Utc>>,
+    /// Why the last sync of this catalog failed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogOverview {
+    pub offline_mode: bool,
+    /// When the last sync finished
+    pub last_sync: Option<DateTime<Utc>>,
+    pub catalogs: Vec<CatalogStatus>,
+}
+
+/// When a sync finished, and its errors by catalog name
+type SyncResult = (DateTime<Utc>, Vec<(String, String)>);
+
+static LAST_SYNC: Mutex<Option<SyncResult>> = Mutex::new(None);
+
+pub async fn overview() -> Result<CatalogOverview> {
+    let last_sync = LAST_SYNC.lock().unwrap().clone();
+    let mut catalogs = Vec::new();
+    for (name, url) in CATALOGS {
+        let error = last_sync
+            .as_ref()
+            .and_then(|(_, errors)| errors.iter().find(|(failed, _)| failed == name))
+            .map(|(_, error)| error.clone());
+        catalogs.push(CatalogStatus {
+            name: name.to_string(),
+            url: url.to_string(),
+            fetched_at: metadata_cache::fetched_at(url).await?,
+            error,
+        });
+    }
+    Ok(CatalogOverview {
+        offline_mode: metadata_cache::offline_mode().await,
+        last_sync: last_sync.map(|(at, _)| at),
+        catalogs,
+    })
+}
+
+/// Refresh every catalog from its provider
+pub async fn sync() -> Result<CatalogOverview> {
+    if metadata_cache::offline_mode().await {
+        return Err(anyhow!("Catalogs are not synced in offline mode"));
+    }
+    let mut errors = Vec::new();
+    for (name, url) in CATALOGS {
+        if let Err(e) = metadata_cache::refresh(url).await {
+            debug!("Could not sync the {} catalog: {:#}", name, e);
+            errors.push((name.to_string(), format!("{:#}", e)));
+        }
+    }
+    if errors.len() < CATALOGS.len() {
+        info!("Synced {} of {} version catalogs", CATALOGS.len() - errors.len(), CATALOGS.len());
+    } else {
+        warn!("Could not sync any version catalog; the providers may be unreachable");
+    }
+    *LAST_SYNC.lock().unwrap() = Some((Utc::now(), errors));
+    overview().await
+}
+
+/// Sync the catalogs now and every [`SYNC_INTERVAL`], except in offline mode
+pub fn spawn_sync() {
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(SYNC_INTERVAL);
+        loop {
+            interval.tick().await;
+            if metadata_cache::offline_mode().await {
+                continue;
+            }
+            if let Err(e) = sync().await {
+                warn!("Version catalog sync failed: {:#}", e);
+            }
+        }
+    });
+}
+
+/// Minecraft versions a server of `edition` (Vanilla, Fabric, Quilt or
+/// Forge) can run, newest first
+pub async fn minecraft_versions(edition: &str) -> Result<(Vec<String>, Freshness)> {
+    match edition.to_ascii_lowercase().as_str() {
+        "vanilla" => {
+            let (manifest, freshness) = metadata_cache::get_json_fresh(MOJANG_MANIFEST_URL, MOJANG_TTL).await?;
+            let versions = manifest["versions"]
+                .as_array()
+                .ok_or_else(|| anyhow!("Invalid version manifest"))?
+                .iter()
+                .filter(|version| version["type"] == "release")
+                .filter_map(|version| version["id"].as_str().map(str::to_string))
+                .collect();
+            Ok((versions, freshness))
+        }
+        "fabric" | "quilt" => {
+            let url = if edition.eq_ignore_ascii_case("fabric") { fabric::GAME_VERSIONS_URL } else { quilt::GAME_VERSIONS_URL };
+            let (list, freshness) = metadata_cache::get_json_fresh(url, LOADER_TTL).await?;
+            let versions = list
+                .as_array()
+                .ok_or_else(|| anyhow!("Invalid {} version list", edition))?
+                .iter()
+                .filter(|version| version["stable"] == true)
+                .filter_map(|version| version["version"].as_str().map(str::to_string))
+                .collect();
+            Ok((versions, freshness))
+        }
+        "forge" => {
+            let (promotions, freshness) = metadata_cache::get_json_fresh(forge::PROMOTIONS_URL, LOADER_TTL).await?;
+            Ok((forge_minecraft_versions(&promotions), freshness))
+        }
+        _ => Err(anyhow!("Unknown edition '{}'", edition)),
+    }
+}
+
+/// Minecraft versions with a Forge build, from keys like `1.20.1-recommended`
+fn forge_minecraft_versions(promotions: &serde_json::Value) -> Vec<String> {
+    let mut versions: Vec<String> = promotions["promos"]
+        .as_object()
+        .map(|promos| promos.keys().filter_map(|key| key.rsplit_once('-')).map(|(version, _)| version.to_string()).collect())
+        .unwrap_or_default();
+    let number = |version: &String| -> Vec<u32> { version.split('.').map(|part| part.parse().unwrap_or(0)).collect() };
+    versions.sort_by_key(|version| std::cmp::Reverse(number(version)));
+    versions.dedup();
+    versions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forge_minecraft_versions() {
+        let promotions = serde_json::json!({
+            "homepage": "https://files.minecraftforge.net/",
+            "promos": {
+                "1.12.2-latest": "14.23.5.2860",
+                "1.20.1-latest": "47.3.0",
+                "1.20.1-recommended": "47.2.0",
+                "1.9-latest": "12.16.1.1938",
+                "1.20-latest": "46.0.14"
+            }
+        });
+        assert_eq!(forge_minecraft_versions(&promotions), ["1.20.1", "1.20", "1.12.2", "1.9"]);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::external_apis::{CurseForgeApiClient, ModrinthApiClient};
+use crate::external_apis::{curseforge, modrinth, CurseForgeApiClient, ModrinthApiClient};
 use crate::database::{DatabaseManager, ModDependency};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -140,53 +140,13 @@ impl VersionResolver {
     ) -> Result<ModVersion, Box<dyn Error>> {
         let project_id = project_id.parse::<u32>()?;
         
-        // Get project files
-        let files = client.get_project_files(project_id, Some(minecraft_version), None, None, None, None).await?;
-        
-        // Filter by Minecraft version and loader
-        let compatible_files: Vec<_> = files.into_iter()
-            .filter(|file| {
-                file.game_versions.contains(&minecraft_version.to_string()) &&
-                file.sortable_game_versions.iter().any(|v| v.game_version == minecraft_version)
-            })
-            .collect();
-        
-        if compatible_files.is_empty() {
-            return Err(format!("No compatible files found for MC {} with loader {}", minecraft_version, loader).into());
-        }
-        
-        // Sort by release type and date (prefer release, then beta, then alpha)
-        let mut sorted_files = compatible_files;
-        sorted_files.sort_by(|a, b| {
-            // Sort by release type first
-            let type_order = |rt: u32| match rt {
-                1 => 0, // Release
-                2 => 1, // Beta
-                3 => 2, // Alpha
-                _ => 3,
-            };
-            
-            let type_cmp = type_order(a.release_type).cmp(&type_order(b.release_type));
-            if type_cmp == std::cmp::Ordering::Equal {
-                // Then by date (newest first)
-                b.file_date.cmp(&a.file_date)
-            } else {
-                type_cmp
-            }
-        });
-        
-        let latest_file = sorted_files.first().ok_or("No files found")?;
-        
-        // Get SHA1 hash
-        let sha1 = latest_file.hashes.iter()
-            .find(|h| h.algo == 1) // SHA1
-            .map(|h| h.value.clone());
-        
-        // Get SHA512 hash
-        let sha512 = latest_file.hashes.iter()
-            .find(|h| h.algo == 2) // SHA512
-            .map(|h| h.value.clone());
-        
+        let project = client.get_project(project_id).await?;
+        let files = client.get_all_project_files(project_id, Some(minecraft_version), Some(loader), 200).await?;
+        let latest_file = curseforge::best_file(&files, Some(minecraft_version), Some(loader))
+            .ok_or_else(|| format!("No compatible files found for MC {} with loader {}", minecraft_version, loader))?;
+
+        let download_url = client.resolve_download_url(&project, latest_file).await?;
+
         Ok(ModVersion {
             id: latest_file.id.to_string(),
             version: latest_file.display_name.clone(),
@@ -194,15 +154,11 @@ impl VersionResolver {
             loader: loader.to_string(),
             filename: latest_file.file_name.clone(),
             file_size: latest_file.file_length,
-            sha1,
-            sha512,
-            download_url: latest_file.download_url.clone(),
-            release_type: match latest_file.release_type {
-                1 => "release".to_string(),
-                2 => "beta".to_string(),
-                3 => "alpha".to_string(),
-                _ => "unknown".to_string(),
-            },
+            sha1: curseforge::file_sha1(latest_file),
+            // CurseForge only publishes SHA-1 and MD5 hashes
+            sha512: None,
+            download_url,
+            release_type: curseforge::release_type_name(latest_file.release_type).to_string(),
             created_at: latest_file.file_date.parse().unwrap_or_else(|_| Utc::now()),
             updated_at: Utc::now(),
         })
@@ -240,8 +196,7 @@ impl VersionResolver {
         
         let latest_version = sorted_versions.first().ok_or("No versions found")?;
         
-        // Get the primary file
-        let primary_file = latest_version.files.first().ok_or("No files in version")?;
+        let primary_file = modrinth::primary_file(latest_version).ok_or("No files in version")?;
         
         Ok(ModVersion {
             id: latest_version.id.clone(),
@@ -297,11 +252,11 @@ impl VersionResolver {
             provider: "curseforge".to_string(),
             project_id: project.id.to_string(),
             slug: Some(project.slug.clone()),
-            category: "mod".to_string(), // CurseForge doesn't have a simple category field
-            side: "both".to_string(), // Default to both, would need to check mod metadata
+            category: curseforge::map_category(&project.categories),
+            side: "both".to_string(), // CurseForge doesn't say which side a mod runs on
             website_url: project.links.website_url.clone(),
-            source_url: None,
-            issues_url: None,
+            source_url: project.links.source_url.clone(),
+            issues_url: project.links.issues_url.clone(),
             created_at: project.date_created.parse().unwrap_or_else(|_| Utc::now()),
             updated_at: project.date_modified.parse().unwrap_or_else(|_| Utc::now()),
         })
@@ -1,538 +1,551 @@
-use crate::config::Config;
-use anyhow::Result;
+//! Outgoing webhooks
+//!
+//! A webhook POSTs lifecycle events, such as a server starting or crashing, a
+//! backup finishing or a player joining, as JSON to a URL. Events are picked
+//! up from the event log every few seconds and queued as deliveries in the
+//! database, which serves as the outbox: a delivery that fails is retried with
+//! exponential backoff, across restarts, until it succeeds or runs out of
+//! attempts. Deliveries are kept for 30 days as the webhook's delivery log.
+//!
+//! With a secret set, every request carries `X-Guardian-Signature:
+//! sha256=<hex>`, the HMAC-SHA256 of `<timestamp>.<body>` keyed by the secret,
+//! where the timestamp is the `X-Guardian-Timestamp` header in Unix seconds.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::Sha256;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::{info, warn, error, debug};
+use tracing::{error, info, warn};
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
-use reqwest::Client;
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
-use base64::Engine;
 
-/// Webhook configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Webhook {
-    pub id: String,
-    pub name: String,
-    pub url: String,
-    pub events: Vec<String>,
-    pub secret: Option<String>,
-    pub headers: HashMap<String, String>,
-    pub retry_config: RetryConfig,
-    pub status: WebhookStatus,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-    pub metadata: HashMap<String, String>,
+use crate::database::{DatabaseManager, EventLog, Webhook, WebhookDelivery};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Deliveries sent per poll
+const BATCH_SIZE: u32 = 50;
+const MAX_ATTEMPTS: u32 = 8;
+const FIRST_RETRY_SECONDS: i64 = 30;
+const MAX_RETRY_SECONDS: i64 = 60 * 60;
+const RETENTION: chrono::Duration = chrono::Duration::days(30);
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Response body kept with a failed attempt
+const MAX_ERROR_LENGTH: usize = 500;
+pub const DEFAULT_DELIVERY_LIMIT: u32 = 50;
+pub const MAX_DELIVERY_LIMIT: u32 = 500;
+
+pub const SIGNATURE_HEADER: &str = "X-Guardian-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Guardian-Timestamp";
+pub const EVENT_HEADER: &str = "X-Guardian-Event";
+pub const DELIVERY_HEADER: &str = "X-Guardian-Delivery";
+
+/// Events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    ServerStarted,
+    ServerStopped,
+    ServerCrashed,
+    BackupCompleted,
+    BackupFailed,
+    PlayerJoined,
+    PlayerLeft,
+    /// Sent by the test endpoint whatever the webhook subscribes to
+    Ping,
 }
 
-/// Retry configuration for failed webhook deliveries
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RetryConfig {
-    pub max_attempts: u32,
-    pub initial_delay_ms: u64,
-    pub max_delay_ms: u64,
-    pub backoff_multiplier: f64,
-}
+pub const EVENTS: [WebhookEvent; 7] = [
+    WebhookEvent::ServerStarted,
+    WebhookEvent::ServerStopped,
+    WebhookEvent::ServerCrashed,
+    WebhookEvent::BackupCompleted,
+    WebhookEvent::BackupFailed,
+    WebhookEvent::PlayerJoined,
+    WebhookEvent::PlayerLeft,
+];
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ServerStarted => "server.started",
+            Self::ServerStopped => "server.stopped",
+            Self::ServerCrashed => "server.crashed",
+            Self::BackupCompleted => "backup.completed",
+            Self::BackupFailed => "backup.failed",
+            Self::PlayerJoined => "player.joined",
+            Self::PlayerLeft => "player.left",
+            Self::Ping => "ping",
+        }
+    }
 
-/// Webhook status
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum WebhookStatus {
-    Active,
-    Inactive,
-    Suspended,
-    Error,
-}
+    /// A subscribable event by name
+    pub fn parse(name: &str) -> Option<Self> {
+        EVENTS.into_iter().find(|event| event.as_str() == name)
+    }
 
-/// Webhook delivery attempt
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WebhookDelivery {
-    pub id: String,
-    pub webhook_id: String,
-    pub event_id: String,
-    pub attempt: u32,
-    pub status: DeliveryStatus,
-    pub response_code: Option<u16>,
-    pub response_body: Option<String>,
-    pub error_message: Option<String>,
-    pub delivered_at: Option<DateTime<Utc>>,
-    pub next_retry_at: Option<DateTime<Utc>>,
+    /// Event log types that raise the event
+    fn event_types(&self) -> &'static [&'static str] {
+        match self {
+            Self::ServerStarted => &["server_started"],
+            Self::ServerStopped => &["server_stopped"],
+            Self::ServerCrashed => &["server_crash", "crash_loop"],
+            Self::BackupCompleted => &["backup_completed"],
+            Self::BackupFailed => &["backup_failed"],
+            Self::PlayerJoined => &["player_joined"],
+            Self::PlayerLeft => &["player_left"],
+            Self::Ping => &[],
+        }
+    }
+
+    fn of(event_type: &str) -> Option<Self> {
+        EVENTS.into_iter().find(|event| event.event_types().contains(&event_type))
+    }
 }
 
-/// Delivery status
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DeliveryStatus {
+    /// Waiting for its first attempt or a retry
     Pending,
     Delivered,
+    /// Out of attempts
     Failed,
-    Retrying,
-    Expired,
 }
 
-/// Webhook event
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WebhookEvent {
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(Self::Pending),
+            "delivered" => Some(Self::Delivered),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Body POSTed for every event
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    /// Delivery ID, the same for every attempt, so receivers can drop repeats
     pub id: String,
-    pub event_type: String,
+    pub event: String,
+    pub occurred_at: DateTime<Utc>,
+    pub server_id: Option<String>,
+    pub server_name: Option<String>,
+    pub message: String,
+    /// Details such as the backup ID or player name
     pub data: serde_json::Value,
-    pub timestamp: DateTime<Utc>,
-    pub source: String,
-    pub metadata: HashMap<String, String>,
 }
 
-/// Webhook manager for external integrations
-pub struct WebhookManager {
-    webhooks: Arc<RwLock<HashMap<String, Webhook>>>,
-    deliveries: Arc<RwLock<HashMap<String, Vec<WebhookDelivery>>>>,
-    events: Arc<RwLock<Vec<WebhookEvent>>>,
-    client: Client,
-    delivery_queue: Arc<RwLock<Vec<DeliveryTask>>>,
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewWebhook {
+    pub name: String,
+    pub url: String,
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub events: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
 }
 
-/// Delivery task for async processing
-#[derive(Debug, Clone)]
-pub struct DeliveryTask {
-    pub webhook_id: String,
-    pub event: WebhookEvent,
-    pub attempt: u32,
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WebhookUpdate {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    /// An empty string removes the secret, so requests go out unsigned
+    pub secret: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `<timestamp>.<body>`
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    format!("sha256={}", hmac_hex(secret, &format!("{}.{}", timestamp, body)))
+}
+
+fn hmac_hex(key: &str, data: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(data.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// Wait before the next attempt after `attempts` failed ones: 30 seconds,
+/// doubling each time, at most an hour. `None` once attempts run out.
+fn retry_delay(attempts: u32) -> Option<chrono::Duration> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+    let seconds = FIRST_RETRY_SECONDS.saturating_mul(1 << attempts.saturating_sub(1).min(20));
+    Some(chrono::Duration::seconds(seconds.min(MAX_RETRY_SECONDS)))
+}
+
+fn validate_events(events: &[String]) -> Result<()> {
+    for event in events {
+        if WebhookEvent::parse(event).is_none() {
+            let known: Vec<&str> = EVENTS.iter().map(|event| event.as_str()).collect();
+            bail!("Unknown event '{}', expected one of {}", event, known.join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn subscribed(webhook: &Webhook, event: WebhookEvent) -> bool {
+    webhook.enabled && (webhook.events.is_empty() || webhook.events.iter().any(|name| name == event.as_str()))
+}
+
+pub struct WebhookManager {
+    database: Arc<DatabaseManager>,
+    client: reqwest::Client,
+    /// Events up to this time have been queued
+    events_checked_until: RwLock<DateTime<Utc>>,
 }
 
 impl WebhookManager {
-    pub fn new() -> Self {
+    pub fn new(database: Arc<DatabaseManager>) -> Self {
         Self {
-            webhooks: Arc::new(RwLock::new(HashMap::new())),
-            deliveries: Arc::new(RwLock::new(HashMap::new())),
-            events: Arc::new(RwLock::new(Vec::new())),
-            client: Client::new(),
-            delivery_queue: Arc::new(RwLock::new(Vec::new())),
+            database,
+            client: reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+            events_checked_until: RwLock::new(Utc::now()),
         }
     }
 
-    /// Initialize webhook manager
-    pub async fn initialize(&self) -> Result<()> {
-        info!("Initializing webhook manager...");
-        
-        // Start delivery worker
-        self.start_delivery_worker().await;
-        
-        // Start retry worker
-        self.start_retry_worker().await;
-        
-        info!("Webhook manager initialized");
-        Ok(())
+    fn validate(webhook: &Webhook) -> Result<()> {
+        if webhook.name.trim().is_empty() {
+            bail!("Webhook name is required");
+        }
+        crate::notifiers::validate_url(&webhook.url)?;
+        validate_events(&webhook.events)
     }
 
-    /// Create webhook
-    pub async fn create_webhook(&self, name: String, url: String, events: Vec<String>) -> Result<Webhook> {
-        let webhook_id = Uuid::new_v4().to_string();
+    pub async fn list(&self) -> Result<Vec<Webhook>> {
+        self.database.get_webhooks().await
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Webhook>> {
+        self.database.get_webhook(id).await
+    }
+
+    pub async fn create(&self, request: NewWebhook) -> Result<Webhook> {
         let now = Utc::now();
-        
         let webhook = Webhook {
-            id: webhook_id.clone(),
-            name: name.clone(),
-            url: url.clone(),
-            events: events.clone(),
-            secret: None,
-            headers: HashMap::new(),
-            retry_config: RetryConfig::default(),
-            status: WebhookStatus::Active,
+            id: Uuid::new_v4().to_string(),
+            name: request.name.trim().to_string(),
+            url: request.url.trim().to_string(),
+            secret: request.secret.filter(|secret| !secret.is_empty()),
+            events: request.events,
+            enabled: request.enabled,
             created_at: now,
             updated_at: now,
-            metadata: HashMap::new(),
         };
-
-        let mut webhooks = self.webhooks.write().await;
-        webhooks.insert(webhook_id.clone(), webhook.clone());
-        
-        info!("Created webhook: {} -> {}", name, url);
+        Self::validate(&webhook)?;
+        self.database.create_webhook(&webhook).await?;
         Ok(webhook)
     }
 
-    /// Get webhook by ID
-    pub async fn get_webhook(&self, webhook_id: &str) -> Option<Webhook> {
-        let webhooks = self.webhooks.read().await;
-        webhooks.get(webhook_id).cloned()
-    }
+    /// Change a webhook's settings. The secret is kept when the update leaves it out.
+    pub async fn update(&self, id: &str, update: WebhookUpdate) -> Result<Option<Webhook>> {
+        let Some(mut webhook) = self.database.get_webhook(id).await? else {
+            return Ok(None);
+        };
+        if let Some(name) = update.name {
+            webhook.name = name.trim().to_string();
+        }
+        if let Some(url) = update.url {
+            webhook.url = url.trim().to_string();
+        }
+        if let Some(secret) = update.secret {
+            webhook.secret = Some(secret).filter(|secret| !secret.is_empty());
+        }
+        if let Some(events) = update.events {
+            webhook.events = events;
+        }
+        if let Some(enabled) = update.enabled {
+            webhook.enabled = enabled;
+        }
+        webhook.updated_at = Utc::now();
 
-    /// List webhooks
-    pub async fn list_webhooks(&self) -> Vec<Webhook> {
-        let webhooks = self.webhooks.read().await;
-        webhooks.values().cloned().collect()
+        Self::validate(&webhook)?;
+        self.database.update_webhook(&webhook).await?;
+        Ok(Some(webhook))
     }
 
-    /// Update webhook
-    pub async fn update_webhook(&self, webhook_id: &str, updates: WebhookUpdate) -> Result<()> {
-        let mut webhooks = self.webhooks.write().await;
-        if let Some(webhook) = webhooks.get_mut(webhook_id) {
-            if let Some(name) = updates.name {
-                webhook.name = name;
-            }
-            if let Some(url) = updates.url {
-                webhook.url = url;
-            }
-            if let Some(events) = updates.events {
-                webhook.events = events;
-            }
-            if let Some(secret) = updates.secret {
-                webhook.secret = Some(secret);
-            }
-            if let Some(headers) = updates.headers {
-                webhook.headers = headers;
-            }
-            if let Some(retry_config) = updates.retry_config {
-                webhook.retry_config = retry_config;
-            }
-            if let Some(status) = updates.status {
-                webhook.status = status;
-            }
-            webhook.updated_at = Utc::now();
-            
-            info!("Updated webhook: {}", webhook_id);
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Webhook not found: {}", webhook_id))
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        if self.database.get_webhook(id).await?.is_none() {
+            return Ok(false);
         }
+        self.database.delete_webhook(id).await?;
+        Ok(true)
     }
 
-    /// Delete webhook
-    pub async fn delete_webhook(&self, webhook_id: &str) -> Result<()> {
-        let mut webhooks = self.webhooks.write().await;
-        webhooks.remove(webhook_id);
-        
-        let mut deliveries = self.deliveries.write().await;
-        deliveries.remove(webhook_id);
-        
-        info!("Deleted webhook: {}", webhook_id);
-        Ok(())
+    /// The newest deliveries of a webhook
+    pub async fn deliveries(&self, id: &str, limit: Option<u32>) -> Result<Option<Vec<WebhookDelivery>>> {
+        if self.database.get_webhook(id).await?.is_none() {
+            return Ok(None);
+        }
+        let limit = limit.unwrap_or(DEFAULT_DELIVERY_LIMIT).clamp(1, MAX_DELIVERY_LIMIT);
+        Ok(Some(self.database.get_webhook_deliveries(id, limit).await?))
     }
 
-    /// Publish event to webhooks
-    pub async fn publish_event(&self, event_type: &str, data: serde_json::Value, source: &str) -> Result<()> {
-        let event = WebhookEvent {
-            id: Uuid::new_v4().to_string(),
-            event_type: event_type.to_string(),
-            data: data.clone(),
-            timestamp: Utc::now(),
-            source: source.to_string(),
-            metadata: HashMap::new(),
+    /// Queue a `ping` delivery, sent on the next poll like any other event
+    pub async fn test(&self, id: &str) -> Result<Option<WebhookDelivery>> {
+        let Some(webhook) = self.database.get_webhook(id).await? else {
+            return Ok(None);
         };
+        let message = format!("Events for '{}' will arrive here.", webhook.name);
+        let delivery = self.queue(&webhook, WebhookEvent::Ping, None, None, message, serde_json::Value::Null, Utc::now()).await?;
+        Ok(Some(delivery))
+    }
 
-        // Store event
-        {
-            let mut events = self.events.write().await;
-            events.push(event.clone());
-            
-            // Keep only recent events (last 10000)
-            if events.len() > 10000 {
-                let keep_count = 10000;
-                let remove_count = events.len() - keep_count;
-                events.drain(0..remove_count);
+    /// Queue new events and send due deliveries every few seconds
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting webhook outbox");
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut last_pruned: Option<tokio::time::Instant> = None;
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.queue_events().await {
+                error!("Failed to queue webhook events: {}", e);
             }
-        }
-
-        // Find webhooks that subscribe to this event
-        let webhooks = self.webhooks.read().await;
-        for webhook in webhooks.values() {
-            if webhook.status == WebhookStatus::Active && 
-               webhook.events.contains(&event_type.to_string()) {
-                
-                // Queue delivery
-                let task = DeliveryTask {
-                    webhook_id: webhook.id.clone(),
-                    event: event.clone(),
-                    attempt: 1,
-                };
-                
-                let mut queue = self.delivery_queue.write().await;
-                queue.push(task);
+            if let Err(e) = self.deliver_due().await {
+                error!("Failed to send webhook deliveries: {}", e);
             }
-        }
-
-        info!("Published event: {} to {} webhooks", event_type, 
-              webhooks.values().filter(|w| w.events.contains(&event_type.to_string())).count());
-        Ok(())
-    }
-
-    /// Start delivery worker
-    async fn start_delivery_worker(&self) {
-        let webhooks = self.webhooks.clone();
-        let deliveries = self.deliveries.clone();
-        let client = self.client.clone();
-        let queue = self.delivery_queue.clone();
-        
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(100));
-            
-            loop {
-                interval.tick().await;
-                
-                // Process delivery queue
-                let tasks = {
-                    let mut queue_guard = queue.write().await;
-                    let tasks = queue_guard.drain(..).collect::<Vec<_>>();
-                    tasks
-                };
-                
-                for task in tasks {
-                    Self::deliver_webhook(&webhooks, &deliveries, &client, task).await;
+            if last_pruned.is_none_or(|at| at.elapsed() >= PRUNE_INTERVAL) {
+                last_pruned = Some(tokio::time::Instant::now());
+                match self.database.prune_webhook_deliveries(Utc::now() - RETENTION).await {
+                    Ok(0) => {}
+                    Ok(removed) => info!("Pruned {} old webhook deliveries", removed),
+                    Err(e) => error!("Failed to prune webhook deliveries: {}", e),
                 }
             }
-        });
+        }
     }
 
-    /// Start retry worker
-    async fn start_retry_worker(&self) {
-        let deliveries = self.deliveries.clone();
-        let queue = self.delivery_queue.clone();
-        
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
-            
-            loop {
-                interval.tick().await;
-                
-                // Check for retries
-                let now = Utc::now();
-                let mut retry_tasks = Vec::new();
-                
+    /// Turn events logged since the last poll into deliveries for subscribed webhooks
+    async fn queue_events(&self) -> Result<()> {
+        let since = *self.events_checked_until.read().await;
+        let event_types: Vec<&str> = EVENTS.iter().flat_map(|event| event.event_types().iter().copied()).collect();
+        let events = self.database.get_events_since(&event_types, since).await?;
+        let Some(last) = events.last() else {
+            return Ok(());
+        };
+        *self.events_checked_until.write().await = last.created_at;
+
+        let webhooks = self.database.get_webhooks().await?;
+        for event in events {
+            let Some(kind) = WebhookEvent::of(&event.event_type) else {
+                continue;
+            };
+            let targets: Vec<&Webhook> = webhooks.iter().filter(|webhook| subscribed(webhook, kind)).collect();
+            if targets.is_empty() {
+                continue;
+            }
+            let server_name = self.server_name(&event).await;
+            for webhook in targets {
+                let data = event.metadata.clone().unwrap_or(serde_json::Value::Null);
+                if let Err(e) = self
+                    .queue(webhook, kind, event.server_id.clone(), server_name.clone(), event.message.clone(), data, event.created_at)
+                    .await
                 {
-                    let deliveries_guard = deliveries.read().await;
-                    for (webhook_id, webhook_deliveries) in deliveries_guard.iter() {
-                        for delivery in webhook_deliveries {
-                            if delivery.status == DeliveryStatus::Retrying &&
-                               delivery.next_retry_at.map_or(false, |t| t <= now) {
-                                retry_tasks.push(DeliveryTask {
-                                    webhook_id: webhook_id.clone(),
-                                    event: WebhookEvent {
-                                        id: delivery.event_id.clone(),
-                                        event_type: "retry".to_string(),
-                                        data: serde_json::Value::Null,
-                                        timestamp: now,
-                                        source: "retry_worker".to_string(),
-                                        metadata: HashMap::new(),
-                                    },
-                                    attempt: delivery.attempt + 1,
-                                });
-                            }
-                        }
-                    }
-                }
-                
-                // Queue retry tasks
-                if !retry_tasks.is_empty() {
-                    let mut queue_guard = queue.write().await;
-                    queue_guard.extend(retry_tasks);
+                    error!("Failed to queue {} for webhook {}: {}", kind.as_str(), webhook.id, e);
                 }
             }
-        });
+        }
+        Ok(())
     }
 
-    /// Deliver webhook
-    async fn deliver_webhook(
-        webhooks: &Arc<RwLock<HashMap<String, Webhook>>>,
-        deliveries: &Arc<RwLock<HashMap<String, Vec<WebhookDelivery>>>>,
-        client: &Client,
-        task: DeliveryTask,
-    ) {
-        let webhook = {
-            let webhooks_guard = webhooks.read().await;
-            webhooks_guard.get(&task.webhook_id).cloned()
-        };
+    async fn server_name(&self, event: &EventLog) -> Option<String> {
+        let server_id = event.server_id.as_deref()?;
+        self.database.get_server(server_id).await.ok().flatten().map(|server| server.name)
+    }
 
-        let webhook = match webhook {
-            Some(w) => w,
-            None => {
-                error!("Webhook not found: {}", task.webhook_id);
-                return;
-            }
+    #[allow(clippy::too_many_arguments)]
+    async fn queue(
+        &self,
+        webhook: &Webhook,
+        event: WebhookEvent,
+        server_id: Option<String>,
+        server_name: Option<String>,
+        message: String,
+        data: serde_json::Value,
+        occurred_at: DateTime<Utc>,
+    ) -> Result<WebhookDelivery> {
+        let now = Utc::now();
+        let id = Uuid::new_v4().to_string();
+        let payload = WebhookPayload {
+            id: id.clone(),
+            event: event.as_str().to_string(),
+            occurred_at,
+            server_id,
+            server_name,
+            message,
+            data,
         };
-
-        // Create delivery record
-        let delivery_id = Uuid::new_v4().to_string();
-        let mut delivery = WebhookDelivery {
-            id: delivery_id.clone(),
-            webhook_id: task.webhook_id.clone(),
-            event_id: task.event.id.clone(),
-            attempt: task.attempt,
+        let delivery = WebhookDelivery {
+            id,
+            webhook_id: webhook.id.clone(),
+            event: event.as_str().to_string(),
+            payload: serde_json::to_value(&payload)?,
             status: DeliveryStatus::Pending,
-            response_code: None,
-            response_body: None,
-            error_message: None,
-            delivered_at: None,
-            next_retry_at: None,
+            attempts: 0,
+            next_attempt_at: Some(now),
+            response_status: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
         };
+        self.database.create_webhook_delivery(&delivery).await?;
+        Ok(delivery)
+    }
 
-        // Prepare request
-        let mut request = client.post(&webhook.url);
-        
-        // Add headers
-        for (key, value) in &webhook.headers {
-            request = request.header(key, value);
+    async fn deliver_due(&self) -> Result<()> {
+        let due = self.database.get_due_webhook_deliveries(Utc::now(), BATCH_SIZE).await?;
+        if due.is_empty() {
+            return Ok(());
         }
-        
-        // Add default headers
-        request = request
-            .header("Content-Type", "application/json")
-            .header("User-Agent", "Guardian-Webhook/1.0")
-            .header("X-Webhook-Event", &task.event.event_type)
-            .header("X-Webhook-ID", &task.event.id)
-            .header("X-Webhook-Timestamp", task.event.timestamp.to_rfc3339());
-
-        // Add signature if secret is configured
-        if let Some(secret) = &webhook.secret {
-            let payload = serde_json::to_string(&task.event).unwrap_or_default();
-            let signature = Self::generate_signature(&payload, secret);
-            request = request.header("X-Webhook-Signature", signature);
-        }
-
-        // Set request body
-        request = request.json(&task.event);
-
-        // Send request
-        let start_time = std::time::Instant::now();
-        let result = request.send().await;
-        let duration = start_time.elapsed();
+        let webhooks = self.database.get_webhooks().await?;
+        let attempts = due.into_iter().map(|delivery| {
+            let webhook = webhooks.iter().find(|webhook| webhook.id == delivery.webhook_id);
+            self.attempt(webhook, delivery)
+        });
+        futures::future::join_all(attempts).await;
+        Ok(())
+    }
 
-        // Process response
+    /// Send one delivery and record the outcome, scheduling a retry if it failed
+    async fn attempt(&self, webhook: Option<&Webhook>, mut delivery: WebhookDelivery) {
+        let result = match webhook {
+            Some(webhook) if webhook.enabled => self.send(webhook, &delivery).await,
+            Some(_) => Err((None, "Webhook is disabled".to_string())),
+            None => Err((None, "Webhook was deleted".to_string())),
+        };
+        let now = Utc::now();
+        delivery.attempts += 1;
+        delivery.updated_at = now;
         match result {
-            Ok(response) => {
-                let status = response.status();
-                let response_text = response.text().await.unwrap_or_default();
-                
-                delivery.status = if status.is_success() {
-                    DeliveryStatus::Delivered
-                } else {
-                    DeliveryStatus::Failed
-                };
-                delivery.response_code = Some(status.as_u16());
-                delivery.response_body = Some(response_text);
-                delivery.delivered_at = Some(Utc::now());
-                
-                if !status.is_success() && task.attempt < webhook.retry_config.max_attempts {
-                    delivery.status = DeliveryStatus::Retrying;
-                    delivery.next_retry_at = Some(Utc::now() + 
-                        chrono::Duration::milliseconds(Self::calculate_retry_delay(
-                            task.attempt,
-                            &webhook.retry_config
-                        )));
-                }
-                
-                info!("Webhook delivered: {} ({}ms, {})", 
-                      webhook.name, duration.as_millis(), status);
+            Ok(status) => {
+                delivery.status = DeliveryStatus::Delivered;
+                delivery.response_status = Some(status);
+                delivery.error = None;
+                delivery.next_attempt_at = None;
             }
-            Err(e) => {
-                delivery.status = if task.attempt < webhook.retry_config.max_attempts {
-                    DeliveryStatus::Retrying
-                } else {
-                    DeliveryStatus::Failed
-                };
-                delivery.error_message = Some(e.to_string());
-                
-                if delivery.status == DeliveryStatus::Retrying {
-                    delivery.next_retry_at = Some(Utc::now() + 
-                        chrono::Duration::milliseconds(Self::calculate_retry_delay(
-                            task.attempt,
-                            &webhook.retry_config
-                        )));
+            Err((status, message)) => {
+                delivery.response_status = status;
+                delivery.error = Some(message);
+                let retry = retry_delay(delivery.attempts).filter(|_| webhook.is_some_and(|webhook| webhook.enabled));
+                match retry {
+                    Some(delay) => delivery.next_attempt_at = Some(now + delay),
+                    None => {
+                        delivery.status = DeliveryStatus::Failed;
+                        delivery.next_attempt_at = None;
+                    }
                 }
-                
-                error!("Webhook delivery failed: {} - {}", webhook.name, e);
+                warn!(
+                    "Webhook delivery {} ({}) failed on attempt {}: {}",
+                    delivery.id,
+                    delivery.event,
+                    delivery.attempts,
+                    delivery.error.as_deref().unwrap_or_default()
+                );
             }
         }
-
-        // Store delivery record
-        {
-            let mut deliveries_guard = deliveries.write().await;
-            let webhook_deliveries = deliveries_guard.entry(task.webhook_id).or_insert_with(Vec::new);
-            webhook_deliveries.push(delivery);
-            
-            // Keep only recent deliveries (last 1000 per webhook)
-            if webhook_deliveries.len() > 1000 {
-                webhook_deliveries.drain(0..webhook_deliveries.len() - 1000);
-            }
+        if let Err(e) = self.database.update_webhook_delivery(&delivery).await {
+            error!("Failed to record webhook delivery {}: {}", delivery.id, e);
         }
     }
 
-    /// Generate webhook signature
-    fn generate_signature(payload: &str, secret: &str) -> String {
-        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
-            .expect("HMAC can take key of any size");
-        mac.update(payload.as_bytes());
-        let result = mac.finalize();
-        base64::engine::general_purpose::STANDARD.encode(result.into_bytes())
-    }
+    /// POST the payload, returning the HTTP status, or the status if any and the error
+    async fn send(&self, webhook: &Webhook, delivery: &WebhookDelivery) -> Result<u16, (Option<u16>, String)> {
+        let body = delivery.payload.to_string();
+        let timestamp = Utc::now().timestamp();
+        let mut request = self
+            .client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(reqwest::header::USER_AGENT, concat!("Guardian-Webhook/", env!("CARGO_PKG_VERSION")))
+            .header(EVENT_HEADER, &delivery.event)
+            .header(DELIVERY_HEADER, &delivery.id)
+            .header(TIMESTAMP_HEADER, timestamp.to_string());
+        if let Some(secret) = &webhook.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, timestamp, &body));
+        }
 
-    /// Calculate retry delay with exponential backoff
-    fn calculate_retry_delay(attempt: u32, config: &RetryConfig) -> i64 {
-        let delay = (config.initial_delay_ms as f64 * 
-                    config.backoff_multiplier.powi(attempt as i32 - 1)) as i64;
-        delay.min(config.max_delay_ms as i64)
+        let response = request.body(body).send().await.map_err(|e| (None, e.to_string()))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(status.as_u16());
+        }
+        let text = response.text().await.unwrap_or_default();
+        let text: String = text.chars().take(MAX_ERROR_LENGTH).collect();
+        Err((Some(status.as_u16()), format!("{} returned {}: {}", webhook.url, status, text.trim())))
     }
+}
 
-    /// Get webhook deliveries
-    pub async fn get_webhook_deliveries(&self, webhook_id: &str, limit: usize) -> Vec<WebhookDelivery> {
-        let deliveries = self.deliveries.read().await;
-        if let Some(webhook_deliveries) = deliveries.get(webhook_id) {
-            webhook_deliveries.iter()
-                .rev()
-                .take(limit)
-                .cloned()
-                .collect()
-        } else {
-            Vec::new()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_map_from_event_log() {
+        for event in EVENTS {
+            assert_eq!(WebhookEvent::parse(event.as_str()), Some(event));
         }
+        assert_eq!(WebhookEvent::of("crash_loop"), Some(WebhookEvent::ServerCrashed));
+        assert_eq!(WebhookEvent::of("player_joined"), Some(WebhookEvent::PlayerJoined));
+        assert_eq!(WebhookEvent::of("console"), None);
+        assert_eq!(WebhookEvent::parse("ping"), None);
+        assert!(validate_events(&["server.started".to_string(), "backup.failed".to_string()]).is_ok());
+        assert!(validate_events(&["server_started".to_string()]).is_err());
     }
 
-    /// Get webhook events
-    pub async fn get_webhook_events(&self, limit: usize) -> Vec<WebhookEvent> {
-        let events = self.events.read().await;
-        events.iter()
-            .rev()
-            .take(limit)
-            .cloned()
-            .collect()
+    #[test]
+    fn test_signature() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            hmac_hex("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(sign("key", 1700000000, "{}"), format!("sha256={}", hmac_hex("key", "1700000000.{}")));
     }
 
-    /// Test webhook
-    pub async fn test_webhook(&self, webhook_id: &str) -> Result<()> {
-        let test_event = WebhookEvent {
-            id: Uuid::new_v4().to_string(),
-            event_type: "test".to_string(),
-            data: serde_json::json!({
-                "message": "This is a test webhook from Guardian",
-                "timestamp": Utc::now().to_rfc3339(),
-            }),
-            timestamp: Utc::now(),
-            source: "webhook_test".to_string(),
-            metadata: HashMap::new(),
-        };
-
-        self.publish_event("test", test_event.data, "webhook_test").await?;
-        info!("Test webhook sent: {}", webhook_id);
-        Ok(())
+    #[test]
+    fn test_retry_backoff() {
+        let delays: Vec<i64> = (1..MAX_ATTEMPTS).map(|attempts| retry_delay(attempts).unwrap().num_seconds()).collect();
+        assert_eq!(delays, [30, 60, 120, 240, 480, 960, 1920]);
+        assert_eq!(retry_delay(MAX_ATTEMPTS), None);
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct WebhookUpdate {
-    pub name: Option<String>,
-    pub url: Option<String>,
-    pub events: Option<Vec<String>>,
-    pub secret: Option<String>,
-    pub headers: Option<HashMap<String, String>>,
-    pub retry_config: Option<RetryConfig>,
-    pub status: Option<WebhookStatus>,
-}
-
-impl Default for RetryConfig {
-    fn default() -> Self {
-        Self {
-            max_attempts: 3,
-            initial_delay_ms: 1000,
-            max_delay_ms: 30000,
-            backoff_multiplier: 2.0,
-        }
+    #[test]
+    fn test_subscription_filter() {
+        let mut webhook = Webhook {
+            id: "w".to_string(),
+            name: "Ops".to_string(),
+            url: "https://example.com/hook".to_string(),
+            secret: None,
+            events: Vec::new(),
+            enabled: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert!(subscribed(&webhook, WebhookEvent::PlayerLeft));
+        webhook.events = vec!["server.crashed".to_string()];
+        assert!(subscribed(&webhook, WebhookEvent::ServerCrashed));
+        assert!(!subscribed(&webhook, WebhookEvent::PlayerLeft));
+        webhook.enabled = false;
+        assert!(!subscribed(&webhook, WebhookEvent::ServerCrashed));
     }
 }
@@ -1,20 +1,24 @@
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        ConnectInfo, State,
     },
+    http::{HeaderMap, Method, Uri},
     response::Response,
 };
 use futures_util::{sink::SinkExt, stream::StreamExt};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tokio::sync::{broadcast, RwLock};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tokio::time::{Duration, Instant};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::core::middleware::{AuthContext, AuthState};
+use crate::event_bus::EventBus;
+
 /// WebSocket message types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -36,6 +40,9 @@ pub enum WebSocketMessage {
         players_online: u32,
         memory_usage_mb: f64,
         cpu_usage_percent: f64,
+        /// CPU affinity, priority and memory cap in force, if any were set
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        resource_limits: Option<serde_json::Value>,
     },
     /// Player join/leave event
     PlayerEvent {
@@ -132,14 +139,148 @@ pub enum WebSocketMessage {
         error: String,
         timestamp: DateTime<Utc>,
     },
+    /// Condition that needs an operator's attention, e.g. a crash loop
+    Alert {
+        server_id: String,
+        timestamp: DateTime<Utc>,
+        level: String,      // "info", "warn", "error"
+        alert_type: String, // "crash_loop", ...
+        message: String,
+        recommended_action: Option<String>,
+    },
+    /// Topics the connection is now subscribed to
+    Subscribed {
+        topics: Vec<String>,
+        timestamp: DateTime<Utc>,
+    },
+    /// Sent after the messages of a replay
+    ReplayComplete {
+        topics: Vec<String>,
+        count: usize,
+        timestamp: DateTime<Utc>,
+    },
+}
+
+/// Message kinds a topic can name, alone for every server or as `server:{id}:{kind}`
+pub const TOPIC_KINDS: [&str; 8] = ["console", "metrics", "players", "status", "freezes", "pregen", "jobs", "alerts"];
+
+/// Messages kept per topic for replay
+pub const REPLAY_PER_TOPIC: usize = 100;
+
+impl WebSocketMessage {
+    /// Topic the message is published on: `server:{id}:{kind}` for messages
+    /// about a server, else the bare kind. Replies to one connection have none.
+    pub fn topic(&self) -> Option<String> {
+        let (server_id, kind) = match self {
+            Self::ConsoleMessage { server_id, .. } => (Some(server_id), "console"),
+            Self::MetricsUpdate { server_id, .. } => (Some(server_id), "metrics"),
+            Self::PlayerEvent { server_id, .. } => (Some(server_id), "players"),
+            Self::ServerStatusChange { server_id, .. } => (Some(server_id), "status"),
+            Self::WorldFreeze { server_id, .. } => (Some(server_id), "freezes"),
+            Self::PregenProgress { server_id, .. } => (Some(server_id), "pregen"),
+            Self::ProgressEvent { server_id, .. }
+            | Self::JobStarted { server_id, .. }
+            | Self::JobProgress { server_id, .. }
+            | Self::JobCompleted { server_id, .. }
+            | Self::JobFailed { server_id, .. } => (server_id.as_ref(), "jobs"),
+            Self::Alert { server_id, .. } => (Some(server_id), "alerts"),
+            Self::Ping { .. } | Self::Pong { .. } | Self::Error { .. } | Self::Subscribed { .. } | Self::ReplayComplete { .. } => {
+                return None
+            }
+        };
+        Some(match server_id {
+            Some(server_id) => format!("server:{}:{}", server_id, kind),
+            None => kind.to_string(),
+        })
+    }
+}
+
+/// Whether a client may subscribe to `topic`: `all`, a kind, `server:{id}` or
+/// `server:{id}:{kind}`
+pub fn is_valid_topic(topic: &str) -> bool {
+    if topic == "all" || TOPIC_KINDS.contains(&topic) {
+        return true;
+    }
+    let Some(rest) = topic.strip_prefix("server:") else {
+        return false;
+    };
+    match rest.split_once(':') {
+        Some((server_id, kind)) => !server_id.is_empty() && TOPIC_KINDS.contains(&kind),
+        None => !rest.is_empty(),
+    }
+}
+
+/// Server a `server:{id}` or `server:{id}:{kind}` topic is about
+pub fn topic_server(topic: &str) -> Option<&str> {
+    topic.strip_prefix("server:")?.split(':').next().filter(|server_id| !server_id.is_empty())
+}
+
+/// Whether a client limited to `server_ids` may see messages on `topic`.
+/// `None` means every server; a limited client only sees its servers' topics.
+pub fn topic_visible(server_ids: Option<&[String]>, topic: &str) -> bool {
+    match server_ids {
+        Some(server_ids) => topic_server(topic).is_some_and(|server_id| server_ids.iter().any(|id| id == server_id)),
+        None => true,
+    }
+}
+
+/// Whether a subscription to `subscription` covers messages published on `topic`
+pub fn topic_matches(subscription: &str, topic: &str) -> bool {
+    if subscription == "all" || subscription == topic {
+        return true;
+    }
+    if subscription.starts_with("server:") {
+        // server:{id} covers every kind of that server
+        return topic.strip_prefix(subscription).is_some_and(|rest| rest.starts_with(':'));
+    }
+    topic.rsplit(':').next() == Some(subscription)
+}
+
+/// The last messages of each topic, kept so clients can catch up after reconnecting
+#[derive(Debug, Default)]
+pub struct ReplayBuffer {
+    next_seq: u64,
+    topics: HashMap<String, VecDeque<(u64, WebSocketMessage)>>,
+}
+
+impl ReplayBuffer {
+    pub fn record(&mut self, topic: String, message: WebSocketMessage) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let messages = self.topics.entry(topic).or_default();
+        if messages.len() == REPLAY_PER_TOPIC {
+            messages.pop_front();
+        }
+        messages.push_back((seq, message));
+    }
+
+    /// The last `count` messages of every topic covered by `subscriptions`,
+    /// oldest first
+    pub fn replay(&self, subscriptions: &[String], count: usize) -> Vec<WebSocketMessage> {
+        let mut messages: Vec<&(u64, WebSocketMessage)> = self
+            .topics
+            .iter()
+            .filter(|(topic, _)| subscriptions.iter().any(|subscription| topic_matches(subscription, topic)))
+            .flat_map(|(_, messages)| messages.iter().skip(messages.len().saturating_sub(count)))
+            .collect();
+        messages.sort_by_key(|(seq, _)| *seq);
+        messages.into_iter().map(|(_, message)| message.clone()).collect()
+    }
 }
 
 /// WebSocket connection information
 #[derive(Debug, Clone)]
 pub struct WebSocketConnection {
     pub id: String,
+    /// Who opened the connection
+    pub auth: AuthContext,
     pub server_id: Option<String>,
     pub subscribed_events: Vec<String>,
+    /// Topics from the subscription handshake; once any are set, only
+    /// messages on these topics are sent
+    pub topics: HashSet<String>,
+    /// Messages for this connection alone, such as replies and replays
+    pub outbox: mpsc::UnboundedSender<WebSocketMessage>,
     pub last_ping: DateTime<Utc>,
     pub last_pong: DateTime<Utc>,
     pub connection_time: Instant,
@@ -157,8 +298,8 @@ pub struct WebSocketManager {
     pub broadcast_tx: broadcast::Sender<WebSocketMessage>,
     /// Global sender for broadcasting messages
     pub global_sender: broadcast::Sender<WebSocketMessage>,
-    /// Server-specific broadcast channels
-    pub server_channels: Arc<RwLock<HashMap<String, broadcast::Sender<WebSocketMessage>>>>,
+    /// Bus the broadcast channel belongs to, shared with other managers and SSE
+    pub event_bus: EventBus,
     /// Heartbeat configuration
     pub heartbeat_interval: Duration,
     pub heartbeat_timeout: Duration,
@@ -168,13 +309,18 @@ pub struct WebSocketManager {
 
 impl WebSocketManager {
     pub fn new() -> Self {
-        let (broadcast_tx, _) = broadcast::channel(1000);
+        Self::with_event_bus(EventBus::new())
+    }
+
+    /// A manager that publishes to and sends from `event_bus`
+    pub fn with_event_bus(event_bus: EventBus) -> Self {
+        let broadcast_tx = event_bus.sender();
         
         Self {
             connections: Arc::new(RwLock::new(HashMap::new())),
             broadcast_tx: broadcast_tx.clone(),
             global_sender: broadcast_tx,
-            server_channels: Arc::new(RwLock::new(HashMap::new())),
+            event_bus,
             heartbeat_interval: Duration::from_secs(30), // Send ping every 30 seconds
             heartbeat_timeout: Duration::from_secs(60),  // Consider dead if no pong for 60 seconds
             max_reconnect_attempts: 5,
@@ -182,26 +328,39 @@ impl WebSocketManager {
         }
     }
 
-    /// Handle WebSocket upgrade
+    /// Handle WebSocket upgrade. The upgrade is authenticated like an API
+    /// request, with a token in the `Authorization` header or `access_token`
+    /// query parameter since browsers cannot set headers on WebSockets.
     pub async fn handle_websocket(
         ws: WebSocketUpgrade,
-        State(manager): State<Arc<WebSocketManager>>,
+        State((manager, auth)): State<(Arc<WebSocketManager>, AuthState)>,
+        connect_info: Option<ConnectInfo<std::net::SocketAddr>>,
+        headers: HeaderMap,
+        uri: Uri,
     ) -> Response {
-        ws.on_upgrade(|socket| manager.handle_socket(socket))
+        let peer = connect_info.map(|info| info.0);
+        match auth.authenticate(&headers, uri.query(), peer, &Method::GET, uri.path()).await {
+            Ok(auth_context) => ws.on_upgrade(move |socket| manager.handle_socket(socket, auth_context)),
+            Err(response) => response,
+        }
     }
 
     /// Handle individual WebSocket connection
-    pub async fn handle_socket(self: Arc<Self>, socket: WebSocket) {
+    pub async fn handle_socket(self: Arc<Self>, socket: WebSocket, auth: AuthContext) {
         let connection_id = Uuid::new_v4().to_string();
         let mut rx = self.broadcast_tx.subscribe();
+        let (outbox, mut inbox) = mpsc::unbounded_channel();
         
         // Register connection
         {
             let mut connections = self.connections.write().await;
             connections.insert(connection_id.clone(), WebSocketConnection {
                 id: connection_id.clone(),
+                auth,
                 server_id: None,
                 subscribed_events: vec!["all".to_string()],
+                topics: HashSet::new(),
+                outbox,
                 last_ping: Utc::now(),
                 last_pong: Utc::now(),
                 connection_time: Instant::now(),
@@ -214,64 +373,82 @@ impl WebSocketManager {
         let (sender, mut receiver) = socket.split();
         let sender = Arc::new(Mutex::new(sender));
 
-        // Spawn task to handle incoming messages
+        // Spawn task to send messages to this connection: broadcasts it is
+        // subscribed to, and messages meant for it alone
         let manager_clone = self.clone();
         let connection_id_clone = connection_id.clone();
         let sender_clone = sender.clone();
-        tokio::spawn(async move {
-            while let Some(msg) = receiver.next().await {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        if let Err(e) = manager_clone.handle_message(&connection_id_clone, &text).await {
-                            tracing::error!("Error handling WebSocket message: {}", e);
+        let send_task = tokio::spawn(async move {
+            loop {
+                let msg = tokio::select! {
+                    msg = inbox.recv() => match msg {
+                        Some(msg) => msg,
+                        None => break,
+                    },
+                    msg = rx.recv() => match msg {
+                        Ok(msg) => {
+                            let connections = manager_clone.connections.read().await;
+                            let Some(connection) = connections.get(&connection_id_clone) else {
+                                break;
+                            };
+                            // Check if this connection should receive this message
+                            if !manager_clone.should_send_message(connection, &msg).await {
+                                continue;
+                            }
+                            msg
                         }
-                    }
-                    Ok(Message::Ping(payload)) => {
-                        let mut sender_guard = sender_clone.lock().await;
-                        if let Err(e) = sender_guard.send(Message::Pong(payload)).await {
-                            tracing::error!("Error sending pong: {}", e);
-                            break;
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!("WebSocket connection {} skipped {} messages", connection_id_clone, skipped);
+                            continue;
                         }
-                    }
-                    Ok(Message::Close(_)) => {
-                        break;
-                    }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                };
+
+                let json = match serde_json::to_string(&msg) {
+                    Ok(json) => json,
                     Err(e) => {
-                        tracing::error!("WebSocket error: {}", e);
-                        break;
+                        tracing::error!("Error serializing WebSocket message: {}", e);
+                        continue;
                     }
-                    _ => {}
+                };
+
+                let mut sender_guard = sender_clone.lock().await;
+                if let Err(e) = sender_guard.send(Message::Text(json)).await {
+                    tracing::error!("Error sending WebSocket message: {}", e);
+                    break;
                 }
             }
         });
 
-        // Spawn task to send messages to this connection
-        let manager_clone = self.clone();
-        let connection_id_clone = connection_id.clone();
-        tokio::spawn(async move {
-            while let Ok(msg) = rx.recv().await {
-                if let Some(connection) = manager_clone.connections.read().await.get(&connection_id_clone) {
-                    // Check if this connection should receive this message
-                    if manager_clone.should_send_message(connection, &msg).await {
-                        let json = match serde_json::to_string(&msg) {
-                            Ok(json) => json,
-                            Err(e) => {
-                                tracing::error!("Error serializing WebSocket message: {}", e);
-                                continue;
-                            }
-                        };
-
-                        let mut sender_guard = sender.lock().await;
-                        if let Err(e) = sender_guard.send(Message::Text(json)).await {
-                            tracing::error!("Error sending WebSocket message: {}", e);
-                            break;
-                        }
+        // Handle incoming messages until the client goes away
+        while let Some(msg) = receiver.next().await {
+            match msg {
+                Ok(Message::Text(text)) => {
+                    if let Err(e) = self.handle_message(&connection_id, &text).await {
+                        tracing::error!("Error handling WebSocket message: {}", e);
+                    }
+                }
+                Ok(Message::Ping(payload)) => {
+                    let mut sender_guard = sender.lock().await;
+                    if let Err(e) = sender_guard.send(Message::Pong(payload)).await {
+                        tracing::error!("Error sending pong: {}", e);
+                        break;
                     }
                 }
+                Ok(Message::Close(_)) => {
+                    break;
+                }
+                Err(e) => {
+                    tracing::error!("WebSocket error: {}", e);
+                    break;
+                }
+                _ => {}
             }
-        });
+        }
 
         // Cleanup on disconnect
+        send_task.abort();
         {
             let mut connections = self.connections.write().await;
             connections.remove(&connection_id);
@@ -281,6 +458,9 @@ impl WebSocketManager {
     /// Handle incoming WebSocket message
     async fn handle_message(&self, connection_id: &str, text: &str) -> Result<(), Box<dyn std::error::Error>> {
         let message: serde_json::Value = serde_json::from_str(text)?;
+        let topics: Option<Vec<String>> = message.get("topics").and_then(|t| t.as_array()).map(|topics| {
+            topics.iter().filter_map(|t| t.as_str().map(|s| s.to_string())).collect()
+        });
         
         match message.get("type").and_then(|t| t.as_str()) {
             Some("ping") => {
@@ -291,16 +471,63 @@ impl WebSocketManager {
             Some("pong") => {
                 self.handle_pong(connection_id).await;
             }
+            Some("subscribe") if topics.is_some() => {
+                let topics = topics.unwrap_or_default();
+                if let Some(invalid) = topics.iter().find(|topic| !is_valid_topic(topic)) {
+                    self.send_to_connection(connection_id, WebSocketMessage::Error {
+                        message: format!("Unknown topic: {}", invalid),
+                        timestamp: Utc::now(),
+                    }).await?;
+                    return Ok(());
+                }
+                let server_ids = self.server_scope(connection_id).await;
+                if let Some(denied) = topics.iter().find(|topic| {
+                    topic_server(topic).is_some() && !topic_visible(server_ids.as_deref(), topic)
+                }) {
+                    self.send_to_connection(connection_id, WebSocketMessage::Error {
+                        message: format!("No access to topic: {}", denied),
+                        timestamp: Utc::now(),
+                    }).await?;
+                    return Ok(());
+                }
+                let subscribed = self.subscribe_to_topics(connection_id, &topics).await?;
+                self.send_to_connection(connection_id, WebSocketMessage::Subscribed {
+                    topics: subscribed,
+                    timestamp: Utc::now(),
+                }).await?;
+                if let Some(count) = message.get("replay").and_then(|c| c.as_u64()) {
+                    self.replay(connection_id, topics, count as usize).await?;
+                }
+            }
             Some("subscribe") => {
                 if let Some(server_id) = message.get("server_id").and_then(|s| s.as_str()) {
                     self.subscribe_to_server(connection_id, server_id).await?;
                 }
             }
+            Some("unsubscribe") if topics.is_some() => {
+                let subscribed = self.unsubscribe_from_topics(connection_id, &topics.unwrap_or_default()).await?;
+                self.send_to_connection(connection_id, WebSocketMessage::Subscribed {
+                    topics: subscribed,
+                    timestamp: Utc::now(),
+                }).await?;
+            }
             Some("unsubscribe") => {
                 if let Some(server_id) = message.get("server_id").and_then(|s| s.as_str()) {
                     self.unsubscribe_from_server(connection_id, server_id).await?;
                 }
             }
+            Some("replay") => {
+                // Without topics, replay everything the connection is subscribed to
+                let topics = match topics {
+                    Some(topics) => topics,
+                    None => self.connections.read().await
+                        .get(connection_id)
+                        .map(|connection| connection.topics.iter().cloned().collect())
+                        .unwrap_or_default(),
+                };
+                let count = message.get("count").and_then(|c| c.as_u64()).unwrap_or(REPLAY_PER_TOPIC as u64);
+                self.replay(connection_id, topics, count as usize).await?;
+            }
             Some("set_events") => {
                 if let Some(events) = message.get("events").and_then(|e| e.as_array()) {
                     let event_list: Vec<String> = events.iter()
@@ -322,6 +549,20 @@ impl WebSocketManager {
 
     /// Check if a connection should receive a specific message
     async fn should_send_message(&self, connection: &WebSocketConnection, msg: &WebSocketMessage) -> bool {
+        // Users limited to some servers only see messages about those
+        if let Some(server_ids) = connection.auth.server_scope() {
+            if !msg.topic().is_some_and(|topic| topic_visible(Some(server_ids), &topic)) {
+                return false;
+            }
+        }
+
+        // After the subscription handshake only the subscribed topics count
+        if !connection.topics.is_empty() {
+            return msg.topic().is_some_and(|topic| {
+                connection.topics.iter().any(|subscription| topic_matches(subscription, &topic))
+            });
+        }
+
         // Check if connection is subscribed to all events
         if connection.subscribed_events.contains(&"all".to_string()) {
             return true;
@@ -336,6 +577,7 @@ impl WebSocketManager {
             WebSocketMessage::WorldFreeze { server_id, .. } => Some(server_id.to_string()),
             WebSocketMessage::PregenProgress { server_id, .. } => Some(server_id.to_string()),
             WebSocketMessage::ProgressEvent { server_id, .. } => server_id.clone(),
+            WebSocketMessage::Alert { server_id, .. } => Some(server_id.to_string()),
             _ => None,
         };
 
@@ -362,6 +604,8 @@ impl WebSocketManager {
             WebSocketMessage::JobProgress { .. } => "jobs",
             WebSocketMessage::JobCompleted { .. } => "jobs",
             WebSocketMessage::JobFailed { .. } => "jobs",
+            WebSocketMessage::Alert { .. } => "alerts",
+            WebSocketMessage::Subscribed { .. } | WebSocketMessage::ReplayComplete { .. } => "subscriptions",
         };
 
         connection.subscribed_events.contains(&event_type.to_string())
@@ -369,9 +613,9 @@ impl WebSocketManager {
 
     /// Send message to a specific connection
     pub async fn send_to_connection(&self, connection_id: &str, message: WebSocketMessage) -> Result<(), Box<dyn std::error::Error>> {
-        // This would be implemented with a proper message queue
-        // For now, we'll use the broadcast channel
-        self.broadcast_tx.send(message)?;
+        let connections = self.connections.read().await;
+        let connection = connections.get(connection_id).ok_or("Connection not found")?;
+        connection.outbox.send(message)?;
         Ok(())
     }
 
@@ -401,6 +645,50 @@ impl WebSocketManager {
         Ok(())
     }
 
+    /// Add topics to a connection's subscriptions, returning all of them
+    async fn subscribe_to_topics(&self, connection_id: &str, topics: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut connections = self.connections.write().await;
+        let connection = connections.get_mut(connection_id).ok_or("Connection not found")?;
+        connection.topics.extend(topics.iter().cloned());
+        let mut subscribed: Vec<String> = connection.topics.iter().cloned().collect();
+        subscribed.sort();
+        Ok(subscribed)
+    }
+
+    /// Remove topics from a connection's subscriptions, returning those left
+    async fn unsubscribe_from_topics(&self, connection_id: &str, topics: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut connections = self.connections.write().await;
+        let connection = connections.get_mut(connection_id).ok_or("Connection not found")?;
+        connection.topics.retain(|topic| !topics.contains(topic));
+        let mut subscribed: Vec<String> = connection.topics.iter().cloned().collect();
+        subscribed.sort();
+        Ok(subscribed)
+    }
+
+    /// Servers a connection's user is limited to, `None` for every server
+    async fn server_scope(&self, connection_id: &str) -> Option<Vec<String>> {
+        let connections = self.connections.read().await;
+        connections.get(connection_id)?.auth.server_scope().map(<[String]>::to_vec)
+    }
+
+    /// Send a connection the last `count` messages of each of `topics` it may see
+    async fn replay(&self, connection_id: &str, topics: Vec<String>, count: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let server_ids = self.server_scope(connection_id).await;
+        let messages: Vec<WebSocketMessage> = self.event_bus.replay(&topics, count.min(REPLAY_PER_TOPIC)).await
+            .into_iter()
+            .filter(|message| message.topic().is_some_and(|topic| topic_visible(server_ids.as_deref(), &topic)))
+            .collect();
+        let count = messages.len();
+        for message in messages {
+            self.send_to_connection(connection_id, message).await?;
+        }
+        self.send_to_connection(connection_id, WebSocketMessage::ReplayComplete {
+            topics,
+            count,
+            timestamp: Utc::now(),
+        }).await
+    }
+
     /// Set subscribed events for a connection
     async fn set_subscribed_events(&self, connection_id: &str, events: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
         let mut connections = self.connections.write().await;
@@ -410,27 +698,17 @@ impl WebSocketManager {
         Ok(())
     }
 
-    /// Broadcast message to all connections
+    /// Broadcast message to all connections and SSE clients, keeping it for
+    /// replay. Having no one to send to is not an error.
     pub async fn broadcast(&self, message: WebSocketMessage) -> Result<(), Box<dyn std::error::Error>> {
-        self.broadcast_tx.send(message)?;
+        self.event_bus.publish(message).await;
         Ok(())
     }
 
-    /// Broadcast message to connections subscribed to a specific server
-    pub async fn broadcast_to_server(&self, server_id: &str, message: WebSocketMessage) -> Result<(), Box<dyn std::error::Error>> {
-        // Get or create server-specific channel
-        let server_tx = {
-            let mut channels = self.server_channels.write().await;
-            channels.entry(server_id.to_string())
-                .or_insert_with(|| {
-                    let (tx, _) = broadcast::channel(100);
-                    tx
-                })
-                .clone()
-        };
-        
-        server_tx.send(message)?;
-        Ok(())
+    /// Broadcast message about a specific server; connections receive it
+    /// according to their subscriptions
+    pub async fn broadcast_to_server(&self, _server_id: &str, message: WebSocketMessage) -> Result<(), Box<dyn std::error::Error>> {
+        self.broadcast(message).await
     }
 
     /// Get connection count
@@ -466,6 +744,7 @@ impl WebSocketManager {
         let players_online = metrics.get("players_online").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
         let memory_usage_mb = metrics.get("memory_usage_mb").and_then(|v| v.as_f64()).unwrap_or(0.0);
         let cpu_usage_percent = metrics.get("cpu_usage_percent").and_then(|v| v.as_f64()).unwrap_or(0.0);
+        let resource_limits = metrics.get("resource_limits").filter(|v| !v.is_null()).cloned();
 
         let message = WebSocketMessage::MetricsUpdate {
             server_id: server_id.to_string(),
@@ -476,10 +755,26 @@ impl WebSocketManager {
             players_online,
             memory_usage_mb,
             cpu_usage_percent,
+            resource_limits,
         };
         self.broadcast_to_server(&server_id.to_string(), message).await
     }
 
+    /// Send an alert to every connection; alerts are rare and should reach
+    /// dashboards that are not looking at the affected server
+    pub async fn send_alert(&self, server_id: Uuid, level: &str, alert_type: &str, message: &str,
+                            recommended_action: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let message = WebSocketMessage::Alert {
+            server_id: server_id.to_string(),
+            timestamp: Utc::now(),
+            level: level.to_string(),
+            alert_type: alert_type.to_string(),
+            message: message.to_string(),
+            recommended_action: recommended_action.map(|s| s.to_string()),
+        };
+        self.broadcast(message).await
+    }
+
     /// Send server status update (alias for compatibility)
     pub async fn send_server_status_update(&self, server_id: Uuid, status: &str) -> Result<(), Box<dyn std::error::Error>> {
         self.send_server_status(server_id, status.to_string()).await
@@ -565,7 +860,7 @@ impl WebSocketManager {
             }
         }
         
-        self.broadcast(ping_message).await
+        self.send_to_connection(connection_id, ping_message).await
     }
 
     /// Handle pong response from client
@@ -735,3 +1030,62 @@ impl Default for WebSocketManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn console(server_id: &str, message: &str) -> WebSocketMessage {
+        WebSocketMessage::ConsoleMessage {
+            server_id: server_id.to_string(),
+            timestamp: Utc::now(),
+            level: "info".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_topic_matching() {
+        assert_eq!(console("abc", "").topic().as_deref(), Some("server:abc:console"));
+        assert!(topic_matches("server:abc:console", "server:abc:console"));
+        assert!(topic_matches("server:abc", "server:abc:metrics"));
+        assert!(!topic_matches("server:ab", "server:abc:metrics"));
+        assert!(topic_matches("metrics", "server:abc:metrics"));
+        assert!(topic_matches("jobs", "jobs"));
+        assert!(!topic_matches("alerts", "server:abc:console"));
+        assert!(is_valid_topic("server:abc:console"));
+        assert!(!is_valid_topic("server:abc:chat"));
+        assert!(!is_valid_topic("everything"));
+    }
+
+    #[test]
+    fn test_topic_visibility_by_server_scope() {
+        let scope = ["abc".to_string()];
+        assert_eq!(topic_server("server:abc:console"), Some("abc"));
+        assert_eq!(topic_server("server:abc"), Some("abc"));
+        assert_eq!(topic_server("console"), None);
+        assert!(topic_visible(None, "jobs"));
+        assert!(topic_visible(Some(&scope), "server:abc:console"));
+        assert!(!topic_visible(Some(&scope), "server:def:console"));
+        // Messages not about a server may concern any of them
+        assert!(!topic_visible(Some(&scope), "jobs"));
+    }
+
+    #[test]
+    fn test_replay_keeps_last_messages_per_topic() {
+        let mut buffer = ReplayBuffer::default();
+        for i in 0..REPLAY_PER_TOPIC + 5 {
+            buffer.record("server:a:console".to_string(), console("a", &i.to_string()));
+            buffer.record("server:b:console".to_string(), console("b", &i.to_string()));
+        }
+        let replayed = buffer.replay(&["server:a:console".to_string()], 2);
+        let lines: Vec<String> = replayed.iter().map(|message| match message {
+            WebSocketMessage::ConsoleMessage { message, .. } => message.clone(),
+            _ => unreachable!(),
+        }).collect();
+        assert_eq!(lines, [(REPLAY_PER_TOPIC + 3).to_string(), (REPLAY_PER_TOPIC + 4).to_string()]);
+        // Two per topic, interleaved in publish order
+        assert_eq!(buffer.replay(&["console".to_string()], 2).len(), 4);
+        assert_eq!(buffer.replay(&["server:a".to_string()], 1000).len(), REPLAY_PER_TOPIC);
+    }
+}
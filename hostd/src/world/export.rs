@@ -0,0 +1,299 @@
+//! World export
+//!
+//! Archives a server's world for download. tar.zst archives are compressed
+//! while they stream out; zip needs to seek back over what it has written,
+//! so a zip is built in a temporary file first and streamed from there. On a
+//! running server saving is paused (`save-off`, `save-all flush`) while the
+//! world is read, so the archive holds one consistent save. Bukkit-style
+//! servers keep the Nether and End in `<world>_nether` and `<world>_the_end`;
+//! those are included when present.
+
+use anyhow::{bail, Result};
+use axum::body::Bytes;
+use serde::Deserialize;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{info, warn};
+
+use crate::database::ServerConfig;
+use crate::restart_scheduler::rcon;
+
+/// Folders of per-player data, left out on request
+const PLAYER_DATA_DIRS: [&str; 3] = ["playerdata", "stats", "advancements"];
+
+/// Size of the chunks the archive is streamed in
+const CHUNK_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum ExportFormat {
+    #[serde(rename = "zip")]
+    Zip,
+    #[default]
+    #[serde(rename = "tar.zst")]
+    TarZst,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::TarZst => "tar.zst",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Zip => "application/zip",
+            Self::TarZst => "application/zstd",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Leave out `playerdata/`, `stats/` and `advancements/`
+    #[serde(default)]
+    pub exclude_player_data: bool,
+}
+
+/// Name to offer the archive under
+pub fn file_name(server: &ServerConfig, format: ExportFormat) -> String {
+    let name: String = server
+        .name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}-{}-{}.{}", name, server.world_name, chrono::Utc::now().format("%Y%m%d-%H%M%S"), format.extension())
+}
+
+/// Start archiving the server's world. `live` servers have saving paused
+/// until the world has been read; the returned stream yields the archive.
+pub async fn export(
+    server: ServerConfig,
+    live: bool,
+    options: ExportOptions,
+) -> Result<ReceiverStream<std::io::Result<Bytes>>> {
+    let files = {
+        let world = crate::world::server_world_dir(&server);
+        let exclude_player_data = options.exclude_player_data;
+        tokio::task::spawn_blocking(move || collect_files(&world, exclude_player_data)).await??
+    };
+    if files.is_empty() {
+        bail!("Server {} has no world to export yet", server.id);
+    }
+
+    if live {
+        if let Err(e) = async {
+            rcon(&server, "save-off".to_string()).await?;
+            rcon(&server, "save-all flush".to_string()).await
+        }
+        .await
+        {
+            let _ = rcon(&server, "save-on".to_string()).await;
+            bail!("Could not pause world saving over RCON: {}", e);
+        }
+    }
+
+    let (sender, receiver) = mpsc::channel(8);
+    tokio::spawn(async move {
+        let writer = ChannelWriter::new(sender.clone());
+        let format = options.format;
+        let built = tokio::task::spawn_blocking(move || -> Result<Option<std::fs::File>> {
+            match format {
+                ExportFormat::TarZst => {
+                    write_tar_zst(&files, writer)?;
+                    Ok(None)
+                }
+                ExportFormat::Zip => {
+                    let mut file = tempfile::tempfile()?;
+                    write_zip(&files, &mut file)?;
+                    file.rewind()?;
+                    Ok(Some(file))
+                }
+            }
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.into()));
+
+        // The world has been read; anything left is streaming the temporary zip
+        if live {
+            if let Err(e) = rcon(&server, "save-on".to_string()).await {
+                warn!("Failed to re-enable saving after exporting server {}: {}", server.id, e);
+            }
+        }
+
+        let result = match built {
+            Ok(Some(mut file)) => {
+                let mut writer = ChannelWriter::new(sender.clone());
+                tokio::task::spawn_blocking(move || -> Result<()> {
+                    std::io::copy(&mut file, &mut writer)?;
+                    writer.flush()?;
+                    Ok(())
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.into()))
+            }
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => info!("Exported world of server {}", server.id),
+            Err(e) => {
+                warn!("World export of server {} failed: {}", server.id, e);
+                // Ends the download with an error rather than a truncated archive that looks complete
+                let _ = sender.send(Err(std::io::Error::other(e.to_string()))).await;
+            }
+        }
+    });
+    Ok(ReceiverStream::new(receiver))
+}
+
+/// The folders of a world: the world itself, then the Bukkit-style Nether
+/// and End folders if there are any
+fn world_roots(world: &Path) -> Vec<PathBuf> {
+    let mut roots = vec![world.to_path_buf()];
+    for suffix in ["_nether", "_the_end"] {
+        let mut name = world.as_os_str().to_owned();
+        name.push(suffix);
+        let dir = PathBuf::from(name);
+        if dir.is_dir() {
+            roots.push(dir);
+        }
+    }
+    roots
+}
+
+/// Files to archive, with their names in the archive (relative to the server directory)
+fn collect_files(world: &Path, exclude_player_data: bool) -> Result<Vec<(PathBuf, String)>> {
+    let mut files = Vec::new();
+    for root in world_roots(world).into_iter().filter(|root| root.is_dir()) {
+        let prefix = root.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        walk(&root, &prefix, true, exclude_player_data, &mut files)?;
+    }
+    Ok(files)
+}
+
+fn walk(dir: &Path, name: &str, top: bool, exclude_player_data: bool, files: &mut Vec<(PathBuf, String)>) -> Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?.filter_map(|entry| entry.ok()).collect();
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        let entry_name = format!("{}/{}", name, file_name);
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if top && exclude_player_data && PLAYER_DATA_DIRS.contains(&file_name.as_str()) {
+                continue;
+            }
+            walk(&path, &entry_name, false, exclude_player_data, files)?;
+        } else if file_type.is_file() && !(top && file_name == "session.lock") {
+            // The game holds session.lock open while running, and it means nothing in a copy
+            files.push((path, entry_name));
+        }
+    }
+    Ok(())
+}
+
+fn write_tar_zst<W: Write>(files: &[(PathBuf, String)], out: W) -> Result<()> {
+    let mut archive = tar::Builder::new(zstd::Encoder::new(out, 3)?);
+    for (path, name) in files {
+        archive.append_path_with_name(path, name)?;
+    }
+    archive.into_inner()?.finish()?.flush()?;
+    Ok(())
+}
+
+fn write_zip<W: Write + Seek>(files: &[(PathBuf, String)], out: W) -> Result<()> {
+    let mut archive = zip::ZipWriter::new(out);
+    for (path, name) in files {
+        let mut file = std::fs::File::open(path)?;
+        let large = file.metadata()?.len() >= u32::MAX as u64;
+        let options = zip::write::FileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(large);
+        archive.start_file(name.as_str(), options)?;
+        std::io::copy(&mut file, &mut archive)?;
+    }
+    archive.finish()?;
+    Ok(())
+}
+
+/// Sends what is written to it down a channel, in chunks. Writing fails once
+/// the receiving side, the download, is gone.
+struct ChannelWriter {
+    sender: mpsc::Sender<std::io::Result<Bytes>>,
+    buffer: Vec<u8>,
+}
+
+impl ChannelWriter {
+    fn new(sender: mpsc::Sender<std::io::Result<Bytes>>) -> Self {
+        Self { sender, buffer: Vec::with_capacity(CHUNK_BYTES) }
+    }
+
+    fn send(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = Bytes::from(std::mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_BYTES)));
+        self.sender
+            .blocking_send(Ok(chunk))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "The download was cancelled"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= CHUNK_BYTES {
+            self.send()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.send()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tar_zst_export_skips_player_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let world = dir.path().join("world");
+        for path in ["region", "playerdata", "data/playerdata"] {
+            std::fs::create_dir_all(world.join(path)).unwrap();
+        }
+        std::fs::create_dir_all(dir.path().join("world_nether/DIM-1/region")).unwrap();
+        for file in ["level.dat", "session.lock", "region/r.0.0.mca", "playerdata/a.dat", "data/playerdata/kept.dat"] {
+            std::fs::write(world.join(file), file).unwrap();
+        }
+        std::fs::write(dir.path().join("world_nether/DIM-1/region/r.0.0.mca"), "nether").unwrap();
+
+        let files = collect_files(&world, true).unwrap();
+        let mut archive = Vec::new();
+        write_tar_zst(&files, &mut archive).unwrap();
+
+        let mut tar = tar::Archive::new(zstd::Decoder::new(archive.as_slice()).unwrap());
+        let names: Vec<String> = tar
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "world/data/playerdata/kept.dat",
+                "world/level.dat",
+                "world/region/r.0.0.mca",
+                "world_nether/DIM-1/region/r.0.0.mca",
+            ]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Activity heatmap: how long players spent in each part of a dimension and
+//! how crowded it is, summed from chunk `InhabitedTime` and entity counts.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::nbt::Compound;
+use super::region::{self, Region};
+use crate::database::{DatabaseManager, ServerConfig};
+
+/// Chunks per cell side by default (64x64 blocks)
+pub const DEFAULT_CELL_CHUNKS: u32 = 4;
+
+/// Cached heatmaps older than this are rebuilt when requested
+pub const MAX_AGE: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapCell {
+    /// Cell coordinates; multiply by `cell_size` for block coordinates
+    pub x: i32,
+    pub z: i32,
+    /// Inhabited time scaled to 0..1 against the busiest cell (logarithmic)
+    pub value: f64,
+    pub inhabited_ticks: i64,
+    pub entities: u32,
+    pub block_entities: u32,
+    pub chunks: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldHeatmap {
+    pub dimension: String,
+    /// Cell side length in blocks
+    pub cell_size: u32,
+    pub cells: Vec<HeatmapCell>,
+    pub max_inhabited_ticks: i64,
+    pub chunks_scanned: usize,
+    /// Chunks that could not be decoded and were left out
+    pub chunks_unreadable: usize,
+    pub generated_at: DateTime<Utc>,
+}
+
+struct Grid {
+    cell_chunks: i32,
+    cells: BTreeMap<(i32, i32), HeatmapCell>,
+    chunks_scanned: usize,
+    chunks_unreadable: usize,
+}
+
+impl Grid {
+    fn cell(&mut self, chunk_x: i32, chunk_z: i32) -> &mut HeatmapCell {
+        let (x, z) = (chunk_x.div_euclid(self.cell_chunks), chunk_z.div_euclid(self.cell_chunks));
+        self.cells.entry((x, z)).or_insert(HeatmapCell {
+            x,
+            z,
+            value: 0.0,
+            inhabited_ticks: 0,
+            entities: 0,
+            block_entities: 0,
+            chunks: 0,
+        })
+    }
+
+    /// Decode every readable chunk of the region files in `dir`
+    fn scan(&mut self, dir: &Path, mut visit: impl FnMut(&mut Self, i32, i32, &Compound)) -> Result<()> {
+        for (x, z, path) in region::list_regions(dir)? {
+            let bytes = std::fs::read(&path)?;
+            let Ok((region, damaged)) = Region::parse_lenient(x, z, &bytes, path.parent()) else {
+                continue;
+            };
+            self.chunks_unreadable += damaged.len();
+            for (index, raw) in region.present() {
+                let Ok(chunk) = raw.decode() else {
+                    self.chunks_unreadable += 1;
+                    continue;
+                };
+                let (cx, cz) = region::chunk_coords(x, z, index);
+                visit(self, cx, cz, chunk.get_compound("Level").unwrap_or(&chunk));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn list_len(chunk: &Compound, names: &[&str]) -> u32 {
+    names.iter().find_map(|name| chunk.get_list(name)).map_or(0, |list| list.len() as u32)
+}
+
+/// Build the heatmap of one dimension. `dimension_dir` is the folder holding `region/` and `entities/`.
+pub fn build_heatmap(dimension_dir: &Path, dimension: &str, cell_chunks: u32) -> Result<WorldHeatmap> {
+    let mut grid = Grid { cell_chunks: cell_chunks.max(1) as i32, cells: BTreeMap::new(), chunks_scanned: 0, chunks_unreadable: 0 };
+
+    grid.scan(&dimension_dir.join("region"), |grid, cx, cz, chunk| {
+        grid.chunks_scanned += 1;
+        let cell = grid.cell(cx, cz);
+        cell.chunks += 1;
+        cell.inhabited_ticks += chunk.get_i64("InhabitedTime").unwrap_or(0).max(0);
+        // Before 1.17 entities are stored in the chunk itself
+        cell.entities += list_len(chunk, &["Entities"]);
+        cell.block_entities += list_len(chunk, &["block_entities", "TileEntities"]);
+    })?;
+    grid.scan(&dimension_dir.join("entities"), |grid, cx, cz, chunk| {
+        grid.cell(cx, cz).entities += list_len(chunk, &["Entities"]);
+    })?;
+
+    let mut cells: Vec<HeatmapCell> = grid.cells.into_values().collect();
+    let max_inhabited_ticks = cells.iter().map(|cell| cell.inhabited_ticks).max().unwrap_or(0);
+    let scale = (max_inhabited_ticks as f64).ln_1p();
+    for cell in &mut cells {
+        cell.value = if scale > 0.0 { (cell.inhabited_ticks as f64).ln_1p() / scale } else { 0.0 };
+    }
+
+    Ok(WorldHeatmap {
+        dimension: dimension.to_string(),
+        cell_size: grid.cell_chunks as u32 * 16,
+        cells,
+        max_inhabited_ticks,
+        chunks_scanned: grid.chunks_scanned,
+        chunks_unreadable: grid.chunks_unreadable,
+        generated_at: Utc::now(),
+    })
+}
+
+/// Rescan a server's dimension and store the result in the heatmap cache
+pub async fn refresh(database: &DatabaseManager, server: &ServerConfig, dimension: &str) -> Result<WorldHeatmap> {
+    let world = super::server_world_dir(server);
+    let Some(root) = super::dimension_root(&world, dimension) else {
+        anyhow::bail!("Unknown dimension '{}'", dimension);
+    };
+    let name = dimension.to_string();
+    let heatmap = tokio::task::spawn_blocking(move || build_heatmap(&root, &name, DEFAULT_CELL_CHUNKS)).await??;
+    database
+        .save_world_heatmap(&server.id, dimension, &serde_json::to_value(&heatmap)?, heatmap.generated_at)
+        .await?;
+    Ok(heatmap)
+}
+
+/// Cached heatmap if it is younger than [`MAX_AGE`], otherwise a fresh scan
+pub async fn get_or_refresh(database: &DatabaseManager, server: &ServerConfig, dimension: &str) -> Result<WorldHeatmap> {
+    if let Some(data) = database.get_world_heatmap(&server.id, dimension).await? {
+        if let Ok(heatmap) = serde_json::from_value::<WorldHeatmap>(data) {
+            let age = (Utc::now() - heatmap.generated_at).to_std().unwrap_or_default();
+            if age < MAX_AGE {
+                return Ok(heatmap);
+            }
+        }
+    }
+    refresh(database, server, dimension).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::nbt::Tag;
+    use crate::world::region::RawChunk;
+
+    fn write_region(dir: &Path, chunks: &[((i32, i32), Compound)]) {
+        std::fs::create_dir_all(dir).unwrap();
+        let mut region = Region::new(-1, 0);
+        for ((cx, cz), chunk) in chunks {
+            region.set_chunk(region::chunk_index(*cx, *cz), Some(RawChunk::encode(chunk, 0).unwrap()));
+        }
+        region.write(&dir.join("r.-1.0.mca")).unwrap();
+    }
+
+    fn chunk(inhabited: i64) -> Compound {
+        let mut chunk = Compound::new();
+        chunk.insert("InhabitedTime", Tag::Long(inhabited));
+        chunk
+    }
+
+    fn with_entities(count: usize) -> Compound {
+        let mut chunk = Compound::new();
+        chunk.insert("Entities", Tag::List(10, vec![Tag::Compound(Compound::new()); count]));
+        chunk
+    }
+
+    #[test]
+    fn test_heatmap_groups_chunks_into_cells() {
+        let dir = tempfile::tempdir().unwrap();
+        write_region(&dir.path().join("region"), &[((-1, 0), chunk(1_000)), ((-4, 3), chunk(0)), ((-5, 0), chunk(0))]);
+        write_region(&dir.path().join("entities"), &[((-1, 0), with_entities(3))]);
+
+        let heatmap = build_heatmap(dir.path(), "overworld", 4).unwrap();
+        assert_eq!(heatmap.cell_size, 64);
+        assert_eq!(heatmap.chunks_scanned, 3);
+        assert_eq!(heatmap.max_inhabited_ticks, 1_000);
+
+        let coords: Vec<_> = heatmap.cells.iter().map(|cell| (cell.x, cell.z, cell.chunks)).collect();
+        assert_eq!(coords, vec![(-2, 0, 1), (-1, 0, 2)]);
+        let busy = &heatmap.cells[1];
+        assert_eq!(busy.entities, 3);
+        assert_eq!(busy.value, 1.0);
+        assert_eq!(heatmap.cells[0].value, 0.0);
+    }
+}
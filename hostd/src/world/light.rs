@@ -0,0 +1,397 @@
+//! Block and sky light recomputation for a single chunk column.
+//!
+//! This follows vanilla's rules (sky light falls straight down at 15 through
+//! transparent blocks, both kinds of light lose one level per step and extra
+//! levels through filtering blocks) using a per-block opacity/emission table
+//! for common blocks. Light does not cross chunk borders here; the server
+//! smooths the seams when the chunk is next loaded.
+
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+
+use super::nbt::{Compound, Tag};
+
+/// DataVersion of 1.16 (20w17a): palette entries no longer span two longs
+const DATA_VERSION_PADDED_STATES: i64 = 2529;
+
+const SECTION_VOLUME: usize = 4096;
+const LIGHT_ARRAY_LEN: usize = 2048;
+
+/// Light-relevant properties of a block state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LightProperties {
+    /// Extra levels lost passing through (15 = fully opaque)
+    pub opacity: u8,
+    pub emission: u8,
+}
+
+impl LightProperties {
+    const AIR: Self = Self { opacity: 0, emission: 0 };
+    const SOLID: Self = Self { opacity: 15, emission: 0 };
+}
+
+/// Look up opacity and emission for a block state from its palette entry
+pub fn block_light_properties(state: &Compound) -> LightProperties {
+    let name = state.get_str("Name").unwrap_or("minecraft:air");
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let lit = state
+        .get_compound("Properties")
+        .and_then(|properties| properties.get_str("lit"))
+        .map(|lit| lit == "true");
+
+    let emission = match name {
+        "glowstone" | "sea_lantern" | "lava" | "fire" | "jack_o_lantern" | "beacon" | "shroomlight" | "lantern"
+        | "end_portal" | "end_gateway" | "conduit" | "respawn_anchor" | "ochre_froglight" | "verdant_froglight"
+        | "pearlescent_froglight" => 15,
+        "campfire" | "redstone_lamp" if lit != Some(false) => 15,
+        "torch" | "wall_torch" | "end_rod" => 14,
+        "furnace" | "blast_furnace" | "smoker" if lit == Some(true) => 13,
+        "nether_portal" => 11,
+        "soul_torch" | "soul_wall_torch" | "soul_lantern" | "soul_fire" | "crying_obsidian" => 10,
+        "soul_campfire" if lit != Some(false) => 10,
+        "redstone_torch" | "redstone_wall_torch" if lit != Some(false) => 7,
+        "enchanting_table" | "ender_chest" => 7,
+        "glow_lichen" | "cave_vines" | "cave_vines_plant" => 7,
+        "amethyst_cluster" => 5,
+        "large_amethyst_bud" => 4,
+        "magma_block" => 3,
+        "medium_amethyst_bud" | "sculk_catalyst" => 2,
+        "brewing_stand" | "small_amethyst_bud" | "dragon_egg" | "end_portal_frame" | "brown_mushroom"
+        | "sculk_sensor" => 1,
+        _ => 0,
+    };
+
+    let opacity = if matches!(name, "air" | "cave_air" | "void_air") {
+        0
+    } else if matches!(name, "water" | "bubble_column" | "ice" | "frosted_ice" | "cobweb" | "slime_block" | "honey_block")
+        || name.ends_with("_leaves")
+    {
+        1
+    } else if is_transparent(name) {
+        0
+    } else {
+        15
+    };
+
+    LightProperties { opacity, emission }
+}
+
+/// Blocks light passes through unchanged
+fn is_transparent(name: &str) -> bool {
+    const SUFFIXES: &[&str] = &[
+        "glass", "_glass_pane", "_torch", "torch", "_sign", "_button", "_pressure_plate", "rail", "_sapling",
+        "_carpet", "_door", "_trapdoor", "_fence", "_fence_gate", "_wall", "_slab", "_stairs", "_bed", "_banner",
+        "_candle", "candle", "_coral", "_coral_fan", "_tulip", "_mushroom", "_head", "_skull", "_pane", "_bars",
+        "lantern", "_rod", "_bud", "_cluster", "chain", "_vine", "_vines", "_roots", "_fungus", "_pot", "_plant",
+        "_petals", "_wire", "_stem", "_chest", "chest", "_portal", "fire", "_ladder", "ladder", "lever",
+    ];
+    const NAMES: &[&str] = &[
+        "grass", "short_grass", "tall_grass", "fern", "large_fern", "dead_bush", "dandelion", "poppy", "blue_orchid",
+        "allium", "azure_bluet", "oxeye_daisy", "cornflower", "lily_of_the_valley", "wither_rose", "sunflower", "lilac",
+        "rose_bush", "peony", "torchflower", "pitcher_plant", "seagrass", "tall_seagrass", "kelp", "kelp_plant",
+        "sugar_cane", "bamboo", "vine", "lily_pad", "snow", "scaffolding", "repeater", "comparator", "tripwire",
+        "tripwire_hook", "beacon", "conduit", "hopper", "cauldron", "lava", "barrier", "light", "structure_void",
+        "wheat", "carrots", "potatoes", "beetroots", "nether_wart", "sweet_berry_bush", "cocoa", "spawner",
+        "enchanting_table", "brewing_stand", "anvil", "bell", "campfire", "soul_campfire", "end_rod", "lectern",
+        "moss_carpet", "spore_blossom", "hanging_roots", "big_dripleaf", "small_dripleaf", "pointed_dripstone",
+        "frogspawn", "sculk_vein", "glow_lichen", "amethyst_cluster", "daylight_detector", "stonecutter",
+        "end_portal_frame", "dragon_egg", "cobweb", "turtle_egg", "sea_pickle", "flower_pot", "decorated_pot",
+    ];
+    NAMES.contains(&name) || SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+/// Result of relighting one chunk
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RelightStats {
+    pub sections: usize,
+    pub emitters: usize,
+}
+
+/// Chunk root: 1.18+ stores everything at the top level, older versions under "Level"
+fn level(chunk: &Compound) -> &Compound {
+    chunk.get_compound("Level").unwrap_or(chunk)
+}
+
+fn level_mut(chunk: &mut Compound) -> &mut Compound {
+    if chunk.get_compound("Level").is_some() {
+        chunk.get_compound_mut("Level").unwrap()
+    } else {
+        chunk
+    }
+}
+
+fn sections_key(level: &Compound) -> &'static str {
+    if level.get("sections").is_some() { "sections" } else { "Sections" }
+}
+
+/// Whether the game considers this chunk's light up to date
+pub fn is_lit(chunk: &Compound) -> bool {
+    level(chunk).get_i64("isLightOn").unwrap_or(0) != 0
+}
+
+/// Proto-chunks still get lit by the world generator, so only finished chunks are touched
+pub fn is_fully_generated(chunk: &Compound) -> bool {
+    match level(chunk).get_str("Status") {
+        Some(status) => matches!(status.trim_start_matches("minecraft:"), "full" | "postprocessed" | "fullchunk"),
+        None => true,
+    }
+}
+
+/// Drop stored light and let the server recompute it on next load
+pub fn invalidate_light(chunk: &mut Compound) {
+    let level = level_mut(chunk);
+    level.insert("isLightOn", Tag::Byte(0));
+    let key = sections_key(level);
+    if let Some(sections) = level.get_list_mut(key) {
+        for section in sections.iter_mut().filter_map(Tag::as_compound_mut) {
+            section.remove("BlockLight");
+            section.remove("SkyLight");
+        }
+    }
+}
+
+/// Decode one section's palette indices into per-block light properties
+fn section_properties(section: &Compound, data_version: i64) -> Result<Vec<LightProperties>> {
+    let (palette, data) = match section.get_compound("block_states") {
+        Some(states) => (states.get_list("palette"), states.get("data").and_then(Tag::as_long_array)),
+        None => (section.get_list("Palette"), section.get("BlockStates").and_then(Tag::as_long_array)),
+    };
+    let Some(palette) = palette.filter(|palette| !palette.is_empty()) else {
+        return Ok(vec![LightProperties::AIR; SECTION_VOLUME]);
+    };
+
+    let properties: Vec<LightProperties> = palette
+        .iter()
+        .map(|entry| entry.as_compound().map(block_light_properties).unwrap_or(LightProperties::SOLID))
+        .collect();
+
+    let Some(data) = data.filter(|_| palette.len() > 1) else {
+        return Ok(vec![properties[0]; SECTION_VOLUME]);
+    };
+
+    let bits = (usize::BITS - (palette.len() - 1).leading_zeros()).max(4) as usize;
+    let mask = (1u64 << bits) - 1;
+    let padded = data_version >= DATA_VERSION_PADDED_STATES;
+    let per_long = 64 / bits;
+
+    let mut out = Vec::with_capacity(SECTION_VOLUME);
+    for index in 0..SECTION_VOLUME {
+        let value = if padded {
+            let word = *data.get(index / per_long).ok_or_else(|| anyhow!("block state data too short"))? as u64;
+            (word >> ((index % per_long) * bits)) & mask
+        } else {
+            let bit = index * bits;
+            let word = *data.get(bit / 64).ok_or_else(|| anyhow!("block state data too short"))? as u64;
+            let mut value = word >> (bit % 64);
+            if bit % 64 + bits > 64 {
+                let next = *data.get(bit / 64 + 1).ok_or_else(|| anyhow!("block state data too short"))? as u64;
+                value |= next << (64 - bit % 64);
+            }
+            value & mask
+        };
+        out.push(*properties.get(value as usize).unwrap_or(&LightProperties::SOLID));
+    }
+    Ok(out)
+}
+
+/// Recompute BlockLight and SkyLight for every section of a chunk and mark it lit
+pub fn relight_chunk(chunk: &mut Compound, has_sky: bool) -> Result<RelightStats> {
+    let data_version = chunk.get_i64("DataVersion").unwrap_or(0);
+    let level = level_mut(chunk);
+    let key = sections_key(level);
+
+    // Column layout, bottom section first
+    let mut columns: Vec<(i8, Vec<LightProperties>)> = Vec::new();
+    if let Some(sections) = level.get_list(key) {
+        for section in sections.iter().filter_map(Tag::as_compound) {
+            let y = section.get_i64("Y").unwrap_or(0) as i8;
+            columns.push((y, section_properties(section, data_version)?));
+        }
+    }
+    columns.sort_by_key(|(y, _)| *y);
+
+    let Some(min_y) = columns.first().map(|(y, _)| *y as i32) else {
+        level.insert("isLightOn", Tag::Byte(1));
+        return Ok(RelightStats::default());
+    };
+    let max_y = columns.last().map(|(y, _)| *y as i32).unwrap();
+    let height = ((max_y - min_y + 1) * 16) as usize;
+
+    // Dense column; gaps between stored sections are air
+    let mut blocks = vec![LightProperties::AIR; height * 256];
+    for (y, properties) in &columns {
+        let base = (*y as i32 - min_y) as usize * SECTION_VOLUME;
+        blocks[base..base + SECTION_VOLUME].copy_from_slice(properties);
+    }
+
+    let mut stats = RelightStats { sections: columns.len(), emitters: 0 };
+    let block_light = propagate(&blocks, height, |i| blocks[i].emission, &mut stats.emitters);
+    let sky_light = if has_sky {
+        let mut sky = vec![0u8; blocks.len()];
+        for xz in 0..256 {
+            let mut level = 15u8;
+            for y in (0..height).rev() {
+                let i = y * 256 + xz;
+                let opacity = blocks[i].opacity;
+                level = if opacity == 0 { level } else { level.saturating_sub(opacity) };
+                sky[i] = level;
+                if level == 0 {
+                    break;
+                }
+            }
+        }
+        let mut unused = 0;
+        Some(propagate(&blocks, height, |i| sky[i], &mut unused))
+    } else {
+        None
+    };
+
+    if let Some(sections) = level.get_list_mut(key) {
+        for section in sections.iter_mut().filter_map(Tag::as_compound_mut) {
+            let y = section.get_i64("Y").unwrap_or(0) as i32;
+            let base = (y - min_y) as usize * SECTION_VOLUME;
+            section.insert("BlockLight", Tag::ByteArray(pack_nibbles(&block_light[base..base + SECTION_VOLUME])));
+            match &sky_light {
+                Some(sky) => section.insert("SkyLight", Tag::ByteArray(pack_nibbles(&sky[base..base + SECTION_VOLUME]))),
+                None => {
+                    section.remove("SkyLight");
+                }
+            }
+        }
+    }
+    level.insert("isLightOn", Tag::Byte(1));
+
+    Ok(stats)
+}
+
+/// Breadth-first flood fill from the seed levels; index = y * 256 + z * 16 + x
+fn propagate(blocks: &[LightProperties], height: usize, seed: impl Fn(usize) -> u8, seeds: &mut usize) -> Vec<u8> {
+    let mut light = vec![0u8; blocks.len()];
+    let mut queue = VecDeque::new();
+    for (i, level) in light.iter_mut().enumerate() {
+        let value = seed(i);
+        if value > 0 {
+            *level = value;
+            queue.push_back(i);
+            *seeds += 1;
+        }
+    }
+
+    while let Some(i) = queue.pop_front() {
+        let level = light[i];
+        if level <= 1 {
+            continue;
+        }
+        let (x, z, y) = (i % 16, (i / 16) % 16, i / 256);
+        let mut neighbours = [usize::MAX; 6];
+        if x > 0 { neighbours[0] = i - 1; }
+        if x < 15 { neighbours[1] = i + 1; }
+        if z > 0 { neighbours[2] = i - 16; }
+        if z < 15 { neighbours[3] = i + 16; }
+        if y > 0 { neighbours[4] = i - 256; }
+        if y + 1 < height { neighbours[5] = i + 256; }
+
+        for n in neighbours.into_iter().filter(|n| *n != usize::MAX) {
+            let next = level.saturating_sub(1 + blocks[n].opacity);
+            if next > light[n] {
+                light[n] = next;
+                queue.push_back(n);
+            }
+        }
+    }
+    light
+}
+
+fn pack_nibbles(levels: &[u8]) -> Vec<i8> {
+    let mut out = vec![0i8; LIGHT_ARRAY_LEN];
+    for (i, pair) in levels.chunks_exact(2).enumerate() {
+        out[i] = ((pair[0] & 0x0F) | (pair[1] << 4)) as i8;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(name: &str) -> Tag {
+        let mut state = Compound::new();
+        state.insert("Name", Tag::String(format!("minecraft:{}", name)));
+        Tag::Compound(state)
+    }
+
+    fn nibble(array: &[i8], index: usize) -> u8 {
+        let byte = array[index / 2] as u8;
+        (byte >> ((index % 2) * 4)) & 0x0F
+    }
+
+    /// One section: stone floor at y=0, a glowstone block at (8, 1, 8), air above
+    fn chunk() -> Compound {
+        let mut data = vec![0i64; 256];
+        // 4 bits per entry, 16 entries per long; palette 0 = air, 1 = stone, 2 = glowstone
+        for long in data.iter_mut().take(16) {
+            *long = 0x1111_1111_1111_1111;
+        }
+        let glowstone = 256 + 8 * 16 + 8;
+        data[glowstone / 16] |= 2 << ((glowstone % 16) * 4);
+
+        let mut states = Compound::new();
+        states.insert("palette", Tag::List(10, vec![state("air"), state("stone"), state("glowstone")]));
+        states.insert("data", Tag::LongArray(data));
+
+        let mut section = Compound::new();
+        section.insert("Y", Tag::Byte(0));
+        section.insert("block_states", Tag::Compound(states));
+
+        let mut chunk = Compound::new();
+        chunk.insert("DataVersion", Tag::Int(3700));
+        chunk.insert("sections", Tag::List(10, vec![Tag::Compound(section)]));
+        chunk
+    }
+
+    #[test]
+    fn test_relight_block_and_sky_light() {
+        let mut chunk = chunk();
+        assert!(!is_lit(&chunk));
+
+        let stats = relight_chunk(&mut chunk, true).unwrap();
+        assert_eq!(stats.sections, 1);
+        assert!(is_lit(&chunk));
+
+        let section = chunk.get_list("sections").unwrap()[0].as_compound().unwrap();
+        let Some(Tag::ByteArray(block)) = section.get("BlockLight") else { panic!("no BlockLight") };
+        let Some(Tag::ByteArray(sky)) = section.get("SkyLight") else { panic!("no SkyLight") };
+
+        // Glowstone is 15, its air neighbours 14, the stone floor stays dark
+        assert_eq!(nibble(block, 256 + 8 * 16 + 8), 15);
+        assert_eq!(nibble(block, 2 * 256 + 8 * 16 + 8), 14);
+        assert_eq!(nibble(block, 256 + 8 * 16 + 11), 12);
+        assert_eq!(nibble(block, 8 * 16 + 8), 0);
+
+        // Open sky above the floor, nothing inside it
+        assert_eq!(nibble(sky, 15 * 256), 15);
+        assert_eq!(nibble(sky, 256 + 3), 15);
+        assert_eq!(nibble(sky, 3), 0);
+    }
+
+    #[test]
+    fn test_invalidate_light() {
+        let mut chunk = chunk();
+        relight_chunk(&mut chunk, false).unwrap();
+        invalidate_light(&mut chunk);
+
+        assert!(!is_lit(&chunk));
+        let section = chunk.get_list("sections").unwrap()[0].as_compound().unwrap();
+        assert!(section.get("BlockLight").is_none());
+    }
+
+    #[test]
+    fn test_light_properties() {
+        let props = |name: &str| block_light_properties(state(name).as_compound().unwrap());
+        assert_eq!(props("stone"), LightProperties::SOLID);
+        assert_eq!(props("glass").opacity, 0);
+        assert_eq!(props("oak_leaves").opacity, 1);
+        assert_eq!(props("torch").emission, 14);
+        assert_eq!(props("grass_block").opacity, 15);
+    }
+}
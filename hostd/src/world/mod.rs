@@ -0,0 +1,62 @@
+//! Reading and rewriting Anvil world data on disk.
+
+pub mod export;
+pub mod heatmap;
+pub mod light;
+pub mod nbt;
+pub mod region;
+pub mod trim;
+pub mod verify;
+
+use std::path::{Path, PathBuf};
+
+use crate::database::ServerConfig;
+
+/// Namespaced ID of a vanilla dimension (`overworld`, `nether`, `end` or their full IDs)
+pub fn dimension_id(dimension: &str) -> Option<&'static str> {
+    match dimension.trim_start_matches("minecraft:") {
+        "overworld" => Some("minecraft:overworld"),
+        "nether" | "the_nether" => Some("minecraft:the_nether"),
+        "end" | "the_end" => Some("minecraft:the_end"),
+        _ => None,
+    }
+}
+
+/// Region directory of a dimension, relative to the world folder
+pub fn region_dir(dimension: &str) -> Option<&'static str> {
+    match dimension_id(dimension)? {
+        "minecraft:overworld" => Some("region"),
+        "minecraft:the_nether" => Some("DIM-1/region"),
+        _ => Some("DIM1/region"),
+    }
+}
+
+/// Folder holding a dimension's `region/`, `entities/` and `poi/` directories
+pub fn dimension_root(world: &Path, dimension: &str) -> Option<PathBuf> {
+    let region = world.join(region_dir(dimension)?);
+    region.parent().map(Path::to_path_buf)
+}
+
+/// The world folder a server is configured to load
+pub fn server_world_dir(server: &ServerConfig) -> PathBuf {
+    Path::new(&server.server_directory).join(&server.world_name)
+}
+
+/// Region directory of a dimension of a server's world. Bukkit-based servers
+/// keep the nether and end in folders of their own next to the world
+/// (`world_nether/DIM-1/region`), which is used when the world has none.
+pub fn server_region_dir(server: &ServerConfig, dimension: &str) -> Option<PathBuf> {
+    let relative = region_dir(dimension)?;
+    let vanilla = server_world_dir(server).join(relative);
+    let suffix = match dimension_id(dimension)? {
+        "minecraft:the_nether" => "_nether",
+        "minecraft:the_end" => "_the_end",
+        _ => return Some(vanilla),
+    };
+    let bukkit = Path::new(&server.server_directory).join(format!("{}{}", server.world_name, suffix));
+    if !vanilla.parent().is_some_and(Path::is_dir) && bukkit.is_dir() {
+        Some(bukkit.join(relative))
+    } else {
+        Some(vanilla)
+    }
+}
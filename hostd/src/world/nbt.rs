@@ -0,0 +1,447 @@
+//! Minimal reader/writer for Minecraft's binary NBT format.
+//!
+//! Compounds keep their entries in file order so that a chunk read and written
+//! back without changes is byte-for-byte identical.
+
+use anyhow::{anyhow, bail, Result};
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+/// Deeper nesting than this is treated as corrupt data rather than recursed into
+const MAX_DEPTH: usize = 512;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    /// Element type id and elements; the type is kept so empty lists round-trip
+    List(u8, Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => TAG_BYTE,
+            Tag::Short(_) => TAG_SHORT,
+            Tag::Int(_) => TAG_INT,
+            Tag::Long(_) => TAG_LONG,
+            Tag::Float(_) => TAG_FLOAT,
+            Tag::Double(_) => TAG_DOUBLE,
+            Tag::ByteArray(_) => TAG_BYTE_ARRAY,
+            Tag::String(_) => TAG_STRING,
+            Tag::List(_, _) => TAG_LIST,
+            Tag::Compound(_) => TAG_COMPOUND,
+            Tag::IntArray(_) => TAG_INT_ARRAY,
+            Tag::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+
+    /// Any integer tag widened to i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(v) => Some(*v as i64),
+            Tag::Short(v) => Some(*v as i64),
+            Tag::Int(v) => Some(*v as i64),
+            Tag::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Tag::Compound(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound_mut(&mut self) -> Option<&mut Compound> {
+        match self {
+            Tag::Compound(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(_, v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_list_mut(&mut self) -> Option<&mut Vec<Tag>> {
+        match self {
+            Tag::List(_, v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_long_array(&self) -> Option<&[i64]> {
+        match self {
+            Tag::LongArray(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// Named tags in file order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Compound {
+    entries: Vec<(String, Tag)>,
+}
+
+impl Compound {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        self.entries.iter().find(|(key, _)| key == name).map(|(_, tag)| tag)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Tag> {
+        self.entries.iter_mut().find(|(key, _)| key == name).map(|(_, tag)| tag)
+    }
+
+    /// Replace an existing entry in place, or append a new one
+    pub fn insert(&mut self, name: impl Into<String>, tag: Tag) {
+        let name = name.into();
+        match self.get_mut(&name) {
+            Some(existing) => *existing = tag,
+            None => self.entries.push((name, tag)),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Tag> {
+        let index = self.entries.iter().position(|(key, _)| key == name)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Tag)> {
+        self.entries.iter().map(|(key, tag)| (key.as_str(), tag))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        self.get(name).and_then(Tag::as_i64)
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(Tag::as_str)
+    }
+
+    pub fn get_compound(&self, name: &str) -> Option<&Compound> {
+        self.get(name).and_then(Tag::as_compound)
+    }
+
+    pub fn get_compound_mut(&mut self, name: &str) -> Option<&mut Compound> {
+        self.get_mut(name).and_then(Tag::as_compound_mut)
+    }
+
+    pub fn get_list(&self, name: &str) -> Option<&[Tag]> {
+        self.get(name).and_then(Tag::as_list)
+    }
+
+    pub fn get_list_mut(&mut self, name: &str) -> Option<&mut Vec<Tag>> {
+        self.get_mut(name).and_then(Tag::as_list_mut)
+    }
+}
+
+/// Parse an uncompressed NBT document into its root name and compound
+pub fn read(bytes: &[u8]) -> Result<(String, Compound)> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let id = reader.u8()?;
+    if id != TAG_COMPOUND {
+        bail!("NBT root is tag type {}, expected a compound", id);
+    }
+    let name = reader.string()?;
+    let root = reader.compound(0)?;
+    Ok((name, root))
+}
+
+/// Serialize a root compound to uncompressed NBT
+pub fn write(name: &str, root: &Compound) -> Vec<u8> {
+    let mut out = Vec::with_capacity(4096);
+    out.push(TAG_COMPOUND);
+    write_string(&mut out, name);
+    write_compound(&mut out, root);
+    out
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| anyhow!("NBT data truncated at byte {}", self.pos))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        let len = self.i32()?;
+        usize::try_from(len).map_err(|_| anyhow!("negative NBT length {} at byte {}", len, self.pos))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.i16()? as u16 as usize;
+        decode_mutf8(self.take(len)?)
+    }
+
+    fn compound(&mut self, depth: usize) -> Result<Compound> {
+        let mut compound = Compound::new();
+        loop {
+            let id = self.u8()?;
+            if id == TAG_END {
+                return Ok(compound);
+            }
+            let name = self.string()?;
+            let tag = self.payload(id, depth + 1)?;
+            compound.entries.push((name, tag));
+        }
+    }
+
+    fn payload(&mut self, id: u8, depth: usize) -> Result<Tag> {
+        if depth > MAX_DEPTH {
+            bail!("NBT nesting deeper than {}", MAX_DEPTH);
+        }
+        Ok(match id {
+            TAG_BYTE => Tag::Byte(self.u8()? as i8),
+            TAG_SHORT => Tag::Short(self.i16()?),
+            TAG_INT => Tag::Int(self.i32()?),
+            TAG_LONG => Tag::Long(self.i64()?),
+            TAG_FLOAT => Tag::Float(f32::from_bits(self.i32()? as u32)),
+            TAG_DOUBLE => Tag::Double(f64::from_bits(self.i64()? as u64)),
+            TAG_BYTE_ARRAY => {
+                let len = self.len()?;
+                Tag::ByteArray(self.take(len)?.iter().map(|b| *b as i8).collect())
+            }
+            TAG_STRING => Tag::String(self.string()?),
+            TAG_LIST => {
+                let element = self.u8()?;
+                let len = self.len()?;
+                // Each element is at least one byte, so a longer list cannot be valid
+                if len > self.bytes.len() - self.pos && element != TAG_END {
+                    bail!("NBT list of {} elements exceeds remaining data", len);
+                }
+                let mut items = Vec::with_capacity(len.min(4096));
+                for _ in 0..len {
+                    items.push(self.payload(element, depth + 1)?);
+                }
+                Tag::List(element, items)
+            }
+            TAG_COMPOUND => Tag::Compound(self.compound(depth)?),
+            TAG_INT_ARRAY => {
+                let len = self.len()?;
+                let data = self.take(len.checked_mul(4).ok_or_else(|| anyhow!("NBT int array too long"))?)?;
+                Tag::IntArray(data.chunks_exact(4).map(|b| i32::from_be_bytes(b.try_into().unwrap())).collect())
+            }
+            TAG_LONG_ARRAY => {
+                let len = self.len()?;
+                let data = self.take(len.checked_mul(8).ok_or_else(|| anyhow!("NBT long array too long"))?)?;
+                Tag::LongArray(data.chunks_exact(8).map(|b| i64::from_be_bytes(b.try_into().unwrap())).collect())
+            }
+            other => bail!("unknown NBT tag type {} at byte {}", other, self.pos),
+        })
+    }
+}
+
+fn write_string(out: &mut Vec<u8>, value: &str) {
+    let encoded = encode_mutf8(value);
+    out.extend_from_slice(&(encoded.len() as u16).to_be_bytes());
+    out.extend_from_slice(&encoded);
+}
+
+fn write_compound(out: &mut Vec<u8>, compound: &Compound) {
+    for (name, tag) in &compound.entries {
+        out.push(tag.id());
+        write_string(out, name);
+        write_payload(out, tag);
+    }
+    out.push(TAG_END);
+}
+
+fn write_payload(out: &mut Vec<u8>, tag: &Tag) {
+    match tag {
+        Tag::Byte(v) => out.push(*v as u8),
+        Tag::Short(v) => out.extend_from_slice(&v.to_be_bytes()),
+        Tag::Int(v) => out.extend_from_slice(&v.to_be_bytes()),
+        Tag::Long(v) => out.extend_from_slice(&v.to_be_bytes()),
+        Tag::Float(v) => out.extend_from_slice(&v.to_bits().to_be_bytes()),
+        Tag::Double(v) => out.extend_from_slice(&v.to_bits().to_be_bytes()),
+        Tag::ByteArray(v) => {
+            out.extend_from_slice(&(v.len() as i32).to_be_bytes());
+            out.extend(v.iter().map(|b| *b as u8));
+        }
+        Tag::String(v) => write_string(out, v),
+        Tag::List(element, items) => {
+            // Writers use TAG_END for empty lists; keep whatever type the list had
+            let element = items.first().map(Tag::id).unwrap_or(*element);
+            out.push(element);
+            out.extend_from_slice(&(items.len() as i32).to_be_bytes());
+            for item in items {
+                write_payload(out, item);
+            }
+        }
+        Tag::Compound(v) => write_compound(out, v),
+        Tag::IntArray(v) => {
+            out.extend_from_slice(&(v.len() as i32).to_be_bytes());
+            for value in v {
+                out.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        Tag::LongArray(v) => {
+            out.extend_from_slice(&(v.len() as i32).to_be_bytes());
+            for value in v {
+                out.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+    }
+}
+
+/// Java's "modified UTF-8": NUL is two bytes and supplementary characters are surrogate pairs
+fn decode_mutf8(bytes: &[u8]) -> Result<String> {
+    if let Ok(s) = std::str::from_utf8(bytes) {
+        if !bytes.contains(&0xED) {
+            return Ok(s.to_string());
+        }
+    }
+
+    let mut units: Vec<u16> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        let (unit, width) = if b & 0x80 == 0 {
+            (b as u16, 1)
+        } else if b & 0xE0 == 0xC0 && i + 1 < bytes.len() {
+            ((((b & 0x1F) as u16) << 6) | (bytes[i + 1] & 0x3F) as u16, 2)
+        } else if b & 0xF0 == 0xE0 && i + 2 < bytes.len() {
+            (
+                (((b & 0x0F) as u16) << 12) | (((bytes[i + 1] & 0x3F) as u16) << 6) | (bytes[i + 2] & 0x3F) as u16,
+                3,
+            )
+        } else {
+            bail!("invalid modified UTF-8 in NBT string");
+        };
+        units.push(unit);
+        i += width;
+    }
+    String::from_utf16(&units).map_err(|e| anyhow!("invalid NBT string: {}", e))
+}
+
+fn encode_mutf8(value: &str) -> Vec<u8> {
+    if !value.chars().any(|c| c == '\0' || c as u32 > 0xFFFF) {
+        return value.as_bytes().to_vec();
+    }
+
+    let mut out = Vec::with_capacity(value.len() + 8);
+    for unit in value.encode_utf16() {
+        match unit {
+            0x0001..=0x007F => out.push(unit as u8),
+            0x0000 | 0x0080..=0x07FF => {
+                out.push(0xC0 | (unit >> 6) as u8);
+                out.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                out.push(0xE0 | (unit >> 12) as u8);
+                out.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                out.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_preserves_order_and_types() {
+        let mut inner = Compound::new();
+        inner.insert("Name", Tag::String("minecraft:stone".to_string()));
+        inner.insert("Sign", Tag::String("null\0 and 🦀".to_string()));
+
+        let mut root = Compound::new();
+        root.insert("DataVersion", Tag::Int(3700));
+        root.insert("xPos", Tag::Int(-3));
+        root.insert("Heights", Tag::LongArray(vec![1, -2, i64::MAX]));
+        root.insert("Light", Tag::ByteArray(vec![-1, 0, 15]));
+        root.insert("Empty", Tag::List(TAG_COMPOUND, Vec::new()));
+        root.insert("sections", Tag::List(TAG_COMPOUND, vec![Tag::Compound(inner)]));
+        root.insert("Temp", Tag::Float(0.5));
+
+        let bytes = write("", &root);
+        let (name, parsed) = read(&bytes).unwrap();
+        assert_eq!(name, "");
+        assert_eq!(parsed, root);
+        assert_eq!(write("", &parsed), bytes);
+
+        let keys: Vec<&str> = parsed.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, ["DataVersion", "xPos", "Heights", "Light", "Empty", "sections", "Temp"]);
+    }
+
+    #[test]
+    fn test_truncated_data_is_an_error() {
+        let mut root = Compound::new();
+        root.insert("Heights", Tag::LongArray(vec![1, 2, 3]));
+        let bytes = write("", &root);
+
+        assert!(read(&bytes[..bytes.len() - 5]).is_err());
+        assert!(read(&[TAG_INT, 0, 0]).is_err());
+    }
+}
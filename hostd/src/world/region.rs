@@ -0,0 +1,287 @@
+//! Anvil region files (`r.<x>.<z>.mca`): 32x32 chunks, each stored as compressed NBT.
+
+use anyhow::{anyhow, bail, Context, Result};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::nbt::{self, Compound};
+
+pub const SECTOR_SIZE: usize = 4096;
+pub const CHUNKS_PER_REGION: usize = 1024;
+
+const COMPRESSION_GZIP: u8 = 1;
+const COMPRESSION_ZLIB: u8 = 2;
+const COMPRESSION_NONE: u8 = 3;
+/// High bit of the compression byte: payload lives in a separate c.<x>.<z>.mcc file
+const COMPRESSION_EXTERNAL: u8 = 0x80;
+
+/// Index of a chunk within its region
+pub fn chunk_index(chunk_x: i32, chunk_z: i32) -> usize {
+    (chunk_x.rem_euclid(32) + chunk_z.rem_euclid(32) * 32) as usize
+}
+
+/// Absolute chunk coordinates of a slot in region (`region_x`, `region_z`)
+pub fn chunk_coords(region_x: i32, region_z: i32, index: usize) -> (i32, i32) {
+    (region_x * 32 + (index % 32) as i32, region_z * 32 + (index / 32) as i32)
+}
+
+/// Parse `r.<x>.<z>.mca` into region coordinates
+pub fn parse_region_name(name: &str) -> Option<(i32, i32)> {
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    parts.next().is_none().then_some((x, z))
+}
+
+/// All region files in a directory, sorted by coordinates
+pub fn list_regions(dir: &Path) -> Result<Vec<(i32, i32, PathBuf)>> {
+    let mut regions = Vec::new();
+    if !dir.exists() {
+        return Ok(regions);
+    }
+    for entry in std::fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+        let entry = entry?;
+        if let Some((x, z)) = entry.file_name().to_str().and_then(parse_region_name) {
+            regions.push((x, z, entry.path()));
+        }
+    }
+    regions.sort_by_key(|(x, z, _)| (*z, *x));
+    Ok(regions)
+}
+
+/// A chunk exactly as stored in the region file
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawChunk {
+    pub timestamp: u32,
+    pub compression: u8,
+    pub data: Vec<u8>,
+}
+
+impl RawChunk {
+    /// Decompress and parse the chunk NBT
+    pub fn decode(&self) -> Result<Compound> {
+        let mut bytes = Vec::new();
+        match self.compression {
+            COMPRESSION_GZIP => {
+                GzDecoder::new(&self.data[..]).read_to_end(&mut bytes)?;
+            }
+            COMPRESSION_ZLIB => {
+                ZlibDecoder::new(&self.data[..]).read_to_end(&mut bytes)?;
+            }
+            COMPRESSION_NONE => bytes.extend_from_slice(&self.data),
+            other => bail!("unsupported chunk compression type {}", other),
+        }
+        Ok(nbt::read(&bytes)?.1)
+    }
+
+    /// Compress chunk NBT the way vanilla writes it (zlib)
+    pub fn encode(chunk: &Compound, timestamp: u32) -> Result<Self> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&nbt::write("", chunk))?;
+        Ok(Self {
+            timestamp,
+            compression: COMPRESSION_ZLIB,
+            data: encoder.finish()?,
+        })
+    }
+}
+
+/// An in-memory region file
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub x: i32,
+    pub z: i32,
+    chunks: Vec<Option<RawChunk>>,
+}
+
+impl Region {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z, chunks: vec![None; CHUNKS_PER_REGION] }
+    }
+
+    /// Read a region file. Any chunk that cannot be located or sized is an error,
+    /// so a region that was read successfully can be written back without losing data.
+    pub fn read(path: &Path) -> Result<Self> {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let (x, z) = parse_region_name(name).ok_or_else(|| anyhow!("{} is not a region file name", path.display()))?;
+        let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(x, z, &bytes, path.parent())
+    }
+
+    /// Parse region bytes; `dir` is where external `.mcc` chunk files are looked up
+    pub fn parse(x: i32, z: i32, bytes: &[u8], dir: Option<&Path>) -> Result<Self> {
+        let (region, damaged) = Self::parse_lenient(x, z, bytes, dir)?;
+        match damaged.into_iter().next() {
+            Some((_, error)) => Err(error),
+            None => Ok(region),
+        }
+    }
+
+    /// Parse region bytes, keeping every chunk that can be located and returning
+    /// the slots that cannot. Only a truncated header is an error.
+    pub fn parse_lenient(x: i32, z: i32, bytes: &[u8], dir: Option<&Path>) -> Result<(Self, Vec<(usize, anyhow::Error)>)> {
+        let mut region = Self::new(x, z);
+        let mut damaged = Vec::new();
+        if bytes.is_empty() {
+            return Ok((region, damaged));
+        }
+        if bytes.len() < SECTOR_SIZE * 2 {
+            bail!("region r.{}.{}.mca is truncated ({} bytes)", x, z, bytes.len());
+        }
+
+        for index in 0..CHUNKS_PER_REGION {
+            match Self::read_slot(x, z, bytes, dir, index) {
+                Ok(chunk) => region.chunks[index] = chunk,
+                Err(e) => damaged.push((index, e)),
+            }
+        }
+        Ok((region, damaged))
+    }
+
+    fn read_slot(x: i32, z: i32, bytes: &[u8], dir: Option<&Path>, index: usize) -> Result<Option<RawChunk>> {
+        let location = u32::from_be_bytes(bytes[index * 4..index * 4 + 4].try_into()?);
+        if location == 0 {
+            return Ok(None);
+        }
+        let offset = (location >> 8) as usize * SECTOR_SIZE;
+        let sectors = (location & 0xFF) as usize;
+        let timestamp = u32::from_be_bytes(bytes[SECTOR_SIZE + index * 4..SECTOR_SIZE + index * 4 + 4].try_into()?);
+        let (cx, cz) = chunk_coords(x, z, index);
+
+        if offset < SECTOR_SIZE * 2 || sectors == 0 || offset + 5 > bytes.len() {
+            bail!("chunk {},{} points outside region r.{}.{}.mca", cx, cz, x, z);
+        }
+        let length = u32::from_be_bytes(bytes[offset..offset + 4].try_into()?) as usize;
+        if length == 0 || offset + 4 + length > bytes.len() || length > sectors * SECTOR_SIZE {
+            bail!("chunk {},{} has invalid length {} in region r.{}.{}.mca", cx, cz, length, x, z);
+        }
+
+        let compression = bytes[offset + 4];
+        let chunk = if compression & COMPRESSION_EXTERNAL != 0 {
+            let dir = dir.ok_or_else(|| anyhow!("chunk {},{} is stored externally", cx, cz))?;
+            let external = dir.join(format!("c.{}.{}.mcc", cx, cz));
+            RawChunk {
+                timestamp,
+                compression: compression & !COMPRESSION_EXTERNAL,
+                data: std::fs::read(&external).with_context(|| format!("reading {}", external.display()))?,
+            }
+        } else {
+            RawChunk {
+                timestamp,
+                compression,
+                data: bytes[offset + 5..offset + 4 + length].to_vec(),
+            }
+        };
+        Ok(Some(chunk))
+    }
+
+    pub fn chunk(&self, index: usize) -> Option<&RawChunk> {
+        self.chunks.get(index).and_then(Option::as_ref)
+    }
+
+    pub fn set_chunk(&mut self, index: usize, chunk: Option<RawChunk>) {
+        self.chunks[index] = chunk;
+    }
+
+    /// Slots that hold a chunk
+    pub fn present(&self) -> impl Iterator<Item = (usize, &RawChunk)> {
+        self.chunks.iter().enumerate().filter_map(|(index, chunk)| chunk.as_ref().map(|c| (index, c)))
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.iter().filter(|chunk| chunk.is_some()).count()
+    }
+
+    /// Serialize with chunks packed back to back (this also defragments the file)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut header = vec![0u8; SECTOR_SIZE * 2];
+        let mut body = Vec::new();
+
+        for (index, chunk) in self.present() {
+            let sector = 2 + body.len() / SECTOR_SIZE;
+            let length = chunk.data.len() + 1;
+            let sectors = (4 + length).div_ceil(SECTOR_SIZE);
+            // Chunks of 1 MiB or more cannot be addressed inline; vanilla moves them to .mcc files
+            if sectors > 255 {
+                continue;
+            }
+
+            body.extend_from_slice(&(length as u32).to_be_bytes());
+            body.push(chunk.compression);
+            body.extend_from_slice(&chunk.data);
+            body.resize(body.len().div_ceil(SECTOR_SIZE) * SECTOR_SIZE, 0);
+
+            let location = ((sector as u32) << 8) | sectors as u32;
+            header[index * 4..index * 4 + 4].copy_from_slice(&location.to_be_bytes());
+            header[SECTOR_SIZE + index * 4..SECTOR_SIZE + index * 4 + 4].copy_from_slice(&chunk.timestamp.to_be_bytes());
+        }
+
+        header.extend_from_slice(&body);
+        header
+    }
+
+    /// Write to a temporary file next to `path` and rename it into place
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if self.present().any(|(_, chunk)| (5 + chunk.data.len()).div_ceil(SECTOR_SIZE) > 255) {
+            bail!("region r.{}.{}.mca has a chunk too large to store inline", self.x, self.z);
+        }
+        let tmp = path.with_extension("mca.tmp");
+        std::fs::write(&tmp, self.to_bytes()).with_context(|| format!("writing {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("replacing {}", path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::nbt::Tag;
+
+    fn sample_chunk(x: i32, z: i32) -> Compound {
+        let mut chunk = Compound::new();
+        chunk.insert("DataVersion", Tag::Int(3700));
+        chunk.insert("xPos", Tag::Int(x));
+        chunk.insert("zPos", Tag::Int(z));
+        chunk.insert("Status", Tag::String("minecraft:full".to_string()));
+        chunk
+    }
+
+    #[test]
+    fn test_region_names() {
+        assert_eq!(parse_region_name("r.-1.2.mca"), Some((-1, 2)));
+        assert_eq!(parse_region_name("r.0.0.mcr"), None);
+        assert_eq!(parse_region_name("r.1.2.3.mca"), None);
+        assert_eq!(chunk_index(-1, -1), 1023);
+        assert_eq!(chunk_coords(-1, 0, 1023), (-1, 31));
+    }
+
+    #[test]
+    fn test_region_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("r.-1.0.mca");
+
+        let mut region = Region::new(-1, 0);
+        for (cx, cz) in [(-32, 0), (-1, 31), (-5, 7)] {
+            let chunk = RawChunk::encode(&sample_chunk(cx, cz), 1_700_000_000).unwrap();
+            region.set_chunk(chunk_index(cx, cz), Some(chunk));
+        }
+        region.write(&path).unwrap();
+
+        let read = Region::read(&path).unwrap();
+        assert_eq!(read.chunk_count(), 3);
+        let chunk = read.chunk(chunk_index(-5, 7)).unwrap();
+        assert_eq!(chunk.timestamp, 1_700_000_000);
+        assert_eq!(chunk.decode().unwrap(), sample_chunk(-5, 7));
+        assert_eq!(read.to_bytes(), region.to_bytes());
+    }
+
+    #[test]
+    fn test_chunk_pointing_past_end_is_an_error() {
+        let mut bytes = Region::new(0, 0).to_bytes();
+        bytes[0..4].copy_from_slice(&((10u32 << 8) | 1).to_be_bytes());
+        assert!(Region::parse(0, 0, &bytes, None).is_err());
+    }
+}
@@ -0,0 +1,293 @@
+//! Chunk pruning: drop chunks far from the play area or that players never
+//! spent time in, so they regenerate on demand and stop taking disk space.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::nbt::Compound;
+use super::region::{self, Region};
+
+/// Per-chunk data kept alongside `region/` since 1.17; trimmed at the same coordinates
+const COMPANION_DIRS: &[&str] = &["entities", "poi"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrimOptions {
+    /// Keep chunks within this many blocks of the center (square, like the world border)
+    pub radius: Option<u32>,
+    #[serde(default)]
+    pub center_x: i32,
+    #[serde(default)]
+    pub center_z: i32,
+    /// Drop chunks players spent less than this many seconds in
+    pub min_inhabited_seconds: Option<u64>,
+    /// Only report what would be removed
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+impl TrimOptions {
+    pub fn validate(&self) -> Result<()> {
+        if self.radius.is_none() && self.min_inhabited_seconds.is_none() {
+            bail!("Set a radius, a minimum inhabited time, or both");
+        }
+        Ok(())
+    }
+
+    fn outside_radius(&self, chunk_x: i32, chunk_z: i32) -> bool {
+        let Some(radius) = self.radius else {
+            return false;
+        };
+        // Keep any chunk that overlaps the square
+        let (min_x, min_z) = (chunk_x as i64 * 16, chunk_z as i64 * 16);
+        let dx = (self.center_x as i64 - (min_x + 15)).max(min_x - self.center_x as i64).max(0);
+        let dz = (self.center_z as i64 - (min_z + 15)).max(min_z - self.center_z as i64).max(0);
+        dx > radius as i64 || dz > radius as i64
+    }
+
+    fn barely_inhabited(&self, chunk: &Compound) -> bool {
+        let Some(seconds) = self.min_inhabited_seconds else {
+            return false;
+        };
+        let level = chunk.get_compound("Level").unwrap_or(chunk);
+        let ticks = level.get_i64("InhabitedTime").unwrap_or(0);
+        (ticks.max(0) as u64) < seconds * 20
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrimReport {
+    pub dry_run: bool,
+    pub regions_scanned: usize,
+    pub chunks_scanned: usize,
+    pub chunks_trimmed: usize,
+    /// Region files that end up empty and are deleted outright
+    pub regions_deleted: usize,
+    /// Chunks that could not be decoded; they are never trimmed
+    pub chunks_unreadable: usize,
+    pub bytes_before: u64,
+    pub reclaimable_bytes: u64,
+}
+
+impl TrimReport {
+    pub fn merge(&mut self, other: TrimReport) {
+        self.regions_scanned += other.regions_scanned;
+        self.chunks_scanned += other.chunks_scanned;
+        self.chunks_trimmed += other.chunks_trimmed;
+        self.regions_deleted += other.regions_deleted;
+        self.chunks_unreadable += other.chunks_unreadable;
+        self.bytes_before += other.bytes_before;
+        self.reclaimable_bytes += other.reclaimable_bytes;
+    }
+}
+
+/// Trim one dimension. `dimension_dir` is the folder holding `region/` (the world root for the overworld).
+pub fn trim_dimension(dimension_dir: &Path, options: &TrimOptions) -> Result<TrimReport> {
+    options.validate()?;
+    let mut report = TrimReport { dry_run: options.dry_run, ..Default::default() };
+
+    for (x, z, path) in region::list_regions(&dimension_dir.join("region"))? {
+        let mut region = Region::read(&path)?;
+        let size = std::fs::metadata(&path)?.len();
+        report.regions_scanned += 1;
+        report.bytes_before += size;
+
+        let mut trimmed = Vec::new();
+        for (index, raw) in region.present() {
+            report.chunks_scanned += 1;
+            let (cx, cz) = region::chunk_coords(x, z, index);
+            let remove = if options.outside_radius(cx, cz) {
+                true
+            } else if options.min_inhabited_seconds.is_some() {
+                match raw.decode() {
+                    Ok(chunk) => options.barely_inhabited(&chunk),
+                    Err(_) => {
+                        report.chunks_unreadable += 1;
+                        false
+                    }
+                }
+            } else {
+                false
+            };
+            if remove {
+                trimmed.push(index);
+            }
+        }
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        report.chunks_trimmed += trimmed.len();
+        for &index in &trimmed {
+            region.set_chunk(index, None);
+        }
+        let empty = region.chunk_count() == 0;
+        if empty {
+            report.regions_deleted += 1;
+            report.reclaimable_bytes += size;
+        } else {
+            report.reclaimable_bytes += size.saturating_sub(region.to_bytes().len() as u64);
+        }
+
+        // Companion files and externally stored chunks for the same slots
+        let name = path.file_name().unwrap_or_default();
+        let mut companions = Vec::new();
+        for dir in COMPANION_DIRS {
+            let companion_path = dimension_dir.join(dir).join(name);
+            if companion_path.exists() {
+                let mut companion = Region::read(&companion_path)?;
+                let companion_size = std::fs::metadata(&companion_path)?.len();
+                for &index in &trimmed {
+                    companion.set_chunk(index, None);
+                }
+                let freed = if companion.chunk_count() == 0 {
+                    companion_size
+                } else {
+                    companion_size.saturating_sub(companion.to_bytes().len() as u64)
+                };
+                report.bytes_before += companion_size;
+                report.reclaimable_bytes += freed;
+                companions.push((companion_path, companion));
+            }
+        }
+        let externals: Vec<_> = trimmed
+            .iter()
+            .map(|&index| {
+                let (cx, cz) = region::chunk_coords(x, z, index);
+                path.with_file_name(format!("c.{}.{}.mcc", cx, cz))
+            })
+            .filter(|external| external.exists())
+            .collect();
+        for external in &externals {
+            report.reclaimable_bytes += std::fs::metadata(external)?.len();
+        }
+
+        if options.dry_run {
+            continue;
+        }
+        if empty {
+            std::fs::remove_file(&path)?;
+        } else {
+            region.write(&path)?;
+        }
+        for (companion_path, companion) in companions {
+            if companion.chunk_count() == 0 {
+                std::fs::remove_file(&companion_path)?;
+            } else {
+                companion.write(&companion_path)?;
+            }
+        }
+        for external in externals {
+            std::fs::remove_file(external)?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Drop a single chunk (and its entities, POI and external data) so it regenerates
+/// the next time it loads. Returns whether the chunk existed.
+pub fn remove_chunk(dimension_dir: &Path, chunk_x: i32, chunk_z: i32) -> Result<bool> {
+    let name = format!("r.{}.{}.mca", chunk_x.div_euclid(32), chunk_z.div_euclid(32));
+    let index = region::chunk_index(chunk_x, chunk_z);
+    let mut removed = false;
+    for dir in std::iter::once("region").chain(COMPANION_DIRS.iter().copied()) {
+        let path = dimension_dir.join(dir).join(&name);
+        if !path.exists() {
+            continue;
+        }
+        let mut region = Region::read(&path)?;
+        if region.chunk(index).is_none() {
+            continue;
+        }
+        region.set_chunk(index, None);
+        if region.chunk_count() == 0 {
+            std::fs::remove_file(&path)?;
+        } else {
+            region.write(&path)?;
+        }
+        removed |= dir == "region";
+    }
+    let external = dimension_dir.join("region").join(format!("c.{}.{}.mcc", chunk_x, chunk_z));
+    if external.exists() {
+        std::fs::remove_file(external)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::nbt::Tag;
+    use crate::world::region::RawChunk;
+
+    fn write_region(dir: &Path, x: i32, z: i32, chunks: &[((i32, i32), i64)]) {
+        std::fs::create_dir_all(dir).unwrap();
+        let mut region = Region::new(x, z);
+        for &((cx, cz), inhabited) in chunks {
+            let mut chunk = Compound::new();
+            chunk.insert("InhabitedTime", Tag::Long(inhabited));
+            region.set_chunk(region::chunk_index(cx, cz), Some(RawChunk::encode(&chunk, 0).unwrap()));
+        }
+        region.write(&dir.join(format!("r.{}.{}.mca", x, z))).unwrap();
+    }
+
+    #[test]
+    fn test_trim_by_radius_and_inhabited_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let regions = dir.path().join("region");
+        write_region(&regions, 0, 0, &[((0, 0), 10_000), ((1, 0), 0), ((20, 20), 10_000)]);
+        write_region(&regions, 5, 5, &[((170, 170), 10_000)]);
+        write_region(&dir.path().join("entities"), 5, 5, &[((170, 170), 0)]);
+
+        let mut options = TrimOptions {
+            radius: Some(64),
+            center_x: 0,
+            center_z: 0,
+            min_inhabited_seconds: Some(60),
+            dry_run: true,
+        };
+        let report = trim_dimension(dir.path(), &options).unwrap();
+        // (1,0) was never visited, (20,20) and the whole r.5.5 lie outside the radius
+        assert_eq!(report.chunks_scanned, 4);
+        assert_eq!(report.chunks_trimmed, 3);
+        assert_eq!(report.regions_deleted, 1);
+        assert!(report.reclaimable_bytes > 0);
+        assert!(regions.join("r.5.5.mca").exists());
+
+        options.dry_run = false;
+        trim_dimension(dir.path(), &options).unwrap();
+        assert!(!regions.join("r.5.5.mca").exists());
+        assert!(!dir.path().join("entities/r.5.5.mca").exists());
+        let kept = Region::read(&regions.join("r.0.0.mca")).unwrap();
+        assert_eq!(kept.chunk_count(), 1);
+        assert!(kept.chunk(region::chunk_index(0, 0)).is_some());
+    }
+
+    #[test]
+    fn test_remove_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        write_region(&dir.path().join("region"), -1, 0, &[((-1, 3), 0), ((-2, 3), 0)]);
+        write_region(&dir.path().join("entities"), -1, 0, &[((-1, 3), 0)]);
+
+        assert!(remove_chunk(dir.path(), -1, 3).unwrap());
+        assert!(!remove_chunk(dir.path(), -1, 3).unwrap());
+        let kept = Region::read(&dir.path().join("region/r.-1.0.mca")).unwrap();
+        assert_eq!(kept.chunk_count(), 1);
+        assert!(!dir.path().join("entities/r.-1.0.mca").exists());
+    }
+
+    #[test]
+    fn test_radius_keeps_overlapping_chunks() {
+        let options = TrimOptions { radius: Some(100), center_x: 8, center_z: 8, min_inhabited_seconds: None, dry_run: true };
+        assert!(!options.outside_radius(6, 0));
+        assert!(!options.outside_radius(-6, -6));
+        assert!(options.outside_radius(7, 0));
+        assert!(options.outside_radius(0, -7));
+        assert!(TrimOptions { radius: None, ..options }.validate().is_err());
+    }
+}
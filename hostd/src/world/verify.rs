@@ -0,0 +1,289 @@
+//! World integrity scan: find chunks whose region header entry, compression or
+//! NBT is damaged, and optionally drop them or put back a copy from a backup.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::nbt::Compound;
+use super::region::{self, RawChunk, Region};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepairAction {
+    /// Report only
+    #[default]
+    None,
+    /// Remove damaged chunks so the server regenerates them
+    Delete,
+    /// Replace damaged chunks with the copy from the newest backup that has a valid one
+    Restore,
+}
+
+/// Somewhere a previous copy of the world may be found, newest first when listed
+#[derive(Debug, Clone)]
+pub enum BackupSource {
+    /// Backup archive with the world stored under `world/`
+    Archive { name: String, path: PathBuf },
+    /// Folder laid out like a world (`region/`, `DIM-1/region/`, ...)
+    Directory { name: String, path: PathBuf },
+}
+
+impl BackupSource {
+    fn name(&self) -> &str {
+        match self {
+            BackupSource::Archive { name, .. } | BackupSource::Directory { name, .. } => name,
+        }
+    }
+
+    /// Raw bytes of a region file, `relative` to the world folder
+    fn read_region(&self, relative: &str) -> Option<Vec<u8>> {
+        match self {
+            BackupSource::Archive { path, .. } => {
+                let mut archive = zip::ZipArchive::new(std::fs::File::open(path).ok()?).ok()?;
+                let mut entry = archive.by_name(&format!("world/{}", relative)).ok()?;
+                let mut bytes = Vec::new();
+                entry.read_to_end(&mut bytes).ok()?;
+                Some(bytes)
+            }
+            BackupSource::Directory { path, .. } => std::fs::read(path.join(relative)).ok(),
+        }
+    }
+}
+
+/// Backups that may hold older copies of a server's regions, newest first: archives under
+/// `backups_dir/<server_id>/`, pre-import copies and lighting backups
+pub fn discover_backups(backups_dir: &Path, server_id: &str, server_dir: &Path, world: &Path) -> Vec<BackupSource> {
+    let mut found = Vec::new();
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    if let Ok(entries) = std::fs::read_dir(backups_dir.join(server_id)) {
+        for entry in entries.flatten() {
+            let archive = entry.path().join("backup.zip");
+            if let Some(time) = modified(&archive) {
+                let name = format!("backup {}", entry.file_name().to_string_lossy());
+                found.push((time, BackupSource::Archive { name, path: archive }));
+            }
+        }
+    }
+    for (dir, prefix) in [(server_dir.join("backups"), "import-"), (world.to_path_buf(), ".lighting-backup-")] {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(prefix) && entry.path().is_dir() {
+                if let Some(time) = modified(&entry.path()) {
+                    found.push((time, BackupSource::Directory { name, path: entry.path() }));
+                }
+            }
+        }
+    }
+
+    found.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+    found.into_iter().map(|(_, source)| source).collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkIssue {
+    pub dimension: String,
+    pub region: String,
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub problem: String,
+    /// What the repair did: `deleted`, `restored from <backup>`, or unset when left as is
+    pub repair: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub action: RepairAction,
+    pub regions_scanned: usize,
+    pub chunks_scanned: usize,
+    pub corrupted: Vec<ChunkIssue>,
+    /// Region files whose header itself is unreadable
+    pub damaged_regions: Vec<String>,
+    pub repaired: usize,
+}
+
+impl VerifyReport {
+    pub fn merge(&mut self, other: VerifyReport) {
+        self.regions_scanned += other.regions_scanned;
+        self.chunks_scanned += other.chunks_scanned;
+        self.corrupted.extend(other.corrupted);
+        self.damaged_regions.extend(other.damaged_regions);
+        self.repaired += other.repaired;
+    }
+}
+
+/// Decode a chunk and check it belongs to the slot it is stored in
+fn validate_chunk(raw: &RawChunk, chunk_x: i32, chunk_z: i32) -> Result<Compound> {
+    let chunk = raw.decode()?;
+    let level = chunk.get_compound("Level").unwrap_or(&chunk);
+    if let (Some(x), Some(z)) = (level.get_i64("xPos"), level.get_i64("zPos")) {
+        if (x, z) != (chunk_x as i64, chunk_z as i64) {
+            bail!("chunk data belongs to {},{}", x, z);
+        }
+    }
+    Ok(chunk)
+}
+
+/// Valid copy of one chunk from the newest backup that has it
+fn find_backup_chunk(backups: &[BackupSource], relative: &str, x: i32, z: i32, index: usize) -> Option<(String, RawChunk)> {
+    let (cx, cz) = region::chunk_coords(x, z, index);
+    backups.iter().find_map(|backup| {
+        let bytes = backup.read_region(relative)?;
+        let (region, _) = Region::parse_lenient(x, z, &bytes, None).ok()?;
+        let chunk = region.chunk(index)?.clone();
+        validate_chunk(&chunk, cx, cz).ok()?;
+        Some((backup.name().to_string(), chunk))
+    })
+}
+
+/// Scan (and optionally repair) one dimension's region files.
+/// `region_dir` is relative to `world`, e.g. `DIM-1/region`.
+pub fn verify_dimension(
+    world: &Path,
+    dimension: &str,
+    region_dir: &str,
+    action: RepairAction,
+    backups: &[BackupSource],
+) -> Result<VerifyReport> {
+    let mut report = VerifyReport { action, ..Default::default() };
+
+    for (x, z, path) in region::list_regions(&world.join(region_dir))? {
+        report.regions_scanned += 1;
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        let relative = format!("{}/{}", region_dir, name);
+        let bytes = std::fs::read(&path)?;
+
+        let (mut region, damaged) = match Region::parse_lenient(x, z, &bytes, path.parent()) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                report.damaged_regions.push(format!("{}: {}", relative, e));
+                match action {
+                    RepairAction::None => {}
+                    RepairAction::Delete => std::fs::remove_file(&path)?,
+                    RepairAction::Restore => {
+                        if let Some(restored) = backups.iter().find_map(|backup| backup.read_region(&relative)) {
+                            std::fs::write(&path, restored)?;
+                            report.repaired += 1;
+                        }
+                    }
+                }
+                continue;
+            }
+        };
+
+        let mut issues: Vec<(usize, String)> = damaged.into_iter().map(|(index, e)| (index, e.to_string())).collect();
+        for (index, raw) in region.present() {
+            report.chunks_scanned += 1;
+            let (cx, cz) = region::chunk_coords(x, z, index);
+            if let Err(e) = validate_chunk(raw, cx, cz) {
+                issues.push((index, e.to_string()));
+            }
+        }
+        report.chunks_scanned += issues.iter().filter(|(index, _)| region.chunk(*index).is_none()).count();
+        issues.sort_by_key(|(index, _)| *index);
+
+        let mut changed = false;
+        for (index, problem) in issues {
+            let (chunk_x, chunk_z) = region::chunk_coords(x, z, index);
+            let repair = match action {
+                RepairAction::None => None,
+                RepairAction::Delete => {
+                    region.set_chunk(index, None);
+                    Some("deleted".to_string())
+                }
+                RepairAction::Restore => find_backup_chunk(backups, &relative, x, z, index).map(|(backup, chunk)| {
+                    region.set_chunk(index, Some(chunk));
+                    format!("restored from {}", backup)
+                }),
+            };
+            if repair.is_some() {
+                changed = true;
+                report.repaired += 1;
+            }
+            report.corrupted.push(ChunkIssue {
+                dimension: dimension.to_string(),
+                region: relative.clone(),
+                chunk_x,
+                chunk_z,
+                problem,
+                repair,
+            });
+        }
+
+        if changed {
+            region.write(&path)?;
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::nbt::Tag;
+
+    fn chunk(x: i32, z: i32) -> RawChunk {
+        let mut chunk = Compound::new();
+        chunk.insert("xPos", Tag::Int(x));
+        chunk.insert("zPos", Tag::Int(z));
+        RawChunk::encode(&chunk, 0).unwrap()
+    }
+
+    fn world_with_damage(root: &Path) -> PathBuf {
+        let dir = root.join("region");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut region = Region::new(0, 0);
+        region.set_chunk(region::chunk_index(0, 0), Some(chunk(0, 0)));
+        region.set_chunk(region::chunk_index(1, 0), Some(RawChunk { timestamp: 0, compression: 2, data: vec![1, 2, 3] }));
+        // Stored in the wrong slot
+        region.set_chunk(region::chunk_index(2, 0), Some(chunk(9, 9)));
+        let path = dir.join("r.0.0.mca");
+        region.write(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_verify_reports_and_deletes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = world_with_damage(dir.path());
+
+        let report = verify_dimension(dir.path(), "overworld", "region", RepairAction::None, &[]).unwrap();
+        assert_eq!(report.chunks_scanned, 3);
+        let coords: Vec<_> = report.corrupted.iter().map(|issue| (issue.chunk_x, issue.chunk_z)).collect();
+        assert_eq!(coords, vec![(1, 0), (2, 0)]);
+        assert!(report.corrupted.iter().all(|issue| issue.repair.is_none()));
+
+        let report = verify_dimension(dir.path(), "overworld", "region", RepairAction::Delete, &[]).unwrap();
+        assert_eq!(report.repaired, 2);
+        assert_eq!(Region::read(&path).unwrap().chunk_count(), 1);
+    }
+
+    #[test]
+    fn test_restore_from_backup_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let world = dir.path().join("world");
+        let path = world_with_damage(&world);
+
+        let backup = dir.path().join("backup");
+        std::fs::create_dir_all(backup.join("region")).unwrap();
+        let mut good = Region::new(0, 0);
+        good.set_chunk(region::chunk_index(1, 0), Some(chunk(1, 0)));
+        good.write(&backup.join("region/r.0.0.mca")).unwrap();
+
+        let backups = vec![BackupSource::Directory { name: "nightly".to_string(), path: backup }];
+        let report = verify_dimension(&world, "overworld", "region", RepairAction::Restore, &backups).unwrap();
+        assert_eq!(report.repaired, 1);
+        assert_eq!(report.corrupted[0].repair.as_deref(), Some("restored from nightly"));
+        // No backup has a good copy of 2,0, so it is left for the operator
+        assert_eq!(report.corrupted[1].repair, None);
+
+        let restored = Region::read(&path).unwrap();
+        assert_eq!(restored.chunk(region::chunk_index(1, 0)), Some(&chunk(1, 0)));
+    }
+}
@@ -0,0 +1,494 @@
+//! World border schedules: a server's vanilla world border grown on a cron
+//! schedule, such as +500 blocks every Monday.
+//!
+//! When a schedule is due the border is moved over RCON (`worldborder center`
+//! and `worldborder set`), and every growth is recorded in the event log
+//! (`world_border`). Growths missed while the server was down are not caught
+//! up one by one: the next check after it is back grows the border once.
+//!
+//! With `pregen_ahead` the schedule also keeps a pregeneration job covering
+//! the border after the next growth, so players reaching the new edge find
+//! the terrain already generated.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::core::process_manager::ProcessManager;
+use crate::database::{DatabaseManager, EventLog, ServerConfig, WorldBorderSchedule};
+use crate::jobs;
+use crate::pregeneration::{NewPregenerationJob, PregenerationJob, PregenerationManager};
+use crate::restart_scheduler::{parse_cron, rcon};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Largest diameter the game accepts
+const MAX_BORDER_SIZE: u32 = 59_999_968;
+/// Center coordinates the game accepts
+const MAX_CENTER: i32 = 29_999_984;
+const MAX_TRANSITION_SECONDS: u32 = 7 * 24 * 60 * 60;
+const MAX_PREGEN_MARGIN: u32 = 10_000;
+const DEFAULT_PREGEN_MARGIN: u32 = 128;
+const PREGEN_SOURCE: &str = "world_border";
+
+/// Changes to a server's schedule; `initial_size`, `growth` and
+/// `cron_expression` are required when the server has none yet
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WorldBorderUpdate {
+    pub enabled: Option<bool>,
+    pub center_x: Option<i32>,
+    pub center_z: Option<i32>,
+    pub initial_size: Option<u32>,
+    pub growth: Option<u32>,
+    /// `Some(None)` (JSON `null`) removes the limit
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub max_size: Option<Option<u32>>,
+    pub cron_expression: Option<String>,
+    pub transition_seconds: Option<u32>,
+    pub pregen_ahead: Option<bool>,
+    pub pregen_margin: Option<u32>,
+}
+
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// A schedule as returned by the API, with what it will do next
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldBorderInfo {
+    #[serde(flatten)]
+    pub schedule: WorldBorderSchedule,
+    /// Diameter the next growth sets; `None` once `max_size` is reached
+    pub next_size: Option<u32>,
+    pub next_growth: Option<DateTime<Utc>>,
+    /// The job pregenerating ahead of the border
+    pub pregen_job: Option<PregenerationJob>,
+}
+
+/// Diameter the next growth sets, or `None` when the border is fully grown
+pub fn next_size(schedule: &WorldBorderSchedule) -> Option<u32> {
+    let max_size = schedule.max_size.unwrap_or(MAX_BORDER_SIZE).min(MAX_BORDER_SIZE);
+    let size = match schedule.current_size {
+        Some(current) if current >= max_size => return None,
+        Some(current) => current.saturating_add(schedule.growth),
+        None => schedule.initial_size,
+    };
+    Some(size.min(max_size))
+}
+
+/// When the schedule is next due; may be in the past when a growth is pending
+pub fn next_growth(schedule: &WorldBorderSchedule) -> Option<DateTime<Utc>> {
+    if !schedule.enabled || next_size(schedule).is_none() {
+        return None;
+    }
+    let cron = parse_cron(&schedule.cron_expression).ok()?;
+    let after = schedule.last_grown_at.unwrap_or(schedule.created_at);
+    cron.after(&after).next()
+}
+
+/// Commands moving the border to `size`
+fn border_commands(schedule: &WorldBorderSchedule, size: u32) -> Vec<String> {
+    let set = if schedule.transition_seconds > 0 {
+        format!("worldborder set {} {}", size, schedule.transition_seconds)
+    } else {
+        format!("worldborder set {}", size)
+    };
+    vec![format!("worldborder center {} {}", schedule.center_x, schedule.center_z), set]
+}
+
+/// Radius to pregenerate so the border after the next growth is covered
+fn pregen_radius(schedule: &WorldBorderSchedule) -> u32 {
+    let current = schedule.current_size.unwrap_or(schedule.initial_size);
+    let grown = WorldBorderSchedule { current_size: Some(current), ..schedule.clone() };
+    let size = next_size(&grown).unwrap_or(current);
+    size.div_ceil(2) + schedule.pregen_margin
+}
+
+fn validate(schedule: &WorldBorderSchedule) -> Result<()> {
+    if !(1..=MAX_BORDER_SIZE).contains(&schedule.initial_size) {
+        bail!("initial_size must be between 1 and {} blocks", MAX_BORDER_SIZE);
+    }
+    if schedule.growth == 0 {
+        bail!("growth must be at least 1 block");
+    }
+    if let Some(max_size) = schedule.max_size {
+        if max_size < schedule.initial_size || max_size > MAX_BORDER_SIZE {
+            bail!("max_size must be between initial_size and {} blocks", MAX_BORDER_SIZE);
+        }
+    }
+    if schedule.center_x.abs() > MAX_CENTER || schedule.center_z.abs() > MAX_CENTER {
+        bail!("The center must be within {} blocks of 0, 0", MAX_CENTER);
+    }
+    if schedule.transition_seconds > MAX_TRANSITION_SECONDS {
+        bail!("transition_seconds must be at most {}", MAX_TRANSITION_SECONDS);
+    }
+    if schedule.pregen_margin > MAX_PREGEN_MARGIN {
+        bail!("pregen_margin must be at most {} blocks", MAX_PREGEN_MARGIN);
+    }
+    parse_cron(&schedule.cron_expression)?;
+    Ok(())
+}
+
+/// Grows world borders when their schedules are due
+pub struct WorldBorderManager {
+    database: Arc<DatabaseManager>,
+    process_manager: Arc<ProcessManager>,
+    pregeneration: Arc<PregenerationManager>,
+    /// Held while a border is being changed, so a manual growth can't race the schedule
+    growing: Mutex<()>,
+}
+
+impl WorldBorderManager {
+    pub fn new(
+        database: Arc<DatabaseManager>,
+        process_manager: Arc<ProcessManager>,
+        pregeneration: Arc<PregenerationManager>,
+    ) -> Self {
+        Self {
+            database,
+            process_manager,
+            pregeneration,
+            growing: Mutex::new(()),
+        }
+    }
+
+    /// Servers Guardian did not start can only be tried
+    async fn is_running(&self, server: &ServerConfig) -> bool {
+        if !server.managed {
+            return true;
+        }
+        match Uuid::parse_str(&server.id) {
+            Ok(uuid) => self.process_manager.is_server_running(uuid).await,
+            Err(_) => false,
+        }
+    }
+
+    async fn info(&self, schedule: WorldBorderSchedule) -> WorldBorderInfo {
+        let pregen_job = match &schedule.pregen_job_id {
+            Some(job_id) => self.pregeneration.get_job(&schedule.server_id, job_id).await.ok().flatten(),
+            None => None,
+        };
+        WorldBorderInfo {
+            next_size: next_size(&schedule),
+            next_growth: next_growth(&schedule),
+            pregen_job,
+            schedule,
+        }
+    }
+
+    pub async fn get(&self, server_id: &str) -> Result<Option<WorldBorderInfo>> {
+        match self.database.get_world_border_schedule(server_id).await? {
+            Some(schedule) => Ok(Some(self.info(schedule).await)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn update(&self, server_id: &str, update: WorldBorderUpdate) -> Result<WorldBorderInfo> {
+        self.database.require_server(server_id).await?;
+        let now = Utc::now();
+        let mut schedule = match self.database.get_world_border_schedule(server_id).await? {
+            Some(schedule) => schedule,
+            None => WorldBorderSchedule {
+                server_id: server_id.to_string(),
+                enabled: true,
+                center_x: 0,
+                center_z: 0,
+                initial_size: update.initial_size.ok_or_else(|| anyhow!("initial_size is required"))?,
+                growth: update.growth.ok_or_else(|| anyhow!("growth is required"))?,
+                max_size: None,
+                cron_expression: update.cron_expression.clone().ok_or_else(|| anyhow!("cron_expression is required"))?,
+                transition_seconds: 0,
+                current_size: None,
+                last_grown_at: None,
+                last_error: None,
+                pregen_ahead: true,
+                pregen_margin: DEFAULT_PREGEN_MARGIN,
+                pregen_job_id: None,
+                created_at: now,
+                updated_at: now,
+            },
+        };
+
+        if let Some(enabled) = update.enabled {
+            schedule.enabled = enabled;
+        }
+        if let Some(center_x) = update.center_x {
+            schedule.center_x = center_x;
+        }
+        if let Some(center_z) = update.center_z {
+            schedule.center_z = center_z;
+        }
+        if let Some(initial_size) = update.initial_size {
+            schedule.initial_size = initial_size;
+        }
+        if let Some(growth) = update.growth {
+            schedule.growth = growth;
+        }
+        if let Some(max_size) = update.max_size {
+            schedule.max_size = max_size;
+        }
+        if let Some(cron_expression) = update.cron_expression {
+            schedule.cron_expression = cron_expression.trim().to_string();
+        }
+        if let Some(transition_seconds) = update.transition_seconds {
+            schedule.transition_seconds = transition_seconds;
+        }
+        if let Some(pregen_ahead) = update.pregen_ahead {
+            schedule.pregen_ahead = pregen_ahead;
+        }
+        if let Some(pregen_margin) = update.pregen_margin {
+            schedule.pregen_margin = pregen_margin;
+        }
+        validate(&schedule)?;
+
+        schedule.updated_at = now;
+        self.database.save_world_border_schedule(&schedule).await?;
+        Ok(self.info(schedule).await)
+    }
+
+    /// Remove the schedule; the border stays where it is in game
+    pub async fn delete(&self, server_id: &str) -> Result<bool> {
+        self.database.delete_world_border_schedule(server_id).await
+    }
+
+    /// Grow the border now, whether or not the schedule is due
+    pub async fn grow_now(&self, server_id: &str) -> Result<WorldBorderInfo> {
+        let schedule = self.grow(server_id, false).await?;
+        Ok(self.info(schedule).await)
+    }
+
+    /// Check schedules periodically, growing borders that are due and keeping
+    /// pregeneration ahead of them
+    pub async fn start(self: Arc<Self>) {
+        info!("Starting world border scheduler");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_schedules().await {
+                error!("World border scheduler error: {}", e);
+            }
+        }
+    }
+
+    async fn check_schedules(&self) -> Result<()> {
+        let now = Utc::now();
+        for schedule in self.database.get_enabled_world_border_schedules().await? {
+            let server_id = schedule.server_id.clone();
+            let schedule = match next_growth(&schedule) {
+                Some(at) if at <= now => match self.grow(&server_id, true).await {
+                    Ok(schedule) => schedule,
+                    Err(e) => {
+                        warn!("Failed to grow world border of server {}: {}", server_id, e);
+                        continue;
+                    }
+                },
+                _ => schedule,
+            };
+            if let Err(e) = self.ensure_pregeneration(schedule).await {
+                warn!("Failed to pregenerate ahead of the world border of server {}: {}", server_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Grow the border once. With `scheduled`, nothing is done unless the
+    /// schedule is still due once the lock is held.
+    async fn grow(&self, server_id: &str, scheduled: bool) -> Result<WorldBorderSchedule> {
+        let _growing = self.growing.lock().await;
+        let mut schedule = self
+            .database
+            .get_world_border_schedule(server_id)
+            .await?
+            .ok_or_else(|| anyhow!("Server {} has no world border schedule", server_id))?;
+        if scheduled && next_growth(&schedule).is_none_or(|at| at > Utc::now()) {
+            return Ok(schedule);
+        }
+        let size = next_size(&schedule).ok_or_else(|| anyhow!("The world border has reached its maximum size"))?;
+        let server = self.database.require_server(&schedule.server_id).await?;
+
+        let result = if self.is_running(&server).await {
+            self.apply(&server, &schedule, size).await
+        } else {
+            Err(anyhow!("Server is not running"))
+        };
+
+        let previous_size = schedule.current_size;
+        let error = result.as_ref().err().map(|e| e.to_string());
+        let changed = error != schedule.last_error;
+        schedule.last_error = error;
+        schedule.updated_at = Utc::now();
+        if result.is_ok() {
+            schedule.current_size = Some(size);
+            schedule.last_grown_at = Some(Utc::now());
+        }
+        // A server that stays down is retried every check; only save when the outcome changes
+        if result.is_ok() || changed {
+            self.database.save_world_border_schedule(&schedule).await?;
+        }
+        result?;
+
+        let message = match previous_size {
+            Some(previous) => format!("World border grown from {} to {} blocks", previous, size),
+            None => format!("World border set to {} blocks", size),
+        };
+        info!("{} (server {})", message, schedule.server_id);
+        let event = EventLog {
+            id: Uuid::new_v4().to_string(),
+            server_id: Some(schedule.server_id.clone()),
+            event_type: "world_border".to_string(),
+            message,
+            level: "info".to_string(),
+            metadata: Some(serde_json::json!({
+                "previous_size": previous_size,
+                "size": size,
+                "center_x": schedule.center_x,
+                "center_z": schedule.center_z,
+                "transition_seconds": schedule.transition_seconds,
+            })),
+            created_at: Utc::now(),
+        };
+        if let Err(e) = self.database.log_event(&event).await {
+            error!("Failed to log world border growth for server {}: {}", schedule.server_id, e);
+        }
+        Ok(schedule)
+    }
+
+    async fn apply(&self, server: &ServerConfig, schedule: &WorldBorderSchedule, size: u32) -> Result<()> {
+        for command in border_commands(schedule, size) {
+            rcon(server, command).await?;
+        }
+        Ok(())
+    }
+
+    /// Make sure a pregeneration job covers the border after the next growth.
+    /// Jobs that failed (e.g. because the server stopped) are resumed; ones
+    /// cancelled by hand are left alone until the border grows again.
+    async fn ensure_pregeneration(&self, mut schedule: WorldBorderSchedule) -> Result<()> {
+        if !schedule.pregen_ahead {
+            return Ok(());
+        }
+        let radius = pregen_radius(&schedule);
+        let server = self.database.require_server(&schedule.server_id).await?;
+        if !server.managed || !self.is_running(&server).await {
+            return Ok(());
+        }
+
+        let existing = match &schedule.pregen_job_id {
+            Some(job_id) => self.pregeneration.get_job(&schedule.server_id, job_id).await?,
+            None => None,
+        };
+        let covers = |job: &PregenerationJob| {
+            job.radius >= radius && job.center_x == schedule.center_x && job.center_z == schedule.center_z
+        };
+        match existing {
+            Some(job) if covers(&job) => {
+                if job.status == jobs::STATUS_FAILED || job.status == jobs::STATUS_PENDING {
+                    self.pregeneration.start_job(&schedule.server_id, &job.id).await?;
+                }
+                return Ok(());
+            }
+            Some(job) if job.is_active() => return Ok(()),
+            _ => {}
+        }
+
+        let job = self
+            .pregeneration
+            .create_job(
+                &schedule.server_id,
+                NewPregenerationJob {
+                    name: Some(format!("Ahead of world border ({} blocks)", radius)),
+                    dimensions: None,
+                    dimension: None,
+                    center_x: schedule.center_x,
+                    center_z: schedule.center_z,
+                    radius,
+                    scale_nether: None,
+                    batch_size: None,
+                    source: Some(PREGEN_SOURCE.to_string()),
+                },
+            )
+            .await?;
+        schedule.pregen_job_id = Some(job.id.clone());
+        schedule.updated_at = Utc::now();
+        self.database.save_world_border_schedule(&schedule).await?;
+        info!("Pregenerating {} blocks around the world border center of server {}", radius, schedule.server_id);
+        self.pregeneration.start_job(&schedule.server_id, &job.id).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(current_size: Option<u32>) -> WorldBorderSchedule {
+        let created_at = "2026-01-01T00:00:00Z".parse().unwrap();
+        WorldBorderSchedule {
+            server_id: "server".to_string(),
+            enabled: true,
+            center_x: 0,
+            center_z: 0,
+            initial_size: 1000,
+            growth: 500,
+            max_size: Some(2200),
+            cron_expression: "0 12 * * MON".to_string(),
+            transition_seconds: 60,
+            current_size,
+            last_grown_at: None,
+            last_error: None,
+            pregen_ahead: true,
+            pregen_margin: 100,
+            pregen_job_id: None,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    #[test]
+    fn test_next_size() {
+        assert_eq!(next_size(&schedule(None)), Some(1000));
+        assert_eq!(next_size(&schedule(Some(1000))), Some(1500));
+        // Growth stops at max_size
+        assert_eq!(next_size(&schedule(Some(2000))), Some(2200));
+        assert_eq!(next_size(&schedule(Some(2200))), None);
+
+        assert_eq!(pregen_radius(&schedule(None)), 850);
+        assert_eq!(pregen_radius(&schedule(Some(1000))), 850);
+        assert_eq!(pregen_radius(&schedule(Some(2200))), 1200);
+    }
+
+    #[test]
+    fn test_next_growth() {
+        let mut border = schedule(None);
+        // 2026-01-01 is a Thursday
+        assert_eq!(next_growth(&border), Some("2026-01-05T12:00:00Z".parse().unwrap()));
+        border.last_grown_at = Some("2026-01-05T12:00:10Z".parse().unwrap());
+        assert_eq!(next_growth(&border), Some("2026-01-12T12:00:00Z".parse().unwrap()));
+        border.enabled = false;
+        assert_eq!(next_growth(&border), None);
+    }
+
+    #[test]
+    fn test_commands_and_validation() {
+        let mut border = schedule(Some(1000));
+        border.center_x = -50;
+        assert_eq!(border_commands(&border, 1500), vec!["worldborder center -50 0", "worldborder set 1500 60"]);
+        border.transition_seconds = 0;
+        assert_eq!(border_commands(&border, 1500)[1], "worldborder set 1500");
+
+        assert!(validate(&border).is_ok());
+        border.max_size = Some(500);
+        assert!(validate(&border).is_err());
+        border.max_size = None;
+        border.cron_expression = "weekly".to_string();
+        assert!(validate(&border).is_err());
+    }
+}
@@ -16,7 +16,6 @@ use tokio::time::sleep;
 #[tokio::test]
 async fn test_database_performance() {
     let db = Database::new(":memory:").await.unwrap();
-    db.run_migrations().await.unwrap();
     
     // Test bulk server creation
     let start = Instant::now();
@@ -92,7 +91,6 @@ async fn test_database_performance() {
 #[tokio::test]
 async fn test_task_performance() {
     let db = Database::new(":memory:").await.unwrap();
-    db.run_migrations().await.unwrap();
     
     // Test bulk task creation
     let start = Instant::now();
@@ -151,7 +149,6 @@ async fn test_task_performance() {
 #[tokio::test]
 async fn test_mod_performance() {
     let db = Database::new(":memory:").await.unwrap();
-    db.run_migrations().await.unwrap();
     
     // Test bulk mod creation
     let start = Instant::now();
@@ -224,7 +221,6 @@ async fn test_websocket_performance() {
 #[tokio::test]
 async fn test_pregeneration_performance() {
     let db = Database::new(":memory:").await.unwrap();
-    db.run_migrations().await.unwrap();
     
     let pregen_manager = PregenerationManager::new(db.clone());
     
@@ -273,7 +269,6 @@ async fn test_pregeneration_performance() {
 #[tokio::test]
 async fn test_hot_import_performance() {
     let db = Database::new(":memory:").await.unwrap();
-    db.run_migrations().await.unwrap();
     
     let import_manager = HotImportManager::new(db.clone());
     
@@ -301,7 +296,6 @@ async fn test_hot_import_performance() {
 #[tokio::test]
 async fn test_lighting_performance() {
     let db = Database::new(":memory:").await.unwrap();
-    db.run_migrations().await.unwrap();
     
     let lighting_manager = LightingManager::new(db.clone());
     
@@ -362,7 +356,6 @@ async fn test_mod_management_performance() {
 #[tokio::test]
 async fn test_memory_usage() {
     let db = Database::new(":memory:").await.unwrap();
-    db.run_migrations().await.unwrap();
     
     // Create a large number of servers with complex configurations
     let start = Instant::now();
@@ -416,7 +409,6 @@ async fn test_memory_usage() {
 #[tokio::test]
 async fn test_concurrent_operations() {
     let db = Database::new(":memory:").await.unwrap();
-    db.run_migrations().await.unwrap();
     
     // Test concurrent operations on the same server
     let server_config = ServerConfig {
@@ -523,7 +515,6 @@ async fn test_concurrent_operations() {
 #[tokio::test]
 async fn test_stress_test() {
     let db = Database::new(":memory:").await.unwrap();
-    db.run_migrations().await.unwrap();
     
     // Stress test with mixed operations
     let start = Instant::now();